@@ -0,0 +1,71 @@
+//! Compares the payload throughput of the batch APIs against a loop over the single symbol APIs.
+//!
+//! Run with `cargo bench --bench batch`.
+
+use std::{hint::black_box, time::Instant};
+
+use software_modem::ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator};
+
+const NUM_SYMBOLS: usize = 2000;
+
+/// Returns the best throughput of a number of runs in MB/s of payload.
+fn throughput(payload_length: usize, mut run: impl FnMut()) -> f64 {
+    (0..10)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            payload_length as f64 / start.elapsed().as_secs_f64() / 1e6
+        })
+        .fold(0.0, f64::max)
+}
+
+fn main() {
+    for num_subcarriers in [64, 256, 1024] {
+        let config = OFDMConfig {
+            num_subcarriers,
+            cyclic_prefix_length: num_subcarriers / 8,
+            ..Default::default()
+        };
+        let modulator = OFDMModulator::new((&config).into());
+        let demodulator = OFDMDemodulator::new((&config).into());
+        let (bytes_per_symbol, symbol_length) = (
+            modulator.get_bytes_per_symbol(),
+            modulator.get_symbol_length(),
+        );
+        let data: Vec<u8> = (0..(NUM_SYMBOLS * bytes_per_symbol) as u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect();
+
+        let mut samples = Vec::new();
+        let loop_modulate = throughput(data.len(), || {
+            samples.clear();
+            for data in black_box(&data).chunks_exact(bytes_per_symbol) {
+                let mut symbol = vec![0.0; symbol_length];
+                modulator.modulate_buffer_as_symbol(data, &mut symbol);
+                samples.extend_from_slice(&symbol);
+            }
+        });
+        let batch_modulate = throughput(data.len(), || {
+            samples.clear();
+            modulator.modulate_batch(black_box(&data), &mut samples);
+        });
+
+        let mut received = Vec::new();
+        let loop_demodulate = throughput(data.len(), || {
+            received.clear();
+            for symbol in black_box(&samples).chunks_exact(symbol_length) {
+                received.extend(demodulator.demodulate_symbol_from_buffer(symbol));
+            }
+        });
+        let batch_demodulate = throughput(data.len(), || {
+            received.clear();
+            demodulator.demodulate_batch(black_box(&samples), &mut received);
+        });
+        assert_eq!(received, data);
+
+        println!(
+            "{num_subcarriers} subcarriers: modulation {loop_modulate:.1} MB/s in a loop, {batch_modulate:.1} MB/s batched, \
+             demodulation {loop_demodulate:.1} MB/s in a loop, {batch_demodulate:.1} MB/s batched"
+        );
+    }
+}
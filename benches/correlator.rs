@@ -0,0 +1,55 @@
+//! Times the preamble search of an idle receiver, the correlation of 64 and 512 sample templates with 1M samples,
+//! with the scalar kernel, the SIMD kernel of the CPU, overlap-save, and the method the correlator picks.
+//!
+//! Run with `cargo bench --bench correlator`.
+
+use std::{hint::black_box, time::Instant};
+
+use software_modem::correlator::{CorrelationMethod, Correlator, correlate_scalar};
+
+const NUM_SAMPLES: usize = 1 << 20;
+
+/// Returns the best time of a number of runs in milliseconds.
+fn time(mut run: impl FnMut()) -> f64 {
+    (0..10)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed().as_secs_f64() * 1e3
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let mut state: u32 = 0x1234_5678;
+    let mut noise = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32 - 0.5
+    };
+    let samples: Vec<f32> = (0..NUM_SAMPLES).map(|_| noise()).collect();
+
+    for length in [64, 512] {
+        let template = &samples[1000..1000 + length];
+        let mut output = vec![0.0; NUM_SAMPLES + 1 - length];
+        let scalar =
+            time(|| correlate_scalar(black_box(template), black_box(&samples), &mut output));
+        let rate = |ms: f64| NUM_SAMPLES as f64 / ms / 1e3;
+        println!(
+            "template of {length} samples: scalar {scalar:.1} ms, {:.1} M samples/s",
+            rate(scalar)
+        );
+        let auto = CorrelationMethod::auto(length);
+        for method in [CorrelationMethod::Direct, CorrelationMethod::OverlapSave] {
+            let correlator = Correlator::with_method(template, method);
+            let ms = time(|| correlator.correlate_into(black_box(&samples), &mut output));
+            println!(
+                "  {method:?}{}: {ms:.1} ms, {:.1} M samples/s, {:.1}x",
+                if method == auto { " (auto)" } else { "" },
+                rate(ms),
+                scalar / ms
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Times the equalization kernels over the subcarriers of a 4096 subcarrier configuration,
+//! the scalar kernels against the ones [Sample] runs, and the demodulation of a whole symbol.
+//!
+//! Run with `cargo bench --bench equalizer`, and on nightly with `cargo +nightly bench --bench equalizer --features portable-simd`.
+
+use std::{hint::black_box, time::Instant};
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    ofdm::{
+        OFDMConfig, SlmConfig, SlmSignaling,
+        demodulator::OFDMDemodulator,
+        equalizer::{derotate, scale, unscale},
+        modulator::OFDMModulator,
+    },
+    samples::Sample,
+};
+
+const NUM_SUBCARRIERS: u32 = 4096;
+const NUM_RUNS: usize = 1000;
+
+/// Returns the best time of a number of runs of `NUM_RUNS` calls in microseconds per call.
+fn time(mut run: impl FnMut()) -> f64 {
+    (0..10)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..NUM_RUNS {
+                run();
+            }
+            start.elapsed().as_secs_f64() * 1e6 / NUM_RUNS as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let vectorized = if cfg!(feature = "portable-simd") {
+        "portable-simd"
+    } else {
+        "Sample (scalar)"
+    };
+    let length = 2 * NUM_SUBCARRIERS as usize + 1;
+    let mut points: Vec<Complex32> = (0..length)
+        .map(|i| Complex32::from_polar(1.0 + (i % 7) as f32 * 0.25, i as f32 * 0.1))
+        .collect();
+    let gains: Vec<f32> = (0..length).map(|i| 0.5 + (i % 3) as f32 * 0.25).collect();
+    let phases: Vec<Complex32> = (0..length)
+        .map(|i| Complex32::from_polar(1.0, i as f32 * 0.37))
+        .collect();
+
+    let kernels = [
+        (
+            "scale",
+            time(|| scale(black_box(&mut points), black_box(1.0))),
+            time(|| f32::scale_points(black_box(&mut points), black_box(1.0))),
+        ),
+        (
+            "unscale",
+            time(|| unscale(black_box(&mut points), &gains)),
+            time(|| f32::unscale_points(black_box(&mut points), &gains)),
+        ),
+        (
+            "derotate",
+            time(|| derotate(black_box(&mut points), &phases)),
+            time(|| f32::derotate_points(black_box(&mut points), &phases)),
+        ),
+    ];
+    for (name, scalar, sample) in kernels {
+        println!(
+            "{name} of {length} points: scalar {scalar:.2} us, {vectorized} {sample:.2} us, {:.1}x",
+            scalar / sample
+        );
+    }
+
+    // every kernel is used, the pilot gain, the power allocation and selected mapping
+    let config = OFDMConfig {
+        num_subcarriers: NUM_SUBCARRIERS,
+        cyclic_prefix_length: NUM_SUBCARRIERS / 8,
+        slm: Some(SlmConfig {
+            candidates: 4,
+            signaling: SlmSignaling::Explicit,
+        }),
+        ..Default::default()
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&config).into()).get_bytes_per_symbol();
+    let config = OFDMConfig {
+        power_allocation: Some(
+            (0..num_data_subcarriers)
+                .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                .collect(),
+        ),
+        ..config
+    };
+    let modulator = OFDMModulator::new((&config).into());
+    let demodulator = OFDMDemodulator::new((&config).into());
+    let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+    let mut scratch = demodulator.make_scratch();
+    let mut output = vec![0; data.len()];
+    let elapsed =
+        time(|| demodulator.demodulate_symbol_into(black_box(&symbol), &mut scratch, &mut output));
+    assert_eq!(output, data);
+    println!(
+        "demodulation of a {NUM_SUBCARRIERS} subcarrier symbol with {vectorized}: {elapsed:.2} us"
+    );
+}
@@ -0,0 +1,40 @@
+//! Times the modulation of one symbol with a reused scratch, for symbol sizes up to 4096 subcarriers.
+//!
+//! Run with `cargo bench --bench modulator`.
+
+use std::{hint::black_box, time::Instant};
+
+use software_modem::ofdm::{OFDMConfig, modulator::OFDMModulator};
+
+fn main() {
+    for num_subcarriers in [256, 1024, 4096] {
+        let config = OFDMConfig {
+            num_subcarriers,
+            cyclic_prefix_length: num_subcarriers / 8,
+            ..Default::default()
+        };
+        let modulator = OFDMModulator::new((&config).into());
+        let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect();
+        let mut scratch = modulator.make_scratch();
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+
+        // the best of a number of runs, long enough to average out the timer
+        let num_symbols = 200_000 / num_subcarriers as usize;
+        let best = (0..20)
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..num_symbols {
+                    modulator.modulate_buffer_as_symbol_with_scratch(
+                        black_box(&data),
+                        &mut scratch,
+                        &mut symbol,
+                    );
+                }
+                start.elapsed().as_secs_f64() * 1e6 / num_symbols as f64
+            })
+            .fold(f64::INFINITY, f64::min);
+        println!("{num_subcarriers} subcarriers: {best:.2} us per symbol");
+    }
+}
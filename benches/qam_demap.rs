@@ -0,0 +1,60 @@
+//! Times the hard decisions of 1M noisy points of every constellation, with the scalar slicer and every demap strategy.
+//!
+//! Run with `cargo bench --bench qam_demap`.
+
+use std::{hint::black_box, time::Instant};
+
+use realfft::num_complex::Complex32;
+use software_modem::qam::{DemapStrategy, QAMModem, QAMOrder, slice_qam};
+
+const NUM_POINTS: usize = 1 << 20;
+
+/// Returns the best time of a number of runs in milliseconds.
+fn time(mut run: impl FnMut()) -> f64 {
+    (0..20)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed().as_secs_f64() * 1e3
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    for order in QAMOrder::ALL {
+        let modem = QAMModem::new(order);
+        let num_bytes = NUM_POINTS * modem.bits_per_symbol() as usize / 8;
+        let data: Vec<u8> = (0..num_bytes as u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect();
+        let mut state: u32 = 0x1234_5678;
+        let points: Vec<Complex32> = modem
+            .modulate(&data)
+            .iter()
+            .map(|point| {
+                let mut noise = || {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as f32 / u32::MAX as f32 - 0.5
+                };
+                point + Complex32::new(noise(), noise())
+            })
+            .collect();
+
+        let mut output = vec![0; data.len()];
+        let scalar = time(|| slice_qam(order, black_box(&points), &mut output));
+        assert_eq!(output, data);
+        println!("{order} scalar slicer: {scalar:.3} ms");
+
+        for strategy in [DemapStrategy::Slicer, DemapStrategy::Table] {
+            let modem = QAMModem::with_demap_strategy(order, strategy);
+            let elapsed = time(|| modem.demodulate_into(black_box(&points), &mut output));
+            assert_eq!(output, data);
+            println!(
+                "{order} {strategy:?}: {elapsed:.3} ms, {:.1}x the scalar slicer",
+                scalar / elapsed
+            );
+        }
+    }
+}
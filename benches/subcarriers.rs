@@ -0,0 +1,77 @@
+//! Times the modulation and demodulation of one symbol with reused scratches for large subcarrier counts,
+//! where packing the points into the bins and out of them is a measurable share next to the FFT.
+//!
+//! Run with `cargo bench --bench subcarriers`.
+
+use std::{hint::black_box, time::Instant};
+
+use software_modem::ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator};
+
+/// Returns the best time of a number of runs in microseconds per call.
+fn time(calls: usize, mut run: impl FnMut()) -> f64 {
+    (0..20)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..calls {
+                run();
+            }
+            start.elapsed().as_secs_f64() * 1e6 / calls as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    for num_subcarriers in [1024, 4096, 16384] {
+        let config = OFDMConfig {
+            num_subcarriers,
+            cyclic_prefix_length: num_subcarriers / 8,
+            ..Default::default()
+        };
+        // the allocated power and the pilots every 4 subcarriers give every data bin two passes and short runs
+        let num_data_subcarriers = 2 * OFDMModulator::new((&config).into()).get_bytes_per_symbol();
+        let shaped = OFDMConfig {
+            power_allocation: Some(
+                (0..num_data_subcarriers)
+                    .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                    .collect(),
+            ),
+            ..config.clone()
+        };
+        let sparse = OFDMConfig {
+            pilot_subcarrier_every: 64,
+            ..config
+        };
+
+        for (name, config) in [("pilots every 4", shaped), ("pilots every 64", sparse)] {
+            let modulator = OFDMModulator::new((&config).into());
+            let demodulator = OFDMDemodulator::new((&config).into());
+            let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+                .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+                .collect();
+            let mut modulator_scratch = modulator.make_scratch();
+            let mut demodulator_scratch = demodulator.make_scratch();
+            let mut symbol = vec![0.0; modulator.get_symbol_length()];
+            let mut output = vec![0; data.len()];
+
+            let calls = 400_000 / num_subcarriers as usize;
+            let modulate = time(calls, || {
+                modulator.modulate_buffer_as_symbol_with_scratch(
+                    black_box(&data),
+                    &mut modulator_scratch,
+                    &mut symbol,
+                )
+            });
+            let demodulate = time(calls, || {
+                demodulator.demodulate_symbol_into(
+                    black_box(&symbol),
+                    &mut demodulator_scratch,
+                    &mut output,
+                )
+            });
+            assert_eq!(output, data);
+            println!(
+                "{num_subcarriers} subcarriers, {name}: modulate {modulate:.2} us, demodulate {demodulate:.2} us"
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Times the receiver end to end against real time at 48 kHz, for a small and a large FFT:
+//! the modulation and demodulation of uncoded frames, the coded decoding of a frame,
+//! and the stream demodulator, which also searches the offset of every frame.
+//!
+//! A rate of 4x real time means a second of samples at 48 kHz is processed in a quarter of a second.
+//!
+//! On one x86-64 core with 1024 subcarriers, the soft demapping working on every axis on its own took
+//! the soft demodulation of a frame from 608 to 68 us, and with the equalization of the points only and
+//! the unrolled bit packing the coded decoding from 6.7 to 5.5 ms, most of which is the Viterbi decoder.
+//!
+//! Run with `cargo bench --bench throughput`.
+
+use std::{hint::black_box, time::Instant};
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::{CodingConfig, FrameDecoder, FrameEncoder},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    stream::StreamDemodulator,
+};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+
+/// Returns the best time of a number of runs in seconds.
+fn time(runs: usize, mut run: impl FnMut()) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn report(name: &str, samples: usize, seconds: f64) {
+    println!(
+        "  {name:<24} {:>9.1} us, {:>7.1}x real time",
+        seconds * 1e6,
+        samples as f64 / SAMPLE_RATE / seconds
+    );
+}
+
+fn main() {
+    for num_subcarriers in [64, 1024] {
+        let config = OFDMConfig {
+            num_subcarriers,
+            cyclic_prefix_length: num_subcarriers / 8,
+            ..Default::default()
+        };
+        let runs = 20;
+        let payload: Vec<u8> = (0..4000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect();
+        println!(
+            "{num_subcarriers} subcarriers, {} payload bytes:",
+            payload.len()
+        );
+
+        // uncoded frames
+        let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+        let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+        let frame = encoder.encode(&payload);
+        let modulate = time(runs, || {
+            black_box(encoder.encode(black_box(&payload)));
+        });
+        report("modulate", frame.len(), modulate);
+        let demodulate = time(runs, || {
+            black_box(decoder.decode(black_box(&frame)));
+        });
+        report("demodulate hard", frame.len(), demodulate);
+        let demodulate_soft = time(runs, || {
+            black_box(decoder.decode_soft(black_box(&frame)));
+        });
+        report("demodulate soft", frame.len(), demodulate_soft);
+        assert_eq!(&decoder.decode(&frame)[..payload.len()], payload);
+
+        // coded frames, with the Viterbi decoder
+        let coding = CodingConfig::default();
+        let modulator = CodedOFDMModulator::new(config.clone(), coding.clone());
+        let demodulator = CodedOFDMDemodulator::new(config.clone(), coding.clone());
+        let frame = modulator.encode_frame(&payload);
+        let decode = time(runs, || {
+            black_box(demodulator.decode_frame(black_box(&frame)).unwrap());
+        });
+        report("decode coded", frame.len(), decode);
+
+        // the stream, with the offset search of the frame starting off the first sample of a block
+        let mut stream = vec![0.0; 1000 + num_subcarriers as usize / 3];
+        stream.extend(&frame);
+        stream.extend(vec![0.0; 5000]);
+        let sync = time(runs.min(5), || {
+            let mut demodulator = StreamDemodulator::new(
+                CodedOFDMDemodulator::new(config.clone(), coding.clone()),
+                0.01,
+                2400,
+            );
+            let decoded: Vec<Vec<u8>> = stream
+                .chunks(128)
+                .flat_map(|block| demodulator.push(black_box(block)))
+                .collect();
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0], payload);
+        });
+        report("stream with sync search", stream.len(), sync);
+    }
+}
@@ -0,0 +1,68 @@
+//! A `#![no_std]` receiver of the [heapless demodulator](software_modem::ofdm::embedded), the way firmware uses it:
+//! the demodulator is made once, and every block of samples from the ADC is demodulated into a buffer of its own,
+//! with neither `std` nor `alloc` in this crate.
+
+#![no_std]
+
+use software_modem::{error::ModemError, ofdm::embedded::HeaplessOFDMDemodulator};
+
+/// Number of subcarriers of the link.
+pub const NUM_SUBCARRIERS: usize = 64;
+/// Length of the cyclic prefix of the link in samples.
+pub const CYCLIC_PREFIX_LENGTH: usize = 16;
+/// Interval of the pilot subcarriers of the link.
+pub const PILOT_SUBCARRIER_EVERY: usize = 4;
+
+/// Demodulates the symbols of the link, see [receive](Receiver::receive).
+pub struct Receiver {
+    demodulator: HeaplessOFDMDemodulator<NUM_SUBCARRIERS, CYCLIC_PREFIX_LENGTH>,
+}
+
+impl Receiver {
+    /// Creates a new receiver, computing the twiddle factors of its FFT.
+    pub fn new() -> Self {
+        Receiver {
+            demodulator: HeaplessOFDMDemodulator::new(PILOT_SUBCARRIER_EVERY),
+        }
+    }
+
+    /// Returns the number of samples of a symbol.
+    pub fn get_symbol_length(&self) -> usize {
+        self.demodulator.get_symbol_length()
+    }
+
+    /// Returns the number of bytes of a symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        self.demodulator.get_bytes_per_symbol()
+    }
+
+    /// Demodulates the whole symbols of the samples into the output, and returns the number of bytes written.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the output is too short for the symbols, with the bytes they need.
+    pub fn receive(&mut self, samples: &[f32], output: &mut [u8]) -> Result<usize, ModemError> {
+        let (symbol_length, bytes_per_symbol) =
+            (self.get_symbol_length(), self.get_bytes_per_symbol());
+        let expected = samples.len() / symbol_length * bytes_per_symbol;
+        if output.len() < expected {
+            return Err(ModemError::BufferLength {
+                expected,
+                got: output.len(),
+            });
+        }
+        for (symbol, output) in samples
+            .chunks_exact(symbol_length)
+            .zip(output.chunks_exact_mut(bytes_per_symbol))
+        {
+            self.demodulator
+                .try_demodulate_symbol_into(symbol, output)?;
+        }
+        Ok(expected)
+    }
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
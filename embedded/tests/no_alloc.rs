@@ -0,0 +1,88 @@
+//! Checks that the receiver demodulates the symbols of a 64-subcarrier modulator without a single heap allocation,
+//! with an allocator wrapping the system allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use software_modem::{
+    error::ModemError,
+    ofdm::{OFDMConfig, modulator::OFDMModulator},
+};
+use software_modem_embedded::{
+    CYCLIC_PREFIX_LENGTH, NUM_SUBCARRIERS, PILOT_SUBCARRIER_EVERY, Receiver,
+};
+
+/// The system allocator, counting the allocations of every thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// SAFETY: every call is passed on to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // the counter of an exiting thread may be gone already
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        // SAFETY: the caller upholds the contract of alloc
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of dealloc
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        // SAFETY: the caller upholds the contract of realloc
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations `f` makes on this thread.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn symbols_demodulate_without_allocating() {
+    let modulator = OFDMModulator::new(
+        (&OFDMConfig {
+            num_subcarriers: NUM_SUBCARRIERS as u32,
+            cyclic_prefix_length: CYCLIC_PREFIX_LENGTH as u32,
+            pilot_subcarrier_every: PILOT_SUBCARRIER_EVERY as u32,
+            ..Default::default()
+        })
+            .into(),
+    );
+    let data: Vec<u8> = (0..10 * modulator.get_bytes_per_symbol() as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let mut samples = Vec::new();
+    modulator.modulate_batch(&data, &mut samples);
+
+    // made before the first block, like at the start of the firmware
+    let mut receiver = Receiver::new();
+    let mut output = [0; 240];
+    let mut received = Ok(0);
+    let allocations = count_allocations(|| received = receiver.receive(&samples, &mut output));
+    assert_eq!(allocations, 0);
+    assert_eq!(received, Ok(240));
+    assert_eq!(output[..], data);
+
+    assert_eq!(
+        receiver.receive(&samples, &mut output[..239]),
+        Err(ModemError::BufferLength {
+            expected: 240,
+            got: 239
+        })
+    );
+}
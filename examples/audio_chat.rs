@@ -0,0 +1,86 @@
+//! A chat between two laptops, each running this example next to the other: the lines typed on the console
+//! are played as frames on the default output device, and the frames heard by the default input device are printed.
+//!
+//! Run with `cargo run --example audio_chat --features cpal`, and end the input with Ctrl-D.
+
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use software_modem::audio::device::cpal::{self, traits::HostTrait};
+use software_modem::audio::{AudioReceiver, AudioTransmitter, ReceiverConfig, TransmitterConfig};
+use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+use software_modem::frame::CodingConfig;
+use software_modem::ofdm::OFDMConfig;
+use software_modem::ofdm::modulator::OutputScale;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // the subcarriers stay below the passband of the resamplers, for sound cards at 44.1 kHz
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        guard_subcarriers_high: 18,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+
+    let host = cpal::default_host();
+    let speaker = host
+        .default_output_device()
+        .ok_or("No default output device")?;
+    let microphone = host
+        .default_input_device()
+        .ok_or("No default input device")?;
+    let (transmitter, output) =
+        AudioTransmitter::open(&speaker, modulator, &TransmitterConfig::default())?;
+    let (receiver, input) =
+        AudioReceiver::open(&microphone, demodulator, &ReceiverConfig::default())?;
+    println!(
+        "Playing on {} at {} Hz, listening on {} at {} Hz",
+        speaker,
+        output.get_config().sample_rate,
+        microphone,
+        input.get_config().sample_rate
+    );
+
+    let done = Arc::new(AtomicBool::new(false));
+    let printer = {
+        let done = done.clone();
+        std::thread::spawn(move || {
+            while !done.load(Ordering::Acquire) {
+                if let Some(payload) = receiver.recv_timeout(Duration::from_millis(100)) {
+                    let metrics = receiver.metrics();
+                    println!(
+                        "> {} ({:.1} dBFS, {} frames, {} failed, {} overruns)",
+                        String::from_utf8_lossy(&payload),
+                        metrics.input_level_db,
+                        metrics.frames_decoded,
+                        metrics.frames_failed,
+                        metrics.overruns
+                    );
+                }
+            }
+        })
+    };
+
+    for line in std::io::stdin().lock().lines() {
+        transmitter.send(line?.as_bytes())?;
+        for error in [output.take_error(), input.take_error()]
+            .into_iter()
+            .flatten()
+        {
+            eprintln!("{}", error);
+        }
+    }
+    transmitter.shutdown();
+    while !output.is_finished() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    done.store(true, Ordering::Release);
+    printer.join().unwrap();
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Sends every line typed on the console as a modem frame, played on the default output device through an AudioTransmitter.
+//!
+//! Run with `cargo run --example audio_transmit --features cpal`, and end the input with Ctrl-D,
+//! which plays the queued frames before it exits.
+
+use std::io::BufRead;
+use std::time::Duration;
+
+use software_modem::audio::device::cpal::{self, traits::HostTrait};
+use software_modem::audio::{AudioTransmitter, TransmitterConfig};
+use software_modem::coded::CodedOFDMModulator;
+use software_modem::frame::CodingConfig;
+use software_modem::ofdm::OFDMConfig;
+use software_modem::ofdm::modulator::OutputScale;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm, CodingConfig::default());
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No default output device")?;
+    let (transmitter, output) =
+        AudioTransmitter::open(&device, modulator, &TransmitterConfig::default())?;
+    let config = output.get_config();
+    println!(
+        "Playing on {} at {} Hz, {} channels of {}",
+        device,
+        config.sample_rate,
+        config.channels,
+        output.get_sample_format()
+    );
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        transmitter.send(line.as_bytes())?;
+        println!(
+            "Queued {} bytes, {} samples waiting",
+            line.len(),
+            transmitter.pending_samples()
+        );
+        if let Some(error) = output.take_error() {
+            eprintln!("{}", error);
+        }
+    }
+    transmitter.shutdown();
+    while !output.is_finished() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
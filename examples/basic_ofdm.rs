@@ -8,19 +8,15 @@ fn main() {
         cyclic_prefix_length: 4,
         pilot_subcarrier_every: 4,
         qam_order: QAMOrder::QAM16,
-        fft: None,
+        ..Default::default()
     });
 
     let test_data = "Hello, OFDM!";
 
-    // move test_data into buffer of correct size
-    let mut data_buffer = vec![0; 32 - 6 - 2]; // 32 bytes for QAM16 (4bits) * 64 Subcarriers minus 6 pilot subcarriers and first and last subcarrier
-    data_buffer[..test_data.len()].copy_from_slice(test_data.as_bytes());
-
-    // modulate the buffer
-    let mut modulated_symbol = vec![0.0; ofdm_modulator.get_symbol_length()];
-
-    ofdm_modulator.modulate_buffer_as_symbol(&data_buffer, &mut modulated_symbol);
+    // modulate the data, padded with zeros to the 24 bytes of a symbol: 4 bits on each of the 48 data subcarriers
+    let modulated_symbol = ofdm_modulator
+        .modulate_symbol(test_data.as_bytes())
+        .unwrap();
     println!("Modulated Symbol: {:?}", &modulated_symbol[..8]); // print first 8 samples
 
     let ofdm_demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
@@ -28,7 +24,7 @@ fn main() {
         cyclic_prefix_length: 4,
         pilot_subcarrier_every: 4,
         qam_order: QAMOrder::QAM16,
-        fft: None,
+        ..Default::default()
     });
 
     // demodulate the symbol
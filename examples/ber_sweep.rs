@@ -0,0 +1,63 @@
+//! Prints the waterfall curves of uncoded QPSK, QAM-16 and QAM-64 and of QAM-16 with the rate 1/2 convolutional code
+//! over AWGN, the bit and frame error rates over the SNR with the 95 % confidence intervals of the bit error rates.
+//!
+//! Every point sends frames until it has 200 bit errors, or 500 frames at high SNRs.
+//! Run with `cargo run --release --example ber_sweep`.
+
+use software_modem::frame::CodingConfig;
+use software_modem::ofdm::{GuardInterval, OFDMConfig};
+use software_modem::qam::QAMOrder;
+use software_modem::testing::{SweepConfig, SweepStop, ber_sweep};
+
+fn main() {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let config = SweepConfig {
+        ofdm: OFDMConfig {
+            num_subcarriers: 64,
+            guard_interval: Some(GuardInterval::Sixteenth),
+            ..Default::default()
+        },
+        snr_start_db: 4.0,
+        snr_stop_db: 24.0,
+        snr_step_db: 2.0,
+        stop: SweepStop::BitErrors {
+            bit_errors: 200,
+            max_frames: 500,
+        },
+        threads,
+        ..Default::default()
+    };
+
+    for (qam_order, coding) in [
+        (QAMOrder::QPSK, None),
+        (QAMOrder::QAM16, None),
+        (QAMOrder::QAM64, None),
+        (QAMOrder::QAM16, Some(CodingConfig::default())),
+    ] {
+        match coding {
+            Some(_) => println!("{qam_order}, convolutional rate 1/2"),
+            None => println!("{qam_order}, uncoded"),
+        }
+        println!("SNR [dB]  BER       95 % interval          FER     frames");
+        for point in ber_sweep(&SweepConfig {
+            ofdm: OFDMConfig {
+                qam_order,
+                ..config.ofdm.clone()
+            },
+            coding,
+            ..config.clone()
+        }) {
+            let (low, high) = point.confidence_interval;
+            println!(
+                "{:8.1}  {:8.2e}  {:8.2e} .. {:8.2e}  {:6.3}  {:6}",
+                point.snr_db,
+                point.bit_error_rate,
+                low,
+                high,
+                point.frame_error_rate,
+                point.meter.num_frames()
+            );
+        }
+        println!();
+    }
+}
@@ -11,7 +11,7 @@ fn main() {
         cyclic_prefix_length: 4,
         pilot_subcarrier_every: 4,
         qam_order: QAMOrder::QAM16,
-        fft: None,
+        ..Default::default()
     });
 
     // demodulate the symbol
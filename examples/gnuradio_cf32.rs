@@ -0,0 +1,102 @@
+//! Demodulates a GNU Radio `complex64` capture of complex OFDM symbols, streaming it symbol by symbol.
+//!
+//! The capture in `examples/data/hello.cf32` holds 3 symbols of 64 subcarriers with a cyclic prefix of 8,
+//! received with a carrier offset of 0.1 subcarrier spacings, a lower level and some noise, as a File Sink would store it.
+//! Run with `cargo run --example gnuradio_cf32`, pass the path of another capture to demodulate it instead,
+//! or write a fresh one with `cargo run --example gnuradio_cf32 -- --write capture.cf32`.
+
+use realfft::num_complex::Complex32;
+use software_modem::io::gr::{GrError, read_cf32, write_cf32};
+use software_modem::ofdm::{
+    GuardInterval,
+    complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator},
+};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/hello.cf32");
+
+fn config() -> ComplexOFDMConfig {
+    ComplexOFDMConfig {
+        num_subcarriers: 64,
+        guard_interval: Some(GuardInterval::Eighth),
+        ..Default::default()
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.as_slice() {
+        [flag, path] if flag == "--write" => {
+            write_capture(path)?;
+            println!("Wrote a capture to {}", path);
+            return Ok(());
+        }
+        [path] => path.as_str(),
+        _ => FIXTURE,
+    };
+
+    let demodulator = ComplexOFDMDemodulator::new(config());
+    let mut reader = read_cf32(path)?;
+    let mut symbol = vec![Complex32::default(); demodulator.get_symbol_length()];
+    let mut payload = Vec::new();
+    loop {
+        match reader.read_chunk(&mut symbol) {
+            Ok(0) => break,
+            Ok(count) if count < symbol.len() => {
+                println!("Dropped {} samples of a partial symbol", count);
+                break;
+            }
+            Ok(_) => {}
+            // a capture cut off within a sample still has its whole symbols
+            Err(GrError::TruncatedSample { trailing_bytes }) => {
+                println!("Dropped {} bytes of a partial sample", trailing_bytes);
+                break;
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        // the offset is estimated from the prefix of every symbol, the equalization takes care of the phase between them
+        let offset = demodulator.estimate_frequency_offset(&symbol);
+        demodulator.correct_frequency_offset(&mut symbol, offset);
+        println!("Symbol with a carrier offset of {:.3} subcarriers", offset);
+        payload.extend(demodulator.demodulate_symbol_from_buffer(&symbol));
+    }
+
+    println!("Decoded: {}", String::from_utf8_lossy(&payload).trim_end());
+    Ok(())
+}
+
+/// Writes the symbols of the payload, as received with a carrier offset, a lower level and some noise.
+fn write_capture(path: &str) -> Result<(), GrError> {
+    let modulator = ComplexOFDMModulator::new(config());
+    let text = "Hello from software-modem, through a GNU Radio file sink and back!";
+    let mut payload = text.as_bytes().to_vec();
+    payload.resize(
+        payload
+            .len()
+            .next_multiple_of(modulator.get_bytes_per_symbol()),
+        b' ',
+    );
+    let transmitted = modulator.modulate_symbols(&payload);
+
+    let offset = 0.1;
+    let mut noise: u32 = 0x1234_5678;
+    let mut uniform = move || {
+        noise ^= noise << 13;
+        noise ^= noise >> 17;
+        noise ^= noise << 5;
+        noise as f32 / u32::MAX as f32 - 0.5
+    };
+    let received: Vec<Complex32> = transmitted
+        .iter()
+        .enumerate()
+        .map(|(n, &sample)| {
+            let phase = 0.7 + std::f32::consts::TAU * offset * n as f32 / 64.0;
+            sample * Complex32::from_polar(0.4, phase) + 0.01 * Complex32::new(uniform(), uniform())
+        })
+        .collect();
+
+    let mut writer = write_cf32(path)?;
+    writer.write(&received)?;
+    writer.finish()?;
+    Ok(())
+}
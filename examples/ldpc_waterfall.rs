@@ -0,0 +1,73 @@
+use software_modem::fec::convolutional::ConvolutionalCode;
+use software_modem::fec::ldpc::{LdpcCode, LdpcDecoder, LdpcDecoderConfig};
+
+/// Small xorshift generator, good enough for noise in a simulation.
+struct Noise(u64);
+
+impl Noise {
+    fn uniform(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 40) as f32 + 0.5) / (1u64 << 24) as f32
+    }
+
+    fn gaussian(&mut self) -> f32 {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
+}
+
+/// Sends BPSK over an AWGN channel and returns the LLRs of the received bits.
+fn channel(bits: &[u8], sigma: f32, noise: &mut Noise) -> Vec<f32> {
+    bits.iter()
+        .map(|&bit| {
+            let received = if bit == 0 { 1.0 } else { -1.0 } + sigma * noise.gaussian();
+            2.0 * received / (sigma * sigma)
+        })
+        .collect()
+}
+
+fn main() {
+    let ldpc = LdpcDecoder::new(LdpcCode::wifi_648_rate_half(), LdpcDecoderConfig::default());
+    let convolutional = ConvolutionalCode::k7_rate_half();
+    let num_frames = 200;
+    let mut noise = Noise(0x2545_f491_4f6c_dd1d);
+
+    println!("Eb/N0 [dB]  BER LDPC  BER convolutional (both rate 1/2, {num_frames} frames)");
+    for eb_n0_db in [1.0, 1.5, 2.0, 2.5, 3.0] {
+        let eb_n0 = 10f32.powf(eb_n0_db / 10.0);
+        let sigma = (1.0 / (2.0 * 0.5 * eb_n0)).sqrt();
+
+        let (mut ldpc_errors, mut convolutional_errors, mut num_bits) = (0, 0, 0);
+        for _ in 0..num_frames {
+            let message: Vec<u8> = (0..ldpc.code().k())
+                .map(|_| u8::from(noise.uniform() < 0.5))
+                .collect();
+
+            let decoded = ldpc.decode(&channel(&ldpc.code().encode(&message), sigma, &mut noise));
+            ldpc_errors += decoded
+                .bits
+                .iter()
+                .zip(&message)
+                .filter(|(a, b)| a != b)
+                .count();
+
+            let decoded = convolutional.decode_soft(&channel(
+                &convolutional.encode(&message),
+                sigma,
+                &mut noise,
+            ));
+            convolutional_errors += decoded.iter().zip(&message).filter(|(a, b)| a != b).count();
+
+            num_bits += message.len();
+        }
+
+        println!(
+            "{:10.1}  {:8.2e}  {:8.2e}",
+            eb_n0_db,
+            ldpc_errors as f32 / num_bits as f32,
+            convolutional_errors as f32 / num_bits as f32
+        );
+    }
+}
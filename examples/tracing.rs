@@ -0,0 +1,61 @@
+//! Prints the events of a stream decoding a capture with the `fmt` subscriber of `tracing-subscriber`.
+//!
+//! The capture holds two frames through an AWGN channel, the second one with its payload drowned in noise,
+//! which the stream syncs to and fails to decode. Run with `cargo run --example tracing --features tracing`,
+//! and with `-- trace` to see every offset tried and the EVM of every symbol as well.
+
+use software_modem::channel::{AwgnChannel, Channel};
+use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+use software_modem::frame::CodingConfig;
+use software_modem::ofdm::OFDMConfig;
+use software_modem::ofdm::modulator::OutputScale;
+use software_modem::stream::StreamDemodulator;
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+fn main() {
+    let subscriber = tracing_subscriber::fmt().with_writer(std::io::stderr);
+    match std::env::args().nth(1).as_deref() {
+        // the closed spans show the fields recorded after they were opened, like the EVM of a symbol
+        Some("trace") => subscriber
+            .with_max_level(Level::TRACE)
+            .with_span_events(FmtSpan::CLOSE)
+            .init(),
+        _ => subscriber.with_max_level(Level::DEBUG).init(),
+    }
+
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    let symbol_length = demodulator.get_symbol_length();
+
+    let mut capture = vec![0.0; 1000];
+    for (index, payload) in [&b"a clean frame"[..], b"a lost frame"]
+        .into_iter()
+        .enumerate()
+    {
+        let mut frame = modulator.encode_frame(payload);
+        AwgnChannel::new(20.0, 1).apply(&mut frame);
+        if index == 1 {
+            AwgnChannel::new(-10.0, 2).apply(&mut frame[symbol_length..]);
+        }
+        capture.extend(frame);
+        capture.extend([0.0; 2000]);
+    }
+
+    let mut stream = StreamDemodulator::new(demodulator, 0.05, 1000);
+    for block in capture.chunks(480) {
+        for payload in stream.push(block) {
+            println!("received {:?}", String::from_utf8_lossy(&payload));
+        }
+    }
+}
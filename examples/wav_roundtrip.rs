@@ -0,0 +1,124 @@
+//! Encodes a payload into a WAV file, simulates recording it on another machine, and decodes the recording.
+//!
+//! Run with `cargo run --example wav_roundtrip --features wav`, or pass the path of a real recording
+//! of the transmitted file, at 48 kHz, to decode it instead: `cargo run --example wav_roundtrip --features wav -- recording.wav`.
+
+use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+use software_modem::frame::CodingConfig;
+use software_modem::io::{
+    WavLevel, WavReadOptions, WavWriteOptions, read_wav_with, write_wav, write_wav_with,
+};
+use software_modem::ofdm::OFDMConfig;
+use software_modem::ofdm::modulator::OutputScale;
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    let coding = CodingConfig::default();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), coding);
+
+    let payload = "Hello from one laptop to another!".as_bytes();
+    let samples = modulator.encode_frame(payload);
+    let transmit_path = std::env::temp_dir().join("software_modem_transmit.wav");
+    write_wav(&transmit_path, &samples, SAMPLE_RATE)?;
+    println!(
+        "Wrote {} samples of {} bytes to {}",
+        samples.len(),
+        payload.len(),
+        transmit_path.display()
+    );
+
+    let recording_path = match std::env::args().nth(1) {
+        Some(path) => path.into(),
+        None => {
+            let recording_path = std::env::temp_dir().join("software_modem_recording.wav");
+            simulate_recording(&transmit_path, &recording_path)?;
+            println!("Simulated a recording in {}", recording_path.display());
+            recording_path
+        }
+    };
+
+    // the recording has another level than the transmitted file, and has to be at the rate of the modem
+    let (recording, _) = read_wav_with(
+        &recording_path,
+        &WavReadOptions {
+            level: WavLevel::PeakNormalize(1.0),
+            expected_sample_rate: Some(SAMPLE_RATE),
+            ..Default::default()
+        },
+    )?;
+
+    let symbol_length = (ofdm.num_subcarriers * 2 + ofdm.cyclic_prefix_length * 2) as usize;
+    match find_frame(&demodulator, &recording, symbol_length) {
+        Some((offset, decoded)) => {
+            println!("Found the frame at sample {}", offset);
+            println!("Decoded: {}", String::from_utf8_lossy(&decoded));
+            assert_eq!(decoded, payload);
+        }
+        None => println!("No frame found in {} samples", recording.len()),
+    }
+    Ok(())
+}
+
+/// Plays the transmitted file into a simulated stereo recording, which starts some time before the frame,
+/// with a lower level and some noise.
+fn simulate_recording(
+    transmit_path: &std::path::Path,
+    recording_path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (transmitted, sample_rate) = read_wav_with(transmit_path, &WavReadOptions::default())?;
+
+    let delay = 17771;
+    let mut noise: u32 = 0x1234_5678;
+    let recording: Vec<f32> = (0..delay + transmitted.len() + 5000)
+        .map(|n| {
+            noise ^= noise << 13;
+            noise ^= noise >> 17;
+            noise ^= noise << 5;
+            let signal = n
+                .checked_sub(delay)
+                .and_then(|n| transmitted.get(n))
+                .unwrap_or(&0.0);
+            0.3 * signal + 0.002 * (noise as f32 / u32::MAX as f32 - 0.5)
+        })
+        .collect();
+
+    write_wav_with(
+        recording_path,
+        &recording,
+        sample_rate,
+        &WavWriteOptions {
+            channels: 2,
+            ..Default::default()
+        },
+    )?;
+    Ok(())
+}
+
+/// Finds the start of a frame in a recording that is not aligned to it, and returns it with the decoded payload.
+///
+/// The frame rises out of the silence within its first symbol, the offsets up to one symbol before that
+/// are tried until one decodes with a valid header and CRC.
+fn find_frame(
+    demodulator: &CodedOFDMDemodulator,
+    recording: &[f32],
+    symbol_length: usize,
+) -> Option<(usize, Vec<u8>)> {
+    let peak = recording.iter().map(|x| x.abs()).fold(0.0, f32::max);
+    let onset = recording.iter().position(|x| x.abs() > 0.25 * peak)?;
+
+    (onset.saturating_sub(symbol_length)..=onset).find_map(|offset| {
+        demodulator
+            .decode_frame(&recording[offset..])
+            .ok()
+            .map(|decoded| (offset, decoded))
+    })
+}
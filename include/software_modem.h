@@ -0,0 +1,338 @@
+#ifndef SOFTWARE_MODEM_H
+#define SOFTWARE_MODEM_H
+
+/* Declarations of src/ffi.rs, regenerate with `cbindgen --config cbindgen.toml --output include/software_modem.h`. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Status returned by the functions of the C API.
+ */
+typedef enum SmStatus {
+  SmStatus_Ok = 0,
+  /**
+   * A handle or buffer is null.
+   */
+  SmStatus_NullPointer = 1,
+  /**
+   * The output buffer is too small, the required length has been written to `out_len`.
+   */
+  SmStatus_BufferTooSmall = 2,
+  /**
+   * The frame could not be decoded.
+   */
+  SmStatus_DecodeFailed = 3,
+  /**
+   * The modem panicked, for example on an invalid configuration or a payload longer than 65535 bytes.
+   */
+  SmStatus_Panic = 4,
+} SmStatus;
+
+/**
+ * A coded demodulator, created by [sm_demodulator_new].
+ */
+typedef struct SmDemodulator SmDemodulator;
+
+/**
+ * A coded modulator, created by [sm_modulator_new].
+ */
+typedef struct SmModulator SmModulator;
+
+/**
+ * A demodulator of single OFDM symbols, created by [sm_ofdm_demodulator_new].
+ */
+typedef struct SmOfdmDemodulator SmOfdmDemodulator;
+
+/**
+ * A modulator of single OFDM symbols, created by [sm_ofdm_modulator_new].
+ */
+typedef struct SmOfdmModulator SmOfdmModulator;
+
+/**
+ * Parameters of a modem created through the C API, the others keep their defaults.
+ *
+ * Both ends of a link must use the same configuration.
+ */
+typedef struct SmConfig {
+  /**
+   * See [OFDMConfig::num_subcarriers].
+   */
+  uint32_t num_subcarriers;
+  /**
+   * See [OFDMConfig::cyclic_prefix_length].
+   */
+  uint32_t cyclic_prefix_length;
+  /**
+   * See [OFDMConfig::pilot_subcarrier_every].
+   */
+  uint32_t pilot_subcarrier_every;
+  /**
+   * See [OFDMConfig::differential_time].
+   */
+  bool differential_time;
+  /**
+   * See [CodingConfig::reed_solomon].
+   */
+  bool reed_solomon;
+  /**
+   * Peak of the modulated frames, see [OutputScale::PeakNormalize], or 0 for the raw samples.
+   */
+  float output_peak;
+} SmConfig;
+
+/**
+ * Returns a configuration of 64 subcarriers with a cyclic prefix of 4 and a pilot on every 4th subcarrier,
+ * coherent, without Reed-Solomon code, and raw samples.
+ */
+struct SmConfig sm_config_default(void);
+
+/**
+ * Returns the message of the last failure on the calling thread, or an empty string.
+ *
+ * The string stays valid until the next failure on the thread, and must not be freed.
+ */
+const char *sm_last_error_message(void);
+
+/**
+ * Creates a modulator, to be freed with [sm_modulator_free], or returns null on an invalid configuration.
+ *
+ * # Safety
+ * The configuration must be null or point to a valid [SmConfig].
+ */
+struct SmModulator *sm_modulator_new(const struct SmConfig *config);
+
+/**
+ * Frees a modulator, null is ignored.
+ *
+ * # Safety
+ * The modulator must be null or a handle returned by [sm_modulator_new], which is not used afterwards.
+ */
+void sm_modulator_free(struct SmModulator *modulator);
+
+/**
+ * Encodes a payload of `len` bytes into a frame of samples.
+ *
+ * Writes the number of samples of the frame to `out_len`. If it exceeds `out_cap`,
+ * returns [SmStatus::BufferTooSmall] without writing any samples, so a call with an empty buffer
+ * returns the length to allocate.
+ *
+ * # Safety
+ * The modulator must be a handle returned by [sm_modulator_new], `data` must point to `len` bytes
+ * and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_modulate(const struct SmModulator *modulator,
+                          const uint8_t *data,
+                          size_t len,
+                          float *out,
+                          size_t out_cap,
+                          size_t *out_len);
+
+/**
+ * Creates a demodulator, to be freed with [sm_demodulator_free], or returns null on an invalid configuration.
+ *
+ * # Safety
+ * The configuration must be null or point to a valid [SmConfig].
+ */
+struct SmDemodulator *sm_demodulator_new(const struct SmConfig *config);
+
+/**
+ * Frees a demodulator, null is ignored.
+ *
+ * # Safety
+ * The demodulator must be null or a handle returned by [sm_demodulator_new], which is not used afterwards.
+ */
+void sm_demodulator_free(struct SmDemodulator *demodulator);
+
+/**
+ * Decodes a frame of `len` samples into its payload.
+ *
+ * Writes the number of payload bytes to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+ * without writing the payload, 65535 bytes always suffice.
+ *
+ * # Safety
+ * The demodulator must be a handle returned by [sm_demodulator_new], `samples` must point to `len` floats
+ * and `out` to `out_cap` bytes, or be null if their length is 0, and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_demodulate(const struct SmDemodulator *demodulator,
+                            const float *samples,
+                            size_t len,
+                            uint8_t *out,
+                            size_t out_cap,
+                            size_t *out_len);
+
+/**
+ * Creates a modulator of single OFDM symbols, to be freed with [sm_ofdm_modulator_free],
+ * or returns null on an invalid configuration.
+ *
+ * The differential encoding and the Reed-Solomon code of the configuration belong to the frames, they are ignored.
+ *
+ * # Safety
+ * The configuration must be null or point to a valid [SmConfig].
+ */
+struct SmOfdmModulator *sm_ofdm_modulator_new(const struct SmConfig *config);
+
+/**
+ * Frees a modulator of single OFDM symbols, null is ignored.
+ *
+ * # Safety
+ * The modulator must be null or a handle returned by [sm_ofdm_modulator_new], which is not used afterwards.
+ */
+void sm_ofdm_modulator_free(struct SmOfdmModulator *modulator);
+
+/**
+ * Returns the number of samples of a symbol, or 0 for a null modulator.
+ *
+ * # Safety
+ * The modulator must be null or a handle returned by [sm_ofdm_modulator_new].
+ */
+size_t sm_ofdm_modulator_symbol_length(const struct SmOfdmModulator *modulator);
+
+/**
+ * Returns the number of data bytes of a symbol, or 0 for a null modulator.
+ *
+ * # Safety
+ * The modulator must be null or a handle returned by [sm_ofdm_modulator_new].
+ */
+size_t sm_ofdm_modulator_bytes_per_symbol(const struct SmOfdmModulator *modulator);
+
+/**
+ * Modulates exactly the bytes per symbol of data into a symbol of samples.
+ *
+ * Writes the symbol length to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+ * without writing any samples. Any other length of data returns [SmStatus::Panic].
+ *
+ * # Safety
+ * The modulator must be a handle returned by [sm_ofdm_modulator_new], `data` must point to `len` bytes
+ * and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_ofdm_modulate_symbol(const struct SmOfdmModulator *modulator,
+                                      const uint8_t *data,
+                                      size_t len,
+                                      float *out,
+                                      size_t out_cap,
+                                      size_t *out_len);
+
+/**
+ * Creates a demodulator of single OFDM symbols, to be freed with [sm_ofdm_demodulator_free],
+ * or returns null on an invalid configuration.
+ *
+ * The differential encoding and the Reed-Solomon code of the configuration belong to the frames, they are ignored.
+ *
+ * # Safety
+ * The configuration must be null or point to a valid [SmConfig].
+ */
+struct SmOfdmDemodulator *sm_ofdm_demodulator_new(const struct SmConfig *config);
+
+/**
+ * Frees a demodulator of single OFDM symbols, null is ignored.
+ *
+ * # Safety
+ * The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new], which is not used afterwards.
+ */
+void sm_ofdm_demodulator_free(struct SmOfdmDemodulator *demodulator);
+
+/**
+ * Returns the number of samples of a symbol, or 0 for a null demodulator.
+ *
+ * # Safety
+ * The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new].
+ */
+size_t sm_ofdm_demodulator_symbol_length(const struct SmOfdmDemodulator *demodulator);
+
+/**
+ * Returns the number of data bytes of a symbol, or 0 for a null demodulator.
+ *
+ * # Safety
+ * The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new].
+ */
+size_t sm_ofdm_demodulator_bytes_per_symbol(const struct SmOfdmDemodulator *demodulator);
+
+/**
+ * Demodulates a symbol of exactly the symbol length of samples into its data bytes.
+ *
+ * Writes the bytes per symbol to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+ * without writing any data. Any other number of samples returns [SmStatus::Panic].
+ *
+ * # Safety
+ * The demodulator must be a handle returned by [sm_ofdm_demodulator_new], `samples` must point to `len` floats
+ * and `out` to `out_cap` bytes, or be null if their length is 0, and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_ofdm_demodulate_symbol(const struct SmOfdmDemodulator *demodulator,
+                                        const float *samples,
+                                        size_t len,
+                                        uint8_t *out,
+                                        size_t out_cap,
+                                        size_t *out_len);
+
+/**
+ * Demodulates a symbol of exactly the symbol length of samples into one LLR per data bit,
+ * see [OFDMDemodulator::demodulate_symbol_soft_from_buffer].
+ *
+ * Writes eight times the bytes per symbol to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+ * without writing any LLRs. Any other number of samples returns [SmStatus::Panic].
+ *
+ * # Safety
+ * The demodulator must be a handle returned by [sm_ofdm_demodulator_new], `samples` must point to `len` floats
+ * and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_ofdm_demodulate_symbol_soft(const struct SmOfdmDemodulator *demodulator,
+                                             const float *samples,
+                                             size_t len,
+                                             float *out,
+                                             size_t out_cap,
+                                             size_t *out_len);
+
+/**
+ * Maps `len` bytes to QAM-16 symbols, two per byte, written as interleaved real and imaginary parts.
+ *
+ * Writes the number of floats, four per byte, to `out_len`. If it exceeds `out_cap`,
+ * returns [SmStatus::BufferTooSmall] without writing any symbols.
+ *
+ * # Safety
+ * `data` must point to `len` bytes and `out` to `out_cap` floats, or be null if their length is 0,
+ * and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_qam_modulate(const uint8_t *data,
+                              size_t len,
+                              float *out,
+                              size_t out_cap,
+                              size_t *out_len);
+
+/**
+ * Demaps QAM-16 symbols, given as `len` interleaved real and imaginary parts, to the nearest bytes.
+ *
+ * Writes the number of bytes, one per two symbols, to `out_len`. If it exceeds `out_cap`,
+ * returns [SmStatus::BufferTooSmall] without writing any data. An odd number of floats or symbols returns [SmStatus::Panic].
+ *
+ * # Safety
+ * `symbols` must point to `len` floats and `out` to `out_cap` bytes, or be null if their length is 0,
+ * and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_qam_demodulate(const float *symbols,
+                                size_t len,
+                                uint8_t *out,
+                                size_t out_cap,
+                                size_t *out_len);
+
+/**
+ * Demaps QAM-16 symbols, given as `len` interleaved real and imaginary parts, to four LLRs per symbol,
+ * see [QAMModem::demodulate_soft].
+ *
+ * Writes the number of LLRs to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+ * without writing any LLRs. An odd `len` returns [SmStatus::Panic].
+ *
+ * # Safety
+ * `symbols` must point to `len` floats and `out` to `out_cap` floats, or be null if their length is 0,
+ * and `out_len` must point to a `size_t`.
+ */
+enum SmStatus sm_qam_demodulate_soft(const float *symbols,
+                                     size_t len,
+                                     float *out,
+                                     size_t out_cap,
+                                     size_t *out_len);
+
+#endif  /* SOFTWARE_MODEM_H */
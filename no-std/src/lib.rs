@@ -0,0 +1,70 @@
+//! A `#![no_std]` link of the coded modem, built on the crate without its `std` feature:
+//! the FFTs of the crate instead of those of `realfft` and `rustfft`, the float math of `libm` through its
+//! [math](software_modem::math) module instead of that of `std`, and `alloc` for the frames.
+//!
+//! Cargo unifies the features of the crates of a workspace built together, so the crate only goes without `std`
+//! when this one is built or tested on its own: `cargo test -p software-modem-no-std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    error::ModemError,
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+};
+
+/// Returns the OFDM configuration of the link: 64 subcarriers, a cyclic prefix of 4 samples
+/// and differential modulation over time.
+pub fn ofdm_config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        ..Default::default()
+    }
+}
+
+/// Returns the coding configuration of the link, the default one with Reed-Solomon codes.
+pub fn coding_config() -> CodingConfig {
+    CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    }
+}
+
+/// Both ends of the link, see [transmit](Link::transmit) and [receive](Link::receive).
+pub struct Link {
+    modulator: CodedOFDMModulator,
+    demodulator: CodedOFDMDemodulator,
+}
+
+impl Link {
+    /// Creates both ends of the link, planning their FFTs.
+    pub fn new() -> Self {
+        Link {
+            modulator: CodedOFDMModulator::new(ofdm_config(), coding_config()),
+            demodulator: CodedOFDMDemodulator::new(ofdm_config(), coding_config()),
+        }
+    }
+
+    /// Encodes the payload into a frame of samples.
+    pub fn transmit(&self, payload: &[u8]) -> Vec<f32> {
+        self.modulator.encode_frame(payload)
+    }
+
+    /// Decodes the payload of a frame of samples.
+    pub fn receive(&self, samples: &[f32]) -> Result<Vec<u8>, ModemError> {
+        self.demodulator.decode_frame(samples)
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self::new()
+    }
+}
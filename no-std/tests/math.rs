@@ -0,0 +1,91 @@
+//! Checks the float math of the crate without `std`, that of `libm`, against that of `std`, over the arguments
+//! of a modem.
+//!
+//! Run on its own to build the crate without `std`: `cargo test -p software-modem-no-std`.
+
+use software_modem::math::Float;
+
+/// Arguments from tiny to large, of both signs, including the integers and halves rounding looks at.
+fn arguments() -> impl Iterator<Item = f64> {
+    (-4000..=4000)
+        .map(|i| f64::from(i) * 0.37)
+        .chain((-40..=40).map(|i| f64::from(i) * 0.5))
+        .chain([1e-300, 1e-9, 0.1, 0.5, 1.0, 1e9, 1e300])
+}
+
+/// Asserts that the value is within some units in the last place of the expected one.
+fn assert_close(actual: f64, expected: f64, what: &str, x: f64) {
+    let tolerance = 1e-14 * expected.abs().max(1e-300);
+    assert!(
+        (actual - expected).abs() <= tolerance || actual == expected,
+        "{what}({x}) is {actual}, but std gives {expected}"
+    );
+}
+
+#[test]
+fn functions_match_std() {
+    for x in arguments() {
+        // the phases the module is documented for
+        if x.abs() <= 1e9 {
+            assert_close(Float::sin(x), x.sin(), "sin", x);
+            assert_close(Float::cos(x), x.cos(), "cos", x);
+        }
+        assert_close(Float::atan(x), x.atan(), "atan", x);
+        assert_close(Float::atan2(x, 1.5), x.atan2(1.5), "atan2", x);
+        assert_close(Float::atan2(1.5, x), 1.5f64.atan2(x), "atan2", x);
+        assert_close(Float::tanh(x), x.tanh(), "tanh", x);
+        assert_close(Float::floor(x), x.floor(), "floor", x);
+        assert_close(Float::ceil(x), x.ceil(), "ceil", x);
+        assert_close(Float::round(x), x.round(), "round", x);
+        assert_close(Float::trunc(x), x.trunc(), "trunc", x);
+        assert_close(Float::cbrt(x), x.cbrt(), "cbrt", x);
+        assert_close(Float::hypot(x, 3.0), x.hypot(3.0), "hypot", x);
+        if x.abs() < 700.0 {
+            assert_close(Float::exp(x), x.exp(), "exp", x);
+            assert_close(Float::exp_m1(x), x.exp_m1(), "exp_m1", x);
+        }
+        if x > 0.0 {
+            assert_close(Float::sqrt(x), x.sqrt(), "sqrt", x);
+            assert_close(Float::ln(x), x.ln(), "ln", x);
+            assert_close(Float::log10(x), x.log10(), "log10", x);
+            assert_close(Float::powf(x, 0.3), x.powf(0.3), "powf", x);
+        }
+    }
+}
+
+#[test]
+fn powers_match_std() {
+    for x in [-3.0, -0.5, 0.0, 0.5, 2.0, 10.0f64] {
+        for n in -8..=8 {
+            assert_close(Float::powi(x, n), x.powi(n), "powi", x);
+            assert_close(
+                Float::powf(x, f64::from(n)),
+                x.powf(f64::from(n)),
+                "powf",
+                x,
+            );
+        }
+    }
+    assert!(Float::powf(-2.0f64, 0.5).is_nan());
+    assert_eq!(Float::powf(10.0f32, 2.0), 100.0);
+}
+
+#[test]
+fn edge_cases_match_std() {
+    for x in [0.0, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        for (what, actual, expected) in [
+            ("sqrt", Float::sqrt(x), x.sqrt()),
+            ("ln", Float::ln(x), x.ln()),
+            ("exp", Float::exp(x), x.exp()),
+            ("floor", Float::floor(x), x.floor()),
+            ("atan", Float::atan(x), x.atan()),
+        ] {
+            assert!(
+                actual == expected || actual.is_nan() && expected.is_nan(),
+                "{what}({x}) is {actual}, but std gives {expected}"
+            );
+        }
+    }
+    assert_eq!(Float::atan2(0.0f64, -1.0), std::f64::consts::PI);
+    assert_eq!(Float::atan2(-0.0f64, -1.0), -std::f64::consts::PI);
+}
@@ -0,0 +1,41 @@
+//! Checks that frames round trip through the link built without `std`, on a clean channel and through noise and echoes.
+//!
+//! Run on its own to build the crate without `std`: `cargo test -p software-modem-no-std`.
+
+use num_complex::Complex32;
+use software_modem::channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel};
+use software_modem_no_std::Link;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+#[test]
+fn frames_round_trip() {
+    let link = Link::new();
+    for length in [0, 1, 24, 300] {
+        let payload = data(length);
+        let samples = link.transmit(&payload);
+        assert_eq!(link.receive(&samples).unwrap(), payload, "{length} bytes");
+    }
+}
+
+#[test]
+fn frames_survive_noise_and_echoes() {
+    let link = Link::new();
+    let payload = data(200);
+    for seed in 0..5 {
+        let mut samples = link.transmit(&payload);
+        ChannelChain::new()
+            .with(MultipathChannel::new(&[
+                (0, Complex32::new(-0.7, 0.0)),
+                (1, Complex32::new(0.4, 0.0)),
+                (2, Complex32::new(0.2, 0.0)),
+            ]))
+            .with(AwgnChannel::new(25.0, seed))
+            .apply(&mut samples);
+        assert_eq!(link.receive(&samples).unwrap(), payload, "seed {seed}");
+    }
+}
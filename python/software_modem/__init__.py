@@ -0,0 +1,13 @@
+"""NumPy bindings of the OFDM modem and the QAM modem.
+
+The classes are the ``pyo3`` extension ``software_modem._native``, built from the ``python`` feature of the crate
+by ``maturin``, and a thin layer over its Rust API: configurations are keyword arguments, arrays of the right type
+and layout are read without copies, and invalid configurations and input lengths raise ``ValueError``.
+
+The bindings are experimental and their interface may still change.
+The test suite runs with ``cargo test --features python -- --ignored``.
+"""
+
+from ._native import OfdmDemodulator, OfdmModulator, QamModem
+
+__all__ = ["OfdmModulator", "OfdmDemodulator", "QamModem"]
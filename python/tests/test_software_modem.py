@@ -0,0 +1,85 @@
+import numpy
+import pytest
+
+from software_modem import OfdmDemodulator, OfdmModulator, QamModem
+
+
+def data(length):
+    return ((numpy.arange(length, dtype=numpy.uint64) * 2654435761) >> 11).astype(numpy.uint8)
+
+
+def test_ofdm_round_trip():
+    modulator = OfdmModulator(num_subcarriers=64, cyclic_prefix_length=4)
+    demodulator = OfdmDemodulator(num_subcarriers=64, cyclic_prefix_length=4)
+    assert (modulator.symbol_length, modulator.bytes_per_symbol) == (132, 24)
+
+    payload = data(10 * modulator.bytes_per_symbol)
+    samples = modulator.modulate(payload)
+    assert samples.dtype == numpy.float32
+    assert samples.shape == (10 * modulator.symbol_length,)
+
+    # the level does not matter, the equalization absorbs it
+    received = 0.01 * samples + numpy.random.default_rng(1).normal(0, 1e-3, samples.shape)
+    numpy.testing.assert_array_equal(demodulator.demodulate(received), payload)
+    assert bytes(demodulator.demodulate(modulator.modulate(b"x" * 24))) == b"x" * 24
+
+
+def test_soft_demapper_shape():
+    modulator = OfdmModulator()
+    demodulator = OfdmDemodulator()
+    payload = data(3 * modulator.bytes_per_symbol)
+    llrs = demodulator.demodulate_soft(modulator.modulate(payload))
+    assert llrs.dtype == numpy.float32
+    assert llrs.shape == (3, 8 * demodulator.bytes_per_symbol)
+    numpy.testing.assert_array_equal(numpy.packbits(llrs.reshape(-1) < 0), payload)
+
+    modem = QamModem(order=16)
+    symbols = modem.modulate(payload)
+    assert symbols.dtype == numpy.complex64
+    assert symbols.shape == (2 * len(payload),)
+    assert modem.demodulate_soft(symbols).shape == (len(symbols), 4)
+    numpy.testing.assert_array_equal(numpy.packbits(modem.demodulate_soft(symbols).reshape(-1) < 0), payload)
+
+
+def test_qam_round_trip():
+    modem = QamModem()
+    assert modem.modulate(b"\x00")[0] == 1 + 1j
+    symbols = modem.modulate(b"Hello") + 0.2 - 0.1j
+    assert bytes(modem.demodulate(symbols)) == b"Hello"
+
+
+@pytest.mark.parametrize("order", [4, 16, 64, 256])
+def test_qam_orders(order):
+    modem = QamModem(order=order)
+    assert modem.order == order
+    payload = data(48)
+    symbols = modem.modulate(payload)
+    assert symbols.shape == (len(payload) * 8 // modem.bits_per_symbol,)
+    numpy.testing.assert_array_equal(modem.demodulate(symbols), payload)
+    llrs = modem.demodulate_soft(symbols)
+    assert llrs.shape == (len(symbols), modem.bits_per_symbol)
+    numpy.testing.assert_array_equal(numpy.packbits(llrs.reshape(-1) < 0), payload)
+
+
+def test_arrays_are_converted():
+    modulator = OfdmModulator()
+    demodulator = OfdmDemodulator()
+    payload = data(2 * modulator.bytes_per_symbol)
+    samples = modulator.modulate(list(payload))
+    numpy.testing.assert_array_equal(modulator.modulate(bytearray(payload.tobytes())), samples)
+    # a strided float64 view is copied into float32
+    strided = numpy.repeat(samples.astype(numpy.float64), 2)[::2]
+    numpy.testing.assert_array_equal(demodulator.demodulate(strided), payload)
+
+
+def test_errors():
+    with pytest.raises(ValueError, match="Guard subcarriers"):
+        OfdmModulator(num_subcarriers=0)
+    with pytest.raises(ValueError, match="multiple of 24 bytes"):
+        OfdmModulator().modulate(b"too short")
+    with pytest.raises(ValueError, match="multiple of 132"):
+        OfdmDemodulator().demodulate(numpy.zeros(100))
+    with pytest.raises(ValueError, match="Invalid chunk size"):
+        QamModem().demodulate(numpy.zeros(3, dtype=numpy.complex64))
+    with pytest.raises(ValueError, match="QAM order must be 4, 16, 64 or 256"):
+        QamModem(order=32)
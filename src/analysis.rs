@@ -0,0 +1,797 @@
+//! This module provides views of a captured signal, to find out why it does not decode.
+//!
+//! The [spectrogram] shows how the spectrum changes over time, like a frame starting late, a carrier drifting
+//! or an interferer, and [write_spectrogram_csv] and [write_spectrogram_pgm] export it for a spreadsheet or an image viewer.
+//! The [summary] tells the level of the capture: too quiet, clipped, or offset by the DC of a sound card.
+//! And [write_constellation_csv] exports the received constellation points, to see how they scatter around their decisions,
+//! while a [ConstellationRecorder] collects them over many symbols with their subcarriers and error vectors.
+//! When the header of a frame is lost, [classify_qam] guesses the order of the constellation of its equalized points.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use std::io::Write;
+
+use num_complex::Complex32;
+
+use crate::{
+    fft::plan_real_forward,
+    metrics::{SpectrumWindow, papr},
+    qam::{QAMModem, QAMOrder},
+    stream::ConstellationSnapshot,
+};
+
+/// The level of the bins of a [spectrogram] without any power, instead of negative infinity.
+pub const SPECTROGRAM_FLOOR_DB: f32 = -200.0;
+
+/// Magnitude from which a sample counts as clipped, the largest value of a 16-bit sample.
+const CLIP_LEVEL: f32 = 32767.0 / 32768.0;
+
+/// Computes the spectrogram of the samples, the power spectra of windowed segments in dB.
+///
+/// The segments are `fft_size` samples long and start every `hop` samples, the samples after the last full segment
+/// are ignored. Every row holds the `fft_size / 2 + 1` bins of one segment from DC to the Nyquist frequency,
+/// bin `k` at `k / fft_size` of the sample rate, scaled like the [power spectrum](crate::metrics::power_spectrum):
+/// the bins of a row add up to the mean power of its segment. Bins without power are [SPECTROGRAM_FLOOR_DB].
+///
+/// # Panics
+/// If the FFT size is odd or zero, or the hop is zero.
+///
+/// # Example
+/// ```
+/// use software_modem::analysis::spectrogram;
+/// use software_modem::metrics::SpectrumWindow;
+///
+/// // a linear chirp from 0.05 to 0.4 of the sample rate
+/// let length = 16384;
+/// let (start, end) = (0.05, 0.4);
+/// let frequency = |n: f64| start + (end - start) * n / length as f64;
+/// let chirp: Vec<f32> = (0..length)
+///     .map(|n| {
+///         let n = n as f64;
+///         (std::f64::consts::TAU * (start * n + (end - start) * n * n / (2.0 * length as f64))).sin() as f32
+///     })
+///     .collect();
+///
+/// let rows = spectrogram(&chirp, 256, 128, SpectrumWindow::Hann);
+/// assert_eq!(rows.len(), (length - 256) / 128 + 1);
+/// assert!(rows.iter().all(|row| row.len() == 129));
+///
+/// // the strongest bin of every segment follows the instantaneous frequency at its middle
+/// for (i, row) in rows.iter().enumerate() {
+///     let peak = (0..row.len()).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap();
+///     let expected = frequency((i * 128 + 128) as f64) * 256.0;
+///     assert!((peak as f64 - expected).abs() <= 1.0, "segment {i}: bin {peak}, expected {expected:.1}");
+/// }
+///
+/// // the sine of amplitude 1 has a mean power of 0.5, -3 dB
+/// let total: f32 = rows[64].iter().map(|db| 10f32.powf(db / 10.0)).sum();
+/// assert!((10.0 * total.log10() + 3.01).abs() < 0.05);
+/// ```
+pub fn spectrogram(
+    samples: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window: SpectrumWindow,
+) -> Vec<Vec<f32>> {
+    if fft_size == 0 || !fft_size.is_multiple_of(2) {
+        panic!("FFT size must be even and non-zero, but got {}", fft_size);
+    }
+    if hop == 0 {
+        panic!("Hop must be at least 1, but got 0");
+    }
+    if samples.len() < fft_size {
+        return Vec::new();
+    }
+
+    let window = window.coefficients(fft_size);
+    let window_power: f32 = window.iter().map(|w| w * w).sum();
+    let scale = 1.0 / (fft_size as f32 * window_power);
+    let fft = plan_real_forward::<f32>(fft_size);
+    let mut segment = vec![0.0; fft_size];
+    let mut bins = vec![Complex32::default(); fft_size / 2 + 1];
+    let mut scratch = vec![Complex32::default(); fft.get_scratch_len()];
+    let nyquist = fft_size / 2;
+
+    let num_segments = (samples.len() - fft_size) / hop + 1;
+    (0..num_segments)
+        .map(|i| {
+            let start = i * hop;
+            for ((segment, sample), w) in segment
+                .iter_mut()
+                .zip(&samples[start..start + fft_size])
+                .zip(&window)
+            {
+                *segment = sample * w;
+            }
+            fft.process_with_scratch(&mut segment, &mut bins, &mut scratch);
+            bins.iter()
+                .enumerate()
+                .map(|(k, bin)| {
+                    // every bin but DC and Nyquist also stands for its negative frequency
+                    let sides = if k == 0 || k == nyquist { 1.0 } else { 2.0 };
+                    let power = sides * scale * bin.norm_sqr();
+                    if power > 0.0 {
+                        (10.0 * power.log10()).max(SPECTROGRAM_FLOOR_DB)
+                    } else {
+                        SPECTROGRAM_FLOOR_DB
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes a spectrogram as CSV, one line per segment and one column per bin, in dB with two decimals.
+///
+/// # Errors
+/// The error of the writer.
+///
+/// # Example
+/// ```
+/// use software_modem::analysis::write_spectrogram_csv;
+///
+/// let mut csv = Vec::new();
+/// write_spectrogram_csv(&[vec![-3.0, -200.0], vec![-6.021, -60.5]], &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "-3.00,-200.00\n-6.02,-60.50\n");
+/// ```
+pub fn write_spectrogram_csv<W: Write>(
+    spectrogram: &[Vec<f32>],
+    mut writer: W,
+) -> std::io::Result<()> {
+    for row in spectrogram {
+        let line: Vec<String> = row.iter().map(|db| format!("{:.2}", db)).collect();
+        writeln!(writer, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+/// Writes a spectrogram as a binary PGM image, a waterfall with time going down and frequency going right.
+///
+/// The levels from `range_db.0` to `range_db.1` are mapped to black to white, levels outside are clipped.
+/// The image is as wide as the rows of the spectrogram, which must all have the same length.
+///
+/// # Panics
+/// If the rows differ in length, or the range is empty.
+///
+/// # Errors
+/// The error of the writer.
+///
+/// # Example
+/// ```
+/// use software_modem::analysis::{spectrogram, write_spectrogram_pgm};
+/// use software_modem::metrics::SpectrumWindow;
+///
+/// let tone: Vec<f32> = (0..4096).map(|n| (std::f32::consts::TAU * 0.125 * n as f32).sin()).collect();
+/// let rows = spectrogram(&tone, 64, 64, SpectrumWindow::Hann);
+///
+/// let mut image = Vec::new();
+/// write_spectrogram_pgm(&rows, (-100.0, 0.0), &mut image).unwrap();
+/// let header = b"P5\n33 64\n255\n";
+/// assert_eq!(image[..header.len()], header[..]);
+/// assert_eq!(image.len(), header.len() + 33 * 64);
+///
+/// // the tone is bin 8, bright, and far from it is black
+/// let first_row = &image[header.len()..header.len() + 33];
+/// assert!(first_row[8] > 200);
+/// assert_eq!(first_row[20], 0);
+/// ```
+pub fn write_spectrogram_pgm<W: Write>(
+    spectrogram: &[Vec<f32>],
+    range_db: (f32, f32),
+    mut writer: W,
+) -> std::io::Result<()> {
+    let (low, high) = range_db;
+    if high <= low || low.is_nan() || high.is_nan() {
+        panic!(
+            "Range must go from a lower to a higher level, but got {} to {} dB",
+            low, high
+        );
+    }
+    let width = spectrogram.first().map_or(0, Vec::len);
+    if let Some(row) = spectrogram.iter().find(|row| row.len() != width) {
+        panic!(
+            "Rows must all have {} bins, but got a row of {}",
+            width,
+            row.len()
+        );
+    }
+
+    write!(writer, "P5\n{} {}\n255\n", width, spectrogram.len())?;
+    let pixels: Vec<u8> = spectrogram
+        .iter()
+        .flatten()
+        .map(|db| {
+            ((db - low) / (high - low) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        })
+        .collect();
+    writer.write_all(&pixels)
+}
+
+/// Writes constellation points as CSV, one line per point with the real part, the imaginary part and the index of the decided point.
+///
+/// The index is the one of the [nearest constellation point](QAMModem::nearest_indices), the bits the point is decided as.
+/// The first line names the columns: `re,im,index`. The points come from
+/// [demodulate_symbol_with_points](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_symbol_with_points)
+/// or [get_constellation](crate::frame::FrameDecoder::get_constellation).
+///
+/// # Errors
+/// The error of the writer.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::analysis::write_constellation_csv;
+/// use software_modem::qam::{QAMModem, QAMOrder};
+///
+/// let points = [Complex32::new(0.8, 2.6), Complex32::new(-4.0, -0.25)];
+/// let mut csv = Vec::new();
+/// write_constellation_csv(&points, &QAMModem::new(QAMOrder::QAM16), &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "re,im,index\n0.800000,2.600000,1\n-4.000000,-0.250000,14\n");
+/// ```
+pub fn write_constellation_csv<W: Write>(
+    points: &[Complex32],
+    modem: &QAMModem,
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(writer, "re,im,index")?;
+    for (point, index) in points.iter().zip(modem.nearest_indices(points)) {
+        writeln!(writer, "{:.6},{:.6},{}", point.re, point.im, index)?;
+    }
+    Ok(())
+}
+
+/// A point recorded by a [ConstellationRecorder].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RecordedPoint {
+    /// Index of the symbol the point belongs to.
+    pub symbol_index: u64,
+    /// Index of the subcarrier of the point.
+    pub subcarrier: u32,
+    /// The equalized point.
+    pub point: Complex32,
+    /// Index of the [nearest constellation point](QAMModem::nearest_indices), the bits the point is decided as.
+    pub index: usize,
+    /// Magnitude of the error vector against the decided point, relative to the RMS of the constellation, in percent.
+    pub evm_percent: f32,
+}
+
+/// Collects the received constellation points of many symbols, for analysis in Python or a spreadsheet.
+///
+/// The points come from the [snapshots](crate::stream::ConstellationSnapshot) of a stream, see
+/// [record_snapshot](Self::record_snapshot), or from a batch demodulation like
+/// [get_constellation](crate::frame::FrameDecoder::get_constellation), see [record_symbol](Self::record_symbol).
+/// Every point is decided and its error vector measured as it is recorded, up to a cap on the points,
+/// after which the points are [dropped](Self::get_dropped) so a long capture can not run out of memory.
+///
+/// [write_to](Self::write_to) writes them as CSV, with a footer of the RMS EVM of all of them and of every subcarrier.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use software_modem::analysis::ConstellationRecorder;
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::stream::StreamDemodulator;
+///
+/// let config = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 8,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(config.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(config.clone(), CodingConfig::default());
+///
+/// // the points of every symbol a stream decodes, up to 1000 of them
+/// let recorder = Arc::new(Mutex::new(ConstellationRecorder::new(
+///     config.qam_order,
+///     demodulator.get_data_subcarriers(),
+///     1000,
+/// )));
+/// let mut stream = StreamDemodulator::new(demodulator, 0.01, 1000);
+/// let kept = recorder.clone();
+/// stream.set_snapshot_hook(
+///     Some(Arc::new(move |snapshot| kept.lock().unwrap().record_snapshot(snapshot))),
+///     1,
+/// );
+///
+/// let mut samples = vec![0.0; 1000];
+/// samples.extend(modulator.encode_frame(&[7; 500]));
+/// samples.extend([0.0; 2000]);
+/// assert_eq!(stream.push(&samples), [vec![7; 500]]);
+///
+/// let mut recorder = recorder.lock().unwrap();
+/// assert!(recorder.is_full());
+/// assert_eq!(recorder.get_points().len(), 1000);
+/// assert!(recorder.get_dropped() > 0);
+/// assert!(recorder.rms_evm_percent() < 1.0);
+///
+/// let mut csv = Vec::new();
+/// recorder.write_to(&mut csv).unwrap();
+/// let csv = String::from_utf8(csv).unwrap();
+/// assert!(csv.starts_with("symbol,subcarrier,re,im,index,evm_percent\n"));
+/// ```
+pub struct ConstellationRecorder {
+    modem: QAMModem,
+    subcarriers: Vec<u32>,
+    capacity: usize,
+    points: Vec<RecordedPoint>,
+    dropped: u64,
+}
+
+impl ConstellationRecorder {
+    /// Creates a recorder of the points of symbols with the data subcarriers, decided with the QAM order,
+    /// keeping up to `capacity` points.
+    ///
+    /// The data subcarriers are the ones of the demodulator, see
+    /// [get_data_subcarriers](crate::coded::CodedOFDMDemodulator::get_data_subcarriers).
+    pub fn new(qam_order: QAMOrder, subcarriers: &[u32], capacity: usize) -> Self {
+        ConstellationRecorder {
+            modem: QAMModem::new(qam_order),
+            subcarriers: subcarriers.to_vec(),
+            capacity,
+            points: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Records the points of a snapshot of a stream, at its symbol index.
+    ///
+    /// # Panics
+    /// If the snapshot does not have a point for every data subcarrier.
+    pub fn record_snapshot(&mut self, snapshot: &ConstellationSnapshot) {
+        self.record_symbol(snapshot.symbol_index, &snapshot.points);
+    }
+
+    /// Records the data subcarrier points of a symbol, in the order of the data subcarriers.
+    ///
+    /// The points that do not fit under the cap are counted as dropped.
+    ///
+    /// # Panics
+    /// If the number of points is not the number of data subcarriers.
+    pub fn record_symbol(&mut self, symbol_index: u64, points: &[Complex32]) {
+        if points.len() != self.subcarriers.len() {
+            panic!(
+                "Symbol must have a point for each of the {} data subcarriers, but got {}",
+                self.subcarriers.len(),
+                points.len()
+            );
+        }
+
+        let kept = points.len().min(self.capacity - self.points.len());
+        self.dropped += (points.len() - kept) as u64;
+        let points = &points[..kept];
+        let reference_rms = self.modem.mean_power().sqrt();
+        let decisions = self.modem.nearest_points(points);
+        let indices = self.modem.nearest_indices(points);
+        for (((&point, decision), index), &subcarrier) in points
+            .iter()
+            .zip(decisions)
+            .zip(indices)
+            .zip(&self.subcarriers)
+        {
+            self.points.push(RecordedPoint {
+                symbol_index,
+                subcarrier,
+                point,
+                index,
+                evm_percent: (point - decision).norm() / reference_rms * 100.0,
+            });
+        }
+    }
+
+    /// Returns the points recorded, in the order they were recorded.
+    pub fn get_points(&self) -> &[RecordedPoint] {
+        &self.points
+    }
+
+    /// Returns the number of points dropped because the recorder was full.
+    pub fn get_dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns `true` if the recorder holds as many points as it can.
+    pub fn is_full(&self) -> bool {
+        self.points.len() == self.capacity
+    }
+
+    /// Forgets the points recorded and dropped.
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.dropped = 0;
+    }
+
+    /// Returns the RMS of the error vectors of all the points, relative to the RMS of the constellation, in percent,
+    /// or 0 without points.
+    pub fn rms_evm_percent(&self) -> f32 {
+        rms(self.points.iter().map(|point| point.evm_percent))
+    }
+
+    /// Returns the RMS EVM in percent of the points of every data subcarrier, in the order of the subcarriers,
+    /// 0 for a subcarrier without points.
+    pub fn subcarrier_evm_percent(&self) -> Vec<(u32, f32)> {
+        self.subcarriers
+            .iter()
+            .map(|&subcarrier| {
+                let points = self
+                    .points
+                    .iter()
+                    .filter(|point| point.subcarrier == subcarrier);
+                (subcarrier, rms(points.map(|point| point.evm_percent)))
+            })
+            .collect()
+    }
+
+    /// Writes the points as CSV, one line per point, followed by a summary.
+    ///
+    /// The first line names the columns: `symbol,subcarrier,re,im,index,evm_percent`. The summary is made of comment
+    /// lines starting with `#`, which `pandas.read_csv(path, comment="#")` skips: the number of points dropped,
+    /// the RMS EVM of all the points in percent and in dB, and a table of the RMS EVM of every subcarrier:
+    ///
+    /// ```text
+    /// # dropped,0
+    /// # rms_evm_percent,1.234567
+    /// # rms_evm_db,-38.170000
+    /// # subcarrier,evm_percent
+    /// # 2,1.200000
+    /// ```
+    ///
+    /// # Errors
+    /// The error of the writer.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "symbol,subcarrier,re,im,index,evm_percent")?;
+        for point in &self.points {
+            writeln!(
+                writer,
+                "{},{},{:.6},{:.6},{},{:.6}",
+                point.symbol_index,
+                point.subcarrier,
+                point.point.re,
+                point.point.im,
+                point.index,
+                point.evm_percent
+            )?;
+        }
+
+        let rms_evm = self.rms_evm_percent();
+        writeln!(writer, "# dropped,{}", self.dropped)?;
+        writeln!(writer, "# rms_evm_percent,{:.6}", rms_evm)?;
+        writeln!(
+            writer,
+            "# rms_evm_db,{:.6}",
+            20.0 * (rms_evm / 100.0).log10()
+        )?;
+        writeln!(writer, "# subcarrier,evm_percent")?;
+        for (subcarrier, evm) in self.subcarrier_evm_percent() {
+            writeln!(writer, "# {},{:.6}", subcarrier, evm)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the root mean square of the values, 0 without values.
+fn rms(values: impl Iterator<Item = f32>) -> f32 {
+    let (count, sum) = values.fold((0usize, 0.0f64), |(count, sum), value| {
+        (count + 1, sum + (value as f64).powi(2))
+    });
+    if count == 0 {
+        0.0
+    } else {
+        (sum / count as f64).sqrt() as f32
+    }
+}
+
+/// Levels of a signal, see [summary].
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct SignalSummary {
+    /// Root mean square, after removing the DC offset.
+    pub rms: f32,
+    /// Largest magnitude of a sample.
+    pub peak: f32,
+    /// Ratio of the peak power to the mean power in dB, the [PAPR](crate::metrics::papr) of the samples.
+    pub crest_factor_db: f32,
+    /// Samples at or above the full scale of a 16-bit sample, `32767 / 32768`.
+    pub clipped: usize,
+    /// Mean of the samples.
+    pub dc_offset: f32,
+}
+
+/// Measures the levels of the samples: RMS, peak, crest factor, clipping and DC offset.
+///
+/// The RMS is of the signal without its DC offset, while the peak and the crest factor are of the samples as they are,
+/// the way a converter sees them. An empty signal has a summary of zeros.
+///
+/// # Example
+/// ```
+/// use software_modem::analysis::summary;
+///
+/// // a sine of amplitude 0.5 on a DC offset of 0.1
+/// let sine: Vec<f32> = (0..4800).map(|n| 0.1 + 0.5 * (std::f32::consts::TAU * n as f32 / 48.0).sin()).collect();
+/// let levels = summary(&sine);
+/// assert!((levels.dc_offset - 0.1).abs() < 1e-4);
+/// assert!((levels.rms - 0.5 / 2f32.sqrt()).abs() < 1e-4);
+/// assert!((levels.peak - 0.6).abs() < 1e-4);
+/// assert_eq!(levels.clipped, 0);
+///
+/// // driven too hard, the converter clips
+/// let clipped: Vec<f32> = sine.iter().map(|sample| (4.0 * sample).clamp(-1.0, 1.0)).collect();
+/// assert!(summary(&clipped).clipped > 1000);
+/// ```
+pub fn summary(samples: &[f32]) -> SignalSummary {
+    if samples.is_empty() {
+        return SignalSummary::default();
+    }
+
+    let length = samples.len() as f64;
+    let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / length;
+    let variance = samples
+        .iter()
+        .map(|&x| (x as f64 - mean).powi(2))
+        .sum::<f64>()
+        / length;
+    SignalSummary {
+        rms: variance.sqrt() as f32,
+        peak: samples.iter().fold(0.0, |peak, x| x.abs().max(peak)),
+        crest_factor_db: papr(samples),
+        clipped: samples.iter().filter(|x| x.abs() >= CLIP_LEVEL).count(),
+        dc_offset: mean as f32,
+    }
+}
+
+/// Bins of the histogram of the magnitudes of the points in [classify_qam], from 0 up to [RADIAL_EXTENT].
+const RADIAL_BINS: usize = 64;
+
+/// Magnitude of the end of the last bin of the histogram of [classify_qam], relative to the RMS of the points;
+/// the magnitudes beyond fall into the last bin.
+const RADIAL_EXTENT: f64 = 2.0;
+
+/// The SNRs at which [classify_qam] matches every constellation to the points, in steps from the lowest
+/// to the highest, which keeps the rings of a constellation from becoming infinitely sharp.
+const MIN_SNR_DB: f64 = -5.0;
+const SNR_STEP_DB: f64 = 0.5;
+const SNR_STEPS: usize = 70;
+
+/// Points of the square constellations [classify_qam] tells apart, from the smallest up: the orders of the modem
+/// along with the ones it does not have, so that points of those do not pass for one of the modem.
+const SQUARE_CONSTELLATIONS: [usize; 4] = [4, 16, 64, 256];
+
+/// Scores the likelihood of the QAM orders of the modem for equalized points of unknown order,
+/// as a confidence between 0 and 1, from the most likely order down.
+///
+/// The points are scaled to a mean power of 1, as the gain of the channel is unknown, and two features are
+/// matched against the normalized constellations plus complex Gaussian noise, at every SNR from -5 to 30 dB:
+/// - the fourth and sixth-order cumulants, `C42` and `C63`, which noise scales down towards the 0 of a Gaussian
+///   by the square and the cube of the share of the power which is signal, within the spread of their estimates;
+/// - the histogram of the magnitudes of the points, against the rings of the constellation blurred by the noise.
+///
+/// As both features come from the same magnitudes, their log-likelihoods are averaged rather than added, which
+/// would count the evidence twice and make the confidence too sure of itself.
+///
+/// The square constellations of 4, 16, 64 and 256 points, the orders of the modem, all take part at their most
+/// likely SNR, and the confidences are their likelihoods relative to each other. So the confidence is shared
+/// between the constellations the points may come from instead of going to the best one: it falls as the noise
+/// blurs the rings together, and stays low for points from a constellation the modem does not have.
+/// A few hundred points tell QPSK, QAM-16 and QAM-64 apart at 20 dB; at 8 dB only QPSK is still clear.
+///
+/// # Panics
+/// If there are no points.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::analysis::classify_qam;
+/// use software_modem::qam::{QAMModem, QAMOrder};
+///
+/// let modem = QAMModem::new(QAMOrder::QAM16);
+/// let data: Vec<u8> = (0..200u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+/// // the points of an unknown gain and phase rotation by a quarter turn
+/// let points: Vec<Complex32> = modem.modulate(&data).iter().map(|point| point * Complex32::new(0.0, 0.3)).collect();
+///
+/// let scores = classify_qam(&points);
+/// assert_eq!(scores[0].0, QAMOrder::QAM16);
+/// assert!(scores[0].1 > 0.99);
+///
+/// // points of constant magnitude, like QPSK, are no QAM-16
+/// let points: Vec<Complex32> = data.iter().map(|&byte| Complex32::from_polar(1.0, byte as f32)).collect();
+/// let qam16 = classify_qam(&points).into_iter().find(|&(order, _)| order == QAMOrder::QAM16).unwrap();
+/// assert!(qam16.1 < 0.01);
+/// ```
+pub fn classify_qam(points: &[Complex32]) -> Vec<(QAMOrder, f32)> {
+    assert!(
+        !points.is_empty(),
+        "Number of points must be at least 1, but got 0"
+    );
+
+    let power = points
+        .iter()
+        .map(|point| point.norm_sqr() as f64)
+        .sum::<f64>()
+        / points.len() as f64;
+    let constellations: Vec<Vec<(f64, f64)>> = SQUARE_CONSTELLATIONS
+        .iter()
+        .map(|&size| constellation_rings(size))
+        .collect();
+    let likelihoods: Vec<f64> = if power > 0.0 && power.is_finite() {
+        let magnitudes: Vec<f64> = points
+            .iter()
+            .map(|point| point.norm() as f64 / power.sqrt())
+            .collect();
+        let (c42, c63) = cumulants(magnitudes.iter().map(|&magnitude| (magnitude, 1.0)));
+        // the variances of the estimates of the cumulants, from the spread of the terms of their moments
+        let variance = |term: &dyn Fn(f64) -> f64| {
+            let terms: Vec<f64> = magnitudes
+                .iter()
+                .map(|&magnitude| term(magnitude * magnitude))
+                .collect();
+            let mean = terms.iter().sum::<f64>() / terms.len() as f64;
+            let variance =
+                terms.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / terms.len() as f64;
+            (variance / terms.len() as f64).max(f64::MIN_POSITIVE)
+        };
+        let c42_variance = variance(&|power| power * power);
+        let c63_variance = variance(&|power| power * power * power - 9.0 * power * power);
+        let mut histogram = [0usize; RADIAL_BINS];
+        for magnitude in &magnitudes {
+            histogram[radial_bin(*magnitude)] += 1;
+        }
+
+        constellations
+            .iter()
+            .map(|rings| {
+                let (c42_signal, c63_signal) = cumulants(rings.iter().copied());
+                // the most likely SNR, noise scaling the cumulants by the square and the cube of the share of signal
+                (0..=SNR_STEPS)
+                    .map(|step| {
+                        let snr = 10f64.powf((MIN_SNR_DB + step as f64 * SNR_STEP_DB) / 10.0);
+                        let signal = snr / (1.0 + snr);
+                        let cumulants = -(c42 - signal.powi(2) * c42_signal).powi(2)
+                            / (2.0 * c42_variance)
+                            - (c63 - signal.powi(3) * c63_signal).powi(2) / (2.0 * c63_variance);
+                        let radial: f64 = radial_probabilities(rings, signal)
+                            .iter()
+                            .zip(&histogram)
+                            .map(|(probability, &count)| count as f64 * probability.ln())
+                            .sum();
+                        (cumulants + radial) / 2.0
+                    })
+                    .fold(f64::NEG_INFINITY, f64::max)
+            })
+            .collect()
+    } else {
+        // silence is as likely in every constellation
+        vec![0.0; constellations.len()]
+    };
+
+    let best = likelihoods
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = likelihoods.iter().map(|l| (l - best).exp()).sum();
+    let mut scores: Vec<(QAMOrder, f32)> = QAMOrder::ALL
+        .iter()
+        .map(|&order| {
+            let size = 1 << QAMModem::new(order).bits_per_symbol();
+            let index = SQUARE_CONSTELLATIONS
+                .iter()
+                .position(|&s| s == size)
+                .unwrap();
+            (order, ((likelihoods[index] - best).exp() / total) as f32)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+}
+
+/// Returns the distinct magnitudes of the points of the square constellation of the size, scaled to a mean power
+/// of 1, with the share of the points on each, the points of an order of the modem from its [QAMModem].
+fn constellation_rings(size: usize) -> Vec<(f64, f64)> {
+    let points: Vec<Complex32> = match QAMOrder::ALL
+        .into_iter()
+        .find(|&order| 1 << QAMModem::new(order).bits_per_symbol() == size)
+    {
+        Some(order) => {
+            let modem = QAMModem::new(order);
+            (0..size as u32).map(|bits| modem.map_bits(bits)).collect()
+        }
+        None => {
+            let side = size.isqrt() as i32;
+            let level = |index: i32| (2 * index - side + 1) as f32;
+            (0..side * side)
+                .map(|index| Complex32::new(level(index / side), level(index % side)))
+                .collect()
+        }
+    };
+    let power = points
+        .iter()
+        .map(|point| point.norm_sqr() as f64)
+        .sum::<f64>()
+        / size as f64;
+
+    let mut rings: Vec<(f64, f64)> = Vec::new();
+    for point in &points {
+        let radius = point.norm() as f64 / power.sqrt();
+        match rings.iter_mut().find(|(r, _)| (r - radius).abs() < 1e-6) {
+            Some((_, share)) => *share += 1.0 / size as f64,
+            None => rings.push((radius, 1.0 / size as f64)),
+        }
+    }
+    rings
+}
+
+/// Returns the cumulants `C42` and `C63` of points of the magnitudes with a mean power of 1, each with a weight,
+/// those of a circularly symmetric constellation, whose moments of other orders vanish.
+fn cumulants(magnitudes: impl Iterator<Item = (f64, f64)>) -> (f64, f64) {
+    let (total, m42, m63) =
+        magnitudes.fold((0.0, 0.0, 0.0), |(total, m42, m63), (magnitude, weight)| {
+            let power = magnitude * magnitude;
+            (
+                total + weight,
+                m42 + weight * power * power,
+                m63 + weight * power * power * power,
+            )
+        });
+    let (m42, m63) = (m42 / total, m63 / total);
+    (m42 - 2.0, m63 - 9.0 * m42 + 12.0)
+}
+
+/// Returns the bin of the histogram of [classify_qam] of a magnitude.
+fn radial_bin(magnitude: f64) -> usize {
+    ((magnitude / RADIAL_EXTENT * RADIAL_BINS as f64) as usize).min(RADIAL_BINS - 1)
+}
+
+/// Returns the probabilities of the bins of the histogram of [classify_qam] for the constellation scaled down to
+/// the share of signal, plus complex Gaussian noise of the rest of the power: a mixture of Rice distributions.
+fn radial_probabilities(rings: &[(f64, f64)], signal: f64) -> [f64; RADIAL_BINS] {
+    // steps of the midpoint rule within a bin
+    const STEPS: usize = 8;
+    let variance = (1.0 - signal) / 2.0;
+    let width = RADIAL_EXTENT / RADIAL_BINS as f64;
+
+    let mut probabilities = [0.0; RADIAL_BINS];
+    for (bin, probability) in probabilities.iter_mut().enumerate() {
+        for step in 0..STEPS {
+            let r = (bin as f64 + (step as f64 + 0.5) / STEPS as f64) * width;
+            let density: f64 = rings
+                .iter()
+                .map(|&(radius, share)| {
+                    let amplitude = radius * signal.sqrt();
+                    share * r / variance
+                        * (-(r - amplitude).powi(2) / (2.0 * variance)).exp()
+                        * bessel_i0_scaled(r * amplitude / variance)
+                })
+                .sum();
+            *probability += density * width / STEPS as f64;
+        }
+    }
+    // the tail beyond the extent falls into the last bin, and no bin is impossible
+    let inside: f64 = probabilities.iter().sum();
+    probabilities[RADIAL_BINS - 1] += (1.0 - inside).max(0.0);
+    let total: f64 = probabilities.iter().map(|p| p.max(1e-9)).sum();
+    probabilities.map(|p| p.max(1e-9) / total)
+}
+
+/// Returns `I0(x) e^-x`, the modified Bessel function of the first kind of order 0 scaled to stay finite,
+/// with the polynomial approximations 9.8.1 and 9.8.2 of Abramowitz and Stegun, for `x >= 0`.
+fn bessel_i0_scaled(x: f64) -> f64 {
+    if x < 3.75 {
+        let t = (x / 3.75).powi(2);
+        let i0 = 1.0
+            + t * (3.5156229
+                + t * (3.0899424
+                    + t * (1.2067492 + t * (0.2659732 + t * (0.0360768 + t * 0.0045813)))));
+        i0 * (-x).exp()
+    } else {
+        let t = 3.75 / x;
+        (0.39894228
+            + t * (0.01328592
+                + t * (0.00225319
+                    + t * (-0.00157565
+                        + t * (0.00916281
+                            + t * (-0.02057706
+                                + t * (0.02635537 + t * (-0.01647633 + t * 0.00392377))))))))
+            / x.sqrt()
+    }
+}
@@ -0,0 +1,339 @@
+//! This module provides stop-and-wait ARQ for half-duplex links: the sender sends a frame, waits for its ACK,
+//! and sends it again when the ACK does not come back within a timeout, up to a number of retries.
+//!
+//! The [ArqSender] and the [ArqReceiver] are state machines without any I/O: they return the payloads of the frames
+//! to send, which the application modulates, and take the payloads it decodes, so they work over any link,
+//! like a [stream modulator](crate::stream::StreamModulator) and [demodulator](crate::stream::StreamDemodulator).
+//! Time only passes when the application [advances](ArqSender::advance_samples) the sender, by the samples
+//! it played and recorded or by the wall time, whichever the [timeout](ArqTimeout) is in.
+//!
+//! Every frame starts with a kind byte and a sequence number as a `u16` in big endian:
+//! - data: `I`, the sequence number, and the payload up to the end;
+//! - ACK: `A`, the sequence number of the data frame it acknowledges.
+//!
+//! The receiver acknowledges every data frame, also a repeated one whose ACK got lost, but delivers only the frames
+//! newer than the last one it delivered, so duplicates and late frames of a reordering link are dropped.
+//! Payloads that are not frames of the ARQ are ignored, so the link can carry other traffic.
+//!
+//! # Example
+//! ```
+//! use software_modem::arq::{ArqConfig, ArqReceiver, ArqSender, ArqTimeout};
+//!
+//! let mut sender = ArqSender::new(ArqConfig {
+//!     timeout: ArqTimeout::Samples(4800),
+//!     max_retries: 3,
+//! });
+//! let mut receiver = ArqReceiver::new();
+//!
+//! // the first transmission is lost, the sender sends the frame again after the timeout
+//! let _lost = sender.send(b"hello").unwrap();
+//! assert_eq!(sender.advance_samples(4000), None);
+//! let frame = sender.advance_samples(800).unwrap();
+//!
+//! let received = receiver.receive(&frame).unwrap();
+//! assert_eq!(received.payload.as_deref(), Some(&b"hello"[..]));
+//! assert_eq!(sender.receive(&received.ack), Some(0));
+//! assert!(sender.is_idle());
+//! ```
+
+use alloc::vec::Vec;
+use core::{fmt::Display, time::Duration};
+
+use smart_default::SmartDefault;
+
+const KIND_DATA: u8 = b'I';
+const KIND_ACK: u8 = b'A';
+const HEADER_LENGTH: usize = 1 + 2;
+
+/// Longest payload of a data frame, which fits into a frame with its header.
+pub const MAX_ARQ_PAYLOAD: usize = 65535 - HEADER_LENGTH;
+
+/// Time the [ArqSender] waits for an ACK before it sends a frame again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArqTimeout {
+    /// Samples the application [advanced](ArqSender::advance_samples) the sender by since the frame was sent,
+    /// which includes the samples of the frame itself and of its ACK.
+    Samples(u64),
+    /// Wall time the application [advanced](ArqSender::advance_time) the sender by since the frame was sent.
+    Wall(Duration),
+}
+
+/// Configuration of an [ArqSender].
+#[derive(SmartDefault, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArqConfig {
+    /// Time after sending a frame without its ACK after which it is sent again.
+    #[default(ArqTimeout::Samples(48000))]
+    pub timeout: ArqTimeout,
+    /// Times a frame is sent again before the sender gives up on it.
+    #[default(3)]
+    pub max_retries: u32,
+}
+
+/// Errors sending a payload with an [ArqSender].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArqError {
+    /// The sender still waits for the ACK of the frame with the sequence number.
+    Busy { sequence: u16 },
+    /// The payload is longer than [MAX_ARQ_PAYLOAD].
+    PayloadTooLong(usize),
+}
+
+impl Display for ArqError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ArqError::Busy { sequence } => {
+                write!(f, "Sender still waits for the ACK of frame {}", sequence)
+            }
+            ArqError::PayloadTooLong(length) => write!(
+                f,
+                "Payload must be at most {} bytes, but got {}",
+                MAX_ARQ_PAYLOAD, length
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ArqError {}
+
+/// A frame of the ARQ, the payload of a frame of the modem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArqFrame<'a> {
+    /// A payload with its sequence number.
+    Data { sequence: u16, payload: &'a [u8] },
+    /// The acknowledgement of the data frame with the sequence number.
+    Ack { sequence: u16 },
+}
+
+impl<'a> ArqFrame<'a> {
+    /// Returns the payload of the frame for the modem.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kind, sequence, payload) = match *self {
+            ArqFrame::Data { sequence, payload } => (KIND_DATA, sequence, payload),
+            ArqFrame::Ack { sequence } => (KIND_ACK, sequence, &[][..]),
+        };
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&sequence.to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    /// Reads a frame from the payload of a frame of the modem, `None` if it is no frame of the ARQ.
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        let (&[kind, high, low], payload) = bytes.split_first_chunk::<HEADER_LENGTH>()?;
+        let sequence = u16::from_be_bytes([high, low]);
+        match kind {
+            KIND_DATA => Some(ArqFrame::Data { sequence, payload }),
+            KIND_ACK if payload.is_empty() => Some(ArqFrame::Ack { sequence }),
+            _ => None,
+        }
+    }
+}
+
+/// State of an [ArqSender].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SenderState {
+    /// No frame is waiting for its ACK, the next payload can be sent.
+    Idle,
+    /// The frame with the sequence number waits for its ACK, and was sent again `retries` times.
+    Waiting { sequence: u16, retries: u32 },
+    /// The frame with the sequence number was sent again the most times without an ACK, the next payload can be sent.
+    Failed { sequence: u16 },
+}
+
+/// Sends frames and sends each again until it is acknowledged, see the [module](self) documentation.
+#[derive(Clone, Debug)]
+pub struct ArqSender {
+    config: ArqConfig,
+    state: SenderState,
+    next_sequence: u16,
+    /// The frame waiting for its ACK, sent again on a timeout.
+    frame: Vec<u8>,
+    elapsed_samples: u64,
+    elapsed_time: Duration,
+    retransmissions: u64,
+}
+
+impl ArqSender {
+    /// Creates a new idle sender, whose first frame has the sequence number 0.
+    pub fn new(config: ArqConfig) -> Self {
+        ArqSender {
+            config,
+            state: SenderState::Idle,
+            next_sequence: 0,
+            frame: Vec::new(),
+            elapsed_samples: 0,
+            elapsed_time: Duration::ZERO,
+            retransmissions: 0,
+        }
+    }
+
+    /// Returns the frame of the payload to send, with the next sequence number, and waits for its ACK.
+    ///
+    /// The timeout starts now, so the application sends the frame right away.
+    ///
+    /// # Errors
+    /// - [ArqError::Busy] if the last frame still waits for its ACK.
+    /// - [ArqError::PayloadTooLong] if the payload does not fit into a frame.
+    pub fn send(&mut self, payload: &[u8]) -> Result<Vec<u8>, ArqError> {
+        if let SenderState::Waiting { sequence, .. } = self.state {
+            return Err(ArqError::Busy { sequence });
+        }
+        if payload.len() > MAX_ARQ_PAYLOAD {
+            return Err(ArqError::PayloadTooLong(payload.len()));
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence = sequence.wrapping_add(1);
+        self.frame = ArqFrame::Data { sequence, payload }.to_bytes();
+        self.state = SenderState::Waiting {
+            sequence,
+            retries: 0,
+        };
+        self.restart_timeout();
+        Ok(self.frame.clone())
+    }
+
+    /// Takes the payload of a decoded frame, and returns the sequence number of the frame it acknowledges,
+    /// if it is the ACK of the frame waiting for one.
+    ///
+    /// Duplicated ACKs, late ACKs of earlier frames and any other payloads are ignored.
+    pub fn receive(&mut self, payload: &[u8]) -> Option<u16> {
+        match (ArqFrame::from_bytes(payload)?, self.state) {
+            (
+                ArqFrame::Ack { sequence },
+                SenderState::Waiting {
+                    sequence: waiting, ..
+                },
+            ) if sequence == waiting => {
+                self.state = SenderState::Idle;
+                self.frame.clear();
+                Some(sequence)
+            }
+            _ => None,
+        }
+    }
+
+    /// Advances the time of the sender by a number of samples, and returns the frame to send again
+    /// if the [timeout](ArqTimeout::Samples) expired without its ACK.
+    ///
+    /// When the frame was sent again the most times, the sender gives up on it instead and [fails](SenderState::Failed).
+    pub fn advance_samples(&mut self, samples: u64) -> Option<Vec<u8>> {
+        self.elapsed_samples = self.elapsed_samples.saturating_add(samples);
+        self.check_timeout()
+    }
+
+    /// Advances the time of the sender by a wall time, like [advance_samples](Self::advance_samples)
+    /// for a [timeout](ArqTimeout::Wall) in wall time.
+    pub fn advance_time(&mut self, time: Duration) -> Option<Vec<u8>> {
+        self.elapsed_time = self.elapsed_time.saturating_add(time);
+        self.check_timeout()
+    }
+
+    /// Returns the state of the sender.
+    pub fn get_state(&self) -> SenderState {
+        self.state
+    }
+
+    /// Returns whether no frame is waiting for its ACK, so the next payload can be sent.
+    pub fn is_idle(&self) -> bool {
+        !matches!(self.state, SenderState::Waiting { .. })
+    }
+
+    /// Returns the number of times frames were sent again, over all frames.
+    pub fn get_retransmissions(&self) -> u64 {
+        self.retransmissions
+    }
+
+    /// Returns the configuration of the sender.
+    pub fn get_config(&self) -> &ArqConfig {
+        &self.config
+    }
+
+    fn restart_timeout(&mut self) {
+        self.elapsed_samples = 0;
+        self.elapsed_time = Duration::ZERO;
+    }
+
+    fn check_timeout(&mut self) -> Option<Vec<u8>> {
+        let SenderState::Waiting { sequence, retries } = self.state else {
+            return None;
+        };
+        let expired = match self.config.timeout {
+            ArqTimeout::Samples(samples) => self.elapsed_samples >= samples,
+            ArqTimeout::Wall(time) => self.elapsed_time >= time,
+        };
+        if !expired {
+            return None;
+        }
+        if retries == self.config.max_retries {
+            self.state = SenderState::Failed { sequence };
+            self.frame.clear();
+            return None;
+        }
+        self.state = SenderState::Waiting {
+            sequence,
+            retries: retries + 1,
+        };
+        self.retransmissions += 1;
+        self.restart_timeout();
+        Some(self.frame.clone())
+    }
+}
+
+/// What an [ArqReceiver] made of a data frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// The payload of the ACK of the frame, which the application sends back.
+    pub ack: Vec<u8>,
+    /// The payload of the frame, `None` if it was delivered before.
+    pub payload: Option<Vec<u8>>,
+}
+
+/// Acknowledges the frames of an [ArqSender] and delivers each of them once, see the [module](self) documentation.
+#[derive(Clone, Debug, Default)]
+pub struct ArqReceiver {
+    last_sequence: Option<u16>,
+    duplicates: u64,
+}
+
+impl ArqReceiver {
+    /// Creates a new receiver, which delivers the first frame it receives whatever its sequence number.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the payload of a decoded frame, and returns its ACK with its payload if it is a data frame.
+    ///
+    /// A frame is delivered if its sequence number is newer than the one of the last frame delivered,
+    /// up to half of the sequence numbers ahead of it, wrapping around. Older ones are duplicates, acknowledged again
+    /// since the sender did not get their ACK, but not delivered.
+    pub fn receive(&mut self, payload: &[u8]) -> Option<Received> {
+        let ArqFrame::Data { sequence, payload } = ArqFrame::from_bytes(payload)? else {
+            return None;
+        };
+        let new = self
+            .last_sequence
+            .is_none_or(|last| (1..=i16::MAX as u16).contains(&sequence.wrapping_sub(last)));
+        let payload = if new {
+            self.last_sequence = Some(sequence);
+            Some(payload.to_vec())
+        } else {
+            self.duplicates += 1;
+            None
+        };
+        Some(Received {
+            ack: ArqFrame::Ack { sequence }.to_bytes(),
+            payload,
+        })
+    }
+
+    /// Returns the sequence number of the last frame delivered.
+    pub fn get_last_sequence(&self) -> Option<u16> {
+        self.last_sequence
+    }
+
+    /// Returns the number of data frames which were not delivered since they were delivered before.
+    pub fn get_duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
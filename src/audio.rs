@@ -0,0 +1,1124 @@
+//! This module provides real-time audio transmission and reception of frames, behind the `audio` feature.
+//!
+//! The [AudioTransmitter] takes payloads from any thread and the [AudioOutput] plays them from the callback of an audio device,
+//! like the output stream callback of `cpal`. The [AudioInput] takes the samples of an input stream callback
+//! and the [AudioReceiver] decodes frames from them on a worker thread.
+//! The glue is a stream whose callback calls [AudioOutput::fill] or [AudioInput::push] with the buffer of the device,
+//! which the [device] module opens on the devices of `cpal`, behind the `cpal` feature.
+//!
+//! A [ChannelMap] decides what the channels of a device carry: the modem on every channel, the modem on one
+//! and a [SyncTone] on the others, or an independent modem stream on every channel, with a transmitter and
+//! a receiver of its own from [AudioTransmitter::new_streams] and [AudioReceiver::new_streams].
+//! [mono_to_interleaved] and [interleaved_to_mono] do the same for buffers, like the interleaved samples of a WAV file.
+
+use std::{
+    collections::VecDeque,
+    f64::consts::TAU,
+    fmt::Display,
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use smart_default::SmartDefault;
+
+#[cfg(feature = "cpal")]
+pub mod device;
+
+use crate::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::Resampler,
+    stream::{InputHealth, StreamDemodulator, StreamModulator},
+};
+
+pub use crate::stream::SyncState;
+
+/// Errors setting up or using an audio stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AudioError {
+    /// The sample rate of the device can not be reached from the sample rate of the modem by a [Resampler].
+    SampleRateMismatch { modem_rate: u32, device_rate: u32 },
+    /// The device has no channels.
+    NoChannels,
+    /// The [ChannelMap] puts the modem on a channel the device does not have.
+    ChannelOutOfRange { channel: u16, channels: u16 },
+    /// The number of modulators or demodulators differs from the streams of the [ChannelMap].
+    StreamCount { expected: usize, got: usize },
+    /// The other end of the stream was dropped, the device stream has stopped.
+    Disconnected,
+    /// An error of `cpal`, querying the device or running its stream, or the device has no usable configuration.
+    #[cfg(feature = "cpal")]
+    Device(String),
+}
+
+impl Display for AudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioError::SampleRateMismatch {
+                modem_rate,
+                device_rate,
+            } => write!(
+                f,
+                "Sample rate mismatch, can not resample between the {} Hz of the modem and the {} Hz of the device",
+                modem_rate, device_rate
+            ),
+            AudioError::NoChannels => write!(f, "The audio device has no channels"),
+            AudioError::ChannelOutOfRange { channel, channels } => write!(
+                f,
+                "Channel {} is out of range, the audio device has {} channels",
+                channel, channels
+            ),
+            AudioError::StreamCount { expected, got } => write!(
+                f,
+                "The channel map carries {} streams, but got {}",
+                expected, got
+            ),
+            AudioError::Disconnected => write!(f, "The audio stream is disconnected"),
+            #[cfg(feature = "cpal")]
+            AudioError::Device(message) => write!(f, "Audio device error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+/// Sample rate and channels of an audio device, as negotiated with it.
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceConfig {
+    #[default(48000)]
+    pub sample_rate: u32,
+    /// Number of interleaved channels, what they carry is up to the [ChannelMap].
+    #[default(1)]
+    pub channels: u16,
+}
+
+/// A tone on the channels that do not carry the modem, like a pilot for a recorder or a VOX to trigger on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SyncTone {
+    /// Frequency in Hz.
+    pub frequency: f32,
+    /// Peak amplitude at full scale.
+    pub amplitude: f32,
+}
+
+/// What the channels of an audio device carry.
+///
+/// # Example
+/// ```
+/// use software_modem::audio::{ChannelLayout, ChannelMap, DeviceConfig, SyncTone, mono_to_interleaved};
+///
+/// // the modem on the right channel, and a 1 kHz tone on the left one
+/// let layout = ChannelLayout {
+///     device: DeviceConfig { sample_rate: 8000, channels: 2 },
+///     map: ChannelMap::Single {
+///         channel: 1,
+///         tone: Some(SyncTone { frequency: 1000.0, amplitude: 0.5 }),
+///     },
+/// };
+/// let interleaved = mono_to_interleaved(&[&[0.1, 0.2, 0.3]], &layout);
+/// let expected = [0.0, 0.1, 0.5 * 0.5f32.sqrt(), 0.2, 0.5, 0.3];
+/// assert!(interleaved.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-6));
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub enum ChannelMap {
+    /// Every channel carries the modem, and the input is the mean of all channels.
+    #[default]
+    Duplicate,
+    /// One channel carries the modem, starting at 0 for the left one, and the others the tone or silence.
+    /// The input is that channel alone.
+    Single {
+        channel: u16,
+        tone: Option<SyncTone>,
+    },
+    /// Every channel carries a modem stream of its own, the first one on the left channel.
+    Independent,
+}
+
+impl ChannelMap {
+    /// Returns the number of modem streams on a device with the channels.
+    pub fn get_streams(&self, channels: u16) -> usize {
+        match self {
+            ChannelMap::Duplicate | ChannelMap::Single { .. } => 1,
+            ChannelMap::Independent => usize::from(channels),
+        }
+    }
+}
+
+/// The channels of an audio device and what they carry.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct ChannelLayout {
+    pub device: DeviceConfig,
+    pub map: ChannelMap,
+}
+
+impl ChannelLayout {
+    /// Returns the number of modem streams of the layout.
+    pub fn get_streams(&self) -> usize {
+        self.map.get_streams(self.device.channels)
+    }
+
+    /// Checks that the device has channels, and the channel of the modem.
+    ///
+    /// # Errors
+    /// - [AudioError::NoChannels] if the device has no channels.
+    /// - [AudioError::ChannelOutOfRange] if the map puts the modem on a channel the device does not have.
+    pub fn check(&self) -> Result<(), AudioError> {
+        let channels = self.device.channels;
+        if channels == 0 {
+            return Err(AudioError::NoChannels);
+        }
+        match self.map {
+            ChannelMap::Single { channel, .. } if channel >= channels => {
+                Err(AudioError::ChannelOutOfRange { channel, channels })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes a sample of every stream into a frame of the channels, and advances the phase of the tone.
+    fn interleave<S: DeviceSample>(&self, streams: &[f32], tone_phase: &mut f64, frame: &mut [S]) {
+        match self.map {
+            ChannelMap::Duplicate => frame.fill(S::from_f32(streams[0])),
+            ChannelMap::Single { channel, tone } => {
+                let other = match tone {
+                    Some(tone) => {
+                        let sample = tone.amplitude * tone_phase.sin() as f32;
+                        let step =
+                            TAU * f64::from(tone.frequency) / f64::from(self.device.sample_rate);
+                        *tone_phase = (*tone_phase + step) % TAU;
+                        sample
+                    }
+                    None => 0.0,
+                };
+                frame.fill(S::from_f32(other));
+                frame[usize::from(channel)] = S::from_f32(streams[0]);
+            }
+            ChannelMap::Independent => {
+                for (output, &sample) in frame.iter_mut().zip(streams) {
+                    *output = S::from_f32(sample);
+                }
+            }
+        }
+    }
+
+    /// Returns the sample of a stream in a frame of the channels.
+    fn deinterleave<S: DeviceSample>(&self, frame: &[S], stream: usize) -> f32 {
+        match self.map {
+            ChannelMap::Duplicate => {
+                frame.iter().map(|sample| sample.to_f32()).sum::<f32>() / frame.len() as f32
+            }
+            ChannelMap::Single { channel, .. } => frame[usize::from(channel)].to_f32(),
+            ChannelMap::Independent => frame[stream].to_f32(),
+        }
+    }
+}
+
+/// Interleaves the mono signals of the streams of a layout into frames of its channels, starting the tone at the phase 0.
+///
+/// Streams shorter than the longest one are followed by silence.
+///
+/// # Panics
+/// If the layout is not [valid](ChannelLayout::check), or the number of streams differs from [its](ChannelLayout::get_streams).
+///
+/// # Example
+/// ```
+/// use software_modem::audio::{ChannelLayout, ChannelMap, DeviceConfig, interleaved_to_mono, mono_to_interleaved};
+///
+/// // two modem streams on the left and the right channel
+/// let layout = ChannelLayout {
+///     device: DeviceConfig { sample_rate: 48000, channels: 2 },
+///     map: ChannelMap::Independent,
+/// };
+/// let left = [0.1, 0.2, 0.3];
+/// let right = [-0.5, -0.25];
+/// let interleaved = mono_to_interleaved(&[&left, &right], &layout);
+/// assert_eq!(interleaved, [0.1, -0.5, 0.2, -0.25, 0.3, 0.0]);
+/// assert_eq!(interleaved_to_mono(&interleaved, &layout), [vec![0.1, 0.2, 0.3], vec![-0.5, -0.25, 0.0]]);
+/// ```
+pub fn mono_to_interleaved(streams: &[&[f32]], layout: &ChannelLayout) -> Vec<f32> {
+    if let Err(error) = layout.check() {
+        panic!("Channel layout must be valid, but got {}", error);
+    }
+    if streams.len() != layout.get_streams() {
+        panic!(
+            "Number of streams must be {}, but got {}",
+            layout.get_streams(),
+            streams.len()
+        );
+    }
+
+    let channels = usize::from(layout.device.channels);
+    let length = streams.iter().map(|stream| stream.len()).max().unwrap_or(0);
+    let mut interleaved = vec![0.0; length * channels];
+    let mut samples = vec![0.0; streams.len()];
+    let mut tone_phase = 0.0;
+    for (n, frame) in interleaved.chunks_exact_mut(channels).enumerate() {
+        for (sample, stream) in samples.iter_mut().zip(streams) {
+            *sample = stream.get(n).copied().unwrap_or(0.0);
+        }
+        layout.interleave(&samples, &mut tone_phase, frame);
+    }
+    interleaved
+}
+
+/// Splits frames of the channels of a layout into the mono signals of its streams,
+/// to be pushed into a [StreamDemodulator] each.
+///
+/// The channels of a [ChannelMap::Duplicate] are mixed down to their mean, and tones are dropped.
+///
+/// # Panics
+/// If the layout is not [valid](ChannelLayout::check), or the length of the samples is not a multiple of the channels.
+pub fn interleaved_to_mono(samples: &[f32], layout: &ChannelLayout) -> Vec<Vec<f32>> {
+    if let Err(error) = layout.check() {
+        panic!("Channel layout must be valid, but got {}", error);
+    }
+    let channels = usize::from(layout.device.channels);
+    if !samples.len().is_multiple_of(channels) {
+        panic!(
+            "Buffer length must be a multiple of {} channels, but got {}",
+            channels,
+            samples.len()
+        );
+    }
+
+    (0..layout.get_streams())
+        .map(|stream| {
+            samples
+                .chunks_exact(channels)
+                .map(|frame| layout.deinterleave(frame, stream))
+                .collect()
+        })
+        .collect()
+}
+
+/// A sample format of an audio device.
+pub trait DeviceSample: Copy {
+    /// Converts a sample at full scale `[-1, 1]`, saturating beyond it.
+    fn from_f32(sample: f32) -> Self;
+
+    /// Converts the sample to full scale `[-1, 1]`.
+    fn to_f32(self) -> f32;
+}
+
+impl DeviceSample for f32 {
+    fn from_f32(sample: f32) -> Self {
+        sample.clamp(-1.0, 1.0)
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl DeviceSample for i16 {
+    fn from_f32(sample: f32) -> Self {
+        // the cast saturates
+        (sample * 32768.0).round() as i16
+    }
+
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 32768.0
+    }
+}
+
+impl DeviceSample for u16 {
+    fn from_f32(sample: f32) -> Self {
+        (i16::from_f32(sample) as u16) ^ 0x8000
+    }
+
+    fn to_f32(self) -> f32 {
+        ((self ^ 0x8000) as i16).to_f32()
+    }
+}
+
+/// Configuration of an [AudioTransmitter].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct TransmitterConfig {
+    /// Sample rate the frames are modulated at, they are resampled to the rate of the device if it differs.
+    #[default(48000)]
+    pub modem_rate: u32,
+    pub device: DeviceConfig,
+    /// What the channels of the device play.
+    pub channel_map: ChannelMap,
+    /// Samples of silence after every frame, at the modem rate,
+    /// so that a receiver can tell consecutive frames apart.
+    #[default(4800)]
+    pub frame_gap: usize,
+}
+
+/// State shared between the transmitter and the output.
+struct Shared {
+    frames: Mutex<VecDeque<Vec<f32>>>,
+    /// The transmitter has been shut down, no frames will follow.
+    closed: AtomicBool,
+    /// The output has been dropped, nothing plays the frames anymore.
+    disconnected: AtomicBool,
+}
+
+/// Modulates payloads and queues them for an [AudioOutput].
+///
+/// [send](AudioTransmitter::send) encodes and resamples on the calling thread, the device callback only copies samples.
+/// Silence is played while the queue is empty, and [shutdown](AudioTransmitter::shutdown)
+/// lets the output play the queued frames before it [finishes](AudioOutput::is_finished).
+///
+/// # Example
+/// ```
+/// use software_modem::audio::{AudioTransmitter, DeviceConfig, TransmitterConfig};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::modulator::OutputScale;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     output_scale: OutputScale::PeakNormalize(0.5),
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let frame_length = modulator.get_frame_length(5);
+///
+/// let config = TransmitterConfig {
+///     device: DeviceConfig { sample_rate: 48000, channels: 2 },
+///     frame_gap: 100,
+///     ..Default::default()
+/// };
+/// let (transmitter, mut output) = AudioTransmitter::new(modulator, &config).unwrap();
+/// let sender = std::thread::spawn(move || {
+///     transmitter.send(b"first").unwrap();
+///     transmitter.send(b"again").unwrap();
+///     transmitter.shutdown();
+/// });
+/// sender.join().unwrap();
+///
+/// // the device callback asks for blocks of interleaved stereo samples
+/// let mut played = Vec::new();
+/// while !output.is_finished() {
+///     let mut block = [0i16; 2 * 480];
+///     output.fill(&mut block);
+///     played.extend(block.chunks(2).map(|frame| f32::from(frame[0]) / 32768.0));
+/// }
+///
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+/// assert_eq!(demodulator.decode_frame(&played).unwrap(), b"first");
+/// assert_eq!(demodulator.decode_frame(&played[frame_length + 100..]).unwrap(), b"again");
+/// ```
+pub struct AudioTransmitter {
+    modulator: StreamModulator,
+    /// Resampler from the modem to the device rate, if they differ.
+    resampler: Option<Mutex<Resampler>>,
+    shared: Arc<Shared>,
+}
+
+impl AudioTransmitter {
+    /// Creates a transmitter and the output to play its frames from the device callback.
+    ///
+    /// # Errors
+    /// - [AudioError::SampleRateMismatch] if the modem rate can not be resampled to the device rate.
+    /// - [AudioError::NoChannels] if the device has no channels.
+    /// - [AudioError::ChannelOutOfRange] if the channel map puts the modem on a channel the device does not have.
+    /// - [AudioError::StreamCount] if the channel map carries more than one stream.
+    pub fn new(
+        modulator: CodedOFDMModulator,
+        config: &TransmitterConfig,
+    ) -> Result<(AudioTransmitter, AudioOutput), AudioError> {
+        let (mut transmitters, output) = AudioTransmitter::new_streams(vec![modulator], config)?;
+        Ok((transmitters.remove(0), output))
+    }
+
+    /// Creates a transmitter for every stream of the channel map and the output to play their frames on their channels.
+    ///
+    /// Every transmitter queues the frames of its stream on its own, the output plays silence on the channel
+    /// of a stream whose queue is empty.
+    ///
+    /// # Errors
+    /// - [AudioError::SampleRateMismatch] if the modem rate can not be resampled to the device rate.
+    /// - [AudioError::NoChannels] if the device has no channels.
+    /// - [AudioError::ChannelOutOfRange] if the channel map puts the modem on a channel the device does not have.
+    /// - [AudioError::StreamCount] if the number of modulators differs from the streams of the channel map.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::audio::{AudioTransmitter, ChannelMap, DeviceConfig, TransmitterConfig};
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OutputScale;
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     output_scale: OutputScale::PeakNormalize(0.5),
+    ///     ..Default::default()
+    /// };
+    /// let modulator = || CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let config = TransmitterConfig {
+    ///     device: DeviceConfig { sample_rate: 48000, channels: 2 },
+    ///     channel_map: ChannelMap::Independent,
+    ///     ..Default::default()
+    /// };
+    /// let (transmitters, mut output) = AudioTransmitter::new_streams(vec![modulator(), modulator()], &config).unwrap();
+    /// let [left, right] = <[AudioTransmitter; 2]>::try_from(transmitters).ok().unwrap();
+    /// left.send(b"left").unwrap();
+    /// right.send(b"right").unwrap();
+    /// left.shutdown();
+    /// right.shutdown();
+    ///
+    /// let mut played = Vec::new();
+    /// while !output.is_finished() {
+    ///     let mut block = [0.0f32; 2 * 480];
+    ///     output.fill(&mut block);
+    ///     played.extend(block);
+    /// }
+    /// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    /// let channel = |c: usize| played.iter().skip(c).step_by(2).copied().collect::<Vec<f32>>();
+    /// assert_eq!(demodulator.decode_frame(&channel(0)).unwrap(), b"left");
+    /// assert_eq!(demodulator.decode_frame(&channel(1)).unwrap(), b"right");
+    /// ```
+    pub fn new_streams(
+        modulators: Vec<CodedOFDMModulator>,
+        config: &TransmitterConfig,
+    ) -> Result<(Vec<AudioTransmitter>, AudioOutput), AudioError> {
+        check_rates(config.modem_rate, config.device.sample_rate)?;
+        let layout = ChannelLayout {
+            device: config.device,
+            map: config.channel_map,
+        };
+        layout.check()?;
+        if modulators.len() != layout.get_streams() {
+            return Err(AudioError::StreamCount {
+                expected: layout.get_streams(),
+                got: modulators.len(),
+            });
+        }
+
+        let mut transmitters = Vec::with_capacity(modulators.len());
+        let mut streams = Vec::with_capacity(modulators.len());
+        for modulator in modulators {
+            let shared = Arc::new(Shared {
+                frames: Mutex::new(VecDeque::new()),
+                closed: AtomicBool::new(false),
+                disconnected: AtomicBool::new(false),
+            });
+            transmitters.push(AudioTransmitter {
+                modulator: StreamModulator::new(modulator, config.frame_gap),
+                resampler: (config.modem_rate != config.device.sample_rate).then(|| {
+                    Mutex::new(Resampler::new(config.modem_rate, config.device.sample_rate))
+                }),
+                shared: shared.clone(),
+            });
+            streams.push(OutputStream {
+                current: Vec::new(),
+                position: 0,
+                underrun: false,
+                shared,
+            });
+        }
+        let output = AudioOutput {
+            layout,
+            samples: vec![0.0; streams.len()],
+            streams,
+            tone_phase: 0.0,
+        };
+        Ok((transmitters, output))
+    }
+
+    /// Modulates the payload and queues its frame, followed by the frame gap.
+    ///
+    /// # Panics
+    /// If the payload is longer than 65535 bytes.
+    ///
+    /// # Errors
+    /// [AudioError::Disconnected] if the output has been dropped.
+    pub fn send(&self, payload: &[u8]) -> Result<(), AudioError> {
+        if self.shared.disconnected.load(Ordering::Acquire) {
+            return Err(AudioError::Disconnected);
+        }
+        let mut frame = Vec::with_capacity(self.modulator.get_frame_length(payload.len()));
+        let Ok(()) = self.modulator.write_frame(payload, &mut frame);
+        // the resampler runs over the frames and gaps as one stream, its delay shifts into the gap
+        if let Some(resampler) = &self.resampler {
+            frame = resampler.lock().unwrap().process(&frame);
+        }
+        self.shared.frames.lock().unwrap().push_back(frame);
+        Ok(())
+    }
+
+    /// Returns the number of samples at the device rate that are queued and not yet played, including the frame gaps.
+    ///
+    /// The frame the output is playing is not counted.
+    pub fn pending_samples(&self) -> usize {
+        self.shared
+            .frames
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Stops accepting payloads, the output still plays the queued frames.
+    pub fn shutdown(self) {}
+}
+
+impl Drop for AudioTransmitter {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+/// The device side of an [AudioTransmitter], to be called from the callback of the output stream.
+///
+/// See [AudioTransmitter] for an example.
+pub struct AudioOutput {
+    layout: ChannelLayout,
+    streams: Vec<OutputStream>,
+    /// The sample of every stream in the frame being filled.
+    samples: Vec<f32>,
+    tone_phase: f64,
+}
+
+/// The frames of a transmitter being played by an [AudioOutput].
+struct OutputStream {
+    /// The frame being played, taken from the queue as a whole.
+    current: Vec<f32>,
+    position: usize,
+    /// Whether the transmitter held the queue when the callback wanted the next frame.
+    underrun: bool,
+    shared: Arc<Shared>,
+}
+
+impl OutputStream {
+    /// Returns the next sample, the next frame from the queue if the current one has been played, or silence.
+    fn next_sample(&mut self) -> f32 {
+        if self.position == self.current.len() && !self.underrun {
+            let next = match self.shared.frames.try_lock() {
+                Ok(mut frames) => frames.pop_front(),
+                Err(_) => {
+                    self.underrun = true;
+                    None
+                }
+            };
+            if let Some(next) = next {
+                self.current = next;
+                self.position = 0;
+            }
+        }
+        match self.current.get(self.position) {
+            Some(&sample) => {
+                self.position += 1;
+                sample
+            }
+            None => 0.0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+            && self.position == self.current.len()
+            && self.shared.frames.lock().unwrap().is_empty()
+    }
+}
+
+impl AudioOutput {
+    /// Fills a buffer of interleaved device samples, with the streams on the channels of the [ChannelMap].
+    ///
+    /// Plays silence when no frame is queued. The callback never waits for the transmitter,
+    /// if [send](AudioTransmitter::send) holds the queue, the next frame starts a callback later.
+    ///
+    /// # Panics
+    /// If the length of the buffer is not a multiple of the channels.
+    pub fn fill<S: DeviceSample>(&mut self, output: &mut [S]) {
+        let channels = usize::from(self.layout.device.channels);
+        if !output.len().is_multiple_of(channels) {
+            panic!(
+                "Buffer length must be a multiple of {} channels, but got {}",
+                channels,
+                output.len()
+            );
+        }
+
+        for stream in &mut self.streams {
+            stream.underrun = false;
+        }
+        for frame in output.chunks_exact_mut(channels) {
+            for (sample, stream) in self.samples.iter_mut().zip(&mut self.streams) {
+                *sample = stream.next_sample();
+            }
+            self.layout
+                .interleave(&self.samples, &mut self.tone_phase, frame);
+        }
+        if self.streams.iter().any(|stream| stream.underrun) {
+            trace_event!(DEBUG, "underrun", samples = output.len() / channels);
+        }
+    }
+
+    /// Returns whether the transmitters have been shut down and every queued frame has been played.
+    pub fn is_finished(&self) -> bool {
+        self.streams.iter().all(OutputStream::is_finished)
+    }
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        for stream in &self.streams {
+            stream.shared.disconnected.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Configuration of an [AudioReceiver].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct ReceiverConfig {
+    /// Sample rate the frames are demodulated at, the input is resampled to it from the rate of the device if it differs.
+    #[default(48000)]
+    pub modem_rate: u32,
+    pub device: DeviceConfig,
+    /// Which channels of the device are received.
+    pub channel_map: ChannelMap,
+    /// Magnitude at full scale above which the squelch opens and a frame is assumed to start.
+    #[default(0.01)]
+    pub squelch_level: f32,
+    /// Samples below the squelch level that end a frame,
+    /// shorter than the [frame gap](TransmitterConfig::frame_gap) of the transmitter.
+    #[default(2400)]
+    pub hang: usize,
+    /// Capacity of the buffer between the device callback and the worker, in samples.
+    #[default(48000)]
+    pub buffer_length: usize,
+}
+
+/// Live metrics of an [AudioReceiver].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct ReceiverMetrics {
+    /// Peak level of the last block of input in dBFS.
+    #[default(f32::NEG_INFINITY)]
+    pub input_level_db: f32,
+    /// Health of the input over the last window of the stream, see [input_stats](StreamDemodulator::input_stats).
+    pub input_health: InputHealth,
+    pub sync_state: SyncState,
+    /// Frames decoded with a valid CRC.
+    pub frames_decoded: usize,
+    /// Bursts above the squelch level that did not decode, like noise or corrupted frames.
+    pub frames_failed: usize,
+    /// Device callbacks whose samples did not fit into the buffer and were dropped,
+    /// because the worker could not keep up.
+    pub overruns: usize,
+}
+
+/// State shared between the receiver, its worker and the input.
+struct ReceiverShared {
+    buffer: Mutex<Vec<f32>>,
+    available: Condvar,
+    buffer_length: usize,
+    metrics: Mutex<ReceiverMetrics>,
+    overruns: AtomicUsize,
+    /// The receiver has been dropped, the worker stops right away.
+    stopped: AtomicBool,
+    /// The input has been dropped, the worker decodes the rest of the buffer and stops.
+    input_closed: AtomicBool,
+}
+
+/// Decodes frames from the samples of an [AudioInput] on a worker thread.
+///
+/// The worker feeds the samples to a [StreamDemodulator], which passes them through a squelch
+/// and synchronizes and decodes the bursts above it. Decoded payloads are delivered in order through
+/// [try_recv](AudioReceiver::try_recv) and [recv_timeout](AudioReceiver::recv_timeout).
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use software_modem::audio::{AudioReceiver, AudioTransmitter, DeviceConfig, ReceiverConfig, TransmitterConfig};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::modulator::OutputScale;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     differential_time: true,
+///     output_scale: OutputScale::PeakNormalize(0.5),
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+/// let (transmitter, mut output) = AudioTransmitter::new(modulator, &TransmitterConfig::default()).unwrap();
+///
+/// // a stereo input device delivering 16-bit samples
+/// let config = ReceiverConfig {
+///     device: DeviceConfig { sample_rate: 48000, channels: 2 },
+///     ..Default::default()
+/// };
+/// let (receiver, mut input) = AudioReceiver::new(demodulator, &config).unwrap();
+///
+/// transmitter.send(b"over the air").unwrap();
+/// transmitter.send(b"and again").unwrap();
+/// transmitter.shutdown();
+///
+/// // the air between the speaker and the microphone delays and attenuates the signal, and adds some noise
+/// let mut noise: u32 = 0x1234_5678;
+/// let mut air = vec![0.0; 1234];
+/// while !output.is_finished() {
+///     let mut played = [0.0f32; 480];
+///     output.fill(&mut played);
+///     air.extend(played);
+///     let recorded: Vec<i16> = air
+///         .drain(..480)
+///         .flat_map(|sample| {
+///             noise ^= noise << 13;
+///             noise ^= noise >> 17;
+///             noise ^= noise << 5;
+///             let sample = 0.3 * sample + 0.002 * (noise as f32 / u32::MAX as f32 - 0.5);
+///             [(sample * 32768.0) as i16; 2]
+///         })
+///         .collect();
+///     input.push(&recorded);
+/// }
+/// drop(input);
+///
+/// assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), b"over the air");
+/// assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), b"and again");
+/// let metrics = receiver.metrics();
+/// assert_eq!((metrics.frames_decoded, metrics.frames_failed, metrics.overruns), (2, 0, 0));
+/// ```
+pub struct AudioReceiver {
+    frames: mpsc::Receiver<Vec<u8>>,
+    shared: Arc<ReceiverShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AudioReceiver {
+    /// Creates a receiver with its worker thread, and the input to push the samples of the device callback into.
+    ///
+    /// # Panics
+    /// If the squelch level is not positive and finite, or the hang or the buffer length are 0.
+    ///
+    /// # Errors
+    /// - [AudioError::SampleRateMismatch] if the device rate can not be resampled to the modem rate.
+    /// - [AudioError::NoChannels] if the device has no channels.
+    /// - [AudioError::ChannelOutOfRange] if the channel map puts the modem on a channel the device does not have.
+    /// - [AudioError::StreamCount] if the channel map carries more than one stream.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use software_modem::audio::{AudioReceiver, AudioTransmitter, DeviceConfig, ReceiverConfig, TransmitterConfig};
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OutputScale;
+    ///
+    /// // a modem at 48 kHz, whose subcarriers stay below the passband of the resamplers at 17.6 kHz
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     differential_time: true,
+    ///     guard_subcarriers_high: 18,
+    ///     output_scale: OutputScale::PeakNormalize(0.5),
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+    ///
+    /// // sound cards that only run at 44.1 kHz
+    /// let device = DeviceConfig { sample_rate: 44100, channels: 1 };
+    /// let (transmitter, mut output) =
+    ///     AudioTransmitter::new(modulator, &TransmitterConfig { device, ..Default::default() }).unwrap();
+    /// let (receiver, mut input) =
+    ///     AudioReceiver::new(demodulator, &ReceiverConfig { device, ..Default::default() }).unwrap();
+    ///
+    /// let payload = "Resampled to 44.1 kHz and back".as_bytes();
+    /// transmitter.send(payload).unwrap();
+    /// transmitter.shutdown();
+    /// while !output.is_finished() {
+    ///     let mut block = [0.0f32; 441];
+    ///     output.fill(&mut block);
+    ///     input.push(&block);
+    /// }
+    /// drop(input);
+    /// assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), payload);
+    ///
+    /// // 48 kHz can not be resampled to 47.999 kHz
+    /// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    /// let device = DeviceConfig { sample_rate: 47999, channels: 1 };
+    /// assert!(AudioReceiver::new(demodulator, &ReceiverConfig { device, ..Default::default() }).is_err());
+    /// ```
+    pub fn new(
+        demodulator: CodedOFDMDemodulator,
+        config: &ReceiverConfig,
+    ) -> Result<(AudioReceiver, AudioInput), AudioError> {
+        let (mut receivers, input) = AudioReceiver::new_streams(vec![demodulator], config)?;
+        Ok((receivers.remove(0), input))
+    }
+
+    /// Creates a receiver with its worker thread for every stream of the channel map,
+    /// and the input to push the samples of the device callback into.
+    ///
+    /// # Panics
+    /// If the squelch level is not positive and finite, or the hang or the buffer length are 0.
+    ///
+    /// # Errors
+    /// - [AudioError::SampleRateMismatch] if the device rate can not be resampled to the modem rate.
+    /// - [AudioError::NoChannels] if the device has no channels.
+    /// - [AudioError::ChannelOutOfRange] if the channel map puts the modem on a channel the device does not have.
+    /// - [AudioError::StreamCount] if the number of demodulators differs from the streams of the channel map.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use software_modem::audio::{
+    ///     AudioReceiver, AudioTransmitter, ChannelMap, DeviceConfig, ReceiverConfig, TransmitterConfig,
+    /// };
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OutputScale;
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     differential_time: true,
+    ///     output_scale: OutputScale::PeakNormalize(0.5),
+    ///     ..Default::default()
+    /// };
+    /// let device = DeviceConfig { sample_rate: 48000, channels: 2 };
+    /// let channel_map = ChannelMap::Independent;
+    /// let modulators = (0..2).map(|_| CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default())).collect();
+    /// let demodulators = (0..2).map(|_| CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default())).collect();
+    /// let config = TransmitterConfig { device, channel_map, ..Default::default() };
+    /// let (transmitters, mut output) = AudioTransmitter::new_streams(modulators, &config).unwrap();
+    /// let config = ReceiverConfig { device, channel_map, ..Default::default() };
+    /// let (receivers, mut input) = AudioReceiver::new_streams(demodulators, &config).unwrap();
+    ///
+    /// transmitters[0].send(b"on the left").unwrap();
+    /// transmitters[1].send(b"on the right").unwrap();
+    /// drop(transmitters);
+    /// while !output.is_finished() {
+    ///     let mut block = [0i16; 2 * 480];
+    ///     output.fill(&mut block);
+    ///     input.push(&block);
+    /// }
+    /// drop(input);
+    ///
+    /// assert_eq!(receivers[0].recv_timeout(Duration::from_secs(10)).unwrap(), b"on the left");
+    /// assert_eq!(receivers[1].recv_timeout(Duration::from_secs(10)).unwrap(), b"on the right");
+    /// ```
+    pub fn new_streams(
+        demodulators: Vec<CodedOFDMDemodulator>,
+        config: &ReceiverConfig,
+    ) -> Result<(Vec<AudioReceiver>, AudioInput), AudioError> {
+        if config.buffer_length == 0 {
+            panic!("Buffer length must be at least 1, but got 0");
+        }
+        check_rates(config.modem_rate, config.device.sample_rate)?;
+        let layout = ChannelLayout {
+            device: config.device,
+            map: config.channel_map,
+        };
+        layout.check()?;
+        if demodulators.len() != layout.get_streams() {
+            return Err(AudioError::StreamCount {
+                expected: layout.get_streams(),
+                got: demodulators.len(),
+            });
+        }
+
+        let mut receivers = Vec::with_capacity(demodulators.len());
+        let mut streams = Vec::with_capacity(demodulators.len());
+        for demodulator in demodulators {
+            let shared = Arc::new(ReceiverShared {
+                buffer: Mutex::new(Vec::with_capacity(config.buffer_length)),
+                available: Condvar::new(),
+                buffer_length: config.buffer_length,
+                metrics: Mutex::new(ReceiverMetrics::default()),
+                overruns: AtomicUsize::new(0),
+                stopped: AtomicBool::new(false),
+                input_closed: AtomicBool::new(false),
+            });
+            let (sender, frames) = mpsc::channel();
+            let stream = StreamDemodulator::new(demodulator, config.squelch_level, config.hang);
+            let resampler = (config.modem_rate != config.device.sample_rate)
+                .then(|| Resampler::new(config.device.sample_rate, config.modem_rate));
+            let worker_shared = shared.clone();
+            let worker = std::thread::spawn(move || {
+                receive(stream, resampler, &worker_shared, sender);
+            });
+            streams.push(shared.clone());
+            receivers.push(AudioReceiver {
+                frames,
+                shared,
+                worker: Some(worker),
+            });
+        }
+        Ok((receivers, AudioInput { layout, streams }))
+    }
+
+    /// Returns the next decoded payload, if one has arrived.
+    pub fn try_recv(&self) -> Option<Vec<u8>> {
+        self.frames.try_recv().ok()
+    }
+
+    /// Waits for the next decoded payload, and returns `None` if none arrives within the timeout
+    /// or the input has been dropped and everything has been decoded.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        self.frames.recv_timeout(timeout).ok()
+    }
+
+    /// Returns whether the input has been dropped and the worker has decoded everything pushed before.
+    ///
+    /// The payloads decoded last may still wait to be received.
+    pub fn is_finished(&self) -> bool {
+        self.worker.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Returns the current metrics.
+    pub fn metrics(&self) -> ReceiverMetrics {
+        ReceiverMetrics {
+            overruns: self.shared.overruns.load(Ordering::Relaxed),
+            ..*self.shared.metrics.lock().unwrap()
+        }
+    }
+}
+
+impl Drop for AudioReceiver {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        self.shared.available.notify_one();
+        if let Some(worker) = self.worker.take() {
+            // a panic of the worker has already been reported on its thread
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The device side of an [AudioReceiver], to be called from the callback of the input stream.
+///
+/// See [AudioReceiver] for an example.
+pub struct AudioInput {
+    layout: ChannelLayout,
+    /// The receiver of every stream.
+    streams: Vec<Arc<ReceiverShared>>,
+}
+
+impl AudioInput {
+    /// Pushes a buffer of interleaved device samples, taking the streams from the channels of the [ChannelMap].
+    ///
+    /// If the buffer between the callback and the worker of a stream is full, the samples that do not fit are dropped
+    /// and an [overrun](ReceiverMetrics::overruns) is counted.
+    ///
+    /// # Panics
+    /// If the length of the buffer is not a multiple of the channels.
+    pub fn push<S: DeviceSample>(&mut self, input: &[S]) {
+        let channels = usize::from(self.layout.device.channels);
+        if !input.len().is_multiple_of(channels) {
+            panic!(
+                "Buffer length must be a multiple of {} channels, but got {}",
+                channels,
+                input.len()
+            );
+        }
+
+        let frames = input.len() / channels;
+        for (stream, shared) in self.streams.iter().enumerate() {
+            let mut buffer = shared.buffer.lock().unwrap();
+            let free = shared.buffer_length - buffer.len();
+            if frames > free {
+                shared.overruns.fetch_add(1, Ordering::Relaxed);
+                trace_event!(DEBUG, "overrun", dropped = frames - free);
+            }
+            buffer.extend(
+                input
+                    .chunks_exact(channels)
+                    .take(free)
+                    .map(|frame| self.layout.deinterleave(frame, stream)),
+            );
+            drop(buffer);
+            shared.available.notify_one();
+        }
+    }
+}
+
+impl Drop for AudioInput {
+    fn drop(&mut self) {
+        for shared in &self.streams {
+            shared.input_closed.store(true, Ordering::Release);
+            shared.available.notify_one();
+        }
+    }
+}
+
+/// The worker of an [AudioReceiver].
+fn receive(
+    mut stream: StreamDemodulator,
+    mut resampler: Option<Resampler>,
+    shared: &ReceiverShared,
+    sender: mpsc::Sender<Vec<u8>>,
+) {
+    let mut block = Vec::with_capacity(shared.buffer_length);
+    loop {
+        {
+            let mut buffer = shared.buffer.lock().unwrap();
+            while buffer.is_empty()
+                && !shared.stopped.load(Ordering::Acquire)
+                && !shared.input_closed.load(Ordering::Acquire)
+            {
+                buffer = shared.available.wait(buffer).unwrap();
+            }
+            // swap the buffers, so that the callback keeps pushing without allocating
+            std::mem::swap(&mut *buffer, &mut block);
+        }
+        if shared.stopped.load(Ordering::Acquire) {
+            return;
+        }
+        let closed = block.is_empty();
+
+        let mut decoded = match &mut resampler {
+            Some(resampler) => stream.push(&resampler.process(&block)),
+            None => stream.push(&block),
+        };
+        if closed {
+            decoded.extend(stream.flush());
+        }
+        let peak = block.iter().map(|x| x.abs()).fold(0.0, f32::max);
+        block.clear();
+
+        {
+            let mut metrics = shared.metrics.lock().unwrap();
+            if !closed {
+                metrics.input_level_db = 20.0 * peak.log10();
+            }
+            metrics.input_health = stream.input_stats().health;
+            metrics.sync_state = stream.get_sync_state();
+            metrics.frames_decoded = stream.get_frames_decoded();
+            metrics.frames_failed = stream.get_frames_failed();
+        }
+        for payload in decoded {
+            if sender.send(payload).is_err() {
+                return;
+            }
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Checks that a [Resampler] can bridge the modem and the device rate.
+///
+/// # Errors
+/// [AudioError::SampleRateMismatch] if the rates differ and are not supported by the resampler.
+fn check_rates(modem_rate: u32, device_rate: u32) -> Result<(), AudioError> {
+    if modem_rate == device_rate || Resampler::supports(modem_rate, device_rate) {
+        Ok(())
+    } else {
+        Err(AudioError::SampleRateMismatch {
+            modem_rate,
+            device_rate,
+        })
+    }
+}
@@ -0,0 +1,394 @@
+//! This module provides the streams of `cpal` that play an [AudioOutput] and feed an [AudioInput], behind the `cpal` feature.
+//!
+//! [AudioTransmitter::open] negotiates a configuration with a device, opens an output stream of it
+//! and plays the frames of the transmitter from its callback, until the [OutputDevice] is dropped.
+//! [AudioReceiver::open] does the same with an input stream, whose callback pushes the captured samples
+//! to the receiver until the [InputDevice] is dropped.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+pub use cpal;
+use cpal::{
+    SampleFormat, SizedSample, StreamConfig, SupportedStreamConfig, SupportedStreamConfigRange,
+    traits::{DeviceTrait, StreamTrait},
+};
+
+use super::{
+    AudioError, AudioInput, AudioOutput, AudioReceiver, AudioTransmitter, DeviceConfig,
+    DeviceSample, ReceiverConfig, TransmitterConfig, check_rates,
+};
+use crate::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+
+/// Sample formats of the devices the modem plays and captures, in the order of preference.
+const SAMPLE_FORMATS: [SampleFormat; 3] = [SampleFormat::F32, SampleFormat::I16, SampleFormat::U16];
+
+/// Device rates tried after the modem rate and the default rate of the device, in the order of preference.
+const DEVICE_RATES: [u32; 6] = [48000, 44100, 96000, 32000, 16000, 8000];
+
+/// The last error reported by the error callback of a stream.
+type StreamError = Arc<Mutex<Option<AudioError>>>;
+
+/// An output stream of `cpal` playing the frames of an [AudioTransmitter], from [AudioTransmitter::open].
+///
+/// The stream plays until it is dropped. After [shutdown](AudioTransmitter::shutdown), it plays the queued frames
+/// and [is_finished](OutputDevice::is_finished) tells when they have been played.
+pub struct OutputDevice {
+    /// Plays until it is dropped.
+    _stream: cpal::Stream,
+    config: DeviceConfig,
+    sample_format: SampleFormat,
+    finished: Arc<AtomicBool>,
+    error: StreamError,
+}
+
+impl OutputDevice {
+    /// Returns the sample rate and channels negotiated with the device.
+    pub fn get_config(&self) -> DeviceConfig {
+        self.config
+    }
+
+    /// Returns the sample format negotiated with the device.
+    pub fn get_sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    /// Returns whether the transmitters have been shut down and the stream has played every queued frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// Returns the last error of the stream since the previous call, like an underrun of the device.
+    pub fn take_error(&self) -> Option<AudioError> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+/// An input stream of `cpal` pushing what it captures to [AudioReceiver]s, from [AudioReceiver::open].
+///
+/// The stream captures until it is dropped, then the receivers decode what was captured before and
+/// [finish](AudioReceiver::is_finished).
+pub struct InputDevice {
+    /// Captures until it is dropped.
+    _stream: cpal::Stream,
+    config: DeviceConfig,
+    sample_format: SampleFormat,
+    error: StreamError,
+}
+
+impl InputDevice {
+    /// Returns the sample rate and channels negotiated with the device.
+    pub fn get_config(&self) -> DeviceConfig {
+        self.config
+    }
+
+    /// Returns the sample format negotiated with the device.
+    pub fn get_sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    /// Returns the last error of the stream since the previous call, like an overrun of the device.
+    ///
+    /// The overruns of the buffer between the callback and a receiver are counted in its [metrics](AudioReceiver::metrics).
+    pub fn take_error(&self) -> Option<AudioError> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+impl AudioTransmitter {
+    /// Creates a transmitter, and plays its frames on an output stream of the device.
+    ///
+    /// The device is opened at the modem rate, or at a rate the modem can be resampled to,
+    /// with the channels of [TransmitterConfig::device] if it has them, and samples of `f32`, `i16` or `u16`.
+    /// The negotiated configuration replaces the one of the transmitter config.
+    ///
+    /// # Errors
+    /// - [AudioError::Device] if the device can not be queried, has no configuration of these sample formats,
+    ///   or the stream can not be opened.
+    /// - [AudioError::SampleRateMismatch] if the device supports no rate the modem can be resampled to.
+    /// - The errors of [AudioTransmitter::new] for the negotiated configuration.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use software_modem::audio::{AudioTransmitter, TransmitterConfig};
+    /// use software_modem::audio::device::cpal::{self, traits::HostTrait};
+    /// use software_modem::coded::CodedOFDMModulator;
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    ///
+    /// let device = cpal::default_host().default_output_device().unwrap();
+    /// let modulator = CodedOFDMModulator::new(OFDMConfig::default(), CodingConfig::default());
+    /// let (transmitter, output) = AudioTransmitter::open(&device, modulator, &TransmitterConfig::default()).unwrap();
+    /// transmitter.send(b"over the speakers").unwrap();
+    /// transmitter.shutdown();
+    /// while !output.is_finished() {
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    /// }
+    /// ```
+    pub fn open(
+        device: &cpal::Device,
+        modulator: CodedOFDMModulator,
+        config: &TransmitterConfig,
+    ) -> Result<(AudioTransmitter, OutputDevice), AudioError> {
+        let (mut transmitters, output) =
+            AudioTransmitter::open_streams(device, vec![modulator], config)?;
+        Ok((transmitters.remove(0), output))
+    }
+
+    /// Creates a transmitter for every stream of the channel map, and plays their frames on an output stream of the device.
+    ///
+    /// The device is negotiated like in [open](AudioTransmitter::open).
+    ///
+    /// # Errors
+    /// - The errors of [open](AudioTransmitter::open).
+    /// - [AudioError::StreamCount] if the number of modulators differs from the streams of the channel map
+    ///   on the negotiated channels.
+    pub fn open_streams(
+        device: &cpal::Device,
+        modulators: Vec<CodedOFDMModulator>,
+        config: &TransmitterConfig,
+    ) -> Result<(Vec<AudioTransmitter>, OutputDevice), AudioError> {
+        let ranges = device.supported_output_configs().map_err(device_error)?;
+        let default_rate = device
+            .default_output_config()
+            .ok()
+            .map(|config| config.sample_rate());
+        let supported = negotiate(
+            ranges,
+            default_rate,
+            config.modem_rate,
+            config.device.channels,
+        )?;
+        let device_config = DeviceConfig {
+            sample_rate: supported.sample_rate(),
+            channels: supported.channels(),
+        };
+        let (transmitters, output) = AudioTransmitter::new_streams(
+            modulators,
+            &TransmitterConfig {
+                device: device_config,
+                ..*config
+            },
+        )?;
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let error = StreamError::default();
+        let stream_config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => play::<f32>(device, stream_config, output, &finished, &error),
+            SampleFormat::I16 => play::<i16>(device, stream_config, output, &finished, &error),
+            _ => play::<u16>(device, stream_config, output, &finished, &error),
+        }?;
+        stream.play().map_err(device_error)?;
+        Ok((
+            transmitters,
+            OutputDevice {
+                _stream: stream,
+                config: device_config,
+                sample_format: supported.sample_format(),
+                finished,
+                error,
+            },
+        ))
+    }
+}
+
+impl AudioReceiver {
+    /// Creates a receiver with its worker thread, and feeds it the samples captured by an input stream of the device.
+    ///
+    /// The device is negotiated like in [AudioTransmitter::open], with the channels of [ReceiverConfig::device]
+    /// if it has them, and the modem is received from the negotiated channels as the channel map tells.
+    ///
+    /// # Panics
+    /// If the squelch level is not positive and finite, or the hang or the buffer length are 0.
+    ///
+    /// # Errors
+    /// - [AudioError::Device] if the device can not be queried, has no configuration of these sample formats,
+    ///   or the stream can not be opened.
+    /// - [AudioError::SampleRateMismatch] if the device supports no rate that can be resampled to the modem rate.
+    /// - The errors of [AudioReceiver::new] for the negotiated configuration.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use software_modem::audio::{AudioReceiver, ReceiverConfig};
+    /// use software_modem::audio::device::cpal::{self, traits::HostTrait};
+    /// use software_modem::coded::CodedOFDMDemodulator;
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    ///
+    /// let device = cpal::default_host().default_input_device().unwrap();
+    /// let demodulator = CodedOFDMDemodulator::new(OFDMConfig::default(), CodingConfig::default());
+    /// let (receiver, input) = AudioReceiver::open(&device, demodulator, &ReceiverConfig::default()).unwrap();
+    /// while let Some(payload) = receiver.recv_timeout(Duration::from_secs(60)) {
+    ///     println!("{}, {:?}", String::from_utf8_lossy(&payload), receiver.metrics());
+    /// }
+    /// drop(input);
+    /// ```
+    pub fn open(
+        device: &cpal::Device,
+        demodulator: CodedOFDMDemodulator,
+        config: &ReceiverConfig,
+    ) -> Result<(AudioReceiver, InputDevice), AudioError> {
+        let (mut receivers, input) =
+            AudioReceiver::open_streams(device, vec![demodulator], config)?;
+        Ok((receivers.remove(0), input))
+    }
+
+    /// Creates a receiver with its worker thread for every stream of the channel map,
+    /// and feeds them the samples captured by an input stream of the device.
+    ///
+    /// The device is negotiated like in [AudioTransmitter::open].
+    ///
+    /// # Panics
+    /// If the squelch level is not positive and finite, or the hang or the buffer length are 0.
+    ///
+    /// # Errors
+    /// - The errors of [open](AudioReceiver::open).
+    /// - [AudioError::StreamCount] if the number of demodulators differs from the streams of the channel map
+    ///   on the negotiated channels.
+    pub fn open_streams(
+        device: &cpal::Device,
+        demodulators: Vec<CodedOFDMDemodulator>,
+        config: &ReceiverConfig,
+    ) -> Result<(Vec<AudioReceiver>, InputDevice), AudioError> {
+        let ranges = device.supported_input_configs().map_err(device_error)?;
+        let default_rate = device
+            .default_input_config()
+            .ok()
+            .map(|config| config.sample_rate());
+        let supported = negotiate(
+            ranges,
+            default_rate,
+            config.modem_rate,
+            config.device.channels,
+        )?;
+        let device_config = DeviceConfig {
+            sample_rate: supported.sample_rate(),
+            channels: supported.channels(),
+        };
+        let (receivers, input) = AudioReceiver::new_streams(
+            demodulators,
+            &ReceiverConfig {
+                device: device_config,
+                ..*config
+            },
+        )?;
+
+        let error = StreamError::default();
+        let stream_config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => capture::<f32>(device, stream_config, input, &error),
+            SampleFormat::I16 => capture::<i16>(device, stream_config, input, &error),
+            _ => capture::<u16>(device, stream_config, input, &error),
+        }?;
+        stream.play().map_err(device_error)?;
+        Ok((
+            receivers,
+            InputDevice {
+                _stream: stream,
+                config: device_config,
+                sample_format: supported.sample_format(),
+                error,
+            },
+        ))
+    }
+}
+
+/// Builds an output stream of the device whose callback fills its buffers from the output.
+fn play<S: DeviceSample + SizedSample>(
+    device: &cpal::Device,
+    config: StreamConfig,
+    mut output: AudioOutput,
+    finished: &Arc<AtomicBool>,
+    error: &StreamError,
+) -> Result<cpal::Stream, AudioError> {
+    let finished = finished.clone();
+    device
+        .build_output_stream::<S, _, _>(
+            config,
+            move |data, _| {
+                output.fill(data);
+                finished.store(output.is_finished(), Ordering::Release);
+            },
+            error_callback(error),
+            None,
+        )
+        .map_err(device_error)
+}
+
+/// Builds an input stream of the device whose callback pushes its buffers to the input.
+fn capture<S: DeviceSample + SizedSample>(
+    device: &cpal::Device,
+    config: StreamConfig,
+    mut input: AudioInput,
+    error: &StreamError,
+) -> Result<cpal::Stream, AudioError> {
+    device
+        .build_input_stream::<S, _, _>(
+            config,
+            move |data, _| input.push(data),
+            error_callback(error),
+            None,
+        )
+        .map_err(device_error)
+}
+
+/// Returns an error callback for a stream, which keeps the last error.
+fn error_callback(error: &StreamError) -> impl FnMut(cpal::Error) + Send + 'static {
+    let error = error.clone();
+    move |cpal_error| {
+        let message = cpal_error.to_string();
+        trace_event!(WARN, "device error", error = message.as_str());
+        *error.lock().unwrap() = Some(AudioError::Device(message));
+    }
+}
+
+fn device_error(error: cpal::Error) -> AudioError {
+    AudioError::Device(error.to_string())
+}
+
+/// Returns the configuration of the ranges of a device to carry the modem.
+///
+/// The ranges with the requested channels come first, then those of the preferred sample formats.
+/// Of the first range with a rate the modem can be resampled to, the modem rate is taken,
+/// or else the default rate of the device, the first of [DEVICE_RATES] in the range, or its highest or lowest rate.
+fn negotiate(
+    ranges: impl Iterator<Item = SupportedStreamConfigRange>,
+    default_rate: Option<u32>,
+    modem_rate: u32,
+    channels: u16,
+) -> Result<SupportedStreamConfig, AudioError> {
+    let mut ranges: Vec<_> = ranges
+        .filter_map(|range| {
+            let format = SAMPLE_FORMATS
+                .iter()
+                .position(|&format| format == range.sample_format())?;
+            Some((range.channels() != channels, format, range))
+        })
+        .collect();
+    if ranges.is_empty() {
+        return Err(AudioError::Device(
+            "The audio device supports no samples of f32, i16 or u16".to_string(),
+        ));
+    }
+    ranges.sort_by_key(|&(other_channels, format, _)| (other_channels, format));
+
+    ranges
+        .iter()
+        .find_map(|&(_, _, range)| {
+            [modem_rate]
+                .into_iter()
+                .chain(default_rate)
+                .chain(DEVICE_RATES)
+                .chain([range.max_sample_rate(), range.min_sample_rate()])
+                .filter(|&rate| check_rates(modem_rate, rate).is_ok())
+                .find_map(|rate| range.try_with_sample_rate(rate))
+        })
+        .ok_or(AudioError::SampleRateMismatch {
+            modem_rate,
+            device_rate: default_rate.unwrap_or(ranges[0].2.max_sample_rate()),
+        })
+}
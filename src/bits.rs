@@ -0,0 +1,149 @@
+//! This module provides helpers to convert between bytes and bits.
+//!
+//! Bits are stored one per byte, with the value `0` or `1`.
+//! The most significant bit of every byte comes first, matching the order used by the [QAM modem](crate::qam).
+
+use alloc::{vec, vec::Vec};
+
+use crate::error::ModemError;
+
+/// Unpacks bytes into bits, most significant bit first.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::bytes_to_bits;
+///
+/// assert_eq!(bytes_to_bits(&[0b1010_0001]), vec![1, 0, 1, 0, 0, 0, 0, 1]);
+/// ```
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<u8> {
+    let mut bits = vec![0; 8 * bytes.len()];
+    for (bits, &byte) in bits.chunks_exact_mut(8).zip(bytes) {
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = (byte >> (7 - i)) & 1;
+        }
+    }
+    bits
+}
+
+/// Packs bits into bytes, most significant bit first.
+///
+/// If the number of bits is not a multiple of 8, the last byte is padded with zeros.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::bits_to_bytes;
+///
+/// assert_eq!(bits_to_bytes(&[1, 0, 1, 0, 0, 0, 0, 1, 1]), vec![0b1010_0001, 0b1000_0000]);
+/// ```
+pub fn bits_to_bytes(bits: &[u8]) -> Vec<u8> {
+    let pack = |bits: &[u8]| bits.iter().fold(0, |byte, &bit| (byte << 1) | (bit & 1));
+    // whole bytes of known length unroll
+    let chunks = bits.chunks_exact(8);
+    let last = chunks.remainder();
+    let mut bytes = Vec::with_capacity(bits.len().div_ceil(8));
+    bytes.extend(chunks.map(pack));
+    if !last.is_empty() {
+        bytes.push(pack(last) << (8 - last.len()));
+    }
+    bytes
+}
+
+/// Maps hard bits to unit log-likelihood ratios, `+1.0` for a `0` bit and `-1.0` for a `1` bit.
+///
+/// This lets hard bits be fed to decoders working on LLRs.
+pub fn bits_to_llrs(bits: &[u8]) -> Vec<f32> {
+    bits.iter()
+        .map(|&bit| if bit & 1 == 0 { 1.0 } else { -1.0 })
+        .collect()
+}
+
+/// Makes hard decisions on log-likelihood ratios, `1` for a negative LLR and `0` otherwise.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::llrs_to_bits;
+///
+/// assert_eq!(llrs_to_bits(&[2.5, -0.1, 0.0, -3.0]), vec![0, 1, 0, 1]);
+/// ```
+pub fn llrs_to_bits(llrs: &[f32]) -> Vec<u8> {
+    llrs.iter().map(|&llr| u8::from(llr < 0.0)).collect()
+}
+
+/// Reads the fields of a serialized configuration, like a [CodingConfig](crate::frame::CodingConfig) or an [OFDMConfig](crate::ofdm::OFDMConfig).
+pub(crate) struct ConfigReader<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> ConfigReader<'a> {
+    pub(crate) fn take<const N: usize>(&mut self) -> Result<[u8; N], ModemError> {
+        let (field, rest) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or(ModemError::InvalidConfig)?;
+        self.bytes = rest;
+        Ok(*field)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn slice(&mut self, length: usize) -> Result<&'a [u8], ModemError> {
+        let (field, rest) = self
+            .bytes
+            .split_at_checked(length)
+            .ok_or(ModemError::InvalidConfig)?;
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, ModemError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, ModemError> {
+        Ok(u16::from_be_bytes(self.take()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, ModemError> {
+        Ok(u32::from_be_bytes(self.take()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, ModemError> {
+        Ok(u64::from_be_bytes(self.take()?))
+    }
+
+    /// Reads an `f32`, which must not be NaN.
+    pub(crate) fn f32(&mut self) -> Result<f32, ModemError> {
+        Some(f32::from_be_bytes(self.take()?))
+            .filter(|value| !value.is_nan())
+            .ok_or(ModemError::InvalidConfig)
+    }
+
+    pub(crate) fn flag(&mut self) -> Result<bool, ModemError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ModemError::InvalidConfig),
+        }
+    }
+
+    /// Reads a `u16` count followed by as many `u32` subcarrier indices, which must be below `num_subcarriers`.
+    pub(crate) fn indices(&mut self, num_subcarriers: u32) -> Result<Vec<u32>, ModemError> {
+        let count = self.u16()?;
+        (0..count)
+            .map(|_| {
+                Some(self.u32()?)
+                    .filter(|&index| index < num_subcarriers)
+                    .ok_or(ModemError::InvalidConfig)
+            })
+            .collect()
+    }
+
+    /// Reads two non-zero `u16` dimensions.
+    pub(crate) fn dimensions(&mut self) -> Result<(usize, usize), ModemError> {
+        let first = self.u16()? as usize;
+        let second = self.u16()? as usize;
+        if first == 0 || second == 0 {
+            return Err(ModemError::InvalidConfig);
+        }
+        Ok((first, second))
+    }
+}
@@ -0,0 +1,8 @@
+//! This module carries byte streams over the modem, behind the `bridge` feature.
+//!
+//! The [pty] bridge, on Linux and macOS, opens a pseudo-terminal that programs use like the serial port
+//! at one end of a cable: the bytes written to it go out as frames through a [SampleSink](crate::io::SampleSink),
+//! and the payloads of the frames decoded from a [SampleSource](crate::io::SampleSource) come out of it.
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub mod pty;
@@ -0,0 +1,510 @@
+//! This module provides a virtual serial port over the modem, a pseudo-terminal whose bytes are sent and received in frames.
+//!
+//! A [PtyBridge] opens a pseudo-terminal pair in raw mode and runs two threads. The reader takes the bytes
+//! written to the terminal into a queue of [queue_length](BridgeConfig::queue_length) bytes. While the queue is full
+//! it stops reading, and the writers of the terminal block, like with the flow control of a serial port.
+//! The modem thread sends the queued bytes in frames of up to [max_payload](BridgeConfig::max_payload) bytes,
+//! and writes the payloads of the frames it decodes back into the terminal.
+//!
+//! The link is half-duplex, both ends share one medium like a radio channel or the air between a speaker and a microphone.
+//! A bridge does not start a frame while it receives one, nor within a [turnaround](BridgeConfig::turnaround) after it.
+//! It ignores its input while it transmits and for a turnaround after, so that it does not decode the echo of its own frames.
+//! Frames lost to noise, or to both ends starting at once, are not repeated, their bytes are lost like on a noisy cable.
+//!
+//! The modem thread writes one block of samples to the sink and then reads one block from the source,
+//! so the sink and the source pace it like the callbacks of a duplex audio stream. Silence is written while there is nothing to send.
+//!
+//! # Example
+//! Bytes written to the terminal go out as frames, into a file of samples here.
+//! ```
+//! use std::convert::Infallible;
+//! use std::io::Write;
+//! use software_modem::bridge::pty::{BridgeConfig, PtyBridge};
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::io::SampleSource;
+//! use software_modem::io::gr::{read_f32, write_f32};
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::stream::StreamDemodulator;
+//!
+//! // a receiver that only hears silence
+//! struct Silence;
+//!
+//! impl SampleSource for Silence {
+//!     type Error = Infallible;
+//!
+//!     fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Infallible> {
+//!         buffer.fill(0.0);
+//!         Ok(buffer.len())
+//!     }
+//! }
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! };
+//! let path = std::env::temp_dir().join("software_modem_doc_pty.f32");
+//! let bridge = PtyBridge::open(
+//!     CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()),
+//!     CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default()),
+//!     write_f32(&path).unwrap(),
+//!     Silence,
+//!     &BridgeConfig::default(),
+//! )
+//! .unwrap();
+//!
+//! // any program opens the terminal like a serial port
+//! let mut terminal = std::fs::OpenOptions::new().write(true).open(bridge.get_path()).unwrap();
+//! terminal.write_all(b"over the serial line").unwrap();
+//! // closing sends what has been written before
+//! bridge.close().unwrap();
+//!
+//! let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+//! let mut stream = StreamDemodulator::new(demodulator, 0.01, 2400);
+//! assert_eq!(stream.run(&mut read_f32(&path).unwrap(), 480).unwrap(), [b"over the serial line"]);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    ffi::{CStr, OsStr, c_int},
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    os::{
+        fd::AsRawFd,
+        unix::{ffi::OsStrExt, fs::OpenOptionsExt},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use smart_default::SmartDefault;
+
+use crate::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    io::{SampleSink, SampleSource},
+    stream::{StreamDemodulator, StreamModulator, SyncState},
+};
+
+/// Milliseconds the reader waits for bytes before it checks whether the bridge is stopping.
+const POLL_INTERVAL: c_int = 50;
+
+/// Errors opening or running a [PtyBridge].
+#[derive(Debug)]
+pub enum BridgeError {
+    /// The pseudo-terminal could not be opened, read or written.
+    Pty(std::io::Error),
+    /// The samples could not be written to the sink.
+    Sink(Box<dyn Error + Send + Sync>),
+    /// The samples could not be read from the source.
+    Source(Box<dyn Error + Send + Sync>),
+}
+
+impl Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeError::Pty(error) => write!(f, "Pseudo-terminal error: {}", error),
+            BridgeError::Sink(error) => write!(f, "Sample sink error: {}", error),
+            BridgeError::Source(error) => write!(f, "Sample source error: {}", error),
+        }
+    }
+}
+
+impl Error for BridgeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BridgeError::Pty(error) => Some(error),
+            BridgeError::Sink(error) | BridgeError::Source(error) => Some(error.as_ref()),
+        }
+    }
+}
+
+/// Configuration of a [PtyBridge].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct BridgeConfig {
+    /// Most bytes sent in one frame, the queued bytes are sent in frames of up to this many.
+    #[default(1024)]
+    pub max_payload: usize,
+    /// Bytes read from the terminal that wait to be sent. While the queue is full, the writers of the terminal block.
+    #[default(16384)]
+    pub queue_length: usize,
+    /// Samples written to the sink and read from the source at a time.
+    #[default(480)]
+    pub block_length: usize,
+    /// Samples of silence after every frame.
+    #[default(4800)]
+    pub frame_gap: usize,
+    /// Magnitude at full scale above which the squelch opens and a frame is assumed to start.
+    #[default(0.01)]
+    pub squelch_level: f32,
+    /// Samples below the squelch level that end a frame, shorter than the frame gap of the other end.
+    #[default(2400)]
+    pub hang: usize,
+    /// Samples to wait after receiving a frame before transmitting, and to ignore the input after transmitting one.
+    ///
+    /// Together with the hang it must be longer than the frame gap of the other end,
+    /// so that a bridge does not start to transmit between two frames of the other end.
+    #[default(4800)]
+    pub turnaround: usize,
+}
+
+/// State shared between the bridge and its threads.
+struct Shared {
+    queue: Mutex<VecDeque<u8>>,
+    /// Signalled when bytes have been taken from the queue, or the bridge is stopping.
+    space: Condvar,
+    queue_length: usize,
+    /// The bridge is closing, the reader stops once the terminal has nothing left to read.
+    closing: AtomicBool,
+    /// The reader has stopped, no bytes are added to the queue anymore.
+    read_finished: AtomicBool,
+    /// The bridge has been dropped or the modem thread has stopped, both threads stop right away.
+    stopped: AtomicBool,
+}
+
+impl Shared {
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+        self.space.notify_all();
+    }
+}
+
+/// A pseudo-terminal whose bytes are sent in frames through a [SampleSink],
+/// and which receives the payloads of the frames decoded from a [SampleSource].
+///
+/// See the [module](self) for how the bridge works and an example.
+pub struct PtyBridge {
+    path: PathBuf,
+    shared: Arc<Shared>,
+    reader: Option<JoinHandle<std::io::Result<()>>>,
+    modem: Option<JoinHandle<Result<(), BridgeError>>>,
+    /// The terminal side is kept open, so that the terminal does not hang up while no program has it open.
+    _terminal: File,
+}
+
+impl PtyBridge {
+    /// Opens a pseudo-terminal, and starts the threads that bridge it to the sink and the source.
+    ///
+    /// The modem thread stops when the source ends or an error occurs, [close](PtyBridge::close) returns the error.
+    ///
+    /// # Panics
+    /// If the maximum payload is 0 or longer than 65535 bytes, the queue or the block length are 0,
+    /// the squelch level is not positive and finite, or the hang is 0.
+    ///
+    /// # Errors
+    /// [BridgeError::Pty] if the pseudo-terminal can not be opened.
+    pub fn open<K, S>(
+        modulator: CodedOFDMModulator,
+        demodulator: CodedOFDMDemodulator,
+        sink: K,
+        source: S,
+        config: &BridgeConfig,
+    ) -> Result<PtyBridge, BridgeError>
+    where
+        K: SampleSink + Send + 'static,
+        K::Error: Send + Sync + 'static,
+        S: SampleSource + Send + 'static,
+        S::Error: Send + Sync + 'static,
+    {
+        if !(1..=65535).contains(&config.max_payload) {
+            panic!(
+                "Maximum payload must be between 1 and 65535 bytes, but got {}",
+                config.max_payload
+            );
+        }
+        if config.queue_length == 0 {
+            panic!("Queue length must be at least 1, but got 0");
+        }
+        if config.block_length == 0 {
+            panic!("Block length must be at least 1, but got 0");
+        }
+        let modem = Modem {
+            modulator: StreamModulator::new(modulator, config.frame_gap),
+            demodulator: StreamDemodulator::new(demodulator, config.squelch_level, config.hang),
+            max_payload: config.max_payload,
+            block_length: config.block_length,
+            frame_gap: config.frame_gap,
+            turnaround: config.turnaround,
+        };
+
+        let (master, terminal, path) = open_pty().map_err(BridgeError::Pty)?;
+        let writer = master.try_clone().map_err(BridgeError::Pty)?;
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::with_capacity(config.queue_length)),
+            space: Condvar::new(),
+            queue_length: config.queue_length,
+            closing: AtomicBool::new(false),
+            read_finished: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+        });
+
+        let reader_shared = shared.clone();
+        let reader = std::thread::spawn(move || {
+            let result = read_terminal(master, &reader_shared);
+            reader_shared.read_finished.store(true, Ordering::Release);
+            result
+        });
+        let modem_shared = shared.clone();
+        let modem = std::thread::spawn(move || {
+            let result = modem.run(sink, source, writer, &modem_shared);
+            modem_shared.stop();
+            result
+        });
+
+        Ok(PtyBridge {
+            path,
+            shared,
+            reader: Some(reader),
+            modem: Some(modem),
+            _terminal: terminal,
+        })
+    }
+
+    /// Returns the path of the terminal, like `/dev/pts/3`, for the programs that use the virtual serial port.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether the modem thread has stopped, because the source ended or an error occurred.
+    pub fn is_finished(&self) -> bool {
+        self.modem.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Sends everything written to the terminal before, and stops the bridge.
+    ///
+    /// A frame being received is decoded and written to the terminal first.
+    ///
+    /// # Errors
+    /// The error that stopped the modem thread, or else the reader of the terminal.
+    pub fn close(mut self) -> Result<(), BridgeError> {
+        self.shared.closing.store(true, Ordering::Release);
+        self.shared.space.notify_all();
+        let modem = join(self.modem.take().unwrap());
+        let read = join(self.reader.take().unwrap());
+        modem?;
+        read.map_err(BridgeError::Pty)
+    }
+}
+
+impl Drop for PtyBridge {
+    fn drop(&mut self) {
+        self.shared.stop();
+        // a panic of a thread has already been reported on it
+        if let Some(modem) = self.modem.take() {
+            let _ = modem.join();
+        }
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Joins a thread of the bridge, passing a panic on.
+fn join<T>(thread: JoinHandle<T>) -> T {
+    thread
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn check(result: c_int) -> std::io::Result<()> {
+    if result < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Opens a pseudo-terminal pair, and returns the master, the terminal side in raw mode, and the path of the terminal.
+fn open_pty() -> std::io::Result<(File, File, PathBuf)> {
+    let master = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open("/dev/ptmx")?;
+    let fd = master.as_raw_fd();
+    // long enough for any name of a terminal, like the 128 bytes of TIOCPTYGNAME on macOS
+    let mut name = [0 as libc::c_char; 128];
+    // SAFETY: the descriptor is an open master, and the buffer is as long as the length passed
+    unsafe {
+        check(libc::grantpt(fd))?;
+        check(libc::unlockpt(fd))?;
+        #[cfg(target_os = "linux")]
+        {
+            let result = libc::ptsname_r(fd, name.as_mut_ptr(), name.len());
+            if result != 0 {
+                return Err(std::io::Error::from_raw_os_error(result));
+            }
+        }
+        #[cfg(target_os = "macos")]
+        check(libc::ioctl(fd, libc::TIOCPTYGNAME as _, name.as_mut_ptr()))?;
+    }
+    // SAFETY: the name was written with its terminating nul within the buffer
+    let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+    let path = PathBuf::from(OsStr::from_bytes(name.to_bytes()));
+
+    let terminal = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(&path)?;
+    // no echo, no line buffering and no translation of line endings, the bytes pass as they are
+    // SAFETY: the descriptor is open, and the termios is filled by tcgetattr before it is changed
+    unsafe {
+        let mut termios = core::mem::zeroed::<libc::termios>();
+        check(libc::tcgetattr(terminal.as_raw_fd(), &mut termios))?;
+        libc::cfmakeraw(&mut termios);
+        check(libc::tcsetattr(
+            terminal.as_raw_fd(),
+            libc::TCSANOW,
+            &termios,
+        ))?;
+    }
+    Ok((master, terminal, path))
+}
+
+/// Waits up to `timeout` milliseconds for the file to be readable.
+fn wait_readable(file: &File, timeout: c_int) -> std::io::Result<bool> {
+    let mut fds = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: the descriptor is open and the pointer is to one pollfd
+    match unsafe { libc::poll(&mut fds, 1, timeout) } {
+        ..0 => {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                Ok(false)
+            } else {
+                Err(error)
+            }
+        }
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+/// The reader thread of a [PtyBridge].
+fn read_terminal(mut master: File, shared: &Shared) -> std::io::Result<()> {
+    let mut buffer = vec![0; shared.queue_length];
+    loop {
+        let free = {
+            let mut queue = shared.queue.lock().unwrap();
+            while queue.len() == shared.queue_length && !shared.stopped.load(Ordering::Acquire) {
+                queue = shared.space.wait(queue).unwrap();
+            }
+            shared.queue_length - queue.len()
+        };
+        if shared.stopped.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        // once closing, only the bytes written to the terminal before are left to read
+        let closing = shared.closing.load(Ordering::Acquire);
+        if !wait_readable(&master, if closing { 0 } else { POLL_INTERVAL })? {
+            if closing {
+                return Ok(());
+            }
+            continue;
+        }
+        let count = master.read(&mut buffer[..free])?;
+        shared.queue.lock().unwrap().extend(&buffer[..count]);
+    }
+}
+
+/// The modem thread of a [PtyBridge].
+struct Modem {
+    modulator: StreamModulator,
+    demodulator: StreamDemodulator,
+    max_payload: usize,
+    block_length: usize,
+    frame_gap: usize,
+    turnaround: usize,
+}
+
+impl Modem {
+    fn run<K, S>(
+        mut self,
+        mut sink: K,
+        mut source: S,
+        mut terminal: File,
+        shared: &Shared,
+    ) -> Result<(), BridgeError>
+    where
+        K: SampleSink,
+        K::Error: Send + Sync + 'static,
+        S: SampleSource,
+        S::Error: Send + Sync + 'static,
+    {
+        let mut transmit = VecDeque::new();
+        let mut block = vec![0.0; self.block_length];
+        let mut payload = Vec::with_capacity(self.max_payload);
+        // samples left before a frame may start after receiving, and before the input is heard again after transmitting
+        let mut holdoff = 0;
+        let mut deaf = 0;
+        loop {
+            if shared.stopped.load(Ordering::Acquire) {
+                return Ok(());
+            }
+
+            if transmit.is_empty()
+                && holdoff == 0
+                && self.demodulator.get_sync_state() == SyncState::Idle
+            {
+                let mut queue = shared.queue.lock().unwrap();
+                if queue.is_empty() && shared.read_finished.load(Ordering::Acquire) {
+                    return Ok(());
+                }
+                let count = queue.len().min(self.max_payload);
+                payload.clear();
+                payload.extend(queue.drain(..count));
+                drop(queue);
+                shared.space.notify_one();
+                if !payload.is_empty() {
+                    let Ok(()) = self.modulator.write_frame(&payload, &mut transmit);
+                }
+            }
+
+            // the gap after a frame is silence, only the frame itself echoes
+            let transmitting = transmit.len() > self.frame_gap;
+            let count = transmit.len().min(self.block_length);
+            block.fill(0.0);
+            for (sample, value) in block.iter_mut().zip(transmit.drain(..count)) {
+                *sample = value;
+            }
+            sink.write(&block)
+                .map_err(|error| BridgeError::Sink(Box::new(error)))?;
+
+            let count = source
+                .read(&mut block)
+                .map_err(|error| BridgeError::Source(Box::new(error)))?;
+            if count == 0 {
+                return Ok(());
+            }
+            if transmitting {
+                deaf = self.turnaround;
+            } else if deaf > 0 {
+                deaf = deaf.saturating_sub(count);
+            } else {
+                for payload in self.demodulator.push(&block[..count]) {
+                    terminal.write_all(&payload).map_err(BridgeError::Pty)?;
+                }
+                holdoff = if self.demodulator.get_sync_state() == SyncState::Receiving {
+                    self.turnaround
+                } else {
+                    holdoff.saturating_sub(count)
+                };
+            }
+        }
+    }
+}
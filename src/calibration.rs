@@ -0,0 +1,398 @@
+//! This module provides the level calibration of a link, run before a long transfer so that the transmitter
+//! neither clips the converter of the receiver nor starves it.
+//!
+//! The transmitter plays a [CalibrationSequence], multitone bursts of the modulator on every data subcarrier
+//! at once, each louder than the one before, with silence around them. The receiver records it and
+//! [analyzes](CalibrationSequence::analyze) the recording into a [CalibrationResult]: the level of every step,
+//! the step at which the receiver started to clip, the level the transmitter should send at, and the tilt
+//! of the channel. The application sends it back, and the transmitter applies it with the
+//! [output scale](crate::ofdm::modulator::OutputScale) and a [pre-emphasis](crate::dsp::Preemphasis)
+//! or a [compensating filter](crate::dsp::FirFilter::compensating).
+//!
+//! # Example
+//! ```
+//! use software_modem::calibration::{CalibrationConfig, CalibrationSequence};
+//! use software_modem::channel::{AwgnChannel, Channel, ChannelChain, QuantizeClip};
+//! use software_modem::ofdm::{OFDMConfig, modulator::OFDMModulator};
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     ..Default::default()
+//! };
+//! let sequence = CalibrationSequence::new(&OFDMModulator::new((&ofdm).into()), CalibrationConfig::default());
+//!
+//! // a speaker 12 dB louder than the transmitter into a 16-bit sound card, which clips from a level of -12 dBFS
+//! let mut recording = vec![0.0; 3000];
+//! recording.extend(sequence.samples().iter().map(|x| 4.0 * x));
+//! ChannelChain::new()
+//!     .with(AwgnChannel::with_reference_power(60.0, 0.01, 1))
+//!     .with(QuantizeClip::new(16, 1.0, None))
+//!     .apply(&mut recording);
+//!
+//! let result = sequence.analyze(&recording);
+//! assert_eq!(result.clipping_step, Some(6));
+//! assert!((result.clip_level_dbfs.unwrap() + 12.0).abs() < 1.0, "{result:?}");
+//! // 6 dB of headroom below it
+//! assert!((result.recommended_level_dbfs + 18.0).abs() < 1.0, "{result:?}");
+//! assert!(result.expected_snr_db > 40.0);
+//! ```
+
+use alloc::{vec, vec::Vec};
+
+use num_complex::Complex32;
+use smart_default::SmartDefault;
+
+use crate::{
+    coded::ChannelTilt,
+    metrics::{SpectrumWindow, power_spectrum},
+    ofdm::modulator::{OFDMModulator, OutputScale},
+    rng::SimulationRng,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// SNR from which the crest factor of a step is trusted as the one of the signal, in dB.
+const MIN_CREST_SNR_DB: f32 = 20.0;
+
+/// Drop of the crest factor of a step below the one of the signal from which the step counts as clipped, in dB.
+const CLIP_COMPRESSION_DB: f32 = 1.0;
+
+/// Seed of the phases of the subcarriers of the bursts, the same every time.
+const PHASE_SEED: u64 = 0xca1_1b8a7e;
+
+/// Parameters of a [CalibrationSequence].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct CalibrationConfig {
+    /// Number of bursts, from the quietest to the loudest.
+    #[default(8)]
+    pub steps: usize,
+    /// Peak level of the quietest burst in dB relative to full scale.
+    #[default(-42.0)]
+    pub first_level_dbfs: f32,
+    /// Level from one burst to the next in dB.
+    #[default(6.0)]
+    pub step_db: f32,
+    /// Symbols of every burst, the reference symbol of differential mode included.
+    #[default(8)]
+    pub symbols_per_step: usize,
+    /// Samples of silence before the first burst and after every burst, in which the noise is measured.
+    #[default(2400)]
+    pub gap: usize,
+    /// Headroom of the recommended level below the level at which the receiver clips, in dB.
+    #[default(6.0)]
+    pub headroom_db: f32,
+}
+
+/// The stepped multitone bursts a transmitter plays for the calibration of a link, see the [module](self) documentation.
+///
+/// Every burst carries the same symbols, whose data subcarriers have the same magnitude and random phases,
+/// scaled to the peak level of its step. The loudest step must not be above full scale, which the transmitter clips itself.
+pub struct CalibrationSequence {
+    config: CalibrationConfig,
+    /// The burst at a peak level of 1.
+    burst: Vec<f32>,
+    /// The length of the FFT and the data subcarriers, for the tilt.
+    fft_length: usize,
+    data_subcarriers: Vec<u32>,
+    /// Samples at the edges of every burst and gap that are left out of the measurements, for the delay and the ringing
+    /// of the channel.
+    margin: usize,
+}
+
+impl CalibrationSequence {
+    /// Creates the sequence of the bursts of the modulator.
+    ///
+    /// # Panics
+    /// If there are no steps, the step is not positive, the loudest step is above full scale,
+    /// there are fewer than 2 symbols per step, or the gap is shorter than 2 symbols.
+    pub fn new(modulator: &OFDMModulator, config: CalibrationConfig) -> Self {
+        if config.steps == 0 {
+            panic!("Steps must be at least 1, but got 0");
+        }
+        if !(config.step_db > 0.0 && config.step_db.is_finite()) {
+            panic!(
+                "Step must be positive and finite, but got {} dB",
+                config.step_db
+            );
+        }
+        let loudest = config.first_level_dbfs + (config.steps - 1) as f32 * config.step_db;
+        if loudest.is_nan() || loudest > 0.0 {
+            panic!(
+                "Loudest step must not be above full scale, but got {} dBFS",
+                loudest
+            );
+        }
+        if config.symbols_per_step < 2 {
+            panic!(
+                "Symbols per step must be at least 2, but got {}",
+                config.symbols_per_step
+            );
+        }
+        let symbol_length = modulator.get_symbol_length();
+        if config.gap < 2 * symbol_length {
+            panic!(
+                "Gap must be at least 2 symbols of {} samples, but got {}",
+                symbol_length, config.gap
+            );
+        }
+
+        let reference_symbols = usize::from(modulator.is_differential_time());
+        let mut phases = SimulationRng::new(PHASE_SEED);
+        let mut burst = modulator.modulate_frame_points(
+            config.symbols_per_step.saturating_sub(reference_symbols),
+            |_, points| {
+                for point in points {
+                    *point = Complex32::from_polar(
+                        1.0,
+                        core::f32::consts::TAU * phases.uniform() as f32,
+                    );
+                }
+            },
+        );
+        let peak = burst.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+        for sample in &mut burst {
+            *sample /= peak;
+        }
+
+        CalibrationSequence {
+            config,
+            burst,
+            fft_length: modulator.get_fft().fft_length(),
+            data_subcarriers: modulator.data_subcarrier_indices().to_vec(),
+            margin: symbol_length / 2,
+        }
+    }
+
+    /// Returns the parameters of the sequence.
+    pub fn get_config(&self) -> CalibrationConfig {
+        self.config
+    }
+
+    /// Returns the peak level of every step in dB relative to full scale.
+    pub fn get_step_levels_dbfs(&self) -> Vec<f32> {
+        (0..self.config.steps)
+            .map(|step| self.config.first_level_dbfs + step as f32 * self.config.step_db)
+            .collect()
+    }
+
+    /// Returns the number of samples of the sequence, a gap before every burst and after the last one.
+    pub fn get_length(&self) -> usize {
+        self.config.gap + self.config.steps * self.get_period()
+    }
+
+    /// Returns the samples of the sequence to play.
+    pub fn samples(&self) -> Vec<f32> {
+        let mut samples = vec![0.0; self.config.gap];
+        for level_db in self.get_step_levels_dbfs() {
+            let gain = 10f32.powf(level_db / 20.0);
+            samples.extend(self.burst.iter().map(|x| gain * x));
+            samples.resize(samples.len() + self.config.gap, 0.0);
+        }
+        samples
+    }
+
+    /// Analyzes a recording of the sequence, which may start with anything before it.
+    ///
+    /// The sequence is found at the offset where the bursts hold the most power. A step counts as clipped
+    /// when its crest factor falls by more than 1 dB below the one of the first step 20 dB above the noise,
+    /// whatever clips it, the converter of the receiver or an amplifier before it. The clip level is extrapolated
+    /// from the peak of the loudest step that did not clip to the peak of the first one that did,
+    /// and the recommended level lies the headroom below it, or below full scale if no step clipped.
+    /// The tilt is measured on the loudest step that did not clip, from the spectrum at the data subcarriers.
+    ///
+    /// # Panics
+    /// If the recording is shorter than the sequence.
+    pub fn analyze(&self, recording: &[f32]) -> CalibrationResult {
+        if recording.len() < self.get_length() {
+            panic!(
+                "Recording must be at least as long as the sequence of {} samples, but got {}",
+                self.get_length(),
+                recording.len()
+            );
+        }
+        let start = self.find_start(recording);
+        let period = self.get_period();
+        let margin = self.margin;
+
+        // the noise in the gaps, within their margins
+        let mut noise = (0.0f64, 0usize);
+        for gap in 0..=self.config.steps {
+            let begin = start
+                + gap * period
+                + if gap == 0 {
+                    0
+                } else {
+                    self.burst.len() + margin
+                };
+            let end = start + gap * period + self.config.gap - margin;
+            for x in &recording[begin.min(end)..end] {
+                noise.0 += (*x as f64).powi(2);
+                noise.1 += 1;
+            }
+        }
+        let noise_power = ((noise.0 / noise.1.max(1) as f64) as f32).max(f32::MIN_POSITIVE);
+
+        let mut steps: Vec<CalibrationStep> = self
+            .get_step_levels_dbfs()
+            .into_iter()
+            .enumerate()
+            .map(|(step, sent_dbfs)| {
+                let samples = self.get_step(recording, start, step);
+                let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+                let peak = samples.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+                CalibrationStep {
+                    sent_dbfs,
+                    rms_dbfs: 10.0 * power.max(f32::MIN_POSITIVE).log10(),
+                    peak_dbfs: 20.0 * peak.max(f32::MIN_POSITIVE).log10(),
+                    snr_db: 10.0
+                        * ((power - noise_power).max(f32::MIN_POSITIVE) / noise_power).log10(),
+                    clipped: false,
+                }
+            })
+            .collect();
+
+        let crest = steps
+            .iter()
+            .find(|step| step.snr_db >= MIN_CREST_SNR_DB)
+            .map(|step| step.peak_dbfs - step.rms_dbfs);
+        if let Some(crest) = crest {
+            for step in &mut steps {
+                step.clipped = step.snr_db >= MIN_CREST_SNR_DB
+                    && step.peak_dbfs - step.rms_dbfs < crest - CLIP_COMPRESSION_DB;
+            }
+        }
+        let clipping_step = steps.iter().position(|step| step.clipped);
+        // the loudest step that did not clip
+        let clean = clipping_step.unwrap_or(steps.len()).checked_sub(1);
+
+        let clip_level_dbfs = match (clipping_step, clean) {
+            (Some(clipped), Some(clean)) => {
+                let clean = &steps[clean];
+                Some(clean.sent_dbfs + steps[clipped].peak_dbfs - clean.peak_dbfs)
+            }
+            // the quietest step clipped already
+            (Some(_), None) => Some(self.config.first_level_dbfs - self.config.step_db),
+            (None, _) => None,
+        };
+        let recommended_level_dbfs =
+            clip_level_dbfs.unwrap_or(0.0).min(0.0) - self.config.headroom_db;
+        let (expected_snr_db, tilt) = match clean {
+            Some(clean) => (
+                steps[clean].snr_db + recommended_level_dbfs - steps[clean].sent_dbfs,
+                self.measure_tilt(recording, start, clean),
+            ),
+            None => (f32::NEG_INFINITY, ChannelTilt::from_gains(Vec::new())),
+        };
+
+        CalibrationResult {
+            steps,
+            noise_dbfs: 10.0 * noise_power.log10(),
+            clipping_step,
+            clip_level_dbfs,
+            recommended_level_dbfs,
+            expected_snr_db,
+            tilt,
+        }
+    }
+
+    /// Returns the samples of a burst and the gap after it.
+    fn get_period(&self) -> usize {
+        self.burst.len() + self.config.gap
+    }
+
+    /// Returns the samples of a step in the recording of the sequence from `start`, within its margins.
+    fn get_step<'a>(&self, recording: &'a [f32], start: usize, step: usize) -> &'a [f32] {
+        let begin = start + self.config.gap + step * self.get_period();
+        &recording[begin + self.margin..begin + self.burst.len() - self.margin]
+    }
+
+    /// Returns the offset of the sequence in the recording, where the bursts hold the most energy.
+    fn find_start(&self, recording: &[f32]) -> usize {
+        let mut energy = Vec::with_capacity(recording.len() + 1);
+        energy.push(0.0f64);
+        for x in recording {
+            energy.push(energy.last().unwrap() + (*x as f64).powi(2));
+        }
+        let burst_energy = |offset: usize| {
+            (0..self.config.steps)
+                .map(|step| {
+                    let begin = offset + self.config.gap + step * self.get_period();
+                    energy[begin + self.burst.len()] - energy[begin]
+                })
+                .sum::<f64>()
+        };
+        (0..=recording.len() - self.get_length())
+            .max_by(|&a, &b| burst_energy(a).total_cmp(&burst_energy(b)))
+            .unwrap_or(0)
+    }
+
+    /// Measures the tilt of a step against the burst it was sent as.
+    fn measure_tilt(&self, recording: &[f32], start: usize, step: usize) -> ChannelTilt {
+        let received = self.get_step(recording, start, step);
+        let sent = &self.burst[self.margin..self.burst.len() - self.margin];
+        let fft_length = self.fft_length.min(received.len() & !1);
+        let received = power_spectrum(received, fft_length, SpectrumWindow::Hann);
+        let sent = power_spectrum(sent, fft_length, SpectrumWindow::Hann);
+        ChannelTilt::from_gains(
+            self.data_subcarriers
+                .iter()
+                .map(|&bin| {
+                    let bin = bin as usize * fft_length / self.fft_length;
+                    let gain = received[bin] / sent[bin].max(f32::MIN_POSITIVE);
+                    (
+                        bin as f32 / fft_length as f32,
+                        10.0 * gain.max(f32::MIN_POSITIVE).log10(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A step of a [CalibrationSequence] as the receiver recorded it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CalibrationStep {
+    /// The peak level the step was sent at in dB relative to full scale.
+    pub sent_dbfs: f32,
+    /// The RMS level of the recording of the step in dB relative to full scale.
+    pub rms_dbfs: f32,
+    /// The peak level of the recording of the step in dB relative to full scale.
+    pub peak_dbfs: f32,
+    /// The power of the step over the one of the noise between the steps in dB.
+    pub snr_db: f32,
+    /// Whether the step clipped, see [CalibrationSequence::analyze].
+    pub clipped: bool,
+}
+
+/// The analysis of a recording of a [CalibrationSequence], for the transmitter to apply.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalibrationResult {
+    /// The steps from the quietest to the loudest.
+    pub steps: Vec<CalibrationStep>,
+    /// The RMS level of the noise between the steps in dB relative to full scale.
+    pub noise_dbfs: f32,
+    /// The index of the first step that clipped, `None` if none did.
+    pub clipping_step: Option<usize>,
+    /// The peak level sent at which the receiver starts to clip in dB relative to full scale,
+    /// `None` if no step clipped.
+    pub clip_level_dbfs: Option<f32>,
+    /// The peak level the transmitter should send at in dB relative to full scale,
+    /// the headroom below the clip level or below full scale.
+    pub recommended_level_dbfs: f32,
+    /// The SNR the receiver gets at the recommended level in dB, which starves it if it lies below the SNR
+    /// the profile of the link needs. Negative infinity if every step clipped.
+    pub expected_snr_db: f32,
+    /// The tilt of the channel over the data subcarriers, for the [pre-emphasis](crate::dsp::Preemphasis)
+    /// or the [compensating filter](crate::dsp::FirFilter::compensating) of the transmitter.
+    /// Without any response if every step clipped.
+    pub tilt: ChannelTilt,
+}
+
+impl CalibrationResult {
+    /// Returns the output scale of the modulator that sends every frame at the recommended peak level.
+    pub fn get_output_scale(&self) -> OutputScale {
+        OutputScale::PeakNormalize(10f32.powf(self.recommended_level_dbfs / 20.0))
+    }
+}
@@ -0,0 +1,1832 @@
+//! This module simulates the channel between a transmitter and a receiver, to test the modem against controlled impairments
+//! rather than a perfect loopback.
+//!
+//! A [Channel] impairs buffers of real samples in place, or of complex baseband samples for the [I/Q](crate::ofdm::complex) modems.
+//! The [AwgnChannel] adds white Gaussian noise at a signal-to-noise ratio, drawn from a seeded generator,
+//! so a failing run can be repeated exactly. The [MultipathChannel] adds delayed and scaled echoes of the signal,
+//! from taps or from the named profiles of [MultipathProfile], fading with a Doppler spread or still,
+//! which the equalizer and the interleavers have to undo. The [OffsetImpairment] offsets the carrier frequency,
+//! the sample clock and the timing of the receiver, for the synchronization. The [QuantizeClip] rounds the samples
+//! to the bit depth of a converter and clips them, for the headroom and resolution a constellation needs.
+//! The [IqImbalance] mismatches the gain and phase of the I and Q branches of an SDR front-end, whose image
+//! interferes across mirrored subcarriers.
+//! The [PhaseNoise] turns the phase of the signal by a random walk, like the jitter of a cheap oscillator,
+//! which rotates the points of a symbol together and smears them into each other.
+//! The [IrChannel] convolves the signal with the impulse response of a room, measured or from [synthetic_rir].
+//! The [BurstNoise] adds bursts of noise and clicks at random times, which the interleavers and the outer code have to spread and repair.
+//! A [ChannelChain] passes the samples through several channels.
+
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc, vec, vec::Vec};
+use core::ops::Range;
+
+use num_complex::Complex32;
+
+use crate::{
+    dsp::{FarrowInterpolator, FirFilter},
+    fft::{
+        ComplexFft, RealForwardFft, RealInverseFft, plan_complex_forward, plan_complex_inverse,
+        plan_real_forward, plan_real_inverse,
+    },
+    math::Euclid,
+    rng::SimulationRng,
+    samples::TpdfDither,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Impairs the samples passing from a transmitter to a receiver.
+///
+/// A channel may keep state between buffers, so a signal passed in several buffers is impaired like one long buffer.
+pub trait Channel {
+    /// Impairs real samples in place.
+    fn apply(&mut self, samples: &mut [f32]);
+
+    /// Impairs complex baseband samples in place.
+    fn apply_complex(&mut self, samples: &mut [Complex32]);
+}
+
+/// Adds white Gaussian noise at a signal-to-noise ratio.
+///
+/// The SNR is the ratio of the mean power of the samples to the power of the noise per sample,
+/// over the whole band of the sample rate. The signal power is measured over every buffer,
+/// unless a reference power is given, which keeps the noise level the same over buffers of silence and of signal.
+/// Complex samples get half of the noise power on each of their components.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{AwgnChannel, Channel};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+///
+/// let mut frame = modulator.encode_frame(b"Through the noise");
+/// AwgnChannel::new(12.0, 42).apply(&mut frame);
+/// assert_eq!(demodulator.decode_frame(&frame).unwrap(), b"Through the noise");
+///
+/// // the same seed adds the same noise
+/// let (mut first, mut second) = (vec![0.5; 100], vec![0.5; 100]);
+/// AwgnChannel::new(3.0, 7).apply(&mut first);
+/// AwgnChannel::new(3.0, 7).apply(&mut second);
+/// assert_eq!(first, second);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AwgnChannel {
+    snr_db: f32,
+    reference_power: Option<f32>,
+    noise: SimulationRng,
+}
+
+impl AwgnChannel {
+    /// Creates a channel adding noise `snr_db` below the power measured over every buffer, drawn from the seed.
+    ///
+    /// A buffer of silence gets no noise.
+    ///
+    /// # Panics
+    /// If the SNR is not finite.
+    pub fn new(snr_db: f32, seed: u64) -> Self {
+        if !snr_db.is_finite() {
+            panic!("SNR must be finite, but got {}", snr_db);
+        }
+
+        AwgnChannel {
+            snr_db,
+            reference_power: None,
+            noise: SimulationRng::new(seed),
+        }
+    }
+
+    /// Creates a channel adding noise `snr_db` below a fixed signal power, the mean square of a sample, drawn from the seed.
+    ///
+    /// # Panics
+    /// If the SNR is not finite, or the reference power is not positive and finite.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::{AwgnChannel, Channel};
+    ///
+    /// // silence gets noise 10 dB below a full scale sine, whose power is 1/2
+    /// let mut samples = vec![0.0; 100_000];
+    /// AwgnChannel::with_reference_power(10.0, 0.5, 1).apply(&mut samples);
+    /// let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    /// assert!((power / 0.05 - 1.0).abs() < 0.02);
+    /// ```
+    pub fn with_reference_power(snr_db: f32, reference_power: f32, seed: u64) -> Self {
+        if !(reference_power > 0.0 && reference_power.is_finite()) {
+            panic!(
+                "Reference power must be positive and finite, but got {}",
+                reference_power
+            );
+        }
+
+        AwgnChannel {
+            reference_power: Some(reference_power),
+            ..Self::new(snr_db, seed)
+        }
+    }
+
+    /// Returns the signal-to-noise ratio in dB.
+    pub fn get_snr_db(&self) -> f32 {
+        self.snr_db
+    }
+
+    /// Returns the reference power of the SNR, `None` if it is measured over every buffer.
+    pub fn get_reference_power(&self) -> Option<f32> {
+        self.reference_power
+    }
+
+    /// Returns the noise power per sample, for the measured power of a buffer.
+    fn noise_power(&self, measured_power: f64) -> f64 {
+        let signal_power = self.reference_power.map_or(measured_power, f64::from);
+        signal_power / 10f64.powf(f64::from(self.snr_db) / 10.0)
+    }
+}
+
+impl Channel for AwgnChannel {
+    fn apply(&mut self, samples: &mut [f32]) {
+        if samples.is_empty() {
+            return;
+        }
+        let power = samples
+            .iter()
+            .map(|&x| f64::from(x) * f64::from(x))
+            .sum::<f64>()
+            / samples.len() as f64;
+        let sigma = self.noise_power(power).sqrt();
+        for sample in samples {
+            *sample += (sigma * self.noise.gaussian()) as f32;
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        if samples.is_empty() {
+            return;
+        }
+        let power =
+            samples.iter().map(|x| f64::from(x.norm_sqr())).sum::<f64>() / samples.len() as f64;
+        let sigma = (self.noise_power(power) / 2.0).sqrt();
+        for sample in samples {
+            sample.re += (sigma * self.noise.gaussian()) as f32;
+            sample.im += (sigma * self.noise.gaussian()) as f32;
+        }
+    }
+}
+
+/// The tap delays in ns and powers in dB of the ITU-R M.1225 Pedestrian A channel.
+const PEDESTRIAN_TAPS: [(f32, f32); 4] =
+    [(0.0, 0.0), (110.0, -9.7), (190.0, -19.2), (410.0, -22.8)];
+
+/// The tap delays in ns and powers in dB of the ITU-R M.1225 Vehicular A channel.
+const VEHICULAR_TAPS: [(f32, f32); 6] = [
+    (0.0, 0.0),
+    (310.0, -1.0),
+    (710.0, -9.0),
+    (1090.0, -10.0),
+    (1730.0, -15.0),
+    (2510.0, -20.0),
+];
+
+/// The power in dB down to which the taps of an [exponential decay](MultipathProfile::ExponentialDecay) reach.
+const EXPONENTIAL_FLOOR_DB: f64 = -40.0;
+
+/// A named power-delay profile of a [MultipathChannel], see [MultipathChannel::from_profile].
+///
+/// The delays are in seconds, so the same profile spans more taps at a higher sample rate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MultipathProfile {
+    /// A direct path and one echo, `delay` seconds later and `relative_db` weaker.
+    TwoRay { delay: f32, relative_db: f32 },
+    /// A tap on every sample, whose power decays exponentially to a delay spread of `rms_delay` seconds,
+    /// down to -40 dB.
+    ExponentialDecay { rms_delay: f32 },
+    /// The Pedestrian A channel of ITU-R M.1225, taps at 0, 110, 190 and 410 ns of 0, -9.7, -19.2 and -22.8 dB,
+    /// a delay spread of 46 ns.
+    Pedestrian,
+    /// The Vehicular A channel of ITU-R M.1225, taps at 0, 310, 710, 1090, 1730 and 2510 ns
+    /// of 0, -1, -9, -10, -15 and -20 dB, a delay spread of 370 ns.
+    Vehicular,
+}
+
+impl MultipathProfile {
+    /// Returns the paths of the profile at a sample rate, pairs of a delay in seconds and a power in dB,
+    /// the strongest 0 dB.
+    ///
+    /// Only the [exponential decay](MultipathProfile::ExponentialDecay) depends on the sample rate,
+    /// which places its taps.
+    ///
+    /// # Panics
+    /// If the sample rate is not positive and finite, or a delay of the profile is not positive and finite,
+    /// or its level not finite.
+    pub fn get_paths(&self, sample_rate: f32) -> Vec<(f32, f32)> {
+        if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+            panic!(
+                "Sample rate must be positive and finite, but got {}",
+                sample_rate
+            );
+        }
+
+        match *self {
+            MultipathProfile::TwoRay { delay, relative_db } => {
+                if !(delay > 0.0 && delay.is_finite()) {
+                    panic!("Echo delay must be positive and finite, but got {}", delay);
+                }
+                if !relative_db.is_finite() {
+                    panic!("Echo level must be finite, but got {}", relative_db);
+                }
+                vec![(0.0, 0.0), (delay, relative_db)]
+            }
+            MultipathProfile::ExponentialDecay { rms_delay } => {
+                if !(rms_delay > 0.0 && rms_delay.is_finite()) {
+                    panic!(
+                        "RMS delay must be positive and finite, but got {}",
+                        rms_delay
+                    );
+                }
+                // the powers r^n of the taps have a delay spread of sqrt(r) / (1 - r) samples
+                let spread = f64::from(rms_delay) * f64::from(sample_rate);
+                let root = ((1.0 + 4.0 * spread * spread).sqrt() - 1.0) / (2.0 * spread);
+                let step_db = 20.0 * root.log10();
+                let num_taps = (EXPONENTIAL_FLOOR_DB / step_db).floor() as usize + 1;
+                (0..num_taps)
+                    .map(|n| (n as f32 / sample_rate, (n as f64 * step_db) as f32))
+                    .collect()
+            }
+            MultipathProfile::Pedestrian => PEDESTRIAN_TAPS
+                .iter()
+                .map(|&(delay, power)| (delay * 1e-9, power))
+                .collect(),
+            MultipathProfile::Vehicular => VEHICULAR_TAPS
+                .iter()
+                .map(|&(delay, power)| (delay * 1e-9, power))
+                .collect(),
+        }
+    }
+
+    /// Returns the RMS delay spread of the profile in seconds, the standard deviation of the delays
+    /// weighted by the powers of the paths, before they are rounded to samples.
+    ///
+    /// # Panics
+    /// As [get_paths](MultipathProfile::get_paths).
+    pub fn get_rms_delay_spread(&self, sample_rate: f32) -> f32 {
+        let paths: Vec<(f64, f64)> = self
+            .get_paths(sample_rate)
+            .iter()
+            .map(|&(delay, power_db)| (f64::from(delay), 10f64.powf(f64::from(power_db) / 10.0)))
+            .collect();
+        rms_delay_spread(&paths) as f32
+    }
+}
+
+/// Returns the RMS delay spread of pairs of a delay and a linear power.
+fn rms_delay_spread(paths: &[(f64, f64)]) -> f64 {
+    let power: f64 = paths.iter().map(|&(_, power)| power).sum();
+    let mean = paths
+        .iter()
+        .map(|&(delay, power)| delay * power)
+        .sum::<f64>()
+        / power;
+    let square = paths
+        .iter()
+        .map(|&(delay, power)| delay * delay * power)
+        .sum::<f64>()
+        / power;
+    (square - mean * mean).max(0.0).sqrt()
+}
+
+/// The sinusoids of the fading of every tap of a [MultipathChannel].
+const DOPPLER_SINUSOIDS: usize = 16;
+
+/// The time-varying fading of the taps of a [MultipathChannel], see [MultipathChannel::with_doppler].
+#[derive(Clone, Debug)]
+struct Doppler {
+    max_doppler_hz: f32,
+    /// For every tap, the frequencies in radians per sample and the phases of its sinusoids.
+    sinusoids: Vec<[(f64, f64); DOPPLER_SINUSOIDS]>,
+    /// The index of the next complex sample.
+    position: u64,
+    /// The in-phase and the quadrature filter of the analytic signal of real samples, which fade with a shift.
+    hilbert: Option<(FirFilter, FirFilter)>,
+}
+
+impl Doppler {
+    /// Returns the fading of a tap at a sample, of mean power 1.
+    fn fading(sinusoids: &[(f64, f64); DOPPLER_SINUSOIDS], position: u64) -> Complex32 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for &(frequency, phase) in sinusoids {
+            let (sin, cos) = (frequency * position as f64 + phase).sin_cos();
+            re += cos;
+            im += sin;
+        }
+        let scale = 1.0 / (DOPPLER_SINUSOIDS as f64).sqrt();
+        Complex32::new((re * scale) as f32, (im * scale) as f32)
+    }
+}
+
+/// A tapped delay line, the sum of delayed and scaled copies of the signal.
+///
+/// Every tap is a delay in samples and a complex gain, real samples see the real parts of the gains
+/// unless they fade.
+/// The channel keeps the last samples of every buffer, so the echoes of one buffer reach into the next.
+/// The taps come from their delays and gains, or from a named [MultipathProfile],
+/// and may fade over time with a Doppler spread, see [with_doppler](MultipathChannel::with_doppler).
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// // the echo fades some subcarriers, which differential symbols compare against their neighbours in time
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 8,
+///     differential_time: true,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+///
+/// // an echo 6 dB down and 5 samples late, inside the cyclic prefix, and noise
+/// let mut channel = ChannelChain::new()
+///     .with(MultipathChannel::two_ray(5, -6.0))
+///     .with(AwgnChannel::new(20.0, 1));
+/// let mut frame = modulator.encode_frame(b"Echoes");
+/// channel.apply(&mut frame);
+/// assert_eq!(demodulator.decode_frame(&frame).unwrap(), b"Echoes");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultipathChannel {
+    taps: Vec<(usize, Complex32)>,
+    /// The last inputs, as many as the longest delay, the oldest first.
+    history: Vec<f32>,
+    complex_history: Vec<Complex32>,
+    doppler: Option<Doppler>,
+}
+
+impl MultipathChannel {
+    /// Creates a channel from its taps, pairs of a delay in samples and a gain.
+    ///
+    /// # Panics
+    /// If there are no taps, or a gain is not finite.
+    pub fn new(taps: &[(usize, Complex32)]) -> Self {
+        if taps.is_empty() {
+            panic!("Taps must not be empty");
+        }
+        if let Some((_, gain)) = taps.iter().find(|(_, gain)| !gain.is_finite()) {
+            panic!("Tap gains must be finite, but got {}", gain);
+        }
+
+        let max_delay = taps.iter().map(|&(delay, _)| delay).max().unwrap_or(0);
+        MultipathChannel {
+            taps: taps.to_vec(),
+            history: vec![0.0; max_delay],
+            complex_history: vec![Complex32::new(0.0, 0.0); max_delay],
+            doppler: None,
+        }
+    }
+
+    /// Creates a channel of a named profile at a sample rate, every path a tap at its delay rounded to samples,
+    /// with the amplitude of its power and a random phase drawn from the seed, the first one real.
+    ///
+    /// The gains are scaled to a total power of 1, paths rounded to the same delay add their powers.
+    /// The phases only make the real parts of the gains, which real samples see, differ from their amplitudes.
+    ///
+    /// # Panics
+    /// As [MultipathProfile::get_paths].
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::{MultipathChannel, MultipathProfile};
+    ///
+    /// // the Vehicular A channel at 20 MHz, the last path 2510 ns or 50 samples late
+    /// let profile = MultipathProfile::Vehicular;
+    /// let channel = MultipathChannel::from_profile(profile, 20e6, 1);
+    /// let delays: Vec<usize> = channel.get_taps().iter().map(|&(delay, _)| delay).collect();
+    /// assert_eq!(delays, [0, 6, 14, 22, 35, 50]);
+    ///
+    /// // the delay spread of the taps is the one of the profile, but for the rounding
+    /// let spread = channel.get_rms_delay_spread() / 20e6;
+    /// assert!((spread / profile.get_rms_delay_spread(20e6) - 1.0).abs() < 0.01);
+    /// ```
+    pub fn from_profile(profile: MultipathProfile, sample_rate: f32, seed: u64) -> Self {
+        let mut powers: Vec<(usize, f64)> = Vec::new();
+        for (delay, power_db) in profile.get_paths(sample_rate) {
+            let delay = (f64::from(delay) * f64::from(sample_rate)).round() as usize;
+            let power = 10f64.powf(f64::from(power_db) / 10.0);
+            match powers.iter_mut().find(|(tap, _)| *tap == delay) {
+                Some((_, sum)) => *sum += power,
+                None => powers.push((delay, power)),
+            }
+        }
+
+        let total: f64 = powers.iter().map(|&(_, power)| power).sum();
+        let mut phases = SimulationRng::new(seed);
+        let taps: Vec<(usize, Complex32)> = powers
+            .iter()
+            .enumerate()
+            .map(|(i, &(delay, power))| {
+                let phase = if i == 0 {
+                    0.0
+                } else {
+                    core::f64::consts::TAU * phases.uniform()
+                };
+                let gain = Complex32::from_polar((power / total).sqrt() as f32, phase as f32);
+                (delay, gain)
+            })
+            .collect();
+        Self::new(&taps)
+    }
+
+    /// Makes the gains of the taps fade over time, each by its own Rayleigh fading with the Jakes spectrum
+    /// of the maximum Doppler shift at the sample rate, drawn from the seed.
+    ///
+    /// Every tap is multiplied by a sum of 16 complex sinusoids at the Doppler shifts of paths arriving
+    /// from evenly spaced angles, with random phases, of mean power 1. Its autocorrelation over time
+    /// is close to `J0(2 pi max_doppler_hz t)`, which first falls to 0 after `0.38 / max_doppler_hz` seconds.
+    /// A maximum Doppler shift of 0 keeps the taps still.
+    ///
+    /// Real samples stand for a band in a passband, like the audio of a phone carried by someone walking.
+    /// Their analytic signal goes through the fading taps and its real part is taken, which delays them
+    /// by 127 samples, see [get_delay](Self::get_delay). Without a shift they see the real parts of the gains.
+    ///
+    /// # Panics
+    /// If the maximum Doppler shift is negative or not finite, or the sample rate is not positive and finite.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::channel::{Channel, MultipathChannel, MultipathProfile};
+    ///
+    /// // a walk at 3 km/h through a 2.4 GHz carrier shifts it by up to 6.7 Hz
+    /// let mut channel = MultipathChannel::from_profile(MultipathProfile::Pedestrian, 1e6, 1)
+    ///     .with_doppler(6.7, 1e6, 2);
+    /// assert_eq!(channel.get_max_doppler_hz(), Some(6.7));
+    /// let mut samples = vec![Complex32::new(1.0, 0.0); 100_000];
+    /// channel.apply_complex(&mut samples);
+    /// // the taps hardly move over a millisecond, but do over a tenth of a second
+    /// assert!((samples[1000] - samples[0]).norm() < 0.1);
+    /// assert!((samples[99_999] - samples[0]).norm() > 0.1);
+    /// ```
+    pub fn with_doppler(mut self, max_doppler_hz: f32, sample_rate: f32, seed: u64) -> Self {
+        if !(max_doppler_hz >= 0.0 && max_doppler_hz.is_finite()) {
+            panic!(
+                "Maximum Doppler shift must be non-negative and finite, but got {}",
+                max_doppler_hz
+            );
+        }
+        if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+            panic!(
+                "Sample rate must be positive and finite, but got {}",
+                sample_rate
+            );
+        }
+
+        let max_doppler =
+            core::f64::consts::TAU * f64::from(max_doppler_hz) / f64::from(sample_rate);
+        let mut noise = SimulationRng::new(seed);
+        let sinusoids = self
+            .taps
+            .iter()
+            .map(|_| {
+                let offset = core::f64::consts::TAU * noise.uniform();
+                core::array::from_fn(|m| {
+                    let angle =
+                        (core::f64::consts::TAU * m as f64 + offset) / DOPPLER_SINUSOIDS as f64;
+                    (
+                        max_doppler * angle.cos(),
+                        core::f64::consts::TAU * noise.uniform(),
+                    )
+                })
+            })
+            .collect();
+        self.doppler = Some(Doppler {
+            max_doppler_hz,
+            sinusoids,
+            position: 0,
+            hilbert: (max_doppler_hz > 0.0).then(hilbert_filters),
+        });
+        self
+    }
+
+    /// Returns the maximum Doppler shift in Hz the taps fade with, if they do, see [with_doppler](MultipathChannel::with_doppler).
+    pub fn get_max_doppler_hz(&self) -> Option<f32> {
+        self.doppler.as_ref().map(|doppler| doppler.max_doppler_hz)
+    }
+
+    /// Returns the RMS delay spread of the taps in samples, the standard deviation of their delays
+    /// weighted by the powers of their gains, without any fading.
+    pub fn get_rms_delay_spread(&self) -> f32 {
+        let paths: Vec<(f64, f64)> = self
+            .taps
+            .iter()
+            .map(|&(delay, gain)| (delay as f64, f64::from(gain.norm_sqr())))
+            .collect();
+        rms_delay_spread(&paths) as f32
+    }
+
+    /// Creates a channel of a direct path with a gain of 1 and one echo, `delay` samples later and `relative_db` weaker.
+    ///
+    /// An echo of 0 dB cancels the signal at the odd multiples of `1 / (2 delay)` of the sample rate.
+    ///
+    /// # Panics
+    /// If the delay is zero, or the level is not finite.
+    pub fn two_ray(delay: usize, relative_db: f32) -> Self {
+        if delay == 0 {
+            panic!("Echo delay must be positive, but got {}", delay);
+        }
+        if !relative_db.is_finite() {
+            panic!("Echo level must be finite, but got {}", relative_db);
+        }
+
+        let echo = 10f32.powf(relative_db / 20.0);
+        Self::new(&[
+            (0, Complex32::new(1.0, 0.0)),
+            (delay, Complex32::new(echo, 0.0)),
+        ])
+    }
+
+    /// Creates a channel with a tap on every delay up to `max_delay`, with random complex Gaussian gains
+    /// whose mean power decays exponentially, by 1/e every `decay` samples, drawn from the seed.
+    ///
+    /// The gains are scaled to a total power of 1.
+    ///
+    /// # Panics
+    /// If the decay is not positive and finite.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::MultipathChannel;
+    ///
+    /// let channel = MultipathChannel::exponential(7, 2.0, 3);
+    /// assert_eq!(channel.get_taps().len(), 8);
+    /// let power: f32 = channel.get_taps().iter().map(|(_, gain)| gain.norm_sqr()).sum();
+    /// assert!((power - 1.0).abs() < 1e-5);
+    /// ```
+    pub fn exponential(max_delay: usize, decay: f32, seed: u64) -> Self {
+        if !(decay > 0.0 && decay.is_finite()) {
+            panic!("Decay must be positive and finite, but got {}", decay);
+        }
+
+        let mut noise = SimulationRng::new(seed);
+        let mut taps: Vec<(usize, Complex32)> = (0..=max_delay)
+            .map(|delay| {
+                let sigma =
+                    (-(delay as f64) / f64::from(decay) / 2.0).exp() / core::f64::consts::SQRT_2;
+                let gain = Complex32::new(
+                    (sigma * noise.gaussian()) as f32,
+                    (sigma * noise.gaussian()) as f32,
+                );
+                (delay, gain)
+            })
+            .collect();
+        let power: f32 = taps.iter().map(|(_, gain)| gain.norm_sqr()).sum();
+        for (_, gain) in &mut taps {
+            *gain /= power.sqrt();
+        }
+        Self::new(&taps)
+    }
+
+    /// Returns the taps, pairs of a delay in samples and a gain.
+    pub fn get_taps(&self) -> &[(usize, Complex32)] {
+        &self.taps
+    }
+
+    /// Returns the delay of the real samples in samples, of the filters of their analytic signal
+    /// if they fade, see [with_doppler](Self::with_doppler), 0 otherwise. Complex samples are not delayed.
+    pub fn get_delay(&self) -> usize {
+        match &self.doppler {
+            Some(Doppler {
+                hilbert: Some(_), ..
+            }) => HILBERT_TAPS / 2,
+            _ => 0,
+        }
+    }
+
+    /// Forgets the samples of the previous buffers, as if the channel had been silent, and restarts the fading.
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.complex_history.fill(Complex32::new(0.0, 0.0));
+        if let Some(doppler) = &mut self.doppler {
+            doppler.position = 0;
+            if let Some((in_phase, quadrature)) = &mut doppler.hilbert {
+                in_phase.reset();
+                quadrature.reset();
+            }
+        }
+    }
+}
+
+/// Convolves the samples with the taps in place, after the history of earlier inputs, which is then updated.
+fn convolve<T>(taps: &[(usize, T)], history: &mut [T], samples: &mut [T])
+where
+    T: Copy + Default + core::ops::Add<Output = T> + core::ops::Mul<Output = T>,
+{
+    let mut input = Vec::with_capacity(history.len() + samples.len());
+    input.extend_from_slice(history);
+    input.extend_from_slice(samples);
+    for (n, sample) in samples.iter_mut().enumerate() {
+        let end = n + history.len();
+        *sample = taps.iter().fold(T::default(), |sum, &(delay, gain)| {
+            sum + gain * input[end - delay]
+        });
+    }
+    let kept = input.len() - history.len();
+    history.copy_from_slice(&input[kept..]);
+}
+
+impl Channel for MultipathChannel {
+    fn apply(&mut self, samples: &mut [f32]) {
+        if let Some(Doppler {
+            hilbert: Some((in_phase, quadrature)),
+            ..
+        }) = &mut self.doppler
+        {
+            let mut analytic: Vec<Complex32> = in_phase
+                .process(samples)
+                .into_iter()
+                .zip(quadrature.process(samples))
+                .map(|(re, im)| Complex32::new(re, im))
+                .collect();
+            self.apply_complex(&mut analytic);
+            for (sample, analytic) in samples.iter_mut().zip(analytic) {
+                *sample = analytic.re;
+            }
+            return;
+        }
+
+        let taps: Vec<(usize, f32)> = self
+            .taps
+            .iter()
+            .map(|&(delay, gain)| (delay, gain.re))
+            .collect();
+        convolve(&taps, &mut self.history, samples);
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        let Some(doppler) = &mut self.doppler else {
+            convolve(&self.taps, &mut self.complex_history, samples);
+            return;
+        };
+
+        let history = &mut self.complex_history;
+        let mut input = Vec::with_capacity(history.len() + samples.len());
+        input.extend_from_slice(history);
+        input.extend_from_slice(samples);
+        for (n, sample) in samples.iter_mut().enumerate() {
+            let end = n + history.len();
+            let position = doppler.position + n as u64;
+            *sample = self.taps.iter().zip(&doppler.sinusoids).fold(
+                Complex32::new(0.0, 0.0),
+                |sum, (&(delay, gain), sinusoids)| {
+                    sum + gain * Doppler::fading(sinusoids, position) * input[end - delay]
+                },
+            );
+        }
+        doppler.position += samples.len() as u64;
+        let kept = input.len() - history.len();
+        history.copy_from_slice(&input[kept..]);
+    }
+}
+
+/// The longest impulse response an [IrChannel] convolves directly, longer ones are convolved by FFTs.
+const DIRECT_IR_LENGTH: usize = 64;
+
+/// Convolves the signal with an impulse response, such as the reverberation of a room between a speaker and a microphone.
+///
+/// The response is measured, read from a WAV file with `from_wav` behind the `wav` feature,
+/// or made by [synthetic_rir]. Responses of up to 64 samples are convolved directly, longer ones by overlap-add
+/// over FFTs of at least twice their length. The channel keeps the tail of the convolution of every buffer,
+/// so a signal passed in several buffers comes out like one long buffer, without any delay.
+/// Complex samples are convolved with the same real response.
+///
+/// A response no longer than the [cyclic prefix](crate::ofdm::OFDMConfig::cyclic_prefix_length) plus one sample
+/// only scales and turns every subcarrier, which the equalizer undoes. Beyond it, one symbol leaks into the next.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{AwgnChannel, Channel, ChannelChain, IrChannel, synthetic_rir};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::{GuardInterval, OFDMConfig};
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     guard_interval: Some(GuardInterval::Quarter),
+///     differential_time: true,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+///
+/// // a small, damped room at 8 kHz, whose reverberation dies away within the cyclic prefix
+/// let rir = synthetic_rir(8000.0, 0.004, 1);
+/// assert_eq!(rir.len(), 33);
+/// let mut channel = ChannelChain::new()
+///     .with(IrChannel::from_samples(&rir))
+///     .with(AwgnChannel::new(30.0, 2));
+/// let mut frame = modulator.encode_frame(b"Across the room");
+/// channel.apply(&mut frame);
+/// assert_eq!(demodulator.decode_frame(&frame).unwrap(), b"Across the room");
+/// ```
+#[derive(Clone)]
+pub struct IrChannel {
+    impulse_response: Vec<f32>,
+    convolution: IrConvolution,
+}
+
+/// The convolution of an [IrChannel] and its state between buffers.
+#[derive(Clone)]
+enum IrConvolution {
+    Direct {
+        taps: Vec<(usize, f32)>,
+        complex_taps: Vec<(usize, Complex32)>,
+        /// The last inputs, as many as the response is long but one, the oldest first.
+        history: Vec<f32>,
+        complex_history: Vec<Complex32>,
+    },
+    Fft(Box<IrFft>),
+}
+
+/// The FFTs of the overlap-add convolution of an [IrChannel].
+#[derive(Clone)]
+struct IrFft {
+    forward: Arc<dyn RealForwardFft<f32>>,
+    inverse: Arc<dyn RealInverseFft<f32>>,
+    complex_forward: Arc<dyn ComplexFft<f32>>,
+    complex_inverse: Arc<dyn ComplexFft<f32>>,
+    /// The spectrum of the response, scaled by the inverse of the FFT length.
+    spectrum: Vec<Complex32>,
+    complex_spectrum: Vec<Complex32>,
+    /// The convolution of the past inputs reaching into the next outputs, as long as the response but one.
+    tail: Vec<f32>,
+    complex_tail: Vec<Complex32>,
+}
+
+impl IrChannel {
+    /// Creates a channel convolving with an impulse response.
+    ///
+    /// # Panics
+    /// If the response is empty, or a sample is not finite.
+    pub fn from_samples(impulse_response: &[f32]) -> Self {
+        if impulse_response.is_empty() {
+            panic!("Impulse response must not be empty");
+        }
+        if let Some(sample) = impulse_response.iter().find(|sample| !sample.is_finite()) {
+            panic!(
+                "Impulse response samples must be finite, but got {}",
+                sample
+            );
+        }
+
+        let length = impulse_response.len();
+        let convolution = if length <= DIRECT_IR_LENGTH {
+            IrConvolution::Direct {
+                taps: impulse_response.iter().copied().enumerate().collect(),
+                complex_taps: impulse_response
+                    .iter()
+                    .map(|&gain| Complex32::new(gain, 0.0))
+                    .enumerate()
+                    .collect(),
+                history: vec![0.0; length - 1],
+                complex_history: vec![Complex32::new(0.0, 0.0); length - 1],
+            }
+        } else {
+            let fft_length = (2 * length).next_power_of_two();
+            let forward = plan_real_forward(fft_length);
+            let complex_forward = plan_complex_forward(fft_length);
+            let scale = 1.0 / fft_length as f32;
+
+            let mut input = vec![0.0; fft_length];
+            input[..length].copy_from_slice(impulse_response);
+            let mut spectrum = vec![Complex32::new(0.0, 0.0); fft_length / 2 + 1];
+            forward.process(&mut input, &mut spectrum);
+            let mut complex_spectrum = vec![Complex32::new(0.0, 0.0); fft_length];
+            for (bin, &sample) in complex_spectrum.iter_mut().zip(impulse_response) {
+                *bin = Complex32::new(sample, 0.0);
+            }
+            complex_forward.process(&mut complex_spectrum);
+            for bin in spectrum.iter_mut().chain(&mut complex_spectrum) {
+                *bin *= scale;
+            }
+
+            IrConvolution::Fft(Box::new(IrFft {
+                forward,
+                inverse: plan_real_inverse(fft_length),
+                complex_forward,
+                complex_inverse: plan_complex_inverse(fft_length),
+                spectrum,
+                complex_spectrum,
+                tail: vec![0.0; length - 1],
+                complex_tail: vec![Complex32::new(0.0, 0.0); length - 1],
+            }))
+        };
+
+        IrChannel {
+            impulse_response: impulse_response.to_vec(),
+            convolution,
+        }
+    }
+
+    /// Reads an impulse response from a WAV file, the mean of its channels, and returns the channel
+    /// with the sample rate of the file, which must be the one of the signal.
+    ///
+    /// # Errors
+    /// As [read_wav](crate::io::read_wav), and [WavError::InvalidFile](crate::io::WavError::InvalidFile)
+    /// if the file holds no samples.
+    ///
+    /// # Panics
+    /// If a sample is not finite.
+    #[cfg(feature = "wav")]
+    pub fn from_wav(path: impl AsRef<std::path::Path>) -> Result<(Self, u32), crate::io::WavError> {
+        let (samples, sample_rate) = crate::io::read_wav(path)?;
+        if samples.is_empty() {
+            return Err(crate::io::WavError::InvalidFile);
+        }
+        Ok((Self::from_samples(&samples), sample_rate))
+    }
+
+    /// Returns the impulse response.
+    pub fn get_impulse_response(&self) -> &[f32] {
+        &self.impulse_response
+    }
+
+    /// Forgets the samples of the previous buffers, as if the channel had been silent.
+    pub fn reset(&mut self) {
+        match &mut self.convolution {
+            IrConvolution::Direct {
+                history,
+                complex_history,
+                ..
+            } => {
+                history.fill(0.0);
+                complex_history.fill(Complex32::new(0.0, 0.0));
+            }
+            IrConvolution::Fft(fft) => {
+                fft.tail.fill(0.0);
+                fft.complex_tail.fill(Complex32::new(0.0, 0.0));
+            }
+        }
+    }
+}
+
+impl IrFft {
+    /// Returns the number of input samples convolved by one FFT, with the response they fill its length.
+    fn block_length(&self) -> usize {
+        self.spectrum.len() * 2 - 2 - self.tail.len()
+    }
+}
+
+/// Adds the tail of the past inputs to the convolution of a block, which replaces the block,
+/// and keeps what reaches beyond it as the next tail.
+fn overlap_add<T: Copy + Default + core::ops::Add<Output = T>>(
+    convolution: &[T],
+    tail: &mut [T],
+    block: &mut [T],
+) {
+    let length = block.len();
+    for (i, sample) in block.iter_mut().enumerate() {
+        *sample = convolution[i] + tail.get(i).copied().unwrap_or_default();
+    }
+    for i in 0..tail.len() {
+        tail[i] = convolution[length + i] + tail.get(length + i).copied().unwrap_or_default();
+    }
+}
+
+impl Channel for IrChannel {
+    fn apply(&mut self, samples: &mut [f32]) {
+        match &mut self.convolution {
+            IrConvolution::Direct { taps, history, .. } => convolve(taps, history, samples),
+            IrConvolution::Fft(fft) => {
+                let fft_length = fft.forward.fft_length();
+                let mut input = vec![0.0; fft_length];
+                let mut bins = vec![Complex32::new(0.0, 0.0); fft_length / 2 + 1];
+                let mut output = vec![0.0; fft_length];
+                for block in samples.chunks_mut(fft.block_length()) {
+                    input.fill(0.0);
+                    input[..block.len()].copy_from_slice(block);
+                    fft.forward.process(&mut input, &mut bins);
+                    for (bin, &gain) in bins.iter_mut().zip(&fft.spectrum) {
+                        *bin *= gain;
+                    }
+                    // the first and the last bins of a real response stay real
+                    bins[0].im = 0.0;
+                    bins[fft_length / 2].im = 0.0;
+                    fft.inverse.process(&mut bins, &mut output);
+                    overlap_add(&output, &mut fft.tail, block);
+                }
+            }
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        match &mut self.convolution {
+            IrConvolution::Direct {
+                complex_taps,
+                complex_history,
+                ..
+            } => convolve(complex_taps, complex_history, samples),
+            IrConvolution::Fft(fft) => {
+                let fft_length = fft.complex_forward.fft_length();
+                let mut buffer = vec![Complex32::new(0.0, 0.0); fft_length];
+                for block in samples.chunks_mut(fft.block_length()) {
+                    buffer.fill(Complex32::new(0.0, 0.0));
+                    buffer[..block.len()].copy_from_slice(block);
+                    fft.complex_forward.process(&mut buffer);
+                    for (bin, &gain) in buffer.iter_mut().zip(&fft.complex_spectrum) {
+                        *bin *= gain;
+                    }
+                    fft.complex_inverse.process(&mut buffer);
+                    overlap_add(&buffer, &mut fft.complex_tail, block);
+                }
+            }
+        }
+    }
+}
+
+/// Makes the impulse response of a room: the direct sound, an impulse, and reverberation,
+/// white Gaussian noise from the seed whose level decays by 60 dB over `rt60` seconds, at a sample rate.
+///
+/// The response lasts `rt60` seconds and one sample, the reverberation holds as much energy as the direct sound,
+/// and the whole response an energy of 1.
+///
+/// # Panics
+/// If the sample rate or the reverberation time is not positive and finite.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::synthetic_rir;
+///
+/// let rir = synthetic_rir(48000.0, 0.25, 1);
+/// assert_eq!(rir.len(), 12001);
+/// let energy = |samples: &[f32]| samples.iter().map(|x| x * x).sum::<f32>();
+/// assert!((energy(&rir) - 1.0).abs() < 1e-4);
+/// assert!((energy(&rir[..1]) - 0.5).abs() < 1e-4);
+/// // the last tenth of the reverberation is 54 dB down on its start
+/// assert!(energy(&rir[10800..]) < 1e-4 * energy(&rir[1..1201]));
+/// ```
+pub fn synthetic_rir(sample_rate: f32, rt60: f32, seed: u64) -> Vec<f32> {
+    if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+        panic!(
+            "Sample rate must be positive and finite, but got {}",
+            sample_rate
+        );
+    }
+    if !(rt60 > 0.0 && rt60.is_finite()) {
+        panic!(
+            "Reverberation time must be positive and finite, but got {}",
+            rt60
+        );
+    }
+
+    let length = (f64::from(rt60) * f64::from(sample_rate)).round() as usize;
+    // an amplitude falling by a factor of 1000 over the length
+    let decay = -3.0 * core::f64::consts::LN_10 / length.max(1) as f64;
+    let mut noise = SimulationRng::new(seed);
+    let mut response = vec![1.0];
+    response.extend((1..=length).map(|n| noise.gaussian() * (decay * n as f64).exp()));
+
+    let reverberation: f64 = response[1..].iter().map(|x| x * x).sum();
+    let scale = if reverberation > 0.0 {
+        1.0 / reverberation.sqrt()
+    } else {
+        0.0
+    };
+    for sample in &mut response[1..] {
+        *sample *= scale;
+    }
+    response
+        .iter()
+        .map(|&x| (x / core::f64::consts::SQRT_2) as f32)
+        .collect()
+}
+
+/// Samples by which an [OffsetImpairment] delays its output beyond the timing offset, which leaves room for the filters
+/// and for a slower receiver clock to read ahead.
+const OFFSET_DELAY: usize = 256;
+
+/// Taps of the Hilbert transformer of the real samples of an [OffsetImpairment], half a sample rate wide
+/// but for about 1 % of it at DC and at the Nyquist frequency.
+const HILBERT_TAPS: usize = 255;
+
+/// Taps of the fractional interpolation of an [OffsetImpairment].
+const INTERPOLATOR_TAPS: usize = 8;
+
+/// Offsets the receiver from the transmitter: in the frequency of the carrier, in the rate of the sample clock,
+/// and in the timing of the first sample.
+///
+/// The receiver samples at `1 + sco_ppm * 1e-6` times the rate of the transmitter, starting `timing_offset` samples late,
+/// through a [FarrowInterpolator], and its carrier is `cfo_hz` above the one of the transmitter.
+/// Real samples stand for a band in a passband, their analytic signal is shifted, so the band moves without an image.
+/// That transform does not reach the lowest and highest 1 % of the sample rate.
+///
+/// The output is delayed by 256 samples beyond the timing offset, see [get_delay](OffsetImpairment::get_delay).
+/// A slower receiver clock reads the input faster than it arrives, which this delay allows for
+/// over more than a million samples at -100 ppm.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::channel::{Channel, OffsetImpairment};
+///
+/// // a carrier 120 Hz off at 48 kHz is half a subcarrier spacing of a 200 bin complex FFT
+/// let mut channel = OffsetImpairment::new(48000.0, 120.0, 0.0, 0.0);
+/// let mut samples = vec![Complex32::new(1.0, 0.0); 1000];
+/// channel.apply_complex(&mut samples);
+/// let delay = channel.get_delay() as usize;
+/// let rotation = samples[delay + 100] * samples[delay].conj();
+/// assert!((rotation.arg() - std::f32::consts::TAU * 120.0 * 100.0 / 48000.0).abs() < 1e-4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OffsetImpairment {
+    sample_rate: f32,
+    cfo_hz: f32,
+    sco_ppm: f32,
+    timing_offset: f32,
+    interpolator: FarrowInterpolator,
+    /// The in-phase and the quadrature filter of the analytic signal of the real samples.
+    hilbert: Option<(FirFilter, FirFilter)>,
+    real: OffsetStream,
+    complex: OffsetStream,
+}
+
+impl OffsetImpairment {
+    /// Creates an impairment with a carrier frequency offset in Hz, a sampling clock offset in ppm
+    /// and a timing offset in samples, at a sample rate.
+    ///
+    /// # Panics
+    /// If the sample rate is not positive and finite, the carrier frequency offset is not below half the sample rate,
+    /// the sampling clock offset is not finite and above -1e6 ppm, or the timing offset is not positive or zero.
+    pub fn new(sample_rate: f32, cfo_hz: f32, sco_ppm: f32, timing_offset: f32) -> Self {
+        if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+            panic!(
+                "Sample rate must be positive and finite, but got {}",
+                sample_rate
+            );
+        }
+        if !(cfo_hz.is_finite() && cfo_hz.abs() < sample_rate / 2.0) {
+            panic!(
+                "Carrier frequency offset must be below {} Hz, but got {}",
+                sample_rate / 2.0,
+                cfo_hz
+            );
+        }
+        if !(sco_ppm > -1e6 && sco_ppm.is_finite()) {
+            panic!(
+                "Sampling clock offset must be finite and above -1e6 ppm, but got {}",
+                sco_ppm
+            );
+        }
+        if !(timing_offset >= 0.0 && timing_offset.is_finite()) {
+            panic!(
+                "Timing offset must be positive or zero, but got {}",
+                timing_offset
+            );
+        }
+
+        // the real samples only need the analytic signal to shift
+        let hilbert = (cfo_hz != 0.0).then(hilbert_filters);
+        let delay = f64::from(timing_offset) + OFFSET_DELAY as f64;
+        let real_delay = delay
+            - if hilbert.is_some() {
+                (HILBERT_TAPS / 2) as f64
+            } else {
+                0.0
+            };
+
+        OffsetImpairment {
+            sample_rate,
+            cfo_hz,
+            sco_ppm,
+            timing_offset,
+            interpolator: FarrowInterpolator::new(INTERPOLATOR_TAPS),
+            hilbert,
+            real: OffsetStream::new(real_delay),
+            complex: OffsetStream::new(delay),
+        }
+    }
+
+    /// Returns the sample rate in Hz.
+    pub fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Returns the carrier frequency offset in Hz.
+    pub fn get_cfo_hz(&self) -> f32 {
+        self.cfo_hz
+    }
+
+    /// Returns the sampling clock offset in ppm.
+    pub fn get_sco_ppm(&self) -> f32 {
+        self.sco_ppm
+    }
+
+    /// Returns the timing offset in samples.
+    pub fn get_timing_offset(&self) -> f32 {
+        self.timing_offset
+    }
+
+    /// Returns the delay of the first output sample in input samples, the timing offset and 256.
+    ///
+    /// Later samples drift by the sampling clock offset, the output `n` reads the input at `n / (1 + sco_ppm * 1e-6) - delay`.
+    pub fn get_delay(&self) -> f64 {
+        f64::from(self.timing_offset) + OFFSET_DELAY as f64
+    }
+
+    /// Returns the number of input samples for one output sample.
+    fn step(&self) -> f64 {
+        1.0 / (1.0 + f64::from(self.sco_ppm) * 1e-6)
+    }
+
+    /// Returns the phase step of the carrier offset per output sample.
+    fn phase_step(&self) -> f64 {
+        core::f64::consts::TAU * f64::from(self.cfo_hz) / f64::from(self.sample_rate)
+    }
+}
+
+impl Channel for OffsetImpairment {
+    fn apply(&mut self, samples: &mut [f32]) {
+        let input: Vec<Complex32> = match &mut self.hilbert {
+            Some((in_phase, quadrature)) => in_phase
+                .process(samples)
+                .into_iter()
+                .zip(quadrature.process(samples))
+                .map(|(re, im)| Complex32::new(re, im))
+                .collect(),
+            None => samples.iter().map(|&x| Complex32::new(x, 0.0)).collect(),
+        };
+        let (step, phase_step) = (self.step(), self.phase_step());
+        let mut output = vec![Complex32::new(0.0, 0.0); samples.len()];
+        self.real
+            .process(&self.interpolator, &input, &mut output, step, phase_step);
+        for (sample, output) in samples.iter_mut().zip(output) {
+            *sample = output.re;
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        let (step, phase_step) = (self.step(), self.phase_step());
+        let input = samples.to_vec();
+        self.complex
+            .process(&self.interpolator, &input, samples, step, phase_step);
+    }
+}
+
+/// Returns the in-phase and the quadrature filter of the analytic signal of real samples, a delay of half their taps
+/// and a Hilbert transformer with a Blackman window.
+fn hilbert_filters() -> (FirFilter, FirFilter) {
+    let center = HILBERT_TAPS / 2;
+    let mut delay = vec![0.0; HILBERT_TAPS];
+    delay[center] = 1.0;
+    let quadrature = (0..HILBERT_TAPS)
+        .map(|n| {
+            let t = n as f32 - center as f32;
+            let phase = core::f32::consts::TAU * n as f32 / (HILBERT_TAPS as f32 - 1.0);
+            let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            if (n + center) % 2 == 1 {
+                window * 2.0 / (core::f32::consts::PI * t)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    (FirFilter::new(delay), FirFilter::new(quadrature))
+}
+
+/// The state of one stream through an [OffsetImpairment].
+#[derive(Clone, Debug)]
+struct OffsetStream {
+    /// The inputs from `first` on, which later outputs may still read.
+    history: VecDeque<Complex32>,
+    first: i64,
+    /// The delay of the first output in inputs.
+    delay: f64,
+    /// The number of outputs so far.
+    outputs: u64,
+}
+
+impl OffsetStream {
+    fn new(delay: f64) -> Self {
+        OffsetStream {
+            history: VecDeque::new(),
+            first: 0,
+            delay,
+            outputs: 0,
+        }
+    }
+
+    /// Appends the input and interpolates the outputs of the same number, rotated by the carrier offset.
+    ///
+    /// # Panics
+    /// If a slower receiver clock has used up the delay and reads inputs which did not arrive yet.
+    fn process(
+        &mut self,
+        interpolator: &FarrowInterpolator,
+        input: &[Complex32],
+        output: &mut [Complex32],
+        step: f64,
+        phase_step: f64,
+    ) {
+        self.history.extend(input);
+        let half = (interpolator.taps() / 2) as i64;
+        let end = self.first + self.history.len() as i64;
+        let mut window = vec![Complex32::new(0.0, 0.0); interpolator.taps()];
+        for output in output.iter_mut() {
+            let n = self.outputs as f64;
+            let position = n * step - self.delay;
+            let index = position.floor() as i64;
+            if index + half >= end {
+                panic!(
+                    "Sampling clock offset must not read ahead of the input, but it did after {} samples",
+                    self.outputs
+                );
+            }
+            // the samples before the first input are zeros
+            for (k, sample) in window.iter_mut().enumerate() {
+                let i = index - half + 1 + k as i64;
+                *sample = if i >= self.first {
+                    self.history[(i - self.first) as usize]
+                } else {
+                    Complex32::new(0.0, 0.0)
+                };
+            }
+            let value = interpolator.interpolate(&window, (position - index as f64) as f32);
+            let phase = Euclid::rem_euclid(&(phase_step * n), &core::f64::consts::TAU);
+            *output = value * Complex32::from_polar(1.0, phase as f32);
+            self.outputs += 1;
+        }
+
+        // drop the inputs before the window of the next output
+        let next = (self.outputs as f64 * step - self.delay).floor() as i64 - half + 1;
+        let unused = (next - self.first).clamp(0, self.history.len() as i64);
+        self.history.drain(..unused as usize);
+        self.first += unused;
+    }
+}
+
+/// Turns the phase of the signal by a random walk, the phase noise of a free-running oscillator with a linewidth,
+/// like the cheap crystal of a sound card or of an SDR.
+///
+/// The phase is a Wiener process: every sample it steps by a Gaussian of variance `2 pi linewidth / sample_rate`,
+/// which spreads the carrier into a Lorentzian line `linewidth_hz` wide at half its power, drawn from a seeded generator.
+/// Complex samples are multiplied by `e^(j phase)`. Real samples stand for a band in a passband like for an
+/// [OffsetImpairment]: their analytic signal is turned and its real part taken, which mixes the band down and back up
+/// through a carrier with the phase noise, and delays them by 127 samples, see [get_delay](PhaseNoise::get_delay).
+///
+/// Within an OFDM symbol the phase turns every subcarrier alike, the common phase error, and the rest of its wander
+/// leaks between the subcarriers as noise.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::channel::{Channel, PhaseNoise};
+///
+/// // a linewidth of 10 Hz at 48 kHz wanders by about 0.36 rad within 100 samples
+/// let mut channel = PhaseNoise::new(48000.0, 10.0, 1);
+/// let mut samples = vec![Complex32::new(1.0, 0.0); 48000];
+/// channel.apply_complex(&mut samples);
+/// assert!(samples.iter().all(|sample| (sample.norm() - 1.0).abs() < 1e-5));
+/// let steps: Vec<f32> = samples.windows(101).map(|pair| (pair[100] * pair[0].conj()).arg()).collect();
+/// let deviation = (steps.iter().map(|step| step * step).sum::<f32>() / steps.len() as f32).sqrt();
+/// assert!((deviation - 0.36).abs() < 0.05, "{deviation}");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PhaseNoise {
+    sample_rate: f32,
+    linewidth_hz: f32,
+    /// Standard deviation of the step of the phase per sample, in radians.
+    deviation: f64,
+    phase: f64,
+    rng: SimulationRng,
+    /// The in-phase and the quadrature filter of the analytic signal of the real samples.
+    hilbert: (FirFilter, FirFilter),
+}
+
+impl PhaseNoise {
+    /// Creates phase noise of a linewidth in Hz at a sample rate, from a seed.
+    ///
+    /// # Panics
+    /// If the sample rate is not positive and finite, or the linewidth is not positive or zero and finite.
+    pub fn new(sample_rate: f32, linewidth_hz: f32, seed: u64) -> Self {
+        if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+            panic!(
+                "Sample rate must be positive and finite, but got {}",
+                sample_rate
+            );
+        }
+        if !(linewidth_hz >= 0.0 && linewidth_hz.is_finite()) {
+            panic!(
+                "Linewidth must be positive or zero and finite, but got {}",
+                linewidth_hz
+            );
+        }
+        PhaseNoise {
+            sample_rate,
+            linewidth_hz,
+            deviation: (core::f64::consts::TAU * f64::from(linewidth_hz) / f64::from(sample_rate))
+                .sqrt(),
+            phase: 0.0,
+            rng: SimulationRng::new(seed),
+            hilbert: hilbert_filters(),
+        }
+    }
+
+    /// Returns the sample rate in Hz.
+    pub fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Returns the linewidth in Hz.
+    pub fn get_linewidth_hz(&self) -> f32 {
+        self.linewidth_hz
+    }
+
+    /// Returns the phase the next sample is turned by, in radians, which starts at 0 and wanders without bound.
+    pub fn get_phase(&self) -> f64 {
+        self.phase
+    }
+
+    /// Returns the delay of the real samples in samples, of the filters of their analytic signal.
+    /// Complex samples are not delayed.
+    pub fn get_delay(&self) -> usize {
+        HILBERT_TAPS / 2
+    }
+
+    /// Returns the rotation of the next sample, and steps the phase.
+    fn next_rotation(&mut self) -> Complex32 {
+        let rotation = Complex32::from_polar(1.0, self.phase as f32);
+        self.phase += self.deviation * self.rng.gaussian();
+        rotation
+    }
+}
+
+impl Channel for PhaseNoise {
+    fn apply(&mut self, samples: &mut [f32]) {
+        let (in_phase, quadrature) = &mut self.hilbert;
+        let analytic: Vec<Complex32> = in_phase
+            .process(samples)
+            .into_iter()
+            .zip(quadrature.process(samples))
+            .map(|(re, im)| Complex32::new(re, im))
+            .collect();
+        for (sample, analytic) in samples.iter_mut().zip(analytic) {
+            *sample = (analytic * self.next_rotation()).re;
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        for sample in samples {
+            *sample *= self.next_rotation();
+        }
+    }
+}
+
+/// Quantizes the samples like a converter of a bit depth, behind an analog chain which clips.
+///
+/// The clip level is the full scale of the converter, it maps to the largest code like 1 to 32767 in 16 bits.
+/// Every sample is scaled to the codes, dithered if a dither is given, rounded, saturated at the largest codes
+/// and scaled back, so the output keeps the level of the input. Complex samples are quantized on every component,
+/// like the two converters of I and Q. The channel counts the clipped samples, see [get_clipped](QuantizeClip::get_clipped).
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{Channel, QuantizeClip};
+/// use software_modem::samples::{f32_to_i16, i16_to_f32};
+///
+/// // 16 bits at a clip level of 1 are the PCM samples of a sound card
+/// let samples: Vec<f32> = (0..1000).map(|n| 1.5 * (0.01 * n as f32).sin()).collect();
+/// let mut quantized = samples.clone();
+/// let mut channel = QuantizeClip::new(16, 1.0, None);
+/// channel.apply(&mut quantized);
+/// assert_eq!(quantized, i16_to_f32(&f32_to_i16(&samples, 1.0, None)));
+/// assert_eq!(channel.get_clipped(), samples.iter().filter(|x| x.abs() > 1.0).count() as u64);
+///
+/// // 4 bits have 8 steps on every side of zero
+/// let mut samples = vec![0.3, -0.3, 0.9, 2.0];
+/// QuantizeClip::new(4, 1.0, None).apply(&mut samples);
+/// assert_eq!(samples, [0.25, -0.25, 0.875, 0.875]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct QuantizeClip {
+    bits: u32,
+    clip_level: f32,
+    dither: Option<TpdfDither>,
+    clipped: u64,
+}
+
+impl QuantizeClip {
+    /// Creates a converter of `bits` bits with its full scale at the clip level, dithered by the dither if one is given.
+    ///
+    /// # Panics
+    /// If the bit depth is not between 2 and 24, which `f32` holds exactly, or the clip level is not positive and finite.
+    pub fn new(bits: u32, clip_level: f32, dither: Option<TpdfDither>) -> Self {
+        if !(2..=24).contains(&bits) {
+            panic!("Bit depth must be between 2 and 24, but got {}", bits);
+        }
+        if !(clip_level > 0.0 && clip_level.is_finite()) {
+            panic!(
+                "Clip level must be positive and finite, but got {}",
+                clip_level
+            );
+        }
+
+        QuantizeClip {
+            bits,
+            clip_level,
+            dither,
+            clipped: 0,
+        }
+    }
+
+    /// Returns the bit depth.
+    pub fn get_bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns the clip level, the full scale of the converter.
+    pub fn get_clip_level(&self) -> f32 {
+        self.clip_level
+    }
+
+    /// Returns whether the samples are dithered before they are rounded.
+    pub fn is_dithered(&self) -> bool {
+        self.dither.is_some()
+    }
+
+    /// Returns the number of samples clipped so far, complex samples count once if any of their components clipped.
+    pub fn get_clipped(&self) -> u64 {
+        self.clipped
+    }
+
+    /// Restarts the count of the clipped samples.
+    pub fn reset_clipped(&mut self) {
+        self.clipped = 0;
+    }
+
+    /// Quantizes a value, and returns whether it clipped.
+    fn quantize(&mut self, value: f32) -> (f32, bool) {
+        let levels = (1u32 << (self.bits - 1)) as f32;
+        let dither = self.dither.as_mut().map_or(0.0, |dither| dither.next());
+        let code = (value / self.clip_level * levels + dither).round();
+        let clipped = !(code >= -levels && code < levels);
+        let code = code.clamp(-levels, levels - 1.0);
+        (code / levels * self.clip_level, clipped)
+    }
+}
+
+impl Channel for QuantizeClip {
+    fn apply(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let (value, clipped) = self.quantize(*sample);
+            *sample = value;
+            self.clipped += u64::from(clipped);
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        for sample in samples {
+            let (re, re_clipped) = self.quantize(sample.re);
+            let (im, im_clipped) = self.quantize(sample.im);
+            *sample = Complex32::new(re, im);
+            self.clipped += u64::from(re_clipped || im_clipped);
+        }
+    }
+}
+
+/// Mismatches the gain and the phase of the I and Q branches of a receiver, like the mixers and filters of
+/// an SDR front-end.
+///
+/// The I branch is the reference, the Q branch has `gain_db` more gain and its oscillator is `phase_deg` off
+/// quadrature: `y = I + j g (Q cos φ - I sin φ)`. That is `y = μ x + ν x*` with `μ = (1 + g e^(-jφ)) / 2` and
+/// `ν = (1 - g e^(jφ)) / 2`, so every subcarrier gets an image of its mirror at the negative frequency,
+/// [get_image_rejection_db](IqImbalance::get_image_rejection_db) below it. The
+/// [complex demodulator](crate::ofdm::complex::ComplexOFDMDemodulator::estimate_iq_imbalance) estimates and
+/// corrects it. Real samples have no I and Q branches, and pass unchanged.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::channel::{Channel, IqImbalance};
+///
+/// let mut imbalance = IqImbalance { gain_db: 1.0, phase_deg: 5.0 };
+/// assert!((imbalance.get_image_rejection_db() - 22.8).abs() < 0.1);
+///
+/// // a tone at a positive frequency leaks into the negative one
+/// let mut tone: Vec<Complex32> = (0..64).map(|n| Complex32::from_polar(1.0, std::f32::consts::TAU * 5.0 * n as f32 / 64.0)).collect();
+/// imbalance.apply_complex(&mut tone);
+/// let bin = |k: f32| tone.iter().enumerate().map(|(n, x)| x * Complex32::from_polar(1.0, -std::f32::consts::TAU * k * n as f32 / 64.0)).sum::<Complex32>().norm();
+/// assert!((20.0 * (bin(5.0) / bin(-5.0)).log10() - 22.8).abs() < 0.1);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IqImbalance {
+    /// Gain of the Q branch over the one of the I branch, in dB.
+    pub gain_db: f32,
+    /// Phase of the oscillator of the Q branch off quadrature, in degrees.
+    pub phase_deg: f32,
+}
+
+impl IqImbalance {
+    /// Returns the power of a subcarrier over the one of its image in dB, infinite without an imbalance.
+    pub fn get_image_rejection_db(&self) -> f32 {
+        let (direct, image) = self.get_coefficients();
+        10.0 * (direct.norm_sqr() / image.norm_sqr()).log10()
+    }
+
+    /// Returns the gain `μ` of the signal and the gain `ν` of its conjugate.
+    pub(crate) fn get_coefficients(&self) -> (Complex32, Complex32) {
+        let quadrature =
+            Complex32::from_polar(10f32.powf(self.gain_db / 20.0), self.phase_deg.to_radians());
+        ((1.0 + quadrature.conj()) / 2.0, (1.0 - quadrature) / 2.0)
+    }
+}
+
+impl Channel for IqImbalance {
+    fn apply(&mut self, _samples: &mut [f32]) {}
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        let (direct, image) = self.get_coefficients();
+        for sample in samples {
+            *sample = direct * *sample + image * sample.conj();
+        }
+    }
+}
+
+/// The shape of the bursts of a [BurstNoise].
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BurstShape {
+    /// White Gaussian noise over the whole burst, like the crackle of a bad contact, the amplitude its RMS level.
+    #[default]
+    White,
+    /// A click, an impulse of the amplitude with a random sign or phase, decaying to 1 % over the burst, like a pop.
+    Click,
+}
+
+/// Adds bursts of noise at random times, the clicks and pops of an acoustic channel rather than a steady hiss.
+///
+/// The bursts start at the times of a Poisson process, `rate_per_sec` bursts per second on average,
+/// and last a number of samples drawn uniformly from a range. The times and the lengths come from the seed,
+/// independently of the noise in the bursts, so the same seed places the same bursts however the samples
+/// are split into buffers. When a burst starts during another one, it replaces it.
+/// The channel records the bursts it placed, see [get_bursts](BurstNoise::get_bursts).
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{BurstNoise, BurstShape, Channel};
+///
+/// // 20 bursts per second of 50 to 200 samples, at 48 kHz
+/// let mut channel = BurstNoise::new(48000.0, 20.0, 50..200, 0.5, BurstShape::White, 1);
+/// let mut samples = vec![0.0; 48000];
+/// channel.apply(&mut samples);
+///
+/// let bursts = channel.get_bursts();
+/// assert!((10..30).contains(&bursts.len()));
+/// assert!(bursts.iter().all(|burst| (50..200).contains(&(burst.end - burst.start))));
+/// // the samples between the bursts are left as they are
+/// assert!(samples[..bursts[0].start as usize].iter().all(|&x| x == 0.0));
+/// assert!(samples[bursts[0].start as usize..][..50].iter().all(|&x| x != 0.0));
+///
+/// // the same seed places the same bursts
+/// let mut again = BurstNoise::new(48000.0, 20.0, 50..200, 0.5, BurstShape::White, 1);
+/// again.apply(&mut vec![0.0; 48000]);
+/// assert_eq!(again.get_bursts(), bursts);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BurstNoise {
+    sample_rate: f32,
+    rate_per_sec: f32,
+    duration_samples: Range<usize>,
+    amplitude: f32,
+    shape: BurstShape,
+    /// The uniform values of the times, the lengths and the signs of the bursts.
+    schedule: SimulationRng,
+    noise: SimulationRng,
+    /// The index of the next sample.
+    position: u64,
+    next_start: u64,
+    current: Option<ActiveBurst>,
+    bursts: Vec<Range<u64>>,
+}
+
+/// The burst a [BurstNoise] is in.
+#[derive(Copy, Clone, Debug)]
+struct ActiveBurst {
+    start: u64,
+    length: usize,
+    /// A uniform value in `(0, 1]`, the sign of a real click, or the phase of a complex one.
+    draw: f64,
+}
+
+impl BurstNoise {
+    /// Creates a channel adding `rate_per_sec` bursts per second on average, at the sample rate,
+    /// lasting a number of samples in the range, of the amplitude and the shape, placed and drawn from the seed.
+    ///
+    /// # Panics
+    /// If the sample rate or the rate of the bursts is not positive and finite, the range of the durations is empty
+    /// or starts at 0, or the amplitude is negative or not finite.
+    pub fn new(
+        sample_rate: f32,
+        rate_per_sec: f32,
+        duration_samples: Range<usize>,
+        amplitude: f32,
+        shape: BurstShape,
+        seed: u64,
+    ) -> Self {
+        if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+            panic!(
+                "Sample rate must be positive and finite, but got {}",
+                sample_rate
+            );
+        }
+        if !(rate_per_sec > 0.0 && rate_per_sec.is_finite()) {
+            panic!(
+                "Burst rate must be positive and finite, but got {}",
+                rate_per_sec
+            );
+        }
+        if duration_samples.is_empty() || duration_samples.start == 0 {
+            panic!(
+                "Burst durations must be a non-empty range from 1 sample, but got {:?}",
+                duration_samples
+            );
+        }
+        if !(amplitude >= 0.0 && amplitude.is_finite()) {
+            panic!(
+                "Amplitude must be non-negative and finite, but got {}",
+                amplitude
+            );
+        }
+
+        let mut channel = BurstNoise {
+            sample_rate,
+            rate_per_sec,
+            duration_samples,
+            amplitude,
+            shape,
+            schedule: SimulationRng::new(seed),
+            noise: SimulationRng::new(seed).stream("burst noise"),
+            position: 0,
+            next_start: 0,
+            current: None,
+            bursts: Vec::new(),
+        };
+        channel.next_start = channel.gap();
+        channel
+    }
+
+    /// Returns the average number of bursts per second.
+    pub fn get_rate_per_sec(&self) -> f32 {
+        self.rate_per_sec
+    }
+
+    /// Returns the range of the lengths of the bursts in samples.
+    pub fn get_duration_samples(&self) -> Range<usize> {
+        self.duration_samples.clone()
+    }
+
+    /// Returns the amplitude of the bursts, the RMS level of white ones or the peak of clicks.
+    pub fn get_amplitude(&self) -> f32 {
+        self.amplitude
+    }
+
+    /// Returns the shape of the bursts.
+    pub fn get_shape(&self) -> BurstShape {
+        self.shape
+    }
+
+    /// Returns the samples of every burst placed so far, counted from the first sample the channel impaired,
+    /// in the order they started. A burst replaced by the next one ends where the next one starts.
+    pub fn get_bursts(&self) -> &[Range<u64>] {
+        &self.bursts
+    }
+
+    /// Forgets the bursts placed so far, the next ones are recorded from the start.
+    pub fn clear_bursts(&mut self) {
+        self.bursts.clear();
+    }
+
+    /// Returns the number of samples to the start of the next burst, at least 1.
+    fn gap(&mut self) -> u64 {
+        let mean = f64::from(self.sample_rate) / f64::from(self.rate_per_sec);
+        ((-self.schedule.uniform().ln() * mean).ceil() as u64).max(1)
+    }
+
+    /// Moves on by one sample, and returns the burst it is in with its index in the burst.
+    fn advance(&mut self) -> Option<(usize, ActiveBurst)> {
+        let position = self.position;
+        self.position += 1;
+        if position == self.next_start {
+            let lengths = self.duration_samples.len();
+            let offset = ((self.schedule.uniform() * lengths as f64) as usize).min(lengths - 1);
+            let burst = ActiveBurst {
+                start: position,
+                length: self.duration_samples.start + offset,
+                draw: self.schedule.uniform(),
+            };
+            // a burst replaced by this one ends here
+            if let Some(last) = self.bursts.last_mut() {
+                last.end = last.end.min(position);
+            }
+            self.bursts.push(position..position + burst.length as u64);
+            self.current = Some(burst);
+            self.next_start = position + self.gap();
+        }
+
+        let burst = self.current?;
+        let index = (position - burst.start) as usize;
+        if index >= burst.length {
+            self.current = None;
+            return None;
+        }
+        Some((index, burst))
+    }
+
+    /// Returns the envelope of a click at an index of its burst, from 1 down to 1 %.
+    fn decay(index: usize, burst: &ActiveBurst) -> f32 {
+        (-CLICK_DECAY * index as f32 / burst.length as f32).exp()
+    }
+}
+
+/// The decay of a click over its burst, to 1 %, `ln(100)`.
+const CLICK_DECAY: f32 = 4.605_17;
+
+impl Channel for BurstNoise {
+    fn apply(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let Some((index, burst)) = self.advance() else {
+                continue;
+            };
+            *sample += match self.shape {
+                BurstShape::White => self.amplitude * self.noise.gaussian() as f32,
+                BurstShape::Click => {
+                    let sign = if burst.draw > 0.5 { 1.0 } else { -1.0 };
+                    sign * self.amplitude * Self::decay(index, &burst)
+                }
+            };
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        for sample in samples {
+            let Some((index, burst)) = self.advance() else {
+                continue;
+            };
+            *sample += match self.shape {
+                BurstShape::White => {
+                    let sigma = self.amplitude / core::f32::consts::SQRT_2;
+                    Complex32::new(
+                        sigma * self.noise.gaussian() as f32,
+                        sigma * self.noise.gaussian() as f32,
+                    )
+                }
+                BurstShape::Click => Complex32::from_polar(
+                    self.amplitude * Self::decay(index, &burst),
+                    core::f32::consts::TAU * burst.draw as f32,
+                ),
+            };
+        }
+    }
+}
+
+/// Passes the samples through several channels, in the order they were added.
+///
+/// See [MultipathChannel] for an example.
+#[derive(Default)]
+pub struct ChannelChain {
+    channels: Vec<Box<dyn Channel>>,
+}
+
+impl ChannelChain {
+    /// Creates a chain without channels, which leaves the samples as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a channel after the ones already in the chain.
+    pub fn with(mut self, channel: impl Channel + 'static) -> Self {
+        self.channels.push(Box::new(channel));
+        self
+    }
+}
+
+impl Channel for ChannelChain {
+    fn apply(&mut self, samples: &mut [f32]) {
+        for channel in &mut self.channels {
+            channel.apply(samples);
+        }
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        for channel in &mut self.channels {
+            channel.apply_complex(samples);
+        }
+    }
+}
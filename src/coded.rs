@@ -0,0 +1,549 @@
+//! This module provides a coded OFDM modulator and demodulator, built from one [OFDMConfig] and one [CodingConfig].
+//!
+//! They compose the scrambler, the FEC, the interleavers and the OFDM modem of a [coded frame](crate::frame),
+//! so that an application only needs to share the two configurations between both ends of a link.
+
+use alloc::vec::Vec;
+
+use num_complex::Complex32;
+
+use crate::{
+    channel::Channel,
+    diagnostics::{TransformCheck, verify_transforms},
+    error::ModemError,
+    fft::plan_real_inverse,
+    frame::{
+        CodedFrameDecoder, CodedFrameEncoder, CodingConfig, FrameDecoder, FrameEncoder, Repetition,
+    },
+    metrics::{DemodulationReport, EvmResult, FecStats, count_bit_errors, evm, papr},
+    ofdm::{OFDMConfig, StageTimer, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::{QAMModem, QAMOrder},
+    tap::StageSink,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The number of bytes of the payload of a [self_test], enough for the symbols of a few interleaver blocks.
+pub const SELF_TEST_PAYLOAD_LENGTH: usize = 256;
+
+/// Scrambles, codes, interleaves and modulates payloads into frames of samples.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     differential_time: true,
+///     ..Default::default()
+/// };
+/// let coding = CodingConfig {
+///     reed_solomon: true,
+///     ..Default::default()
+/// };
+///
+/// // the receiver gets the coding configuration from the transmitter
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::from_bytes(&coding.to_bytes()).unwrap());
+///
+/// let payload = "Coded OFDM over a multipath channel".as_bytes();
+/// let samples = modulator.encode_frame(payload);
+/// assert_eq!(samples.len(), modulator.get_frame_length(payload.len()));
+///
+/// // static multipath shorter than the cyclic prefix, and noise
+/// let mut channel = ChannelChain::new()
+///     .with(MultipathChannel::new(&[
+///         (0, Complex32::new(-0.7, 0.0)),
+///         (1, Complex32::new(0.4, 0.0)),
+///         (2, Complex32::new(0.2, 0.0)),
+///     ]))
+///     .with(AwgnChannel::new(25.0, 1));
+/// let mut received = samples.clone();
+/// channel.apply(&mut received);
+///
+/// assert_eq!(demodulator.decode_frame(&received).unwrap(), payload);
+/// ```
+pub struct CodedOFDMModulator {
+    encoder: CodedFrameEncoder,
+}
+
+impl CodedOFDMModulator {
+    /// Creates a new coded modulator.
+    ///
+    /// # Panics
+    /// If the coding configuration is invalid, see [CodedFrameEncoder::new].
+    pub fn new(ofdm_config: OFDMConfig, coding_config: CodingConfig) -> Self {
+        let modulator = OFDMModulator::new((&ofdm_config).into());
+        CodedOFDMModulator {
+            encoder: CodedFrameEncoder::new(FrameEncoder::new(modulator), coding_config),
+        }
+    }
+
+    /// Encodes the payload into a frame of samples.
+    ///
+    /// The returned buffer has a length of `get_frame_length(payload.len())`.
+    ///
+    /// # Panics
+    /// If the payload is longer than 65535 bytes.
+    pub fn encode_frame(&self, payload: &[u8]) -> Vec<f32> {
+        self.encoder.encode(payload)
+    }
+
+    /// Returns the number of samples in a frame carrying `payload_length` bytes.
+    pub fn get_frame_length(&self, payload_length: usize) -> usize {
+        self.encoder.get_frame_length(payload_length)
+    }
+
+    /// Switches the QAM order of the following frames, see [OFDMModulator::set_qam_order].
+    ///
+    /// # Errors
+    /// See [OFDMModulator::set_qam_order].
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.encoder.set_qam_order(qam_order)
+    }
+}
+
+/// Demodulates, deinterleaves, decodes and descrambles frames of samples back into payloads.
+///
+/// See [CodedOFDMModulator] for an example.
+pub struct CodedOFDMDemodulator {
+    decoder: CodedFrameDecoder,
+}
+
+impl CodedOFDMDemodulator {
+    /// Creates a new coded demodulator.
+    pub fn new(ofdm_config: OFDMConfig, coding_config: CodingConfig) -> Self {
+        let demodulator = OFDMDemodulator::new((&ofdm_config).into());
+        CodedOFDMDemodulator {
+            decoder: CodedFrameDecoder::new(FrameDecoder::new(demodulator), coding_config),
+        }
+    }
+
+    /// Switches the QAM order of the following frames, see [OFDMDemodulator::set_qam_order].
+    ///
+    /// # Errors
+    /// See [OFDMDemodulator::set_qam_order].
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.decoder.set_qam_order(qam_order)
+    }
+
+    /// Decodes a frame of samples into the payload.
+    ///
+    /// # Errors
+    /// See [CodedFrameDecoder::decode].
+    pub fn decode_frame(&self, samples: &[f32]) -> Result<Vec<u8>, ModemError> {
+        self.decoder.decode(samples)
+    }
+
+    /// Decodes a frame of samples into the payload, and returns it with the [report](DemodulationReport) of its quality.
+    ///
+    /// See [CodedFrameDecoder::decode_with_report].
+    pub fn decode_frame_with_report(
+        &self,
+        samples: &[f32],
+    ) -> (Result<Vec<u8>, ModemError>, DemodulationReport) {
+        self.decoder.decode_with_report(samples)
+    }
+
+    /// Demodulates a frame of samples into the LLRs of every bit of its whole symbols.
+    ///
+    /// See [CodedFrameDecoder::demodulate_llrs].
+    pub fn demodulate_llrs(&self, samples: &[f32]) -> Vec<f32> {
+        self.decoder.demodulate_llrs(samples)
+    }
+
+    /// Decodes the payload from the LLRs of every bit of a frame, or their sum over several receptions of it.
+    ///
+    /// See [CodedFrameDecoder::decode_llrs].
+    pub fn decode_llrs(&self, llrs: &[f32]) -> Result<Vec<u8>, ModemError> {
+        self.decoder.decode_llrs(llrs)
+    }
+
+    /// Decodes the header from the LLRs of every bit of a frame, and returns the [Repetition] it carries.
+    ///
+    /// See [CodedFrameDecoder::decode_repetition].
+    pub fn decode_repetition(&self, llrs: &[f32]) -> Result<Option<Repetition>, ModemError> {
+        self.decoder.decode_repetition(llrs)
+    }
+
+    pub(crate) fn get_header_llrs(&self) -> usize {
+        self.decoder.get_header_llrs()
+    }
+
+    /// Decodes a frame of samples into the payload, and hands every stage of it to the tap.
+    ///
+    /// See [CodedFrameDecoder::decode_with_tap].
+    pub fn decode_frame_with_tap(
+        &self,
+        samples: &[f32],
+        tap: &mut dyn StageSink,
+    ) -> Result<Vec<u8>, ModemError> {
+        self.decoder.decode_with_tap(samples, tap)
+    }
+
+    /// Decodes a frame of samples into the payload like [decode_frame](Self::decode_frame), clobbering the samples.
+    ///
+    /// Returns the payload with the number of symbols of the frame, and the timer times every stage.
+    /// The data subcarrier points of the frame are left in `points`, and the corrections of the FEC in `stats`.
+    pub(crate) fn decode_frame_in_place(
+        &self,
+        samples: &mut [f32],
+        points: &mut Vec<Complex32>,
+        stats: &mut FecStats,
+        timer: &mut impl StageTimer,
+    ) -> Result<(Vec<u8>, usize), ModemError> {
+        self.decoder.decode_in_place(samples, points, stats, timer)
+    }
+
+    /// Returns the data subcarrier points of every whole symbol of a frame of samples, one after the other.
+    ///
+    /// See [CodedFrameDecoder::demodulate_points].
+    pub(crate) fn demodulate_points(&self, samples: &[f32]) -> Vec<Complex32> {
+        self.decoder.demodulate_points(samples)
+    }
+
+    /// Returns what the inner code adds to the LLRs of every bit of a frame, `None` if its header does not decode.
+    ///
+    /// See [CodedFrameDecoder::get_extrinsic_llrs].
+    pub(crate) fn get_extrinsic_llrs(&self, llrs: &[f32]) -> Option<Vec<f32>> {
+        self.decoder.get_extrinsic_llrs(llrs)
+    }
+
+    /// Returns the QAM modem the points are decided with.
+    pub(crate) fn qam_modem(&self) -> &QAMModem {
+        self.decoder.get_frame_decoder().qam_modem()
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns the number of data subcarriers of a symbol, the points of every symbol.
+    pub(crate) fn get_num_data_subcarriers(&self) -> usize {
+        self.decoder.get_frame_decoder().get_num_data_subcarriers()
+    }
+
+    /// Returns the indices of the data subcarriers of a symbol in the order of its points,
+    /// ascending unless [permuted](crate::ofdm::SubcarrierPermutation).
+    pub fn get_data_subcarriers(&self) -> &[u32] {
+        self.decoder.get_frame_decoder().get_data_subcarriers()
+    }
+
+    /// Returns the length of the FFT of a symbol, with the oversampling, whose bins are the subcarrier spacing apart.
+    pub(crate) fn get_fft_length(&self) -> usize {
+        self.decoder
+            .get_frame_decoder()
+            .get_demodulator()
+            .get_fft()
+            .fft_length()
+    }
+
+    /// Returns the indices of the pilot subcarriers of a symbol, the bins of its channel estimates.
+    pub(crate) fn get_pilot_subcarriers(&self) -> &[u32] {
+        self.decoder
+            .get_frame_decoder()
+            .get_demodulator()
+            .pilot_subcarrier_indices()
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns the samples of a frame the decoder reads, without the samples after the last symbol and the roll-off.
+    pub(crate) fn whole_symbols<'a>(&self, samples: &'a [f32]) -> &'a [f32] {
+        self.decoder.whole_symbols(samples)
+    }
+
+    /// Returns the number of samples of one OFDM symbol.
+    pub fn get_symbol_length(&self) -> usize {
+        self.decoder.get_symbol_length()
+    }
+
+    /// Returns the number of samples of the symbols carrying the header of a frame,
+    /// see [CodedFrameDecoder::get_header_length].
+    pub fn get_header_length(&self) -> usize {
+        self.decoder.get_header_length()
+    }
+
+    /// Returns the number of samples of the longest frame the demodulator can receive,
+    /// see [CodedFrameDecoder::get_max_frame_length].
+    pub fn get_max_frame_length(&self) -> usize {
+        self.decoder.get_max_frame_length()
+    }
+}
+
+/// The result of a [self_test] of a modulator and a demodulator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfTestReport {
+    /// Whether the frame decoded into the payload.
+    pub passed: bool,
+    /// The error of the demodulator, if the frame did not decode.
+    pub error: Option<ModemError>,
+    /// The number of wrong bytes of the payload, all of them if the frame did not decode.
+    pub byte_errors: usize,
+    /// The EVM of the data subcarrier points of the frame against their decisions,
+    /// `None` if the points were not finite.
+    pub evm: Option<EvmResult>,
+    /// The PAPR of the frame as it was sent, before the channel, in dB.
+    pub papr_db: f32,
+    /// The accuracy of the FFT of the demodulator and the inverse FFT of the modulator,
+    /// see [verify_transforms].
+    pub transform: TransformCheck,
+}
+
+/// Sends a frame of a pseudorandom payload from the modulator to the demodulator, through the channel if one is given,
+/// and reports whether it arrived, with the EVM of its points and the PAPR of its samples,
+/// and whether the FFTs of both ends are accurate.
+///
+/// The frame passes every stage the two configurations have, the codes, the interleavers, the pilots, the windowing
+/// and the filters, so a pair of configurations that does not fit together fails here rather than on the air.
+/// An FFT backend that is broken on the target fails the test even if the frame decodes, see
+/// [verify_transforms]: the FFT of the demodulator is checked against the inverse FFT of the modulator,
+/// or a planned one if their lengths differ. An application calls it once at startup and logs the report. The payload has [SELF_TEST_PAYLOAD_LENGTH] bytes
+/// and is the same every time, as is the report of a configuration without a channel.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::AwgnChannel;
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator, self_test};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 8,
+///     roll_off: 4,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+///
+/// let report = self_test(&modulator, &demodulator, None);
+/// assert!(report.passed && report.byte_errors == 0 && report.transform.passed);
+/// assert!(report.evm.unwrap().rms_db < -60.0 && report.papr_db > 3.0);
+///
+/// // and over a noisy channel
+/// let report = self_test(&modulator, &demodulator, Some(&mut AwgnChannel::new(20.0, 1)));
+/// assert!(report.passed);
+/// assert!((report.evm.unwrap().rms_db + 20.0).abs() < 2.0);
+///
+/// // a receiver expecting differential symbols does not decode the frame
+/// let mismatched = CodedOFDMDemodulator::new(OFDMConfig { differential_time: true, ..ofdm }, CodingConfig::default());
+/// let report = self_test(&modulator, &mismatched, None);
+/// assert!(!report.passed && report.error.is_some());
+/// ```
+pub fn self_test(
+    modulator: &CodedOFDMModulator,
+    demodulator: &CodedOFDMDemodulator,
+    channel: Option<&mut dyn Channel>,
+) -> SelfTestReport {
+    let payload: Vec<u8> = (0..SELF_TEST_PAYLOAD_LENGTH as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let mut samples = modulator.encode_frame(&payload);
+    let papr_db = papr(&samples);
+    if let Some(channel) = channel {
+        channel.apply(&mut samples);
+    }
+
+    let mut points = Vec::new();
+    let result = demodulator.decode_frame_in_place(
+        &mut samples,
+        &mut points,
+        &mut FecStats::default(),
+        &mut (),
+    );
+    let evm = (!points.is_empty() && points.iter().all(|point| point.is_finite()))
+        .then(|| evm(&points, &demodulator.qam_modem().nearest_points(&points)));
+
+    let (error, byte_errors) = match result {
+        Ok((decoded, _)) if decoded.len() == payload.len() => {
+            (None, count_bit_errors(&payload, &decoded).byte_errors)
+        }
+        // a frame which decodes with another length is as wrong as one which does not decode
+        Ok(_) => (None, payload.len()),
+        Err(error) => (Some(error), payload.len()),
+    };
+
+    let forward = demodulator
+        .decoder
+        .get_frame_decoder()
+        .get_demodulator()
+        .get_fft();
+    let inverse = modulator
+        .encoder
+        .get_frame_encoder()
+        .get_modulator()
+        .get_fft();
+    let transform = if inverse.fft_length() == forward.fft_length() {
+        verify_transforms(forward.as_ref(), inverse.as_ref())
+    } else {
+        verify_transforms(
+            forward.as_ref(),
+            plan_real_inverse(forward.fft_length()).as_ref(),
+        )
+    };
+    SelfTestReport {
+        passed: error.is_none() && byte_errors == 0 && transform.passed,
+        error,
+        byte_errors,
+        evm,
+        papr_db,
+        transform,
+    }
+}
+
+/// The response of a channel over the band of a modem, measured by [measure_tilt].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelTilt {
+    /// The gain of the channel at every pilot subcarrier in dB, relative to their mean,
+    /// after the frequency of its bin as a fraction of the sample rate, ascending.
+    /// It is the response [FirFilter::compensating](crate::dsp::FirFilter::compensating) compensates.
+    pub response: Vec<(f32, f32)>,
+    /// The slope of the line through the gains over the octaves of their frequencies, in dB per octave,
+    /// negative for a channel which rolls off.
+    pub db_per_octave: f32,
+}
+
+/// Sends a calibration frame through the channel and measures how its gain tilts over the band of the modem.
+///
+/// The frame carries the payload of a [self_test], and the gains come from the
+/// [channel estimates](StageSink::channel_estimate) at the pilot subcarriers, their power averaged over its symbols,
+/// whether the frame decodes or not. The frame must start at the first sample after the channel,
+/// within the cyclic prefix, and the gains include the filters of both ends.
+/// A channel which rolls off by 6 dB per octave over the whole band measures about -6 dB per octave,
+/// a flat one about 0.
+///
+/// # Panics
+/// If the demodulator has fewer than two pilot subcarriers.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{ChannelChain, IrChannel};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator, measure_tilt};
+/// use software_modem::dsp::{Deemphasis, EmphasisConfig, FirFilter};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 16,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+///
+/// // a loopback is flat
+/// let tilt = measure_tilt(&modulator, &demodulator, &mut ChannelChain::new());
+/// assert!(tilt.db_per_octave.abs() < 0.1, "{tilt:?}");
+///
+/// // a speaker and a microphone rolling off from 0.05 of the sample rate, within the cyclic prefix
+/// let mut impulse = vec![0.0; 17];
+/// impulse[0] = 1.0;
+/// let response = Deemphasis::new(EmphasisConfig { corner: 0.05, boost_db: 12.0 }).process(&impulse);
+/// let tilt = measure_tilt(&modulator, &demodulator, &mut IrChannel::from_samples(&response));
+/// assert!(tilt.db_per_octave < -2.0, "{tilt:?}");
+///
+/// // a transmit filter against it
+/// let filter = FirFilter::compensating(&tilt.response, 20.0, 31);
+/// assert!(filter.gain_db(0.4) > filter.gain_db(0.05) + 6.0);
+/// ```
+pub fn measure_tilt(
+    modulator: &CodedOFDMModulator,
+    demodulator: &CodedOFDMDemodulator,
+    channel: &mut dyn Channel,
+) -> ChannelTilt {
+    let pilots = demodulator.get_pilot_subcarriers();
+    if pilots.len() < 2 {
+        panic!(
+            "Calibration needs at least two pilot subcarriers, but got {}",
+            pilots.len()
+        );
+    }
+    let payload: Vec<u8> = (0..SELF_TEST_PAYLOAD_LENGTH as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let mut samples = modulator.encode_frame(&payload);
+    channel.apply(&mut samples);
+
+    let mut power = PilotPower::default();
+    let _ = demodulator.decode_frame_with_tap(&samples, &mut power);
+    let gains: Vec<f32> = power
+        .sums
+        .iter()
+        .map(|sum| {
+            10.0 * (sum / power.symbols.max(1) as f32)
+                .max(f32::MIN_POSITIVE)
+                .log10()
+        })
+        .collect();
+    let fft_length = demodulator.get_fft_length() as f32;
+    ChannelTilt::from_gains(
+        pilots
+            .iter()
+            .zip(gains)
+            .map(|(&bin, gain)| (bin as f32 / fft_length, gain))
+            .collect(),
+    )
+}
+
+impl ChannelTilt {
+    /// Returns the tilt of the gains in dB at ascending frequencies, taking their mean out of them.
+    pub(crate) fn from_gains(mut response: Vec<(f32, f32)>) -> Self {
+        let mean =
+            response.iter().map(|(_, gain)| gain).sum::<f32>() / response.len().max(1) as f32;
+        for (_, gain) in &mut response {
+            *gain -= mean;
+        }
+
+        // the least squares line of the gains over the octaves
+        let octaves: Vec<f32> = response
+            .iter()
+            .map(|(frequency, _)| frequency.log2())
+            .collect();
+        let mean_octave = octaves.iter().sum::<f32>() / octaves.len().max(1) as f32;
+        let (covariance, variance) = octaves.iter().zip(&response).fold(
+            (0.0, 0.0),
+            |(covariance, variance), (octave, (_, gain))| {
+                let deviation = octave - mean_octave;
+                (
+                    covariance + deviation * gain,
+                    variance + deviation * deviation,
+                )
+            },
+        );
+        ChannelTilt {
+            db_per_octave: if variance > 0.0 {
+                covariance / variance
+            } else {
+                0.0
+            },
+            response,
+        }
+    }
+}
+
+/// Sums the power of the channel estimates of every pilot over the symbols of a frame, for [measure_tilt].
+#[derive(Default)]
+struct PilotPower {
+    sums: Vec<f32>,
+    symbols: usize,
+}
+
+impl StageSink for PilotPower {
+    fn start_frame(&mut self) {}
+    fn raw_samples(&mut self, _: usize, _: &[f32]) {}
+    fn corrected_samples(&mut self, _: usize, _: &[f32]) {}
+    fn fft_bins(&mut self, _: usize, _: &[Complex32]) {}
+    fn channel_estimate(&mut self, _: usize, pilots: &[Complex32]) {
+        self.sums.resize(pilots.len(), 0.0);
+        for (sum, pilot) in self.sums.iter_mut().zip(pilots) {
+            *sum += pilot.norm_sqr();
+        }
+        self.symbols += 1;
+    }
+    fn equalized_points(&mut self, _: usize, _: &[Complex32]) {}
+    fn llrs(&mut self, _: usize, _: &[f32]) {}
+    fn fec_bits(&mut self, _: &[u8]) {}
+}
@@ -0,0 +1,389 @@
+//! This module provides a sliding correlator, which finds a known template, like the first symbol of a frame,
+//! in a stream of samples.
+//!
+//! The [Correlator] computes the dot product of the template with the samples at every offset, the most expensive part
+//! of an idle receiver looking for a preamble. Short templates are correlated directly, 8 offsets at a time with AVX
+//! and FMA on x86-64, 4 with SSE2 where the CPU has no AVX and with NEON on AArch64, and with the scalar kernel
+//! [correlate_scalar] on other CPUs. Long templates are correlated in the frequency domain by overlap-save, whose cost
+//! grows with the logarithm of the template length rather than with the length. [CorrelationMethod::auto] picks
+//! the faster of both, and either gives the correlation of the scalar kernel up to the rounding of the floats.
+//!
+//! # Example
+//! ```
+//! use software_modem::channel::{AwgnChannel, Channel};
+//! use software_modem::coded::CodedOFDMModulator;
+//! use software_modem::correlator::Correlator;
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     ..Default::default()
+//! };
+//! let frame = CodedOFDMModulator::new(ofdm, CodingConfig::default()).encode_frame(b"hello");
+//!
+//! // the first symbol of the frame, after noise
+//! let correlator = Correlator::new(&frame[..144]);
+//! let mut samples = vec![0.0; 1234];
+//! samples.extend(&frame);
+//! AwgnChannel::with_reference_power(10.0, 1.0, 1).apply(&mut samples);
+//!
+//! let (offset, coefficient) = correlator.find(&samples, 0.5).unwrap();
+//! assert_eq!(offset, 1234);
+//! assert!(coefficient > 0.9);
+//! assert_eq!(correlator.correlate(&samples).len(), samples.len() - 143);
+//! ```
+
+use alloc::{sync::Arc, vec, vec::Vec};
+
+use num_complex::Complex32;
+
+use crate::fft::{RealForwardFft, RealInverseFft, plan_real_forward, plan_real_inverse};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Template length from which [CorrelationMethod::auto] correlates by overlap-save.
+pub const OVERLAP_SAVE_MIN_LENGTH: usize = 256;
+
+/// How a [Correlator] computes the correlation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CorrelationMethod {
+    /// The dot product at every offset, with the SIMD instructions of the CPU.
+    Direct,
+    /// Overlap-save, the product of the spectra of blocks of the samples and of the template.
+    OverlapSave,
+}
+
+impl CorrelationMethod {
+    /// Returns the faster method for a template of the length, overlap-save from [OVERLAP_SAVE_MIN_LENGTH] samples.
+    pub fn auto(template_length: usize) -> Self {
+        if template_length >= OVERLAP_SAVE_MIN_LENGTH {
+            CorrelationMethod::OverlapSave
+        } else {
+            CorrelationMethod::Direct
+        }
+    }
+}
+
+/// The FFTs of the blocks of overlap-save, with the spectrum of the template.
+struct OverlapSave {
+    forward: Arc<dyn RealForwardFft<f32>>,
+    inverse: Arc<dyn RealInverseFft<f32>>,
+    /// The conjugated spectrum of the template padded to the FFT length, divided by the FFT length.
+    spectrum: Vec<Complex32>,
+}
+
+impl OverlapSave {
+    fn new(template: &[f32]) -> Self {
+        // a block of four templates keeps three quarters of its offsets
+        let fft_length = (4 * template.len()).next_power_of_two();
+        let forward = plan_real_forward(fft_length);
+        let inverse = plan_real_inverse(fft_length);
+        let mut padded = template.to_vec();
+        padded.resize(fft_length, 0.0);
+        let mut spectrum = vec![Complex32::default(); fft_length / 2 + 1];
+        forward.process(&mut padded, &mut spectrum);
+        let scale = 1.0 / fft_length as f32;
+        spectrum
+            .iter_mut()
+            .for_each(|bin| *bin = bin.conj() * scale);
+        OverlapSave {
+            forward,
+            inverse,
+            spectrum,
+        }
+    }
+
+    fn correlate(&self, template_length: usize, samples: &[f32], output: &mut [f32]) {
+        let fft_length = self.forward.fft_length();
+        let step = fft_length - template_length + 1;
+        let mut block = vec![0.0; fft_length];
+        let mut bins = vec![Complex32::default(); fft_length / 2 + 1];
+        let mut time = vec![0.0; fft_length];
+        let scratch_length = self
+            .forward
+            .get_scratch_len()
+            .max(self.inverse.get_scratch_len());
+        let mut scratch = vec![Complex32::default(); scratch_length];
+
+        for (start, output) in (0..).step_by(step).zip(output.chunks_mut(step)) {
+            let end = (start + fft_length).min(samples.len());
+            block[..end - start].copy_from_slice(&samples[start..end]);
+            block[end - start..].fill(0.0);
+            self.forward
+                .process_with_scratch(&mut block, &mut bins, &mut scratch);
+            bins.iter_mut()
+                .zip(&self.spectrum)
+                .for_each(|(bin, template)| *bin *= template);
+            self.inverse
+                .process_with_scratch(&mut bins, &mut time, &mut scratch);
+            // the offsets whose template does not wrap around the block
+            output.copy_from_slice(&time[..output.len()]);
+        }
+    }
+}
+
+/// Correlates a template with the samples at every offset, see the [module](self) documentation.
+pub struct Correlator {
+    template: Vec<f32>,
+    energy: f64,
+    method: CorrelationMethod,
+    overlap_save: Option<OverlapSave>,
+}
+
+impl Correlator {
+    /// Creates a correlator of the template, with the method [CorrelationMethod::auto] picks for its length.
+    ///
+    /// # Panics
+    /// If the template is empty.
+    pub fn new(template: &[f32]) -> Self {
+        Self::with_method(template, CorrelationMethod::auto(template.len()))
+    }
+
+    /// Creates a correlator of the template, which correlates with the method.
+    ///
+    /// # Panics
+    /// If the template is empty.
+    pub fn with_method(template: &[f32], method: CorrelationMethod) -> Self {
+        assert!(
+            !template.is_empty(),
+            "Template must have at least 1 sample, but got 0"
+        );
+        Correlator {
+            template: template.to_vec(),
+            energy: template.iter().map(|&x| f64::from(x).powi(2)).sum(),
+            method,
+            overlap_save: (method == CorrelationMethod::OverlapSave)
+                .then(|| OverlapSave::new(template)),
+        }
+    }
+
+    /// Returns the template.
+    pub fn get_template(&self) -> &[f32] {
+        &self.template
+    }
+
+    /// Returns the method of the correlation.
+    pub fn get_method(&self) -> CorrelationMethod {
+        self.method
+    }
+
+    /// Returns the number of offsets of the template within `num_samples` samples, the length of their correlation.
+    pub fn get_output_length(&self, num_samples: usize) -> usize {
+        (num_samples + 1).saturating_sub(self.template.len())
+    }
+
+    /// Returns the correlation of the template with the samples, `sum_k template[k] * samples[i + k]`
+    /// at every offset `i` the whole template fits the samples at.
+    pub fn correlate(&self, samples: &[f32]) -> Vec<f32> {
+        let mut output = vec![0.0; self.get_output_length(samples.len())];
+        self.correlate_into(samples, &mut output);
+        output
+    }
+
+    /// Writes the correlation of the template with the samples into the output, see [correlate](Self::correlate).
+    ///
+    /// # Panics
+    /// If the length of the output is not the one of [get_output_length](Self::get_output_length).
+    pub fn correlate_into(&self, samples: &[f32], output: &mut [f32]) {
+        check_output_length(&self.template, samples, output);
+        match &self.overlap_save {
+            Some(overlap_save) => overlap_save.correlate(self.template.len(), samples, output),
+            None => correlate_direct(&self.template, samples, output),
+        }
+    }
+
+    /// Returns the offset of the first peak of the normalized correlation at or above the threshold, with its value.
+    ///
+    /// The normalized correlation is the correlation divided by the norms of the template and of the samples under it,
+    /// 1 where the samples are the template scaled by a positive gain. The peak is the highest value within
+    /// a template length from the first one at or above the threshold.
+    pub fn find(&self, samples: &[f32], threshold: f32) -> Option<(usize, f32)> {
+        let correlation = self.correlate(samples);
+        let length = self.template.len();
+        let mut energy = Vec::with_capacity(samples.len() + 1);
+        energy.push(0.0f64);
+        for &x in samples {
+            energy.push(energy.last().unwrap() + f64::from(x).powi(2));
+        }
+        let coefficient = |offset: usize| {
+            let norm = (self.energy * (energy[offset + length] - energy[offset])).sqrt();
+            if norm > 0.0 {
+                (f64::from(correlation[offset]) / norm) as f32
+            } else {
+                0.0
+            }
+        };
+
+        let first = (0..correlation.len()).find(|&offset| coefficient(offset) >= threshold)?;
+        (first..correlation.len().min(first + length))
+            .map(|offset| (offset, coefficient(offset)))
+            .fold(None, |best: Option<(usize, f32)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+impl core::fmt::Debug for Correlator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Correlator")
+            .field("template_length", &self.template.len())
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+fn check_output_length(template: &[f32], samples: &[f32], output: &[f32]) {
+    let expected = (samples.len() + 1).saturating_sub(template.len());
+    if output.len() != expected {
+        panic!(
+            "Output length must be {}, but got {}",
+            expected,
+            output.len()
+        );
+    }
+}
+
+/// Writes the correlation of the template with the samples into the output, one dot product after another,
+/// the reference of the vectorized kernels.
+///
+/// # Panics
+/// If the output does not have one value for every offset the whole template fits the samples at.
+pub fn correlate_scalar(template: &[f32], samples: &[f32], output: &mut [f32]) {
+    check_output_length(template, samples, output);
+    for (offset, output) in output.iter_mut().enumerate() {
+        *output = template
+            .iter()
+            .zip(&samples[offset..])
+            .fold(0.0, |sum, (&t, &x)| sum + t * x);
+    }
+}
+
+/// Correlates like [correlate_scalar], with the SIMD instructions of the CPU.
+///
+/// AVX and FMA are detected at runtime, or without the `std` feature used if the target enables them,
+/// SSE2 is part of every x86-64 CPU and NEON of every AArch64 one.
+fn correlate_direct(template: &[f32], samples: &[f32], output: &mut [f32]) {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    let avx =
+        std::arch::is_x86_feature_detected!("avx") && std::arch::is_x86_feature_detected!("fma");
+    #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+    let avx = cfg!(all(target_feature = "avx", target_feature = "fma"));
+    #[cfg(target_arch = "x86_64")]
+    let done = if avx {
+        // SAFETY: the CPU supports AVX and FMA
+        unsafe { x86_64::correlate_avx(template, samples, output) }
+    } else {
+        // SAFETY: SSE2 is part of x86-64
+        unsafe { x86_64::correlate_sse2(template, samples, output) }
+    };
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: NEON is part of AArch64
+    let done = unsafe { aarch64::correlate_neon(template, samples, output) };
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let done = 0;
+
+    // the offsets after the last whole block
+    correlate_scalar(template, &samples[done..], &mut output[done..]);
+}
+
+/// Number of blocks of lanes correlated at once, whose accumulators hide the latency of the additions.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const BLOCKS: usize = 4;
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use core::arch::x86_64::*;
+
+    use super::BLOCKS;
+
+    /// Correlates blocks of 8 offsets with AVX and FMA, and returns the number of offsets written.
+    #[target_feature(enable = "avx,fma")]
+    pub(super) fn correlate_avx(template: &[f32], samples: &[f32], output: &mut [f32]) -> usize {
+        let mut done = 0;
+        for blocks in [BLOCKS, 1] {
+            while done + 8 * blocks <= output.len() {
+                let mut sums = [_mm256_setzero_ps(); BLOCKS];
+                for (k, &t) in template.iter().enumerate() {
+                    let t = _mm256_set1_ps(t);
+                    for (block, sum) in sums[..blocks].iter_mut().enumerate() {
+                        // SAFETY: the last offset of the block is an offset of the output,
+                        // so its template ends within the samples
+                        let x =
+                            unsafe { _mm256_loadu_ps(samples.as_ptr().add(done + 8 * block + k)) };
+                        *sum = _mm256_fmadd_ps(t, x, *sum);
+                    }
+                }
+                for (block, sum) in sums[..blocks].iter().enumerate() {
+                    // SAFETY: the block is within the output
+                    unsafe { _mm256_storeu_ps(output.as_mut_ptr().add(done + 8 * block), *sum) };
+                }
+                done += 8 * blocks;
+            }
+        }
+        done
+    }
+
+    /// Correlates blocks of 4 offsets with SSE2, which is part of every x86-64 CPU,
+    /// and returns the number of offsets written.
+    #[target_feature(enable = "sse2")]
+    pub(super) fn correlate_sse2(template: &[f32], samples: &[f32], output: &mut [f32]) -> usize {
+        let mut done = 0;
+        for blocks in [BLOCKS, 1] {
+            while done + 4 * blocks <= output.len() {
+                let mut sums = [_mm_setzero_ps(); BLOCKS];
+                for (k, &t) in template.iter().enumerate() {
+                    let t = _mm_set1_ps(t);
+                    for (block, sum) in sums[..blocks].iter_mut().enumerate() {
+                        // SAFETY: see correlate_avx
+                        let x = unsafe { _mm_loadu_ps(samples.as_ptr().add(done + 4 * block + k)) };
+                        *sum = _mm_add_ps(*sum, _mm_mul_ps(t, x));
+                    }
+                }
+                for (block, sum) in sums[..blocks].iter().enumerate() {
+                    // SAFETY: the block is within the output
+                    unsafe { _mm_storeu_ps(output.as_mut_ptr().add(done + 4 * block), *sum) };
+                }
+                done += 4 * blocks;
+            }
+        }
+        done
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use core::arch::aarch64::*;
+
+    use super::BLOCKS;
+
+    /// Correlates blocks of 4 offsets with NEON, which is part of every AArch64 CPU,
+    /// and returns the number of offsets written.
+    #[target_feature(enable = "neon")]
+    pub(super) fn correlate_neon(template: &[f32], samples: &[f32], output: &mut [f32]) -> usize {
+        let mut done = 0;
+        for blocks in [BLOCKS, 1] {
+            while done + 4 * blocks <= output.len() {
+                let mut sums = [vdupq_n_f32(0.0); BLOCKS];
+                for (k, &t) in template.iter().enumerate() {
+                    let t = vdupq_n_f32(t);
+                    for (block, sum) in sums[..blocks].iter_mut().enumerate() {
+                        // SAFETY: the last offset of the block is an offset of the output,
+                        // so its template ends within the samples
+                        let x = unsafe { vld1q_f32(samples.as_ptr().add(done + 4 * block + k)) };
+                        *sum = vfmaq_f32(*sum, t, x);
+                    }
+                }
+                for (block, sum) in sums[..blocks].iter().enumerate() {
+                    // SAFETY: the block is within the output
+                    unsafe { vst1q_f32(output.as_mut_ptr().add(done + 4 * block), *sum) };
+                }
+                done += 4 * blocks;
+            }
+        }
+        done
+    }
+}
@@ -0,0 +1,66 @@
+//! This module provides the cyclic redundancy checks (CRC) used to detect corrupted frames.
+
+/// Computes the CRC-8 of the ATM header error control (ITU-T I.432.1),
+/// the polynomial `x^8 + x^2 + x + 1` (0x07) with a zero initial value and the result XORed with 0x55.
+///
+/// Without the XOR, all-zero bytes would have a zero CRC, so silence would check as a header of zeros.
+///
+/// # Example
+/// ```
+/// use software_modem::crc::crc8;
+///
+/// assert_eq!(crc8(b"123456789"), 0xa1);
+/// assert_ne!(crc8(&[0; 3]), 0);
+/// ```
+pub fn crc8(data: &[u8]) -> u8 {
+    0x55 ^ data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Computes the CRC-16 of X.25, the frame check sequence of HDLC and AX.25
+/// (the polynomial `x^16 + x^12 + x^5 + 1`, reflected, inverted at the start and the end).
+///
+/// # Example
+/// ```
+/// use software_modem::crc::crc16_x25;
+///
+/// assert_eq!(crc16_x25(b"123456789"), 0x906e);
+/// ```
+pub fn crc16_x25(data: &[u8]) -> u16 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u16, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Computes the CRC-32 used by Ethernet and zip (IEEE 802.3, reflected).
+///
+/// # Example
+/// ```
+/// use software_modem::crc::crc32;
+///
+/// assert_eq!(crc32(b"123456789"), 0xcbf43926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
@@ -0,0 +1,205 @@
+//! This module provides checks of the numerical building blocks of the modem, to run on a target before trusting it.
+//!
+//! An FFT backend that is subtly broken on one target, by a miscompiled SIMD path or a swapped implementation,
+//! still decodes noise into something, and shows up as a link that is worse than it should be.
+//! [verify_transform_accuracy] runs known signals through the FFTs the modems plan by default, and [verify_transforms]
+//! through any pair of them, like the ones in a modem's configuration. The [self_test](crate::coded::self_test)
+//! of the coded modem checks the FFTs of its modulator and demodulator the same way.
+
+use alloc::{vec, vec::Vec};
+use core::f64::consts::TAU;
+
+use num_complex::Complex32;
+
+use crate::{
+    fft::{RealForwardFft, RealInverseFft, plan_real_forward, plan_real_inverse},
+    rng::SimulationRng,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Largest error of a [TransformCheck] that passes, relative to the scale of the signal.
+///
+/// A correct `f32` FFT of up to 65536 samples stays below `1e-5`, while a wrong twiddle factor, bin or scale
+/// is far above it.
+pub const TRANSFORM_TOLERANCE: f32 = 1e-4;
+
+/// Seed of the random vector of a [TransformCheck], the same every time.
+const RANDOM_SEED: u64 = 0x5eed_f00d;
+
+/// The accuracy of a forward and inverse FFT pair, see [verify_transforms].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TransformCheck {
+    /// The number of samples transformed.
+    pub fft_size: usize,
+    /// Largest difference between a signal and its round trip through both transforms divided by the FFT size,
+    /// relative to the peak of the signal.
+    pub max_reconstruction_error: f32,
+    /// Largest difference between the energy of a signal and of its spectrum, relative to the energy of the signal.
+    pub parseval_mismatch: f32,
+    /// Largest difference between the bins of the impulse and the sinusoid and their known values,
+    /// relative to the largest of them.
+    pub max_spectrum_error: f32,
+    /// Whether every error is within [TRANSFORM_TOLERANCE], none of them NaN.
+    pub passed: bool,
+}
+
+/// Checks the FFTs the modems plan by default for the FFT size, see [verify_transforms].
+///
+/// # Panics
+/// If the FFT size is less than 2.
+///
+/// # Example
+/// ```
+/// use software_modem::diagnostics::{TRANSFORM_TOLERANCE, verify_transform_accuracy};
+///
+/// for fft_size in [64, 100, 1024, 4096] {
+///     let check = verify_transform_accuracy(fft_size);
+///     assert!(check.passed, "{check:?}");
+///     assert!(check.max_reconstruction_error < TRANSFORM_TOLERANCE);
+/// }
+/// ```
+pub fn verify_transform_accuracy(fft_size: usize) -> TransformCheck {
+    if fft_size < 2 {
+        panic!("FFT size must be at least 2, but got {}", fft_size);
+    }
+    verify_transforms(
+        plan_real_forward(fft_size).as_ref(),
+        plan_real_inverse(fft_size).as_ref(),
+    )
+}
+
+/// Runs an impulse, a sinusoid and a random vector through the forward FFT and back through the inverse FFT,
+/// and measures how far the spectra and the round trips are from the exact ones.
+///
+/// The impulse has a flat spectrum of ones, and a cosine at an eighth of the sample rate has half the FFT size
+/// in its bin and nothing elsewhere. Every signal must come back from both transforms scaled by the FFT size,
+/// and have the energy of its spectrum, by Parseval's theorem.
+///
+/// # Panics
+/// If the transforms differ in length, or their length is less than 2.
+pub fn verify_transforms(
+    forward: &dyn RealForwardFft<f32>,
+    inverse: &dyn RealInverseFft<f32>,
+) -> TransformCheck {
+    let fft_size = forward.fft_length();
+    if inverse.fft_length() != fft_size {
+        panic!(
+            "Forward and inverse FFT lengths must match, but got {} and {}",
+            fft_size,
+            inverse.fft_length()
+        );
+    }
+    if fft_size < 2 {
+        panic!("FFT size must be at least 2, but got {}", fft_size);
+    }
+
+    let num_bins = fft_size / 2 + 1;
+    let size = fft_size as f64;
+    let mut impulse = vec![0.0; fft_size];
+    impulse[0] = 1.0;
+    let tone_bin = (fft_size / 8).max(1);
+    let tone: Vec<f32> = (0..fft_size)
+        .map(|n| (TAU * (tone_bin * n) as f64 / size).cos() as f32)
+        .collect();
+    let mut rng = SimulationRng::new(RANDOM_SEED);
+    let random: Vec<f32> = (0..fft_size)
+        .map(|_| (2.0 * rng.uniform() - 1.0) as f32)
+        .collect();
+
+    // the known spectra, the tone at DC or Nyquist with the whole FFT size in its bin
+    let flat = vec![Complex32::new(1.0, 0.0); num_bins];
+    let mut line = vec![Complex32::default(); num_bins];
+    line[tone_bin] = Complex32::new(
+        if 2 * tone_bin == fft_size {
+            fft_size as f32
+        } else {
+            fft_size as f32 / 2.0
+        },
+        0.0,
+    );
+
+    let mut reconstruction_error = 0.0;
+    let mut parseval_mismatch = 0.0;
+    let mut spectrum_error = 0.0;
+    let mut forward_scratch = vec![Complex32::default(); forward.get_scratch_len()];
+    let mut inverse_scratch = vec![Complex32::default(); inverse.get_scratch_len()];
+    for (signal, expected) in [
+        (&impulse, Some(&flat)),
+        (&tone, Some(&line)),
+        (&random, None),
+    ] {
+        let mut input = signal.clone();
+        let mut bins = vec![Complex32::default(); num_bins];
+        forward.process_with_scratch(&mut input, &mut bins, &mut forward_scratch);
+
+        if let Some(expected) = expected {
+            let error = bins
+                .iter()
+                .zip(expected)
+                .map(|(bin, expected)| (bin - expected).norm() as f64)
+                .fold(0.0, nan_max);
+            let peak = expected
+                .iter()
+                .fold(0.0f32, |peak, bin| peak.max(bin.norm())) as f64;
+            spectrum_error = nan_max(spectrum_error, error / peak);
+        }
+
+        // the bins of a real signal, the other half mirrored, every one but DC and Nyquist counted twice
+        let signal_energy: f64 = signal.iter().map(|&x| (x as f64).powi(2)).sum();
+        let spectrum_energy: f64 = bins
+            .iter()
+            .enumerate()
+            .map(|(k, bin)| {
+                let weight = if k == 0 || 2 * k == fft_size {
+                    1.0
+                } else {
+                    2.0
+                };
+                weight * bin.norm_sqr() as f64
+            })
+            .sum::<f64>()
+            / size;
+        parseval_mismatch = nan_max(
+            parseval_mismatch,
+            ((spectrum_energy - signal_energy) / signal_energy).abs(),
+        );
+
+        // which the inverse wants exactly real, as a correct forward transform makes them up to rounding
+        bins[0].im = 0.0;
+        if fft_size % 2 == 0 {
+            bins[num_bins - 1].im = 0.0;
+        }
+        let mut output = vec![0.0; fft_size];
+        inverse.process_with_scratch(&mut bins, &mut output, &mut inverse_scratch);
+        let peak = signal.iter().fold(0.0f32, |peak, x| peak.max(x.abs())) as f64;
+        let error = signal
+            .iter()
+            .zip(&output)
+            .map(|(&x, &y)| (y as f64 / size - x as f64).abs())
+            .fold(0.0, nan_max);
+        reconstruction_error = nan_max(reconstruction_error, error / peak);
+    }
+
+    let tolerance = TRANSFORM_TOLERANCE as f64;
+    TransformCheck {
+        fft_size,
+        max_reconstruction_error: reconstruction_error as f32,
+        parseval_mismatch: parseval_mismatch as f32,
+        max_spectrum_error: spectrum_error as f32,
+        // false for NaN
+        passed: reconstruction_error <= tolerance
+            && parseval_mismatch <= tolerance
+            && spectrum_error <= tolerance,
+    }
+}
+
+/// Returns the larger of the values, or NaN if either is, so a NaN anywhere fails the check.
+fn nan_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else {
+        a.max(b)
+    }
+}
@@ -0,0 +1,1588 @@
+//! This module provides signal processing blocks around the modem, like filters that band-limit the samples.
+//!
+//! The [FirFilter] can run on a stream of samples, keeping its state across calls,
+//! or on whole frames, as the [tx_filter](crate::ofdm::modulator::OFDMModulatorConfig::tx_filter)
+//! and [rx_filter](crate::ofdm::demodulator::OFDMDemodulatorConfig::rx_filter) of the modem.
+//! The [Resampler] bridges a modem and a sound card running at different sample rates,
+//! the [Upconverter] and [Downconverter] move the band of the modem to a carrier frequency and back,
+//! the [Decimator] brings the complex samples of an SDR down to the rate of the modem,
+//! the [FarrowInterpolator] reads samples between the samples, for fractional delays and clock offsets,
+//! the [Agc] brings the level of a microphone to the one the receiver expects, the [DcBlocker] removes its offset,
+//! the [Preemphasis] and [Deemphasis] tilt the band against a channel which rolls off, like a speaker and a microphone,
+//! and [goertzel_power] measures the power of a single frequency.
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use num_complex::Complex32;
+use smart_default::SmartDefault;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A linear phase FIR filter with an odd number of taps.
+///
+/// Filters designed with [lowpass](FirFilter::lowpass) or [bandpass](FirFilter::bandpass) are windowed sincs,
+/// tapered with a Blackman window, whose stopband stays below -70 dB.
+/// The transition from passband to stopband is about `5.5 / taps` of the sample rate wide.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::FirFilter;
+///
+/// let filter = FirFilter::lowpass(0.2, 63);
+/// assert_eq!(filter.group_delay(), 31);
+///
+/// // flat in the passband, and at least 70 dB down in the stopband
+/// for frequency in [0.0, 0.05, 0.1, 0.15] {
+///     assert!(filter.gain_db(frequency).abs() < 0.01, "{frequency}");
+/// }
+/// for frequency in [0.3, 0.35, 0.4, 0.45, 0.5] {
+///     assert!(filter.gain_db(frequency) < -70.0, "{frequency}");
+/// }
+///
+/// // a telephone channel at 8 kHz
+/// let filter = FirFilter::bandpass(300.0 / 8000.0, 3400.0 / 8000.0, 101);
+/// assert!(filter.gain_db(1000.0 / 8000.0).abs() < 0.01);
+/// assert!(filter.gain_db(3000.0 / 8000.0).abs() < 0.01);
+/// assert!(filter.gain_db(50.0 / 8000.0) < -60.0);
+/// assert!(filter.gain_db(3800.0 / 8000.0) < -60.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FirFilter {
+    taps: Vec<f32>,
+    /// The last `taps.len() - 1` inputs, the newest first.
+    history: Vec<f32>,
+}
+
+impl FirFilter {
+    /// Creates a new filter from its impulse response.
+    ///
+    /// # Panics
+    /// If the number of taps is even, or a tap is not finite.
+    pub fn new(taps: Vec<f32>) -> Self {
+        if taps.len().is_multiple_of(2) {
+            panic!("Number of taps must be odd, but got {}", taps.len());
+        }
+        if let Some(tap) = taps.iter().find(|tap| !tap.is_finite()) {
+            panic!("Taps must be finite, but got {}", tap);
+        }
+        let history = vec![0.0; taps.len() - 1];
+        FirFilter { taps, history }
+    }
+
+    /// Designs a low-pass filter with the cutoff as a fraction of the sample rate and a gain of 1 at DC.
+    ///
+    /// # Panics
+    /// If the cutoff is not between 0 and 0.5, exclusive, or the number of taps is even.
+    pub fn lowpass(cutoff: f32, taps: usize) -> Self {
+        if !(cutoff > 0.0 && cutoff < 0.5) {
+            panic!("Cutoff must be between 0 and 0.5, but got {}", cutoff);
+        }
+        let mut filter = FirFilter::new(windowed_sinc(cutoff, taps));
+        filter.normalize(0.0);
+        filter
+    }
+
+    /// Designs a band-pass filter with the band edges as fractions of the sample rate and a gain of 1 in the center.
+    ///
+    /// # Panics
+    /// If the edges are not `0 < low < high < 0.5`, or the number of taps is even.
+    pub fn bandpass(low: f32, high: f32, taps: usize) -> Self {
+        if !(low > 0.0 && low < high && high < 0.5) {
+            panic!(
+                "Band edges must be between 0 and 0.5 and ascending, but got {} and {}",
+                low, high
+            );
+        }
+        let taps = windowed_sinc(high, taps)
+            .iter()
+            .zip(windowed_sinc(low, taps))
+            .map(|(high, low)| high - low)
+            .collect();
+        let mut filter = FirFilter::new(taps);
+        filter.normalize((low + high) / 2.0);
+        filter
+    }
+
+    /// Designs a filter which compensates a measured response of a channel from the transmitter, as its
+    /// [tx_filter](crate::ofdm::modulator::OFDMModulatorConfig::tx_filter), like the one of
+    /// [measure_tilt](crate::coded::measure_tilt).
+    ///
+    /// The response holds the gains of the channel in dB at ascending frequencies, as fractions of the sample rate.
+    /// The filter boosts every frequency by as much as the channel attenuates it below its strongest one,
+    /// up to `max_boost_db`, interpolating linearly between the measured frequencies and holding the first and the last
+    /// gain beyond them, and is scaled to keep the mean power over the measured frequencies.
+    /// It samples the boost on a dense grid and tapers its impulse response with a Blackman window,
+    /// so it follows the response as smoothly as its taps resolve, about `5.5 / taps` of the sample rate.
+    ///
+    /// # Panics
+    /// If the response is empty, its frequencies are not ascending between 0 and 0.5, a gain is not finite,
+    /// the maximum boost is negative, or the number of taps is even.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::dsp::FirFilter;
+    ///
+    /// // a channel falling by 6 dB per octave from 0.05 of the sample rate
+    /// let response: Vec<(f32, f32)> = (1..=8).map(|k| (0.05 * k as f32, -6.0 * (k as f32).log2())).collect();
+    /// let filter = FirFilter::compensating(&response, 24.0, 63);
+    ///
+    /// // the channel and the filter are flat within a dB
+    /// let flat: Vec<f32> = response.iter().map(|&(frequency, gain)| gain + filter.gain_db(frequency)).collect();
+    /// let (low, high) = flat.iter().fold((f32::MAX, f32::MIN), |(low, high), &gain| (low.min(gain), high.max(gain)));
+    /// assert!(high - low < 1.0, "{flat:?}");
+    /// ```
+    pub fn compensating(response: &[(f32, f32)], max_boost_db: f32, taps: usize) -> Self {
+        if response.is_empty() {
+            panic!("Response must have at least one frequency, but got none");
+        }
+        if response
+            .iter()
+            .any(|&(frequency, _)| !(0.0..=0.5).contains(&frequency))
+            || response.windows(2).any(|pair| pair[0].0 >= pair[1].0)
+        {
+            panic!(
+                "Response frequencies must be ascending between 0 and 0.5, but got {:?}",
+                response
+            );
+        }
+        if let Some((_, gain)) = response.iter().find(|(_, gain)| !gain.is_finite()) {
+            panic!("Response gains must be finite, but got {}", gain);
+        }
+        if max_boost_db.is_nan() || max_boost_db < 0.0 {
+            panic!(
+                "Maximum boost must be non-negative, but got {} dB",
+                max_boost_db
+            );
+        }
+
+        let strongest = response
+            .iter()
+            .map(|&(_, gain)| gain)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let boost = |frequency: f32| {
+            let gain = match response.partition_point(|&(measured, _)| measured < frequency) {
+                0 => response[0].1,
+                index if index == response.len() => response[index - 1].1,
+                index => {
+                    let ((low, low_gain), (high, high_gain)) =
+                        (response[index - 1], response[index]);
+                    low_gain + (high_gain - low_gain) * (frequency - low) / (high - low)
+                }
+            };
+            (strongest - gain).min(max_boost_db)
+        };
+
+        // the zero-phase impulse response of the boost, from the midpoints of a grid from DC to Nyquist
+        let grid = 8 * taps.max(1);
+        let amplitudes: Vec<(f32, f32)> = (0..grid)
+            .map(|k| {
+                let frequency = (k as f32 + 0.5) / (2 * grid) as f32;
+                (frequency, 10f32.powf(boost(frequency) / 20.0))
+            })
+            .collect();
+        let center = (taps as f32 - 1.0) / 2.0;
+        let impulse = (0..taps)
+            .map(|n| {
+                let t = n as f32 - center;
+                let sum: f32 = amplitudes
+                    .iter()
+                    .map(|(frequency, amplitude)| {
+                        amplitude * (core::f32::consts::TAU * frequency * t).cos()
+                    })
+                    .sum();
+                sum / grid as f32 * blackman(n, taps)
+            })
+            .collect();
+
+        let mut filter = FirFilter::new(impulse);
+        let power = response
+            .iter()
+            .map(|&(frequency, _)| filter.gain(frequency).powi(2))
+            .sum::<f32>()
+            / response.len() as f32;
+        for tap in filter.taps.iter_mut() {
+            *tap /= power.sqrt();
+        }
+        filter
+    }
+
+    /// Returns the impulse response.
+    pub fn taps(&self) -> &[f32] {
+        &self.taps
+    }
+
+    /// Returns the delay of the filter in samples, `(taps - 1) / 2` for every frequency.
+    pub fn group_delay(&self) -> usize {
+        self.history.len() / 2
+    }
+
+    /// Returns the gain at a frequency, given as a fraction of the sample rate, in dB.
+    pub fn gain_db(&self, frequency: f32) -> f32 {
+        20.0 * self.gain(frequency).log10()
+    }
+
+    /// Filters a block of a stream of samples, continuing from the previous block.
+    ///
+    /// The output is delayed by the [group delay](FirFilter::group_delay).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::dsp::FirFilter;
+    ///
+    /// let samples: Vec<f32> = (0..500).map(|n| ((n * 37) % 11) as f32 - 5.0).collect();
+    /// let mut filter = FirFilter::lowpass(0.1, 31);
+    /// let whole = filter.clone().process(&samples);
+    ///
+    /// // blocks of any size give the same output
+    /// let mut blocks = Vec::new();
+    /// for block in samples.chunks(7) {
+    ///     blocks.extend(filter.process(block));
+    /// }
+    /// assert_eq!(blocks, whole);
+    /// ```
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input
+            .iter()
+            .map(|&sample| {
+                let output = self.taps[0] * sample
+                    + self.taps[1..]
+                        .iter()
+                        .zip(&self.history)
+                        .map(|(tap, past)| tap * past)
+                        .sum::<f32>();
+                if !self.history.is_empty() {
+                    self.history.rotate_right(1);
+                    self.history[0] = sample;
+                }
+                output
+            })
+            .collect()
+    }
+
+    /// Clears the state, as if the filter had only seen zeros.
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+    }
+
+    /// Filters a whole buffer without touching the stream state, compensating the group delay.
+    ///
+    /// The output has the same length as the input and lines up with it,
+    /// the ringing of the filter before the first and after the last sample is cut.
+    pub fn filter_frame(&self, samples: &[f32]) -> Vec<f32> {
+        let mut filter = FirFilter::new(self.taps.clone());
+        let delay = filter.group_delay();
+        let mut output = filter.process(samples);
+        output.extend(filter.process(&vec![0.0; delay]));
+        output.split_off(delay.min(output.len()))
+    }
+
+    /// Returns the magnitude of the frequency response.
+    fn gain(&self, frequency: f32) -> f32 {
+        let omega = core::f32::consts::TAU * frequency;
+        let (re, im) = self
+            .taps
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (n, tap)| {
+                let phase = omega * n as f32;
+                (re + tap * phase.cos(), im - tap * phase.sin())
+            });
+        (re * re + im * im).sqrt()
+    }
+
+    /// Scales the taps to a gain of 1 at the frequency.
+    fn normalize(&mut self, frequency: f32) {
+        let gain = self.gain(frequency);
+        for tap in self.taps.iter_mut() {
+            *tap /= gain;
+        }
+    }
+}
+
+/// Returns the ideal low-pass impulse response, centered and tapered with a Blackman window.
+fn windowed_sinc(cutoff: f32, taps: usize) -> Vec<f32> {
+    let center = (taps as f32 - 1.0) / 2.0;
+    (0..taps)
+        .map(|n| {
+            let t = n as f32 - center;
+            let sinc = if t == 0.0 {
+                2.0 * cutoff
+            } else {
+                (core::f32::consts::TAU * cutoff * t).sin() / (core::f32::consts::PI * t)
+            };
+            sinc * blackman(n, taps)
+        })
+        .collect()
+}
+
+/// Returns the Blackman window at tap `n` of `taps`.
+fn blackman(n: usize, taps: usize) -> f32 {
+    if taps > 1 {
+        let phase = core::f32::consts::TAU * n as f32 / (taps as f32 - 1.0);
+        0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+    } else {
+        1.0
+    }
+}
+
+/// Carrier of a modem in a passband, see [Upconverter].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Passband {
+    /// Sample rate of the modem in Hz.
+    pub sample_rate: f32,
+    /// Frequency the center of the band of the modem is moved to, in Hz.
+    pub carrier_hz: f32,
+}
+
+/// Moves a band of real samples from baseband up to a carrier, mixing with a cos/sin oscillator.
+///
+/// The band `[low, high]` is taken out of the real samples as an analytic signal, by a complex band-pass
+/// filter that rejects the negative frequencies, shifted by `carrier - (low + high) / 2` and turned back into real samples,
+/// so that the band keeps its orientation and is centered on the carrier, without an image below it.
+/// The filter transition fits into the smallest of the margins between both bands, DC and the Nyquist frequency,
+/// with a Blackman window for at least 70 dB of rejection, it gets longer as the margins get narrower.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{Downconverter, Upconverter};
+/// use software_modem::metrics::{SpectrumWindow, power_spectrum};
+///
+/// // a band from 1 to 7 kHz at 48 kHz, centered on 8 kHz
+/// let tone = |frequency: f32| -> Vec<f32> {
+///     (0..9600).map(|n| (std::f32::consts::TAU * frequency * n as f32 / 48000.0).cos()).collect()
+/// };
+/// let mut upconverter = Upconverter::new(48000.0, (1000.0, 7000.0), 8000.0);
+/// let up = upconverter.process(&tone(2000.0));
+///
+/// // 2 kHz lands at 6 kHz, without an image at 10 kHz
+/// let spectrum = power_spectrum(&up[2000..], 480, SpectrumWindow::Hann);
+/// let power_db = |frequency: f32| 10.0 * spectrum[(frequency / 100.0) as usize].log10();
+/// assert!(power_db(10000.0) - power_db(6000.0) < -60.0);
+///
+/// // and back, with the delay of both filters
+/// let mut downconverter = Downconverter::new(48000.0, (1000.0, 7000.0), 8000.0);
+/// let down = downconverter.process(&up);
+/// let delay = upconverter.group_delay() + downconverter.group_delay();
+/// let original = tone(2000.0);
+/// for n in 2000..9600 {
+///     assert!((down[n] - original[n - delay]).abs() < 1e-3, "{n}");
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Upconverter {
+    shifter: FrequencyShifter,
+}
+
+impl Upconverter {
+    /// Creates an upconverter from the band `(low, high)` in Hz to a carrier, at a sample rate.
+    ///
+    /// # Panics
+    /// If the band is not `0 < low < high`, or the band does not fit between DC and the Nyquist frequency,
+    /// before or after the shift.
+    pub fn new(sample_rate: f32, band: (f32, f32), carrier_hz: f32) -> Self {
+        let (low, high) = band;
+        Upconverter {
+            shifter: FrequencyShifter::new(
+                sample_rate,
+                band,
+                carrier_hz,
+                (low + high) / 2.0,
+                carrier_hz - (low + high) / 2.0,
+            ),
+        }
+    }
+
+    /// Converts a block of a stream of samples, continuing from the previous block,
+    /// both the filter and the phase of the oscillator.
+    ///
+    /// The output is delayed by the [group delay](Upconverter::group_delay).
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.shifter.process(input)
+    }
+
+    /// Converts a whole frame without touching the stream state, compensating the group delay,
+    /// with the oscillator starting at phase zero, see [FirFilter::filter_frame].
+    pub fn convert_frame(&self, samples: &[f32]) -> Vec<f32> {
+        self.shifter.convert_frame(samples)
+    }
+
+    /// Returns the delay of the output in samples.
+    pub fn group_delay(&self) -> usize {
+        self.shifter.in_phase.group_delay()
+    }
+
+    /// Clears the state, as if the upconverter had only seen zeros, and resets the phase of the oscillator.
+    pub fn reset(&mut self) {
+        self.shifter.reset();
+    }
+}
+
+/// Moves a band of real samples from a carrier back down to baseband, the inverse of an [Upconverter].
+///
+/// The complex band-pass filter around the carrier rejects the image below it and the noise beside the band.
+/// A carrier that is off by a few Hz shifts all subcarriers by the same frequency,
+/// which appears as a phase rotation from symbol to symbol, like a slowly changing channel.
+///
+/// See [Upconverter] for an example.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Downconverter {
+    shifter: FrequencyShifter,
+}
+
+impl Downconverter {
+    /// Creates a downconverter from a carrier to the band `(low, high)` in Hz, at a sample rate.
+    ///
+    /// # Panics
+    /// If the band is not `0 < low < high`, or the band does not fit between DC and the Nyquist frequency,
+    /// before or after the shift.
+    pub fn new(sample_rate: f32, band: (f32, f32), carrier_hz: f32) -> Self {
+        let (low, high) = band;
+        Downconverter {
+            shifter: FrequencyShifter::new(
+                sample_rate,
+                band,
+                carrier_hz,
+                carrier_hz,
+                (low + high) / 2.0 - carrier_hz,
+            ),
+        }
+    }
+
+    /// Converts a block of a stream of samples, continuing from the previous block,
+    /// both the filter and the phase of the oscillator.
+    ///
+    /// The output is delayed by the [group delay](Downconverter::group_delay).
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.shifter.process(input)
+    }
+
+    /// Converts a whole frame without touching the stream state, compensating the group delay,
+    /// with the oscillator starting at phase zero, see [FirFilter::filter_frame].
+    pub fn convert_frame(&self, samples: &[f32]) -> Vec<f32> {
+        self.shifter.convert_frame(samples)
+    }
+
+    /// Returns the delay of the output in samples.
+    pub fn group_delay(&self) -> usize {
+        self.shifter.in_phase.group_delay()
+    }
+
+    /// Clears the state, as if the downconverter had only seen zeros, and resets the phase of the oscillator.
+    pub fn reset(&mut self) {
+        self.shifter.reset();
+    }
+}
+
+/// Shifts the real samples in a band by a frequency, through the analytic signal of the band.
+#[derive(Clone, Debug, PartialEq)]
+struct FrequencyShifter {
+    /// Real and imaginary part of the complex band-pass filter.
+    in_phase: FirFilter,
+    quadrature: FirFilter,
+    /// Phase increment of the oscillator per sample, in radians.
+    step: f64,
+    phase: f64,
+}
+
+impl FrequencyShifter {
+    /// # Panics
+    /// If the band is not `0 < low < high`, or the band does not fit between DC and the Nyquist frequency,
+    /// before or after the shift.
+    fn new(sample_rate: f32, band: (f32, f32), carrier_hz: f32, center: f32, shift: f32) -> Self {
+        let (low, high) = band;
+        let nyquist = sample_rate / 2.0;
+        let half_width = (high - low) / 2.0;
+        if !(low > 0.0 && low < high && high < nyquist) {
+            panic!(
+                "Band must be between 0 and {} Hz and ascending, but got {} and {} Hz",
+                nyquist, low, high
+            );
+        }
+        if !(carrier_hz - half_width > 0.0 && carrier_hz + half_width < nyquist) {
+            panic!(
+                "Carrier must keep the band between 0 and {} Hz, but got {} Hz for a band of {} Hz",
+                nyquist,
+                carrier_hz,
+                high - low
+            );
+        }
+
+        // the filter passes the band and stops beyond the margin, so neither image reaches the band
+        let margin = low
+            .min(nyquist - high)
+            .min(carrier_hz - half_width)
+            .min(nyquist - carrier_hz - half_width);
+        let taps = (5.5 * sample_rate / margin).ceil() as usize | 1;
+        let lowpass = windowed_sinc((half_width + margin / 2.0) / sample_rate, taps);
+        let gain: f32 = lowpass.iter().sum();
+        let delay = (taps / 2) as f32;
+        let (in_phase, quadrature) = lowpass
+            .iter()
+            .enumerate()
+            .map(|(n, tap)| {
+                let phase = core::f32::consts::TAU * center / sample_rate * (n as f32 - delay);
+                let tap = 2.0 * tap / gain;
+                (tap * phase.cos(), tap * phase.sin())
+            })
+            .unzip();
+
+        FrequencyShifter {
+            in_phase: FirFilter::new(in_phase),
+            quadrature: FirFilter::new(quadrature),
+            step: core::f64::consts::TAU * f64::from(shift) / f64::from(sample_rate),
+            phase: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let in_phase = self.in_phase.process(input);
+        let quadrature = self.quadrature.process(input);
+        let output = mix(&in_phase, &quadrature, self.phase, self.step);
+        self.phase = (self.phase + self.step * input.len() as f64) % core::f64::consts::TAU;
+        output
+    }
+
+    fn convert_frame(&self, samples: &[f32]) -> Vec<f32> {
+        let in_phase = self.in_phase.filter_frame(samples);
+        let quadrature = self.quadrature.filter_frame(samples);
+        mix(&in_phase, &quadrature, 0.0, self.step)
+    }
+
+    fn reset(&mut self) {
+        self.in_phase.reset();
+        self.quadrature.reset();
+        self.phase = 0.0;
+    }
+}
+
+/// Returns the real part of the analytic signal, rotated by an oscillator starting at a phase.
+fn mix(in_phase: &[f32], quadrature: &[f32], phase: f64, step: f64) -> Vec<f32> {
+    in_phase
+        .iter()
+        .zip(quadrature)
+        .enumerate()
+        .map(|(n, (re, im))| {
+            let (sin, cos) = ((phase + step * n as f64) % core::f64::consts::TAU).sin_cos();
+            re * cos as f32 - im * sin as f32
+        })
+        .collect()
+}
+
+/// Largest up or down factor of a [Resampler], after reducing the ratio of the rates, and of a [Decimator].
+const MAX_RESAMPLING_FACTOR: usize = 1024;
+
+/// Stopband attenuation of the [Resampler] and [Decimator] prototype filters in dB.
+const RESAMPLER_ATTENUATION: f64 = 90.0;
+
+/// A streaming polyphase resampler between two sample rates with a rational ratio, like 48 kHz and 44.1 kHz.
+///
+/// The rates are reduced to an up factor `L` and a down factor `M`, and the samples are interpolated by a windowed sinc
+/// on the grid `L` times finer than the input, with a Kaiser window for 90 dB of stopband attenuation.
+/// The passband reaches 0.4 of the lower of both rates, aliases stay outside of it,
+/// so a modem that keeps its subcarriers below that frequency is not limited by the resampling.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::Resampler;
+///
+/// // a sweep from 100 Hz to 16 kHz, resampled to 44.1 kHz and back
+/// let sweep = |t: f64| (std::f64::consts::TAU * (100.0 * t + 7950.0 * t * t)).sin() as f32 * 0.5;
+/// let input: Vec<f32> = (0..48000).map(|n| sweep(n as f64 / 48000.0)).collect();
+/// let mut down = Resampler::new(48000, 44100);
+/// let mut up = Resampler::new(44100, 48000);
+/// let output = up.process(&down.process(&input));
+/// assert!(output.len().abs_diff(48000) <= 1);
+///
+/// // it matches the sweep, delayed by both resamplers, 80 dB below the signal
+/// let delay = down.delay() as f64 / 48000.0 + up.delay() as f64 / 44100.0;
+/// let (mut signal, mut error) = (0.0, 0.0);
+/// for (n, y) in output.iter().enumerate().skip(100).take(47000) {
+///     let x = sweep(n as f64 / 48000.0 - delay);
+///     signal += x * x;
+///     error += (x - y) * (x - y);
+/// }
+/// let snr = 10.0 * (signal / error).log10();
+/// assert!(snr > 80.0, "{snr} dB");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resampler {
+    up: usize,
+    down: usize,
+    /// The `L` phases of the prototype filter, phase `p` holds the taps `p, p + L, p + 2L, ...`.
+    phases: Vec<Vec<f32>>,
+    /// The last inputs, the newest first.
+    history: VecDeque<f32>,
+    /// Position of the next output on the fine grid, relative to the newest input.
+    phase: usize,
+}
+
+impl Resampler {
+    /// Creates a resampler from the input to the output rate.
+    ///
+    /// # Panics
+    /// If a rate is 0, or the rates are not [supported](Resampler::supports).
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        if input_rate == 0 || output_rate == 0 {
+            panic!(
+                "Sample rates must be positive, but got {} and {}",
+                input_rate, output_rate
+            );
+        }
+        if !Resampler::supports(input_rate, output_rate) {
+            panic!(
+                "Ratio of the sample rates must reduce to factors up to {}, but got {} and {}",
+                MAX_RESAMPLING_FACTOR, input_rate, output_rate
+            );
+        }
+        let divisor = gcd(input_rate, output_rate);
+        let up = (output_rate / divisor) as usize;
+        let down = (input_rate / divisor) as usize;
+
+        // the transition from 0.4 to 0.6 of the lower rate, relative to the input rate
+        let lower = (up as f64 / down as f64).min(1.0);
+        let transition = 0.2 * lower;
+        let taps_per_phase = ((RESAMPLER_ATTENUATION - 8.0)
+            / (2.285 * core::f64::consts::TAU * transition))
+            .ceil() as usize;
+        let taps_per_phase = taps_per_phase + taps_per_phase % 2;
+
+        // the prototype is centered on the fine grid, so that the delay is a whole number of inputs,
+        // its last tap, the mirror of the first, is left out
+        let length = up * taps_per_phase;
+        let center = length as f64 / 2.0;
+        let prototype = kaiser_sinc(0.5 * lower / up as f64, length, center);
+        let gain = up as f64 / prototype.iter().sum::<f64>();
+        let phases = (0..up)
+            .map(|phase| {
+                prototype[phase..]
+                    .iter()
+                    .step_by(up)
+                    .map(|tap| (tap * gain) as f32)
+                    .collect()
+            })
+            .collect();
+
+        Resampler {
+            up,
+            down,
+            phases,
+            history: VecDeque::from(vec![0.0; taps_per_phase]),
+            phase: 0,
+        }
+    }
+
+    /// Returns whether a resampler between the rates can be created, that is whether their ratio reduces to
+    /// up and down factors of at most 1024, like 160 and 147 for 48 kHz and 44.1 kHz.
+    pub fn supports(input_rate: u32, output_rate: u32) -> bool {
+        if input_rate == 0 || output_rate == 0 {
+            return false;
+        }
+        let divisor = gcd(input_rate, output_rate);
+        (input_rate / divisor).max(output_rate / divisor) as usize <= MAX_RESAMPLING_FACTOR
+    }
+
+    /// Returns the delay of the output in input samples, which bounds the latency of the resampler.
+    pub fn delay(&self) -> usize {
+        self.history.len() / 2
+    }
+
+    /// Resamples a block of a stream of samples, continuing from the previous block.
+    ///
+    /// Every input yields `L / M` outputs on average, the output is delayed by the [delay](Resampler::delay).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::dsp::Resampler;
+    ///
+    /// let samples: Vec<f32> = (0..4410).map(|n| ((n * 37) % 11) as f32 - 5.0).collect();
+    /// let mut resampler = Resampler::new(44100, 48000);
+    /// let whole = resampler.clone().process(&samples);
+    /// assert_eq!(whole.len(), 4800);
+    ///
+    /// // blocks of any size give the same output
+    /// let mut blocks = Vec::new();
+    /// for block in samples.chunks(7) {
+    ///     blocks.extend(resampler.process(block));
+    /// }
+    /// assert_eq!(blocks, whole);
+    /// ```
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity(input.len() * self.up / self.down + 1);
+        for &sample in input {
+            self.history.pop_back();
+            self.history.push_front(sample);
+            while self.phase < self.up {
+                output.push(
+                    self.phases[self.phase]
+                        .iter()
+                        .zip(&self.history)
+                        .map(|(tap, past)| tap * past)
+                        .sum(),
+                );
+                self.phase += self.down;
+            }
+            self.phase -= self.up;
+        }
+        output
+    }
+
+    /// Clears the state, as if the resampler had only seen zeros.
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|past| *past = 0.0);
+        self.phase = 0;
+    }
+}
+
+/// A streaming decimator of complex samples by an integer factor, like from the 2.4 MHz of an RTL-SDR
+/// to the rate of a [complex modem](crate::ofdm::complex).
+///
+/// The samples are low-pass filtered by a windowed sinc with a Kaiser window for 90 dB of stopband attenuation,
+/// and only every `factor`-th output is computed. Like the [Resampler], the passband reaches 0.4 of the output rate
+/// and the stopband starts at 0.6 of it, on both sides of DC, so nothing aliases into the passband.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::dsp::Decimator;
+///
+/// // a tone in the passband and one far outside of it, at 240 kHz and 2.4 MHz
+/// let tone = |frequency: f64, n: usize| {
+///     Complex32::from_polar(0.5, (std::f64::consts::TAU * frequency * n as f64 % std::f64::consts::TAU) as f32)
+/// };
+/// let input: Vec<Complex32> = (0..24000)
+///     .map(|n| tone(-70e3 / 2.4e6, n) + tone(700e3 / 2.4e6, n))
+///     .collect();
+/// let mut decimator = Decimator::new(10);
+/// let output = decimator.process(&input);
+/// assert_eq!(output.len(), 2400);
+///
+/// // the delay is a whole number of outputs
+/// let delay = decimator.delay() / 10;
+/// // once the filter is filled, the tone in the passband comes through, the other is gone
+/// let error: f32 = output[delay..]
+///     .iter()
+///     .enumerate()
+///     .skip(delay)
+///     .map(|(n, y)| (y - tone(-70e3 / 240e3, n)).norm_sqr())
+///     .sum();
+/// let snr = 10.0 * (0.25 * (output.len() - 2 * delay) as f32 / error).log10();
+/// assert!(snr > 80.0, "{snr} dB");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decimator {
+    factor: usize,
+    taps: Vec<f32>,
+    /// The last inputs, the newest first.
+    history: VecDeque<Complex32>,
+    /// Number of inputs until the next output.
+    countdown: usize,
+}
+
+impl Decimator {
+    /// Creates a decimator by the factor.
+    ///
+    /// # Panics
+    /// If the factor is 0 or above 1024.
+    pub fn new(factor: usize) -> Self {
+        if !(1..=MAX_RESAMPLING_FACTOR).contains(&factor) {
+            panic!(
+                "Decimation factor must be between 1 and {}, but got {}",
+                MAX_RESAMPLING_FACTOR, factor
+            );
+        }
+
+        // the transition from 0.4 to 0.6 of the output rate, with the delay rounded up to whole outputs
+        let transition = 0.2 / factor as f64;
+        let half_length =
+            ((RESAMPLER_ATTENUATION - 8.0) / (2.285 * core::f64::consts::TAU * transition) / 2.0)
+                .ceil() as usize;
+        let half_length = half_length.div_ceil(factor) * factor;
+        let prototype = kaiser_sinc(0.5 / factor as f64, 2 * half_length + 1, half_length as f64);
+        let gain = prototype.iter().sum::<f64>();
+
+        Decimator {
+            factor,
+            taps: prototype.iter().map(|tap| (tap / gain) as f32).collect(),
+            history: VecDeque::from(vec![Complex32::default(); 2 * half_length + 1]),
+            countdown: 1,
+        }
+    }
+
+    /// Returns the decimation factor.
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Returns the delay of the output in input samples, a multiple of the factor.
+    pub fn delay(&self) -> usize {
+        self.history.len() / 2
+    }
+
+    /// Decimates a block of a stream of samples, continuing from the previous block.
+    ///
+    /// The first input yields the first output, then every `factor`-th input yields one,
+    /// the output is delayed by the [delay](Decimator::delay).
+    pub fn process(&mut self, input: &[Complex32]) -> Vec<Complex32> {
+        let mut output = Vec::with_capacity(input.len() / self.factor + 1);
+        for &sample in input {
+            self.history.pop_back();
+            self.history.push_front(sample);
+            self.countdown -= 1;
+            if self.countdown == 0 {
+                output.push(
+                    self.taps
+                        .iter()
+                        .zip(&self.history)
+                        .map(|(&tap, &past)| past * tap)
+                        .sum(),
+                );
+                self.countdown = self.factor;
+            }
+        }
+        output
+    }
+
+    /// Clears the state, as if the decimator had only seen zeros.
+    pub fn reset(&mut self) {
+        self.history
+            .iter_mut()
+            .for_each(|past| *past = Complex32::default());
+        self.countdown = 1;
+    }
+}
+
+/// Interpolates between samples at fractional positions, for fractional delays and offsets of the sample clock.
+///
+/// The Farrow structure evaluates the Lagrange polynomial through `taps` samples around the position:
+/// one FIR filter per power of the fractional position `mu`, whose outputs are summed by Horner's rule,
+/// so moving the position costs no new filter design. Polynomials of a degree below the number of taps
+/// are interpolated exactly, sines the better the further they are below the Nyquist frequency.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::FarrowInterpolator;
+///
+/// // exact for a cubic with 4 taps, between the samples at 1 and 2
+/// let cubic = |x: f32| x * x * x - 2.0 * x + 1.0;
+/// let samples: Vec<f32> = (0..4).map(|n| cubic(n as f32)).collect();
+/// let interpolator = FarrowInterpolator::new(4);
+/// assert!((interpolator.interpolate(&samples, 0.3) - cubic(1.3)).abs() < 1e-5);
+///
+/// // a sine at a tenth of the sample rate, 70 dB down with 8 taps
+/// let sine = |x: f32| (std::f32::consts::TAU * 0.1 * x).sin();
+/// let samples: Vec<f32> = (0..8).map(|n| sine(n as f32)).collect();
+/// let interpolator = FarrowInterpolator::new(8);
+/// for mu in [0.0, 0.25, 0.5, 0.9] {
+///     let error = interpolator.interpolate(&samples, mu) - sine(3.0 + mu);
+///     assert!(error.abs() < 3e-4, "{mu}: {error}");
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FarrowInterpolator {
+    /// The filter of every power of `mu`, the constant one first.
+    coefficients: Vec<Vec<f32>>,
+}
+
+impl FarrowInterpolator {
+    /// Creates an interpolator through `taps` samples, with a polynomial of degree `taps - 1`.
+    ///
+    /// # Panics
+    /// If the number of taps is odd, or not between 2 and 16.
+    pub fn new(taps: usize) -> Self {
+        if !(taps.is_multiple_of(2) && (2..=16).contains(&taps)) {
+            panic!(
+                "Number of taps must be even and between 2 and 16, but got {}",
+                taps
+            );
+        }
+
+        // the Lagrange basis polynomial of every tap, through the nodes -(taps / 2 - 1) to taps / 2
+        let node = |j: usize| j as f64 - (taps / 2 - 1) as f64;
+        let basis: Vec<Vec<f64>> = (0..taps)
+            .map(|k| {
+                (0..taps)
+                    .filter(|&j| j != k)
+                    .fold(vec![1.0], |polynomial, j| {
+                        let scale = 1.0 / (node(k) - node(j));
+                        let mut product = vec![0.0; polynomial.len() + 1];
+                        for (power, c) in polynomial.iter().enumerate() {
+                            product[power + 1] += c * scale;
+                            product[power] -= c * scale * node(j);
+                        }
+                        product
+                    })
+            })
+            .collect();
+        let coefficients = (0..taps)
+            .map(|power| {
+                basis
+                    .iter()
+                    .map(|polynomial| polynomial[power] as f32)
+                    .collect()
+            })
+            .collect();
+        FarrowInterpolator { coefficients }
+    }
+
+    /// Returns the number of samples the interpolator reads.
+    pub fn taps(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Interpolates a window of [taps](FarrowInterpolator::taps) samples between its two middle samples,
+    /// `mu` from 0 at the sample `taps / 2 - 1` to 1 at the sample `taps / 2`.
+    ///
+    /// Works on real and complex samples.
+    ///
+    /// # Panics
+    /// If the window is not as long as the number of taps.
+    pub fn interpolate<S>(&self, window: &[S], mu: f32) -> S
+    where
+        S: Copy + Default + core::ops::Add<Output = S> + core::ops::Mul<f32, Output = S>,
+    {
+        if window.len() != self.taps() {
+            panic!(
+                "Window must be {} samples long, but got {}",
+                self.taps(),
+                window.len()
+            );
+        }
+
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(S::default(), |sum, filter| {
+                let branch = filter
+                    .iter()
+                    .zip(window)
+                    .fold(S::default(), |branch, (&c, &sample)| branch + sample * c);
+                sum * mu + branch
+            })
+    }
+}
+
+/// Configuration of an [Agc].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct AgcConfig {
+    /// RMS level the output is brought to.
+    #[default(0.1)]
+    pub target_rms: f32,
+    /// Time constant in samples over which the power is measured and the gain falls when the level rises,
+    /// longer than the period of the lowest frequency of the signal.
+    #[default(240.0)]
+    pub attack: f32,
+    /// Time constant in samples over which the gain rises when the level falls, longer than the attack,
+    /// so the gain does not follow the envelope of the signal.
+    #[default(4800.0)]
+    pub release: f32,
+    /// Largest gain in dB, which is the gain over silence, so it also bounds how loud the noise between frames gets.
+    #[default(40.0)]
+    pub max_gain_db: f32,
+}
+
+/// An automatic gain control, which brings the RMS level of a stream of samples to a target.
+///
+/// The power of the input is measured by a one-pole filter with the [attack](AgcConfig::attack) time constant,
+/// and the gain follows the one that brings that power to the target, up to the [maximum gain](AgcConfig::max_gain_db),
+/// with the attack time constant when it falls and the [release](AgcConfig::release) one when it rises.
+/// It starts at 0 dB.
+/// While [held](Agc::set_hold), the gain stays where it is, so it does not pump during a frame.
+/// The [stream demodulator](crate::stream::StreamDemodulator::set_agc) holds it while its squelch is open.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{Agc, AgcConfig};
+///
+/// // a tone 23 dB below the target, the gain rises over the release time
+/// let tone: Vec<f32> = (0..60000).map(|n| 0.01 * (std::f32::consts::TAU * 0.01 * n as f32).sin()).collect();
+/// let mut agc = Agc::new(AgcConfig::default());
+/// let output = agc.process(&tone);
+/// assert!((agc.get_gain_db() - 23.0).abs() < 0.5);
+///
+/// // the RMS level of the last periods is the target
+/// let rms = (output[59000..].iter().map(|x| x * x).sum::<f32>() / 1000.0).sqrt();
+/// assert!((rms / 0.1 - 1.0).abs() < 0.05);
+///
+/// // held, the gain ignores the level
+/// agc.set_hold(true);
+/// agc.process(&vec![0.0; 10000]);
+/// assert!((agc.get_gain_db() - 23.0).abs() < 0.5);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Agc {
+    target_power: f32,
+    /// The filter coefficients of the power and a falling gain, and of a rising gain.
+    attack: f32,
+    release: f32,
+    max_gain: f32,
+    /// The tracked power of the input.
+    power: f32,
+    gain: f32,
+    hold: bool,
+}
+
+impl Agc {
+    /// Creates a gain control with a gain of 0 dB.
+    ///
+    /// # Panics
+    /// If the target RMS level is not positive and finite, a time constant is below 1 sample or not finite,
+    /// or the maximum gain is not finite.
+    pub fn new(config: AgcConfig) -> Self {
+        if !(config.target_rms > 0.0 && config.target_rms.is_finite()) {
+            panic!(
+                "Target RMS level must be positive and finite, but got {}",
+                config.target_rms
+            );
+        }
+        for time_constant in [config.attack, config.release] {
+            if !(time_constant >= 1.0 && time_constant.is_finite()) {
+                panic!(
+                    "Time constants must be at least 1 sample and finite, but got {}",
+                    time_constant
+                );
+            }
+        }
+        if !config.max_gain_db.is_finite() {
+            panic!(
+                "Maximum gain must be finite, but got {}",
+                config.max_gain_db
+            );
+        }
+
+        let target_power = config.target_rms * config.target_rms;
+        Agc {
+            target_power,
+            attack: 1.0 - (-1.0 / config.attack).exp(),
+            release: 1.0 - (-1.0 / config.release).exp(),
+            max_gain: 10f32.powf(config.max_gain_db / 20.0),
+            power: target_power,
+            gain: 1.0,
+            hold: false,
+        }
+    }
+
+    /// Applies the gain to a block of a stream of samples, continuing from the previous block.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = input.to_vec();
+        self.process_in_place(&mut output);
+        output
+    }
+
+    /// Applies the gain to a block of a stream of samples where they are, like [process](Self::process).
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        if self.hold {
+            samples.iter_mut().for_each(|sample| *sample *= self.gain);
+            return;
+        }
+        for sample in samples {
+            self.power += self.attack * (*sample * *sample - self.power);
+            let gain = if self.power > 0.0 {
+                (self.target_power / self.power).sqrt().min(self.max_gain)
+            } else {
+                self.max_gain
+            };
+            let coefficient = if gain < self.gain {
+                self.attack
+            } else {
+                self.release
+            };
+            self.gain += coefficient * (gain - self.gain);
+            *sample *= self.gain;
+        }
+    }
+
+    /// Holds the gain where it is, or lets it follow the level again.
+    ///
+    /// While held, the power of the input is not tracked either, the gain continues from where it was held.
+    pub fn set_hold(&mut self, hold: bool) {
+        self.hold = hold;
+    }
+
+    /// Returns whether the gain is [held](Self::set_hold).
+    pub fn is_held(&self) -> bool {
+        self.hold
+    }
+
+    /// Returns the current gain in dB.
+    pub fn get_gain_db(&self) -> f32 {
+        20.0 * self.gain.log10()
+    }
+
+    /// Returns the gain to 0 dB and releases the hold, as if the gain control had only seen the target level.
+    pub fn reset(&mut self) {
+        self.power = self.target_power;
+        self.gain = 1.0;
+        self.hold = false;
+    }
+}
+
+/// A single-pole IIR high-pass filter that removes the DC offset of a stream of samples, like the one of a cheap ADC,
+/// which would land on the DC bin of the FFT.
+///
+/// The filter is `y[n] = g (x[n] - x[n - 1]) + p y[n - 1]`, with a zero at DC and a pole `p` just inside the unit circle,
+/// at `exp(-2 pi cutoff)` for a cutoff as a fraction of the sample rate, and `g = (1 + p) / 2` for unity gain
+/// at the Nyquist frequency. The gain is 3 dB down at about the cutoff and within 0.05 dB of unity from 10 times it.
+/// Its [group delay](DcBlocker::group_delay) is about `1 / (2 pi cutoff)` samples near DC,
+/// and falls to a hundredth of that at 10 times the cutoff.
+///
+/// It is computed as the input less an estimate of its offset, `d[n] = p d[n - 1] + (1 - p) (x[n] + x[n - 1]) / 2`,
+/// which starts settled: over its first `1 / (1 - p)` samples, a time constant, the estimate is the mean of the
+/// samples so far instead, so the offset of a stream is gone from the start instead of decaying like a step.
+///
+/// A frame far above the level of the noise would move the estimate, which then rings for a few time constants
+/// after it and keeps a squelch open. With a [hold](DcBlocker::set_hold), the estimate only follows the samples
+/// which come out quiet, and keeps its value over the loud ones, like the samples of a frame behind a squelch.
+/// Loud samples which stay within twice the level of each other for 16 samples in a row are a step of the offset
+/// instead, like at the start of a stream with an offset or after a frame at its start, and the estimate follows them.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{DcBlocker, DcBlockerHold};
+///
+/// // a tone with an offset of 0.1
+/// let tone = |n: usize| 0.5 * (std::f32::consts::TAU * 0.05 * n as f32).sin();
+/// let input: Vec<f32> = (0..20000).map(|n| tone(n) + 0.1).collect();
+/// let mut blocker = DcBlocker::new(0.001);
+/// let output = blocker.process(&input);
+///
+/// // after the filter settled, the offset is gone and the tone passes
+/// let mean = output[10000..].iter().sum::<f32>() / 10000.0;
+/// assert!(mean.abs() < 1e-3, "{mean}");
+/// assert!(blocker.gain_db(0.05).abs() < 0.01);
+/// assert!(blocker.gain_db(0.001) + 3.0 < 0.1);
+///
+/// // a burst far above the tone leaves the estimate ringing, unless it is held at the level of a squelch
+/// let mut unheld = blocker.clone();
+/// blocker.set_hold(Some(DcBlockerHold { level: 0.01, max_length: 1000 }));
+/// let burst: Vec<f32> = (0..500).map(|n| 100.0 * tone(n) + 0.1).collect();
+/// blocker.process(&burst);
+/// unheld.process(&burst);
+/// assert!(blocker.process(&[0.1; 100]).iter().all(|x| x.abs() < 0.01));
+/// assert!(unheld.process(&[0.1; 100]).iter().any(|x| x.abs() > 0.5));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DcBlocker {
+    pole: f32,
+    /// The last input the estimate followed, and the estimate of the offset.
+    input: f32,
+    offset: f32,
+    /// Samples seen since the filter was made or reset, up to the end of the mean over the first of them.
+    seen: u32,
+    hold: Option<DcBlockerHold>,
+    /// Loud samples in a row, and the last of them within twice the level of each other, with the smallest and largest.
+    held: usize,
+    steady: usize,
+    run: (f32, f32),
+}
+
+/// Loud samples in a row within twice the level of each other after which a [DcBlocker] follows them
+/// as a step of the offset.
+const STEP_LENGTH: usize = 16;
+
+/// When a [DcBlocker] holds its estimate of the offset, see [set_hold](DcBlocker::set_hold).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DcBlockerHold {
+    /// Magnitude of the output above which the estimate holds, like the level of a squelch.
+    pub level: f32,
+    /// Largest number of loud samples in a row the estimate holds over, like the longest burst, after which it
+    /// follows the input again until it is quiet, so a signal which never falls silent does not hold it for good.
+    pub max_length: usize,
+}
+
+impl DcBlocker {
+    /// Creates a DC blocker whose gain is 3 dB down at about the cutoff, a fraction of the sample rate.
+    ///
+    /// # Panics
+    /// If the cutoff is not between 0 and 0.5.
+    pub fn new(cutoff: f32) -> Self {
+        if !(cutoff > 0.0 && cutoff < 0.5) {
+            panic!(
+                "Cutoff must be between 0 and 0.5 of the sample rate, but got {}",
+                cutoff
+            );
+        }
+        DcBlocker::from_pole((-core::f32::consts::TAU * cutoff).exp())
+    }
+
+    /// Creates a DC blocker with its pole, like 0.995, the closer to 1 the lower the cutoff.
+    ///
+    /// # Panics
+    /// If the pole is not between 0 and 1.
+    pub fn from_pole(pole: f32) -> Self {
+        if !(pole > 0.0 && pole < 1.0) {
+            panic!("Pole must be between 0 and 1, but got {}", pole);
+        }
+        DcBlocker {
+            pole,
+            input: 0.0,
+            offset: 0.0,
+            seen: 0,
+            hold: None,
+            held: 0,
+            steady: 0,
+            run: (0.0, 0.0),
+        }
+    }
+
+    /// Returns the pole of the filter.
+    pub fn get_pole(&self) -> f32 {
+        self.pole
+    }
+
+    /// Returns the gain at a frequency, given as a fraction of the sample rate, in dB.
+    pub fn gain_db(&self, frequency: f32) -> f32 {
+        let z = Complex32::from_polar(1.0, -core::f32::consts::TAU * frequency);
+        let gain = 0.5 * (1.0 + self.pole) * (1.0 - z) / (1.0 - self.pole * z);
+        20.0 * gain.norm().log10()
+    }
+
+    /// Returns the group delay at a frequency, given as a fraction of the sample rate, in samples.
+    pub fn group_delay(&self, frequency: f32) -> f32 {
+        // the zero at DC delays by half a sample, the pole by the rest
+        let cos = (core::f32::consts::TAU * frequency).cos();
+        let pole = self.pole;
+        0.5 - (pole * pole - pole * cos) / (1.0 - 2.0 * pole * cos + pole * pole)
+    }
+
+    /// Filters a block of a stream of samples, continuing from the previous block.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = input.to_vec();
+        self.process_in_place(&mut output);
+        output
+    }
+
+    /// Filters a block of a stream of samples where they are, like [process](Self::process).
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        let weight = 1.0 - self.pole;
+        for sample in samples {
+            // whether the estimate stays put, and whether the sample may take part in the mean it starts from
+            let (held, settling) = match self.hold {
+                Some(hold) if (*sample - self.offset).abs() > hold.level => {
+                    let run = (self.run.0.min(*sample), self.run.1.max(*sample));
+                    if self.steady > 0 && run.1 - run.0 <= 2.0 * hold.level {
+                        self.run = run;
+                        self.steady += 1;
+                    } else {
+                        self.run = (*sample, *sample);
+                        self.steady = 1;
+                    }
+                    self.held += 1;
+                    // loud samples which stay within the level of each other are a step of the offset, not a frame,
+                    // and a burst longer than the longest one is followed slowly, whatever it is
+                    if self.steady >= STEP_LENGTH {
+                        (false, true)
+                    } else {
+                        (self.held <= hold.max_length, false)
+                    }
+                }
+                _ => {
+                    self.held = 0;
+                    self.steady = 0;
+                    (false, true)
+                }
+            };
+            if !held {
+                let mean = 1.0 / (self.seen + 1) as f32;
+                if settling && mean > weight {
+                    self.offset += mean * (*sample - self.offset);
+                    self.seen += 1;
+                } else {
+                    self.offset = self.pole * self.offset + weight * 0.5 * (*sample + self.input);
+                }
+                self.input = *sample;
+            }
+            *sample -= self.offset;
+        }
+    }
+
+    /// Holds the estimate of the offset over the samples which come out louder than a level, or never with `None`.
+    ///
+    /// The [StreamDemodulator](crate::stream::StreamDemodulator) holds its blocker at 4 times the level of its squelch,
+    /// for at most the largest number of samples of a burst.
+    pub fn set_hold(&mut self, hold: Option<DcBlockerHold>) {
+        self.hold = hold;
+    }
+
+    /// Returns when the estimate holds, see [set_hold](Self::set_hold).
+    pub fn get_hold(&self) -> Option<DcBlockerHold> {
+        self.hold
+    }
+
+    /// Clears the state, as if the filter were new: it settles again on the next samples. The hold is kept.
+    pub fn reset(&mut self) {
+        self.input = 0.0;
+        self.offset = 0.0;
+        self.seen = 0;
+        self.held = 0;
+        self.steady = 0;
+        self.run = (0.0, 0.0);
+    }
+}
+
+/// The shape of a [Preemphasis] and of the matching [Deemphasis].
+///
+/// The pre-emphasis is a first-order high shelf: flat below the corner, rising by 6 dB per octave above it,
+/// up to the boost at the Nyquist frequency. A channel rolling off like a speaker and a microphone,
+/// or a simple RC low-pass, has the opposite slope.
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct EmphasisConfig {
+    /// Frequency from which the gain rises, as a fraction of the sample rate.
+    #[default(0.05)]
+    pub corner: f32,
+    /// Gain at the Nyquist frequency over the one at DC in dB, where the slope levels off.
+    #[default(12.0)]
+    pub boost_db: f32,
+}
+
+/// A first-order shelving filter that boosts the high frequencies of the transmitted samples,
+/// ahead of a channel which attenuates them, see [EmphasisConfig].
+///
+/// The gain is 0 dB at DC and the boost at the Nyquist frequency, so the peaks of a frame grow with the boost,
+/// which the headroom of the output has to leave room for.
+/// The [Deemphasis] of the same configuration is its exact inverse, for a channel which is flat on its own.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{Deemphasis, EmphasisConfig, Preemphasis};
+///
+/// let config = EmphasisConfig { corner: 0.01, boost_db: 24.0 };
+/// let mut preemphasis = Preemphasis::new(config);
+/// assert!(preemphasis.gain_db(0.0).abs() < 1e-3);
+/// assert!((preemphasis.gain_db(0.5) - 24.0).abs() < 1e-3);
+/// // about 6 dB per octave above the corner
+/// let octave = preemphasis.gain_db(0.06) - preemphasis.gain_db(0.03);
+/// assert!((octave - 6.0).abs() < 1.5, "{octave}");
+///
+/// // the de-emphasis restores the samples
+/// let samples: Vec<f32> = (0..500).map(|n| ((n * 37) % 11) as f32 - 5.0).collect();
+/// let restored = Deemphasis::new(config).process(&preemphasis.process(&samples));
+/// for (restored, sample) in restored.iter().zip(&samples) {
+///     assert!((restored - sample).abs() < 1e-3);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preemphasis {
+    config: EmphasisConfig,
+    section: FirstOrderSection,
+}
+
+impl Preemphasis {
+    /// Creates a pre-emphasis of the configuration.
+    ///
+    /// # Panics
+    /// If the corner is not between 0 and 0.5, or the boost is negative or not finite.
+    pub fn new(config: EmphasisConfig) -> Self {
+        let (zero, pole, gain) = design_shelf(&config);
+        Preemphasis {
+            config,
+            section: FirstOrderSection::new(gain, -gain * zero, pole),
+        }
+    }
+
+    /// Returns the configuration of the filter.
+    pub fn get_config(&self) -> EmphasisConfig {
+        self.config
+    }
+
+    /// Returns the gain at a frequency, given as a fraction of the sample rate, in dB.
+    pub fn gain_db(&self, frequency: f32) -> f32 {
+        self.section.gain_db(frequency)
+    }
+
+    /// Filters a block of a stream of samples, continuing from the previous block.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = input.to_vec();
+        self.section.process_in_place(&mut output);
+        output
+    }
+
+    /// Filters a block of a stream of samples where they are, like [process](Self::process).
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        self.section.process_in_place(samples);
+    }
+
+    /// Clears the state, as if the filter had only seen zeros.
+    pub fn reset(&mut self) {
+        self.section.reset();
+    }
+}
+
+/// A first-order shelving filter that cuts the high frequencies of the received samples by as much as
+/// the [Preemphasis] of the same configuration boosted them, see [EmphasisConfig].
+///
+/// Over a channel which is flat, the pair leaves the signal as it was, and cuts the noise the channel adds
+/// at the high frequencies. Over a channel which rolls off on its own, the channel takes its place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deemphasis {
+    config: EmphasisConfig,
+    section: FirstOrderSection,
+}
+
+impl Deemphasis {
+    /// Creates a de-emphasis of the configuration.
+    ///
+    /// # Panics
+    /// If the corner is not between 0 and 0.5, or the boost is negative or not finite.
+    pub fn new(config: EmphasisConfig) -> Self {
+        let (zero, pole, gain) = design_shelf(&config);
+        Deemphasis {
+            config,
+            section: FirstOrderSection::new(1.0 / gain, -pole / gain, zero),
+        }
+    }
+
+    /// Returns the configuration of the filter.
+    pub fn get_config(&self) -> EmphasisConfig {
+        self.config
+    }
+
+    /// Returns the gain at a frequency, given as a fraction of the sample rate, in dB.
+    pub fn gain_db(&self, frequency: f32) -> f32 {
+        self.section.gain_db(frequency)
+    }
+
+    /// Filters a block of a stream of samples, continuing from the previous block.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = input.to_vec();
+        self.section.process_in_place(&mut output);
+        output
+    }
+
+    /// Filters a block of a stream of samples where they are, like [process](Self::process).
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        self.section.process_in_place(samples);
+    }
+
+    /// Clears the state, as if the filter had only seen zeros.
+    pub fn reset(&mut self) {
+        self.section.reset();
+    }
+}
+
+/// Returns the zero and the pole of the pre-emphasis of a configuration, and its gain for 0 dB at DC.
+///
+/// The zero sits at the corner, and the pole where the gain at the Nyquist frequency is the boost.
+fn design_shelf(config: &EmphasisConfig) -> (f32, f32, f32) {
+    if !(config.corner > 0.0 && config.corner < 0.5) {
+        panic!(
+            "Corner must be between 0 and 0.5 of the sample rate, but got {}",
+            config.corner
+        );
+    }
+    if !(config.boost_db >= 0.0 && config.boost_db.is_finite()) {
+        panic!(
+            "Boost must be finite and non-negative, but got {} dB",
+            config.boost_db
+        );
+    }
+    let zero = (-core::f32::consts::TAU * config.corner).exp();
+    // the gain at Nyquist over the one at DC is (1 + zero) (1 - pole) / ((1 - zero) (1 + pole))
+    let ratio = 10f32.powf(config.boost_db / 20.0) * (1.0 - zero) / (1.0 + zero);
+    let pole = (1.0 - ratio) / (1.0 + ratio);
+    (zero, pole, (1.0 - pole) / (1.0 - zero))
+}
+
+/// The first-order IIR filter `y[n] = b0 x[n] + b1 x[n - 1] + p y[n - 1]` of the emphasis filters.
+#[derive(Clone, Debug, PartialEq)]
+struct FirstOrderSection {
+    b0: f32,
+    b1: f32,
+    pole: f32,
+    /// The last input and output.
+    input: f32,
+    output: f32,
+}
+
+impl FirstOrderSection {
+    fn new(b0: f32, b1: f32, pole: f32) -> Self {
+        FirstOrderSection {
+            b0,
+            b1,
+            pole,
+            input: 0.0,
+            output: 0.0,
+        }
+    }
+
+    fn gain_db(&self, frequency: f32) -> f32 {
+        let z = Complex32::from_polar(1.0, -core::f32::consts::TAU * frequency);
+        let gain = (self.b0 + self.b1 * z) / (1.0 - self.pole * z);
+        20.0 * gain.norm().log10()
+    }
+
+    fn process_in_place(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            self.output = self.b0 * *sample + self.b1 * self.input + self.pole * self.output;
+            self.input = *sample;
+            *sample = self.output;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input = 0.0;
+        self.output = 0.0;
+    }
+}
+
+/// Returns the power of the samples at a frequency, given as a fraction of the sample rate,
+/// the squared magnitude of their DFT at that frequency, with the Goertzel algorithm.
+///
+/// A single frequency costs one multiplication per sample, without an FFT, and needs not be a bin of one.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::goertzel_power;
+///
+/// // a cosine of amplitude 1 over 200 samples has 100 in its bin, its power 10000
+/// let samples: Vec<f32> = (0..200).map(|n| (std::f32::consts::TAU * 0.05 * n as f32).cos()).collect();
+/// assert!((goertzel_power(&samples, 0.05) / 1e4 - 1.0).abs() < 1e-3);
+/// // and nothing in the others
+/// assert!(goertzel_power(&samples, 0.1) < 1e-2);
+/// ```
+pub fn goertzel_power(samples: &[f32], frequency: f32) -> f32 {
+    let coefficient = 2.0 * (core::f64::consts::TAU * frequency as f64).cos();
+    let (s1, s2) = samples.iter().fold((0.0, 0.0), |(s1, s2), &sample| {
+        (sample as f64 + coefficient * s1 - s2, s1)
+    });
+    (s1 * s1 + s2 * s2 - coefficient * s1 * s2) as f32
+}
+
+/// Returns the ideal low-pass impulse response around the center, tapered with a Kaiser window
+/// for the attenuation of the [Resampler] and the [Decimator].
+fn kaiser_sinc(cutoff: f64, length: usize, center: f64) -> Vec<f64> {
+    let beta = 0.1102 * (RESAMPLER_ATTENUATION - 8.7);
+    (0..length)
+        .map(|n| {
+            let t = n as f64 - center;
+            let sinc = if t == 0.0 {
+                2.0 * cutoff
+            } else {
+                (core::f64::consts::TAU * cutoff * t).sin() / (core::f64::consts::PI * t)
+            };
+            let x = t / center;
+            sinc * bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / bessel_i0(beta)
+        })
+        .collect()
+}
+
+pub(crate) fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns the modified Bessel function of the first kind and order zero, for the Kaiser window.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..50 {
+        term *= (x / (2.0 * k as f64)).powi(2);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
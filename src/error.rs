@@ -0,0 +1,95 @@
+//! This module provides the error type of the crate.
+
+use core::fmt::Display;
+
+/// Errors returned by the modem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModemError {
+    /// The header of a frame could not be decoded.
+    InvalidHeader,
+    /// The CRC of a decoded payload does not match, the payload is corrupted.
+    CrcMismatch,
+    /// The frame is shorter than announced by its header.
+    FrameTooShort { expected: usize, got: usize },
+    /// A code word has more errors than the code can correct.
+    TooManyErrors,
+    /// A serialized configuration could not be read.
+    InvalidConfig,
+    /// A buffer passed to a symbol does not have the length the modem expects, in samples, bytes or points.
+    BufferLength { expected: usize, got: usize },
+    /// A scratch was made by a modem of another configuration.
+    ScratchMismatch,
+    /// A buffer passed to a symbol does not have the length the modem expects, telling which one.
+    BufferLengthMismatch {
+        buffer: Buffer,
+        expected: usize,
+        got: usize,
+    },
+}
+
+impl ModemError {
+    /// Returns the name of the variant, the value of the `error` fields of the `tracing` events.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ModemError::InvalidHeader => "InvalidHeader",
+            ModemError::CrcMismatch => "CrcMismatch",
+            ModemError::FrameTooShort { .. } => "FrameTooShort",
+            ModemError::TooManyErrors => "TooManyErrors",
+            ModemError::InvalidConfig => "InvalidConfig",
+            ModemError::BufferLength { .. } => "BufferLength",
+            ModemError::ScratchMismatch => "ScratchMismatch",
+            ModemError::BufferLengthMismatch { .. } => "BufferLengthMismatch",
+        }
+    }
+}
+
+/// The buffer of a [ModemError::BufferLengthMismatch].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Buffer {
+    /// The input, like the data of a modulated symbol.
+    Input,
+    /// The output, like the samples of a modulated symbol.
+    Output,
+}
+
+impl Display for Buffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Buffer::Input => write!(f, "Input"),
+            Buffer::Output => write!(f, "Output"),
+        }
+    }
+}
+
+impl Display for ModemError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ModemError::InvalidHeader => write!(f, "Invalid frame header"),
+            ModemError::CrcMismatch => write!(f, "CRC mismatch, the payload is corrupted"),
+            ModemError::FrameTooShort { expected, got } => write!(
+                f,
+                "Frame is too short, expected {} samples, but got {} samples",
+                expected, got
+            ),
+            ModemError::TooManyErrors => write!(f, "Too many errors to correct"),
+            ModemError::InvalidConfig => write!(f, "Invalid serialized configuration"),
+            ModemError::BufferLength { expected, got } => {
+                write!(f, "Buffer length must be {}, but got {}", expected, got)
+            }
+            ModemError::ScratchMismatch => {
+                write!(f, "Scratch was made for another configuration")
+            }
+            ModemError::BufferLengthMismatch {
+                buffer,
+                expected,
+                got,
+            } => write!(
+                f,
+                "{} buffer length must be {}, but got {}",
+                buffer, expected, got
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ModemError {}
@@ -0,0 +1,604 @@
+//! This module provides a convolutional encoder and a Viterbi decoder.
+//!
+//! Use [ConvolutionalCode::k7_rate_half] for the standard K=7, rate 1/2 code with the (171, 133) octal polynomials,
+//! or [ConvolutionalCode::new] for any other set of polynomials.
+//!
+//! Bits are stored one per byte, see the [bits](crate::bits) module.
+//! The decoder accepts hard bits or soft log-likelihood ratios (LLRs), sharing the same trellis.
+//! For every input bit, the encoder emits one output bit per polynomial, in the order the polynomials were given.
+
+use alloc::{collections::VecDeque, format, string::String, vec, vec::Vec};
+
+use crate::bits::bits_to_llrs;
+
+/// A convolutional code, defined by its constraint length and generator polynomials.
+///
+/// The most significant bit of each polynomial (bit `constraint_length - 1`) taps the current input bit,
+/// the lower bits tap the previous inputs.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::convolutional::ConvolutionalCode;
+///
+/// let code = ConvolutionalCode::k7_rate_half();
+/// let bits = [1, 0, 1, 1, 0, 0, 1, 0, 1, 1, 1, 0, 0, 0, 1, 0];
+///
+/// let mut coded = code.encode(&bits);
+/// assert_eq!(coded.len(), code.get_encoded_length(bits.len()));
+///
+/// // flip a few well separated bits
+/// for i in [3, 17, 30] {
+///     coded[i] ^= 1;
+/// }
+///
+/// assert_eq!(code.decode(&coded), bits);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConvolutionalCode {
+    constraint_length: u32,
+    polynomials: Vec<u32>,
+    /// Packed output bits for every `state * 2 + input_bit`, polynomial `i` in bit `i`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    outputs: Vec<u32>,
+}
+
+impl ConvolutionalCode {
+    /// Creates a new convolutional code.
+    ///
+    /// # Panics
+    /// If the constraint length is not between 2 and 16,
+    /// or if not between 1 and 8 polynomials are given.
+    pub fn new(constraint_length: u32, polynomials: &[u32]) -> Self {
+        ConvolutionalCode::try_new(constraint_length, polynomials)
+            .unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    /// Creates a new convolutional code, or returns why the parameters are invalid.
+    fn try_new(constraint_length: u32, polynomials: &[u32]) -> Result<Self, String> {
+        if !(2..=16).contains(&constraint_length) {
+            return Err(format!(
+                "Constraint length must be between 2 and 16, but got {}",
+                constraint_length
+            ));
+        }
+        if polynomials.is_empty() || polynomials.len() > 8 {
+            return Err(format!(
+                "Between 1 and 8 polynomials are supported, but got {}",
+                polynomials.len()
+            ));
+        }
+
+        let num_states = 1usize << (constraint_length - 1);
+        let mut outputs = vec![0; 2 * num_states];
+        for state in 0..num_states {
+            for bit in 0..2 {
+                let register = ((bit << (constraint_length - 1)) | state) as u32;
+                outputs[2 * state + bit] =
+                    polynomials
+                        .iter()
+                        .enumerate()
+                        .fold(0, |output, (i, &polynomial)| {
+                            output | (((register & polynomial).count_ones() & 1) << i)
+                        });
+            }
+        }
+
+        Ok(ConvolutionalCode {
+            constraint_length,
+            polynomials: polynomials.to_vec(),
+            outputs,
+        })
+    }
+
+    /// The industry standard K=7, rate 1/2 code with the generator polynomials 171 and 133 (octal).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::convolutional::ConvolutionalCode;
+    ///
+    /// let code = ConvolutionalCode::k7_rate_half();
+    ///
+    /// // the impulse response are the interleaved polynomials, 171 = 1111001 and 133 = 1011011
+    /// assert_eq!(
+    ///     code.encode(&[1]),
+    ///     vec![1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1]
+    /// );
+    /// ```
+    pub fn k7_rate_half() -> Self {
+        ConvolutionalCode::new(7, &[0o171, 0o133])
+    }
+
+    /// Returns the constraint length of the code.
+    pub fn constraint_length(&self) -> u32 {
+        self.constraint_length
+    }
+
+    /// Returns the generator polynomials of the code.
+    pub fn polynomials(&self) -> &[u32] {
+        &self.polynomials
+    }
+
+    /// Returns the number of output bits per input bit.
+    pub fn num_outputs(&self) -> usize {
+        self.polynomials.len()
+    }
+
+    /// Returns the number of coded bits for `num_bits` input bits, including the tail bits.
+    pub fn get_encoded_length(&self, num_bits: usize) -> usize {
+        (num_bits + self.num_tail_bits()) * self.num_outputs()
+    }
+
+    /// Encodes the bits, terminating the trellis with `constraint_length - 1` zero tail bits.
+    pub fn encode(&self, bits: &[u8]) -> Vec<u8> {
+        let mut encoder = ConvolutionalEncoder::new(self.clone());
+        let mut coded = encoder.push(bits);
+        coded.extend(encoder.finish());
+        coded
+    }
+
+    /// Decodes hard bits of a terminated code word back into the input bits.
+    ///
+    /// The tail bits are removed. Trailing coded bits that do not form a complete output word are ignored.
+    pub fn decode(&self, coded: &[u8]) -> Vec<u8> {
+        // unit LLRs order the paths exactly like the Hamming distance does
+        self.decode_soft(&bits_to_llrs(coded))
+    }
+
+    /// Decodes log-likelihood ratios of a terminated code word back into the input bits.
+    ///
+    /// Positive LLRs favour a `0` bit, negative ones a `1`, and zero marks an erased bit.
+    /// This is the same convention as the [soft QAM demapper](crate::qam::QAMModem::demodulate_soft).
+    /// The tail bits are removed. Trailing LLRs that do not form a complete output word are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::convolutional::ConvolutionalCode;
+    ///
+    /// let code = ConvolutionalCode::k7_rate_half();
+    /// let bits = [0, 1, 1, 0, 1, 0, 0, 1];
+    ///
+    /// let mut llrs: Vec<f32> = code
+    ///     .encode(&bits)
+    ///     .iter()
+    ///     .map(|&bit| if bit == 0 { 2.0 } else { -2.0 })
+    ///     .collect();
+    ///
+    /// // weakly wrong and erased values are outvoted by the confident neighbours
+    /// llrs[2] = -0.3;
+    /// llrs[3] = 0.0;
+    /// llrs[9] = 0.0;
+    ///
+    /// assert_eq!(code.decode_soft(&llrs), bits);
+    /// ```
+    pub fn decode_soft(&self, llrs: &[f32]) -> Vec<u8> {
+        let num_outputs = self.num_outputs();
+        let num_steps = llrs.len() / num_outputs;
+
+        let mut bits = self.viterbi(num_steps, true, |step, branch_metrics| {
+            soft_branch_metrics(
+                &llrs[step * num_outputs..(step + 1) * num_outputs],
+                branch_metrics,
+            );
+        });
+        bits.truncate(num_steps.saturating_sub(self.num_tail_bits()));
+        bits
+    }
+
+    /// Returns the a posteriori LLRs of the coded bits of a terminated code word, given their LLRs.
+    ///
+    /// This is the max-log BCJR algorithm: the LLR of a coded bit compares the best path through the trellis
+    /// with the bit `1` to the best one with the bit `0`, in the convention of [decode_soft](Self::decode_soft).
+    /// What the code adds to the LLR of a bit, its extrinsic LLR, is the posterior minus the LLR given,
+    /// which an [iterative](crate::iterative) receiver feeds back into the demapper.
+    /// Returns one LLR per coded bit of the complete output words.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::convolutional::ConvolutionalCode;
+    ///
+    /// let code = ConvolutionalCode::k7_rate_half();
+    /// let bits = [1, 1, 0, 1, 0, 0, 1, 0];
+    /// let coded = code.encode(&bits);
+    ///
+    /// let mut llrs: Vec<f32> = coded.iter().map(|&bit| if bit == 0 { 1.0 } else { -1.0 }).collect();
+    /// // a wrong and an erased bit
+    /// llrs[5] = -llrs[5];
+    /// llrs[8] = 0.0;
+    ///
+    /// // the code restores both, and is surer of every bit than its LLR alone
+    /// let posterior = code.decode_posterior(&llrs);
+    /// assert_eq!(posterior.len(), coded.len());
+    /// for (posterior, &bit) in posterior.iter().zip(&coded) {
+    ///     assert_eq!(*posterior < 0.0, bit == 1);
+    ///     assert!(posterior.abs() > 1.0);
+    /// }
+    /// ```
+    pub fn decode_posterior(&self, llrs: &[f32]) -> Vec<f32> {
+        let num_outputs = self.num_outputs();
+        let num_steps = llrs.len() / num_outputs;
+        let num_states = self.num_states();
+        let num_words = 1 << num_outputs;
+        let input_shift = self.constraint_length - 2;
+
+        // the branch metrics cost twice a path's sum of the LLRs of its 1 bits, minus the sum of all
+        let mut branch_metrics = vec![0.0; num_steps * num_words];
+        for (llrs, branch_metrics) in llrs
+            .chunks_exact(num_outputs)
+            .zip(branch_metrics.chunks_exact_mut(num_words))
+        {
+            soft_branch_metrics(llrs, branch_metrics);
+        }
+
+        // the best metric of a path from the start in the zero state to every state of every step
+        let mut forward = vec![f32::INFINITY; (num_steps + 1) * num_states];
+        forward[0] = 0.0;
+        for step in 0..num_steps {
+            let (before, after) = forward.split_at_mut((step + 1) * num_states);
+            let before = &before[step * num_states..];
+            for (state, &metric) in before.iter().enumerate() {
+                for bit in 0..2 {
+                    let next = (bit << input_shift) | (state >> 1);
+                    let word = self.outputs[2 * state + bit] as usize;
+                    let metric = metric + branch_metrics[step * num_words + word];
+                    after[next] = after[next].min(metric);
+                }
+            }
+        }
+
+        // and backwards from the end in the zero state, comparing the best paths through every branch
+        let mut posterior = vec![0.0; num_steps * num_outputs];
+        let mut backward = vec![f32::INFINITY; num_states];
+        backward[0] = 0.0;
+        let mut earlier = vec![0.0; num_states];
+        let mut best = vec![(f32::INFINITY, f32::INFINITY); num_outputs];
+        for step in (0..num_steps).rev() {
+            best.fill((f32::INFINITY, f32::INFINITY));
+            for (state, earlier) in earlier.iter_mut().enumerate() {
+                *earlier = f32::INFINITY;
+                for bit in 0..2 {
+                    let next = (bit << input_shift) | (state >> 1);
+                    let word = self.outputs[2 * state + bit] as usize;
+                    let metric = branch_metrics[step * num_words + word] + backward[next];
+                    *earlier = earlier.min(metric);
+                    let metric = forward[step * num_states + state] + metric;
+                    for (i, (zero, one)) in best.iter_mut().enumerate() {
+                        if (word >> i) & 1 == 1 {
+                            *one = one.min(metric);
+                        } else {
+                            *zero = zero.min(metric);
+                        }
+                    }
+                }
+            }
+            for (posterior, (zero, one)) in posterior[step * num_outputs..].iter_mut().zip(&best) {
+                *posterior = (one - zero) / 2.0;
+            }
+            core::mem::swap(&mut backward, &mut earlier);
+        }
+        posterior
+    }
+
+    fn num_states(&self) -> usize {
+        1 << (self.constraint_length - 1)
+    }
+
+    fn num_tail_bits(&self) -> usize {
+        (self.constraint_length - 1) as usize
+    }
+
+    /// Runs the Viterbi algorithm over `num_steps` trellis steps.
+    ///
+    /// `fill_branch_metrics` gets the step index and fills the cost of every possible output word,
+    /// lower is better. If `terminated`, traceback starts at the zero state, otherwise at the best one.
+    pub(crate) fn viterbi(
+        &self,
+        num_steps: usize,
+        terminated: bool,
+        mut fill_branch_metrics: impl FnMut(usize, &mut [f32]),
+    ) -> Vec<u8> {
+        let num_states = self.num_states();
+
+        let mut path_metrics = vec![f32::INFINITY; num_states];
+        path_metrics[0] = 0.0;
+        let mut next_metrics = vec![0.0; num_states];
+        let mut branch_metrics = vec![0.0; 1 << self.num_outputs()];
+        let mut decisions = vec![0; num_steps * num_states];
+
+        for (step, step_decisions) in decisions.chunks_exact_mut(num_states).enumerate() {
+            fill_branch_metrics(step, &mut branch_metrics);
+            self.add_compare_select(
+                &path_metrics,
+                &branch_metrics,
+                &mut next_metrics,
+                step_decisions,
+            );
+            core::mem::swap(&mut path_metrics, &mut next_metrics);
+        }
+
+        let state = if terminated {
+            0
+        } else {
+            best_state(&path_metrics)
+        };
+
+        let mut bits = vec![0; num_steps];
+        self.traceback(decisions.chunks_exact(num_states).rev(), state, |i, bit| {
+            bits[num_steps - 1 - i] = bit
+        });
+        bits
+    }
+
+    fn add_compare_select(
+        &self,
+        path_metrics: &[f32],
+        branch_metrics: &[f32],
+        next_metrics: &mut [f32],
+        decisions: &mut [u8],
+    ) {
+        let state_mask = self.num_states() - 1;
+        let input_shift = self.constraint_length - 2;
+
+        for (next_state, (metric, decision)) in next_metrics
+            .iter_mut()
+            .zip(decisions.iter_mut())
+            .enumerate()
+        {
+            let bit = next_state >> input_shift;
+            let previous = (next_state << 1) & state_mask;
+
+            let metric_0 =
+                path_metrics[previous] + branch_metrics[self.outputs[2 * previous + bit] as usize];
+            let metric_1 = path_metrics[previous | 1]
+                + branch_metrics[self.outputs[2 * (previous | 1) + bit] as usize];
+
+            if metric_1 < metric_0 {
+                *metric = metric_1;
+                *decision = 1;
+            } else {
+                *metric = metric_0;
+                *decision = 0;
+            }
+        }
+    }
+
+    /// Walks the decisions backwards from `state`, calling `emit` with the step count from the end and the decoded bit.
+    fn traceback<'a>(
+        &self,
+        decisions: impl Iterator<Item = &'a [u8]>,
+        mut state: usize,
+        mut emit: impl FnMut(usize, u8),
+    ) {
+        let state_mask = self.num_states() - 1;
+        let input_shift = self.constraint_length - 2;
+
+        for (i, step_decisions) in decisions.enumerate() {
+            emit(i, (state >> input_shift) as u8);
+            state = ((state << 1) & state_mask) | step_decisions[state] as usize;
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConvolutionalCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            constraint_length: u32,
+            polynomials: Vec<u32>,
+        }
+
+        let Fields {
+            constraint_length,
+            polynomials,
+        } = Fields::deserialize(deserializer)?;
+        ConvolutionalCode::try_new(constraint_length, &polynomials)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A streaming convolutional encoder, keeping the register state between calls.
+pub struct ConvolutionalEncoder {
+    code: ConvolutionalCode,
+    state: usize,
+}
+
+impl ConvolutionalEncoder {
+    /// Creates a new encoder starting in the zero state.
+    pub fn new(code: ConvolutionalCode) -> Self {
+        ConvolutionalEncoder { code, state: 0 }
+    }
+
+    /// Encodes the next bits of the stream.
+    pub fn push(&mut self, bits: &[u8]) -> Vec<u8> {
+        let num_outputs = self.code.num_outputs();
+        let input_shift = self.code.constraint_length - 2;
+
+        let mut coded = Vec::with_capacity(bits.len() * num_outputs);
+        for &bit in bits {
+            let bit = (bit & 1) as usize;
+            let output = self.code.outputs[2 * self.state + bit];
+            coded.extend((0..num_outputs).map(|i| ((output >> i) & 1) as u8));
+            self.state = (bit << input_shift) | (self.state >> 1);
+        }
+        coded
+    }
+
+    /// Terminates the stream with the zero tail bits and resets the encoder.
+    pub fn finish(&mut self) -> Vec<u8> {
+        let tail = vec![0; self.code.num_tail_bits()];
+        self.push(&tail)
+    }
+}
+
+/// A streaming Viterbi decoder with a fixed traceback depth, accepting hard bits or LLRs.
+///
+/// Decoded bits are delayed by the traceback depth.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::convolutional::{ConvolutionalCode, ConvolutionalEncoder, ViterbiDecoder};
+///
+/// let code = ConvolutionalCode::k7_rate_half();
+/// let mut encoder = ConvolutionalEncoder::new(code.clone());
+/// let mut decoder = ViterbiDecoder::new(code, 35);
+///
+/// let bits: Vec<u8> = (0..200).map(|i| ((i * 7 + i / 3) % 2) as u8).collect();
+///
+/// let mut decoded = Vec::new();
+/// for chunk in bits.chunks(30) {
+///     decoded.extend(decoder.push(&encoder.push(chunk)));
+/// }
+/// decoded.extend(decoder.push(&encoder.finish()));
+/// decoded.extend(decoder.finish());
+///
+/// assert_eq!(decoded, bits);
+/// ```
+pub struct ViterbiDecoder {
+    code: ConvolutionalCode,
+    traceback_depth: usize,
+    path_metrics: Vec<f32>,
+    next_metrics: Vec<f32>,
+    branch_metrics: Vec<f32>,
+    decisions: VecDeque<Vec<u8>>,
+    spare_decisions: Option<Vec<u8>>,
+    pending: Vec<f32>,
+}
+
+impl ViterbiDecoder {
+    /// Creates a new streaming decoder. A traceback depth of about five constraint lengths is usual.
+    ///
+    /// # Panics
+    /// If the traceback depth is shorter than the number of tail bits (`constraint_length - 1`).
+    pub fn new(code: ConvolutionalCode, traceback_depth: usize) -> Self {
+        if traceback_depth < code.num_tail_bits() {
+            panic!(
+                "Traceback depth must be at least {}, but got {}",
+                code.num_tail_bits(),
+                traceback_depth
+            );
+        }
+
+        let num_states = code.num_states();
+        let mut path_metrics = vec![f32::INFINITY; num_states];
+        path_metrics[0] = 0.0;
+
+        ViterbiDecoder {
+            next_metrics: vec![0.0; num_states],
+            branch_metrics: vec![0.0; 1 << code.num_outputs()],
+            code,
+            traceback_depth,
+            path_metrics,
+            decisions: VecDeque::with_capacity(traceback_depth + 1),
+            spare_decisions: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decodes the next hard coded bits of the stream, returning the bits that left the traceback window.
+    pub fn push(&mut self, coded: &[u8]) -> Vec<u8> {
+        self.push_soft(&bits_to_llrs(coded))
+    }
+
+    /// Decodes the next LLRs of the stream, returning the bits that left the traceback window.
+    ///
+    /// See [ConvolutionalCode::decode_soft] for the LLR convention.
+    pub fn push_soft(&mut self, llrs: &[f32]) -> Vec<u8> {
+        let num_outputs = self.code.num_outputs();
+        self.pending.extend_from_slice(llrs);
+
+        let mut bits = Vec::new();
+        let num_words = self.pending.len() / num_outputs;
+        for word in 0..num_words {
+            soft_branch_metrics(
+                &self.pending[word * num_outputs..(word + 1) * num_outputs],
+                &mut self.branch_metrics,
+            );
+            self.step();
+
+            if self.decisions.len() > self.traceback_depth {
+                bits.push(self.oldest_bit());
+                self.spare_decisions = self.decisions.pop_front();
+            }
+        }
+        self.pending.drain(..num_words * num_outputs);
+
+        bits
+    }
+
+    /// Ends a terminated stream, returning the remaining bits without the tail, and resets the decoder.
+    pub fn finish(&mut self) -> Vec<u8> {
+        let num_remaining = self.decisions.len();
+        let mut bits = vec![0; num_remaining];
+        self.code.traceback(
+            self.decisions.iter().rev().map(Vec::as_slice),
+            0,
+            |i, bit| bits[num_remaining - 1 - i] = bit,
+        );
+        bits.truncate(num_remaining.saturating_sub(self.code.num_tail_bits()));
+
+        self.decisions.clear();
+        self.pending.clear();
+        self.path_metrics.fill(f32::INFINITY);
+        self.path_metrics[0] = 0.0;
+
+        bits
+    }
+
+    fn step(&mut self) {
+        let mut decisions = self
+            .spare_decisions
+            .take()
+            .unwrap_or_else(|| vec![0; self.code.num_states()]);
+        self.code.add_compare_select(
+            &self.path_metrics,
+            &self.branch_metrics,
+            &mut self.next_metrics,
+            &mut decisions,
+        );
+        self.decisions.push_back(decisions);
+        core::mem::swap(&mut self.path_metrics, &mut self.next_metrics);
+
+        // keep the metrics small, only their differences matter
+        let min = self.path_metrics[best_state(&self.path_metrics)];
+        for metric in self.path_metrics.iter_mut() {
+            *metric -= min;
+        }
+    }
+
+    fn oldest_bit(&self) -> u8 {
+        let mut oldest = 0;
+        self.code.traceback(
+            self.decisions.iter().rev().map(Vec::as_slice),
+            best_state(&self.path_metrics),
+            |_, bit| oldest = bit,
+        );
+        oldest
+    }
+}
+
+/// Fills the cost of every output word, the sum of the LLRs disagreeing with it, minus the agreeing ones.
+fn soft_branch_metrics(llrs: &[f32], branch_metrics: &mut [f32]) {
+    for (expected, metric) in branch_metrics.iter_mut().enumerate() {
+        *metric = llrs
+            .iter()
+            .enumerate()
+            .map(|(i, llr)| {
+                if (expected >> i) & 1 == 1 {
+                    *llr
+                } else {
+                    -*llr
+                }
+            })
+            .sum();
+    }
+}
+
+fn best_state(path_metrics: &[f32]) -> usize {
+    path_metrics
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(state, _)| state)
+        .unwrap_or(0)
+}
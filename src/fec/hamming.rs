@@ -0,0 +1,156 @@
+//! This module provides the Hamming(7, 4) code and the extended Hamming(8, 4) code.
+//!
+//! Hamming codes correct a single bit error in every code word, at a fraction of the cost of the convolutional code,
+//! which makes them a good fit for short control data like frame headers.
+//! The extended code adds an overall parity bit, which additionally detects double bit errors (SECDED).
+//!
+//! Code words carry the bits `p1 p2 d1 p4 d2 d3 d4`, followed by the overall parity bit for the extended code.
+
+use alloc::vec::Vec;
+
+use crate::error::ModemError;
+
+/// Number of data bits per code word.
+const DATA_BITS: usize = 4;
+
+/// A Hamming code.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::hamming::HammingCode;
+///
+/// let code = HammingCode::Hamming74;
+/// assert_eq!(code.encode(&[1, 0, 1, 1]), vec![0, 1, 1, 0, 0, 1, 1]);
+///
+/// // every single bit error in every code word is corrected
+/// for data in 0..16u8 {
+///     let bits: Vec<u8> = (0..4).rev().map(|i| (data >> i) & 1).collect();
+///     let codeword = code.encode(&bits);
+///     for i in 0..7 {
+///         let mut received = codeword.clone();
+///         received[i] ^= 1;
+///         assert_eq!(code.decode(&received).unwrap(), bits);
+///     }
+/// }
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HammingCode {
+    /// Hamming(7, 4), correcting single bit errors.
+    Hamming74,
+    /// Extended Hamming(8, 4), correcting single and detecting double bit errors.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::hamming::HammingCode;
+    ///
+    /// let code = HammingCode::ExtendedHamming84;
+    /// assert_eq!(code.encode(&[1, 0, 1, 1]), vec![0, 1, 1, 0, 0, 1, 1, 0]);
+    ///
+    /// for data in 0..16u8 {
+    ///     let bits: Vec<u8> = (0..4).rev().map(|i| (data >> i) & 1).collect();
+    ///     let codeword = code.encode(&bits);
+    ///     for i in 0..8 {
+    ///         let mut received = codeword.clone();
+    ///         received[i] ^= 1;
+    ///         assert_eq!(code.decode(&received).unwrap(), bits);
+    ///
+    ///         // every double bit error is detected
+    ///         for j in i + 1..8 {
+    ///             let mut received = received.clone();
+    ///             received[j] ^= 1;
+    ///             assert!(code.decode(&received).is_err());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[default]
+    ExtendedHamming84,
+}
+
+impl HammingCode {
+    /// Returns the number of bits per code word.
+    pub fn codeword_length(&self) -> usize {
+        match self {
+            HammingCode::Hamming74 => 7,
+            HammingCode::ExtendedHamming84 => 8,
+        }
+    }
+
+    /// Returns the number of coded bits for `num_bits` data bits.
+    ///
+    /// # Panics
+    /// If `num_bits` is not a multiple of 4.
+    pub fn get_encoded_length(&self, num_bits: usize) -> usize {
+        if !num_bits.is_multiple_of(DATA_BITS) {
+            panic!(
+                "Number of bits must be a multiple of {}, but got {}",
+                DATA_BITS, num_bits
+            );
+        }
+        num_bits / DATA_BITS * self.codeword_length()
+    }
+
+    /// Encodes the bits, 4 data bits per code word.
+    ///
+    /// # Panics
+    /// If the number of bits is not a multiple of 4.
+    pub fn encode(&self, bits: &[u8]) -> Vec<u8> {
+        let mut coded = Vec::with_capacity(self.get_encoded_length(bits.len()));
+        for data in bits.chunks(DATA_BITS) {
+            let (d1, d2, d3, d4) = (data[0] & 1, data[1] & 1, data[2] & 1, data[3] & 1);
+            let codeword = [d1 ^ d2 ^ d4, d1 ^ d3 ^ d4, d1, d2 ^ d3 ^ d4, d2, d3, d4];
+            coded.extend(codeword);
+            if *self == HammingCode::ExtendedHamming84 {
+                coded.push(codeword.iter().fold(0, |parity, bit| parity ^ bit));
+            }
+        }
+        coded
+    }
+
+    /// Decodes the code words into the data bits, correcting single bit errors.
+    ///
+    /// # Panics
+    /// If the number of bits is not a multiple of the code word length.
+    ///
+    /// # Errors
+    /// [ModemError::TooManyErrors] if the extended code detects a double bit error.
+    pub fn decode(&self, coded: &[u8]) -> Result<Vec<u8>, ModemError> {
+        let codeword_length = self.codeword_length();
+        if !coded.len().is_multiple_of(codeword_length) {
+            panic!(
+                "Number of coded bits must be a multiple of {}, but got {}",
+                codeword_length,
+                coded.len()
+            );
+        }
+
+        let mut bits = Vec::with_capacity(coded.len() / codeword_length * DATA_BITS);
+        for received in coded.chunks(codeword_length) {
+            let mut codeword = [0; 7];
+            codeword
+                .iter_mut()
+                .zip(received)
+                .for_each(|(bit, received)| *bit = received & 1);
+
+            // the syndrome is the position of a single error, counting from 1
+            let syndrome = (1..=7)
+                .filter(|position| codeword[position - 1] == 1)
+                .fold(0, |syndrome, position| syndrome ^ position);
+
+            if *self == HammingCode::ExtendedHamming84 {
+                let parity = received.iter().fold(0, |parity, bit| parity ^ (bit & 1));
+                // an even number of errors with a non-zero syndrome can not be corrected
+                if parity == 0 && syndrome != 0 {
+                    return Err(ModemError::TooManyErrors);
+                }
+            }
+
+            if syndrome != 0 {
+                codeword[syndrome - 1] ^= 1;
+            }
+            bits.extend([codeword[2], codeword[4], codeword[5], codeword[6]]);
+        }
+        Ok(bits)
+    }
+}
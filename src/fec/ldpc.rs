@@ -0,0 +1,343 @@
+//! This module provides the quasi-cyclic LDPC codes of 802.11n with a code word length of 648 bits.
+//!
+//! The parity check matrix is built from a base matrix of 12×24 (rate 1/2) or 6×24 (rate 3/4) entries.
+//! Every entry is either a zero block or the 27×27 identity matrix cyclically shifted by the entry.
+//! The dual-diagonal parity part allows encoding directly from the parity check matrix.
+//!
+//! The [LdpcDecoder] runs layered, normalized min-sum belief propagation on the LLRs of the demapper.
+//!
+//! This module is only available with the `ldpc` feature.
+
+use smart_default::SmartDefault;
+
+/// Size of the cyclically shifted identity blocks.
+const LIFTING_SIZE: usize = 27;
+
+/// Number of block columns of the base matrices.
+const BASE_COLUMNS: usize = 24;
+
+/// Marks a zero block in the base matrices.
+const ZERO_BLOCK: i8 = -1;
+
+#[rustfmt::skip]
+const BASE_MATRIX_648_RATE_HALF: [[i8; BASE_COLUMNS]; 12] = [
+    [ 0, -1, -1, -1,  0,  0, -1, -1,  0, -1, -1,  0,  1,  0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [22,  0, -1, -1, 17, -1,  0,  0, 12, -1, -1, -1, -1,  0,  0, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [ 6, -1,  0, -1, 10, -1, -1, -1, 24, -1,  0, -1, -1, -1,  0,  0, -1, -1, -1, -1, -1, -1, -1, -1],
+    [ 2, -1, -1,  0, 20, -1, -1, -1, 25,  0, -1, -1, -1, -1, -1,  0,  0, -1, -1, -1, -1, -1, -1, -1],
+    [23, -1, -1, -1,  3, -1, -1, -1,  0, -1,  9, 11, -1, -1, -1, -1,  0,  0, -1, -1, -1, -1, -1, -1],
+    [24, -1, 23,  1, 17, -1,  3, -1, 10, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1, -1, -1, -1, -1],
+    [25, -1, -1, -1,  8, -1, -1, -1,  7, 18, -1, -1,  0, -1, -1, -1, -1, -1,  0,  0, -1, -1, -1, -1],
+    [13, 24, -1, -1,  0, -1,  8, -1,  6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1, -1, -1],
+    [ 7, 20, -1, 16, 22, 10, -1, -1, 23, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1, -1],
+    [11, -1, -1, -1, 19, -1, -1, -1, 13, -1,  3, 17, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1],
+    [25, -1,  8, -1, 23, 18, -1, 14,  9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0],
+    [ 3, -1, -1, -1, 16, -1, -1,  2, 25,  5, -1, -1,  1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0],
+];
+
+#[rustfmt::skip]
+const BASE_MATRIX_648_RATE_THREE_QUARTERS: [[i8; BASE_COLUMNS]; 6] = [
+    [16, 17, 22, 24,  9,  3, 14, -1,  4,  2,  7, -1, 26, -1,  2, -1, 21, -1,  1,  0, -1, -1, -1, -1],
+    [25, 12, 12,  3,  3, 26,  6, 21, -1, 15, 22, -1, 15, -1,  4, -1, -1, 16, -1,  0,  0, -1, -1, -1],
+    [25, 18, 26, 16, 22, 23,  9, -1,  0, -1,  4, -1,  4, -1,  8, 23, 11, -1, -1, -1,  0,  0, -1, -1],
+    [ 9,  7,  0,  1, 17, -1, -1,  7,  3, -1,  3, 23, -1, 16, -1, -1, 21, -1,  0, -1, -1,  0,  0, -1],
+    [24,  5, 26,  7,  1, -1, -1, 15, 24, 15, -1,  8, -1, 13, -1, 13, -1, 11, -1, -1, -1, -1,  0,  0],
+    [ 2,  2, 19, 14, 24,  1, 15, 19, -1, 21, -1,  2, -1, 24, -1,  3, -1,  2,  1, -1, -1, -1, -1,  0],
+];
+
+/// A quasi-cyclic LDPC code.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::ldpc::LdpcCode;
+///
+/// let code = LdpcCode::wifi_648_rate_half();
+/// assert_eq!((code.n(), code.k()), (648, 324));
+///
+/// let message: Vec<u8> = (0..324).map(|i| ((i * 7) % 3 == 0) as u8).collect();
+/// let codeword = code.encode(&message);
+/// assert!(code.check(&codeword));
+/// assert_eq!(&codeword[..324], message);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LdpcCode {
+    base_matrix: Vec<[i8; BASE_COLUMNS]>,
+    /// Variable nodes connected to each check node.
+    checks: Vec<Vec<usize>>,
+}
+
+impl LdpcCode {
+    fn new(base_matrix: &[[i8; BASE_COLUMNS]]) -> Self {
+        let checks = base_matrix
+            .iter()
+            .flat_map(|row| {
+                (0..LIFTING_SIZE).map(move |r| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, shift)| **shift != ZERO_BLOCK)
+                        .map(|(column, &shift)| {
+                            column * LIFTING_SIZE + (r + shift as usize) % LIFTING_SIZE
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+
+        LdpcCode {
+            base_matrix: base_matrix.to_vec(),
+            checks,
+        }
+    }
+
+    /// The rate 1/2 code of 802.11n with 648 bit code words.
+    pub fn wifi_648_rate_half() -> Self {
+        LdpcCode::new(&BASE_MATRIX_648_RATE_HALF)
+    }
+
+    /// The rate 3/4 code of 802.11n with 648 bit code words.
+    pub fn wifi_648_rate_three_quarters() -> Self {
+        LdpcCode::new(&BASE_MATRIX_648_RATE_THREE_QUARTERS)
+    }
+
+    /// Returns the code word length in bits.
+    pub fn n(&self) -> usize {
+        BASE_COLUMNS * LIFTING_SIZE
+    }
+
+    /// Returns the message length in bits.
+    pub fn k(&self) -> usize {
+        self.n() - self.num_parity()
+    }
+
+    /// Returns the number of parity bits, `n - k`.
+    pub fn num_parity(&self) -> usize {
+        self.base_matrix.len() * LIFTING_SIZE
+    }
+
+    /// Encodes the message into a systematic code word of `n` bits, the message followed by the parity bits.
+    ///
+    /// # Panics
+    /// If the message is not `k` bits long.
+    pub fn encode(&self, message: &[u8]) -> Vec<u8> {
+        if message.len() != self.k() {
+            panic!(
+                "Message length must be {} bits, but got {} bits",
+                self.k(),
+                message.len()
+            );
+        }
+
+        let info_columns = self.k() / LIFTING_SIZE;
+        let shifted =
+            |block: &[u8], shift: i8, r: usize| block[(r + shift as usize) % LIFTING_SIZE];
+
+        // check sums of the message part, one block per base row
+        let sums: Vec<Vec<u8>> = self
+            .base_matrix
+            .iter()
+            .map(|row| {
+                (0..LIFTING_SIZE)
+                    .map(|r| {
+                        row[..info_columns]
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, shift)| **shift != ZERO_BLOCK)
+                            .fold(0, |sum, (column, &shift)| {
+                                sum ^ shifted(
+                                    &message[column * LIFTING_SIZE..(column + 1) * LIFTING_SIZE],
+                                    shift,
+                                    r,
+                                )
+                            })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // the shifts of the first parity column add up to the identity, so the first parity block is the sum of all
+        let first: Vec<u8> = (0..LIFTING_SIZE)
+            .map(|r| sums.iter().fold(0, |sum, block| sum ^ block[r]))
+            .collect();
+
+        // the dual diagonal gives the other parity blocks row by row
+        let mut parity = vec![first];
+        for (i, row) in self.base_matrix[..self.base_matrix.len() - 1]
+            .iter()
+            .enumerate()
+        {
+            let previous = if i == 0 { None } else { parity.last() };
+            let shift = row[info_columns];
+            let next = (0..LIFTING_SIZE)
+                .map(|r| {
+                    let mut bit = sums[i][r];
+                    if shift != ZERO_BLOCK {
+                        bit ^= shifted(&parity[0], shift, r);
+                    }
+                    if let Some(previous) = previous {
+                        bit ^= previous[r];
+                    }
+                    bit
+                })
+                .collect();
+            parity.push(next);
+        }
+
+        let mut codeword = message.to_vec();
+        codeword.extend(parity.into_iter().flatten());
+        codeword
+    }
+
+    /// Returns whether all parity checks hold for the code word.
+    pub fn check(&self, codeword: &[u8]) -> bool {
+        self.checks.iter().all(|variables| {
+            variables
+                .iter()
+                .fold(0, |parity, &variable| parity ^ (codeword[variable] & 1))
+                == 0
+        })
+    }
+}
+
+/// Configuration of the [LdpcDecoder].
+#[derive(SmartDefault, Copy, Clone, Debug)]
+pub struct LdpcDecoderConfig {
+    /// Maximum number of belief propagation iterations.
+    #[default(50)]
+    pub max_iterations: usize,
+    /// Stop as soon as all parity checks hold.
+    #[default(true)]
+    pub early_termination: bool,
+    /// Scaling of the check node messages, 1.0 is plain min-sum.
+    #[default(0.75)]
+    pub normalization: f32,
+}
+
+/// The result of decoding an LDPC code word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LdpcDecoded {
+    /// The decoded message bits.
+    pub bits: Vec<u8>,
+    /// Whether the decoded code word satisfies all parity checks.
+    pub converged: bool,
+    /// The number of iterations run.
+    pub iterations: usize,
+    /// The a posteriori LLRs of all `n` bits of the code word, minus the LLRs decoded are the extrinsic ones
+    /// an [iterative](crate::iterative) receiver feeds back into the demapper.
+    pub posterior: Vec<f32>,
+}
+
+/// Decodes LDPC code words with normalized min-sum belief propagation.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::bits_to_llrs;
+/// use software_modem::fec::ldpc::{LdpcCode, LdpcDecoder, LdpcDecoderConfig};
+///
+/// let code = LdpcCode::wifi_648_rate_three_quarters();
+/// let message: Vec<u8> = (0..code.k()).map(|i| (i % 5 == 1) as u8).collect();
+/// let mut llrs = bits_to_llrs(&code.encode(&message));
+///
+/// // flip a few bits and erase some more
+/// for i in [3, 100, 250, 400, 600] {
+///     llrs[i] = -llrs[i];
+/// }
+/// for llr in &mut llrs[500..520] {
+///     *llr = 0.0;
+/// }
+///
+/// let decoder = LdpcDecoder::new(code, LdpcDecoderConfig::default());
+/// let decoded = decoder.decode(&llrs);
+/// assert!(decoded.converged);
+/// assert_eq!(decoded.bits, message);
+/// ```
+pub struct LdpcDecoder {
+    code: LdpcCode,
+    config: LdpcDecoderConfig,
+}
+
+impl LdpcDecoder {
+    /// Creates a new decoder for the code.
+    pub fn new(code: LdpcCode, config: LdpcDecoderConfig) -> Self {
+        LdpcDecoder { code, config }
+    }
+
+    /// Returns the code of the decoder.
+    pub fn code(&self) -> &LdpcCode {
+        &self.code
+    }
+
+    /// Decodes the LLRs of a code word, positive values favouring `0`.
+    ///
+    /// # Panics
+    /// If the number of LLRs is not `n`.
+    pub fn decode(&self, llrs: &[f32]) -> LdpcDecoded {
+        let n = self.code.n();
+        if llrs.len() != n {
+            panic!("Number of LLRs must be {}, but got {}", n, llrs.len());
+        }
+
+        // check to variable messages, in the order of the variables of each check
+        let mut messages: Vec<Vec<f32>> = self
+            .code
+            .checks
+            .iter()
+            .map(|variables| vec![0.0; variables.len()])
+            .collect();
+        let mut posterior = llrs.to_vec();
+        let mut hard = vec![0; n];
+        let mut iterations = 0;
+        let mut converged = false;
+
+        while iterations < self.config.max_iterations {
+            iterations += 1;
+
+            for (variables, messages) in self.code.checks.iter().zip(messages.iter_mut()) {
+                // variable to check messages, leaving out the previous message of this check
+                let mut min1 = f32::INFINITY;
+                let mut min2 = f32::INFINITY;
+                let mut min_index = 0;
+                let mut sign = false;
+                for (i, (&variable, message)) in variables.iter().zip(messages.iter()).enumerate() {
+                    let incoming = posterior[variable] - message;
+                    sign ^= incoming < 0.0;
+                    let magnitude = incoming.abs();
+                    if magnitude < min1 {
+                        min2 = min1;
+                        min1 = magnitude;
+                        min_index = i;
+                    } else if magnitude < min2 {
+                        min2 = magnitude;
+                    }
+                }
+
+                for (i, (&variable, message)) in
+                    variables.iter().zip(messages.iter_mut()).enumerate()
+                {
+                    let incoming = posterior[variable] - *message;
+                    let magnitude = if i == min_index { min2 } else { min1 };
+                    let negative = sign ^ (incoming < 0.0);
+                    let outgoing =
+                        self.config.normalization * if negative { -magnitude } else { magnitude };
+                    posterior[variable] = incoming + outgoing;
+                    *message = outgoing;
+                }
+            }
+
+            hard.iter_mut()
+                .zip(&posterior)
+                .for_each(|(bit, llr)| *bit = u8::from(*llr < 0.0));
+            converged = self.code.check(&hard);
+            if converged && self.config.early_termination {
+                break;
+            }
+        }
+
+        hard.truncate(self.code.k());
+        LdpcDecoded {
+            bits: hard,
+            converged,
+            iterations,
+            posterior,
+        }
+    }
+}
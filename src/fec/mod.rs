@@ -0,0 +1,86 @@
+//! This module provides forward error correction (FEC) codes.
+//!
+//! The [convolutional] code with its Viterbi decoder protects the bits of a frame,
+//! and [puncture] raises its rate for good channels.
+//! The [rs] (Reed-Solomon) code works on bytes and cleans up the bursts the Viterbi decoder leaves behind.
+//! The [hamming] codes are a lightweight alternative for short data like frame headers,
+//! the [repetition] code a dead simple one for beacons, alone or repeating the bits of the convolutional code.
+//! With the `ldpc` feature, the `ldpc` module adds the LDPC codes of 802.11n for the highest performance.
+//!
+//! The [FecScheme] selects which of the codes protects the payload of a coded frame.
+
+pub mod convolutional;
+pub mod hamming;
+#[cfg(feature = "ldpc")]
+pub mod ldpc;
+pub mod puncture;
+pub mod repetition;
+pub mod rs;
+
+use core::fmt::Display;
+
+use puncture::CodeRate;
+
+/// The code protecting the payload of a coded frame.
+///
+/// All schemes take and produce bits, so switching between them is a single change of the frame configuration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FecScheme {
+    /// The [convolutional] code, punctured to the code rate.
+    Convolutional(CodeRate),
+    /// The [repetition](repetition::RepetitionCode) code, sending every bit the given number of times.
+    Repetition(usize),
+    /// The [convolutional] code, unpunctured, with its coded bits sent the given number of times one after another,
+    /// for frames far below the noise, like [beacons](crate::ofdm::profiles::robust_beacon).
+    ///
+    /// The receiver adds up the LLRs of the copies before the Viterbi decoder, which gains the coding gain
+    /// of the convolutional code on top of the repetitions.
+    RepeatedConvolutional(usize),
+    /// The 648 bit LDPC code of 802.11n, at rate 1/2 or 3/4.
+    #[cfg(feature = "ldpc")]
+    Ldpc(CodeRate),
+}
+
+impl Default for FecScheme {
+    fn default() -> Self {
+        FecScheme::Convolutional(CodeRate::Half)
+    }
+}
+
+impl Display for FecScheme {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FecScheme::Convolutional(rate) => write!(f, "convolutional, rate {}", rate),
+            FecScheme::Repetition(n) => write!(f, "repetition, rate 1/{}", n),
+            FecScheme::RepeatedConvolutional(n) => {
+                write!(f, "convolutional repeated {} times, rate 1/{}", n, 2 * n)
+            }
+            #[cfg(feature = "ldpc")]
+            FecScheme::Ldpc(rate) => write!(f, "LDPC, rate {}", rate),
+        }
+    }
+}
+
+impl FecScheme {
+    /// Returns the code rate, the number of data bits per coded bit, not counting termination and padding.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::FecScheme;
+    /// use software_modem::fec::puncture::CodeRate;
+    ///
+    /// assert_eq!(FecScheme::Convolutional(CodeRate::ThreeQuarters).rate(), 0.75);
+    /// assert_eq!(FecScheme::Repetition(4).rate(), 0.25);
+    /// assert_eq!(FecScheme::RepeatedConvolutional(4).rate(), 0.125);
+    /// ```
+    pub fn rate(&self) -> f32 {
+        match self {
+            FecScheme::Convolutional(rate) => rate.rate(),
+            FecScheme::Repetition(n) => 1.0 / *n as f32,
+            FecScheme::RepeatedConvolutional(n) => 0.5 / *n as f32,
+            #[cfg(feature = "ldpc")]
+            FecScheme::Ldpc(rate) => rate.rate(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! This module provides puncturing of the rate 1/2 convolutional code to higher code rates.
+//!
+//! Puncturing drops coded bits according to a repeating pattern after encoding.
+//! Before decoding, the dropped bits are re-inserted as erasures (LLRs of zero).
+//! The patterns are the ones used by 802.11 and DVB, applied to the interleaved output `A0 B0 A1 B1 ...`.
+
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// The code rate after puncturing the rate 1/2 mother code.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CodeRate {
+    /// No puncturing.
+    #[default]
+    Half,
+    TwoThirds,
+    ThreeQuarters,
+    FiveSixths,
+}
+
+impl Display for CodeRate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CodeRate::Half => write!(f, "1/2"),
+            CodeRate::TwoThirds => write!(f, "2/3"),
+            CodeRate::ThreeQuarters => write!(f, "3/4"),
+            CodeRate::FiveSixths => write!(f, "5/6"),
+        }
+    }
+}
+
+impl CodeRate {
+    /// Returns the puncturing pattern over the interleaved coded bits, `1` keeps a bit and `0` drops it.
+    pub fn pattern(&self) -> &'static [u8] {
+        match self {
+            CodeRate::Half => &[1, 1],
+            CodeRate::TwoThirds => &[1, 1, 1, 0],
+            CodeRate::ThreeQuarters => &[1, 1, 1, 0, 0, 1],
+            CodeRate::FiveSixths => &[1, 1, 1, 0, 0, 1, 1, 0, 0, 1],
+        }
+    }
+
+    /// Returns the code rate as a number, like `0.75` for 3/4.
+    pub fn rate(&self) -> f32 {
+        match self {
+            CodeRate::Half => 1.0 / 2.0,
+            CodeRate::TwoThirds => 2.0 / 3.0,
+            CodeRate::ThreeQuarters => 3.0 / 4.0,
+            CodeRate::FiveSixths => 5.0 / 6.0,
+        }
+    }
+
+    /// Drops the coded bits not kept by the pattern.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::convolutional::ConvolutionalCode;
+    /// use software_modem::fec::puncture::CodeRate;
+    ///
+    /// let coded = ConvolutionalCode::k7_rate_half().encode(&[1]);
+    /// assert_eq!(coded, vec![1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1]);
+    ///
+    /// assert_eq!(CodeRate::ThreeQuarters.puncture(&coded), vec![1, 1, 1, 1, 1, 1, 0, 1, 1, 1]);
+    /// ```
+    pub fn puncture<T: Copy>(&self, coded: &[T]) -> Vec<T> {
+        let pattern = self.pattern();
+        coded
+            .iter()
+            .zip(pattern.iter().cycle())
+            .filter(|(_, keep)| **keep == 1)
+            .map(|(bit, _)| *bit)
+            .collect()
+    }
+
+    /// Re-inserts the dropped bits as erasures, restoring `coded_length` LLRs.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::bits::bits_to_llrs;
+    /// use software_modem::fec::convolutional::ConvolutionalCode;
+    /// use software_modem::fec::puncture::CodeRate;
+    ///
+    /// let code = ConvolutionalCode::k7_rate_half();
+    /// let bits = [1, 1, 0, 1, 0, 0, 0, 1, 0, 1, 1, 0];
+    ///
+    /// for rate in [CodeRate::TwoThirds, CodeRate::ThreeQuarters, CodeRate::FiveSixths] {
+    ///     let coded = code.encode(&bits);
+    ///     let punctured = rate.puncture(&coded);
+    ///     assert_eq!(punctured.len(), rate.get_punctured_length(coded.len()));
+    ///
+    ///     let llrs = rate.depuncture(&bits_to_llrs(&punctured), coded.len());
+    ///     assert_eq!(code.decode_soft(&llrs), bits);
+    /// }
+    /// ```
+    pub fn depuncture(&self, llrs: &[f32], coded_length: usize) -> Vec<f32> {
+        let mut llrs = llrs.iter();
+        self.pattern()
+            .iter()
+            .cycle()
+            .take(coded_length)
+            .map(|keep| {
+                if *keep == 1 {
+                    *llrs.next().unwrap_or(&0.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the number of bits left of `coded_length` coded bits after puncturing.
+    pub fn get_punctured_length(&self, coded_length: usize) -> usize {
+        let pattern = self.pattern();
+        let kept_per_period = pattern.iter().filter(|keep| **keep == 1).count();
+        let kept_in_rest = pattern[..coded_length % pattern.len()]
+            .iter()
+            .filter(|keep| **keep == 1)
+            .count();
+        (coded_length / pattern.len()) * kept_per_period + kept_in_rest
+    }
+
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            CodeRate::Half => 0,
+            CodeRate::TwoThirds => 1,
+            CodeRate::ThreeQuarters => 2,
+            CodeRate::FiveSixths => 3,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(CodeRate::Half),
+            1 => Some(CodeRate::TwoThirds),
+            2 => Some(CodeRate::ThreeQuarters),
+            3 => Some(CodeRate::FiveSixths),
+            _ => None,
+        }
+    }
+}
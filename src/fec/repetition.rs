@@ -0,0 +1,94 @@
+//! This module provides a repetition code, sending every bit `n` times.
+//!
+//! Repetition is a poor code, but it is trivial to implement on both sides, which makes it a fit for beacons.
+//! Hard bits are majority voted, soft bits combine their LLRs, which also weighs the copies by their reliability.
+
+use alloc::vec::Vec;
+
+/// A repetition code sending every bit `n` times in a row.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::repetition::RepetitionCode;
+///
+/// let code = RepetitionCode::new(5);
+/// let mut coded = code.encode(&[1, 0]);
+/// assert_eq!(coded, vec![1, 1, 1, 1, 1, 0, 0, 0, 0, 0]);
+///
+/// // up to 2 of the 5 copies may be flipped
+/// coded[0] ^= 1;
+/// coded[3] ^= 1;
+/// coded[7] ^= 1;
+/// coded[9] ^= 1;
+/// assert_eq!(code.decode(&coded), vec![1, 0]);
+///
+/// // a strong copy outweighs two weak ones, where majority voting fails
+/// let code = RepetitionCode::new(3);
+/// let llrs = [3.0, -0.2, -0.3];
+/// assert_eq!(code.decode(&[0, 1, 1]), vec![1]);
+/// assert_eq!(code.decode_soft(&llrs), vec![0]);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RepetitionCode {
+    n: usize,
+}
+
+impl RepetitionCode {
+    /// Creates a new repetition code sending every bit `n` times.
+    ///
+    /// # Panics
+    /// If `n` is zero.
+    pub fn new(n: usize) -> Self {
+        if n == 0 {
+            panic!("Number of repetitions must be at least 1, but got {}", n);
+        }
+        RepetitionCode { n }
+    }
+
+    /// Returns the number of copies of every bit.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the number of coded bits for `num_bits` data bits.
+    pub fn get_encoded_length(&self, num_bits: usize) -> usize {
+        num_bits * self.n
+    }
+
+    /// Repeats every bit `n` times.
+    pub fn encode(&self, bits: &[u8]) -> Vec<u8> {
+        bits.iter()
+            .flat_map(|&bit| core::iter::repeat_n(bit, self.n))
+            .collect()
+    }
+
+    /// Majority votes the copies of every bit, ties are decided for `0`.
+    ///
+    /// Incomplete copies at the end are ignored.
+    pub fn decode(&self, coded: &[u8]) -> Vec<u8> {
+        coded
+            .chunks_exact(self.n)
+            .map(|copies| {
+                let ones = copies.iter().filter(|&&bit| bit & 1 == 1).count();
+                u8::from(2 * ones > self.n)
+            })
+            .collect()
+    }
+
+    /// Adds up the LLRs of the copies of every bit and decides on the sum.
+    ///
+    /// Incomplete copies at the end are ignored.
+    pub fn decode_soft(&self, llrs: &[f32]) -> Vec<u8> {
+        self.combine(llrs)
+            .into_iter()
+            .map(|llr| u8::from(llr < 0.0))
+            .collect()
+    }
+
+    /// Adds up the LLRs of the copies of every bit.
+    pub fn combine(&self, llrs: &[f32]) -> Vec<f32> {
+        llrs.chunks_exact(self.n)
+            .map(|copies| copies.iter().sum())
+            .collect()
+    }
+}
@@ -0,0 +1,389 @@
+//! This module provides a Reed-Solomon code over GF(256).
+//!
+//! Reed-Solomon codes correct whole bytes, which makes them a good outer code
+//! for the bursty errors left behind by the Viterbi decoder.
+//! With `n - k = 2t` parity bytes, up to `t` byte errors, or `2t` erasures (errors at known positions),
+//! or any combination with `2 * errors + erasures <= 2t` can be corrected.
+//!
+//! The field uses the primitive polynomial `x^8 + x^4 + x^3 + x^2 + 1` (0x11d),
+//! the generator polynomial has the roots `α^0 .. α^(2t-1)`.
+//! Code words are systematic, the message is followed by the parity bytes.
+
+use alloc::{vec, vec::Vec};
+
+use crate::error::ModemError;
+
+const PRIMITIVE_POLYNOMIAL: u16 = 0x11d;
+
+const GF_EXP: [u8; 512] = build_exp_table();
+const GF_LOG: [u8; 256] = build_log_table();
+
+const fn build_exp_table() -> [u8; 512] {
+    let mut table = [0; 512];
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        table[i] = value as u8;
+        table[i + 255] = value as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= PRIMITIVE_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn build_log_table() -> [u8; 256] {
+    let exp = build_exp_table();
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        GF_EXP[GF_LOG[a as usize] as usize + GF_LOG[b as usize] as usize]
+    }
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        0
+    } else {
+        GF_EXP[GF_LOG[a as usize] as usize + 255 - GF_LOG[b as usize] as usize]
+    }
+}
+
+/// Returns `α^power`.
+fn gf_pow_alpha(power: usize) -> u8 {
+    GF_EXP[power % 255]
+}
+
+/// Evaluates a polynomial with ascending coefficients at `x`.
+fn poly_eval(polynomial: &[u8], x: u8) -> u8 {
+    polynomial
+        .iter()
+        .rev()
+        .fold(0, |value, &coefficient| gf_mul(value, x) ^ coefficient)
+}
+
+/// A Reed-Solomon code with `n` byte code words carrying `k` message bytes.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::rs::ReedSolomon;
+///
+/// let rs = ReedSolomon::rs255_223();
+/// let message: Vec<u8> = (0..223).map(|i| (i * 3) as u8).collect();
+///
+/// let mut codeword = rs.encode(&message);
+/// assert_eq!(codeword.len(), 255);
+///
+/// // t = 16 byte errors are corrected
+/// for i in 0..16 {
+///     codeword[i * 15] ^= 0x5a;
+/// }
+/// assert_eq!(rs.decode(&codeword).unwrap(), message);
+///
+/// // one more is detected
+/// codeword[250] ^= 0x01;
+/// assert!(rs.decode(&codeword).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReedSolomon {
+    n: usize,
+    k: usize,
+    /// Generator polynomial, ascending coefficients, monic.
+    generator: Vec<u8>,
+}
+
+impl ReedSolomon {
+    /// Creates a new RS(n, k) code. Codes with `n < 255` are shortened codes.
+    ///
+    /// # Panics
+    /// If `n` is larger than 255, `k` is zero or not smaller than `n`.
+    pub fn new(n: usize, k: usize) -> Self {
+        if n > 255 || k == 0 || k >= n {
+            panic!(
+                "Invalid Reed-Solomon code RS({}, {}), 0 < k < n <= 255 is required",
+                n, k
+            );
+        }
+
+        let mut generator = vec![1];
+        for i in 0..n - k {
+            // multiply by (x - α^i)
+            let root = gf_pow_alpha(i);
+            let mut next = vec![0; generator.len() + 1];
+            for (j, &coefficient) in generator.iter().enumerate() {
+                next[j + 1] ^= coefficient;
+                next[j] ^= gf_mul(coefficient, root);
+            }
+            generator = next;
+        }
+
+        ReedSolomon { n, k, generator }
+    }
+
+    /// The RS(255, 223) code used by CCSDS and many others, correcting up to 16 byte errors.
+    pub fn rs255_223() -> Self {
+        ReedSolomon::new(255, 223)
+    }
+
+    /// Returns this code shortened to `k` message bytes, keeping the number of parity bytes.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::rs::ReedSolomon;
+    ///
+    /// let rs = ReedSolomon::rs255_223().shortened(10);
+    /// assert_eq!((rs.n(), rs.k()), (42, 10));
+    ///
+    /// let mut codeword = rs.encode(b"0123456789");
+    /// codeword[3] = 0;
+    /// codeword[40] = 0;
+    /// assert_eq!(rs.decode(&codeword).unwrap(), b"0123456789");
+    /// ```
+    pub fn shortened(&self, k: usize) -> Self {
+        ReedSolomon::new(k + self.num_parity(), k)
+    }
+
+    /// Returns the code word length in bytes.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the message length in bytes.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of parity bytes, `n - k`.
+    pub fn num_parity(&self) -> usize {
+        self.n - self.k
+    }
+
+    /// Encodes the message into a code word of `n` bytes.
+    ///
+    /// # Panics
+    /// If the message is not `k` bytes long.
+    pub fn encode(&self, message: &[u8]) -> Vec<u8> {
+        if message.len() != self.k {
+            panic!(
+                "Message length must be {} bytes, but got {} bytes",
+                self.k,
+                message.len()
+            );
+        }
+
+        // remainder of message * x^(n-k) divided by the generator, via a shift register
+        let num_parity = self.num_parity();
+        let mut parity = vec![0; num_parity];
+        for &byte in message {
+            let feedback = byte ^ parity[0];
+            parity.rotate_left(1);
+            parity[num_parity - 1] = 0;
+            if feedback != 0 {
+                for (j, value) in parity.iter_mut().enumerate() {
+                    *value ^= gf_mul(feedback, self.generator[num_parity - 1 - j]);
+                }
+            }
+        }
+
+        let mut codeword = message.to_vec();
+        codeword.extend(parity);
+        codeword
+    }
+
+    /// Decodes a code word into the message.
+    ///
+    /// # Errors
+    /// [ModemError::TooManyErrors] if the code word can not be corrected.
+    pub fn decode(&self, codeword: &[u8]) -> Result<Vec<u8>, ModemError> {
+        self.decode_with_erasures(codeword, &[])
+    }
+
+    /// Decodes a code word into the message, using the known positions of unreliable bytes.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::fec::rs::ReedSolomon;
+    ///
+    /// let rs = ReedSolomon::rs255_223();
+    /// let message = vec![0x42; 223];
+    /// let mut codeword = rs.encode(&message);
+    ///
+    /// // 2t = 32 erasures are corrected
+    /// let erasures: Vec<usize> = (100..132).collect();
+    /// for &i in &erasures {
+    ///     codeword[i] = 0;
+    /// }
+    /// assert_eq!(rs.decode_with_erasures(&codeword, &erasures).unwrap(), message);
+    /// ```
+    ///
+    /// # Errors
+    /// [ModemError::TooManyErrors] if the code word can not be corrected.
+    pub fn decode_with_erasures(
+        &self,
+        codeword: &[u8],
+        erasures: &[usize],
+    ) -> Result<Vec<u8>, ModemError> {
+        let mut codeword = codeword.to_vec();
+        self.correct(&mut codeword, erasures)?;
+        codeword.truncate(self.k);
+        Ok(codeword)
+    }
+
+    /// Corrects a code word in place, returning the number of corrected bytes.
+    ///
+    /// # Panics
+    /// If the code word is not `n` bytes long, or an erasure position is out of range.
+    ///
+    /// # Errors
+    /// [ModemError::TooManyErrors] if the code word can not be corrected, the code word is left unchanged.
+    pub fn correct(&self, codeword: &mut [u8], erasures: &[usize]) -> Result<usize, ModemError> {
+        if codeword.len() != self.n {
+            panic!(
+                "Code word length must be {} bytes, but got {} bytes",
+                self.n,
+                codeword.len()
+            );
+        }
+        if let Some(&position) = erasures.iter().find(|&&position| position >= self.n) {
+            panic!(
+                "Erasure position must be below {}, but got {}",
+                self.n, position
+            );
+        }
+
+        let num_parity = self.num_parity();
+        let syndromes = self.syndromes(codeword);
+        if syndromes.iter().all(|&syndrome| syndrome == 0) {
+            return Ok(0);
+        }
+        if erasures.len() > num_parity {
+            return Err(ModemError::TooManyErrors);
+        }
+
+        // erasure locator, the product of (1 - X x) over all erased positions
+        let mut locator = vec![1];
+        for &position in erasures {
+            let x = self.locator_value(position);
+            let mut next = vec![0; locator.len() + 1];
+            for (j, &coefficient) in locator.iter().enumerate() {
+                next[j] ^= coefficient;
+                next[j + 1] ^= gf_mul(coefficient, x);
+            }
+            locator = next;
+        }
+
+        // Berlekamp-Massey, initialized with the erasures
+        let num_erasures = erasures.len();
+        let mut previous = locator.clone();
+        let mut length = num_erasures;
+        for step in num_erasures..num_parity {
+            let discrepancy = (0..=length.min(step))
+                .filter(|&j| j < locator.len())
+                .fold(0, |sum, j| sum ^ gf_mul(locator[j], syndromes[step - j]));
+
+            previous.insert(0, 0);
+            if discrepancy != 0 {
+                let mut next = locator.clone();
+                next.resize(next.len().max(previous.len()), 0);
+                for (j, &coefficient) in previous.iter().enumerate() {
+                    next[j] ^= gf_mul(discrepancy, coefficient);
+                }
+
+                if 2 * length <= step + num_erasures {
+                    length = step + 1 + num_erasures - length;
+                    previous = locator.iter().map(|&c| gf_div(c, discrepancy)).collect();
+                }
+                locator = next;
+            }
+        }
+        while locator.len() > 1 && locator[locator.len() - 1] == 0 {
+            locator.pop();
+        }
+
+        let degree = locator.len() - 1;
+        if degree != length || 2 * length > num_parity + num_erasures {
+            return Err(ModemError::TooManyErrors);
+        }
+
+        // Chien search
+        let positions: Vec<usize> = (0..self.n)
+            .filter(|&position| {
+                let x_inverse = gf_div(1, self.locator_value(position));
+                poly_eval(&locator, x_inverse) == 0
+            })
+            .collect();
+        if positions.len() != degree {
+            return Err(ModemError::TooManyErrors);
+        }
+
+        // Forney, with the evaluator S(x) * Λ(x) mod x^(n-k)
+        let mut evaluator = vec![0; num_parity];
+        for (i, &syndrome) in syndromes.iter().enumerate() {
+            for (j, &coefficient) in locator.iter().enumerate() {
+                if i + j < num_parity {
+                    evaluator[i + j] ^= gf_mul(syndrome, coefficient);
+                }
+            }
+        }
+        let derivative: Vec<u8> = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &coefficient)| if i % 2 == 1 { coefficient } else { 0 })
+            .collect();
+
+        let mut corrected = codeword.to_vec();
+        for &position in &positions {
+            let x = self.locator_value(position);
+            let x_inverse = gf_div(1, x);
+            let denominator = poly_eval(&derivative, x_inverse);
+            if denominator == 0 {
+                return Err(ModemError::TooManyErrors);
+            }
+            corrected[position] ^= gf_mul(x, gf_div(poly_eval(&evaluator, x_inverse), denominator));
+        }
+
+        if self
+            .syndromes(&corrected)
+            .iter()
+            .any(|&syndrome| syndrome != 0)
+        {
+            return Err(ModemError::TooManyErrors);
+        }
+
+        let num_corrected = positions
+            .iter()
+            .filter(|&&position| corrected[position] != codeword[position])
+            .count();
+        codeword.copy_from_slice(&corrected);
+        Ok(num_corrected)
+    }
+
+    fn syndromes(&self, codeword: &[u8]) -> Vec<u8> {
+        (0..self.num_parity())
+            .map(|i| {
+                let root = gf_pow_alpha(i);
+                codeword
+                    .iter()
+                    .fold(0, |value, &byte| gf_mul(value, root) ^ byte)
+            })
+            .collect()
+    }
+
+    /// Returns the error locator value `X = α^(n - 1 - position)` of a byte position.
+    fn locator_value(&self, position: usize) -> u8 {
+        gf_pow_alpha(self.n - 1 - position)
+    }
+}
@@ -0,0 +1,733 @@
+//! This module provides a C API of the coded modem, behind the `ffi` feature.
+//!
+//! `cargo rustc --lib --crate-type cdylib --features ffi` builds the crate as a `cdylib`,
+//! which the crate does not declare in its manifest, as it could not link without the `std` feature otherwise,
+//! and `include/software_modem.h`, generated by `cbindgen` with `cbindgen.toml`, declares its functions.
+//! [sm_modulator_new] and [sm_demodulator_new] create opaque handles from an [SmConfig],
+//! [sm_modulate] encodes a payload into a frame of samples and [sm_demodulate] decodes it again,
+//! into buffers owned by the caller. Every function returns an [SmStatus], or a null handle,
+//! and [sm_last_error_message] describes the last failure on the calling thread.
+//!
+//! Below the frames, [sm_ofdm_modulate_symbol] and [sm_ofdm_demodulate_symbol] modulate and demodulate
+//! single OFDM symbols, and [sm_qam_modulate] and [sm_qam_demodulate_soft] map bytes to QAM-16 symbols and back,
+//! as interleaved real and imaginary parts.
+//!
+//! No panic unwinds into C: all of them are caught at the boundary and reported as [SmStatus::Panic],
+//! like an invalid configuration that the Rust constructors reject with a panic.
+//! Null pointers are checked, buffers of length 0 may be null.
+//!
+//! # Example
+//! ```
+//! use software_modem::ffi::*;
+//!
+//! let config = sm_config_default();
+//! let modulator = unsafe { sm_modulator_new(&config) };
+//! let demodulator = unsafe { sm_demodulator_new(&config) };
+//! assert!(!modulator.is_null() && !demodulator.is_null());
+//!
+//! // a buffer that is too small tells the length of the frame
+//! let payload = b"Hello from C";
+//! let mut length = 0;
+//! let status = unsafe { sm_modulate(modulator, payload.as_ptr(), payload.len(), std::ptr::null_mut(), 0, &mut length) };
+//! assert_eq!(status, SmStatus::BufferTooSmall);
+//!
+//! let mut samples = vec![0.0; length];
+//! let status = unsafe { sm_modulate(modulator, payload.as_ptr(), payload.len(), samples.as_mut_ptr(), samples.len(), &mut length) };
+//! assert_eq!(status, SmStatus::Ok);
+//!
+//! let mut decoded = [0; 64];
+//! let status = unsafe { sm_demodulate(demodulator, samples.as_ptr(), length, decoded.as_mut_ptr(), decoded.len(), &mut length) };
+//! assert_eq!(status, SmStatus::Ok);
+//! assert_eq!(&decoded[..length], payload);
+//!
+//! // errors come with a message
+//! let status = unsafe { sm_demodulate(demodulator, samples.as_ptr(), 100, decoded.as_mut_ptr(), decoded.len(), &mut length) };
+//! assert_eq!(status, SmStatus::DecodeFailed);
+//! let message = unsafe { std::ffi::CStr::from_ptr(sm_last_error_message()) };
+//! assert!(message.to_str().unwrap().starts_with("Frame is too short"));
+//!
+//! unsafe {
+//!     sm_modulator_free(modulator);
+//!     sm_demodulator_free(demodulator);
+//! }
+//! ```
+
+use std::{
+    cell::RefCell,
+    ffi::{CString, c_char},
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+use num_complex::Complex32;
+
+use crate::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{
+        OFDMConfig,
+        demodulator::OFDMDemodulator,
+        modulator::{OFDMModulator, OutputScale},
+    },
+    qam::{QAMModem, QAMOrder},
+};
+
+/// Parameters of a modem created through the C API, the others keep their defaults.
+///
+/// Both ends of a link must use the same configuration.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmConfig {
+    /// See [OFDMConfig::num_subcarriers].
+    pub num_subcarriers: u32,
+    /// See [OFDMConfig::cyclic_prefix_length].
+    pub cyclic_prefix_length: u32,
+    /// See [OFDMConfig::pilot_subcarrier_every].
+    pub pilot_subcarrier_every: u32,
+    /// See [OFDMConfig::differential_time].
+    pub differential_time: bool,
+    /// See [CodingConfig::reed_solomon].
+    pub reed_solomon: bool,
+    /// Peak of the modulated frames, see [OutputScale::PeakNormalize], or 0 for the raw samples.
+    pub output_peak: f32,
+}
+
+/// Status returned by the functions of the C API.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmStatus {
+    Ok = 0,
+    /// A handle or buffer is null.
+    NullPointer = 1,
+    /// The output buffer is too small, the required length has been written to `out_len`.
+    BufferTooSmall = 2,
+    /// The frame could not be decoded.
+    DecodeFailed = 3,
+    /// The modem panicked, for example on an invalid configuration or a payload longer than 65535 bytes.
+    Panic = 4,
+}
+
+/// A coded modulator, created by [sm_modulator_new].
+pub struct SmModulator(CodedOFDMModulator);
+
+/// A coded demodulator, created by [sm_demodulator_new].
+pub struct SmDemodulator(CodedOFDMDemodulator);
+
+/// A modulator of single OFDM symbols, created by [sm_ofdm_modulator_new].
+pub struct SmOfdmModulator(OFDMModulator);
+
+/// A demodulator of single OFDM symbols, created by [sm_ofdm_demodulator_new].
+pub struct SmOfdmDemodulator(OFDMDemodulator);
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: impl ToString) {
+    // an interior nul would cut the message, it can not be represented in C anyway
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).unwrap_or_default());
+}
+
+/// Runs the closure, turning a panic into [SmStatus::Panic] with its message.
+fn guard(f: impl FnOnce() -> SmStatus) -> SmStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| payload.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown panic");
+        set_last_error(format!("Panic: {}", message));
+        SmStatus::Panic
+    })
+}
+
+impl SmConfig {
+    fn configs(&self) -> (OFDMConfig, CodingConfig) {
+        let ofdm = OFDMConfig {
+            num_subcarriers: self.num_subcarriers,
+            cyclic_prefix_length: self.cyclic_prefix_length,
+            pilot_subcarrier_every: self.pilot_subcarrier_every,
+            differential_time: self.differential_time,
+            output_scale: if self.output_peak > 0.0 {
+                OutputScale::PeakNormalize(self.output_peak)
+            } else {
+                OutputScale::Raw
+            },
+            ..Default::default()
+        };
+        let coding = CodingConfig {
+            reed_solomon: self.reed_solomon,
+            ..Default::default()
+        };
+        (ofdm, coding)
+    }
+}
+
+/// Returns a configuration of 64 subcarriers with a cyclic prefix of 4 and a pilot on every 4th subcarrier,
+/// coherent, without Reed-Solomon code, and raw samples.
+#[unsafe(no_mangle)]
+pub extern "C" fn sm_config_default() -> SmConfig {
+    SmConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        pilot_subcarrier_every: 4,
+        differential_time: false,
+        reed_solomon: false,
+        output_peak: 0.0,
+    }
+}
+
+/// Returns the message of the last failure on the calling thread, or an empty string.
+///
+/// The string stays valid until the next failure on the thread, and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn sm_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Creates a modulator, to be freed with [sm_modulator_free], or returns null on an invalid configuration.
+///
+/// # Safety
+/// The configuration must be null or point to a valid [SmConfig].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_modulator_new(config: *const SmConfig) -> *mut SmModulator {
+    // SAFETY: the caller passes null or a valid configuration
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        set_last_error("Config must not be null");
+        return std::ptr::null_mut();
+    };
+    let mut modulator = std::ptr::null_mut();
+    guard(|| {
+        let (ofdm, coding) = config.configs();
+        modulator = Box::into_raw(Box::new(SmModulator(CodedOFDMModulator::new(ofdm, coding))));
+        SmStatus::Ok
+    });
+    modulator
+}
+
+/// Frees a modulator, null is ignored.
+///
+/// # Safety
+/// The modulator must be null or a handle returned by [sm_modulator_new], which is not used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_modulator_free(modulator: *mut SmModulator) {
+    if !modulator.is_null() {
+        // SAFETY: the handle was created by Box::into_raw and is freed once
+        drop(unsafe { Box::from_raw(modulator) });
+    }
+}
+
+/// Encodes a payload of `len` bytes into a frame of samples.
+///
+/// Writes the number of samples of the frame to `out_len`. If it exceeds `out_cap`,
+/// returns [SmStatus::BufferTooSmall] without writing any samples, so a call with an empty buffer
+/// returns the length to allocate.
+///
+/// # Safety
+/// The modulator must be a handle returned by [sm_modulator_new], `data` must point to `len` bytes
+/// and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_modulate(
+    modulator: *const SmModulator,
+    data: *const u8,
+    len: usize,
+    out: *mut f32,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(modulator), Some(payload), Some(out), Some(out_len)) = (unsafe {
+        (
+            modulator.as_ref(),
+            slice(data, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Handle, data, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let length = modulator.0.get_frame_length(payload.len());
+        *out_len = length;
+        if length > out.len() {
+            set_last_error(format!(
+                "Output buffer must hold {} samples, but got {}",
+                length,
+                out.len()
+            ));
+            return SmStatus::BufferTooSmall;
+        }
+        out[..length].copy_from_slice(&modulator.0.encode_frame(payload));
+        SmStatus::Ok
+    })
+}
+
+/// Creates a demodulator, to be freed with [sm_demodulator_free], or returns null on an invalid configuration.
+///
+/// # Safety
+/// The configuration must be null or point to a valid [SmConfig].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_demodulator_new(config: *const SmConfig) -> *mut SmDemodulator {
+    // SAFETY: the caller passes null or a valid configuration
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        set_last_error("Config must not be null");
+        return std::ptr::null_mut();
+    };
+    let mut demodulator = std::ptr::null_mut();
+    guard(|| {
+        let (ofdm, coding) = config.configs();
+        demodulator = Box::into_raw(Box::new(SmDemodulator(CodedOFDMDemodulator::new(
+            ofdm, coding,
+        ))));
+        SmStatus::Ok
+    });
+    demodulator
+}
+
+/// Frees a demodulator, null is ignored.
+///
+/// # Safety
+/// The demodulator must be null or a handle returned by [sm_demodulator_new], which is not used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_demodulator_free(demodulator: *mut SmDemodulator) {
+    if !demodulator.is_null() {
+        // SAFETY: the handle was created by Box::into_raw and is freed once
+        drop(unsafe { Box::from_raw(demodulator) });
+    }
+}
+
+/// Decodes a frame of `len` samples into its payload.
+///
+/// Writes the number of payload bytes to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+/// without writing the payload, 65535 bytes always suffice.
+///
+/// # Safety
+/// The demodulator must be a handle returned by [sm_demodulator_new], `samples` must point to `len` floats
+/// and `out` to `out_cap` bytes, or be null if their length is 0, and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_demodulate(
+    demodulator: *const SmDemodulator,
+    samples: *const f32,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(demodulator), Some(samples), Some(out), Some(out_len)) = (unsafe {
+        (
+            demodulator.as_ref(),
+            slice(samples, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Handle, samples, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| match demodulator.0.decode_frame(samples) {
+        Ok(payload) => copy_out(&payload, out, out_len, "bytes"),
+        Err(error) => {
+            set_last_error(error);
+            SmStatus::DecodeFailed
+        }
+    })
+}
+
+/// Creates a modulator of single OFDM symbols, to be freed with [sm_ofdm_modulator_free],
+/// or returns null on an invalid configuration.
+///
+/// The differential encoding and the Reed-Solomon code of the configuration belong to the frames, they are ignored.
+///
+/// # Safety
+/// The configuration must be null or point to a valid [SmConfig].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_modulator_new(config: *const SmConfig) -> *mut SmOfdmModulator {
+    // SAFETY: the caller passes null or a valid configuration
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        set_last_error("Config must not be null");
+        return std::ptr::null_mut();
+    };
+    let mut modulator = std::ptr::null_mut();
+    guard(|| {
+        let (mut ofdm, _) = config.configs();
+        ofdm.differential_time = false;
+        modulator = Box::into_raw(Box::new(SmOfdmModulator(OFDMModulator::new(
+            (&ofdm).into(),
+        ))));
+        SmStatus::Ok
+    });
+    modulator
+}
+
+/// Frees a modulator of single OFDM symbols, null is ignored.
+///
+/// # Safety
+/// The modulator must be null or a handle returned by [sm_ofdm_modulator_new], which is not used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_modulator_free(modulator: *mut SmOfdmModulator) {
+    if !modulator.is_null() {
+        // SAFETY: the handle was created by Box::into_raw and is freed once
+        drop(unsafe { Box::from_raw(modulator) });
+    }
+}
+
+/// Returns the number of samples of a symbol, or 0 for a null modulator.
+///
+/// # Safety
+/// The modulator must be null or a handle returned by [sm_ofdm_modulator_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_modulator_symbol_length(
+    modulator: *const SmOfdmModulator,
+) -> usize {
+    // SAFETY: the caller passes null or a valid handle
+    unsafe { modulator.as_ref() }.map_or(0, |modulator| modulator.0.get_symbol_length())
+}
+
+/// Returns the number of data bytes of a symbol, or 0 for a null modulator.
+///
+/// # Safety
+/// The modulator must be null or a handle returned by [sm_ofdm_modulator_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_modulator_bytes_per_symbol(
+    modulator: *const SmOfdmModulator,
+) -> usize {
+    // SAFETY: the caller passes null or a valid handle
+    unsafe { modulator.as_ref() }.map_or(0, |modulator| modulator.0.get_bytes_per_symbol())
+}
+
+/// Modulates exactly the bytes per symbol of data into a symbol of samples.
+///
+/// Writes the symbol length to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+/// without writing any samples. Any other length of data returns [SmStatus::Panic].
+///
+/// # Safety
+/// The modulator must be a handle returned by [sm_ofdm_modulator_new], `data` must point to `len` bytes
+/// and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_modulate_symbol(
+    modulator: *const SmOfdmModulator,
+    data: *const u8,
+    len: usize,
+    out: *mut f32,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(modulator), Some(data), Some(out), Some(out_len)) = (unsafe {
+        (
+            modulator.as_ref(),
+            slice(data, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Handle, data, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let mut symbol = vec![0.0; modulator.0.get_symbol_length()];
+        modulator.0.modulate_buffer_as_symbol(data, &mut symbol);
+        copy_out(&symbol, out, out_len, "samples")
+    })
+}
+
+/// Creates a demodulator of single OFDM symbols, to be freed with [sm_ofdm_demodulator_free],
+/// or returns null on an invalid configuration.
+///
+/// The differential encoding and the Reed-Solomon code of the configuration belong to the frames, they are ignored.
+///
+/// # Safety
+/// The configuration must be null or point to a valid [SmConfig].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulator_new(
+    config: *const SmConfig,
+) -> *mut SmOfdmDemodulator {
+    // SAFETY: the caller passes null or a valid configuration
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        set_last_error("Config must not be null");
+        return std::ptr::null_mut();
+    };
+    let mut demodulator = std::ptr::null_mut();
+    guard(|| {
+        let (mut ofdm, _) = config.configs();
+        ofdm.differential_time = false;
+        demodulator = Box::into_raw(Box::new(SmOfdmDemodulator(OFDMDemodulator::new(
+            (&ofdm).into(),
+        ))));
+        SmStatus::Ok
+    });
+    demodulator
+}
+
+/// Frees a demodulator of single OFDM symbols, null is ignored.
+///
+/// # Safety
+/// The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new], which is not used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulator_free(demodulator: *mut SmOfdmDemodulator) {
+    if !demodulator.is_null() {
+        // SAFETY: the handle was created by Box::into_raw and is freed once
+        drop(unsafe { Box::from_raw(demodulator) });
+    }
+}
+
+/// Returns the number of samples of a symbol, or 0 for a null demodulator.
+///
+/// # Safety
+/// The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulator_symbol_length(
+    demodulator: *const SmOfdmDemodulator,
+) -> usize {
+    // SAFETY: the caller passes null or a valid handle
+    unsafe { demodulator.as_ref() }.map_or(0, |demodulator| demodulator.0.get_symbol_length())
+}
+
+/// Returns the number of data bytes of a symbol, or 0 for a null demodulator.
+///
+/// # Safety
+/// The demodulator must be null or a handle returned by [sm_ofdm_demodulator_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulator_bytes_per_symbol(
+    demodulator: *const SmOfdmDemodulator,
+) -> usize {
+    // SAFETY: the caller passes null or a valid handle
+    unsafe { demodulator.as_ref() }.map_or(0, |demodulator| demodulator.0.get_bytes_per_symbol())
+}
+
+/// Demodulates a symbol of exactly the symbol length of samples into its data bytes.
+///
+/// Writes the bytes per symbol to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+/// without writing any data. Any other number of samples returns [SmStatus::Panic].
+///
+/// # Safety
+/// The demodulator must be a handle returned by [sm_ofdm_demodulator_new], `samples` must point to `len` floats
+/// and `out` to `out_cap` bytes, or be null if their length is 0, and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulate_symbol(
+    demodulator: *const SmOfdmDemodulator,
+    samples: *const f32,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(demodulator), Some(samples), Some(out), Some(out_len)) = (unsafe {
+        (
+            demodulator.as_ref(),
+            slice(samples, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Handle, samples, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let data = demodulator.0.demodulate_symbol_from_buffer(samples);
+        copy_out(&data, out, out_len, "bytes")
+    })
+}
+
+/// Demodulates a symbol of exactly the symbol length of samples into one LLR per data bit,
+/// see [OFDMDemodulator::demodulate_symbol_soft_from_buffer].
+///
+/// Writes eight times the bytes per symbol to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+/// without writing any LLRs. Any other number of samples returns [SmStatus::Panic].
+///
+/// # Safety
+/// The demodulator must be a handle returned by [sm_ofdm_demodulator_new], `samples` must point to `len` floats
+/// and `out` to `out_cap` floats, or be null if their length is 0, and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_ofdm_demodulate_symbol_soft(
+    demodulator: *const SmOfdmDemodulator,
+    samples: *const f32,
+    len: usize,
+    out: *mut f32,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(demodulator), Some(samples), Some(out), Some(out_len)) = (unsafe {
+        (
+            demodulator.as_ref(),
+            slice(samples, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Handle, samples, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let llrs = demodulator.0.demodulate_symbol_soft_from_buffer(samples);
+        copy_out(&llrs, out, out_len, "LLRs")
+    })
+}
+
+/// Maps `len` bytes to QAM-16 symbols, two per byte, written as interleaved real and imaginary parts.
+///
+/// Writes the number of floats, four per byte, to `out_len`. If it exceeds `out_cap`,
+/// returns [SmStatus::BufferTooSmall] without writing any symbols.
+///
+/// # Safety
+/// `data` must point to `len` bytes and `out` to `out_cap` floats, or be null if their length is 0,
+/// and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_qam_modulate(
+    data: *const u8,
+    len: usize,
+    out: *mut f32,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(data), Some(out), Some(out_len)) =
+        (unsafe { (slice(data, len), slice_mut(out, out_cap), out_len.as_mut()) })
+    else {
+        set_last_error("Data, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let symbols = QAMModem::new(QAMOrder::QAM16).modulate(data);
+        let floats: Vec<f32> = symbols
+            .iter()
+            .flat_map(|symbol| [symbol.re, symbol.im])
+            .collect();
+        copy_out(&floats, out, out_len, "floats")
+    })
+}
+
+/// Demaps QAM-16 symbols, given as `len` interleaved real and imaginary parts, to the nearest bytes.
+///
+/// Writes the number of bytes, one per two symbols, to `out_len`. If it exceeds `out_cap`,
+/// returns [SmStatus::BufferTooSmall] without writing any data. An odd number of floats or symbols returns [SmStatus::Panic].
+///
+/// # Safety
+/// `symbols` must point to `len` floats and `out` to `out_cap` bytes, or be null if their length is 0,
+/// and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_qam_demodulate(
+    symbols: *const f32,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(symbols), Some(out), Some(out_len)) = (unsafe {
+        (
+            slice(symbols, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Symbols, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let data = QAMModem::new(QAMOrder::QAM16).demodulate(&complex_symbols(symbols));
+        copy_out(&data, out, out_len, "bytes")
+    })
+}
+
+/// Demaps QAM-16 symbols, given as `len` interleaved real and imaginary parts, to four LLRs per symbol,
+/// see [QAMModem::demodulate_soft].
+///
+/// Writes the number of LLRs to `out_len`. If it exceeds `out_cap`, returns [SmStatus::BufferTooSmall]
+/// without writing any LLRs. An odd `len` returns [SmStatus::Panic].
+///
+/// # Safety
+/// `symbols` must point to `len` floats and `out` to `out_cap` floats, or be null if their length is 0,
+/// and `out_len` must point to a `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sm_qam_demodulate_soft(
+    symbols: *const f32,
+    len: usize,
+    out: *mut f32,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> SmStatus {
+    // SAFETY: the caller passes valid pointers or null
+    let (Some(symbols), Some(out), Some(out_len)) = (unsafe {
+        (
+            slice(symbols, len),
+            slice_mut(out, out_cap),
+            out_len.as_mut(),
+        )
+    }) else {
+        set_last_error("Symbols, out and out_len must not be null");
+        return SmStatus::NullPointer;
+    };
+
+    guard(|| {
+        let llrs = QAMModem::new(QAMOrder::QAM16).demodulate_soft(&complex_symbols(symbols));
+        copy_out(&llrs, out, out_len, "LLRs")
+    })
+}
+
+/// Pairs the interleaved real and imaginary parts into symbols.
+///
+/// # Panics
+/// If the number of floats is odd.
+fn complex_symbols(floats: &[f32]) -> Vec<Complex32> {
+    if !floats.len().is_multiple_of(2) {
+        panic!("Number of floats must be even, but got {}", floats.len());
+    }
+    floats
+        .chunks_exact(2)
+        .map(|pair| Complex32::new(pair[0], pair[1]))
+        .collect()
+}
+
+/// Writes the length of the values to `out_len`, and the values to `out` if they fit.
+fn copy_out<T: Copy>(values: &[T], out: &mut [T], out_len: &mut usize, unit: &str) -> SmStatus {
+    *out_len = values.len();
+    if values.len() > out.len() {
+        set_last_error(format!(
+            "Output buffer must hold {} {}, but got {}",
+            values.len(),
+            unit,
+            out.len()
+        ));
+        return SmStatus::BufferTooSmall;
+    }
+    out[..values.len()].copy_from_slice(values);
+    SmStatus::Ok
+}
+
+/// Returns the buffer, or `None` if it is null but not empty.
+///
+/// # Safety
+/// A non-null pointer must point to `len` valid elements.
+unsafe fn slice<'a, T>(pointer: *const T, len: usize) -> Option<&'a [T]> {
+    match (pointer.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        // SAFETY: the caller passes a pointer to len elements
+        (false, _) => Some(unsafe { std::slice::from_raw_parts(pointer, len) }),
+    }
+}
+
+/// Returns the buffer, or `None` if it is null but not empty.
+///
+/// # Safety
+/// A non-null pointer must point to `len` valid elements, which are not aliased.
+unsafe fn slice_mut<'a, T>(pointer: *mut T, len: usize) -> Option<&'a mut [T]> {
+    match (pointer.is_null(), len) {
+        (_, 0) => Some(&mut []),
+        (true, _) => None,
+        // SAFETY: the caller passes a pointer to len elements
+        (false, _) => Some(unsafe { std::slice::from_raw_parts_mut(pointer, len) }),
+    }
+}
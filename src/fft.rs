@@ -0,0 +1,755 @@
+//! This module provides the FFT traits of the modems, so the FFT implementation can be swapped.
+//!
+//! The OFDM modulator and demodulator take a [RealInverseFft] and a [RealForwardFft] of the FFT length
+//! in their configurations. Without one, they plan the FFTs of `realfft` with [plan_real_forward] and [plan_real_inverse],
+//! and the [complex baseband modem](crate::ofdm::complex) plans a [ComplexFft] of `rustfft` with [plan_complex_forward]
+//! and [plan_complex_inverse]. A plan of `realfft` or `rustfft` becomes an FFT of the modem
+//! through the `From` impls of [RealfftForward], [RealfftInverse] and [RustfftComplex].
+//!
+//! The planning functions share the [global](FftPlannerHandle::global) [FftPlannerHandle], so every modem of
+//! the same FFT length and sample type shares one plan and its twiddle factors. A modem with a planner of its own
+//! takes an FFT planned by a [new](FftPlannerHandle::new) handle in its configuration.
+//!
+//! Without the `std` feature, neither `realfft` nor `rustfft` is linked, and the planning functions plan the FFTs
+//! of this module, a [NativeComplexFft] and the [NativeRealForward] and [NativeRealInverse] on top of it,
+//! a new plan on every call.
+//!
+//! Unlike the FFTs of `rustfft` and `realfft`, none of the transforms is normalized.
+//!
+//! # Example
+//! A test double, which checks the buffers the demodulator passes.
+//! ```
+//! use std::sync::{Arc, Mutex};
+//!
+//! use realfft::RealFftPlanner;
+//! use realfft::num_complex::Complex32;
+//! use software_modem::fft::{RealForwardFft, RealfftInverse, plan_real_forward};
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+//! use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+//!
+//! struct MockFft {
+//!     inner: Arc<dyn RealForwardFft<f32>>,
+//!     calls: Mutex<Vec<(usize, usize)>>,
+//! }
+//!
+//! impl RealForwardFft<f32> for MockFft {
+//!     fn fft_length(&self) -> usize {
+//!         self.inner.fft_length()
+//!     }
+//!
+//!     fn process_with_scratch(&self, input: &mut [f32], output: &mut [Complex32], scratch: &mut [Complex32]) {
+//!         self.calls.lock().unwrap().push((input.len(), output.len()));
+//!         self.inner.process_with_scratch(input, output, scratch);
+//!     }
+//! }
+//!
+//! let config = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     ..Default::default()
+//! };
+//! let mock = Arc::new(MockFft {
+//!     inner: plan_real_forward(128),
+//!     calls: Mutex::new(Vec::new()),
+//! });
+//! let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+//!     fft: Some(mock.clone()),
+//!     ..(&config).into()
+//! });
+//!
+//! // a plan of realfft becomes an FFT of the modem through From
+//! let plan = RealFftPlanner::<f32>::new().plan_fft_inverse(128);
+//! let modulator = OFDMModulator::new(OFDMModulatorConfig {
+//!     fft: Some(Arc::new(RealfftInverse::from(plan))),
+//!     ..(&config).into()
+//! });
+//! let data: Vec<u8> = (0..48u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+//! let mut symbols = vec![0.0; 2 * modulator.get_symbol_length()];
+//! for (data, symbol) in data.chunks(24).zip(symbols.chunks_exact_mut(132)) {
+//!     modulator.modulate_buffer_as_symbol(data, symbol);
+//! }
+//! for (data, symbol) in data.chunks(24).zip(symbols.chunks_exact(132)) {
+//!     assert_eq!(demodulator.demodulate_symbol_from_buffer(symbol), data);
+//! }
+//!
+//! // one transform per symbol, of the 128 samples after the cyclic prefix into 65 bins
+//! assert_eq!(*mock.calls.lock().unwrap(), [(128, 65), (128, 65)]);
+//! ```
+
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+#[cfg(feature = "std")]
+use core::any::{Any, TypeId};
+use core::f64::consts::PI;
+#[cfg(not(feature = "std"))]
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
+
+use num_complex::Complex;
+#[cfg(not(feature = "std"))]
+use num_traits::FromPrimitive;
+#[cfg(not(feature = "std"))]
+use num_traits::Signed;
+use num_traits::{Num, Zero};
+#[cfg(feature = "std")]
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+#[cfg(feature = "std")]
+use rustfft::{Fft, FftPlanner};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The sample types of the FFTs, `f32` and `f64`.
+#[cfg(feature = "std")]
+pub use realfft::FftNum;
+
+/// The sample types of the FFTs, `f32` and `f64`, the same trait as the one of `rustfft`.
+#[cfg(not(feature = "std"))]
+pub trait FftNum: Copy + FromPrimitive + Signed + Sync + Send + Debug + 'static {}
+
+#[cfg(not(feature = "std"))]
+impl<T> FftNum for T where T: Copy + FromPrimitive + Signed + Sync + Send + Debug + 'static {}
+
+/// A forward FFT of a fixed length from real samples to the bins up to half the sample rate.
+pub trait RealForwardFft<T>: Send + Sync {
+    /// Returns the number of samples transformed.
+    fn fft_length(&self) -> usize;
+
+    /// Returns the number of complex values of scratch space needed by [process_with_scratch](Self::process_with_scratch).
+    fn get_scratch_len(&self) -> usize {
+        0
+    }
+
+    /// Transforms the `fft_length()` samples of the input, which is used as scratch space too,
+    /// into `fft_length() / 2 + 1` bins.
+    fn process_with_scratch(
+        &self,
+        input: &mut [T],
+        output: &mut [Complex<T>],
+        scratch: &mut [Complex<T>],
+    );
+
+    /// Transforms like [process_with_scratch](Self::process_with_scratch), allocating the scratch space.
+    fn process(&self, input: &mut [T], output: &mut [Complex<T>])
+    where
+        T: Clone + Num,
+    {
+        let mut scratch = vec![Complex::zero(); self.get_scratch_len()];
+        self.process_with_scratch(input, output, &mut scratch);
+    }
+}
+
+/// An inverse FFT of a fixed length from the bins up to half the sample rate to real samples.
+pub trait RealInverseFft<T>: Send + Sync {
+    /// Returns the number of samples produced.
+    fn fft_length(&self) -> usize;
+
+    /// Returns the number of complex values of scratch space needed by [process_with_scratch](Self::process_with_scratch).
+    fn get_scratch_len(&self) -> usize {
+        0
+    }
+
+    /// Transforms the `fft_length() / 2 + 1` bins of the input, which is used as scratch space too,
+    /// into `fft_length()` samples. The imaginary parts of the first bin, and of the last for even lengths, must be 0.
+    fn process_with_scratch(
+        &self,
+        input: &mut [Complex<T>],
+        output: &mut [T],
+        scratch: &mut [Complex<T>],
+    );
+
+    /// Transforms like [process_with_scratch](Self::process_with_scratch), allocating the scratch space.
+    fn process(&self, input: &mut [Complex<T>], output: &mut [T])
+    where
+        T: Clone + Num,
+    {
+        let mut scratch = vec![Complex::zero(); self.get_scratch_len()];
+        self.process_with_scratch(input, output, &mut scratch);
+    }
+}
+
+/// A complex FFT of a fixed length and direction, in place.
+pub trait ComplexFft<T>: Send + Sync {
+    /// Returns the number of values transformed.
+    fn fft_length(&self) -> usize;
+
+    /// Returns the number of complex values of scratch space needed by [process_with_scratch](Self::process_with_scratch).
+    fn get_scratch_len(&self) -> usize {
+        0
+    }
+
+    /// Transforms the `fft_length()` values of the buffer in place.
+    fn process_with_scratch(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]);
+
+    /// Transforms like [process_with_scratch](Self::process_with_scratch), allocating the scratch space.
+    fn process(&self, buffer: &mut [Complex<T>])
+    where
+        T: Clone + Num,
+    {
+        let mut scratch = vec![Complex::zero(); self.get_scratch_len()];
+        self.process_with_scratch(buffer, &mut scratch);
+    }
+}
+
+/// A [RealForwardFft] computed by `realfft`.
+///
+/// # Panics
+/// The transforms panic if a buffer does not have the length of the plan.
+#[cfg(feature = "std")]
+pub struct RealfftForward<T: FftNum>(Arc<dyn RealToComplex<T>>);
+
+#[cfg(feature = "std")]
+impl<T: FftNum> From<Arc<dyn RealToComplex<T>>> for RealfftForward<T> {
+    fn from(plan: Arc<dyn RealToComplex<T>>) -> Self {
+        RealfftForward(plan)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> RealForwardFft<T> for RealfftForward<T> {
+    fn fft_length(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [T],
+        output: &mut [Complex<T>],
+        scratch: &mut [Complex<T>],
+    ) {
+        self.0.process_with_scratch(input, output, scratch).unwrap();
+    }
+}
+
+/// A [RealInverseFft] computed by `realfft`.
+///
+/// # Panics
+/// The transforms panic if a buffer does not have the length of the plan,
+/// or an imaginary part that must be 0 is not.
+#[cfg(feature = "std")]
+pub struct RealfftInverse<T: FftNum>(Arc<dyn ComplexToReal<T>>);
+
+#[cfg(feature = "std")]
+impl<T: FftNum> From<Arc<dyn ComplexToReal<T>>> for RealfftInverse<T> {
+    fn from(plan: Arc<dyn ComplexToReal<T>>) -> Self {
+        RealfftInverse(plan)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> RealInverseFft<T> for RealfftInverse<T> {
+    fn fft_length(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [Complex<T>],
+        output: &mut [T],
+        scratch: &mut [Complex<T>],
+    ) {
+        self.0.process_with_scratch(input, output, scratch).unwrap();
+    }
+}
+
+/// A [ComplexFft] computed by `rustfft`.
+///
+/// # Panics
+/// The transforms panic if the buffer does not have the length of the plan.
+#[cfg(feature = "std")]
+pub struct RustfftComplex<T: FftNum>(Arc<dyn Fft<T>>);
+
+#[cfg(feature = "std")]
+impl<T: FftNum> From<Arc<dyn Fft<T>>> for RustfftComplex<T> {
+    fn from(plan: Arc<dyn Fft<T>>) -> Self {
+        RustfftComplex(plan)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> ComplexFft<T> for RustfftComplex<T> {
+    fn fft_length(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.get_inplace_scratch_len()
+    }
+
+    fn process_with_scratch(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        if buffer.len() != self.0.len() {
+            panic!(
+                "Buffer length must be {}, but got {}",
+                self.0.len(),
+                buffer.len()
+            );
+        }
+        self.0.process_with_scratch(buffer, scratch);
+    }
+}
+
+/// The planners of a [FftPlannerHandle] and the plans they made, by FFT length.
+#[cfg(feature = "std")]
+struct Plans<T: FftNum> {
+    real: RealFftPlanner<T>,
+    complex: FftPlanner<T>,
+    real_forward: HashMap<usize, Arc<dyn RealForwardFft<T>>>,
+    real_inverse: HashMap<usize, Arc<dyn RealInverseFft<T>>>,
+    complex_forward: HashMap<usize, Arc<dyn ComplexFft<T>>>,
+    complex_inverse: HashMap<usize, Arc<dyn ComplexFft<T>>>,
+}
+
+/// A planner of the FFTs of `realfft` and `rustfft` which keeps its plans, shared between threads.
+///
+/// Planning an FFT computes its twiddle factors, planning the same length again returns the same plan.
+/// Clones of a handle share the planner and its plans, which live as long as the last handle.
+/// The modems plan with the [global](Self::global) handle, so a modulator and demodulator, or many demodulators
+/// working in parallel, keep one plan of their FFT length.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use software_modem::fft::{FftPlannerHandle, plan_real_forward};
+///
+/// let planner = FftPlannerHandle::<f32>::new();
+/// assert!(Arc::ptr_eq(&planner.plan_real_forward(512), &planner.clone().plan_real_forward(512)));
+///
+/// // the global planner is another one
+/// assert!(Arc::ptr_eq(&plan_real_forward::<f32>(512), &FftPlannerHandle::global().plan_real_forward(512)));
+/// assert!(!Arc::ptr_eq(&planner.plan_real_forward(512), &plan_real_forward(512)));
+/// ```
+#[cfg(feature = "std")]
+pub struct FftPlannerHandle<T: FftNum>(Arc<Mutex<Plans<T>>>);
+
+#[cfg(feature = "std")]
+impl<T: FftNum> FftPlannerHandle<T> {
+    /// Creates a planner without any plans.
+    pub fn new() -> Self {
+        FftPlannerHandle(Arc::new(Mutex::new(Plans {
+            real: RealFftPlanner::new(),
+            complex: FftPlanner::new(),
+            real_forward: HashMap::new(),
+            real_inverse: HashMap::new(),
+            complex_forward: HashMap::new(),
+            complex_inverse: HashMap::new(),
+        })))
+    }
+
+    /// Returns the planner of the crate for the sample type, used by [plan_real_forward] and the other planning functions.
+    ///
+    /// Its plans are kept until the program ends.
+    pub fn global() -> Self {
+        static HANDLES: OnceLock<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>> =
+            OnceLock::new();
+        let mut handles = HANDLES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        handles
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Self::new()))
+            .downcast_ref::<Self>()
+            .expect("the handle of a type id has that type")
+            .clone()
+    }
+
+    /// Plans a forward FFT of `realfft`, or returns the one planned before.
+    pub fn plan_real_forward(&self, fft_length: usize) -> Arc<dyn RealForwardFft<T>> {
+        let plans = &mut *self.lock();
+        plans
+            .real_forward
+            .entry(fft_length)
+            .or_insert_with(|| {
+                Arc::new(RealfftForward::from(
+                    plans.real.plan_fft_forward(fft_length),
+                ))
+            })
+            .clone()
+    }
+
+    /// Plans an inverse FFT of `realfft`, or returns the one planned before.
+    pub fn plan_real_inverse(&self, fft_length: usize) -> Arc<dyn RealInverseFft<T>> {
+        let plans = &mut *self.lock();
+        plans
+            .real_inverse
+            .entry(fft_length)
+            .or_insert_with(|| {
+                Arc::new(RealfftInverse::from(
+                    plans.real.plan_fft_inverse(fft_length),
+                ))
+            })
+            .clone()
+    }
+
+    /// Plans a forward complex FFT of `rustfft`, or returns the one planned before.
+    pub fn plan_complex_forward(&self, fft_length: usize) -> Arc<dyn ComplexFft<T>> {
+        let plans = &mut *self.lock();
+        plans
+            .complex_forward
+            .entry(fft_length)
+            .or_insert_with(|| {
+                Arc::new(RustfftComplex::from(
+                    plans.complex.plan_fft_forward(fft_length),
+                ))
+            })
+            .clone()
+    }
+
+    /// Plans an inverse complex FFT of `rustfft`, or returns the one planned before.
+    pub fn plan_complex_inverse(&self, fft_length: usize) -> Arc<dyn ComplexFft<T>> {
+        let plans = &mut *self.lock();
+        plans
+            .complex_inverse
+            .entry(fft_length)
+            .or_insert_with(|| {
+                Arc::new(RustfftComplex::from(
+                    plans.complex.plan_fft_inverse(fft_length),
+                ))
+            })
+            .clone()
+    }
+
+    /// Locks the plans, which stay consistent even if planning panicked, as a plan is only kept once made.
+    fn lock(&self) -> MutexGuard<'_, Plans<T>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> Clone for FftPlannerHandle<T> {
+    fn clone(&self) -> Self {
+        FftPlannerHandle(self.0.clone())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> Default for FftPlannerHandle<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plans a forward FFT of `realfft` with the [global planner](FftPlannerHandle::global),
+/// or a [NativeRealForward] without the `std` feature.
+pub fn plan_real_forward<T: FftNum>(fft_length: usize) -> Arc<dyn RealForwardFft<T>> {
+    #[cfg(feature = "std")]
+    return FftPlannerHandle::global().plan_real_forward(fft_length);
+    #[cfg(not(feature = "std"))]
+    return Arc::new(NativeRealForward::new(fft_length));
+}
+
+/// Plans an inverse FFT of `realfft` with the [global planner](FftPlannerHandle::global),
+/// or a [NativeRealInverse] without the `std` feature.
+pub fn plan_real_inverse<T: FftNum>(fft_length: usize) -> Arc<dyn RealInverseFft<T>> {
+    #[cfg(feature = "std")]
+    return FftPlannerHandle::global().plan_real_inverse(fft_length);
+    #[cfg(not(feature = "std"))]
+    return Arc::new(NativeRealInverse::new(fft_length));
+}
+
+/// Plans a forward complex FFT of `rustfft` with the [global planner](FftPlannerHandle::global),
+/// or a [NativeComplexFft] without the `std` feature.
+pub fn plan_complex_forward<T: FftNum>(fft_length: usize) -> Arc<dyn ComplexFft<T>> {
+    #[cfg(feature = "std")]
+    return FftPlannerHandle::global().plan_complex_forward(fft_length);
+    #[cfg(not(feature = "std"))]
+    return Arc::new(NativeComplexFft::new(fft_length, false));
+}
+
+/// Plans an inverse complex FFT of `rustfft` with the [global planner](FftPlannerHandle::global),
+/// or a [NativeComplexFft] without the `std` feature.
+pub fn plan_complex_inverse<T: FftNum>(fft_length: usize) -> Arc<dyn ComplexFft<T>> {
+    #[cfg(feature = "std")]
+    return FftPlannerHandle::global().plan_complex_inverse(fft_length);
+    #[cfg(not(feature = "std"))]
+    return Arc::new(NativeComplexFft::new(fft_length, true));
+}
+
+/// Returns `exp(i * phase)` in the sample type, computed in `f64`.
+fn twiddle<T: FftNum>(phase: f64) -> Complex<T> {
+    let (sin, cos) = phase.sin_cos();
+    Complex::new(
+        T::from_f64(cos).expect("an FFT sample type holds every f64 within [-1, 1]"),
+        T::from_f64(sin).expect("an FFT sample type holds every f64 within [-1, 1]"),
+    )
+}
+
+/// A [ComplexFft] of this module, needing neither `std` nor `rustfft`: an iterative radix-2 FFT for lengths
+/// of a power of 2, and Bluestein's algorithm on top of one for every other length.
+///
+/// It is the FFT of the modems without the `std` feature, and slower than `rustfft`, which the modems plan with it.
+///
+/// # Panics
+/// The transforms panic if the buffer does not have the length of the plan.
+///
+/// # Example
+/// ```
+/// use num_complex::Complex64;
+/// use software_modem::fft::{ComplexFft, NativeComplexFft};
+///
+/// // the inverse of the forward transform, scaled by the length, of a length which is no power of 2
+/// let input: Vec<Complex64> = (0..12).map(|i| Complex64::new(i as f64, 1.0)).collect();
+/// let mut buffer = input.clone();
+/// NativeComplexFft::new(12, false).process(&mut buffer);
+/// assert!((buffer[0] - Complex64::new(66.0, 12.0)).norm() < 1e-9);
+/// NativeComplexFft::new(12, true).process(&mut buffer);
+/// for (output, input) in buffer.iter().zip(&input) {
+///     assert!((output / 12.0 - input).norm() < 1e-9);
+/// }
+/// ```
+pub struct NativeComplexFft<T> {
+    length: usize,
+    /// `exp(-+2 pi i k / length)` for `k` up to half the length, for a power of 2.
+    twiddles: Vec<Complex<T>>,
+    bluestein: Option<Box<Bluestein<T>>>,
+}
+
+/// The chirps of Bluestein's algorithm, which turn an FFT of any length into a convolution
+/// computed by FFTs of a power of 2.
+struct Bluestein<T> {
+    /// The forward FFT of the convolution, of a power of 2 of at least twice the length.
+    inner: NativeComplexFft<T>,
+    /// `exp(-+pi i k^2 / length)`.
+    chirp: Vec<Complex<T>>,
+    /// The forward FFT of the conjugated chirp, wrapped around, over the length of the inner FFT.
+    filter: Vec<Complex<T>>,
+}
+
+impl<T: FftNum> NativeComplexFft<T> {
+    /// Plans the FFT of the length, the inverse one if `inverse` is set.
+    ///
+    /// # Panics
+    /// If the length is 0.
+    pub fn new(fft_length: usize, inverse: bool) -> Self {
+        if fft_length == 0 {
+            panic!("FFT length must be positive, but got {}", fft_length);
+        }
+        let sign = if inverse { 1.0 } else { -1.0 };
+        if fft_length.is_power_of_two() {
+            let twiddles = (0..fft_length / 2)
+                .map(|k| twiddle(sign * 2.0 * PI * k as f64 / fft_length as f64))
+                .collect();
+            return NativeComplexFft {
+                length: fft_length,
+                twiddles,
+                bluestein: None,
+            };
+        }
+
+        let inner = NativeComplexFft::new((2 * fft_length - 1).next_power_of_two(), false);
+        // k^2 modulo twice the length keeps the phase exact for long FFTs
+        let chirp: Vec<Complex<T>> = (0..fft_length)
+            .map(|k| {
+                let square = (k as u64 * k as u64) % (2 * fft_length as u64);
+                twiddle(sign * PI * square as f64 / fft_length as f64)
+            })
+            .collect();
+        let mut filter = vec![Complex::zero(); inner.length];
+        filter[0] = chirp[0].conj();
+        for k in 1..fft_length {
+            filter[k] = chirp[k].conj();
+            filter[inner.length - k] = chirp[k].conj();
+        }
+        inner.radix2(&mut filter);
+        // the inverse FFT of the convolution is left unnormalized
+        let scale = T::from_usize(inner.length).expect("an FFT sample type holds the FFT length");
+        filter.iter_mut().for_each(|value| *value = *value / scale);
+        NativeComplexFft {
+            length: fft_length,
+            twiddles: Vec::new(),
+            bluestein: Some(Box::new(Bluestein {
+                inner,
+                chirp,
+                filter,
+            })),
+        }
+    }
+
+    /// Transforms a buffer of a power of 2 in place.
+    fn radix2(&self, buffer: &mut [Complex<T>]) {
+        let length = buffer.len();
+        let bits = length.trailing_zeros();
+        if bits == 0 {
+            return;
+        }
+        for i in 0..length {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if i < j {
+                buffer.swap(i, j);
+            }
+        }
+        let mut size = 2;
+        while size <= length {
+            let step = length / size;
+            for block in buffer.chunks_exact_mut(size) {
+                let (low, high) = block.split_at_mut(size / 2);
+                for (j, (low, high)) in low.iter_mut().zip(high).enumerate() {
+                    let product = *high * self.twiddles[j * step];
+                    *high = *low - product;
+                    *low = *low + product;
+                }
+            }
+            size *= 2;
+        }
+    }
+}
+
+impl<T: FftNum> ComplexFft<T> for NativeComplexFft<T> {
+    fn fft_length(&self) -> usize {
+        self.length
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.bluestein
+            .as_ref()
+            .map_or(0, |bluestein| bluestein.inner.length)
+    }
+
+    fn process_with_scratch(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        if buffer.len() != self.length {
+            panic!(
+                "Buffer length must be {}, but got {}",
+                self.length,
+                buffer.len()
+            );
+        }
+        let Some(bluestein) = &self.bluestein else {
+            self.radix2(buffer);
+            return;
+        };
+
+        let scratch = &mut scratch[..bluestein.inner.length];
+        for (scratch, (value, chirp)) in scratch.iter_mut().zip(buffer.iter().zip(&bluestein.chirp))
+        {
+            *scratch = *value * *chirp;
+        }
+        scratch[self.length..].fill(Complex::zero());
+        bluestein.inner.radix2(scratch);
+        // the inverse FFT by the forward one of the conjugates
+        for (value, filter) in scratch.iter_mut().zip(&bluestein.filter) {
+            *value = (*value * *filter).conj();
+        }
+        bluestein.inner.radix2(scratch);
+        for (value, (convolved, chirp)) in
+            buffer.iter_mut().zip(scratch.iter().zip(&bluestein.chirp))
+        {
+            *value = convolved.conj() * *chirp;
+        }
+    }
+}
+
+/// A [RealForwardFft] of this module, a [NativeComplexFft] of the real samples.
+///
+/// # Panics
+/// The transforms panic if a buffer does not have the length of the plan.
+pub struct NativeRealForward<T>(NativeComplexFft<T>);
+
+impl<T: FftNum> NativeRealForward<T> {
+    /// Plans the FFT of the length.
+    ///
+    /// # Panics
+    /// If the length is 0.
+    pub fn new(fft_length: usize) -> Self {
+        NativeRealForward(NativeComplexFft::new(fft_length, false))
+    }
+}
+
+impl<T: FftNum> RealForwardFft<T> for NativeRealForward<T> {
+    fn fft_length(&self) -> usize {
+        self.0.length
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.length + self.0.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [T],
+        output: &mut [Complex<T>],
+        scratch: &mut [Complex<T>],
+    ) {
+        let length = self.0.length;
+        if input.len() != length || output.len() != length / 2 + 1 {
+            panic!(
+                "Buffer lengths must be {} and {}, but got {} and {}",
+                length,
+                length / 2 + 1,
+                input.len(),
+                output.len()
+            );
+        }
+        let (buffer, scratch) = scratch.split_at_mut(length);
+        for (value, &sample) in buffer.iter_mut().zip(input.iter()) {
+            *value = Complex::new(sample, T::zero());
+        }
+        self.0.process_with_scratch(buffer, scratch);
+        output.copy_from_slice(&buffer[..output.len()]);
+    }
+}
+
+/// A [RealInverseFft] of this module, a [NativeComplexFft] of the bins completed by their conjugates.
+///
+/// # Panics
+/// The transforms panic if a buffer does not have the length of the plan.
+pub struct NativeRealInverse<T>(NativeComplexFft<T>);
+
+impl<T: FftNum> NativeRealInverse<T> {
+    /// Plans the FFT of the length.
+    ///
+    /// # Panics
+    /// If the length is 0.
+    pub fn new(fft_length: usize) -> Self {
+        NativeRealInverse(NativeComplexFft::new(fft_length, true))
+    }
+}
+
+impl<T: FftNum> RealInverseFft<T> for NativeRealInverse<T> {
+    fn fft_length(&self) -> usize {
+        self.0.length
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.length + self.0.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [Complex<T>],
+        output: &mut [T],
+        scratch: &mut [Complex<T>],
+    ) {
+        let length = self.0.length;
+        if input.len() != length / 2 + 1 || output.len() != length {
+            panic!(
+                "Buffer lengths must be {} and {}, but got {} and {}",
+                length / 2 + 1,
+                length,
+                input.len(),
+                output.len()
+            );
+        }
+        let (buffer, scratch) = scratch.split_at_mut(length);
+        buffer[..input.len()].copy_from_slice(input);
+        for k in input.len()..length {
+            buffer[k] = input[length - k].conj();
+        }
+        self.0.process_with_scratch(buffer, scratch);
+        for (sample, value) in output.iter_mut().zip(buffer.iter()) {
+            *sample = value.re;
+        }
+    }
+}
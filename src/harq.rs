@@ -0,0 +1,405 @@
+//! This module provides hybrid ARQ with chase combining: the soft information of a frame which failed to decode
+//! is kept, and added to the one of its retransmission, so the FEC decodes the frame from both receptions.
+//!
+//! The [HarqBuffer] keeps the [LLRs](crate::coded::CodedOFDMDemodulator::demodulate_llrs) of the failed frames
+//! by the sequence number the application gives them, like the one of the [ARQ](crate::arq) frame the sender
+//! retransmits. The LLRs of two noisy receptions of the same bits add up to the ones of a reception at twice the SNR,
+//! 3 dB more, so a frame decodes from both where it decodes from neither alone. The buffer keeps a bounded number
+//! of frames, and evicts the one used least recently to keep another.
+//!
+//! The [RepeatCombiner] does the same for the copies of [repeated frames](crate::frame::CodingConfig::repeat_frames),
+//! keyed on the sequence number in their header: the first copy which decodes is delivered and the later ones dropped,
+//! and when none does, their LLRs are combined for a last attempt.
+//!
+//! # Example
+//! ```
+//! use software_modem::channel::{AwgnChannel, Channel};
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::harq::HarqBuffer;
+//! use software_modem::ofdm::OFDMConfig;
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     soft_output: true,
+//!     ..Default::default()
+//! };
+//! let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+//! let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+//! let mut buffer = HarqBuffer::new(4);
+//!
+//! // the frame is sent until it decodes, every reception adding to the ones before
+//! let payload = vec![0x5a; 200];
+//! for seed in 1.. {
+//!     let mut samples = modulator.encode_frame(&payload);
+//!     AwgnChannel::new(6.0, seed).apply(&mut samples);
+//!     let outcome = buffer.decode(&demodulator, 7, &samples);
+//!     if let Ok(decoded) = outcome.payload {
+//!         assert_eq!(decoded, payload);
+//!         assert_eq!(outcome.receptions, seed as u32);
+//!         break;
+//!     }
+//!     assert!(buffer.contains(7));
+//! }
+//! assert!(buffer.is_empty());
+//! ```
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+use crate::{coded::CodedOFDMDemodulator, error::ModemError, frame::Repetition};
+
+/// The LLRs of a frame which did not decode yet.
+#[derive(Clone, Debug)]
+struct HarqEntry {
+    sequence: u16,
+    llrs: Vec<f32>,
+    receptions: u32,
+}
+
+/// What a [HarqBuffer] made of a reception of a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HarqOutcome {
+    /// The payload of the frame, or the error of the decoding of all its receptions combined.
+    pub payload: Result<Vec<u8>, ModemError>,
+    /// The number of receptions of the frame combined, 1 for the first one.
+    pub receptions: u32,
+    /// Whether the frame decoded only from the combined receptions, not from the last one alone.
+    pub rescued: bool,
+}
+
+/// Keeps the LLRs of the frames which failed to decode, and combines them with their retransmissions,
+/// see the [module](self) documentation.
+#[derive(Clone, Debug)]
+pub struct HarqBuffer {
+    capacity: usize,
+    /// The frames from the one used least recently to the one used last.
+    entries: VecDeque<HarqEntry>,
+    rescued: u64,
+}
+
+impl HarqBuffer {
+    /// Creates a new empty buffer, which keeps the LLRs of up to `capacity` frames.
+    ///
+    /// # Panics
+    /// If the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Capacity must be at least 1, but got 0");
+        HarqBuffer {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            rescued: 0,
+        }
+    }
+
+    /// Demodulates a reception of the frame with the sequence number, and decodes it, combined with the receptions
+    /// kept of the frame if it does not decode alone.
+    ///
+    /// See [decode_llrs](Self::decode_llrs).
+    pub fn decode(
+        &mut self,
+        demodulator: &CodedOFDMDemodulator,
+        sequence: u16,
+        samples: &[f32],
+    ) -> HarqOutcome {
+        self.decode_llrs(demodulator, sequence, demodulator.demodulate_llrs(samples))
+    }
+
+    /// Decodes the LLRs of a reception of the frame with the sequence number, combined with the receptions kept
+    /// of the frame if it does not decode alone.
+    ///
+    /// A frame which decodes is forgotten. One which does not is kept with the sum of the LLRs of all its receptions,
+    /// evicting the frame used least recently from a full buffer. The LLRs of receptions of different lengths
+    /// are added over the shorter one, with the rest of the longer one, which only differ in the samples after the frame.
+    pub fn decode_llrs(
+        &mut self,
+        demodulator: &CodedOFDMDemodulator,
+        sequence: u16,
+        mut llrs: Vec<f32>,
+    ) -> HarqOutcome {
+        let single = demodulator.decode_llrs(&llrs);
+        let Some(kept) = self.take(sequence) else {
+            if single.is_err() {
+                self.keep(sequence, llrs, 1);
+            }
+            return HarqOutcome {
+                payload: single,
+                receptions: 1,
+                rescued: false,
+            };
+        };
+
+        let receptions = kept.receptions + 1;
+        if single.is_ok() {
+            return HarqOutcome {
+                payload: single,
+                receptions,
+                rescued: false,
+            };
+        }
+        add_llrs(&mut llrs, &kept.llrs, 0);
+        let payload = demodulator.decode_llrs(&llrs);
+        trace_event!(
+            DEBUG,
+            "receptions combined",
+            sequence = sequence as u64,
+            receptions = receptions as u64,
+            error = payload.as_ref().err().map(ModemError::name),
+        );
+        let rescued = payload.is_ok();
+        if rescued {
+            self.rescued += 1;
+        } else {
+            self.keep(sequence, llrs, receptions);
+        }
+        HarqOutcome {
+            payload,
+            receptions,
+            rescued,
+        }
+    }
+
+    /// Forgets the receptions of the frame with the sequence number, like once the sender gave up on it,
+    /// and returns whether any were kept.
+    pub fn remove(&mut self, sequence: u16) -> bool {
+        self.take(sequence).is_some()
+    }
+
+    /// Returns whether receptions of the frame with the sequence number are kept.
+    pub fn contains(&self, sequence: u16) -> bool {
+        self.entries.iter().any(|entry| entry.sequence == sequence)
+    }
+
+    /// Returns the number of frames whose receptions are kept.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no receptions are kept.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the most frames whose receptions are kept.
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of frames which decoded only from their combined receptions.
+    pub fn get_rescued(&self) -> u64 {
+        self.rescued
+    }
+
+    /// Takes the receptions kept of the frame with the sequence number out of the buffer.
+    fn take(&mut self, sequence: u16) -> Option<HarqEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.sequence == sequence)?;
+        self.entries.remove(index)
+    }
+
+    fn keep(&mut self, sequence: u16, llrs: Vec<f32>, receptions: u32) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HarqEntry {
+            sequence,
+            llrs,
+            receptions,
+        });
+    }
+}
+
+/// Adds the kept LLRs to the ones of a reception from the `skip`th one on, over the shorter of both,
+/// with the rest of the longer one.
+fn add_llrs(llrs: &mut Vec<f32>, kept: &[f32], skip: usize) {
+    if llrs.len() < kept.len() {
+        llrs.extend_from_slice(&kept[llrs.len()..]);
+    }
+    llrs.iter_mut()
+        .zip(kept)
+        .skip(skip)
+        .for_each(|(llr, kept)| *llr += kept);
+}
+
+/// What a [RepeatCombiner] made of a copy of a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepeatOutcome {
+    /// The payload of the frame, `None` if the copy did not decode but more copies of its frame follow,
+    /// or if the frame was delivered before. Or the error of the header of the copy,
+    /// or of the decoding of all the copies of the frame combined.
+    pub payload: Result<Option<Vec<u8>>, ModemError>,
+    /// The copy, `None` if its header did not decode, or if the frames are not repeated.
+    pub repetition: Option<Repetition>,
+    /// Whether the frame decoded only from its copies combined, not from any of them alone.
+    pub rescued: bool,
+}
+
+/// Delivers the first copy of a [repeated frame](crate::frame::CodingConfig::repeat_frames) which decodes,
+/// and combines the copies when none does, see the [module](self) documentation.
+///
+/// The copies which fail are kept in a [HarqBuffer] by their sequence number, with the LLRs of their headers,
+/// which differ in the index of the copy, left as the ones of the first copy kept. The last copy decodes from all
+/// of them combined. The copies of a frame whose last copy is lost are kept until the buffer evicts them.
+///
+/// # Example
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::{CodingConfig, Repetition};
+/// use software_modem::harq::RepeatCombiner;
+/// use software_modem::ofdm::OFDMConfig;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 16,
+///     soft_output: true,
+///     ..Default::default()
+/// };
+/// let coding = CodingConfig {
+///     repeat_frames: 3,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+/// let mut combiner = RepeatCombiner::new(4);
+///
+/// // the three copies of the frame, one after another
+/// let samples = modulator.encode_frame(b"ping");
+/// let copies: Vec<&[f32]> = samples.chunks(samples.len() / 3).collect();
+///
+/// // the first copy is delivered, the others are dropped
+/// let outcome = combiner.decode(&demodulator, copies[0]);
+/// assert_eq!(outcome.payload, Ok(Some(b"ping".to_vec())));
+/// assert_eq!(
+///     outcome.repetition,
+///     Some(Repetition {
+///         sequence: 0,
+///         index: 0,
+///         count: 3
+///     })
+/// );
+/// for copy in &copies[1..] {
+///     assert_eq!(combiner.decode(&demodulator, copy).payload, Ok(None));
+/// }
+/// assert_eq!(combiner.get_duplicates(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RepeatCombiner {
+    buffer: HarqBuffer,
+    /// The sequence number of the frame delivered last, whose later copies are dropped.
+    delivered: Option<u8>,
+    duplicates: u64,
+}
+
+impl RepeatCombiner {
+    /// Creates a new combiner, which keeps the copies of up to `capacity` frames.
+    ///
+    /// # Panics
+    /// If the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        RepeatCombiner {
+            buffer: HarqBuffer::new(capacity),
+            delivered: None,
+            duplicates: 0,
+        }
+    }
+
+    /// Demodulates a copy of a frame and decodes it, see [decode_llrs](Self::decode_llrs).
+    pub fn decode(&mut self, demodulator: &CodedOFDMDemodulator, samples: &[f32]) -> RepeatOutcome {
+        self.decode_llrs(demodulator, demodulator.demodulate_llrs(samples))
+    }
+
+    /// Decodes the LLRs of a copy of a frame.
+    ///
+    /// A copy of the frame delivered last is dropped. Another one is delivered if it decodes,
+    /// otherwise it is added to the copies kept of its frame, and the last copy of the frame
+    /// decodes from all of them combined. Frames which are not repeated are decoded alone.
+    pub fn decode_llrs(
+        &mut self,
+        demodulator: &CodedOFDMDemodulator,
+        mut llrs: Vec<f32>,
+    ) -> RepeatOutcome {
+        let repetition = match demodulator.decode_repetition(&llrs) {
+            Ok(Some(repetition)) => repetition,
+            Ok(None) => {
+                return RepeatOutcome {
+                    payload: demodulator.decode_llrs(&llrs).map(Some),
+                    repetition: None,
+                    rescued: false,
+                };
+            }
+            Err(error) => {
+                return RepeatOutcome {
+                    payload: Err(error),
+                    repetition: None,
+                    rescued: false,
+                };
+            }
+        };
+        let outcome = |payload, rescued| RepeatOutcome {
+            payload,
+            repetition: Some(repetition),
+            rescued,
+        };
+        if self.delivered == Some(repetition.sequence) {
+            self.duplicates += 1;
+            return outcome(Ok(None), false);
+        }
+
+        let sequence = u16::from(repetition.sequence);
+        let kept = self.buffer.take(sequence);
+        let single = demodulator.decode_llrs(&llrs);
+        if single.is_ok() {
+            self.delivered = Some(repetition.sequence);
+            return outcome(single.map(Some), false);
+        }
+
+        let receptions = match kept {
+            Some(mut kept) => {
+                // the header of the copy kept, so only the payloads of the copies add up
+                add_llrs(&mut kept.llrs, &llrs, demodulator.get_header_llrs());
+                llrs = kept.llrs;
+                kept.receptions + 1
+            }
+            None => 1,
+        };
+        if repetition.index + 1 < repetition.count {
+            self.buffer.keep(sequence, llrs, receptions);
+            return outcome(Ok(None), false);
+        }
+        if receptions == 1 {
+            return outcome(single.map(Some), false);
+        }
+
+        let payload = demodulator.decode_llrs(&llrs);
+        trace_event!(
+            DEBUG,
+            "copies combined",
+            sequence = sequence as u64,
+            receptions = receptions as u64,
+            error = payload.as_ref().err().map(ModemError::name),
+        );
+        let rescued = payload.is_ok();
+        if rescued {
+            self.buffer.rescued += 1;
+            self.delivered = Some(repetition.sequence);
+        }
+        outcome(payload.map(Some), rescued)
+    }
+
+    /// Returns the number of frames which decoded only from their copies combined.
+    pub fn get_rescued(&self) -> u64 {
+        self.buffer.get_rescued()
+    }
+
+    /// Returns the number of copies dropped, as their frame was delivered before.
+    pub fn get_duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Returns the buffer of the copies kept of the frames which did not decode yet.
+    pub fn get_buffer(&self) -> &HarqBuffer {
+        &self.buffer
+    }
+}
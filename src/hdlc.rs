@@ -0,0 +1,187 @@
+//! This module provides the HDLC framing of AX.25 packet radio, the bits a packet modem sends for a frame.
+//!
+//! A frame is sent between flags `0x7e`, with its bytes from the least significant bit and a
+//! [frame check sequence](crate::crc::crc16_x25) after them, the low byte first. Within a frame,
+//! a 0 is stuffed after every five 1s, so the six 1s of a flag never appear inside it.
+//! The bits are sent [NRZI](nrzi_encode) encoded, a 0 as a change of the line and a 1 as no change,
+//! so a receiver only has to tell whether the line changed, not which way.
+//! The [HdlcDecoder] finds the frames in a stream of bits and checks them.
+//!
+//! # Example
+//! ```
+//! use software_modem::hdlc::{HdlcDecoder, encode_frame, nrzi_decode, nrzi_encode};
+//!
+//! let line = nrzi_encode(&encode_frame(b"packet", 4), 1);
+//! let mut decoder = HdlcDecoder::new();
+//! assert_eq!(decoder.push(&nrzi_decode(&line, 1)), vec![b"packet".to_vec()]);
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::crc::crc16_x25;
+
+/// The flag between frames, six 1s between two 0s.
+pub const FLAG: u8 = 0x7e;
+
+/// The fewest bytes of a frame the [HdlcDecoder] checks, one with the frame check sequence.
+pub const MIN_FRAME_BYTES: usize = 3;
+
+/// The most bytes of a frame the [HdlcDecoder] collects, more than the 330 of the largest AX.25 frame.
+pub const MAX_FRAME_BYTES: usize = 1024;
+
+/// Returns the bits of the frame with its frame check sequence, stuffed, after `preamble_flags` flags
+/// and before a closing one.
+///
+/// The flags of the preamble give the receiver time to lock onto the bits.
+pub fn encode_frame(frame: &[u8], preamble_flags: usize) -> Vec<u8> {
+    let flag: Vec<u8> = (0..8).map(|i| (FLAG >> i) & 1).collect();
+    let mut bits = flag.repeat(preamble_flags.max(1));
+
+    let mut ones = 0;
+    for byte in frame.iter().chain(&crc16_x25(frame).to_le_bytes()) {
+        for i in 0..8 {
+            let bit = (byte >> i) & 1;
+            bits.push(bit);
+            ones = if bit == 1 { ones + 1 } else { 0 };
+            if ones == 5 {
+                bits.push(0);
+                ones = 0;
+            }
+        }
+    }
+    bits.extend(flag);
+    bits
+}
+
+/// Encodes bits into the levels of an NRZI line starting at a level, a 0 toggles the level and a 1 keeps it.
+///
+/// # Example
+/// ```
+/// use software_modem::hdlc::nrzi_encode;
+///
+/// assert_eq!(nrzi_encode(&[0, 1, 1, 0, 0], 1), vec![0, 0, 0, 1, 0]);
+/// ```
+pub fn nrzi_encode(bits: &[u8], initial_level: u8) -> Vec<u8> {
+    bits.iter()
+        .scan(initial_level & 1, |level, &bit| {
+            if bit & 1 == 0 {
+                *level ^= 1;
+            }
+            Some(*level)
+        })
+        .collect()
+}
+
+/// Decodes the levels of an NRZI line back into bits, the inverse of [nrzi_encode] with the same initial level.
+pub fn nrzi_decode(levels: &[u8], initial_level: u8) -> Vec<u8> {
+    levels
+        .iter()
+        .scan(initial_level & 1, |previous, &level| {
+            let bit = u8::from(level & 1 == *previous);
+            *previous = level & 1;
+            Some(bit)
+        })
+        .collect()
+}
+
+/// Finds the frames between the flags of a stream of bits, and keeps the ones whose frame check sequence matches.
+///
+/// The decoder keeps its state between calls, so a stream can be pushed in pieces of any length.
+/// Seven 1s abort a frame, and frames that are not whole bytes, shorter than [MIN_FRAME_BYTES]
+/// or longer than [MAX_FRAME_BYTES] are dropped. Flags in a row are not frames, but noise between transmissions
+/// can look like flags around a frame, whose check fails and is counted with the [errors](Self::get_fcs_errors).
+#[derive(Clone, Debug, Default)]
+pub struct HdlcDecoder {
+    /// The last eight bits, the latest in the most significant bit.
+    shift: u8,
+    /// The 1s in a row up to the latest bit.
+    ones: u32,
+    /// Whether a flag started a frame, which no abort ended since.
+    in_frame: bool,
+    bits: Vec<u8>,
+    frames: u64,
+    fcs_errors: u64,
+}
+
+impl HdlcDecoder {
+    /// Creates a decoder waiting for a flag.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the bits, and returns the frames they end, without their frame check sequences.
+    pub fn push(&mut self, bits: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        for &bit in bits {
+            let bit = bit & 1;
+            self.shift = (self.shift >> 1) | (bit << 7);
+            if bit == 1 {
+                self.ones += 1;
+                if self.ones >= 7 {
+                    self.in_frame = false;
+                    self.bits.clear();
+                    continue;
+                }
+            } else {
+                let ones = core::mem::replace(&mut self.ones, 0);
+                // a stuffed 0
+                if ones == 5 {
+                    continue;
+                }
+            }
+            if self.shift == FLAG {
+                // the bits of the flag but its last were taken as bits of the frame
+                self.bits.truncate(self.bits.len().saturating_sub(7));
+                if self.in_frame
+                    && let Some(frame) = self.finish()
+                {
+                    frames.push(frame);
+                }
+                self.in_frame = true;
+                self.bits.clear();
+            } else if self.in_frame {
+                self.bits.push(bit);
+                if self.bits.len() > 8 * MAX_FRAME_BYTES {
+                    self.in_frame = false;
+                    self.bits.clear();
+                }
+            }
+        }
+        frames
+    }
+
+    /// Returns the frames whose frame check sequence matched.
+    pub fn get_frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the frames whose frame check sequence did not match.
+    pub fn get_fcs_errors(&self) -> u64 {
+        self.fcs_errors
+    }
+
+    /// Returns the frame of the bits between two flags, if it is whole bytes long enough and its check matches.
+    fn finish(&mut self) -> Option<Vec<u8>> {
+        if !self.bits.len().is_multiple_of(8) || self.bits.len() < 8 * MIN_FRAME_BYTES {
+            return None;
+        }
+        let mut bytes: Vec<u8> = self
+            .bits
+            .chunks_exact(8)
+            .map(|bits| {
+                bits.iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &bit)| byte | (bit << i))
+            })
+            .collect();
+        let fcs = u16::from_le_bytes([bytes[bytes.len() - 2], bytes[bytes.len() - 1]]);
+        bytes.truncate(bytes.len() - 2);
+        if crc16_x25(&bytes) == fcs {
+            self.frames += 1;
+            Some(bytes)
+        } else {
+            self.fcs_errors += 1;
+            None
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! This module provides interleavers, which reorder coded bits so that bursts of errors are spread out.
+//!
+//! Deep fades on adjacent subcarriers corrupt consecutive coded bits, which a convolutional code handles poorly.
+//! After deinterleaving, the corrupted bits are scattered between good ones and can be corrected.
+//!
+//! The [Interleaver] works on blocks like the coded bits of one OFDM symbol,
+//! the [ConvolutionalInterleaver] spreads bursts across several symbols, like impulse noise or audio dropouts.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+/// A row-column block interleaver.
+///
+/// Blocks of `rows * cols` values are written row by row and read column by column.
+/// A shorter last block only fills the first positions, the empty positions are skipped when reading.
+///
+/// # Example
+/// ```
+/// use software_modem::interleaver::Interleaver;
+///
+/// let interleaver = Interleaver::new(2, 3);
+/// assert_eq!(interleaver.interleave(&[0, 1, 2, 3, 4, 5]), vec![0, 3, 1, 4, 2, 5]);
+///
+/// // partially filled blocks are inverted as well
+/// let values: Vec<u32> = (0..17).collect();
+/// let interleaved = interleaver.interleave(&values);
+/// assert_eq!(interleaved[12..], [12, 15, 13, 16, 14]);
+/// assert_eq!(interleaver.deinterleave(&interleaved), values);
+/// ```
+///
+/// A burst of corrupted LLRs, like the coded bits of faded subcarriers, breaks the Viterbi decoder,
+/// unless it is spread out by the interleaver.
+/// ```
+/// use software_modem::bits::bits_to_llrs;
+/// use software_modem::fec::convolutional::ConvolutionalCode;
+/// use software_modem::interleaver::Interleaver;
+///
+/// let code = ConvolutionalCode::k7_rate_half();
+/// let interleaver = Interleaver::new(12, 16);
+/// let bits: Vec<u8> = (0..378).map(|i| ((i * 13) % 7 < 3) as u8).collect();
+/// let coded = code.encode(&bits);
+///
+/// let fade = |llrs: &mut Vec<f32>| {
+///     // 16 consecutive bits of every block of 192 are received weak and wrong
+///     for block in llrs.chunks_mut(192) {
+///         for llr in block.iter_mut().skip(40).take(16) {
+///             *llr *= -0.5;
+///         }
+///     }
+/// };
+///
+/// let mut llrs = bits_to_llrs(&coded);
+/// fade(&mut llrs);
+/// assert_ne!(code.decode_soft(&llrs), bits);
+///
+/// let mut llrs = bits_to_llrs(&interleaver.interleave(&coded));
+/// fade(&mut llrs);
+/// assert_eq!(code.decode_soft(&interleaver.deinterleave(&llrs)), bits);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Interleaver {
+    rows: usize,
+    cols: usize,
+}
+
+impl Interleaver {
+    /// Creates a new block interleaver with blocks of `rows * cols` values.
+    ///
+    /// # Panics
+    /// If `rows` or `cols` is zero.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Interleaver::try_new(rows, cols).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    /// Creates a new block interleaver, or returns why the dimensions are invalid.
+    fn try_new(rows: usize, cols: usize) -> Result<Self, String> {
+        if rows == 0 || cols == 0 {
+            return Err(format!(
+                "Interleaver dimensions must not be zero, but got {} rows and {} cols",
+                rows, cols
+            ));
+        }
+        Ok(Interleaver { rows, cols })
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the number of values per block, `rows * cols`.
+    pub fn block_length(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Interleaves the values, block by block.
+    pub fn interleave<T: Copy>(&self, values: &[T]) -> Vec<T> {
+        let mut output = Vec::with_capacity(values.len());
+        for block in values.chunks(self.block_length()) {
+            output.extend(self.read_order(block.len()).map(|i| block[i]));
+        }
+        output
+    }
+
+    /// Reverts [interleave](Interleaver::interleave), block by block.
+    pub fn deinterleave<T: Copy>(&self, values: &[T]) -> Vec<T> {
+        let mut output = values.to_vec();
+        let mut offset = 0;
+        for block in values.chunks(self.block_length()) {
+            for (&value, i) in block.iter().zip(self.read_order(block.len())) {
+                output[offset + i] = value;
+            }
+            offset += block.len();
+        }
+        output
+    }
+
+    /// Returns the positions written row by row, in the order they are read column by column.
+    fn read_order(&self, length: usize) -> impl Iterator<Item = usize> + use<> {
+        let (rows, cols) = (self.rows, self.cols);
+        (0..cols)
+            .flat_map(move |col| (0..rows).map(move |row| row * cols + col))
+            .filter(move |&i| i < length)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Interleaver {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            rows: usize,
+            cols: usize,
+        }
+
+        let Fields { rows, cols } = Fields::deserialize(deserializer)?;
+        Interleaver::try_new(rows, cols).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A Forney convolutional interleaver.
+///
+/// Values are distributed over `branches` in turn, branch `j` delays its values by `j * depth` cells.
+/// Unlike the block interleaver, it spreads a burst over a long span without large blocks,
+/// at the price of a latency of `(branches - 1) * depth * branches` values, which are flushed at the end of a frame.
+///
+/// # Example
+/// ```
+/// use software_modem::interleaver::ConvolutionalInterleaver;
+///
+/// let interleaver = ConvolutionalInterleaver::new(3, 1);
+/// assert_eq!(interleaver.latency(), 6);
+///
+/// let values: Vec<u32> = (1..=9).collect();
+/// let interleaved = interleaver.interleave(&values);
+/// assert_eq!(interleaved, vec![1, 0, 0, 4, 2, 0, 7, 5, 3, 0, 8, 6, 0, 0, 9]);
+/// assert_eq!(interleaver.deinterleave(&interleaved), values);
+///
+/// // a burst of 3 values is spread out
+/// let mut received = interleaved.clone();
+/// received[6..9].fill(99);
+/// assert_eq!(interleaver.deinterleave(&received), vec![1, 2, 99, 4, 99, 6, 99, 8, 9]);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConvolutionalInterleaver {
+    branches: usize,
+    depth: usize,
+}
+
+impl ConvolutionalInterleaver {
+    /// Creates a new convolutional interleaver with `branches` branches, each `depth` cells deeper than the previous.
+    ///
+    /// # Panics
+    /// If `branches` or `depth` is zero.
+    pub fn new(branches: usize, depth: usize) -> Self {
+        ConvolutionalInterleaver::try_new(branches, depth)
+            .unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    /// Creates a new convolutional interleaver, or returns why the dimensions are invalid.
+    fn try_new(branches: usize, depth: usize) -> Result<Self, String> {
+        if branches == 0 || depth == 0 {
+            return Err(format!(
+                "Interleaver dimensions must not be zero, but got {} branches and a depth of {}",
+                branches, depth
+            ));
+        }
+        Ok(ConvolutionalInterleaver { branches, depth })
+    }
+
+    /// Returns the number of branches.
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+
+    /// Returns the number of cells every branch is deeper than the previous one.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the combined delay of the interleaver and the deinterleaver in values.
+    pub fn latency(&self) -> usize {
+        (self.branches - 1) * self.depth * self.branches
+    }
+
+    /// Interleaves the values of one frame, starting from and flushing with `T::default()`.
+    ///
+    /// The returned buffer is [latency](ConvolutionalInterleaver::latency) values longer than the input.
+    pub fn interleave<T: Copy + Default>(&self, values: &[T]) -> Vec<T> {
+        let mut output = vec![T::default(); values.len() + self.latency()];
+        for (n, &value) in values.iter().enumerate() {
+            output[self.delayed(n)] = value;
+        }
+        output
+    }
+
+    /// Reverts [interleave](ConvolutionalInterleaver::interleave) for one frame, dropping the flushed values.
+    ///
+    /// # Panics
+    /// If there are fewer values than the latency.
+    pub fn deinterleave<T: Copy>(&self, values: &[T]) -> Vec<T> {
+        if values.len() < self.latency() {
+            panic!(
+                "Number of values must be at least the latency of {}, but got {}",
+                self.latency(),
+                values.len()
+            );
+        }
+        (0..values.len() - self.latency())
+            .map(|n| values[self.delayed(n)])
+            .collect()
+    }
+
+    /// Returns the position value `n` leaves the interleaver at.
+    fn delayed(&self, n: usize) -> usize {
+        n + (n % self.branches) * self.depth * self.branches
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConvolutionalInterleaver {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            branches: usize,
+            depth: usize,
+        }
+
+        let Fields { branches, depth } = Fields::deserialize(deserializer)?;
+        ConvolutionalInterleaver::try_new(branches, depth).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,490 @@
+//! Captures of the input of a receiver, to be replayed exactly as it came in.
+//!
+//! A capture holds the blocks of samples a [StreamDemodulator](crate::stream::StreamDemodulator) was pushed,
+//! each with the time it arrived, after the [metadata](CaptureMetadata) of the modem: the sample rate, the name of
+//! the profile and a [hash](profile_hash) of its configuration. [Markers](Marker) note where the receiver acquired
+//! and lost sync and decoded or failed a frame. A receiver records with
+//! [push_recorded](crate::stream::StreamDemodulator::push_recorded), and a stream of the same profile and squelch
+//! [replays](crate::stream::StreamDemodulator::replay) the blocks as they were pushed, to the same payloads and
+//! [stats](crate::stream::LinkStats).
+//!
+//! The file is the magic `SMCP` and a version byte, followed by chunks of a four byte id, the little-endian `u32`
+//! length of their content and the content, all in little-endian:
+//!
+//! | Id | Content |
+//! |---|---|
+//! | `meta` | sample rate `u32`, start time `u64` in µs since the Unix epoch, profile hash `u32`, `u16` length and UTF-8 bytes of the profile name |
+//! | `samp` | time of the block `u64` in µs since the Unix epoch, followed by its `f32` samples |
+//! | `mark` | sample of the capture the marker is at `u64`, kind `u8` |
+//!
+//! The metadata is the first chunk, the others follow in the order they were written, and readers skip
+//! chunks with other ids.
+//!
+//! # Example
+//! ```
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::io::capture::{CaptureMetadata, CaptureReader, CaptureWriter};
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::stream::StreamDemodulator;
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! };
+//! let coding = CodingConfig::default();
+//! let stream = || StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm.clone(), coding.clone()), 0.01, 2400);
+//! let mut signal = vec![0.0; 1000];
+//! signal.extend(CodedOFDMModulator::new(ofdm.clone(), coding.clone()).encode_frame(b"it did decode"));
+//! signal.extend(vec![0.0; 4800]);
+//!
+//! // the user's receiver records what it is pushed
+//! let metadata = CaptureMetadata::new(48000, "custom", &ofdm, &coding);
+//! let mut capture = CaptureWriter::new(Vec::new(), &metadata).unwrap();
+//! let mut live = stream();
+//! let mut decoded = Vec::new();
+//! for block in signal.chunks(441) {
+//!     decoded.extend(live.push_recorded(block, &mut capture).unwrap());
+//! }
+//! let bytes = capture.finish().unwrap();
+//!
+//! // and the capture replays to the same payloads and stats elsewhere
+//! let mut reader = CaptureReader::new(bytes.as_slice()).unwrap();
+//! assert_eq!(reader.get_metadata(), &metadata);
+//! let mut replayed = stream();
+//! assert_eq!(replayed.replay(&mut reader).unwrap(), decoded);
+//! assert_eq!(replayed.stats(), live.stats());
+//! ```
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    crc::crc32,
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, profiles::beacon_payload},
+};
+
+/// The first bytes of a capture.
+const MAGIC: &[u8; 4] = b"SMCP";
+
+/// The version of the format written.
+const CAPTURE_VERSION: u8 = 1;
+
+const METADATA_ID: &[u8; 4] = b"meta";
+const SAMPLES_ID: &[u8; 4] = b"samp";
+const MARKER_ID: &[u8; 4] = b"mark";
+
+/// Errors reading or writing a capture.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The capture could not be read or written.
+    Io(std::io::Error),
+    /// The capture does not start with the magic and the metadata, or a chunk is malformed.
+    InvalidFile,
+    /// The capture was written in a version of the format this one does not read.
+    UnsupportedVersion(u8),
+    /// The capture ends within a chunk, after the whole chunks.
+    TruncatedChunk,
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Io(error) => write!(f, "Capture error: {}", error),
+            CaptureError::InvalidFile => write!(f, "Invalid capture file"),
+            CaptureError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported capture version {}", version)
+            }
+            CaptureError::TruncatedChunk => write!(f, "Capture ends within a chunk"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CaptureError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(error: std::io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+/// Returns a hash of the configurations of a coded modem, the CRC-32 of their [beacon payload](beacon_payload),
+/// to tell whether a capture is replayed with the profile it was recorded with.
+pub fn profile_hash(ofdm: &OFDMConfig, coding: &CodingConfig) -> u32 {
+    crc32(&beacon_payload(ofdm, coding))
+}
+
+/// Returns the current time in µs since the Unix epoch, 0 before it.
+fn now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_micros() as u64)
+}
+
+/// The modem a capture was recorded with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureMetadata {
+    /// Sample rate of the samples in Hz.
+    pub sample_rate: u32,
+    /// Time the capture started in µs since the Unix epoch.
+    pub start_time_us: u64,
+    /// Name of the profile, like the one of an [OFDMProfile](crate::ofdm::profiles::OFDMProfile), at most 65535 bytes.
+    pub profile_name: String,
+    /// See [profile_hash].
+    pub profile_hash: u32,
+}
+
+impl CaptureMetadata {
+    /// Creates the metadata of a capture starting now, of a coded modem of the configurations.
+    pub fn new(
+        sample_rate: u32,
+        profile_name: impl Into<String>,
+        ofdm: &OFDMConfig,
+        coding: &CodingConfig,
+    ) -> Self {
+        CaptureMetadata {
+            sample_rate,
+            start_time_us: now_us(),
+            profile_name: profile_name.into(),
+            profile_hash: profile_hash(ofdm, coding),
+        }
+    }
+}
+
+/// What happened in a receiver at a [Marker].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    /// The squelch opened.
+    SyncAcquired,
+    /// The squelch closed.
+    SyncLost,
+    /// A frame decoded with a valid CRC.
+    FrameDecoded,
+    /// A burst was tried as a frame and did not decode.
+    FrameFailed,
+}
+
+impl MarkerKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            MarkerKind::SyncAcquired => 0,
+            MarkerKind::SyncLost => 1,
+            MarkerKind::FrameDecoded => 2,
+            MarkerKind::FrameFailed => 3,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(MarkerKind::SyncAcquired),
+            1 => Some(MarkerKind::SyncLost),
+            2 => Some(MarkerKind::FrameDecoded),
+            3 => Some(MarkerKind::FrameFailed),
+            _ => None,
+        }
+    }
+}
+
+/// An event of the receiver in a capture.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// Number of samples written before the marker.
+    pub sample: u64,
+    pub kind: MarkerKind,
+}
+
+/// A chunk of a capture after its metadata.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptureChunk {
+    /// A block of samples as it was pushed, with the time it arrived in µs since the Unix epoch.
+    Samples {
+        time_us: u64,
+        samples: Vec<f32>,
+    },
+    Marker(Marker),
+}
+
+/// Creates a capture file with the metadata.
+///
+/// # Errors
+/// [CaptureError::Io] if the file can not be created or written.
+pub fn create_capture(
+    path: impl AsRef<Path>,
+    metadata: &CaptureMetadata,
+) -> Result<CaptureWriter, CaptureError> {
+    CaptureWriter::new(BufWriter::new(File::create(path)?), metadata)
+}
+
+/// Opens a capture file and reads its metadata.
+///
+/// # Errors
+/// See [CaptureReader::new].
+pub fn open_capture(path: impl AsRef<Path>) -> Result<CaptureReader, CaptureError> {
+    CaptureReader::new(BufReader::new(File::open(path)?))
+}
+
+/// Writes a capture to a file, or to any other writer, block by block, see the [module](self) documentation.
+pub struct CaptureWriter<W: Write = BufWriter<File>> {
+    writer: W,
+    samples_written: u64,
+    bytes: Vec<u8>,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Creates a writer of a capture to the writer, and writes the header and the metadata.
+    ///
+    /// # Panics
+    /// If the profile name is longer than 65535 bytes.
+    ///
+    /// # Errors
+    /// [CaptureError::Io] if the metadata can not be written.
+    pub fn new(mut writer: W, metadata: &CaptureMetadata) -> Result<Self, CaptureError> {
+        let name = metadata.profile_name.as_bytes();
+        let name_length = u16::try_from(name.len()).unwrap_or_else(|_| {
+            panic!(
+                "Profile name must be at most {} bytes, but got {} bytes",
+                u16::MAX,
+                name.len()
+            )
+        });
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[CAPTURE_VERSION])?;
+
+        let mut content = Vec::with_capacity(18 + name.len());
+        content.extend(metadata.sample_rate.to_le_bytes());
+        content.extend(metadata.start_time_us.to_le_bytes());
+        content.extend(metadata.profile_hash.to_le_bytes());
+        content.extend(name_length.to_le_bytes());
+        content.extend(name);
+        let mut capture = CaptureWriter {
+            writer,
+            samples_written: 0,
+            bytes: Vec::new(),
+        };
+        capture.write_chunk(METADATA_ID, &content)?;
+        Ok(capture)
+    }
+
+    /// Appends a block of samples, stamped with the current time.
+    ///
+    /// # Errors
+    /// [CaptureError::Io] if the samples can not be written.
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<(), CaptureError> {
+        self.write_samples_at(now_us(), samples)
+    }
+
+    /// Appends a block of samples, which arrived at the time in µs since the Unix epoch.
+    ///
+    /// # Errors
+    /// [CaptureError::Io] if the samples can not be written.
+    pub fn write_samples_at(&mut self, time_us: u64, samples: &[f32]) -> Result<(), CaptureError> {
+        let mut content = Vec::with_capacity(8 + 4 * samples.len());
+        content.extend(time_us.to_le_bytes());
+        for sample in samples {
+            content.extend(sample.to_le_bytes());
+        }
+        self.write_chunk(SAMPLES_ID, &content)?;
+        self.samples_written += samples.len() as u64;
+        Ok(())
+    }
+
+    /// Appends a marker after the samples written so far.
+    ///
+    /// # Errors
+    /// [CaptureError::Io] if the marker can not be written.
+    pub fn write_marker(&mut self, kind: MarkerKind) -> Result<(), CaptureError> {
+        let mut content = self.samples_written.to_le_bytes().to_vec();
+        content.push(kind.to_byte());
+        self.write_chunk(MARKER_ID, &content)
+    }
+
+    /// Returns the number of samples written so far.
+    pub fn get_samples_written(&self) -> u64 {
+        self.samples_written
+    }
+
+    /// Flushes the capture written so far, and returns the underlying writer.
+    ///
+    /// Dropping the writer flushes it as well, but loses the errors.
+    ///
+    /// # Errors
+    /// [CaptureError::Io] if the capture can not be flushed.
+    pub fn finish(mut self) -> Result<W, CaptureError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_chunk(&mut self, id: &[u8; 4], content: &[u8]) -> Result<(), CaptureError> {
+        self.bytes.clear();
+        self.bytes.extend(id);
+        self.bytes.extend((content.len() as u32).to_le_bytes());
+        self.bytes.extend(content);
+        self.writer.write_all(&self.bytes)?;
+        Ok(())
+    }
+}
+
+/// Reads a capture from a file, or from any other reader, chunk by chunk, see the [module](self) documentation.
+///
+/// It is also an iterator over the chunks.
+pub struct CaptureReader<R: Read = BufReader<File>> {
+    reader: R,
+    metadata: CaptureMetadata,
+    finished: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Creates a reader of the capture the reader yields, and reads its header and metadata.
+    ///
+    /// # Errors
+    /// - [CaptureError::Io] if the capture can not be read.
+    /// - [CaptureError::InvalidFile] if it does not start with the magic and the metadata.
+    /// - [CaptureError::UnsupportedVersion] if it is of another version of the format.
+    pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+        let mut header = [0; 5];
+        match read_full(&mut reader, &mut header)? {
+            5 if &header[..4] == MAGIC => {}
+            _ => return Err(CaptureError::InvalidFile),
+        }
+        if header[4] != CAPTURE_VERSION {
+            return Err(CaptureError::UnsupportedVersion(header[4]));
+        }
+
+        let (id, content) = match read_chunk(&mut reader) {
+            Ok(Some(chunk)) => chunk,
+            Err(CaptureError::Io(error)) => return Err(CaptureError::Io(error)),
+            _ => return Err(CaptureError::InvalidFile),
+        };
+        if &id != METADATA_ID || content.len() < 18 {
+            return Err(CaptureError::InvalidFile);
+        }
+        let name_length = usize::from(u16::from_le_bytes([content[16], content[17]]));
+        let profile_name = content
+            .get(18..18 + name_length)
+            .and_then(|name| String::from_utf8(name.to_vec()).ok())
+            .ok_or(CaptureError::InvalidFile)?;
+        let metadata = CaptureMetadata {
+            sample_rate: u32::from_le_bytes(content[..4].try_into().unwrap()),
+            start_time_us: u64::from_le_bytes(content[4..12].try_into().unwrap()),
+            profile_hash: u32::from_le_bytes(content[12..16].try_into().unwrap()),
+            profile_name,
+        };
+        Ok(CaptureReader {
+            reader,
+            metadata,
+            finished: false,
+        })
+    }
+
+    /// Returns the metadata of the capture.
+    pub fn get_metadata(&self) -> &CaptureMetadata {
+        &self.metadata
+    }
+
+    /// Reads the next chunk, `None` at the end of the capture.
+    ///
+    /// # Errors
+    /// - [CaptureError::Io] if the capture can not be read.
+    /// - [CaptureError::InvalidFile] if a chunk of samples or a marker is malformed.
+    /// - [CaptureError::TruncatedChunk] once, if the capture ends within a chunk.
+    pub fn read_chunk(&mut self) -> Result<Option<CaptureChunk>, CaptureError> {
+        while !self.finished {
+            let (id, content) = match read_chunk(&mut self.reader) {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(error) => {
+                    self.finished = true;
+                    return Err(error);
+                }
+            };
+            match &id {
+                SAMPLES_ID if content.len() >= 8 && content.len() % 4 == 0 => {
+                    return Ok(Some(CaptureChunk::Samples {
+                        time_us: u64::from_le_bytes(content[..8].try_into().unwrap()),
+                        samples: content[8..]
+                            .chunks_exact(4)
+                            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                            .collect(),
+                    }));
+                }
+                MARKER_ID if content.len() == 9 => {
+                    let kind =
+                        MarkerKind::from_byte(content[8]).ok_or(CaptureError::InvalidFile)?;
+                    return Ok(Some(CaptureChunk::Marker(Marker {
+                        sample: u64::from_le_bytes(content[..8].try_into().unwrap()),
+                        kind,
+                    })));
+                }
+                SAMPLES_ID | MARKER_ID => return Err(CaptureError::InvalidFile),
+                _ => {}
+            }
+        }
+        self.finished = true;
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureChunk, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_chunk().transpose()
+    }
+}
+
+/// Reads into the buffer up to its end or the end of the input, and returns the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, CaptureError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(count) => filled += count,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// The id and the content of a chunk.
+type RawChunk = ([u8; 4], Vec<u8>);
+
+/// Reads the id and the content of the next chunk, `None` at the end of the input.
+fn read_chunk<R: Read>(reader: &mut R) -> Result<Option<RawChunk>, CaptureError> {
+    let mut header = [0; 8];
+    match read_full(reader, &mut header)? {
+        0 => return Ok(None),
+        8 => {}
+        _ => return Err(CaptureError::TruncatedChunk),
+    }
+    let length = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+    // the content is read in steps, so that a corrupted length does not allocate all of it up front
+    let mut content = Vec::new();
+    let mut step = [0; 4096];
+    while content.len() < length {
+        let wanted = (length - content.len()).min(step.len());
+        let count = read_full(reader, &mut step[..wanted])?;
+        content.extend_from_slice(&step[..count]);
+        if count < wanted {
+            return Err(CaptureError::TruncatedChunk);
+        }
+    }
+    Ok(Some((header[..4].try_into().unwrap(), content)))
+}
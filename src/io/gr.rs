@@ -0,0 +1,359 @@
+//! Readers and writers of the raw sample files of GNU Radio.
+//!
+//! The File Source and File Sink blocks of GNU Radio store samples without a header, as consecutive
+//! little-endian `f32`, or as pairs of them for `complex64`, I first. [read_cf32] and [read_f32] open such files
+//! as a [GrReader], which reads them in chunks, so captures of many GB never have to fit into memory,
+//! and [write_cf32] and [write_f32] create them as a [GrWriter].
+//!
+//! A capture cut off by a killed process can end in the middle of a sample. The reader returns all whole samples,
+//! then a [GrError::TruncatedSample] with the bytes left over, which can be ignored to drop them, like GNU Radio does.
+//!
+//! # Example
+//! ```
+//! use realfft::num_complex::Complex32;
+//! use software_modem::io::gr::{read_cf32, write_cf32};
+//!
+//! let path = std::env::temp_dir().join("software_modem_doc_gr.cf32");
+//! let samples: Vec<Complex32> = (0..10000).map(|n| Complex32::from_polar(0.5, 0.01 * n as f32)).collect();
+//! let mut writer = write_cf32(&path).unwrap();
+//! for block in samples.chunks(1000) {
+//!     writer.write(block).unwrap();
+//! }
+//! writer.finish().unwrap();
+//! assert_eq!(std::fs::metadata(&path).unwrap().len(), 80000);
+//!
+//! // chunks of any size give the same samples back
+//! let mut reader = read_cf32(&path).unwrap();
+//! let mut chunk = vec![Complex32::default(); 4096];
+//! let mut read = Vec::new();
+//! loop {
+//!     let count = reader.read_chunk(&mut chunk).unwrap();
+//!     if count == 0 {
+//!         break;
+//!     }
+//!     read.extend_from_slice(&chunk[..count]);
+//! }
+//! assert_eq!(read, samples);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+use num_complex::Complex32;
+
+/// Errors reading or writing a raw sample file.
+#[derive(Debug)]
+pub enum GrError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The file ends within a sample, after the whole samples.
+    TruncatedSample { trailing_bytes: usize },
+}
+
+impl Display for GrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrError::Io(error) => write!(f, "Sample file error: {}", error),
+            GrError::TruncatedSample { trailing_bytes } => write!(
+                f,
+                "Sample file ends within a sample, {} bytes are left over",
+                trailing_bytes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GrError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for GrError {
+    fn from(error: std::io::Error) -> Self {
+        GrError::Io(error)
+    }
+}
+
+/// A sample type of the raw files, `f32` for `float` and [Complex32] for `complex` in GNU Radio.
+pub trait GrSample: Copy + Default {
+    /// Number of bytes of one sample in a file.
+    const SIZE: usize;
+
+    /// Decodes a sample from its `SIZE` little-endian bytes.
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+
+    /// Appends the `SIZE` little-endian bytes of the sample.
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+impl GrSample for f32 {
+    const SIZE: usize = 4;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend(self.to_le_bytes());
+    }
+}
+
+impl GrSample for Complex32 {
+    const SIZE: usize = 8;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Complex32::new(
+            <f32 as GrSample>::from_le_bytes(bytes),
+            <f32 as GrSample>::from_le_bytes(&bytes[4..]),
+        )
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        self.re.extend_le_bytes(bytes);
+        self.im.extend_le_bytes(bytes);
+    }
+}
+
+/// Opens a file of interleaved complex samples, GNU Radio's `complex64`, for reading.
+///
+/// # Errors
+/// [GrError::Io] if the file can not be opened.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::io::gr::{GrError, read_cf32};
+///
+/// // a capture cut off after the I part and one byte of the Q part of its third sample
+/// let path = std::env::temp_dir().join("software_modem_doc_read_cf32.cf32");
+/// let mut bytes = Vec::new();
+/// for x in [0.5f32, -0.25, 1.0, 2.0, 3.0] {
+///     bytes.extend(x.to_le_bytes());
+/// }
+/// bytes.push(0x40);
+/// std::fs::write(&path, &bytes).unwrap();
+///
+/// let mut reader = read_cf32(&path).unwrap();
+/// let mut chunk = [Complex32::default(); 16];
+/// assert_eq!(reader.read_chunk(&mut chunk).unwrap(), 2);
+/// assert_eq!(chunk[..2], [Complex32::new(0.5, -0.25), Complex32::new(1.0, 2.0)]);
+/// match reader.read_chunk(&mut chunk) {
+///     Err(GrError::TruncatedSample { trailing_bytes }) => assert_eq!(trailing_bytes, 5),
+///     other => panic!("{other:?}"),
+/// }
+/// assert_eq!(reader.read_chunk(&mut chunk).unwrap(), 0);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_cf32(path: impl AsRef<Path>) -> Result<GrReader<Complex32>, GrError> {
+    Ok(GrReader::new(BufReader::new(File::open(path)?)))
+}
+
+/// Opens a file of real `f32` samples for reading.
+///
+/// # Errors
+/// [GrError::Io] if the file can not be opened.
+pub fn read_f32(path: impl AsRef<Path>) -> Result<GrReader<f32>, GrError> {
+    Ok(GrReader::new(BufReader::new(File::open(path)?)))
+}
+
+/// Creates a file of interleaved complex samples, GNU Radio's `complex64`, replacing an existing one.
+///
+/// # Errors
+/// [GrError::Io] if the file can not be created.
+pub fn write_cf32(path: impl AsRef<Path>) -> Result<GrWriter<Complex32>, GrError> {
+    Ok(GrWriter::new(BufWriter::new(File::create(path)?)))
+}
+
+/// Creates a file of real `f32` samples, replacing an existing one.
+///
+/// # Errors
+/// [GrError::Io] if the file can not be created.
+pub fn write_f32(path: impl AsRef<Path>) -> Result<GrWriter<f32>, GrError> {
+    Ok(GrWriter::new(BufWriter::new(File::create(path)?)))
+}
+
+/// Reads the raw samples of a file, or of any other reader, in chunks.
+///
+/// It is also an iterator over the single samples, which reads chunks of 4096 samples behind the scenes.
+///
+/// # Example
+/// ```
+/// use software_modem::io::gr::{GrError, GrReader};
+///
+/// // two little-endian samples, 1.0 and -2.0, and 3 bytes of a third one
+/// let bytes = [0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00, 0xc0, 0x12, 0x34, 0x56];
+/// let mut reader = GrReader::<f32, _>::new(&bytes[..]);
+/// assert_eq!(reader.next().unwrap().unwrap(), 1.0);
+/// assert_eq!(reader.next().unwrap().unwrap(), -2.0);
+/// assert!(matches!(reader.next(), Some(Err(GrError::TruncatedSample { trailing_bytes: 3 }))));
+/// assert!(reader.next().is_none());
+///
+/// // the truncation can be ignored, to keep the whole samples
+/// let samples: Vec<f32> = GrReader::new(&bytes[..]).map_while(Result::ok).collect();
+/// assert_eq!(samples, [1.0, -2.0]);
+/// ```
+pub struct GrReader<S: GrSample, R: Read = BufReader<File>> {
+    reader: R,
+    bytes: Vec<u8>,
+    /// Bytes of a partial sample at the end of the input, reported after the whole samples.
+    trailing_bytes: Option<usize>,
+    finished: bool,
+    /// Samples read ahead for the iterator, the next one at `position`.
+    chunk: Vec<S>,
+    position: usize,
+}
+
+/// Number of samples the iterator of a [GrReader] reads at once.
+const ITERATOR_CHUNK_LENGTH: usize = 4096;
+
+impl<S: GrSample, R: Read> GrReader<S, R> {
+    /// Creates a reader of the raw samples the reader yields.
+    pub fn new(reader: R) -> Self {
+        GrReader {
+            reader,
+            bytes: Vec::new(),
+            trailing_bytes: None,
+            finished: false,
+            chunk: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Reads up to `buffer.len()` samples into the buffer, and returns how many, fewer only at the end of the input.
+    ///
+    /// Returns 0 at the end of the input.
+    ///
+    /// # Errors
+    /// [GrError::Io] if the input can not be read, and [GrError::TruncatedSample] once,
+    /// after the last whole sample, if the input ends within a sample.
+    pub fn read_chunk(&mut self, buffer: &mut [S]) -> Result<usize, GrError> {
+        if let Some(trailing_bytes) = self.trailing_bytes.take() {
+            return Err(GrError::TruncatedSample { trailing_bytes });
+        }
+        if self.finished || buffer.is_empty() {
+            return Ok(0);
+        }
+
+        self.bytes.resize(buffer.len() * S::SIZE, 0);
+        let mut filled = 0;
+        while filled < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => {
+                    self.finished = true;
+                    break;
+                }
+                Ok(count) => filled += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let count = filled / S::SIZE;
+        for (sample, bytes) in buffer
+            .iter_mut()
+            .zip(self.bytes[..filled].chunks_exact(S::SIZE))
+        {
+            *sample = S::from_le_bytes(bytes);
+        }
+
+        let trailing_bytes = filled % S::SIZE;
+        if trailing_bytes > 0 {
+            if count == 0 {
+                return Err(GrError::TruncatedSample { trailing_bytes });
+            }
+            self.trailing_bytes = Some(trailing_bytes);
+        }
+        Ok(count)
+    }
+}
+
+impl<S: GrSample, R: Read> Iterator for GrReader<S, R> {
+    type Item = Result<S, GrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.chunk.len() {
+            let mut chunk = std::mem::take(&mut self.chunk);
+            chunk.resize(ITERATOR_CHUNK_LENGTH, S::default());
+            let result = self.read_chunk(&mut chunk);
+            self.position = 0;
+            match result {
+                Ok(count) => chunk.truncate(count),
+                Err(error) => {
+                    chunk.clear();
+                    self.chunk = chunk;
+                    return Some(Err(error));
+                }
+            }
+            self.chunk = chunk;
+            if self.chunk.is_empty() {
+                return None;
+            }
+        }
+        self.position += 1;
+        Some(Ok(self.chunk[self.position - 1]))
+    }
+}
+
+/// Writes raw samples to a file, or to any other writer, block by block.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::io::gr::GrWriter;
+///
+/// // I before Q, the least significant byte first
+/// let mut writer = GrWriter::new(Vec::new());
+/// writer.write(&[Complex32::new(1.0, -2.0)]).unwrap();
+/// let bytes = writer.finish().unwrap();
+/// assert_eq!(bytes, [0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00, 0xc0]);
+/// ```
+pub struct GrWriter<S: GrSample, W: Write = BufWriter<File>> {
+    writer: W,
+    bytes: Vec<u8>,
+    sample_type: PhantomData<S>,
+}
+
+impl<S: GrSample, W: Write> GrWriter<S, W> {
+    /// Creates a writer of raw samples to the writer.
+    pub fn new(writer: W) -> Self {
+        GrWriter {
+            writer,
+            bytes: Vec::new(),
+            sample_type: PhantomData,
+        }
+    }
+
+    /// Appends the samples.
+    ///
+    /// # Errors
+    /// [GrError::Io] if the samples can not be written.
+    pub fn write(&mut self, samples: &[S]) -> Result<(), GrError> {
+        self.bytes.clear();
+        for &sample in samples {
+            sample.extend_le_bytes(&mut self.bytes);
+        }
+        self.writer.write_all(&self.bytes)?;
+        Ok(())
+    }
+
+    /// Flushes the samples written so far, and returns the underlying writer.
+    ///
+    /// Dropping the writer flushes it as well, but loses the errors.
+    ///
+    /// # Errors
+    /// [GrError::Io] if the samples can not be flushed.
+    pub fn finish(mut self) -> Result<W, GrError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
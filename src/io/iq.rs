@@ -0,0 +1,108 @@
+//! Converters from the interleaved I/Q samples of SDR receivers to complex samples.
+//!
+//! Receivers like the RTL-SDR deliver pairs of unsigned 8-bit samples, I first, centered at 127.5,
+//! others pairs of signed 16-bit samples. [u8_iq_to_complex] and [i16_iq_to_complex] convert them
+//! to complex samples at full scale `[-1, 1]`, and remove the DC offset the local oscillator of the receiver
+//! leaks into them. A [Decimator](crate::dsp::Decimator) brings them down to the rate of a
+//! [complex demodulator](crate::ofdm::complex::ComplexOFDMDemodulator).
+//!
+//! # Example
+//! ```
+//! use realfft::num_complex::Complex32;
+//! use software_modem::dsp::{Decimator, Resampler};
+//! use software_modem::io::iq::u8_iq_to_complex;
+//! use software_modem::ofdm::complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator};
+//!
+//! // the modem at 240 kHz, keeping its subcarriers within the passband of the decimator, 0.4 of the rate
+//! let config = ComplexOFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 8,
+//!     guard_subcarriers_low: 6,
+//!     guard_subcarriers_high: 6,
+//!     ..Default::default()
+//! };
+//! let modulator = ComplexOFDMModulator::new(config.clone());
+//! let demodulator = ComplexOFDMDemodulator::new(config);
+//! let data: Vec<u8> = (0..190u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+//! let transmitted = modulator.modulate_symbols(&data);
+//!
+//! // a capture of the frame at 2.4 MHz: a strong signal 700 kHz away, a DC offset, and 8-bit quantization
+//! let mut up = [Resampler::new(240_000, 2_400_000), Resampler::new(240_000, 2_400_000)];
+//! let padded: Vec<Complex32> = transmitted.iter().copied().chain([Complex32::default(); 100]).collect();
+//! let re = up[0].process(&padded.iter().map(|x| x.re).collect::<Vec<_>>());
+//! let im = up[1].process(&padded.iter().map(|x| x.im).collect::<Vec<_>>());
+//! let capture: Vec<u8> = re
+//!     .iter()
+//!     .zip(&im)
+//!     .enumerate()
+//!     .flat_map(|(n, (&re, &im))| {
+//!         let interferer = Complex32::from_polar(0.3, std::f32::consts::TAU * 700e3 / 2.4e6 * n as f32);
+//!         let sample = 0.05 * Complex32::new(re, im) + interferer + Complex32::new(0.02, -0.03);
+//!         [sample.re, sample.im].map(|x| (127.5 + 127.5 * x).round().clamp(0.0, 255.0) as u8)
+//!     })
+//!     .collect();
+//!
+//! // convert, decimate, and skip the delay of both filters
+//! let mut decimator = Decimator::new(10);
+//! let received = decimator.process(&u8_iq_to_complex(&capture));
+//! let start = up[0].delay() + decimator.delay() / 10;
+//! let symbols = &received[start..start + transmitted.len()];
+//! assert_eq!(demodulator.demodulate_symbols(symbols), data);
+//! ```
+
+use num_complex::Complex32;
+
+/// Converts interleaved unsigned 8-bit I/Q samples, like those of an RTL-SDR, to complex samples,
+/// and removes their mean, the DC offset.
+///
+/// # Panics
+/// If the number of samples is odd.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::io::iq::u8_iq_to_complex;
+///
+/// // a DC offset of 2 LSB in the I samples
+/// let samples = u8_iq_to_complex(&[255, 128, 4, 127, 128, 128, 131, 127]);
+/// assert_eq!(samples.len(), 4);
+/// assert!((samples[0] - Complex32::new(125.5, 0.5) / 127.5).norm() < 1e-6);
+/// assert!((samples[1] - Complex32::new(-125.5, -0.5) / 127.5).norm() < 1e-6);
+/// assert!(samples.iter().sum::<Complex32>().norm() < 1e-6);
+/// ```
+pub fn u8_iq_to_complex(samples: &[u8]) -> Vec<Complex32> {
+    iq_to_complex(samples, |sample| (f32::from(sample) - 127.5) / 127.5)
+}
+
+/// Converts interleaved signed 16-bit I/Q samples to complex samples, and removes their mean, the DC offset.
+///
+/// # Panics
+/// If the number of samples is odd.
+pub fn i16_iq_to_complex(samples: &[i16]) -> Vec<Complex32> {
+    iq_to_complex(samples, |sample| f32::from(sample) / 32768.0)
+}
+
+fn iq_to_complex<S: Copy>(samples: &[S], to_f32: impl Fn(S) -> f32) -> Vec<Complex32> {
+    if !samples.len().is_multiple_of(2) {
+        panic!(
+            "Number of I/Q samples must be even, but got {}",
+            samples.len()
+        );
+    }
+
+    let mut output: Vec<Complex32> = samples
+        .chunks_exact(2)
+        .map(|pair| Complex32::new(to_f32(pair[0]), to_f32(pair[1])))
+        .collect();
+
+    // the mean in f64, so long captures do not lose precision
+    let (re, im) = output.iter().fold((0.0, 0.0), |(re, im), sample| {
+        (re + f64::from(sample.re), im + f64::from(sample.im))
+    });
+    let count = output.len().max(1) as f64;
+    let mean = Complex32::new((re / count) as f32, (im / count) as f32);
+    for sample in output.iter_mut() {
+        *sample -= mean;
+    }
+    output
+}
@@ -0,0 +1,128 @@
+//! This module provides the input and output of samples, between the modem and files or radios.
+//!
+//! The WAV export and import, behind the `wav` feature, saves modulated signals to be played on one machine
+//! and loads recordings of them on another, see `write_wav` and `read_wav`.
+//! The [iq] converters turn the raw interleaved I/Q captures of SDR receivers into complex samples,
+//! and the [gr] readers and writers exchange raw sample files with GNU Radio.
+//! A [capture] records the input of a receiver with its sync events, to be replayed exactly as it came in.
+//!
+//! The [SampleSource] and [SampleSink] traits connect the [stream](crate::stream) runners to any of them:
+//! slices and vectors, a `VecDeque` as a ring buffer, and the [GrReader] and [GrWriter]
+//! of `f32` samples over any `std::io::Read` and `Write`.
+
+pub mod capture;
+pub mod gr;
+pub mod iq;
+#[cfg(feature = "wav")]
+mod wav;
+
+#[cfg(feature = "wav")]
+pub use wav::*;
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    io::{Read, Write},
+};
+
+use gr::{GrError, GrReader, GrWriter};
+
+/// A source of samples, read block by block.
+pub trait SampleSource {
+    /// The error of a failed read.
+    type Error: std::error::Error;
+
+    /// Reads up to `buffer.len()` samples into the buffer, and returns how many.
+    ///
+    /// Returns 0 at the end of the source only.
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Self::Error>;
+}
+
+/// A sink of samples, written block by block.
+pub trait SampleSink {
+    /// The error of a failed write.
+    type Error: std::error::Error;
+
+    /// Writes all samples.
+    fn write(&mut self, samples: &[f32]) -> Result<(), Self::Error>;
+}
+
+impl<S: SampleSource + ?Sized> SampleSource for &mut S {
+    type Error = S::Error;
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Self::Error> {
+        (**self).read(buffer)
+    }
+}
+
+impl<S: SampleSink + ?Sized> SampleSink for &mut S {
+    type Error = S::Error;
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Self::Error> {
+        (**self).write(samples)
+    }
+}
+
+/// Reads the samples from the front of the slice, and advances it past them.
+impl SampleSource for &[f32] {
+    type Error = Infallible;
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Self::Error> {
+        let count = buffer.len().min(self.len());
+        let (read, rest) = self.split_at(count);
+        buffer[..count].copy_from_slice(read);
+        *self = rest;
+        Ok(count)
+    }
+}
+
+/// Appends the samples.
+impl SampleSink for Vec<f32> {
+    type Error = Infallible;
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Self::Error> {
+        self.extend_from_slice(samples);
+        Ok(())
+    }
+}
+
+/// Takes the samples from the front, as a ring buffer between a writer and a reader.
+impl SampleSource for VecDeque<f32> {
+    type Error = Infallible;
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Self::Error> {
+        let count = buffer.len().min(self.len());
+        for (sample, read) in buffer.iter_mut().zip(self.drain(..count)) {
+            *sample = read;
+        }
+        Ok(count)
+    }
+}
+
+/// Appends the samples at the back, as a ring buffer between a writer and a reader.
+impl SampleSink for VecDeque<f32> {
+    type Error = Infallible;
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Self::Error> {
+        self.extend(samples);
+        Ok(())
+    }
+}
+
+/// Reads little-endian `f32` samples, see [GrReader::read_chunk].
+impl<R: Read> SampleSource for GrReader<f32, R> {
+    type Error = GrError;
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Self::Error> {
+        self.read_chunk(buffer)
+    }
+}
+
+/// Writes little-endian `f32` samples, see [GrWriter::write].
+impl<W: Write> SampleSink for GrWriter<f32, W> {
+    type Error = GrError;
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Self::Error> {
+        GrWriter::write(self, samples)
+    }
+}
@@ -0,0 +1,507 @@
+//! WAV file export and import of modulated signals, behind the `wav` feature.
+//!
+//! [write_wav] saves samples at full scale `[-1, 1]` as a mono file, to be played on one machine,
+//! and [read_wav] loads a recording of it on another, as mono samples and its sample rate.
+//! Both handle 16-bit PCM and 32-bit float files, [write_wav_with] and [read_wav_with] take options
+//! for the format, the channels and the level. [write_wav_interleaved] and [read_wav_interleaved] keep
+//! a signal of its own on every channel, like the independent streams of a stereo `audio::ChannelMap`.
+//!
+//! The raw samples of the modulator reach far beyond full scale, normalize them first,
+//! with an [OutputScale](crate::ofdm::modulator::OutputScale) or a [WavLevel].
+
+use std::{fmt::Display, fs, path::Path};
+
+use smart_default::SmartDefault;
+
+use crate::samples::{f32_to_i16, i16_to_f32};
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// Errors reading or writing a WAV file.
+#[derive(Debug)]
+pub enum WavError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The file is not a RIFF WAVE file, or its chunks are malformed.
+    InvalidFile,
+    /// The samples are neither 16-bit PCM nor 32-bit float.
+    UnsupportedFormat {
+        format_tag: u16,
+        bits_per_sample: u16,
+    },
+    /// The sample rate of the file is not the one the modem runs at.
+    SampleRateMismatch { expected: u32, got: u32 },
+    /// The channel to extract does not exist in the file.
+    ChannelOutOfRange { channel: u16, channels: u16 },
+}
+
+impl Display for WavError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WavError::Io(error) => write!(f, "WAV file error: {}", error),
+            WavError::InvalidFile => write!(f, "Invalid WAV file"),
+            WavError::UnsupportedFormat {
+                format_tag,
+                bits_per_sample,
+            } => write!(
+                f,
+                "Unsupported WAV format {} with {} bits per sample, expected 16-bit PCM or 32-bit float",
+                format_tag, bits_per_sample
+            ),
+            WavError::SampleRateMismatch { expected, got } => write!(
+                f,
+                "Sample rate mismatch, expected {} Hz, but got {} Hz",
+                expected, got
+            ),
+            WavError::ChannelOutOfRange { channel, channels } => write!(
+                f,
+                "Channel {} is out of range, the file has {} channels",
+                channel, channels
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WavError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WavError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WavError {
+    fn from(error: std::io::Error) -> Self {
+        WavError::Io(error)
+    }
+}
+
+/// Sample format of a written WAV file.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WavSampleFormat {
+    /// 16-bit PCM, which every player and sound card takes. Samples beyond full scale saturate.
+    #[default]
+    Pcm16,
+    /// 32-bit float, which keeps the samples exactly, even beyond full scale.
+    Float32,
+}
+
+/// Level adjustment of the samples, before writing or after reading.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub enum WavLevel {
+    /// Keep the samples as they are.
+    #[default]
+    Raw,
+    /// Scale the samples so that their peak magnitude is the given value, like `1.0` for full scale.
+    ///
+    /// Silence stays silent.
+    PeakNormalize(f32),
+}
+
+/// Which channel of a multichannel file [read_wav_with] returns.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WavChannel {
+    /// The mean of all channels.
+    #[default]
+    Mix,
+    /// A single channel, starting at 0 for the left one.
+    Index(u16),
+}
+
+/// Options of [write_wav_with].
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct WavWriteOptions {
+    pub format: WavSampleFormat,
+    /// Number of channels, [write_wav_with] writes the same samples to every one,
+    /// [write_wav_interleaved] takes a sample for every one.
+    #[default(1)]
+    pub channels: u16,
+    pub level: WavLevel,
+}
+
+/// Options of [read_wav_with].
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct WavReadOptions {
+    pub channel: WavChannel,
+    pub level: WavLevel,
+    /// Sample rate the modem runs at, a file at another rate is rejected.
+    pub expected_sample_rate: Option<u32>,
+}
+
+/// Writes the samples as a mono 16-bit PCM WAV file.
+///
+/// # Errors
+/// [WavError::Io] if the file can not be written.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{read_wav, write_wav};
+///
+/// let path = std::env::temp_dir().join("software_modem_doc_write_wav.wav");
+/// let samples: Vec<f32> = (0..1000).map(|n| 0.5 * (0.05 * n as f32).sin()).collect();
+/// write_wav(&path, &samples, 48000).unwrap();
+///
+/// // 16 bits keep the samples within half a LSB
+/// let (read, sample_rate) = read_wav(&path).unwrap();
+/// assert_eq!(sample_rate, 48000);
+/// assert_eq!(read.len(), samples.len());
+/// assert!(read.iter().zip(&samples).all(|(a, b)| (a - b).abs() <= 0.5 / 32768.0));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn write_wav(
+    path: impl AsRef<Path>,
+    samples: &[f32],
+    sample_rate: u32,
+) -> Result<(), WavError> {
+    write_wav_with(path, samples, sample_rate, &WavWriteOptions::default())
+}
+
+/// Writes the samples as a WAV file, with the format, channels and level of the options.
+///
+/// # Panics
+/// If the number of channels is 0, or the peak of a [WavLevel::PeakNormalize] is not positive and finite.
+///
+/// # Errors
+/// [WavError::Io] if the file can not be written.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{WavChannel, WavReadOptions, WavSampleFormat, WavWriteOptions, read_wav_with, write_wav_with};
+///
+/// let path = std::env::temp_dir().join("software_modem_doc_write_wav_with.wav");
+/// let samples = [0.25, -2.0, 1.5, 0.0];
+/// let options = WavWriteOptions {
+///     format: WavSampleFormat::Float32,
+///     channels: 2,
+///     ..Default::default()
+/// };
+/// write_wav_with(&path, &samples, 44100, &options).unwrap();
+///
+/// // float keeps the samples beyond full scale, on both channels
+/// for channel in [WavChannel::Mix, WavChannel::Index(0), WavChannel::Index(1)] {
+///     let options = WavReadOptions { channel, ..Default::default() };
+///     assert_eq!(read_wav_with(&path, &options).unwrap(), (samples.to_vec(), 44100));
+/// }
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn write_wav_with(
+    path: impl AsRef<Path>,
+    samples: &[f32],
+    sample_rate: u32,
+    options: &WavWriteOptions,
+) -> Result<(), WavError> {
+    if options.channels == 0 {
+        panic!("Number of channels must be at least 1, but got 0");
+    }
+    let channels = usize::from(options.channels);
+    let interleaved: Vec<f32> = samples
+        .iter()
+        .flat_map(|&sample| std::iter::repeat_n(sample, channels))
+        .collect();
+    write_wav_interleaved(path, &interleaved, sample_rate, options)
+}
+
+/// Writes interleaved samples, frames of a sample of every channel of the options, as a WAV file.
+///
+/// The level of the options applies to all channels together.
+///
+/// # Panics
+/// If the number of channels is 0, the length of the samples is not a multiple of it,
+/// or the peak of a [WavLevel::PeakNormalize] is not positive and finite.
+///
+/// # Errors
+/// [WavError::Io] if the file can not be written.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{WavSampleFormat, WavWriteOptions, read_wav_interleaved, write_wav_interleaved};
+///
+/// let path = std::env::temp_dir().join("software_modem_doc_write_wav_interleaved.wav");
+/// // a left and a right signal of their own
+/// let samples = [0.25, -0.5, 0.75, 0.0];
+/// let options = WavWriteOptions {
+///     format: WavSampleFormat::Float32,
+///     channels: 2,
+///     ..Default::default()
+/// };
+/// write_wav_interleaved(&path, &samples, 48000, &options).unwrap();
+/// assert_eq!(read_wav_interleaved(&path, &Default::default()).unwrap(), (samples.to_vec(), 2, 48000));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn write_wav_interleaved(
+    path: impl AsRef<Path>,
+    samples: &[f32],
+    sample_rate: u32,
+    options: &WavWriteOptions,
+) -> Result<(), WavError> {
+    if options.channels == 0 {
+        panic!("Number of channels must be at least 1, but got 0");
+    }
+    if !samples.len().is_multiple_of(usize::from(options.channels)) {
+        panic!(
+            "Number of samples must be a multiple of {} channels, but got {}",
+            options.channels,
+            samples.len()
+        );
+    }
+    let samples = apply_level(samples.to_vec(), options.level);
+
+    let (format_tag, bits_per_sample) = match options.format {
+        WavSampleFormat::Pcm16 => (FORMAT_PCM, 16),
+        WavSampleFormat::Float32 => (FORMAT_FLOAT, 32),
+    };
+    let mut data = Vec::with_capacity(samples.len() * usize::from(bits_per_sample / 8));
+    match options.format {
+        WavSampleFormat::Pcm16 => {
+            for sample in f32_to_i16(&samples, 1.0, None) {
+                data.extend(sample.to_le_bytes());
+            }
+        }
+        WavSampleFormat::Float32 => {
+            for sample in samples {
+                data.extend(sample.to_le_bytes());
+            }
+        }
+    }
+
+    let block_align = options.channels * bits_per_sample / 8;
+    let mut fmt = Vec::new();
+    fmt.extend(format_tag.to_le_bytes());
+    fmt.extend(options.channels.to_le_bytes());
+    fmt.extend(sample_rate.to_le_bytes());
+    fmt.extend((sample_rate * u32::from(block_align)).to_le_bytes());
+    fmt.extend(block_align.to_le_bytes());
+    fmt.extend(bits_per_sample.to_le_bytes());
+
+    let mut chunks = Vec::new();
+    if format_tag == FORMAT_PCM {
+        push_chunk(&mut chunks, b"fmt ", &fmt);
+    } else {
+        // other formats than PCM have an empty extension and the number of sample frames
+        fmt.extend(0u16.to_le_bytes());
+        push_chunk(&mut chunks, b"fmt ", &fmt);
+        let num_frames = (data.len() / usize::from(block_align)) as u32;
+        push_chunk(&mut chunks, b"fact", &num_frames.to_le_bytes());
+    }
+    push_chunk(&mut chunks, b"data", &data);
+
+    let mut file = Vec::with_capacity(12 + chunks.len());
+    file.extend(b"RIFF");
+    file.extend((4 + chunks.len() as u32).to_le_bytes());
+    file.extend(b"WAVE");
+    file.extend(chunks);
+    fs::write(path, file)?;
+    Ok(())
+}
+
+/// Reads a WAV file as mono samples, the mean of all channels, and returns them with the sample rate.
+///
+/// # Errors
+/// See [read_wav_with].
+pub fn read_wav(path: impl AsRef<Path>) -> Result<(Vec<f32>, u32), WavError> {
+    read_wav_with(path, &WavReadOptions::default())
+}
+
+/// Reads a WAV file as mono samples of the channel of the options, and returns them with the sample rate.
+///
+/// 16-bit samples are scaled to full scale at 1, float samples are returned as they are, and then adjusted to the level of the options.
+/// Chunks other than the format and the data are skipped, and a data chunk running past the end of the file,
+/// as left behind by an interrupted recording, is cut at the last whole sample frame.
+///
+/// # Panics
+/// If the peak of a [WavLevel::PeakNormalize] is not positive and finite.
+///
+/// # Errors
+/// - [WavError::Io] if the file can not be read.
+/// - [WavError::InvalidFile] if it is not a WAV file.
+/// - [WavError::UnsupportedFormat] if the samples are neither 16-bit PCM nor 32-bit float.
+/// - [WavError::SampleRateMismatch] if the options expect another sample rate.
+/// - [WavError::ChannelOutOfRange] if the options ask for a channel the file does not have.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{WavError, WavLevel, WavReadOptions, read_wav_with, write_wav};
+///
+/// let path = std::env::temp_dir().join("software_modem_doc_read_wav_with.wav");
+/// write_wav(&path, &[0.0, 0.1, -0.2], 44100).unwrap();
+///
+/// // a modem running at 48 kHz can not use a recording at 44.1 kHz
+/// let options = WavReadOptions {
+///     expected_sample_rate: Some(48000),
+///     ..Default::default()
+/// };
+/// assert!(matches!(
+///     read_wav_with(&path, &options),
+///     Err(WavError::SampleRateMismatch { expected: 48000, got: 44100 })
+/// ));
+///
+/// // a quiet recording, normalized to full scale
+/// let options = WavReadOptions {
+///     level: WavLevel::PeakNormalize(1.0),
+///     ..Default::default()
+/// };
+/// let (samples, _) = read_wav_with(&path, &options).unwrap();
+/// assert!((samples[1] - 0.5).abs() < 1e-3 && samples[2] == -1.0);
+///
+/// // 8-bit PCM is not supported
+/// let mut file = std::fs::read(&path).unwrap();
+/// file[34] = 8;
+/// std::fs::write(&path, &file).unwrap();
+/// assert!(matches!(
+///     read_wav_with(&path, &WavReadOptions::default()),
+///     Err(WavError::UnsupportedFormat { format_tag: 1, bits_per_sample: 8 })
+/// ));
+///
+/// std::fs::write(&path, b"not a wav file").unwrap();
+/// assert!(matches!(read_wav_with(&path, &WavReadOptions::default()), Err(WavError::InvalidFile)));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_wav_with(
+    path: impl AsRef<Path>,
+    options: &WavReadOptions,
+) -> Result<(Vec<f32>, u32), WavError> {
+    let (interleaved, channels, sample_rate) = read_samples(path, options.expected_sample_rate)?;
+    if let WavChannel::Index(channel) = options.channel
+        && channel >= channels
+    {
+        return Err(WavError::ChannelOutOfRange { channel, channels });
+    }
+
+    let samples = interleaved
+        .chunks_exact(usize::from(channels))
+        .map(|frame| match options.channel {
+            WavChannel::Mix => frame.iter().sum::<f32>() / f32::from(channels),
+            WavChannel::Index(channel) => frame[usize::from(channel)],
+        })
+        .collect();
+    Ok((apply_level(samples, options.level), sample_rate))
+}
+
+/// Reads a WAV file as interleaved samples of all its channels, and returns them with the number of channels and the sample rate.
+///
+/// The level of the options applies to all channels together, and their channel is ignored.
+///
+/// # Panics
+/// If the peak of a [WavLevel::PeakNormalize] is not positive and finite.
+///
+/// # Errors
+/// See [read_wav_with], except for [WavError::ChannelOutOfRange].
+pub fn read_wav_interleaved(
+    path: impl AsRef<Path>,
+    options: &WavReadOptions,
+) -> Result<(Vec<f32>, u16, u32), WavError> {
+    let (samples, channels, sample_rate) = read_samples(path, options.expected_sample_rate)?;
+    Ok((apply_level(samples, options.level), channels, sample_rate))
+}
+
+/// Returns the interleaved samples of a WAV file, its channels and its sample rate.
+fn read_samples(
+    path: impl AsRef<Path>,
+    expected_sample_rate: Option<u32>,
+) -> Result<(Vec<f32>, u16, u32), WavError> {
+    let file = fs::read(path)?;
+    if file.len() < 12 || &file[..4] != b"RIFF" || &file[8..12] != b"WAVE" {
+        return Err(WavError::InvalidFile);
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut chunks = &file[12..];
+    while chunks.len() >= 8 {
+        let id = &chunks[..4];
+        let size = u32::from_le_bytes([chunks[4], chunks[5], chunks[6], chunks[7]]) as usize;
+        let body = &chunks[8..];
+        let content = &body[..size.min(body.len())];
+        match id {
+            b"fmt " => format = Some(read_format(content)?),
+            b"data" => data = Some(content),
+            _ => {}
+        }
+        // chunks are padded to an even size
+        chunks = &body[(size + size % 2).min(body.len())..];
+    }
+    let (format_tag, channels, sample_rate, bits_per_sample) =
+        format.ok_or(WavError::InvalidFile)?;
+    let data = data.ok_or(WavError::InvalidFile)?;
+
+    let decode: fn(&[u8]) -> f32 = match (format_tag, bits_per_sample) {
+        (FORMAT_PCM, 16) => |bytes| i16_to_f32(&[i16::from_le_bytes([bytes[0], bytes[1]])])[0],
+        (FORMAT_FLOAT, 32) => |bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        _ => {
+            return Err(WavError::UnsupportedFormat {
+                format_tag,
+                bits_per_sample,
+            });
+        }
+    };
+    if let Some(expected) = expected_sample_rate
+        && expected != sample_rate
+    {
+        return Err(WavError::SampleRateMismatch {
+            expected,
+            got: sample_rate,
+        });
+    }
+    if channels == 0 {
+        return Err(WavError::InvalidFile);
+    }
+
+    // the data is cut at the last whole sample frame
+    let sample_size = usize::from(bits_per_sample / 8);
+    let frame_size = usize::from(channels) * sample_size;
+    let samples = data[..data.len() - data.len() % frame_size]
+        .chunks_exact(sample_size)
+        .map(decode)
+        .collect();
+    Ok((samples, channels, sample_rate))
+}
+
+/// Returns the format tag, the channels, the sample rate and the bits per sample of a format chunk.
+fn read_format(chunk: &[u8]) -> Result<(u16, u16, u32, u16), WavError> {
+    if chunk.len() < 16 {
+        return Err(WavError::InvalidFile);
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([chunk[offset], chunk[offset + 1]]);
+    let mut format_tag = u16_at(0);
+    let channels = u16_at(2);
+    let sample_rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+    let bits_per_sample = u16_at(14);
+
+    // the extensible format keeps the actual format in the first bytes of its sub format GUID
+    if format_tag == FORMAT_EXTENSIBLE {
+        if chunk.len() < 26 {
+            return Err(WavError::InvalidFile);
+        }
+        format_tag = u16_at(24);
+    }
+    Ok((format_tag, channels, sample_rate, bits_per_sample))
+}
+
+fn push_chunk(chunks: &mut Vec<u8>, id: &[u8; 4], content: &[u8]) {
+    chunks.extend(id);
+    chunks.extend((content.len() as u32).to_le_bytes());
+    chunks.extend(content);
+    if content.len() % 2 == 1 {
+        chunks.push(0);
+    }
+}
+
+/// # Panics
+/// If the peak of a [WavLevel::PeakNormalize] is not positive and finite.
+fn apply_level(mut samples: Vec<f32>, level: WavLevel) -> Vec<f32> {
+    if let WavLevel::PeakNormalize(target) = level {
+        if !(target.is_finite() && target > 0.0) {
+            panic!("Peak level must be positive and finite, but got {}", target);
+        }
+        let peak = samples.iter().map(|x| x.abs()).fold(0.0, f32::max);
+        if peak > 0.0 {
+            for sample in samples.iter_mut() {
+                *sample *= target / peak;
+            }
+        }
+    }
+    samples
+}
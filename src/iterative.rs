@@ -0,0 +1,138 @@
+//! This module provides iterative demapping and decoding, also called turbo equalization: the decoder of the inner code
+//! feeds what it learned of the coded bits back into the demapper, which demaps the points of the frame again.
+//!
+//! The soft demapper weighs the points of a symbol by their distances alone, and a bit of a point near the boundary
+//! between two others is close to erased. Once the code told which points its other bits make likely,
+//! the [demapper](crate::qam::QAMModem::demodulate_soft_with_priors) decides the bit between fewer points.
+//! Every pass of the [IterativeDecoder] decodes the LLRs of the frame, and stops as soon as the CRC of its payload matches.
+//! Otherwise the extrinsic LLRs of the inner code, the
+//! [a posteriori LLRs](crate::fec::convolutional::ConvolutionalCode::decode_posterior) of its coded bits less the ones
+//! it decoded, are the a priori LLRs of the demapper of the next pass, interleaved like the coded bits.
+//!
+//! The Gray labels of QAM-16 already make the bits of a point nearly independent, so the passes after the first
+//! gain a fraction of a dB, most over a frequency-selective channel, whose faded subcarriers put their points
+//! near the boundaries.
+//!
+//! # Example
+//! ```
+//! use software_modem::channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel};
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::iterative::{IterativeConfig, IterativeDecoder};
+//! use software_modem::ofdm::OFDMConfig;
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 8,
+//!     differential_time: true,
+//!     ..Default::default()
+//! };
+//! let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+//! let decoder = IterativeDecoder::new(
+//!     CodedOFDMDemodulator::new(ofdm, CodingConfig::default()),
+//!     IterativeConfig { iterations: 4 },
+//! );
+//!
+//! let payload = vec![0xa5; 300];
+//! let mut samples = modulator.encode_frame(&payload);
+//! ChannelChain::new()
+//!     .with(MultipathChannel::two_ray(3, -3.0))
+//!     .with(AwgnChannel::new(25.0, 7))
+//!     .apply(&mut samples);
+//!
+//! let outcome = decoder.decode(&samples);
+//! assert_eq!(outcome.payload, Ok(payload));
+//! assert_eq!(outcome.iterations, 1);
+//! ```
+
+use alloc::vec::Vec;
+
+use smart_default::SmartDefault;
+
+use crate::{coded::CodedOFDMDemodulator, error::ModemError};
+
+/// Configuration of an [IterativeDecoder].
+#[derive(SmartDefault, Clone, Copy, Debug, PartialEq)]
+pub struct IterativeConfig {
+    /// Most passes of demapping and decoding of a frame, 1 for a single pass like
+    /// [decode_llrs](CodedOFDMDemodulator::decode_llrs) of the soft LLRs.
+    #[default(4)]
+    pub iterations: usize,
+}
+
+/// What an [IterativeDecoder] made of a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterativeOutcome {
+    /// The payload of the frame, or the error of the decoding of its last pass.
+    pub payload: Result<Vec<u8>, ModemError>,
+    /// The number of passes run, 1 if the frame decoded from the first one.
+    pub iterations: usize,
+}
+
+/// Decodes frames with iterative demapping and decoding, see the [module](self) documentation.
+pub struct IterativeDecoder {
+    demodulator: CodedOFDMDemodulator,
+    config: IterativeConfig,
+}
+
+impl IterativeDecoder {
+    /// Creates a new iterative decoder of the frames of the demodulator.
+    ///
+    /// # Panics
+    /// If the number of iterations is 0.
+    pub fn new(demodulator: CodedOFDMDemodulator, config: IterativeConfig) -> Self {
+        assert!(
+            config.iterations > 0,
+            "Iterations must be at least 1, but got 0"
+        );
+        IterativeDecoder {
+            demodulator,
+            config,
+        }
+    }
+
+    /// Returns the demodulator of the decoder.
+    pub fn get_demodulator(&self) -> &CodedOFDMDemodulator {
+        &self.demodulator
+    }
+
+    /// Returns the configuration of the decoder.
+    pub fn get_config(&self) -> &IterativeConfig {
+        &self.config
+    }
+
+    /// Demodulates a frame of samples, and decodes it in passes until its CRC matches or the iterations run out.
+    ///
+    /// The points are always demapped into soft LLRs, whether the demodulator is configured for soft output or not,
+    /// and decoded like [decode_llrs](CodedOFDMDemodulator::decode_llrs), without erasures of the outer code.
+    /// A frame whose header does not decode has nothing to feed back, and ends after the first pass.
+    pub fn decode(&self, samples: &[f32]) -> IterativeOutcome {
+        let points = self.demodulator.demodulate_points(samples);
+        let modem = self.demodulator.qam_modem();
+        let mut llrs = modem.demodulate_soft(&points);
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            let payload = self.demodulator.decode_llrs(&llrs);
+            trace_event!(
+                TRACE,
+                "iteration decoded",
+                iterations = iterations,
+                error = payload.as_ref().err().map(ModemError::name),
+            );
+            if payload.is_ok() || iterations == self.config.iterations {
+                return IterativeOutcome {
+                    payload,
+                    iterations,
+                };
+            }
+            let Some(priors) = self.demodulator.get_extrinsic_llrs(&llrs) else {
+                return IterativeOutcome {
+                    payload,
+                    iterations,
+                };
+            };
+            llrs = modem.demodulate_soft_with_priors(&points, Some(&priors));
+        }
+    }
+}
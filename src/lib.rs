@@ -1,4 +1,114 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
 
+// the modem itself only uses `core` and `alloc`, the `std` feature adds the FFTs of `realfft` and `rustfft`,
+// the float math of `std` and the modules around the modem: io, streams, threads, files and the audio, bridge and ffi
+extern crate alloc;
+
+/// Sends an event of the `tracing` level with the fields, evaluated only if a subscriber records the level,
+/// and nothing without the `tracing` feature.
+macro_rules! trace_event {
+    ($level:ident, $message:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($name = $value,)* $message);
+        #[cfg(not(feature = "tracing"))]
+        {
+            $(let _ = || {
+                let _ = &$value;
+            };)*
+        }
+    }};
+}
+
+/// Binds a `tracing` span of the level with the fields, entered until the binding is dropped, like [trace_event]
+/// for the fields, and a unit without the `tracing` feature. The fields after a `;` are recorded later by [trace_record].
+macro_rules! trace_span {
+    ($span:ident = $level:ident, $name:literal $(, $field:ident = $value:expr)* $(; $($later:ident),+)? $(,)?) => {
+        #[cfg(feature = "tracing")]
+        let $span = tracing::span!(
+            tracing::Level::$level,
+            $name,
+            $($field = $value,)*
+            $($($later = tracing::field::Empty,)+)?
+        )
+        .entered();
+        #[cfg(not(feature = "tracing"))]
+        let $span = {
+            $(let _ = || {
+                let _ = &$value;
+            };)*
+        };
+    };
+}
+
+/// Records a field of a span of [trace_span], the value evaluated only if the span is enabled.
+macro_rules! trace_record {
+    ($span:ident, $name:ident = $value:expr) => {{
+        #[cfg(feature = "tracing")]
+        if !$span.is_disabled() {
+            $span.record(stringify!($name), $value);
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (&$span, || {
+                let _ = &$value;
+            });
+        }
+    }};
+}
+
+#[cfg(feature = "std")]
+pub mod analysis;
+pub mod arq;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bits;
+#[cfg(feature = "bridge")]
+pub mod bridge;
+pub mod calibration;
+pub mod channel;
+pub mod coded;
+pub mod correlator;
+pub mod crc;
+pub mod diagnostics;
+pub mod dsp;
+pub mod error;
+pub mod fec;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fft;
+pub mod frame;
+pub mod harq;
+pub mod hdlc;
+pub mod interleaver;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod iterative;
+pub mod math;
+pub mod metrics;
 pub mod ofdm;
+#[cfg(feature = "perf")]
+pub mod perf;
+pub mod phy;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod qam;
+pub mod rng;
+pub mod samples;
+#[cfg(feature = "std")]
+pub mod scan;
+pub mod scrambler;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod tap;
+#[cfg(feature = "std")]
+pub mod tdd;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod xfer;
@@ -0,0 +1,20 @@
+//! This module provides the float math of the modem, which `core` lacks: square roots, exponentials, logarithms
+//! and trigonometric functions.
+//!
+//! [Float] is the trait of `num-traits`. With the `std` feature its methods call the math of `std`, and `f32`,
+//! `f64` and [Complex](num_complex::Complex) have these methods of their own. Without it, `num-traits` and
+//! `num-complex` implement them with `libm`, a port of the math library of musl, and the modules of the modem
+//! import [Float] for the methods of `f32` and `f64`, and [Euclid] for their `rem_euclid`.
+//!
+//! # Example
+//! ```
+//! use software_modem::math::Float;
+//!
+//! fn db<T: Float>(power: T) -> T {
+//!     T::from(10.0).unwrap() * power.log10()
+//! }
+//!
+//! assert_eq!(db(100.0f64), 20.0);
+//! ```
+
+pub use num_traits::{Euclid, Float};
@@ -0,0 +1,915 @@
+//! This module provides error counting between sent and received data, to measure the quality of a link.
+//!
+//! [count_bit_errors] compares a single frame, the [BerMeter] accumulates the errors of many frames
+//! and estimates the bit error rate with a confidence interval, for example against a known test payload on a live link.
+//!
+//! [papr] and [papr_ccdf] measure the peak-to-average power ratio of the transmitted samples,
+//! to quantify PAPR reduction like [clipping](crate::ofdm::modulator::Clipping) or [selected mapping](crate::ofdm::SlmConfig).
+//! [evm] measures the error vector magnitude of received constellation points against their reference,
+//! [mer_db] the modulation error ratio against the decisions, and the [MerEstimator] keeps a running estimate of it.
+//! A [DemodulationReport] gathers what the receiver of a symbol, a batch or a frame measured of the link on the way,
+//! from the EVM to the offsets the pilots show and the corrections of the FEC.
+//!
+//! [power_spectrum] estimates the spectrum of the transmitted samples, [occupied_bandwidth_99pct] and [oob_power_db]
+//! measure how well it stays within its band, to check windowing and filtering.
+
+use alloc::{vec, vec::Vec};
+
+use num_complex::Complex;
+
+use crate::{channel::IqImbalance, fft::plan_real_forward, samples::Sample};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Errors between two buffers of bytes, see [count_bit_errors].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct BitErrorStats {
+    /// Number of compared bits.
+    pub num_bits: usize,
+    /// Number of wrong bits.
+    pub bit_errors: usize,
+    /// Number of bytes with at least one wrong bit.
+    pub byte_errors: usize,
+    /// Number of wrong bits by their position in the byte, the most significant bit first.
+    pub bit_position_errors: [usize; 8],
+    /// Length of the longest run of consecutive wrong bits.
+    pub longest_burst: usize,
+}
+
+impl BitErrorStats {
+    /// Returns the number of compared bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.num_bits / 8
+    }
+
+    /// Returns the ratio of wrong bits, or 0 if no bits were compared.
+    pub fn bit_error_rate(&self) -> f64 {
+        ratio(self.bit_errors, self.num_bits)
+    }
+
+    /// Returns the ratio of wrong bytes, or 0 if no bytes were compared.
+    pub fn byte_error_rate(&self) -> f64 {
+        ratio(self.byte_errors, self.num_bytes())
+    }
+}
+
+/// Compares the sent and the received bytes bit by bit.
+///
+/// The buffers are compared over the longer length,
+/// bytes missing from the shorter buffer count as 8 wrong bits, as if the frame was cut off.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::count_bit_errors;
+///
+/// let sent = [0x00, 0xff, 0x0f, 0xaa];
+/// let received = [0x00, 0xf0, 0x0f];
+///
+/// let stats = count_bit_errors(&sent, &received);
+/// assert_eq!(stats.num_bits, 32);
+/// assert_eq!(stats.bit_errors, 12);
+/// assert_eq!(stats.byte_errors, 2);
+/// assert_eq!(stats.bit_position_errors, [1, 1, 1, 1, 2, 2, 2, 2]);
+/// assert_eq!(stats.longest_burst, 8);
+/// assert_eq!(stats.bit_error_rate(), 0.375);
+/// ```
+pub fn count_bit_errors(sent: &[u8], received: &[u8]) -> BitErrorStats {
+    let num_bytes = sent.len().max(received.len());
+    let mut stats = BitErrorStats {
+        num_bits: 8 * num_bytes,
+        ..Default::default()
+    };
+
+    let mut burst = 0;
+    for i in 0..num_bytes {
+        let errors = match (sent.get(i), received.get(i)) {
+            (Some(a), Some(b)) => a ^ b,
+            _ => 0xff,
+        };
+        if errors != 0 {
+            stats.byte_errors += 1;
+        }
+
+        for (position, count) in stats.bit_position_errors.iter_mut().enumerate() {
+            if errors & (0x80 >> position) != 0 {
+                *count += 1;
+                stats.bit_errors += 1;
+                burst += 1;
+                stats.longest_burst = stats.longest_burst.max(burst);
+            } else {
+                burst = 0;
+            }
+        }
+    }
+
+    stats
+}
+
+/// Accumulates bit errors over many frames and estimates the bit error rate.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::BerMeter;
+///
+/// let mut meter = BerMeter::new();
+/// let sent = [0u8; 125];
+/// let mut received = sent;
+/// for frame in 0..10 {
+///     received[frame] = 0x01;
+///     meter.add_frame(&sent, &received);
+/// }
+///
+/// // 55 errors in 10000 bits
+/// assert_eq!(meter.num_frames(), 10);
+/// assert_eq!(meter.frame_errors(), 10);
+/// assert_eq!(meter.bit_errors(), 55);
+/// assert_eq!(meter.bit_error_rate(), 0.0055);
+///
+/// let (low, high) = meter.confidence_interval(0.95);
+/// assert!(low < 0.0055 && 0.0055 < high);
+/// assert!((low - 0.00423).abs() < 1e-5 && (high - 0.00715).abs() < 1e-5, "{low} {high}");
+/// ```
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct BerMeter {
+    num_frames: usize,
+    frame_errors: usize,
+    num_bits: usize,
+    bit_errors: usize,
+    longest_burst: usize,
+}
+
+impl BerMeter {
+    /// Creates a new meter without any counted bits.
+    pub fn new() -> Self {
+        BerMeter::default()
+    }
+
+    /// Compares the sent and received bytes of one frame and adds the errors, see [count_bit_errors].
+    pub fn add_frame(&mut self, sent: &[u8], received: &[u8]) -> BitErrorStats {
+        let stats = count_bit_errors(sent, received);
+        self.add(&stats);
+        stats
+    }
+
+    /// Adds the errors of one frame.
+    pub fn add(&mut self, stats: &BitErrorStats) {
+        self.num_frames += 1;
+        if stats.bit_errors > 0 {
+            self.frame_errors += 1;
+        }
+        self.num_bits += stats.num_bits;
+        self.bit_errors += stats.bit_errors;
+        self.longest_burst = self.longest_burst.max(stats.longest_burst);
+    }
+
+    /// Adds a frame which was lost completely, like one with a wrong CRC, as `num_bits` wrong bits.
+    pub fn add_lost_frame(&mut self, num_bits: usize) {
+        self.num_frames += 1;
+        self.frame_errors += 1;
+        self.num_bits += num_bits;
+        self.bit_errors += num_bits;
+    }
+
+    /// Clears all counts.
+    pub fn reset(&mut self) {
+        *self = BerMeter::default();
+    }
+
+    /// Returns the number of added frames.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Returns the number of frames with at least one wrong bit.
+    pub fn frame_errors(&self) -> usize {
+        self.frame_errors
+    }
+
+    /// Returns the number of compared bits.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns the number of wrong bits.
+    pub fn bit_errors(&self) -> usize {
+        self.bit_errors
+    }
+
+    /// Returns the longest run of consecutive wrong bits within a frame.
+    pub fn longest_burst(&self) -> usize {
+        self.longest_burst
+    }
+
+    /// Returns the estimated bit error rate, or 0 if no bits were compared.
+    pub fn bit_error_rate(&self) -> f64 {
+        ratio(self.bit_errors, self.num_bits)
+    }
+
+    /// Returns the ratio of frames with errors, or 0 if no frames were added.
+    pub fn frame_error_rate(&self) -> f64 {
+        ratio(self.frame_errors, self.num_frames)
+    }
+
+    /// Returns the Wilson score interval of the bit error rate at the given confidence level, like `0.95`.
+    ///
+    /// Unlike the normal approximation, the interval stays meaningful for few or no errors,
+    /// with zero errors the upper bound is roughly `z^2 / num_bits`.
+    /// If no bits were compared, the interval is `(0, 1)`.
+    ///
+    /// The interval assumes independent bit errors, bursts make it too narrow.
+    ///
+    /// # Panics
+    /// If the confidence level is not between 0 and 1, exclusive.
+    pub fn confidence_interval(&self, confidence: f64) -> (f64, f64) {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            panic!(
+                "Confidence level must be between 0 and 1, but got {}",
+                confidence
+            );
+        }
+        if self.num_bits == 0 {
+            return (0.0, 1.0);
+        }
+
+        let z = inverse_normal_cdf(0.5 + confidence / 2.0);
+        let n = self.num_bits as f64;
+        let p = self.bit_error_rate();
+        let z2 = z * z;
+
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half_width = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        (
+            (center - half_width).max(0.0),
+            (center + half_width).min(1.0),
+        )
+    }
+}
+
+/// Returns the peak-to-average power ratio of the samples in dB.
+///
+/// The peak is the largest squared sample, the average the mean of all squared samples.
+/// An empty or silent buffer has a PAPR of 0 dB.
+/// To leave out the cyclic prefix of a symbol, pass only the samples after it.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::papr;
+///
+/// // a constant envelope has no peaks
+/// assert_eq!(papr(&[1.0, -1.0, 1.0, -1.0]), 0.0);
+///
+/// // a full period of a sine peaks at twice its mean power, 3.01 dB
+/// let sine: Vec<f32> = (0..64).map(|n| (std::f32::consts::TAU * n as f32 / 64.0).sin()).collect();
+/// assert!((papr(&sine) - 3.0103).abs() < 1e-3);
+///
+/// // a single pulse in 100 samples peaks at 100 times its mean power
+/// let mut pulse = [0.0; 100];
+/// pulse[42] = -0.5;
+/// assert!((papr(&pulse) - 20.0).abs() < 1e-4);
+/// ```
+pub fn papr<T: Sample>(samples: &[T]) -> f32 {
+    let peak = samples.iter().map(|&x| x * x).fold(T::zero(), T::max);
+    let mean = samples.iter().map(|&x| x * x).sum::<T>() / T::cast(samples.len() as f64);
+    if peak > T::zero() {
+        (T::cast(10.0) * (peak / mean).log10()).into_f32()
+    } else {
+        0.0
+    }
+}
+
+/// Returns the complementary cumulative distribution of the PAPR over many symbols.
+///
+/// For every threshold in dB, the share of symbols whose [PAPR](papr) exceeds it.
+/// Without any symbols, every share is 0.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::papr_ccdf;
+///
+/// let flat = [1.0; 8];
+/// let sine = [0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5];
+/// let pulse = [0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0];
+/// let symbols = [&flat[..], &sine, &pulse, &pulse];
+///
+/// // 0 dB, 4.26 dB and 9.03 dB
+/// let ccdf = papr_ccdf(symbols.iter().copied(), &[-1.0, 3.0, 6.0, 10.0]);
+/// assert_eq!(ccdf, vec![1.0, 0.75, 0.5, 0.0]);
+/// ```
+pub fn papr_ccdf<'a, T: Sample>(
+    symbols: impl Iterator<Item = &'a [T]>,
+    thresholds_db: &[f32],
+) -> Vec<f32> {
+    let mut above = vec![0; thresholds_db.len()];
+    let mut num_symbols = 0;
+    for symbol in symbols {
+        let papr = papr(symbol);
+        for (above, &threshold) in above.iter_mut().zip(thresholds_db) {
+            if papr > threshold {
+                *above += 1;
+            }
+        }
+        num_symbols += 1;
+    }
+    above
+        .into_iter()
+        .map(|above| ratio(above, num_symbols) as f32)
+        .collect()
+}
+
+/// Error vector magnitude of received constellation points, see [evm].
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvmResult {
+    /// RMS of the error vectors relative to the RMS of the reference points, in percent.
+    pub rms_percent: f32,
+    /// RMS error vector magnitude in dB, negative infinity without errors.
+    pub rms_db: f32,
+    /// Largest error vector relative to the RMS of the reference points, in percent.
+    pub peak_percent: f32,
+    /// Peak error vector magnitude in dB, negative infinity without errors.
+    pub peak_db: f32,
+}
+
+/// Returns the error vector magnitude of the received points against their reference points.
+///
+/// The error vectors are normalized to the mean power of the reference, so the EVM of a constellation does not depend
+/// on its scale. The reference is either the sent points, or for a data-aided estimate the decisions of the receiver,
+/// see [demodulate_symbol_with_evm](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_symbol_with_evm).
+///
+/// # Panics
+/// If the received points and the reference differ in length, or the power of the reference is not positive and finite.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::metrics::evm;
+///
+/// // a reference of power 2, and errors of 0.1 and 0.3
+/// let reference = [Complex32::new(1.0, 1.0), Complex32::new(-1.0, 1.0)];
+/// let received = [Complex32::new(1.1, 1.0), Complex32::new(-1.0, 0.7)];
+/// let result = evm(&received, &reference);
+/// assert!((result.rms_percent - 100.0 * (0.05f32 / 2.0).sqrt()).abs() < 1e-3);
+/// assert!((result.peak_percent - 100.0 * 0.3 / 2f32.sqrt()).abs() < 1e-3);
+/// assert!((result.rms_db - 20.0 * (result.rms_percent / 100.0).log10()).abs() < 1e-4);
+///
+/// // the same points without errors
+/// assert_eq!(evm(&reference, &reference).rms_db, f32::NEG_INFINITY);
+/// ```
+pub fn evm<T: Sample>(received: &[Complex<T>], reference: &[Complex<T>]) -> EvmResult {
+    if received.len() != reference.len() {
+        panic!(
+            "Reference length must be {}, but got {}",
+            received.len(),
+            reference.len()
+        );
+    }
+    let power = reference.iter().map(|x| x.norm_sqr()).sum::<T>() / T::cast(reference.len() as f64);
+    if !(power > T::zero() && power.is_finite()) {
+        panic!(
+            "Reference power must be positive and finite, but got {}",
+            power.into_f32()
+        );
+    }
+
+    let errors = received
+        .iter()
+        .zip(reference)
+        .map(|(&x, &y)| (x - y).norm_sqr());
+    let (sum, peak) = errors.fold((T::zero(), T::zero()), |(sum, peak), error| {
+        (sum + error, peak.max(error))
+    });
+    let rms = (sum / T::cast(received.len() as f64) / power)
+        .sqrt()
+        .into_f32();
+    let peak = (peak / power).sqrt().into_f32();
+    EvmResult {
+        rms_percent: 100.0 * rms,
+        rms_db: 20.0 * rms.log10(),
+        peak_percent: 100.0 * peak,
+        peak_db: 20.0 * peak.log10(),
+    }
+}
+
+/// Returns the modulation error ratio of the received points against the decisions of the receiver, in dB.
+///
+/// The MER is the mean power of the decisions over the mean power of the error vectors, the SNR of the constellation
+/// as the receiver sees it. With the decisions as the reference it is the negative [EVM](evm) in dB.
+///
+/// # Panics
+/// If the received points and the decisions differ in length.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::metrics::{evm, mer_db};
+///
+/// // decisions of power 2, and errors of power 0.01 and 0.09
+/// let decisions = [Complex32::new(1.0, 1.0), Complex32::new(-1.0, 1.0)];
+/// let received = [Complex32::new(1.1, 1.0), Complex32::new(-1.0, 0.7)];
+/// assert!((mer_db(&received, &decisions) - 10.0 * (2.0f32 / 0.05).log10()).abs() < 1e-4);
+/// assert!((mer_db(&received, &decisions) + evm(&received, &decisions).rms_db).abs() < 1e-4);
+///
+/// // the decisions themselves have no errors
+/// assert_eq!(mer_db(&decisions, &decisions), f32::INFINITY);
+/// ```
+pub fn mer_db<T: Sample>(received: &[Complex<T>], decisions: &[Complex<T>]) -> f32 {
+    let (signal, error) = mean_powers(received, decisions);
+    10.0 * (signal / error).log10()
+}
+
+/// Returns the mean power of the decisions and of the error vectors.
+fn mean_powers<T: Sample>(received: &[Complex<T>], decisions: &[Complex<T>]) -> (f32, f32) {
+    if received.len() != decisions.len() {
+        panic!(
+            "Decisions length must be {}, but got {}",
+            received.len(),
+            decisions.len()
+        );
+    }
+    let (signal, error) = received
+        .iter()
+        .zip(decisions)
+        .fold((T::zero(), T::zero()), |(signal, error), (&x, &y)| {
+            (signal + y.norm_sqr(), error + (x - y).norm_sqr())
+        });
+    let length = T::cast(received.len() as f64);
+    ((signal / length).into_f32(), (error / length).into_f32())
+}
+
+/// A running estimate of the [modulation error ratio](mer_db), for a live display of the quality of a link.
+///
+/// The mean powers of the decisions and of the error vectors are averaged exponentially over the blocks of points,
+/// like the symbols or the frames, and the MER is their ratio. Every block weighs the same, however many points it has.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::metrics::MerEstimator;
+///
+/// let mut estimator = MerEstimator::new(0.5);
+/// assert_eq!(estimator.get_db(), None);
+///
+/// // errors of power 0.02 against decisions of power 2, 20 dB
+/// let decisions = [Complex32::new(1.0, 1.0); 4];
+/// let received = [Complex32::new(1.1, 1.1); 4];
+/// estimator.add(&received, &decisions);
+/// assert!((estimator.get_db().unwrap() - 20.0).abs() < 1e-3);
+///
+/// // the errors grow tenfold, and the estimate follows them halfway per block
+/// let received = decisions.map(|x| x + Complex32::new(0.1, 0.1) * 10f32.sqrt());
+/// estimator.add(&received, &decisions);
+/// assert!((estimator.get_db().unwrap() - 10.0 * (2.0f32 / 0.11).log10()).abs() < 1e-3);
+/// for _ in 0..30 {
+///     estimator.add(&received, &decisions);
+/// }
+/// assert!((estimator.get_db().unwrap() - 10.0).abs() < 1e-3);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerEstimator {
+    smoothing: f32,
+    /// The mean powers of the decisions and of the error vectors, after the first block.
+    powers: Option<(f32, f32)>,
+}
+
+impl MerEstimator {
+    /// Creates an estimator which moves by `smoothing` of the way to the powers of every new block,
+    /// 1 for only the last block.
+    ///
+    /// # Panics
+    /// If the smoothing is not above 0 and at most 1.
+    pub fn new(smoothing: f32) -> Self {
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            panic!("Smoothing must be between 0 and 1, but got {}", smoothing);
+        }
+        MerEstimator {
+            smoothing,
+            powers: None,
+        }
+    }
+
+    /// Adds a block of received points and the decisions of the receiver.
+    ///
+    /// # Panics
+    /// If the received points and the decisions differ in length.
+    pub fn add<T: Sample>(&mut self, received: &[Complex<T>], decisions: &[Complex<T>]) {
+        if received.is_empty() && decisions.is_empty() {
+            return;
+        }
+        let (signal, error) = mean_powers(received, decisions);
+        self.powers = Some(match self.powers {
+            None => (signal, error),
+            Some((average_signal, average_error)) => (
+                average_signal + self.smoothing * (signal - average_signal),
+                average_error + self.smoothing * (error - average_error),
+            ),
+        });
+    }
+
+    /// Returns the MER in dB, or `None` before the first block.
+    pub fn get_db(&self) -> Option<f32> {
+        self.powers
+            .map(|(signal, error)| 10.0 * (signal / error).log10())
+    }
+
+    /// Forgets all blocks.
+    pub fn reset(&mut self) {
+        self.powers = None;
+    }
+}
+
+/// The quality of the link a symbol, a batch of symbols or a frame was received over, as the receiver measured it.
+///
+/// Returned next to the data by [demodulate_symbol_with_report](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_symbol_with_report),
+/// [demodulate_batch_with_report](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_batch_with_report)
+/// and [decode_with_report](crate::frame::CodedFrameDecoder::decode_with_report).
+/// Every field the configuration and the API can not measure is `None`: the offsets need pilots without
+/// [selected mapping](crate::ofdm::SlmConfig), which flips their signs, the drifts need more than one symbol,
+/// and the CRC and the FEC a coded frame.
+///
+/// The offsets are the residual ones the demodulator leaves, it only equalizes the magnitude.
+/// With the `serde` feature, reports serialize to log or plot them elsewhere.
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DemodulationReport {
+    /// Data-aided [EVM](evm) of the data subcarrier points against their decisions, `None` if the points were not finite
+    /// or are not decided on their own, like the points of single differential symbols.
+    pub evm: Option<EvmResult>,
+    /// The SNR of the data subcarrier points in dB, their [modulation error ratio](mer_db) against the decisions.
+    pub snr_db: Option<f32>,
+    /// The SNR of every data subcarrier in dB over the symbols, like
+    /// [get_subcarrier_snr](crate::frame::FrameDecoder::get_subcarrier_snr), `None` for a single symbol.
+    pub subcarrier_snr_db: Option<Vec<f32>>,
+    /// The mean phase of the pilots against the ones sent, in radians, after the phase slope of the timing offset.
+    pub common_phase_error: Option<f32>,
+    /// Carrier frequency offset in subcarrier spacings, from the turn of the common phase error from symbol to symbol,
+    /// positive for a received carrier above the one sent.
+    pub residual_cfo: Option<f32>,
+    /// Change of the timing offset from symbol to symbol, in samples per symbol, positive for symbols which arrive later
+    /// and later, like from a receiver clock faster than the one of the transmitter.
+    pub timing_drift: Option<f32>,
+    /// Whether the CRC of the payload matched, `None` if the frame did not get as far as the payload.
+    pub crc_ok: Option<bool>,
+    /// What the FEC corrected in the payload, `None` if the frame did not get as far as the payload.
+    pub fec: Option<FecStats>,
+    /// Confidence of the decision of every data subcarrier point, a row per symbol from the first, `None` unless
+    /// the demodulator [keeps it](crate::ofdm::demodulator::OFDMDemodulatorConfig::confidence_symbols).
+    ///
+    /// A point on a constellation point has a confidence of 1, which falls with its distance from it to 0
+    /// half the [minimum distance](crate::qam::GenericQAMModem::min_distance) away, on the nearest decision boundary.
+    /// A glitch shows as a symbol whose confidences collapse between neighbours which stay high,
+    /// a low SNR as confidences low in every symbol.
+    pub confidence: Option<Vec<Vec<f32>>>,
+    /// Imbalance of the gain and the phase of the I and Q branches of the receiver, estimated by a
+    /// [complex demodulator](crate::ofdm::complex::ComplexOFDMDemodulator::estimate_iq_imbalance),
+    /// `None` for real samples.
+    pub iq_imbalance: Option<IqImbalance>,
+}
+
+impl DemodulationReport {
+    /// Returns a report of the [EVM](evm) and [SNR](mer_db) of the points against their decisions, and if the points are
+    /// whole symbols of `subcarriers` points each, of the SNR of every data subcarrier.
+    pub(crate) fn from_points<T: Sample>(
+        points: &[Complex<T>],
+        decisions: &[Complex<T>],
+        subcarriers: Option<usize>,
+    ) -> Self {
+        if points.is_empty()
+            || !points
+                .iter()
+                .all(|point| point.re.is_finite() && point.im.is_finite())
+        {
+            return DemodulationReport::default();
+        }
+        let subcarrier_snr_db = subcarriers.map(|subcarriers| {
+            let mut snr = SnrEstimate::default();
+            for (points, decisions) in points
+                .chunks_exact(subcarriers)
+                .zip(decisions.chunks_exact(subcarriers))
+            {
+                snr.add(points, decisions);
+            }
+            snr.get_db()
+        });
+        DemodulationReport {
+            evm: Some(evm(points, decisions)),
+            snr_db: Some(mer_db(points, decisions)),
+            subcarrier_snr_db,
+            ..Default::default()
+        }
+    }
+
+    /// Keeps the [confidence](Self::confidence) of the decisions of the first symbols of `subcarriers` points each,
+    /// of a constellation whose points are `min_distance` apart.
+    pub(crate) fn keep_confidence<T: Sample>(
+        &mut self,
+        points: &[Complex<T>],
+        decisions: &[Complex<T>],
+        subcarriers: usize,
+        max_symbols: usize,
+        min_distance: f32,
+    ) {
+        let radius = min_distance / 2.0;
+        let confidence = points
+            .chunks_exact(subcarriers)
+            .zip(decisions.chunks_exact(subcarriers))
+            .take(max_symbols)
+            .map(|(points, decisions)| {
+                points
+                    .iter()
+                    .zip(decisions)
+                    .map(|(point, decision)| {
+                        let distance = (point - decision).norm_sqr().into_f32().sqrt() / radius;
+                        // a point which is not finite has no confidence
+                        if distance < 1.0 { 1.0 - distance } else { 0.0 }
+                    })
+                    .collect()
+            })
+            .collect();
+        self.confidence = Some(confidence);
+    }
+}
+
+/// What the FEC of a coded frame corrected in its payload, see [DemodulationReport::fec].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FecStats {
+    /// Number of coded bits of the payload whose hard decisions differ from the decoded data coded again.
+    pub corrected_bits: usize,
+    /// Number of bytes of the Reed-Solomon code words which differ from the decoded data coded again,
+    /// `None` without the outer code. A block beyond correction is passed on as received and counts its parity.
+    pub corrected_bytes: Option<usize>,
+    /// Number of belief propagation iterations over all LDPC code words, `None` for the other codes.
+    pub iterations: Option<usize>,
+}
+
+/// Decision directed SNR estimate of every data subcarrier, summed over symbols.
+#[derive(Default)]
+pub(crate) struct SnrEstimate {
+    signal: Vec<f32>,
+    noise: Vec<f32>,
+}
+
+impl SnrEstimate {
+    /// Adds the data subcarrier points of one symbol with their decisions.
+    pub(crate) fn add<T: Sample>(&mut self, points: &[Complex<T>], decisions: &[Complex<T>]) {
+        self.signal.resize(points.len(), 0.0);
+        self.noise.resize(points.len(), 0.0);
+        for (i, (point, decision)) in points.iter().zip(decisions).enumerate() {
+            self.signal[i] += decision.norm_sqr().into_f32();
+            self.noise[i] += (point - decision).norm_sqr().into_f32();
+        }
+    }
+
+    /// Returns the SNR of every subcarrier in dB.
+    pub(crate) fn get_db(&self) -> Vec<f32> {
+        self.signal
+            .iter()
+            .zip(&self.noise)
+            .map(|(signal, noise)| 10.0 * (signal / noise).log10())
+            .collect()
+    }
+}
+
+/// Window applied to every segment of a [power spectrum](power_spectrum).
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpectrumWindow {
+    /// No tapering, for segments that are exactly one period of the signal, like the FFT window of an OFDM symbol.
+    Rectangular,
+    /// The periodic Hann window, which keeps the leakage of a sine within its neighbouring bins.
+    #[default]
+    Hann,
+}
+
+impl SpectrumWindow {
+    /// Returns the window over a segment of `length` samples.
+    pub(crate) fn coefficients(self, length: usize) -> Vec<f32> {
+        match self {
+            SpectrumWindow::Rectangular => vec![1.0; length],
+            SpectrumWindow::Hann => (0..length)
+                .map(|n| 0.5 * (1.0 - (core::f32::consts::TAU * n as f32 / length as f32).cos()))
+                .collect(),
+        }
+    }
+}
+
+/// Estimates the power spectrum of the samples with Welch's method.
+///
+/// The samples are split into segments of `fft_size` overlapping by half, each segment is windowed and transformed,
+/// and the periodograms are averaged. Returns `fft_size / 2 + 1` bins from DC to the Nyquist frequency,
+/// bin `k` at `k / fft_size` of the sample rate. The bins hold the one-sided power,
+/// so they add up to the mean power of the samples, apart from the leakage of the edges.
+/// Samples beyond the last full segment are ignored.
+///
+/// # Panics
+/// If the FFT size is odd or zero, or there are fewer samples than the FFT size.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::{SpectrumWindow, power_spectrum};
+///
+/// // a sine in the center of bin 10, with an amplitude of 2
+/// let sine: Vec<f32> = (0..1024).map(|n| 2.0 * (std::f32::consts::TAU * 10.0 * n as f32 / 64.0).sin()).collect();
+/// let spectrum = power_spectrum(&sine, 64, SpectrumWindow::Hann);
+/// assert_eq!(spectrum.len(), 33);
+///
+/// // the total power is the power of the sine, 2
+/// let total: f32 = spectrum.iter().sum();
+/// assert!((total - 2.0).abs() < 1e-3, "{total}");
+///
+/// // the Hann window leaks a sixth of it into each neighbouring bin, 6.02 dB below the peak
+/// assert!((spectrum[10] - 4.0 / 3.0).abs() < 1e-3);
+/// assert!((spectrum[9] - 1.0 / 3.0).abs() < 1e-3);
+/// assert!((spectrum[11] - 1.0 / 3.0).abs() < 1e-3);
+/// assert!(spectrum.iter().enumerate().all(|(k, &bin)| (9..=11).contains(&k) || bin < 1e-6));
+///
+/// // without a window, the sine fits a single bin
+/// let spectrum = power_spectrum(&sine, 64, SpectrumWindow::Rectangular);
+/// assert!((spectrum[10] - 2.0).abs() < 1e-3);
+/// ```
+pub fn power_spectrum(samples: &[f32], fft_size: usize, window: SpectrumWindow) -> Vec<f32> {
+    if fft_size == 0 || !fft_size.is_multiple_of(2) {
+        panic!("FFT size must be even and non-zero, but got {}", fft_size);
+    }
+    if samples.len() < fft_size {
+        panic!(
+            "Number of samples must be at least the FFT size of {}, but got {}",
+            fft_size,
+            samples.len()
+        );
+    }
+
+    let window = window.coefficients(fft_size);
+    let window_power: f32 = window.iter().map(|w| w * w).sum();
+
+    let fft = plan_real_forward::<f32>(fft_size);
+    let mut segment = vec![0.0; fft_size];
+    let mut bins = vec![Complex::default(); fft_size / 2 + 1];
+    let mut scratch = vec![Complex::default(); fft.get_scratch_len()];
+    let mut spectrum = vec![0.0; bins.len()];
+    let hop = fft_size / 2;
+    let num_segments = (samples.len() - fft_size) / hop + 1;
+    for start in (0..num_segments).map(|i| i * hop) {
+        for ((segment, sample), w) in segment
+            .iter_mut()
+            .zip(&samples[start..start + fft_size])
+            .zip(&window)
+        {
+            *segment = sample * w;
+        }
+        fft.process_with_scratch(&mut segment, &mut bins, &mut scratch);
+        for (power, bin) in spectrum.iter_mut().zip(&bins) {
+            *power += bin.norm_sqr();
+        }
+    }
+
+    // every bin but DC and Nyquist also stands for its negative frequency
+    let scale = 1.0 / (num_segments as f32 * fft_size as f32 * window_power);
+    let nyquist = spectrum.len() - 1;
+    for (k, power) in spectrum.iter_mut().enumerate() {
+        *power *= if k == 0 || k == nyquist {
+            scale
+        } else {
+            2.0 * scale
+        };
+    }
+    spectrum
+}
+
+/// Returns the bandwidth containing 99 % of the power of a [power spectrum](power_spectrum), as a fraction of the sample rate.
+///
+/// The band starts at the bin where 0.5 % of the power lie below, and ends at the bin where 0.5 % lie above,
+/// both bins included. A silent spectrum has no bandwidth.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::{SpectrumWindow, occupied_bandwidth_99pct, power_spectrum};
+///
+/// // the Hann window spreads a sine over 3 bins of 1 / 64
+/// let sine: Vec<f32> = (0..1024).map(|n| (std::f32::consts::TAU * 10.0 * n as f32 / 64.0).sin()).collect();
+/// let bandwidth = occupied_bandwidth_99pct(&power_spectrum(&sine, 64, SpectrumWindow::Hann));
+/// assert!((bandwidth - 3.0 / 64.0).abs() < 1e-6);
+/// ```
+pub fn occupied_bandwidth_99pct(spectrum: &[f32]) -> f32 {
+    let total: f32 = spectrum.iter().sum();
+    if total <= 0.0 || spectrum.len() < 2 {
+        return 0.0;
+    }
+
+    let mut cumulative = 0.0;
+    let mut low = None;
+    let mut high = spectrum.len() - 1;
+    for (k, power) in spectrum.iter().enumerate() {
+        cumulative += power;
+        if low.is_none() && cumulative > 0.005 * total {
+            low = Some(k);
+        }
+        if cumulative >= 0.995 * total {
+            high = k;
+            break;
+        }
+    }
+
+    let bin_width = 0.5 / (spectrum.len() - 1) as f32;
+    (high + 1 - low.unwrap_or(high)) as f32 * bin_width
+}
+
+/// Returns the power of a [power spectrum](power_spectrum) outside the band relative to the power inside, in dB.
+///
+/// The band edges are given as fractions of the sample rate, from 0 to 0.5,
+/// a bin belongs to the band if its center frequency lies between them.
+/// Without any power outside the band, the result is negative infinity.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::{SpectrumWindow, oob_power_db, power_spectrum};
+///
+/// // a strong tone in band and a weak one at 1 % of its amplitude outside
+/// let tones: Vec<f32> = (0..4096)
+///     .map(|n| {
+///         let t = std::f32::consts::TAU * n as f32 / 256.0;
+///         (20.0 * t).sin() + 0.01 * (100.0 * t).sin()
+///     })
+///     .collect();
+/// let spectrum = power_spectrum(&tones, 256, SpectrumWindow::Hann);
+/// let oob = oob_power_db(&spectrum, (0.05, 0.2));
+/// assert!((oob + 40.0).abs() < 0.01, "{oob} dB");
+/// ```
+pub fn oob_power_db(spectrum: &[f32], band_edges: (f32, f32)) -> f32 {
+    let bin_width = 0.5 / (spectrum.len().max(2) - 1) as f32;
+    let (low, high) = band_edges;
+    let (in_band, out_of_band) =
+        spectrum
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(in_band, out_of_band), (k, power)| {
+                let frequency = k as f32 * bin_width;
+                if (low..=high).contains(&frequency) {
+                    (in_band + power, out_of_band)
+                } else {
+                    (in_band, out_of_band + power)
+                }
+            });
+    10.0 * (out_of_band / in_band).log10()
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Returns the quantile of the standard normal distribution, with Acklam's rational approximation.
+///
+/// The relative error is below `1.2e-9` for `p` between 0 and 1, exclusive.
+fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.383_577_518_672_69e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        let q = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail(p)
+    } else if p > 1.0 - P_LOW {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
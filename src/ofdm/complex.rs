@@ -0,0 +1,662 @@
+//! This module provides OFDM on complex baseband I/Q samples, the natural interface of an SDR.
+//!
+//! The real modulator produces a Hermitian-symmetric spectrum, so only half of its FFT bins carry data.
+//! The [ComplexOFDMModulator] uses a complex FFT of `num_subcarriers` bins, and puts data on the positive
+//! and the negative frequencies independently, carrying the same data in half the samples.
+//! The [ComplexOFDMDemodulator] demodulates the symbols, and estimates and corrects the carrier frequency offset
+//! between the oscillators of two radios from the cyclic prefix, and the [imbalance](IqImbalance) between the I and
+//! Q branches of the receiver from the images between mirrored subcarriers.
+
+use alloc::{borrow::Cow, sync::Arc, vec, vec::Vec};
+
+use num_complex::{Complex, Complex32};
+use smart_default::SmartDefault;
+
+use crate::{
+    channel::IqImbalance,
+    fft::{ComplexFft, plan_complex_forward, plan_complex_inverse},
+    math::Euclid,
+    metrics::DemodulationReport,
+    ofdm::{GuardInterval, OFDMConstants, pilot_magnitude},
+    qam::{QAMModem, QAMOrder},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+const PILOT_VALUE: Complex32 = Complex32 { re: 1.0, im: 0.0 };
+
+/// Configuration shared by a matching [ComplexOFDMModulator] and [ComplexOFDMDemodulator].
+///
+/// Unlike [OFDMConfig](crate::ofdm::OFDMConfig), `num_subcarriers` is the size of the complex FFT,
+/// and a symbol has `num_subcarriers + cyclic_prefix_length` samples.
+/// The subcarriers lie at the frequencies `-num_subcarriers / 2 + 1` to `num_subcarriers / 2 - 1`,
+/// in multiples of the subcarrier spacing `sample_rate / num_subcarriers`,
+/// the bin at half the sample rate stays empty.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::complex::{ComplexOFDMConfig, ComplexOFDMModulator};
+///
+/// // 63 subcarriers around DC, 14 of them pilots at multiples of 4, and 48 data subcarriers
+/// let modulator = ComplexOFDMModulator::new(ComplexOFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// });
+/// assert_eq!(modulator.get_symbol_length(), 68);
+/// assert_eq!(modulator.get_bytes_per_symbol(), 24);
+/// ```
+#[derive(SmartDefault, Clone, Debug, PartialEq)]
+pub struct ComplexOFDMConfig {
+    /// Number of subcarriers, the size of the complex FFT, an even number of at least 4.
+    pub num_subcarriers: u32,
+    /// Length of the cyclic prefix in samples.
+    pub cyclic_prefix_length: u32,
+    /// Length of the cyclic prefix as a fraction of the FFT length of `num_subcarriers` samples,
+    /// which replaces the cyclic prefix length if set.
+    pub guard_interval: Option<GuardInterval>,
+    /// Interval for pilot subcarriers, counted from DC in both directions,
+    /// unless the [pilot frequencies](ComplexOFDMConfig::pilot_frequencies) are given.
+    #[default(4)]
+    pub pilot_subcarrier_every: u32,
+    pub qam_order: QAMOrder,
+    /// Number of unused subcarriers at the lowest, most negative, frequencies.
+    pub guard_subcarriers_low: u32,
+    /// Number of unused subcarriers below half the sample rate.
+    pub guard_subcarriers_high: u32,
+    /// Leave the DC subcarrier empty, where the local oscillator of most receivers leaks into the signal.
+    /// Otherwise DC carries a pilot.
+    #[default(true)]
+    pub null_dc: bool,
+    /// Frequencies of the pilot subcarriers in subcarrier spacings, negative below DC, like the pilots at ±7 and ±21
+    /// of [802.11a](crate::ofdm::profiles::ieee80211a_like).
+    /// If empty, the pilots are at the multiples of the [pilot interval](ComplexOFDMConfig::pilot_subcarrier_every).
+    pub pilot_frequencies: Vec<i32>,
+}
+
+impl ComplexOFDMConfig {
+    /// # Panics
+    /// If the number of subcarriers is odd or below 4, the [guard interval](GuardInterval) is not a whole number of samples,
+    /// the guard subcarriers leave no subcarriers, or a pilot frequency is repeated or is not a subcarrier in use.
+    fn constants(&self) -> OFDMConstants {
+        OFDMConstants::new_complex(
+            self.num_subcarriers,
+            super::resolve_cyclic_prefix_length(
+                self.guard_interval,
+                self.cyclic_prefix_length,
+                self.num_subcarriers,
+            ),
+            self.qam_order,
+            (self.pilot_subcarrier_every, &self.pilot_frequencies),
+            (self.guard_subcarriers_low, self.guard_subcarriers_high),
+            self.null_dc,
+        )
+    }
+
+    /// Returns the frequencies of the data subcarriers in subcarrier spacings, negative below DC,
+    /// in the order the points of a symbol are put on them, from the lowest frequency up.
+    ///
+    /// # Panics
+    /// As [ComplexOFDMModulator::new].
+    pub fn get_data_frequencies(&self) -> Vec<i32> {
+        let constants = self.constants();
+        to_frequencies(&constants.data_subcarrier_indices, self.num_subcarriers)
+    }
+
+    /// Returns the frequencies of the pilot subcarriers in subcarrier spacings, negative below DC, from the lowest up.
+    ///
+    /// # Panics
+    /// As [ComplexOFDMModulator::new].
+    pub fn get_pilot_frequencies(&self) -> Vec<i32> {
+        let constants = self.constants();
+        let mut frequencies =
+            to_frequencies(&constants.pilot_subcarrier_indices, self.num_subcarriers);
+        frequencies.sort_unstable();
+        frequencies
+    }
+}
+
+/// Returns the frequencies of FFT bins, the upper half of them below DC.
+fn to_frequencies(bins: &[u32], num_subcarriers: u32) -> Vec<i32> {
+    bins.iter()
+        .map(|&bin| {
+            if bin < num_subcarriers / 2 {
+                bin as i32
+            } else {
+                bin as i32 - num_subcarriers as i32
+            }
+        })
+        .collect()
+}
+
+/// Modulates data into OFDM symbols of complex baseband samples.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator};
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// let config = ComplexOFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// };
+/// let modulator = ComplexOFDMModulator::new(config.clone());
+/// let demodulator = ComplexOFDMDemodulator::new(config);
+///
+/// let data: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+/// let samples = modulator.modulate_symbols(&data);
+/// assert_eq!(demodulator.demodulate_symbols(&samples), data);
+///
+/// // the real modulator needs twice the samples per byte, less the shorter prefix
+/// let real = OFDMModulator::new((&OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// }).into());
+/// assert_eq!(real.get_bytes_per_symbol(), modulator.get_bytes_per_symbol());
+/// assert_eq!(real.get_symbol_length(), 2 * modulator.get_symbol_length() - 4);
+///
+/// // the spectrum is not symmetric, the negative frequencies carry data of their own
+/// let spectrum = rustfft::FftPlanner::new().plan_fft_forward(64);
+/// let mut bins = samples[4..68].to_vec();
+/// spectrum.process(&mut bins);
+/// assert!((bins[5] - bins[59].conj()).norm() > 1.0);
+/// ```
+pub struct ComplexOFDMModulator {
+    fft: Arc<dyn ComplexFft<f32>>,
+    qam_modem: QAMModem,
+    constants: OFDMConstants,
+}
+
+impl ComplexOFDMModulator {
+    /// Creates a new modulator with the given [configuration](ComplexOFDMConfig).
+    ///
+    /// # Panics
+    /// If the number of subcarriers is odd or below 4, the [guard interval](GuardInterval) is not a whole number of samples,
+    /// the guard subcarriers leave no subcarriers, or a pilot frequency is repeated or is not a subcarrier in use.
+    pub fn new(config: ComplexOFDMConfig) -> Self {
+        let constants = config.constants();
+        ComplexOFDMModulator {
+            fft: plan_complex_inverse(constants.fft_length()),
+            qam_modem: QAMModem::new(config.qam_order),
+            constants,
+        }
+    }
+
+    /// Modulates the data of one symbol into the output buffer, including the cyclic prefix.
+    ///
+    /// The samples are scaled by `1 / sqrt(num_subcarriers)`, so their mean power is the mean power
+    /// of the points, times the fraction of the subcarriers in use.
+    ///
+    /// # Panics
+    /// If the data length is not [get_bytes_per_symbol](Self::get_bytes_per_symbol),
+    /// or the output length is not [get_symbol_length](Self::get_symbol_length).
+    pub fn modulate_buffer_as_symbol(&self, data: &[u8], output_buffer: &mut [Complex32]) {
+        if data.len() != self.get_bytes_per_symbol() {
+            panic!(
+                "Data length must be {} bytes, but got {} bytes",
+                self.get_bytes_per_symbol(),
+                data.len()
+            );
+        }
+        if output_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                output_buffer.len()
+            );
+        }
+
+        let cyclic_prefix_length = self.constants.cyclic_prefix_samples();
+        let symbol = &mut output_buffer[cyclic_prefix_length..];
+        symbol.fill(Complex::default());
+        for (&idx, point) in self
+            .constants
+            .data_subcarrier_indices
+            .iter()
+            .zip(self.qam_modem.modulate(data))
+        {
+            symbol[idx as usize] = point;
+        }
+        let pilot = PILOT_VALUE * pilot_magnitude(self.constants.qam_order) as f32;
+        for &idx in &self.constants.pilot_subcarrier_indices {
+            symbol[idx as usize] = pilot;
+        }
+
+        self.fft.process(symbol);
+        let scale = 1.0 / (self.constants.fft_length() as f32).sqrt();
+        for sample in symbol.iter_mut() {
+            *sample *= scale;
+        }
+
+        // add cp
+        output_buffer.copy_within(output_buffer.len() - cyclic_prefix_length.., 0);
+    }
+
+    /// Modulates the data into consecutive symbols.
+    ///
+    /// # Panics
+    /// If the data length is not a multiple of the bytes per symbol.
+    pub fn modulate_symbols(&self, data: &[u8]) -> Vec<Complex32> {
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        if !data.len().is_multiple_of(bytes_per_symbol) {
+            panic!(
+                "Data length must be a multiple of {} bytes, but got {} bytes",
+                bytes_per_symbol,
+                data.len()
+            );
+        }
+
+        let symbol_length = self.get_symbol_length();
+        let mut output = vec![Complex::default(); data.len() / bytes_per_symbol * symbol_length];
+        for (data, output) in data
+            .chunks_exact(bytes_per_symbol)
+            .zip(output.chunks_exact_mut(symbol_length))
+        {
+            self.modulate_buffer_as_symbol(data, output);
+        }
+        output
+    }
+
+    /// Returns the length of the OFDM symbol, `num_subcarriers + cyclic_prefix_length`.
+    pub fn get_symbol_length(&self) -> usize {
+        self.constants.symbol_length()
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        (self.constants.bits_per_symbol / 8) as usize
+    }
+}
+
+/// How a [ComplexOFDMDemodulator] compensates the [imbalance](IqImbalance) of the I and Q branches of the receiver,
+/// [correcting](ComplexOFDMDemodulator::correct_iq_imbalance) the samples before the FFT.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub enum IqCompensation {
+    /// The samples are demodulated as they are.
+    #[default]
+    None,
+    /// The imbalance is [estimated](ComplexOFDMDemodulator::estimate_iq_imbalance) from the samples of every call.
+    /// The symbols of [demodulate_symbols](ComplexOFDMDemodulator::demodulate_symbols) give a fine estimate,
+    /// but a single symbol has too few subcarriers for one, a front-end better estimated once and then fixed.
+    Blind,
+    /// The imbalance is known, like estimated from earlier symbols of the same front-end.
+    Fixed(IqImbalance),
+}
+
+/// Demodulates OFDM symbols of complex baseband samples back into data.
+///
+/// Every symbol is equalized by the mean of its pilots, which removes the gain and the phase common
+/// to all subcarriers, like the phase left over by a carrier frequency offset that is not fully corrected.
+/// With an [IQ compensation](IqCompensation), the imbalance of the I and Q branches is corrected before the FFT.
+pub struct ComplexOFDMDemodulator {
+    fft: Arc<dyn ComplexFft<f32>>,
+    qam_modem: QAMModem,
+    constants: OFDMConstants,
+    /// The bins of the data subcarriers whose mirror at the negative frequency is a data subcarrier too, once per pair.
+    image_pairs: Vec<(usize, usize)>,
+    iq_compensation: IqCompensation,
+}
+
+impl ComplexOFDMDemodulator {
+    /// Creates a new demodulator with the given [configuration](ComplexOFDMConfig).
+    ///
+    /// # Panics
+    /// If the number of subcarriers is odd or below 4, the guard subcarriers leave no subcarriers,
+    /// a pilot frequency is repeated or is not a subcarrier in use, or there is no pilot subcarrier to equalize with.
+    pub fn new(config: ComplexOFDMConfig) -> Self {
+        let constants = config.constants();
+        if constants.pilot_subcarrier_indices.is_empty() {
+            panic!(
+                "Demodulation needs a pilot subcarrier, but got none every {} of {} subcarriers",
+                config.pilot_subcarrier_every, config.num_subcarriers
+            );
+        }
+        let fft_length = constants.fft_length();
+        let data = &constants.data_subcarrier_indices;
+        let image_pairs = data
+            .iter()
+            .map(|&bin| (bin as usize, (fft_length - bin as usize) % fft_length))
+            .filter(|&(bin, mirror)| bin < mirror && data.contains(&(mirror as u32)))
+            .collect();
+        ComplexOFDMDemodulator {
+            fft: plan_complex_forward(fft_length),
+            qam_modem: QAMModem::new(config.qam_order),
+            constants,
+            image_pairs,
+            iq_compensation: IqCompensation::None,
+        }
+    }
+
+    /// Sets how the imbalance of the I and Q branches is compensated, not at all by default.
+    pub fn set_iq_compensation(&mut self, compensation: IqCompensation) {
+        self.iq_compensation = compensation;
+    }
+
+    /// Returns how the imbalance of the I and Q branches is compensated.
+    pub fn get_iq_compensation(&self) -> IqCompensation {
+        self.iq_compensation
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match [get_symbol_length](Self::get_symbol_length).
+    pub fn demodulate_symbol_from_buffer(&self, input_buffer: &[Complex32]) -> Vec<u8> {
+        self.qam_modem
+            .demodulate(&self.demodulate_ofdm_symbol(&self.compensate(input_buffer)))
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer into soft bit decisions.
+    ///
+    /// Returns one LLR per data bit, see [QAMModem::demodulate_soft] for the convention.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match [get_symbol_length](Self::get_symbol_length).
+    pub fn demodulate_symbol_soft_from_buffer(&self, input_buffer: &[Complex32]) -> Vec<f32> {
+        self.qam_modem
+            .demodulate_soft(&self.demodulate_ofdm_symbol(&self.compensate(input_buffer)))
+    }
+
+    /// Demodulates consecutive symbols into their data.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length.
+    pub fn demodulate_symbols(&self, samples: &[Complex32]) -> Vec<u8> {
+        let symbol_length = self.get_symbol_length();
+        if !samples.len().is_multiple_of(symbol_length) {
+            panic!(
+                "Number of samples must be a multiple of {}, but got {}",
+                symbol_length,
+                samples.len()
+            );
+        }
+
+        self.compensate(samples)
+            .chunks_exact(symbol_length)
+            .flat_map(|symbol| {
+                self.qam_modem
+                    .demodulate(&self.demodulate_ofdm_symbol(symbol))
+            })
+            .collect()
+    }
+
+    /// Demodulates consecutive symbols into their data, and returns the [report](DemodulationReport) of their quality.
+    ///
+    /// The report holds the EVM and the SNR of the points against their decisions, the SNR of every data subcarrier,
+    /// and the [imbalance](DemodulationReport::iq_imbalance) of the I and Q branches estimated from the samples,
+    /// whether it is [compensated](Self::set_iq_compensation) or not.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length.
+    pub fn demodulate_symbols_with_report(
+        &self,
+        samples: &[Complex32],
+    ) -> (Vec<u8>, DemodulationReport) {
+        let symbol_length = self.get_symbol_length();
+        if !samples.len().is_multiple_of(symbol_length) {
+            panic!(
+                "Number of samples must be a multiple of {}, but got {}",
+                symbol_length,
+                samples.len()
+            );
+        }
+
+        let imbalance = self.estimate_iq_imbalance(samples);
+        let mut corrected = Cow::Borrowed(samples);
+        match self.iq_compensation {
+            IqCompensation::None => {}
+            IqCompensation::Blind => self.correct_iq_imbalance(corrected.to_mut(), imbalance),
+            IqCompensation::Fixed(fixed) => self.correct_iq_imbalance(corrected.to_mut(), fixed),
+        }
+        let points: Vec<Complex32> = corrected
+            .chunks_exact(symbol_length)
+            .flat_map(|symbol| self.demodulate_ofdm_symbol(symbol))
+            .collect();
+        let decisions = self.qam_modem.nearest_points(&points);
+        let report = DemodulationReport {
+            iq_imbalance: Some(imbalance),
+            ..DemodulationReport::from_points(
+                &points,
+                &decisions,
+                Some(self.constants.data_subcarrier_indices.len()),
+            )
+        };
+        (self.qam_modem.demodulate(&points), report)
+    }
+
+    /// Returns the samples with the imbalance of their I and Q branches corrected by the compensation.
+    fn compensate<'a>(&self, samples: &'a [Complex32]) -> Cow<'a, [Complex32]> {
+        let imbalance = match self.iq_compensation {
+            IqCompensation::None => return Cow::Borrowed(samples),
+            IqCompensation::Blind => self.estimate_iq_imbalance(samples),
+            IqCompensation::Fixed(imbalance) => imbalance,
+        };
+        let mut samples = samples.to_vec();
+        self.correct_iq_imbalance(&mut samples, imbalance);
+        Cow::Owned(samples)
+    }
+
+    /// Returns the equalized data subcarrier points of one symbol.
+    fn demodulate_ofdm_symbol(&self, input: &[Complex32]) -> Vec<Complex32> {
+        if input.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input.len()
+            );
+        }
+
+        // remove cyclic prefix
+        let mut bins = input[self.constants.cyclic_prefix_samples()..].to_vec();
+        self.fft.process(&mut bins);
+
+        // equalize by the common gain and phase of the pilots
+        let pilots = &self.constants.pilot_subcarrier_indices;
+        let pilot = PILOT_VALUE * pilot_magnitude(self.constants.qam_order) as f32;
+        let eq_factor = pilots
+            .iter()
+            .map(|&idx| bins[idx as usize] / pilot)
+            .sum::<Complex32>()
+            / pilots.len().max(1) as f32;
+
+        self.constants
+            .data_subcarrier_indices
+            .iter()
+            .map(|&idx| {
+                // a silent symbol has nothing to equalize
+                if eq_factor.norm_sqr() > 0.0 {
+                    bins[idx as usize] / eq_factor
+                } else {
+                    bins[idx as usize]
+                }
+            })
+            .collect()
+    }
+
+    /// Estimates the carrier frequency offset of the symbols, in multiples of the subcarrier spacing.
+    ///
+    /// An offset rotates the samples at the end of a symbol against its cyclic prefix by `2π` times the offset,
+    /// the estimate is the angle of their correlation, summed over all whole symbols of the samples.
+    /// Only offsets of less than half a subcarrier spacing can be told apart,
+    /// larger offsets are estimated modulo one spacing. Without a cyclic prefix, returns 0.
+    ///
+    /// The samples have to start at a symbol boundary. Multiply the estimate by
+    /// `sample_rate / num_subcarriers` for the offset in Hz.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator};
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let config = ComplexOFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = ComplexOFDMModulator::new(config.clone());
+    /// let demodulator = ComplexOFDMDemodulator::new(config);
+    ///
+    /// let data: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let transmitted = modulator.modulate_symbols(&data);
+    ///
+    /// // the oscillator of the receiver is 0.23 subcarrier spacings off, at some phase, gain and noise
+    /// let offset = 0.23;
+    /// let mut noise: u32 = 0x1234_5678;
+    /// let mut uniform = move || {
+    ///     noise ^= noise << 13;
+    ///     noise ^= noise >> 17;
+    ///     noise ^= noise << 5;
+    ///     noise as f32 / u32::MAX as f32 - 0.5
+    /// };
+    /// let received: Vec<Complex32> = transmitted
+    ///     .iter()
+    ///     .enumerate()
+    ///     .map(|(n, &sample)| {
+    ///         let phase = 1.0 + std::f32::consts::TAU * offset * n as f32 / 64.0;
+    ///         sample * Complex32::from_polar(0.3, phase)
+    ///             + 0.01 * Complex32::new(uniform(), uniform())
+    ///     })
+    ///     .collect();
+    ///
+    /// // the interference between the shifted subcarriers breaks the decisions
+    /// assert_ne!(demodulator.demodulate_symbols(&received), data);
+    ///
+    /// let estimate = demodulator.estimate_frequency_offset(&received);
+    /// assert!((estimate - offset).abs() < 0.005, "{estimate}");
+    /// let mut corrected = received.clone();
+    /// demodulator.correct_frequency_offset(&mut corrected, estimate);
+    /// assert_eq!(demodulator.demodulate_symbols(&corrected), data);
+    /// ```
+    pub fn estimate_frequency_offset(&self, samples: &[Complex32]) -> f32 {
+        let cyclic_prefix_length = self.constants.cyclic_prefix_samples();
+        let fft_length = self.constants.fft_length();
+
+        let correlation: Complex32 = samples
+            .chunks_exact(self.get_symbol_length())
+            .flat_map(|symbol| {
+                symbol[..cyclic_prefix_length]
+                    .iter()
+                    .zip(&symbol[fft_length..])
+                    .map(|(prefix, end)| prefix.conj() * end)
+            })
+            .sum();
+
+        if correlation.norm_sqr() > 0.0 {
+            correlation.arg() / core::f32::consts::TAU
+        } else {
+            0.0
+        }
+    }
+
+    /// Removes a carrier frequency offset, in multiples of the subcarrier spacing, from the samples.
+    ///
+    /// The samples are rotated back by `offset` spacings from their first sample on, the phase the rotation
+    /// leaves at the start is common to all subcarriers and removed by the equalization.
+    /// See [estimate_frequency_offset](Self::estimate_frequency_offset) for an example.
+    pub fn correct_frequency_offset(&self, samples: &mut [Complex32], offset: f32) {
+        // the phase is accumulated in f64, so long recordings do not drift
+        let step = -core::f64::consts::TAU * offset as f64 / self.constants.fft_length() as f64;
+        for (n, sample) in samples.iter_mut().enumerate() {
+            let phase = Euclid::rem_euclid(&(step * n as f64), &core::f64::consts::TAU);
+            *sample *= Complex32::from_polar(1.0, phase as f32);
+        }
+    }
+
+    /// Estimates the imbalance of the gain and the phase of the I and Q branches of the receiver, blind,
+    /// from the images between mirrored data subcarriers.
+    ///
+    /// An [imbalance](IqImbalance) turns the received signal into `μ x + ν x*`, which puts an image of the subcarrier
+    /// at `-k` onto the one at `k`. The data of mirrored subcarriers is independent, so the bins of a pair only
+    /// correlate through the image: the mean of `Y(k) Y(-k)` over the pairs of all whole symbols, relative to
+    /// their power, is `μ ν / (|μ|² + |ν|²)`, whatever the gains of the channel at `k` and `-k`, and gives
+    /// `ν / μ*` and the imbalance. The pilots, which mirror each other, are left out. Noise only makes the estimate
+    /// smaller. Without a pair of data subcarriers or without signal, returns no imbalance.
+    ///
+    /// The samples have to start at a symbol boundary, with the carrier frequency offset corrected.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::channel::{Channel, IqImbalance};
+    /// use software_modem::ofdm::complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator, IqCompensation};
+    ///
+    /// let config = ComplexOFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = ComplexOFDMModulator::new(config.clone());
+    /// let mut demodulator = ComplexOFDMDemodulator::new(config);
+    ///
+    /// let data: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let mut samples = modulator.modulate_symbols(&data);
+    /// let mut imbalance = IqImbalance { gain_db: 3.0, phase_deg: 20.0 };
+    /// imbalance.apply_complex(&mut samples);
+    ///
+    /// // the images of the corner points reach across the decision boundaries
+    /// assert_ne!(demodulator.demodulate_symbols(&samples), data);
+    ///
+    /// let estimate = demodulator.estimate_iq_imbalance(&samples);
+    /// assert!((estimate.gain_db - 3.0).abs() < 0.1, "{estimate:?}");
+    /// assert!((estimate.phase_deg - 20.0).abs() < 0.5, "{estimate:?}");
+    /// demodulator.set_iq_compensation(IqCompensation::Blind);
+    /// assert_eq!(demodulator.demodulate_symbols(&samples), data);
+    /// ```
+    pub fn estimate_iq_imbalance(&self, samples: &[Complex32]) -> IqImbalance {
+        let cyclic_prefix_length = self.constants.cyclic_prefix_samples();
+        let (mut correlation, mut power) = (Complex32::default(), 0.0);
+        let mut bins = Vec::new();
+        for symbol in samples.chunks_exact(self.get_symbol_length()) {
+            bins.clear();
+            bins.extend_from_slice(&symbol[cyclic_prefix_length..]);
+            self.fft.process(&mut bins);
+            for &(bin, mirror) in &self.image_pairs {
+                correlation += bins[bin] * bins[mirror];
+                power += bins[bin].norm_sqr() + bins[mirror].norm_sqr();
+            }
+        }
+        if power == 0.0 || correlation.norm_sqr() == 0.0 {
+            return IqImbalance::default();
+        }
+
+        // z = w / (1 + |w|²) for w = ν / μ*, of the root with |w| < 1
+        let ratio = correlation / power;
+        let magnitude = ratio.norm().min(0.5);
+        let image = ratio / magnitude * (1.0 - (1.0 - 4.0 * magnitude * magnitude).sqrt())
+            / (2.0 * magnitude);
+        // g e^(jφ) = (1 - w) / (1 + w)
+        let quadrature = (1.0 - image) / (1.0 + image);
+        IqImbalance {
+            gain_db: 20.0 * quadrature.norm().log10(),
+            phase_deg: quadrature.arg().to_degrees(),
+        }
+    }
+
+    /// Removes an imbalance of the I and Q branches from the samples, with the 2×2 matrix which undoes it on I and Q.
+    ///
+    /// Written on the complex samples, the signal `y = μ x + ν x*` of the imbalance is corrected to
+    /// `(y - w y*) / (μ - w ν*)` with `w = ν / μ*`, which is `x`.
+    /// See [estimate_iq_imbalance](Self::estimate_iq_imbalance) for an example.
+    pub fn correct_iq_imbalance(&self, samples: &mut [Complex32], imbalance: IqImbalance) {
+        let (direct, image) = imbalance.get_coefficients();
+        let ratio = image / direct.conj();
+        let gain = 1.0 / (direct - ratio * image.conj());
+        for sample in samples {
+            *sample = (*sample - ratio * sample.conj()) * gain;
+        }
+    }
+
+    /// Returns the length of the OFDM symbol, `num_subcarriers + cyclic_prefix_length`.
+    pub fn get_symbol_length(&self) -> usize {
+        self.constants.symbol_length()
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        (self.constants.bits_per_symbol / 8) as usize
+    }
+}
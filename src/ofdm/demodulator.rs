@@ -1,50 +1,166 @@
-use std::sync::Arc;
+use alloc::{sync::Arc, vec, vec::Vec};
 
-use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex32};
+use num_complex::{Complex, Complex32};
 use smart_default::SmartDefault;
 
 use crate::{
-    ofdm::OFDMConstants,
-    qam::{QAMModem, QAMOrder},
+    dsp::{Downconverter, FirFilter, Passband},
+    error::{Buffer, ModemError},
+    fft::{RealForwardFft, plan_real_forward},
+    metrics::{DemodulationReport, EvmResult, evm},
+    ofdm::{
+        BatchStats, DftSpreading, GuardInterval, GuardType, MAX_PILOT_SIGNALING_BITS,
+        OFDMConstants, SelectedMapping, SlmConfig, SlmSignaling, Stage, StageTimer,
+        SubcarrierLayout, SubcarrierPermutation, check_buffer_length, check_dft_spread,
+        check_guard_type, check_length, check_pilot_signaling, check_power_allocation,
+        correlate_pilot_pairs, differential_pilot_signs, pilot_magnitude,
+    },
+    qam::{GenericQAMModem, QAMOrder},
+    samples::{Sample, i16_to_f32_into},
 };
 
 #[allow(dead_code)]
 const PILOT_VALUE_TO_BE_CHANGED: Complex32 = Complex32 { re: 1.0, im: 0.0 };
 
-pub struct OFDMDemodulator {
-    fft: Arc<dyn RealToComplex<f32>>,
-    qam_modem: QAMModem,
+/// OFDM Demodulator computing in `f32`.
+///
+/// See [GenericOFDMDemodulator] for other sample types.
+pub type OFDMDemodulator = GenericOFDMDemodulator<f32>;
+
+/// OFDM Demodulator
+///
+/// Demodulates OFDM symbols of the [sample type](Sample) `T` back into data.
+pub struct GenericOFDMDemodulator<T: Sample> {
+    fft: Arc<dyn RealForwardFft<T>>,
+    qam_modem: GenericQAMModem<T>,
     constants: OFDMConstants,
+    differential_time: bool,
+    soft_output: bool,
+    roll_off: usize,
+    slm: Option<SelectedMapping<T>>,
+    pilot_signaling_bits: u32,
+    dft_spreading: Option<DftSpreading<T>>,
+    power_allocation: Option<Vec<T>>,
+    rx_filter: Option<FirFilter>,
+    downconverter: Option<Downconverter>,
+    confidence_symbols: Option<usize>,
+    track_common_phase: bool,
 }
 
-impl OFDMDemodulator {
+impl<T: Sample> GenericOFDMDemodulator<T> {
     /// Creates a new OFDM modulator with the given [configuration](OFDMDemodulatorConfig).
-    pub fn new(config: OFDMDemodulatorConfig) -> Self {
-        let qam_modem = QAMModem::new(config.qam_order);
+    ///
+    /// # Panics
+    /// If the [guard interval](GuardInterval) is not a whole number of samples, the guard subcarriers leave no subcarriers,
+    /// coherent demodulation has no pilot subcarrier to equalize with,
+    /// a reserved subcarrier is not a data subcarrier, the [selected mapping](SlmConfig) is invalid, like blind detection in differential mode,
+    /// the [pilot signaling bits](OFDMDemodulatorConfig::pilot_signaling_bits) are invalid,
+    /// [DFT spreading](OFDMDemodulatorConfig::dft_spread) is combined with differential mode or selected mapping,
+    /// [zero padding](GuardType::ZeroPad) is combined with a roll-off,
+    /// the [power allocation](OFDMDemodulatorConfig::power_allocation) does not have one valid gain per data subcarrier,
+    /// the [passband](OFDMDemodulatorConfig::passband) does not fit the subcarriers in use, see [Downconverter::new],
+    /// or the [FFT](OFDMDemodulatorConfig::fft) does not have the FFT length.
+    pub fn new(config: OFDMDemodulatorConfig<T>) -> Self {
+        if config.differential_time
+            && config
+                .slm
+                .is_some_and(|slm| slm.signaling == SlmSignaling::Blind)
+        {
+            panic!("Blind SLM detection needs coherent demodulation, but got differential_time");
+        }
+        check_dft_spread(
+            config.dft_spread,
+            config.differential_time,
+            config.slm.is_some(),
+        );
+        check_guard_type(config.guard_type, config.roll_off);
+
+        let qam_modem = GenericQAMModem::new(config.qam_order);
 
+        let cyclic_prefix_length = super::resolve_cyclic_prefix_length(
+            config.guard_interval,
+            config.cyclic_prefix_length,
+            2 * config.num_subcarriers,
+        );
         let constants = OFDMConstants::new(
             config.num_subcarriers,
-            config.pilot_subcarrier_every,
-            config.cyclic_prefix_length,
+            cyclic_prefix_length,
             config.qam_order,
-            qam_modem.bits_per_symbol(),
+            config.oversampling,
+            SubcarrierLayout {
+                pilot_subcarrier_every: config.pilot_subcarrier_every,
+                guard_subcarriers_low: config.guard_subcarriers_low,
+                guard_subcarriers_high: config.guard_subcarriers_high,
+                null_dc: config.null_dc,
+                reserved_subcarriers: &config.reserved_subcarriers,
+                masked_subcarriers: &config.masked_subcarriers,
+            },
+        )
+        .with_guard(config.guard_type, config.cyclic_suffix_length)
+        .with_permutation(config.subcarrier_permutation);
+        if !config.differential_time && constants.pilot_subcarrier_indices.is_empty() {
+            panic!(
+                "Coherent demodulation needs a pilot subcarrier, but got none every {} of {} subcarriers",
+                config.pilot_subcarrier_every, config.num_subcarriers
+            );
+        }
+
+        let slm = config.slm.map(|slm| constants.selected_mapping(&slm));
+        check_pilot_signaling(
+            config.pilot_signaling_bits,
+            config.slm,
+            constants.pilot_subcarrier_indices.len(),
         );
+        let dft_spreading = config.dft_spread.then(|| constants.dft_spreading());
+
+        if let Some(gains) = &config.power_allocation {
+            check_power_allocation(gains, constants.num_data_subcarriers as usize);
+        }
 
-        let fft = config.fft.unwrap_or_else(|| {
-            RealFftPlanner::<f32>::new().plan_fft_forward(2 * config.num_subcarriers as usize)
+        let fft = config
+            .fft
+            .unwrap_or_else(|| plan_real_forward(constants.fft_length()));
+        if fft.fft_length() != constants.fft_length() {
+            panic!(
+                "FFT length must be {}, but got {}",
+                constants.fft_length(),
+                fft.fft_length()
+            );
+        }
+
+        let downconverter = config.passband.map(|passband| {
+            let (low, high) = constants.band();
+            Downconverter::new(
+                passband.sample_rate,
+                (low * passband.sample_rate, high * passband.sample_rate),
+                passband.carrier_hz,
+            )
         });
 
-        OFDMDemodulator {
+        GenericOFDMDemodulator {
             fft,
             qam_modem,
             constants,
+            differential_time: config.differential_time,
+            soft_output: config.soft_output,
+            roll_off: (config.roll_off * config.oversampling) as usize,
+            slm,
+            pilot_signaling_bits: config.pilot_signaling_bits,
+            dft_spreading,
+            power_allocation: config
+                .power_allocation
+                .map(|gains| gains.iter().map(|&gain| T::cast(gain.into())).collect()),
+            rx_filter: config.rx_filter,
+            downconverter,
+            confidence_symbols: config.confidence_symbols,
+            track_common_phase: config.track_common_phase,
         }
     }
 
     /// Demodulates a single OFDM symbol from the given input buffer.
     ///
     /// The input buffer must have a length equal to the expected symbol length,
-    /// which is `2 * num_subcarriers + cyclic_prefix_length`,
+    /// which is `2 * num_subcarriers + cyclic_prefix_length + cyclic_suffix_length`,
     /// or: `self.get_symbol_length()`.
     ///
     /// # Panics
@@ -54,22 +170,607 @@ impl OFDMDemodulator {
     /// ```
     /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
     /// use software_modem::qam::QAMOrder;
+    /// use software_modem::testing::golden::read_fixtures;
     ///
     /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
     ///     num_subcarriers: 64,
     ///     cyclic_prefix_length: 4,
     ///     pilot_subcarrier_every: 4,
     ///     qam_order: QAMOrder::QAM16,
-    ///     fft: None,
+    ///     ..Default::default()
     /// });
     ///
-    /// let input_buffer = vec![1.5578203, 10.757554, -60.41084, -22.017548, 170.0, -42.44605, 54.674767, 22.390936, 6.2399883, -4.9697013, 22.430595, 17.925348, -2.8670907, -23.034523, -11.360638, 0.024665833, -3.071948, -7.734082, 3.0158787, 21.293457, 0.82842445, -35.719788, -33.072395, -19.85823, -0.14415121, -1.0148859, 1.0802565, 1.3617897, 1.0318756, -7.007739, 2.1753244, 15.374781, 21.054213, 0.07890889, -1.2171764, -3.3891459, -2.0, 41.081707, -4.085703, 0.47892523, -0.24726725, 6.605378, -11.310527, -4.8029222, -3.2976942, 6.129626, -5.986044, 17.46577, 33.94296, 56.904747, 10.276956, 26.332466, -21.798985, -45.932056, 16.227457, -11.979431, -5.4379044, -10.107577, 12.925878, 5.066286, 7.585412, -2.9996142, 5.774047, -8.335448, -6.82592, -9.922427, 26.371922, 19.215015, -6.0, -0.36616898, -44.328407, -32.542404, -11.508089, -6.3610272, -14.268342, -14.096208, 4.5239453, 3.1953726, -9.655043, -32.157936, -18.771591, -23.806992, -12.9909935, -65.67099, -4.8284245, 67.96052, 26.218727, 38.012096, 13.98769, 15.913272, -13.206813, -18.395777, -10.68873, 22.887703, 19.290443, -5.741539, -23.786112, -0.9140358, 27.256096, 6.191677, -42.0, 1.7305107, -14.260653, 9.6725445, -2.4846325, 4.7253504, -4.8517256, 0.97378147, -6.3591604, 13.709526, 19.001724, 14.6675, -20.099422, -25.363672, -8.301841, 18.045067, 17.798985, 13.69133, -17.373789, -6.1744323, -16.405634, -4.7908087, -8.799321, 11.967701, -5.9285583, -12.88035, -35.239815, -1.2977934, 1.5578203, 10.757554, -60.41084, -22.017548];
+    /// // the symbol of the text, as the modulator made it when the fixtures of testing::golden were stored
+    /// let fixtures = read_fixtures(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/golden.bin")).unwrap();
+    /// let input_buffer = &fixtures.iter().find(|fixture| fixture.name == "hello_ofdm").unwrap().samples;
     ///
-    /// let demodulated_data = demodulator.demodulate_symbol_from_buffer(&input_buffer);
+    /// let demodulated_data = demodulator.demodulate_symbol_from_buffer(input_buffer);
     ///
     /// assert_eq!(demodulated_data, "Hello, OFDM!            ".as_bytes());
     /// ```
-    pub fn demodulate_symbol_from_buffer(&self, input_buffer: &[f32]) -> Vec<u8> {
+    pub fn demodulate_symbol_from_buffer(&self, input_buffer: &[T]) -> Vec<u8> {
+        match self.demodulate_to_symbols(input_buffer) {
+            Ok(symbol) => self.qam_modem.demodulate(&symbol.data),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Demodulates a single OFDM symbol up to the QAM demapper, and returns its [frequency-domain symbol](FreqDomainSymbol):
+    /// every bin of the FFT, the data subcarrier points ready for the demapper, the pilots and the channel estimate.
+    ///
+    /// A custom demapper or detector starts from here, [demodulate_symbol_from_buffer](Self::demodulate_symbol_from_buffer)
+    /// demaps the same points with the QAM modem of the demodulator.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLengthMismatch] with [Buffer::Input] if the input does not have the
+    /// [symbol length](Self::get_symbol_length).
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    /// use software_modem::qam::{QAMModem, QAMOrder};
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    /// let symbol = modulator.modulate_symbol(b"Frequency domain").unwrap();
+    ///
+    /// // a channel halving the amplitude
+    /// let received: Vec<f32> = symbol.iter().map(|x| 0.5 * x).collect();
+    /// let frequency_domain = demodulator.demodulate_to_symbols(&received).unwrap();
+    /// assert_eq!(frequency_domain.bins.len(), 65);
+    /// assert_eq!(frequency_domain.data.len(), 48);
+    ///
+    /// // the pilots estimate the channel, the points are equalized by it
+    /// let gain = frequency_domain.channel_estimate.unwrap();
+    /// let (subcarrier, pilot) = frequency_domain.pilots[0];
+    /// assert_eq!(frequency_domain.bins[subcarrier as usize], pilot);
+    /// assert!((pilot.norm() - gain).abs() < 1e-3 * gain);
+    /// let sent = QAMModem::new(QAMOrder::QAM16).modulate(b"Frequency domain");
+    /// for (point, sent) in frequency_domain.data.iter().zip(&sent) {
+    ///     assert!((point - sent).norm() < 1e-3);
+    /// }
+    /// ```
+    pub fn demodulate_to_symbols(&self, input: &[T]) -> Result<FreqDomainSymbol<T>, ModemError> {
+        check_buffer_length(Buffer::Input, self.get_symbol_length(), input.len())?;
+        let mut scratch = self.make_scratch();
+        let data = self.demodulate_points(input, &mut scratch).to_vec();
+        let (bins, pilots) = self.get_bins(&scratch);
+        Ok(FreqDomainSymbol {
+            data,
+            pilots: self
+                .constants
+                .pilot_subcarrier_indices
+                .iter()
+                .copied()
+                .zip(pilots)
+                .collect(),
+            channel_estimate: self.channel_gain(bins),
+            bins: bins.to_vec(),
+        })
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer into the output, without allocating.
+    ///
+    /// The scratch holds the intermediate buffers, so a receiver demodulating symbol after symbol makes it once
+    /// with [make_scratch](Self::make_scratch) and reuses it. [demodulate_symbol_from_buffer](Self::demodulate_symbol_from_buffer)
+    /// allocates a scratch and the output for every symbol. Only the index detection of [explicit](SlmSignaling::Explicit)
+    /// selected mapping still allocates.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length,
+    /// the output does not have the [bytes per symbol](Self::get_bytes_per_symbol),
+    /// or the scratch was made by a demodulator of another configuration.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let data: Vec<u8> = (0..4 * modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let mut symbols = vec![0.0; 4 * modulator.get_symbol_length()];
+    /// for (data, symbol) in data
+    ///     .chunks(modulator.get_bytes_per_symbol())
+    ///     .zip(symbols.chunks_exact_mut(modulator.get_symbol_length()))
+    /// {
+    ///     modulator.modulate_buffer_as_symbol(data, symbol);
+    /// }
+    ///
+    /// // one scratch and one output for all symbols
+    /// let mut scratch = demodulator.make_scratch();
+    /// let mut received = vec![0; data.len()];
+    /// for (symbol, output) in symbols
+    ///     .chunks_exact(demodulator.get_symbol_length())
+    ///     .zip(received.chunks_exact_mut(demodulator.get_bytes_per_symbol()))
+    /// {
+    ///     demodulator.demodulate_symbol_into(symbol, &mut scratch, output);
+    /// }
+    /// assert_eq!(received, data);
+    /// ```
+    pub fn demodulate_symbol_into(
+        &self,
+        input_buffer: &[T],
+        scratch: &mut DemodulatorScratch<T>,
+        output: &mut [u8],
+    ) {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
+        if output.len() != self.get_bytes_per_symbol() {
+            panic!(
+                "Output length must be {} bytes, but got {} bytes",
+                self.get_bytes_per_symbol(),
+                output.len()
+            );
+        }
+
+        let points = self.demodulate_points(input_buffer, scratch);
+        self.qam_modem.demodulate_into(points, output);
+    }
+
+    /// Demodulates a single OFDM symbol like [demodulate_symbol_into](Self::demodulate_symbol_into),
+    /// returning an error instead of panicking, for a real-time thread like an audio callback.
+    ///
+    /// Once the scratch is made, the call neither allocates nor takes a lock, and it does not panic,
+    /// whatever the samples are: silence, noise, infinities or NaN all decide some data.
+    /// This holds for every configuration with the default FFT, or an FFT which does the same,
+    /// and only for this call: the [FrameDecoder](crate::frame::FrameDecoder) filters and allocates per frame.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the input does not have the symbol length or the output the bytes per symbol,
+    /// [ModemError::ScratchMismatch] if the scratch was made by a demodulator of another configuration.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ModemError;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    ///
+    /// let demodulator = OFDMDemodulator::new((&OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// }).into());
+    /// // made before the callback starts
+    /// let mut scratch = demodulator.make_scratch();
+    /// let mut output = [0; 24];
+    ///
+    /// let garbage = [f32::NAN; 132];
+    /// assert_eq!(demodulator.try_demodulate_symbol_into(&garbage, &mut scratch, &mut output), Ok(()));
+    /// assert_eq!(
+    ///     demodulator.try_demodulate_symbol_into(&garbage[..128], &mut scratch, &mut output),
+    ///     Err(ModemError::BufferLength { expected: 132, got: 128 })
+    /// );
+    /// ```
+    pub fn try_demodulate_symbol_into(
+        &self,
+        input_buffer: &[T],
+        scratch: &mut DemodulatorScratch<T>,
+        output: &mut [u8],
+    ) -> Result<(), ModemError> {
+        check_length(self.get_symbol_length(), input_buffer.len())?;
+        check_length(self.get_bytes_per_symbol(), output.len())?;
+        if !self.fits(scratch) {
+            return Err(ModemError::ScratchMismatch);
+        }
+        self.demodulate_symbol_into(input_buffer, scratch, output);
+        Ok(())
+    }
+
+    /// Demodulates a single OFDM symbol into the output like [demodulate_symbol_into](Self::demodulate_symbol_into),
+    /// transforming the samples where they are instead of copying them into the scratch first.
+    ///
+    /// The FFT uses its input as scratch space, so the samples after the cyclic prefix are left with unspecified values,
+    /// the cyclic prefix is left as it was. The data is the same as from [demodulate_symbol_into](Self::demodulate_symbol_into),
+    /// to the last bit. Use it when the samples are not needed afterwards, like a buffer refilled for every symbol.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length,
+    /// the output does not have the [bytes per symbol](Self::get_bytes_per_symbol),
+    /// or the scratch was made by a demodulator of another configuration.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    /// let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let mut symbol = modulator.modulate_symbol(&data).unwrap();
+    /// let original = symbol.clone();
+    ///
+    /// let mut scratch = demodulator.make_scratch();
+    /// let mut output = vec![0; data.len()];
+    /// demodulator.demodulate_symbol_in_place(&mut symbol, &mut scratch, &mut output);
+    /// assert_eq!(output, data);
+    /// // only the cyclic prefix is left
+    /// assert_eq!(symbol[..4], original[..4]);
+    /// ```
+    pub fn demodulate_symbol_in_place(
+        &self,
+        input_buffer: &mut [T],
+        scratch: &mut DemodulatorScratch<T>,
+        output: &mut [u8],
+    ) {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
+        if output.len() != self.get_bytes_per_symbol() {
+            panic!(
+                "Output length must be {} bytes, but got {} bytes",
+                self.get_bytes_per_symbol(),
+                output.len()
+            );
+        }
+
+        let points = self.demodulate_points_in_place(input_buffer, scratch, &mut ());
+        self.qam_modem.demodulate_into(points, output);
+    }
+
+    /// Demodulates every complete symbol of the input and appends their data to the output.
+    ///
+    /// Like calling [demodulate_symbol_into](Self::demodulate_symbol_into) for every symbol,
+    /// but with one scratch and the output grown once for all of them.
+    /// The samples after the last complete symbol are left untouched, the [stats](BatchStats) tell how many there are,
+    /// so a long capture can be demodulated block by block.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::{BatchStats, OFDMConfig};
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    /// let data: Vec<u8> = (0..10 * modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let mut samples = Vec::new();
+    /// modulator.modulate_batch(&data, &mut samples);
+    ///
+    /// // a block ending in the middle of the eighth symbol
+    /// let mut received = Vec::new();
+    /// let stats = demodulator.demodulate_batch(&samples[..7 * 132 + 50], &mut received);
+    /// assert_eq!(stats, BatchStats { symbols: 7, consumed: 7 * 132, trailing: 50 });
+    /// demodulator.demodulate_batch(&samples[stats.consumed..], &mut received);
+    /// assert_eq!(received, data);
+    /// ```
+    pub fn demodulate_batch(&self, input: &[T], output: &mut Vec<u8>) -> BatchStats {
+        let symbol_length = self.get_symbol_length();
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        let symbols = input.chunks_exact(symbol_length);
+        let stats = BatchStats {
+            symbols: symbols.len(),
+            consumed: symbols.len() * symbol_length,
+            trailing: symbols.remainder().len(),
+        };
+
+        let start = output.len();
+        output.resize(start + stats.symbols * bytes_per_symbol, 0);
+        let mut scratch = self.make_scratch();
+        for (symbol, output) in symbols.zip(output[start..].chunks_exact_mut(bytes_per_symbol)) {
+            self.demodulate_symbol_into(symbol, &mut scratch, output);
+        }
+        stats
+    }
+
+    /// Demodulates the whole symbols of the input like [demodulate_batch](Self::demodulate_batch),
+    /// and returns the [report](DemodulationReport) of their quality with the statistics.
+    ///
+    /// The EVM and SNR are the ones of all points, with the SNR of every data subcarrier over the symbols,
+    /// and the pilots of consecutive symbols give the residual CFO and the timing drift.
+    /// In differential mode, where a symbol on its own is not decided, only the pilots are measured.
+    pub fn demodulate_batch_with_report(
+        &self,
+        input: &[T],
+        output: &mut Vec<u8>,
+    ) -> (BatchStats, DemodulationReport) {
+        let symbol_length = self.get_symbol_length();
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        let symbols = input.chunks_exact(symbol_length);
+        let stats = BatchStats {
+            symbols: symbols.len(),
+            consumed: symbols.len() * symbol_length,
+            trailing: symbols.remainder().len(),
+        };
+
+        let start = output.len();
+        output.resize(start + stats.symbols * bytes_per_symbol, 0);
+        let mut scratch = self.make_scratch();
+        let mut points = Vec::new();
+        let mut phases = Vec::with_capacity(stats.symbols);
+        for (symbol, output) in symbols.zip(output[start..].chunks_exact_mut(bytes_per_symbol)) {
+            let symbol_points = self.demodulate_points(symbol, &mut scratch);
+            self.qam_modem.demodulate_into(symbol_points, output);
+            points.extend_from_slice(symbol_points);
+            phases.push(self.measure_pilots(&scratch));
+        }
+
+        let mut report = DemodulationReport::default();
+        if !self.differential_time {
+            let decisions = self.qam_modem.nearest_points(&points);
+            report = DemodulationReport::from_points(
+                &points,
+                &decisions,
+                Some(self.constants.data_subcarrier_indices.len()),
+            );
+            self.report_confidence(&points, &decisions, &mut report);
+        }
+        self.report_pilots(&phases, &mut report);
+        (stats, report)
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer into soft bit decisions.
+    ///
+    /// Returns one LLR per data bit, see [QAMModem::demodulate_soft](crate::qam::QAMModem::demodulate_soft) for the convention.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length.
+    pub fn demodulate_symbol_soft_from_buffer(&self, input_buffer: &[T]) -> Vec<T> {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
+
+        let mut scratch = self.make_scratch();
+        let points = self.demodulate_points(input_buffer, &mut scratch);
+
+        self.qam_modem.demodulate_soft(points)
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer, and returns the data subcarrier points with the data.
+    ///
+    /// The points are the ones the data is decided from, equalized and with the power allocation and selected mapping undone,
+    /// in the order of the data subcarriers. They show how far the received symbol is from the constellation,
+    /// see [write_constellation_csv](crate::analysis::write_constellation_csv) to plot them.
+    /// In differential mode a single symbol has no reference, use [FrameDecoder::get_constellation](crate::frame::FrameDecoder::get_constellation) instead.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::{QAMModem, QAMOrder};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let symbol = modulator.modulate_symbol(&data).unwrap();
+    ///
+    /// let (demodulated_data, points) = demodulator.demodulate_symbol_with_points(&symbol);
+    /// assert_eq!(demodulated_data, data);
+    ///
+    /// // on a clean loopback, the points are the ideal constellation points of the data
+    /// let ideal = QAMModem::new(QAMOrder::QAM16).modulate(&data);
+    /// assert_eq!(points.len(), ideal.len());
+    /// for (point, ideal) in points.iter().zip(&ideal) {
+    ///     assert!((point - ideal).norm() < 1e-4, "{point} vs {ideal}");
+    /// }
+    /// ```
+    pub fn demodulate_symbol_with_points(&self, input_buffer: &[T]) -> (Vec<u8>, Vec<Complex<T>>) {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
+
+        let points = self
+            .demodulate_points(input_buffer, &mut self.make_scratch())
+            .to_vec();
+
+        (self.qam_modem.demodulate(&points), points)
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer, and returns its data-aided [EVM](crate::metrics::evm) with the data.
+    ///
+    /// The reference of the [points](Self::demodulate_symbol_with_points) are their own hard decisions,
+    /// so a point decided wrong counts with its distance to the wrong constellation point, and the EVM is too optimistic
+    /// once the decisions go wrong. Like the points, it needs a coherent symbol.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::{AwgnChannel, Channel};
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let mut symbol = modulator.modulate_symbol(&data).unwrap();
+    ///
+    /// // a loopback only has the rounding of the FFTs
+    /// let (demodulated, evm) = demodulator.demodulate_symbol_with_evm(&symbol);
+    /// assert_eq!(demodulated, data);
+    /// assert!(evm.rms_db < -100.0, "{evm:?}");
+    ///
+    /// // noise 30 dB below the symbol
+    /// AwgnChannel::new(30.0, 1).apply(&mut symbol);
+    /// let (_, evm) = demodulator.demodulate_symbol_with_evm(&symbol);
+    /// assert!(evm.rms_db > -40.0 && evm.rms_db < -20.0, "{evm:?}");
+    /// assert!(evm.peak_db > evm.rms_db);
+    /// ```
+    pub fn demodulate_symbol_with_evm(&self, input_buffer: &[T]) -> (Vec<u8>, EvmResult) {
+        let (data, points) = self.demodulate_symbol_with_points(input_buffer);
+        let evm = evm(&points, &self.qam_modem.nearest_points(&points));
+        (data, evm)
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer, and returns the [report](DemodulationReport)
+    /// of its quality with the data.
+    ///
+    /// The report has the [EVM](Self::demodulate_symbol_with_evm) and SNR of the points, and the common phase error
+    /// of the pilots, a single symbol shows no drift. In differential mode only the pilots are measured.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::{AwgnChannel, Channel};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    ///
+    /// let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    /// let mut symbol = modulator.modulate_symbol(&data).unwrap();
+    /// AwgnChannel::new(20.0, 1).apply(&mut symbol);
+    ///
+    /// let (demodulated, report) = demodulator.demodulate_symbol_with_report(&symbol);
+    /// assert_eq!(demodulated, data);
+    /// assert!((report.snr_db.unwrap() - 20.0).abs() < 3.0, "{report:?}");
+    /// assert!(report.common_phase_error.unwrap().abs() < 0.1);
+    /// // which a single symbol has no drift of, nor a CRC
+    /// assert_eq!((report.residual_cfo, report.timing_drift, report.crc_ok), (None, None, None));
+    /// ```
+    pub fn demodulate_symbol_with_report(
+        &self,
+        input_buffer: &[T],
+    ) -> (Vec<u8>, DemodulationReport) {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
+
+        let mut scratch = self.make_scratch();
+        let points = self.demodulate_points(input_buffer, &mut scratch).to_vec();
+        let mut report = DemodulationReport::default();
+        if !self.differential_time {
+            let decisions = self.qam_modem.nearest_points(&points);
+            report = DemodulationReport::from_points(&points, &decisions, None);
+            self.report_confidence(&points, &decisions, &mut report);
+        }
+        self.report_pilots(&[self.measure_pilots(&scratch)], &mut report);
+        (self.qam_modem.demodulate(&points), report)
+    }
+
+    /// Demodulates a single OFDM symbol from the given input buffer, and returns the signaling value of its pilots
+    /// with the data, see [OFDMDemodulatorConfig::pilot_signaling_bits].
+    ///
+    /// The other calls recover the value as well, into the [scratch](DemodulatorScratch::get_signaling).
+    /// Without signaling bits the value is always 0.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match the expected length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::{AwgnChannel, Channel};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     pilot_signaling_bits: 3,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    ///
+    /// let data = [0xa5; 24];
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol_with_signaling(&data, 0b101, &mut modulator.make_scratch(), &mut symbol);
+    /// AwgnChannel::new(15.0, 1).apply(&mut symbol);
+    ///
+    /// let (demodulated, signaling) = demodulator.demodulate_symbol_with_signaling(&symbol);
+    /// assert_eq!(demodulated, data);
+    /// assert_eq!(signaling.value, 0b101);
+    /// // every bit is repeated over 4 or 5 of the 14 pairs of the 15 pilots, which mostly agree
+    /// assert!(signaling.confidence[..3].iter().all(|&confidence| confidence > 0.5), "{signaling:?}");
+    /// assert_eq!(signaling.confidence[3..], [0.0; 5]);
+    /// ```
+    pub fn demodulate_symbol_with_signaling(
+        &self,
+        input_buffer: &[T],
+    ) -> (Vec<u8>, PilotSignaling) {
         if input_buffer.len() != self.get_symbol_length() {
             panic!(
                 "Symbol buffer length must be {}, but got {}",
@@ -78,67 +779,736 @@ impl OFDMDemodulator {
             );
         }
 
-        let demodulated_symbol = self.demodulate_ofdm_symbol(input_buffer).unwrap();
+        let mut scratch = self.make_scratch();
+        let mut data = vec![0; self.get_bytes_per_symbol()];
+        self.demodulate_symbol_into(input_buffer, &mut scratch, &mut data);
+        (data, scratch.signaling)
+    }
 
-        self.qam_modem.demodulate(&demodulated_symbol)
+    /// Makes the buffers to demodulate symbols in, see [demodulate_symbol_into](Self::demodulate_symbol_into).
+    pub fn make_scratch(&self) -> DemodulatorScratch<T> {
+        let fft_length = self.constants.fft_length();
+        let num_points = self.constants.data_subcarrier_indices.len();
+        DemodulatorScratch {
+            samples: vec![T::zero(); fft_length],
+            bins: vec![Complex::default(); fft_length / 2 + 1],
+            fft: vec![Complex::default(); self.fft_scratch_len()],
+            points: vec![Complex::default(); num_points],
+            pilots: vec![Complex::default(); self.constants.pilot_subcarrier_indices.len()],
+            rotated: vec![Complex::default(); num_points],
+            signaling: PilotSignaling::default(),
+        }
     }
 
-    fn demodulate_ofdm_symbol(&self, input: &[f32]) -> Result<Vec<Complex32>, String> {
-        // remove cyclic prefix
-        let mut input_no_cp = vec![0.0; 2 * self.constants.num_subcarriers as usize];
-        input_no_cp.clone_from_slice(&input[self.constants.cyclic_prefix_length as usize..]);
+    /// Returns the data subcarrier points of one symbol, demodulated in the scratch.
+    ///
+    /// The points are equalized, unless the demodulator works differentially in time.
+    pub(crate) fn demodulate_points<'a>(
+        &self,
+        input: &[T],
+        scratch: &'a mut DemodulatorScratch<T>,
+    ) -> &'a [Complex<T>] {
+        self.check_scratch(scratch);
+        let DemodulatorScratch {
+            samples,
+            bins,
+            fft,
+            points,
+            pilots,
+            rotated,
+            signaling,
+        } = scratch;
+
+        // remove the cyclic prefix, or add the zero padding back, the FFT clobbers its input
+        self.constants.gather_body(input, samples);
 
         // time domain to frequency domain
-        let mut output_buffer = self.fft.make_output_vec();
-        self.fft
-            .process(&mut input_no_cp, &mut output_buffer)
-            .unwrap();
+        let real_fft = &mut fft[..self.fft.get_scratch_len()];
+        self.fft.process_with_scratch(samples, bins, real_fft);
+        self.remove_pilot_signaling(bins, pilots, signaling);
+        self.equalize(bins, points, pilots, rotated, fft)
+    }
+
+    /// Returns the data subcarrier points of one symbol like [demodulate_points](Self::demodulate_points),
+    /// transforming the samples after the cyclic prefix where they are, which clobbers them.
+    ///
+    /// The timer times the FFT and the equalization.
+    pub(crate) fn demodulate_points_in_place<'a>(
+        &self,
+        input: &mut [T],
+        scratch: &'a mut DemodulatorScratch<T>,
+        timer: &mut impl StageTimer,
+    ) -> &'a [Complex<T>] {
+        self.check_scratch(scratch);
+        let DemodulatorScratch {
+            bins,
+            fft,
+            points,
+            pilots,
+            rotated,
+            signaling,
+            ..
+        } = scratch;
+
+        let samples = self.constants.gather_body_in_place(input);
+        timer.time(Stage::Fft, || {
+            let real_fft = &mut fft[..self.fft.get_scratch_len()];
+            self.fft.process_with_scratch(samples, bins, real_fft)
+        });
+        timer.time(Stage::Equalize, || {
+            self.remove_pilot_signaling(bins, pilots, signaling);
+            self.equalize(bins, points, pilots, rotated, fft)
+        })
+    }
 
-        // equalize
-        // todo this just uses one pilot for all subcarriers
-        let eq_factor = output_buffer[4].to_polar().0;
+    /// Recovers the signaling value from the pilot bins of one symbol, and flips the pilot bins back to the signs
+    /// of no signaling, so the channel estimate and the measurements of the pilots see the pilots they expect.
+    ///
+    /// A bit received in error flips the pilots after its pairs the wrong way, which the pilot magnitudes
+    /// the equalizer divides by do not show, but the phases of the pilots do.
+    fn remove_pilot_signaling(
+        &self,
+        bins: &mut [Complex<T>],
+        pilots: &mut [Complex<T>],
+        signaling: &mut PilotSignaling,
+    ) {
+        if self.pilot_signaling_bits == 0 {
+            return;
+        }
+        let pilot_indices = &self.constants.pilot_subcarrier_indices;
+        for (pilot, &idx) in pilots.iter_mut().zip(pilot_indices) {
+            *pilot = bins[idx as usize];
+        }
+        *signaling = PilotSignaling::default();
+        let correlations = correlate_pilot_pairs(pilots, self.pilot_signaling_bits);
+        for (bit, &(correlation, magnitude)) in correlations.iter().enumerate() {
+            if correlation < T::zero() {
+                signaling.value |= 1 << bit;
+            }
+            if magnitude > T::zero() {
+                signaling.confidence[bit] = (correlation.abs() / magnitude).into_f32();
+            }
+        }
 
-        for sample in output_buffer.iter_mut() {
-            *sample = sample.scale(1.0 / eq_factor);
+        let signs = differential_pilot_signs::<T>(
+            signaling.value.into(),
+            self.pilot_signaling_bits,
+            pilot_indices.len(),
+        );
+        for (&idx, sign) in pilot_indices.iter().zip(signs) {
+            bins[idx as usize] *= sign;
         }
+    }
 
+    /// Equalizes the bins of one symbol, and returns its data subcarrier points, despread if they were spread.
+    fn equalize<'a>(
+        &self,
+        bins: &[Complex<T>],
+        points: &'a mut [Complex<T>],
+        pilots: &mut [Complex<T>],
+        rotated: &mut [Complex<T>],
+        fft: &mut [Complex<T>],
+    ) -> &'a [Complex<T>] {
         // extract data subcarriers
-        let mut output_symbols =
-            vec![Complex32::default(); self.constants.data_subcarrier_indices.len()];
-        for (i, &idx) in self.constants.data_subcarrier_indices.iter().enumerate() {
-            output_symbols[i] = output_buffer[idx as usize];
+        self.constants.data_subcarrier_map.gather(bins, points);
+
+        // equalize the points and the pilots of the selected mapping rather than every bin
+        // todo this uses the mean pilot magnitude for all subcarriers
+        let pilot_indices = &self.constants.pilot_subcarrier_indices;
+        let scale = self.channel_gain(bins).map(|gain| T::one() / gain);
+        if let Some(factor) = scale {
+            T::scale_points(points, factor);
+        }
+        if self.track_common_phase && scale.is_some() && self.slm.is_none() {
+            // the pilots have the signs of no signaling by now
+            let common = pilot_indices
+                .iter()
+                .map(|&idx| bins[idx as usize])
+                .fold(Complex::<T>::default(), |sum, bin| sum + bin);
+            let norm = common.norm_sqr().sqrt();
+            if norm > T::zero() {
+                let turn = common.conj() / norm;
+                points.iter_mut().for_each(|point| *point *= turn);
+            }
+        }
+
+        // the pilots only give the common gain, the allocated gains are known
+        // in differential mode, the reference symbol carries them as well
+        if let Some(gains) = self
+            .power_allocation
+            .as_ref()
+            .filter(|_| !self.differential_time)
+        {
+            T::unscale_points(points, gains);
+        }
+
+        if let Some(slm) = &self.slm {
+            for (pilot, &idx) in pilots.iter_mut().zip(pilot_indices) {
+                *pilot = bins[idx as usize];
+            }
+            if let Some(factor) = scale {
+                T::scale_points(pilots, factor);
+            }
+            let index = slm
+                .detect_index(pilots)
+                .unwrap_or_else(|| self.detect_slm_index_blind(slm, points, rotated));
+            T::derotate_points(points, slm.phases(index));
+        }
+
+        if let Some(spreading) = &self.dft_spreading {
+            spreading.despread(points, fft);
+        }
+
+        points
+    }
+
+    /// Returns the gain the equalizer divides the points of the bins by, the mean magnitude of the pilots over theirs,
+    /// or `None` in differential mode, which is not equalized, and for a silent symbol, which has nothing to equalize.
+    fn channel_gain(&self, bins: &[Complex<T>]) -> Option<T> {
+        if self.differential_time {
+            return None;
+        }
+        let pilot_indices = &self.constants.pilot_subcarrier_indices;
+        // the root of the squared magnitude, hypot guards against an overflow the bins never reach
+        let gain = pilot_indices
+            .iter()
+            .map(|&idx| bins[idx as usize].norm_sqr().sqrt())
+            .sum::<T>()
+            / T::cast(
+                (pilot_indices.len().max(1) as u32 * pilot_magnitude(self.constants.qam_order))
+                    .into(),
+            );
+        (gain > T::zero()).then_some(gain)
+    }
+
+    /// Panics if the scratch was not made by a demodulator of this configuration.
+    fn check_scratch(&self, scratch: &DemodulatorScratch<T>) {
+        if !self.fits(scratch) {
+            panic!(
+                "Scratch must be made for an FFT length of {} and {} data subcarriers, but got {} and {}",
+                self.constants.fft_length(),
+                self.constants.data_subcarrier_indices.len(),
+                scratch.samples.len(),
+                scratch.points.len()
+            );
         }
+    }
 
-        Ok(output_symbols)
+    /// Returns `true` if the scratch was made by a demodulator of this configuration.
+    fn fits(&self, scratch: &DemodulatorScratch<T>) -> bool {
+        scratch.samples.len() == self.constants.fft_length()
+            && scratch.fft.len() == self.fft_scratch_len()
+            && scratch.points.len() == self.constants.data_subcarrier_indices.len()
+            && scratch.pilots.len() == self.constants.pilot_subcarrier_indices.len()
     }
 
-    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    /// Returns the scratch space the FFT and the DFT spreading share, enough for either of them.
+    fn fft_scratch_len(&self) -> usize {
+        self.fft.get_scratch_len().max(
+            self.dft_spreading
+                .as_ref()
+                .map_or(0, DftSpreading::scratch_len),
+        )
+    }
+
+    /// Returns the candidate whose rotated back points lie closest to the constellation.
+    fn detect_slm_index_blind(
+        &self,
+        slm: &SelectedMapping<T>,
+        points: &[Complex<T>],
+        rotated: &mut [Complex<T>],
+    ) -> usize {
+        let mut distances = (0..slm.candidates()).map(|index| {
+            for ((rotated, &point), phase) in rotated.iter_mut().zip(points).zip(slm.phases(index))
+            {
+                *rotated = point * phase.conj();
+            }
+            rotated
+                .iter()
+                .map(|&point| self.qam_modem.nearest_point(point).1)
+                .sum::<T>()
+        });
+        let first = distances.next().unwrap_or_else(T::zero);
+        distances
+            .enumerate()
+            .fold((0, first), |best, (index, distance)| {
+                if distance < best.1 {
+                    (index + 1, distance)
+                } else {
+                    best
+                }
+            })
+            .0
+    }
+
+    /// Measures the pilots of the symbol last demodulated in the scratch,
+    /// or returns `None` if there is nothing to measure them against.
+    ///
+    /// The phase slope between neighbouring pilots gives the timing offset, a symbol `delay` samples late
+    /// turns bin `k` by `-2 pi k delay / fft_length`, and the sum of the pilots without that slope the common phase.
+    /// The pilots of selected mapping have their signs flipped by the candidate, which the slope does not know of.
+    pub(crate) fn measure_pilots(&self, scratch: &DemodulatorScratch<T>) -> Option<PilotPhase> {
+        if self.slm.is_some() {
+            return None;
+        }
+        let fft_length = self.constants.fft_length() as f32;
+        // the DC bin is real, it carries no phase
+        let pilots: Vec<(f32, Complex32)> = self
+            .constants
+            .pilot_subcarrier_indices
+            .iter()
+            .filter(|&&idx| idx != 0)
+            .map(|&idx| {
+                let bin = scratch.bins[idx as usize];
+                (
+                    idx as f32,
+                    Complex32::new(bin.re.into_f32(), bin.im.into_f32()),
+                )
+            })
+            .collect();
+
+        // the slopes of the pairs, weighted by their magnitudes
+        let (mut slope, mut weight) = (0.0, 0.0);
+        for pair in pilots.windows(2) {
+            let product = pair[1].1 * pair[0].1.conj();
+            slope += product.norm() * product.arg() / (pair[1].0 - pair[0].0);
+            weight += product.norm();
+        }
+        if !(weight > 0.0 && slope.is_finite()) {
+            return None;
+        }
+
+        let delay = -slope / weight * fft_length / core::f32::consts::TAU;
+        let common = pilots
+            .iter()
+            .map(|&(k, pilot)| {
+                pilot * Complex32::from_polar(1.0, core::f32::consts::TAU * k * delay / fft_length)
+            })
+            .sum();
+        Some(PilotPhase { common, delay })
+    }
+
+    /// Returns the bins of the symbol last demodulated in the scratch, from DC to the Nyquist frequency,
+    /// with the bins of its pilot subcarriers divided by their pilot, which so estimate the channel at them.
+    pub(crate) fn get_bins<'a>(
+        &self,
+        scratch: &'a DemodulatorScratch<T>,
+    ) -> (&'a [Complex<T>], Vec<Complex<T>>) {
+        let pilot = Complex::new(
+            T::cast(PILOT_VALUE_TO_BE_CHANGED.re.into()),
+            T::cast(PILOT_VALUE_TO_BE_CHANGED.im.into()),
+        ) * T::cast(pilot_magnitude(self.constants.qam_order).into());
+        let pilots = self
+            .constants
+            .pilot_subcarrier_indices
+            .iter()
+            .map(|&idx| scratch.bins[idx as usize] / pilot)
+            .collect();
+        (&scratch.bins, pilots)
+    }
+
+    /// Keeps the confidence of the decisions of the first symbols of the points in the report,
+    /// if the configuration asks for it, see [OFDMDemodulatorConfig::confidence_symbols].
+    pub(crate) fn report_confidence(
+        &self,
+        points: &[Complex<T>],
+        decisions: &[Complex<T>],
+        report: &mut DemodulationReport,
+    ) {
+        if let Some(max_symbols) = self.confidence_symbols {
+            report.keep_confidence(
+                points,
+                decisions,
+                self.constants.data_subcarrier_indices.len(),
+                max_symbols,
+                self.qam_modem.min_distance(),
+            );
+        }
+    }
+
+    /// Fills the common phase error of the report from the pilots of consecutive symbols,
+    /// and with more than one symbol the residual CFO and the timing drift.
+    ///
+    /// A symbol whose pilots could not be measured leaves them all unknown.
+    pub(crate) fn report_pilots(
+        &self,
+        phases: &[Option<PilotPhase>],
+        report: &mut DemodulationReport,
+    ) {
+        let Some(phases) = phases.iter().copied().collect::<Option<Vec<_>>>() else {
+            return;
+        };
+        if phases.is_empty() {
+            return;
+        }
+        report.common_phase_error = Some(
+            phases
+                .iter()
+                .map(|phase| phase.common)
+                .sum::<Complex32>()
+                .arg(),
+        );
+        if phases.len() < 2 {
+            return;
+        }
+
+        // an offset of one subcarrier spacing turns the phase by 2 pi over the FFT length, a symbol is longer by the prefix
+        let turn = phases
+            .windows(2)
+            .map(|pair| pair[1].common * pair[0].common.conj())
+            .sum::<Complex32>()
+            .arg();
+        let symbol_length = self.get_symbol_length() as f32;
+        let fft_length = self.constants.fft_length() as f32;
+        report.residual_cfo = Some(turn * fft_length / (core::f32::consts::TAU * symbol_length));
+
+        // the least squares slope of the delays over the symbols
+        let mean_index = (phases.len() - 1) as f32 / 2.0;
+        let mean_delay = phases.iter().map(|phase| phase.delay).sum::<f32>() / phases.len() as f32;
+        let (covariance, variance) =
+            phases
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(covariance, variance), (i, phase)| {
+                    let index = i as f32 - mean_index;
+                    (
+                        covariance + index * (phase.delay - mean_delay),
+                        variance + index * index,
+                    )
+                });
+        report.timing_drift = Some(covariance / variance);
+    }
+
+    /// Returns the length of the OFDM symbol, including the cyclic prefix and suffix.
     ///
     /// The length is calculated as:
-    /// `2 * num_subcarriers + cyclic_prefix_length`.
+    /// `(2 * num_subcarriers + cyclic_prefix_length + cyclic_suffix_length) * oversampling`.
     pub fn get_symbol_length(&self) -> usize {
-        (2 * self.constants.num_subcarriers + self.constants.cyclic_prefix_length) as usize
+        self.constants.symbol_length()
+    }
+
+    /// Switches the QAM order of the data subcarriers, like an adaptive link does between two frames.
+    ///
+    /// Only the QAM modem and the number of bytes per symbol change. The FFT is not planned again,
+    /// and the scratches made before stay valid, so a real-time thread can switch without allocating.
+    /// It must match the order of the other end for every frame.
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the order carries the whole bytes of a symbol on a different number
+    /// of data subcarriers, which the scratches, the selected mapping and the power allocation are made for.
+    /// The demodulator is then left as it was.
+    ///
+    /// See [OFDMModulator::set_qam_order](crate::ofdm::modulator::GenericOFDMModulator::set_qam_order) for an example.
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.constants.set_qam_order(qam_order)?;
+        self.qam_modem = GenericQAMModem::new(qam_order);
+        Ok(())
+    }
+
+    /// Returns the QAM order of the data subcarriers.
+    pub fn get_qam_order(&self) -> QAMOrder {
+        self.constants.qam_order
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        (self.constants.bits_per_symbol / 8) as usize
+    }
+
+    /// Returns `true` if the demodulator is configured for differential decoding in time.
+    pub fn is_differential_time(&self) -> bool {
+        self.differential_time
+    }
+
+    /// Returns the number of roll-off samples a frame of windowed symbols ends with.
+    pub fn get_roll_off(&self) -> usize {
+        self.roll_off
+    }
+
+    /// Returns the filter applied to every frame before demodulation, see [OFDMDemodulatorConfig::rx_filter].
+    pub fn get_rx_filter(&self) -> Option<&FirFilter> {
+        self.rx_filter.as_ref()
+    }
+
+    /// Returns the downconverter moving every frame back from its carrier, see [OFDMDemodulatorConfig::passband].
+    pub fn get_downconverter(&self) -> Option<&Downconverter> {
+        self.downconverter.as_ref()
+    }
+
+    /// Returns the forward FFT of the demodulator, see [OFDMDemodulatorConfig::fft].
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use software_modem::fft::FftPlannerHandle;
+    /// use software_modem::ofdm::{GuardInterval, OFDMConfig};
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 256,
+    ///     guard_interval: Some(GuardInterval::Sixteenth),
+    ///     ..Default::default()
+    /// };
+    /// // demodulators of the same FFT length share the plan of the global planner
+    /// let first = OFDMDemodulator::new((&config).into());
+    /// let second = OFDMDemodulator::new((&config).into());
+    /// assert!(Arc::ptr_eq(first.get_fft(), second.get_fft()));
+    ///
+    /// // unless planned with another planner
+    /// let own = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     fft: Some(FftPlannerHandle::new().plan_real_forward(512)),
+    ///     ..(&config).into()
+    /// });
+    /// assert!(!Arc::ptr_eq(first.get_fft(), own.get_fft()));
+    /// ```
+    pub fn get_fft(&self) -> &Arc<dyn RealForwardFft<T>> {
+        &self.fft
+    }
+
+    /// Returns `true` if the demodulator is configured for soft output.
+    pub fn is_soft_output(&self) -> bool {
+        self.soft_output
+    }
+
+    /// Returns the subcarrier indices carrying data, in the order of the demodulated points.
+    pub(crate) fn data_subcarrier_indices(&self) -> &[u32] {
+        &self.constants.data_subcarrier_indices
+    }
+
+    /// Returns the subcarrier indices carrying pilots, in the order of the pilots of [get_bins](Self::get_bins).
+    pub(crate) fn pilot_subcarrier_indices(&self) -> &[u32] {
+        &self.constants.pilot_subcarrier_indices
+    }
+
+    /// Returns the length of the cyclic prefix in samples, with the oversampling.
+    pub(crate) fn get_cyclic_prefix_samples(&self) -> usize {
+        self.constants.cyclic_prefix_samples()
+    }
+
+    pub(crate) fn qam_modem(&self) -> &GenericQAMModem<T> {
+        &self.qam_modem
     }
 }
 
+impl OFDMDemodulator {
+    /// Demodulates consecutive symbols of `i16` samples into their data, see [i16_to_f32](crate::samples::i16_to_f32).
+    ///
+    /// The level of the samples does not matter, the equalization absorbs it.
+    /// See [OFDMModulator::modulate_bytes_i16](crate::ofdm::modulator::OFDMModulator::modulate_bytes_i16) for an example.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length.
+    pub fn demodulate_symbols_i16(&self, samples: &[i16]) -> Vec<u8> {
+        let symbol_length = self.get_symbol_length();
+        if !samples.len().is_multiple_of(symbol_length) {
+            panic!(
+                "Number of samples must be a multiple of {}, but got {}",
+                symbol_length,
+                samples.len()
+            );
+        }
+
+        let mut symbol = vec![0.0; symbol_length];
+        let mut scratch = self.make_scratch();
+        let mut data = vec![0; samples.len() / symbol_length * self.get_bytes_per_symbol()];
+        for (samples, output) in samples
+            .chunks_exact(symbol_length)
+            .zip(data.chunks_exact_mut(self.get_bytes_per_symbol()))
+        {
+            i16_to_f32_into(samples, &mut symbol);
+            self.demodulate_symbol_into(&symbol, &mut scratch, output);
+        }
+        data
+    }
+}
+
+/// The pilots of one symbol, see [measure_pilots](GenericOFDMDemodulator::measure_pilots).
+#[derive(Clone, Copy)]
+pub(crate) struct PilotPhase {
+    /// The sum of the pilots without the phase slope of the delay, along the common phase.
+    common: Complex32,
+    /// The delay of the symbol against the FFT window, in samples.
+    delay: f32,
+}
+
+impl PilotPhase {
+    /// Returns the delay of the symbol against the FFT window, in samples.
+    pub(crate) fn get_delay(&self) -> f32 {
+        self.delay
+    }
+}
+
+/// Buffers a [GenericOFDMDemodulator] demodulates symbols in, made by [make_scratch](GenericOFDMDemodulator::make_scratch).
+///
+/// A scratch only fits demodulators of the same configuration.
+pub struct DemodulatorScratch<T: Sample = f32> {
+    samples: Vec<T>,
+    bins: Vec<Complex<T>>,
+    fft: Vec<Complex<T>>,
+    points: Vec<Complex<T>>,
+    pilots: Vec<Complex<T>>,
+    rotated: Vec<Complex<T>>,
+    signaling: PilotSignaling,
+}
+
+impl<T: Sample> DemodulatorScratch<T> {
+    /// Returns the signaling value recovered from the pilots of the symbol last demodulated in the scratch,
+    /// see [OFDMDemodulatorConfig::pilot_signaling_bits].
+    pub fn get_signaling(&self) -> PilotSignaling {
+        self.signaling
+    }
+}
+
+/// The signaling bits of a symbol recovered from the signs of its pilots, see [OFDMDemodulatorConfig::pilot_signaling_bits].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PilotSignaling {
+    /// The signaling value, its bit `b` decided from the pilot pairs `b`, `b + bits`, `b + 2 * bits` and so on.
+    pub value: u8,
+    /// How far the pilot pairs of every bit agree, from 0 for pairs cancelling each other, where the bit is a guess,
+    /// to 1 for pairs which all point the same way. The bits beyond the configured ones have 0.
+    ///
+    /// Noise and a phase slope of the channel between neighbouring pilots, like from a timing offset, lower it,
+    /// and a phase step of more than a quarter turn between neighbouring pilots turns the bits over.
+    pub confidence: [f32; MAX_PILOT_SIGNALING_BITS as usize],
+}
+
+/// A symbol demodulated up to the QAM demapper, see [demodulate_to_symbols](GenericOFDMDemodulator::demodulate_to_symbols).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreqDomainSymbol<T: Sample = f32> {
+    /// Every bin of the FFT of the symbol after its cyclic prefix, from DC to the Nyquist frequency, as received,
+    /// the pilots with the signs of the [signaling](OFDMDemodulatorConfig::pilot_signaling_bits) taken off.
+    pub bins: Vec<Complex<T>>,
+    /// The data subcarrier points, equalized, with the power allocation divided out, turned back after selected mapping
+    /// and despread, which the demapper decides. In differential mode they are the bins, which are not equalized.
+    pub data: Vec<Complex<T>>,
+    /// The bins of the pilot subcarriers divided by the pilot they carry, the channel at them, with their subcarriers.
+    pub pilots: Vec<(u32, Complex<T>)>,
+    /// The gain the data subcarrier points were divided by, the mean magnitude of the pilots,
+    /// `None` in differential mode and for a silent symbol, whose points are not equalized.
+    pub channel_estimate: Option<T>,
+}
+
 /// Configuration for the [OFDM Demodulator](OFDMDemodulator).
 ///
 /// Just contruct this struct with the desired parameters and pass it to the `OFDMDemodulator::new()` method.
 #[derive(SmartDefault)]
-pub struct OFDMDemodulatorConfig {
+pub struct OFDMDemodulatorConfig<T: Sample = f32> {
     pub num_subcarriers: u32,
     /// Length of the cyclic prefix in samples.
     ///
-    /// One OFDM symbol double num_subcarriers samples. If you want to have a CP of 1/4 you need to set this to `(2 * num_subcarriers) / 4`
+    /// One OFDM symbol has `2 * num_subcarriers` samples, for a CP of 1/4 set the [guard interval](GuardInterval) instead.
     pub cyclic_prefix_length: u32,
+    /// Length of the cyclic prefix as a fraction of the FFT length, which replaces the cyclic prefix length if set.
+    pub guard_interval: Option<GuardInterval>,
+    /// What fills the guard interval of the length of the cyclic prefix, the end of the symbol or zeros, see [GuardType].
+    ///
+    /// In [zero padding](GuardType::ZeroPad) mode the samples of the guard interval after the symbol are added
+    /// back onto its head before the FFT.
+    /// Must match [OFDMModulatorConfig::guard_type](crate::ofdm::modulator::OFDMModulatorConfig::guard_type).
+    pub guard_type: GuardType,
+    /// Length of the cyclic suffix after every symbol in samples, which the demodulator skips.
+    ///
+    /// Must match [OFDMModulatorConfig::cyclic_suffix_length](crate::ofdm::modulator::OFDMModulatorConfig::cyclic_suffix_length).
+    pub cyclic_suffix_length: u32,
     /// Interval for pilot subcarriers.
     ///
     /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
     #[default(4)]
     pub pilot_subcarrier_every: u32,
     pub qam_order: QAMOrder,
-    /// Optional FFT implementation/planner to use.
+    /// Optional forward FFT of the FFT length to use, see the [fft](crate::fft) module.
+    ///
+    /// If `None`, an FFT of `realfft` is planned with the [global planner](crate::fft::FftPlannerHandle::global),
+    /// shared with the other modems of the FFT length.
+    pub fft: Option<Arc<dyn RealForwardFft<T>>>,
+    /// Decode each data subcarrier from the change against the previous symbol on the same bin.
+    ///
+    /// Must match the modulator setting. Equalization is skipped in this mode,
+    /// so it only makes sense for whole frames decoded by the [FrameDecoder](crate::frame::FrameDecoder).
+    pub differential_time: bool,
+    /// Produce soft bit decisions (LLRs) instead of hard bits.
+    ///
+    /// The [CodedFrameDecoder](crate::frame::CodedFrameDecoder) then uses soft-decision Viterbi decoding.
+    pub soft_output: bool,
+    /// Roll-off of the modulator, see [OFDMModulatorConfig::roll_off](crate::ofdm::modulator::OFDMModulatorConfig::roll_off).
+    ///
+    /// The symbols themselves are demodulated as usual, frames are expected to be longer by the roll-off.
+    pub roll_off: u32,
+    /// Subcarriers reserved for peak cancellation, which carry no data and are skipped.
+    ///
+    /// Must match [OFDMModulatorConfig::reserved_subcarriers](crate::ofdm::modulator::OFDMModulatorConfig::reserved_subcarriers).
+    pub reserved_subcarriers: Vec<u32>,
+    /// Rotate the data subcarriers of every symbol back after selected mapping, see [SlmConfig].
+    ///
+    /// Must match [OFDMModulatorConfig::slm](crate::ofdm::modulator::OFDMModulatorConfig::slm).
+    pub slm: Option<SlmConfig>,
+    /// Number of signaling bits every symbol carries on the signs of its pilots, which the demodulator recovers
+    /// and takes off the pilots before it estimates the channel, see [PilotSignaling].
+    ///
+    /// Must match [OFDMModulatorConfig::pilot_signaling_bits](crate::ofdm::modulator::OFDMModulatorConfig::pilot_signaling_bits).
+    pub pilot_signaling_bits: u32,
+    /// Interpolation factor of the samples, the FFT grows by it and only uses the lowest subcarriers.
+    ///
+    /// Must match [OFDMModulatorConfig::oversampling](crate::ofdm::modulator::OFDMModulatorConfig::oversampling).
+    #[default(1)]
+    pub oversampling: u32,
+    /// Number of unused subcarriers above DC, which the demodulator ignores.
+    ///
+    /// Must match [OFDMModulatorConfig::guard_subcarriers_low](crate::ofdm::modulator::OFDMModulatorConfig::guard_subcarriers_low).
+    pub guard_subcarriers_low: u32,
+    /// Number of unused subcarriers below the Nyquist frequency, which the demodulator ignores.
+    ///
+    /// Must match [OFDMModulatorConfig::guard_subcarriers_high](crate::ofdm::modulator::OFDMModulatorConfig::guard_subcarriers_high).
+    pub guard_subcarriers_high: u32,
+    /// Expect an empty DC bin, otherwise a pilot.
+    ///
+    /// Must match [OFDMModulatorConfig::null_dc](crate::ofdm::modulator::OFDMModulatorConfig::null_dc).
+    #[default(true)]
+    pub null_dc: bool,
+    /// Subcarriers carrying neither data nor pilots, which the demodulator ignores.
+    ///
+    /// Must match [OFDMModulatorConfig::masked_subcarriers](crate::ofdm::modulator::OFDMModulatorConfig::masked_subcarriers).
+    pub masked_subcarriers: Vec<u32>,
+    /// Linear gains of the data subcarriers, which the demodulator divides out after equalization.
+    ///
+    /// Must match [OFDMModulatorConfig::power_allocation](crate::ofdm::modulator::OFDMModulatorConfig::power_allocation).
+    /// Subcarriers without power are demodulated as the zero point.
+    /// In differential mode the gains are ignored, the reference symbol of the frame absorbs them.
+    pub power_allocation: Option<Vec<f32>>,
+    /// Filter removing the noise outside the band of every frame, before it is demodulated.
+    ///
+    /// Frames keep their length and timing, the group delay of the filter is compensated, see [FirFilter::filter_frame].
+    /// Unlike the other parameters, it does not need to match the modulator,
+    /// but the subcarriers in use must lie within its passband.
+    /// It is applied by the [FrameDecoder](crate::frame::FrameDecoder), not to single symbols.
+    pub rx_filter: Option<FirFilter>,
+    /// Carrier every frame is moved back from before the RX filter, see [Downconverter].
+    ///
+    /// Must match [OFDMModulatorConfig::passband](crate::ofdm::modulator::OFDMModulatorConfig::passband),
+    /// a carrier off by a few Hz is tolerated like a slowly changing channel.
+    /// It is applied by the [FrameDecoder](crate::frame::FrameDecoder), not to single symbols.
+    pub passband: Option<Passband>,
+    /// Undoes the DFT spreading of the points of every symbol after the equalizer.
+    ///
+    /// Must match [OFDMModulatorConfig::dft_spread](crate::ofdm::modulator::OFDMModulatorConfig::dft_spread).
+    pub dft_spread: bool,
+    /// Order in which the points of every symbol are put on the data subcarriers, see [SubcarrierPermutation].
+    ///
+    /// Must match [OFDMModulatorConfig::subcarrier_permutation](crate::ofdm::modulator::OFDMModulatorConfig::subcarrier_permutation).
+    pub subcarrier_permutation: SubcarrierPermutation,
+    /// Number of symbols, from the first, the [reports](DemodulationReport::confidence) keep the confidence
+    /// of every decision of, `None` for none.
+    ///
+    /// The confidences tell the marginal symbols apart, which the EVM of all symbols averages away.
+    /// Unlike the other parameters, it does not need to match the modulator.
+    pub confidence_symbols: Option<usize>,
+    /// Turns the points of every coherent symbol back by the common phase of its pilots, their sum,
+    /// which follows the phase noise of the oscillators from symbol to symbol instead of only the gain.
     ///
-    /// If `None`, a default FFT planner will be used.
-    pub fft: Option<Arc<dyn RealToComplex<f32>>>,
+    /// The pilots of [selected mapping](OFDMDemodulatorConfig::slm) have the signs of the candidate,
+    /// so its symbols are not turned, and differential symbols have no common phase to take out.
+    /// Unlike the other parameters, it does not need to match the modulator.
+    /// QAM-64 needs it at a phase noise QAM-16 tolerates, see `tests/phase_noise.rs`.
+    pub track_common_phase: bool,
 }
@@ -0,0 +1,409 @@
+//! This module provides receive diversity, a frame received on several branches, like two microphones,
+//! combined into one by maximal-ratio combining.
+//!
+//! The [DiversityDemodulator] estimates the channel of every branch on every data subcarrier from the pilots
+//! of each symbol, and adds the points of the branches weighted by their conjugate channels.
+//! A subcarrier in a deep fade on one branch is then carried by the others, and the SNR of the combined points
+//! is the sum of the SNRs of the branches.
+//!
+//! The branches must be time aligned up to a [maximum offset](DiversityDemodulator::new) of a few samples,
+//! which the demodulator measures on every branch from the phase slope of its pilots.
+//!
+//! # Example
+//! ```
+//! use software_modem::channel::{AwgnChannel, Channel, MultipathChannel};
+//! use software_modem::frame::FrameEncoder;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::diversity::DiversityDemodulator;
+//! use software_modem::ofdm::modulator::OFDMModulator;
+//!
+//! let config = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     ..Default::default()
+//! };
+//! let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+//! let payload = b"Heard by two microphones, one of them 3 samples late";
+//! let frame = encoder.encode(payload);
+//!
+//! // the second branch hears the frame 3 samples later, and an echo
+//! let mut first = frame.clone();
+//! let mut second = vec![0.0; 3];
+//! second.extend_from_slice(&frame[..frame.len() - 3]);
+//! MultipathChannel::two_ray(2, -6.0).apply(&mut second);
+//! AwgnChannel::new(25.0, 1).apply(&mut first);
+//! AwgnChannel::new(25.0, 2).apply(&mut second);
+//!
+//! let demodulator = DiversityDemodulator::new((&config).into(), 4);
+//! assert_eq!(demodulator.get_branch_offsets(&[&first, &second]), [0, 3]);
+//! assert_eq!(demodulator.decode(&[&first, &second])[..payload.len()], payload[..]);
+//! ```
+
+use alloc::{vec, vec::Vec};
+
+use num_complex::Complex32;
+
+use crate::ofdm::{
+    GuardType,
+    demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Demodulates frames received on several branches, combining them by maximal-ratio combining,
+/// see the [module](self) documentation.
+///
+/// The frames are the ones of the [FrameDecoder](crate::frame::FrameDecoder) in coherent mode,
+/// every branch goes through the downconverter and the RX filter of the configuration.
+pub struct DiversityDemodulator {
+    demodulator: OFDMDemodulator,
+    max_offset: usize,
+    smoothing_symbols: usize,
+}
+
+impl DiversityDemodulator {
+    /// Creates a demodulator of branches offset by at most `max_offset` samples against the first one.
+    ///
+    /// The FFT window of every branch starts `max_offset` samples early, so the cyclic prefix must cover
+    /// twice the offset and the delay spread of the channels.
+    ///
+    /// # Panics
+    /// If the configuration asks for differential mode, [selected mapping](crate::ofdm::SlmConfig),
+    /// a [power allocation](OFDMDemodulatorConfig::power_allocation), [DFT spreading](OFDMDemodulatorConfig::dft_spread)
+    /// or [zero padding](crate::ofdm::GuardType::ZeroPad), whose guard interval the early FFT windows would miss, twice the offset is not below the cyclic prefix,
+    /// or the configuration is invalid, see [OFDMDemodulator::new].
+    pub fn new(config: OFDMDemodulatorConfig, max_offset: usize) -> Self {
+        if config.differential_time {
+            panic!("Diversity combining needs coherent demodulation, but got differential_time");
+        }
+        if config.slm.is_some() {
+            panic!("Diversity combining does not support selected mapping, but got slm");
+        }
+        if config.power_allocation.is_some() {
+            panic!(
+                "Diversity combining does not support power allocation, but got power_allocation"
+            );
+        }
+        if config.dft_spread {
+            panic!("Diversity combining does not support DFT spreading, but got dft_spread");
+        }
+        if config.guard_type == GuardType::ZeroPad {
+            panic!("Diversity combining needs a cyclic prefix, but got zero padding");
+        }
+
+        let demodulator = OFDMDemodulator::new(config);
+        if 2 * max_offset >= demodulator.get_cyclic_prefix_samples() {
+            panic!(
+                "Branch offset must be below half the cyclic prefix of {} samples, but got {}",
+                demodulator.get_cyclic_prefix_samples(),
+                max_offset
+            );
+        }
+        DiversityDemodulator {
+            demodulator,
+            max_offset,
+            smoothing_symbols: SMOOTHING_SYMBOLS,
+        }
+    }
+
+    /// Sets the number of symbols before and after a symbol whose pilots estimate its channel with its own, 4 by default.
+    ///
+    /// More symbols average out more of the noise of the pilots, fewer follow a channel which fades faster,
+    /// like the one of a transmitter carried around. A number of at least the symbols of the frame estimates
+    /// one static channel from the pilots of the whole frame.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::diversity::DiversityDemodulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 16,
+    ///     ..Default::default()
+    /// };
+    /// let demodulator = DiversityDemodulator::new((&config).into(), 4).with_smoothing_symbols(usize::MAX);
+    /// assert_eq!(demodulator.get_smoothing_symbols(), usize::MAX);
+    /// ```
+    pub fn with_smoothing_symbols(mut self, symbols: usize) -> Self {
+        self.smoothing_symbols = symbols;
+        self
+    }
+
+    /// Returns the number of symbols before and after a symbol whose pilots estimate its channel with its own,
+    /// see [with_smoothing_symbols](Self::with_smoothing_symbols).
+    pub fn get_smoothing_symbols(&self) -> usize {
+        self.smoothing_symbols
+    }
+
+    /// Returns the offset of every branch against the first one in samples, positive if it is late,
+    /// measured from the phase slope of its pilots over the frame and limited to the maximum offset.
+    ///
+    /// # Panics
+    /// If there are no branches, they differ in length, or their length is not a multiple of the symbol length,
+    /// plus the roll-off.
+    pub fn get_branch_offsets(&self, branches: &[&[f32]]) -> Vec<isize> {
+        let prepared = self.prepare(branches);
+        self.offsets(&prepared)
+    }
+
+    /// Demodulates the frame of the branches into the payload, with the zero padding of the last symbol.
+    ///
+    /// # Panics
+    /// As [get_branch_offsets](Self::get_branch_offsets).
+    pub fn decode(&self, branches: &[&[f32]]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.for_each_symbol(branches, |points, _| {
+            payload.extend(self.demodulator.qam_modem().demodulate(points))
+        });
+        payload
+    }
+
+    /// Demodulates the frame of the branches into soft bit decisions of the payload.
+    ///
+    /// The LLRs of every point are weighted by the combined power of the channels of its subcarrier
+    /// against the mean of the symbol, so the bits of faded subcarriers count less.
+    /// See [QAMModem::demodulate_soft](crate::qam::QAMModem::demodulate_soft).
+    ///
+    /// # Panics
+    /// As [get_branch_offsets](Self::get_branch_offsets).
+    pub fn decode_soft(&self, branches: &[&[f32]]) -> Vec<f32> {
+        let mut llrs = Vec::new();
+        self.for_each_symbol(branches, |points, gains| {
+            let mean_gain = gains.iter().sum::<f32>() / gains.len().max(1) as f32;
+            let bits = self.demodulator.qam_modem().bits_per_symbol() as usize;
+            let symbol_llrs = self.demodulator.qam_modem().demodulate_soft(points);
+            for (llrs_of_point, &gain) in symbol_llrs.chunks_exact(bits).zip(gains) {
+                let weight = if mean_gain > 0.0 {
+                    gain / mean_gain
+                } else {
+                    0.0
+                };
+                llrs.extend(llrs_of_point.iter().map(|llr| llr * weight));
+            }
+        });
+        llrs
+    }
+
+    /// Returns the combined points of every payload symbol of the frame of the branches.
+    ///
+    /// # Panics
+    /// As [get_branch_offsets](Self::get_branch_offsets).
+    pub fn get_constellation(&self, branches: &[&[f32]]) -> Vec<Vec<Complex32>> {
+        let mut constellation = Vec::new();
+        self.for_each_symbol(branches, |points, _| constellation.push(points.to_vec()));
+        constellation
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        self.demodulator.get_bytes_per_symbol()
+    }
+
+    /// Returns the number of samples of one OFDM symbol.
+    pub fn get_symbol_length(&self) -> usize {
+        self.demodulator.get_symbol_length()
+    }
+
+    /// Returns the payload symbols of every branch, downconverted and filtered if the demodulator is configured to.
+    fn prepare(&self, branches: &[&[f32]]) -> Vec<Vec<f32>> {
+        let Some(first) = branches.first() else {
+            panic!("Diversity combining needs a branch, but got none");
+        };
+        if let Some(branch) = branches.iter().find(|branch| branch.len() != first.len()) {
+            panic!(
+                "Branches must have the same length, but got {} and {}",
+                first.len(),
+                branch.len()
+            );
+        }
+        branches
+            .iter()
+            .map(|branch| self.demodulator.prepare(branch).into_owned())
+            .collect()
+    }
+
+    /// Returns the offsets of the prepared branches against the first one.
+    fn offsets(&self, prepared: &[Vec<f32>]) -> Vec<isize> {
+        let mut scratch = self.demodulator.make_scratch();
+        let delays: Vec<f32> = prepared
+            .iter()
+            .map(|branch| {
+                let (sum, count) = branch
+                    .chunks_exact(self.get_symbol_length())
+                    .filter_map(|symbol| {
+                        self.demodulator.demodulate_points(symbol, &mut scratch);
+                        self.demodulator.measure_pilots(&scratch)
+                    })
+                    .fold((0.0, 0), |(sum, count), phase| {
+                        (sum + phase.get_delay(), count + 1)
+                    });
+                if count > 0 { sum / count as f32 } else { 0.0 }
+            })
+            .collect();
+
+        let max_offset = self.max_offset as isize;
+        delays
+            .iter()
+            .map(|delay| ((delay - delays[0]).round() as isize).clamp(-max_offset, max_offset))
+            .collect()
+    }
+
+    /// Calls `process` with the combined points of every payload symbol and the combined power of their channels.
+    fn for_each_symbol(&self, branches: &[&[f32]], mut process: impl FnMut(&[Complex32], &[f32])) {
+        let prepared = self.prepare(branches);
+        let offsets = self.offsets(&prepared);
+        let estimates: Vec<BranchEstimate> = prepared
+            .iter()
+            .zip(&offsets)
+            .map(|(branch, &offset)| self.estimate_branch(branch, offset))
+            .collect();
+
+        let data_indices = self.demodulator.data_subcarrier_indices();
+        let pilot_indices = self.demodulator.pilot_subcarrier_indices();
+        let mut flat = vec![Complex32::default(); pilot_indices.len()];
+        let mut channel = vec![Complex32::default(); data_indices.len()];
+        let mut numerators = vec![Complex32::default(); data_indices.len()];
+        let mut gains = vec![0.0; data_indices.len()];
+        let mut points = vec![Complex32::default(); data_indices.len()];
+
+        let num_symbols = prepared[0].len() / self.get_symbol_length();
+        for symbol in 0..num_symbols {
+            numerators.fill(Complex32::default());
+            gains.fill(0.0);
+            // the pilots of the symbols around, which the channel changes little over
+            let window = symbol.saturating_sub(self.smoothing_symbols)
+                ..symbol
+                    .saturating_add(self.smoothing_symbols)
+                    .saturating_add(1)
+                    .min(num_symbols);
+            for estimate in &estimates {
+                flat.fill(Complex32::default());
+                for pilots in &estimate.flat_pilots[window.clone()] {
+                    for (sum, pilot) in flat.iter_mut().zip(pilots) {
+                        *sum += pilot / window.len() as f32;
+                    }
+                }
+                interpolate_channel(
+                    pilot_indices,
+                    &flat,
+                    estimate.delay,
+                    data_indices,
+                    &mut channel,
+                );
+
+                let bins = &estimate.data_bins[symbol];
+                for (((numerator, gain), estimate), bin) in numerators
+                    .iter_mut()
+                    .zip(gains.iter_mut())
+                    .zip(&channel)
+                    .zip(bins)
+                {
+                    *numerator += estimate.conj() * bin;
+                    *gain += estimate.norm_sqr();
+                }
+            }
+
+            for ((point, numerator), &gain) in points.iter_mut().zip(&numerators).zip(&gains) {
+                *point = if gain > 0.0 {
+                    numerator / gain
+                } else {
+                    Complex32::default()
+                };
+            }
+            process(&points, &gains);
+        }
+    }
+
+    /// Transforms every symbol of a prepared branch, `offset` samples late against the first one,
+    /// with the FFT window the maximum offset early.
+    fn estimate_branch(&self, branch: &[f32], offset: isize) -> BranchEstimate {
+        let delay = (self.max_offset as isize - offset) as usize;
+        let mut aligned = vec![0.0; branch.len()];
+        aligned[delay..].copy_from_slice(&branch[..branch.len() - delay]);
+
+        let fft_length = self.demodulator.get_fft().fft_length() as f32;
+        let data_indices = self.demodulator.data_subcarrier_indices();
+        let pilot_indices = self.demodulator.pilot_subcarrier_indices();
+        let mut scratch = self.demodulator.make_scratch();
+        let mut estimate = BranchEstimate::default();
+        let (mut delay_sum, mut delay_count) = (0.0, 0);
+        for symbol in aligned.chunks_exact(self.get_symbol_length()) {
+            self.demodulator.demodulate_points(symbol, &mut scratch);
+            if let Some(phase) = self.demodulator.measure_pilots(&scratch) {
+                delay_sum += phase.get_delay();
+                delay_count += 1;
+            }
+            let (bins, pilots) = self.demodulator.get_bins(&scratch);
+            estimate.flat_pilots.push(pilots);
+            estimate
+                .data_bins
+                .push(data_indices.iter().map(|&idx| bins[idx as usize]).collect());
+        }
+
+        // the delay of the single symbols is too noisy to take out, the one of the frame is not
+        if delay_count > 0 {
+            estimate.delay = delay_sum / delay_count as f32 / fft_length;
+        }
+        for pilots in &mut estimate.flat_pilots {
+            for (pilot, &idx) in pilots.iter_mut().zip(pilot_indices) {
+                *pilot *= delay_slope(idx, estimate.delay);
+            }
+        }
+        estimate
+    }
+}
+
+/// The number of symbols before and after a symbol whose pilots estimate its channel with its own, by default.
+///
+/// The pilots carry less power than the data points, so the pilots of a single symbol estimate the channel
+/// of every subcarrier with more noise than its point has.
+/// Channels fading in a few symbols are not followed.
+const SMOOTHING_SYMBOLS: usize = 4;
+
+/// The symbols of a branch transformed, with their timing.
+#[derive(Default)]
+struct BranchEstimate {
+    /// The channel on the pilots of every symbol, with the phase slope of the delay taken out.
+    flat_pilots: Vec<Vec<Complex32>>,
+    /// The bins of the data subcarriers of every symbol.
+    data_bins: Vec<Vec<Complex32>>,
+    /// The mean delay of the symbols against the FFT window, a fraction of the FFT length.
+    delay: f32,
+}
+
+/// Returns the turn of subcarrier `idx` undoing a delay of a fraction of the FFT length.
+fn delay_slope(idx: u32, delay: f32) -> Complex32 {
+    Complex32::from_polar(1.0, core::f32::consts::TAU * idx as f32 * delay)
+}
+
+/// Estimates the channel on the data subcarriers by interpolating the channel on the pilots linearly,
+/// with the phase slope of the delay taken out, and putting the slope of a delay, a fraction of the FFT length, back.
+/// Beyond the outermost pilots the channel is the one of the nearest pilot.
+fn interpolate_channel(
+    pilot_indices: &[u32],
+    flat: &[Complex32],
+    delay: f32,
+    data_indices: &[u32],
+    channel: &mut [Complex32],
+) {
+    if flat.is_empty() {
+        channel.fill(Complex32::default());
+        return;
+    }
+
+    for (estimate, &idx) in channel.iter_mut().zip(data_indices) {
+        let above = pilot_indices.partition_point(|&pilot| pilot < idx);
+        let flat_estimate = if above == 0 {
+            flat[0]
+        } else if above == flat.len() {
+            flat[flat.len() - 1]
+        } else {
+            let (low, high) = (pilot_indices[above - 1], pilot_indices[above]);
+            let fraction = (idx - low) as f32 / (high - low) as f32;
+            flat[above - 1] * (1.0 - fraction) + flat[above] * fraction
+        };
+        *estimate = flat_estimate * delay_slope(idx, delay).conj();
+    }
+}
@@ -0,0 +1,326 @@
+//! This module provides a demodulator for deeply embedded targets, whose sizes are fixed at compile time
+//! and whose buffers live in the demodulator itself, so it never touches the heap.
+//!
+//! The [HeaplessOFDMDemodulator] takes the number of subcarriers and the length of the cyclic prefix as const generics,
+//! and keeps the samples and bins of a symbol in arrays it owns, with no `Arc` and no `Vec`.
+//! Its FFT is any [RealForwardFft] which needs no scratch space, by default the [HeaplessRealFft] of this module,
+//! a radix-2 FFT whose twiddle factors are an array too. Only the twiddle factors are computed at construction,
+//! and demodulating a symbol neither allocates nor panics, whatever the samples are.
+//!
+//! It supports a subset of the [OFDMDemodulator](crate::ofdm::demodulator::OFDMDemodulator): hard decisions of QAM-16,
+//! a comb of pilots at the multiples of an interval, no guard subcarriers and an empty DC bin,
+//! the layout of an [OFDMConfig](crate::ofdm::OFDMConfig) with only these fields set.
+//! Unlike the coherent equalizer of the float demodulator, which divides every point by the mean pilot magnitude,
+//! it equalizes every data subcarrier by zero forcing, dividing it by the channel interpolated between its pilots,
+//! so it also undoes the phase of an echo within the cyclic prefix.
+//!
+//! The module itself only uses `core` and the [math](crate::math) of the crate, and the `embedded` crate of the workspace,
+//! a receiver built on it, is `#![no_std]` and uses the crate without its `std` feature.
+//!
+//! # Example
+//! ```
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::embedded::HeaplessOFDMDemodulator;
+//! use software_modem::ofdm::modulator::OFDMModulator;
+//!
+//! let modulator = OFDMModulator::new((&OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     pilot_subcarrier_every: 4,
+//!     ..Default::default()
+//! }).into());
+//! let data: Vec<u8> = (0..24u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+//! let symbol = modulator.modulate_symbol(&data).unwrap();
+//!
+//! let mut demodulator = HeaplessOFDMDemodulator::<64, 16>::new(4);
+//! assert_eq!(demodulator.get_symbol_length(), 144);
+//! let mut output = [0; 24];
+//! demodulator.try_demodulate_symbol_into(&symbol, &mut output).unwrap();
+//! assert_eq!(output[..], data);
+//! ```
+
+use num_complex::Complex32;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+use crate::{error::ModemError, fft::RealForwardFft, ofdm::check_length};
+
+/// A real forward FFT of `2 * N` samples into `N + 1` bins, which keeps its twiddle factors in an array
+/// and needs no scratch space.
+///
+/// The samples are packed in pairs into `N` complex values, transformed by an in-place radix-2 FFT of length `N`
+/// in the output, and split into the bins of the real samples. Like the other FFTs of the crate it is not normalized.
+///
+/// # Panics
+/// The transform panics if the input does not have `2 * N` samples or the output `N + 1` bins.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::fft::{RealForwardFft, plan_real_forward};
+/// use software_modem::ofdm::embedded::HeaplessRealFft;
+///
+/// let fft = HeaplessRealFft::<64>::new();
+/// assert_eq!((fft.fft_length(), fft.get_scratch_len()), (128, 0));
+///
+/// let samples: Vec<f32> = (0..128).map(|n| ((n * n) % 17) as f32 - 8.0).collect();
+/// let mut bins = [Complex32::default(); 65];
+/// fft.process_with_scratch(&mut samples.clone(), &mut bins, &mut []);
+///
+/// let mut expected = vec![Complex32::default(); 65];
+/// plan_real_forward(128).process(&mut samples.clone(), &mut expected);
+/// for (bin, expected) in bins.iter().zip(&expected) {
+///     assert!((bin - expected).norm() < 1e-3, "{bin} vs {expected}");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeaplessRealFft<const N: usize> {
+    /// `e^(-iπk/N)` for every `k` below `N`, the twiddles of the FFT of length `N` at the even `k`.
+    twiddles: [Complex32; N],
+}
+
+impl<const N: usize> HeaplessRealFft<N> {
+    /// Creates a new FFT of `2 * N` samples.
+    ///
+    /// # Panics
+    /// If `N` is not a power of two of at least 2.
+    pub fn new() -> Self {
+        if N < 2 || !N.is_power_of_two() {
+            panic!(
+                "Half the FFT length must be a power of two of at least 2, but got {}",
+                N
+            );
+        }
+        let twiddles = core::array::from_fn(|k| {
+            let phase = -core::f64::consts::PI * k as f64 / N as f64;
+            Complex32::new(phase.cos() as f32, phase.sin() as f32)
+        });
+        HeaplessRealFft { twiddles }
+    }
+
+    /// Transforms the values in place with a radix-2 decimation in time.
+    fn transform(&self, values: &mut [Complex32]) {
+        let shift = usize::BITS - N.trailing_zeros();
+        for i in 0..N {
+            let j = i.reverse_bits() >> shift;
+            if i < j {
+                values.swap(i, j);
+            }
+        }
+
+        let mut half = 1;
+        while half < N {
+            let stride = N / half;
+            for start in (0..N).step_by(2 * half) {
+                for k in 0..half {
+                    let a = values[start + k];
+                    let b = values[start + k + half] * self.twiddles[k * stride];
+                    values[start + k] = a + b;
+                    values[start + k + half] = a - b;
+                }
+            }
+            half *= 2;
+        }
+    }
+}
+
+impl<const N: usize> Default for HeaplessRealFft<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RealForwardFft<f32> for HeaplessRealFft<N> {
+    fn fft_length(&self) -> usize {
+        2 * N
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [f32],
+        output: &mut [Complex32],
+        _scratch: &mut [Complex32],
+    ) {
+        if input.len() != 2 * N || output.len() != N + 1 {
+            panic!(
+                "FFT buffers must be {} samples and {} bins, but got {} and {}",
+                2 * N,
+                N + 1,
+                input.len(),
+                output.len()
+            );
+        }
+        for (value, pair) in output.iter_mut().zip(input.chunks_exact(2)) {
+            *value = Complex32::new(pair[0], pair[1]);
+        }
+        self.transform(&mut output[..N]);
+
+        // the even samples are the real parts of the values and the odd ones their imaginary parts,
+        // the bins of both are the conjugate symmetric and antisymmetric parts of the transform
+        let packed = output[0];
+        output[0] = Complex32::new(packed.re + packed.im, 0.0);
+        output[N] = Complex32::new(packed.re - packed.im, 0.0);
+        for k in 1..=N / 2 {
+            let (low, high) = (output[k], output[N - k]);
+            let even = (low + high.conj()) * 0.5;
+            let odd = (low - high.conj()) * Complex32::new(0.0, -0.5);
+            output[k] = even + self.twiddles[k] * odd;
+            output[N - k] = even.conj() + self.twiddles[N - k] * odd.conj();
+        }
+    }
+}
+
+/// The `N + 1` bins of a symbol, from DC to the Nyquist frequency, one after the other.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct Bins<const N: usize> {
+    below_nyquist: [Complex32; N],
+    nyquist: Complex32,
+}
+
+impl<const N: usize> Bins<N> {
+    fn as_mut_slice(&mut self) -> &mut [Complex32] {
+        // SAFETY: with repr(C) the value follows the array without padding, as both have the alignment of an f32
+        // and the size of the array is a multiple of it, so the struct holds N + 1 values one after the other
+        unsafe { core::slice::from_raw_parts_mut((self as *mut Self).cast::<Complex32>(), N + 1) }
+    }
+}
+
+/// Demodulates symbols of `N_SC` subcarriers and a cyclic prefix of `CP` samples without touching the heap,
+/// see the [module](self) documentation.
+///
+/// The FFT has the length `2 * N_SC`, DC is left empty and the pilots lie at the multiples of the pilot interval
+/// from it, the data subcarriers are the others, as many as carry whole bytes.
+pub struct HeaplessOFDMDemodulator<const N_SC: usize, const CP: usize, F = HeaplessRealFft<N_SC>> {
+    fft: F,
+    pilot_subcarrier_every: usize,
+    num_data_subcarriers: usize,
+    /// The samples of the symbol after the cyclic prefix, in pairs, which the FFT overwrites.
+    samples: [[f32; 2]; N_SC],
+    bins: Bins<N_SC>,
+}
+
+impl<const N_SC: usize, const CP: usize> HeaplessOFDMDemodulator<N_SC, CP> {
+    /// Creates a new demodulator with the [HeaplessRealFft] and pilots every `pilot_subcarrier_every` subcarriers.
+    ///
+    /// # Panics
+    /// If `N_SC` is not a power of two of at least 2, see [HeaplessRealFft::new], or see [with_fft](Self::with_fft).
+    pub fn new(pilot_subcarrier_every: usize) -> Self {
+        Self::with_fft(HeaplessRealFft::new(), pilot_subcarrier_every)
+    }
+}
+
+impl<const N_SC: usize, const CP: usize, F: RealForwardFft<f32>>
+    HeaplessOFDMDemodulator<N_SC, CP, F>
+{
+    /// Creates a new demodulator with the FFT and pilots every `pilot_subcarrier_every` subcarriers.
+    ///
+    /// # Panics
+    /// If the FFT does not have the length `2 * N_SC` or needs scratch space,
+    /// or if the pilot interval is not at least 2 and below `N_SC`, which leaves no pilot or no data.
+    pub fn with_fft(fft: F, pilot_subcarrier_every: usize) -> Self {
+        if fft.fft_length() != 2 * N_SC {
+            panic!(
+                "FFT length must be {}, but got {}",
+                2 * N_SC,
+                fft.fft_length()
+            );
+        }
+        if fft.get_scratch_len() > 0 {
+            panic!(
+                "FFT must need no scratch space, but got {}",
+                fft.get_scratch_len()
+            );
+        }
+        if !(2..N_SC).contains(&pilot_subcarrier_every) {
+            panic!(
+                "Pilot interval must be at least 2 and below {}, but got {}",
+                N_SC, pilot_subcarrier_every
+            );
+        }
+
+        // a point of QAM-16 is half a byte, a symbol carries an even number of them
+        let used = N_SC - 1;
+        let num_data_subcarriers = (used - used / pilot_subcarrier_every) / 2 * 2;
+        HeaplessOFDMDemodulator {
+            fft,
+            pilot_subcarrier_every,
+            num_data_subcarriers,
+            samples: [[0.0; 2]; N_SC],
+            bins: Bins {
+                below_nyquist: [Complex32::default(); N_SC],
+                nyquist: Complex32::default(),
+            },
+        }
+    }
+
+    /// Returns the number of samples of a symbol, including the cyclic prefix.
+    pub fn get_symbol_length(&self) -> usize {
+        2 * N_SC + CP
+    }
+
+    /// Returns the number of bytes of a symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        self.num_data_subcarriers / 2
+    }
+
+    /// Returns the data subcarriers in the order of the points, from the lowest.
+    pub fn get_data_subcarriers(&self) -> impl Iterator<Item = usize> + '_ {
+        (1..N_SC)
+            .filter(|idx| !idx.is_multiple_of(self.pilot_subcarrier_every))
+            .take(self.num_data_subcarriers)
+    }
+
+    /// Demodulates a single symbol into the output, without allocating.
+    ///
+    /// The cyclic prefix is skipped, and every data subcarrier is divided by the channel at its pilots,
+    /// linearly interpolated between the two around it, or the nearest one beyond the outermost pilots.
+    /// Points are sliced like [QAMModem::demodulate](crate::qam::QAMModem::demodulate), so on a flat channel
+    /// the data is the one of the [OFDMDemodulator](crate::ofdm::demodulator::OFDMDemodulator).
+    /// Silence, noise, infinities or NaN all decide some data.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the input does not have the symbol length or the output the bytes per symbol.
+    pub fn try_demodulate_symbol_into(
+        &mut self,
+        input: &[f32],
+        output: &mut [u8],
+    ) -> Result<(), ModemError> {
+        check_length(self.get_symbol_length(), input.len())?;
+        check_length(self.get_bytes_per_symbol(), output.len())?;
+
+        let samples = self.samples.as_flattened_mut();
+        samples.copy_from_slice(&input[CP..]);
+        self.fft
+            .process_with_scratch(samples, self.bins.as_mut_slice(), &mut []);
+
+        let bins = &self.bins.below_nyquist;
+        let every = self.pilot_subcarrier_every;
+        let channel = |idx: usize| {
+            let below = idx - idx % every;
+            let above = below + every;
+            match (below > 0, above < N_SC) {
+                (true, true) => {
+                    let fraction = (idx - below) as f32 / every as f32;
+                    bins[below] + (bins[above] - bins[below]) * fraction
+                }
+                (true, false) => bins[below],
+                _ => bins[above],
+            }
+        };
+
+        // the levels are 1 and 3 times the pilot, the thresholds lie at 0 and twice the pilot
+        let mut nibbles = self.get_data_subcarriers().map(|idx| {
+            let point = bins[idx] / channel(idx);
+            (u8::from(point.re < 0.0) << 3)
+                | (u8::from(point.im < 0.0) << 2)
+                | (u8::from(point.re.abs() > 2.0) << 1)
+                | u8::from(point.im.abs() > 2.0)
+        });
+        for byte in output.iter_mut() {
+            let high = nibbles.next().unwrap_or(0);
+            *byte = (high << 4) | nibbles.next().unwrap_or(0);
+        }
+        Ok(())
+    }
+}
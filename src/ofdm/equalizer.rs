@@ -0,0 +1,174 @@
+//! This module provides the per-subcarrier kernels of the equalization.
+//!
+//! The [demodulator](super::demodulator) divides the bins by the gain the pilots estimate with [scale],
+//! divides out the [allocated power](super::demodulator::OFDMDemodulatorConfig::power_allocation) with [unscale],
+//! and turns the phases of [selected mapping](super::SlmConfig) back with [derotate].
+//!
+//! The functions here are the scalar kernels, the demodulator calls them through [Sample],
+//! which with the `portable-simd` feature runs them on the vectors of `core::simd` for `f32` and `f64`.
+//! The feature needs a nightly compiler. Both paths do the same operations in the same order,
+//! so their results agree to the last bit.
+
+use num_complex::Complex;
+
+use crate::samples::Sample;
+
+/// Multiplies every point by a common factor.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::ofdm::equalizer::scale;
+///
+/// let mut points = [Complex32::new(1.0, -2.0), Complex32::new(0.5, 4.0)];
+/// scale(&mut points, 0.5);
+/// assert_eq!(points, [Complex32::new(0.5, -1.0), Complex32::new(0.25, 2.0)]);
+/// ```
+pub fn scale<T: Sample>(points: &mut [Complex<T>], factor: T) {
+    for point in points {
+        *point = point.scale(factor);
+    }
+}
+
+/// Divides every point by its gain, and zeroes the points of gains which are not positive.
+///
+/// # Panics
+/// If there are not as many gains as points.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::ofdm::equalizer::unscale;
+///
+/// let mut points = [Complex32::new(1.0, -2.0), Complex32::new(0.5, 4.0)];
+/// unscale(&mut points, &[2.0, 0.0]);
+/// assert_eq!(points, [Complex32::new(0.5, -1.0), Complex32::new(0.0, 0.0)]);
+/// ```
+pub fn unscale<T: Sample>(points: &mut [Complex<T>], gains: &[T]) {
+    check_coefficients(points.len(), gains.len());
+    for (point, &gain) in points.iter_mut().zip(gains) {
+        *point = if gain > T::zero() {
+            point.unscale(gain)
+        } else {
+            Complex::default()
+        };
+    }
+}
+
+/// Multiplies every point by the conjugate of its phase, which turns a rotation by the phase back.
+///
+/// # Panics
+/// If there are not as many phases as points.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::ofdm::equalizer::derotate;
+///
+/// let mut points = [Complex32::new(0.0, 3.0), Complex32::new(-1.0, 1.0)];
+/// derotate(&mut points, &[Complex32::new(0.0, 1.0), Complex32::new(-1.0, 0.0)]);
+/// assert_eq!(points, [Complex32::new(3.0, 0.0), Complex32::new(1.0, -1.0)]);
+/// ```
+pub fn derotate<T: Sample>(points: &mut [Complex<T>], phases: &[Complex<T>]) {
+    check_coefficients(points.len(), phases.len());
+    for (point, phase) in points.iter_mut().zip(phases) {
+        *point *= phase.conj();
+    }
+}
+
+fn check_coefficients(points: usize, coefficients: usize) {
+    if points != coefficients {
+        panic!(
+            "Coefficients must be one per point, {}, but got {}",
+            points, coefficients
+        );
+    }
+}
+
+/// The kernels on the vectors of `core::simd`, for the [Sample] implementations of `f32` and `f64`.
+///
+/// The points are loaded as interleaved lanes and split into vectors of their real and imaginary parts,
+/// the points after the last whole vector go through the scalar kernels.
+#[cfg(feature = "portable-simd")]
+pub(crate) mod simd {
+    use core::simd::{Select, Simd, cmp::SimdPartialOrd};
+
+    use num_complex::Complex;
+
+    /// Returns the points as their interleaved parts.
+    macro_rules! parts {
+        ($points:expr, $float:ty) => {
+            // SAFETY: Complex is repr(C) of two floats, so the points are twice as many floats
+            unsafe {
+                core::slice::from_raw_parts_mut(
+                    $points.as_mut_ptr().cast::<$float>(),
+                    2 * $points.len(),
+                )
+            }
+        };
+    }
+
+    macro_rules! kernels {
+        ($float:ty, $lanes:literal, $scale:ident, $unscale:ident, $derotate:ident) => {
+            pub(crate) fn $scale(points: &mut [Complex<$float>], factor: $float) {
+                let done = points.len() / $lanes * $lanes;
+                let factor = Simd::<$float, $lanes>::splat(factor);
+                for block in parts!(points[..done], $float).chunks_exact_mut(2 * $lanes) {
+                    let (re, im) = Simd::<$float, $lanes>::from_slice(&block[..$lanes])
+                        .deinterleave(Simd::from_slice(&block[$lanes..]));
+                    let (low, high) = (re * factor).interleave(im * factor);
+                    low.copy_to_slice(&mut block[..$lanes]);
+                    high.copy_to_slice(&mut block[$lanes..]);
+                }
+                super::scale(&mut points[done..], factor[0]);
+            }
+
+            pub(crate) fn $unscale(points: &mut [Complex<$float>], gains: &[$float]) {
+                super::check_coefficients(points.len(), gains.len());
+                let done = points.len() / $lanes * $lanes;
+                let zero = Simd::<$float, $lanes>::splat(0.0);
+                for (block, gains) in parts!(points[..done], $float)
+                    .chunks_exact_mut(2 * $lanes)
+                    .zip(gains.chunks_exact($lanes))
+                {
+                    let (re, im) = Simd::<$float, $lanes>::from_slice(&block[..$lanes])
+                        .deinterleave(Simd::from_slice(&block[$lanes..]));
+                    let gains = Simd::from_slice(gains);
+                    let positive = gains.simd_gt(zero);
+                    let (low, high) = positive
+                        .select(re / gains, zero)
+                        .interleave(positive.select(im / gains, zero));
+                    low.copy_to_slice(&mut block[..$lanes]);
+                    high.copy_to_slice(&mut block[$lanes..]);
+                }
+                super::unscale(&mut points[done..], &gains[done..]);
+            }
+
+            pub(crate) fn $derotate(points: &mut [Complex<$float>], phases: &[Complex<$float>]) {
+                super::check_coefficients(points.len(), phases.len());
+                let done = points.len() / $lanes * $lanes;
+                // SAFETY: as for the points, and there are as many phases as points
+                let phase_parts = unsafe {
+                    core::slice::from_raw_parts(phases.as_ptr().cast::<$float>(), 2 * done)
+                };
+                for (block, phases) in parts!(points[..done], $float)
+                    .chunks_exact_mut(2 * $lanes)
+                    .zip(phase_parts.chunks_exact(2 * $lanes))
+                {
+                    let (a, b) = Simd::<$float, $lanes>::from_slice(&block[..$lanes])
+                        .deinterleave(Simd::from_slice(&block[$lanes..]));
+                    let (c, d) = Simd::<$float, $lanes>::from_slice(&phases[..$lanes])
+                        .deinterleave(Simd::from_slice(&phases[$lanes..]));
+                    // the product of num-complex with the conjugate, (a + bi)(c - di)
+                    let (low, high) = (a * c - b * -d).interleave(b * c + a * -d);
+                    low.copy_to_slice(&mut block[..$lanes]);
+                    high.copy_to_slice(&mut block[$lanes..]);
+                }
+                super::derotate(&mut points[done..], &phases[done..]);
+            }
+        };
+    }
+
+    kernels!(f32, 8, scale_f32, unscale_f32, derotate_f32);
+    kernels!(f64, 4, scale_f64, unscale_f64, derotate_f64);
+}
@@ -0,0 +1,368 @@
+//! This module provides a fixed-point demodulator, which only computes with integers, for processors without an FPU.
+//!
+//! The [FixedOFDMDemodulator] takes `i16` samples in Q15, like the PCM samples of
+//! [OFDMModulator::modulate_bytes_i16](crate::ofdm::modulator::OFDMModulator::modulate_bytes_i16),
+//! and demodulates them like the float [OFDMDemodulator](crate::ofdm::demodulator::OFDMDemodulator).
+
+use alloc::{vec, vec::Vec};
+
+use num_complex::Complex;
+
+use crate::{
+    ofdm::{OFDMConstants, SubcarrierLayout, demodulator::OFDMDemodulatorConfig, pilot_magnitude},
+    qam::{axis_label, pack, point_index},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Demodulates symbols of `i16` samples in Q15 fixed point.
+///
+/// The cyclic prefix is removed, a radix-2 FFT in Q15 transforms the symbol, see [transform](FixedOFDMDemodulator::transform),
+/// the decision thresholds are scaled by the mean pilot magnitude instead of dividing every point by it,
+/// and the points are sliced by integer comparisons.
+/// Only the twiddle factors are computed in floating point, once in [new](FixedOFDMDemodulator::new).
+///
+/// On clean signals, the decisions match the float demodulator bit for bit.
+/// The rounding of the FFT adds noise about 1 LSB per bin, so on noisy signals
+/// the decisions only differ for points that lie within a few LSB of a threshold,
+/// which are less than 0.5% of the bits even on a link with a few percent of bit errors,
+/// see [demodulate_symbols](FixedOFDMDemodulator::demodulate_symbols).
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::fixed::FixedOFDMDemodulator;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// let config = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// };
+/// let modulator = OFDMModulator::new((&config).into());
+/// let demodulator = OFDMDemodulator::new((&config).into());
+/// let fixed = FixedOFDMDemodulator::new((&config).into());
+///
+/// let data: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+///
+/// // from the worst-case peak at full scale to 20 dB below it
+/// let full_scale = 10f32.powf(-modulator.get_peak_level_db() / 20.0);
+/// for gain in [full_scale, full_scale / 10.0] {
+///     let pcm = modulator.modulate_bytes_i16(&data, gain);
+///     let decoded = fixed.demodulate_symbols(&pcm);
+///     assert_eq!(decoded, demodulator.demodulate_symbols_i16(&pcm));
+///     assert_eq!(decoded, data);
+/// }
+///
+/// // 12 dB hotter, the peaks saturate at full scale, and both paths see the same clipped symbols
+/// let pcm = modulator.modulate_bytes_i16(&data, full_scale * 4.0);
+/// assert!(pcm.iter().any(|&sample| sample == i16::MAX || sample == i16::MIN));
+/// assert_eq!(fixed.demodulate_symbols(&pcm), demodulator.demodulate_symbols_i16(&pcm));
+/// ```
+pub struct FixedOFDMDemodulator {
+    constants: OFDMConstants,
+    /// `e^(-2πik/N)` in Q15 for the first half of the FFT length.
+    twiddles: Vec<Complex<i32>>,
+}
+
+impl FixedOFDMDemodulator {
+    /// Creates a new fixed-point demodulator with the given [configuration](OFDMDemodulatorConfig).
+    ///
+    /// The demodulator only makes hard decisions, [soft_output](OFDMDemodulatorConfig::soft_output) is ignored.
+    /// So are the [fft](OFDMDemodulatorConfig::fft) of the configuration, the [roll-off](OFDMDemodulatorConfig::roll_off)
+    /// the [rx_filter](OFDMDemodulatorConfig::rx_filter) and the [passband](OFDMDemodulatorConfig::passband), which only apply to frames.
+    ///
+    /// # Panics
+    /// If the FFT length, `2 * num_subcarriers` times the oversampling, is not a power of two,
+    /// if the configuration asks for differential demodulation, [selected mapping](crate::ofdm::SlmConfig),
+    /// [pilot signaling](OFDMDemodulatorConfig::pilot_signaling_bits),
+    /// a [power allocation](OFDMDemodulatorConfig::power_allocation) or [DFT spreading](OFDMDemodulatorConfig::dft_spread),
+    /// which are only supported by the float demodulator,
+    /// or if the subcarriers are invalid, see [OFDMDemodulator::new](crate::ofdm::demodulator::OFDMDemodulator::new).
+    pub fn new(config: OFDMDemodulatorConfig) -> Self {
+        if config.differential_time {
+            panic!(
+                "Fixed-point demodulation needs coherent demodulation, but got differential_time"
+            );
+        }
+        if config.slm.is_some() {
+            panic!("Fixed-point demodulation does not support selected mapping, but got slm");
+        }
+        if config.pilot_signaling_bits > 0 {
+            panic!(
+                "Fixed-point demodulation does not support pilot signaling, but got pilot_signaling_bits"
+            );
+        }
+        if config.power_allocation.is_some() {
+            panic!(
+                "Fixed-point demodulation does not support power allocation, but got power_allocation"
+            );
+        }
+        if config.dft_spread {
+            panic!("Fixed-point demodulation does not support DFT spreading, but got dft_spread");
+        }
+
+        let cyclic_prefix_length = crate::ofdm::resolve_cyclic_prefix_length(
+            config.guard_interval,
+            config.cyclic_prefix_length,
+            2 * config.num_subcarriers,
+        );
+        let constants = OFDMConstants::new(
+            config.num_subcarriers,
+            cyclic_prefix_length,
+            config.qam_order,
+            config.oversampling,
+            SubcarrierLayout {
+                pilot_subcarrier_every: config.pilot_subcarrier_every,
+                guard_subcarriers_low: config.guard_subcarriers_low,
+                guard_subcarriers_high: config.guard_subcarriers_high,
+                null_dc: config.null_dc,
+                reserved_subcarriers: &config.reserved_subcarriers,
+                masked_subcarriers: &config.masked_subcarriers,
+            },
+        )
+        .with_permutation(config.subcarrier_permutation);
+
+        let fft_length = constants.fft_length();
+        if !fft_length.is_power_of_two() {
+            panic!("FFT length must be a power of two, but got {}", fft_length);
+        }
+
+        let twiddles = (0..fft_length / 2)
+            .map(|k| {
+                let phase = -core::f64::consts::TAU * k as f64 / fft_length as f64;
+                Complex::new(to_q15(phase.cos()), to_q15(phase.sin()))
+            })
+            .collect();
+
+        FixedOFDMDemodulator {
+            constants,
+            twiddles,
+        }
+    }
+
+    /// Removes the cyclic prefix and transforms the symbol into its bins from DC to the Nyquist frequency, in Q15.
+    ///
+    /// The bins are the DFT divided by the FFT length, as every stage of the FFT halves its values,
+    /// so a full scale input stays within full scale. Values rounding beyond it saturate.
+    ///
+    /// # Panics
+    /// If the symbol does not have the [symbol length](FixedOFDMDemodulator::get_symbol_length).
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulatorConfig;
+    /// use software_modem::ofdm::fixed::FixedOFDMDemodulator;
+    ///
+    /// let fixed = FixedOFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// // full scale at DC, exactly
+    /// for value in [i16::MIN, i16::MAX] {
+    ///     let bins = fixed.transform(&[value; 132]);
+    ///     assert_eq!(bins[0], Complex::new(value, 0));
+    ///     assert!(bins[1..].iter().all(|bin| bin.re.abs() <= 1 && bin.im.abs() <= 1));
+    /// }
+    ///
+    /// // alternating full scale at the Nyquist frequency, the other twiddles fall short of 1 by a LSB
+    /// let nyquist: Vec<i16> = (0..132).map(|n| if n % 2 == 0 { i16::MIN } else { i16::MAX }).collect();
+    /// let bins = fixed.transform(&nyquist);
+    /// assert!(bins[64].re <= -32766 && bins[64].im == 0, "{:?}", bins[64]);
+    ///
+    /// // a full scale cosine on a bin, split in half between it and its mirror image
+    /// let cosine: Vec<i16> = (0..132)
+    ///     .map(|n| (32767.0 * (std::f64::consts::TAU * 10.0 * (n as f64 - 4.0) / 128.0).cos()).round() as i16)
+    ///     .collect();
+    /// let bins = fixed.transform(&cosine);
+    /// assert!((i32::from(bins[10].re) - 16384).abs() <= 2, "{:?}", bins[10]);
+    /// assert!(bins[10].im.abs() <= 2);
+    /// ```
+    pub fn transform(&self, symbol: &[i16]) -> Vec<Complex<i16>> {
+        if symbol.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                symbol.len()
+            );
+        }
+
+        // remove cyclic prefix, in bit reversed order for the iterative FFT
+        let body = &symbol[self.constants.cyclic_prefix_samples()..];
+        let fft_length = body.len();
+        let shift = usize::BITS - fft_length.trailing_zeros();
+        let mut bins: Vec<Complex<i32>> = (0..fft_length)
+            .map(|i| {
+                let j = if fft_length > 1 {
+                    i.reverse_bits() >> shift
+                } else {
+                    i
+                };
+                Complex::new(i32::from(body[j]), 0)
+            })
+            .collect();
+
+        // radix-2 decimation in time, the products of a Q15 sample and a twiddle fit into an i32
+        let mut half = 1;
+        while half < fft_length {
+            let stride = fft_length / (2 * half);
+            for start in (0..fft_length).step_by(2 * half) {
+                for k in 0..half {
+                    let w = self.twiddles[k * stride];
+                    let a = bins[start + k];
+                    let b = bins[start + k + half];
+                    // the first twiddle is 1, which Q15 falls just short of
+                    let t = if k == 0 {
+                        b
+                    } else {
+                        Complex::new(
+                            round_q15(b.re * w.re - b.im * w.im),
+                            round_q15(b.re * w.im + b.im * w.re),
+                        )
+                    };
+                    bins[start + k] = Complex::new(halve(a.re + t.re), halve(a.im + t.im));
+                    bins[start + k + half] = Complex::new(halve(a.re - t.re), halve(a.im - t.im));
+                }
+            }
+            half *= 2;
+        }
+
+        bins[..fft_length / 2 + 1]
+            .iter()
+            .map(|bin| Complex::new(bin.re as i16, bin.im as i16))
+            .collect()
+    }
+
+    /// Demodulates a single symbol of `i16` samples into its data.
+    ///
+    /// # Panics
+    /// If the symbol does not have the [symbol length](FixedOFDMDemodulator::get_symbol_length).
+    pub fn demodulate_symbol(&self, symbol: &[i16]) -> Vec<u8> {
+        let bins = self.transform(symbol);
+
+        // the equalizer of the float path divides by the mean pilot magnitude, the thresholds are multiplied instead
+        let pilots = &self.constants.pilot_subcarrier_indices;
+        let magnitude = pilots
+            .iter()
+            .map(|&idx| {
+                let bin = bins[idx as usize];
+                (i32::from(bin.re).pow(2) as u32 + i32::from(bin.im).pow(2) as u32).isqrt()
+            })
+            .sum::<u32>()
+            / pilots.len().max(1) as u32;
+
+        // the levels are 1, 3 and so on times the pilot over its magnitude,
+        // the thresholds lie at 0 and at 2, 4 and so on times that
+        let qam_order = self.constants.qam_order;
+        let pilot = pilot_magnitude(qam_order) as i32;
+        let axis_bits = qam_order.bits_per_symbol() / 2;
+        let axis = |x: i16| {
+            let magnitude_index = (1..1 << (axis_bits - 1))
+                .take_while(|&threshold| {
+                    pilot * i32::from(x).abs() > 2 * threshold * magnitude as i32
+                })
+                .count();
+            axis_label(x < 0, magnitude_index, axis_bits)
+        };
+        let mut data = vec![0; self.get_bytes_per_symbol()];
+        pack(
+            self.constants.data_subcarrier_indices.iter().map(|&idx| {
+                let bin = bins[idx as usize];
+                point_index(axis(bin.re), axis(bin.im), axis_bits) as u32
+            }),
+            qam_order.bits_per_symbol(),
+            &mut data,
+        );
+        data
+    }
+
+    /// Demodulates consecutive symbols of `i16` samples into their data.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::metrics::count_bit_errors;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::fixed::FixedOFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    /// let fixed = FixedOFDMDemodulator::new((&config).into());
+    ///
+    /// let data: Vec<u8> = (0..24000u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let gain = 10f32.powf(-modulator.get_peak_level_db() / 20.0);
+    /// let mut pcm = modulator.modulate_bytes_i16(&data, gain);
+    ///
+    /// // uniform noise, strong enough for a few percent of bit errors
+    /// let mut state: u32 = 0x1234_5678;
+    /// for sample in pcm.iter_mut() {
+    ///     state ^= state << 13;
+    ///     state ^= state >> 17;
+    ///     state ^= state << 5;
+    ///     *sample = sample.saturating_add((state >> 21) as i16 - 1024);
+    /// }
+    ///
+    /// let float = demodulator.demodulate_symbols_i16(&pcm);
+    /// let decoded = fixed.demodulate_symbols(&pcm);
+    /// assert!(count_bit_errors(&data, &float).bit_error_rate() > 0.01);
+    ///
+    /// // the decisions only differ near the thresholds
+    /// let differences = count_bit_errors(&float, &decoded);
+    /// assert!(differences.bit_error_rate() < 0.005, "{}", differences.bit_error_rate());
+    /// assert!(count_bit_errors(&data, &decoded).bit_error_rate() < 0.05);
+    /// ```
+    pub fn demodulate_symbols(&self, samples: &[i16]) -> Vec<u8> {
+        let symbol_length = self.get_symbol_length();
+        if !samples.len().is_multiple_of(symbol_length) {
+            panic!(
+                "Number of samples must be a multiple of {}, but got {}",
+                symbol_length,
+                samples.len()
+            );
+        }
+
+        samples
+            .chunks_exact(symbol_length)
+            .flat_map(|symbol| self.demodulate_symbol(symbol))
+            .collect()
+    }
+
+    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    pub fn get_symbol_length(&self) -> usize {
+        self.constants.symbol_length()
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        (self.constants.bits_per_symbol / 8) as usize
+    }
+}
+
+/// Converts a value between -1 and 1 to Q15, saturating 1 to the largest value.
+fn to_q15(value: f64) -> i32 {
+    (value * 32768.0).round().clamp(-32768.0, 32767.0) as i32
+}
+
+/// Rounds a product of two Q15 values back to Q15.
+fn round_q15(product: i32) -> i32 {
+    (product + (1 << 14)) >> 15
+}
+
+/// Halves a sum with rounding, saturating it to the range of an `i16`.
+fn halve(sum: i32) -> i32 {
+    ((sum + 1) >> 1).clamp(i32::from(i16::MIN), i32::from(i16::MAX))
+}
@@ -2,11 +2,1421 @@
 //!
 //! The [OFDM Modulator](modulator) modulates data into OFDM symbols.
 //! And the [OFDM Demodulator](demodulator) demodulates OFDM symbols back into data.
+//! The [fixed-point demodulator](fixed) does the same with integers only.
+//! The [embedded] demodulator, behind the `embedded` feature, has its sizes fixed at compile time and never touches the heap.
+//! The [complex modulator and demodulator](complex) work on complex baseband I/Q samples instead of real ones,
+//! and the [profiles] preset their configuration to published layouts, like the one of 802.11a.
+//! The [diversity] demodulator combines a frame received on several branches by maximal-ratio combining.
+//! The [equalizer] holds the per-subcarrier kernels the demodulator equalizes with.
+//! The [ofdma] modulator and demodulator share the data subcarriers of every symbol among logical channels.
+//! The [plan] helpers pick the subcarriers of a bandwidth at a sample rate and sum up the rate of a configuration.
+//! The [OFDMConfig] holds the parameters both ends must agree on.
+//!
+//! # Real-time use
+//! A thread which must not block, like an audio callback, makes the scratches and outputs it needs up front,
+//! with [make_scratch](modulator::GenericOFDMModulator::make_scratch) of the modulator and of the demodulator.
+//! Then these calls neither allocate nor take a lock, and return an error instead of panicking:
+//! - [try_modulate_buffer_as_symbol_with_scratch](modulator::GenericOFDMModulator::try_modulate_buffer_as_symbol_with_scratch)
+//! - [try_demodulate_symbol_into](demodulator::GenericOFDMDemodulator::try_demodulate_symbol_into)
+//! - [demodulate_into](crate::qam::GenericQAMModem::demodulate_into) of the QAM modem, which panics only on buffers of the wrong length
+//!
+//! Their time only depends on the configuration, not on the samples or the data.
+//! The default FFTs are planned at construction and take no lock when they run, a custom FFT has to do the same.
+//! The frame encoder and decoder, the batch calls and the `i16` conversions allocate and are not covered.
+
+use alloc::{sync::Arc, vec, vec::Vec};
 
-use crate::qam::QAMOrder;
+use num_complex::Complex;
+use smart_default::SmartDefault;
 
+use crate::{
+    bits::ConfigReader,
+    dsp::{FirFilter, Passband},
+    error::{Buffer, ModemError},
+    fft::{ComplexFft, plan_complex_forward, plan_complex_inverse},
+    qam::{QAMModem, QAMOrder},
+    rng::SimulationRng,
+    samples::Sample,
+    scrambler::Scrambler,
+};
+
+pub mod complex;
 pub mod demodulator;
+pub mod diversity;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+pub mod equalizer;
+pub mod fixed;
 pub mod modulator;
+#[cfg(feature = "std")]
+pub mod ofdma;
+pub mod plan;
+pub mod profiles;
+
+use core::fmt::Display;
+
+use demodulator::OFDMDemodulatorConfig;
+use modulator::{Clipping, OFDMModulatorConfig, OutputScale, ToneReservation};
+
+/// Configuration shared by a matching [modulator](modulator::OFDMModulator) and [demodulator](demodulator::OFDMDemodulator).
+///
+/// Convert it into an [OFDMModulatorConfig] or [OFDMDemodulatorConfig] with `From`,
+/// to be sure both ends use the same parameters.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// let config = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// };
+/// let modulator = OFDMModulator::new((&config).into());
+/// let demodulator = OFDMDemodulator::new((&config).into());
+/// assert_eq!(modulator.get_symbol_length(), demodulator.get_symbol_length());
+/// ```
+#[derive(SmartDefault, Clone, Debug, PartialEq)]
+pub struct OFDMConfig {
+    pub num_subcarriers: u32,
+    /// Length of the cyclic prefix in samples.
+    ///
+    /// It must cover the delay spread of the channel: echoes and reverberation up to this many samples late
+    /// only scale and turn the subcarriers, which the equalizer undoes, later ones leak into the next symbol.
+    /// A room impulse response may run past it as long as the energy beyond it is well below the noise,
+    /// see [IrChannel](crate::channel::IrChannel).
+    pub cyclic_prefix_length: u32,
+    /// Length of the cyclic prefix as a fraction of the FFT length, which replaces the
+    /// [cyclic_prefix_length](OFDMConfig::cyclic_prefix_length) if set, see [GuardInterval].
+    pub guard_interval: Option<GuardInterval>,
+    /// What fills the guard interval, the cyclic prefix or zeros, see [GuardType].
+    pub guard_type: GuardType,
+    /// Length of the copy of the head of every symbol after it, see [OFDMModulatorConfig::cyclic_suffix_length].
+    pub cyclic_suffix_length: u32,
+    /// Interval for pilot subcarriers.
+    #[default(4)]
+    pub pilot_subcarrier_every: u32,
+    pub qam_order: QAMOrder,
+    /// Encode the data subcarriers differentially in time, see [OFDMModulatorConfig::differential_time].
+    pub differential_time: bool,
+    /// Produce soft bit decisions in the demodulator, see [OFDMDemodulatorConfig::soft_output].
+    #[default(true)]
+    pub soft_output: bool,
+    /// Number of samples by which consecutive symbols are tapered and overlapped, see [OFDMModulatorConfig::roll_off].
+    pub roll_off: u32,
+    /// Clip and filter the transmitted symbols, see [OFDMModulatorConfig::clipping].
+    pub clipping: Option<Clipping>,
+    /// Subcarriers carrying no data, but a peak cancelling signal, see [OFDMModulatorConfig::reserved_subcarriers].
+    pub reserved_subcarriers: Vec<u32>,
+    /// The peak cancellation on the reserved subcarriers, only used by the modulator.
+    pub tone_reservation: ToneReservation,
+    /// Selected mapping of every symbol, see [SlmConfig].
+    pub slm: Option<SlmConfig>,
+    /// Number of signaling bits every symbol carries on the signs of its pilots, see [OFDMModulatorConfig::pilot_signaling_bits].
+    pub pilot_signaling_bits: u32,
+    /// Interpolation factor of the samples, see [OFDMModulatorConfig::oversampling].
+    #[default(1)]
+    pub oversampling: u32,
+    /// Number of unused subcarriers above DC, see [OFDMModulatorConfig::guard_subcarriers_low].
+    pub guard_subcarriers_low: u32,
+    /// Number of unused subcarriers below the Nyquist frequency, see [OFDMModulatorConfig::guard_subcarriers_high].
+    pub guard_subcarriers_high: u32,
+    /// Leave the DC bin empty, see [OFDMModulatorConfig::null_dc].
+    #[default(true)]
+    pub null_dc: bool,
+    /// Subcarriers carrying neither data nor pilots, see [OFDMModulatorConfig::masked_subcarriers].
+    pub masked_subcarriers: Vec<u32>,
+    /// Linear gains of the data subcarriers, see [OFDMModulatorConfig::power_allocation].
+    pub power_allocation: Option<Vec<f32>>,
+    /// Scaling of the frames, only used by the modulator, see [OFDMModulatorConfig::output_scale].
+    pub output_scale: OutputScale,
+    /// Gain of the frames in dB, only used by the modulator, see [OFDMModulatorConfig::tx_gain_db].
+    pub tx_gain_db: f32,
+    /// Refuse output that can exceed full scale, only used by the modulator, see [OFDMModulatorConfig::strict_headroom].
+    pub strict_headroom: bool,
+    /// Filter band-limiting the frames, only used by the modulator, see [OFDMModulatorConfig::tx_filter].
+    pub tx_filter: Option<FirFilter>,
+    /// Filter removing the noise outside the band, only used by the demodulator, see [OFDMDemodulatorConfig::rx_filter].
+    pub rx_filter: Option<FirFilter>,
+    /// Carrier the frames are moved to, see [OFDMModulatorConfig::passband].
+    pub passband: Option<Passband>,
+    /// Spread the points of every symbol over the data subcarriers with a DFT, see [OFDMModulatorConfig::dft_spread].
+    pub dft_spread: bool,
+    /// Order in which the points of every symbol are put on the data subcarriers, see [SubcarrierPermutation].
+    pub subcarrier_permutation: SubcarrierPermutation,
+    /// Symbols the reports keep the confidence of every decision of, only used by the demodulator,
+    /// see [OFDMDemodulatorConfig::confidence_symbols].
+    pub confidence_symbols: Option<usize>,
+    /// Turn the points of every coherent symbol back by the common phase of its pilots, only used by the demodulator,
+    /// see [OFDMDemodulatorConfig::track_common_phase].
+    pub track_common_phase: bool,
+}
+
+/// Version of the serialized [OFDMConfig].
+const OFDM_CONFIG_VERSION: u8 = 1;
+
+impl OFDMConfig {
+    /// Returns the length of the cyclic prefix in samples, from the [guard interval](OFDMConfig::guard_interval)
+    /// if it is set.
+    ///
+    /// # Panics
+    /// If the fraction of the FFT length is not a whole number of samples.
+    pub fn get_cyclic_prefix_length(&self) -> u32 {
+        resolve_cyclic_prefix_length(
+            self.guard_interval,
+            self.cyclic_prefix_length,
+            2 * self.num_subcarriers,
+        )
+    }
+
+    /// Returns the subcarriers carrying data in the order of the points of a symbol, from the lowest unless
+    /// [permuted](OFDMConfig::subcarrier_permutation), the ones an [OFDMA allocation](ofdma::SubcarrierAllocation)
+    /// shares among its logical channels.
+    ///
+    /// A symbol carries whole bytes of the QAM order, the subcarriers left over are not data subcarriers.
+    ///
+    /// # Panics
+    /// If the subcarriers are invalid, see [OFDMModulator::new](modulator::OFDMModulator::new).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::OFDMConfig;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 16,
+    ///     guard_subcarriers_high: 4,
+    ///     ..Default::default()
+    /// };
+    /// // every fourth subcarrier is a pilot, DC is left empty, and so is 11, half a byte of QAM-16
+    /// assert_eq!(config.get_data_subcarriers(), [1, 2, 3, 5, 6, 7, 9, 10]);
+    /// ```
+    pub fn get_data_subcarriers(&self) -> Vec<u32> {
+        self.constants().data_subcarrier_indices
+    }
+
+    /// Returns the constants of the symbols of the configuration.
+    fn constants(&self) -> OFDMConstants {
+        OFDMConstants::new(
+            self.num_subcarriers,
+            self.get_cyclic_prefix_length(),
+            self.qam_order,
+            self.oversampling,
+            SubcarrierLayout {
+                pilot_subcarrier_every: self.pilot_subcarrier_every,
+                guard_subcarriers_low: self.guard_subcarriers_low,
+                guard_subcarriers_high: self.guard_subcarriers_high,
+                null_dc: self.null_dc,
+                reserved_subcarriers: &self.reserved_subcarriers,
+                masked_subcarriers: &self.masked_subcarriers,
+            },
+        )
+        .with_guard(self.guard_type, self.cyclic_suffix_length)
+        .with_permutation(self.subcarrier_permutation)
+    }
+
+    /// Serializes the configuration, so it can be shared with the other end of a link.
+    ///
+    /// A [guard interval](OFDMConfig::guard_interval) is serialized as the length of the cyclic prefix it gives.
+    /// The [confidence symbols](OFDMConfig::confidence_symbols) are a diagnostic of the receiver and not serialized,
+    /// and neither is the [tracking of the common phase](OFDMConfig::track_common_phase), a choice of the receiver.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::dsp::Passband;
+    /// use software_modem::ofdm::{OFDMConfig, SlmConfig};
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     guard_subcarriers_low: 2,
+    ///     masked_subcarriers: vec![20, 21, 22, 23, 24],
+    ///     slm: Some(SlmConfig::default()),
+    ///     passband: Some(Passband { sample_rate: 48000.0, carrier_hz: 12000.0 }),
+    ///     ..Default::default()
+    /// };
+    /// let bytes = config.to_bytes();
+    /// assert_eq!(OFDMConfig::from_bytes(&bytes), Ok(config));
+    /// assert!(OFDMConfig::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![OFDM_CONFIG_VERSION];
+
+        bytes.extend(self.num_subcarriers.to_be_bytes());
+        bytes.extend(self.get_cyclic_prefix_length().to_be_bytes());
+        bytes.extend(self.pilot_subcarrier_every.to_be_bytes());
+        bytes.push(match self.qam_order {
+            QAMOrder::QAM16 => 0,
+            QAMOrder::QPSK => 1,
+            QAMOrder::QAM64 => 2,
+            QAMOrder::QAM256 => 3,
+        });
+        bytes.push(u8::from(self.differential_time));
+        bytes.push(u8::from(self.soft_output));
+        bytes.extend(self.roll_off.to_be_bytes());
+
+        match self.clipping {
+            None => bytes.push(0),
+            Some(clipping) => {
+                bytes.push(1);
+                bytes.extend(clipping.threshold_db.to_be_bytes());
+                bytes.extend((clipping.iterations as u32).to_be_bytes());
+            }
+        }
+
+        bytes.extend((self.reserved_subcarriers.len() as u16).to_be_bytes());
+        for index in &self.reserved_subcarriers {
+            bytes.extend(index.to_be_bytes());
+        }
+        bytes.extend(self.tone_reservation.threshold_db.to_be_bytes());
+        bytes.extend((self.tone_reservation.iterations as u32).to_be_bytes());
+
+        match self.slm {
+            None => bytes.push(0),
+            Some(slm) => {
+                bytes.push(1);
+                bytes.push(slm.candidates);
+                bytes.push(match slm.signaling {
+                    SlmSignaling::Explicit => 0,
+                    SlmSignaling::Blind => 1,
+                });
+            }
+        }
+
+        bytes.push(self.oversampling as u8);
+        bytes.extend(self.guard_subcarriers_low.to_be_bytes());
+        bytes.extend(self.guard_subcarriers_high.to_be_bytes());
+        bytes.push(u8::from(self.null_dc));
+
+        bytes.extend((self.masked_subcarriers.len() as u16).to_be_bytes());
+        for index in &self.masked_subcarriers {
+            bytes.extend(index.to_be_bytes());
+        }
+
+        match &self.power_allocation {
+            None => bytes.push(0),
+            Some(gains) => {
+                bytes.push(1);
+                bytes.extend((gains.len() as u16).to_be_bytes());
+                for gain in gains {
+                    bytes.extend(gain.to_be_bytes());
+                }
+            }
+        }
+
+        match self.output_scale {
+            OutputScale::Raw => bytes.push(0),
+            OutputScale::PeakNormalize(target) => {
+                bytes.push(1);
+                bytes.extend(target.to_be_bytes());
+            }
+            OutputScale::FixedGain(gain) => {
+                bytes.push(2);
+                bytes.extend(gain.to_be_bytes());
+            }
+        }
+        bytes.extend(self.tx_gain_db.to_be_bytes());
+        bytes.push(u8::from(self.strict_headroom));
+
+        for filter in [&self.tx_filter, &self.rx_filter] {
+            match filter {
+                None => bytes.push(0),
+                Some(filter) => {
+                    bytes.push(1);
+                    bytes.extend((filter.taps().len() as u16).to_be_bytes());
+                    for tap in filter.taps() {
+                        bytes.extend(tap.to_be_bytes());
+                    }
+                }
+            }
+        }
+
+        match self.passband {
+            None => bytes.push(0),
+            Some(passband) => {
+                bytes.push(1);
+                bytes.extend(passband.sample_rate.to_be_bytes());
+                bytes.extend(passband.carrier_hz.to_be_bytes());
+            }
+        }
+        bytes.push(u8::from(self.dft_spread));
+        bytes.push(u8::from(self.guard_type == GuardType::ZeroPad));
+        bytes.extend(self.cyclic_suffix_length.to_be_bytes());
+        bytes.push(self.pilot_signaling_bits as u8);
+        match self.subcarrier_permutation {
+            SubcarrierPermutation::Identity => bytes.push(0),
+            SubcarrierPermutation::BitReversal => bytes.push(1),
+            SubcarrierPermutation::Random(seed) => {
+                bytes.push(2);
+                bytes.extend(seed.to_be_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Reads a configuration serialized with [to_bytes](OFDMConfig::to_bytes).
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the bytes are not a valid configuration of this version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModemError> {
+        let mut reader = ConfigReader { bytes };
+        if reader.u8()? != OFDM_CONFIG_VERSION {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        let num_subcarriers = reader.u32()?;
+        let cyclic_prefix_length = reader.u32()?;
+        let pilot_subcarrier_every = reader.u32()?;
+        let qam_order = match reader.u8()? {
+            0 => QAMOrder::QAM16,
+            1 => QAMOrder::QPSK,
+            2 => QAMOrder::QAM64,
+            3 => QAMOrder::QAM256,
+            _ => return Err(ModemError::InvalidConfig),
+        };
+        let differential_time = reader.flag()?;
+        let soft_output = reader.flag()?;
+        let roll_off = reader.u32()?;
+        if pilot_subcarrier_every == 0 || roll_off > cyclic_prefix_length {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        let clipping = if reader.flag()? {
+            Some(Clipping {
+                threshold_db: reader.f32()?,
+                iterations: reader.u32()? as usize,
+            })
+        } else {
+            None
+        };
+
+        let reserved_subcarriers = reader.indices(num_subcarriers)?;
+        let tone_reservation = ToneReservation {
+            threshold_db: reader.f32()?,
+            iterations: reader.u32()? as usize,
+        };
+
+        let slm = if reader.flag()? {
+            let candidates = reader.u8()?;
+            let signaling = match reader.u8()? {
+                0 => SlmSignaling::Explicit,
+                1 => SlmSignaling::Blind,
+                _ => return Err(ModemError::InvalidConfig),
+            };
+            if !(1..=128).contains(&candidates) {
+                return Err(ModemError::InvalidConfig);
+            }
+            Some(SlmConfig {
+                candidates,
+                signaling,
+            })
+        } else {
+            None
+        };
+
+        let oversampling = u32::from(reader.u8()?);
+        let guard_subcarriers_low = reader.u32()?;
+        let guard_subcarriers_high = reader.u32()?;
+        let null_dc = reader.flag()?;
+        if ![1, 2, 4].contains(&oversampling)
+            || u64::from(guard_subcarriers_low) + u64::from(guard_subcarriers_high) + 1
+                >= u64::from(num_subcarriers)
+        {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        let masked_subcarriers = reader.indices(num_subcarriers)?;
+
+        let power_allocation = if reader.flag()? {
+            let count = reader.u16()?;
+            let gains = (0..count)
+                .map(|_| {
+                    Some(reader.f32()?)
+                        .filter(|gain| gain.is_finite() && *gain >= 0.0)
+                        .ok_or(ModemError::InvalidConfig)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(gains)
+        } else {
+            None
+        };
+
+        let output_scale = match reader.u8()? {
+            0 => OutputScale::Raw,
+            tag @ (1 | 2) => {
+                let value = reader.f32()?;
+                if !(value.is_finite() && value > 0.0) {
+                    return Err(ModemError::InvalidConfig);
+                }
+                if tag == 1 {
+                    OutputScale::PeakNormalize(value)
+                } else {
+                    OutputScale::FixedGain(value)
+                }
+            }
+            _ => return Err(ModemError::InvalidConfig),
+        };
+        let tx_gain_db = reader.f32()?;
+        let strict_headroom = reader.flag()?;
+        if !tx_gain_db.is_finite() {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        let mut filter = || -> Result<Option<FirFilter>, ModemError> {
+            if !reader.flag()? {
+                return Ok(None);
+            }
+            let count = reader.u16()?;
+            let taps = (0..count)
+                .map(|_| reader.f32())
+                .collect::<Result<Vec<_>, _>>()?;
+            if taps.len().is_multiple_of(2) || taps.iter().any(|tap| tap.is_infinite()) {
+                return Err(ModemError::InvalidConfig);
+            }
+            Ok(Some(FirFilter::new(taps)))
+        };
+        let tx_filter = filter()?;
+        let rx_filter = filter()?;
+
+        let passband = if reader.flag()? {
+            let sample_rate = reader.f32()?;
+            let carrier_hz = reader.f32()?;
+            if !(sample_rate.is_finite() && sample_rate > 0.0 && carrier_hz.is_finite()) {
+                return Err(ModemError::InvalidConfig);
+            }
+            Some(Passband {
+                sample_rate,
+                carrier_hz,
+            })
+        } else {
+            None
+        };
+
+        let dft_spread = reader.flag()?;
+        if dft_spread && (differential_time || slm.is_some()) {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        let guard_type = if reader.flag()? {
+            GuardType::ZeroPad
+        } else {
+            GuardType::CyclicPrefix
+        };
+        if guard_type == GuardType::ZeroPad && roll_off > 0 {
+            return Err(ModemError::InvalidConfig);
+        }
+        let cyclic_suffix_length = reader.u32()?;
+        if cyclic_suffix_length > 2 * num_subcarriers {
+            return Err(ModemError::InvalidConfig);
+        }
+        let pilot_signaling_bits = u32::from(reader.u8()?);
+        if pilot_signaling_bits > MAX_PILOT_SIGNALING_BITS
+            || (pilot_signaling_bits > 0
+                && slm.is_some_and(|slm| slm.signaling == SlmSignaling::Explicit))
+        {
+            return Err(ModemError::InvalidConfig);
+        }
+        let subcarrier_permutation = match reader.u8()? {
+            0 => SubcarrierPermutation::Identity,
+            1 => SubcarrierPermutation::BitReversal,
+            2 => SubcarrierPermutation::Random(reader.u64()?),
+            _ => return Err(ModemError::InvalidConfig),
+        };
+
+        if !reader.bytes.is_empty() {
+            return Err(ModemError::InvalidConfig);
+        }
+
+        Ok(OFDMConfig {
+            num_subcarriers,
+            cyclic_prefix_length,
+            guard_interval: None,
+            guard_type,
+            cyclic_suffix_length,
+            pilot_subcarrier_every,
+            qam_order,
+            differential_time,
+            soft_output,
+            roll_off,
+            clipping,
+            reserved_subcarriers,
+            tone_reservation,
+            slm,
+            pilot_signaling_bits,
+            oversampling,
+            guard_subcarriers_low,
+            guard_subcarriers_high,
+            null_dc,
+            masked_subcarriers,
+            power_allocation,
+            output_scale,
+            tx_gain_db,
+            strict_headroom,
+            tx_filter,
+            rx_filter,
+            passband,
+            dft_spread,
+            subcarrier_permutation,
+            confidence_symbols: None,
+            track_common_phase: false,
+        })
+    }
+}
+
+impl<T: Sample> From<&OFDMConfig> for OFDMModulatorConfig<T> {
+    fn from(config: &OFDMConfig) -> Self {
+        OFDMModulatorConfig {
+            num_subcarriers: config.num_subcarriers,
+            cyclic_prefix_length: config.cyclic_prefix_length,
+            guard_interval: config.guard_interval,
+            guard_type: config.guard_type,
+            cyclic_suffix_length: config.cyclic_suffix_length,
+            pilot_subcarrier_every: config.pilot_subcarrier_every,
+            qam_order: config.qam_order,
+            differential_time: config.differential_time,
+            roll_off: config.roll_off,
+            clipping: config.clipping,
+            reserved_subcarriers: config.reserved_subcarriers.clone(),
+            tone_reservation: config.tone_reservation,
+            slm: config.slm,
+            pilot_signaling_bits: config.pilot_signaling_bits,
+            oversampling: config.oversampling,
+            guard_subcarriers_low: config.guard_subcarriers_low,
+            guard_subcarriers_high: config.guard_subcarriers_high,
+            null_dc: config.null_dc,
+            masked_subcarriers: config.masked_subcarriers.clone(),
+            power_allocation: config.power_allocation.clone(),
+            output_scale: config.output_scale,
+            tx_gain_db: config.tx_gain_db,
+            strict_headroom: config.strict_headroom,
+            tx_filter: config.tx_filter.clone(),
+            passband: config.passband,
+            dft_spread: config.dft_spread,
+            subcarrier_permutation: config.subcarrier_permutation,
+            ..Default::default()
+        }
+    }
+}
+
+impl<T: Sample> From<&OFDMConfig> for OFDMDemodulatorConfig<T> {
+    fn from(config: &OFDMConfig) -> Self {
+        OFDMDemodulatorConfig {
+            num_subcarriers: config.num_subcarriers,
+            cyclic_prefix_length: config.cyclic_prefix_length,
+            guard_interval: config.guard_interval,
+            guard_type: config.guard_type,
+            cyclic_suffix_length: config.cyclic_suffix_length,
+            pilot_subcarrier_every: config.pilot_subcarrier_every,
+            qam_order: config.qam_order,
+            differential_time: config.differential_time,
+            soft_output: config.soft_output,
+            roll_off: config.roll_off,
+            reserved_subcarriers: config.reserved_subcarriers.clone(),
+            slm: config.slm,
+            pilot_signaling_bits: config.pilot_signaling_bits,
+            oversampling: config.oversampling,
+            guard_subcarriers_low: config.guard_subcarriers_low,
+            guard_subcarriers_high: config.guard_subcarriers_high,
+            null_dc: config.null_dc,
+            masked_subcarriers: config.masked_subcarriers.clone(),
+            power_allocation: config.power_allocation.clone(),
+            rx_filter: config.rx_filter.clone(),
+            passband: config.passband,
+            dft_spread: config.dft_spread,
+            subcarrier_permutation: config.subcarrier_permutation,
+            confidence_symbols: config.confidence_symbols,
+            track_common_phase: config.track_common_phase,
+            ..Default::default()
+        }
+    }
+}
+
+/// The length of the cyclic prefix, as a fraction of the FFT length of a symbol or in samples,
+/// see [OFDMConfig::guard_interval].
+///
+/// The FFT length of the real modem is `2 * num_subcarriers` samples, before the oversampling,
+/// and the one of the [complex modem](complex) is `num_subcarriers` samples.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{GuardInterval, OFDMConfig};
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// // a quarter of the 128 samples of the FFT
+/// let config = OFDMConfig {
+///     num_subcarriers: 64,
+///     guard_interval: Some(GuardInterval::Quarter),
+///     ..Default::default()
+/// };
+/// assert_eq!(config.get_cyclic_prefix_length(), 32);
+/// assert_eq!(OFDMModulator::new((&config).into()).get_symbol_length(), 160);
+/// assert_eq!(GuardInterval::Samples(32).cyclic_prefix_length(128), 32);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuardInterval {
+    /// 1/4 of the FFT length.
+    #[default]
+    Quarter,
+    /// 1/8 of the FFT length.
+    Eighth,
+    /// 1/16 of the FFT length.
+    Sixteenth,
+    /// 1/32 of the FFT length.
+    ThirtySecond,
+    /// A number of samples, whatever the FFT length.
+    Samples(u32),
+}
+
+impl Display for GuardInterval {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.get_denominator() {
+            Some(denominator) => write!(f, "1/{}", denominator),
+            None => write!(f, "{} samples", self.cyclic_prefix_length(0)),
+        }
+    }
+}
+
+impl GuardInterval {
+    /// The fractions of the FFT length, from the longest to the shortest.
+    pub const FRACTIONS: [GuardInterval; 4] = [
+        GuardInterval::Quarter,
+        GuardInterval::Eighth,
+        GuardInterval::Sixteenth,
+        GuardInterval::ThirtySecond,
+    ];
+
+    /// Returns the denominator of the fraction, 4 for a quarter, or `None` for a number of samples.
+    pub fn get_denominator(&self) -> Option<u32> {
+        match self {
+            GuardInterval::Quarter => Some(4),
+            GuardInterval::Eighth => Some(8),
+            GuardInterval::Sixteenth => Some(16),
+            GuardInterval::ThirtySecond => Some(32),
+            GuardInterval::Samples(_) => None,
+        }
+    }
+
+    /// Returns the length of the cyclic prefix in samples for an FFT of the length.
+    ///
+    /// # Panics
+    /// If the fraction of the FFT length is not a whole number of samples.
+    pub fn cyclic_prefix_length(&self, fft_length: u32) -> u32 {
+        match (self, self.get_denominator()) {
+            (GuardInterval::Samples(samples), _) => *samples,
+            (_, Some(denominator)) if fft_length.is_multiple_of(denominator) => {
+                fft_length / denominator
+            }
+            _ => panic!(
+                "Guard interval must be a whole number of samples, but got {} of {}",
+                self, fft_length
+            ),
+        }
+    }
+}
+
+/// What fills the guard interval between the symbols, see [OFDMConfig::guard_type].
+///
+/// Both keep the length of a symbol, the FFT length plus the guard interval, and both let echoes
+/// up to the guard interval late only scale and turn the subcarriers.
+/// A cyclic prefix repeats the end of the symbol before it, which the demodulator skips.
+/// Zero padding leaves the guard after the symbol silent, so no power is spent on it,
+/// and the demodulator adds the echoes which land in it back onto the head of the symbol,
+/// which makes them cyclic again. It cannot be combined with a [roll-off](OFDMConfig::roll_off),
+/// nor with the early FFT windows of the [diversity] demodulator.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{GuardType, OFDMConfig};
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// let config = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 16,
+///     guard_type: GuardType::ZeroPad,
+///     ..Default::default()
+/// };
+/// let modulator = OFDMModulator::new((&config).into());
+/// let data = vec![0x5a; modulator.get_bytes_per_symbol()];
+/// let symbol = modulator.modulate_symbol(&data).unwrap();
+/// // the 128 samples of the symbol, then 16 zeros
+/// assert_eq!(symbol.len(), 144);
+/// assert!(symbol[128..].iter().all(|&x| x == 0.0));
+///
+/// let demodulator = OFDMDemodulator::new((&config).into());
+/// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuardType {
+    /// The end of the symbol, repeated before it.
+    #[default]
+    CyclicPrefix,
+    /// Zeros after the symbol.
+    ZeroPad,
+}
+
+/// The order in which the points of a symbol are put on its data subcarriers, see [OFDMConfig::subcarrier_permutation].
+///
+/// The points of a symbol carry consecutive bits, which the frames code and interleave over all the symbols of a frame.
+/// In the order of the subcarriers, from the lowest, neighbouring points land on neighbouring subcarriers,
+/// and a notch of the channel, like the echo of a wall or a room mode, fades a run of them together.
+/// A permutation spreads the neighbouring points over the band, so a notch fades points far apart,
+/// whose bits the code corrects like scattered errors. Both ends must use the same permutation,
+/// the subcarriers, pilots and capacity of a symbol do not change.
+///
+/// The [data subcarriers](OFDMConfig::get_data_subcarriers), and with them the gains of a
+/// [power allocation](OFDMConfig::power_allocation), are in the order of the points.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{OFDMConfig, SubcarrierPermutation};
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+///
+/// let config = |subcarrier_permutation| OFDMConfig {
+///     num_subcarriers: 16,
+///     guard_subcarriers_high: 4,
+///     subcarrier_permutation,
+///     ..Default::default()
+/// };
+/// assert_eq!(config(SubcarrierPermutation::Identity).get_data_subcarriers(), [1, 2, 3, 5, 6, 7, 9, 10]);
+/// // the positions 0 to 7 with their three bits reversed
+/// assert_eq!(config(SubcarrierPermutation::BitReversal).get_data_subcarriers(), [1, 6, 3, 9, 2, 7, 5, 10]);
+///
+/// let config = config(SubcarrierPermutation::Random(7));
+/// let modulator = OFDMModulator::new((&config).into());
+/// let demodulator = OFDMDemodulator::new((&config).into());
+/// let data = [0x12, 0x34, 0x56, 0x78];
+/// let symbol = modulator.modulate_symbol(&data).unwrap();
+/// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SubcarrierPermutation {
+    /// The points in the order of the subcarriers, from the lowest.
+    #[default]
+    Identity,
+    /// The point at every position goes to the subcarrier at the position with its bits reversed, over the
+    /// next power of two of the number of data subcarriers, skipping the positions beyond them.
+    /// Consecutive points land about half the band apart.
+    BitReversal,
+    /// A pseudo-random shuffle of the subcarriers, the same for the same seed.
+    Random(u64),
+}
+
+impl SubcarrierPermutation {
+    /// Returns the position among the data subcarriers, from the lowest, of every point.
+    fn positions(self, count: usize) -> Vec<usize> {
+        match self {
+            SubcarrierPermutation::Identity => (0..count).collect(),
+            SubcarrierPermutation::BitReversal => {
+                let bits = count.next_power_of_two().trailing_zeros();
+                (0..count.next_power_of_two())
+                    .map(|position: usize| {
+                        position
+                            .reverse_bits()
+                            .checked_shr(usize::BITS - bits)
+                            .unwrap_or(0)
+                    })
+                    .filter(|&position| position < count)
+                    .collect()
+            }
+            SubcarrierPermutation::Random(seed) => {
+                let mut rng = SimulationRng::new(seed);
+                let mut positions: Vec<usize> = (0..count).collect();
+                for i in (1..count).rev() {
+                    positions.swap(i, rng.below(i as u64 + 1) as usize);
+                }
+                positions
+            }
+        }
+    }
+}
+
+/// Panics if zero padding is combined with a roll-off, which tapers the guard interval.
+fn check_guard_type(guard_type: GuardType, roll_off: u32) {
+    if guard_type == GuardType::ZeroPad && roll_off > 0 {
+        panic!(
+            "Zero padding does not support a roll-off, but got {}",
+            roll_off
+        );
+    }
+}
+
+/// Returns the length of the cyclic prefix of a configuration, from its guard interval if it has one.
+///
+/// # Panics
+/// If the fraction of the FFT length is not a whole number of samples.
+fn resolve_cyclic_prefix_length(
+    guard_interval: Option<GuardInterval>,
+    cyclic_prefix_length: u32,
+    fft_length: u32,
+) -> u32 {
+    guard_interval.map_or(cyclic_prefix_length, |guard| {
+        guard.cyclic_prefix_length(fft_length)
+    })
+}
+
+/// Parameters of the selected mapping (SLM) PAPR reduction.
+///
+/// The modulator multiplies the data subcarriers of every symbol with each of `candidates` known phase sequences
+/// and transmits the candidate with the lowest PAPR. The receiver finds out which sequence was used,
+/// as configured by the [signaling](SlmSignaling), and rotates the data subcarriers back.
+/// The first sequence leaves the symbol unchanged, the others are pseudo random multiples of 45°,
+/// so that a rotation can not map a square constellation onto itself and hide from blind detection.
+/// Both ends must use the same configuration.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::modulator::OFDMModulator;
+/// use software_modem::ofdm::{OFDMConfig, SlmConfig, SlmSignaling};
+///
+/// let config = |slm| OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     slm,
+///     ..Default::default()
+/// };
+/// let papr = |samples: &[f32]| {
+///     let peak = samples.iter().map(|x| x * x).fold(0.0, f32::max);
+///     let mean = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+///     10.0 * (peak / mean).log10()
+/// };
+///
+/// // the share of 1000 random symbols with a PAPR above the level
+/// let ccdf = |slm, level| {
+///     let modulator = OFDMModulator::new((&config(slm)).into());
+///     let demodulator = OFDMDemodulator::new((&config(slm)).into());
+///     let mut symbol = vec![0.0; modulator.get_symbol_length()];
+///     let mut above = 0;
+///     for i in 0..1000u32 {
+///         let data: Vec<u8> = (0..24u32).map(|j| ((i * 24 + j).wrapping_mul(2654435761) >> 11) as u8).collect();
+///         modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+///         assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+///         above += usize::from(papr(&symbol[4..]) > level);
+///     }
+///     above as f32 / 1000.0
+/// };
+///
+/// let explicit = SlmConfig {
+///     candidates: 4,
+///     signaling: SlmSignaling::Explicit,
+/// };
+/// let blind = SlmConfig {
+///     candidates: 4,
+///     signaling: SlmSignaling::Blind,
+/// };
+///
+/// // almost half of the plain symbols exceed 10 dB, with 4 candidates hardly any
+/// let plain = ccdf(None, 10.0);
+/// assert!(plain > 0.4, "{plain}");
+/// for slm in [explicit, blind] {
+///     let selected = ccdf(Some(slm), 10.0);
+///     assert!(selected < 0.01, "{slm:?}: {selected}");
+/// }
+/// ```
+///
+/// With explicit signaling, every symbol of a coded frame, the header included, carries its own index,
+/// also over multipath in differential mode.
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::{OFDMConfig, SlmConfig};
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     differential_time: true,
+///     slm: Some(SlmConfig::default()),
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+///
+/// let payload = "Every symbol picks its own rotation".as_bytes();
+/// let samples = modulator.encode_frame(payload);
+///
+/// let taps = [-0.7, 0.4, 0.2];
+/// let received: Vec<f32> = (0..samples.len())
+///     .map(|n| (0..taps.len().min(n + 1)).map(|k| taps[k] * samples[n - k]).sum())
+///     .collect();
+/// assert_eq!(demodulator.decode_frame(&received).unwrap(), payload);
+/// ```
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SlmConfig {
+    /// Number of phase sequences to choose from, between 1 and 128.
+    #[default(4)]
+    pub candidates: u8,
+    /// How the receiver learns the chosen sequence.
+    pub signaling: SlmSignaling,
+}
+
+/// How the index of the phase sequence chosen by [selected mapping](SlmConfig) reaches the receiver.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SlmSignaling {
+    /// Every symbol carries its index as side information on the pilots, in the sign changes from one pilot to the next.
+    ///
+    /// The pilot magnitudes, which the equalizer uses, stay unchanged.
+    /// Each index bit is repeated over as many pilot pairs as there are,
+    /// so frames need no extra symbols or header bits, and every header and payload symbol is decoded on its own.
+    #[default]
+    Explicit,
+    /// The receiver tries every sequence and keeps the one whose points lie closest to the constellation.
+    ///
+    /// This needs no side information, but costs one QAM decision per candidate and symbol,
+    /// and only works with coherent demodulation, not [differential](OFDMConfig::differential_time) in time.
+    Blind,
+}
+
+/// What a batch of symbols covered, see [demodulate_batch](demodulator::GenericOFDMDemodulator::demodulate_batch)
+/// and [modulate_batch](modulator::GenericOFDMModulator::modulate_batch).
+///
+/// The input is counted in samples for the demodulator and in data bytes for the modulator.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Number of symbols processed.
+    pub symbols: usize,
+    /// Length of the input the symbols took up.
+    pub consumed: usize,
+    /// Length of the input after the last complete symbol, which is left for the next batch.
+    pub trailing: usize,
+}
+
+/// Most signaling bits the pilots of a symbol carry, see [OFDMModulatorConfig::pilot_signaling_bits].
+pub const MAX_PILOT_SIGNALING_BITS: u32 = 8;
+
+/// The phase sequences and pilot patterns of a [SlmConfig], shared by the modulator and the demodulator.
+struct SelectedMapping<T: Sample> {
+    signaling: SlmSignaling,
+    phases: Vec<Vec<Complex<T>>>,
+    index_bits: u32,
+}
+
+impl<T: Sample> SelectedMapping<T> {
+    /// # Panics
+    /// If the number of candidates is not between 1 and 128,
+    /// or there are not enough pilots to signal the index explicitly.
+    fn new(config: &SlmConfig, num_data_subcarriers: usize, num_pilot_subcarriers: usize) -> Self {
+        if !(1..=128).contains(&config.candidates) {
+            panic!(
+                "Number of SLM candidates must be between 1 and 128, but got {}",
+                config.candidates
+            );
+        }
+        let index_bits = u8::BITS - (config.candidates - 1).leading_zeros();
+        if config.signaling == SlmSignaling::Explicit
+            && num_pilot_subcarriers <= index_bits as usize
+        {
+            panic!(
+                "Explicit SLM signaling needs more than {} pilot subcarriers, but got {}",
+                index_bits, num_pilot_subcarriers
+            );
+        }
+
+        let phases = (0..u32::from(config.candidates))
+            .map(|index| {
+                if index == 0 {
+                    return vec![Complex::new(T::one(), T::zero()); num_data_subcarriers];
+                }
+                Scrambler::default()
+                    .with_seed(index)
+                    .sequence(3 * num_data_subcarriers)
+                    .chunks_exact(3)
+                    .map(|bits| {
+                        let step = bits.iter().fold(0, |step, &bit| (step << 1) | bit);
+                        Complex::from_polar(T::one(), T::FRAC_PI_4() * T::cast(step.into()))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        SelectedMapping {
+            signaling: config.signaling,
+            phases,
+            index_bits,
+        }
+    }
+
+    fn candidates(&self) -> usize {
+        self.phases.len()
+    }
+
+    /// Returns the phase of every data subcarrier for the candidate.
+    fn phases(&self, index: usize) -> &[Complex<T>] {
+        &self.phases[index]
+    }
+
+    /// Returns the sign of every pilot, which carries the index for explicit signaling.
+    fn pilot_signs(
+        &self,
+        index: usize,
+        num_pilot_subcarriers: usize,
+    ) -> impl Iterator<Item = T> + '_ {
+        let bits = if self.signaling == SlmSignaling::Explicit {
+            self.index_bits
+        } else {
+            0
+        };
+        differential_pilot_signs(index, bits, num_pilot_subcarriers)
+    }
+
+    /// Returns the index signaled on the received pilots, or `None` for blind detection.
+    fn detect_index(&self, pilots: &[Complex<T>]) -> Option<usize> {
+        if self.signaling != SlmSignaling::Explicit {
+            return None;
+        }
+        let index = correlate_pilot_pairs(pilots, self.index_bits)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &(correlation, _))| correlation < T::zero())
+            .fold(0, |index, (bit, _)| index | (1 << bit));
+        // a corrupted index beyond the candidates falls back to the unchanged symbol
+        Some(if index < self.candidates() { index } else { 0 })
+    }
+}
+
+/// Returns the sign of every pilot carrying the value in the sign changes from one pilot to the next,
+/// the pair of pilots `p` and `p + 1` flipping the sign where bit `p % bits` of the value is set.
+///
+/// Only the changes carry the value, so the unknown phase of the channel cancels in the products of neighbouring pilots.
+fn differential_pilot_signs<T: Sample>(
+    value: usize,
+    bits: u32,
+    num_pilot_subcarriers: usize,
+) -> impl Iterator<Item = T> {
+    let mut sign = T::one();
+    (0..num_pilot_subcarriers).map(move |pilot| {
+        if bits > 0 && pilot > 0 && (value >> ((pilot - 1) % bits as usize)) & 1 == 1 {
+            sign = -sign;
+        }
+        sign
+    })
+}
+
+/// Correlates the neighbouring received pilots of every bit of [differential_pilot_signs], and returns for every bit
+/// the sum of the real parts of the products of its pairs, negative for a bit of 1, and the sum of their magnitudes.
+///
+/// The bits beyond `bits` are left at 0, there are at most as many as the pilots carry, counted without allocating.
+fn correlate_pilot_pairs<T: Sample>(
+    pilots: &[Complex<T>],
+    bits: u32,
+) -> [(T, T); MAX_PILOT_SIGNALING_BITS as usize] {
+    let mut correlations = [(T::zero(), T::zero()); MAX_PILOT_SIGNALING_BITS as usize];
+    if bits == 0 {
+        return correlations;
+    }
+    for (pair, pilots) in pilots.windows(2).enumerate() {
+        let product = pilots[1] * pilots[0].conj();
+        let (correlation, magnitude) = &mut correlations[pair % bits as usize];
+        *correlation += product.re;
+        *magnitude += product.norm_sqr().sqrt();
+    }
+    correlations
+}
+
+/// Panics if there are more pilot signaling bits than the pilots carry,
+/// or they are combined with [explicit SLM signaling](SlmSignaling::Explicit), which takes the signs of the pilots.
+fn check_pilot_signaling(bits: u32, slm: Option<SlmConfig>, num_pilot_subcarriers: usize) {
+    if bits > MAX_PILOT_SIGNALING_BITS {
+        panic!(
+            "Pilot signaling bits must be at most {}, but got {}",
+            MAX_PILOT_SIGNALING_BITS, bits
+        );
+    }
+    if bits > 0 && slm.is_some_and(|slm| slm.signaling == SlmSignaling::Explicit) {
+        panic!("Pilot signaling does not support explicit SLM signaling, but got slm");
+    }
+    if bits > 0 && num_pilot_subcarriers <= bits as usize {
+        panic!(
+            "Pilot signaling needs more than {} pilot subcarriers, but got {}",
+            bits, num_pilot_subcarriers
+        );
+    }
+}
+
+/// The DFT which spreads the points of a symbol over its data subcarriers, see [OFDMModulatorConfig::dft_spread],
+/// shared by the modulator and the demodulator.
+struct DftSpreading<T: Sample> {
+    forward: Arc<dyn ComplexFft<T>>,
+    inverse: Arc<dyn ComplexFft<T>>,
+    /// Makes the transforms unitary, so the points keep their mean power.
+    scale: T,
+}
+
+impl<T: Sample> DftSpreading<T> {
+    fn new(num_data_subcarriers: usize) -> Self {
+        DftSpreading {
+            forward: plan_complex_forward(num_data_subcarriers),
+            inverse: plan_complex_inverse(num_data_subcarriers),
+            scale: T::one() / T::cast(num_data_subcarriers as f64).sqrt(),
+        }
+    }
+
+    /// Returns the scratch space either transform needs.
+    fn scratch_len(&self) -> usize {
+        self.forward
+            .get_scratch_len()
+            .max(self.inverse.get_scratch_len())
+    }
+
+    /// Spreads the points of a symbol, one per data subcarrier, in place.
+    fn spread(&self, points: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.forward
+            .process_with_scratch(points, &mut scratch[..self.forward.get_scratch_len()]);
+        T::scale_points(points, self.scale);
+    }
+
+    /// Undoes [spread](Self::spread) on the equalized points of a symbol in place.
+    fn despread(&self, points: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.inverse
+            .process_with_scratch(points, &mut scratch[..self.inverse.get_scratch_len()]);
+        T::scale_points(points, self.scale);
+    }
+}
+
+/// Returns [ModemError::BufferLength] unless a buffer has the expected length.
+fn check_length(expected: usize, got: usize) -> Result<(), ModemError> {
+    if got == expected {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+/// Returns [ModemError::BufferLengthMismatch] for the buffer unless it has the expected length.
+fn check_buffer_length(buffer: Buffer, expected: usize, got: usize) -> Result<(), ModemError> {
+    if got == expected {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLengthMismatch {
+            buffer,
+            expected,
+            got,
+        })
+    }
+}
+
+/// Panics unless there is one finite, non-negative gain per data subcarrier.
+fn check_power_allocation(gains: &[f32], num_data_subcarriers: usize) {
+    if gains.len() != num_data_subcarriers {
+        panic!(
+            "Power allocation must have a gain for each of the {} data subcarriers, but got {}",
+            num_data_subcarriers,
+            gains.len()
+        );
+    }
+    if let Some(gain) = gains.iter().find(|gain| !gain.is_finite() || **gain < 0.0) {
+        panic!(
+            "Power allocation gains must be finite and non-negative, but got {}",
+            gain
+        );
+    }
+}
+
+/// Panics if DFT spreading is combined with differential mode or selected mapping.
+fn check_dft_spread(dft_spread: bool, differential_time: bool, slm: bool) {
+    if dft_spread && differential_time {
+        panic!("DFT spreading needs coherent demodulation, but got differential_time");
+    }
+    if dft_spread && slm {
+        panic!("DFT spreading does not support selected mapping, but got slm");
+    }
+}
+
+/// Which subcarriers of a symbol carry pilots and data, taken from the modulator or demodulator configuration.
+struct SubcarrierLayout<'a> {
+    pilot_subcarrier_every: u32,
+    guard_subcarriers_low: u32,
+    guard_subcarriers_high: u32,
+    null_dc: bool,
+    reserved_subcarriers: &'a [u32],
+    masked_subcarriers: &'a [u32],
+}
+
+/// The stages of receiving a frame, which a [StageTimer] times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stage {
+    #[cfg(feature = "std")]
+    /// Cutting the bursts of signal out of a stream of samples.
+    Sync,
+    /// Transforming a symbol into its bins.
+    Fft,
+    /// Equalizing the bins into the data subcarrier points.
+    Equalize,
+    /// Demapping the points of a frame into bits or LLRs.
+    Demap,
+    /// Deinterleaving and decoding the bits of a frame into its payload.
+    Fec,
+}
+
+/// Times the stages of receiving a frame, for the metrics of the `perf` feature.
+///
+/// The unit type times nothing, so the paths without metrics compile to the calls alone.
+pub(crate) trait StageTimer {
+    /// Calls `f`, which runs the stage, and returns its result.
+    fn time<R>(&mut self, stage: Stage, f: impl FnOnce() -> R) -> R;
+}
+
+impl StageTimer for () {
+    fn time<R>(&mut self, _stage: Stage, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+impl<T: StageTimer> StageTimer for Option<T> {
+    fn time<R>(&mut self, stage: Stage, f: impl FnOnce() -> R) -> R {
+        match self {
+            Some(timer) => timer.time(stage, f),
+            None => f(),
+        }
+    }
+}
+
+/// The bins of the data subcarriers, checked once to lie within the bins, to pack the points into the bins and out of them.
+///
+/// With sparse pilots most data subcarriers are consecutive, and every run of them is copied like a slice.
+/// Dense pilots leave runs of a few subcarriers, which are packed one by one through the checked bins instead.
+#[derive(Clone, Debug, Default)]
+struct SubcarrierMap {
+    /// The bin of every point, all below `num_bins`.
+    bins: Vec<usize>,
+    /// The runs of consecutive bins, if they are long enough to copy them as slices.
+    runs: Option<Vec<SubcarrierRun>>,
+    num_bins: usize,
+}
+
+/// Consecutive data subcarriers, carrying consecutive points.
+#[derive(Clone, Copy, Debug)]
+struct SubcarrierRun {
+    bin: usize,
+    point: usize,
+    length: usize,
+}
+
+/// Mean length of the runs from which they are copied as slices, below it a copy costs more than it saves.
+const MIN_MEAN_RUN_LENGTH: usize = 8;
+
+impl SubcarrierMap {
+    /// Maps the points to the bins of the data subcarriers, in the order of the points.
+    ///
+    /// # Panics
+    /// If a bin is not below the number of bins.
+    fn new(indices: &[u32], num_bins: usize) -> Self {
+        if let Some(&idx) = indices.iter().find(|&&idx| idx as usize >= num_bins) {
+            panic!(
+                "Data subcarriers must be below {} bins, but got {}",
+                num_bins, idx
+            );
+        }
+        let bins: Vec<usize> = indices.iter().map(|&idx| idx as usize).collect();
+
+        let mut runs: Vec<SubcarrierRun> = Vec::new();
+        for (point, &bin) in bins.iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if run.bin + run.length == bin => run.length += 1,
+                _ => runs.push(SubcarrierRun {
+                    bin,
+                    point,
+                    length: 1,
+                }),
+            }
+        }
+        let runs = (bins.len() >= MIN_MEAN_RUN_LENGTH * runs.len()).then_some(runs);
+
+        SubcarrierMap {
+            bins,
+            runs,
+            num_bins,
+        }
+    }
+
+    /// Calls `f` with every data bin and the value of its point.
+    ///
+    /// # Panics
+    /// If there are not as many bins as the map was made for, or fewer values than points.
+    fn for_each_bin<B, V>(&self, bins: &mut [B], values: &[V], mut f: impl FnMut(&mut B, &V)) {
+        self.check_bins(bins.len());
+        match &self.runs {
+            Some(runs) => {
+                for run in runs {
+                    for (bin, value) in bins[run.bin..][..run.length]
+                        .iter_mut()
+                        .zip(&values[run.point..][..run.length])
+                    {
+                        f(bin, value);
+                    }
+                }
+            }
+            None => {
+                for (&idx, value) in self.bins.iter().zip(&values[..self.bins.len()]) {
+                    // SAFETY: every bin of the map is below num_bins, the length of the bins
+                    f(unsafe { bins.get_unchecked_mut(idx) }, value);
+                }
+            }
+        }
+    }
+
+    /// Copies the points into their data bins.
+    ///
+    /// # Panics
+    /// If there are not as many bins as the map was made for, or fewer points than the map has.
+    fn scatter<V: Copy>(&self, points: &[V], bins: &mut [V]) {
+        match &self.runs {
+            Some(runs) => {
+                self.check_bins(bins.len());
+                for run in runs {
+                    bins[run.bin..][..run.length]
+                        .copy_from_slice(&points[run.point..][..run.length]);
+                }
+            }
+            None => self.for_each_bin(bins, points, |bin, &point| *bin = point),
+        }
+    }
+
+    /// Copies the data bins into their points.
+    ///
+    /// # Panics
+    /// If there are not as many bins as the map was made for, or fewer points than the map has.
+    fn gather<V: Copy>(&self, bins: &[V], points: &mut [V]) {
+        self.check_bins(bins.len());
+        match &self.runs {
+            Some(runs) => {
+                for run in runs {
+                    points[run.point..][..run.length]
+                        .copy_from_slice(&bins[run.bin..][..run.length]);
+                }
+            }
+            None => {
+                for (point, &idx) in points[..self.bins.len()].iter_mut().zip(&self.bins) {
+                    // SAFETY: every bin of the map is below num_bins, the length of the bins
+                    *point = unsafe { *bins.get_unchecked(idx) };
+                }
+            }
+        }
+    }
+
+    fn check_bins(&self, num_bins: usize) {
+        if num_bins != self.num_bins {
+            panic!("Bins must be {}, but got {}", self.num_bins, num_bins);
+        }
+    }
+}
+
+/// Returns the magnitude of the pilots of the QAM order, 1 up to QAM-16 and doubling with every further bit of an axis,
+/// like the outer levels of the points. The mean pilot magnitude the equalizer divides by is biased up by the noise,
+/// which a pilot of 1 would make too large against the levels of QAM-64 and QAM-256.
+pub(crate) fn pilot_magnitude(qam_order: QAMOrder) -> u32 {
+    1 << (qam_order.bits_per_symbol() / 2).saturating_sub(2)
+}
+
+/// Returns the number of the data subcarriers whose points of the order carry whole bytes, with no bits left over,
+/// 4 subcarriers to 3 bytes for QAM-64.
+fn whole_byte_subcarriers(num_data_subcarriers: u32, bits_per_subcarrier: u32) -> u32 {
+    let step = 8 >> bits_per_subcarrier.trailing_zeros().min(3);
+    num_data_subcarriers / step * step
+}
 
 #[allow(dead_code)]
 struct OFDMConstants {
@@ -17,30 +1427,126 @@ struct OFDMConstants {
     cyclic_prefix_length: u32,
 
     data_subcarrier_indices: Vec<u32>,
+    /// The data subcarrier indices as bins, checked to lie within the bins of the FFT.
+    data_subcarrier_map: SubcarrierMap,
     pilot_subcarrier_indices: Vec<u32>,
+    reserved_subcarrier_indices: Vec<u32>,
+    oversampling: u32,
 
     bits_per_subcarrier: u32,
     bits_per_symbol: u32,
+
+    /// The symbols are complex baseband, with one FFT bin per subcarrier.
+    complex: bool,
+    /// What fills the guard interval, the cyclic prefix unless [with_guard](Self::with_guard) says otherwise.
+    guard_type: GuardType,
+    /// Length of the cyclic suffix before the oversampling, none unless [with_guard](Self::with_guard) says otherwise.
+    cyclic_suffix_length: u32,
 }
 impl OFDMConstants {
     fn new(
         num_subcarriers: u32,
-        pilot_subcarrier_every: u32,
         cyclic_prefix_length: u32,
         qam_order: QAMOrder,
-        bits_per_subcarrier: u32,
+        oversampling: u32,
+        layout: SubcarrierLayout,
     ) -> Self {
-        let pilot_subcarrier_indices: Vec<u32> = (1..num_subcarriers)
-            .filter(|&i| i % pilot_subcarrier_every == 0)
-            .collect();
+        let SubcarrierLayout {
+            pilot_subcarrier_every,
+            guard_subcarriers_low: guard_low,
+            guard_subcarriers_high: guard_high,
+            null_dc,
+            reserved_subcarriers,
+            masked_subcarriers,
+        } = layout;
+
+        if ![1, 2, 4].contains(&oversampling) {
+            panic!(
+                "Oversampling factor must be 1, 2 or 4, but got {}",
+                oversampling
+            );
+        }
+
+        // DC can only carry a real pilot and the Nyquist bin is never used, the guard bands lie next to them
+        if guard_low + guard_high + 1 >= num_subcarriers {
+            panic!(
+                "Guard subcarriers must leave subcarriers between them, but got {} low and {} high of {}",
+                guard_low, guard_high, num_subcarriers
+            );
+        }
+        let used_subcarriers = 1 + guard_low..num_subcarriers - guard_high;
+
+        if let Some(&idx) = masked_subcarriers
+            .iter()
+            .find(|&&idx| idx >= num_subcarriers)
+        {
+            panic!(
+                "Masked subcarriers must be below {}, but got {}",
+                num_subcarriers, idx
+            );
+        }
+        let is_masked = |idx: u32| masked_subcarriers.contains(&idx);
+
+        // pilots on masked subcarriers move to the nearest free subcarrier, the lower one first
+        let dc_pilot = (!null_dc && !is_masked(0)).then_some(0);
+        let mut pilot_subcarrier_indices: Vec<u32> = dc_pilot.into_iter().collect();
+        for pilot in used_subcarriers
+            .clone()
+            .filter(|&i| i.is_multiple_of(pilot_subcarrier_every))
+        {
+            let is_free = |idx: u32| {
+                used_subcarriers.contains(&idx)
+                    && !is_masked(idx)
+                    && !idx.is_multiple_of(pilot_subcarrier_every)
+                    && !pilot_subcarrier_indices.contains(&idx)
+            };
+            if !is_masked(pilot) {
+                pilot_subcarrier_indices.push(pilot);
+            } else if let Some(relocated) = (1..pilot_subcarrier_every)
+                .flat_map(|distance| [pilot.wrapping_sub(distance), pilot + distance])
+                .find(|&idx| is_free(idx))
+            {
+                pilot_subcarrier_indices.push(relocated);
+            }
+        }
+        pilot_subcarrier_indices.sort_unstable();
         let num_pilot_subcarriers = pilot_subcarrier_indices.len() as u32;
 
-        let data_subcarrier_indices: Vec<u32> = (1..num_subcarriers)
-            .filter(|&i| i % pilot_subcarrier_every != 0)
+        if let Some(&idx) = reserved_subcarriers.iter().find(|&&idx| {
+            !used_subcarriers.contains(&idx)
+                || is_masked(idx)
+                || pilot_subcarrier_indices.contains(&idx)
+        }) {
+            panic!(
+                "Reserved subcarriers must be data subcarriers between {} and {}, but got {}",
+                used_subcarriers.start,
+                used_subcarriers.end - 1,
+                idx
+            );
+        }
+        let mut reserved_subcarrier_indices = reserved_subcarriers.to_vec();
+        reserved_subcarrier_indices.sort_unstable();
+        reserved_subcarrier_indices.dedup();
+
+        let mut data_subcarrier_indices: Vec<u32> = used_subcarriers
+            .filter(|&i| !is_masked(i))
+            .filter(|i| pilot_subcarrier_indices.binary_search(i).is_err())
+            .filter(|i| reserved_subcarrier_indices.binary_search(i).is_err())
             .collect();
-        let num_data_subcarriers = data_subcarrier_indices.len() as u32;
 
-        let bits_per_symbol = num_data_subcarriers * bits_per_subcarrier;
+        // a symbol carries whole bytes, the subcarriers left over stay empty
+        let bits_per_subcarrier = QAMModem::new(qam_order).bits_per_symbol();
+        data_subcarrier_indices.truncate(whole_byte_subcarriers(
+            data_subcarrier_indices.len() as u32,
+            bits_per_subcarrier,
+        ) as usize);
+        let bits_per_symbol = data_subcarrier_indices.len() as u32 * bits_per_subcarrier;
+        let num_data_subcarriers = data_subcarrier_indices.len() as u32;
+        // the real FFT has a bin from DC up to the Nyquist bin
+        let data_subcarrier_map = SubcarrierMap::new(
+            &data_subcarrier_indices,
+            (num_subcarriers * oversampling) as usize + 1,
+        );
 
         OFDMConstants {
             num_data_subcarriers,
@@ -49,9 +1555,276 @@ impl OFDMConstants {
             num_subcarriers,
             cyclic_prefix_length,
             data_subcarrier_indices,
+            data_subcarrier_map,
+            pilot_subcarrier_indices,
+            reserved_subcarrier_indices,
+            oversampling,
+            bits_per_subcarrier,
+            bits_per_symbol,
+            complex: false,
+            guard_type: GuardType::CyclicPrefix,
+            cyclic_suffix_length: 0,
+        }
+    }
+
+    /// Creates the constants of complex baseband symbols, see [complex::ComplexOFDMConfig].
+    ///
+    /// The subcarriers lie at the frequencies `-num_subcarriers / 2 + 1..num_subcarriers / 2`,
+    /// the guard bands are at both edges, and the indices are the FFT bins, with the negative frequencies
+    /// at the top. Pilots are at the explicit frequencies, or else at the multiples of the pilot interval,
+    /// on both sides of DC.
+    fn new_complex(
+        num_subcarriers: u32,
+        cyclic_prefix_length: u32,
+        qam_order: QAMOrder,
+        (pilot_subcarrier_every, pilot_frequencies): (u32, &[i32]),
+        guard_subcarriers: (u32, u32),
+        null_dc: bool,
+    ) -> Self {
+        if num_subcarriers < 4 || !num_subcarriers.is_multiple_of(2) {
+            panic!(
+                "Number of subcarriers must be even and at least 4, but got {}",
+                num_subcarriers
+            );
+        }
+
+        // the bin at half the sample rate is never used, it is both the highest and the lowest frequency
+        let (guard_low, guard_high) = guard_subcarriers;
+        let half = (num_subcarriers / 2) as i64;
+        if guard_low + guard_high + 2 >= num_subcarriers {
+            panic!(
+                "Guard subcarriers must leave subcarriers between them, but got {} low and {} high of {}",
+                guard_low, guard_high, num_subcarriers
+            );
+        }
+        let used = -half + 1 + guard_low as i64..half - guard_high as i64;
+        let used_frequencies = used
+            .clone()
+            .filter(|&frequency| !(null_dc && frequency == 0));
+        let bin = |frequency: i64| frequency.rem_euclid(num_subcarriers as i64) as u32;
+        for (i, &frequency) in pilot_frequencies.iter().enumerate() {
+            let frequency = i64::from(frequency);
+            if !used.contains(&frequency) || (null_dc && frequency == 0) {
+                panic!(
+                    "Pilot frequencies must be subcarriers in use from {} to {}, but got {}",
+                    used.start,
+                    used.end - 1,
+                    frequency
+                );
+            }
+            if pilot_frequencies[..i].contains(&(frequency as i32)) {
+                panic!(
+                    "Pilot frequencies must be distinct, but got {} twice",
+                    frequency
+                );
+            }
+        }
+
+        let (pilots, data): (Vec<i64>, Vec<i64>) = used_frequencies.partition(|&frequency| {
+            if pilot_frequencies.is_empty() {
+                frequency
+                    .unsigned_abs()
+                    .is_multiple_of(pilot_subcarrier_every as u64)
+            } else {
+                pilot_frequencies.contains(&(frequency as i32))
+            }
+        });
+        let mut pilot_subcarrier_indices: Vec<u32> = pilots.into_iter().map(bin).collect();
+        pilot_subcarrier_indices.sort_unstable();
+        let mut data_subcarrier_indices: Vec<u32> = data.into_iter().map(bin).collect();
+
+        // a symbol carries whole bytes, the subcarriers left over stay empty
+        let bits_per_subcarrier = QAMModem::new(qam_order).bits_per_symbol();
+        data_subcarrier_indices.truncate(whole_byte_subcarriers(
+            data_subcarrier_indices.len() as u32,
+            bits_per_subcarrier,
+        ) as usize);
+        let bits_per_symbol = data_subcarrier_indices.len() as u32 * bits_per_subcarrier;
+
+        OFDMConstants {
+            num_data_subcarriers: data_subcarrier_indices.len() as u32,
+            num_pilot_subcarriers: pilot_subcarrier_indices.len() as u32,
+            qam_order,
+            num_subcarriers,
+            cyclic_prefix_length,
+            data_subcarrier_map: SubcarrierMap::new(
+                &data_subcarrier_indices,
+                num_subcarriers as usize,
+            ),
+            data_subcarrier_indices,
             pilot_subcarrier_indices,
+            reserved_subcarrier_indices: Vec::new(),
+            oversampling: 1,
             bits_per_subcarrier,
             bits_per_symbol,
+            complex: true,
+            guard_type: GuardType::CyclicPrefix,
+            cyclic_suffix_length: 0,
+        }
+    }
+
+    /// Returns the band of the subcarriers in use as fractions of the sample rate,
+    /// from half a subcarrier below the lowest to half a subcarrier above the highest.
+    fn band(&self) -> (f32, f32) {
+        let used = || {
+            self.data_subcarrier_indices
+                .iter()
+                .chain(&self.pilot_subcarrier_indices)
+                .chain(&self.reserved_subcarrier_indices)
+                .map(|&idx| idx as f32)
+        };
+        let lowest = used().fold(f32::INFINITY, f32::min);
+        let highest = used().fold(0.0, f32::max);
+        let spacing = self.fft_length() as f32;
+        ((lowest - 0.5) / spacing, (highest + 0.5) / spacing)
+    }
+
+    /// Returns the number of samples of the FFT window, `2 * num_subcarriers` times the oversampling,
+    /// or `num_subcarriers` for complex symbols.
+    fn fft_length(&self) -> usize {
+        if self.complex {
+            self.num_subcarriers as usize
+        } else {
+            (2 * self.num_subcarriers * self.oversampling) as usize
+        }
+    }
+
+    /// Returns the number of samples of the cyclic prefix at the oversampled rate.
+    fn cyclic_prefix_samples(&self) -> usize {
+        (self.cyclic_prefix_length * self.oversampling) as usize
+    }
+
+    /// Changes the QAM order of the data subcarriers, which keep their indices.
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the order carries the whole bytes of a symbol on a different number
+    /// of data subcarriers, and then the constants are left as they were.
+    fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        let bits_per_subcarrier = QAMModem::new(qam_order).bits_per_symbol();
+        if whole_byte_subcarriers(self.num_data_subcarriers, bits_per_subcarrier)
+            != self.num_data_subcarriers
+        {
+            return Err(ModemError::InvalidConfig);
+        }
+        let bits_per_symbol = self.num_data_subcarriers * bits_per_subcarrier;
+        self.qam_order = qam_order;
+        self.bits_per_subcarrier = bits_per_subcarrier;
+        self.bits_per_symbol = bits_per_symbol;
+        Ok(())
+    }
+
+    /// Returns the number of samples of the cyclic suffix at the oversampled rate.
+    fn cyclic_suffix_samples(&self) -> usize {
+        (self.cyclic_suffix_length * self.oversampling) as usize
+    }
+
+    /// Returns the number of samples of a symbol, including the cyclic prefix and suffix.
+    fn symbol_length(&self) -> usize {
+        self.fft_length() + self.cyclic_prefix_samples() + self.cyclic_suffix_samples()
+    }
+
+    /// Sets what fills the guard interval of the real modem, and the length of its cyclic suffix.
+    ///
+    /// # Panics
+    /// If the suffix is longer than the FFT length before the oversampling.
+    fn with_guard(self, guard_type: GuardType, cyclic_suffix_length: u32) -> Self {
+        if cyclic_suffix_length > 2 * self.num_subcarriers {
+            panic!(
+                "Cyclic suffix must be at most the FFT length of {}, but got {}",
+                2 * self.num_subcarriers,
+                cyclic_suffix_length
+            );
+        }
+        OFDMConstants {
+            guard_type,
+            cyclic_suffix_length,
+            ..self
+        }
+    }
+
+    /// Puts the points of every symbol on the data subcarriers in the order of the permutation.
+    fn with_permutation(self, permutation: SubcarrierPermutation) -> Self {
+        if permutation == SubcarrierPermutation::Identity {
+            return self;
+        }
+        let data_subcarrier_indices: Vec<u32> = permutation
+            .positions(self.data_subcarrier_indices.len())
+            .into_iter()
+            .map(|position| self.data_subcarrier_indices[position])
+            .collect();
+        let data_subcarrier_map =
+            SubcarrierMap::new(&data_subcarrier_indices, self.data_subcarrier_map.num_bins);
+        OFDMConstants {
+            data_subcarrier_indices,
+            data_subcarrier_map,
+            ..self
+        }
+    }
+
+    /// Returns the samples of a symbol the FFT window covers, after the cyclic prefix or before the zero padding.
+    fn body_range(&self) -> core::ops::Range<usize> {
+        match self.guard_type {
+            GuardType::CyclicPrefix => {
+                self.cyclic_prefix_samples()..self.cyclic_prefix_samples() + self.fft_length()
+            }
+            GuardType::ZeroPad => 0..self.fft_length(),
+        }
+    }
+
+    /// Fills the guard interval of a symbol whose body is in place, with the end of the body before it
+    /// and the head of the body after it, or with zeros.
+    fn fill_guard<T: Sample>(&self, symbol: &mut [T]) {
+        let body = self.body_range();
+        match self.guard_type {
+            GuardType::CyclicPrefix => {
+                symbol.copy_within(body.end - self.cyclic_prefix_samples()..body.end, 0);
+                symbol.copy_within(
+                    body.start..body.start + self.cyclic_suffix_samples(),
+                    body.end,
+                );
+            }
+            GuardType::ZeroPad => symbol[body.end..].fill(T::zero()),
+        }
+    }
+
+    /// Copies the body of a received symbol into the input of the FFT, the zero padding,
+    /// with the echoes of the body in it, added back onto its head.
+    fn gather_body<T: Sample>(&self, symbol: &[T], samples: &mut [T]) {
+        samples.copy_from_slice(&symbol[self.body_range()]);
+        if self.guard_type == GuardType::ZeroPad {
+            let padding = &symbol[self.fft_length()..];
+            for (sample, &echo) in samples.iter_mut().zip(padding) {
+                *sample += echo;
+            }
+        }
+    }
+
+    /// Returns the body of a received symbol like [gather_body](Self::gather_body), in place,
+    /// the guard interval is left as it was.
+    fn gather_body_in_place<'a, T: Sample>(&self, symbol: &'a mut [T]) -> &'a mut [T] {
+        match self.guard_type {
+            GuardType::CyclicPrefix => &mut symbol[self.body_range()],
+            GuardType::ZeroPad => {
+                let (body, padding) = symbol.split_at_mut(self.fft_length());
+                for (sample, &echo) in body.iter_mut().zip(padding.iter()) {
+                    *sample += echo;
+                }
+                body
+            }
         }
     }
+
+    /// # Panics
+    /// If the selected mapping is invalid for these subcarriers.
+    fn selected_mapping<T: Sample>(&self, config: &SlmConfig) -> SelectedMapping<T> {
+        SelectedMapping::new(
+            config,
+            self.data_subcarrier_indices.len(),
+            self.pilot_subcarrier_indices.len(),
+        )
+    }
+
+    fn dft_spreading<T: Sample>(&self) -> DftSpreading<T> {
+        DftSpreading::new(self.data_subcarrier_indices.len())
+    }
 }
@@ -1,50 +1,394 @@
-use std::sync::Arc;
+use alloc::{sync::Arc, vec, vec::Vec};
 
-use realfft::{ComplexToReal, num_complex::Complex32};
+use num_complex::{Complex, Complex32};
 use smart_default::SmartDefault;
 
 use crate::{
-    ofdm::OFDMConstants,
-    qam::{QAMModem, QAMOrder},
+    dsp::{FirFilter, Passband, Upconverter},
+    error::{Buffer, ModemError},
+    fft::{RealForwardFft, RealInverseFft, plan_real_forward, plan_real_inverse},
+    metrics::{papr, papr_ccdf},
+    ofdm::{
+        BatchStats, DftSpreading, GuardInterval, GuardType, OFDMConstants, SelectedMapping,
+        SlmConfig, SubcarrierLayout, SubcarrierPermutation, check_buffer_length, check_dft_spread,
+        check_guard_type, check_length, check_pilot_signaling, check_power_allocation,
+        differential_pilot_signs, pilot_magnitude,
+    },
+    qam::{GenericQAMModem, QAMOrder},
+    samples::{Sample, f32_to_i16_into},
 };
 
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
 const PILOT_VALUE_TO_BE_CHANGED: Complex32 = Complex32 { re: 1.0, im: 0.0 };
 
+/// OFDM Modulator computing in `f32`.
+///
+/// See [GenericOFDMModulator] for other sample types.
+pub type OFDMModulator = GenericOFDMModulator<f32>;
+
 /// OFDM Modulator
 ///
 /// With this modulator, you can modulate data into OFDM symbols.
 /// It supports QAM modulation and allows for pilot subcarriers.
 /// The modulator can be configured with the number of subcarriers, cyclic prefix length,
 /// pilot subcarrier interval, and QAM order.
-pub struct OFDMModulator {
-    fft: Arc<dyn ComplexToReal<f32>>,
-    qam_modem: QAMModem,
+/// The symbols are computed in the [sample type](Sample) `T`, see the example there.
+pub struct GenericOFDMModulator<T: Sample> {
+    fft: Arc<dyn RealInverseFft<T>>,
+    qam_modem: GenericQAMModem<T>,
     constants: OFDMConstants,
+    differential_time: bool,
+    window: Vec<T>,
+    clipping: Option<Clipping>,
+    tone_reservation: ToneReservation,
+    slm: Option<SelectedMapping<T>>,
+    pilot_signaling_bits: u32,
+    dft_spreading: Option<DftSpreading<T>>,
+    power_allocation: Option<Vec<T>>,
+    output_scale: OutputScale,
+    tx_gain: T,
+    tx_filter: Option<FirFilter>,
+    upconverter: Option<Upconverter>,
+    forward_fft: Arc<dyn RealForwardFft<T>>,
+    /// Bins of the data, pilot and reserved subcarriers, which keep the clipping noise.
+    in_band: Vec<bool>,
 }
 
-impl OFDMModulator {
+impl<T: Sample> GenericOFDMModulator<T> {
     /// Creates a new OFDM modulator with the given [configuration](OFDMModulatorConfig).
-    pub fn new(config: OFDMModulatorConfig) -> Self {
-        let qam_modem = QAMModem::new(config.qam_order);
+    ///
+    /// # Panics
+    /// If the [guard interval](GuardInterval) is not a whole number of samples, the roll-off is longer than the cyclic prefix,
+    /// the guard subcarriers leave no subcarriers,
+    /// a reserved subcarrier is not a data subcarrier, the [selected mapping](SlmConfig) is invalid,
+    /// the [pilot signaling bits](OFDMModulatorConfig::pilot_signaling_bits) are invalid,
+    /// [DFT spreading](OFDMModulatorConfig::dft_spread) is combined with differential mode or selected mapping,
+    /// [zero padding](GuardType::ZeroPad) is combined with a roll-off,
+    /// the [power allocation](OFDMModulatorConfig::power_allocation) does not have one valid gain per data subcarrier,
+    /// the [output scale](OutputScale) is not positive and finite, the [TX gain](OFDMModulatorConfig::tx_gain_db) is not finite,
+    /// the [passband](OFDMModulatorConfig::passband) does not fit the subcarriers in use, see [Upconverter::new],
+    /// the [FFT](OFDMModulatorConfig::fft) does not have the FFT length, or in [strict headroom](OFDMModulatorConfig::strict_headroom) mode, if the output can exceed full scale.
+    pub fn new(config: OFDMModulatorConfig<T>) -> Self {
+        let cyclic_prefix_length = super::resolve_cyclic_prefix_length(
+            config.guard_interval,
+            config.cyclic_prefix_length,
+            2 * config.num_subcarriers,
+        );
+        if config.roll_off > cyclic_prefix_length {
+            panic!(
+                "Roll-off must be at most the cyclic prefix length of {}, but got {}",
+                cyclic_prefix_length, config.roll_off
+            );
+        }
+
+        if let OutputScale::PeakNormalize(value) | OutputScale::FixedGain(value) =
+            config.output_scale
+            && !(value.is_finite() && value > 0.0)
+        {
+            panic!(
+                "Output scale must be positive and finite, but got {}",
+                value
+            );
+        }
+
+        if !config.tx_gain_db.is_finite() {
+            panic!("TX gain must be finite, but got {} dB", config.tx_gain_db);
+        }
+
+        check_dft_spread(
+            config.dft_spread,
+            config.differential_time,
+            config.slm.is_some(),
+        );
+        check_guard_type(config.guard_type, config.roll_off);
+
+        let qam_modem = GenericQAMModem::new(config.qam_order);
 
         let constants = OFDMConstants::new(
             config.num_subcarriers,
-            config.pilot_subcarrier_every,
-            config.cyclic_prefix_length,
+            cyclic_prefix_length,
             config.qam_order,
-            qam_modem.bits_per_symbol(),
+            config.oversampling,
+            SubcarrierLayout {
+                pilot_subcarrier_every: config.pilot_subcarrier_every,
+                guard_subcarriers_low: config.guard_subcarriers_low,
+                guard_subcarriers_high: config.guard_subcarriers_high,
+                null_dc: config.null_dc,
+                reserved_subcarriers: &config.reserved_subcarriers,
+                masked_subcarriers: &config.masked_subcarriers,
+            },
+        )
+        .with_guard(config.guard_type, config.cyclic_suffix_length)
+        .with_permutation(config.subcarrier_permutation);
+
+        let slm = config.slm.map(|slm| constants.selected_mapping(&slm));
+        check_pilot_signaling(
+            config.pilot_signaling_bits,
+            config.slm,
+            constants.pilot_subcarrier_indices.len(),
         );
+        let dft_spreading = config.dft_spread.then(|| constants.dft_spreading());
+
+        if let Some(gains) = &config.power_allocation {
+            check_power_allocation(gains, constants.num_data_subcarriers as usize);
+        }
 
-        let fft = config.fft.unwrap_or_else(|| {
-            realfft::RealFftPlanner::<f32>::new()
-                .plan_fft_inverse(2 * config.num_subcarriers as usize)
+        let fft = config
+            .fft
+            .unwrap_or_else(|| plan_real_inverse(constants.fft_length()));
+        if fft.fft_length() != constants.fft_length() {
+            panic!(
+                "FFT length must be {}, but got {}",
+                constants.fft_length(),
+                fft.fft_length()
+            );
+        }
+        let forward_fft = plan_real_forward(constants.fft_length());
+        let mut in_band = vec![false; constants.fft_length() / 2 + 1];
+        for &idx in constants
+            .data_subcarrier_indices
+            .iter()
+            .chain(&constants.pilot_subcarrier_indices)
+            .chain(&constants.reserved_subcarrier_indices)
+        {
+            in_band[idx as usize] = true;
+        }
+
+        let upconverter = config.passband.map(|passband| {
+            let (low, high) = constants.band();
+            Upconverter::new(
+                passband.sample_rate,
+                (low * passband.sample_rate, high * passband.sample_rate),
+                passband.carrier_hz,
+            )
         });
 
-        OFDMModulator {
+        let modulator = GenericOFDMModulator {
             fft,
             qam_modem,
             constants,
+            differential_time: config.differential_time,
+            window: raised_cosine_window((config.roll_off * config.oversampling) as usize),
+            clipping: config.clipping,
+            tone_reservation: config.tone_reservation,
+            slm,
+            pilot_signaling_bits: config.pilot_signaling_bits,
+            dft_spreading,
+            power_allocation: config
+                .power_allocation
+                .map(|gains| gains.iter().map(|&gain| T::cast(gain.into())).collect()),
+            output_scale: config.output_scale,
+            tx_gain: T::cast(10.0).powf(T::cast(config.tx_gain_db.into()) / T::cast(20.0)),
+            tx_filter: config.tx_filter,
+            upconverter,
+            forward_fft,
+            in_band,
+        };
+
+        if config.strict_headroom && modulator.get_peak_level_db() > 0.0 {
+            panic!(
+                "Peak level must be at most full scale, but got {} dB, lower the TX gain",
+                modulator.get_peak_level_db()
+            );
         }
+
+        modulator
+    }
+
+    /// Tapers and overlaps consecutive symbols of [symbol length](OFDMModulator::get_symbol_length) samples.
+    ///
+    /// The first [roll-off](OFDMModulatorConfig::roll_off) samples of every cyclic prefix rise with a raised cosine,
+    /// and every symbol is continued cyclically for the roll-off, falling and added onto the start of the next symbol.
+    /// The continuation starts after the [cyclic suffix](OFDMModulatorConfig::cyclic_suffix_length), if there is one.
+    /// The symbols keep their stride, the returned buffer is longer by the roll-off of the last symbol.
+    /// Without roll-off, the symbols are returned as they are.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = |roll_off| OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 32,
+    ///     roll_off,
+    ///     ..Default::default()
+    /// });
+    /// let (rectangular, windowed) = (modulator(0), modulator(16));
+    ///
+    /// let mut symbols = vec![0.0; 2 * rectangular.get_symbol_length()];
+    /// for (symbol, data) in symbols.chunks_exact_mut(160).zip([[0x5a; 24], [0xc3; 24]]) {
+    ///     rectangular.modulate_buffer_as_symbol(&data, symbol);
+    /// }
+    ///
+    /// let output = windowed.apply_window(&symbols);
+    /// assert_eq!(output.len(), symbols.len() + 16);
+    ///
+    /// // only the start of the cyclic prefixes changes, the FFT windows after them are untouched
+    /// assert_ne!(output[..16], symbols[..16]);
+    /// assert_ne!(output[160..176], symbols[160..176]);
+    /// assert_eq!(output[32..160], symbols[32..160]);
+    /// assert_eq!(output[192..320], symbols[192..320]);
+    /// ```
+    ///
+    /// Frames are windowed by the [FrameEncoder](crate::frame::FrameEncoder), and decode as before.
+    /// ```
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::{GuardInterval, OFDMConfig};
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     guard_interval: Some(GuardInterval::Quarter),
+    ///     roll_off: 16,
+    ///     ..Default::default()
+    /// };
+    /// let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    /// let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    ///
+    /// let payload = "Windowed symbols keep their spectrum to themselves".as_bytes();
+    /// let samples = encoder.encode(payload);
+    /// assert_eq!(samples.len(), 3 * 160 + 16);
+    /// assert_eq!(samples.len(), decoder.get_frame_length(payload.len()));
+    /// assert_eq!(&decoder.decode(&samples)[..payload.len()], payload);
+    /// ```
+    ///
+    /// The tapers lower the spectrum beyond the band edge, here in a guard band of 16 subcarriers.
+    /// ```
+    /// use software_modem::frame::FrameEncoder;
+    /// use software_modem::metrics::{SpectrumWindow, oob_power_db, power_spectrum};
+    /// use software_modem::ofdm::{GuardInterval, OFDMConfig};
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let out_of_band_power = |roll_off| {
+    ///     let config = OFDMConfig {
+    ///         num_subcarriers: 64,
+    ///         guard_interval: Some(GuardInterval::Quarter),
+    ///         guard_subcarriers_high: 16,
+    ///         roll_off,
+    ///         ..Default::default()
+    ///     };
+    ///     let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    ///     let payload: Vec<u8> = (0..2000u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    ///     let samples = encoder.encode(&payload);
+    ///
+    ///     // from 8 subcarriers above the band edge up to the Nyquist frequency, subcarrier k is at k / 128
+    ///     let spectrum = power_spectrum(&samples, 1024, SpectrumWindow::Hann);
+    ///     oob_power_db(&spectrum, (0.0, 56.0 / 128.0))
+    /// };
+    ///
+    /// let reduction = out_of_band_power(0) - out_of_band_power(16);
+    /// assert!(reduction >= 15.0, "{reduction} dB");
+    /// ```
+    pub fn apply_window(&self, symbols: &[T]) -> Vec<T> {
+        let symbol_length = self.get_symbol_length();
+        if !symbols.len().is_multiple_of(symbol_length) {
+            panic!(
+                "Number of samples must be a multiple of {}, but got {}",
+                symbol_length,
+                symbols.len()
+            );
+        }
+
+        let roll_off = self.window.len();
+        let body = self.constants.body_range();
+        let cyclic_suffix_length = self.constants.cyclic_suffix_samples();
+        let mut output = symbols.to_vec();
+        if roll_off == 0 {
+            return output;
+        }
+        output.resize(symbols.len() + roll_off, T::zero());
+
+        for (i, symbol) in symbols.chunks_exact(symbol_length).enumerate() {
+            let start = i * symbol_length;
+            for (n, &rising) in self.window.iter().enumerate() {
+                // the continuation of the previous symbol is already added here
+                output[start + n] -= (T::one() - rising) * symbol[n];
+                // the cyclic continuation after the end of the symbol and its suffix
+                let continuation = body.start + (cyclic_suffix_length + n) % body.len();
+                output[start + symbol_length + n] += (T::one() - rising) * symbol[continuation];
+            }
+        }
+
+        output
+    }
+
+    /// Scales a frame of samples as configured by the [output scale](OFDMModulatorConfig::output_scale)
+    /// and the [TX gain](OFDMModulatorConfig::tx_gain_db), and returns the gain.
+    ///
+    /// The whole frame gets a single gain, so its level does not jump between symbols.
+    /// Frames are scaled by the [FrameEncoder](crate::frame::FrameEncoder) after windowing and filtering,
+    /// single symbols from [modulate_buffer_as_symbol](OFDMModulator::modulate_buffer_as_symbol) are left as they are.
+    /// A silent frame is not scaled.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OutputScale};
+    ///
+    /// let config = |output_scale, differential_time| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     differential_time,
+    ///     output_scale,
+    ///     ..Default::default()
+    /// };
+    /// let payload: Vec<u8> = (0..480u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let peak = |samples: &[f32]| samples.iter().map(|x| x.abs()).fold(0.0, f32::max);
+    ///
+    /// for differential_time in [false, true] {
+    ///     // the raw samples go far beyond the full scale of a DAC
+    ///     let raw = FrameEncoder::new(OFDMModulator::new((&config(OutputScale::Raw, differential_time)).into()));
+    ///     let raw_peak = peak(&raw.encode(&payload));
+    ///     assert!(raw_peak > 10.0, "{raw_peak}");
+    ///
+    ///     for output_scale in [OutputScale::PeakNormalize(0.9), OutputScale::FixedGain(0.002)] {
+    ///         let config = config(output_scale, differential_time);
+    ///         let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    ///         let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    ///
+    ///         let samples = encoder.encode(&payload);
+    ///         let expected = match output_scale {
+    ///             OutputScale::PeakNormalize(target) => target,
+    ///             OutputScale::FixedGain(gain) => gain * raw_peak,
+    ///             OutputScale::Raw => raw_peak,
+    ///         };
+    ///         assert!((peak(&samples) - expected).abs() < 1e-5, "{} vs {expected}", peak(&samples));
+    ///         assert!(peak(&samples) <= 0.9);
+    ///
+    ///         // the receiver equalizes the gain away
+    ///         assert_eq!(decoder.decode(&samples), payload);
+    ///     }
+    /// }
+    /// ```
+    pub fn scale_output(&self, samples: &mut [T]) -> T {
+        let gain = self.tx_gain
+            * match self.output_scale {
+                OutputScale::Raw => T::one(),
+                OutputScale::FixedGain(gain) => T::cast(gain.into()),
+                OutputScale::PeakNormalize(target) => {
+                    let peak = samples.iter().map(|x| x.abs()).fold(T::zero(), T::max);
+                    if peak == T::zero() {
+                        return T::one();
+                    }
+                    T::cast(target.into()) / peak
+                }
+            };
+        if gain == T::one() {
+            return gain;
+        }
+
+        for sample in samples.iter_mut() {
+            *sample *= gain;
+        }
+        gain
     }
 
     /// Modulates the given data buffer into an OFDM symbol.
@@ -54,10 +398,11 @@ impl OFDMModulator {
     ///
     /// The length of the output buffer must be double the total length of the OFDM symbol plus the cyclic prefix length.
     /// You can calculate the expected length of the output buffer using `get_symbol_length()`.
+    /// [modulate_symbol](Self::modulate_symbol) allocates it instead, and pads short data.
     ///
     /// # Panics
-    /// If the data length does not match the expected length,
-    /// which is `bits_per_symbol / 8`.
+    /// If the data length does not match the expected length, which is `bits_per_symbol / 8`,
+    /// or the output buffer does not have the symbol length, see [try_modulate_buffer_as_symbol](Self::try_modulate_buffer_as_symbol).
     ///
     /// # Arguments
     /// - `data` - A slice of bytes to be modulated.
@@ -73,7 +418,7 @@ impl OFDMModulator {
     ///   cyclic_prefix_length: 4,
     ///   pilot_subcarrier_every: 4,
     ///   qam_order: QAMOrder::QAM16,
-    ///  fft: None,
+    ///   ..Default::default()
     /// });
     ///
     /// let mut output_buffer = vec![0.0; ofdm_modulator.get_symbol_length()];
@@ -81,7 +426,221 @@ impl OFDMModulator {
     ///
     /// ofdm_modulator.modulate_buffer_as_symbol(&data_buffer, &mut output_buffer);
     /// ```
-    pub fn modulate_buffer_as_symbol(&self, data: &[u8], output_buffer: &mut [f32]) {
+    pub fn modulate_buffer_as_symbol(&self, data: &[u8], output_buffer: &mut [T]) {
+        if let Err(error) = self.try_modulate_buffer_as_symbol(data, output_buffer) {
+            panic!("{}", error);
+        }
+    }
+
+    /// Modulates the given data buffer into an OFDM symbol like [modulate_buffer_as_symbol](Self::modulate_buffer_as_symbol),
+    /// returning an error instead of panicking when a buffer has the wrong length.
+    ///
+    /// Both lengths are checked before anything is modulated, so the output is left untouched on an error.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLengthMismatch] with [Buffer::Input] if the data does not have the
+    /// [bytes per symbol](Self::get_bytes_per_symbol), or with [Buffer::Output] if the output buffer does not have
+    /// the [symbol length](Self::get_symbol_length).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::{Buffer, ModemError};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let modulator = OFDMModulator::new((&OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// }).into());
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// assert_eq!(modulator.try_modulate_buffer_as_symbol(&[0x5a; 24], &mut symbol), Ok(()));
+    ///
+    /// // a byte short of the capacity of a symbol
+    /// assert_eq!(
+    ///     modulator.try_modulate_buffer_as_symbol(&[0x5a; 23], &mut symbol),
+    ///     Err(ModemError::BufferLengthMismatch { buffer: Buffer::Input, expected: 24, got: 23 })
+    /// );
+    /// // the symbol without its cyclic prefix
+    /// assert_eq!(
+    ///     modulator.try_modulate_buffer_as_symbol(&[0x5a; 24], &mut symbol[..128]),
+    ///     Err(ModemError::BufferLengthMismatch { buffer: Buffer::Output, expected: 132, got: 128 })
+    /// );
+    /// ```
+    pub fn try_modulate_buffer_as_symbol(
+        &self,
+        data: &[u8],
+        output_buffer: &mut [T],
+    ) -> Result<(), ModemError> {
+        check_buffer_length(Buffer::Input, self.get_bytes_per_symbol(), data.len())?;
+        check_buffer_length(
+            Buffer::Output,
+            self.get_symbol_length(),
+            output_buffer.len(),
+        )?;
+        self.modulate_buffer_as_symbol_with_scratch(data, &mut self.make_scratch(), output_buffer);
+        Ok(())
+    }
+
+    /// Modulates the data into a new OFDM symbol, padding it with zeros to the [bytes per symbol](Self::get_bytes_per_symbol).
+    ///
+    /// The convenient sibling of [modulate_buffer_as_symbol](Self::modulate_buffer_as_symbol) for scripts, tests and examples,
+    /// which allocates the symbol and the scratch on every call.
+    /// A transmitter modulating symbol after symbol fills its own buffers with
+    /// [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch) instead.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLengthMismatch] with [Buffer::Input] if the data is longer than the bytes per symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::{Buffer, ModemError};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let symbol = modulator.modulate_symbol(b"Hello, OFDM!").unwrap();
+    /// assert_eq!(symbol.len(), modulator.get_symbol_length());
+    ///
+    /// let demodulated = OFDMDemodulator::new((&config).into()).demodulate_symbol_from_buffer(&symbol);
+    /// assert_eq!(demodulated[..12], *b"Hello, OFDM!");
+    /// assert!(demodulated[12..].iter().all(|&byte| byte == 0));
+    ///
+    /// assert_eq!(
+    ///     modulator.modulate_symbol(&[0x5a; 25]),
+    ///     Err(ModemError::BufferLengthMismatch { buffer: Buffer::Input, expected: 24, got: 25 })
+    /// );
+    /// ```
+    pub fn modulate_symbol(&self, data: &[u8]) -> Result<Vec<T>, ModemError> {
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        if data.len() > bytes_per_symbol {
+            return Err(ModemError::BufferLengthMismatch {
+                buffer: Buffer::Input,
+                expected: bytes_per_symbol,
+                got: data.len(),
+            });
+        }
+
+        let mut padded = vec![0; bytes_per_symbol];
+        padded[..data.len()].copy_from_slice(data);
+        let mut symbol = vec![T::zero(); self.get_symbol_length()];
+        self.modulate_buffer_as_symbol(&padded, &mut symbol);
+        Ok(symbol)
+    }
+
+    /// Modulates the given data buffer into an OFDM symbol like [modulate_buffer_as_symbol](Self::modulate_buffer_as_symbol),
+    /// without allocating.
+    ///
+    /// The scratch holds the intermediate buffers and the scratch space of the FFTs, so a transmitter modulating symbol
+    /// after symbol makes it once with [make_scratch](Self::make_scratch) and reuses it.
+    /// Clipping, tone reservation and selected mapping work in the scratch too.
+    ///
+    /// # Panics
+    /// If the data length does not match the expected length, the output buffer does not have the symbol length,
+    /// or the scratch was made by a modulator of another configuration.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let data: Vec<u8> = (0..4 * modulator.get_bytes_per_symbol() as u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+    ///     .collect();
+    ///
+    /// // one scratch for all symbols
+    /// let mut scratch = modulator.make_scratch();
+    /// let mut symbols = vec![0.0; 4 * modulator.get_symbol_length()];
+    /// for (data, symbol) in data
+    ///     .chunks(modulator.get_bytes_per_symbol())
+    ///     .zip(symbols.chunks_exact_mut(modulator.get_symbol_length()))
+    /// {
+    ///     modulator.modulate_buffer_as_symbol_with_scratch(data, &mut scratch, symbol);
+    /// }
+    ///
+    /// let mut expected = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data[..modulator.get_bytes_per_symbol()], &mut expected);
+    /// assert_eq!(symbols[..modulator.get_symbol_length()], expected);
+    /// ```
+    pub fn modulate_buffer_as_symbol_with_scratch(
+        &self,
+        data: &[u8],
+        scratch: &mut ModulatorScratch<T>,
+        output_buffer: &mut [T],
+    ) {
+        self.modulate_data(data, 0, scratch, output_buffer);
+    }
+
+    /// Modulates the given data buffer into an OFDM symbol like
+    /// [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch),
+    /// with the signaling value on the signs of its pilots, see [OFDMModulatorConfig::pilot_signaling_bits].
+    ///
+    /// The demodulator recovers the value with
+    /// [demodulate_symbol_with_signaling](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_symbol_with_signaling).
+    ///
+    /// # Panics
+    /// If the value does not fit the signaling bits, or like
+    /// [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     pilot_signaling_bits: 2,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    ///
+    /// // the last symbol of a burst, with a power control command of one step down
+    /// let data = [0x5a; 24];
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol_with_signaling(&data, 0b11, &mut modulator.make_scratch(), &mut symbol);
+    /// symbol.iter_mut().for_each(|sample| *sample *= 0.5);
+    ///
+    /// let (demodulated, signaling) = demodulator.demodulate_symbol_with_signaling(&symbol);
+    /// assert_eq!(demodulated, data);
+    /// assert_eq!(signaling.value, 0b11);
+    /// ```
+    pub fn modulate_buffer_as_symbol_with_signaling(
+        &self,
+        data: &[u8],
+        signaling: u8,
+        scratch: &mut ModulatorScratch<T>,
+        output_buffer: &mut [T],
+    ) {
+        if u32::from(signaling) >> self.pilot_signaling_bits != 0 {
+            panic!(
+                "Signaling value must fit {} bits, but got {}",
+                self.pilot_signaling_bits, signaling
+            );
+        }
+        self.modulate_data(data, signaling, scratch, output_buffer);
+    }
+
+    /// Maps the data to points in the scratch and modulates them, with the signaling value on the pilots.
+    fn modulate_data(
+        &self,
+        data: &[u8],
+        signaling: u8,
+        scratch: &mut ModulatorScratch<T>,
+        output_buffer: &mut [T],
+    ) {
         if data.len() != ((self.constants.bits_per_symbol / 8) as usize) {
             panic!(
                 "Data length must be {} bytes, but got {} bytes",
@@ -90,71 +649,1559 @@ impl OFDMModulator {
             );
         }
 
-        let qam_symbols = self.qam_modem.modulate(data);
+        // the points need the scratch too, they are moved out while the symbol is transformed
+        let mut points = core::mem::take(&mut scratch.points);
+        if points.len() != self.get_num_data_subcarriers() {
+            panic!(
+                "Scratch must be made for {} data subcarriers, but got {}",
+                self.get_num_data_subcarriers(),
+                points.len()
+            );
+        }
+        self.qam_modem.modulate_into(data, &mut points);
+        self.modulate_ofdm_symbol(&points, signaling, scratch, output_buffer);
+        scratch.points = points;
+    }
 
-        self.modulate_ofdm_symbol(qam_symbols, output_buffer)
-            .unwrap();
+    /// Modulates the given data buffer into an OFDM symbol like [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch),
+    /// returning an error instead of panicking, for a real-time thread like an audio callback.
+    ///
+    /// Once the scratch is made, the call neither allocates nor takes a lock, and it does not panic for any data.
+    /// This holds for every configuration with the default FFT, or an FFT which does the same,
+    /// and only for this call: the [FrameEncoder](crate::frame::FrameEncoder) allocates per frame.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the data does not have the bytes per symbol or the output the symbol length,
+    /// [ModemError::ScratchMismatch] if the scratch was made by a modulator of another configuration.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ModemError;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// // made before the callback starts
+    /// let mut scratch = modulator.make_scratch();
+    /// let mut symbol = [0.0; 132];
+    ///
+    /// assert_eq!(modulator.try_modulate_buffer_as_symbol_with_scratch(&[0x5a; 24], &mut scratch, &mut symbol), Ok(()));
+    /// assert_eq!(
+    ///     modulator.try_modulate_buffer_as_symbol_with_scratch(&[0x5a; 23], &mut scratch, &mut symbol),
+    ///     Err(ModemError::BufferLength { expected: 24, got: 23 })
+    /// );
+    ///
+    /// // the scratch of a modulator with more subcarriers does not fit
+    /// let mut other = OFDMModulator::new((&OFDMConfig { num_subcarriers: 128, ..config }).into()).make_scratch();
+    /// assert_eq!(
+    ///     modulator.try_modulate_buffer_as_symbol_with_scratch(&[0x5a; 24], &mut other, &mut symbol),
+    ///     Err(ModemError::ScratchMismatch)
+    /// );
+    /// ```
+    pub fn try_modulate_buffer_as_symbol_with_scratch(
+        &self,
+        data: &[u8],
+        scratch: &mut ModulatorScratch<T>,
+        output_buffer: &mut [T],
+    ) -> Result<(), ModemError> {
+        check_length(self.get_bytes_per_symbol(), data.len())?;
+        check_length(self.get_symbol_length(), output_buffer.len())?;
+        if !self.fits(scratch) {
+            return Err(ModemError::ScratchMismatch);
+        }
+        self.modulate_buffer_as_symbol_with_scratch(data, scratch, output_buffer);
+        Ok(())
     }
 
-    fn modulate_ofdm_symbol(
+    /// Modulates one point per data subcarrier into an OFDM symbol, bypassing the QAM mapper.
+    ///
+    /// The points take the place of the QAM points of [modulate_buffer_as_symbol](Self::modulate_buffer_as_symbol)
+    /// and go the rest of its way: the DFT spreading, selected mapping, power allocation, pilots, the inverse FFT,
+    /// tone reservation, the cyclic prefix and clipping, as the configuration asks for them.
+    /// Points of the QAM mapper give the same symbol to the last bit, any others a custom constellation,
+    /// a precoded symbol or a sounding waveform. It allocates a scratch on every call,
+    /// see [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch).
+    ///
+    /// # Errors
+    /// [ModemError::BufferLengthMismatch] with [Buffer::Input] if there is not one point per
+    /// [data subcarrier](Self::get_num_data_subcarriers), or with [Buffer::Output] if the output does not have
+    /// the [symbol length](Self::get_symbol_length).
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    ///
+    /// // a sounding symbol of points of unit magnitude with a quadratic phase
+    /// let points: Vec<Complex32> = (0..modulator.get_num_data_subcarriers())
+    ///     .map(|k| Complex32::from_polar(1.0, 0.1 * (k * k) as f32))
+    ///     .collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_freq_symbols(&points, &mut symbol).unwrap();
+    ///
+    /// let received = demodulator.demodulate_to_symbols(&symbol).unwrap();
+    /// for (point, sent) in received.data.iter().zip(&points) {
+    ///     assert!((point - sent).norm() < 1e-3);
+    /// }
+    /// ```
+    pub fn modulate_freq_symbols(
         &self,
-        qam_symbols: Vec<realfft::num_complex::Complex<f32>>,
-        output: &mut [f32],
-    ) -> Result<(), String> {
-        // data prep
-        let mut input: Vec<realfft::num_complex::Complex<f32>> = self.fft.make_input_vec();
+        symbols: &[Complex<T>],
+        output: &mut [T],
+    ) -> Result<(), ModemError> {
+        check_buffer_length(
+            Buffer::Input,
+            self.get_num_data_subcarriers(),
+            symbols.len(),
+        )?;
+        check_buffer_length(Buffer::Output, self.get_symbol_length(), output.len())?;
+        self.modulate_ofdm_symbol(symbols, 0, &mut self.make_scratch(), output);
+        Ok(())
+    }
 
-        for (i, &idx) in self.constants.data_subcarrier_indices.iter().enumerate() {
-            input[idx as usize] = qam_symbols[i];
+    /// Modulates every complete symbol of data and appends the samples to the output.
+    ///
+    /// Like calling [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch)
+    /// for every symbol, with one scratch and the output grown once for all of them.
+    /// The data after the last complete symbol is left untouched, the [stats](BatchStats) tell how many bytes there are.
+    /// The symbols are neither windowed nor framed, see the [FrameEncoder](crate::frame::FrameEncoder) for frames.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::{BatchStats, OFDMConfig};
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let modulator = OFDMModulator::new((&OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// }).into());
+    /// let data = vec![0x5a; 100];
+    /// let mut samples = Vec::new();
+    /// let stats = modulator.modulate_batch(&data, &mut samples);
+    /// assert_eq!(stats, BatchStats { symbols: 4, consumed: 96, trailing: 4 });
+    /// assert_eq!(samples.len(), 4 * modulator.get_symbol_length());
+    /// ```
+    pub fn modulate_batch(&self, data: &[u8], output: &mut Vec<T>) -> BatchStats {
+        let symbol_length = self.get_symbol_length();
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        let symbols = data.chunks_exact(bytes_per_symbol);
+        let stats = BatchStats {
+            symbols: symbols.len(),
+            consumed: symbols.len() * bytes_per_symbol,
+            trailing: symbols.remainder().len(),
+        };
+
+        let start = output.len();
+        output.resize(start + stats.symbols * symbol_length, T::zero());
+        let mut scratch = self.make_scratch();
+        for (data, output) in symbols.zip(output[start..].chunks_exact_mut(symbol_length)) {
+            self.modulate_buffer_as_symbol_with_scratch(data, &mut scratch, output);
         }
+        stats
+    }
 
-        for &idx in &self.constants.pilot_subcarrier_indices {
-            input[idx as usize] = PILOT_VALUE_TO_BE_CHANGED;
+    /// Makes the buffers to modulate symbols in, see [modulate_buffer_as_symbol_with_scratch](Self::modulate_buffer_as_symbol_with_scratch).
+    pub fn make_scratch(&self) -> ModulatorScratch<T> {
+        let fft_length = self.constants.fft_length();
+        ModulatorScratch {
+            points: vec![Complex::default(); self.get_num_data_subcarriers()],
+            spread: vec![Complex::default(); self.spread_len()],
+            bins: vec![Complex::default(); fft_length / 2 + 1],
+            candidate: vec![T::zero(); fft_length],
+            fft: vec![Complex::default(); self.fft_scratch_len()],
+            excess: vec![T::zero(); fft_length],
+            cancellation: vec![Complex::default(); fft_length / 2 + 1],
+            correction: vec![T::zero(); fft_length],
         }
+    }
 
-        let mut output_buffer = self.fft.make_output_vec();
+    /// Returns whether the scratch was made by a modulator of this configuration.
+    fn fits(&self, scratch: &ModulatorScratch<T>) -> bool {
+        let fft_length = self.constants.fft_length();
+        scratch.points.len() == self.get_num_data_subcarriers()
+            && scratch.spread.len() == self.spread_len()
+            && scratch.bins.len() == fft_length / 2 + 1
+            && scratch.candidate.len() == fft_length
+            && scratch.fft.len() == self.fft_scratch_len()
+            && scratch.excess.len() == fft_length
+            && scratch.cancellation.len() == fft_length / 2 + 1
+            && scratch.correction.len() == fft_length
+    }
+
+    /// Returns the scratch space the FFTs share, enough for any of them.
+    fn fft_scratch_len(&self) -> usize {
+        self.fft
+            .get_scratch_len()
+            .max(self.forward_fft.get_scratch_len())
+            .max(
+                self.dft_spreading
+                    .as_ref()
+                    .map_or(0, DftSpreading::scratch_len),
+            )
+    }
+
+    /// Returns the number of spread points of a symbol, none without DFT spreading.
+    fn spread_len(&self) -> usize {
+        if self.dft_spreading.is_some() {
+            self.get_num_data_subcarriers()
+        } else {
+            0
+        }
+    }
+
+    /// Maps one point per data subcarrier to the time domain, inserting pilots carrying the signaling value
+    /// and the cyclic prefix.
+    ///
+    /// # Panics
+    /// If the output does not have the symbol length, or the scratch was made by a modulator of another configuration.
+    pub(crate) fn modulate_ofdm_symbol(
+        &self,
+        qam_symbols: &[Complex<T>],
+        signaling: u8,
+        scratch: &mut ModulatorScratch<T>,
+        output: &mut [T],
+    ) {
+        if output.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                output.len()
+            );
+        }
+        if scratch.bins.len() != self.constants.fft_length() / 2 + 1
+            || scratch.fft.len() != self.fft_scratch_len()
+        {
+            panic!(
+                "Scratch must be made for an FFT length of {}, but got {}",
+                self.constants.fft_length(),
+                2 * (scratch.bins.len() - 1)
+            );
+        }
+
+        // the spread points are moved out of the scratch while the symbol is transformed
+        let mut spread = core::mem::take(&mut scratch.spread);
+        let qam_symbols = match &self.dft_spreading {
+            None => qam_symbols,
+            Some(spreading) => {
+                spread.copy_from_slice(qam_symbols);
+                spreading.spread(&mut spread, &mut scratch.fft);
+                &spread
+            }
+        };
+
+        // the symbol is transformed right behind the cyclic prefix, which is copied from its end,
+        // or right before the zero padding
+        let body = &mut output[self.constants.body_range()];
+        match &self.slm {
+            None => self.transform_candidate(
+                qam_symbols,
+                signaling,
+                None,
+                &mut scratch.bins,
+                &mut scratch.fft,
+                body,
+            ),
+            Some(slm) => {
+                // keep the candidate with the lowest peak, the mean power is the same for all
+                let mut lowest_peak = T::infinity();
+                for index in 0..slm.candidates() {
+                    self.transform_candidate(
+                        qam_symbols,
+                        signaling,
+                        Some((slm, index)),
+                        &mut scratch.bins,
+                        &mut scratch.fft,
+                        &mut scratch.candidate,
+                    );
+                    let peak = scratch
+                        .candidate
+                        .iter()
+                        .map(|x| x.abs())
+                        .fold(T::zero(), T::max);
+                    if peak < lowest_peak {
+                        lowest_peak = peak;
+                        body.copy_from_slice(&scratch.candidate);
+                    }
+                }
+            }
+        }
+        scratch.spread = spread;
+
+        if !self.constants.reserved_subcarrier_indices.is_empty() {
+            self.reserve_tones(body, scratch);
+        }
+
+        self.constants.fill_guard(output);
+
+        if let Some(clipping) = &self.clipping {
+            self.clip_and_filter_in(output, clipping, scratch);
+        }
+    }
+
+    /// Maps the points and pilots of one symbol to the time domain, without the cyclic prefix,
+    /// rotated by the phase sequence of the selected mapping candidate, the pilots flipped by the signaling value.
+    ///
+    /// The bins are mapped in `input`, and transformed into the FFT length samples of the output.
+    fn transform_candidate(
+        &self,
+        qam_symbols: &[Complex<T>],
+        signaling: u8,
+        candidate: Option<(&SelectedMapping<T>, usize)>,
+        input: &mut [Complex<T>],
+        fft: &mut [Complex<T>],
+        output: &mut [T],
+    ) {
+        input.fill(Complex::default());
+
+        let map = &self.constants.data_subcarrier_map;
+        map.scatter(qam_symbols, input);
+        if let Some(gains) = &self.power_allocation {
+            map.for_each_bin(input, gains, |bin, &gain| *bin *= gain);
+        }
+
+        let pilots = &self.constants.pilot_subcarrier_indices;
+        let pilot = Complex::new(
+            T::cast(PILOT_VALUE_TO_BE_CHANGED.re.into()),
+            T::cast(PILOT_VALUE_TO_BE_CHANGED.im.into()),
+        ) * T::cast(pilot_magnitude(self.constants.qam_order).into());
+        let signs = differential_pilot_signs::<T>(
+            signaling.into(),
+            self.pilot_signaling_bits,
+            pilots.len(),
+        );
+        for (&idx, sign) in pilots.iter().zip(signs) {
+            input[idx as usize] = pilot * sign;
+        }
+
+        if let Some((slm, index)) = candidate {
+            map.for_each_bin(input, slm.phases(index), |bin, phase| *bin *= phase);
+            for (&idx, sign) in pilots.iter().zip(slm.pilot_signs(index, pilots.len())) {
+                input[idx as usize] *= sign;
+            }
+        }
 
         // frequency domain to time domain
-        self.fft.process(&mut input, &mut output_buffer).unwrap();
+        let scratch_len = self.fft.get_scratch_len();
+        self.fft
+            .process_with_scratch(input, output, &mut fft[..scratch_len]);
+    }
+
+    /// Reduces the peak-to-average power ratio of a symbol by clipping and filtering.
+    ///
+    /// The samples after the cyclic prefix are clipped at the threshold above their RMS,
+    /// then the clipping noise outside the data and pilot subcarriers is removed in the frequency domain.
+    /// Filtering lets the peaks grow back a little, which further iterations clip again.
+    /// The cyclic prefix is copied from the result.
+    ///
+    /// Returns the achieved PAPR and the error the clipping left on the data subcarriers.
+    /// Symbols are clipped automatically if the modulator is configured with [clipping](OFDMModulatorConfig::clipping).
+    ///
+    /// # Panics
+    /// If the symbol length does not match the expected length.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::metrics::count_bit_errors;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{Clipping, OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// });
+    /// let clipping = Clipping {
+    ///     threshold_db: 6.0,
+    ///     iterations: 2,
+    /// };
+    ///
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// let (mut errors, mut num_bits) = (0, 0);
+    /// for i in 0..20u32 {
+    ///     let data: Vec<u8> = (0..24u32).map(|j| ((i * 24 + j).wrapping_mul(2654435761) >> 11) as u8).collect();
+    ///     modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+    ///
+    ///     let report = modulator.clip_and_filter(&mut symbol, &clipping);
+    ///     assert!(report.papr_db <= 7.0, "{report:?}");
+    ///     assert!(report.original_papr_db > report.papr_db);
+    ///     assert!(report.evm_db < -12.0, "{report:?}");
+    ///
+    ///     let stats = count_bit_errors(&data, &demodulator.demodulate_symbol_from_buffer(&symbol));
+    ///     errors += stats.bit_errors;
+    ///     num_bits += stats.num_bits;
+    /// }
+    ///
+    /// // without noise, the clipping alone flips less than 1 % of the bits
+    /// assert!(errors * 100 < num_bits, "{errors} of {num_bits}");
+    /// ```
+    pub fn clip_and_filter(&self, symbol: &mut [T], clipping: &Clipping) -> ClippingReport {
+        if symbol.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                symbol.len()
+            );
+        }
 
-        // add cp
-        output[self.constants.cyclic_prefix_length as usize..].copy_from_slice(&output_buffer);
+        self.clip_and_filter_in(symbol, clipping, &mut self.make_scratch())
+    }
+
+    /// Clips and filters a symbol of the symbol length like [clip_and_filter](Self::clip_and_filter),
+    /// in the buffers of the scratch.
+    fn clip_and_filter_in(
+        &self,
+        symbol: &mut [T],
+        clipping: &Clipping,
+        scratch: &mut ModulatorScratch<T>,
+    ) -> ClippingReport {
+        let ModulatorScratch {
+            bins,
+            fft,
+            excess: time,
+            cancellation: original,
+            ..
+        } = scratch;
+        let body = &mut symbol[self.constants.body_range()];
+        let scale = T::one() / T::cast(body.len() as f64);
 
-        output[..self.constants.cyclic_prefix_length as usize].copy_from_slice(
-            &output_buffer
-                [(output_buffer.len() - (self.constants.cyclic_prefix_length as usize))..],
+        time.copy_from_slice(body);
+        self.forward_fft.process_with_scratch(
+            time,
+            original,
+            &mut fft[..self.forward_fft.get_scratch_len()],
         );
 
-        Ok(())
+        let original_papr_db = papr(body);
+        let rms = (body.iter().map(|&x| x * x).sum::<T>() * scale).sqrt();
+        let limit = rms * T::cast(10.0).powf(T::cast(clipping.threshold_db.into()) / T::cast(20.0));
+
+        for _ in 0..clipping.iterations {
+            for sample in body.iter_mut() {
+                *sample = sample.clamp(-limit, limit);
+            }
+
+            time.copy_from_slice(body);
+            self.forward_fft.process_with_scratch(
+                time,
+                bins,
+                &mut fft[..self.forward_fft.get_scratch_len()],
+            );
+            for (bin, &in_band) in bins.iter_mut().zip(&self.in_band) {
+                if !in_band {
+                    *bin = Complex::default();
+                }
+            }
+            self.fft
+                .process_with_scratch(bins, body, &mut fft[..self.fft.get_scratch_len()]);
+            for sample in body.iter_mut() {
+                *sample *= scale;
+            }
+        }
+
+        let (error, signal) = {
+            time.copy_from_slice(body);
+            self.forward_fft.process_with_scratch(
+                time,
+                bins,
+                &mut fft[..self.forward_fft.get_scratch_len()],
+            );
+            self.constants
+                .data_subcarrier_indices
+                .iter()
+                .map(|&idx| (bins[idx as usize], original[idx as usize]))
+                .fold(
+                    (T::zero(), T::zero()),
+                    |(error, signal), (clipped, original)| {
+                        (
+                            error + (clipped - original).norm_sqr(),
+                            signal + original.norm_sqr(),
+                        )
+                    },
+                )
+        };
+
+        let papr_db = papr(body);
+        self.constants.fill_guard(symbol);
+
+        ClippingReport {
+            original_papr_db,
+            papr_db,
+            evm_db: 10.0 * (error / signal).into_f32().log10(),
+        }
+    }
+
+    /// Lowers the peaks of a symbol without a cyclic prefix by modulating the reserved subcarriers.
+    ///
+    /// Every iteration clips the samples at the threshold above their RMS,
+    /// projects the clipped excess onto the reserved subcarriers and subtracts it,
+    /// so the data and pilot subcarriers stay untouched.
+    fn reserve_tones(&self, body: &mut [T], scratch: &mut ModulatorScratch<T>) {
+        let ModulatorScratch {
+            bins,
+            fft,
+            excess,
+            cancellation,
+            correction,
+            ..
+        } = scratch;
+        let scale = T::one() / T::cast(body.len() as f64);
+        let reserved = &self.constants.reserved_subcarrier_indices;
+        // the projection only keeps a fraction of the excess, larger steps diverge
+        let step = T::cast(2.0);
+
+        let rms = (body.iter().map(|&x| x * x).sum::<T>() * scale).sqrt();
+        let limit = rms
+            * T::cast(10.0)
+                .powf(T::cast(self.tone_reservation.threshold_db.into()) / T::cast(20.0));
+
+        let forward_scratch_len = self.forward_fft.get_scratch_len();
+        let inverse_scratch_len = self.fft.get_scratch_len();
+        for _ in 0..self.tone_reservation.iterations {
+            for (excess, &sample) in excess.iter_mut().zip(body.iter()) {
+                *excess = sample - sample.clamp(-limit, limit);
+            }
+            if excess.iter().all(|&x| x == T::zero()) {
+                break;
+            }
+
+            self.forward_fft
+                .process_with_scratch(excess, bins, &mut fft[..forward_scratch_len]);
+            cancellation.fill(Complex::default());
+            for &idx in reserved {
+                cancellation[idx as usize] = bins[idx as usize];
+            }
+            self.fft.process_with_scratch(
+                cancellation,
+                correction,
+                &mut fft[..inverse_scratch_len],
+            );
+            for (sample, correction) in body.iter_mut().zip(correction.iter()) {
+                *sample -= step * scale * *correction;
+            }
+        }
+    }
+
+    /// Modulates `num_symbols` symbols of pseudo random data and returns their [PAPR CCDF](papr_ccdf).
+    ///
+    /// The symbols go through the configured PAPR reduction, and are measured without their cyclic prefix.
+    /// The data is the same on every call, so configurations can be compared on equal terms.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::modulator::{Clipping, OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = |clipping| OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     clipping,
+    ///     ..Default::default()
+    /// });
+    /// let thresholds = [6.0, 8.0, 10.0];
+    ///
+    /// let plain = modulator(None).measure_papr_ccdf(500, &thresholds);
+    /// let clipped = modulator(Some(Clipping::default())).measure_papr_ccdf(500, &thresholds);
+    /// assert!(plain[2] > 0.1, "{plain:?}");
+    /// assert_eq!(clipped[1..], [0.0, 0.0], "{clipped:?}");
+    /// assert!(clipped.iter().zip(&plain).all(|(clipped, plain)| clipped <= plain));
+    /// ```
+    pub fn measure_papr_ccdf(&self, num_symbols: usize, thresholds_db: &[f32]) -> Vec<f32> {
+        let body = self.constants.body_range();
+        let mut data = vec![0; self.get_bytes_per_symbol()];
+        let mut state: u32 = 0x2545_f491;
+        let mut symbols = vec![T::zero(); num_symbols * self.get_symbol_length()];
+        let mut scratch = self.make_scratch();
+        for symbol in symbols.chunks_exact_mut(self.get_symbol_length()) {
+            for byte in data.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                *byte = (state >> 24) as u8;
+            }
+            self.modulate_buffer_as_symbol_with_scratch(&data, &mut scratch, symbol);
+        }
+
+        papr_ccdf(
+            symbols
+                .chunks_exact(self.get_symbol_length())
+                .map(|symbol| &symbol[body.clone()]),
+            thresholds_db,
+        )
+    }
+
+    /// Returns the worst-case PAPR of a symbol in dB, the headroom the output needs above its RMS level.
+    ///
+    /// The worst case is a symbol whose data subcarriers all carry a corner of the constellation,
+    /// adding up in phase with the pilots. Real symbols hardly come close, but never exceed it,
+    /// also not with windowing, selected mapping or [power allocation](OFDMModulatorConfig::power_allocation).
+    /// With [DFT spreading](OFDMModulatorConfig::dft_spread), the spread points can at most add up to one peak
+    /// of the power of all points, which is never above the worst case without it.
+    /// Clipping and tone reservation lower the actual peaks, but do not bound them, so they are not taken into account.
+    pub fn required_headroom_db(&self) -> f32 {
+        let (peak, power) = self.worst_case_peak();
+        20.0 * (peak / power.sqrt()).log10()
     }
 
-    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    /// Returns the worst-case peak of the scaled output relative to full scale in dB, see [required_headroom_db](OFDMModulator::required_headroom_db).
+    ///
+    /// The output never exceeds `[-1, 1]` if this is at most 0 dB.
+    /// It includes the [output scale](OFDMModulatorConfig::output_scale) and the [TX gain](OFDMModulatorConfig::tx_gain_db),
+    /// so `tx_gain_db = -get_peak_level_db()` of a modulator without TX gain just fits full scale, up to rounding.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::FrameEncoder;
+    /// use software_modem::metrics::papr;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let config = |tx_gain_db| OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     tx_gain_db,
+    ///     strict_headroom: true,
+    ///     ..Default::default()
+    /// };
+    /// let uncalibrated = OFDMModulator::new(OFDMModulatorConfig {
+    ///     strict_headroom: false,
+    ///     ..config(0.0)
+    /// });
+    /// let headroom = uncalibrated.required_headroom_db();
+    /// // with a margin against rounding
+    /// let tx_gain_db = -uncalibrated.get_peak_level_db() - 0.1;
+    ///
+    /// let calibrated = OFDMModulator::new(config(tx_gain_db));
+    /// assert!((calibrated.get_peak_level_db() + 0.1).abs() < 1e-3);
+    /// let encoder = FrameEncoder::new(calibrated);
+    ///
+    /// // every frame gets the same gain, and none reaches full scale
+    /// let mut rms = Vec::new();
+    /// for seed in 0..4u32 {
+    ///     let payload: Vec<u8> = (0..1200u32).map(|i| ((i + seed * 1200).wrapping_mul(2654435761) >> 11) as u8).collect();
+    ///     let samples = encoder.encode(&payload);
+    ///     assert!(samples.iter().all(|x| x.abs() <= 1.0));
+    ///     assert!(papr(&samples) < headroom);
+    ///     rms.push((samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt());
+    /// }
+    /// assert!(rms.iter().all(|level| (level / rms[0] - 1.0).abs() < 0.02), "{rms:?}");
+    /// ```
+    ///
+    /// In strict mode, a gain that could clip is refused.
+    /// ```should_panic
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     tx_gain_db: -40.0,
+    ///     strict_headroom: true,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn get_peak_level_db(&self) -> f32 {
+        // the filter can add up the taps of one sign on the samples of the worst case
+        let filtered_peak = self.worst_case_peak().0
+            * self.tx_filter.as_ref().map_or(1.0, |filter| {
+                filter.taps().iter().map(|tap| tap.abs()).sum()
+            });
+        let level = match self.output_scale {
+            OutputScale::Raw => filtered_peak,
+            OutputScale::FixedGain(gain) => gain * filtered_peak,
+            OutputScale::PeakNormalize(target) => target,
+        };
+        20.0 * (self.tx_gain.into_f32() * level).log10()
+    }
+
+    /// Returns the largest possible magnitude of a sample and the mean power of the samples, without scaling.
+    fn worst_case_peak(&self) -> (f32, f32) {
+        // the samples of the real IFFT add every bin but DC and Nyquist with its conjugate
+        let nyquist = self.constants.fft_length() as u32 / 2;
+        let weight = |idx: u32| if idx == 0 || idx == nyquist { 1.0 } else { 2.0 };
+
+        let mut peak = 0.0;
+        let mut spread_peak = 0.0;
+        let mut power = 0.0;
+        for (i, &idx) in self.constants.data_subcarrier_indices.iter().enumerate() {
+            let gain = self
+                .power_allocation
+                .as_ref()
+                .map_or(1.0, |gains| gains[i].into_f32());
+            peak += weight(idx) * gain * self.qam_modem.peak_magnitude();
+            spread_peak += (weight(idx) * gain).powi(2);
+            power += weight(idx) * gain * gain * self.qam_modem.mean_power();
+        }
+        if self.dft_spreading.is_some() {
+            // the spread points of a symbol have the power of its points, at most all at the peak magnitude
+            let num_points = self.get_num_data_subcarriers() as f32;
+            peak = (spread_peak * num_points).sqrt() * self.qam_modem.peak_magnitude();
+        }
+        let pilot = PILOT_VALUE_TO_BE_CHANGED * pilot_magnitude(self.constants.qam_order) as f32;
+        for &idx in &self.constants.pilot_subcarrier_indices {
+            peak += weight(idx) * pilot.norm();
+            power += weight(idx) * pilot.norm_sqr();
+        }
+        (peak, power)
+    }
+
+    /// Returns the length of the OFDM symbol, including the cyclic prefix and suffix.
     ///
     /// The length is calculated as:
-    /// `2 * num_subcarriers + cyclic_prefix_length`.
+    /// `(2 * num_subcarriers + cyclic_prefix_length + cyclic_suffix_length) * oversampling`.
     pub fn get_symbol_length(&self) -> usize {
-        (2 * self.constants.num_subcarriers + self.constants.cyclic_prefix_length) as usize
+        self.constants.symbol_length()
+    }
+
+    /// Switches the QAM order of the data subcarriers, like an adaptive link does between two frames.
+    ///
+    /// Only the QAM modem and the number of bytes per symbol change. The FFT is not planned again,
+    /// and the scratches made before stay valid, so a real-time thread can switch without allocating.
+    /// It must match the order of the other end for every frame.
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the order carries the whole bytes of a symbol on a different number
+    /// of data subcarriers, which the scratches, the selected mapping and the power allocation are made for.
+    /// The modulator is then left as it was.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    /// use software_modem::qam::QAMOrder;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 16,
+    ///     ..Default::default()
+    /// };
+    /// let mut modulator = OFDMModulator::new((&config).into());
+    /// let fft = modulator.get_fft().clone();
+    /// let mut scratch = modulator.make_scratch();
+    ///
+    /// for qam_order in QAMOrder::ALL {
+    ///     modulator.set_qam_order(qam_order).unwrap();
+    ///     assert_eq!(modulator.get_qam_order(), qam_order);
+    ///     let data = vec![0x5a; modulator.get_bytes_per_symbol()];
+    ///     let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    ///     modulator.modulate_buffer_as_symbol_with_scratch(&data, &mut scratch, &mut symbol);
+    /// }
+    /// assert!(Arc::ptr_eq(&fft, modulator.get_fft()));
+    /// ```
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.constants.set_qam_order(qam_order)?;
+        self.qam_modem = GenericQAMModem::new(qam_order);
+        Ok(())
+    }
+
+    /// Returns the QAM order of the data subcarriers.
+    pub fn get_qam_order(&self) -> QAMOrder {
+        self.constants.qam_order
+    }
+
+    /// Returns the number of data bytes carried by one OFDM symbol.
+    pub fn get_bytes_per_symbol(&self) -> usize {
+        (self.constants.bits_per_symbol / 8) as usize
+    }
+
+    /// Returns `true` if the modulator is configured for differential encoding in time.
+    pub fn is_differential_time(&self) -> bool {
+        self.differential_time
+    }
+
+    /// Returns the number of roll-off samples by which windowed symbols overlap, at the oversampled rate.
+    pub fn get_roll_off(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Returns the filter band-limiting the frames, see [OFDMModulatorConfig::tx_filter].
+    pub fn get_tx_filter(&self) -> Option<&FirFilter> {
+        self.tx_filter.as_ref()
+    }
+
+    /// Returns the upconverter moving the frames to their carrier, see [OFDMModulatorConfig::passband].
+    pub fn get_upconverter(&self) -> Option<&Upconverter> {
+        self.upconverter.as_ref()
+    }
+
+    /// Returns the inverse FFT of the modulator, see [OFDMModulatorConfig::fft].
+    pub fn get_fft(&self) -> &Arc<dyn RealInverseFft<T>> {
+        &self.fft
+    }
+
+    /// Returns the factor by which the samples are interpolated, see [OFDMModulatorConfig::oversampling].
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = |oversampling| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     oversampling,
+    ///     ..Default::default()
+    /// };
+    /// let critical = OFDMModulator::new((&config(1)).into());
+    /// let symbol = critical.modulate_symbol(&[0xa7; 24]).unwrap();
+    ///
+    /// for oversampling in [2, 4] {
+    ///     let modulator = OFDMModulator::new((&config(oversampling)).into());
+    ///     assert_eq!(modulator.get_oversampling(), oversampling as usize);
+    ///     assert_eq!(modulator.get_symbol_length(), oversampling as usize * 132);
+    ///
+    ///     // every oversampling-th sample is the critically sampled waveform
+    ///     let oversampled = modulator.modulate_symbol(&[0xa7; 24]).unwrap();
+    ///     for (decimated, original) in oversampled.iter().step_by(oversampling as usize).zip(&symbol) {
+    ///         assert!((decimated - original).abs() < 1e-3, "{decimated} vs {original}");
+    ///     }
+    ///
+    ///     // and frames round trip at the oversampled rate
+    ///     let encoder = FrameEncoder::new(modulator);
+    ///     let decoder = FrameDecoder::new(OFDMDemodulator::new((&config(oversampling)).into()));
+    ///     let payload = "Oversampled samples leave room for the reconstruction filter".as_bytes();
+    ///     let samples = encoder.encode(payload);
+    ///     assert_eq!(samples.len(), decoder.get_frame_length(payload.len()));
+    ///     assert_eq!(&decoder.decode(&samples)[..payload.len()], payload);
+    /// }
+    /// ```
+    pub fn get_oversampling(&self) -> usize {
+        self.constants.oversampling as usize
+    }
+
+    /// Returns the number of data subcarriers, the points of a symbol, see [modulate_freq_symbols](Self::modulate_freq_symbols).
+    pub fn get_num_data_subcarriers(&self) -> usize {
+        self.constants.num_data_subcarriers as usize
+    }
+
+    pub(crate) fn data_subcarrier_indices(&self) -> &[u32] {
+        &self.constants.data_subcarrier_indices
+    }
+
+    pub(crate) fn qam_modem(&self) -> &GenericQAMModem<T> {
+        &self.qam_modem
+    }
+}
+
+impl OFDMModulator {
+    /// Modulates the data into consecutive symbols of `i16` samples, multiplied by the gain and saturated at full scale.
+    ///
+    /// Every [bytes per symbol](OFDMModulator::get_bytes_per_symbol) of data become a symbol,
+    /// without windowing, filtering and output scaling, see [f32_to_i16](crate::samples::f32_to_i16).
+    ///
+    /// # Panics
+    /// If the data length is not a multiple of the bytes per symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    /// use software_modem::samples::i16_to_f32;
+    ///
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new((&config).into());
+    /// let demodulator = OFDMDemodulator::new((&config).into());
+    /// let data: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    ///
+    /// // the symbols without quantization, at a gain that never clips
+    /// let gain = 10f32.powf(-modulator.get_peak_level_db() / 20.0);
+    /// let mut symbols = vec![0.0; 100 * modulator.get_symbol_length()];
+    /// for (data, symbol) in data.chunks(24).zip(symbols.chunks_exact_mut(132)) {
+    ///     modulator.modulate_buffer_as_symbol(data, symbol);
+    /// }
+    ///
+    /// for (gain, min_snr) in [(gain, 65.0), (gain / 100.0, 25.0)] {
+    ///     let pcm = modulator.modulate_bytes_i16(&data, gain);
+    ///     assert_eq!(pcm.len(), symbols.len());
+    ///
+    ///     // the quantization noise stays at a fixed level, the worst-case headroom costs the SNR of the unused bits
+    ///     let restored = i16_to_f32(&pcm);
+    ///     let signal: f32 = symbols.iter().map(|x| x * x * gain * gain).sum();
+    ///     let noise: f32 = symbols.iter().zip(&restored).map(|(x, y)| (x * gain - y).powi(2)).sum();
+    ///     let snr = 10.0 * (signal / noise).log10();
+    ///     assert!(snr > min_snr, "{snr} dB");
+    ///
+    ///     assert_eq!(demodulator.demodulate_symbols_i16(&pcm), data);
+    /// }
+    /// ```
+    pub fn modulate_bytes_i16(&self, data: &[u8], gain: f32) -> Vec<i16> {
+        let bytes_per_symbol = self.get_bytes_per_symbol();
+        if !data.len().is_multiple_of(bytes_per_symbol) {
+            panic!(
+                "Data length must be a multiple of {} bytes, but got {} bytes",
+                bytes_per_symbol,
+                data.len()
+            );
+        }
+
+        let symbol_length = self.get_symbol_length();
+        let mut symbol = vec![0.0; symbol_length];
+        let mut scratch = self.make_scratch();
+        let mut output = vec![0; data.len() / bytes_per_symbol * symbol_length];
+        for (data, output) in data
+            .chunks_exact(bytes_per_symbol)
+            .zip(output.chunks_exact_mut(symbol_length))
+        {
+            self.modulate_buffer_as_symbol_with_scratch(data, &mut scratch, &mut symbol);
+            f32_to_i16_into(&symbol, gain, None, output);
+        }
+        output
     }
 }
 
+/// Buffers a [GenericOFDMModulator] modulates symbols in, made by [make_scratch](GenericOFDMModulator::make_scratch).
+///
+/// A scratch only fits modulators of the same configuration.
+pub struct ModulatorScratch<T: Sample = f32> {
+    points: Vec<Complex<T>>,
+    spread: Vec<Complex<T>>,
+    bins: Vec<Complex<T>>,
+    candidate: Vec<T>,
+    fft: Vec<Complex<T>>,
+    excess: Vec<T>,
+    cancellation: Vec<Complex<T>>,
+    correction: Vec<T>,
+}
+
 /// Configuration for the [OFDM Modulator](OFDMModulator).
 ///
 /// Just contruct this struct with the desired parameters and pass it to the `OFDMModulator::new()` method.
 #[derive(SmartDefault)]
-pub struct OFDMModulatorConfig {
+pub struct OFDMModulatorConfig<T: Sample = f32> {
     pub num_subcarriers: u32,
     /// Length of the cyclic prefix in samples.
     ///
-    /// One OFDM symbol double num_subcarriers samples. If you want to have a CP of 1/4 you need to set this to `(2 * num_subcarriers) / 4`
+    /// One OFDM symbol has `2 * num_subcarriers` samples, for a CP of 1/4 set the [guard interval](GuardInterval) instead.
     pub cyclic_prefix_length: u32,
+    /// Length of the cyclic prefix as a fraction of the FFT length, which replaces the cyclic prefix length if set.
+    pub guard_interval: Option<GuardInterval>,
+    /// What fills the guard interval of the length of the cyclic prefix, the end of the symbol or zeros, see [GuardType].
+    ///
+    /// In [zero padding](GuardType::ZeroPad) mode the symbol comes first and the guard interval after it stays silent.
+    pub guard_type: GuardType,
+    /// Length of the cyclic suffix in samples, a copy of the head of every symbol after its end.
+    ///
+    /// Windowed transmissions and some synchronization schemes want the symbol to continue past its end
+    /// as well as before its start. The suffix is part of every symbol, so a symbol and a frame grow by it,
+    /// and the [window](OFDMModulator::apply_window) tapers its continuation into the next symbol.
+    /// It must not be longer than `2 * num_subcarriers`. With [zero padding](GuardType::ZeroPad) it is zeros as well.
+    /// Must match [OFDMDemodulatorConfig::cyclic_suffix_length](crate::ofdm::demodulator::OFDMDemodulatorConfig::cyclic_suffix_length).
+    pub cyclic_suffix_length: u32,
     /// Interval for pilot subcarriers.
     ///
     /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
     #[default(4)]
     pub pilot_subcarrier_every: u32,
     pub qam_order: QAMOrder,
-    /// Optional FFT implementation/planner to use.
+    /// Optional inverse FFT of the FFT length to use, see the [fft](crate::fft) module.
+    ///
+    /// If `None`, an FFT of `realfft` is planned with the [global planner](crate::fft::FftPlannerHandle::global),
+    /// shared with the other modems of the FFT length.
+    pub fft: Option<Arc<dyn RealInverseFft<T>>>,
+    /// Encode each data subcarrier as the change from the previous symbol on the same bin.
+    ///
+    /// Only applies to whole frames produced by the [FrameEncoder](crate::frame::FrameEncoder),
+    /// which starts every frame with a known reference symbol.
+    /// The receiver then needs no channel phase estimate, as long as the channel is static over two symbols.
+    pub differential_time: bool,
+    /// Number of samples by which consecutive symbols are tapered with a raised cosine and overlapped.
+    ///
+    /// Windowing lowers the spectral skirts of the rectangular symbol boundaries, see [OFDMModulator::apply_window].
+    /// It must not be longer than the cyclic prefix, and shortens the part of the prefix that absorbs multipath.
+    /// Frames grow by the roll-off, so the demodulator must be configured with the same value.
+    pub roll_off: u32,
+    /// Reduce the peak-to-average power ratio of every symbol by clipping and filtering, see [OFDMModulator::clip_and_filter].
+    pub clipping: Option<Clipping>,
+    /// Subcarriers which carry no data, but a signal cancelling the peaks of every symbol.
+    ///
+    /// Must be data subcarriers, not pilots, not DC and not above `num_subcarriers`.
+    /// The demodulator must be configured with the same subcarriers, it skips them.
+    /// Reserving the subcarriers with the worst SNR costs the least capacity,
+    /// see [FrameDecoder::get_worst_subcarriers](crate::frame::FrameDecoder::get_worst_subcarriers).
+    pub reserved_subcarriers: Vec<u32>,
+    /// Parameters of the peak cancellation on the [reserved subcarriers](OFDMModulatorConfig::reserved_subcarriers).
+    pub tone_reservation: ToneReservation,
+    /// Transmit every symbol with the lowest PAPR of several phase rotations, see [SlmConfig].
+    ///
+    /// The demodulator must be configured with the same selected mapping.
+    pub slm: Option<SlmConfig>,
+    /// Number of signaling bits every symbol carries on the signs of its pilots, at most [MAX_PILOT_SIGNALING_BITS](crate::ofdm::MAX_PILOT_SIGNALING_BITS).
+    ///
+    /// A side channel of a few bits per symbol, like a flag on the last symbol of a burst or a power control command,
+    /// which costs neither a data subcarrier nor a header symbol,
+    /// see [modulate_buffer_as_symbol_with_signaling](GenericOFDMModulator::modulate_buffer_as_symbol_with_signaling).
+    /// The bits are in the sign changes from one pilot to the next, like the index of
+    /// [explicit SLM signaling](crate::ofdm::SlmSignaling::Explicit), which they cannot be combined with.
+    /// The receiver compares neighbouring pilots, so it needs no absolute phase of the channel,
+    /// and every bit is repeated over every `pilot_signaling_bits`-th pair, so there must be more pilots than bits.
+    /// The pilot magnitudes, which the equalizer uses, stay unchanged,
+    /// and the symbols of the other calls and of frames carry a value of 0, the pilots of no signaling.
+    ///
+    /// The demodulator must be configured with the same number of bits.
+    pub pilot_signaling_bits: u32,
+    /// Interpolation factor of the samples, 1, 2 or 4.
+    ///
+    /// The frequency domain is zero padded to a correspondingly larger IFFT,
+    /// which leaves an empty band above the subcarriers for the reconstruction filter of the DAC,
+    /// and shows the peaks between the critically sampled points.
+    /// The cyclic prefix, the cyclic suffix and the roll-off keep their duration, so a symbol has
+    /// `(2 * num_subcarriers + cyclic_prefix_length + cyclic_suffix_length) * oversampling` samples.
+    /// A custom [fft](OFDMModulatorConfig::fft) must have the oversampled length.
+    #[default(1)]
+    pub oversampling: u32,
+    /// Number of subcarriers above DC which carry neither data nor pilots.
+    ///
+    /// Guard bands keep the signal out of the edges of band-limited channels, like the low end of telephone audio.
+    /// The demodulator must be configured with the same guards, it ignores the guard subcarriers.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::RealFftPlanner;
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = |guard_subcarriers_low, guard_subcarriers_high| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     guard_subcarriers_low,
+    ///     guard_subcarriers_high,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// // a brick-wall channel, which passes only the subcarriers between the guards
+    /// let channel = |samples: &[f32]| {
+    ///     let mut planner = RealFftPlanner::<f32>::new();
+    ///     let (forward, inverse) = (planner.plan_fft_forward(samples.len()), planner.plan_fft_inverse(samples.len()));
+    ///     let mut spectrum = forward.make_output_vec();
+    ///     forward.process(&mut samples.to_vec(), &mut spectrum).unwrap();
+    ///     for (k, bin) in spectrum.iter_mut().enumerate() {
+    ///         let subcarrier = k as f32 * 128.0 / samples.len() as f32;
+    ///         if subcarrier <= 6.0 || subcarrier >= 59.0 {
+    ///             *bin = Complex32::default();
+    ///         }
+    ///     }
+    ///     let mut output = vec![0.0; samples.len()];
+    ///     inverse.process(&mut spectrum, &mut output).unwrap();
+    ///     output.iter().map(|x| x / samples.len() as f32).collect::<Vec<f32>>()
+    /// };
+    ///
+    /// let payload: Vec<u8> = (0..400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let decode = |config: &OFDMConfig| {
+    ///     let encoder = FrameEncoder::new(OFDMModulator::new(config.into()));
+    ///     let decoder = FrameDecoder::new(OFDMDemodulator::new(config.into()));
+    ///     decoder.decode(&channel(&encoder.encode(&payload)))[..payload.len()].to_vec()
+    /// };
+    ///
+    /// assert_ne!(decode(&config(0, 0)), payload);
+    ///
+    /// // 6 low and 5 high guards leave 39 data subcarriers, of which 38 carry 19 bytes
+    /// let guarded = config(6, 5);
+    /// assert_eq!(FrameEncoder::new(OFDMModulator::new((&guarded).into())).get_bytes_per_symbol(), 19);
+    /// assert_eq!(decode(&guarded), payload);
+    /// ```
+    pub guard_subcarriers_low: u32,
+    /// Number of subcarriers below the Nyquist frequency which carry neither data nor pilots.
+    ///
+    /// See [guard_subcarriers_low](OFDMModulatorConfig::guard_subcarriers_low).
+    pub guard_subcarriers_high: u32,
+    /// Leave the DC bin empty.
+    ///
+    /// A real signal can only carry a real value at DC, so the DC bin never carries data.
+    /// Without the null, it carries a pilot, which costs no data capacity,
+    /// but is lost by AC coupled audio hardware and distorted by the LO leakage of an RF mixer.
+    /// The equalizer averages the pilot magnitudes and does not interpolate between them,
+    /// so a weak DC pilot only biases it slightly.
+    /// The demodulator must be configured with the same setting.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// let config = |null_dc| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     null_dc,
+    ///     ..Default::default()
+    /// };
+    /// let payload: Vec<u8> = (0..480u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    ///
+    /// for null_dc in [true, false] {
+    ///     let encoder = FrameEncoder::new(OFDMModulator::new((&config(null_dc)).into()));
+    ///     let decoder = FrameDecoder::new(OFDMDemodulator::new((&config(null_dc)).into()));
+    ///     assert_eq!(encoder.get_bytes_per_symbol(), 24);
+    ///     let samples = encoder.encode(&payload);
+    ///
+    ///     // the mean of a symbol after its cyclic prefix is its DC bin
+    ///     let dc = samples[4..132].iter().sum::<f32>() / 128.0;
+    ///     assert_eq!(dc.abs() < 1e-4, null_dc, "{dc}");
     ///
-    /// If `None`, a default FFT planner will be used.
-    pub fft: Option<Arc<dyn ComplexToReal<f32>>>,
+    ///     // a DC blocking high-pass filter, like a coupling capacitor
+    ///     let mut received = vec![0.0; samples.len()];
+    ///     let (mut input, mut output) = (0.0, 0.0);
+    ///     for (sample, received) in samples.iter().zip(received.iter_mut()) {
+    ///         output = sample - input + 0.995 * output;
+    ///         input = *sample;
+    ///         *received = output;
+    ///     }
+    ///     assert_eq!(decoder.decode(&received), payload);
+    /// }
+    /// ```
+    #[default(true)]
+    pub null_dc: bool,
+    /// Subcarriers which carry neither data nor pilots, to notch out frequencies used by something else.
+    ///
+    /// A pilot that would land on a masked subcarrier moves to the nearest free data subcarrier, the lower one first,
+    /// and is dropped if there is none before the neighbouring pilots.
+    /// The demodulator must be configured with the same mask.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::RealFftPlanner;
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// // a notch of 5 subcarriers, two of them pilots
+    /// let notch = [20, 21, 22, 23, 24];
+    /// let config = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     masked_subcarriers: notch.to_vec(),
+    ///     ..Default::default()
+    /// };
+    /// let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    /// let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    ///
+    /// // the notch takes 3 data subcarriers and 2 more for the moved pilots, 43 remain for 21 bytes
+    /// assert_eq!(encoder.get_bytes_per_symbol(), 21);
+    ///
+    /// let payload: Vec<u8> = (0..420u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let samples = encoder.encode(&payload);
+    /// assert_eq!(decoder.decode(&samples), payload);
+    ///
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let mut spectrum = fft.make_output_vec();
+    /// let (mut notched, mut used) = (0.0, 0.0);
+    /// for symbol in samples.chunks_exact(132) {
+    ///     fft.process(&mut symbol[4..].to_vec(), &mut spectrum).unwrap();
+    ///     for (k, bin) in spectrum.iter().enumerate().take(64).skip(1) {
+    ///         if notch.contains(&(k as u32)) {
+    ///             notched += bin.norm_sqr() / 5.0;
+    ///         } else {
+    ///             used += bin.norm_sqr() / 58.0;
+    ///         }
+    ///     }
+    /// }
+    /// let attenuation = 10.0 * (used / notched).log10();
+    /// assert!(attenuation >= 40.0, "{attenuation} dB");
+    /// ```
+    pub masked_subcarriers: Vec<u32>,
+    /// Linear gains of the data subcarriers, applied to their points after mapping.
+    ///
+    /// Gains above 1 boost a subcarrier, gains below 1 de-emphasize it, a gain of 0 turns it off.
+    /// Pilots keep their amplitude, so the equalization is not affected.
+    /// See [PowerAllocation::water_fill] to derive the gains from a measured SNR profile.
+    /// The demodulator must be configured with the same gains.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// // de-emphasize 8 data subcarriers around a resonance, and give the others the saved power
+    /// let mut gains = vec![1.0; 48];
+    /// gains[20..28].fill(0.5);
+    /// let boost = ((48.0 - 8.0 * 0.25) / 40.0f32).sqrt();
+    /// for gain in gains.iter_mut().filter(|gain| **gain == 1.0) {
+    ///     *gain = boost;
+    /// }
+    ///
+    /// let config = |power_allocation, differential_time| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     differential_time,
+    ///     power_allocation,
+    ///     ..Default::default()
+    /// };
+    /// let payload: Vec<u8> = (0..2400u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let power = |samples: &[f32]| samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    ///
+    /// let flat = FrameEncoder::new(OFDMModulator::new((&config(None, false)).into())).encode(&payload);
+    /// for differential_time in [false, true] {
+    ///     let config = config(Some(gains.clone()), differential_time);
+    ///     let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    ///     let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    ///     let samples = encoder.encode(&payload);
+    ///     assert_eq!(decoder.decode(&samples), payload);
+    ///
+    ///     // the transmit power stays the same
+    ///     if !differential_time {
+    ///         let ratio = power(&samples) / power(&flat);
+    ///         assert!((ratio - 1.0).abs() < 0.01, "{ratio}");
+    ///     }
+    /// }
+    /// ```
+    pub power_allocation: Option<Vec<f32>>,
+    /// Scaling of the frames, to fit the full scale of the audio output or DAC, see [OFDMModulator::scale_output].
+    ///
+    /// The receiver does not need to know the gain, the equalization absorbs it.
+    pub output_scale: OutputScale,
+    /// Gain in dB applied to every frame after the [output scale](OFDMModulatorConfig::output_scale).
+    ///
+    /// With a raw or fixed output scale, the output level is the same for every frame,
+    /// so an audio chain only needs to be calibrated once.
+    /// See [OFDMModulator::get_peak_level_db] to pick a gain that never clips.
+    pub tx_gain_db: f32,
+    /// Refuse a configuration whose worst-case peak exceeds full scale, see [OFDMModulator::get_peak_level_db].
+    ///
+    /// Otherwise, check the peak level yourself, samples beyond `[-1, 1]` are clipped by most audio outputs.
+    pub strict_headroom: bool,
+    /// Filter band-limiting every frame after windowing, see [FirFilter::filter_frame].
+    ///
+    /// The frames keep their length and timing, the group delay of the filter is compensated.
+    /// The subcarriers in use must lie within the passband, away from its edges,
+    /// see the [guard subcarriers](OFDMModulatorConfig::guard_subcarriers_low) to keep them there.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::dsp::FirFilter;
+    /// use software_modem::frame::{FrameDecoder, FrameEncoder};
+    /// use software_modem::metrics::{SpectrumWindow, oob_power_db, power_spectrum};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::demodulator::OFDMDemodulator;
+    /// use software_modem::ofdm::modulator::OFDMModulator;
+    ///
+    /// // a telephone channel from 300 to 3400 Hz at 8 kHz, subcarrier k is at k * 62.5 Hz
+    /// let filter = FirFilter::bandpass(300.0 / 8000.0, 3400.0 / 8000.0, 101);
+    /// let config = |filter: Option<FirFilter>| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 16,
+    ///     guard_subcarriers_low: 8,
+    ///     guard_subcarriers_high: 13,
+    ///     tx_filter: filter.clone(),
+    ///     rx_filter: filter,
+    ///     ..Default::default()
+    /// };
+    /// let encoder = FrameEncoder::new(OFDMModulator::new((&config(Some(filter.clone()))).into()));
+    /// let decoder = FrameDecoder::new(OFDMDemodulator::new((&config(Some(filter))).into()));
+    /// let unfiltered = FrameEncoder::new(OFDMModulator::new((&config(None)).into()));
+    ///
+    /// let payload: Vec<u8> = (0..1000u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+    /// let samples = encoder.encode(&payload);
+    /// assert_eq!(samples.len(), decoder.get_frame_length(payload.len()));
+    /// assert_eq!(&decoder.decode(&samples)[..payload.len()], payload);
+    ///
+    /// // the filter removes the spectral skirts outside the channel
+    /// let out_of_band = |samples: &[f32]| {
+    ///     let spectrum = power_spectrum(samples, 512, SpectrumWindow::Hann);
+    ///     oob_power_db(&spectrum, (300.0 / 8000.0, 3400.0 / 8000.0))
+    /// };
+    /// assert!(out_of_band(&unfiltered.encode(&payload)) > -30.0);
+    /// assert!(out_of_band(&samples) < -40.0, "{} dB", out_of_band(&samples));
+    /// ```
+    pub tx_filter: Option<FirFilter>,
+    /// Carrier every frame is moved to after filtering, see [Upconverter].
+    ///
+    /// The band of the subcarriers in use, from the lowest to the highest, is centered on the carrier,
+    /// at the sample rate of the output. It needs an empty DC bin and some room on both sides,
+    /// which the [guard subcarriers](OFDMModulatorConfig::guard_subcarriers_low) provide,
+    /// the less room, the longer the filters of the up- and downconverter.
+    /// Must match [OFDMDemodulatorConfig::passband](crate::ofdm::demodulator::OFDMDemodulatorConfig::passband),
+    /// up to the offset of the carriers at both ends.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::dsp::Passband;
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::metrics::{SpectrumWindow, power_spectrum};
+    /// use software_modem::ofdm::OFDMConfig;
+    ///
+    /// // subcarriers 4 to 19 at 375 Hz each, from 1.3 to 7.3 kHz, moved to 5 to 11 kHz
+    /// let config = |carrier_hz| OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 16,
+    ///     differential_time: true,
+    ///     guard_subcarriers_low: 3,
+    ///     guard_subcarriers_high: 44,
+    ///     passband: Some(Passband { sample_rate: 48000.0, carrier_hz }),
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(config(8000.0), CodingConfig::default());
+    /// let payload = "An acoustic link between 5 and 11 kHz".as_bytes();
+    /// let samples = modulator.encode_frame(payload);
+    ///
+    /// let spectrum = power_spectrum(&samples, 480, SpectrumWindow::Hann);
+    /// let total: f32 = spectrum.iter().sum();
+    /// let in_channel: f32 = spectrum[40..=120].iter().sum();
+    /// assert!(in_channel / total > 0.999, "between 4 and 12 kHz");
+    ///
+    /// // the carrier of the receiver is 3 Hz off, and there is some noise
+    /// let mut noise: u32 = 0x1234_5678;
+    /// let received: Vec<f32> = samples
+    ///     .iter()
+    ///     .map(|sample| {
+    ///         noise ^= noise << 13;
+    ///         noise ^= noise >> 17;
+    ///         noise ^= noise << 5;
+    ///         sample + 0.2 * (noise as f32 / u32::MAX as f32 - 0.5)
+    ///     })
+    ///     .collect();
+    /// let demodulator = CodedOFDMDemodulator::new(config(8003.0), CodingConfig::default());
+    /// assert_eq!(demodulator.decode_frame(&received).unwrap(), payload);
+    /// ```
+    pub passband: Option<Passband>,
+    /// Spreads the points of every symbol over the data subcarriers with a DFT of their number before they are mapped,
+    /// which the demodulator undoes after the equalizer, like SC-FDMA.
+    ///
+    /// Every subcarrier then carries a bit of every point, so the symbol looks more like a single carrier
+    /// and has fewer high peaks, at the same capacity. It needs coherent demodulation, so neither
+    /// [differential mode](OFDMModulatorConfig::differential_time) nor [selected mapping](OFDMModulatorConfig::slm).
+    /// Must match [OFDMDemodulatorConfig::dft_spread](crate::ofdm::demodulator::OFDMDemodulatorConfig::dft_spread).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = |dft_spread| OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 16,
+    ///     guard_subcarriers_low: 4,
+    ///     guard_subcarriers_high: 20,
+    ///     pilot_subcarrier_every: 16,
+    ///     dft_spread,
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(modulator(true).get_bytes_per_symbol(), modulator(false).get_bytes_per_symbol());
+    ///
+    /// let plain = modulator(false).measure_papr_ccdf(1000, &[10.0]);
+    /// let spread = modulator(true).measure_papr_ccdf(1000, &[10.0]);
+    /// assert!(spread[0] < plain[0] / 3.0, "{spread:?} {plain:?}");
+    /// ```
+    pub dft_spread: bool,
+    /// Order in which the points of every symbol are put on the data subcarriers, see [SubcarrierPermutation].
+    ///
+    /// Must match [OFDMDemodulatorConfig::subcarrier_permutation](crate::ofdm::demodulator::OFDMDemodulatorConfig::subcarrier_permutation).
+    pub subcarrier_permutation: SubcarrierPermutation,
+}
+
+/// Scaling of the output samples, see [OFDMModulatorConfig::output_scale].
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub enum OutputScale {
+    /// The samples as they come out of the IFFT, which reach far beyond 1.
+    #[default]
+    Raw,
+    /// Every frame is scaled so that its peak magnitude is the given level, like 0.9 for some headroom below full scale.
+    PeakNormalize(f32),
+    /// Every frame is scaled by the given gain.
+    FixedGain(f32),
+}
+
+/// Algorithms for the [power allocation](OFDMModulatorConfig::power_allocation) of the data subcarriers.
+pub struct PowerAllocation;
+
+impl PowerAllocation {
+    /// Distributes `total_power` over the data subcarriers by water-filling, returning the linear gain of each.
+    ///
+    /// The SNR profile holds the SNR of every data subcarrier at the flat allocation in dB,
+    /// like [FrameDecoder::get_subcarrier_snr](crate::frame::FrameDecoder::get_subcarrier_snr).
+    /// The power is given in units of one subcarrier at the flat allocation,
+    /// so the number of data subcarriers keeps the transmit power.
+    ///
+    /// Every subcarrier is filled up to a common water level above its noise, `1 / snr`,
+    /// which maximizes the capacity of the symbol. Subcarriers whose noise lies above the level get no power,
+    /// their data is lost unless the FEC corrects it.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::modulator::PowerAllocation;
+    ///
+    /// // a channel sloping from 20 dB down to -10 dB over 48 data subcarriers
+    /// let snr_profile: Vec<f32> = (0..48).map(|i| 20.0 - 30.0 * i as f32 / 47.0).collect();
+    /// let gains = PowerAllocation::water_fill(&snr_profile, 48.0);
+    ///
+    /// // the total power is conserved
+    /// let total_power: f32 = gains.iter().map(|gain| gain * gain).sum();
+    /// assert!((total_power - 48.0).abs() < 1e-3, "{total_power}");
+    ///
+    /// // the worst subcarriers are turned off, and the power goes where it buys more capacity
+    /// assert_eq!(gains[40..], [0.0; 8]);
+    /// let capacity = |gains: &[f32]| -> f32 {
+    ///     gains
+    ///         .iter()
+    ///         .zip(&snr_profile)
+    ///         .map(|(gain, snr)| (1.0 + gain * gain * 10f32.powf(snr / 10.0)).log2())
+    ///         .sum()
+    /// };
+    /// let flat = capacity(&[1.0; 48]);
+    /// let filled = capacity(&gains);
+    /// assert!(filled > 1.05 * flat, "{filled} vs {flat} bits per symbol");
+    ///
+    /// // an equal profile keeps the flat allocation
+    /// let gains = PowerAllocation::water_fill(&[12.0; 8], 8.0);
+    /// assert!(gains.iter().all(|gain| (gain - 1.0).abs() < 1e-5), "{gains:?}");
+    /// ```
+    ///
+    /// # Panics
+    /// If an SNR is NaN, or the total power is negative or not finite.
+    pub fn water_fill(snr_profile: &[f32], total_power: f32) -> Vec<f32> {
+        if snr_profile.iter().any(|snr| snr.is_nan()) {
+            panic!(
+                "SNR profile must not contain NaN, but got {:?}",
+                snr_profile
+            );
+        }
+        if !total_power.is_finite() || total_power < 0.0 {
+            panic!(
+                "Total power must be finite and non-negative, but got {}",
+                total_power
+            );
+        }
+
+        let noise: Vec<f32> = snr_profile
+            .iter()
+            .map(|snr| 10f32.powf(-snr / 10.0))
+            .collect();
+        let mut sorted = noise.clone();
+        sorted.sort_by(f32::total_cmp);
+
+        // add subcarriers from the least noisy one, as long as the level stays above their noise
+        let mut level = 0.0;
+        let mut filled_noise = 0.0;
+        for (filled, &noise) in sorted.iter().enumerate() {
+            let candidate = (total_power + filled_noise + noise) / (filled + 1) as f32;
+            if candidate <= noise {
+                break;
+            }
+            filled_noise += noise;
+            level = candidate;
+        }
+
+        noise
+            .iter()
+            .map(|noise| (level - noise).max(0.0).sqrt())
+            .collect()
+    }
+}
+
+/// Parameters of the clipping-and-filtering PAPR reduction.
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct Clipping {
+    /// Clipping level in dB above the RMS of the symbol.
+    #[default(6.0)]
+    pub threshold_db: f32,
+    /// Number of times the symbol is clipped and filtered.
+    #[default(2)]
+    pub iterations: usize,
+}
+
+/// Parameters of the tone reservation PAPR reduction on the [reserved subcarriers](OFDMModulatorConfig::reserved_subcarriers).
+///
+/// # Example
+/// ```
+/// use software_modem::frame::{FrameDecoder, FrameEncoder};
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::demodulator::OFDMDemodulator;
+/// use software_modem::ofdm::modulator::{OFDMModulator, ToneReservation};
+///
+/// let config = |iterations| OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     reserved_subcarriers: vec![3, 11, 18, 26, 37, 45, 50, 61],
+///     tone_reservation: ToneReservation {
+///         iterations,
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let papr = |samples: &[f32]| {
+///     let peak = samples.iter().map(|x| x * x).fold(0.0, f32::max);
+///     let mean = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+///     10.0 * (peak / mean).log10()
+/// };
+///
+/// // the reserved subcarriers are left at zero without iterations
+/// let unreserved = FrameEncoder::new(OFDMModulator::new((&config(0)).into()));
+/// let reserved = FrameEncoder::new(OFDMModulator::new((&config(16)).into()));
+/// let decoder = FrameDecoder::new(OFDMDemodulator::new((&config(16)).into()));
+/// assert_eq!(decoder.get_bytes_per_symbol(), 20);
+///
+/// let payload: Vec<u8> = (0..1000u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+/// let before = unreserved.encode(&payload);
+/// let after = reserved.encode(&payload);
+///
+/// let mean_papr = |samples: &[f32]| {
+///     let symbols: Vec<f32> = samples.chunks_exact(132).map(|symbol| papr(&symbol[4..])).collect();
+///     symbols.iter().sum::<f32>() / symbols.len() as f32
+/// };
+/// let reduction = mean_papr(&before) - mean_papr(&after);
+/// assert!(reduction >= 2.0, "{reduction} dB");
+///
+/// // the data subcarriers are untouched
+/// assert_eq!(decoder.decode(&after), payload);
+/// ```
+#[derive(SmartDefault, Copy, Clone, Debug, PartialEq)]
+pub struct ToneReservation {
+    /// Target peak level in dB above the RMS of the symbol.
+    #[default(5.0)]
+    pub threshold_db: f32,
+    /// Maximum number of times the peaks above the target are cancelled.
+    #[default(16)]
+    pub iterations: usize,
+}
+
+/// Result of [clipping and filtering](OFDMModulator::clip_and_filter) a symbol.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClippingReport {
+    /// Peak-to-average power ratio before clipping, in dB.
+    pub original_papr_db: f32,
+    /// Peak-to-average power ratio after the last iteration, in dB.
+    pub papr_db: f32,
+    /// Error vector magnitude on the data subcarriers, relative to their original power in dB.
+    pub evm_db: f32,
+}
+
+/// Returns the rising half of a raised cosine over `length` samples.
+fn raised_cosine_window<T: Sample>(length: usize) -> Vec<T> {
+    (0..length)
+        .map(|n| {
+            let phase = T::PI() * (T::cast(n as f64) + T::cast(0.5)) / T::cast(length as f64);
+            T::cast(0.5) * (T::one() - phase.cos())
+        })
+        .collect()
 }
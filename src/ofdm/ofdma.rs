@@ -0,0 +1,317 @@
+//! This module provides OFDMA, logical channels sharing the data subcarriers of every symbol.
+//!
+//! A [SubcarrierAllocation] gives every logical channel a set of data subcarriers and a QAM order of its own,
+//! like a robust control channel on a few subcarriers beside a fast data channel on the rest.
+//! The [OFDMAModulator] sends a payload per channel in one frame, and the [OFDMADemodulator] returns them apart,
+//! so a receiver only interested in one channel allocates only that one.
+//!
+//! The frames are the ones of the [FrameEncoder](crate::frame::FrameEncoder), with the reference symbol
+//! of differential mode, the window, the filters and the carrier of the [OFDMConfig].
+//!
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::ofdma::{LogicalChannel, OFDMADemodulator, OFDMAModulator, SubcarrierAllocation};
+//! use software_modem::qam::QAMOrder;
+//!
+//! let config = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     differential_time: true,
+//!     ..Default::default()
+//! };
+//! // the control channel gets every eighth data subcarrier, the data channel the others
+//! let (control, data): (Vec<_>, Vec<_>) = config
+//!     .get_data_subcarriers()
+//!     .into_iter()
+//!     .enumerate()
+//!     .partition(|(i, _)| i % 8 == 0);
+//! let subcarriers = |channel: Vec<(usize, u32)>| channel.into_iter().map(|(_, subcarrier)| subcarrier).collect();
+//! let allocation = SubcarrierAllocation {
+//!     channels: vec![
+//!         LogicalChannel { id: "control", subcarriers: subcarriers(control), qam_order: QAMOrder::QAM16 },
+//!         LogicalChannel { id: "data", subcarriers: subcarriers(data), qam_order: QAMOrder::QAM16 },
+//!     ],
+//! };
+//!
+//! let modulator = OFDMAModulator::new(config.clone(), allocation.clone());
+//! assert_eq!(modulator.get_channel_bytes_per_symbol("control"), Some(3));
+//! assert_eq!(modulator.get_channel_bytes_per_symbol("data"), Some(21));
+//! let samples = modulator.modulate_channels(&HashMap::from([
+//!     ("control", &b"ACK 7"[..]),
+//!     ("data", &b"The payload of the data channel"[..]),
+//! ]));
+//!
+//! let payloads = OFDMADemodulator::new(config, allocation).demodulate_channels(&samples);
+//! assert!(payloads["control"].starts_with(b"ACK 7"));
+//! assert!(payloads["data"].starts_with(b"The payload of the data channel"));
+//! ```
+
+use alloc::{vec, vec::Vec};
+use std::collections::HashMap;
+
+use num_complex::Complex32;
+
+use crate::{
+    frame::DIFFERENTIAL_REFERENCE,
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::{QAMModem, QAMOrder},
+};
+
+/// The name of a [logical channel](LogicalChannel).
+pub type ChannelId = &'static str;
+
+/// A logical channel of a [SubcarrierAllocation], the data subcarriers it is sent on and its QAM order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogicalChannel {
+    pub id: ChannelId,
+    /// Data subcarriers of the channel, see [OFDMConfig::get_data_subcarriers].
+    ///
+    /// The bytes of a symbol are sent on them in the order given.
+    /// Subcarriers left over by the bytes per symbol carry nothing.
+    pub subcarriers: Vec<u32>,
+    pub qam_order: QAMOrder,
+}
+
+/// Logical channels sharing the data subcarriers of every symbol, see the [module](self) documentation.
+///
+/// The channels must have distinct ids and disjoint subcarriers, which must be data subcarriers
+/// of the [OFDMConfig], and every channel must carry at least a byte per symbol.
+/// Data subcarriers no channel is allocated carry nothing, in differential mode the point of the reference symbol.
+/// The QAM order of the [OFDMConfig] is not used, every channel has its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubcarrierAllocation {
+    pub channels: Vec<LogicalChannel>,
+}
+
+impl SubcarrierAllocation {
+    /// Returns the channels with the positions of their subcarriers among the data subcarriers.
+    ///
+    /// # Panics
+    /// If the allocation is invalid, see [SubcarrierAllocation].
+    fn resolve(&self, data_subcarriers: &[u32]) -> Vec<ChannelState> {
+        let mut owners: Vec<Option<ChannelId>> = vec![None; data_subcarriers.len()];
+        let mut channels: Vec<ChannelState> = Vec::new();
+        for channel in &self.channels {
+            if channels.iter().any(|other| other.id == channel.id) {
+                panic!(
+                    "Logical channels must have distinct ids, but got {} twice",
+                    channel.id
+                );
+            }
+
+            let mut positions = Vec::with_capacity(channel.subcarriers.len());
+            for &subcarrier in &channel.subcarriers {
+                let Some(position) = data_subcarriers.iter().position(|&idx| idx == subcarrier)
+                else {
+                    panic!(
+                        "Subcarriers of logical channel {} must be data subcarriers, but got {}",
+                        channel.id, subcarrier
+                    );
+                };
+                if let Some(owner) = owners[position].replace(channel.id) {
+                    panic!(
+                        "Logical channels must have disjoint subcarriers, but got {} in {} and {}",
+                        subcarrier, owner, channel.id
+                    );
+                }
+                positions.push(position);
+            }
+
+            let qam_modem = QAMModem::new(channel.qam_order);
+            let bits_per_point = qam_modem.bits_per_symbol();
+            let whole = super::whole_byte_subcarriers(positions.len() as u32, bits_per_point);
+            if whole == 0 {
+                panic!(
+                    "Logical channel {} must carry a byte per symbol, but got {} subcarriers",
+                    channel.id,
+                    positions.len()
+                );
+            }
+            positions.truncate(whole as usize);
+            let bytes_per_symbol = positions.len() * bits_per_point as usize / 8;
+
+            channels.push(ChannelState {
+                id: channel.id,
+                positions,
+                qam_modem,
+                bytes_per_symbol,
+            });
+        }
+        channels
+    }
+}
+
+/// A logical channel resolved against the data subcarriers.
+struct ChannelState {
+    id: ChannelId,
+    /// The positions among the data subcarriers of the points of a symbol, as many as the bytes need.
+    positions: Vec<usize>,
+    qam_modem: QAMModem,
+    bytes_per_symbol: usize,
+}
+
+/// Modulates a payload per logical channel into one frame, see the [module](self) documentation.
+pub struct OFDMAModulator {
+    modulator: OFDMModulator,
+    channels: Vec<ChannelState>,
+}
+
+impl OFDMAModulator {
+    /// Creates a modulator of the logical channels of the allocation.
+    ///
+    /// # Panics
+    /// If the allocation is invalid, see [SubcarrierAllocation], the configuration asks for
+    /// [DFT spreading](OFDMConfig::dft_spread), which spreads every point over all channels,
+    /// or the configuration is invalid, see [OFDMModulator::new].
+    pub fn new(config: OFDMConfig, allocation: SubcarrierAllocation) -> Self {
+        if config.dft_spread {
+            panic!("OFDMA does not support DFT spreading, but got dft_spread");
+        }
+        let channels = allocation.resolve(&config.get_data_subcarriers());
+        OFDMAModulator {
+            modulator: OFDMModulator::new((&config).into()),
+            channels,
+        }
+    }
+
+    /// Modulates the payload of every channel into a frame, as many symbols long as the longest payload needs.
+    ///
+    /// The payloads are padded with zeros to whole symbols, channels without a payload send zeros.
+    ///
+    /// # Panics
+    /// If a payload is for a channel which is not allocated.
+    pub fn modulate_channels(&self, payloads: &HashMap<ChannelId, &[u8]>) -> Vec<f32> {
+        if let Some(id) = payloads
+            .keys()
+            .find(|id| !self.channels.iter().any(|channel| channel.id == **id))
+        {
+            panic!(
+                "Payloads must be for allocated logical channels, but got {}",
+                id
+            );
+        }
+
+        let num_symbols = self
+            .channels
+            .iter()
+            .map(|channel| {
+                payloads.get(channel.id).map_or(0, |payload| {
+                    payload.len().div_ceil(channel.bytes_per_symbol)
+                })
+            })
+            .max()
+            .unwrap_or(0);
+
+        let unallocated = if self.modulator.is_differential_time() {
+            DIFFERENTIAL_REFERENCE
+        } else {
+            Complex32::default()
+        };
+        let mut data_buffer = Vec::new();
+        let mut channel_points = Vec::new();
+        self.modulator
+            .modulate_frame_points(num_symbols, |index, points| {
+                points.fill(unallocated);
+                for channel in &self.channels {
+                    let payload = payloads.get(channel.id).copied().unwrap_or_default();
+                    let chunk = payload
+                        .chunks(channel.bytes_per_symbol)
+                        .nth(index)
+                        .unwrap_or_default();
+                    data_buffer.clear();
+                    data_buffer.extend_from_slice(chunk);
+                    data_buffer.resize(channel.bytes_per_symbol, 0);
+
+                    channel_points.resize(channel.positions.len(), Complex32::default());
+                    channel
+                        .qam_modem
+                        .modulate_into(&data_buffer, &mut channel_points);
+                    for (&position, &point) in channel.positions.iter().zip(&channel_points) {
+                        points[position] = point;
+                    }
+                }
+            })
+    }
+
+    /// Returns the number of bytes a channel carries in one symbol, or `None` if it is not allocated.
+    pub fn get_channel_bytes_per_symbol(&self, id: ChannelId) -> Option<usize> {
+        get_channel_bytes_per_symbol(&self.channels, id)
+    }
+
+    /// Returns the number of samples of one symbol.
+    pub fn get_symbol_length(&self) -> usize {
+        self.modulator.get_symbol_length()
+    }
+}
+
+/// Demodulates the frames of an [OFDMAModulator] into a payload per logical channel,
+/// see the [module](self) documentation.
+pub struct OFDMADemodulator {
+    demodulator: OFDMDemodulator,
+    channels: Vec<ChannelState>,
+}
+
+impl OFDMADemodulator {
+    /// Creates a demodulator of the logical channels of the allocation, which may leave out
+    /// the channels of the modulator it is not interested in.
+    ///
+    /// # Panics
+    /// If the allocation is invalid, see [SubcarrierAllocation], the configuration asks for
+    /// [DFT spreading](OFDMConfig::dft_spread), or the configuration is invalid, see [OFDMDemodulator::new].
+    pub fn new(config: OFDMConfig, allocation: SubcarrierAllocation) -> Self {
+        if config.dft_spread {
+            panic!("OFDMA does not support DFT spreading, but got dft_spread");
+        }
+        let channels = allocation.resolve(&config.get_data_subcarriers());
+        OFDMADemodulator {
+            demodulator: OFDMDemodulator::new((&config).into()),
+            channels,
+        }
+    }
+
+    /// Demodulates a frame into the payload of every allocated channel, with the zero padding of the last symbol.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the symbol length, plus the roll-off.
+    pub fn demodulate_channels(&self, samples: &[f32]) -> HashMap<ChannelId, Vec<u8>> {
+        let mut payloads: HashMap<ChannelId, Vec<u8>> = self
+            .channels
+            .iter()
+            .map(|channel| (channel.id, Vec::new()))
+            .collect();
+        let mut channel_points = Vec::new();
+        self.demodulator.for_each_symbol(samples, |points| {
+            for channel in &self.channels {
+                channel_points.clear();
+                channel_points.extend(channel.positions.iter().map(|&position| points[position]));
+                let payload = payloads.get_mut(channel.id).unwrap();
+                let start = payload.len();
+                payload.resize(start + channel.bytes_per_symbol, 0);
+                channel
+                    .qam_modem
+                    .demodulate_into(&channel_points, &mut payload[start..]);
+            }
+        });
+        payloads
+    }
+
+    /// Returns the number of bytes a channel carries in one symbol, or `None` if it is not allocated.
+    pub fn get_channel_bytes_per_symbol(&self, id: ChannelId) -> Option<usize> {
+        get_channel_bytes_per_symbol(&self.channels, id)
+    }
+
+    /// Returns the number of samples of one symbol.
+    pub fn get_symbol_length(&self) -> usize {
+        self.demodulator.get_symbol_length()
+    }
+}
+
+fn get_channel_bytes_per_symbol(channels: &[ChannelState], id: ChannelId) -> Option<usize> {
+    channels
+        .iter()
+        .find(|channel| channel.id == id)
+        .map(|channel| channel.bytes_per_symbol)
+}
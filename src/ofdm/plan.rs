@@ -0,0 +1,267 @@
+//! This module plans the [OFDM configuration](OFDMConfig) of a link from its sample rate:
+//! [for_bandwidth] suggests the number of subcarriers and the guard subcarriers of a target bandwidth,
+//! and [describe] sums up the spacing, the band and the bit rate of an existing configuration.
+//!
+//! The subcarrier spacing of the real modem is `sample_rate / (2 * num_subcarriers * oversampling)`,
+//! the band between DC and half the sample rate holds `num_subcarriers - 1` subcarriers, and the guard subcarriers
+//! at both ends of it leave the rest to the data and the pilots.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{ofdm::OFDMConfig, qam::QAMOrder};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The number of subcarriers [for_bandwidth] splits the band into without a target symbol duration.
+pub const DEFAULT_SUBCARRIERS_IN_BAND: u32 = 64;
+
+/// Returns the number nearest to `n` whose only prime factors are 2, 3 and 5, the smaller one of two as near,
+/// so an FFT of twice its length runs as fast as one of a power of two. Zero gives 1.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::plan::nearest_fft_friendly;
+///
+/// assert_eq!(nearest_fft_friendly(128), 128);
+/// assert_eq!(nearest_fft_friendly(97), 96);
+/// assert_eq!(nearest_fft_friendly(1023), 1024);
+/// // 7 lies between 6 and 8
+/// assert_eq!(nearest_fft_friendly(7), 6);
+/// ```
+pub fn nearest_fft_friendly(n: u32) -> u32 {
+    let is_friendly = |mut k: u32| {
+        for factor in [2, 3, 5] {
+            while k.is_multiple_of(factor) {
+                k /= factor;
+            }
+        }
+        k == 1
+    };
+    let n = n.max(1);
+    (0..)
+        .flat_map(|distance| [n.saturating_sub(distance), n.saturating_add(distance)])
+        .find(|&k| k > 0 && is_friendly(k))
+        .unwrap()
+}
+
+/// Parameters of the real modem [for_bandwidth] suggests, the band centered between DC and half the sample rate.
+///
+/// [config](SuggestedParams::config) turns them into an [OFDMConfig], whose band the guard subcarriers
+/// or a [passband](OFDMConfig::passband) move elsewhere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuggestedParams {
+    /// Number of subcarriers, an FFT-friendly size, see [nearest_fft_friendly].
+    pub num_subcarriers: u32,
+    /// Number of unused subcarriers above DC.
+    pub guard_subcarriers_low: u32,
+    /// Number of unused subcarriers below half the sample rate.
+    pub guard_subcarriers_high: u32,
+    /// Length of the cyclic prefix in samples, a quarter of the FFT length rounded down.
+    pub cyclic_prefix_length: u32,
+    /// Spacing of the subcarriers in Hz.
+    pub subcarrier_spacing_hz: f32,
+    /// Bandwidth of the subcarriers in use in Hz, at most the target.
+    pub occupied_bandwidth_hz: f32,
+    /// Duration of a symbol without its cyclic prefix, the inverse of the subcarrier spacing.
+    pub symbol_duration: Duration,
+    /// The uncoded bit rate at every QAM order in bit/s, see [LinkBudget::bit_rate_bps].
+    pub capacity_bps: Vec<(QAMOrder, f32)>,
+}
+
+impl SuggestedParams {
+    /// Returns the configuration of the parameters at the QAM order, with the default pilots.
+    pub fn config(&self, qam_order: QAMOrder) -> OFDMConfig {
+        OFDMConfig {
+            num_subcarriers: self.num_subcarriers,
+            cyclic_prefix_length: self.cyclic_prefix_length,
+            qam_order,
+            guard_subcarriers_low: self.guard_subcarriers_low,
+            guard_subcarriers_high: self.guard_subcarriers_high,
+            ..Default::default()
+        }
+    }
+}
+
+/// Summary of an [OFDMConfig] at a sample rate, see [describe].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkBudget {
+    /// Spacing of the subcarriers in Hz.
+    pub subcarrier_spacing_hz: f32,
+    /// Band of the subcarriers in use in Hz, from half a subcarrier below the lowest to half a subcarrier
+    /// above the highest, around the carrier of the [passband](OFDMConfig::passband) if it has one.
+    pub band_hz: (f32, f32),
+    /// Width of the band in Hz.
+    pub occupied_bandwidth_hz: f32,
+    /// Number of subcarriers carrying data, whole bytes of them per symbol.
+    pub data_subcarriers: u32,
+    /// Number of pilot subcarriers.
+    pub pilot_subcarriers: u32,
+    /// Duration of a symbol with its cyclic prefix.
+    pub symbol_duration: Duration,
+    /// Duration of the cyclic prefix, the longest delay spread the equalizer undoes.
+    pub cyclic_prefix_duration: Duration,
+    /// Bit rate of the data subcarriers in bit/s, before the preamble and the header of the frames and the code.
+    pub bit_rate_bps: f32,
+    /// The bit rate per Hz of the occupied bandwidth.
+    pub spectral_efficiency: f32,
+}
+
+/// Suggests the subcarriers of the real modem for a target bandwidth in Hz at a sample rate in Hz.
+///
+/// The target symbol duration, without the cyclic prefix, sets the spacing of the subcarriers,
+/// which is rounded so the FFT has a friendly size. Without one, the spacing splits the target bandwidth
+/// into [DEFAULT_SUBCARRIERS_IN_BAND] subcarriers. As many subcarriers as fit into the target bandwidth are in use,
+/// and the guard subcarriers take the rest.
+/// The capacities count the pilots at every 4th subcarrier and a cyclic prefix of a quarter of the FFT length.
+///
+/// # Panics
+/// If the sample rate is not positive and finite, the target bandwidth is not positive and below half the sample rate,
+/// the target symbol duration is zero, or the target bandwidth does not hold a subcarrier of the spacing.
+///
+/// # Example
+/// ```
+/// use core::time::Duration;
+///
+/// use software_modem::ofdm::modulator::OFDMModulator;
+/// use software_modem::ofdm::plan::for_bandwidth;
+/// use software_modem::qam::QAMOrder;
+///
+/// // 12 kHz of a sound card, split into subcarriers of 12000 / 64 Hz
+/// let params = for_bandwidth(48000.0, 12000.0, None);
+/// assert_eq!(params.num_subcarriers, 128);
+/// assert_eq!(params.subcarrier_spacing_hz, 187.5);
+/// assert_eq!((params.guard_subcarriers_low, params.guard_subcarriers_high), (31, 32));
+/// assert_eq!(params.occupied_bandwidth_hz, 12000.0);
+/// assert_eq!(
+///     params.capacity_bps,
+///     [
+///         (QAMOrder::QPSK, 14400.0),
+///         (QAMOrder::QAM16, 28800.0),
+///         (QAMOrder::QAM64, 43200.0),
+///         (QAMOrder::QAM256, 57600.0)
+///     ]
+/// );
+///
+/// // symbols of 10 ms from an 8 kHz voice channel
+/// let params = for_bandwidth(8000.0, 2400.0, Some(Duration::from_millis(10)));
+/// assert_eq!((params.num_subcarriers, params.subcarrier_spacing_hz), (40, 100.0));
+/// let modulator = OFDMModulator::new((&params.config(QAMOrder::QAM16)).into());
+/// assert_eq!(modulator.get_symbol_length(), 100);
+/// ```
+pub fn for_bandwidth(
+    sample_rate: f32,
+    target_bw_hz: f32,
+    target_symbol_duration: Option<Duration>,
+) -> SuggestedParams {
+    if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+        panic!(
+            "Sample rate must be positive and finite, but got {} Hz",
+            sample_rate
+        );
+    }
+    if !(target_bw_hz > 0.0 && target_bw_hz < sample_rate / 2.0) {
+        panic!(
+            "Target bandwidth must be positive and below half the sample rate of {} Hz, but got {} Hz",
+            sample_rate, target_bw_hz
+        );
+    }
+    if target_symbol_duration == Some(Duration::ZERO) {
+        panic!("Target symbol duration must be positive, but got 0 s");
+    }
+
+    let symbol_duration = target_symbol_duration.map_or(
+        f64::from(DEFAULT_SUBCARRIERS_IN_BAND) / f64::from(target_bw_hz),
+        |duration| duration.as_secs_f64(),
+    );
+    // the FFT of a symbol has 2 * num_subcarriers samples
+    let ideal_subcarriers = (f64::from(sample_rate) * symbol_duration / 2.0).round();
+    let num_subcarriers = nearest_fft_friendly(ideal_subcarriers.min(f64::from(u32::MAX)) as u32);
+    let subcarrier_spacing_hz = sample_rate / (2 * num_subcarriers) as f32;
+
+    // DC and the Nyquist bin stay empty
+    let used_subcarriers = ((target_bw_hz / subcarrier_spacing_hz) as u32).min(num_subcarriers - 1);
+    if used_subcarriers == 0 {
+        panic!(
+            "Target bandwidth must hold a subcarrier of {} Hz, but got {} Hz",
+            subcarrier_spacing_hz, target_bw_hz
+        );
+    }
+    let guard_subcarriers = num_subcarriers - 1 - used_subcarriers;
+
+    let mut params = SuggestedParams {
+        num_subcarriers,
+        guard_subcarriers_low: guard_subcarriers / 2,
+        guard_subcarriers_high: guard_subcarriers - guard_subcarriers / 2,
+        cyclic_prefix_length: num_subcarriers / 2,
+        subcarrier_spacing_hz,
+        occupied_bandwidth_hz: used_subcarriers as f32 * subcarrier_spacing_hz,
+        symbol_duration: Duration::from_secs_f64(
+            f64::from(2 * num_subcarriers) / f64::from(sample_rate),
+        ),
+        capacity_bps: Vec::new(),
+    };
+    params.capacity_bps = QAMOrder::ALL
+        .iter()
+        .map(|&qam_order| {
+            let budget = describe(&params.config(qam_order), sample_rate);
+            (qam_order, budget.bit_rate_bps)
+        })
+        .collect();
+    params
+}
+
+/// Sums up the configuration of the real modem at the sample rate of its samples in Hz, after the oversampling.
+///
+/// # Panics
+/// If the sample rate is not positive and finite, or the configuration is invalid,
+/// see [OFDMModulator::new](crate::ofdm::modulator::OFDMModulator::new).
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::plan::describe;
+/// use software_modem::ofdm::profiles::narrowband_voice;
+///
+/// let (ofdm, _) = narrowband_voice(8000.0);
+/// let budget = describe(&ofdm, 8000.0);
+/// assert_eq!(budget.subcarrier_spacing_hz, 50.0);
+/// assert_eq!(budget.band_hz, (375.0, 2575.0));
+/// assert_eq!((budget.data_subcarriers, budget.pilot_subcarriers), (28, 15));
+/// // 56 bits in 40 ms, a long cyclic prefix of 20 ms against the reverberation of a room
+/// assert_eq!(budget.cyclic_prefix_duration.as_millis(), 20);
+/// assert_eq!(budget.bit_rate_bps, 1400.0);
+/// ```
+pub fn describe(config: &OFDMConfig, sample_rate: f32) -> LinkBudget {
+    if !(sample_rate > 0.0 && sample_rate.is_finite()) {
+        panic!(
+            "Sample rate must be positive and finite, but got {} Hz",
+            sample_rate
+        );
+    }
+
+    let constants = config.constants();
+
+    let (low, high) = constants.band();
+    let (mut low, mut high) = (low * sample_rate, high * sample_rate);
+    if let Some(passband) = config.passband {
+        let shift = passband.carrier_hz - (low + high) / 2.0;
+        (low, high) = (low + shift, high + shift);
+    }
+    let occupied_bandwidth_hz = high - low;
+
+    let seconds = |samples: usize| samples as f64 / f64::from(sample_rate);
+    let symbol_duration = seconds(constants.symbol_length());
+    let bit_rate_bps = (f64::from(constants.bits_per_symbol) / symbol_duration) as f32;
+    LinkBudget {
+        subcarrier_spacing_hz: sample_rate / constants.fft_length() as f32,
+        band_hz: (low, high),
+        occupied_bandwidth_hz,
+        data_subcarriers: constants.num_data_subcarriers,
+        pilot_subcarriers: constants.num_pilot_subcarriers,
+        symbol_duration: Duration::from_secs_f64(symbol_duration),
+        cyclic_prefix_duration: Duration::from_secs_f64(seconds(constants.cyclic_prefix_samples())),
+        bit_rate_bps,
+        spectral_efficiency: bit_rate_bps / occupied_bandwidth_hz,
+    }
+}
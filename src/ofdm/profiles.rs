@@ -0,0 +1,565 @@
+//! This module presets the [complex OFDM configuration](ComplexOFDMConfig) to the layouts of published standards,
+//! so a link can be compared against the numbers in the literature.
+//! And [narrowband_voice] presets a whole coded modem for a voice channel, to push data through a telephone,
+//! a radio or a loudspeaker and a microphone, and [robust_beacon] one for beacons below the noise.
+//! An [OFDMProfile] names each of them, so an application can pick one from a command line flag or a config file.
+//!
+//! Only the subcarrier allocation, the FFT size and the cyclic prefix follow the standard. The frames, the coding,
+//! the scrambling and the pilot polarity and boost are the ones of this crate, so the modem does not interoperate
+//! with the devices of the standard.
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    dsp::FirFilter,
+    error::ModemError,
+    fec::{FecScheme, puncture::CodeRate},
+    frame::{CodingConfig, HeaderCode},
+    ofdm::{GuardInterval, OFDMConfig, complex::ComplexOFDMConfig},
+    qam::QAMOrder,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The frequencies of the four pilots of 802.11a, in subcarrier spacings.
+pub const IEEE80211A_PILOT_FREQUENCIES: [i32; 4] = [-21, -7, 7, 21];
+
+/// Returns the layout of an 802.11a OFDM symbol: 64 subcarriers with a cyclic prefix of 16 samples,
+/// 48 data subcarriers and 4 pilots at ±7 and ±21 within the frequencies -26 to 26, and DC and the 11 subcarriers
+/// at the edges left empty.
+///
+/// At the sample rate of 20 MHz of the standard, the subcarrier spacing is 312.5 kHz, a symbol lasts 4 µs,
+/// of which the cyclic prefix takes 0.8 µs, and the data subcarriers are put on in the order of the standard,
+/// from -26 up to 26.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{
+///     complex::{ComplexOFDMDemodulator, ComplexOFDMModulator},
+///     profiles::ieee80211a_like,
+/// };
+/// use software_modem::qam::QAMOrder;
+///
+/// let config = ieee80211a_like(QAMOrder::QAM16);
+/// assert_eq!(config.get_data_frequencies().len(), 48);
+/// assert_eq!(config.get_pilot_frequencies(), [-21, -7, 7, 21]);
+///
+/// // 48 subcarriers of 4 bits, 24 bytes in 80 samples
+/// let modulator = ComplexOFDMModulator::new(config.clone());
+/// assert_eq!(modulator.get_symbol_length(), 80);
+/// let data: Vec<u8> = (0..24).collect();
+/// let symbol = modulator.modulate_symbols(&data);
+/// assert_eq!(symbol.len(), 80);
+/// assert_eq!(ComplexOFDMDemodulator::new(config).demodulate_symbols(&symbol), data);
+/// ```
+pub fn ieee80211a_like(qam_order: QAMOrder) -> ComplexOFDMConfig {
+    ComplexOFDMConfig {
+        num_subcarriers: 64,
+        guard_interval: Some(GuardInterval::Quarter),
+        qam_order,
+        // the frequencies -31 to -27 and 27 to 31
+        guard_subcarriers_low: 5,
+        guard_subcarriers_high: 5,
+        null_dc: true,
+        pilot_frequencies: IEEE80211A_PILOT_FREQUENCIES.to_vec(),
+        ..Default::default()
+    }
+}
+
+/// The continual pilots of the DVB-T 2K mode, as carrier indices from 0 to 1704.
+const DVBT_2K_CONTINUAL_PILOTS: [i32; 45] = [
+    0, 48, 54, 87, 141, 156, 192, 201, 255, 279, 282, 333, 432, 450, 483, 525, 531, 618, 636, 714,
+    759, 765, 780, 804, 873, 888, 918, 939, 942, 969, 984, 1050, 1101, 1107, 1110, 1137, 1140,
+    1146, 1206, 1269, 1323, 1377, 1491, 1683, 1704,
+];
+
+/// The TPS carriers of the DVB-T 2K mode, as carrier indices from 0 to 1704.
+const DVBT_2K_TPS_CARRIERS: [i32; 17] = [
+    34, 50, 209, 346, 413, 569, 595, 688, 790, 901, 1073, 1219, 1262, 1286, 1469, 1594, 1687,
+];
+
+/// The number of active carriers of the DVB-T 2K mode.
+const DVBT_2K_CARRIERS: i32 = 1705;
+
+/// Returns the layout of a DVB-T symbol in the 2K mode: an FFT of 2048 bins, of which the 1705 carriers
+/// around DC are active, the cyclic prefix of the guard interval, and 193 pilots, which leave 1512 data carriers.
+///
+/// The pilots are the 45 continual pilots, the 17 TPS carriers, which carry the signaling of the transmission
+/// parameters in the standard, and the scattered pilots on every 12th carrier. The scattered pilots of the standard
+/// move by 3 carriers from one symbol to the next, but the pilots of this crate are the same in every symbol,
+/// so they stay where the first symbol of a DVB-T frame has them. DVB-T does not leave DC empty,
+/// the carrier in the middle is one of these scattered pilots.
+///
+/// At the sample rate of 64/7 MHz of the 8 MHz channels, the carrier spacing is about 4.46 kHz, and the symbol
+/// without the guard interval lasts 224 µs.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{GuardInterval, complex::ComplexOFDMModulator, profiles::dvbt_2k_like};
+/// use software_modem::qam::QAMOrder;
+///
+/// let config = dvbt_2k_like(GuardInterval::Eighth, QAMOrder::QAM16);
+/// assert_eq!(config.get_data_frequencies().len(), 1512);
+/// assert_eq!(config.get_pilot_frequencies().len(), 193);
+///
+/// // 1512 carriers of 4 bits in 2048 + 256 samples
+/// let modulator = ComplexOFDMModulator::new(config);
+/// assert_eq!(modulator.get_symbol_length(), 2304);
+/// assert_eq!(modulator.get_bytes_per_symbol(), 756);
+/// ```
+pub fn dvbt_2k_like(guard: GuardInterval, qam_order: QAMOrder) -> ComplexOFDMConfig {
+    let num_subcarriers = 2048;
+    // the carrier 852 is at DC, and the 171 bins at each edge, besides the one at half the sample rate, stay empty
+    let middle = DVBT_2K_CARRIERS / 2;
+    let guard_subcarriers = (num_subcarriers - 2) / 2 - middle as u32;
+
+    let mut carriers: Vec<i32> = (0..DVBT_2K_CARRIERS)
+        .step_by(12)
+        .chain(DVBT_2K_CONTINUAL_PILOTS)
+        .chain(DVBT_2K_TPS_CARRIERS)
+        .collect();
+    carriers.sort_unstable();
+    carriers.dedup();
+
+    ComplexOFDMConfig {
+        num_subcarriers,
+        guard_interval: Some(guard),
+        qam_order,
+        guard_subcarriers_low: guard_subcarriers,
+        guard_subcarriers_high: guard_subcarriers,
+        null_dc: false,
+        pilot_frequencies: carriers.iter().map(|carrier| carrier - middle).collect(),
+        ..Default::default()
+    }
+}
+
+/// The band of a voice channel in Hz, which the filters of [narrowband_voice] pass.
+pub const VOICE_BAND: (f32, f32) = (300.0, 2700.0);
+
+/// The subcarrier spacing of [narrowband_voice] in Hz.
+const VOICE_SUBCARRIER_SPACING: f32 = 50.0;
+
+/// Returns the configurations of a [coded modulator and demodulator](crate::coded::CodedOFDMModulator)
+/// for a voice channel from 300 to 2700 Hz at the sample rate.
+///
+/// The subcarriers are 50 Hz apart, a symbol lasts 20 ms, and the 45 subcarriers from 400 to 2600 Hz
+/// are in use, within the 300 to 2700 Hz that the band-pass filters of both ends pass.
+/// Every third of them is a pilot, which leaves 30 data subcarriers, of which QPSK fills 28 with whole bytes
+/// and the last 2 stay silent. The cyclic prefix of 20 ms
+/// absorbs most of the reverberation of a room or the echoes of a phone line, and the subcarriers are encoded
+/// differentially in time, which needs no channel estimate to undo them. The payload is protected by the
+/// outer Reed-Solomon code and the inner K = 7 convolutional code at rate 1/2.
+///
+/// A symbol then lasts 40 ms and carries 7 bytes, 1400 bit/s before coding. The preamble, the header and the codes
+/// leave about 600 bit/s for payloads of 1 kB, at any sample rate, see the example for the calculation.
+///
+/// The modulation is QPSK, whose points are 7 dB further apart for their power than the ones of QAM-16.
+/// At 8 kHz, frames decode over white noise at an SNR of 8 dB, where QAM-16 needed 16 dB; at 48 kHz, where the noise
+/// spreads over the whole band, they also decode across small rooms with an RT60 of 50 and 100 ms and noise at 10 dB,
+/// see the tests. A subcarrier in a deep fade of the room stays there for the whole frame, so longer reverberation
+/// loses frames at lower SNRs. [OFDMConfig::qam_order] trades the margin back for the bit rate.
+///
+/// # Panics
+/// If the sample rate is not above twice the upper edge of the band, with the transition of the filter.
+///
+/// # Example
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::ofdm::profiles::narrowband_voice;
+///
+/// let sample_rate = 8000.0;
+/// let (ofdm, coding) = narrowband_voice(sample_rate);
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+/// assert_eq!(demodulator.get_symbol_length(), 320);
+///
+/// // the net bit rate of a payload of 1000 bytes
+/// let seconds = modulator.get_frame_length(1000) as f32 / sample_rate;
+/// let bit_rate = 8000.0 / seconds;
+/// assert!(bit_rate > 550.0 && bit_rate < 650.0, "{bit_rate}");
+///
+/// let payload = "Through a telephone line".as_bytes();
+/// assert_eq!(demodulator.decode_frame(&modulator.encode_frame(payload)).unwrap(), payload);
+/// ```
+pub fn narrowband_voice(sample_rate: f32) -> (OFDMConfig, CodingConfig) {
+    let (low, high) = VOICE_BAND;
+    // a Blackman window makes the transition about 5.5 / taps of the sample rate wide,
+    // which has to fit into the 100 Hz between the band edges and the subcarriers
+    let taps = (sample_rate / 32.0) as usize | 1;
+    if !(sample_rate > min_voice_sample_rate() && sample_rate.is_finite()) {
+        panic!(
+            "Sample rate must be above {} Hz, but got {}",
+            min_voice_sample_rate(),
+            sample_rate
+        );
+    }
+    let filter = FirFilter::bandpass(low / sample_rate, high / sample_rate, taps);
+
+    // subcarrier k is at k * 50 Hz, the ones from 8 to 52 are in use
+    let num_subcarriers = (sample_rate / (2.0 * VOICE_SUBCARRIER_SPACING)).round() as u32;
+    let ofdm = OFDMConfig {
+        num_subcarriers,
+        cyclic_prefix_length: 2 * num_subcarriers,
+        pilot_subcarrier_every: 3,
+        qam_order: QAMOrder::QPSK,
+        differential_time: true,
+        guard_subcarriers_low: 7,
+        guard_subcarriers_high: num_subcarriers - 53,
+        tx_filter: Some(filter.clone()),
+        rx_filter: Some(filter),
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        scheme: FecScheme::Convolutional(CodeRate::Half),
+        reed_solomon: true,
+        ..Default::default()
+    };
+    (ofdm, coding)
+}
+
+/// The number of subcarriers of [robust_beacon].
+const BEACON_SUBCARRIERS: u32 = 64;
+
+/// The number of subcarriers in the band of [robust_beacon], around a quarter of the sample rate.
+const BEACON_BAND_SUBCARRIERS: u32 = 8;
+
+/// The number of repetitions of the convolutional code of [robust_beacon].
+const BEACON_REPETITIONS: usize = 4;
+
+/// Returns the configurations of a [coded modulator and demodulator](crate::coded::CodedOFDMModulator)
+/// for beacons, the low-rate frames announcing a station and the profile it talks on, which decode below the noise.
+///
+/// Of the 64 subcarriers, only the 8 around a quarter of the sample rate are in use, so the noise outside of them
+/// drops out of the FFT. Every other one of them is a pilot, which averages the common gain over more noise,
+/// and the cyclic prefix is as long as the FFT, a symbol lasts 256 samples. The payload and the header are protected
+/// by the K = 7 convolutional code at rate 1/2 with every coded bit [repeated](FecScheme::RepeatedConvolutional)
+/// 4 times, rate 1/8 in all. The 4 data subcarriers carry QPSK, 1 byte a symbol before coding, which leaves
+/// the payloads of 1 kB about 1/260 of the sample rate in bit/s, 185 bit/s at 48 kHz.
+///
+/// The frames have no preamble, a [stream](crate::stream::StreamDemodulator) synchronizes by trying the offsets
+/// of the first symbol. Beacons decode over white noise at an SNR of -12 dB over the whole band,
+/// 3 dB below the noise in the band, see the tests.
+/// [beacon_payload] and [parse_beacon] carry the configurations of the main profile of a link in a beacon.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{AwgnChannel, Channel};
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::ofdm::profiles::robust_beacon;
+///
+/// let (ofdm, coding) = robust_beacon();
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+/// assert_eq!(demodulator.get_symbol_length(), 256);
+///
+/// let mut frame = modulator.encode_frame(b"station 7");
+/// AwgnChannel::new(0.0, 3).apply(&mut frame);
+/// assert_eq!(demodulator.decode_frame(&frame).unwrap(), b"station 7");
+/// ```
+pub fn robust_beacon() -> (OFDMConfig, CodingConfig) {
+    // the subcarriers 28 to 35 are in use
+    let guard_subcarriers_low = (BEACON_SUBCARRIERS - BEACON_BAND_SUBCARRIERS) / 2 - 1;
+    let ofdm = OFDMConfig {
+        num_subcarriers: BEACON_SUBCARRIERS,
+        cyclic_prefix_length: 2 * BEACON_SUBCARRIERS,
+        pilot_subcarrier_every: 2,
+        qam_order: QAMOrder::QPSK,
+        soft_output: true,
+        guard_subcarriers_low,
+        guard_subcarriers_high: BEACON_SUBCARRIERS
+            - BEACON_BAND_SUBCARRIERS
+            - guard_subcarriers_low
+            - 1,
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        scheme: FecScheme::RepeatedConvolutional(BEACON_REPETITIONS),
+        header_code: HeaderCode::RepeatedConvolutional(BEACON_REPETITIONS),
+        ..Default::default()
+    };
+    (ofdm, coding)
+}
+
+/// Returns the payload of a beacon advertising the configurations of a coded modem,
+/// the two [serialized](OFDMConfig::to_bytes) configurations after the length of the first in 2 bytes.
+///
+/// # Panics
+/// If the serialized OFDM configuration is longer than 65535 bytes.
+///
+/// # Example
+/// ```
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::profiles::{beacon_payload, parse_beacon};
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 128,
+///     cyclic_prefix_length: 16,
+///     ..Default::default()
+/// };
+/// let payload = beacon_payload(&ofdm, &CodingConfig::default());
+/// assert_eq!(parse_beacon(&payload), Ok((ofdm, CodingConfig::default())));
+/// assert!(parse_beacon(&payload[1..]).is_err());
+/// ```
+pub fn beacon_payload(ofdm: &OFDMConfig, coding: &CodingConfig) -> Vec<u8> {
+    let ofdm_bytes = ofdm.to_bytes();
+    let length = u16::try_from(ofdm_bytes.len()).unwrap_or_else(|_| {
+        panic!(
+            "OFDM configuration must be at most {} bytes, but got {} bytes",
+            u16::MAX,
+            ofdm_bytes.len()
+        )
+    });
+    let mut payload = length.to_be_bytes().to_vec();
+    payload.extend(ofdm_bytes);
+    payload.extend(coding.to_bytes());
+    payload
+}
+
+/// Reads the configurations advertised by a beacon, see [beacon_payload].
+///
+/// # Errors
+/// [ModemError::InvalidConfig] if the payload does not hold two valid configurations.
+pub fn parse_beacon(payload: &[u8]) -> Result<(OFDMConfig, CodingConfig), ModemError> {
+    let (length, rest) = payload
+        .split_first_chunk::<2>()
+        .ok_or(ModemError::InvalidConfig)?;
+    let length = u16::from_be_bytes(*length) as usize;
+    if rest.len() < length {
+        return Err(ModemError::InvalidConfig);
+    }
+    let (ofdm, coding) = rest.split_at(length);
+    Ok((
+        OFDMConfig::from_bytes(ofdm)?,
+        CodingConfig::from_bytes(coding)?,
+    ))
+}
+
+/// A named configuration of the modem, see [OFDMProfile::build].
+///
+/// The names are the ones of [Display] and [FromStr], a name followed by its parameters, separated by colons.
+/// Parameters left out take their defaults, so `wifi-like` is `wifi-like:qam16`, and the guard interval of DVB-T
+/// is a fraction like `1/8` or a number of samples like `dvbt-2k-like:100`. The net bit rates are
+/// the ones of QAM-16 at the sample rate of the profile.
+///
+/// | Name | Profile | Modem | Net bit rate |
+/// |------|---------|-------|--------------|
+/// | `wifi-like:qam16` | [ieee80211a_like] | complex, 20 MHz | 48 Mbit/s, uncoded |
+/// | `dvbt-2k-like:1/4:qam16` | [dvbt_2k_like] | complex, 64/7 MHz | 21.6 Mbit/s at 1/4 to 26.2 Mbit/s at 1/32, uncoded |
+/// | `narrowband-voice:48000` | [narrowband_voice] | coded, any sample rate | 600 bit/s for payloads of 1 kB |
+/// | `robust-beacon` | [robust_beacon] | coded, any sample rate | 185 bit/s at 48 kHz for payloads of 1 kB |
+///
+/// # Example
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::ofdm::profiles::{OFDMProfile, ProfileConfig};
+///
+/// let profile: OFDMProfile = "narrowband-voice:8000".parse().unwrap();
+/// let ProfileConfig::Coded { ofdm, coding } = profile.build() else { unreachable!() };
+///
+/// let modulator = CodedOFDMModulator::new(*ofdm.clone(), coding.clone());
+/// let demodulator = CodedOFDMDemodulator::new(*ofdm, coding);
+/// let payload = "Picked by name".as_bytes();
+/// assert_eq!(demodulator.decode_frame(&modulator.encode_frame(payload)).unwrap(), payload);
+///
+/// // every named profile
+/// let names: Vec<String> = OFDMProfile::all().iter().map(|profile| profile.to_string()).collect();
+/// assert_eq!(names, ["wifi-like:qam16", "dvbt-2k-like:1/4:qam16", "narrowband-voice:48000", "robust-beacon"]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum OFDMProfile {
+    /// The layout of 802.11a, see [ieee80211a_like].
+    WifiLike(QAMOrder),
+    /// The layout of the DVB-T 2K mode, see [dvbt_2k_like].
+    DvbT2kLike(GuardInterval, QAMOrder),
+    /// The coded modem for a voice channel at the sample rate in Hz, see [narrowband_voice].
+    NarrowbandVoice { sample_rate: u32 },
+    /// The coded modem for beacons, see [robust_beacon].
+    RobustBeacon,
+    /// A configuration of its own, which has no name to be parsed from.
+    Custom(Box<ProfileConfig>),
+}
+
+/// The configuration an [OFDMProfile] builds, for the modem it runs on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProfileConfig {
+    /// The configurations of a [coded modulator](crate::coded::CodedOFDMModulator)
+    /// and [demodulator](crate::coded::CodedOFDMDemodulator) of real samples.
+    Coded {
+        ofdm: Box<OFDMConfig>,
+        coding: CodingConfig,
+    },
+    /// The configuration of a [complex modulator](crate::ofdm::complex::ComplexOFDMModulator)
+    /// and [demodulator](crate::ofdm::complex::ComplexOFDMDemodulator) of baseband I/Q samples.
+    Complex(ComplexOFDMConfig),
+}
+
+/// Errors parsing an [OFDMProfile] from its name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    /// No profile has the name.
+    UnknownProfile(String),
+    /// A parameter of the profile is not valid, or the profile has no more parameters.
+    InvalidParameter { profile: String, parameter: String },
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProfileError::UnknownProfile(name) => write!(f, "Unknown profile {:?}", name),
+            ProfileError::InvalidParameter { profile, parameter } => {
+                write!(
+                    f,
+                    "Invalid parameter {:?} of profile {}",
+                    parameter, profile
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ProfileError {}
+
+/// The sample rate of [OFDMProfile::NarrowbandVoice] when the name leaves it out, the one of most sound cards.
+const DEFAULT_VOICE_SAMPLE_RATE: u32 = 48000;
+
+/// Returns the sample rate [narrowband_voice] needs to be above, twice the upper band edge with the transition.
+fn min_voice_sample_rate() -> f32 {
+    2.0 * (VOICE_BAND.1 + VOICE_SUBCARRIER_SPACING)
+}
+
+impl OFDMProfile {
+    /// Returns every named profile with its default parameters.
+    pub fn all() -> Vec<OFDMProfile> {
+        vec![
+            OFDMProfile::WifiLike(QAMOrder::default()),
+            OFDMProfile::DvbT2kLike(GuardInterval::default(), QAMOrder::default()),
+            OFDMProfile::NarrowbandVoice {
+                sample_rate: DEFAULT_VOICE_SAMPLE_RATE,
+            },
+            OFDMProfile::RobustBeacon,
+        ]
+    }
+
+    /// Returns the configuration of the profile.
+    ///
+    /// # Panics
+    /// If the sample rate of the voice profile is too low, see [narrowband_voice].
+    pub fn build(self) -> ProfileConfig {
+        match self {
+            OFDMProfile::WifiLike(qam_order) => ProfileConfig::Complex(ieee80211a_like(qam_order)),
+            OFDMProfile::DvbT2kLike(guard, qam_order) => {
+                ProfileConfig::Complex(dvbt_2k_like(guard, qam_order))
+            }
+            OFDMProfile::NarrowbandVoice { sample_rate } => {
+                let (ofdm, coding) = narrowband_voice(sample_rate as f32);
+                ProfileConfig::Coded {
+                    ofdm: Box::new(ofdm),
+                    coding,
+                }
+            }
+            OFDMProfile::RobustBeacon => {
+                let (ofdm, coding) = robust_beacon();
+                ProfileConfig::Coded {
+                    ofdm: Box::new(ofdm),
+                    coding,
+                }
+            }
+            OFDMProfile::Custom(config) => *config,
+        }
+    }
+}
+
+impl Display for OFDMProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OFDMProfile::WifiLike(qam_order) => write!(f, "wifi-like:{}", qam_name(*qam_order)),
+            OFDMProfile::DvbT2kLike(GuardInterval::Samples(samples), qam_order) => {
+                write!(f, "dvbt-2k-like:{}:{}", samples, qam_name(*qam_order))
+            }
+            OFDMProfile::DvbT2kLike(guard, qam_order) => {
+                write!(f, "dvbt-2k-like:{}:{}", guard, qam_name(*qam_order))
+            }
+            OFDMProfile::NarrowbandVoice { sample_rate } => {
+                write!(f, "narrowband-voice:{}", sample_rate)
+            }
+            OFDMProfile::RobustBeacon => write!(f, "robust-beacon"),
+            OFDMProfile::Custom(_) => write!(f, "custom"),
+        }
+    }
+}
+
+/// Returns the name of the QAM order in a profile, `qam16` for QAM-16.
+fn qam_name(qam_order: QAMOrder) -> &'static str {
+    match qam_order {
+        QAMOrder::QPSK => "qpsk",
+        QAMOrder::QAM16 => "qam16",
+        QAMOrder::QAM64 => "qam64",
+        QAMOrder::QAM256 => "qam256",
+    }
+}
+
+impl FromStr for OFDMProfile {
+    type Err = ProfileError;
+
+    /// Parses the name of a profile, case-insensitively, see [OFDMProfile].
+    ///
+    /// # Errors
+    /// [ProfileError::UnknownProfile] if no profile has the name, `custom` included,
+    /// and [ProfileError::InvalidParameter] if a parameter is not valid or there are too many of them.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().to_ascii_lowercase();
+        let mut parts = name.split(':');
+        let profile = parts.next().unwrap_or_default();
+        let invalid = |parameter: &str| ProfileError::InvalidParameter {
+            profile: profile.into(),
+            parameter: parameter.into(),
+        };
+        let parse_qam = |parameter: &str| match parameter {
+            "qpsk" | "qam4" | "qam-4" => Ok(QAMOrder::QPSK),
+            "qam16" | "qam-16" => Ok(QAMOrder::QAM16),
+            "qam64" | "qam-64" => Ok(QAMOrder::QAM64),
+            "qam256" | "qam-256" => Ok(QAMOrder::QAM256),
+            _ => Err(invalid(parameter)),
+        };
+
+        let parsed = match profile {
+            "wifi-like" => OFDMProfile::WifiLike(parse_qam(parts.next().unwrap_or("qam16"))?),
+            "dvbt-2k-like" => {
+                let guard = parts.next().unwrap_or("1/4");
+                let guard = GuardInterval::FRACTIONS
+                    .into_iter()
+                    .find(|interval| interval.to_string() == guard)
+                    .or_else(|| guard.parse().ok().map(GuardInterval::Samples))
+                    .ok_or_else(|| invalid(guard))?;
+                OFDMProfile::DvbT2kLike(guard, parse_qam(parts.next().unwrap_or("qam16"))?)
+            }
+            "narrowband-voice" => {
+                let sample_rate = parts.next().unwrap_or("48000");
+                OFDMProfile::NarrowbandVoice {
+                    sample_rate: sample_rate
+                        .parse()
+                        .ok()
+                        .filter(|&rate: &u32| rate as f32 > min_voice_sample_rate())
+                        .ok_or_else(|| invalid(sample_rate))?,
+                }
+            }
+            "robust-beacon" => OFDMProfile::RobustBeacon,
+            _ => return Err(ProfileError::UnknownProfile(profile.into())),
+        };
+        match parts.next() {
+            Some(parameter) => Err(invalid(parameter)),
+            None => Ok(parsed),
+        }
+    }
+}
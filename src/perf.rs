@@ -0,0 +1,268 @@
+//! This module measures the throughput and latency of a [StreamDemodulator](crate::stream::StreamDemodulator),
+//! to find out whether a receiver keeps up with its sample rate and which stage to speed up if it does not.
+//!
+//! [Metrics] count the samples, symbols, frames and payload bytes, and time the stages of receiving a frame.
+//! Reading the clock for every symbol would cost a share of the symbol itself for small configurations,
+//! so every stage is only timed once every `sample_every` calls, and its total time is estimated from those calls.
+//! The [modulation error ratio](crate::metrics::mer_db) of the decoded frames is averaged with a [MerEstimator],
+//! for a live display of the quality of the link.
+//! [snapshot](Metrics::snapshot) returns the counts and the rates as a plain [Snapshot].
+//!
+//! The metrics are enabled with [enable_metrics](crate::stream::StreamDemodulator::enable_metrics),
+//! without them the stream demodulator reads no clock at all.
+
+use std::time::{Duration, Instant};
+
+use num_complex::Complex32;
+
+use crate::{
+    metrics::MerEstimator,
+    ofdm::{Stage, StageTimer},
+};
+
+/// How far the [MER](Snapshot::mer_db) moves to the one of every decoded frame.
+const MER_SMOOTHING: f32 = 0.2;
+
+/// The counters and clocks of a [StreamDemodulator](crate::stream::StreamDemodulator).
+///
+/// # Example
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::stream::StreamDemodulator;
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+/// let mut stream = StreamDemodulator::new(demodulator, 0.01, 200);
+/// stream.enable_metrics(16);
+///
+/// let mut signal = vec![0.0; 500];
+/// signal.extend(modulator.encode_frame(&[0x5a; 300]));
+/// signal.extend([0.0; 500]);
+/// for block in signal.chunks(128) {
+///     stream.push(block);
+/// }
+///
+/// let snapshot = stream.get_metrics().unwrap().snapshot();
+/// assert_eq!((snapshot.samples, snapshot.frames_decoded, snapshot.payload_bytes), (signal.len() as u64, 1, 300));
+/// assert!(snapshot.symbols_per_sec > 0.0);
+/// // every symbol is transformed at least once, more if the frame was tried at several offsets
+/// assert!(snapshot.stages.fft.calls >= snapshot.symbols);
+/// println!("{:?} per FFT", snapshot.stages.fft.per_call());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    sample_every: u32,
+    start: Instant,
+    busy: Duration,
+    samples: u64,
+    symbols: u64,
+    frames_decoded: u64,
+    frames_failed: u64,
+    payload_bytes: u64,
+    squelch_high_water: usize,
+    frame_high_water: usize,
+    mer: MerEstimator,
+    clocks: [Clock; 5],
+}
+
+/// The clock of one stage.
+#[derive(Default, Clone, Copy, Debug)]
+struct Clock {
+    calls: u64,
+    sampled: u64,
+    time: Duration,
+}
+
+impl Metrics {
+    /// Creates metrics which time every stage once every `sample_every` calls, starting with the first.
+    ///
+    /// # Panics
+    /// If `sample_every` is 0.
+    pub fn new(sample_every: u32) -> Self {
+        if sample_every == 0 {
+            panic!("Sample every must be at least 1, but got 0");
+        }
+
+        Metrics {
+            sample_every,
+            start: Instant::now(),
+            busy: Duration::ZERO,
+            samples: 0,
+            symbols: 0,
+            frames_decoded: 0,
+            frames_failed: 0,
+            payload_bytes: 0,
+            squelch_high_water: 0,
+            frame_high_water: 0,
+            mer: MerEstimator::new(MER_SMOOTHING),
+            clocks: [Clock::default(); 5],
+        }
+    }
+
+    /// Returns the counts, the rates over the time spent processing, and the estimated time of every stage.
+    pub fn snapshot(&self) -> Snapshot {
+        let busy = self.busy.as_secs_f64();
+        let rate = |count: u64| {
+            if busy > 0.0 { count as f64 / busy } else { 0.0 }
+        };
+        let stage = |stage: Stage| {
+            let clock = self.clocks[stage as usize];
+            StageTime {
+                calls: clock.calls,
+                sampled: clock.sampled,
+                time: if clock.sampled == 0 {
+                    Duration::ZERO
+                } else {
+                    clock
+                        .time
+                        .mul_f64(clock.calls as f64 / clock.sampled as f64)
+                },
+            }
+        };
+
+        Snapshot {
+            elapsed: self.start.elapsed(),
+            busy: self.busy,
+            samples: self.samples,
+            symbols: self.symbols,
+            frames_decoded: self.frames_decoded,
+            frames_failed: self.frames_failed,
+            payload_bytes: self.payload_bytes,
+            symbols_per_sec: rate(self.symbols),
+            payload_bytes_per_sec: rate(self.payload_bytes),
+            stages: StageTimes {
+                sync: stage(Stage::Sync),
+                fft: stage(Stage::Fft),
+                equalize: stage(Stage::Equalize),
+                demap: stage(Stage::Demap),
+                fec: stage(Stage::Fec),
+            },
+            squelch_high_water: self.squelch_high_water,
+            frame_high_water: self.frame_high_water,
+            mer_db: self.mer.get_db(),
+        }
+    }
+
+    /// Counts a block of samples pushed, and the time spent processing it.
+    pub(crate) fn record_push(&mut self, samples: usize, busy: Duration) {
+        self.samples += samples as u64;
+        self.busy += busy;
+    }
+
+    /// Counts a burst, decoded into a payload carried by `symbols` symbols or not at all.
+    pub(crate) fn record_frame(&mut self, frame: Option<(usize, usize)>) {
+        match frame {
+            Some((payload_length, symbols)) => {
+                self.frames_decoded += 1;
+                self.payload_bytes += payload_length as u64;
+                self.symbols += symbols as u64;
+            }
+            None => self.frames_failed += 1,
+        }
+    }
+
+    /// Adds the data subcarrier points of a decoded frame and their decisions to the MER.
+    pub(crate) fn record_mer(&mut self, points: &[Complex32], decisions: &[Complex32]) {
+        self.mer.add(points, decisions);
+    }
+
+    /// Raises the high-water marks to the capacities of the buffers of the squelch and of the frame, in samples.
+    pub(crate) fn record_buffers(&mut self, squelch: usize, frame: usize) {
+        self.squelch_high_water = self.squelch_high_water.max(squelch);
+        self.frame_high_water = self.frame_high_water.max(frame);
+    }
+}
+
+impl StageTimer for Metrics {
+    fn time<R>(&mut self, stage: Stage, f: impl FnOnce() -> R) -> R {
+        let clock = &mut self.clocks[stage as usize];
+        clock.calls += 1;
+        if !(clock.calls - 1).is_multiple_of(self.sample_every as u64) {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = f();
+        clock.time += start.elapsed();
+        clock.sampled += 1;
+        result
+    }
+}
+
+/// The counts and rates of [Metrics] at one moment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// Time since the metrics were enabled.
+    pub elapsed: Duration,
+    /// Time spent within [push](crate::stream::StreamDemodulator::push) and [flush](crate::stream::StreamDemodulator::flush).
+    pub busy: Duration,
+    /// Number of samples pushed.
+    pub samples: u64,
+    /// Number of symbols of the decoded frames, including the reference symbols.
+    pub symbols: u64,
+    /// Number of frames decoded with a valid CRC.
+    pub frames_decoded: u64,
+    /// Number of bursts that did not decode.
+    pub frames_failed: u64,
+    /// Number of bytes of the decoded payloads.
+    pub payload_bytes: u64,
+    /// Symbols of the decoded frames per second of processing, or 0 before any processing.
+    pub symbols_per_sec: f64,
+    /// Payload bytes per second of processing, or 0 before any processing.
+    pub payload_bytes_per_sec: f64,
+    /// The estimated time of every stage.
+    pub stages: StageTimes,
+    /// Largest capacity of the buffer of the squelch, in samples.
+    pub squelch_high_water: usize,
+    /// Largest capacity of the buffer of the frame being decoded, in samples.
+    pub frame_high_water: usize,
+    /// Modulation error ratio of the decoded frames in dB, averaged exponentially over them,
+    /// or `None` before the first frame.
+    pub mer_db: Option<f32>,
+}
+
+/// The estimated time of every stage of receiving a frame.
+///
+/// The stages of decoding count every offset tried to find a frame, not only the one it was found at.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StageTimes {
+    /// Cutting the bursts out of the stream with the squelch, once per block.
+    pub sync: StageTime,
+    /// Transforming the symbols, once per symbol.
+    pub fft: StageTime,
+    /// Equalizing the bins into points, once per symbol.
+    pub equalize: StageTime,
+    /// Demapping the points into LLRs or bits, once per frame.
+    pub demap: StageTime,
+    /// Deinterleaving and decoding the frame, once per frame.
+    pub fec: StageTime,
+}
+
+/// The estimated time of one stage.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StageTime {
+    /// Number of times the stage ran.
+    pub calls: u64,
+    /// Number of the calls which were timed.
+    pub sampled: u64,
+    /// Total time of the stage, the time of the timed calls scaled to all the calls.
+    pub time: Duration,
+}
+
+impl StageTime {
+    /// Returns the mean time of one call, or 0 if the stage has not run.
+    pub fn per_call(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            self.time.div_f64(self.calls as f64)
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! This module provides an AFSK modem, bits sent as one of two audio tones, compatible with the Bell 202 modems
+//! of 1200 baud packet radio.
+//!
+//! The [AfskModulator] sends a 1 as the mark tone and a 0 as the space tone, continuing the phase from tone to tone
+//! so the audio does not click. The [AfskDemodulator] correlates the samples with both tones over a bit,
+//! and a PLL moves its bit clock to the changes of the tone, so it reads every bit in its middle
+//! at sample rates that are no multiple of the baud rate, and with a clock that is off.
+//!
+//! Through the [PHY traits](super) a block is the fewest whole bytes that are a whole number of samples,
+//! their bits sent from the least significant one. Packet radio sends [HDLC](crate::hdlc) frames instead,
+//! NRZI encoded, which [modulate_packet](AfskModulator::modulate_packet) and
+//! [decode_packets](AfskDemodulator::decode_packets) do, compatible with the TNCs and sound card modems of APRS.
+//!
+//! # Example
+//! ```
+//! use software_modem::phy::afsk::{AfskConfig, AfskDemodulator, AfskModulator};
+//!
+//! let config = AfskConfig::default();
+//! let samples = AfskModulator::new(config.clone()).modulate_packet(b"an AX.25 frame");
+//! let packets = AfskDemodulator::new(config).decode_packets(&samples);
+//! assert_eq!(packets, vec![b"an AX.25 frame".to_vec()]);
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::f64::consts::TAU;
+
+use num_complex::Complex64;
+
+use crate::{
+    dsp::gcd,
+    error::ModemError,
+    hdlc::{HdlcDecoder, encode_frame, nrzi_decode, nrzi_encode},
+    phy::{PhyDemodulator, PhyModulator},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The level of the line before a packet, the mark tone.
+const IDLE_LEVEL: u8 = 1;
+
+/// The configuration of an [AfskModulator] and an [AfskDemodulator], by default the tones of Bell 202.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AfskConfig {
+    /// The sample rate in Hz.
+    pub sample_rate: u32,
+    /// The number of bits per second.
+    pub baud_rate: u32,
+    /// The frequency of a 1 in Hz.
+    pub mark_hz: f32,
+    /// The frequency of a 0 in Hz.
+    pub space_hz: f32,
+    /// The peak of the tones.
+    pub amplitude: f32,
+    /// The number of flags before a packet, for the receiver to lock onto its bits.
+    pub preamble_flags: usize,
+    /// How far the bit clock moves towards a change of the tone, from 0 for not at all to 1 for all the way.
+    pub clock_gain: f32,
+}
+
+impl Default for AfskConfig {
+    /// Bell 202, 1200 baud with the mark at 1200 Hz and the space at 2200 Hz, at a sample rate of 48000 Hz,
+    /// with 32 flags, about 210 ms, before a packet.
+    fn default() -> Self {
+        AfskConfig {
+            sample_rate: 48000,
+            baud_rate: 1200,
+            mark_hz: 1200.0,
+            space_hz: 2200.0,
+            amplitude: 0.5,
+            preamble_flags: 32,
+            clock_gain: 0.3,
+        }
+    }
+}
+
+impl AfskConfig {
+    /// Checks the configuration, and returns the number of bytes of a block.
+    fn check(&self) -> usize {
+        if !(self.baud_rate > 0 && self.sample_rate >= 4 * self.baud_rate) {
+            panic!(
+                "Sample rate must be at least 4 times the baud rate, but got {} and {}",
+                self.sample_rate, self.baud_rate
+            );
+        }
+        let nyquist = self.sample_rate as f32 / 2.0;
+        let in_band = |tone: f32| tone > 0.0 && tone < nyquist;
+        if !(in_band(self.mark_hz) && in_band(self.space_hz) && self.mark_hz != self.space_hz) {
+            panic!(
+                "Tones must be different and between 0 and {} Hz, but got {} and {}",
+                nyquist, self.mark_hz, self.space_hz
+            );
+        }
+        if !(self.amplitude > 0.0 && self.amplitude.is_finite()) {
+            panic!(
+                "Amplitude must be positive and finite, but got {}",
+                self.amplitude
+            );
+        }
+        if !(self.clock_gain > 0.0 && self.clock_gain <= 1.0) {
+            panic!(
+                "Clock gain must be above 0 and at most 1, but got {}",
+                self.clock_gain
+            );
+        }
+        (self.baud_rate / gcd(self.baud_rate, 8 * self.sample_rate)) as usize
+    }
+
+    /// Returns the number of samples of the first `bits` bits, up to the sample the last one ends in.
+    fn samples_of_bits(&self, bits: usize) -> usize {
+        (bits as u64 * self.sample_rate as u64).div_ceil(self.baud_rate as u64) as usize
+    }
+}
+
+/// Returns the bits of the bytes, each from the least significant one.
+fn to_bits(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1))
+        .collect()
+}
+
+/// Packs the bits into the bytes of the output, the inverse of [to_bits], the bytes without bits set to 0.
+fn to_bytes(bits: &[u8], output: &mut [u8]) {
+    output.fill(0);
+    for (byte, bits) in output.iter_mut().zip(bits.chunks(8)) {
+        *byte = bits
+            .iter()
+            .enumerate()
+            .fold(0, |byte, (i, &bit)| byte | (bit << i));
+    }
+}
+
+fn check_length(expected: usize, got: usize) -> Result<(), ModemError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+/// Modulates bits into tones, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct AfskModulator {
+    config: AfskConfig,
+    block_bytes: usize,
+}
+
+impl AfskModulator {
+    /// Creates a modulator of the configuration.
+    ///
+    /// # Panics
+    /// If the sample rate is less than 4 times the baud rate, the tones are the same or not between 0 Hz and
+    /// half the sample rate, the amplitude is not positive and finite, or the clock gain not above 0 and at most 1.
+    pub fn new(config: AfskConfig) -> Self {
+        let block_bytes = config.check();
+        AfskModulator {
+            config,
+            block_bytes,
+        }
+    }
+
+    /// Returns the configuration of the modulator.
+    pub fn get_config(&self) -> &AfskConfig {
+        &self.config
+    }
+
+    /// Modulates the levels of a line, a 1 as the mark and a 0 as the space tone, starting at the phase 0,
+    /// into the samples up to the one the last level ends in.
+    pub fn modulate_bits(&self, levels: &[u8]) -> Vec<f32> {
+        let config = &self.config;
+        let rate = config.sample_rate as f64;
+        let steps = [
+            TAU * config.space_hz as f64 / rate,
+            TAU * config.mark_hz as f64 / rate,
+        ];
+        let mut phase: f64 = 0.0;
+        (0..config.samples_of_bits(levels.len()))
+            .map(|n| {
+                let bit = (n as u64 * config.baud_rate as u64 / config.sample_rate as u64) as usize;
+                let sample = config.amplitude * phase.sin() as f32;
+                phase = (phase + steps[usize::from(levels[bit] & 1)]) % TAU;
+                sample
+            })
+            .collect()
+    }
+
+    /// Modulates an HDLC frame, after the flags of the preamble and NRZI encoded, like a packet radio TNC sends it.
+    ///
+    /// The frame is the bytes of an AX.25 frame without the frame check sequence, which is added.
+    pub fn modulate_packet(&self, frame: &[u8]) -> Vec<f32> {
+        let bits = encode_frame(frame, self.config.preamble_flags);
+        self.modulate_bits(&nrzi_encode(&bits, IDLE_LEVEL))
+    }
+}
+
+impl PhyModulator for AfskModulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_of_bits(8 * self.block_bytes)
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.block_bytes
+    }
+
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+        check_length(self.block_bytes, data.len())?;
+        check_length(self.samples_per_block(), output.len())?;
+        output.copy_from_slice(&self.modulate_bits(&to_bits(data)));
+        Ok(())
+    }
+
+    /// Modulates the bits of the payload, the last block padded with zeros, with the phase continuous from block to block.
+    fn modulate_frame(&self, payload: &[u8]) -> Vec<f32> {
+        let mut data = payload.to_vec();
+        data.resize(self.frame_blocks(payload.len()) * self.block_bytes, 0);
+        self.modulate_bits(&to_bits(&data))
+    }
+}
+
+/// Demodulates tones into bits, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct AfskDemodulator {
+    config: AfskConfig,
+    block_bytes: usize,
+}
+
+impl AfskDemodulator {
+    /// Creates a demodulator of the configuration.
+    ///
+    /// # Panics
+    /// See [AfskModulator::new].
+    pub fn new(config: AfskConfig) -> Self {
+        let block_bytes = config.check();
+        AfskDemodulator {
+            config,
+            block_bytes,
+        }
+    }
+
+    /// Returns the configuration of the demodulator.
+    pub fn get_config(&self) -> &AfskConfig {
+        &self.config
+    }
+
+    /// Returns the levels of the line in the samples, 1 for the mark and 0 for the space tone,
+    /// one for every bit the bit clock counts, which starts at the first sample.
+    ///
+    /// Every sample gets the power of both tones over the bit before it, and their difference relative to their sum
+    /// changes sign where the tone changes. The bit clock counts the baud rate, and reads a level when a bit ends,
+    /// half a bit after the change, towards which every change of the tone moves it by the clock gain,
+    /// and the last bit if more than half of it is in the samples.
+    pub fn demodulate_bits(&self, samples: &[f32]) -> Vec<u8> {
+        let config = &self.config;
+        let rate = config.sample_rate as f64;
+        let window = (rate / config.baud_rate as f64).round() as usize;
+        let oscillator = |frequency: f32| {
+            let step = TAU * frequency as f64 / rate;
+            move |n: usize| Complex64::from_polar(1.0, -step * n as f64)
+        };
+        let (mark, space) = (oscillator(config.mark_hz), oscillator(config.space_hz));
+
+        // the clock in units of a bit per sample rate, reading a bit when it passes the sample rate
+        let period = config.sample_rate as i64;
+        // a change shows half a bit after the edge of a bit, when the window holds as much of both bits,
+        // which is read at the sample after it or at that one
+        let change = (period + config.baud_rate as i64) / 2;
+        let gain = config.clock_gain as f64;
+        let mut clock = 0;
+        let mut sums = [Complex64::default(); 2];
+        let mut previous = None;
+        let mut levels = Vec::new();
+        for (n, &sample) in samples.iter().enumerate() {
+            sums[0] += mark(n) * sample as f64;
+            sums[1] += space(n) * sample as f64;
+            if n >= window {
+                let old = samples[n - window] as f64;
+                sums[0] -= mark(n - window) * old;
+                sums[1] -= space(n - window) * old;
+            }
+            let (mark_power, space_power) = (sums[0].norm(), sums[1].norm());
+            let difference =
+                (mark_power - space_power) / (mark_power + space_power).max(f64::MIN_POSITIVE);
+
+            let level = u8::from(difference > 0.0);
+
+            clock += config.baud_rate as i64;
+            if previous.is_some_and(|previous| previous != level) {
+                clock -= ((clock - change) as f64 * gain).round() as i64;
+            }
+            previous = Some(level);
+            if clock >= period {
+                clock -= period;
+                levels.push(level);
+            }
+        }
+        // a last bit the clock runs late for
+        if let Some(level) = previous.filter(|_| clock > period / 2) {
+            levels.push(level);
+        }
+        levels
+    }
+
+    /// Finds the HDLC frames NRZI encoded in the samples, like a packet radio TNC, and returns the ones
+    /// whose frame check sequence matches, without it.
+    pub fn decode_packets(&self, samples: &[f32]) -> Vec<Vec<u8>> {
+        let levels = self.demodulate_bits(samples);
+        HdlcDecoder::new().push(&nrzi_decode(&levels, IDLE_LEVEL))
+    }
+}
+
+impl PhyDemodulator for AfskDemodulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_of_bits(8 * self.block_bytes)
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.block_bytes
+    }
+
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+        check_length(self.samples_per_block(), samples.len())?;
+        check_length(self.block_bytes, output.len())?;
+        to_bytes(&self.demodulate_bits(samples), output);
+        Ok(())
+    }
+
+    /// Demodulates the bits of the whole blocks of a frame with one bit clock, which follows the tones across the blocks.
+    fn demodulate_frame(&self, samples: &[f32]) -> Vec<u8> {
+        let blocks = samples.len() / self.samples_per_block();
+        let levels = self.demodulate_bits(&samples[..blocks * self.samples_per_block()]);
+        let mut payload = vec![0; blocks * self.block_bytes];
+        to_bytes(&levels, &mut payload);
+        payload
+    }
+}
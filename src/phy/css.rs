@@ -0,0 +1,539 @@
+//! This module provides a chirp spread spectrum modem like LoRa, for beacons far below the noise and ranging.
+//!
+//! A symbol of `spreading_factor` bits is a chirp, whose frequency sweeps up over the bandwidth around the carrier
+//! in `2^spreading_factor` chips of `1 / bandwidth` seconds each. The value of the symbol shifts the chirp cyclically:
+//! a symbol of value `k` starts `k` chips up the sweep, and wraps around to the bottom of the band when it reaches the top.
+//! The phase stays continuous throughout, so the signal has a constant envelope.
+//!
+//! The [CssDemodulator] mixes a symbol down to the band, sums the samples of every chip, and multiplies the chips
+//! with a down-chirp, which turns the chirp of value `k` into a tone of `k` cycles over the symbol.
+//! An FFT of the chips then puts all of its power into the bin `k`, while the noise spreads over all of them, a gain
+//! of `2^spreading_factor` in the signal-to-noise ratio. The values are Gray coded, so the bin next to the right one,
+//! the most likely mistake, costs a single bit.
+//!
+//! A frame starts with a preamble of chirps of the value 0. Over a window of a symbol that starts `d` samples into
+//! such a chirp, the FFT peaks at `d` over the samples of a chip, so windows one after the other in a preamble peak
+//! at the same bin, and the bin tells the [detector](CssDemodulator::detect_preamble) where the chirps start.
+//! A carrier frequency offset moves the peak as well, and so shifts the timing, by a chip for every `bandwidth / 2^spreading_factor` Hz.
+//!
+//! # Example
+//! ```
+//! use software_modem::frame::{FrameDecoder, FrameEncoder};
+//! use software_modem::phy::css::{CssConfig, CssDemodulator, CssModulator};
+//!
+//! let encoder = FrameEncoder::new(CssModulator::new(CssConfig::default()));
+//! let decoder = FrameDecoder::new(CssDemodulator::new(CssConfig::default()));
+//!
+//! // 8 symbols of 7 bits carry 7 bytes, after a preamble of 8 chirps of 128 chips of 8 samples
+//! assert_eq!(encoder.get_bytes_per_symbol(), 7);
+//! let samples = encoder.encode(b"beacon");
+//! assert_eq!(samples.len(), (8 + 8) * 128 * 8);
+//!
+//! // the preamble finds the frame after silence
+//! let mut received = vec![0.0; 3000];
+//! received.extend(samples);
+//! assert_eq!(&decoder.decode(&received)[..6], b"beacon");
+//! ```
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::f64::consts::TAU;
+
+use num_complex::Complex32;
+
+use crate::{
+    dsp::gcd,
+    error::ModemError,
+    fft::{ComplexFft, plan_complex_forward},
+    phy::{PhyDemodulator, PhyModulator},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The windows in a row that must peak at the same bin to detect a preamble.
+const PREAMBLE_MATCHES: usize = 3;
+
+/// How many times the mean power of the bins the peak of a window of a preamble must have.
+const PREAMBLE_THRESHOLD: f32 = 4.0;
+
+/// The configuration of a [CssModulator] and a [CssDemodulator].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CssConfig {
+    /// The sample rate in Hz, a whole multiple of at least 2 times the bandwidth.
+    pub sample_rate: u32,
+    /// The bandwidth the chirps sweep over in Hz, also the number of chips per second.
+    pub bandwidth: u32,
+    /// The number of bits of a symbol, from 6 to 12, a symbol `2^spreading_factor` chips long.
+    pub spreading_factor: u32,
+    /// The center of the band in Hz.
+    pub carrier_hz: f32,
+    /// The peak of the chirps.
+    pub amplitude: f32,
+    /// The number of chirps of the value 0 before the symbols of a frame, at least 4.
+    pub preamble_chirps: usize,
+}
+
+impl Default for CssConfig {
+    /// Spreading factor 7 over 1000 Hz from 1000 to 2000 Hz, at a sample rate of 8000 Hz.
+    fn default() -> Self {
+        CssConfig {
+            sample_rate: 8000,
+            bandwidth: 1000,
+            spreading_factor: 7,
+            carrier_hz: 1500.0,
+            amplitude: 0.5,
+            preamble_chirps: 8,
+        }
+    }
+}
+
+impl CssConfig {
+    /// Returns the number of chips of a symbol, `2^spreading_factor`.
+    pub fn get_chips(&self) -> usize {
+        1 << self.spreading_factor
+    }
+
+    /// Returns the number of samples of a chip.
+    pub fn get_samples_per_chip(&self) -> usize {
+        (self.sample_rate / self.bandwidth.max(1)) as usize
+    }
+
+    /// Returns the number of samples of a symbol.
+    pub fn get_samples_per_symbol(&self) -> usize {
+        self.get_chips() * self.get_samples_per_chip()
+    }
+
+    /// Returns the number of symbols of a block, the fewest that carry whole bytes.
+    pub fn get_symbols_per_block(&self) -> usize {
+        (8 / gcd(self.spreading_factor, 8)) as usize
+    }
+
+    fn check(&self) {
+        if !(6..=12).contains(&self.spreading_factor) {
+            panic!(
+                "Spreading factor must be between 6 and 12, but got {}",
+                self.spreading_factor
+            );
+        }
+        if !(self.bandwidth > 0
+            && self.sample_rate >= 2 * self.bandwidth
+            && self.sample_rate.is_multiple_of(self.bandwidth))
+        {
+            panic!(
+                "Sample rate must be a whole multiple of at least 2 times the bandwidth, but got {} and {}",
+                self.sample_rate, self.bandwidth
+            );
+        }
+        let half_band = self.bandwidth as f32 / 2.0;
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if !(self.carrier_hz - half_band > 0.0 && self.carrier_hz + half_band < nyquist) {
+            panic!(
+                "Band must be between 0 and {} Hz, but got {} to {} Hz",
+                nyquist,
+                self.carrier_hz - half_band,
+                self.carrier_hz + half_band
+            );
+        }
+        if !(self.amplitude > 0.0 && self.amplitude.is_finite()) {
+            panic!(
+                "Amplitude must be positive and finite, but got {}",
+                self.amplitude
+            );
+        }
+        if self.preamble_chirps < PREAMBLE_MATCHES + 1 {
+            panic!(
+                "Preamble must be at least {} chirps, but got {}",
+                PREAMBLE_MATCHES + 1,
+                self.preamble_chirps
+            );
+        }
+    }
+
+    fn samples_per_block(&self) -> usize {
+        self.get_symbols_per_block() * self.get_samples_per_symbol()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.get_symbols_per_block() * self.spreading_factor as usize / 8
+    }
+
+    /// Returns the radians the carrier turns by every sample.
+    fn carrier_step(&self) -> f64 {
+        TAU * self.carrier_hz as f64 / self.sample_rate as f64
+    }
+
+    /// Returns the chips of the symbols of the bytes, `spreading_factor` of their bits each from the most significant one,
+    /// the inverse of their Gray code.
+    fn map(&self, data: &[u8]) -> Vec<u32> {
+        let bits = self.spreading_factor;
+        let mut symbols = Vec::with_capacity(data.len() * 8 / bits as usize);
+        let (mut buffer, mut buffered) = (0u32, 0);
+        for &byte in data {
+            buffer = (buffer << 8) | byte as u32;
+            buffered += 8;
+            while buffered >= bits {
+                buffered -= bits;
+                let mut value = (buffer >> buffered) & ((1 << bits) - 1);
+                let mut shift = 0;
+                while value != 0 {
+                    shift ^= value;
+                    value >>= 1;
+                }
+                symbols.push(shift);
+            }
+            buffer &= (1 << buffered) - 1;
+        }
+        symbols
+    }
+
+    /// Packs the Gray codes of the chips of the symbols into the bytes of the output, the inverse of [map](Self::map).
+    fn unmap(&self, symbols: &[u32], output: &mut [u8]) {
+        let bits = self.spreading_factor;
+        let (mut buffer, mut buffered) = (0u32, 0);
+        let mut bytes = output.iter_mut();
+        for &shift in symbols {
+            buffer = (buffer << bits) | (shift ^ (shift >> 1));
+            buffered += bits;
+            while buffered >= 8 {
+                buffered -= 8;
+                match bytes.next() {
+                    Some(byte) => *byte = (buffer >> buffered) as u8,
+                    None => return,
+                }
+            }
+            buffer &= (1 << buffered) - 1;
+        }
+    }
+}
+
+fn check_length(expected: usize, got: usize) -> Result<(), ModemError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+/// Modulates bytes into chirps, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct CssModulator {
+    config: CssConfig,
+}
+
+impl CssModulator {
+    /// Creates a modulator of the configuration.
+    ///
+    /// # Panics
+    /// If the spreading factor is not between 6 and 12, the sample rate is not a whole multiple of at least
+    /// 2 times the bandwidth, the band is not between 0 Hz and half the sample rate, the amplitude is not positive
+    /// and finite, or the preamble is shorter than 4 chirps.
+    pub fn new(config: CssConfig) -> Self {
+        config.check();
+        CssModulator { config }
+    }
+
+    /// Returns the configuration of the modulator.
+    pub fn get_config(&self) -> &CssConfig {
+        &self.config
+    }
+
+    /// Modulates symbols, each the number of chips its chirp is shifted by, one after the other,
+    /// the phase of the carrier continuous from the first sample.
+    ///
+    /// # Panics
+    /// If a symbol is not below `2^spreading_factor`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::phy::css::{CssConfig, CssDemodulator, CssModulator};
+    ///
+    /// let config = CssConfig::default();
+    /// let samples = CssModulator::new(config.clone()).modulate_symbols(&[0, 1, 64, 127]);
+    /// assert_eq!(samples.len(), 4 * 1024);
+    /// assert_eq!(CssDemodulator::new(config).demodulate_symbols(&samples), [0, 1, 64, 127]);
+    /// ```
+    pub fn modulate_symbols(&self, symbols: &[u32]) -> Vec<f32> {
+        let config = &self.config;
+        let chips = config.get_chips();
+        let samples_per_chip = config.get_samples_per_chip();
+        let step = config.carrier_step();
+        let mut samples = Vec::with_capacity(symbols.len() * config.get_samples_per_symbol());
+        for &symbol in symbols {
+            if symbol as usize >= chips {
+                panic!("Symbol must be below {}, but got {}", chips, symbol);
+            }
+            let wrap = (chips - symbol as usize) as f64;
+            for n in 0..config.get_samples_per_symbol() {
+                // the time in chips, and the cycles of the sweep up to it, which lose one after the wrap
+                let u = n as f64 / samples_per_chip as f64;
+                let mut cycles = (u * u / 2.0 + symbol as f64 * u) / chips as f64 - u / 2.0;
+                if u >= wrap {
+                    cycles -= u - wrap;
+                }
+                let phase = (step * samples.len() as f64) % TAU + TAU * cycles.fract();
+                samples.push(config.amplitude * phase.cos() as f32);
+            }
+        }
+        samples
+    }
+
+    /// Modulates the bytes into the symbols of a whole number of blocks, the last one padded with zeros.
+    fn modulate_bytes(&self, data: &[u8]) -> Vec<u32> {
+        let capacity = self.config.capacity_bytes();
+        let mut data = data.to_vec();
+        data.resize(data.len().div_ceil(capacity) * capacity, 0);
+        self.config.map(&data)
+    }
+}
+
+impl PhyModulator for CssModulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.config.capacity_bytes()
+    }
+
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+        check_length(self.capacity_bytes(), data.len())?;
+        check_length(self.samples_per_block(), output.len())?;
+        output.copy_from_slice(&self.modulate_symbols(&self.config.map(data)));
+        Ok(())
+    }
+
+    fn frame_length(&self, payload_length: usize) -> usize {
+        self.config.preamble_chirps * self.config.get_samples_per_symbol()
+            + self.frame_blocks(payload_length) * self.samples_per_block()
+    }
+
+    /// Modulates the preamble and the symbols of the payload as one stream of chirps.
+    fn modulate_frame(&self, payload: &[u8]) -> Vec<f32> {
+        let mut symbols = vec![0; self.config.preamble_chirps];
+        symbols.extend(self.modulate_bytes(payload));
+        self.modulate_symbols(&symbols)
+    }
+}
+
+/// Demodulates chirps into bytes, see the [module](self).
+#[derive(Clone)]
+pub struct CssDemodulator {
+    config: CssConfig,
+    fft: Arc<dyn ComplexFft<f32>>,
+    /// The conjugate of the chirp of the value 0 at the start of every chip.
+    down_chirp: Vec<Complex32>,
+}
+
+impl CssDemodulator {
+    /// Creates a demodulator of the configuration.
+    ///
+    /// # Panics
+    /// See [CssModulator::new].
+    pub fn new(config: CssConfig) -> Self {
+        config.check();
+        let chips = config.get_chips();
+        let down_chirp = (0..chips)
+            .map(|m| {
+                let u = m as f64;
+                let cycles = (u * u / 2.0 / chips as f64 - u / 2.0).fract();
+                let phase = TAU * cycles;
+                Complex32::new(phase.cos() as f32, -phase.sin() as f32)
+            })
+            .collect();
+        CssDemodulator {
+            fft: plan_complex_forward(chips),
+            config,
+            down_chirp,
+        }
+    }
+
+    /// Returns the configuration of the demodulator.
+    pub fn get_config(&self) -> &CssConfig {
+        &self.config
+    }
+
+    /// Returns the power of every bin of the FFT of the dechirped symbol which starts at a sample, `2^spreading_factor`
+    /// of them, with zeros where the symbol reaches beyond the samples.
+    ///
+    /// The chips are summed over windows of a chip centered at their starts, the one of the first chip cyclically
+    /// from the end of the symbol, so the phase of a chip is the one of the chirp at its start.
+    /// A sample halfway between two starts counts half to both.
+    pub fn bin_powers(&self, samples: &[f32], start: isize) -> Vec<f32> {
+        let config = &self.config;
+        let samples_per_chip = config.get_samples_per_chip();
+        let samples_per_symbol = config.get_samples_per_symbol();
+        let step = config.carrier_step();
+        let num_chips = config.get_chips();
+        let mut chips = vec![Complex32::new(0.0, 0.0); num_chips];
+        for n in 0..samples_per_symbol {
+            let position = start + n as isize;
+            let Some(&sample) = usize::try_from(position)
+                .ok()
+                .and_then(|position| samples.get(position))
+            else {
+                continue;
+            };
+            let phase = (step * position as f64) % TAU;
+            let value = Complex32::new(phase.cos() as f32, -phase.sin() as f32) * sample;
+            let chip = (n + samples_per_chip / 2) / samples_per_chip % num_chips;
+            if samples_per_chip.is_multiple_of(2) && n % samples_per_chip == samples_per_chip / 2 {
+                // halfway between the starts of two chips
+                chips[chip] += value / 2.0;
+                chips[(chip + num_chips - 1) % num_chips] += value / 2.0;
+            } else {
+                chips[chip] += value;
+            }
+        }
+        for (chip, reference) in chips.iter_mut().zip(&self.down_chirp) {
+            *chip *= reference;
+        }
+        self.fft.process(&mut chips);
+        chips.iter().map(|bin| bin.norm_sqr()).collect()
+    }
+
+    /// Demodulates the symbols which follow one after the other from the first sample, each the number of chips its
+    /// chirp is shifted by, the bin of the FFT with the most power.
+    pub fn demodulate_symbols(&self, samples: &[f32]) -> Vec<u32> {
+        self.demodulate_from(
+            samples,
+            0,
+            samples.len() / self.config.get_samples_per_symbol(),
+        )
+    }
+
+    /// Returns the sample where a preamble of chirps of the value 0 starts, if the samples have one.
+    ///
+    /// The samples are cut into windows of a symbol. A preamble makes windows in a row peak at the same bin,
+    /// well above the mean of the others, which gives where its chirps start to a chip. The start is then
+    /// refined to the sample with the most power in the bin 0, and moved back over the chirps before it
+    /// with at least half of that power.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::phy::css::{CssConfig, CssDemodulator, CssModulator};
+    ///
+    /// let config = CssConfig::default();
+    /// let mut samples = vec![0.0; 5000];
+    /// samples.extend(CssModulator::new(config.clone()).modulate_symbols(&[0; 8]));
+    /// let demodulator = CssDemodulator::new(config);
+    /// assert_eq!(demodulator.detect_preamble(&samples), Some(5000));
+    /// assert_eq!(demodulator.detect_preamble(&samples[..5000]), None);
+    /// ```
+    pub fn detect_preamble(&self, samples: &[f32]) -> Option<usize> {
+        let chips = self.config.get_chips();
+        let samples_per_chip = self.config.get_samples_per_chip() as isize;
+        let samples_per_symbol = self.config.get_samples_per_symbol() as isize;
+
+        let peaks: Vec<Option<(usize, f32)>> = (0..samples.len() as isize / samples_per_symbol)
+            .map(|window| {
+                let powers = self.bin_powers(samples, window * samples_per_symbol);
+                let (bin, peak) = strongest(&powers);
+                let mean = powers.iter().sum::<f32>() / chips as f32;
+                (peak > PREAMBLE_THRESHOLD * mean).then_some((bin, peak))
+            })
+            .collect();
+        // the strongest of the first windows in a row that peaked at the same bin, within one,
+        // as the first may have held only the end of a chirp
+        let (window, bin) =
+            peaks
+                .windows(PREAMBLE_MATCHES)
+                .enumerate()
+                .find_map(|(first, peaks)| {
+                    let (bin, _) = peaks[0]?;
+                    let matched = peaks.iter().all(|peak| {
+                        peak.is_some_and(|(peak, _)| {
+                            let distance = (peak + chips - bin) % chips;
+                            distance <= 1 || distance == chips - 1
+                        })
+                    });
+                    matched.then(|| {
+                        let (offset, (bin, _)) = peaks.iter().flatten().enumerate().fold(
+                            (0, (bin, f32::NEG_INFINITY)),
+                            |best, (offset, &peak)| {
+                                if peak.1 > best.1.1 {
+                                    (offset, peak)
+                                } else {
+                                    best
+                                }
+                            },
+                        );
+                        ((first + offset) as isize, bin as isize)
+                    })
+                })?;
+
+        // the chirp of that window, within a chip of the coarse start
+        let coarse = window * samples_per_symbol - bin * samples_per_chip;
+        let (mut start, reference) = (coarse - samples_per_chip..=coarse + samples_per_chip)
+            .map(|start| (start, self.bin_powers(samples, start)[0]))
+            .fold((coarse, f32::NEG_INFINITY), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        while start - samples_per_symbol > -samples_per_chip
+            && self.bin_powers(samples, start - samples_per_symbol)[0] > reference / 2.0
+        {
+            start -= samples_per_symbol;
+        }
+        Some(start.max(0) as usize)
+    }
+
+    /// Demodulates a number of symbols one after the other from a sample.
+    fn demodulate_from(&self, samples: &[f32], start: usize, num_symbols: usize) -> Vec<u32> {
+        let samples_per_symbol = self.config.get_samples_per_symbol();
+        (0..num_symbols)
+            .map(|symbol| {
+                let powers =
+                    self.bin_powers(samples, (start + symbol * samples_per_symbol) as isize);
+                strongest(&powers).0 as u32
+            })
+            .collect()
+    }
+}
+
+/// Returns the index and the value of the largest value.
+fn strongest(values: &[f32]) -> (usize, f32) {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (index, &value)| {
+            if value > best.1 { (index, value) } else { best }
+        })
+}
+
+impl PhyDemodulator for CssDemodulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.config.capacity_bytes()
+    }
+
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+        check_length(self.samples_per_block(), samples.len())?;
+        check_length(self.capacity_bytes(), output.len())?;
+        self.config.unmap(&self.demodulate_symbols(samples), output);
+        Ok(())
+    }
+
+    fn frame_length(&self, payload_length: usize) -> usize {
+        self.config.preamble_chirps * self.config.get_samples_per_symbol()
+            + payload_length.div_ceil(self.capacity_bytes()) * self.samples_per_block()
+    }
+
+    /// Demodulates the whole blocks after the [preamble](CssDemodulator::detect_preamble) of a frame,
+    /// or after the first sample if there is none.
+    fn demodulate_frame(&self, samples: &[f32]) -> Vec<u8> {
+        let start = self.detect_preamble(samples).unwrap_or(0)
+            + self.config.preamble_chirps * self.config.get_samples_per_symbol();
+        let blocks = samples.len().saturating_sub(start) / self.samples_per_block();
+        let symbols =
+            self.demodulate_from(samples, start, blocks * self.config.get_symbols_per_block());
+        let mut payload = vec![0; blocks * self.capacity_bytes()];
+        self.config.unmap(&symbols, &mut payload);
+        payload
+    }
+}
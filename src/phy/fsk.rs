@@ -0,0 +1,406 @@
+//! This module provides a non-coherent FSK modem, every symbol one of 2 or 4 tones, for slow and robust links.
+//!
+//! The [FskModulator] sends a tone for every symbol, continuing the phase from tone to tone, so the signal
+//! has a constant envelope and passes amplifiers that clip. The [FskDemodulator] measures the power of every tone
+//! over a symbol with the [Goertzel algorithm](crate::dsp::goertzel_power) and decides for the strongest,
+//! without knowing the phase of the carrier.
+//!
+//! The tones are orthogonal, so a symbol of one tone has no power in the others, if their spacing is a whole multiple
+//! of the symbol rate: a tone `k * symbol_rate` away turns `k` whole cycles more over a symbol.
+//! [FskConfig::is_orthogonal] checks it, and the modulator and demodulator refuse other spacings.
+//!
+//! The demodulator finds the timing of the symbols of a frame from their energy: a window on a symbol
+//! has all of its power in one tone, while a window across two symbols splits it between two.
+//! It tries offsets within half a symbol of the start of the frame, and reads the symbols at the one
+//! where the strongest tones have the most power.
+//!
+//! # Example
+//! ```
+//! use software_modem::frame::{FrameDecoder, FrameEncoder};
+//! use software_modem::phy::fsk::{FskConfig, FskDemodulator, FskModulator, FskOrder};
+//!
+//! let config = FskConfig {
+//!     order: FskOrder::Quaternary,
+//!     ..Default::default()
+//! };
+//! let encoder = FrameEncoder::new(FskModulator::new(config.clone()));
+//! let decoder = FrameDecoder::new(FskDemodulator::new(config));
+//!
+//! // 4 symbols of 80 samples for every byte, starting 30 samples late
+//! let mut samples = vec![0.0; 30];
+//! samples.extend(encoder.encode(b"telemetry"));
+//! assert_eq!(samples.len(), 30 + 9 * 4 * 80);
+//! assert_eq!(decoder.decode(&samples[..9 * 4 * 80]), b"telemetry");
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::f64::consts::TAU;
+
+use crate::{
+    dsp::goertzel_power,
+    error::ModemError,
+    phy::{PhyDemodulator, PhyModulator},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The number of offsets within a symbol the timing of a frame is searched at.
+const TIMING_STEPS: usize = 16;
+
+/// The number of tones of an [FskConfig].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FskOrder {
+    /// 2 tones, a bit per symbol.
+    #[default]
+    Binary,
+    /// 4 tones, 2 bits per symbol, Gray coded so the neighbouring tones differ in one bit.
+    Quaternary,
+}
+
+impl FskOrder {
+    /// Returns the number of tones.
+    pub fn tones(&self) -> usize {
+        match self {
+            FskOrder::Binary => 2,
+            FskOrder::Quaternary => 4,
+        }
+    }
+
+    /// Returns the number of bits a symbol carries.
+    pub fn bits_per_symbol(&self) -> u32 {
+        self.tones().trailing_zeros()
+    }
+}
+
+/// The configuration of an [FskModulator] and an [FskDemodulator].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FskConfig {
+    /// The number of tones.
+    pub order: FskOrder,
+    /// The sample rate in Hz.
+    pub sample_rate: u32,
+    /// The number of symbols per second, which the sample rate must be a whole multiple of.
+    pub symbol_rate: u32,
+    /// The frequency of the lowest tone in Hz.
+    pub base_hz: f32,
+    /// The distance between neighbouring tones in Hz, a whole multiple of the symbol rate.
+    pub tone_spacing_hz: f32,
+    /// The peak of the tones.
+    pub amplitude: f32,
+}
+
+impl Default for FskConfig {
+    /// 2-FSK at 100 symbols per second, with tones at 1000 and 1200 Hz at a sample rate of 8000 Hz.
+    fn default() -> Self {
+        FskConfig {
+            order: FskOrder::default(),
+            sample_rate: 8000,
+            symbol_rate: 100,
+            base_hz: 1000.0,
+            tone_spacing_hz: 200.0,
+            amplitude: 0.5,
+        }
+    }
+}
+
+impl FskConfig {
+    /// Returns whether the tones are orthogonal over a symbol, their spacing a positive whole multiple of the symbol rate.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::phy::fsk::FskConfig;
+    ///
+    /// assert!(FskConfig::default().is_orthogonal());
+    /// for tone_spacing_hz in [100.0, 300.0, 1000.0] {
+    ///     assert!(FskConfig { tone_spacing_hz, ..Default::default() }.is_orthogonal());
+    /// }
+    /// for tone_spacing_hz in [0.0, 50.0, 150.0, -200.0] {
+    ///     assert!(!FskConfig { tone_spacing_hz, ..Default::default() }.is_orthogonal());
+    /// }
+    /// ```
+    pub fn is_orthogonal(&self) -> bool {
+        let cycles = self.tone_spacing_hz as f64 / self.symbol_rate as f64;
+        cycles.round() >= 1.0 && (cycles - cycles.round()).abs() < 1e-6
+    }
+
+    /// Returns the frequency of every tone in Hz, from the lowest.
+    pub fn get_tones(&self) -> Vec<f32> {
+        (0..self.order.tones())
+            .map(|tone| self.base_hz + tone as f32 * self.tone_spacing_hz)
+            .collect()
+    }
+
+    /// Returns the number of samples of a symbol.
+    pub fn get_samples_per_symbol(&self) -> usize {
+        (self.sample_rate / self.symbol_rate.max(1)) as usize
+    }
+
+    fn check(&self) {
+        if !(self.symbol_rate > 0
+            && self.sample_rate >= 2 * self.symbol_rate
+            && self.sample_rate.is_multiple_of(self.symbol_rate))
+        {
+            panic!(
+                "Sample rate must be a whole multiple of at least 2 times the symbol rate, but got {} and {}",
+                self.sample_rate, self.symbol_rate
+            );
+        }
+        if !self.is_orthogonal() {
+            panic!(
+                "Tone spacing must be a whole multiple of the symbol rate of {} Hz for orthogonal tones, but got {} Hz",
+                self.symbol_rate, self.tone_spacing_hz
+            );
+        }
+        let tones = self.get_tones();
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if !(tones[0] > 0.0 && tones[tones.len() - 1] < nyquist) {
+            panic!(
+                "Tones must be between 0 and {} Hz, but got {} to {} Hz",
+                nyquist,
+                tones[0],
+                tones[tones.len() - 1]
+            );
+        }
+        if !(self.amplitude > 0.0 && self.amplitude.is_finite()) {
+            panic!(
+                "Amplitude must be positive and finite, but got {}",
+                self.amplitude
+            );
+        }
+    }
+
+    /// Returns the number of samples of a block, a byte.
+    fn samples_per_block(&self) -> usize {
+        8 / self.order.bits_per_symbol() as usize * self.get_samples_per_symbol()
+    }
+
+    /// Returns the tone of every symbol of the bytes, the bits of each from the most significant one,
+    /// Gray coded.
+    fn map(&self, data: &[u8]) -> Vec<usize> {
+        let bits = self.order.bits_per_symbol() as usize;
+        let mask = (1 << bits) - 1;
+        data.iter()
+            .flat_map(|&byte| {
+                (0..8 / bits).rev().map(move |position| {
+                    let value = (byte as usize >> (position * bits)) & mask;
+                    // the inverse of the Gray code of the tone
+                    value ^ (value >> 1)
+                })
+            })
+            .collect()
+    }
+
+    /// Packs the tones of the symbols into the bytes of the output, the inverse of [map](Self::map).
+    fn unmap(&self, tones: &[usize], output: &mut [u8]) {
+        let bits = self.order.bits_per_symbol() as usize;
+        for (byte, tones) in output.iter_mut().zip(tones.chunks(8 / bits)) {
+            *byte = tones
+                .iter()
+                .fold(0, |byte, &tone| (byte << bits) | (tone ^ (tone >> 1)) as u8);
+        }
+    }
+}
+
+fn check_length(expected: usize, got: usize) -> Result<(), ModemError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+/// Modulates bytes into tones, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct FskModulator {
+    config: FskConfig,
+}
+
+impl FskModulator {
+    /// Creates a modulator of the configuration.
+    ///
+    /// # Panics
+    /// If the sample rate is not a whole multiple of at least 2 times the symbol rate, the tones are not
+    /// [orthogonal](FskConfig::is_orthogonal) or not between 0 Hz and half the sample rate,
+    /// or the amplitude is not positive and finite.
+    pub fn new(config: FskConfig) -> Self {
+        config.check();
+        FskModulator { config }
+    }
+
+    /// Returns the configuration of the modulator.
+    pub fn get_config(&self) -> &FskConfig {
+        &self.config
+    }
+
+    /// Modulates the bytes into a tone for every symbol, starting at the phase 0.
+    fn modulate_bytes(&self, data: &[u8]) -> Vec<f32> {
+        let config = &self.config;
+        let rate = config.sample_rate as f64;
+        let steps: Vec<f64> = config
+            .get_tones()
+            .iter()
+            .map(|&tone| TAU * tone as f64 / rate)
+            .collect();
+        let mut phase: f64 = 0.0;
+        config
+            .map(data)
+            .into_iter()
+            .flat_map(|tone| core::iter::repeat_n(steps[tone], config.get_samples_per_symbol()))
+            .map(|step| {
+                let sample = config.amplitude * phase.sin() as f32;
+                phase = (phase + step) % TAU;
+                sample
+            })
+            .collect()
+    }
+}
+
+impl PhyModulator for FskModulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        1
+    }
+
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+        check_length(1, data.len())?;
+        check_length(self.samples_per_block(), output.len())?;
+        output.copy_from_slice(&self.modulate_bytes(data));
+        Ok(())
+    }
+
+    /// Modulates the bytes of the payload with the phase continuous from symbol to symbol over the whole frame.
+    fn modulate_frame(&self, payload: &[u8]) -> Vec<f32> {
+        self.modulate_bytes(payload)
+    }
+}
+
+/// Demodulates tones into bytes, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct FskDemodulator {
+    config: FskConfig,
+    /// The tones as fractions of the sample rate.
+    frequencies: Vec<f32>,
+}
+
+impl FskDemodulator {
+    /// Creates a demodulator of the configuration.
+    ///
+    /// # Panics
+    /// See [FskModulator::new].
+    pub fn new(config: FskConfig) -> Self {
+        config.check();
+        let frequencies = config
+            .get_tones()
+            .iter()
+            .map(|&tone| tone / config.sample_rate as f32)
+            .collect();
+        FskDemodulator {
+            config,
+            frequencies,
+        }
+    }
+
+    /// Returns the configuration of the demodulator.
+    pub fn get_config(&self) -> &FskConfig {
+        &self.config
+    }
+
+    /// Returns the power of every tone over the window.
+    pub fn tone_powers(&self, window: &[f32]) -> Vec<f32> {
+        self.frequencies
+            .iter()
+            .map(|&frequency| goertzel_power(window, frequency))
+            .collect()
+    }
+
+    /// Returns the offset in samples of the symbols of a frame from its first sample, within half a symbol,
+    /// where the strongest tones of the symbols have the most power.
+    pub fn find_timing(&self, samples: &[f32], num_symbols: usize) -> isize {
+        let samples_per_symbol = self.config.get_samples_per_symbol() as isize;
+        let step = (samples_per_symbol / TIMING_STEPS as isize).max(1);
+        (-samples_per_symbol / 2..(samples_per_symbol + 1) / 2)
+            .step_by(step as usize)
+            .map(|offset| {
+                let energy: f32 = (0..num_symbols)
+                    .map(|symbol| {
+                        let powers = self.tone_powers(&self.window(samples, symbol, offset));
+                        powers.into_iter().fold(0.0, f32::max)
+                    })
+                    .sum();
+                (offset, energy)
+            })
+            .fold((0, f32::NEG_INFINITY), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .0
+    }
+
+    /// Returns the samples of a symbol at an offset, with zeros where it reaches beyond the samples.
+    fn window(&self, samples: &[f32], symbol: usize, offset: isize) -> Vec<f32> {
+        let samples_per_symbol = self.config.get_samples_per_symbol();
+        let start = (symbol * samples_per_symbol) as isize + offset;
+        (start..start + samples_per_symbol as isize)
+            .map(|n| {
+                usize::try_from(n)
+                    .ok()
+                    .and_then(|n| samples.get(n))
+                    .copied()
+                    .unwrap_or(0.0)
+            })
+            .collect()
+    }
+
+    /// Returns the strongest tone of every symbol at an offset.
+    fn detect(&self, samples: &[f32], num_symbols: usize, offset: isize) -> Vec<usize> {
+        (0..num_symbols)
+            .map(|symbol| {
+                self.tone_powers(&self.window(samples, symbol, offset))
+                    .iter()
+                    .enumerate()
+                    .fold((0, f32::NEG_INFINITY), |best, (tone, &power)| {
+                        if power > best.1 { (tone, power) } else { best }
+                    })
+                    .0
+            })
+            .collect()
+    }
+}
+
+impl PhyDemodulator for FskDemodulator {
+    fn samples_per_block(&self) -> usize {
+        self.config.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        1
+    }
+
+    /// Demodulates a block at the timing of the transmitter.
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+        check_length(self.samples_per_block(), samples.len())?;
+        check_length(1, output.len())?;
+        let num_symbols = 8 / self.config.order.bits_per_symbol() as usize;
+        self.config
+            .unmap(&self.detect(samples, num_symbols, 0), output);
+        Ok(())
+    }
+
+    /// Demodulates the whole blocks of a frame at the timing [found](FskDemodulator::find_timing) from their energy.
+    fn demodulate_frame(&self, samples: &[f32]) -> Vec<u8> {
+        let blocks = samples.len() / self.samples_per_block();
+        let num_symbols = blocks * 8 / self.config.order.bits_per_symbol() as usize;
+        let offset = self.find_timing(samples, num_symbols);
+        let mut payload = vec![0; blocks];
+        self.config
+            .unmap(&self.detect(samples, num_symbols, offset), &mut payload);
+        payload
+    }
+}
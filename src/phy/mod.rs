@@ -0,0 +1,156 @@
+//! This module provides the traits of a physical layer, the waveform that carries the bytes of a frame.
+//!
+//! A [PhyModulator] turns blocks of a fixed number of bytes into blocks of a fixed number of samples,
+//! and a [PhyDemodulator] turns them back. The [FrameEncoder](crate::frame::FrameEncoder) and the
+//! [FrameDecoder](crate::frame::FrameDecoder) split a payload into such blocks, so the framing works with any
+//! waveform, not just OFDM. The OFDM modulator and demodulator implement both traits with a block for every symbol,
+//! and frame their symbols themselves, with the reference symbol of differential mode, the roll-off and the filters.
+//! The [single carrier](single_carrier) modem sends QAM symbols one after the other in a band of a carrier,
+//! shaped with root-raised-cosine pulses, the [AFSK](afsk) modem sends bits as audio tones like packet radio,
+//! the [FSK](fsk) modem sends symbols of 2 or 4 orthogonal tones, detected without their phase,
+//! and the [chirp spread spectrum](css) modem sends symbols as shifted chirps, which reach far below the noise.
+//!
+//! # Example
+//! A physical layer which sends every byte as a sample of its value, to test the framing without a waveform.
+//! ```
+//! use software_modem::error::ModemError;
+//! use software_modem::frame::{FrameDecoder, FrameEncoder};
+//! use software_modem::phy::{PhyDemodulator, PhyModulator};
+//!
+//! struct Identity;
+//!
+//! impl PhyModulator for Identity {
+//!     fn samples_per_block(&self) -> usize {
+//!         8
+//!     }
+//!     fn capacity_bytes(&self) -> usize {
+//!         8
+//!     }
+//!     fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+//!         output.iter_mut().zip(data).for_each(|(sample, &byte)| *sample = byte as f32);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl PhyDemodulator for Identity {
+//!     fn samples_per_block(&self) -> usize {
+//!         8
+//!     }
+//!     fn capacity_bytes(&self) -> usize {
+//!         8
+//!     }
+//!     fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+//!         output.iter_mut().zip(samples).for_each(|(byte, &sample)| *byte = sample as u8);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let encoder = FrameEncoder::new(Identity);
+//! let decoder = FrameDecoder::new(Identity);
+//! let samples = encoder.encode(b"framed by any PHY");
+//! assert_eq!(samples.len(), encoder.get_frame_length(17));
+//! assert_eq!(samples.len(), 24);
+//! // with the zero padding of the last block
+//! assert_eq!(&decoder.decode(&samples)[..17], b"framed by any PHY");
+//! ```
+
+pub mod afsk;
+pub mod css;
+pub mod fsk;
+pub mod single_carrier;
+
+use alloc::{vec, vec::Vec};
+
+use crate::error::ModemError;
+
+/// Modulates blocks of bytes into blocks of samples, see the [module](self).
+pub trait PhyModulator {
+    /// Returns the number of samples of a block.
+    fn samples_per_block(&self) -> usize;
+
+    /// Returns the number of bytes a block carries.
+    fn capacity_bytes(&self) -> usize;
+
+    /// Modulates the [capacity](Self::capacity_bytes) bytes of the data into the [samples](Self::samples_per_block)
+    /// of a block.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the data or the output does not have the length of a block.
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError>;
+
+    /// Returns the number of blocks of a frame carrying `payload_length` bytes.
+    fn frame_blocks(&self, payload_length: usize) -> usize {
+        payload_length.div_ceil(self.capacity_bytes())
+    }
+
+    /// Returns the number of samples of a frame carrying `payload_length` bytes.
+    fn frame_length(&self, payload_length: usize) -> usize {
+        self.frame_blocks(payload_length) * self.samples_per_block()
+    }
+
+    /// Modulates the payload into a frame of [frame_length](Self::frame_length) samples.
+    ///
+    /// By default, every block of the payload is modulated on its own, the last one padded with zeros.
+    fn modulate_frame(&self, payload: &[u8]) -> Vec<f32> {
+        let capacity = self.capacity_bytes();
+        let mut samples = vec![0.0; self.frame_length(payload.len())];
+        let mut data = vec![0; capacity];
+        for (chunk, output) in payload
+            .chunks(capacity)
+            .zip(samples.chunks_exact_mut(self.samples_per_block()))
+        {
+            data.fill(0);
+            data[..chunk.len()].copy_from_slice(chunk);
+            self.modulate(&data, output)
+                .expect("Blocks of a frame have the length of a block");
+        }
+        samples
+    }
+}
+
+/// Demodulates blocks of samples into blocks of bytes, see the [module](self).
+pub trait PhyDemodulator {
+    /// Returns the number of samples of a block.
+    fn samples_per_block(&self) -> usize;
+
+    /// Returns the number of bytes a block carries.
+    fn capacity_bytes(&self) -> usize;
+
+    /// Demodulates the [samples](Self::samples_per_block) of a block into its [capacity](Self::capacity_bytes) bytes.
+    ///
+    /// # Errors
+    /// [ModemError::BufferLength] if the samples or the output do not have the length of a block.
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError>;
+
+    /// Returns the number of samples of a frame carrying `payload_length` bytes.
+    fn frame_length(&self, payload_length: usize) -> usize {
+        payload_length.div_ceil(self.capacity_bytes()) * self.samples_per_block()
+    }
+
+    /// Demodulates a frame of samples into its payload, with the zero padding of the last block.
+    ///
+    /// By default, every block of the frame is demodulated on its own.
+    ///
+    /// # Panics
+    /// If the number of samples is not a multiple of the block length.
+    fn demodulate_frame(&self, samples: &[f32]) -> Vec<u8> {
+        let block_length = self.samples_per_block();
+        if !samples.len().is_multiple_of(block_length) {
+            panic!(
+                "Frame length must be a multiple of {} samples, but got {} samples",
+                block_length,
+                samples.len()
+            );
+        }
+        let capacity = self.capacity_bytes();
+        let mut payload = vec![0; samples.len() / block_length * capacity];
+        for (block, output) in samples
+            .chunks_exact(block_length)
+            .zip(payload.chunks_exact_mut(capacity))
+        {
+            self.demodulate(block, output)
+                .expect("Blocks of a frame have the length of a block");
+        }
+        payload
+    }
+}
@@ -0,0 +1,729 @@
+//! This module provides a single carrier modem, QAM symbols sent one after the other on a carrier,
+//! behind the traits of a [physical layer](super).
+//!
+//! The symbols are shaped with [root-raised-cosine](root_raised_cosine) pulses at a symbol rate,
+//! which confine them to a band of `(1 + roll_off) * symbol_rate` around the carrier, and the receiver filters them
+//! with the same pulse, which matches it. The two filters together are a raised cosine, whose symbols do not interfere
+//! when they are read at the right time.
+//!
+//! A frame starts with a preamble of known symbols, which gives the receiver the gain and phase of the channel.
+//! A Gardner timing error detector then moves the instants the symbols are read to where they are,
+//! between the samples through a [FarrowInterpolator], which follows a sample clock that runs faster or slower
+//! than the one of the transmitter. A decision-directed phase tracker turns the points back onto the constellation,
+//! against the drift of the carrier phase. Blocks modulated on their own are shaped cyclically,
+//! so they need no preamble, but neither get a channel estimate nor any tracking.
+//!
+//! # Example
+//! ```
+//! use software_modem::frame::{FrameDecoder, FrameEncoder};
+//! use software_modem::phy::single_carrier::{
+//!     Constellation, SingleCarrierConfig, SingleCarrierDemodulator, SingleCarrierModulator,
+//! };
+//!
+//! let config = SingleCarrierConfig {
+//!     constellation: Constellation::Qam16,
+//!     ..Default::default()
+//! };
+//! let encoder = FrameEncoder::new(SingleCarrierModulator::new(config.clone()));
+//! let decoder = FrameDecoder::new(SingleCarrierDemodulator::new(config));
+//!
+//! // a block of 64 symbols carries 32 bytes
+//! assert_eq!(encoder.get_bytes_per_symbol(), 32);
+//! let samples = encoder.encode(b"one carrier");
+//! assert_eq!(samples.len(), encoder.get_frame_length(11));
+//! assert_eq!(&decoder.decode(&samples)[..11], b"one carrier");
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::f64::consts::{PI, TAU};
+
+use num_complex::Complex32;
+
+use crate::{
+    dsp::FarrowInterpolator,
+    error::ModemError,
+    phy::{PhyDemodulator, PhyModulator},
+    qam::{QAMModem, QAMOrder},
+    rng::SimulationRng,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Seed of the known symbols of the preamble, the same at both ends.
+const PREAMBLE_SEED: u64 = 0x5c_a11e;
+
+/// Taps of the interpolation between the samples of the matched filter.
+const INTERPOLATOR_TAPS: usize = 8;
+
+/// Damping of the timing and the phase loop.
+const LOOP_DAMPING: f64 = core::f64::consts::FRAC_1_SQRT_2;
+
+/// The points the symbols of a [SingleCarrierConfig] are drawn from.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Constellation {
+    /// Four points, 2 bits per symbol, the first one in the sign of the in-phase and the second of the quadrature component.
+    #[default]
+    Qpsk,
+    /// The sixteen points of [QAMOrder::QAM16], 4 bits per symbol, mapped like the OFDM modem maps them.
+    Qam16,
+}
+
+impl Constellation {
+    /// Returns the number of bits a symbol carries.
+    pub fn bits_per_symbol(&self) -> u32 {
+        match self {
+            Constellation::Qpsk => 2,
+            Constellation::Qam16 => QAMModem::new(QAMOrder::QAM16).bits_per_symbol(),
+        }
+    }
+
+    /// Returns the points of the constellation, the point of every value of the bits of a symbol at its index,
+    /// scaled to a mean power of 1.
+    fn points(&self) -> Vec<Complex32> {
+        let points = match self {
+            Constellation::Qpsk => (0..4)
+                .map(|index| {
+                    let sign = |bit: usize| if bit == 0 { 1.0 } else { -1.0 };
+                    Complex32::new(sign(index >> 1), sign(index & 1))
+                })
+                .collect(),
+            Constellation::Qam16 => {
+                // every value in two nibbles, in order
+                let bytes: Vec<u8> = (0..8).map(|pair| (pair * 0x22 + 0x01) as u8).collect();
+                QAMModem::new(QAMOrder::QAM16).modulate(&bytes)
+            }
+        };
+        let power = points.iter().map(|point| point.norm_sqr()).sum::<f32>() / points.len() as f32;
+        let scale = power.sqrt().recip();
+        points.into_iter().map(|point| point * scale).collect()
+    }
+}
+
+/// The configuration of a [SingleCarrierModulator] and a [SingleCarrierDemodulator].
+///
+/// The sample rate must be a whole multiple of the symbol rate, and the band of the symbols,
+/// `(1 + roll_off) * symbol_rate` wide around the carrier, must lie between 0 Hz and half the sample rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SingleCarrierConfig {
+    /// The constellation of the symbols.
+    pub constellation: Constellation,
+    /// The sample rate in Hz.
+    pub sample_rate: f32,
+    /// The number of symbols per second.
+    pub symbol_rate: f32,
+    /// The frequency of the carrier in Hz.
+    pub carrier_hz: f32,
+    /// The excess bandwidth of the pulses beyond the symbol rate, from 0 to 1.
+    pub roll_off: f32,
+    /// The length of the pulses in symbols, even.
+    pub filter_span: usize,
+    /// The number of symbols of a block.
+    pub symbols_per_block: usize,
+    /// The number of known symbols at the start of a frame.
+    pub preamble_symbols: usize,
+    /// The peak of the carrier for a symbol of the mean power, the RMS of the samples is `amplitude / sqrt(2)`.
+    pub amplitude: f32,
+    /// The noise bandwidth of the timing loop, as a fraction of the symbol rate.
+    pub timing_bandwidth: f32,
+    /// The noise bandwidth of the phase loop, as a fraction of the symbol rate.
+    pub phase_bandwidth: f32,
+}
+
+impl Default for SingleCarrierConfig {
+    /// QPSK at 1000 symbols per second on a carrier at 1800 Hz, from 1125 to 2475 Hz at a sample rate of 8000 Hz.
+    fn default() -> Self {
+        SingleCarrierConfig {
+            constellation: Constellation::default(),
+            sample_rate: 8000.0,
+            symbol_rate: 1000.0,
+            carrier_hz: 1800.0,
+            roll_off: 0.35,
+            filter_span: 10,
+            symbols_per_block: 64,
+            preamble_symbols: 64,
+            amplitude: 0.25,
+            timing_bandwidth: 0.005,
+            phase_bandwidth: 0.01,
+        }
+    }
+}
+
+/// Returns the taps of a root-raised-cosine pulse `span` symbols long, with `samples_per_symbol` samples per symbol,
+/// one more tap than `span * samples_per_symbol`, scaled to an energy of 1.
+///
+/// The pulse filtered with itself is a raised cosine, which is 0 at every multiple of the symbol period but the center.
+///
+/// # Panics
+/// If the roll-off is not above 0 and at most 1, the span is not even and at least 2,
+/// or there are less than 2 samples per symbol.
+///
+/// # Example
+/// ```
+/// use software_modem::phy::single_carrier::root_raised_cosine;
+///
+/// let taps = root_raised_cosine(0.35, 4, 12);
+/// assert_eq!(taps.len(), 49);
+/// let energy: f32 = taps.iter().map(|tap| tap * tap).sum();
+/// assert!((energy - 1.0).abs() < 1e-5);
+///
+/// // filtered with itself, a symbol does not leak into the next ones
+/// let raised_cosine = |lag: usize| taps[lag..].iter().zip(&taps).map(|(a, b)| a * b).sum::<f32>();
+/// for symbols in 1..6 {
+///     assert!(raised_cosine(4 * symbols).abs() < 1e-2, "{symbols}");
+/// }
+/// ```
+pub fn root_raised_cosine(roll_off: f32, samples_per_symbol: usize, span: usize) -> Vec<f32> {
+    if !(roll_off > 0.0 && roll_off <= 1.0) {
+        panic!(
+            "Roll-off must be above 0 and at most 1, but got {}",
+            roll_off
+        );
+    }
+    if !(span >= 2 && span.is_multiple_of(2)) {
+        panic!("Filter span must be even and at least 2, but got {}", span);
+    }
+    if samples_per_symbol < 2 {
+        panic!(
+            "Samples per symbol must be at least 2, but got {}",
+            samples_per_symbol
+        );
+    }
+
+    let beta = roll_off as f64;
+    let center = (span * samples_per_symbol / 2) as f64;
+    let taps: Vec<f64> = (0..=span * samples_per_symbol)
+        .map(|n| {
+            let t = (n as f64 - center) / samples_per_symbol as f64;
+            if t == 0.0 {
+                1.0 - beta + 4.0 * beta / PI
+            } else if (4.0 * beta * t).abs() == 1.0 {
+                beta / 2f64.sqrt()
+                    * ((1.0 + 2.0 / PI) * (PI / (4.0 * beta)).sin()
+                        + (1.0 - 2.0 / PI) * (PI / (4.0 * beta)).cos())
+            } else {
+                ((PI * t * (1.0 - beta)).sin() + 4.0 * beta * t * (PI * t * (1.0 + beta)).cos())
+                    / (PI * t * (1.0 - (4.0 * beta * t).powi(2)))
+            }
+        })
+        .collect();
+    let scale = taps.iter().map(|tap| tap * tap).sum::<f64>().sqrt().recip();
+    taps.iter().map(|tap| (tap * scale) as f32).collect()
+}
+
+/// The pulse, the symbols and the carrier a modulator and a demodulator share.
+#[derive(Clone, Debug)]
+struct Shaping {
+    config: SingleCarrierConfig,
+    samples_per_symbol: usize,
+    taps: Vec<f32>,
+    points: Vec<Complex32>,
+    preamble: Vec<Complex32>,
+}
+
+impl Shaping {
+    fn new(config: SingleCarrierConfig) -> Self {
+        let ratio = config.sample_rate / config.symbol_rate;
+        if !(config.symbol_rate > 0.0 && ratio >= 2.0 && ratio.fract() == 0.0) {
+            panic!(
+                "Sample rate must be a whole multiple of at least 2 times the symbol rate, but got {} and {}",
+                config.sample_rate, config.symbol_rate
+            );
+        }
+        let samples_per_symbol = ratio as usize;
+        let taps = root_raised_cosine(config.roll_off, samples_per_symbol, config.filter_span);
+
+        let half_band = (1.0 + config.roll_off) * config.symbol_rate / 2.0;
+        if !(config.carrier_hz - half_band > 0.0
+            && config.carrier_hz + half_band < config.sample_rate / 2.0)
+        {
+            panic!(
+                "Band must be between 0 and {} Hz, but got {} to {} Hz",
+                config.sample_rate / 2.0,
+                config.carrier_hz - half_band,
+                config.carrier_hz + half_band
+            );
+        }
+        let symbols_per_byte = (8 / config.constellation.bits_per_symbol()) as usize;
+        if !(config.symbols_per_block > 0
+            && config.symbols_per_block.is_multiple_of(symbols_per_byte))
+        {
+            panic!(
+                "Symbols per block must be a positive multiple of {}, but got {}",
+                symbols_per_byte, config.symbols_per_block
+            );
+        }
+        if config.preamble_symbols == 0 {
+            panic!(
+                "Preamble must be at least 1 symbol, but got {}",
+                config.preamble_symbols
+            );
+        }
+        if !(config.amplitude > 0.0 && config.amplitude.is_finite()) {
+            panic!(
+                "Amplitude must be positive and finite, but got {}",
+                config.amplitude
+            );
+        }
+        for bandwidth in [config.timing_bandwidth, config.phase_bandwidth] {
+            if !(bandwidth > 0.0 && bandwidth <= 0.25) {
+                panic!(
+                    "Loop bandwidth must be above 0 and at most 0.25, but got {}",
+                    bandwidth
+                );
+            }
+        }
+
+        let points = config.constellation.points();
+        let qpsk = Constellation::Qpsk.points();
+        let mut rng = SimulationRng::new(PREAMBLE_SEED);
+        let preamble = (0..config.preamble_symbols)
+            .map(|_| qpsk[rng.below(4) as usize])
+            .collect();
+        Shaping {
+            config,
+            samples_per_symbol,
+            taps,
+            points,
+            preamble,
+        }
+    }
+
+    fn samples_per_block(&self) -> usize {
+        self.config.symbols_per_block * self.samples_per_symbol
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.config.symbols_per_block * self.config.constellation.bits_per_symbol() as usize / 8
+    }
+
+    /// Returns the number of samples of a frame of a number of blocks,
+    /// the preamble, the symbols of the blocks, and the tails of the pulses.
+    fn frame_length(&self, blocks: usize) -> usize {
+        (self.config.preamble_symbols
+            + blocks * self.config.symbols_per_block
+            + self.config.filter_span)
+            * self.samples_per_symbol
+    }
+
+    /// Returns the radians the carrier turns by every sample.
+    fn carrier_step(&self) -> f64 {
+        TAU * self.config.carrier_hz as f64 / self.config.sample_rate as f64
+    }
+
+    /// Returns the carrier at a sample, the phase reduced in `f64` to stay exact over long frames.
+    fn carrier(&self, n: usize) -> Complex32 {
+        let phase = (self.carrier_step() * n as f64) % TAU;
+        Complex32::new(phase.cos() as f32, phase.sin() as f32)
+    }
+
+    /// Returns the symbols of the data, each `bits_per_symbol` of its bits from the most significant one.
+    fn map(&self, data: &[u8]) -> Vec<Complex32> {
+        let bits = self.config.constellation.bits_per_symbol() as usize;
+        let mask = (1 << bits) - 1;
+        data.iter()
+            .flat_map(|&byte| {
+                (0..8 / bits)
+                    .rev()
+                    .map(move |position| self.points[(byte as usize >> (position * bits)) & mask])
+            })
+            .collect()
+    }
+
+    /// Returns the index of the point closest to the symbol, at a mean power of 1.
+    fn nearest(&self, symbol: Complex32) -> usize {
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| (index, (symbol - point).norm_sqr()))
+            .fold((0, f32::INFINITY), |nearest, candidate| {
+                if candidate.1 < nearest.1 {
+                    candidate
+                } else {
+                    nearest
+                }
+            })
+            .0
+    }
+
+    /// Decides the symbols into the bytes of the output, the inverse of [map](Self::map).
+    fn decide(&self, symbols: &[Complex32], output: &mut [u8]) {
+        let bits = self.config.constellation.bits_per_symbol() as usize;
+        for (byte, symbols) in output.iter_mut().zip(symbols.chunks_exact(8 / bits)) {
+            *byte = symbols.iter().fold(0, |byte, &symbol| {
+                (byte << bits) | self.nearest(symbol) as u8
+            });
+        }
+    }
+
+    /// Returns the constellation point closest to the symbol.
+    fn nearest_point(&self, symbol: Complex32) -> Complex32 {
+        self.points[self.nearest(symbol)]
+    }
+
+    /// Returns the factor from the points out of the matched filter to the symbols, for a channel of unit gain.
+    fn receive_scale(&self) -> f32 {
+        (self.config.amplitude * (self.samples_per_symbol as f32).sqrt()).recip()
+    }
+}
+
+/// Modulates blocks of bytes into QAM symbols on a carrier, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct SingleCarrierModulator {
+    shaping: Shaping,
+}
+
+impl SingleCarrierModulator {
+    /// Creates a modulator of the configuration.
+    ///
+    /// # Panics
+    /// - If the sample rate is not a whole multiple of at least 2 times the symbol rate.
+    /// - If the roll-off is not above 0 and at most 1, or the filter span not even and at least 2.
+    /// - If the band of the symbols does not lie between 0 Hz and half the sample rate.
+    /// - If a block does not have a positive whole number of bytes, there is no preamble,
+    ///   the amplitude is not positive and finite, or a loop bandwidth is not above 0 and at most 0.25.
+    pub fn new(config: SingleCarrierConfig) -> Self {
+        SingleCarrierModulator {
+            shaping: Shaping::new(config),
+        }
+    }
+
+    /// Returns the configuration of the modulator.
+    pub fn get_config(&self) -> &SingleCarrierConfig {
+        &self.shaping.config
+    }
+
+    /// Returns the number of samples of a symbol.
+    pub fn get_samples_per_symbol(&self) -> usize {
+        self.shaping.samples_per_symbol
+    }
+
+    /// Shapes the symbols with the pulse on the carrier, into a frame of their pulses and their tails.
+    fn shape(&self, symbols: &[Complex32]) -> Vec<f32> {
+        let shaping = &self.shaping;
+        let samples_per_symbol = shaping.samples_per_symbol;
+        let mut baseband = vec![
+            Complex32::default();
+            (symbols.len() + shaping.config.filter_span) * samples_per_symbol
+        ];
+        let gain = (samples_per_symbol as f32).sqrt();
+        for (k, &symbol) in symbols.iter().enumerate() {
+            let start = k * samples_per_symbol;
+            for (sample, &tap) in baseband[start..].iter_mut().zip(&shaping.taps) {
+                *sample += symbol * (tap * gain);
+            }
+        }
+        baseband
+            .iter()
+            .enumerate()
+            .map(|(n, &sample)| shaping.config.amplitude * (sample * shaping.carrier(n)).re)
+            .collect()
+    }
+}
+
+impl PhyModulator for SingleCarrierModulator {
+    fn samples_per_block(&self) -> usize {
+        self.shaping.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.shaping.capacity_bytes()
+    }
+
+    /// Modulates a block on its own, the pulses of its symbols wrapped around its ends,
+    /// so the block is one period of a signal that repeats.
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+        let shaping = &self.shaping;
+        check_length(shaping.capacity_bytes(), data.len())?;
+        check_length(shaping.samples_per_block(), output.len())?;
+
+        let length = output.len();
+        let samples_per_symbol = shaping.samples_per_symbol;
+        let center = shaping.taps.len() / 2;
+        let gain = (samples_per_symbol as f32).sqrt();
+        let mut baseband = vec![Complex32::default(); length];
+        for (k, symbol) in shaping.map(data).into_iter().enumerate() {
+            for (m, &tap) in shaping.taps.iter().enumerate() {
+                let n = (k * samples_per_symbol + m + length * center - center) % length;
+                baseband[n] += symbol * (tap * gain);
+            }
+        }
+        for (n, (sample, baseband)) in output.iter_mut().zip(baseband).enumerate() {
+            *sample = shaping.config.amplitude * (baseband * shaping.carrier(n)).re;
+        }
+        Ok(())
+    }
+
+    fn frame_length(&self, payload_length: usize) -> usize {
+        self.shaping.frame_length(self.frame_blocks(payload_length))
+    }
+
+    /// Modulates the preamble and the symbols of the payload, the last block padded with zeros,
+    /// as one stream of pulses, followed by their tails.
+    fn modulate_frame(&self, payload: &[u8]) -> Vec<f32> {
+        let mut data = payload.to_vec();
+        data.resize(self.frame_blocks(payload.len()) * self.capacity_bytes(), 0);
+        let mut symbols = self.shaping.preamble.clone();
+        symbols.extend(self.shaping.map(&data));
+        self.shape(&symbols)
+    }
+}
+
+/// The data symbols of a frame and the clock the receiver followed, see [SingleCarrierDemodulator::recover].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolRecovery {
+    /// The symbols of the blocks after the timing and the phase loop, at a mean power of 1.
+    pub points: Vec<Complex32>,
+    /// How much faster the sample clock of the receiver ran than the one of the transmitter, in ppm,
+    /// from the time the timing loop read the last symbol at, against the time the transmitter sent it.
+    pub clock_offset_ppm: f32,
+}
+
+/// Demodulates QAM symbols on a carrier into blocks of bytes, see the [module](self).
+#[derive(Clone, Debug)]
+pub struct SingleCarrierDemodulator {
+    shaping: Shaping,
+    interpolator: FarrowInterpolator,
+    /// The gains of the timing loop, in samples per unit of the error of the Gardner detector.
+    timing_gains: (f64, f64),
+    /// The gains of the phase loop, in radians per radian of error.
+    phase_gains: (f64, f64),
+}
+
+impl SingleCarrierDemodulator {
+    /// Creates a demodulator of the configuration.
+    ///
+    /// # Panics
+    /// See [SingleCarrierModulator::new].
+    pub fn new(config: SingleCarrierConfig) -> Self {
+        let shaping = Shaping::new(config);
+        let (proportional, integral) = loop_gains(shaping.config.timing_bandwidth as f64);
+        // the detector gain is per symbol of timing error, the loop steps in samples
+        let detector_gain =
+            gardner_gain(shaping.config.roll_off as f64) / shaping.samples_per_symbol as f64;
+        let timing_gains = (proportional / detector_gain, integral / detector_gain);
+        let phase_gains = loop_gains(shaping.config.phase_bandwidth as f64);
+        SingleCarrierDemodulator {
+            shaping,
+            interpolator: FarrowInterpolator::new(INTERPOLATOR_TAPS),
+            timing_gains,
+            phase_gains,
+        }
+    }
+
+    /// Returns the configuration of the demodulator.
+    pub fn get_config(&self) -> &SingleCarrierConfig {
+        &self.shaping.config
+    }
+
+    /// Returns the number of samples of a symbol.
+    pub fn get_samples_per_symbol(&self) -> usize {
+        self.shaping.samples_per_symbol
+    }
+
+    /// Recovers the data symbols of the whole blocks of a frame, which starts at the first sample.
+    ///
+    /// The samples are moved down from the carrier and filtered with the pulse.
+    /// The preamble gives the gain and phase of the channel, then the timing loop reads every symbol where
+    /// the Gardner detector finds it, and the phase loop turns it by the phase of its decision,
+    /// the known symbol in the preamble. Samples after the last whole block are ignored.
+    pub fn recover(&self, samples: &[f32]) -> SymbolRecovery {
+        let shaping = &self.shaping;
+        let config = &shaping.config;
+        let samples_per_symbol = shaping.samples_per_symbol;
+        let blocks = (samples.len() / samples_per_symbol)
+            .saturating_sub(config.preamble_symbols + config.filter_span)
+            / config.symbols_per_block;
+        let num_symbols = config.preamble_symbols + blocks * config.symbols_per_block;
+
+        // down from the carrier, and through the matched filter, with room for the window of the interpolator
+        let padding = INTERPOLATOR_TAPS;
+        let mut filtered =
+            vec![Complex32::default(); samples.len() + shaping.taps.len() + 2 * padding];
+        for (n, &sample) in samples.iter().enumerate() {
+            let baseband = shaping.carrier(n).conj() * (2.0 * sample);
+            for (output, &tap) in filtered[n + padding..].iter_mut().zip(&shaping.taps) {
+                *output += baseband * tap;
+            }
+        }
+        let at = |time: f64| {
+            let index = time.floor();
+            let start = (index as isize + padding as isize + 1 - (INTERPOLATOR_TAPS / 2) as isize)
+                .clamp(0, (filtered.len() - INTERPOLATOR_TAPS) as isize)
+                as usize;
+            self.interpolator.interpolate(
+                &filtered[start..start + INTERPOLATOR_TAPS],
+                (time - index) as f32,
+            )
+        };
+
+        // the gain and phase of the channel, the preamble read at the nominal times
+        let first = (config.filter_span * samples_per_symbol) as f64;
+        let period = samples_per_symbol as f64;
+        let correlation: Complex32 = shaping
+            .preamble
+            .iter()
+            .enumerate()
+            .map(|(k, symbol)| at(first + k as f64 * period) * symbol.conj())
+            .sum();
+        let gain = correlation / config.preamble_symbols as f32;
+        let gain = if gain.norm_sqr() > 0.0 {
+            gain.inv()
+        } else {
+            Complex32::new(shaping.receive_scale(), 0.0)
+        };
+
+        let (timing_proportional, timing_integral) = self.timing_gains;
+        let (phase_proportional, phase_integral) = self.phase_gains;
+        let mut time = first;
+        let mut drift = 0.0;
+        let mut phase = 0.0;
+        let mut frequency = 0.0;
+        let mut previous = Complex32::default();
+        let mut last = first;
+        let mut points = Vec::with_capacity(num_symbols - config.preamble_symbols);
+        for k in 0..num_symbols {
+            let symbol = at(time) * gain;
+            last = time;
+
+            // late if the point halfway after a transition has the sign it goes to
+            if k > 0 {
+                let middle = at(time - (period - drift) / 2.0) * gain;
+                let error = (middle.conj() * (symbol - previous)).re as f64;
+                drift += timing_integral * error;
+                time -= timing_proportional * error + drift;
+            }
+            previous = symbol;
+            time += period;
+
+            let point = symbol * Complex32::from_polar(1.0, -phase as f32);
+            let decision = match shaping.preamble.get(k) {
+                Some(&known) => known,
+                None => shaping.nearest_point(point),
+            };
+            let error = (point * decision.conj()).arg() as f64;
+            frequency += phase_integral * error;
+            phase += phase_proportional * error + frequency;
+
+            if k >= config.preamble_symbols {
+                points.push(point);
+            }
+        }
+
+        let elapsed = (num_symbols - 1) as f64 * period;
+        SymbolRecovery {
+            points,
+            clock_offset_ppm: if elapsed > 0.0 {
+                ((last - first) / elapsed - 1.0) * 1e6
+            } else {
+                0.0
+            } as f32,
+        }
+    }
+}
+
+impl PhyDemodulator for SingleCarrierDemodulator {
+    fn samples_per_block(&self) -> usize {
+        self.shaping.samples_per_block()
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.shaping.capacity_bytes()
+    }
+
+    /// Demodulates a block [modulated](SingleCarrierModulator) on its own, with the pulses wrapped around its ends,
+    /// read at the times of the transmitter with a channel of unit gain.
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+        let shaping = &self.shaping;
+        check_length(shaping.samples_per_block(), samples.len())?;
+        check_length(shaping.capacity_bytes(), output.len())?;
+
+        let length = samples.len();
+        let center = shaping.taps.len() / 2;
+        let scale = shaping.receive_scale();
+        let baseband: Vec<Complex32> = samples
+            .iter()
+            .enumerate()
+            .map(|(n, &sample)| shaping.carrier(n).conj() * (2.0 * sample))
+            .collect();
+        let symbols: Vec<Complex32> = (0..shaping.config.symbols_per_block)
+            .map(|k| {
+                let sum: Complex32 = shaping
+                    .taps
+                    .iter()
+                    .enumerate()
+                    .map(|(m, &tap)| {
+                        baseband[(k * shaping.samples_per_symbol + m + length * center - center)
+                            % length]
+                            * tap
+                    })
+                    .sum();
+                sum * scale
+            })
+            .collect();
+        shaping.decide(&symbols, output);
+        Ok(())
+    }
+
+    fn frame_length(&self, payload_length: usize) -> usize {
+        self.shaping
+            .frame_length(payload_length.div_ceil(self.capacity_bytes()))
+    }
+
+    /// Demodulates the whole blocks of a frame which starts at the first sample,
+    /// with the [recovery](SingleCarrierDemodulator::recover) of the timing and the phase.
+    fn demodulate_frame(&self, samples: &[f32]) -> Vec<u8> {
+        let points = self.recover(samples).points;
+        let mut payload =
+            vec![0; points.len() / self.shaping.config.symbols_per_block * self.capacity_bytes()];
+        self.shaping.decide(&points, &mut payload);
+        payload
+    }
+}
+
+fn check_length(expected: usize, got: usize) -> Result<(), ModemError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+/// Returns the proportional and integral gain of a second order loop with a noise bandwidth,
+/// as a fraction of the update rate, for a detector and an oscillator of unit gain.
+fn loop_gains(bandwidth: f64) -> (f64, f64) {
+    let theta = bandwidth / (LOOP_DAMPING + 1.0 / (4.0 * LOOP_DAMPING));
+    let denominator = 1.0 + 2.0 * LOOP_DAMPING * theta + theta * theta;
+    (
+        4.0 * LOOP_DAMPING * theta / denominator,
+        4.0 * theta * theta / denominator,
+    )
+}
+
+/// Returns the slope of the mean error of the Gardner detector at the right timing, per symbol of timing error,
+/// for symbols of unit power through a raised cosine of the roll-off.
+fn gardner_gain(roll_off: f64) -> f64 {
+    let raised_cosine = |t: f64| {
+        let sinc = if t == 0.0 {
+            1.0
+        } else {
+            (PI * t).sin() / (PI * t)
+        };
+        let denominator = 1.0 - (2.0 * roll_off * t).powi(2);
+        if denominator.abs() < 1e-9 {
+            PI / 4.0 * sinc
+        } else {
+            sinc * (PI * roll_off * t).cos() / denominator
+        }
+    };
+    // only the symbol with itself contributes to the mean, the others are uncorrelated
+    let mean_error = |offset: f64| {
+        (-32..=32)
+            .map(|n| {
+                let t = offset - n as f64;
+                raised_cosine(t - 0.5) * (raised_cosine(t) - raised_cosine(t - 1.0))
+            })
+            .sum::<f64>()
+    };
+    (mean_error(0.01) - mean_error(-0.01)) / 0.02
+}
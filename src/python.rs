@@ -0,0 +1,335 @@
+//! This module provides the Python extension `software_modem._native` of the OFDM modem and the QAM modem,
+//! behind the `python` feature.
+//!
+//! `pyo3` exports [PyOfdmModulator], [PyOfdmDemodulator] and [PyQamModem] as the classes `OfdmModulator`,
+//! `OfdmDemodulator` and `QamModem`, which `python/software_modem` re-exports, and `maturin` builds and installs
+//! the extension through `pyproject.toml`. The classes are a thin layer over [OFDMModulator], [OFDMDemodulator]
+//! and [QAMModem]: configurations are keyword arguments, and the samples, bytes and LLRs are NumPy arrays
+//! of `float32`, `uint8` and `complex64`. Contiguous arrays of the right type are read without copies,
+//! any other array-like is converted, and `bytes` are taken as they are.
+//!
+//! The Rust API panics on invalid configurations and input lengths, the classes raise these panics as `ValueError`.
+//!
+//! # Example
+//! ```python
+//! import numpy
+//! from software_modem import OfdmDemodulator, OfdmModulator
+//!
+//! modulator = OfdmModulator(num_subcarriers=64, cyclic_prefix_length=4)
+//! demodulator = OfdmDemodulator(num_subcarriers=64, cyclic_prefix_length=4)
+//! samples = modulator.modulate(b"x" * modulator.bytes_per_symbol)
+//! assert bytes(demodulator.demodulate(samples)) == b"x" * 24
+//! ```
+
+use std::{
+    borrow::Cow,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+use num_complex::Complex32;
+use numpy::{
+    AllowTypeChange, Element, PyArray1, PyArray2, PyArrayLike1, PyArrayMethods, PyReadonlyArray1,
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+use crate::{
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::{QAMModem, QAMOrder},
+};
+
+/// Runs the closure, raising a panic of the modem as a `ValueError` with its message.
+fn guard<T>(f: impl FnOnce() -> T) -> PyResult<T> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| payload.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown panic");
+        PyValueError::new_err(message.to_string())
+    })
+}
+
+/// Returns the elements of the array, borrowed if it is contiguous.
+fn contiguous<'a, T: Element + Clone>(array: &'a PyReadonlyArray1<'_, T>) -> Cow<'a, [T]> {
+    match array.as_slice() {
+        Ok(slice) => Cow::Borrowed(slice),
+        Err(_) => Cow::Owned(array.as_array().to_vec()),
+    }
+}
+
+/// Runs the closure with the bytes of a `bytes` object or of an array-like converted to `uint8`.
+fn with_bytes<R>(data: &Bound<'_, PyAny>, f: impl FnOnce(&[u8]) -> R) -> PyResult<R> {
+    if let Ok(bytes) = data.cast::<PyBytes>() {
+        return Ok(f(bytes.as_bytes()));
+    }
+    let array = data.extract::<PyArrayLike1<u8, AllowTypeChange>>()?;
+    Ok(f(&contiguous(&array)))
+}
+
+/// Returns the configuration of the keyword arguments, without the differential encoding of the frames.
+fn config(
+    num_subcarriers: u32,
+    cyclic_prefix_length: u32,
+    pilot_subcarrier_every: u32,
+) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers,
+        cyclic_prefix_length,
+        pilot_subcarrier_every,
+        ..Default::default()
+    }
+}
+
+/// Returns the number of chunks in the length of an input, or a `ValueError` if it is not a multiple of the chunk.
+fn chunk_count(name: &str, length: usize, chunk: usize, unit: &str) -> PyResult<usize> {
+    if !length.is_multiple_of(chunk) {
+        return Err(PyValueError::new_err(format!(
+            "{} must be a multiple of {}{}, but got {}{}",
+            name, chunk, unit, length, unit
+        )));
+    }
+    Ok(length / chunk)
+}
+
+/// Modulates bytes into OFDM symbols of real samples, the `OfdmModulator` of Python.
+///
+/// The keyword arguments are the fields of [OFDMConfig], the samples are not scaled.
+#[pyclass(name = "OfdmModulator", module = "software_modem", frozen)]
+pub struct PyOfdmModulator {
+    modulator: OFDMModulator,
+}
+
+#[pymethods]
+impl PyOfdmModulator {
+    #[new]
+    #[pyo3(signature = (*, num_subcarriers = 64, cyclic_prefix_length = 4, pilot_subcarrier_every = 4))]
+    fn new(
+        num_subcarriers: u32,
+        cyclic_prefix_length: u32,
+        pilot_subcarrier_every: u32,
+    ) -> PyResult<Self> {
+        let config = config(
+            num_subcarriers,
+            cyclic_prefix_length,
+            pilot_subcarrier_every,
+        );
+        guard(|| PyOfdmModulator {
+            modulator: OFDMModulator::new((&config).into()),
+        })
+    }
+
+    /// The number of samples of a symbol.
+    #[getter]
+    fn symbol_length(&self) -> usize {
+        self.modulator.get_symbol_length()
+    }
+
+    /// The number of data bytes of a symbol.
+    #[getter]
+    fn bytes_per_symbol(&self) -> usize {
+        self.modulator.get_bytes_per_symbol()
+    }
+
+    /// Modulates a multiple of `bytes_per_symbol` bytes into a `float32` array of consecutive symbols.
+    fn modulate<'py>(
+        &self,
+        py: Python<'py>,
+        data: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyArray1<f32>>> {
+        let (symbol_length, bytes_per_symbol) = (self.symbol_length(), self.bytes_per_symbol());
+        let samples = with_bytes(data, |data| {
+            let count = chunk_count("Data length", data.len(), bytes_per_symbol, " bytes")?;
+            let mut samples = vec![0.0; count * symbol_length];
+            guard(|| {
+                for (data, symbol) in data
+                    .chunks_exact(bytes_per_symbol)
+                    .zip(samples.chunks_exact_mut(symbol_length))
+                {
+                    self.modulator.modulate_buffer_as_symbol(data, symbol);
+                }
+            })?;
+            Ok::<_, PyErr>(samples)
+        })??;
+        Ok(PyArray1::from_vec(py, samples))
+    }
+}
+
+/// Demodulates OFDM symbols of real samples into bytes or LLRs, the `OfdmDemodulator` of Python.
+///
+/// Takes the same keyword arguments as the [PyOfdmModulator].
+#[pyclass(name = "OfdmDemodulator", module = "software_modem", frozen)]
+pub struct PyOfdmDemodulator {
+    demodulator: OFDMDemodulator,
+}
+
+impl PyOfdmDemodulator {
+    /// Returns the samples and the number of symbols in them.
+    fn symbols<'a>(
+        &self,
+        samples: &'a PyReadonlyArray1<'_, f32>,
+    ) -> PyResult<(Cow<'a, [f32]>, usize)> {
+        let samples = contiguous(samples);
+        let count = chunk_count("Number of samples", samples.len(), self.symbol_length(), "")?;
+        Ok((samples, count))
+    }
+}
+
+#[pymethods]
+impl PyOfdmDemodulator {
+    #[new]
+    #[pyo3(signature = (*, num_subcarriers = 64, cyclic_prefix_length = 4, pilot_subcarrier_every = 4))]
+    fn new(
+        num_subcarriers: u32,
+        cyclic_prefix_length: u32,
+        pilot_subcarrier_every: u32,
+    ) -> PyResult<Self> {
+        let config = config(
+            num_subcarriers,
+            cyclic_prefix_length,
+            pilot_subcarrier_every,
+        );
+        guard(|| PyOfdmDemodulator {
+            demodulator: OFDMDemodulator::new((&config).into()),
+        })
+    }
+
+    /// The number of samples of a symbol.
+    #[getter]
+    fn symbol_length(&self) -> usize {
+        self.demodulator.get_symbol_length()
+    }
+
+    /// The number of data bytes of a symbol.
+    #[getter]
+    fn bytes_per_symbol(&self) -> usize {
+        self.demodulator.get_bytes_per_symbol()
+    }
+
+    /// Demodulates consecutive symbols into a `uint8` array of their data.
+    fn demodulate<'py>(
+        &self,
+        py: Python<'py>,
+        samples: PyArrayLike1<'py, f32, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyArray1<u8>>> {
+        let (samples, count) = self.symbols(&samples)?;
+        let (symbol_length, bytes_per_symbol) = (self.symbol_length(), self.bytes_per_symbol());
+        let mut data = vec![0; count * bytes_per_symbol];
+        guard(|| {
+            let mut scratch = self.demodulator.make_scratch();
+            for (symbol, output) in samples
+                .chunks_exact(symbol_length)
+                .zip(data.chunks_exact_mut(bytes_per_symbol))
+            {
+                self.demodulator
+                    .demodulate_symbol_into(symbol, &mut scratch, output);
+            }
+        })?;
+        Ok(PyArray1::from_vec(py, data))
+    }
+
+    /// Demodulates consecutive symbols into a `float32` array of one LLR per data bit, of shape (symbols, bits).
+    ///
+    /// A positive LLR favours a 0 bit, see `QAMModem::demodulate_soft`.
+    fn demodulate_soft<'py>(
+        &self,
+        py: Python<'py>,
+        samples: PyArrayLike1<'py, f32, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let (samples, count) = self.symbols(&samples)?;
+        let llrs = guard(|| {
+            samples
+                .chunks_exact(self.symbol_length())
+                .flat_map(|symbol| self.demodulator.demodulate_symbol_soft_from_buffer(symbol))
+                .collect()
+        })?;
+        PyArray1::from_vec(py, llrs).reshape([count, 8 * self.bytes_per_symbol()])
+    }
+}
+
+/// Maps bytes to `complex64` QAM symbols and back, the `QamModem` of Python.
+///
+/// The order is 4, 16, 64 or 256, the bits of the bytes are mapped with the most significant first.
+#[pyclass(name = "QamModem", module = "software_modem", frozen)]
+pub struct PyQamModem {
+    modem: QAMModem,
+}
+
+#[pymethods]
+impl PyQamModem {
+    #[new]
+    #[pyo3(signature = (*, order = 16))]
+    fn new(order: u32) -> PyResult<Self> {
+        let order = match order {
+            4 => QAMOrder::QPSK,
+            16 => QAMOrder::QAM16,
+            64 => QAMOrder::QAM64,
+            256 => QAMOrder::QAM256,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "QAM order must be 4, 16, 64 or 256, but got {}",
+                    order
+                )));
+            }
+        };
+        Ok(PyQamModem {
+            modem: QAMModem::new(order),
+        })
+    }
+
+    /// The number of points of the constellation.
+    #[getter]
+    fn order(&self) -> u32 {
+        1 << self.modem.bits_per_symbol()
+    }
+
+    /// The number of bits of a symbol.
+    #[getter]
+    fn bits_per_symbol(&self) -> u32 {
+        self.modem.bits_per_symbol()
+    }
+
+    /// Maps the bytes to a `complex64` array of symbols.
+    fn modulate<'py>(
+        &self,
+        py: Python<'py>,
+        data: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyArray1<Complex32>>> {
+        let symbols = with_bytes(data, |data| guard(|| self.modem.modulate(data)))??;
+        Ok(PyArray1::from_vec(py, symbols))
+    }
+
+    /// Demaps the symbols to a `uint8` array of the nearest bytes.
+    fn demodulate<'py>(
+        &self,
+        py: Python<'py>,
+        symbols: PyArrayLike1<'py, Complex32, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyArray1<u8>>> {
+        let symbols = contiguous(&symbols);
+        let data = guard(|| self.modem.demodulate(&symbols))?;
+        Ok(PyArray1::from_vec(py, data))
+    }
+
+    /// Demaps the symbols to a `float32` array of LLRs, of shape (symbols, bits per symbol).
+    ///
+    /// A positive LLR favours a 0 bit.
+    fn demodulate_soft<'py>(
+        &self,
+        py: Python<'py>,
+        symbols: PyArrayLike1<'py, Complex32, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let symbols = contiguous(&symbols);
+        let llrs = guard(|| self.modem.demodulate_soft(&symbols))?;
+        let bits = self.bits_per_symbol() as usize;
+        PyArray1::from_vec(py, llrs).reshape([symbols.len(), bits])
+    }
+}
+
+/// The module `software_modem._native`, whose classes `python/software_modem` re-exports.
+#[pymodule]
+#[pyo3(name = "_native")]
+pub fn native(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyOfdmModulator>()?;
+    module.add_class::<PyOfdmDemodulator>()?;
+    module.add_class::<PyQamModem>()?;
+    Ok(())
+}
@@ -1,50 +1,236 @@
 //! This module provides the QAM (Quadrature Amplitude Modulation) implementation.
 //!
 //! Use the [QAMModem] struct to modulate and demodulate data into QAM symbols.
-//! See the [QAMOrder] enum for supported QAM orders.
+//! Single constellation points are mapped and decided with [map_bits](GenericQAMModem::map_bits),
+//! [demap_point](GenericQAMModem::demap_point) and [nearest_point](GenericQAMModem::nearest_point),
+//! which the byte-level functions are built on.
+//! See the [QAMOrder] enum for supported QAM orders, and the [DemapStrategy] enum for how hard decisions are made.
 
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
 use core::panic;
-use std::fmt::Display;
-
-use realfft::num_complex::Complex32;
-
-// QAM-16 lookup table
-const QAM16_LOOKUP: [Complex32; 16] = [
-    Complex32::new(1.0, 1.0),   // 0000
-    Complex32::new(1.0, 3.0),   // 0001
-    Complex32::new(3.0, 1.0),   // 0010
-    Complex32::new(3.0, 3.0),   // 0011
-    Complex32::new(1.0, -1.0),  // 0100
-    Complex32::new(1.0, -3.0),  // 0101
-    Complex32::new(3.0, -1.0),  // 0110
-    Complex32::new(3.0, -3.0),  // 0111
-    Complex32::new(-1.0, 1.0),  // 1000
-    Complex32::new(-1.0, 3.0),  // 1001
-    Complex32::new(-3.0, 1.0),  // 1010
-    Complex32::new(-3.0, 3.0),  // 1011
-    Complex32::new(-1.0, -1.0), // 1100
-    Complex32::new(-1.0, -3.0), // 1101
-    Complex32::new(-3.0, -1.0), // 1110
-    Complex32::new(-3.0, -3.0), // 1111
-];
-
-#[derive(Default, Copy, Clone, Debug)]
+use core::{fmt::Display, marker::PhantomData};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use num_complex::{Complex, Complex32};
+
+use crate::samples::Sample;
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 /// Represents the QAM order for modulation.
+///
+/// Every order is a square of points on the odd levels `±1`, `±3` and so on of both axes, 2 apart.
+/// Every axis carries half of the bits of a point: the sign of its level, and the Gray code of the magnitude below it,
+/// so the neighbouring points differ in one bit. The bits of the axes take turns from the first bit of a point,
+/// the real one first, so a QAM-16 point carries `re < 0`, `im < 0`, `|re| > 2` and `|im| > 2` from its highest bit down.
 pub enum QAMOrder {
+    /// 4 points of 2 bits, the signs of the axes.
+    QPSK,
+    /// 16 points of 4 bits.
     #[default]
     QAM16,
+    /// 64 points of 6 bits, 4 of them to 3 bytes.
+    QAM64,
+    /// 256 points of 8 bits.
+    QAM256,
+}
+
+impl QAMOrder {
+    /// Every QAM order, from the smallest constellation up.
+    pub const ALL: [QAMOrder; 4] = [
+        QAMOrder::QPSK,
+        QAMOrder::QAM16,
+        QAMOrder::QAM64,
+        QAMOrder::QAM256,
+    ];
+
+    /// Returns the number of bits of a point, half of which every axis carries.
+    pub const fn bits_per_symbol(self) -> u32 {
+        match self {
+            QAMOrder::QPSK => 2,
+            QAMOrder::QAM16 => 4,
+            QAMOrder::QAM64 => 6,
+            QAMOrder::QAM256 => 8,
+        }
+    }
+
+    /// Returns the number of bits of an axis.
+    const fn axis_bits(self) -> u32 {
+        self.bits_per_symbol() / 2
+    }
+
+    /// Returns the number of levels of an axis on either side of 0.
+    const fn levels(self) -> usize {
+        1 << (self.axis_bits() - 1)
+    }
 }
+
 impl Display for QAMOrder {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
+            QAMOrder::QPSK => write!(f, "QPSK"),
             QAMOrder::QAM16 => write!(f, "QAM-16"),
+            QAMOrder::QAM64 => write!(f, "QAM-64"),
+            QAMOrder::QAM256 => write!(f, "QAM-256"),
         }
     }
 }
 
+/// Returns the bits of an axis of `axis_bits` bits, the sign above the Gray code of the magnitude,
+/// the index of the level `2 * magnitude + 1` away from 0.
+pub(crate) fn axis_label(negative: bool, magnitude: usize, axis_bits: u32) -> usize {
+    (usize::from(negative) << (axis_bits - 1)) | (magnitude ^ (magnitude >> 1))
+}
+
+/// Returns the index of a point from the bits of its axes, which take turns from the highest bit, the real one first.
+pub(crate) fn point_index(re: usize, im: usize, axis_bits: u32) -> usize {
+    (0..axis_bits).fold(0, |index, bit| {
+        index | (((re >> bit) & 1) << (2 * bit + 1)) | (((im >> bit) & 1) << (2 * bit))
+    })
+}
+
+/// Returns the level of the bits of an axis of `axis_bits` bits, see [axis_label].
+fn axis_level(label: usize, axis_bits: u32) -> i32 {
+    let gray = label & ((1 << (axis_bits - 1)) - 1);
+    // the prefix XOR of the Gray code, up to the 3 bits of the magnitude of QAM-256
+    let magnitude = gray ^ (gray >> 1) ^ (gray >> 2);
+    let level = 2 * magnitude as i32 + 1;
+    if label >> (axis_bits - 1) == 1 {
+        -level
+    } else {
+        level
+    }
+}
+
+/// Returns the point of an index of the order, in the units of the levels.
+fn constellation_point(qam_order: QAMOrder, index: usize) -> (i32, i32) {
+    let axis_bits = qam_order.axis_bits();
+    let axis = |shift: usize| {
+        (0..axis_bits).fold(0, |label, bit| {
+            label | (((index >> (2 * bit as usize + shift)) & 1) << bit)
+        })
+    };
+    (
+        axis_level(axis(1), axis_bits),
+        axis_level(axis(0), axis_bits),
+    )
+}
+
+/// Returns the points of the order in `f32`, the one of every index in turn.
+fn constellation(qam_order: QAMOrder) -> Vec<Complex32> {
+    (0..1 << qam_order.bits_per_symbol())
+        .map(|index| {
+            let (re, im) = constellation_point(qam_order, index);
+            Complex32::new(re as f32, im as f32)
+        })
+        .collect()
+}
+
+/// How [GenericQAMModem::demodulate_into] makes its hard decisions, the nearest constellation point either way.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DemapStrategy {
+    /// Slices every axis with comparisons, with SIMD instructions for `f32` where the CPU has them.
+    #[default]
+    Slicer,
+    /// Quantizes every point to a cell of a grid and looks up the decision of the cell, in a table the modem builds once.
+    ///
+    /// The grid has 256 × 256 cells, from one beyond the outermost level of the constellation to one beyond on the other side,
+    /// `-4` to `4` and 1/32 wide for QAM-16, `-16` to `16` and 1/8 wide for QAM-256, and points outside decide like the cell at the edge.
+    /// Every cell holds the decision of its center, which is exact as the decision boundaries lie on the edges of the cells.
+    /// Exactly on a boundary, where two points are equally near, it may take the other point than the slicer.
+    /// It is two lookups per point whatever the shape of the constellation, but the table takes 64 KiB,
+    /// and for the square orders, whose axes are sliced on their own with a few comparisons each, the slicer is faster.
+    /// The table of an order is built by the first modem that needs it and shared by all the others, on any thread.
+    Table,
+}
+
+/// Cells of the grid of [DemapStrategy::Table] along one axis.
+const DEMAP_CELLS: usize = 256;
+
+/// The decisions of the cells of the grid of [DemapStrategy::Table].
+struct DemapTable {
+    /// The index of the nearest point of every cell, a row of cells along the imaginary axis per cell of the real axis.
+    indices: Box<[u8]>,
+    /// Cells per unit of a coordinate.
+    scale: f32,
+}
+
+impl DemapTable {
+    /// Builds the table of the points, searching the nearest point of every cell center.
+    fn new(points: &[Complex32]) -> Self {
+        let extent = points
+            .iter()
+            .map(|point| point.re.abs().max(point.im.abs()))
+            .fold(0.0, f32::max)
+            + 1.0;
+        let scale = DEMAP_CELLS as f32 / (2.0 * extent);
+        let center = |cell: usize| (cell as f32 - (DEMAP_CELLS / 2) as f32 + 0.5) / scale;
+        let indices = (0..DEMAP_CELLS * DEMAP_CELLS)
+            .map(|cell| {
+                let point = Complex32::new(center(cell / DEMAP_CELLS), center(cell % DEMAP_CELLS));
+                // the first of equally near points, though no cell center is equally near two points
+                let mut nearest = (0, f32::INFINITY);
+                for (index, candidate) in points.iter().enumerate() {
+                    let distance = (point - candidate).norm_sqr();
+                    if distance < nearest.1 {
+                        nearest = (index as u8, distance);
+                    }
+                }
+                nearest.0
+            })
+            .collect();
+        DemapTable { indices, scale }
+    }
+
+    /// Returns the table of a built-in order, built on first use and shared by every modem of the order.
+    ///
+    /// Without the `std` feature, every modem builds a table of its own.
+    #[cfg(feature = "std")]
+    fn shared(qam_order: QAMOrder) -> Arc<Self> {
+        static TABLES: [OnceLock<Arc<DemapTable>>; 4] = [
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+        ];
+        let position = QAMOrder::ALL
+            .iter()
+            .position(|&order| order == qam_order)
+            .unwrap();
+        TABLES[position]
+            .get_or_init(|| Arc::new(DemapTable::new(&constellation(qam_order))))
+            .clone()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn shared(qam_order: QAMOrder) -> Arc<Self> {
+        Arc::new(DemapTable::new(&constellation(qam_order)))
+    }
+
+    /// Returns the index of the point the cell of the symbol decides for.
+    fn index<T: Sample>(&self, symbol: &Complex<T>) -> u8 {
+        let half = (DEMAP_CELLS / 2) as i32;
+        let cell = |x: T| {
+            // clamping keeps NaN, which the cast takes to 0, the cell at the origin like the slicer
+            let cells = (x.into_f32() * self.scale).clamp(-half as f32, half as f32);
+            // floors without a call to floor, which is a library call without SSE4.1
+            let truncated = cells as i32;
+            let floor = truncated - i32::from(cells < truncated as f32);
+            (floor + half).min(DEMAP_CELLS as i32 - 1) as usize
+        };
+        self.indices[cell(symbol.re) * DEMAP_CELLS + cell(symbol.im)]
+    }
+}
+
+/// A modulator and demodulator for Quadrature Amplitude Modulation (QAM), in `f32`.
+///
+/// See [GenericQAMModem] for other sample types.
+pub type QAMModem = GenericQAMModem<f32>;
+
 /// A modulator and demodulator for Quadrature Amplitude Modulation (QAM).
 ///
-/// This struct allows modulating and demodulating data (byte slices) into QAM symbols.
+/// This struct allows modulating and demodulating data (byte slices) into QAM symbols
+/// of the [sample type](Sample) `T`.
 ///
 /// # Example
 /// ```
@@ -58,20 +244,76 @@ impl Display for QAMOrder {
 ///
 /// assert_eq!(data, demodulated_data);
 /// ```
-pub struct QAMModem {
+pub struct GenericQAMModem<T: Sample> {
     qam_order: QAMOrder,
+    demap_strategy: DemapStrategy,
+    demap_table: Option<Arc<DemapTable>>,
+    sample_type: PhantomData<T>,
 }
 
-impl QAMModem {
+impl<T: Sample> GenericQAMModem<T> {
     /// Create a new QAMModem for the specified QAM order.
     pub fn new(qam_order: QAMOrder) -> Self {
-        QAMModem { qam_order }
+        Self::with_demap_strategy(qam_order, DemapStrategy::default())
+    }
+
+    /// Create a new QAMModem for the specified QAM order, making its hard decisions with the demap strategy.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ DemapStrategy, QAMModem, QAMOrder };
+    ///
+    /// let data = "Hello, world!".as_bytes();
+    /// let modem = QAMModem::with_demap_strategy(QAMOrder::QAM16, DemapStrategy::Table);
+    /// let symbols: Vec<_> = modem.modulate(data).iter().map(|symbol| symbol * 0.8).collect();
+    ///
+    /// assert_eq!(modem.demodulate(&symbols), data);
+    /// assert_eq!(modem.get_demap_strategy(), DemapStrategy::Table);
+    /// ```
+    pub fn with_demap_strategy(qam_order: QAMOrder, demap_strategy: DemapStrategy) -> Self {
+        let demap_table = match demap_strategy {
+            DemapStrategy::Slicer => None,
+            DemapStrategy::Table => Some(DemapTable::shared(qam_order)),
+        };
+        GenericQAMModem {
+            qam_order,
+            demap_strategy,
+            demap_table,
+            sample_type: PhantomData,
+        }
+    }
+
+    /// Returns the strategy of the hard decisions.
+    pub fn get_demap_strategy(&self) -> DemapStrategy {
+        self.demap_strategy
+    }
+
+    /// Returns `true` if both modems look up their hard decisions in the same table,
+    /// which every modem of the same order with [DemapStrategy::Table] does, whatever its sample type.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ DemapStrategy, GenericQAMModem, QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::with_demap_strategy(QAMOrder::QAM16, DemapStrategy::Table);
+    /// let other = GenericQAMModem::<f64>::with_demap_strategy(QAMOrder::QAM16, DemapStrategy::Table);
+    /// assert!(modem.shares_demap_table(&other));
+    ///
+    /// // the slicer needs no table
+    /// assert!(!modem.shares_demap_table(&QAMModem::new(QAMOrder::QAM16)));
+    /// ```
+    pub fn shares_demap_table<U: Sample>(&self, other: &GenericQAMModem<U>) -> bool {
+        match (&self.demap_table, &other.demap_table) {
+            (Some(table), Some(other)) => Arc::ptr_eq(table, other),
+            _ => false,
+        }
     }
 
     /// Modulate a byte array into QAM symbols.
     ///
-    /// Each byte will result in QAMModulator.bits_per_symbol() symbols,
-    /// as the number of bits per symbol depends on the QAM order.
+    /// Each symbol carries [bits_per_symbol](Self::bits_per_symbol) bits of the data, the first bit highest,
+    /// and the last symbol is filled up with zero bits where the bits of the data do not fill it,
+    /// like the 4 bits of 3 bytes in 4 QAM-64 symbols plus one.
     ///
     /// # Example
     /// ```
@@ -82,21 +324,134 @@ impl QAMModem {
     /// let symbols = modem.modulate(data);
     ///
     /// assert_eq!(symbols.len(), data.len() * 2); // Each byte produces two QAM symbols for QAM-16
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM64);
+    /// assert_eq!(modem.modulate(data).len(), 18); // 104 bits, 17 symbols and 2 bits of the last one
+    /// assert_eq!(modem.demodulate(&modem.modulate(data)), data);
     /// ```
-    pub fn modulate(&self, data: &[u8]) -> Vec<Complex32> {
-        let mut symbols = Vec::new();
-        match self.qam_order {
-            QAMOrder::QAM16 => {
-                for &byte in data {
-                    let first_nibble = (byte >> 4) & 0x0f; // Get the first 4 bits
-                    let second_nibble = byte & 0x0f; // Get the last 4 bits
+    pub fn modulate(&self, data: &[u8]) -> Vec<Complex<T>> {
+        let mut symbols =
+            vec![Complex::default(); (data.len() * 8).div_ceil(self.bits_per_symbol() as usize)];
+        self.modulate_into(data, &mut symbols);
+        symbols
+    }
 
-                    symbols.push(QAM16_LOOKUP[first_nibble as usize]);
-                    symbols.push(QAM16_LOOKUP[second_nibble as usize]);
-                }
+    /// Modulate a byte array into the QAM symbols of the output, without allocating.
+    ///
+    /// # Panics
+    /// If the output does not have the number of symbols the data is modulated into.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let mut symbols = [Complex32::default(); 2];
+    /// modem.modulate_into(&[0x3c], &mut symbols);
+    /// assert_eq!(symbols.to_vec(), modem.modulate(&[0x3c]));
+    /// ```
+    pub fn modulate_into(&self, data: &[u8], output: &mut [Complex<T>]) {
+        let bits = self.bits_per_symbol();
+        let num_symbols = (data.len() * 8).div_ceil(bits as usize);
+        if output.len() != num_symbols {
+            panic!(
+                "Output length must be {} symbols, but got {} symbols",
+                num_symbols,
+                output.len()
+            );
+        }
+
+        // the bits not taken yet are the lowest of the accumulator, fewer than a symbol and a byte
+        let (mut accumulator, mut available) = (0u32, 0);
+        let mut bytes = data.iter();
+        for symbol in output.iter_mut() {
+            while available < bits {
+                accumulator = (accumulator << 8) | bytes.next().map_or(0, |&byte| u32::from(byte));
+                available += 8;
             }
+            available -= bits;
+            *symbol = self.map_bits(accumulator >> available);
+            accumulator &= (1 << available) - 1;
+        }
+    }
+
+    /// Returns the constellation point of the bits of one symbol, the lowest [bits per symbol](Self::bits_per_symbol)
+    /// of the number with the first bit highest, like the points of [modulate](Self::modulate).
+    ///
+    /// # Panics
+    /// If the bits do not fit in a symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// // a pilot symbol by hand: the corner points in turn, then a header byte
+    /// let mut symbol: Vec<Complex32> = [0b0011, 0b1111].iter().cycle().take(6).map(|&bits| modem.map_bits(bits)).collect();
+    /// symbol.extend([0x5, 0xa].map(|bits| modem.map_bits(bits)));
+    /// assert_eq!(symbol[..2], [Complex32::new(3.0, 3.0), Complex32::new(-3.0, -3.0)]);
+    /// assert_eq!(symbol[6..], modem.modulate(&[0x5a]));
+    ///
+    /// // the receiver decides every point back into its bits
+    /// let decided: Vec<u32> = symbol.iter().map(|&point| modem.demap_point(point * 0.9)).collect();
+    /// assert_eq!(decided, [0b0011, 0b1111, 0b0011, 0b1111, 0b0011, 0b1111, 0x5, 0xa]);
+    /// ```
+    pub fn map_bits(&self, bits: u32) -> Complex<T> {
+        let num_points = 1 << self.bits_per_symbol();
+        if bits >= num_points {
+            panic!(
+                "Bits must be below {} for {}, but got {}",
+                num_points, self.qam_order, bits
+            );
+        }
+
+        let (re, im) = constellation_point(self.qam_order, bits as usize);
+        Complex::new(T::cast(re.into()), T::cast(im.into()))
+    }
+
+    /// Returns the bits of the hard decision of a received point, as a number like the one of [map_bits](Self::map_bits).
+    ///
+    /// The decision is made with the [demap strategy](DemapStrategy) of the modem,
+    /// the same as [demodulate_into](Self::demodulate_into) makes for the point.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ DemapStrategy, QAMModem, QAMOrder };
+    ///
+    /// for strategy in [DemapStrategy::Slicer, DemapStrategy::Table] {
+    ///     let modem = QAMModem::with_demap_strategy(QAMOrder::QAM16, strategy);
+    ///     assert_eq!(modem.demap_point(Complex32::new(2.7, -0.6)), 0b0110);
+    /// }
+    /// ```
+    pub fn demap_point(&self, point: Complex<T>) -> u32 {
+        match &self.demap_table {
+            None => qam_index(self.qam_order, &point) as u32,
+            Some(table) => u32::from(table.index(&point)),
         }
-        symbols
+    }
+
+    /// Returns the bits of the constellation point nearest to a received point and its squared distance to it.
+    ///
+    /// The nearest point is the one of the [slicer](DemapStrategy::Slicer) whatever the demap strategy,
+    /// on a tie the point of the lower bits.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let (bits, distance) = modem.nearest_point(Complex32::new(-0.5, 3.5));
+    /// assert_eq!(bits, 0b1001);
+    /// assert_eq!(modem.map_bits(bits), Complex32::new(-1.0, 3.0));
+    /// assert_eq!(distance, 0.5);
+    /// ```
+    pub fn nearest_point(&self, point: Complex<T>) -> (u32, T) {
+        let bits = qam_index(self.qam_order, &point) as u32;
+        (bits, (point - self.map_bits(bits)).norm_sqr())
     }
 
     /// Demodulate QAM symbols back into bytes.
@@ -115,48 +470,505 @@ impl QAMModem {
     ///
     /// assert_eq!(data, demodulated_data);
     /// ```
-    pub fn demodulate(&self, symbols: &[Complex32]) -> Vec<u8> {
-        match self.qam_order {
-            QAMOrder::QAM16 => {
-                let mut nibbles = Vec::new();
-                // demulation
-                for symbol in symbols {
-                    QAM16_LOOKUP
+    pub fn demodulate(&self, symbols: &[Complex<T>]) -> Vec<u8> {
+        let mut bytes = vec![0; symbols.len() * self.bits_per_symbol() as usize / 8];
+        self.demodulate_into(symbols, &mut bytes);
+        bytes
+    }
+
+    /// Demodulate QAM symbols back into the bytes of the output, without allocating.
+    ///
+    /// The hard decisions are made with the [demap strategy](DemapStrategy) of the modem.
+    /// The bits of the last symbol beyond the last whole byte are the ones [modulate](Self::modulate) fills up with, and dropped.
+    ///
+    /// # Panics
+    /// If the symbols are not the ones of whole bytes, or the output does not have the number of bytes they carry.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let data = "Hello, world!".as_bytes();
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(data);
+    ///
+    /// let mut demodulated_data = [0; 13];
+    /// modem.demodulate_into(&symbols, &mut demodulated_data);
+    /// assert_eq!(data, demodulated_data);
+    /// ```
+    pub fn demodulate_into(&self, symbols: &[Complex<T>], output: &mut [u8]) {
+        check_lengths(self.qam_order, symbols.len(), output.len());
+        match &self.demap_table {
+            None => T::slice_qam(self.qam_order, symbols, output),
+            Some(_) => pack(
+                symbols.iter().map(|&symbol| self.demap_point(symbol)),
+                self.bits_per_symbol(),
+                output,
+            ),
+        }
+    }
+
+    /// Demodulate QAM symbols into soft bit decisions.
+    ///
+    /// Returns one log-likelihood ratio (LLR) per bit, in the same order as the bits returned by [demodulate](Self::demodulate).
+    /// Positive values favour a `0` bit, negative values a `1` bit, and the magnitude is the confidence.
+    /// The max-log approximation is used, assuming a unit noise variance.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::bits::bytes_to_bits;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let data = "Soft".as_bytes();
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(data);
+    /// let llrs = modem.demodulate_soft(&symbols);
+    ///
+    /// let hard_bits: Vec<u8> = llrs.iter().map(|&llr| (llr < 0.0) as u8).collect();
+    /// assert_eq!(hard_bits, bytes_to_bits(data));
+    /// ```
+    pub fn demodulate_soft(&self, symbols: &[Complex<T>]) -> Vec<T> {
+        let bits = self.bits_per_symbol() as usize;
+        let mut llrs = vec![T::zero(); symbols.len() * bits];
+        for (symbol, llrs) in symbols.iter().zip(llrs.chunks_exact_mut(bits)) {
+            let re = Axis::new(self.qam_order, symbol.re);
+            let im = Axis::new(self.qam_order, symbol.im);
+            // the nearest point of a bit of one axis is the nearest of that axis and the nearest of the other,
+            // and adding the distance along the other axis rounds like the search over all the points
+            for (position, llr) in llrs.iter_mut().enumerate() {
+                let bit = (bits - 1 - position) / 2;
+                let (axis, other) = if position % 2 == 0 {
+                    (&re, &im)
+                } else {
+                    (&im, &re)
+                };
+                *llr = axis.nearest_with(bit, 1) + other.nearest()
+                    - (axis.nearest_with(bit, 0) + other.nearest());
+            }
+        }
+        llrs
+    }
+
+    /// Demodulate QAM symbols into soft bit decisions, with a priori LLRs of their bits,
+    /// like the extrinsic information of a decoder fed back into the demapper, see [iterative](crate::iterative).
+    ///
+    /// Returns one extrinsic LLR per bit in the units of [demodulate_soft](Self::demodulate_soft): every point is weighed
+    /// by its distance and the a priori LLRs of its bits, and the LLR of a bit leaves out its own a priori LLR,
+    /// so only what the symbol and the other bits of its point tell about it. Knowing the outer or inner bit
+    /// of an axis, the demapper decides its sign between two points instead of four.
+    /// Without a priori LLRs, or with all of them 0, the LLRs are the ones of [demodulate_soft](Self::demodulate_soft).
+    ///
+    /// # Panics
+    /// If there is not one a priori LLR per bit of the symbols.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// // between the inner and the outer point of the positive real axis, near the imaginary axis
+    /// let symbols = [Complex32::new(0.2, 2.0)];
+    /// let llrs = modem.demodulate_soft_with_priors(&symbols, None);
+    /// assert_eq!(llrs, modem.demodulate_soft(&symbols));
+    /// assert!((llrs[0] - 0.8).abs() < 1e-5);
+    ///
+    /// // once the decoder is sure the real part is an outer one, its sign is decided between -3 and 3
+    /// let llrs = modem.demodulate_soft_with_priors(&symbols, Some(&[0.0, 0.0, -20.0, 0.0]));
+    /// assert!((llrs[0] - 2.4).abs() < 1e-5);
+    /// ```
+    pub fn demodulate_soft_with_priors(
+        &self,
+        symbols: &[Complex<T>],
+        priors: Option<&[T]>,
+    ) -> Vec<T> {
+        let Some(priors) = priors else {
+            return self.demodulate_soft(symbols);
+        };
+        let bits = self.bits_per_symbol() as usize;
+        if priors.len() != symbols.len() * bits {
+            panic!(
+                "Number of a priori LLRs must be {}, but got {}",
+                symbols.len() * bits,
+                priors.len()
+            );
+        }
+
+        let points: Vec<Complex<T>> = (0..1u32 << bits)
+            .map(|index| self.map_bits(index))
+            .collect();
+        let mut llrs = vec![T::zero(); priors.len()];
+        let mut costs = vec![T::zero(); points.len()];
+        for ((symbol, priors), llrs) in symbols
+            .iter()
+            .zip(priors.chunks_exact(bits))
+            .zip(llrs.chunks_exact_mut(bits))
+        {
+            // a 1 bit costs its a priori LLR, a 0 bit nothing, in the units of the squared distance
+            for (index, (cost, point)) in costs.iter_mut().zip(&points).enumerate() {
+                *cost = (symbol - point).norm_sqr()
+                    + priors
                         .iter()
                         .enumerate()
-                        .min_by(|(_, a), (_, b)| {
-                            distance(symbol, a)
-                                .partial_cmp(&distance(symbol, b))
-                                .unwrap()
-                        })
-                        .map(|(index, _)| {
-                            nibbles.push(index as u8);
-                        })
-                        .unwrap_or_else(|| panic!("Symbol not found in QAM-16 lookup table"));
-                }
-                // nubbles to bytes
-                let mut bytes = Vec::new();
-                for chunk in nibbles.chunks(2) {
-                    if chunk.len() == 2 {
-                        let byte = (chunk[0] << 4) | chunk[1]; // Combine two nibbles into a byte
-                        bytes.push(byte);
+                        .filter(|(bit, _)| (index >> (bits - 1 - bit)) & 1 == 1)
+                        .map(|(_, &prior)| prior)
+                        .sum();
+            }
+            for (bit, (llr, &prior)) in llrs.iter_mut().zip(priors).enumerate() {
+                let (mut zero, mut one) = (T::infinity(), T::infinity());
+                for (index, &cost) in costs.iter().enumerate() {
+                    if (index >> (bits - 1 - bit)) & 1 == 1 {
+                        one = one.min(cost - prior);
                     } else {
-                        panic!("Invalid chunk size on {} demodulation", self.qam_order);
+                        zero = zero.min(cost);
                     }
                 }
-                bytes
+                *llr = one - zero;
             }
         }
+        llrs
+    }
+
+    /// Returns the constellation point closest to each symbol, as a hard decision.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let received = [Complex32::new(0.8, 2.6), Complex32::new(-4.0, -0.2)];
+    /// assert_eq!(modem.nearest_points(&received), vec![Complex32::new(1.0, 3.0), Complex32::new(-3.0, -1.0)]);
+    /// ```
+    pub fn nearest_points(&self, symbols: &[Complex<T>]) -> Vec<Complex<T>> {
+        symbols
+            .iter()
+            .map(|&symbol| self.map_bits(self.nearest_point(symbol).0))
+            .collect()
+    }
+
+    /// Returns the index of the constellation point closest to each symbol, the bits it carries as a number.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(&[0x3c]);
+    /// let received: Vec<Complex32> = symbols.iter().map(|symbol| symbol + Complex32::new(0.3, -0.4)).collect();
+    /// assert_eq!(modem.nearest_indices(&received), vec![0x3, 0xc]);
+    /// ```
+    pub fn nearest_indices(&self, symbols: &[Complex<T>]) -> Vec<usize> {
+        symbols
+            .iter()
+            .map(|&symbol| self.nearest_point(symbol).0 as usize)
+            .collect()
+    }
+
+    /// Returns the mean power of the constellation points, 10 for the unnormalized QAM-16.
+    pub fn mean_power(&self) -> f32 {
+        // twice the mean square of the odd levels of an axis
+        let levels = 1u32 << self.qam_order.axis_bits();
+        2.0 * (levels * levels - 1) as f32 / 3.0
+    }
+
+    /// Returns the largest magnitude of the constellation points, the corners of the square.
+    pub fn peak_magnitude(&self) -> f32 {
+        let corner = (2 * self.qam_order.levels() - 1) as f32;
+        Complex32::new(corner, corner).norm()
+    }
+
+    /// Returns the smallest distance between two constellation points, 2 for the unnormalized orders.
+    pub fn min_distance(&self) -> f32 {
+        2.0
     }
 
     /// Returns the number of bits per symbol for the specified QAM order.
     pub fn bits_per_symbol(&self) -> u32 {
-        match self.qam_order {
-            QAMOrder::QAM16 => 4, // QAM-16 uses 4 bits per symbol
+        self.qam_order.bits_per_symbol()
+    }
+}
+
+/// Decides QAM points into bytes in scalar code, the bits of every point in turn, the first bit highest.
+///
+/// Every axis is sliced on its own, the sign gives one bit and the number of thresholds `2`, `4` and so on
+/// below the magnitude the others, as a Gray code. This is the nearest point of the constellation,
+/// on a tie the point of the lower index: the positive level at 0 and the inner level at a threshold.
+/// A NaN coordinate is sliced like 0. The bits of the last point beyond the last whole byte are dropped.
+/// [QAMModem::demodulate_into] uses the SIMD slicer of `f32` where the CPU has one, which decides exactly like this one.
+///
+/// # Panics
+/// If the points are not the ones of whole bytes, or the output does not have the number of bytes they carry,
+/// see [demodulate_into](GenericQAMModem::demodulate_into).
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::qam::{ QAMModem, QAMOrder, slice_qam };
+///
+/// for order in QAMOrder::ALL {
+///     let modem = QAMModem::new(order);
+///     let points: Vec<Complex32> = modem.modulate(b"QAM").iter().map(|point| point + Complex32::new(0.3, -0.3)).collect();
+///     let mut output = [0; 3];
+///     slice_qam(order, &points, &mut output);
+///     assert_eq!(&output, b"QAM");
+///
+///     // the SIMD slicer behind demodulate_into decides the same
+///     modem.demodulate_into(&points, &mut output);
+///     assert_eq!(&output, b"QAM");
+/// }
+/// ```
+pub fn slice_qam<T: Sample>(qam_order: QAMOrder, points: &[Complex<T>], output: &mut [u8]) {
+    check_lengths(qam_order, points.len(), output.len());
+    pack(
+        points
+            .iter()
+            .map(|point| qam_index(qam_order, point) as u32),
+        qam_order.bits_per_symbol(),
+        output,
+    );
+}
+
+/// Panics unless the points carry the bytes of the output, with fewer bits left over than a point has.
+fn check_lengths(qam_order: QAMOrder, points: usize, bytes: usize) {
+    let bits = qam_order.bits_per_symbol() as usize;
+    if (points * bits / 8 * 8).div_ceil(bits) != points {
+        panic!("Invalid chunk size on {} demodulation", qam_order);
+    }
+    if bytes != points * bits / 8 {
+        panic!(
+            "Output length must be {} bytes, but got {} bytes",
+            points * bits / 8,
+            bytes
+        );
+    }
+}
+
+/// Writes the bits of the indices into the bytes of the output, the first bit highest, and drops the bits left over.
+pub(crate) fn pack(indices: impl Iterator<Item = u32>, bits: u32, output: &mut [u8]) {
+    // the bits not written yet are the lowest of the accumulator, fewer than a byte
+    let (mut accumulator, mut available) = (0u32, 0);
+    let mut bytes = output.iter_mut();
+    for index in indices {
+        accumulator = (accumulator << bits) | index;
+        available += bits;
+        while available >= 8 {
+            available -= 8;
+            if let Some(byte) = bytes.next() {
+                *byte = (accumulator >> available) as u8;
+            }
         }
+        accumulator &= (1 << available) - 1;
     }
 }
 
-fn distance(a: &Complex32, b: &Complex32) -> f32 {
-    ((a.re - b.re).powi(2) + (a.im - b.im).powi(2)).sqrt()
+/// Returns the bits of the level of an axis nearest to the coordinate, see [slice_qam].
+fn axis_index<T: Sample>(qam_order: QAMOrder, x: T) -> usize {
+    let magnitude = x.abs();
+    let below = (1..qam_order.levels())
+        .take_while(|&threshold| magnitude > T::cast(2.0 * threshold as f64))
+        .count();
+    axis_label(x < T::zero(), below, qam_order.axis_bits())
+}
+
+/// Returns the index of the point of the order closest to the symbol, see [slice_qam].
+fn qam_index<T: Sample>(qam_order: QAMOrder, symbol: &Complex<T>) -> usize {
+    point_index(
+        axis_index(qam_order, symbol.re),
+        axis_index(qam_order, symbol.im),
+        qam_order.axis_bits(),
+    )
+}
+
+/// Decides `f32` QAM points into bytes like [slice_qam], with AVX or SSE2 on x86-64.
+///
+/// AVX is detected at runtime, or without the `std` feature used if the target enables it,
+/// SSE2 is part of every x86-64 CPU. Both decide 4 points into the bits of their axes at a time,
+/// as many bytes as an axis has bits, and the scalar slicer decides the points left over.
+pub(crate) fn slice_qam_f32(qam_order: QAMOrder, points: &[Complex32], output: &mut [u8]) {
+    check_lengths(qam_order, points.len(), output.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[cfg(feature = "std")]
+        let avx = std::arch::is_x86_feature_detected!("avx");
+        #[cfg(not(feature = "std"))]
+        let avx = cfg!(target_feature = "avx");
+        let done = match qam_order {
+            QAMOrder::QPSK => x86_64::slice::<1>(avx, points, output),
+            QAMOrder::QAM16 => x86_64::slice::<2>(avx, points, output),
+            QAMOrder::QAM64 => x86_64::slice::<3>(avx, points, output),
+            QAMOrder::QAM256 => x86_64::slice::<4>(avx, points, output),
+        };
+        let bytes = done / 4 * qam_order.axis_bits() as usize;
+        slice_qam(qam_order, &points[done..], &mut output[bytes..]);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    slice_qam(qam_order, points, output);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use core::arch::x86_64::*;
+
+    use num_complex::Complex32;
+
+    /// Spreads the 8 lane bits of four points to the bits of their indices, for `K` bits an axis and one bit of the axes.
+    ///
+    /// The lanes are re and im of every point in turn, and the indices of the points follow each other
+    /// from the highest bit of `8 * K` bits, see [point_index](super::point_index).
+    /// `SPREAD[K - 1][bit]` spreads the lanes of the bit `bit` of the axes, the sign for `bit` `K - 1`.
+    const SPREAD: [[[u32; 256]; 4]; 4] = {
+        let mut spread = [[[0; 256]; 4]; 4];
+        let mut axis_bits = 1;
+        while axis_bits <= 4 {
+            let mut bit = 0;
+            while bit < axis_bits {
+                let mut lanes = 0;
+                while lanes < 256 {
+                    let mut bits = 0;
+                    let mut point = 0;
+                    while point < 4 {
+                        let (re, im) = ((lanes >> (2 * point)) & 1, (lanes >> (2 * point + 1)) & 1);
+                        let shift = (3 - point) * 2 * axis_bits + 2 * bit;
+                        bits |= ((re << 1) | im) << shift;
+                        point += 1;
+                    }
+                    spread[axis_bits - 1][bit][lanes] = bits as u32;
+                    lanes += 1;
+                }
+                bit += 1;
+            }
+            axis_bits += 1;
+        }
+        spread
+    };
+
+    /// Writes the `K` bytes of four points from the lane masks of every bit of their axes.
+    fn write<const K: usize>(masks: &[i32; K], output: &mut [u8]) {
+        let bits = masks.iter().enumerate().fold(0, |bits, (bit, &mask)| {
+            bits | SPREAD[K - 1][bit][(mask & 255) as usize]
+        });
+        output.copy_from_slice(&(bits << (32 - 8 * K)).to_be_bytes()[..K]);
+    }
+
+    /// Slices blocks of 4 points with AVX where the CPU has it and SSE2 otherwise,
+    /// for `K` bits an axis, and returns the number of points sliced.
+    pub(super) fn slice<const K: usize>(
+        avx: bool,
+        points: &[Complex32],
+        output: &mut [u8],
+    ) -> usize {
+        if avx {
+            // SAFETY: the CPU supports AVX
+            unsafe { slice_avx::<K>(points, output) }
+        } else {
+            // SAFETY: SSE2 is part of x86-64
+            unsafe { slice_sse2::<K>(points, output) }
+        }
+    }
+
+    /// Slices blocks of 4 points into `K` bytes each with AVX, 8 points an iteration.
+    #[target_feature(enable = "avx")]
+    fn slice_avx<const K: usize>(points: &[Complex32], output: &mut [u8]) -> usize {
+        let zero = _mm256_setzero_ps();
+        let sign = _mm256_set1_ps(-0.0);
+        let blocks = (points.len() / 4).min(output.len() / K);
+        for (block, output) in points[..4 * blocks]
+            .chunks(8)
+            .zip(output[..K * blocks].chunks_mut(2 * K))
+        {
+            for (half, output) in output.chunks_exact_mut(K).enumerate() {
+                // SAFETY: Complex32 is repr(C) of two f32, so the 4 points are 8 floats
+                let lanes = unsafe { _mm256_loadu_ps(block[4 * half..].as_ptr().cast::<f32>()) };
+                let magnitude = _mm256_andnot_ps(sign, lanes);
+                // the sign, then the Gray code of the magnitude, whose bit flips at every threshold
+                // with as many trailing zeros
+                let mut masks = [0; K];
+                masks[K - 1] = _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_LT_OQ>(lanes, zero));
+                for threshold in 1..1usize << (K - 1) {
+                    let level = _mm256_set1_ps(2.0 * threshold as f32);
+                    masks[threshold.trailing_zeros() as usize] ^=
+                        _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_GT_OQ>(magnitude, level));
+                }
+                write(&masks, output);
+            }
+        }
+        4 * blocks
+    }
+
+    /// Slices blocks of 4 points into `K` bytes each with SSE2, which is part of every x86-64 CPU.
+    #[target_feature(enable = "sse2")]
+    fn slice_sse2<const K: usize>(points: &[Complex32], output: &mut [u8]) -> usize {
+        let zero = _mm_setzero_ps();
+        let sign = _mm_set1_ps(-0.0);
+        let blocks = (points.len() / 4).min(output.len() / K);
+        for (block, output) in points.chunks_exact(4).zip(output.chunks_exact_mut(K)) {
+            let mut masks = [0; K];
+            for half in 0..2 {
+                // SAFETY: Complex32 is repr(C) of two f32, so the 2 points are 4 floats
+                let lanes = unsafe { _mm_loadu_ps(block[2 * half..].as_ptr().cast::<f32>()) };
+                let magnitude = _mm_andnot_ps(sign, lanes);
+                let shift = 4 * half;
+                masks[K - 1] |= _mm_movemask_ps(_mm_cmplt_ps(lanes, zero)) << shift;
+                for threshold in 1..1usize << (K - 1) {
+                    let level = _mm_set1_ps(2.0 * threshold as f32);
+                    masks[threshold.trailing_zeros() as usize] ^=
+                        _mm_movemask_ps(_mm_cmpgt_ps(magnitude, level)) << shift;
+                }
+            }
+            write(&masks, output);
+        }
+        4 * blocks
+    }
+}
+
+/// The squared distances of a coordinate to the levels of an axis, from the lowest level up, and the bits of the levels.
+///
+/// The level of an axis carries half of the bits of the point, its sign and the Gray code of its magnitude.
+struct Axis<T> {
+    distances: [T; 16],
+    labels: [u8; 16],
+    levels: usize,
+}
+
+impl<T: Sample> Axis<T> {
+    fn new(qam_order: QAMOrder, x: T) -> Self {
+        let mut axis = Axis {
+            distances: [T::zero(); 16],
+            labels: [0; 16],
+            levels: 2 * qam_order.levels(),
+        };
+        let half = qam_order.levels();
+        for level in 0..axis.levels {
+            let difference = x - T::cast(2.0 * level as f64 - (axis.levels - 1) as f64);
+            axis.distances[level] = difference * difference;
+            // the negative levels from the outermost in, then the positive ones from the innermost out
+            let (negative, magnitude) = if level < half {
+                (true, half - 1 - level)
+            } else {
+                (false, level - half)
+            };
+            axis.labels[level] = axis_label(negative, magnitude, qam_order.axis_bits()) as u8;
+        }
+        axis
+    }
+
+    /// Returns the distance to the nearest level whose bit `bit` is `value`.
+    fn nearest_with(&self, bit: usize, value: u8) -> T {
+        (0..self.levels)
+            .filter(|&level| (self.labels[level] >> bit) & 1 == value)
+            .map(|level| self.distances[level])
+            .reduce(T::min)
+            .unwrap()
+    }
+
+    fn nearest(&self) -> T {
+        self.distances[..self.levels]
+            .iter()
+            .copied()
+            .reduce(T::min)
+            .unwrap()
+    }
 }
@@ -0,0 +1,139 @@
+//! This module provides the seeded generator of the simulations, so a failing run is repeated exactly from one seed.
+//!
+//! Every randomized component of the crate, the [channels](crate::channel), the [dither](crate::samples::TpdfDither)
+//! and the [testing](crate::testing) harnesses, takes an explicit seed and never seeds itself from the system.
+//! A [SimulationRng] made from a master seed derives the seeds of the components from their names,
+//! so a test states one seed, and every component draws its own stream from it, independent of the others
+//! and of the order they are made in.
+//!
+//! The generator is SplitMix64, its Gaussian values come from the Box-Muller transform.
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The increment of SplitMix64, the golden ratio in 64 bits.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The finalizer of SplitMix64, a bijection mixing every bit of the input into every bit of the output.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns the 64-bit FNV-1a hash of the bytes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// A seeded generator of uniform and Gaussian values, which derives the seeds of the components of a simulation
+/// from its master seed.
+///
+/// The [seed](SimulationRng::seed) of a component only depends on the master seed and the name of the component,
+/// and [seed_at](SimulationRng::seed_at) on an index too, like the number of a frame. The values drawn from the
+/// generator itself do not change them.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::{AwgnChannel, BurstNoise, BurstShape, Channel, ChannelChain, MultipathChannel};
+/// use software_modem::rng::SimulationRng;
+///
+/// let channel = |master_seed| {
+///     let rng = SimulationRng::new(master_seed);
+///     ChannelChain::new()
+///         .with(MultipathChannel::exponential(8, 2.0, rng.seed("multipath")))
+///         .with(BurstNoise::new(48000.0, 10.0, 10..100, 1.0, BurstShape::Click, rng.seed("bursts")))
+///         .with(AwgnChannel::new(20.0, rng.seed("awgn")))
+/// };
+/// let (mut first, mut second) = (vec![0.5; 10000], vec![0.5; 10000]);
+/// channel(7).apply(&mut first);
+/// channel(7).apply(&mut second);
+/// assert_eq!(first, second);
+///
+/// let rng = SimulationRng::new(7);
+/// assert_ne!(rng.seed("awgn"), rng.seed("bursts"));
+/// assert_ne!(rng.seed_at("frame", 0), rng.seed_at("frame", 1));
+///
+/// // a stream of its own for a component, the same whatever was drawn before
+/// let first = rng.stream("payload").next_u64();
+/// let mut other = SimulationRng::new(7);
+/// other.gaussian();
+/// assert_eq!(other.stream("payload").next_u64(), first);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationRng {
+    master_seed: u64,
+    state: u64,
+    /// The second value of the last Box-Muller transform.
+    spare: Option<f64>,
+}
+
+impl SimulationRng {
+    /// Creates a generator from a master seed, its values start from it.
+    pub fn new(master_seed: u64) -> Self {
+        SimulationRng {
+            master_seed,
+            state: master_seed,
+            spare: None,
+        }
+    }
+
+    /// Returns the master seed of the generator.
+    pub fn get_master_seed(&self) -> u64 {
+        self.master_seed
+    }
+
+    /// Returns the seed of the named component, see [seed_at](SimulationRng::seed_at).
+    pub fn seed(&self, component: &str) -> u64 {
+        self.seed_at(component, 0)
+    }
+
+    /// Returns the seed of the named component at an index, like the number of a frame or of a point of a sweep.
+    ///
+    /// The name and the index are hashed into the master seed, every name and index gives an unrelated seed.
+    pub fn seed_at(&self, component: &str, index: u64) -> u64 {
+        let domain =
+            mix(fnv1a(component.as_bytes()).wrapping_add(mix(index.wrapping_add(GOLDEN_GAMMA))));
+        mix(self.master_seed ^ domain)
+    }
+
+    /// Returns a generator of the named component, from its [seed](SimulationRng::seed).
+    pub fn stream(&self, component: &str) -> SimulationRng {
+        SimulationRng::new(self.seed(component))
+    }
+
+    /// Returns the next uniform 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    /// Returns a uniform value in `(0, 1]`, which keeps a logarithm of it finite.
+    pub fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a standard normal value, two of them from every pair of uniform values.
+    pub fn gaussian(&mut self) -> f64 {
+        if let Some(spare) = self.spare.take() {
+            return spare;
+        }
+        let radius = (-2.0 * self.uniform().ln()).sqrt();
+        let angle = core::f64::consts::TAU * self.uniform();
+        self.spare = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+
+    /// Returns a value below the bound, the remainder of a 64-bit value, which is biased by at most `bound / 2^64`.
+    ///
+    /// # Panics
+    /// If the bound is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            panic!("Bound must be positive, but got 0");
+        }
+        self.next_u64() % bound
+    }
+}
@@ -0,0 +1,170 @@
+//! This module provides a [Receiver] which finds every frame of a long recording in one call,
+//! offline and without the squelch and the buffers of a [stream demodulator](crate::stream::StreamDemodulator).
+//!
+//! The header of a coded frame is its sync word: the receiver slides over the recording by a
+//! [stride](ScanConfig::stride), and detects a frame at an offset where the symbols of a header decode with a matching
+//! CRC, into points at an SNR high enough that they are no noise. The detections of a frame within the
+//! [de-duplication window](ScanConfig::dedup_window) are one frame, taken at the offset of the best of them,
+//! decoded with a [report](DemodulationReport) of its quality, and the scan goes on after its end.
+//!
+//! # Example
+//! ```
+//! use software_modem::channel::{AwgnChannel, Channel};
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::scan::{Receiver, ScanConfig};
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 16,
+//!     ..Default::default()
+//! };
+//! let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+//! let receiver = Receiver::new(
+//!     CodedOFDMDemodulator::new(ofdm, CodingConfig::default()),
+//!     ScanConfig::default(),
+//! );
+//!
+//! // two frames between stretches of noise
+//! let mut recording = vec![0.0; 1234];
+//! recording.extend(modulator.encode_frame(b"first"));
+//! recording.extend(vec![0.0; 2000]);
+//! recording.extend(modulator.encode_frame(b"second"));
+//! recording.extend(vec![0.0; 500]);
+//! AwgnChannel::with_reference_power(25.0, 0.01, 1).apply(&mut recording);
+//!
+//! let hits = receiver.scan(&recording);
+//! assert_eq!(hits.len(), 2);
+//! assert_eq!(hits[0].offset, 1234);
+//! assert_eq!(hits[0].payload.as_deref(), Ok(&b"first"[..]));
+//! assert_eq!(hits[1].payload.as_deref(), Ok(&b"second"[..]));
+//! assert_eq!(hits[1].report.crc_ok, Some(true));
+//! ```
+
+use alloc::vec::Vec;
+
+use smart_default::SmartDefault;
+
+use crate::{coded::CodedOFDMDemodulator, error::ModemError, metrics::DemodulationReport};
+
+/// Configuration of the search of a [Receiver].
+#[derive(SmartDefault, Clone, Copy, Debug, PartialEq)]
+pub struct ScanConfig {
+    /// Samples from one offset the receiver tries to the next.
+    ///
+    /// A coherent demodulator only decodes a header at the offset a frame starts at, so it needs a stride of 1.
+    /// A [differential](crate::ofdm::OFDMConfig::differential_time) one also decodes it up to about the cyclic prefix early,
+    /// so it finds every frame with a stride up to half the cyclic prefix, in fewer tries.
+    #[default(1)]
+    pub stride: usize,
+    /// Samples after a detection in which the detections are of the same frame, whose best one is kept.
+    #[default(64)]
+    pub dedup_window: usize,
+    /// SNR of the points of a header from which it is a frame and not noise whose CRC happens to match, in dB.
+    ///
+    /// The points of noise are within a few dB of the nearest decisions, and the products of noise
+    /// in [differential](crate::ofdm::OFDMConfig::differential_time) symbols further still.
+    #[default(10.0)]
+    pub min_snr_db: f32,
+}
+
+/// A frame found by a [Receiver].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanHit {
+    /// The sample of the recording the frame starts at.
+    pub offset: usize,
+    /// The payload of the frame, or the error it did not decode with, [ModemError::CrcMismatch] for a corrupted payload
+    /// and [ModemError::FrameTooShort] for a frame cut off by the end of the recording.
+    pub payload: Result<Vec<u8>, ModemError>,
+    /// The quality of the frame, see [decode_frame_with_report](CodedOFDMDemodulator::decode_frame_with_report).
+    pub report: DemodulationReport,
+}
+
+/// Finds and decodes every frame of a recording, see the [module](self) documentation.
+pub struct Receiver {
+    demodulator: CodedOFDMDemodulator,
+    config: ScanConfig,
+}
+
+impl Receiver {
+    /// Creates a new receiver of the frames of the demodulator.
+    ///
+    /// # Panics
+    /// If the stride is 0.
+    pub fn new(demodulator: CodedOFDMDemodulator, config: ScanConfig) -> Self {
+        assert!(
+            config.stride > 0,
+            "Stride must be at least 1, but got {}",
+            config.stride
+        );
+        Receiver {
+            demodulator,
+            config,
+        }
+    }
+
+    /// Returns the demodulator of the receiver.
+    pub fn get_demodulator(&self) -> &CodedOFDMDemodulator {
+        &self.demodulator
+    }
+
+    /// Returns the configuration of the search.
+    pub fn get_config(&self) -> &ScanConfig {
+        &self.config
+    }
+
+    /// Finds every frame of the samples, in the order they start in.
+    ///
+    /// A frame is reported once, at the offset of the header with the best SNR within the
+    /// [de-duplication window](ScanConfig::dedup_window) of the first offset it was detected at,
+    /// and the search goes on after the end of the frame its header announces.
+    pub fn scan(&self, samples: &[f32]) -> Vec<ScanHit> {
+        let mut hits = Vec::new();
+        let mut offset = 0;
+        while offset < samples.len() {
+            let Some(first) = self.detect(samples, offset) else {
+                offset += self.config.stride;
+                continue;
+            };
+            let (start, (frame_length, _)) = (offset + 1..=offset + self.config.dedup_window)
+                .filter_map(|offset| Some((offset, self.detect(samples, offset)?)))
+                .fold((offset, first), |best, detection| {
+                    if detection.1.1 > best.1.1 {
+                        detection
+                    } else {
+                        best
+                    }
+                });
+
+            let frame = &samples[start..(start + frame_length).min(samples.len())];
+            let (payload, report) = self.demodulator.decode_frame_with_report(frame);
+            trace_event!(
+                DEBUG,
+                "frame scanned",
+                offset = start,
+                error = payload.as_ref().err().map(ModemError::name),
+            );
+            hits.push(ScanHit {
+                offset: start,
+                payload,
+                report,
+            });
+            offset = start + frame_length;
+        }
+        hits
+    }
+
+    /// Returns the length of the frame starting at the offset with the SNR of its header in dB,
+    /// `None` if no header decodes there.
+    fn detect(&self, samples: &[f32], offset: usize) -> Option<(usize, f32)> {
+        let header = samples.get(offset..offset + self.demodulator.get_header_length())?;
+        let frame_length = match self.demodulator.decode_frame(header) {
+            Ok(_) => header.len(),
+            Err(ModemError::FrameTooShort { expected, .. }) => expected,
+            Err(_) => return None,
+        };
+        let snr_db = self.demodulator.decode_frame_with_report(header).1.snr_db?;
+        (snr_db >= self.config.min_snr_db).then_some((frame_length, snr_db))
+    }
+}
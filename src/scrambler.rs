@@ -0,0 +1,178 @@
+//! This module provides an additive LFSR scrambler, which whitens the data before it is coded and mapped.
+//!
+//! Long runs of identical bits, like zero padding, map to the same constellation point on many subcarriers,
+//! which adds up to strong spectral lines and peaks in the time domain.
+//! Scrambling adds a pseudo random sequence to the bits, adding the same sequence again restores them.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Display;
+
+/// An additive scrambler, defined by the polynomial and the seed of its LFSR.
+///
+/// The polynomial is given as a bit mask of its terms, `x^7 + x^4 + 1` is `0b1001_0001`.
+/// The default is the scrambler of 802.11 with the all ones seed.
+///
+/// # Example
+/// ```
+/// use software_modem::scrambler::Scrambler;
+///
+/// let scrambler = Scrambler::default();
+///
+/// // the first bits of the 802.11 sequence with the all ones seed
+/// assert_eq!(scrambler.sequence(16), vec![0, 0, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 1, 0]);
+/// assert_eq!(scrambler.scramble_bytes(&[0; 4]), vec![0x0e, 0xf2, 0xc9, 0x02]);
+///
+/// // the sequence repeats after 127 bits
+/// let sequence = scrambler.sequence(254);
+/// assert_eq!(sequence[..127], sequence[127..]);
+///
+/// let bits = [1, 1, 1, 1, 0, 0, 0, 0, 1, 0, 1, 0];
+/// let scrambled = scrambler.scramble(&bits);
+/// assert_eq!(scrambler.scramble(&scrambled), bits);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Scrambler {
+    polynomial: u32,
+    seed: u32,
+}
+
+impl Default for Scrambler {
+    fn default() -> Self {
+        Scrambler::new(0b1001_0001, 0b111_1111)
+    }
+}
+
+impl Display for Scrambler {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "scrambler {:#x}, seed {:#x}", self.polynomial, self.seed)
+    }
+}
+
+impl Scrambler {
+    /// Creates a new scrambler with the polynomial and the initial state of the LFSR.
+    ///
+    /// # Panics
+    /// If the polynomial has a degree of less than 2 or more than 31, or the seed is zero or does not fit the degree.
+    pub fn new(polynomial: u32, seed: u32) -> Self {
+        Scrambler::try_new(polynomial, seed).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    /// Creates a new scrambler, or returns why the polynomial or the seed is invalid.
+    fn try_new(polynomial: u32, seed: u32) -> Result<Self, String> {
+        let degree = 31 - polynomial.leading_zeros().min(31);
+        if !(2..=31).contains(&degree) {
+            return Err(format!(
+                "Polynomial degree must be between 2 and 31, but got {}",
+                degree
+            ));
+        }
+        if seed == 0 || seed >> degree != 0 {
+            return Err(format!(
+                "Seed must be a non-zero value of {} bits, but got {:#x}",
+                degree, seed
+            ));
+        }
+
+        Ok(Scrambler { polynomial, seed })
+    }
+
+    /// Returns the polynomial as a bit mask of its terms.
+    pub fn polynomial(&self) -> u32 {
+        self.polynomial
+    }
+
+    /// Returns the initial state of the LFSR.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Returns the degree of the polynomial, the length of the LFSR.
+    pub fn degree(&self) -> u32 {
+        31 - self.polynomial.leading_zeros()
+    }
+
+    /// Returns the same scrambler with a different seed.
+    ///
+    /// # Panics
+    /// If the seed is zero or does not fit the degree.
+    pub fn with_seed(&self, seed: u32) -> Self {
+        Scrambler::new(self.polynomial, seed)
+    }
+
+    /// Returns the first `length` bits of the scrambling sequence.
+    pub fn sequence(&self, length: usize) -> Vec<u8> {
+        let degree = self.degree();
+        let mask = (1 << degree) - 1;
+        // the term x^d taps bit d - 1 of the state, x^1 being the newest bit
+        let taps = (self.polynomial >> 1) & mask;
+
+        let mut state = self.seed;
+        (0..length)
+            .map(|_| {
+                let bit = (state & taps).count_ones() & 1;
+                state = ((state << 1) | bit) & mask;
+                bit as u8
+            })
+            .collect()
+    }
+
+    /// Adds the scrambling sequence to the bits. Scrambling twice restores the bits.
+    pub fn scramble(&self, bits: &[u8]) -> Vec<u8> {
+        bits.iter()
+            .zip(self.sequence(bits.len()))
+            .map(|(bit, scrambling)| (bit & 1) ^ scrambling)
+            .collect()
+    }
+
+    /// Adds the scrambling sequence to the bits of the bytes, most significant bit first.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::frame::FrameEncoder;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::scrambler::Scrambler;
+    ///
+    /// let encoder = FrameEncoder::new(OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 4,
+    ///     ..Default::default()
+    /// }));
+    /// let papr = |samples: &[f32]| {
+    ///     let peak = samples.iter().map(|x| x * x).fold(0.0, f32::max);
+    ///     let mean = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    ///     10.0 * (peak / mean).log10()
+    /// };
+    ///
+    /// let zeros = vec![0; 240];
+    /// let unscrambled = papr(&encoder.encode(&zeros));
+    /// let scrambled = papr(&encoder.encode(&Scrambler::default().scramble_bytes(&zeros)));
+    /// assert!(unscrambled - scrambled > 6.0, "{unscrambled} dB vs {scrambled} dB");
+    /// ```
+    pub fn scramble_bytes(&self, bytes: &[u8]) -> Vec<u8> {
+        let sequence = self.sequence(8 * bytes.len());
+        bytes
+            .iter()
+            .zip(sequence.chunks(8))
+            .map(|(byte, scrambling)| {
+                byte ^ scrambling
+                    .iter()
+                    .fold(0, |scrambling, bit| (scrambling << 1) | bit)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Scrambler {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            polynomial: u32,
+            seed: u32,
+        }
+
+        let Fields { polynomial, seed } = Fields::deserialize(deserializer)?;
+        Scrambler::try_new(polynomial, seed).map_err(serde::de::Error::custom)
+    }
+}
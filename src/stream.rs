@@ -0,0 +1,1455 @@
+//! This module provides the transmission and reception of frames over a continuous stream of samples, on the calling thread.
+//!
+//! The [StreamModulator] writes frames separated by silence to a [SampleSink].
+//! The [StreamDemodulator] takes blocks of any size, down to the 128 samples of a Web Audio `AudioWorklet`,
+//! cuts the bursts of signal out of them with a squelch, and decodes the frames in them,
+//! pushed one by one or [run](StreamDemodulator::run) over a [SampleSource].
+//! It can [listen for beacons](StreamDemodulator::listen_for_beacons) of another profile at the same time,
+//! so a link can hear the profile advertised by the other end and [switch to it](StreamDemodulator::set_demodulator).
+//! A live display can [sample the constellation](StreamDemodulator::set_snapshot_hook) of the frames it decodes,
+//! and a [debug tap](StreamDemodulator::set_debug_tap) sees every stage of every burst.
+//! A receiver can [record](StreamDemodulator::push_recorded) its input into a [capture](crate::io::capture),
+//! which another stream [replays](StreamDemodulator::replay) to the same payloads and counters.
+//! Both keep [counters](StreamDemodulator::stats) of what went through them, for monitoring a link,
+//! and the demodulator [meters its input](StreamDemodulator::input_stats), to tell a quiet input from a clipping one.
+//! The demodulator [removes the DC offset](StreamDemodulator::enable_dc_blocker) of its input,
+//! and can [level it](StreamDemodulator::set_agc) with an automatic gain control.
+//! Against a channel which rolls off, the modulator can [boost the high frequencies](StreamModulator::set_preemphasis)
+//! of its frames, and the demodulator [cut them](StreamDemodulator::set_deemphasis) as much.
+//! They need neither threads nor clocks, the [audio](crate::audio) module runs them on its worker thread and for its queue.
+//!
+//! # Example
+//! The same round trip over three sources and sinks.
+//! ```
+//! use std::collections::VecDeque;
+//!
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::io::gr::{GrReader, GrWriter};
+//! use software_modem::io::{SampleSink, SampleSource};
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::stream::{StreamDemodulator, StreamModulator};
+//!
+//! fn transmit<K: SampleSink>(ofdm: &OFDMConfig, payloads: &[Vec<u8>], mut sink: K) -> K {
+//!     let modulator = StreamModulator::new(CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()), 4800);
+//!     for payload in payloads {
+//!         modulator.write_frame(payload, &mut sink).unwrap();
+//!     }
+//!     sink
+//! }
+//!
+//! fn receive<S: SampleSource>(ofdm: &OFDMConfig, mut source: S) -> Vec<Vec<u8>> {
+//!     let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+//!     StreamDemodulator::new(demodulator, 0.01, 2400).run(&mut source, 128).unwrap()
+//! }
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! };
+//! let payloads = [b"first frame".to_vec(), b"second frame".to_vec()];
+//!
+//! // a vector, read back as a slice
+//! let samples = transmit(&ofdm, &payloads, Vec::new());
+//! assert_eq!(receive(&ofdm, samples.as_slice()), payloads);
+//!
+//! // a ring buffer
+//! let ring = transmit(&ofdm, &payloads, VecDeque::new());
+//! assert_eq!(receive(&ofdm, ring), payloads);
+//!
+//! // the bytes of f32 samples through std::io::Write and Read
+//! let bytes = transmit(&ofdm, &payloads, GrWriter::<f32, _>::new(Vec::new())).finish().unwrap();
+//! assert_eq!(receive(&ofdm, GrReader::<f32, _>::new(bytes.as_slice())), payloads);
+//! ```
+
+use alloc::{collections::VecDeque, sync::Arc};
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::io::{Read, Write};
+
+use num_complex::Complex32;
+
+#[cfg(feature = "perf")]
+use crate::perf::Metrics;
+use crate::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::{Agc, DcBlocker, DcBlockerHold, Deemphasis, Preemphasis},
+    error::ModemError,
+    io::{
+        SampleSink, SampleSource,
+        capture::{CaptureChunk, CaptureError, CaptureReader, CaptureWriter, MarkerKind},
+    },
+    metrics::{DemodulationReport, EvmResult, FecStats},
+    ofdm::{Stage, StageTimer},
+    qam::QAMOrder,
+    tap::StageSink,
+};
+
+/// The metrics of a [StreamDemodulator], if enabled, nothing without the `perf` feature.
+#[cfg(feature = "perf")]
+type Timer = Option<Metrics>;
+#[cfg(not(feature = "perf"))]
+type Timer = ();
+
+/// Whether the squelch of a [StreamDemodulator] is open.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SyncState {
+    /// Waiting for a frame to rise above the squelch level.
+    #[default]
+    Idle,
+    /// Collecting the samples of a frame, until the signal stays below the squelch level for the hang time.
+    Receiving,
+}
+
+/// The equalized points of a symbol of a frame decoded by a [StreamDemodulator], for a live constellation display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstellationSnapshot {
+    /// Index of the symbol in the stream, counting the symbols of every frame decoded.
+    pub symbol_index: u64,
+    /// The data subcarrier points of the symbol.
+    pub points: Vec<Complex32>,
+    /// The EVM of the points against their decisions, unknown if a point is not finite.
+    pub evm: Option<EvmResult>,
+}
+
+/// Receives the [snapshots](ConstellationSnapshot) of a [StreamDemodulator],
+/// see [set_snapshot_hook](StreamDemodulator::set_snapshot_hook).
+pub type SnapshotHook = Arc<dyn Fn(&ConstellationSnapshot) + Send + Sync>;
+
+/// The hook of a [StreamDemodulator] and the snapshot it is called with, refilled for every symbol.
+struct Snapshots {
+    hook: SnapshotHook,
+    every: u64,
+    snapshot: ConstellationSnapshot,
+}
+
+/// Counters of the frames, symbols and samples that went through a [StreamDemodulator],
+/// see [stats](StreamDemodulator::stats).
+///
+/// They count from the creation of the stream or the last [reset](StreamDemodulator::reset_stats),
+/// without the beacons of a [beacon profile](StreamDemodulator::listen_for_beacons).
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Bursts cut out by the squelch and tried as a frame, decoded or not.
+    pub frames_attempted: u64,
+    /// Frames decoded with a valid CRC.
+    pub frames_decoded: u64,
+    /// Bursts which did not decode, but whose header did at some offset, with a payload CRC that did not match.
+    pub frames_crc_failed: u64,
+    /// Frames decoded whose FEC corrected at least one bit or byte, see [FecStats].
+    pub frames_fec_corrected: u64,
+    /// Symbols of the frames decoded, with their header and reference symbols.
+    pub symbols_demodulated: u64,
+    /// Times the squelch opened.
+    pub sync_acquisitions: u64,
+    /// Times the squelch closed, after the hang, at the longest burst or at a [flush](StreamDemodulator::flush).
+    pub sync_losses: u64,
+    /// Bytes of the payloads decoded.
+    pub bytes_delivered: u64,
+    /// Samples dropped by the squelch while waiting for a frame, before its pre-roll.
+    pub samples_discarded: u64,
+}
+
+/// How the level of the input of a [StreamDemodulator] looks for demodulation, see [InputStats].
+///
+/// The input is classified over the last window of the [meter](StreamDemodulator::set_input_meter),
+/// from the most severe condition down:
+///
+/// | Health | Condition |
+/// |---|---|
+/// | [Clipping](InputHealth::Clipping) | a sample at or beyond full scale |
+/// | [NearClipping](InputHealth::NearClipping) | the peak above [NEAR_CLIPPING_DBFS] |
+/// | [TooQuiet](InputHealth::TooQuiet) | the RMS level below [TOO_QUIET_DBFS] |
+/// | [Ok](InputHealth::Ok) | otherwise |
+///
+/// A window of silence between frames reads as too quiet like an input that is, a link which sends in bursts
+/// looks at the health while a frame is received.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputHealth {
+    /// The level of the signal is below what the squelch and the synchronization expect.
+    TooQuiet,
+    /// The level leaves room to full scale.
+    #[default]
+    Ok,
+    /// Peaks come close to full scale, a little more gain clips them.
+    NearClipping,
+    /// Samples reach full scale, the signal is distorted.
+    Clipping,
+}
+
+impl InputHealth {
+    /// Returns the name of the variant, the value of the `health` field of the `tracing` warning.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            InputHealth::TooQuiet => "TooQuiet",
+            InputHealth::Ok => "Ok",
+            InputHealth::NearClipping => "NearClipping",
+            InputHealth::Clipping => "Clipping",
+        }
+    }
+}
+
+/// Peak level in dBFS above which the input is [near clipping](InputHealth::NearClipping).
+pub const NEAR_CLIPPING_DBFS: f32 = -1.0;
+
+/// RMS level in dBFS below which the input is [too quiet](InputHealth::TooQuiet).
+pub const TOO_QUIET_DBFS: f32 = -50.0;
+
+/// The level of the input of a [StreamDemodulator] over the last window of its meter,
+/// see [input_stats](StreamDemodulator::input_stats).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InputStats {
+    /// RMS level of the window in dBFS, `-inf` before the first window or over digital silence.
+    pub rms_dbfs: f32,
+    /// Largest magnitude of a sample of the window in dBFS.
+    pub peak_dbfs: f32,
+    /// Mean of the samples of the window, relative to full scale.
+    pub dc_offset: f32,
+    /// Samples at or beyond full scale, counted like the [LinkStats].
+    pub clipped_samples: u64,
+    /// The classification of the window, [Ok](InputHealth::Ok) before the first one.
+    pub health: InputHealth,
+    /// Gain of the [AGC](StreamDemodulator::set_agc) in dB after the last push, 0 without one.
+    /// The level of the window is the one before it.
+    pub agc_gain_db: f32,
+}
+
+impl Default for InputStats {
+    fn default() -> Self {
+        InputStats {
+            rms_dbfs: f32::NEG_INFINITY,
+            peak_dbfs: f32::NEG_INFINITY,
+            dc_offset: 0.0,
+            clipped_samples: 0,
+            health: InputHealth::Ok,
+            agc_gain_db: 0.0,
+        }
+    }
+}
+
+/// Measures the level of the input of a [StreamDemodulator] window by window.
+struct InputMeter {
+    full_scale: f32,
+    window: usize,
+    /// Sums over the window being measured, and the samples in it.
+    sum: f32,
+    sum_squares: f32,
+    peak: f32,
+    count: usize,
+    /// The measurement of the last whole window.
+    stats: InputStats,
+}
+
+impl InputMeter {
+    fn new(full_scale: f32, window: usize) -> Self {
+        InputMeter {
+            full_scale,
+            window,
+            sum: 0.0,
+            sum_squares: 0.0,
+            peak: 0.0,
+            count: 0,
+            stats: InputStats::default(),
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let magnitude = sample.abs();
+            self.sum += sample;
+            self.sum_squares += sample * sample;
+            self.peak = self.peak.max(magnitude);
+            if magnitude >= self.full_scale {
+                self.stats.clipped_samples += 1;
+            }
+            self.count += 1;
+            if self.count == self.window {
+                self.end_window();
+            }
+        }
+    }
+
+    /// Publishes the measurement of the window and starts the next one.
+    fn end_window(&mut self) {
+        let count = self.count as f32;
+        let rms = (self.sum_squares / count).sqrt() / self.full_scale;
+        self.stats.rms_dbfs = 20.0 * rms.log10();
+        self.stats.peak_dbfs = 20.0 * (self.peak / self.full_scale).log10();
+        self.stats.dc_offset = self.sum / count / self.full_scale;
+
+        let health = if self.peak >= self.full_scale {
+            InputHealth::Clipping
+        } else if self.stats.peak_dbfs > NEAR_CLIPPING_DBFS {
+            InputHealth::NearClipping
+        } else if self.stats.rms_dbfs < TOO_QUIET_DBFS {
+            InputHealth::TooQuiet
+        } else {
+            InputHealth::Ok
+        };
+        if health != self.stats.health {
+            trace_event!(
+                WARN,
+                "input health changed",
+                health = health.name(),
+                rms_dbfs = self.stats.rms_dbfs,
+                peak_dbfs = self.stats.peak_dbfs,
+                dc_offset = self.stats.dc_offset,
+            );
+            self.stats.health = health;
+        }
+
+        self.sum = 0.0;
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.count = 0;
+    }
+}
+
+/// Counters of the frames written by a [StreamModulator], see [stats](StreamModulator::stats).
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TransmitStats {
+    /// Frames written to a sink without an error.
+    pub frames_sent: u64,
+    /// Bytes of their payloads.
+    pub bytes_sent: u64,
+    /// Samples of their frames and gaps.
+    pub samples_written: u64,
+}
+
+/// Writes frames to a [SampleSink], each followed by a gap of silence.
+///
+/// The gap lets the squelch of a [StreamDemodulator] close between two frames, it must be longer than its hang.
+/// See the [module](self) for an example.
+pub struct StreamModulator {
+    modulator: CodedOFDMModulator,
+    frame_gap: usize,
+    preemphasis: Option<Preemphasis>,
+    /// The [TransmitStats], atomic as the frames are written through a shared reference.
+    frames_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    samples_written: AtomicU64,
+}
+
+impl StreamModulator {
+    /// Creates a stream modulator, which follows every frame by `frame_gap` samples of silence.
+    pub fn new(modulator: CodedOFDMModulator, frame_gap: usize) -> Self {
+        StreamModulator {
+            modulator,
+            frame_gap,
+            preemphasis: None,
+            frames_sent: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            samples_written: AtomicU64::new(0),
+        }
+    }
+
+    /// Encodes the payload and writes its frame and the gap after it.
+    ///
+    /// # Panics
+    /// If the payload is longer than 65535 bytes.
+    ///
+    /// # Errors
+    /// The error of the sink, if the samples can not be written.
+    pub fn write_frame<K: SampleSink + ?Sized>(
+        &self,
+        payload: &[u8],
+        sink: &mut K,
+    ) -> Result<(), K::Error> {
+        let mut frame = self.modulator.encode_frame(payload);
+        frame.resize(frame.len() + self.frame_gap, 0.0);
+        if let Some(preemphasis) = &self.preemphasis {
+            preemphasis.clone().process_in_place(&mut frame);
+        }
+        sink.write(&frame)?;
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent
+            .fetch_add(payload.len() as u64, Ordering::Relaxed);
+        self.samples_written
+            .fetch_add(frame.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Passes every frame and the gap after it through a [Preemphasis], or none with `None`,
+    /// replacing the one before.
+    ///
+    /// Every frame starts from a filter at rest, after the gap of the previous one, into which the filter rings.
+    /// The boost raises the peaks of the frames, which the [output scale](crate::ofdm::modulator::OutputScale)
+    /// of the modulator has to leave room for.
+    pub fn set_preemphasis(&mut self, preemphasis: Option<Preemphasis>) {
+        self.preemphasis = preemphasis;
+    }
+
+    /// Returns the [Preemphasis] of the frames, if there is one.
+    pub fn get_preemphasis(&self) -> Option<&Preemphasis> {
+        self.preemphasis.as_ref()
+    }
+
+    /// Switches the QAM order of the following frames, like an adaptive link, see
+    /// [OFDMModulator::set_qam_order](crate::ofdm::modulator::GenericOFDMModulator::set_qam_order).
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the order does not fit the data subcarriers, then the order is left as it was.
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.modulator.set_qam_order(qam_order)
+    }
+
+    /// Returns the number of samples written for a payload of `payload_length` bytes, including the gap.
+    pub fn get_frame_length(&self, payload_length: usize) -> usize {
+        self.modulator.get_frame_length(payload_length) + self.frame_gap
+    }
+
+    /// Returns the counters of the frames written since the creation of the stream or the last [reset](Self::reset_stats).
+    pub fn stats(&self) -> TransmitStats {
+        TransmitStats {
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            samples_written: self.samples_written.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets the counters of the frames written back to 0.
+    pub fn reset_stats(&self) {
+        self.frames_sent.store(0, Ordering::Relaxed);
+        self.bytes_sent.store(0, Ordering::Relaxed);
+        self.samples_written.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Decodes the frames of a stream of samples, pushed in blocks.
+///
+/// The samples pass through a squelch, which opens when the magnitude rises above the squelch level
+/// and keeps the symbol before it, and closes when the signal stays below it for the hang time,
+/// or when the burst reaches its [maximum length](StreamDemodulator::set_max_burst_length).
+/// The burst in between is synchronized by trying every offset of its first symbol,
+/// until one decodes with a valid header and CRC. The decoding runs within the [push](StreamDemodulator::push)
+/// that closes the squelch, the others only copy the samples.
+///
+/// # Example
+/// ```
+/// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+/// use software_modem::frame::CodingConfig;
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::ofdm::modulator::OutputScale;
+/// use software_modem::stream::{StreamDemodulator, SyncState};
+///
+/// let ofdm = OFDMConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix_length: 4,
+///     differential_time: true,
+///     output_scale: OutputScale::PeakNormalize(0.5),
+///     ..Default::default()
+/// };
+/// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+/// let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+/// let mut stream = StreamDemodulator::new(demodulator, 0.01, 2400);
+///
+/// // two frames with silence around them, attenuated and with some noise
+/// let mut signal = vec![0.0; 1234];
+/// for payload in [b"first frame".as_slice(), b"second frame"] {
+///     signal.extend(modulator.encode_frame(payload));
+///     signal.extend([0.0; 4800]);
+/// }
+/// let mut noise: u32 = 0x1234_5678;
+/// let received: Vec<f32> = signal
+///     .iter()
+///     .map(|sample| {
+///         noise ^= noise << 13;
+///         noise ^= noise >> 17;
+///         noise ^= noise << 5;
+///         0.3 * sample + 0.002 * (noise as f32 / u32::MAX as f32 - 0.5)
+///     })
+///     .collect();
+///
+/// // in the blocks of an AudioWorklet
+/// let mut payloads = Vec::new();
+/// for block in received.chunks(128) {
+///     payloads.extend(stream.push(block));
+/// }
+/// assert_eq!(payloads, [b"first frame".to_vec(), b"second frame".to_vec()]);
+/// assert_eq!((stream.get_frames_decoded(), stream.get_frames_failed()), (2, 0));
+/// assert_eq!(stream.get_sync_state(), SyncState::Idle);
+/// ```
+pub struct StreamDemodulator {
+    demodulator: CodedOFDMDemodulator,
+    squelch: Squelch,
+    /// The frame being decoded, a copy of a burst from an offset, which the FFT clobbers.
+    frame: Vec<f32>,
+    /// The data subcarrier points of the frame being decoded.
+    points: Vec<Complex32>,
+    frames_decoded: usize,
+    frames_failed: usize,
+    timer: Timer,
+    beacons: Option<BeaconListener>,
+    /// Symbols of the frames decoded, the index of the next snapshot.
+    symbols_decoded: u64,
+    snapshots: Option<Snapshots>,
+    /// The counters kept by the stream, the ones of the squelch are added to them.
+    stats: LinkStats,
+    fec: FecStats,
+    input: InputMeter,
+    dc_blocker: Option<DcBlocker>,
+    /// Whether the DC blocker is the one of [enable_dc_blocker](StreamDemodulator::enable_dc_blocker),
+    /// made again for the subcarrier spacing of another demodulator.
+    default_dc_blocker: bool,
+    deemphasis: Option<Deemphasis>,
+    agc: Option<Agc>,
+    /// The block after the DC blocker, the de-emphasis and the AGC.
+    conditioned: Vec<f32>,
+    tap: Option<Box<dyn StageSink + Send>>,
+}
+
+/// The second synchronizer of a [StreamDemodulator], decoding the frames of the beacon profile in the same samples.
+struct BeaconListener {
+    demodulator: CodedOFDMDemodulator,
+    squelch: Squelch,
+    frame: Vec<f32>,
+    points: Vec<Complex32>,
+    /// The payloads of the beacons decoded since they were last taken.
+    payloads: Vec<Vec<u8>>,
+    beacons_decoded: usize,
+}
+
+impl BeaconListener {
+    fn decode_bursts(&mut self, bursts: &[Burst]) {
+        for burst in bursts {
+            if let Ok((payload, _, _)) = find_frame(
+                &self.demodulator,
+                burst,
+                &mut self.frame,
+                &mut self.points,
+                &mut FecStats::default(),
+                &mut (),
+            ) {
+                trace_event!(DEBUG, "beacon decoded", bytes = payload.len());
+                self.payloads.push(payload);
+                self.beacons_decoded += 1;
+            }
+        }
+    }
+}
+
+/// Returns the DC blocker of a stream of the demodulator, with a cutoff of a tenth of the subcarrier spacing.
+fn get_default_dc_blocker(demodulator: &CodedOFDMDemodulator) -> DcBlocker {
+    DcBlocker::new(0.1 / demodulator.get_fft_length() as f32)
+}
+
+/// Returns the longest burst of the demodulator, the longest frame with a symbol of pre-roll and the hang.
+fn get_default_max_length(demodulator: &CodedOFDMDemodulator, hang: usize) -> usize {
+    demodulator.get_symbol_length() + demodulator.get_max_frame_length() + hang
+}
+
+impl StreamDemodulator {
+    /// Creates a stream demodulator, whose squelch opens above the level, a magnitude at full scale,
+    /// and closes after `hang` samples below it, shorter than the gap between two frames.
+    ///
+    /// # Panics
+    /// If the squelch level is not positive and finite, or the hang is 0.
+    pub fn new(demodulator: CodedOFDMDemodulator, squelch_level: f32, hang: usize) -> Self {
+        if !(squelch_level > 0.0 && squelch_level.is_finite()) {
+            panic!(
+                "Squelch level must be positive and finite, but got {}",
+                squelch_level
+            );
+        }
+        if hang == 0 {
+            panic!("Hang must be at least 1, but got 0");
+        }
+
+        let squelch = Squelch::new(
+            squelch_level,
+            hang,
+            demodulator.get_symbol_length(),
+            get_default_max_length(&demodulator, hang),
+        );
+        let dc_blocker = get_default_dc_blocker(&demodulator);
+        StreamDemodulator {
+            demodulator,
+            squelch,
+            frame: Vec::new(),
+            points: Vec::new(),
+            frames_decoded: 0,
+            frames_failed: 0,
+            timer: Timer::default(),
+            beacons: None,
+            symbols_decoded: 0,
+            snapshots: None,
+            stats: LinkStats::default(),
+            fec: FecStats::default(),
+            input: InputMeter::new(1.0, 4800),
+            dc_blocker: Some(dc_blocker),
+            default_dc_blocker: true,
+            deemphasis: None,
+            agc: None,
+            conditioned: Vec::new(),
+            tap: None,
+        }
+    }
+
+    /// Listens for the frames of a second, beacon profile, like [robust_beacon](crate::ofdm::profiles::robust_beacon),
+    /// besides the frames of the main profile, replacing the beacon profile listened for before.
+    ///
+    /// The beacons have a squelch of their own, with the level and hang of the main one, and are decoded
+    /// from the same samples in the [push](StreamDemodulator::push) and [flush](StreamDemodulator::flush)
+    /// that end them. Their payloads are kept for [take_beacons](StreamDemodulator::take_beacons),
+    /// apart from the ones of the main profile. A burst is tried with both profiles, so a beacon also counts
+    /// as a [failed frame](StreamDemodulator::get_frames_failed) of the main profile, and a frame of the main profile
+    /// is tried with the beacon profile.
+    ///
+    /// The squelch of a beacon opens on its samples like the one of a frame, a beacon below the noise
+    /// needs a squelch level above the noise, and the noise must fall silent between the frames.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::ofdm::profiles::{beacon_payload, parse_beacon, robust_beacon};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::stream::StreamDemodulator;
+    ///
+    /// // the other end advertises its main profile in a beacon, then sends a frame of it
+    /// let main = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let (beacon_ofdm, beacon_coding) = robust_beacon();
+    /// let beacon = CodedOFDMModulator::new(beacon_ofdm.clone(), beacon_coding.clone());
+    /// let mut signal = beacon.encode_frame(&beacon_payload(&main, &CodingConfig::default()));
+    /// signal.extend([0.0; 4800]);
+    /// let frame_start = signal.len();
+    /// let data = CodedOFDMModulator::new(main.clone(), CodingConfig::default());
+    /// signal.extend(data.encode_frame(b"over the main profile"));
+    /// signal.extend([0.0; 4800]);
+    ///
+    /// // a receiver which does not know it yet
+    /// let unknown = OFDMConfig {
+    ///     num_subcarriers: 128,
+    ///     cyclic_prefix_length: 16,
+    ///     ..Default::default()
+    /// };
+    /// let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(unknown, CodingConfig::default()), 0.01, 2400);
+    /// stream.listen_for_beacons(CodedOFDMDemodulator::new(beacon_ofdm, beacon_coding));
+    /// stream.push(&signal[..frame_start]);
+    /// let (ofdm, coding) = parse_beacon(&stream.take_beacons()[0]).unwrap();
+    /// assert_eq!(ofdm, main);
+    ///
+    /// // switched over, it decodes the frames of the main profile
+    /// stream.set_demodulator(CodedOFDMDemodulator::new(ofdm, coding));
+    /// assert_eq!(stream.push(&signal[frame_start..]), [b"over the main profile".to_vec()]);
+    /// assert_eq!(stream.get_beacons_decoded(), 1);
+    /// ```
+    pub fn listen_for_beacons(&mut self, demodulator: CodedOFDMDemodulator) {
+        let squelch = Squelch::new(
+            self.squelch.level,
+            self.squelch.hang,
+            demodulator.get_symbol_length(),
+            get_default_max_length(&demodulator, self.squelch.hang),
+        );
+        self.beacons = Some(BeaconListener {
+            demodulator,
+            squelch,
+            frame: Vec::new(),
+            points: Vec::new(),
+            payloads: Vec::new(),
+            beacons_decoded: 0,
+        });
+    }
+
+    /// Returns the payloads of the beacons decoded since the last call, none if no beacon profile is listened for.
+    pub fn take_beacons(&mut self) -> Vec<Vec<u8>> {
+        self.beacons
+            .as_mut()
+            .map(|beacons| core::mem::take(&mut beacons.payloads))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of beacons decoded with a valid CRC.
+    pub fn get_beacons_decoded(&self) -> usize {
+        self.beacons
+            .as_ref()
+            .map_or(0, |beacons| beacons.beacons_decoded)
+    }
+
+    /// Switches the main profile to the demodulator, like the one advertised by a beacon.
+    ///
+    /// The burst being received is decoded with it when it ends. The pre-roll of the squelch becomes a symbol
+    /// of the demodulator, and the [maximum burst length](StreamDemodulator::set_max_burst_length) the default of it.
+    /// The DC blocker of [enable_dc_blocker](StreamDemodulator::enable_dc_blocker) is made again
+    /// for the subcarrier spacing of the demodulator, one [set](StreamDemodulator::set_dc_blocker) is kept.
+    pub fn set_demodulator(&mut self, demodulator: CodedOFDMDemodulator) {
+        self.squelch.set_pre_roll(demodulator.get_symbol_length());
+        self.squelch.max_length = get_default_max_length(&demodulator, self.squelch.hang);
+        if self.default_dc_blocker {
+            self.dc_blocker = Some(get_default_dc_blocker(&demodulator));
+        }
+        self.demodulator = demodulator;
+    }
+
+    /// Switches the QAM order of the main profile, like an adaptive link between two frames, see
+    /// [OFDMDemodulator::set_qam_order](crate::ofdm::demodulator::GenericOFDMDemodulator::set_qam_order).
+    ///
+    /// Unlike [set_demodulator](StreamDemodulator::set_demodulator), neither the FFT nor the frame buffers
+    /// are made again. The burst being received is decoded with the new order when it ends,
+    /// and the [maximum burst length](StreamDemodulator::set_max_burst_length) becomes the default of it.
+    ///
+    /// # Errors
+    /// [ModemError::InvalidConfig] if the order does not fit the data subcarriers, then the order is left as it was.
+    pub fn set_qam_order(&mut self, qam_order: QAMOrder) -> Result<(), ModemError> {
+        self.demodulator.set_qam_order(qam_order)?;
+        self.squelch.max_length = get_default_max_length(&self.demodulator, self.squelch.hang);
+        Ok(())
+    }
+
+    /// Pushes a block of samples, and returns the payloads of the frames that ended in it.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<u8>> {
+        #[cfg(feature = "perf")]
+        let start = self.timer.is_some().then(std::time::Instant::now);
+
+        self.input.process(samples);
+        let mut conditioned = core::mem::take(&mut self.conditioned);
+        let samples =
+            if self.dc_blocker.is_some() || self.deemphasis.is_some() || self.agc.is_some() {
+                conditioned.clear();
+                conditioned.extend_from_slice(samples);
+                if let Some(dc_blocker) = &mut self.dc_blocker {
+                    // the estimate of the offset stays put over the samples of a frame, and follows what is left
+                    // of an offset just above the squelch level, like after a step or a stuck transmitter
+                    dc_blocker.set_hold(Some(DcBlockerHold {
+                        level: 4.0 * self.squelch.level,
+                        max_length: self.squelch.max_length,
+                    }));
+                    dc_blocker.process_in_place(&mut conditioned);
+                }
+                if let Some(deemphasis) = &mut self.deemphasis {
+                    deemphasis.process_in_place(&mut conditioned);
+                }
+                if let Some(agc) = &mut self.agc {
+                    // the gain follows the level between frames, and stays put within one
+                    agc.set_hold(self.squelch.state == SyncState::Receiving);
+                    agc.process_in_place(&mut conditioned);
+                    self.input.stats.agc_gain_db = agc.get_gain_db();
+                }
+                &conditioned
+            } else {
+                samples
+            };
+        let bursts = StageTimer::time(&mut self.timer, Stage::Sync, || {
+            self.squelch.process(samples)
+        });
+        let payloads = bursts
+            .iter()
+            .filter_map(|burst| self.decode_burst(burst))
+            .collect();
+        if let Some(beacons) = &mut self.beacons {
+            let bursts = beacons.squelch.process(samples);
+            beacons.decode_bursts(&bursts);
+        }
+
+        #[cfg(feature = "perf")]
+        if let (Some(metrics), Some(start)) = (&mut self.timer, start) {
+            metrics.record_push(samples.len(), start.elapsed());
+            metrics.record_buffers(self.squelch.samples.capacity(), self.frame.capacity());
+        }
+        self.conditioned = conditioned;
+        payloads
+    }
+
+    /// Reads the source in blocks of `block_length` samples up to its end, and returns the payloads of the frames in it.
+    ///
+    /// The burst being received at the end is [flushed](StreamDemodulator::flush).
+    ///
+    /// # Panics
+    /// If the block length is 0.
+    ///
+    /// # Errors
+    /// The error of the source, if it can not be read. The payloads decoded before it are lost.
+    pub fn run<S: SampleSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+        block_length: usize,
+    ) -> Result<Vec<Vec<u8>>, S::Error> {
+        if block_length == 0 {
+            panic!("Block length must be at least 1, but got 0");
+        }
+
+        let mut block = vec![0.0; block_length];
+        let mut payloads = Vec::new();
+        loop {
+            let count = source.read(&mut block)?;
+            if count == 0 {
+                break;
+            }
+            payloads.extend(self.push(&block[..count]));
+        }
+        payloads.extend(self.flush());
+        Ok(payloads)
+    }
+
+    /// Pushes a block of samples like [push](StreamDemodulator::push), and appends it to the capture,
+    /// with a [marker](crate::io::capture::Marker) after it for every sync acquired or lost and every frame decoded or failed in it.
+    ///
+    /// See the [capture](crate::io::capture) module for an example.
+    ///
+    /// # Errors
+    /// The error of the capture, if it can not be written. The samples have been pushed nevertheless.
+    pub fn push_recorded<W: Write>(
+        &mut self,
+        samples: &[f32],
+        capture: &mut CaptureWriter<W>,
+    ) -> Result<Vec<Vec<u8>>, CaptureError> {
+        let before = self.stats();
+        let payloads = self.push(samples);
+        let after = self.stats();
+
+        capture.write_samples(samples)?;
+        let frames_failed = |stats: &LinkStats| stats.frames_attempted - stats.frames_decoded;
+        for (kind, count) in [
+            (
+                MarkerKind::SyncAcquired,
+                after.sync_acquisitions - before.sync_acquisitions,
+            ),
+            (
+                MarkerKind::FrameDecoded,
+                after.frames_decoded - before.frames_decoded,
+            ),
+            (
+                MarkerKind::FrameFailed,
+                frames_failed(&after) - frames_failed(&before),
+            ),
+            (MarkerKind::SyncLost, after.sync_losses - before.sync_losses),
+        ] {
+            for _ in 0..count {
+                capture.write_marker(kind)?;
+            }
+        }
+        Ok(payloads)
+    }
+
+    /// Pushes the blocks of a capture as they were recorded, flushes the stream at its end,
+    /// and returns the payloads of the frames in it.
+    ///
+    /// A stream of the profile and the squelch the capture was recorded with decodes the same payloads
+    /// and ends with the same [stats](StreamDemodulator::stats) as the receiver that recorded it.
+    /// The markers of the capture are skipped.
+    ///
+    /// # Errors
+    /// The error of the capture, if it can not be read. The payloads decoded before it are lost.
+    pub fn replay<R: Read>(
+        &mut self,
+        capture: &mut CaptureReader<R>,
+    ) -> Result<Vec<Vec<u8>>, CaptureError> {
+        let mut payloads = Vec::new();
+        while let Some(chunk) = capture.read_chunk()? {
+            if let CaptureChunk::Samples { samples, .. } = chunk {
+                payloads.extend(self.push(&samples));
+            }
+        }
+        payloads.extend(self.flush());
+        Ok(payloads)
+    }
+
+    /// Decodes the burst being received at the end of the stream, and returns its payload.
+    ///
+    /// The squelch is closed afterwards, the stream can go on.
+    pub fn flush(&mut self) -> Option<Vec<u8>> {
+        #[cfg(feature = "perf")]
+        let start = self.timer.is_some().then(std::time::Instant::now);
+
+        if let Some(beacons) = &mut self.beacons {
+            let bursts: Vec<Burst> = beacons.squelch.flush().into_iter().collect();
+            beacons.decode_bursts(&bursts);
+        }
+        let burst = self.squelch.flush()?;
+        let payload = self.decode_burst(&burst);
+
+        #[cfg(feature = "perf")]
+        if let (Some(metrics), Some(start)) = (&mut self.timer, start) {
+            metrics.record_push(0, start.elapsed());
+            metrics.record_buffers(self.squelch.samples.capacity(), self.frame.capacity());
+        }
+        payload
+    }
+
+    /// Limits the bursts to a number of samples, by default enough for the longest frame,
+    /// see [get_max_frame_length](CodedOFDMDemodulator::get_max_frame_length), its pre-roll and its hang.
+    ///
+    /// A burst that reaches the limit is decoded as it is and the squelch closes, so a signal that never falls silent,
+    /// like a stuck transmitter or an interferer, holds no more samples than that. A receiver which knows
+    /// the longest payload of its link can limit a burst to the frame of it, which also bounds the time
+    /// spent trying to decode such a signal.
+    ///
+    /// # Panics
+    /// If the length is shorter than one symbol.
+    pub fn set_max_burst_length(&mut self, length: usize) {
+        let symbol_length = self.demodulator.get_symbol_length();
+        if length < symbol_length {
+            panic!(
+                "Maximum burst length must be at least the symbol length {}, but got {}",
+                symbol_length, length
+            );
+        }
+        self.squelch.max_length = length;
+    }
+
+    /// Returns the largest number of samples of a burst, see [set_max_burst_length](Self::set_max_burst_length).
+    pub fn get_max_burst_length(&self) -> usize {
+        self.squelch.max_length
+    }
+
+    /// Calls the hook with the points of every `every`th symbol of the frames decoded from now on,
+    /// or stops calling it with `None`.
+    ///
+    /// The symbols are those of the header and the payload, without the reference symbol of differential modulation,
+    /// counted over the whole stream: a snapshot is taken of the ones
+    /// whose [index](ConstellationSnapshot::symbol_index) is a multiple of `every`, after their frame has decoded.
+    /// The hook is called within the [push](Self::push) that decodes the frame, on the thread demodulating it,
+    /// and gets a snapshot copied out of the demodulator, which it must copy in turn to keep it.
+    /// It should not block or take long, or the demodulation waits for it: a display on another thread
+    /// hands the snapshots to it with [try_send](std::sync::mpsc::SyncSender::try_send),
+    /// dropping them while it is behind.
+    ///
+    /// # Panics
+    /// If `every` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::{Arc, mpsc};
+    ///
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::stream::StreamDemodulator;
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, CodingConfig::default()), 0.01, 500);
+    ///
+    /// // a display which takes up to 2 snapshots at a time
+    /// let (sender, receiver) = mpsc::sync_channel(2);
+    /// stream.set_snapshot_hook(Some(Arc::new(move |snapshot| {
+    ///     let _ = sender.try_send(snapshot.clone());
+    /// })), 2);
+    ///
+    /// let mut signal = modulator.encode_frame(&[0x5a; 100]);
+    /// signal.extend([0.0; 1000]);
+    /// assert_eq!(stream.push(&signal), [vec![0x5a; 100]]);
+    ///
+    /// // the symbols 0 and 2 of the frame, the later ones dropped
+    /// let snapshots: Vec<_> = receiver.try_iter().collect();
+    /// assert_eq!(snapshots.iter().map(|snapshot| snapshot.symbol_index).collect::<Vec<_>>(), [0, 2]);
+    /// assert!(snapshots[0].evm.unwrap().rms_db < -40.0);
+    /// ```
+    pub fn set_snapshot_hook(&mut self, hook: Option<SnapshotHook>, every: usize) {
+        if every == 0 {
+            panic!("Snapshot every must be at least 1, but got 0");
+        }
+        self.snapshots = hook.map(|hook| Snapshots {
+            hook,
+            every: every as u64,
+            snapshot: ConstellationSnapshot {
+                symbol_index: 0,
+                points: Vec::new(),
+                evm: None,
+            },
+        });
+    }
+
+    /// Hands every stage of every burst decoded from now on to the tap, see the [tap](crate::tap) module,
+    /// or stops with `None`, and returns the tap handed them so far.
+    ///
+    /// A burst is tapped at the offset its frame decoded at, or at the sample that opened the squelch
+    /// if it did not decode, by decoding it a second time with [decode_frame_with_tap](CodedOFDMDemodulator::decode_frame_with_tap)
+    /// within the [push](Self::push) that decodes it. Without a tap nothing of it runs.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::stream::StreamDemodulator;
+    /// use software_modem::tap::FileStageSink;
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, CodingConfig::default()), 0.01, 500);
+    ///
+    /// let directory = std::env::temp_dir().join("software_modem_doc_tap");
+    /// std::fs::create_dir_all(&directory).unwrap();
+    /// stream.set_debug_tap(Some(Box::new(FileStageSink::new(&directory))));
+    /// let mut signal = modulator.encode_frame(b"look into it");
+    /// signal.extend([0.0; 1000]);
+    /// stream.push(&signal);
+    /// stream.set_debug_tap(None);
+    ///
+    /// // the points of the header symbol of the first frame
+    /// let points = std::fs::read_to_string(directory.join("frame0000_symbol0000_points.csv")).unwrap();
+    /// assert_eq!(points.lines().count(), 1 + 48);
+    /// ```
+    pub fn set_debug_tap(
+        &mut self,
+        tap: Option<Box<dyn StageSink + Send>>,
+    ) -> Option<Box<dyn StageSink + Send>> {
+        core::mem::replace(&mut self.tap, tap)
+    }
+
+    /// Returns whether the squelch is open.
+    pub fn get_sync_state(&self) -> SyncState {
+        self.squelch.state
+    }
+
+    /// Returns the number of frames decoded with a valid CRC.
+    pub fn get_frames_decoded(&self) -> usize {
+        self.frames_decoded
+    }
+
+    /// Returns the number of bursts above the squelch level that did not decode, like noise or corrupted frames.
+    pub fn get_frames_failed(&self) -> usize {
+        self.frames_failed
+    }
+
+    /// Returns the counters of the stream since its creation or the last [reset](Self::reset_stats).
+    ///
+    /// They are always kept, unlike the [metrics](crate::perf) of the `perf` feature, which also time the stages.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::stream::StreamDemodulator;
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, CodingConfig::default()), 0.01, 500);
+    ///
+    /// let mut signal = vec![0.0; 1000];
+    /// signal.extend(modulator.encode_frame(&[0x5a; 100]));
+    /// signal.extend([0.0; 1000]);
+    /// stream.push(&signal);
+    ///
+    /// let stats = stream.stats();
+    /// assert_eq!((stats.frames_attempted, stats.frames_decoded, stats.bytes_delivered), (1, 1, 100));
+    /// assert_eq!((stats.sync_acquisitions, stats.sync_losses), (1, 1));
+    /// stream.reset_stats();
+    /// assert_eq!(stream.stats().frames_decoded, 0);
+    /// ```
+    pub fn stats(&self) -> LinkStats {
+        LinkStats {
+            sync_acquisitions: self.squelch.acquisitions,
+            sync_losses: self.squelch.losses,
+            samples_discarded: self.squelch.discarded,
+            ..self.stats
+        }
+    }
+
+    /// Sets the counters of the stream back to 0, without the [frames decoded](Self::get_frames_decoded)
+    /// and [failed](Self::get_frames_failed).
+    pub fn reset_stats(&mut self) {
+        self.stats = LinkStats::default();
+        self.squelch.acquisitions = 0;
+        self.squelch.losses = 0;
+        self.squelch.discarded = 0;
+        self.input.stats.clipped_samples = 0;
+    }
+
+    /// Measures the level of the input in windows of `window` samples, relative to a magnitude of `full_scale`,
+    /// by default windows of 4800 samples, a tenth of a second at 48 kHz, with a full scale of 1.
+    ///
+    /// Every [push](Self::push) adds its samples to the window being measured, and the [input stats](Self::input_stats)
+    /// are those of the last whole window. A change of the [health](InputHealth) of the input sends a `WARN` event
+    /// of `tracing` with the `tracing` feature, once for every change rather than for every block. Setting the meter
+    /// starts a new window, the clipped samples and the health of the last window are kept.
+    ///
+    /// # Panics
+    /// If the full scale is not positive and finite, or the window is 0.
+    pub fn set_input_meter(&mut self, full_scale: f32, window: usize) {
+        if !(full_scale > 0.0 && full_scale.is_finite()) {
+            panic!(
+                "Full scale must be positive and finite, but got {}",
+                full_scale
+            );
+        }
+        if window == 0 {
+            panic!("Window must be at least 1, but got 0");
+        }
+        let stats = self.input.stats;
+        self.input = InputMeter::new(full_scale, window);
+        self.input.stats = stats;
+    }
+
+    /// Returns the level of the input over the last window of the [meter](Self::set_input_meter),
+    /// with its [health](InputHealth) and the samples clipped since the creation of the stream or the last
+    /// [reset](Self::reset_stats).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+    /// use software_modem::frame::CodingConfig;
+    /// use software_modem::ofdm::OFDMConfig;
+    /// use software_modem::stream::{InputHealth, StreamDemodulator};
+    ///
+    /// let ofdm = OFDMConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix_length: 8,
+    ///     ..Default::default()
+    /// };
+    /// let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    /// let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, CodingConfig::default()), 0.01, 500);
+    /// stream.set_input_meter(1.0, 1000);
+    ///
+    /// // a frame with too much gain
+    /// let signal: Vec<f32> = modulator.encode_frame(&[0x5a; 100]).iter().map(|x| 20.0 * x).collect();
+    /// stream.push(&signal[..1000]);
+    /// let input = stream.input_stats();
+    /// assert_eq!(input.health, InputHealth::Clipping);
+    /// assert!(input.clipped_samples > 0 && input.peak_dbfs > 0.0);
+    /// ```
+    pub fn input_stats(&self) -> InputStats {
+        self.input.stats
+    }
+
+    /// Puts a [DcBlocker] with a cutoff of a tenth of the subcarrier spacing in front of the squelch
+    /// and the [AGC](Self::set_agc), against the offset of a cheap ADC, replacing the one before.
+    ///
+    /// A new stream has this one. The blocker passes the subcarriers within 0.05 dB and delays them
+    /// by at most about a sixtieth of the FFT length. Its phase turns the lowest subcarriers by up to a tenth of a radian,
+    /// which coherent demodulation, equalizing the magnitude of the pilots only, sees as an MER of about 35 dB
+    /// on a clean signal. It settles on the first samples of the stream, and [holds](DcBlocker::set_hold) its estimate
+    /// of the offset over samples above 4 times the squelch level, for at most the
+    /// [largest number of samples of a burst](Self::set_max_burst_length), so a frame does not leave it ringing.
+    /// [set_demodulator](Self::set_demodulator) makes it again for the subcarrier spacing of the new demodulator.
+    /// Without it, a DC offset above the squelch level keeps the squelch open.
+    pub fn enable_dc_blocker(&mut self) {
+        self.dc_blocker = Some(get_default_dc_blocker(&self.demodulator));
+        self.default_dc_blocker = true;
+    }
+
+    /// Puts a [DcBlocker] in front of the squelch and the [AGC](Self::set_agc), or takes it out with `None`,
+    /// replacing the one before, like the one of [enable_dc_blocker](Self::enable_dc_blocker) with another cutoff.
+    ///
+    /// Unlike that one, it is kept by [set_demodulator](Self::set_demodulator). Its hold is set from the squelch
+    /// on every push.
+    pub fn set_dc_blocker(&mut self, dc_blocker: Option<DcBlocker>) {
+        self.dc_blocker = dc_blocker;
+        self.default_dc_blocker = false;
+    }
+
+    /// Returns the [DcBlocker] in front of the squelch, if there is one.
+    pub fn get_dc_blocker(&self) -> Option<&DcBlocker> {
+        self.dc_blocker.as_ref()
+    }
+
+    /// Puts a [Deemphasis] in front of the squelch, after the [DC blocker](Self::set_dc_blocker)
+    /// and before the [AGC](Self::set_agc), or takes it out with `None`, replacing the one before.
+    ///
+    /// It undoes the [pre-emphasis](StreamModulator::set_preemphasis) of the same configuration over a channel
+    /// which is flat on its own, and is left out over one that rolls off as much.
+    pub fn set_deemphasis(&mut self, deemphasis: Option<Deemphasis>) {
+        self.deemphasis = deemphasis;
+    }
+
+    /// Returns the [Deemphasis] in front of the squelch, if there is one.
+    pub fn get_deemphasis(&self) -> Option<&Deemphasis> {
+        self.deemphasis.as_ref()
+    }
+
+    /// Puts an [Agc] in front of the squelch, after the [DC blocker](Self::set_dc_blocker) and the
+    /// [de-emphasis](Self::set_deemphasis), or takes it out with `None`, replacing the one before.
+    ///
+    /// The gain is held while the squelch is open, from the block after the one that opens it
+    /// to the block that closes it, so that it does not pump within a frame, and follows the level between frames.
+    /// The [squelch level](StreamDemodulator::new) applies to the samples after the gain, which brings the noise
+    /// between frames up to its [maximum](crate::dsp::AgcConfig::max_gain_db), so the level must lie above that.
+    /// The [input meter](Self::set_input_meter) measures the samples before the gain,
+    /// and the [input stats](Self::input_stats) hold the gain.
+    pub fn set_agc(&mut self, agc: Option<Agc>) {
+        if agc.is_none() {
+            self.input.stats.agc_gain_db = 0.0;
+        }
+        self.agc = agc;
+    }
+
+    /// Starts counting samples, symbols, frames and payload bytes, and timing the stages once every `sample_every` calls,
+    /// see the [perf](crate::perf) module. Enabling them again starts over.
+    ///
+    /// # Panics
+    /// If `sample_every` is 0.
+    #[cfg(feature = "perf")]
+    pub fn enable_metrics(&mut self, sample_every: u32) {
+        self.timer = Some(Metrics::new(sample_every));
+    }
+
+    /// Returns the metrics, if they are enabled.
+    #[cfg(feature = "perf")]
+    pub fn get_metrics(&self) -> Option<&Metrics> {
+        self.timer.as_ref()
+    }
+
+    fn decode_burst(&mut self, burst: &Burst) -> Option<Vec<u8>> {
+        trace_span!(
+            _span = DEBUG,
+            "decode_burst",
+            length = burst.samples.len(),
+            start = burst.start,
+        );
+        let frame = find_frame(
+            &self.demodulator,
+            burst,
+            &mut self.frame,
+            &mut self.points,
+            &mut self.fec,
+            &mut self.timer,
+        );
+        self.stats.frames_attempted += 1;
+        if let Some(tap) = &mut self.tap {
+            let offset = match &frame {
+                Ok((_, _, offset)) => *offset,
+                Err(_) => burst.start.min(self.demodulator.get_symbol_length()),
+            };
+            let _ = self.demodulator.decode_frame_with_tap(
+                &burst.samples[offset.min(burst.samples.len())..],
+                tap.as_mut(),
+            );
+        }
+        #[cfg(feature = "perf")]
+        if let Some(metrics) = &mut self.timer {
+            metrics.record_frame(
+                frame
+                    .as_ref()
+                    .ok()
+                    .map(|(payload, symbols, _)| (payload.len(), *symbols)),
+            );
+            if frame.is_ok() {
+                let decisions = self.demodulator.qam_modem().nearest_points(&self.points);
+                metrics.record_mer(&self.points, &decisions);
+            }
+        }
+        match &frame {
+            Ok((payload, symbols, _)) => {
+                trace_event!(
+                    DEBUG,
+                    "frame decoded",
+                    bytes = payload.len(),
+                    symbols = *symbols,
+                );
+                self.frames_decoded += 1;
+                self.stats.frames_decoded += 1;
+                self.stats.symbols_demodulated += *symbols as u64;
+                self.stats.bytes_delivered += payload.len() as u64;
+                if self.fec.corrected_bits > 0
+                    || self.fec.corrected_bytes.is_some_and(|bytes| bytes > 0)
+                {
+                    self.stats.frames_fec_corrected += 1;
+                }
+                self.take_snapshots();
+            }
+            Err(error) => {
+                trace_event!(DEBUG, "frame failed");
+                self.frames_failed += 1;
+                if *error == ModemError::CrcMismatch {
+                    self.stats.frames_crc_failed += 1;
+                }
+            }
+        }
+        frame.ok().map(|(payload, _, _)| payload)
+    }
+
+    /// Calls the snapshot hook with the symbols of the frame just decoded it takes, and counts them.
+    fn take_snapshots(&mut self) {
+        let subcarriers = self.demodulator.get_num_data_subcarriers();
+        let symbols = self.points.chunks_exact(subcarriers);
+        let first = self.symbols_decoded;
+        self.symbols_decoded += symbols.len() as u64;
+        let Some(snapshots) = &mut self.snapshots else {
+            return;
+        };
+
+        let modem = self.demodulator.qam_modem();
+        // the first index of the frame which is a multiple of `every`
+        let skip = (snapshots.every - first % snapshots.every) % snapshots.every;
+        for (index, points) in (first..)
+            .zip(symbols)
+            .skip(skip as usize)
+            .step_by(snapshots.every as usize)
+        {
+            let snapshot = &mut snapshots.snapshot;
+            snapshot.symbol_index = index;
+            snapshot.points.clear();
+            snapshot.points.extend_from_slice(points);
+            snapshot.evm =
+                DemodulationReport::from_points(points, &modem.nearest_points(points), None).evm;
+            (snapshots.hook)(snapshot);
+        }
+    }
+}
+
+/// A burst of signal cut out of the stream by the squelch.
+struct Burst {
+    samples: Vec<f32>,
+    /// Index of the sample that opened the squelch, after the pre-roll.
+    start: usize,
+}
+
+/// Cuts bursts of signal out of a stream of samples.
+struct Squelch {
+    level: f32,
+    hang: usize,
+    /// Samples kept before the sample that opens the squelch.
+    pre_roll: usize,
+    /// Samples of a burst at which the squelch closes, however loud the signal.
+    max_length: usize,
+    /// The last samples while idle, or the burst while receiving.
+    samples: VecDeque<f32>,
+    /// Samples below the level at the end of the burst.
+    quiet: usize,
+    /// Index of the sample that opened the squelch in the burst.
+    start: usize,
+    state: SyncState,
+    /// Times the squelch opened and closed, and samples it dropped while idle, see [LinkStats].
+    acquisitions: u64,
+    losses: u64,
+    discarded: u64,
+}
+
+impl Squelch {
+    fn new(level: f32, hang: usize, pre_roll: usize, max_length: usize) -> Self {
+        Squelch {
+            level,
+            hang,
+            pre_roll,
+            max_length,
+            samples: VecDeque::new(),
+            quiet: 0,
+            start: 0,
+            state: SyncState::Idle,
+            acquisitions: 0,
+            losses: 0,
+            discarded: 0,
+        }
+    }
+
+    /// Feeds samples and returns the bursts that ended, including the pre-roll and the quiet samples after them.
+    fn process(&mut self, input: &[f32]) -> Vec<Burst> {
+        let mut bursts = Vec::new();
+        for &sample in input {
+            let loud = sample.abs() > self.level;
+            self.samples.push_back(sample);
+            match self.state {
+                SyncState::Idle => {
+                    if loud {
+                        self.state = SyncState::Receiving;
+                        self.quiet = 0;
+                        self.start = self.samples.len() - 1;
+                        self.acquisitions += 1;
+                        trace_event!(DEBUG, "sync acquired", pre_roll = self.start);
+                    } else if self.samples.len() > self.pre_roll {
+                        self.samples.pop_front();
+                        self.discarded += 1;
+                    }
+                }
+                SyncState::Receiving => {
+                    self.quiet = if loud { 0 } else { self.quiet + 1 };
+                    if self.quiet >= self.hang || self.samples.len() >= self.max_length {
+                        bursts.push(self.take_burst());
+                        self.state = SyncState::Idle;
+                    }
+                }
+            }
+        }
+        bursts
+    }
+
+    /// Changes the pre-roll, dropping the samples kept before the new one.
+    fn set_pre_roll(&mut self, pre_roll: usize) {
+        let kept = match self.state {
+            SyncState::Idle => self.samples.len(),
+            SyncState::Receiving => self.start,
+        };
+        let excess = kept.saturating_sub(pre_roll);
+        self.samples.drain(..excess);
+        self.discarded += excess as u64;
+        self.start = self.start.saturating_sub(excess);
+        self.pre_roll = pre_roll;
+    }
+
+    /// Returns the burst being received, at the end of the stream.
+    fn flush(&mut self) -> Option<Burst> {
+        let burst = match self.state {
+            SyncState::Idle => None,
+            SyncState::Receiving => Some(self.take_burst()),
+        };
+        self.state = SyncState::Idle;
+        burst
+    }
+
+    fn take_burst(&mut self) -> Burst {
+        // a burst is cut when it ends before the hang, at the longest burst or the end of the stream
+        trace_event!(
+            DEBUG,
+            "sync lost",
+            length = self.samples.len(),
+            cut = self.quiet < self.hang,
+        );
+        self.losses += 1;
+        Burst {
+            samples: self.samples.drain(..).collect(),
+            start: self.start,
+        }
+    }
+}
+
+/// Decodes the frame of a burst, trying the offsets up to one symbol into it.
+///
+/// The frame most likely starts at the sample that opened the squelch or shortly before, so the offsets
+/// are tried from there back into the pre-roll first. Starting in the silence before the frame is not only slower,
+/// an all-zero header and payload would decode to an empty frame with a valid CRC.
+///
+/// Every offset is decoded from a copy of the burst in `frame`, transformed in place.
+/// Returns the payload with the number of symbols of the frame and the offset it starts at, whose points are left
+/// in `points` and the corrections of its FEC in `stats`. Without a frame, the error is [ModemError::CrcMismatch]
+/// if the header decoded at some offset, the error of the last offset otherwise.
+fn find_frame(
+    demodulator: &CodedOFDMDemodulator,
+    burst: &Burst,
+    frame: &mut Vec<f32>,
+    points: &mut Vec<Complex32>,
+    stats: &mut FecStats,
+    timer: &mut impl StageTimer,
+) -> Result<(Vec<u8>, usize, usize), ModemError> {
+    let last = demodulator.get_symbol_length().min(burst.samples.len());
+    let start = burst.start.min(last);
+    let mut error = ModemError::InvalidHeader;
+    for offset in (0..=start).rev().chain(start + 1..=last) {
+        frame.clear();
+        frame.extend_from_slice(demodulator.whole_symbols(&burst.samples[offset..]));
+        *stats = FecStats::default();
+        let result = demodulator.decode_frame_in_place(frame, points, stats, timer);
+        trace_event!(
+            TRACE,
+            "frame tried",
+            offset = offset,
+            error = result.as_ref().err().map(ModemError::name),
+        );
+        match result {
+            Ok((payload, symbols)) => return Ok((payload, symbols, offset)),
+            Err(result) if error != ModemError::CrcMismatch => error = result,
+            Err(_) => {}
+        }
+    }
+    Err(error)
+}
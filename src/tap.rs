@@ -0,0 +1,219 @@
+//! This module provides a tap on every stage of the receiver, to find out where a frame that does not decode goes wrong.
+//!
+//! A [StageSink] is handed the samples, bins, points and LLRs of every symbol of a frame as it is demodulated,
+//! and the bits the FEC decoded. A [stream](crate::stream::StreamDemodulator::set_debug_tap) hands it every burst
+//! it decodes, and [decode_frame_with_tap](crate::coded::CodedOFDMDemodulator::decode_frame_with_tap) a single frame.
+//! The [FileStageSink] writes every stage of every symbol to a file of its own, with the `std` feature.
+//!
+//! The tap is a path of its own: a receiver without one never calls it, and runs as fast as ever.
+//!
+//! # Example
+//! ```
+//! use realfft::num_complex::Complex32;
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::tap::StageSink;
+//!
+//! /// Keeps the points of every symbol.
+//! #[derive(Default)]
+//! struct Points(Vec<(usize, Vec<Complex32>)>);
+//!
+//! impl StageSink for Points {
+//!     fn start_frame(&mut self) {}
+//!     fn raw_samples(&mut self, _: usize, _: &[f32]) {}
+//!     fn corrected_samples(&mut self, _: usize, _: &[f32]) {}
+//!     fn fft_bins(&mut self, _: usize, _: &[Complex32]) {}
+//!     fn channel_estimate(&mut self, _: usize, _: &[Complex32]) {}
+//!     fn equalized_points(&mut self, symbol: usize, points: &[Complex32]) {
+//!         self.0.push((symbol, points.to_vec()));
+//!     }
+//!     fn llrs(&mut self, _: usize, _: &[f32]) {}
+//!     fn fec_bits(&mut self, _: &[u8]) {}
+//! }
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 8,
+//!     ..Default::default()
+//! };
+//! let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+//! let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+//!
+//! let mut points = Points::default();
+//! let frame = modulator.encode_frame(b"tapped");
+//! assert_eq!(demodulator.decode_frame_with_tap(&frame, &mut points), Ok(b"tapped".to_vec()));
+//! // the header symbol and the payload symbol, with 48 data subcarriers
+//! assert_eq!(points.0.len(), 2);
+//! assert!(points.0.iter().all(|(_, points)| points.len() == 48));
+//! ```
+
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use num_complex::Complex32;
+
+/// Receives every stage of the frames a receiver demodulates, see the [module](self).
+///
+/// The symbols are counted from the first of the frame, the reference symbol of differential modulation included,
+/// which has neither points nor LLRs. The stages of a frame come one after the other, every symbol of a stage
+/// before the next stage, and stop at the one the frame fails at: a frame whose header does not decode
+/// has no FEC bits.
+pub trait StageSink {
+    /// Starts a frame, before its stages.
+    fn start_frame(&mut self);
+    /// The samples of a symbol as received, with its cyclic prefix, after the
+    /// [DC blocker](crate::stream::StreamDemodulator::set_dc_blocker), the de-emphasis and the automatic gain control of a stream.
+    fn raw_samples(&mut self, symbol: usize, samples: &[f32]);
+    /// The samples of a symbol the FFT transforms, after the corrections of the receiver: moved back from the carrier
+    /// by the [downconverter](crate::dsp::Downconverter) and through the RX filter, as received without them.
+    fn corrected_samples(&mut self, symbol: usize, samples: &[f32]);
+    /// The bins of a symbol from DC to the Nyquist frequency, `fft_length / 2 + 1` of them.
+    fn fft_bins(&mut self, symbol: usize, bins: &[Complex32]);
+    /// The channel at the pilot subcarriers of a symbol, their bins over the pilot they carry.
+    fn channel_estimate(&mut self, symbol: usize, pilots: &[Complex32]);
+    /// The data subcarrier points of a symbol the bits are decided from, equalized or differentially demodulated.
+    fn equalized_points(&mut self, symbol: usize, points: &[Complex32]);
+    /// The LLRs of the bits of the points of a symbol, the hard decisions as LLRs without soft output.
+    fn llrs(&mut self, symbol: usize, llrs: &[f32]);
+    /// The bits of the payload of the frame out of the inner code, one bit a byte, before the outer code
+    /// and the descrambler, once its header has decoded.
+    fn fec_bits(&mut self, bits: &[u8]);
+}
+
+/// Writes every stage of every symbol to a file of its own in a directory.
+///
+/// The files are named after the frame, counted from 0 by the sink, the symbol and the stage,
+/// like `frame0002_symbol0013_points.csv`, and the bits of a frame `frame0002_fec_bits.u8`:
+///
+/// | Stage | File | Format |
+/// |---|---|---|
+/// | [raw samples](StageSink::raw_samples) | `raw.f32` | little endian `f32`, like a file sink of GNU Radio |
+/// | [corrected samples](StageSink::corrected_samples) | `corrected.f32` | little endian `f32` |
+/// | [FFT bins](StageSink::fft_bins) | `bins.csv` | a header `re,im` and a row for every bin |
+/// | [channel estimate](StageSink::channel_estimate) | `channel.csv` | a header `re,im` and a row for every pilot |
+/// | [equalized points](StageSink::equalized_points) | `points.csv` | a header `re,im` and a row for every point |
+/// | [LLRs](StageSink::llrs) | `llrs.csv` | a header `llr` and a row for every bit |
+/// | [FEC bits](StageSink::fec_bits) | `fec_bits.u8` | a byte of 0 or 1 for every bit |
+///
+/// A file that can not be written is skipped, and the first error is kept for [take_error](Self::take_error).
+#[cfg(feature = "std")]
+pub struct FileStageSink {
+    directory: PathBuf,
+    frames: usize,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl FileStageSink {
+    /// Creates a sink writing into the directory, which must exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        FileStageSink {
+            directory: directory.into(),
+            frames: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the directory the files are written into.
+    pub fn get_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns the number of frames started, the index of the next one.
+    pub fn get_frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the first error of writing a file since the last call, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Returns the path of the file of a stage of a symbol of the current frame, or of the frame without a symbol.
+    fn get_path(&self, symbol: Option<usize>, stage: &str) -> PathBuf {
+        let frame = self.frames.saturating_sub(1);
+        let name = match symbol {
+            Some(symbol) => format!("frame{frame:04}_symbol{symbol:04}_{stage}"),
+            None => format!("frame{frame:04}_{stage}"),
+        };
+        self.directory.join(name)
+    }
+
+    /// Writes a file of the current frame, keeping the error if it fails.
+    fn write(
+        &mut self,
+        symbol: Option<usize>,
+        stage: &str,
+        contents: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+    ) {
+        let path = self.get_path(symbol, stage);
+        let result = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            contents(&mut writer)?;
+            writer.flush()
+        });
+        if let Err(error) = result {
+            self.error.get_or_insert(error);
+        }
+    }
+
+    fn write_f32(&mut self, symbol: usize, stage: &str, values: &[f32]) {
+        self.write(Some(symbol), stage, |writer| {
+            values
+                .iter()
+                .try_for_each(|value| writer.write_all(&value.to_le_bytes()))
+        });
+    }
+
+    fn write_complex(&mut self, symbol: usize, stage: &str, values: &[Complex32]) {
+        self.write(Some(symbol), stage, |writer| {
+            writeln!(writer, "re,im")?;
+            values
+                .iter()
+                .try_for_each(|value| writeln!(writer, "{},{}", value.re, value.im))
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+impl StageSink for FileStageSink {
+    fn start_frame(&mut self) {
+        self.frames += 1;
+    }
+
+    fn raw_samples(&mut self, symbol: usize, samples: &[f32]) {
+        self.write_f32(symbol, "raw.f32", samples);
+    }
+
+    fn corrected_samples(&mut self, symbol: usize, samples: &[f32]) {
+        self.write_f32(symbol, "corrected.f32", samples);
+    }
+
+    fn fft_bins(&mut self, symbol: usize, bins: &[Complex32]) {
+        self.write_complex(symbol, "bins.csv", bins);
+    }
+
+    fn channel_estimate(&mut self, symbol: usize, pilots: &[Complex32]) {
+        self.write_complex(symbol, "channel.csv", pilots);
+    }
+
+    fn equalized_points(&mut self, symbol: usize, points: &[Complex32]) {
+        self.write_complex(symbol, "points.csv", points);
+    }
+
+    fn llrs(&mut self, symbol: usize, llrs: &[f32]) {
+        self.write(Some(symbol), "llrs.csv", |writer| {
+            writeln!(writer, "llr")?;
+            llrs.iter().try_for_each(|llr| writeln!(writer, "{}", llr))
+        });
+    }
+
+    fn fec_bits(&mut self, bits: &[u8]) {
+        self.write(None, "fec_bits.u8", |writer| writer.write_all(bits));
+    }
+}
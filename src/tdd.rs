@@ -0,0 +1,332 @@
+//! This module provides the turnaround of a half-duplex link, whose two ends take turns on one channel.
+//!
+//! The [HalfDuplexController] owns a [modulator](CodedOFDMModulator) and a [stream demodulator](StreamDemodulator),
+//! and [processes](HalfDuplexController::process) the input and output of the audio device one block at a time:
+//! it decodes the input while it listens, and plays the frames [requested](HalfDuplexController::request_transmit)
+//! into the output, one at a time, in the order they were requested.
+//!
+//! A frame is held while the squelch of the demodulator hears the carrier of the other end, and is only sent after
+//! the channel stayed quiet for a [guard time](TddConfig::guard_before). While it plays, the receive chain is muted,
+//! so the demodulator neither decodes nor levels itself on its own signal. After it, another
+//! [guard time](TddConfig::guard_after) leaves the other end the channel to answer before the next frame, and the
+//! input can stay muted for an [echo suppression](TddConfig::echo_suppression) window within it, while the
+//! tail of the frame rings in the channel.
+//!
+//! Like the [ARQ](crate::arq), the controller needs neither threads nor clocks: time only passes with the samples
+//! it processes, so it runs the same on an audio device, a file or a simulated channel.
+//! Every change of its [state](TddState) is kept as an [event](TddEvent) with the sample it happened at.
+//!
+//! # Example
+//! ```
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::stream::StreamDemodulator;
+//! use software_modem::tdd::{HalfDuplexController, TddConfig, TddState};
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! };
+//! let controller = |config| {
+//!     HalfDuplexController::new(
+//!         CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()),
+//!         StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default()), 0.01, 2400),
+//!         config,
+//!     )
+//! };
+//! let mut alice = controller(TddConfig::default());
+//! let mut bob = controller(TddConfig::default());
+//!
+//! // the output of each end is the input of the other, block by block
+//! alice.request_transmit(b"over");
+//! let (mut to_bob, mut to_alice) = (vec![0.0; 128], vec![0.0; 128]);
+//! let mut received = Vec::new();
+//! for _ in 0..200 {
+//!     let from_alice = to_bob.clone();
+//!     assert!(alice.process(&to_alice, &mut to_bob).is_empty());
+//!     received.extend(bob.process(&from_alice, &mut to_alice));
+//! }
+//! assert_eq!(received, [b"over".to_vec()]);
+//! assert_eq!(alice.get_state(), TddState::Idle);
+//! ```
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+use smart_default::SmartDefault;
+
+use crate::{
+    coded::CodedOFDMModulator,
+    stream::{StreamDemodulator, SyncState},
+};
+
+/// Configuration of a [HalfDuplexController], in samples.
+#[derive(SmartDefault, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TddConfig {
+    /// Samples the channel has to stay quiet before a frame is sent, counted again whenever the carrier of the
+    /// other end is heard.
+    #[default(2400)]
+    pub guard_before: usize,
+    /// Samples after a frame during which no other frame is sent, for the other end to answer.
+    #[default(2400)]
+    pub guard_after: usize,
+    /// Samples after a frame during which the input stays muted, at most the guard after it,
+    /// or `None` to listen again as soon as the frame ends.
+    pub echo_suppression: Option<usize>,
+}
+
+/// State of a [HalfDuplexController].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TddState {
+    /// Listening, with no frame to send.
+    #[default]
+    Idle,
+    /// Hearing the carrier of the other end, holding the frames to send.
+    Receiving,
+    /// Waiting for the channel to stay quiet for the guard time before sending a frame.
+    GuardBefore,
+    /// Sending a frame, with the receive chain muted.
+    Transmitting,
+    /// Waiting for the guard time after a frame before sending the next one, muted during the echo suppression.
+    GuardAfter,
+}
+
+impl TddState {
+    /// Returns the name of the variant, the value of the fields of the `tracing` events.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            TddState::Idle => "Idle",
+            TddState::Receiving => "Receiving",
+            TddState::GuardBefore => "GuardBefore",
+            TddState::Transmitting => "Transmitting",
+            TddState::GuardAfter => "GuardAfter",
+        }
+    }
+}
+
+/// A change of the state of a [HalfDuplexController].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TddEvent {
+    /// Index of the sample processed by the controller at which the state changed.
+    pub sample: u64,
+    pub from: TddState,
+    pub to: TddState,
+}
+
+/// Coordinates the modulator and the demodulator of one end of a half-duplex link, see the [module](self).
+pub struct HalfDuplexController {
+    modulator: CodedOFDMModulator,
+    demodulator: StreamDemodulator,
+    config: TddConfig,
+    state: TddState,
+    /// Samples left of the guard time of the state.
+    timer: usize,
+    /// Payloads requested and not sent yet.
+    queue: VecDeque<Vec<u8>>,
+    /// The samples of the frame being sent and the next one to play.
+    frame: Vec<f32>,
+    position: usize,
+    /// Samples left of the echo suppression after the last frame.
+    echo: usize,
+    /// Samples processed since the creation of the controller.
+    sample: u64,
+    events: Vec<TddEvent>,
+}
+
+impl HalfDuplexController {
+    /// Creates a controller, idle with nothing to send.
+    ///
+    /// # Panics
+    /// If the echo suppression is longer than the guard time after a frame.
+    pub fn new(
+        modulator: CodedOFDMModulator,
+        demodulator: StreamDemodulator,
+        config: TddConfig,
+    ) -> Self {
+        if let Some(echo_suppression) = config.echo_suppression {
+            assert!(
+                echo_suppression <= config.guard_after,
+                "Echo suppression must be at most the guard after of {} samples, but got {}",
+                config.guard_after,
+                echo_suppression
+            );
+        }
+        HalfDuplexController {
+            modulator,
+            demodulator,
+            config,
+            state: TddState::Idle,
+            timer: 0,
+            queue: VecDeque::new(),
+            frame: Vec::new(),
+            position: 0,
+            echo: 0,
+            sample: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Queues the payload, sent as a frame as soon as the channel is free.
+    ///
+    /// # Panics
+    /// If the payload is longer than 65535 bytes.
+    pub fn request_transmit(&mut self, payload: &[u8]) {
+        assert!(
+            payload.len() <= u16::MAX as usize,
+            "Payload must be at most {} bytes, but got {} bytes",
+            u16::MAX,
+            payload.len()
+        );
+        self.queue.push_back(payload.to_vec());
+    }
+
+    /// Processes a block of the input, and fills the block of the output of the same length with the frame
+    /// being sent or silence. Returns the payloads decoded from the input.
+    ///
+    /// # Panics
+    /// If the output is not as long as the input.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> Vec<Vec<u8>> {
+        assert!(
+            output.len() == input.len(),
+            "Output length must be {}, but got {}",
+            input.len(),
+            output.len()
+        );
+
+        let mut payloads = Vec::new();
+        let mut offset = 0;
+        loop {
+            // the changes which take no samples, also at the end of the block
+            match self.state {
+                TddState::Idle if !self.queue.is_empty() => {
+                    self.timer = self.config.guard_before;
+                    self.set_state(TddState::GuardBefore);
+                    continue;
+                }
+                TddState::GuardBefore if self.timer == 0 => {
+                    let payload = self.queue.pop_front().unwrap();
+                    self.frame = self.modulator.encode_frame(&payload);
+                    self.position = 0;
+                    self.set_state(TddState::Transmitting);
+                    continue;
+                }
+                TddState::GuardAfter if self.timer == 0 => {
+                    self.set_state(TddState::Idle);
+                    continue;
+                }
+                _ => {}
+            }
+            if offset == input.len() {
+                break;
+            }
+
+            let remaining = input.len() - offset;
+            if self.state == TddState::Transmitting {
+                let length = remaining.min(self.frame.len() - self.position);
+                output[offset..offset + length]
+                    .copy_from_slice(&self.frame[self.position..self.position + length]);
+                self.position += length;
+                self.advance(length, &mut offset);
+                if self.position == self.frame.len() {
+                    self.frame = Vec::new();
+                    self.timer = self.config.guard_after;
+                    self.echo = self.config.echo_suppression.unwrap_or(0);
+                    self.set_state(TddState::GuardAfter);
+                }
+                continue;
+            }
+
+            // listening, up to the end of the guard time or of the echo suppression
+            let mut length = remaining;
+            if matches!(self.state, TddState::GuardBefore | TddState::GuardAfter) {
+                length = length.min(self.timer);
+            }
+            if self.echo > 0 {
+                length = length.min(self.echo);
+                self.echo -= length;
+            } else {
+                payloads.extend(self.demodulator.push(&input[offset..offset + length]));
+            }
+            output[offset..offset + length].fill(0.0);
+            if matches!(self.state, TddState::GuardBefore | TddState::GuardAfter) {
+                self.timer -= length;
+            }
+            self.advance(length, &mut offset);
+
+            let carrier = self.demodulator.get_sync_state() == SyncState::Receiving;
+            match self.state {
+                TddState::Idle | TddState::GuardBefore | TddState::GuardAfter if carrier => {
+                    self.set_state(TddState::Receiving)
+                }
+                TddState::Receiving if !carrier => self.set_state(TddState::Idle),
+                _ => {}
+            }
+        }
+        payloads
+    }
+
+    fn advance(&mut self, length: usize, offset: &mut usize) {
+        *offset += length;
+        self.sample += length as u64;
+    }
+
+    fn set_state(&mut self, state: TddState) {
+        trace_event!(
+            DEBUG,
+            "half-duplex state",
+            from = self.state.name(),
+            to = state.name(),
+            sample = self.sample,
+        );
+        self.events.push(TddEvent {
+            sample: self.sample,
+            from: self.state,
+            to: state,
+        });
+        self.state = state;
+    }
+
+    /// Returns the changes of state since the last call.
+    pub fn take_events(&mut self) -> Vec<TddEvent> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Returns the state of the controller.
+    pub fn get_state(&self) -> TddState {
+        self.state
+    }
+
+    /// Returns the number of payloads requested and not sent yet, without the frame being sent.
+    pub fn get_pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether the input is muted, while a frame is sent and during the echo suppression after it.
+    pub fn is_receive_muted(&self) -> bool {
+        self.state == TddState::Transmitting || self.echo > 0
+    }
+
+    /// Returns the number of samples processed since the creation of the controller.
+    pub fn get_samples_processed(&self) -> u64 {
+        self.sample
+    }
+
+    /// Returns the configuration of the controller.
+    pub fn get_config(&self) -> &TddConfig {
+        &self.config
+    }
+
+    /// Returns the demodulator, for its counters.
+    pub fn get_demodulator(&self) -> &StreamDemodulator {
+        &self.demodulator
+    }
+
+    /// Returns the demodulator, to configure it.
+    pub fn get_demodulator_mut(&mut self) -> &mut StreamDemodulator {
+        &mut self.demodulator
+    }
+}
@@ -0,0 +1,289 @@
+//! This module simulates links over the [channels](crate::channel), for the error rate curves of a configuration.
+//!
+//! [ber_sweep] sends frames over a channel at a range of SNRs and measures the bit and frame error rates at every SNR,
+//! the waterfall curve of a modem and its codes. The [SweepConfig] chooses the modem, the channel,
+//! and how many frames a point needs, a fixed number or enough for a number of bit errors.
+//!
+//! The [property] module holds the configurations and the properties of the modem, like the round trip of a frame,
+//! which the tests check with `proptest` over configurations drawn at random,
+//! the [fuzz] module holds the entry points of the fuzz targets of the receiver,
+//! and the [golden] module keeps reference waveforms, to notice any change of the samples of the modulator.
+
+pub mod fuzz;
+pub mod golden;
+pub mod property;
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use smart_default::SmartDefault;
+
+use crate::{
+    channel::{AwgnChannel, Channel, ChannelChain},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::{CodingConfig, FrameDecoder, FrameEncoder},
+    metrics::BerMeter,
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    rng::SimulationRng,
+};
+
+/// Makes the channel of one frame from the SNR in dB and a seed, see [SweepConfig::channel].
+pub type ChannelFactory = Arc<dyn Fn(f32, u64) -> ChannelChain + Send + Sync>;
+
+/// When a point of a [ber_sweep] has enough frames.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SweepStop {
+    /// The same number of frames at every SNR.
+    Frames(usize),
+    /// Frames until the bit errors reach a number, at most `max_frames` of them.
+    ///
+    /// A number of errors bounds the relative width of the confidence interval at every SNR,
+    /// about ±20 % at 95 % for 100 errors, while the frames at high SNRs stay bounded.
+    BitErrors {
+        bit_errors: usize,
+        max_frames: usize,
+    },
+}
+
+impl Default for SweepStop {
+    fn default() -> Self {
+        SweepStop::Frames(100)
+    }
+}
+
+/// Configuration of a [ber_sweep].
+#[derive(SmartDefault, Clone)]
+pub struct SweepConfig {
+    /// The configuration of the modem.
+    pub ofdm: OFDMConfig,
+    /// The codes of the frames of a [CodedOFDMModulator], `None` for the uncoded frames of a [FrameEncoder].
+    ///
+    /// A coded frame which does not decode counts as lost, with all of its bits wrong.
+    pub coding: Option<CodingConfig>,
+    /// The bytes of payload in every frame.
+    #[default(400)]
+    pub payload_length: usize,
+    /// Makes the channel of every frame from its SNR in dB and a seed, by default an [AwgnChannel]
+    /// whose SNR is relative to the power of the frame.
+    #[default(Arc::new(|snr_db, seed| ChannelChain::new().with(AwgnChannel::new(snr_db, seed))))]
+    pub channel: ChannelFactory,
+    /// The lowest SNR in dB.
+    pub snr_start_db: f32,
+    /// The highest SNR in dB, the last point unless it is not a whole number of steps above the lowest.
+    #[default(20.0)]
+    pub snr_stop_db: f32,
+    /// The step between the SNRs in dB.
+    #[default(2.0)]
+    pub snr_step_db: f32,
+    /// When a point has enough frames.
+    pub stop: SweepStop,
+    /// The confidence level of the intervals of the bit error rates.
+    #[default(0.95)]
+    pub confidence: f64,
+    /// The number of threads simulating the points, every point runs on one thread.
+    #[default(1)]
+    pub threads: usize,
+    /// The seed of the payloads and the channels, which decides the result together with the configuration.
+    ///
+    /// It is the master seed of a [SimulationRng], which derives the seed of every frame from the point and the frame.
+    pub seed: u64,
+}
+
+/// The error rates at one SNR of a [ber_sweep].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BerPoint {
+    /// The SNR in dB.
+    pub snr_db: f32,
+    /// The ratio of wrong bits.
+    pub bit_error_rate: f64,
+    /// The ratio of frames with wrong bits, or lost.
+    pub frame_error_rate: f64,
+    /// The Wilson score interval of the bit error rate, see [BerMeter::confidence_interval].
+    pub confidence_interval: (f64, f64),
+    /// The counts of the frames and bits.
+    pub meter: BerMeter,
+}
+
+/// Sends frames over the channel at every SNR of the configuration and returns the error rates, from the lowest SNR up.
+///
+/// Every frame has its own payload and channel, made from the seed, the point and the frame,
+/// so the result does not depend on the number of threads.
+///
+/// # Panics
+/// If the SNRs are not finite, the step is not positive or the highest SNR is below the lowest,
+/// a number of the [stop](SweepConfig::stop) is zero, the payload is empty or the number of threads is zero,
+/// or the modem or the coding configuration is invalid.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::testing::{SweepConfig, SweepStop, ber_sweep};
+///
+/// let points = ber_sweep(&SweepConfig {
+///     ofdm: OFDMConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix_length: 8,
+///         ..Default::default()
+///     },
+///     snr_start_db: 8.0,
+///     snr_stop_db: 16.0,
+///     snr_step_db: 4.0,
+///     stop: SweepStop::Frames(10),
+///     threads: 2,
+///     ..Default::default()
+/// });
+///
+/// // uncoded QAM-16 falls over the SNRs
+/// assert_eq!(points.iter().map(|point| point.snr_db).collect::<Vec<_>>(), [8.0, 12.0, 16.0]);
+/// assert!(points.windows(2).all(|pair| pair[1].bit_error_rate < pair[0].bit_error_rate));
+/// for point in &points {
+///     let (low, high) = point.confidence_interval;
+///     assert!(low < point.bit_error_rate && point.bit_error_rate < high);
+/// }
+/// ```
+pub fn ber_sweep(config: &SweepConfig) -> Vec<BerPoint> {
+    let snrs = snr_points(config);
+    match config.stop {
+        SweepStop::Frames(0) => panic!("Number of frames must be positive, but got 0"),
+        SweepStop::BitErrors { bit_errors: 0, .. } => {
+            panic!("Number of bit errors must be positive, but got 0")
+        }
+        SweepStop::BitErrors { max_frames: 0, .. } => {
+            panic!("Maximum number of frames must be positive, but got 0")
+        }
+        _ => {}
+    }
+    if config.payload_length == 0 {
+        panic!("Payload length must be positive, but got 0");
+    }
+    if config.threads == 0 {
+        panic!("Number of threads must be positive, but got 0");
+    }
+
+    let next = AtomicUsize::new(0);
+    let points = Mutex::new(vec![None; snrs.len()]);
+    let worker = || {
+        let link = Link::new(config);
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(&snr_db) = snrs.get(index) else {
+                break;
+            };
+            let point = link.run(config, index, snr_db);
+            points.lock().unwrap()[index] = Some(point);
+        }
+    };
+    if config.threads == 1 {
+        worker();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..config.threads.min(snrs.len()) {
+                scope.spawn(worker);
+            }
+        });
+    }
+
+    points
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+/// Returns the SNRs of the points of a sweep.
+fn snr_points(config: &SweepConfig) -> Vec<f32> {
+    let (start, stop, step) = (config.snr_start_db, config.snr_stop_db, config.snr_step_db);
+    if !(start.is_finite() && stop.is_finite()) {
+        panic!("SNRs must be finite, but got {} to {}", start, stop);
+    }
+    if !(step > 0.0 && step.is_finite()) {
+        panic!("SNR step must be positive and finite, but got {}", step);
+    }
+    if stop < start {
+        panic!(
+            "Highest SNR must be at least the lowest one {}, but got {}",
+            start, stop
+        );
+    }
+
+    // with a margin against rounding, so the highest SNR is not lost to it
+    let steps = ((stop - start) / step + 1e-3).floor() as usize;
+    (0..=steps).map(|i| start + i as f32 * step).collect()
+}
+
+/// The transmitter and the receiver of the frames of a sweep.
+enum Link {
+    Uncoded(Box<(FrameEncoder, FrameDecoder)>),
+    Coded(Box<(CodedOFDMModulator, CodedOFDMDemodulator)>),
+}
+
+impl Link {
+    fn new(config: &SweepConfig) -> Self {
+        match &config.coding {
+            None => Link::Uncoded(Box::new((
+                FrameEncoder::new(OFDMModulator::new((&config.ofdm).into())),
+                FrameDecoder::new(OFDMDemodulator::new((&config.ofdm).into())),
+            ))),
+            Some(coding) => Link::Coded(Box::new((
+                CodedOFDMModulator::new(config.ofdm.clone(), coding.clone()),
+                CodedOFDMDemodulator::new(config.ofdm.clone(), coding.clone()),
+            ))),
+        }
+    }
+
+    /// Sends frames at the SNR of a point until they are enough, and counts their errors.
+    fn run(&self, config: &SweepConfig, index: usize, snr_db: f32) -> BerPoint {
+        let mut meter = BerMeter::new();
+        let mut payload = vec![0; config.payload_length];
+        loop {
+            let enough = match config.stop {
+                SweepStop::Frames(frames) => meter.num_frames() >= frames,
+                SweepStop::BitErrors {
+                    bit_errors,
+                    max_frames,
+                } => meter.bit_errors() >= bit_errors || meter.num_frames() >= max_frames,
+            };
+            if enough {
+                break;
+            }
+
+            let seed = SimulationRng::new(config.seed)
+                .seed_at("frame", (index as u64) << 32 | meter.num_frames() as u64);
+            let key = (seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as u32;
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte = (key.wrapping_add(i as u32).wrapping_mul(2654435761) >> 11) as u8;
+            }
+
+            let mut samples = match self {
+                Link::Uncoded(uncoded) => uncoded.0.encode(&payload),
+                Link::Coded(coded) => coded.0.encode_frame(&payload),
+            };
+            (config.channel)(snr_db, seed).apply(&mut samples);
+            match self {
+                Link::Uncoded(uncoded) => {
+                    meter.add_frame(&payload, &uncoded.1.decode(&samples)[..payload.len()]);
+                }
+                Link::Coded(coded) => match coded.1.decode_frame(&samples) {
+                    Ok(decoded) => {
+                        meter.add_frame(&payload, &decoded);
+                    }
+                    Err(_) => meter.add_lost_frame(8 * payload.len()),
+                },
+            }
+        }
+
+        BerPoint {
+            snr_db,
+            bit_error_rate: meter.bit_error_rate(),
+            frame_error_rate: meter.frame_error_rate(),
+            confidence_interval: meter.confidence_interval(config.confidence),
+            meter,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! This module holds the entry points of the fuzz targets in `fuzz/`, which turn arbitrary bytes into the inputs
+//! of the receiver: a receiver is exposed to whatever its input carries, so no input may make it panic,
+//! index out of bounds or allocate without a bound.
+//!
+//! Every entry point takes the bytes of one fuzz input. The first byte picks a configuration or a decoder
+//! from a fixed list, the following bytes are the samples, as little-endian `f32`, or the bits and LLRs.
+//! The same entry points run in the regression tests on the interesting inputs, like NaN patterns,
+//! silence and full-scale square waves, so a crash found by the fuzzer stays fixed without it.
+//!
+//! Run a target with [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz) from `fuzz/`, `cargo +nightly fuzz run stream`,
+//! which builds it with coverage and the AddressSanitizer. `cargo build` in `fuzz/` builds the targets on stable,
+//! without either, to check that they compile.
+
+use crate::{
+    coded::CodedOFDMDemodulator,
+    dsp::{FirFilter, Passband},
+    fec::{
+        FecScheme,
+        convolutional::{ConvolutionalCode, ViterbiDecoder},
+        hamming::HammingCode,
+        puncture::CodeRate,
+        repetition::RepetitionCode,
+        rs::ReedSolomon,
+    },
+    frame::{CodingConfig, FrameDecoder, HeaderCode, Interleaving},
+    interleaver::ConvolutionalInterleaver,
+    ofdm::{OFDMConfig, SlmConfig, SlmSignaling, demodulator::OFDMDemodulator},
+    stream::StreamDemodulator,
+};
+
+/// The longest input the sample targets read, which keeps every run short.
+const MAX_SAMPLES: usize = 1 << 16;
+
+/// Returns the configurations the targets pick from with their first byte, covering the stages of the receiver.
+pub fn configs() -> Vec<(OFDMConfig, CodingConfig)> {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    vec![
+        (ofdm.clone(), CodingConfig::default()),
+        (
+            OFDMConfig {
+                differential_time: true,
+                roll_off: 4,
+                ..ofdm.clone()
+            },
+            CodingConfig {
+                reed_solomon: true,
+                ..Default::default()
+            },
+        ),
+        (
+            OFDMConfig {
+                soft_output: false,
+                null_dc: false,
+                masked_subcarriers: vec![10, 11, 12],
+                ..ofdm.clone()
+            },
+            CodingConfig {
+                header_code: HeaderCode::Hamming(HammingCode::ExtendedHamming84),
+                scheme: FecScheme::Repetition(3),
+                interleaving: Interleaving::None,
+                ..Default::default()
+            },
+        ),
+        (
+            OFDMConfig {
+                oversampling: 2,
+                guard_subcarriers_low: 4,
+                guard_subcarriers_high: 8,
+                rx_filter: Some(FirFilter::lowpass(0.25, 31)),
+                passband: Some(Passband {
+                    sample_rate: 48000.0,
+                    carrier_hz: 12000.0,
+                }),
+                ..ofdm.clone()
+            },
+            CodingConfig {
+                burst_interleaver: Some(ConvolutionalInterleaver::new(4, 3)),
+                erasure_threshold: Some(3.0),
+                ..Default::default()
+            },
+        ),
+        (
+            OFDMConfig {
+                slm: Some(SlmConfig {
+                    candidates: 4,
+                    signaling: SlmSignaling::Blind,
+                }),
+                ..ofdm.clone()
+            },
+            CodingConfig {
+                scheme: FecScheme::Convolutional(CodeRate::ThreeQuarters),
+                scrambler: None,
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// Splits an input into the configuration its first byte picks and the rest.
+fn pick_config(data: &[u8]) -> Option<((OFDMConfig, CodingConfig), &[u8])> {
+    let (&first, rest) = data.split_first()?;
+    let mut configs = configs();
+    let index = first as usize % configs.len();
+    Some((configs.swap_remove(index), rest))
+}
+
+/// Returns the little-endian `f32` values of the bytes, at most 65536 of them.
+pub fn to_samples(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(4)
+        .take(MAX_SAMPLES)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect()
+}
+
+/// Returns the bytes of little-endian `f32` values, the inverse of [to_samples].
+pub fn from_samples(samples: &[f32]) -> Vec<u8> {
+    samples.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+/// Demodulates one symbol of the samples, repeated or cut to the symbol length, with every symbol demodulator.
+pub fn demodulate_symbol(data: &[u8]) {
+    let Some(((ofdm, _), rest)) = pick_config(data) else {
+        return;
+    };
+    let demodulator = OFDMDemodulator::new((&ofdm).into());
+    let samples = to_samples(rest);
+    let symbol: Vec<f32> = if samples.is_empty() {
+        vec![0.0; demodulator.get_symbol_length()]
+    } else {
+        samples
+            .iter()
+            .cycle()
+            .take(demodulator.get_symbol_length())
+            .copied()
+            .collect()
+    };
+
+    demodulator.demodulate_symbol_from_buffer(&symbol);
+    demodulator.demodulate_symbol_with_points(&symbol);
+    demodulator.demodulate_symbol_with_evm(&symbol);
+    if ofdm.soft_output {
+        demodulator.demodulate_symbol_soft_from_buffer(&symbol);
+    }
+}
+
+/// Pushes the samples into a stream demodulator in blocks.
+///
+/// The second byte is the number of the bytes after it giving the lengths of the blocks, each one plus 1,
+/// which repeat over the stream.
+pub fn stream(data: &[u8]) {
+    let Some(((ofdm, coding), rest)) = pick_config(data) else {
+        return;
+    };
+    let Some((&num_lengths, rest)) = rest.split_first() else {
+        return;
+    };
+    let (lengths, rest) = rest.split_at((num_lengths as usize).min(rest.len()));
+    let block_lengths: Vec<usize> = if lengths.is_empty() {
+        vec![256]
+    } else {
+        lengths.iter().map(|&length| length as usize + 1).collect()
+    };
+
+    let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, 200);
+    let samples = to_samples(rest);
+    let mut start = 0;
+    for length in block_lengths.iter().cycle() {
+        if start >= samples.len() {
+            break;
+        }
+        let end = (start + length).min(samples.len());
+        stream.push(&samples[start..end]);
+        start = end;
+    }
+    stream.flush();
+}
+
+/// Decodes the samples as a coded frame, and their whole symbols as an uncoded frame.
+pub fn frame_decoder(data: &[u8]) {
+    let Some(((ofdm, coding), rest)) = pick_config(data) else {
+        return;
+    };
+    let samples = to_samples(rest);
+    let coded = CodedOFDMDemodulator::new(ofdm.clone(), coding);
+    // any error is fine, only a panic is not
+    let _ = coded.decode_frame(&samples);
+
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&ofdm).into()));
+    let (symbol_length, roll_off) = (decoder.get_symbol_length(), ofdm.roll_off as usize);
+    if samples.len() >= roll_off {
+        let frame =
+            &samples[..(samples.len() - roll_off) / symbol_length * symbol_length + roll_off];
+        decoder.decode(frame);
+        decoder.get_subcarrier_snr(frame);
+        decoder.get_symbol_evm(frame);
+    }
+}
+
+/// Decodes the bytes with the FEC decoder the first byte picks, as bits, as `f32` LLRs or as a code word.
+pub fn fec_decoders(data: &[u8]) {
+    let Some((&first, rest)) = data.split_first() else {
+        return;
+    };
+    let code = ConvolutionalCode::k7_rate_half();
+    let llrs = to_samples(rest);
+    let bits: Vec<u8> = rest.iter().map(|byte| byte & 1).collect();
+    match first % 8 {
+        0 => {
+            code.decode_soft(&llrs);
+        }
+        1 => {
+            code.decode(&bits);
+        }
+        2 => {
+            // the LLRs in blocks as long as the bytes say
+            let mut decoder = ViterbiDecoder::new(code.clone(), 5 * 7);
+            let mut llrs = llrs.as_slice();
+            for &length in rest.iter().cycle().take(llrs.len()) {
+                if llrs.is_empty() {
+                    break;
+                }
+                let (block, remaining) = llrs.split_at((length as usize).min(llrs.len()));
+                decoder.push_soft(block);
+                llrs = remaining;
+            }
+            decoder.finish();
+        }
+        3 => {
+            for rate in [
+                CodeRate::TwoThirds,
+                CodeRate::ThreeQuarters,
+                CodeRate::FiveSixths,
+            ] {
+                let coded_length = 2 * llrs.len();
+                code.decode_soft(&rate.depuncture(&llrs, coded_length));
+            }
+        }
+        4 => {
+            let repetition = RepetitionCode::new(1 + first as usize / 8 % 15);
+            repetition.decode_soft(&llrs);
+            repetition.decode(&bits);
+        }
+        5 => {
+            for hamming in [HammingCode::Hamming74, HammingCode::ExtendedHamming84] {
+                let length = bits.len() - bits.len() % hamming.codeword_length();
+                let _ = hamming.decode(&bits[..length]);
+            }
+        }
+        6 => {
+            // a shortened code of the length the second byte says, then the code word and the erasures
+            let Some((&k, rest)) = rest.split_first() else {
+                return;
+            };
+            let rs = ReedSolomon::rs255_223().shortened(1 + k as usize % 223);
+            let mut codeword = rest[..rest.len().min(rs.n())].to_vec();
+            codeword.resize(rs.n(), 0);
+            let erasures: Vec<usize> = rest
+                .iter()
+                .skip(rs.n())
+                .map(|&position| position as usize % rs.n())
+                .collect();
+            let _ = rs.decode(&codeword);
+            let _ = rs.decode_with_erasures(&codeword, &erasures);
+        }
+        _ => {
+            #[cfg(feature = "ldpc")]
+            {
+                use crate::fec::ldpc::{LdpcCode, LdpcDecoder, LdpcDecoderConfig};
+                let code = LdpcCode::wifi_648_rate_half();
+                let mut llrs = llrs;
+                llrs.resize(code.n(), 0.0);
+                LdpcDecoder::new(code, LdpcDecoderConfig::default()).decode(&llrs);
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! This module keeps reference waveforms, the samples the modulator made for a payload and a configuration when they were stored,
+//! so a change of the samples, like another normalization of a constellation, is noticed and made deliberately.
+//!
+//! A [Fixture] holds a name, the [Waveform] with its configuration, the payload and the samples.
+//! A fixture file holds fixtures serialized with [to_bytes], configurations included, so it describes itself.
+//! [check_fixtures] compares fixtures made by the current modulator with a stored file and returns the first difference
+//! beyond a tolerance; with the `WRITE_GOLDEN` environment variable set, it stores them instead.
+//!
+//! [reference_fixtures] are the fixtures of the crate, stored in `tests/data/golden.bin`:
+//! after an intended change of the samples, `WRITE_GOLDEN=1 cargo test --test golden` stores the new ones.
+
+use std::{fmt::Display, io, path::Path};
+
+use crate::{
+    bits::ConfigReader,
+    coded::CodedOFDMModulator,
+    error::ModemError,
+    frame::{CodingConfig, FrameEncoder},
+    ofdm::{OFDMConfig, modulator::OFDMModulator},
+    qam::QAMOrder,
+};
+
+/// The version of the serialized fixtures, see [to_bytes].
+const GOLDEN_VERSION: u8 = 1;
+
+/// The environment variable which makes [check_fixtures] store the fixtures rather than compare them.
+pub const WRITE_GOLDEN: &str = "WRITE_GOLDEN";
+
+/// How the samples of a fixture are made from its payload.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Waveform {
+    /// One symbol, see [modulate_buffer_as_symbol](crate::ofdm::modulator::GenericOFDMModulator::modulate_buffer_as_symbol),
+    /// whose payload must fill it.
+    Symbol(OFDMConfig),
+    /// An uncoded frame, see [FrameEncoder::encode].
+    Frame(OFDMConfig),
+    /// A coded frame, see [CodedOFDMModulator::encode_frame].
+    CodedFrame(OFDMConfig, CodingConfig),
+}
+
+impl Waveform {
+    /// Returns the samples the current modulator makes for the payload.
+    ///
+    /// # Panics
+    /// If the configuration is invalid, or the payload of a symbol does not fill it.
+    pub fn modulate(&self, payload: &[u8]) -> Vec<f32> {
+        match self {
+            Waveform::Symbol(config) => {
+                let modulator = OFDMModulator::new(config.into());
+                let mut samples = vec![0.0; modulator.get_symbol_length()];
+                modulator.modulate_buffer_as_symbol(payload, &mut samples);
+                samples
+            }
+            Waveform::Frame(config) => {
+                FrameEncoder::new(OFDMModulator::new(config.into())).encode(payload)
+            }
+            Waveform::CodedFrame(ofdm, coding) => {
+                CodedOFDMModulator::new(ofdm.clone(), coding.clone()).encode_frame(payload)
+            }
+        }
+    }
+}
+
+/// A named reference waveform: a payload, how it is modulated, and the samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+    /// The name the fixture is found by.
+    pub name: String,
+    /// How the samples are made from the payload.
+    pub waveform: Waveform,
+    /// The payload the samples carry.
+    pub payload: Vec<u8>,
+    /// The samples, of the current modulator when [generated](Fixture::generate), or as they were stored.
+    pub samples: Vec<f32>,
+}
+
+impl Fixture {
+    /// Makes a fixture with the samples the current modulator makes for the payload.
+    ///
+    /// # Panics
+    /// If the configuration is invalid, or the payload of a symbol does not fill it.
+    pub fn generate(name: &str, waveform: Waveform, payload: &[u8]) -> Self {
+        Fixture {
+            name: name.to_string(),
+            samples: waveform.modulate(payload),
+            waveform,
+            payload: payload.to_vec(),
+        }
+    }
+}
+
+/// Returns the reference fixtures of the crate: the symbol of the example of
+/// [demodulate_symbol_from_buffer](crate::ofdm::demodulator::GenericOFDMDemodulator::demodulate_symbol_from_buffer),
+/// a symbol of every QAM order, an uncoded and a coded frame.
+pub fn reference_fixtures() -> Vec<Fixture> {
+    let config = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let payload = |length: usize| -> Vec<u8> {
+        (0..length as u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect()
+    };
+
+    let mut fixtures = vec![Fixture::generate(
+        "hello_ofdm",
+        Waveform::Symbol(OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 4,
+            pilot_subcarrier_every: 4,
+            qam_order: QAMOrder::QAM16,
+            ..Default::default()
+        }),
+        "Hello, OFDM!            ".as_bytes(),
+    )];
+    for qam_order in QAMOrder::ALL {
+        let config = OFDMConfig {
+            qam_order,
+            ..config.clone()
+        };
+        let length = OFDMModulator::new((&config).into()).get_bytes_per_symbol();
+        fixtures.push(Fixture::generate(
+            &format!("{:?}_symbol", qam_order).to_lowercase(),
+            Waveform::Symbol(config),
+            &payload(length),
+        ));
+    }
+    fixtures.push(Fixture::generate(
+        "uncoded_frame",
+        Waveform::Frame(config.clone()),
+        &payload(100),
+    ));
+    fixtures.push(Fixture::generate(
+        "coded_frame",
+        Waveform::CodedFrame(config, CodingConfig::default()),
+        &payload(100),
+    ));
+    fixtures
+}
+
+/// Serializes the fixtures, configurations included, in a format read by [from_bytes].
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::testing::golden::{Fixture, Waveform, from_bytes, to_bytes};
+///
+/// let config = OFDMConfig { num_subcarriers: 64, cyclic_prefix_length: 8, ..Default::default() };
+/// let fixture = Fixture::generate("frame", Waveform::Frame(config), b"payload");
+/// let bytes = to_bytes(std::slice::from_ref(&fixture));
+/// assert_eq!(from_bytes(&bytes), Ok(vec![fixture]));
+/// assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+/// ```
+pub fn to_bytes(fixtures: &[Fixture]) -> Vec<u8> {
+    let mut bytes = vec![GOLDEN_VERSION];
+    bytes.extend((fixtures.len() as u32).to_be_bytes());
+    for fixture in fixtures {
+        let mut field = |field: &[u8]| {
+            bytes.extend((field.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(field);
+        };
+        field(fixture.name.as_bytes());
+        match &fixture.waveform {
+            Waveform::Symbol(config) => {
+                field(&[0]);
+                field(&config.to_bytes());
+            }
+            Waveform::Frame(config) => {
+                field(&[1]);
+                field(&config.to_bytes());
+            }
+            Waveform::CodedFrame(ofdm, coding) => {
+                field(&[2]);
+                field(&ofdm.to_bytes());
+                field(&coding.to_bytes());
+            }
+        }
+        field(&fixture.payload);
+        let samples: Vec<u8> = fixture
+            .samples
+            .iter()
+            .flat_map(|sample| sample.to_be_bytes())
+            .collect();
+        field(&samples);
+    }
+    bytes
+}
+
+/// Reads fixtures serialized with [to_bytes].
+///
+/// # Errors
+/// [ModemError::InvalidConfig] if the bytes are not valid fixtures of this version.
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Fixture>, ModemError> {
+    let mut reader = ConfigReader { bytes };
+    if reader.u8()? != GOLDEN_VERSION {
+        return Err(ModemError::InvalidConfig);
+    }
+
+    let count = reader.u32()?;
+    let mut fixtures = Vec::new();
+    for _ in 0..count {
+        let mut field = || -> Result<&[u8], ModemError> {
+            let length = reader.u32()? as usize;
+            reader.slice(length)
+        };
+        let name = String::from_utf8(field()?.to_vec()).map_err(|_| ModemError::InvalidConfig)?;
+        let waveform = match field()? {
+            [0] => Waveform::Symbol(OFDMConfig::from_bytes(field()?)?),
+            [1] => Waveform::Frame(OFDMConfig::from_bytes(field()?)?),
+            [2] => Waveform::CodedFrame(
+                OFDMConfig::from_bytes(field()?)?,
+                CodingConfig::from_bytes(field()?)?,
+            ),
+            _ => return Err(ModemError::InvalidConfig),
+        };
+        let payload = field()?.to_vec();
+        let samples = field()?;
+        if !samples.len().is_multiple_of(4) {
+            return Err(ModemError::InvalidConfig);
+        }
+        let samples = samples
+            .chunks_exact(4)
+            .map(|bytes| f32::from_be_bytes(bytes.try_into().unwrap()))
+            .collect();
+        fixtures.push(Fixture {
+            name,
+            waveform,
+            payload,
+            samples,
+        });
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(ModemError::InvalidConfig);
+    }
+    Ok(fixtures)
+}
+
+/// Writes the fixtures to a file, see [to_bytes].
+///
+/// # Errors
+/// The error of the file, if it can not be written.
+pub fn write_fixtures(path: impl AsRef<Path>, fixtures: &[Fixture]) -> io::Result<()> {
+    std::fs::write(path, to_bytes(fixtures))
+}
+
+/// Reads the fixtures of a file, see [from_bytes].
+///
+/// # Errors
+/// The error of the file, if it can not be read, or [io::ErrorKind::InvalidData] if it does not hold valid fixtures.
+pub fn read_fixtures(path: impl AsRef<Path>) -> io::Result<Vec<Fixture>> {
+    from_bytes(&std::fs::read(path)?)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// The first difference [check_fixtures] found between the fixtures and the stored ones.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GoldenMismatch {
+    /// The file could not be read or written, with the message of the error.
+    File(String),
+    /// The names of the fixtures differ from the stored ones.
+    Names {
+        expected: Vec<String>,
+        got: Vec<String>,
+    },
+    /// The configuration or the payload of a fixture differs from the stored one.
+    Waveform { name: String },
+    /// A fixture has another number of samples than the stored one.
+    Length {
+        name: String,
+        expected: usize,
+        got: usize,
+    },
+    /// A sample of a fixture is further from the stored one than the tolerance.
+    Sample {
+        name: String,
+        index: usize,
+        expected: f32,
+        got: f32,
+    },
+}
+
+impl Display for GoldenMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GoldenMismatch::File(message) => write!(f, "Fixture file error: {}", message),
+            GoldenMismatch::Names { expected, got } => {
+                write!(f, "Fixtures must be {:?}, but got {:?}", expected, got)
+            }
+            GoldenMismatch::Waveform { name } => {
+                write!(f, "Fixture {} has another configuration or payload", name)
+            }
+            GoldenMismatch::Length {
+                name,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Fixture {} must have {} samples, but got {}",
+                name, expected, got
+            ),
+            GoldenMismatch::Sample {
+                name,
+                index,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Sample {} of fixture {} must be {}, but got {}",
+                index, name, expected, got
+            ),
+        }?;
+        write!(f, ", set {} to store the current fixtures", WRITE_GOLDEN)
+    }
+}
+
+impl std::error::Error for GoldenMismatch {}
+
+/// Compares the fixtures with the ones stored in a file, and returns the first difference,
+/// a sample further than the tolerance from the stored one included.
+///
+/// With the [WRITE_GOLDEN] environment variable set, the fixtures are stored in the file instead,
+/// after an intended change of the samples.
+///
+/// # Panics
+/// If the tolerance is negative or not finite.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::OFDMConfig;
+/// use software_modem::testing::golden::{Fixture, GoldenMismatch, Waveform, check_fixtures, write_fixtures};
+///
+/// let path = std::env::temp_dir().join("software_modem_golden_example.bin");
+/// let config = OFDMConfig { num_subcarriers: 64, cyclic_prefix_length: 8, ..Default::default() };
+/// let mut fixture = Fixture::generate("frame", Waveform::Frame(config), b"payload");
+/// write_fixtures(&path, std::slice::from_ref(&fixture)).unwrap();
+/// # if std::env::var_os("WRITE_GOLDEN").is_none() {
+/// assert_eq!(check_fixtures(&path, std::slice::from_ref(&fixture), 1e-6), Ok(()));
+///
+/// // a modulator whose samples moved
+/// fixture.samples[10] += 1e-3;
+/// assert!(matches!(
+///     check_fixtures(&path, &[fixture.clone()], 1e-6),
+///     Err(GoldenMismatch::Sample { index: 10, .. })
+/// ));
+/// assert_eq!(check_fixtures(&path, &[fixture], 1e-2), Ok(()));
+/// # }
+/// ```
+pub fn check_fixtures(
+    path: impl AsRef<Path>,
+    fixtures: &[Fixture],
+    tolerance: f32,
+) -> Result<(), GoldenMismatch> {
+    if !(tolerance >= 0.0 && tolerance.is_finite()) {
+        panic!(
+            "Tolerance must be non-negative and finite, but got {}",
+            tolerance
+        );
+    }
+    if std::env::var_os(WRITE_GOLDEN).is_some() {
+        return write_fixtures(path, fixtures)
+            .map_err(|error| GoldenMismatch::File(error.to_string()));
+    }
+
+    let stored = read_fixtures(path).map_err(|error| GoldenMismatch::File(error.to_string()))?;
+    let names = |fixtures: &[Fixture]| -> Vec<String> {
+        fixtures
+            .iter()
+            .map(|fixture| fixture.name.clone())
+            .collect()
+    };
+    if names(&stored) != names(fixtures) {
+        return Err(GoldenMismatch::Names {
+            expected: names(&stored),
+            got: names(fixtures),
+        });
+    }
+
+    for (fixture, stored) in fixtures.iter().zip(&stored) {
+        let name = fixture.name.clone();
+        if fixture.waveform != stored.waveform || fixture.payload != stored.payload {
+            return Err(GoldenMismatch::Waveform { name });
+        }
+        if fixture.samples.len() != stored.samples.len() {
+            return Err(GoldenMismatch::Length {
+                name,
+                expected: stored.samples.len(),
+                got: fixture.samples.len(),
+            });
+        }
+        // NaN is never within the tolerance
+        if let Some(index) =
+            fixture
+                .samples
+                .iter()
+                .zip(&stored.samples)
+                .position(|(got, expected)| {
+                    (got - expected).is_nan() || (got - expected).abs() > tolerance
+                })
+        {
+            return Err(GoldenMismatch::Sample {
+                name,
+                index,
+                expected: stored.samples[index],
+                got: fixture.samples[index],
+            });
+        }
+    }
+    Ok(())
+}
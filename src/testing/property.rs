@@ -0,0 +1,155 @@
+//! This module provides the configuration space and the properties of the modem that the crate checks
+//! with `proptest`, over configurations drawn at random.
+//!
+//! A [ModemCase] is a point of a configuration space kept well-factored: every parameter is a plain field
+//! with its own range, from [ModemCase::MIN] to [ModemCase::MAX], and [ModemCase::ofdm_config] turns it into
+//! a valid [OFDMConfig]. A strategy draws every field from its range on its own, so `proptest` shrinks
+//! a failing case one parameter at a time towards the simplest configuration that still fails,
+//! and the payload and the noise of a case follow from its seed, so a case fails the same way every time.
+//!
+//! [round_trip] is the property the crate is checked with: a frame over a clean or a quiet channel decodes
+//! into its payload, and the capacity accessors agree with the frame.
+
+use crate::{
+    channel::{AwgnChannel, Channel},
+    frame::{FrameDecoder, FrameEncoder},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::QAMOrder,
+    rng::SimulationRng,
+};
+
+/// The SNR of the quiet channel of a case, in dB, far above any decision error,
+/// also of QAM-256 on a few subcarriers, which loses a byte in a few frames at 50 dB.
+const QUIET_SNR_DB: f32 = 60.0;
+
+/// A configuration of the modem, a payload and a channel, drawn at random by a strategy of `proptest`.
+///
+/// Every field is within its range of [ModemCase::MIN] and [ModemCase::MAX], and only the
+/// [valid](ModemCase::is_valid) combinations are cases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModemCase {
+    /// The number of subcarriers, not necessarily a power of two.
+    pub num_subcarriers: u32,
+    /// The length of the cyclic prefix in samples.
+    pub cyclic_prefix_length: u32,
+    /// The interval of the pilot subcarriers.
+    pub pilot_subcarrier_every: u32,
+    /// The constellation of the data subcarriers.
+    pub qam_order: QAMOrder,
+    /// Whether the data subcarriers are encoded differentially in time.
+    pub differential_time: bool,
+    /// The number of bytes of the payload.
+    pub payload_length: usize,
+    /// The seed of the payload and of the noise.
+    pub seed: u64,
+    /// Whether the frame passes a channel with noise at 60 dB, rather than a clean one.
+    pub noise: bool,
+}
+
+impl ModemCase {
+    /// The simplest case, the one failing cases shrink towards.
+    pub const MIN: ModemCase = ModemCase {
+        num_subcarriers: 4,
+        cyclic_prefix_length: 0,
+        pilot_subcarrier_every: 2,
+        qam_order: QAMOrder::QAM16,
+        differential_time: false,
+        payload_length: 0,
+        seed: 0,
+        noise: false,
+    };
+
+    /// The upper ends of the ranges of the fields, but for the constellation, which is any of [QAM_ORDERS](Self::QAM_ORDERS).
+    pub const MAX: ModemCase = ModemCase {
+        num_subcarriers: 256,
+        cyclic_prefix_length: 64,
+        pilot_subcarrier_every: 16,
+        qam_order: QAMOrder::QAM16,
+        differential_time: true,
+        payload_length: 600,
+        seed: u64::MAX,
+        noise: true,
+    };
+
+    /// The constellations of the cases, the one of the [minimum](ModemCase::MIN) first, for a strategy to shrink towards.
+    pub const QAM_ORDERS: [QAMOrder; 4] = [
+        QAMOrder::QAM16,
+        QAMOrder::QPSK,
+        QAMOrder::QAM64,
+        QAMOrder::QAM256,
+    ];
+
+    /// Returns whether the modem of the case can be built: in coherent mode, the pilots must fall on some subcarrier.
+    pub fn is_valid(&self) -> bool {
+        self.differential_time || self.pilot_subcarrier_every < self.num_subcarriers
+    }
+
+    /// Returns the configuration of the modem of the case.
+    pub fn ofdm_config(&self) -> OFDMConfig {
+        OFDMConfig {
+            num_subcarriers: self.num_subcarriers,
+            cyclic_prefix_length: self.cyclic_prefix_length,
+            pilot_subcarrier_every: self.pilot_subcarrier_every,
+            qam_order: self.qam_order,
+            differential_time: self.differential_time,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the payload of the case, made from its seed.
+    pub fn payload(&self) -> Vec<u8> {
+        let key = (self.seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as u32;
+        (0..self.payload_length as u32)
+            .map(|i| (key.wrapping_add(i).wrapping_mul(2654435761) >> 11) as u8)
+            .collect()
+    }
+}
+
+/// The round trip property: the frame of the payload of a case, over its channel, decodes into the payload.
+///
+/// The lengths of the frame and of the decoded payload must agree with
+/// [get_frame_length](FrameEncoder::get_frame_length) and [get_bytes_per_symbol](FrameEncoder::get_bytes_per_symbol)
+/// of the encoder and the decoder.
+pub fn round_trip(case: &ModemCase) -> Result<(), String> {
+    let config = case.ofdm_config();
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    let payload = case.payload();
+
+    let bytes_per_symbol = encoder.get_bytes_per_symbol();
+    if bytes_per_symbol == 0 || decoder.get_bytes_per_symbol() != bytes_per_symbol {
+        return Err(format!(
+            "bytes per symbol of {} and {}",
+            bytes_per_symbol,
+            decoder.get_bytes_per_symbol()
+        ));
+    }
+    let mut samples = encoder.encode(&payload);
+    let frame_length = encoder.get_frame_length(payload.len());
+    if samples.len() != frame_length || decoder.get_frame_length(payload.len()) != frame_length {
+        return Err(format!(
+            "frame of {} samples, expected {}",
+            samples.len(),
+            frame_length
+        ));
+    }
+
+    if case.noise {
+        AwgnChannel::new(QUIET_SNR_DB, SimulationRng::new(case.seed).seed("noise"))
+            .apply(&mut samples);
+    }
+    let decoded = decoder.decode(&samples);
+    let payload_symbols = payload.len().div_ceil(bytes_per_symbol);
+    if decoded.len() != payload_symbols * bytes_per_symbol {
+        return Err(format!(
+            "decoded {} bytes, expected {} symbols of {}",
+            decoded.len(),
+            payload_symbols,
+            bytes_per_symbol
+        ));
+    }
+    if decoded[..payload.len()] != payload[..] {
+        return Err("decoded payload differs".to_string());
+    }
+    Ok(())
+}
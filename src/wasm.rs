@@ -0,0 +1,221 @@
+//! This module provides wrappers of the coded modem for JavaScript, behind the `wasm` feature.
+//!
+//! `wasm-bindgen` exports the wrappers as JavaScript classes, whose methods are named in camel case,
+//! like `getFrameLength`. They only take and return types it maps to JavaScript: `&[f32]` and `Vec<f32>`
+//! as `Float32Array`, `&[u8]` and `Vec<u8>` as `Uint8Array`, numbers, and `Result<_, String>` as exceptions.
+//! `cargo rustc --lib --crate-type cdylib --release --target wasm32-unknown-unknown --features wasm` builds the module,
+//! and the `wasm-bindgen` CLI generates its JavaScript glue.
+//! Both ends are configured by a [serialized OFDMConfig](OFDMConfig::to_bytes) and [CodingConfig](CodingConfig::to_bytes),
+//! so the page and the other end of the link can share them as bytes.
+//!
+//! Nothing here spawns threads, reads clocks or touches the filesystem, and the FFTs of `realfft` are plain Rust,
+//! so the modem runs on `wasm32-unknown-unknown`. Only the [audio](crate::audio) module uses threads and `std::time`,
+//! and the [StreamDemodulator] reads the clock for the timers of its metrics alone, which the wrapper leaves off.
+//! The [WasmStreamDemodulator] is fed from the 128-sample blocks of an `AudioWorklet`.
+//!
+//! # Example
+//! ```
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::wasm::{WasmDemodulator, WasmModulator, WasmStreamDemodulator};
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! }
+//! .to_bytes();
+//! let coding = CodingConfig::default().to_bytes();
+//!
+//! let modulator = WasmModulator::new(&ofdm, &coding).unwrap();
+//! let samples = modulator.modulate(b"data over sound");
+//! assert_eq!(samples.len(), modulator.get_frame_length(15));
+//!
+//! // a whole frame at once
+//! let demodulator = WasmDemodulator::new(&ofdm, &coding).unwrap();
+//! assert_eq!(demodulator.demodulate(&samples).unwrap(), b"data over sound");
+//! assert!(demodulator.demodulate(&samples[..samples.len() / 2]).is_err());
+//!
+//! // or in the blocks of an AudioWorklet, with silence around the frame
+//! let mut stream = WasmStreamDemodulator::new(&ofdm, &coding, 0.01, 2400).unwrap();
+//! let mut signal = vec![0.0; 1000];
+//! signal.extend(&samples);
+//! signal.extend([0.0; 4000]);
+//! let mut waiting = 0;
+//! for block in signal.chunks(128) {
+//!     waiting = stream.push(block);
+//! }
+//! assert_eq!(waiting, 1);
+//! assert_eq!(stream.next_frame().unwrap(), b"data over sound");
+//! assert_eq!(stream.next_frame(), None);
+//!
+//! // broken configurations are errors, not panics
+//! assert!(WasmModulator::new(&ofdm[..5], &coding).is_err());
+//! ```
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    stream::{StreamDemodulator, SyncState},
+};
+
+/// Reads both serialized configurations.
+fn parse_configs(
+    ofdm_config: &[u8],
+    coding_config: &[u8],
+) -> Result<(OFDMConfig, CodingConfig), String> {
+    let ofdm = OFDMConfig::from_bytes(ofdm_config).map_err(|error| error.to_string())?;
+    let coding = CodingConfig::from_bytes(coding_config).map_err(|error| error.to_string())?;
+    Ok((ofdm, coding))
+}
+
+/// A coded modulator for JavaScript.
+#[wasm_bindgen]
+pub struct WasmModulator {
+    modulator: CodedOFDMModulator,
+}
+
+#[wasm_bindgen]
+impl WasmModulator {
+    /// Creates a modulator from the serialized configurations.
+    ///
+    /// # Errors
+    /// The message of the [ModemError](crate::error::ModemError) if a configuration can not be read.
+    #[wasm_bindgen(constructor)]
+    pub fn new(ofdm_config: &[u8], coding_config: &[u8]) -> Result<WasmModulator, String> {
+        let (ofdm, coding) = parse_configs(ofdm_config, coding_config)?;
+        Ok(WasmModulator {
+            modulator: CodedOFDMModulator::new(ofdm, coding),
+        })
+    }
+
+    /// Encodes the payload into a frame of samples.
+    ///
+    /// # Panics
+    /// If the payload is longer than 65535 bytes.
+    pub fn modulate(&self, payload: &[u8]) -> Vec<f32> {
+        self.modulator.encode_frame(payload)
+    }
+
+    /// Returns the number of samples in a frame carrying `payload_length` bytes.
+    #[wasm_bindgen(js_name = getFrameLength)]
+    pub fn get_frame_length(&self, payload_length: usize) -> usize {
+        self.modulator.get_frame_length(payload_length)
+    }
+}
+
+/// A coded demodulator of whole frames for JavaScript.
+#[wasm_bindgen]
+pub struct WasmDemodulator {
+    demodulator: CodedOFDMDemodulator,
+}
+
+#[wasm_bindgen]
+impl WasmDemodulator {
+    /// Creates a demodulator from the serialized configurations.
+    ///
+    /// # Errors
+    /// The message of the [ModemError](crate::error::ModemError) if a configuration can not be read.
+    #[wasm_bindgen(constructor)]
+    pub fn new(ofdm_config: &[u8], coding_config: &[u8]) -> Result<WasmDemodulator, String> {
+        let (ofdm, coding) = parse_configs(ofdm_config, coding_config)?;
+        Ok(WasmDemodulator {
+            demodulator: CodedOFDMDemodulator::new(ofdm, coding),
+        })
+    }
+
+    /// Decodes a frame starting at the first sample into its payload.
+    ///
+    /// # Errors
+    /// The message of the [ModemError](crate::error::ModemError) if the frame can not be decoded.
+    pub fn demodulate(&self, samples: &[f32]) -> Result<Vec<u8>, String> {
+        self.demodulator
+            .decode_frame(samples)
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// A [StreamDemodulator] for JavaScript, which queues the decoded payloads,
+/// as `wasm-bindgen` can not return a list of arrays.
+#[wasm_bindgen]
+pub struct WasmStreamDemodulator {
+    stream: StreamDemodulator,
+    frames: VecDeque<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl WasmStreamDemodulator {
+    /// Creates a stream demodulator from the serialized configurations, see [StreamDemodulator::new].
+    ///
+    /// # Errors
+    /// The message of the [ModemError](crate::error::ModemError) if a configuration can not be read,
+    /// or a message if the squelch level is not positive and finite, or the hang is 0.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        ofdm_config: &[u8],
+        coding_config: &[u8],
+        squelch_level: f32,
+        hang: usize,
+    ) -> Result<WasmStreamDemodulator, String> {
+        let (ofdm, coding) = parse_configs(ofdm_config, coding_config)?;
+        if !(squelch_level > 0.0 && squelch_level.is_finite()) || hang == 0 {
+            return Err(format!(
+                "Squelch level must be positive and finite and hang at least 1, but got {} and {}",
+                squelch_level, hang
+            ));
+        }
+        Ok(WasmStreamDemodulator {
+            stream: StreamDemodulator::new(
+                CodedOFDMDemodulator::new(ofdm, coding),
+                squelch_level,
+                hang,
+            ),
+            frames: VecDeque::new(),
+        })
+    }
+
+    /// Pushes a block of samples, and returns the number of decoded payloads waiting in the queue.
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        self.frames.extend(self.stream.push(samples));
+        self.frames.len()
+    }
+
+    /// Decodes the burst being received at the end of the stream,
+    /// and returns the number of decoded payloads waiting in the queue.
+    pub fn flush(&mut self) -> usize {
+        self.frames.extend(self.stream.flush());
+        self.frames.len()
+    }
+
+    /// Returns the oldest decoded payload of the queue.
+    #[wasm_bindgen(js_name = nextFrame)]
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        self.frames.pop_front()
+    }
+
+    /// Returns `true` while the squelch is open and a frame is being received.
+    #[wasm_bindgen(js_name = isReceiving)]
+    pub fn is_receiving(&self) -> bool {
+        self.stream.get_sync_state() == SyncState::Receiving
+    }
+
+    /// Returns the number of frames decoded with a valid CRC.
+    #[wasm_bindgen(js_name = getFramesDecoded)]
+    pub fn get_frames_decoded(&self) -> usize {
+        self.stream.get_frames_decoded()
+    }
+
+    /// Returns the number of bursts above the squelch level that did not decode.
+    #[wasm_bindgen(js_name = getFramesFailed)]
+    pub fn get_frames_failed(&self) -> usize {
+        self.stream.get_frames_failed()
+    }
+}
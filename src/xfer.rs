@@ -0,0 +1,657 @@
+//! This module provides file transfers over frames, split into numbered fragments and checked by a SHA-256 hash.
+//!
+//! A transfer starts with a metadata frame, carrying the name, the size, the fragment size and the hash of the file,
+//! followed by one data frame per fragment. [fragment_file] returns the payloads of these frames, and [send_file]
+//! modulates them into a stream of samples. The [FileReceiver] takes the decoded payloads in any order and with duplicates,
+//! writes the fragments to a temporary file, and moves it to its name once every fragment has arrived and the hash matches.
+//! Until then it reports the [progress](FileReceiver::get_progress) and the [missing fragments](FileReceiver::get_missing_fragments),
+//! which are lost frames the sender has to repeat.
+//!
+//! Every frame starts with a kind byte and an identifier of the transfer, the first 4 bytes of the hash, in big endian:
+//! - metadata: `M`, identifier, the size as a `u64`, the fragment size as a `u16`, the hash, and the UTF-8 name up to the end;
+//! - data: `D`, identifier, the index of the fragment as a `u32`, and the fragment up to the end.
+//!
+//! Payloads that are not frames of a transfer are ignored, so the link can carry other traffic.
+//!
+//! # Example
+//! ```
+//! use software_modem::coded::{CodedOFDMDemodulator, CodedOFDMModulator};
+//! use software_modem::frame::CodingConfig;
+//! use software_modem::ofdm::OFDMConfig;
+//! use software_modem::ofdm::modulator::OutputScale;
+//! use software_modem::stream::{StreamDemodulator, StreamModulator};
+//! use software_modem::xfer::{FileReceiver, send_file};
+//!
+//! let ofdm = OFDMConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix_length: 4,
+//!     differential_time: true,
+//!     output_scale: OutputScale::PeakNormalize(0.5),
+//!     ..Default::default()
+//! };
+//! let directory = std::env::temp_dir().join("software_modem_doc_xfer");
+//! std::fs::create_dir_all(directory.join("received")).unwrap();
+//! let path = directory.join("notes.txt");
+//! let data: Vec<u8> = (0..600u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+//! std::fs::write(&path, &data).unwrap();
+//!
+//! // the metadata and three fragments of 256 bytes
+//! let modulator = StreamModulator::new(CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()), 4800);
+//! let samples = send_file(&path, &modulator, 256).unwrap();
+//!
+//! let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+//! let mut stream = StreamDemodulator::new(demodulator, 0.01, 2400);
+//! let mut receiver = FileReceiver::new(directory.join("received"));
+//! let mut received = None;
+//! for payload in stream.run(&mut samples.as_slice(), 480).unwrap() {
+//!     received = receiver.push(&payload).unwrap().or(received);
+//! }
+//! let received = received.unwrap();
+//! assert_eq!(received, directory.join("received/notes.txt"));
+//! assert_eq!(std::fs::read(&received).unwrap(), data);
+//! # std::fs::remove_dir_all(&directory).unwrap();
+//! ```
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::stream::StreamModulator;
+
+const KIND_METADATA: u8 = b'M';
+const KIND_DATA: u8 = b'D';
+const METADATA_HEADER_LENGTH: usize = 1 + 4 + 8 + 2 + 32;
+const DATA_HEADER_LENGTH: usize = 1 + 4 + 4;
+/// Longest payload of a frame.
+const MAX_PAYLOAD: usize = 65535;
+
+/// Largest fragment that fits into a frame with the header of a data frame.
+pub const MAX_FRAGMENT_SIZE: usize = MAX_PAYLOAD - DATA_HEADER_LENGTH;
+
+/// Data fragments kept while the metadata of their transfer has not arrived yet.
+const MAX_EARLY_FRAGMENTS: usize = 256;
+
+/// Errors sending or receiving a file.
+#[derive(Debug)]
+pub enum XferError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The name of the file is empty, a path rather than a name, or does not fit into a frame.
+    InvalidName(String),
+    /// Every fragment has arrived, but the hash of the file does not match, a fragment has been corrupted.
+    HashMismatch { name: String },
+    /// The transfer ended before every fragment arrived.
+    Incomplete { name: String, missing: Vec<u32> },
+}
+
+impl Display for XferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XferError::Io(error) => write!(f, "File transfer error: {}", error),
+            XferError::InvalidName(name) => write!(f, "Invalid file name {:?}", name),
+            XferError::HashMismatch { name } => {
+                write!(
+                    f,
+                    "SHA-256 mismatch, the received file {:?} is corrupted",
+                    name
+                )
+            }
+            XferError::Incomplete { name, missing } => write!(
+                f,
+                "Incomplete transfer of {:?}, {} fragments are missing",
+                name,
+                missing.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for XferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XferError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for XferError {
+    fn from(error: std::io::Error) -> Self {
+        XferError::Io(error)
+    }
+}
+
+/// The metadata of a file being transferred.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Bytes of every fragment, only the last one may be shorter.
+    pub fragment_size: usize,
+    pub sha256: [u8; 32],
+}
+
+impl FileMetadata {
+    /// Returns the number of fragments of the file, 0 for an empty file.
+    pub fn get_num_fragments(&self) -> u32 {
+        self.size.div_ceil(self.fragment_size as u64) as u32
+    }
+
+    /// Returns the identifier of the transfer in its frames.
+    fn get_id(&self) -> u32 {
+        u32::from_be_bytes(self.sha256[..4].try_into().unwrap())
+    }
+
+    fn to_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(METADATA_HEADER_LENGTH + self.name.len());
+        payload.push(KIND_METADATA);
+        payload.extend(self.get_id().to_be_bytes());
+        payload.extend(self.size.to_be_bytes());
+        payload.extend((self.fragment_size as u16).to_be_bytes());
+        payload.extend(self.sha256);
+        payload.extend(self.name.as_bytes());
+        payload
+    }
+
+    fn from_payload(payload: &[u8]) -> Option<FileMetadata> {
+        if payload.len() < METADATA_HEADER_LENGTH || payload[0] != KIND_METADATA {
+            return None;
+        }
+        let metadata = FileMetadata {
+            name: String::from_utf8(payload[METADATA_HEADER_LENGTH..].to_vec()).ok()?,
+            size: u64::from_be_bytes(payload[5..13].try_into().unwrap()),
+            fragment_size: u16::from_be_bytes([payload[13], payload[14]]) as usize,
+            sha256: payload[15..47].try_into().unwrap(),
+        };
+        let id = u32::from_be_bytes(payload[1..5].try_into().unwrap());
+        let fragments = metadata.size.div_ceil(metadata.fragment_size.max(1) as u64);
+        (id == metadata.get_id()
+            && metadata.fragment_size > 0
+            && metadata.fragment_size <= MAX_FRAGMENT_SIZE
+            && fragments <= u32::MAX as u64)
+            .then_some(metadata)
+    }
+}
+
+/// Checks that the name is the name of a file, and not a path that could leave the directory of the receiver.
+fn check_name(name: &str) -> Result<(), XferError> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0'])
+        && METADATA_HEADER_LENGTH + name.len() <= MAX_PAYLOAD;
+    if valid {
+        Ok(())
+    } else {
+        Err(XferError::InvalidName(name.to_string()))
+    }
+}
+
+/// Returns the payloads of the frames of a file transfer, the metadata first and then the fragments in order.
+///
+/// # Panics
+/// If the fragment size is 0 or larger than [MAX_FRAGMENT_SIZE], or the file has more than `u32::MAX` fragments.
+///
+/// # Errors
+/// [XferError::InvalidName] if the name is empty, contains a path separator, or is too long for a frame.
+///
+/// # Example
+/// ```
+/// use software_modem::xfer::{FileReceiver, fragment_file};
+///
+/// let directory = std::env::temp_dir().join("software_modem_doc_fragment");
+/// std::fs::create_dir_all(&directory).unwrap();
+/// let data: Vec<u8> = (0..1000u32).map(|i| (i.wrapping_mul(2654435761) >> 11) as u8).collect();
+/// let payloads = fragment_file("data.bin", &data, 100).unwrap();
+/// assert_eq!(payloads.len(), 11);
+///
+/// // fragments before the metadata, duplicates, and one lost
+/// let mut receiver = FileReceiver::new(&directory);
+/// for index in [5, 3, 0, 3, 1, 2, 4, 6, 7, 8] {
+///     assert_eq!(receiver.push(&payloads[index]).unwrap(), None);
+/// }
+/// assert_eq!(receiver.get_missing_fragments(), [8, 9]);
+/// assert_eq!(receiver.get_progress().unwrap().fragments_received, 8);
+///
+/// // the metadata is the first payload, fragment n the payload n + 1
+/// assert_eq!(receiver.push(&payloads[9]).unwrap(), None);
+/// let path = receiver.push(&payloads[10]).unwrap().unwrap();
+/// assert_eq!(std::fs::read(&path).unwrap(), data);
+/// # std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+pub fn fragment_file(
+    name: &str,
+    data: &[u8],
+    fragment_size: usize,
+) -> Result<Vec<Vec<u8>>, XferError> {
+    if !(1..=MAX_FRAGMENT_SIZE).contains(&fragment_size) {
+        panic!(
+            "Fragment size must be between 1 and {} bytes, but got {}",
+            MAX_FRAGMENT_SIZE, fragment_size
+        );
+    }
+    if data.len().div_ceil(fragment_size) > u32::MAX as usize {
+        panic!(
+            "Number of fragments must be at most {}, but got {}",
+            u32::MAX,
+            data.len().div_ceil(fragment_size)
+        );
+    }
+    check_name(name)?;
+
+    let metadata = FileMetadata {
+        name: name.to_string(),
+        size: data.len() as u64,
+        fragment_size,
+        sha256: sha256(data),
+    };
+    let id = metadata.get_id().to_be_bytes();
+    let mut payloads = vec![metadata.to_payload()];
+    for (index, fragment) in data.chunks(fragment_size).enumerate() {
+        let mut payload = Vec::with_capacity(DATA_HEADER_LENGTH + fragment.len());
+        payload.push(KIND_DATA);
+        payload.extend(id);
+        payload.extend((index as u32).to_be_bytes());
+        payload.extend(fragment);
+        payloads.push(payload);
+    }
+    Ok(payloads)
+}
+
+/// Reads a file and modulates the frames of its transfer, see [fragment_file], into a stream of samples.
+///
+/// # Panics
+/// If the fragment size is 0 or larger than [MAX_FRAGMENT_SIZE], or the file has more than `u32::MAX` fragments.
+///
+/// # Errors
+/// - [XferError::Io] if the file can not be read.
+/// - [XferError::InvalidName] if the file name is not UTF-8 or too long for a frame.
+pub fn send_file(
+    path: impl AsRef<Path>,
+    modulator: &StreamModulator,
+    fragment_size: usize,
+) -> Result<Vec<f32>, XferError> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| XferError::InvalidName(path.display().to_string()))?;
+    let data = std::fs::read(path)?;
+
+    let payloads = fragment_file(name, &data, fragment_size)?;
+    let mut samples = Vec::with_capacity(
+        payloads
+            .iter()
+            .map(|payload| modulator.get_frame_length(payload.len()))
+            .sum(),
+    );
+    for payload in &payloads {
+        let Ok(()) = modulator.write_frame(payload, &mut samples);
+    }
+    Ok(samples)
+}
+
+/// Progress of the transfer being received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub fragments_received: u32,
+    pub fragments_total: u32,
+    pub bytes_received: u64,
+    pub size: u64,
+}
+
+/// The transfer being received.
+struct Transfer {
+    id: u32,
+    metadata: FileMetadata,
+    file: File,
+    temp_path: PathBuf,
+    received: Vec<bool>,
+    progress: Progress,
+}
+
+/// Receives files from the decoded payloads of their frames, into a directory.
+///
+/// One transfer is received at a time, the metadata of another file abandons the current one.
+/// Fragments that arrive before their metadata are kept, up to 256 of them. The fragments are written
+/// to a hidden temporary file next to the destination, `.name.part`, which is removed if the transfer
+/// is abandoned or corrupted. An existing file of the same name is replaced.
+///
+/// See [fragment_file] for an example.
+pub struct FileReceiver {
+    directory: PathBuf,
+    transfer: Option<Transfer>,
+    /// Data fragments whose metadata has not arrived yet, with the identifier of their transfer and their index.
+    early: Vec<(u32, u32, Vec<u8>)>,
+    /// The identifier of the last completed transfer, whose repeated frames are ignored.
+    completed: Option<u32>,
+}
+
+impl FileReceiver {
+    /// Creates a receiver that writes the files into the directory.
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        FileReceiver {
+            directory: directory.as_ref().to_path_buf(),
+            transfer: None,
+            early: Vec::new(),
+            completed: None,
+        }
+    }
+
+    /// Takes a decoded payload, and returns the path of the file once it is complete and its hash matches.
+    ///
+    /// Duplicates and payloads that are not frames of a transfer are ignored.
+    ///
+    /// # Errors
+    /// - [XferError::Io] if the file can not be written.
+    /// - [XferError::InvalidName] if the metadata names a path rather than a file, the transfer is ignored.
+    /// - [XferError::HashMismatch] if every fragment has arrived but the hash does not match, the file is removed.
+    pub fn push(&mut self, payload: &[u8]) -> Result<Option<PathBuf>, XferError> {
+        match payload.first() {
+            Some(&KIND_METADATA) => {
+                let Some(metadata) = FileMetadata::from_payload(payload) else {
+                    return Ok(None);
+                };
+                self.start(metadata)
+            }
+            Some(&KIND_DATA) if payload.len() >= DATA_HEADER_LENGTH => {
+                let id = u32::from_be_bytes(payload[1..5].try_into().unwrap());
+                let index = u32::from_be_bytes(payload[5..9].try_into().unwrap());
+                let fragment = &payload[DATA_HEADER_LENGTH..];
+                match &self.transfer {
+                    Some(transfer) if transfer.id == id => self.write(index, fragment),
+                    _ if self.completed == Some(id) => Ok(None),
+                    _ => {
+                        if self.early.len() < MAX_EARLY_FRAGMENTS
+                            && !self
+                                .early
+                                .iter()
+                                .any(|early| early.0 == id && early.1 == index)
+                        {
+                            self.early.push((id, index, fragment.to_vec()));
+                        }
+                        Ok(None)
+                    }
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the metadata of the transfer being received.
+    pub fn get_metadata(&self) -> Option<&FileMetadata> {
+        self.transfer.as_ref().map(|transfer| &transfer.metadata)
+    }
+
+    /// Returns the progress of the transfer being received.
+    pub fn get_progress(&self) -> Option<Progress> {
+        self.transfer.as_ref().map(|transfer| transfer.progress)
+    }
+
+    /// Returns the indices of the fragments of the transfer being received that have not arrived yet, in order.
+    pub fn get_missing_fragments(&self) -> Vec<u32> {
+        self.transfer.as_ref().map_or_else(Vec::new, |transfer| {
+            (0..transfer.metadata.get_num_fragments())
+                .filter(|&index| !transfer.received[index as usize])
+                .collect()
+        })
+    }
+
+    /// Ends the reception, abandoning the transfer being received.
+    ///
+    /// # Errors
+    /// [XferError::Incomplete] with the missing fragments if a transfer was being received.
+    pub fn finish(mut self) -> Result<(), XferError> {
+        let missing = self.get_missing_fragments();
+        match self.abandon() {
+            Some(metadata) => Err(XferError::Incomplete {
+                name: metadata.name,
+                missing,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn start(&mut self, metadata: FileMetadata) -> Result<Option<PathBuf>, XferError> {
+        let id = metadata.get_id();
+        if self.completed == Some(id)
+            || self
+                .transfer
+                .as_ref()
+                .is_some_and(|transfer| transfer.metadata == metadata)
+        {
+            return Ok(None);
+        }
+        check_name(&metadata.name)?;
+        self.abandon();
+
+        let temp_path = self.directory.join(format!(".{}.part", metadata.name));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        let fragments = metadata.get_num_fragments();
+        self.transfer = Some(Transfer {
+            id,
+            file,
+            temp_path,
+            received: vec![false; fragments as usize],
+            progress: Progress {
+                fragments_received: 0,
+                fragments_total: fragments,
+                bytes_received: 0,
+                size: metadata.size,
+            },
+            metadata,
+        });
+        self.completed = None;
+
+        let early = std::mem::take(&mut self.early);
+        let mut result = Ok(None);
+        for (_, index, fragment) in early.iter().filter(|early| early.0 == id) {
+            result = self.write(*index, fragment);
+            if !matches!(result, Ok(None)) {
+                break;
+            }
+        }
+        self.early = early.into_iter().filter(|early| early.0 != id).collect();
+        if fragments == 0 {
+            result = self.complete();
+        }
+        result
+    }
+
+    fn write(&mut self, index: u32, fragment: &[u8]) -> Result<Option<PathBuf>, XferError> {
+        let transfer = self.transfer.as_mut().unwrap();
+        let metadata = &transfer.metadata;
+        let offset = index as u64 * metadata.fragment_size as u64;
+        let expected = (metadata.size.saturating_sub(offset)).min(metadata.fragment_size as u64);
+        // a fragment that does not fit the metadata is not one of this file
+        if index >= metadata.get_num_fragments()
+            || fragment.len() as u64 != expected
+            || transfer.received[index as usize]
+        {
+            return Ok(None);
+        }
+
+        transfer.file.seek(SeekFrom::Start(offset))?;
+        transfer.file.write_all(fragment)?;
+        transfer.received[index as usize] = true;
+        transfer.progress.fragments_received += 1;
+        transfer.progress.bytes_received += fragment.len() as u64;
+        if transfer.progress.fragments_received == transfer.progress.fragments_total {
+            self.complete()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Checks the hash of the complete file and moves it to its name.
+    fn complete(&mut self) -> Result<Option<PathBuf>, XferError> {
+        let mut transfer = self.transfer.take().unwrap();
+        transfer.file.flush()?;
+        transfer.file.seek(SeekFrom::Start(0))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 65536];
+        loop {
+            let count = transfer.file.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            hasher.update(&buffer[..count]);
+        }
+        drop(transfer.file);
+
+        if hasher.finish() != transfer.metadata.sha256 {
+            std::fs::remove_file(&transfer.temp_path)?;
+            return Err(XferError::HashMismatch {
+                name: transfer.metadata.name,
+            });
+        }
+        let path = self.directory.join(&transfer.metadata.name);
+        std::fs::rename(&transfer.temp_path, &path)?;
+        self.completed = Some(transfer.id);
+        Ok(Some(path))
+    }
+
+    /// Drops the transfer being received and removes its temporary file.
+    fn abandon(&mut self) -> Option<FileMetadata> {
+        let transfer = self.transfer.take()?;
+        drop(transfer.file);
+        // the file may already be gone, there is nothing to do about it
+        let _ = std::fs::remove_file(&transfer.temp_path);
+        Some(transfer.metadata)
+    }
+}
+
+impl Drop for FileReceiver {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+/// Computes the SHA-256 hash of the data (FIPS 180-4).
+///
+/// # Example
+/// ```
+/// use software_modem::xfer::sha256;
+///
+/// let hash = sha256(b"abc");
+/// assert_eq!(hash[..4], [0xba, 0x78, 0x16, 0xbf]);
+/// assert_eq!(hash[28..], [0xf2, 0x00, 0x15, 0xad]);
+/// ```
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finish()
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// A SHA-256 hash computed over data fed in pieces.
+struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    /// Bytes in the block.
+    filled: usize,
+    length: u64,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Sha256 {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let count = data.len().min(64 - self.filled);
+            self.block[self.filled..self.filled + count].copy_from_slice(&data[..count]);
+            self.filled += count;
+            data = &data[count..];
+            if self.filled == 64 {
+                self.compress();
+                self.filled = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        let length = self.length * 8;
+        // a 1 bit, zeros up to 8 bytes before the end of a block, and the length in bits
+        self.update(&[0x80]);
+        while self.filled != 56 {
+            self.update(&[0]);
+        }
+        self.update(&length.to_be_bytes());
+
+        let mut hash = [0; 32];
+        for (bytes, word) in hash.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        hash
+    }
+
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(self.block.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for t in 16..64 {
+            let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+            let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+            w[t] = w[t - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for (k, w) in SHA256_K.iter().zip(w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
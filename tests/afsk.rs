@@ -0,0 +1,169 @@
+//! Sends bits and packets over the [AFSK](software_modem::phy::afsk) modem, through the frame layer, over noise
+//! and offsets, and decodes the APRS packets of a recording in `tests/data/aprs_afsk.wav`.
+//!
+//! The recording is written by `tests/data/aprs_afsk.py` from the AX.25 and Bell 202 specifications, independently of
+//! the modem, with the tones and the clock off, a lower space tone and noise, like the audio of a radio.
+//! Reading it needs the `wav` feature: `cargo test --features wav`.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel, OffsetImpairment},
+    error::ModemError,
+    frame::{FrameDecoder, FrameEncoder},
+    phy::{
+        PhyDemodulator, PhyModulator,
+        afsk::{AfskConfig, AfskDemodulator, AfskModulator},
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Returns the address field of an AX.25 callsign, the characters shifted up by one and the SSID in the last byte,
+/// with the bit that ends the addresses.
+fn address(callsign: &str, ssid: u8, command: bool, last: bool) -> Vec<u8> {
+    let mut field: Vec<u8> = format!("{callsign:<6}").bytes().map(|c| c << 1).collect();
+    field.push(u8::from(command) << 7 | 0x60 | ssid << 1 | u8::from(last));
+    field
+}
+
+/// Returns an AX.25 UI frame to APRS, from the source over the digipeaters, with the information field.
+fn aprs_frame(source: (&str, u8), path: &[(&str, u8)], info: &[u8]) -> Vec<u8> {
+    let mut frame = address("APRS", 0, true, false);
+    frame.extend(address(source.0, source.1, false, path.is_empty()));
+    for (i, &(callsign, ssid)) in path.iter().enumerate() {
+        frame.extend(address(callsign, ssid, false, i == path.len() - 1));
+    }
+    frame.extend([0x03, 0xf0]);
+    frame.extend(info);
+    frame
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn aprs_recording() {
+    use software_modem::io::read_wav;
+
+    let (samples, sample_rate) = read_wav("tests/data/aprs_afsk.wav").unwrap();
+    assert_eq!(sample_rate, 22050);
+    let demodulator = AfskDemodulator::new(AfskConfig {
+        sample_rate,
+        ..Default::default()
+    });
+
+    // the third packet was corrupted after its check was computed
+    let packets = demodulator.decode_packets(&samples);
+    assert_eq!(
+        packets,
+        vec![
+            aprs_frame(
+                ("N0CALL", 7),
+                &[("WIDE1", 1)],
+                b"!4903.50N/07201.75W-Test 001"
+            ),
+            aprs_frame(
+                ("N0CALL", 7),
+                &[("WIDE1", 1), ("WIDE2", 1)],
+                b">Software modem AFSK fixture"
+            ),
+            aprs_frame(("N0CALL", 9), &[], b":N0CALL   :~~ stuffed \xff\xfe ~~{01"),
+        ]
+    );
+}
+
+#[test]
+fn frames_through_the_traits() {
+    // a block is the fewest bytes of a whole number of samples
+    for (sample_rate, block_bytes, block_length) in [
+        (48000, 1, 320),
+        (22050, 1, 147),
+        (11025, 2, 147),
+        (8000, 3, 160),
+    ] {
+        let config = AfskConfig {
+            sample_rate,
+            ..Default::default()
+        };
+        let modulator = AfskModulator::new(config.clone());
+        assert_eq!(PhyModulator::capacity_bytes(&modulator), block_bytes);
+        assert_eq!(PhyModulator::samples_per_block(&modulator), block_length);
+
+        let encoder = FrameEncoder::new(modulator);
+        let decoder = FrameDecoder::new(AfskDemodulator::new(config));
+        for length in [0, 1, 7, 100] {
+            let payload = data(length as u32);
+            let samples = encoder.encode(&payload);
+            assert_eq!(samples.len(), decoder.get_frame_length(length));
+            let decoded = decoder.decode(&samples);
+            assert_eq!(decoded.len(), length.div_ceil(block_bytes) * block_bytes);
+            assert_eq!(decoded[..length], payload, "{sample_rate}");
+        }
+    }
+}
+
+#[test]
+fn blocks_on_their_own() {
+    let modulator = AfskModulator::new(AfskConfig::default());
+    let demodulator = AfskDemodulator::new(AfskConfig::default());
+    let mut block = vec![0.0; 320];
+    modulator.modulate(&[0xa7], &mut block).unwrap();
+    // the tones start at the phase 0, with the peak of the amplitude
+    assert_eq!(block[0], 0.0);
+    assert!(block.iter().all(|sample| sample.abs() <= 0.5));
+    let mut byte = [0];
+    demodulator.demodulate(&block, &mut byte).unwrap();
+    assert_eq!(byte, [0xa7]);
+
+    assert_eq!(
+        modulator.modulate(&[0, 0], &mut block),
+        Err(ModemError::BufferLength {
+            expected: 1,
+            got: 2
+        })
+    );
+}
+
+#[test]
+fn packets_over_noise_and_clock_offset() {
+    let config = AfskConfig {
+        sample_rate: 22050,
+        ..Default::default()
+    };
+    let modulator = AfskModulator::new(config.clone());
+    let demodulator = AfskDemodulator::new(config);
+    let frames = [
+        aprs_frame(("N0CALL", 1), &[("WIDE2", 2)], b">on the air"),
+        aprs_frame(("N0CALL", 2), &[], &data(200)),
+        // all 1s, stuffed after every five of them
+        vec![0xff; 40],
+    ];
+
+    for (seed, sco_ppm) in [(1, 0.0), (2, 1000.0), (3, -1000.0)] {
+        let mut transmission = vec![0.0; 2000];
+        for frame in &frames {
+            transmission.extend(modulator.modulate_packet(frame));
+            transmission.extend([0.0; 1000]);
+        }
+        let mut offset = OffsetImpairment::new(22050.0, 0.0, sco_ppm, 0.0);
+        offset.apply(&mut transmission);
+        AwgnChannel::with_reference_power(10.0, 0.125, seed).apply(&mut transmission);
+        assert_eq!(
+            demodulator.decode_packets(&transmission),
+            frames,
+            "{sco_ppm} ppm"
+        );
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "Sample rate must be at least 4 times the baud rate, but got 4000 and 1200"
+)]
+fn enough_samples_per_bit() {
+    AfskModulator::new(AfskConfig {
+        sample_rate: 4000,
+        ..Default::default()
+    });
+}
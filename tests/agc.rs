@@ -0,0 +1,163 @@
+//! Checks that the [AGC](software_modem::dsp::Agc) brings a signal ramping over 30 dB to its target,
+//! that it holds its gain when asked to, and that in front of a stream it lets the squelch hear quiet frames
+//! without changing its gain within them.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::{Agc, AgcConfig},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    stream::{StreamDemodulator, SyncState},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+/// Returns the RMS level of the samples in dB.
+fn rms_db(samples: &[f32]) -> f32 {
+    10.0 * (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).log10()
+}
+
+/// Returns a tone whose level ramps linearly in dB from one level to another over the samples.
+fn ramp(from_db: f32, to_db: f32, length: usize) -> Vec<f32> {
+    (0..length)
+        .map(|n| {
+            let level_db = from_db + (to_db - from_db) * n as f32 / length as f32;
+            let amplitude = 10f32.powf(level_db / 20.0) * std::f32::consts::SQRT_2;
+            amplitude * (std::f32::consts::TAU * 0.0123 * n as f32).sin()
+        })
+        .collect()
+}
+
+#[test]
+fn ramps_over_30_db_are_held_at_the_target() {
+    let config = AgcConfig {
+        target_rms: 0.1,
+        release: 2400.0,
+        ..Default::default()
+    };
+    // two seconds at 48 kHz up from -50 dB to -20 dB, and down again
+    for (from_db, to_db) in [(-50.0, -20.0), (-20.0, -50.0)] {
+        let input = ramp(from_db, to_db, 96000);
+        let mut agc = Agc::new(config);
+        let output = agc.process(&input);
+
+        // after converging, every window of 20 ms is within 2 dB of the target
+        for (window, (output, input)) in output
+            .chunks_exact(960)
+            .zip(input.chunks_exact(960))
+            .enumerate()
+            .skip(20)
+        {
+            let error = rms_db(output) + 20.0;
+            assert!(
+                error.abs() < 2.0,
+                "window {window} of {} dB: {error} dB",
+                rms_db(input)
+            );
+        }
+    }
+}
+
+#[test]
+fn held_gain_does_not_follow_the_level() {
+    let mut agc = Agc::new(AgcConfig::default());
+    agc.process(&ramp(-40.0, -40.0, 48000));
+    let gain = agc.get_gain_db();
+    assert!((gain - 20.0).abs() < 0.5, "{gain}");
+
+    // a burst 30 dB louder while held keeps the gain, and its level
+    agc.set_hold(true);
+    assert!(agc.is_held());
+    let loud = ramp(-10.0, -10.0, 4800);
+    let output = agc.process(&loud);
+    assert_eq!(agc.get_gain_db(), gain);
+    assert!((rms_db(&output) - rms_db(&loud) - gain).abs() < 0.01);
+
+    // released, the gain falls within the attack
+    agc.set_hold(false);
+    let output = agc.process(&loud);
+    assert!((rms_db(&output[2400..]) + 20.0).abs() < 0.5);
+
+    agc.reset();
+    assert_eq!(agc.get_gain_db(), 0.0);
+    assert!(!agc.is_held());
+}
+
+#[test]
+fn stream_hears_quiet_frames_with_a_held_gain() {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let payloads = [data(200), data(300), data(100)];
+    // frames with peaks 40 dB apart, the quietest below the squelch, in a quiet noise floor
+    let mut signal = vec![0.0; 9600];
+    for (payload, peak) in payloads.iter().zip([0.01, 1.0, 0.1]) {
+        let frame = modulator.encode_frame(payload);
+        let max = frame.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+        signal.extend(frame.iter().map(|x| peak / max * x));
+        signal.extend([0.0; 9600]);
+    }
+    let mut noise: u32 = 0x1234_5678;
+    for sample in &mut signal {
+        noise ^= noise << 13;
+        noise ^= noise >> 17;
+        noise ^= noise << 5;
+        *sample += 1e-4 * (noise as f32 / u32::MAX as f32 - 0.5);
+    }
+
+    let stream = || {
+        StreamDemodulator::new(
+            CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+            0.05,
+            2400,
+        )
+    };
+
+    // without a gain control, the squelch misses the quietest frame
+    let mut plain = stream();
+    let decoded: Vec<Vec<u8>> = signal
+        .chunks(128)
+        .flat_map(|block| plain.push(block))
+        .collect();
+    assert_eq!(decoded, payloads[1..]);
+    assert_eq!(plain.input_stats().agc_gain_db, 0.0);
+
+    let mut leveled = stream();
+    leveled.set_agc(Some(Agc::new(AgcConfig::default())));
+    let mut decoded = Vec::new();
+    let mut gain = 0.0;
+    let mut held_blocks = 0;
+    for block in signal.chunks(128) {
+        let receiving = leveled.get_sync_state() == SyncState::Receiving;
+        decoded.extend(leveled.push(block));
+        // within a frame the gain stays where it was when the squelch opened
+        if receiving {
+            assert_eq!(leveled.input_stats().agc_gain_db, gain);
+            held_blocks += 1;
+        }
+        gain = leveled.input_stats().agc_gain_db;
+    }
+    assert_eq!(decoded, payloads);
+    assert!(held_blocks > 0);
+    // over the noise floor after the last frame the gain rises to its maximum of 40 dB
+    assert!(gain > 35.0 && gain <= 40.0, "{gain}");
+}
+
+#[test]
+#[should_panic(expected = "Time constants must be at least 1 sample and finite, but got 0.5")]
+fn attack_below_a_sample() {
+    Agc::new(AgcConfig {
+        attack: 0.5,
+        ..Default::default()
+    });
+}
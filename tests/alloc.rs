@@ -0,0 +1,398 @@
+//! Counts the heap allocations of the modulation and demodulation hot paths, with an allocator wrapping the system allocator.
+//!
+//! The real-time calls are also fed garbage and buffers of the wrong length, which must return rather than panic.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+use software_modem::{
+    error::ModemError,
+    ofdm::{
+        OFDMConfig, SlmConfig, SlmSignaling,
+        demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+        modulator::{Clipping, OFDMModulator, OFDMModulatorConfig},
+    },
+    qam::{DemapStrategy, QAMModem, QAMOrder},
+};
+
+/// The system allocator, counting the allocations of every thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// SAFETY: every call is passed on to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // the counter of an exiting thread may be gone already
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        // SAFETY: the caller upholds the contract of alloc
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of dealloc
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        // SAFETY: the caller upholds the contract of realloc
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations `f` makes on this thread.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Checks that modulating symbols of data and demodulating them with reused scratches and outputs allocates nothing.
+fn assert_round_trip_without_allocating(
+    modulator: OFDMModulatorConfig,
+    demodulator: OFDMDemodulatorConfig,
+) {
+    let modulator = OFDMModulator::new(modulator);
+    let demodulator = OFDMDemodulator::new(demodulator);
+    let bytes_per_symbol = modulator.get_bytes_per_symbol();
+    let data = data(8 * bytes_per_symbol);
+    let mut symbols = vec![0.0; 8 * modulator.get_symbol_length()];
+    let mut scratch = modulator.make_scratch();
+    let allocations = count_allocations(|| {
+        for (data, symbol) in data
+            .chunks(bytes_per_symbol)
+            .zip(symbols.chunks_exact_mut(modulator.get_symbol_length()))
+        {
+            modulator.modulate_buffer_as_symbol_with_scratch(data, &mut scratch, symbol);
+        }
+    });
+    assert_eq!(allocations, 0);
+
+    let mut scratch = demodulator.make_scratch();
+    let mut received = vec![0; data.len()];
+    let allocations = count_allocations(|| {
+        for (symbol, output) in symbols
+            .chunks_exact(demodulator.get_symbol_length())
+            .zip(received.chunks_exact_mut(bytes_per_symbol))
+        {
+            demodulator.demodulate_symbol_into(symbol, &mut scratch, output);
+        }
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(received, data);
+
+    // last, as it clobbers the symbols
+    received.fill(0);
+    let allocations = count_allocations(|| {
+        for (symbol, output) in symbols
+            .chunks_exact_mut(demodulator.get_symbol_length())
+            .zip(received.chunks_exact_mut(bytes_per_symbol))
+        {
+            demodulator.demodulate_symbol_in_place(symbol, &mut scratch, output);
+        }
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(received, data);
+}
+
+#[test]
+fn symbols_round_trip_through_scratches_without_allocating() {
+    let config = OFDMConfig {
+        num_subcarriers: 256,
+        cyclic_prefix_length: 32,
+        ..Default::default()
+    };
+    assert_round_trip_without_allocating((&config).into(), (&config).into());
+
+    // power allocation, tone reservation and blind selected mapping need buffers of their own
+    let config = OFDMConfig {
+        reserved_subcarriers: vec![11, 51, 91, 131, 171, 211],
+        ..config
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&config).into()).get_bytes_per_symbol();
+    let config = OFDMConfig {
+        power_allocation: Some(
+            (0..num_data_subcarriers)
+                .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                .collect(),
+        ),
+        ..config
+    };
+    let slm = SlmConfig {
+        candidates: 4,
+        signaling: SlmSignaling::Blind,
+    };
+    assert_round_trip_without_allocating(
+        OFDMModulatorConfig {
+            slm: Some(slm),
+            ..(&config).into()
+        },
+        OFDMDemodulatorConfig {
+            slm: Some(slm),
+            ..(&config).into()
+        },
+    );
+}
+
+#[cfg(feature = "embedded")]
+#[test]
+fn heapless_demodulation_does_not_allocate() {
+    use software_modem::ofdm::embedded::HeaplessOFDMDemodulator;
+
+    let modulator = OFDMModulator::new(
+        (&OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 16,
+            ..Default::default()
+        })
+            .into(),
+    );
+    let data = data(8 * modulator.get_bytes_per_symbol());
+    let mut symbols = Vec::new();
+    modulator.modulate_batch(&data, &mut symbols);
+
+    let mut demodulator = HeaplessOFDMDemodulator::<64, 16>::new(4);
+    let mut received = vec![0; data.len()];
+    let allocations = count_allocations(|| {
+        for (symbol, output) in symbols
+            .chunks_exact(demodulator.get_symbol_length())
+            .zip(received.chunks_exact_mut(demodulator.get_bytes_per_symbol()))
+        {
+            demodulator
+                .try_demodulate_symbol_into(symbol, output)
+                .unwrap();
+        }
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(received, data);
+}
+
+#[test]
+fn qam_demodulation_into_does_not_allocate() {
+    for strategy in [DemapStrategy::Slicer, DemapStrategy::Table] {
+        let modem = QAMModem::with_demap_strategy(QAMOrder::QAM16, strategy);
+        let data = data(1000);
+        let symbols = modem.modulate(&data);
+        let mut output = vec![0; data.len()];
+        assert_eq!(
+            count_allocations(|| modem.demodulate_into(&symbols, &mut output)),
+            0
+        );
+        assert_eq!(output, data);
+    }
+}
+
+/// Configurations using every stage of the modulator and the demodulator which needs buffers of its own.
+fn real_time_configs() -> Vec<OFDMConfig> {
+    let config = OFDMConfig {
+        num_subcarriers: 256,
+        cyclic_prefix_length: 32,
+        ..Default::default()
+    };
+    let reserved = OFDMConfig {
+        reserved_subcarriers: vec![11, 51, 91, 131, 171, 211],
+        ..config.clone()
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&reserved).into()).get_bytes_per_symbol();
+    vec![
+        OFDMConfig {
+            clipping: Some(Clipping::default()),
+            slm: Some(SlmConfig {
+                candidates: 8,
+                signaling: SlmSignaling::Explicit,
+            }),
+            ..config.clone()
+        },
+        OFDMConfig {
+            power_allocation: Some(
+                (0..num_data_subcarriers)
+                    .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                    .collect(),
+            ),
+            slm: Some(SlmConfig {
+                candidates: 4,
+                signaling: SlmSignaling::Blind,
+            }),
+            ..reserved
+        },
+        OFDMConfig {
+            differential_time: true,
+            soft_output: false,
+            ..config
+        },
+    ]
+}
+
+#[test]
+fn real_time_calls_do_not_allocate() {
+    for config in real_time_configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        let demodulator = OFDMDemodulator::new((&config).into());
+        let bytes_per_symbol = modulator.get_bytes_per_symbol();
+        let data = data(8 * bytes_per_symbol);
+        let mut modulator_scratch = modulator.make_scratch();
+        let mut demodulator_scratch = demodulator.make_scratch();
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+        let mut received = vec![0; data.len()];
+
+        let allocations = count_allocations(|| {
+            for (data, output) in data
+                .chunks(bytes_per_symbol)
+                .zip(received.chunks_exact_mut(bytes_per_symbol))
+            {
+                modulator
+                    .try_modulate_buffer_as_symbol_with_scratch(
+                        data,
+                        &mut modulator_scratch,
+                        &mut symbol,
+                    )
+                    .unwrap();
+                demodulator
+                    .try_demodulate_symbol_into(&symbol, &mut demodulator_scratch, output)
+                    .unwrap();
+            }
+        });
+        assert_eq!(allocations, 0, "{:?}", config);
+        if !config.differential_time {
+            assert_eq!(received, data, "{:?}", config);
+        }
+    }
+}
+
+#[test]
+fn real_time_demodulation_of_garbage_does_not_panic() {
+    let mut noise = 0x9e37_79b9u32;
+    let mut random = move || {
+        noise ^= noise << 13;
+        noise ^= noise >> 17;
+        noise ^= noise << 5;
+        noise
+    };
+    for config in real_time_configs() {
+        let demodulator = OFDMDemodulator::new((&config).into());
+        let length = demodulator.get_symbol_length();
+        let mut scratch = demodulator.make_scratch();
+        let mut output = vec![0; demodulator.get_bytes_per_symbol()];
+
+        let mut inputs = vec![
+            vec![0.0; length],
+            vec![f32::NAN; length],
+            vec![f32::INFINITY; length],
+            vec![f32::MAX; length],
+            vec![f32::MIN_POSITIVE / 8.0; length],
+        ];
+        // alternating extremes and pseudo-random bit patterns, which hold every kind of float
+        inputs.push(
+            (0..length)
+                .map(|i| {
+                    if i % 2 == 0 {
+                        f32::MAX
+                    } else {
+                        f32::NEG_INFINITY
+                    }
+                })
+                .collect(),
+        );
+        for _ in 0..64 {
+            inputs.push((0..length).map(|_| f32::from_bits(random())).collect());
+        }
+
+        for input in &inputs {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                demodulator.try_demodulate_symbol_into(input, &mut scratch, &mut output)
+            }));
+            assert_eq!(result.ok(), Some(Ok(())), "{:?}", config);
+        }
+    }
+}
+
+#[test]
+fn real_time_calls_reject_wrong_buffers() {
+    let configs = real_time_configs();
+    let modulator = OFDMModulator::new((&configs[0]).into());
+    let demodulator = OFDMDemodulator::new((&configs[0]).into());
+    let symbol_length = modulator.get_symbol_length();
+    let bytes_per_symbol = modulator.get_bytes_per_symbol();
+    let mut modulator_scratch = modulator.make_scratch();
+    let mut demodulator_scratch = demodulator.make_scratch();
+    let mut symbol = vec![0.0; symbol_length + 1];
+    let mut output = vec![0; bytes_per_symbol + 1];
+    let data = data(bytes_per_symbol + 1);
+
+    assert_eq!(
+        modulator.try_modulate_buffer_as_symbol_with_scratch(
+            &data[1..],
+            &mut modulator_scratch,
+            &mut symbol
+        ),
+        Err(ModemError::BufferLength {
+            expected: symbol_length,
+            got: symbol_length + 1,
+        })
+    );
+    assert_eq!(
+        modulator.try_modulate_buffer_as_symbol_with_scratch(
+            &data,
+            &mut modulator_scratch,
+            &mut symbol[1..]
+        ),
+        Err(ModemError::BufferLength {
+            expected: bytes_per_symbol,
+            got: bytes_per_symbol + 1,
+        })
+    );
+    assert_eq!(
+        demodulator.try_demodulate_symbol_into(
+            &symbol[..symbol_length - 1],
+            &mut demodulator_scratch,
+            &mut output[1..]
+        ),
+        Err(ModemError::BufferLength {
+            expected: symbol_length,
+            got: symbol_length - 1,
+        })
+    );
+    assert_eq!(
+        demodulator.try_demodulate_symbol_into(&symbol[1..], &mut demodulator_scratch, &mut output),
+        Err(ModemError::BufferLength {
+            expected: bytes_per_symbol,
+            got: bytes_per_symbol + 1,
+        })
+    );
+
+    // the scratches of another configuration, with the same FFT length but other subcarriers
+    let other = &configs[1];
+    let mut other_modulator_scratch = OFDMModulator::new(other.into()).make_scratch();
+    let mut other_demodulator_scratch = OFDMDemodulator::new(other.into()).make_scratch();
+    assert_eq!(
+        modulator.try_modulate_buffer_as_symbol_with_scratch(
+            &data[1..],
+            &mut other_modulator_scratch,
+            &mut symbol[1..]
+        ),
+        Err(ModemError::ScratchMismatch)
+    );
+    assert_eq!(
+        demodulator.try_demodulate_symbol_into(
+            &symbol[1..],
+            &mut other_demodulator_scratch,
+            &mut output[1..]
+        ),
+        Err(ModemError::ScratchMismatch)
+    );
+}
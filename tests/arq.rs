@@ -0,0 +1,284 @@
+//! Drives the stop-and-wait ARQ through a scripted in-memory link which loses, duplicates and reorders frames,
+//! and checks that every payload is delivered once and in order, and that the sender gives up after its retries.
+
+use std::{collections::VecDeque, time::Duration};
+
+use software_modem::arq::{
+    ArqConfig, ArqError, ArqFrame, ArqReceiver, ArqSender, ArqTimeout, MAX_ARQ_PAYLOAD, SenderState,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// What the link does to a frame.
+#[derive(Clone, Copy, Debug)]
+enum Fate {
+    Deliver,
+    Lose,
+    Duplicate,
+    /// Holds the frame back until after the next frame of the direction.
+    Delay,
+}
+
+/// One direction of a half-duplex link, which does to every frame what the script says, in order,
+/// and delivers the frames after the script runs out.
+#[derive(Default)]
+struct ScriptedLink {
+    script: VecDeque<Fate>,
+    held: Option<Vec<u8>>,
+}
+
+impl ScriptedLink {
+    fn new(script: &[Fate]) -> Self {
+        ScriptedLink {
+            script: script.iter().copied().collect(),
+            held: None,
+        }
+    }
+
+    /// Sends a frame, and returns the frames which arrive.
+    fn send(&mut self, frame: Vec<u8>) -> Vec<Vec<u8>> {
+        let mut arrived = match self.script.pop_front().unwrap_or(Fate::Deliver) {
+            Fate::Deliver => vec![frame],
+            Fate::Lose => vec![],
+            Fate::Duplicate => vec![frame.clone(), frame],
+            Fate::Delay => {
+                return self.held.replace(frame).into_iter().collect();
+            }
+        };
+        arrived.extend(self.held.take());
+        arrived
+    }
+}
+
+const STEP: u64 = 1200;
+
+fn config() -> ArqConfig {
+    ArqConfig {
+        timeout: ArqTimeout::Samples(4 * STEP),
+        max_retries: 3,
+    }
+}
+
+/// Sends the payloads one after the other through the links, advancing the sender by a step at a time
+/// while it waits, and returns the payloads delivered with the sequence numbers acknowledged.
+fn run(
+    sender: &mut ArqSender,
+    receiver: &mut ArqReceiver,
+    payloads: &[Vec<u8>],
+    data_link: &mut ScriptedLink,
+    ack_link: &mut ScriptedLink,
+) -> (Vec<Vec<u8>>, Vec<u16>) {
+    let mut delivered = Vec::new();
+    let mut acknowledged = Vec::new();
+    for payload in payloads {
+        let mut transmit = Some(sender.send(payload).unwrap());
+        for _ in 0..100 {
+            for frame in transmit
+                .take()
+                .map(|frame| data_link.send(frame))
+                .unwrap_or_default()
+            {
+                let received = receiver.receive(&frame).unwrap();
+                delivered.extend(received.payload);
+                for ack in ack_link.send(received.ack) {
+                    acknowledged.extend(sender.receive(&ack));
+                }
+            }
+            if sender.is_idle() {
+                break;
+            }
+            transmit = sender.advance_samples(STEP);
+        }
+    }
+    (delivered, acknowledged)
+}
+
+#[test]
+fn clean_link() {
+    let payloads: Vec<_> = (0..5).map(|n| data(10 + n)).collect();
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    let (delivered, acknowledged) = run(
+        &mut sender,
+        &mut receiver,
+        &payloads,
+        &mut ScriptedLink::default(),
+        &mut ScriptedLink::default(),
+    );
+    assert_eq!(delivered, payloads);
+    assert_eq!(acknowledged, [0, 1, 2, 3, 4]);
+    assert_eq!(sender.get_retransmissions(), 0);
+    assert_eq!(receiver.get_duplicates(), 0);
+    assert_eq!(receiver.get_last_sequence(), Some(4));
+}
+
+#[test]
+fn lost_frames_are_sent_again() {
+    use Fate::*;
+    let payloads: Vec<_> = (0..3).map(|n| data(20 + n)).collect();
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    // the first frame is lost twice, the ACK of the second one once
+    let (delivered, acknowledged) = run(
+        &mut sender,
+        &mut receiver,
+        &payloads,
+        &mut ScriptedLink::new(&[Lose, Lose, Deliver, Deliver, Deliver, Deliver]),
+        &mut ScriptedLink::new(&[Deliver, Lose, Deliver]),
+    );
+    assert_eq!(delivered, payloads);
+    assert_eq!(acknowledged, [0, 1, 2]);
+    assert_eq!(sender.get_retransmissions(), 3);
+    // the second frame arrived twice, and was acknowledged again the second time
+    assert_eq!(receiver.get_duplicates(), 1);
+}
+
+#[test]
+fn duplicated_acks_are_ignored() {
+    use Fate::*;
+    let payloads: Vec<_> = (0..3).map(|n| data(30 + n)).collect();
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    let (delivered, acknowledged) = run(
+        &mut sender,
+        &mut receiver,
+        &payloads,
+        &mut ScriptedLink::default(),
+        &mut ScriptedLink::new(&[Duplicate, Duplicate, Deliver]),
+    );
+    assert_eq!(delivered, payloads);
+    assert_eq!(acknowledged, [0, 1, 2]);
+    assert_eq!(sender.get_retransmissions(), 0);
+
+    // a late ACK of an earlier frame does not acknowledge the frame waiting
+    let frame = sender.send(&data(5)).unwrap();
+    assert_eq!(
+        sender.receive(&ArqFrame::Ack { sequence: 2 }.to_bytes()),
+        None
+    );
+    assert_eq!(
+        sender.get_state(),
+        SenderState::Waiting {
+            sequence: 3,
+            retries: 0
+        }
+    );
+    let ack = receiver.receive(&frame).unwrap().ack;
+    assert_eq!(sender.receive(&ack), Some(3));
+    assert_eq!(sender.receive(&ack), None);
+}
+
+#[test]
+fn reordered_frames_are_delivered_once() {
+    use Fate::*;
+    let payloads: Vec<_> = (0..3).map(|n| data(40 + n)).collect();
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    // the first frame is held back until its retransmission overtook it, so it arrives after it
+    // and is acknowledged again, the ACK of the second one is held back behind the ACK of its retransmission
+    let (delivered, acknowledged) = run(
+        &mut sender,
+        &mut receiver,
+        &payloads,
+        &mut ScriptedLink::new(&[Delay, Deliver, Deliver, Deliver]),
+        &mut ScriptedLink::new(&[Deliver, Deliver, Delay, Deliver]),
+    );
+    assert_eq!(delivered, payloads);
+    assert_eq!(acknowledged, [0, 1, 2]);
+    assert_eq!(receiver.get_duplicates(), 2);
+    assert_eq!(sender.get_retransmissions(), 2);
+
+    // a frame delayed past the frames after it is still a duplicate
+    let old = ArqFrame::Data {
+        sequence: 0,
+        payload: &payloads[0],
+    }
+    .to_bytes();
+    let received = receiver.receive(&old).unwrap();
+    assert_eq!(received.payload, None);
+    assert_eq!(received.ack, ArqFrame::Ack { sequence: 0 }.to_bytes());
+}
+
+#[test]
+fn sender_gives_up_after_the_retries() {
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    sender.send(&data(8)).unwrap();
+    let mut retransmitted = 0;
+    for _ in 0..100 {
+        retransmitted += usize::from(sender.advance_samples(STEP).is_some());
+    }
+    assert_eq!(retransmitted, 3);
+    assert_eq!(sender.get_state(), SenderState::Failed { sequence: 0 });
+    assert!(sender.is_idle());
+    assert_eq!(sender.advance_samples(10 * STEP), None);
+
+    // the next frame gets the next sequence number, and a receiver which never saw the first one delivers it
+    let frame = sender.send(&data(9)).unwrap();
+    let received = receiver.receive(&frame).unwrap();
+    assert_eq!(received.payload, Some(data(9)));
+    assert_eq!(sender.receive(&received.ack), Some(1));
+}
+
+#[test]
+fn timeout_in_wall_time() {
+    let mut sender = ArqSender::new(ArqConfig {
+        timeout: ArqTimeout::Wall(Duration::from_millis(500)),
+        max_retries: 1,
+    });
+    let frame = sender.send(b"wall").unwrap();
+    // samples do not count towards a timeout in wall time
+    assert_eq!(sender.advance_samples(1_000_000), None);
+    assert_eq!(sender.advance_time(Duration::from_millis(499)), None);
+    assert_eq!(sender.advance_time(Duration::from_millis(1)), Some(frame));
+    assert_eq!(sender.advance_time(Duration::from_millis(500)), None);
+    assert_eq!(sender.get_state(), SenderState::Failed { sequence: 0 });
+}
+
+#[test]
+fn sequence_numbers_wrap_around() {
+    let mut sender = ArqSender::new(config());
+    let mut receiver = ArqReceiver::new();
+    for n in 0..70000u32 {
+        let frame = sender.send(&n.to_be_bytes()).unwrap();
+        let received = receiver.receive(&frame).unwrap();
+        assert_eq!(received.payload.as_deref(), Some(&n.to_be_bytes()[..]));
+        assert_eq!(sender.receive(&received.ack), Some(n as u16));
+    }
+    assert_eq!(receiver.get_duplicates(), 0);
+}
+
+#[test]
+fn frames_and_errors() {
+    let frame = ArqFrame::Data {
+        sequence: 0x1234,
+        payload: b"abc",
+    };
+    assert_eq!(frame.to_bytes(), b"I\x12\x34abc");
+    assert_eq!(ArqFrame::from_bytes(&frame.to_bytes()), Some(frame));
+    assert_eq!(
+        ArqFrame::from_bytes(b"A\x00\x07"),
+        Some(ArqFrame::Ack { sequence: 7 })
+    );
+    // other traffic on the link
+    for other in [&b""[..], b"I\x00", b"A\x00\x07x", b"M\x00\x00"] {
+        assert_eq!(ArqFrame::from_bytes(other), None, "{other:?}");
+        assert_eq!(ArqReceiver::new().receive(other), None);
+    }
+
+    let mut sender = ArqSender::new(ArqConfig::default());
+    assert_eq!(
+        sender.send(&vec![0; MAX_ARQ_PAYLOAD + 1]),
+        Err(ArqError::PayloadTooLong(MAX_ARQ_PAYLOAD + 1))
+    );
+    sender.send(&vec![0; MAX_ARQ_PAYLOAD]).unwrap();
+    assert_eq!(sender.send(b"next"), Err(ArqError::Busy { sequence: 0 }));
+    assert_eq!(
+        ArqError::Busy { sequence: 0 }.to_string(),
+        "Sender still waits for the ACK of frame 0"
+    );
+}
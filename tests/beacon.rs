@@ -0,0 +1,224 @@
+//! Checks that frames of the [beacon profile](software_modem::ofdm::profiles::robust_beacon) decode below the noise,
+//! and that a [stream](software_modem::stream::StreamDemodulator) listening for beacons decodes them
+//! from the same capture as the frames of its main profile, and switches to the profile a beacon advertises.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    fec::FecScheme,
+    frame::{CodingConfig, HeaderCode},
+    ofdm::{
+        OFDMConfig,
+        modulator::OutputScale,
+        profiles::{beacon_payload, parse_beacon, robust_beacon},
+    },
+    rng::SimulationRng,
+    stream::StreamDemodulator,
+};
+
+/// The silence after every frame of a capture, twice the hang of the squelch.
+const GAP: usize = 4800;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Returns the beacon profile, normalized like the main one.
+fn beacon() -> (OFDMConfig, CodingConfig) {
+    let (ofdm, coding) = robust_beacon();
+    let ofdm = OFDMConfig {
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..ofdm
+    };
+    (ofdm, coding)
+}
+
+fn main_profile() -> (OFDMConfig, CodingConfig) {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    (ofdm, coding)
+}
+
+/// Returns the frames one after another, each followed by the gap, with a faint noise far below the squelch level.
+fn capture(frames: &[Vec<f32>], seed: u64) -> Vec<f32> {
+    let mut samples = vec![0.0; 1000];
+    for frame in frames {
+        samples.extend(frame);
+        samples.extend([0.0; GAP]);
+    }
+    let mut rng = SimulationRng::new(seed);
+    for sample in &mut samples {
+        *sample += 0.001 * rng.gaussian() as f32;
+    }
+    samples
+}
+
+fn stream(ofdm: OFDMConfig, coding: CodingConfig) -> StreamDemodulator {
+    let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, GAP / 2);
+    let (beacon_ofdm, beacon_coding) = beacon();
+    stream.listen_for_beacons(CodedOFDMDemodulator::new(beacon_ofdm, beacon_coding));
+    stream
+}
+
+#[test]
+fn beacons_decode_below_the_noise() {
+    let (ofdm, coding) = robust_beacon();
+    assert_eq!(coding.scheme, FecScheme::RepeatedConvolutional(4));
+    assert_eq!(coding.scheme.rate(), 0.125);
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding.clone());
+    let (main_ofdm, main_coding) = main_profile();
+    let payload = beacon_payload(&main_ofdm, &main_coding);
+
+    // at -12 dB over the whole band, the 8 subcarriers in use are 3 dB below their noise
+    for seed in 0..10 {
+        let mut frame = modulator.encode_frame(&payload);
+        AwgnChannel::new(-12.0, seed).apply(&mut frame);
+        let decoded = demodulator.decode_frame(&frame).unwrap();
+        assert_eq!(
+            parse_beacon(&decoded),
+            Ok((main_ofdm.clone(), main_coding.clone()))
+        );
+    }
+
+    // which a single convolutional code does not survive
+    let plain = CodingConfig {
+        scheme: FecScheme::RepeatedConvolutional(1),
+        header_code: HeaderCode::RepeatedConvolutional(1),
+        ..coding.clone()
+    };
+    let (ofdm, _) = robust_beacon();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), plain.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, plain.clone());
+    let lost = (0..10)
+        .filter(|&seed| {
+            let mut frame = modulator.encode_frame(&payload);
+            AwgnChannel::new(-12.0, seed).apply(&mut frame);
+            demodulator.decode_frame(&frame).is_err()
+        })
+        .count();
+    assert_eq!(lost, 10);
+
+    // the repetitions of the header are serialized with the coding
+    assert_eq!(CodingConfig::from_bytes(&coding.to_bytes()), Ok(coding));
+    assert_eq!(CodingConfig::from_bytes(&plain.to_bytes()), Ok(plain));
+}
+
+#[test]
+fn beacons_and_frames_share_a_capture() {
+    let (ofdm, coding) = main_profile();
+    let (beacon_ofdm, beacon_coding) = beacon();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let beacon_modulator = CodedOFDMModulator::new(beacon_ofdm, beacon_coding);
+
+    let payloads = [data(300), b"second frame".to_vec(), data(50)];
+    let beacons = [b"station 7".to_vec(), beacon_payload(&ofdm, &coding)];
+    let samples = capture(
+        &[
+            modulator.encode_frame(&payloads[0]),
+            beacon_modulator.encode_frame(&beacons[0]),
+            modulator.encode_frame(&payloads[1]),
+            modulator.encode_frame(&payloads[2]),
+            beacon_modulator.encode_frame(&beacons[1]),
+        ],
+        1,
+    );
+
+    for block_length in [128, 4096] {
+        let mut stream = stream(ofdm.clone(), coding.clone());
+        let mut decoded = Vec::new();
+        let mut heard = Vec::new();
+        for block in samples.chunks(block_length) {
+            decoded.extend(stream.push(block));
+            heard.extend(stream.take_beacons());
+        }
+        assert_eq!(decoded, payloads);
+        assert_eq!(heard, beacons);
+        assert!(stream.take_beacons().is_empty());
+        // the main profile does not decode the beacons
+        assert_eq!(
+            (
+                stream.get_frames_decoded(),
+                stream.get_frames_failed(),
+                stream.get_beacons_decoded()
+            ),
+            (3, 2, 2)
+        );
+    }
+
+    // a beacon cut off by the end of the stream is decoded by the flush
+    let mut stream = stream(ofdm, coding);
+    let end = samples.len() - GAP;
+    let mut decoded = stream.push(&samples[..end]);
+    assert_eq!(stream.take_beacons(), beacons[..1]);
+    decoded.extend(stream.flush());
+    assert_eq!(decoded, payloads);
+    assert_eq!(stream.take_beacons(), beacons[1..]);
+}
+
+#[test]
+fn a_link_bootstraps_from_a_beacon() {
+    let (ofdm, coding) = main_profile();
+    let (beacon_ofdm, beacon_coding) = beacon();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let beacon_modulator = CodedOFDMModulator::new(beacon_ofdm, beacon_coding);
+
+    let payloads = [data(200), b"after the beacon".to_vec(), data(20)];
+    let samples = capture(
+        &[
+            modulator.encode_frame(&payloads[0]),
+            beacon_modulator.encode_frame(&beacon_payload(&ofdm, &coding)),
+            modulator.encode_frame(&payloads[1]),
+            modulator.encode_frame(&payloads[2]),
+        ],
+        2,
+    );
+
+    // the receiver starts on another profile, with a longer symbol
+    let mut stream = stream(
+        OFDMConfig {
+            num_subcarriers: 128,
+            cyclic_prefix_length: 32,
+            ..ofdm.clone()
+        },
+        coding.clone(),
+    );
+    let mut decoded = Vec::new();
+    for block in samples.chunks(256) {
+        decoded.extend(stream.push(block));
+        for beacon in stream.take_beacons() {
+            let (ofdm, coding) = parse_beacon(&beacon).unwrap();
+            stream.set_demodulator(CodedOFDMDemodulator::new(ofdm, coding));
+        }
+    }
+    // the frame before the beacon is lost, the ones after it decode
+    assert_eq!(decoded, payloads[1..]);
+    assert_eq!(stream.get_frames_failed(), 2);
+    assert_eq!(stream.get_beacons_decoded(), 1);
+}
+
+#[test]
+#[should_panic(
+    expected = "Number of repetitions of the convolutional code must be between 1 and 7, but got 8"
+)]
+fn repetitions_of_the_convolutional_code_are_limited() {
+    let (ofdm, coding) = robust_beacon();
+    CodedOFDMModulator::new(
+        ofdm,
+        CodingConfig {
+            header_code: HeaderCode::RepeatedConvolutional(8),
+            ..coding
+        },
+    );
+}
@@ -0,0 +1,117 @@
+//! Regression tests of the [BER sweep](software_modem::testing::ber_sweep): a pinned point in the middle
+//! of the waterfall curve of uncoded QAM-16, the independence of the result from the threads, and the stop rules.
+
+use std::sync::Arc;
+
+use software_modem::{
+    channel::{AwgnChannel, ChannelChain, MultipathChannel},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    testing::{SweepConfig, SweepStop, ber_sweep},
+};
+
+fn config() -> SweepConfig {
+    SweepConfig {
+        ofdm: OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn uncoded_qam16_at_16_db() {
+    let points = ber_sweep(&SweepConfig {
+        snr_start_db: 16.0,
+        snr_stop_db: 16.0,
+        // 1000 errors put ±10 % at 3 sigma, about 150 frames
+        stop: SweepStop::BitErrors {
+            bit_errors: 1000,
+            max_frames: 1000,
+        },
+        confidence: 0.997,
+        seed: 0,
+        ..config()
+    });
+    assert_eq!(points.len(), 1);
+    let point = &points[0];
+    assert!(point.meter.bit_errors() >= 1000);
+    assert_eq!(point.meter.num_bits(), point.meter.num_frames() * 8 * 400);
+    // pinned, the curve is at 1.98e-3 over 4000 frames
+    let (low, high) = point.confidence_interval;
+    assert!(low < 2.0e-3 && 2.0e-3 < high, "{low} {high}");
+    assert!(
+        high - low < 0.2 * point.bit_error_rate,
+        "{} {low} {high}",
+        point.bit_error_rate
+    );
+    // about 7 errors a frame, hardly one is without
+    assert!(point.frame_error_rate > 0.95, "{}", point.frame_error_rate);
+}
+
+#[test]
+fn threads_do_not_change_the_result() {
+    let sweep = |threads| {
+        ber_sweep(&SweepConfig {
+            snr_start_db: 10.0,
+            snr_stop_db: 18.0,
+            stop: SweepStop::Frames(4),
+            threads,
+            seed: 7,
+            ..config()
+        })
+    };
+    let single = sweep(1);
+    assert_eq!(
+        single.iter().map(|point| point.snr_db).collect::<Vec<_>>(),
+        [10.0, 12.0, 14.0, 16.0, 18.0]
+    );
+    assert_eq!(sweep(3), single);
+    assert_eq!(sweep(8), single);
+}
+
+#[test]
+fn points_stop_at_the_bit_errors() {
+    let points = ber_sweep(&SweepConfig {
+        snr_start_db: 10.0,
+        snr_stop_db: 20.0,
+        snr_step_db: 5.0,
+        stop: SweepStop::BitErrors {
+            bit_errors: 100,
+            max_frames: 20,
+        },
+        ..config()
+    });
+    // a frame at 10 dB has hundreds of errors, the ones at 20 dB hardly any
+    assert_eq!(points[0].meter.num_frames(), 1);
+    assert!(points[1].meter.bit_errors() >= 100 && points[1].meter.num_frames() > 1);
+    assert_eq!(points[2].meter.num_frames(), 20);
+    assert!(points[2].meter.bit_errors() < 100);
+}
+
+#[test]
+fn coded_frames_over_multipath() {
+    let points = ber_sweep(&SweepConfig {
+        ofdm: OFDMConfig {
+            differential_time: true,
+            ..config().ofdm
+        },
+        coding: Some(CodingConfig::default()),
+        channel: Arc::new(|snr_db, seed| {
+            ChannelChain::new()
+                .with(MultipathChannel::two_ray(3, -6.0))
+                .with(AwgnChannel::new(snr_db, seed))
+        }),
+        snr_start_db: 6.0,
+        snr_stop_db: 24.0,
+        snr_step_db: 18.0,
+        stop: SweepStop::Frames(5),
+        ..config()
+    });
+    // the frames are lost as a whole, or all arrive
+    assert_eq!(points[0].frame_error_rate, 1.0);
+    assert_eq!(points[0].bit_error_rate, 1.0);
+    assert_eq!(points[1].meter.frame_errors(), 0);
+}
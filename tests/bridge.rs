@@ -0,0 +1,163 @@
+//! Wires two pseudo-terminal bridges back to back through a simulated channel, and sends bytes across both ways.
+//!
+//! The test needs the `bridge` feature: `cargo test --features bridge`.
+
+#![cfg(all(feature = "bridge", any(target_os = "linux", target_os = "macos")))]
+
+use std::{
+    convert::Infallible,
+    fs::OpenOptions,
+    io::{Read, Write},
+    path::Path,
+    sync::mpsc::{self, Receiver, SyncSender},
+    time::Duration,
+};
+
+use software_modem::{
+    bridge::pty::{BridgeConfig, PtyBridge},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    io::{SampleSink, SampleSource},
+    ofdm::{OFDMConfig, modulator::OutputScale},
+};
+
+/// The sending side of one direction of the channel, which attenuates the samples and adds noise.
+struct ChannelSink {
+    blocks: SyncSender<Vec<f32>>,
+    noise: u32,
+}
+
+impl SampleSink for ChannelSink {
+    type Error = Infallible;
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Infallible> {
+        let block = samples
+            .iter()
+            .map(|sample| {
+                self.noise ^= self.noise << 13;
+                self.noise ^= self.noise >> 17;
+                self.noise ^= self.noise << 5;
+                0.3 * sample + 0.002 * (self.noise as f32 / u32::MAX as f32 - 0.5)
+            })
+            .collect();
+        // like sound into an empty room, nobody has to listen
+        let _ = self.blocks.send(block);
+        Ok(())
+    }
+}
+
+/// The receiving side of one direction of the channel, which ends when the sending side is dropped.
+struct ChannelSource {
+    blocks: Receiver<Vec<f32>>,
+    pending: Vec<f32>,
+    position: usize,
+}
+
+impl SampleSource for ChannelSource {
+    type Error = Infallible;
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<usize, Infallible> {
+        if self.position == self.pending.len() {
+            match self.blocks.recv() {
+                Ok(block) => {
+                    self.pending = block;
+                    self.position = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let count = buffer.len().min(self.pending.len() - self.position);
+        buffer[..count].copy_from_slice(&self.pending[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// One direction of the channel, delayed by `delay` samples.
+fn channel(delay: usize, seed: u32) -> (ChannelSink, ChannelSource) {
+    let (sender, receiver) = mpsc::sync_channel(16);
+    (
+        ChannelSink {
+            blocks: sender,
+            noise: seed,
+        },
+        ChannelSource {
+            blocks: receiver,
+            pending: vec![0.0; delay],
+            position: 0,
+        },
+    )
+}
+
+fn bridge(sink: ChannelSink, source: ChannelSource) -> PtyBridge {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 256,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    PtyBridge::open(
+        CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()),
+        CodedOFDMDemodulator::new(ofdm, CodingConfig::default()),
+        sink,
+        source,
+        &BridgeConfig::default(),
+    )
+    .unwrap()
+}
+
+/// Writes the data into one terminal on a thread, and checks that it comes out of the other.
+fn transfer(from: &Path, to: &Path, data: &[u8]) {
+    let mut writer = OpenOptions::new().write(true).open(from).unwrap();
+    let mut reader = OpenOptions::new().read(true).open(to).unwrap();
+    let (chunks, received) = mpsc::channel();
+    let length = data.len();
+    std::thread::spawn(move || {
+        let mut total = 0;
+        while total < length {
+            let mut chunk = vec![0; 4096];
+            let count = reader.read(&mut chunk).unwrap();
+            chunk.truncate(count);
+            total += count;
+            if chunks.send(chunk).is_err() {
+                break;
+            }
+        }
+    });
+    // far more than the queue of the bridge, the writer blocks while it is full
+    let sent = data.to_vec();
+    let writer = std::thread::spawn(move || writer.write_all(&sent).unwrap());
+
+    let mut output = Vec::with_capacity(length);
+    while output.len() < length {
+        match received.recv_timeout(Duration::from_secs(30)) {
+            Ok(chunk) => output.extend(chunk),
+            Err(_) => panic!("Received {} of {} bytes", output.len(), length),
+        }
+    }
+    writer.join().unwrap();
+    assert!(
+        output == data,
+        "The received bytes differ from the sent bytes"
+    );
+}
+
+#[test]
+fn bridges_carry_bytes_both_ways() {
+    let (sink_a, source_b) = channel(1234, 0x1234_5678);
+    let (sink_b, source_a) = channel(567, 0x8765_4321);
+    let a = bridge(sink_a, source_a);
+    let b = bridge(sink_b, source_b);
+
+    let data: Vec<u8> = (0..320 * 1024u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    transfer(a.get_path(), b.get_path(), &data[..256 * 1024]);
+    // the other way once the link has turned around
+    transfer(b.get_path(), a.get_path(), &data[256 * 1024..]);
+
+    // closing one end ends the source of the other
+    a.close().unwrap();
+    b.close().unwrap();
+}
@@ -0,0 +1,119 @@
+//! Checks that the modulator tells which buffer of a symbol has the wrong length before it modulates anything,
+//! and that the symbols it allocates are the ones of the buffers.
+
+use software_modem::{
+    error::{Buffer, ModemError},
+    ofdm::{OFDMConfig, modulator::OFDMModulator},
+};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn configs() -> Vec<OFDMConfig> {
+    let base = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    };
+    vec![
+        base.clone(),
+        OFDMConfig {
+            cyclic_prefix_length: 32,
+            oversampling: 2,
+            ..base.clone()
+        },
+        OFDMConfig {
+            num_subcarriers: 256,
+            pilot_subcarrier_every: 8,
+            ..base
+        },
+    ]
+}
+
+#[test]
+fn wrong_buffers_are_told_apart() {
+    for config in configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        let bytes = modulator.get_bytes_per_symbol();
+        let length = modulator.get_symbol_length();
+        let payload = data(bytes);
+
+        let mut symbol = vec![0.0; length];
+        assert_eq!(
+            modulator.try_modulate_buffer_as_symbol(&payload, &mut symbol),
+            Ok(())
+        );
+        let mut expected = vec![0.0; length];
+        modulator.modulate_buffer_as_symbol(&payload, &mut expected);
+        assert_eq!(symbol, expected);
+
+        for got in [0, bytes - 1, bytes + 1] {
+            let mut untouched = vec![7.0; length];
+            assert_eq!(
+                modulator.try_modulate_buffer_as_symbol(&data(got), &mut untouched),
+                Err(ModemError::BufferLengthMismatch {
+                    buffer: Buffer::Input,
+                    expected: bytes,
+                    got,
+                })
+            );
+            assert!(untouched.iter().all(|&x| x == 7.0));
+        }
+
+        for got in [0, length - 1, length + 1, 2 * length] {
+            let mut untouched = vec![7.0; got];
+            assert_eq!(
+                modulator.try_modulate_buffer_as_symbol(&payload, &mut untouched),
+                Err(ModemError::BufferLengthMismatch {
+                    buffer: Buffer::Output,
+                    expected: length,
+                    got,
+                })
+            );
+            assert!(untouched.iter().all(|&x| x == 7.0));
+        }
+
+        // the data is checked first
+        assert!(matches!(
+            modulator.try_modulate_buffer_as_symbol(&[], &mut []),
+            Err(ModemError::BufferLengthMismatch {
+                buffer: Buffer::Input,
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn allocated_symbols_are_padded() {
+    for config in configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        let bytes = modulator.get_bytes_per_symbol();
+        for length in [0, 1, bytes / 2, bytes] {
+            let mut padded = data(length);
+            padded.resize(bytes, 0);
+            let mut expected = vec![0.0; modulator.get_symbol_length()];
+            modulator.modulate_buffer_as_symbol(&padded, &mut expected);
+            assert_eq!(modulator.modulate_symbol(&data(length)), Ok(expected));
+        }
+
+        assert_eq!(
+            modulator.modulate_symbol(&data(bytes + 1)),
+            Err(ModemError::BufferLengthMismatch {
+                buffer: Buffer::Input,
+                expected: bytes,
+                got: bytes + 1,
+            })
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "Output buffer length must be 144, but got 128")]
+fn short_output_buffer() {
+    let modulator = OFDMModulator::new((&configs()[0]).into());
+    modulator.modulate_buffer_as_symbol(&data(24), &mut [0.0; 128]);
+}
@@ -0,0 +1,173 @@
+//! Runs the level calibration sequence through speakers of several gains into converters that clip,
+//! and checks that the recommended level lies the headroom below the clip point, that frames sent at it
+//! do not clip, and that the tilt of the channel is measured.
+
+use software_modem::{
+    calibration::{CalibrationConfig, CalibrationSequence},
+    channel::{AwgnChannel, Channel, ChannelChain, QuantizeClip},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::{Deemphasis, EmphasisConfig},
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OFDMModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    }
+}
+
+fn sequence() -> CalibrationSequence {
+    CalibrationSequence::new(
+        &OFDMModulator::new((&config()).into()),
+        CalibrationConfig::default(),
+    )
+}
+
+/// Plays the samples through a speaker of the gain, after a delay, into a 16-bit converter clipping at the level,
+/// over some noise.
+fn record(samples: &[f32], gain_db: f32, clip_level: f32) -> (Vec<f32>, QuantizeClip) {
+    let gain = 10f32.powf(gain_db / 20.0);
+    let mut recording = vec![0.0; 1234];
+    recording.extend(samples.iter().map(|x| gain * x));
+    recording.extend([0.0; 500]);
+    AwgnChannel::with_reference_power(60.0, 0.01, 7).apply(&mut recording);
+    let mut converter = QuantizeClip::new(16, clip_level, None);
+    converter.apply(&mut recording);
+    (recording, converter)
+}
+
+#[test]
+fn recommendation_below_the_clip_point() {
+    let sequence = sequence();
+    let headroom_db = sequence.get_config().headroom_db;
+    for (gain_db, clip_level) in [(12.0, 1.0), (3.0, 1.0), (20.0, 0.5), (9.0, 0.25)] {
+        let clip_point_dbfs = 20.0 * f32::log10(clip_level) - gain_db;
+        let (recording, _) = record(&sequence.samples(), gain_db, clip_level);
+        let result = sequence.analyze(&recording);
+
+        let clipping_step = result.clipping_step.unwrap();
+        assert!(
+            result.steps[clipping_step].sent_dbfs > clip_point_dbfs,
+            "{gain_db} {clip_level} {result:?}"
+        );
+        assert!(
+            result.steps[..clipping_step]
+                .iter()
+                .all(|step| !step.clipped)
+        );
+        let clip_level_dbfs = result.clip_level_dbfs.unwrap();
+        assert!(
+            (clip_level_dbfs - clip_point_dbfs).abs() < 1.0,
+            "{gain_db} {clip_level} {result:?}"
+        );
+        // below the clip point by the headroom, give or take the extrapolation
+        let recommended = result.recommended_level_dbfs;
+        assert!(
+            recommended <= clip_point_dbfs - headroom_db + 0.5
+                && recommended > clip_point_dbfs - headroom_db - 1.0,
+            "{gain_db} {clip_level} {recommended}"
+        );
+        assert!(result.expected_snr_db > 30.0, "{result:?}");
+    }
+}
+
+#[test]
+fn frames_at_the_recommended_level_do_not_clip() {
+    let sequence = sequence();
+    let (recording, _) = record(&sequence.samples(), 15.0, 1.0);
+    let result = sequence.analyze(&recording);
+
+    let ofdm = OFDMConfig {
+        output_scale: result.get_output_scale(),
+        ..config()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    let payload = data(500);
+    let frame = modulator.encode_frame(&payload);
+    let peak = frame.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    assert!((20.0 * peak.log10() - result.recommended_level_dbfs).abs() < 1e-3);
+
+    let (recording, converter) = record(&frame, 15.0, 1.0);
+    assert_eq!(converter.get_clipped(), 0);
+    let received = &recording[1234..1234 + frame.len()];
+    assert_eq!(demodulator.decode_frame(received).unwrap(), payload);
+}
+
+#[test]
+fn quiet_speaker_does_not_clip() {
+    let sequence = sequence();
+    let (recording, converter) = record(&sequence.samples(), -10.0, 1.0);
+    assert_eq!(converter.get_clipped(), 0);
+    let result = sequence.analyze(&recording);
+    assert_eq!(result.clipping_step, None);
+    assert_eq!(result.clip_level_dbfs, None);
+    assert!(result.steps.iter().all(|step| !step.clipped));
+    // only the full scale of the transmitter limits it
+    assert_eq!(
+        result.recommended_level_dbfs,
+        -sequence.get_config().headroom_db
+    );
+
+    // the steps rise with their level, above the noise
+    for pair in result.steps.windows(2) {
+        let rise = pair[1].rms_dbfs - pair[0].rms_dbfs;
+        assert!((rise - 6.0).abs() < 0.5, "{result:?}");
+    }
+    assert!(
+        result.noise_dbfs < -75.0 && result.noise_dbfs > -85.0,
+        "{result:?}"
+    );
+    assert!(result.tilt.db_per_octave.abs() < 0.5, "{:?}", result.tilt);
+}
+
+#[test]
+fn tilt_of_a_speaker_rolling_off() {
+    let sequence = sequence();
+    let mut samples = sequence.samples();
+    Deemphasis::new(EmphasisConfig {
+        corner: 0.05,
+        boost_db: 12.0,
+    })
+    .process_in_place(&mut samples);
+    let (recording, _) = record(&samples, 0.0, 1.0);
+
+    let tilt = sequence.analyze(&recording).tilt;
+    assert!(tilt.db_per_octave < -2.0, "{tilt:?}");
+    let (first, last) = (tilt.response[0], tilt.response[tilt.response.len() - 1]);
+    assert!(first.1 - last.1 > 8.0, "{tilt:?}");
+
+    // the same steps through a chain without a tilt
+    let mut flat = sequence.samples();
+    ChannelChain::new().apply(&mut flat);
+    let (recording, _) = record(&flat, 0.0, 1.0);
+    assert!(sequence.analyze(&recording).tilt.db_per_octave.abs() < 0.5);
+}
+
+#[test]
+#[should_panic(expected = "Loudest step must not be above full scale, but got 6 dBFS")]
+fn steps_above_full_scale() {
+    CalibrationSequence::new(
+        &OFDMModulator::new((&config()).into()),
+        CalibrationConfig {
+            steps: 9,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "Recording must be at least as long as the sequence")]
+fn short_recording() {
+    let sequence = sequence();
+    sequence.analyze(&vec![0.0; sequence.get_length() - 1]);
+}
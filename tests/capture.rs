@@ -0,0 +1,225 @@
+//! Checks that a [capture](software_modem::io::capture) of the input of a receiver replays to the payloads and
+//! [LinkStats] of the receiver that recorded it, that its markers note the sync events, that the capture in
+//! `tests/data/capture.smcp` still replays to the frames it was recorded with, and that broken captures are told apart.
+//!
+//! After an intended change of the recorded session, `WRITE_GOLDEN=1 cargo test --test capture` records it again.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    io::capture::{
+        CaptureChunk, CaptureError, CaptureMetadata, CaptureReader, CaptureWriter, MarkerKind,
+        create_capture, open_capture, profile_hash,
+    },
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    stream::{LinkStats, StreamDemodulator},
+    testing::golden::WRITE_GOLDEN,
+};
+
+const CAPTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/capture.smcp");
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+fn stream() -> StreamDemodulator {
+    StreamDemodulator::new(
+        CodedOFDMDemodulator::new(ofdm(), CodingConfig::default()),
+        0.01,
+        480,
+    )
+}
+
+fn metadata() -> CaptureMetadata {
+    CaptureMetadata::new(48000, "capture-test", &ofdm(), &CodingConfig::default())
+}
+
+/// The payloads of the session, whose second frame is corrupted.
+fn payloads() -> [Vec<u8>; 3] {
+    [data(60), data(90).split_off(40), data(30)]
+}
+
+/// A session of three frames in quiet noise, the second one corrupted in its middle,
+/// and the last one still being received at the end.
+fn session() -> Vec<f32> {
+    let modulator = CodedOFDMModulator::new(ofdm(), CodingConfig::default());
+    let mut signal = vec![0.0; 700];
+    for (i, payload) in payloads().iter().enumerate() {
+        let mut frame = modulator.encode_frame(payload);
+        if i == 1 {
+            let middle = frame.len() / 2;
+            frame[middle..middle + 400]
+                .iter_mut()
+                .for_each(|x| *x = -*x);
+        }
+        signal.extend(frame);
+        signal.extend(vec![0.0; if i == 2 { 300 } else { 1500 }]);
+    }
+    AwgnChannel::with_reference_power(35.0, 0.01, 7).apply(&mut signal);
+    signal
+}
+
+/// Receives the session in blocks of varying lengths, like the callbacks of a device,
+/// recording them into the capture, and returns the payloads with the stats after the end of the input.
+fn receive_live<W: std::io::Write>(
+    capture: &mut CaptureWriter<W>,
+) -> (StreamDemodulator, Vec<Vec<u8>>) {
+    let signal = session();
+    let mut live = stream();
+    let mut payloads = Vec::new();
+    let mut rest = signal.as_slice();
+    for length in [128, 441, 480, 1000, 37].into_iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (block, next) = rest.split_at(length.min(rest.len()));
+        payloads.extend(live.push_recorded(block, capture).unwrap());
+        rest = next;
+    }
+    payloads.extend(live.flush());
+    (live, payloads)
+}
+
+#[test]
+fn replay_matches_the_live_receiver() {
+    let mut capture = CaptureWriter::new(Vec::new(), &metadata()).unwrap();
+    let (live, decoded) = receive_live(&mut capture);
+    assert_eq!(capture.get_samples_written(), session().len() as u64);
+    let bytes = capture.finish().unwrap();
+    let [first, _, last] = payloads();
+    assert_eq!(decoded, [first, last]);
+
+    let mut reader = CaptureReader::new(bytes.as_slice()).unwrap();
+    let mut replayed = stream();
+    assert_eq!(replayed.replay(&mut reader).unwrap(), decoded);
+    assert_eq!(replayed.stats(), live.stats());
+    assert_eq!(replayed.input_stats(), live.input_stats());
+
+    // the blocks come back as they were pushed, between the markers of the events of the live receiver
+    let chunks: Vec<CaptureChunk> = CaptureReader::new(bytes.as_slice())
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let samples: Vec<f32> = chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            CaptureChunk::Samples { samples, .. } => Some(samples.clone()),
+            CaptureChunk::Marker(_) => None,
+        })
+        .flatten()
+        .collect();
+    assert_eq!(samples, session());
+    let markers: Vec<_> = chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            CaptureChunk::Marker(marker) => Some(marker),
+            CaptureChunk::Samples { .. } => None,
+        })
+        .collect();
+    assert!(markers.is_sorted_by_key(|marker| marker.sample));
+    let count = |kind| markers.iter().filter(|marker| marker.kind == kind).count() as u64;
+    // the last frame ends at the end of the input, after the markers
+    let stats = live.stats();
+    assert_eq!(count(MarkerKind::SyncAcquired), stats.sync_acquisitions);
+    assert_eq!(count(MarkerKind::SyncLost), stats.sync_losses - 1);
+    assert_eq!(count(MarkerKind::FrameDecoded), stats.frames_decoded - 1);
+    assert_eq!(
+        count(MarkerKind::FrameFailed),
+        stats.frames_attempted - stats.frames_decoded
+    );
+}
+
+#[test]
+fn fixture_replays_to_the_recorded_frames() {
+    if std::env::var_os(WRITE_GOLDEN).is_some() {
+        let mut capture = create_capture(CAPTURE, &metadata()).unwrap();
+        receive_live(&mut capture);
+        capture.finish().unwrap();
+    }
+
+    let mut reader = open_capture(CAPTURE).unwrap();
+    let metadata = reader.get_metadata().clone();
+    assert_eq!(metadata.sample_rate, 48000);
+    assert_eq!(metadata.profile_name, "capture-test");
+    assert_eq!(
+        metadata.profile_hash,
+        profile_hash(&ofdm(), &CodingConfig::default())
+    );
+
+    let mut replayed = stream();
+    let [first, _, last] = payloads();
+    assert_eq!(replayed.replay(&mut reader).unwrap(), [first, last]);
+    assert_eq!(
+        replayed.stats(),
+        LinkStats {
+            frames_attempted: 3,
+            frames_decoded: 2,
+            frames_crc_failed: 1,
+            frames_fec_corrected: 0,
+            symbols_demodulated: 13,
+            sync_acquisitions: 3,
+            sync_losses: 3,
+            bytes_delivered: 90,
+            samples_discarded: 2352,
+        }
+    );
+}
+
+#[test]
+fn broken_captures() {
+    let mut bytes = CaptureWriter::new(Vec::new(), &metadata())
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert!(matches!(
+        CaptureReader::new(&bytes[..4]),
+        Err(CaptureError::InvalidFile)
+    ));
+    assert!(matches!(
+        CaptureReader::new(&b"RIFF\x01"[..]),
+        Err(CaptureError::InvalidFile)
+    ));
+    let mut version = bytes.clone();
+    version[4] = 2;
+    assert!(matches!(
+        CaptureReader::new(version.as_slice()),
+        Err(CaptureError::UnsupportedVersion(2))
+    ));
+
+    // chunks of other ids are skipped, and a capture cut off within a chunk keeps the whole ones
+    let mut capture = CaptureWriter::new(Vec::new(), &metadata()).unwrap();
+    capture.write_samples_at(1234, &[0.5, -0.25]).unwrap();
+    bytes = capture.finish().unwrap();
+    bytes.extend(b"note");
+    bytes.extend(5u32.to_le_bytes());
+    bytes.extend(b"hello");
+    bytes.extend(b"mark");
+    bytes.extend(9u32.to_le_bytes());
+    bytes.extend([0; 6]);
+    let mut reader = CaptureReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(
+        reader.read_chunk().unwrap(),
+        Some(CaptureChunk::Samples {
+            time_us: 1234,
+            samples: vec![0.5, -0.25]
+        })
+    );
+    assert!(matches!(
+        reader.read_chunk(),
+        Err(CaptureError::TruncatedChunk)
+    ));
+    assert!(reader.read_chunk().unwrap().is_none());
+}
@@ -0,0 +1,131 @@
+//! Checks the statistics of the noise of the AWGN channel over long buffers: its power against the requested SNR,
+//! its mean, its shape, and that the seed alone decides it.
+
+use realfft::num_complex::Complex32;
+use software_modem::channel::{AwgnChannel, Channel};
+
+const LENGTH: usize = 1 << 20;
+
+/// A full scale sine of power 1/2, which is not a multiple of the length of a buffer.
+fn sine(length: usize) -> Vec<f32> {
+    (0..length).map(|i| (i as f32 * 0.0371).sin()).collect()
+}
+
+/// Returns the mean, the power and the fourth moment of the noise added to the signal.
+fn moments(noise: impl Iterator<Item = f64>) -> (f64, f64, f64) {
+    let (mut count, mut sum, mut power, mut fourth) = (0.0, 0.0, 0.0, 0.0);
+    for x in noise {
+        count += 1.0;
+        sum += x;
+        power += x * x;
+        fourth += x * x * x * x;
+    }
+    (sum / count, power / count, fourth / count)
+}
+
+#[test]
+fn noise_power_matches_the_snr() {
+    let signal = sine(LENGTH);
+    let signal_power = signal.iter().map(|&x| f64::from(x * x)).sum::<f64>() / LENGTH as f64;
+    for snr_db in [-5.0, 0.0, 6.0, 20.0] {
+        let mut samples = signal.clone();
+        AwgnChannel::new(snr_db, 0x5eed).apply(&mut samples);
+        let (mean, power, fourth) =
+            moments(samples.iter().zip(&signal).map(|(x, y)| f64::from(x - y)));
+
+        let expected = signal_power / 10f64.powf(f64::from(snr_db) / 10.0);
+        // the standard error of the power of 2^20 Gaussian values is 0.14 %
+        assert!(
+            (power / expected - 1.0).abs() < 0.01,
+            "{} dB: noise power {}, expected {}",
+            snr_db,
+            power,
+            expected
+        );
+        assert!(
+            mean.abs() < 0.01 * expected.sqrt(),
+            "{} dB: mean {}",
+            snr_db,
+            mean
+        );
+        // a Gaussian has a kurtosis of 3, a uniform distribution 1.8
+        let kurtosis = fourth / (power * power);
+        assert!(
+            (kurtosis - 3.0).abs() < 0.05,
+            "{} dB: kurtosis {}",
+            snr_db,
+            kurtosis
+        );
+    }
+}
+
+#[test]
+fn complex_noise_splits_over_the_components() {
+    let signal: Vec<Complex32> = (0..LENGTH)
+        .map(|i| Complex32::from_polar(2.0, i as f32 * 0.0371))
+        .collect();
+    let mut samples = signal.clone();
+    AwgnChannel::new(10.0, 3).apply_complex(&mut samples);
+
+    // 10 dB below a power of 4, half of it on each component
+    for component in [|x: Complex32| x.re, |x: Complex32| x.im] {
+        let (mean, power, _) = moments(
+            samples
+                .iter()
+                .zip(&signal)
+                .map(|(&x, &y)| f64::from(component(x - y))),
+        );
+        assert!((power / 0.2 - 1.0).abs() < 0.01, "power {}", power);
+        assert!(mean.abs() < 0.01 * 0.2f64.sqrt(), "mean {}", mean);
+    }
+}
+
+#[test]
+fn reference_power_keeps_the_noise_level() {
+    // the measured power of every buffer sets the noise of that buffer, the reference power of all the same
+    let mut measured = AwgnChannel::new(10.0, 9);
+    let mut referenced = AwgnChannel::with_reference_power(10.0, 0.5, 9);
+    let (loud, mut quiet) = (sine(LENGTH / 2), sine(LENGTH / 2));
+    quiet.iter_mut().for_each(|x| *x *= 0.1);
+
+    let noise_power = |channel: &mut AwgnChannel, signal: &[f32]| {
+        let mut samples = signal.to_vec();
+        channel.apply(&mut samples);
+        moments(samples.iter().zip(signal).map(|(x, y)| f64::from(x - y))).1
+    };
+    let (loud_measured, quiet_measured) = (
+        noise_power(&mut measured, &loud),
+        noise_power(&mut measured, &quiet),
+    );
+    assert!((loud_measured / quiet_measured / 100.0 - 1.0).abs() < 0.02);
+    let (loud_referenced, quiet_referenced) = (
+        noise_power(&mut referenced, &loud),
+        noise_power(&mut referenced, &quiet),
+    );
+    assert!((loud_referenced / 0.05 - 1.0).abs() < 0.02);
+    assert!((quiet_referenced / 0.05 - 1.0).abs() < 0.02);
+
+    // silence gets noise only with a reference
+    let mut silence = vec![0.0; 1000];
+    measured.apply(&mut silence);
+    assert!(silence.iter().all(|&x| x == 0.0));
+    referenced.apply(&mut silence);
+    assert!(silence.iter().all(|&x| x != 0.0));
+    // and an empty buffer is left alone
+    measured.apply(&mut []);
+    referenced.apply_complex(&mut []);
+}
+
+#[test]
+fn the_seed_decides_the_noise() {
+    let run = |seed: u64| {
+        let mut channel = AwgnChannel::new(0.0, seed);
+        let mut samples = sine(10_000);
+        for block in samples.chunks_mut(333) {
+            channel.apply(block);
+        }
+        samples
+    };
+    assert_eq!(run(1), run(1));
+    assert_ne!(run(1), run(2));
+}
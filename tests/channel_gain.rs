@@ -0,0 +1,107 @@
+//! Checks that the coherent equalizer divides the points by the mean magnitude of all pilots,
+//! so a flat gain is undone, a fade on a data subcarrier leaves the points of the others in place,
+//! and a fade on a pilot only moves the mean a little.
+
+use realfft::{RealFftPlanner, num_complex::Complex32};
+use software_modem::ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    }
+}
+
+/// Scales one bin of the symbol after its cyclic prefix, and copies the end of the symbol into the prefix again.
+fn fade_bin(symbol: &mut [f32], bin: usize, gain: f32) {
+    let cyclic_prefix_length = config().cyclic_prefix_length as usize;
+    let fft_length = symbol.len() - cyclic_prefix_length;
+    let mut planner = RealFftPlanner::<f32>::new();
+    let mut spectrum = vec![Complex32::default(); fft_length / 2 + 1];
+    planner
+        .plan_fft_forward(fft_length)
+        .process(&mut symbol[cyclic_prefix_length..].to_vec(), &mut spectrum)
+        .unwrap();
+    spectrum[bin] *= gain;
+    planner
+        .plan_fft_inverse(fft_length)
+        .process(&mut spectrum, &mut symbol[cyclic_prefix_length..])
+        .unwrap();
+    for sample in symbol[cyclic_prefix_length..].iter_mut() {
+        *sample /= fft_length as f32;
+    }
+    symbol.copy_within(fft_length.., 0);
+}
+
+#[test]
+fn a_flat_gain_is_undone() {
+    let modulator = OFDMModulator::new((&config()).into());
+    let demodulator = OFDMDemodulator::new((&config()).into());
+    let symbol = modulator
+        .modulate_symbol(&data(modulator.get_bytes_per_symbol()))
+        .unwrap();
+    let (_, expected) = demodulator.demodulate_symbol_with_points(&symbol);
+
+    let faded: Vec<f32> = symbol.iter().map(|sample| sample * 0.05).collect();
+    let (_, points) = demodulator.demodulate_symbol_with_points(&faded);
+    for (point, expected) in points.iter().zip(&expected) {
+        assert!(
+            (point - expected).norm() < 1e-3,
+            "{point} against {expected}"
+        );
+    }
+}
+
+#[test]
+fn a_fade_on_one_subcarrier_stays_on_it() {
+    let modulator = OFDMModulator::new((&config()).into());
+    let demodulator = OFDMDemodulator::new((&config()).into());
+    let payload = data(modulator.get_bytes_per_symbol());
+    let symbol = modulator.modulate_symbol(&payload).unwrap();
+    let (_, expected) = demodulator.demodulate_symbol_with_points(&symbol);
+
+    // the data subcarriers, every fourth one is a pilot
+    for bin in (1..64).filter(|bin| bin % 4 != 0) {
+        let mut faded = symbol.clone();
+        fade_bin(&mut faded, bin, 0.05);
+        let (_, points) = demodulator.demodulate_symbol_with_points(&faded);
+        let moved = points
+            .iter()
+            .zip(&expected)
+            .filter(|(point, expected)| (*point - *expected).norm() > 0.05)
+            .count();
+        assert!(moved <= 1, "a fade on bin {bin} moved {moved} points");
+    }
+}
+
+#[test]
+fn a_fade_on_one_pilot_lowers_the_mean_of_all() {
+    let modulator = OFDMModulator::new((&config()).into());
+    let demodulator = OFDMDemodulator::new((&config()).into());
+    let symbol = modulator
+        .modulate_symbol(&data(modulator.get_bytes_per_symbol()))
+        .unwrap();
+    let (_, expected) = demodulator.demodulate_symbol_with_points(&symbol);
+
+    // 15 pilots, one of which keeps 0.05 of its magnitude
+    let gain = 15.0 / 14.05;
+    for bin in (4..64).step_by(4) {
+        let mut faded = symbol.clone();
+        fade_bin(&mut faded, bin, 0.05);
+        let (_, points) = demodulator.demodulate_symbol_with_points(&faded);
+        for (point, expected) in points.iter().zip(&expected) {
+            assert!(
+                (point - expected * gain).norm() < 1e-3,
+                "a fade on bin {bin}: {point} against {}",
+                expected * gain
+            );
+        }
+    }
+}
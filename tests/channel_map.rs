@@ -0,0 +1,309 @@
+//! Checks the [channel maps](ChannelMap) of the audio devices: two independent payloads through a stereo
+//! interleaved buffer, with the helpers, the transmitter and receiver and a WAV file, the modem on one channel
+//! next to a sync tone, and the errors of maps that do not fit the device.
+//!
+//! The test needs the `audio` feature: `cargo test --features audio`.
+
+#![cfg(feature = "audio")]
+
+use std::time::Duration;
+
+use software_modem::{
+    audio::{
+        AudioError, AudioReceiver, AudioTransmitter, ChannelLayout, ChannelMap, DeviceConfig,
+        ReceiverConfig, SyncTone, TransmitterConfig, interleaved_to_mono, mono_to_interleaved,
+    },
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    stream::StreamDemodulator,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+fn modulator() -> CodedOFDMModulator {
+    CodedOFDMModulator::new(ofdm(), CodingConfig::default())
+}
+
+fn demodulator() -> CodedOFDMDemodulator {
+    CodedOFDMDemodulator::new(ofdm(), CodingConfig::default())
+}
+
+const STEREO: DeviceConfig = DeviceConfig {
+    sample_rate: 48000,
+    channels: 2,
+};
+
+fn independent() -> ChannelLayout {
+    ChannelLayout {
+        device: STEREO,
+        map: ChannelMap::Independent,
+    }
+}
+
+/// Pushes every stream into a streaming demodulator of its own and returns what they decode.
+fn demodulate_streams(streams: Vec<Vec<f32>>) -> Vec<Vec<Vec<u8>>> {
+    streams
+        .iter()
+        .map(|samples| {
+            let mut stream = StreamDemodulator::new(demodulator(), 0.01, 2400);
+            let mut decoded = Vec::new();
+            for block in samples.chunks(480) {
+                decoded.extend(stream.push(block));
+            }
+            decoded.extend(stream.flush());
+            decoded
+        })
+        .collect()
+}
+
+#[test]
+fn independent_payloads_round_trip_through_a_stereo_buffer() {
+    let left = data(100);
+    let right = data(300).split_off(150);
+    // frames of different lengths, the right one starting later
+    let left_frame = modulator().encode_frame(&left);
+    let mut right_frame = vec![0.0; 1000];
+    right_frame.extend(modulator().encode_frame(&right));
+    assert_ne!(left_frame.len(), right_frame.len());
+
+    let mut interleaved = mono_to_interleaved(&[&left_frame, &right_frame], &independent());
+    assert_eq!(
+        interleaved.len(),
+        2 * right_frame.len().max(left_frame.len())
+    );
+    interleaved.extend(vec![0.0; 2 * 4800]);
+
+    let streams = interleaved_to_mono(&interleaved, &independent());
+    assert_eq!(streams.len(), 2);
+    assert_eq!(demodulate_streams(streams), [vec![left], vec![right]]);
+}
+
+#[test]
+fn duplicate_and_single_maps_carry_one_stream() {
+    let payload = data(120);
+    let frame = modulator().encode_frame(&payload);
+
+    // every channel carries the frame, and their mean is the frame again
+    let layout = ChannelLayout {
+        device: DeviceConfig {
+            sample_rate: 48000,
+            channels: 3,
+        },
+        map: ChannelMap::Duplicate,
+    };
+    let interleaved = mono_to_interleaved(&[&frame], &layout);
+    assert!(
+        interleaved
+            .chunks(3)
+            .zip(&frame)
+            .all(|(samples, &sample)| samples == [sample; 3])
+    );
+    let mono = interleaved_to_mono(&interleaved, &layout);
+    assert!(
+        mono[0]
+            .iter()
+            .zip(&frame)
+            .all(|(a, b)| (a - b).abs() < 1e-6)
+    );
+
+    // the modem on the left, a 1 kHz tone as loud on the right
+    let tone = SyncTone {
+        frequency: 1000.0,
+        amplitude: 0.5,
+    };
+    let layout = ChannelLayout {
+        device: STEREO,
+        map: ChannelMap::Single {
+            channel: 0,
+            tone: Some(tone),
+        },
+    };
+    let mut interleaved = mono_to_interleaved(&[&frame], &layout);
+    for (n, pair) in interleaved.chunks(2).enumerate() {
+        let expected = 0.5 * (std::f32::consts::TAU * n as f32 / 48.0).sin();
+        assert!((pair[1] - expected).abs() < 1e-4, "{n}: {}", pair[1]);
+    }
+    interleaved.extend(vec![0.0; 2 * 4800]);
+    // the receiver hears the modem alone
+    let streams = interleaved_to_mono(&interleaved, &layout);
+    assert_eq!(demodulate_streams(streams), [vec![payload.clone()]]);
+    // while the mean of both channels drowns it in the tone
+    let mixed = interleaved_to_mono(
+        &interleaved,
+        &ChannelLayout {
+            device: STEREO,
+            map: ChannelMap::Duplicate,
+        },
+    );
+    assert_ne!(demodulate_streams(mixed), [vec![payload]]);
+}
+
+#[test]
+fn independent_transmitters_and_receivers_share_a_stereo_device() {
+    let config = TransmitterConfig {
+        device: STEREO,
+        channel_map: ChannelMap::Independent,
+        ..Default::default()
+    };
+    let (transmitters, mut output) =
+        AudioTransmitter::new_streams(vec![modulator(), modulator()], &config).unwrap();
+    let config = ReceiverConfig {
+        device: STEREO,
+        channel_map: ChannelMap::Independent,
+        ..Default::default()
+    };
+    let (receivers, mut input) =
+        AudioReceiver::new_streams(vec![demodulator(), demodulator()], &config).unwrap();
+
+    // two frames on the left, while the right one sends one and falls silent
+    let payloads = [data(40), data(200), data(90)];
+    transmitters[0].send(&payloads[0]).unwrap();
+    transmitters[1].send(&payloads[1]).unwrap();
+    transmitters[0].send(&payloads[2]).unwrap();
+    drop(transmitters);
+    while !output.is_finished() {
+        let mut block = [0i16; 2 * 480];
+        output.fill(&mut block);
+        input.push(&block);
+    }
+    drop(input);
+
+    let timeout = Duration::from_secs(10);
+    assert_eq!(
+        receivers[0].recv_timeout(timeout).as_ref(),
+        Some(&payloads[0])
+    );
+    assert_eq!(
+        receivers[0].recv_timeout(timeout).as_ref(),
+        Some(&payloads[2])
+    );
+    assert_eq!(
+        receivers[1].recv_timeout(timeout).as_ref(),
+        Some(&payloads[1])
+    );
+    for receiver in &receivers {
+        assert_eq!(receiver.recv_timeout(timeout), None);
+        assert_eq!(receiver.metrics().frames_failed, 0);
+    }
+}
+
+#[test]
+fn maps_that_do_not_fit_the_device() {
+    let single = |channel| TransmitterConfig {
+        device: STEREO,
+        channel_map: ChannelMap::Single {
+            channel,
+            tone: None,
+        },
+        ..Default::default()
+    };
+    assert!(AudioTransmitter::new(modulator(), &single(1)).is_ok());
+    assert!(matches!(
+        AudioTransmitter::new(modulator(), &single(2)),
+        Err(AudioError::ChannelOutOfRange {
+            channel: 2,
+            channels: 2
+        })
+    ));
+
+    // two streams need two modulators
+    let config = TransmitterConfig {
+        device: STEREO,
+        channel_map: ChannelMap::Independent,
+        ..Default::default()
+    };
+    assert!(matches!(
+        AudioTransmitter::new(modulator(), &config),
+        Err(AudioError::StreamCount {
+            expected: 2,
+            got: 1
+        })
+    ));
+    let config = ReceiverConfig {
+        device: STEREO,
+        channel_map: ChannelMap::Independent,
+        ..Default::default()
+    };
+    let demodulators = vec![demodulator(), demodulator(), demodulator()];
+    assert!(matches!(
+        AudioReceiver::new_streams(demodulators, &config),
+        Err(AudioError::StreamCount {
+            expected: 2,
+            got: 3
+        })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Number of streams must be 2, but got 1")]
+fn interleaving_too_few_streams() {
+    mono_to_interleaved(&[&[0.0; 10]], &independent());
+}
+
+#[test]
+#[should_panic(expected = "Buffer length must be a multiple of 2 channels, but got 5")]
+fn deinterleaving_a_partial_frame() {
+    interleaved_to_mono(&[0.0; 5], &independent());
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn independent_payloads_round_trip_through_a_stereo_wav_file() {
+    use software_modem::io::{
+        WavChannel, WavReadOptions, WavWriteOptions, read_wav_interleaved, read_wav_with,
+        write_wav_interleaved,
+    };
+
+    let payloads = [data(70), data(150)];
+    let frames: Vec<Vec<f32>> = payloads
+        .iter()
+        .map(|payload| {
+            let mut frame = modulator().encode_frame(payload);
+            frame.extend(vec![0.0; 4800]);
+            frame
+        })
+        .collect();
+    let interleaved = mono_to_interleaved(&[&frames[0], &frames[1]], &independent());
+
+    let path = std::env::temp_dir().join("software_modem_test_channel_map.wav");
+    let options = WavWriteOptions {
+        channels: 2,
+        ..Default::default()
+    };
+    write_wav_interleaved(&path, &interleaved, 48000, &options).unwrap();
+    let (read, channels, sample_rate) =
+        read_wav_interleaved(&path, &WavReadOptions::default()).unwrap();
+    assert_eq!(
+        (read.len(), channels, sample_rate),
+        (interleaved.len(), 2, 48000)
+    );
+    let streams = interleaved_to_mono(&read, &independent());
+    assert_eq!(
+        demodulate_streams(streams),
+        [vec![payloads[0].clone()], vec![payloads[1].clone()]]
+    );
+
+    // a single channel of the file is a stream too
+    let options = WavReadOptions {
+        channel: WavChannel::Index(1),
+        ..Default::default()
+    };
+    let (right, _) = read_wav_with(&path, &options).unwrap();
+    assert_eq!(demodulate_streams(vec![right]), [vec![payloads[1].clone()]]);
+    std::fs::remove_file(&path).unwrap();
+}
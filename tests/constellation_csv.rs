@@ -0,0 +1,170 @@
+//! Checks the CSV of a [ConstellationRecorder] by parsing it back, after a loopback of known payloads
+//! through a clean and a noisy channel.
+
+use software_modem::{
+    analysis::ConstellationRecorder,
+    channel::{AwgnChannel, Channel},
+    frame::{FrameDecoder, FrameEncoder},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::{QAMModem, QAMOrder},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+/// The CSV of a recorder, split into its rows of numbers and its summary.
+struct Parsed {
+    header: String,
+    rows: Vec<(u64, u32, f32, f32, usize, f32)>,
+    summary: Vec<(String, String)>,
+}
+
+fn parse(csv: &str) -> Parsed {
+    let mut lines = csv.lines();
+    let header = lines.next().unwrap().to_string();
+    let mut rows = Vec::new();
+    let mut summary = Vec::new();
+    for line in lines {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let (key, value) = comment.split_once(',').unwrap();
+            summary.push((key.to_string(), value.to_string()));
+            continue;
+        }
+        assert!(summary.is_empty(), "row after the summary: {line}");
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(fields.len(), 6, "{line}");
+        rows.push((
+            fields[0].parse().unwrap(),
+            fields[1].parse().unwrap(),
+            fields[2].parse().unwrap(),
+            fields[3].parse().unwrap(),
+            fields[4].parse().unwrap(),
+            fields[5].parse().unwrap(),
+        ));
+    }
+    Parsed {
+        header,
+        rows,
+        summary,
+    }
+}
+
+/// Records the constellation of a frame of the payload, through a channel at the SNR if any.
+fn record(payload: &[u8], snr_db: Option<f32>, capacity: usize) -> (ConstellationRecorder, Parsed) {
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config()).into()));
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config()).into()));
+    let mut frame = encoder.encode(payload);
+    if let Some(snr_db) = snr_db {
+        AwgnChannel::new(snr_db, 5).apply(&mut frame);
+    }
+
+    let mut recorder =
+        ConstellationRecorder::new(QAMOrder::QAM16, decoder.get_data_subcarriers(), capacity);
+    for (index, points) in decoder.get_constellation(&frame).iter().enumerate() {
+        recorder.record_symbol(index as u64, points);
+    }
+    let mut csv = Vec::new();
+    recorder.write_to(&mut csv).unwrap();
+    let parsed = parse(&String::from_utf8(csv).unwrap());
+    (recorder, parsed)
+}
+
+#[test]
+fn clean_loopback_round_trips() {
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config()).into()));
+    let subcarriers = decoder.get_data_subcarriers().to_vec();
+    assert_eq!(subcarriers.len(), 48);
+    // 24 bytes a symbol, 48 points of 4 bits
+    let payload = data(3 * 24);
+    let (recorder, parsed) = record(&payload, None, 10_000);
+
+    assert_eq!(parsed.header, "symbol,subcarrier,re,im,index,evm_percent");
+    assert_eq!(parsed.rows.len(), 3 * 48);
+    assert_eq!(parsed.rows.len(), recorder.get_points().len());
+
+    // every symbol runs over the data subcarriers in order, and decides the points that were sent
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    let sent = modem.modulate(&payload);
+    let indices = modem.nearest_indices(&sent);
+    for (i, &(symbol, subcarrier, re, im, index, evm)) in parsed.rows.iter().enumerate() {
+        assert_eq!(symbol, (i / 48) as u64);
+        assert_eq!(subcarrier, subcarriers[i % 48]);
+        assert_eq!(index, indices[i]);
+        assert!((re - sent[i].re).abs() < 1e-3 && (im - sent[i].im).abs() < 1e-3);
+        assert!(evm < 0.1, "{evm}% at row {i}");
+    }
+
+    // the summary, with a subcarrier line for every data subcarrier
+    assert_eq!(parsed.summary[0], ("dropped".into(), "0".into()));
+    assert_eq!(parsed.summary[1].0, "rms_evm_percent");
+    assert_eq!(parsed.summary[2].0, "rms_evm_db");
+    assert!(parsed.summary[2].1.parse::<f32>().unwrap() < -60.0);
+    assert_eq!(
+        parsed.summary[3],
+        ("subcarrier".into(), "evm_percent".into())
+    );
+    let listed: Vec<u32> = parsed.summary[4..]
+        .iter()
+        .map(|(subcarrier, _)| subcarrier.parse().unwrap())
+        .collect();
+    assert_eq!(listed, subcarriers);
+}
+
+#[test]
+fn noise_shows_in_the_summary() {
+    let (recorder, parsed) = record(&data(20 * 24), Some(20.0), 10_000);
+    assert_eq!(parsed.rows.len(), 20 * 48);
+
+    // the RMS EVM of the channel, which counts the power of every subcarrier, and the mean of its subcarriers
+    let rms: f32 = parsed.summary[1].1.parse().unwrap();
+    let rms_db: f32 = parsed.summary[2].1.parse().unwrap();
+    assert!((rms - recorder.rms_evm_percent()).abs() < 1e-4);
+    assert!((rms_db - 20.0 * (rms / 100.0).log10()).abs() < 1e-3);
+    assert!(-26.0 < rms_db && rms_db < -14.0, "{rms_db} dB");
+
+    let subcarriers: Vec<f32> = parsed.summary[4..]
+        .iter()
+        .map(|(_, evm)| evm.parse().unwrap())
+        .collect();
+    assert_eq!(subcarriers.len(), 48);
+    let mean_power = subcarriers.iter().map(|evm| evm * evm).sum::<f32>() / 48.0;
+    assert!((mean_power.sqrt() - rms).abs() < 1e-3 * rms);
+
+    // and the RMS of the column of the rows
+    let column =
+        parsed.rows.iter().map(|row| row.5 * row.5).sum::<f32>() / parsed.rows.len() as f32;
+    assert!((column.sqrt() - rms).abs() < 1e-3 * rms);
+}
+
+#[test]
+fn points_beyond_the_cap_are_dropped() {
+    let (recorder, parsed) = record(&data(3 * 24), None, 100);
+    assert!(recorder.is_full());
+    assert_eq!(parsed.rows.len(), 100);
+    assert_eq!(recorder.get_dropped(), 3 * 48 - 100);
+    assert_eq!(parsed.summary[0], ("dropped".into(), "44".into()));
+    // the third symbol is cut short
+    assert_eq!(parsed.rows.last().unwrap().0, 2);
+}
+
+#[test]
+#[should_panic(
+    expected = "Symbol must have a point for each of the 48 data subcarriers, but got 47"
+)]
+fn symbols_have_a_point_for_every_subcarrier() {
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config()).into()));
+    let mut recorder =
+        ConstellationRecorder::new(QAMOrder::QAM16, decoder.get_data_subcarriers(), 100);
+    recorder.record_symbol(0, &[Default::default(); 47]);
+}
@@ -0,0 +1,102 @@
+//! Pins the trellis of the K=7 convolutional code: its output against the test vector of IEEE 802.11a,
+//! its free distance, and that the Viterbi decoder corrects every burst within half of it but not a burst beyond.
+
+use software_modem::{bits::bytes_to_bits, fec::convolutional::ConvolutionalCode};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn bits(text: &str) -> Vec<u8> {
+    text.bytes().map(|c| c - b'0').collect()
+}
+
+#[test]
+fn the_k7_code_matches_the_802_11_test_vector() {
+    // the SIGNAL field of the example packet of IEEE 802.11a, annex G, 36 Mbit/s and 100 bytes,
+    // which ends in its own 6 tail bits
+    let signal = bits("101100010011000000000000");
+    let expected = bits("110100011010000100000010001111100111000000000000");
+
+    // 802.11 sends the output of 133 first, then of 171
+    let ieee = ConvolutionalCode::new(7, &[0o133, 0o171]);
+    let coded = ieee.encode(&signal);
+    assert_eq!(coded.len(), expected.len() + 12);
+    assert_eq!(&coded[..expected.len()], expected);
+    assert!(coded[expected.len()..].iter().all(|&bit| bit == 0));
+
+    // the same code, with the outputs of every input bit the other way around
+    let code = ConvolutionalCode::k7_rate_half();
+    let swapped: Vec<u8> = code
+        .encode(&signal)
+        .chunks(2)
+        .flat_map(|pair| [pair[1], pair[0]])
+        .collect();
+    assert_eq!(swapped, coded);
+    assert_eq!(ieee.decode(&coded), signal);
+}
+
+#[test]
+fn the_free_distance_is_10() {
+    let code = ConvolutionalCode::k7_rate_half();
+    // every terminated input of up to 12 bits starting with a 1, the code being linear and time-invariant
+    let weights = (1u32..1 << 12).map(|input| {
+        let length = 32 - input.leading_zeros();
+        let bits: Vec<u8> = (0..length).rev().map(|i| (input >> i) as u8 & 1).collect();
+        code.encode(&bits)
+            .iter()
+            .map(|&bit| u32::from(bit))
+            .sum::<u32>()
+    });
+    assert_eq!(weights.min(), Some(10));
+    assert_eq!(
+        code.encode(&[1]).iter().filter(|&&bit| bit == 1).count(),
+        10
+    );
+}
+
+#[test]
+fn bursts_within_half_the_free_distance_are_corrected() {
+    let code = ConvolutionalCode::k7_rate_half();
+    let bits = bytes_to_bits(&data(40));
+    let coded = code.encode(&bits);
+
+    for start in 0..coded.len() - 4 {
+        let mut received = coded.clone();
+        received[start..start + 4]
+            .iter_mut()
+            .for_each(|bit| *bit ^= 1);
+        assert_eq!(code.decode(&received), bits, "burst at {start}");
+    }
+}
+
+#[test]
+fn bursts_beyond_the_free_distance_are_not() {
+    let code = ConvolutionalCode::k7_rate_half();
+    let bits = bytes_to_bits(&data(40));
+    let coded = code.encode(&bits);
+
+    // the code word of the data with one bit flipped differs by the impulse response, 10 bits within 14
+    let step = 100;
+    let mut wrong = bits.clone();
+    wrong[step] ^= 1;
+    let impulse: Vec<usize> = code
+        .encode(&[1])
+        .iter()
+        .enumerate()
+        .filter(|&(_, &bit)| bit == 1)
+        .map(|(i, _)| 2 * step + i)
+        .collect();
+    assert_eq!(impulse.len(), 10);
+
+    // 6 of them leave the received bits closer to the wrong code word, all of them turn the bits into it
+    for flipped in [6, 10] {
+        let mut received = coded.clone();
+        for &i in &impulse[..flipped] {
+            received[i] ^= 1;
+        }
+        assert_eq!(code.decode(&received), wrong, "{flipped} bits");
+    }
+}
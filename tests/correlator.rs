@@ -0,0 +1,140 @@
+//! Checks the SIMD and overlap-save correlations against the scalar kernel on random templates and samples,
+//! of lengths around the blocks of the SIMD kernels and of overlap-save, and the detection of a template in noise.
+
+use software_modem::{
+    correlator::{CorrelationMethod, Correlator, OVERLAP_SAVE_MIN_LENGTH, correlate_scalar},
+    rng::SimulationRng,
+};
+
+fn gaussian(rng: &mut SimulationRng, length: usize) -> Vec<f32> {
+    (0..length).map(|_| rng.gaussian() as f32).collect()
+}
+
+/// Checks the correlation of the method against the scalar kernel, to the rounding of the sums of the products.
+fn assert_matches_scalar(method: CorrelationMethod, template: &[f32], samples: &[f32]) {
+    let correlator = Correlator::with_method(template, method);
+    let correlation = correlator.correlate(samples);
+    let mut expected = vec![0.0; correlator.get_output_length(samples.len())];
+    correlate_scalar(template, samples, &mut expected);
+    assert_eq!(correlation.len(), expected.len());
+
+    for (offset, (value, expected)) in correlation.iter().zip(&expected).enumerate() {
+        let magnitude: f32 = template
+            .iter()
+            .zip(&samples[offset..])
+            .map(|(t, x)| (t * x).abs())
+            .sum();
+        assert!(
+            (value - expected).abs() <= 1e-5 * magnitude,
+            "{method:?} of {} samples into {}: offset {offset}, {value} vs {expected}",
+            template.len(),
+            samples.len()
+        );
+    }
+}
+
+#[test]
+fn direct_correlation_matches_the_scalar_kernel() {
+    let mut rng = SimulationRng::new(1);
+    for length in [1, 2, 3, 7, 8, 31, 64, 100, 143] {
+        let template = gaussian(&mut rng, length);
+        // every number of offsets up to past two whole blocks of 4 times 8 lanes, for the tails
+        for offsets in 0..70 {
+            let samples = gaussian(&mut rng, length - 1 + offsets);
+            assert_matches_scalar(CorrelationMethod::Direct, &template, &samples);
+        }
+        let samples = gaussian(&mut rng, 5000);
+        assert_matches_scalar(CorrelationMethod::Direct, &template, &samples);
+        // shorter than the template
+        let samples = gaussian(&mut rng, length / 2);
+        assert!(
+            Correlator::with_method(&template, CorrelationMethod::Direct)
+                .correlate(&samples)
+                .is_empty()
+        );
+    }
+}
+
+#[test]
+fn overlap_save_matches_the_scalar_kernel() {
+    let mut rng = SimulationRng::new(2);
+    for length in [1, 5, 64, 255, 256, 512, 700] {
+        let template = gaussian(&mut rng, length);
+        let block = (4 * length).next_power_of_two();
+        // within the first block, at its end, and over blocks with a partial last one
+        for num_samples in [
+            length,
+            length + 1,
+            block - 1,
+            block,
+            block + 1,
+            3 * block + 17,
+        ] {
+            let samples = gaussian(&mut rng, num_samples);
+            assert_matches_scalar(CorrelationMethod::OverlapSave, &template, &samples);
+        }
+        assert!(
+            Correlator::with_method(&template, CorrelationMethod::OverlapSave)
+                .correlate(&[])
+                .is_empty()
+        );
+    }
+}
+
+#[test]
+fn the_method_follows_the_template_length() {
+    for (length, method) in [
+        (64, CorrelationMethod::Direct),
+        (OVERLAP_SAVE_MIN_LENGTH - 1, CorrelationMethod::Direct),
+        (OVERLAP_SAVE_MIN_LENGTH, CorrelationMethod::OverlapSave),
+        (512, CorrelationMethod::OverlapSave),
+    ] {
+        assert_eq!(CorrelationMethod::auto(length), method);
+        assert_eq!(Correlator::new(&vec![1.0; length]).get_method(), method);
+    }
+}
+
+#[test]
+fn the_template_is_found_in_noise() {
+    let mut rng = SimulationRng::new(3);
+    for length in [64, 512] {
+        let template = gaussian(&mut rng, length);
+        let correlator = Correlator::new(&template);
+
+        // noise alone stays well below the template, whose normalized correlation with noise is about 1 / sqrt(length)
+        let mut samples: Vec<f32> = gaussian(&mut rng, 20_000).iter().map(|x| 0.5 * x).collect();
+        assert_eq!(correlator.find(&samples, 0.6), None);
+
+        // a copy at half the level, and a louder one later, where the first one is found
+        for (start, gain) in [(7000, 0.5), (15_000, 2.0)] {
+            for (sample, t) in samples[start..].iter_mut().zip(&template) {
+                *sample += gain * t;
+            }
+        }
+        let (offset, coefficient) = correlator.find(&samples, 0.6).unwrap();
+        assert_eq!(offset, 7000);
+        assert!(coefficient > 0.6 && coefficient < 0.8, "{coefficient}");
+        let (offset, coefficient) = correlator.find(&samples[8000..], 0.6).unwrap();
+        assert_eq!(offset, 7000);
+        assert!(coefficient > 0.95, "{coefficient}");
+
+        // a negated copy correlates negatively
+        let negated: Vec<f32> = template.iter().map(|x| -x).collect();
+        assert_eq!(correlator.find(&negated, 0.0), None);
+        let (offset, coefficient) = correlator.find(&template, 0.99).unwrap();
+        assert_eq!(offset, 0);
+        assert!((coefficient - 1.0).abs() < 1e-5, "{coefficient}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "Template must have at least 1 sample, but got 0")]
+fn empty_template() {
+    Correlator::new(&[]);
+}
+
+#[test]
+#[should_panic(expected = "Output length must be 8, but got 9")]
+fn output_of_the_wrong_length() {
+    Correlator::new(&[1.0; 3]).correlate_into(&[0.0; 10], &mut [0.0; 9]);
+}
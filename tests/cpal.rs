@@ -0,0 +1,572 @@
+//! Checks the streams of `cpal` opened by the [AudioTransmitter] and the [AudioReceiver] on a software device
+//! of the `custom` host of `cpal`: the frames played by the output stream, the frames captured by the input stream
+//! in every sample format and a few channel counts, a live loopback from the output to the input,
+//! the negotiation of the rate, channels and sample format, and its errors.
+//!
+//! The test needs the `cpal` feature: `cargo test --features cpal`.
+
+#![cfg(feature = "cpal")]
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use software_modem::{
+    audio::{
+        AudioError, AudioReceiver, AudioTransmitter, DeviceConfig, DeviceSample, ReceiverConfig,
+        TransmitterConfig,
+        device::{
+            InputDevice, OutputDevice,
+            cpal::{
+                self, Data, DeviceDescription, DeviceDescriptionBuilder, DeviceId, ErrorKind,
+                InputCallbackInfo, InputStreamTimestamp, OutputCallbackInfo, OutputStreamTimestamp,
+                SampleFormat, StreamConfig, StreamInstant, SupportedBufferSize,
+                SupportedStreamConfig, SupportedStreamConfigRange,
+                platform::CustomDevice,
+                traits::{DeviceTrait, StreamTrait},
+            },
+        },
+    },
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    stream::StreamDemodulator,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+fn modulator() -> CodedOFDMModulator {
+    CodedOFDMModulator::new(ofdm(), CodingConfig::default())
+}
+
+fn demodulator() -> CodedOFDMDemodulator {
+    CodedOFDMDemodulator::new(ofdm(), CodingConfig::default())
+}
+
+/// Frames of a buffer of the software device.
+const BLOCK: usize = 480;
+
+/// A device of `cpal` in software, whose streams move buffers of [BLOCK] frames as fast as they can.
+///
+/// The output stream plays into the air, the first channel of what it plays. The input stream captures
+/// from the air on every channel, whenever it holds a full buffer, like a microphone next to the speaker.
+#[derive(Clone, Debug)]
+struct SoftwareDevice {
+    outputs: Vec<SupportedStreamConfigRange>,
+    inputs: Vec<SupportedStreamConfigRange>,
+    air: Arc<Mutex<VecDeque<f32>>>,
+}
+
+fn ranges(configs: &[(u16, u32, u32, SampleFormat)]) -> Vec<SupportedStreamConfigRange> {
+    configs
+        .iter()
+        .map(|&(channels, min_rate, max_rate, format)| {
+            SupportedStreamConfigRange::new(
+                channels,
+                min_rate,
+                max_rate,
+                SupportedBufferSize::Unknown,
+                format,
+            )
+        })
+        .collect()
+}
+
+impl SoftwareDevice {
+    fn new(
+        outputs: &[(u16, u32, u32, SampleFormat)],
+        inputs: &[(u16, u32, u32, SampleFormat)],
+    ) -> SoftwareDevice {
+        SoftwareDevice {
+            outputs: ranges(outputs),
+            inputs: ranges(inputs),
+            air: Arc::default(),
+        }
+    }
+
+    fn device(&self) -> cpal::Device {
+        cpal::Device::from(CustomDevice::from_device(self.clone()))
+    }
+}
+
+impl PartialEq for SoftwareDevice {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.air, &other.air)
+    }
+}
+
+impl Eq for SoftwareDevice {}
+
+impl Hash for SoftwareDevice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.air).hash(state);
+    }
+}
+
+impl fmt::Display for SoftwareDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "software")
+    }
+}
+
+/// Converts the samples of a buffer of the format to full scale.
+fn to_f32(data: &Data) -> Vec<f32> {
+    fn convert<S: DeviceSample + cpal::SizedSample>(data: &Data) -> Vec<f32> {
+        data.as_slice::<S>()
+            .unwrap()
+            .iter()
+            .map(|&sample| sample.to_f32())
+            .collect()
+    }
+    match data.sample_format() {
+        SampleFormat::F32 => convert::<f32>(data),
+        SampleFormat::I16 => convert::<i16>(data),
+        SampleFormat::U16 => convert::<u16>(data),
+        format => panic!("Unexpected sample format {}", format),
+    }
+}
+
+/// Writes full scale samples into a buffer of the format.
+fn from_f32(samples: &[f32], data: &mut Data) {
+    fn convert<S: DeviceSample + cpal::SizedSample>(samples: &[f32], data: &mut Data) {
+        for (output, &sample) in data.as_slice_mut::<S>().unwrap().iter_mut().zip(samples) {
+            *output = S::from_f32(sample);
+        }
+    }
+    match data.sample_format() {
+        SampleFormat::F32 => convert::<f32>(samples, data),
+        SampleFormat::I16 => convert::<i16>(samples, data),
+        SampleFormat::U16 => convert::<u16>(samples, data),
+        format => panic!("Unexpected sample format {}", format),
+    }
+}
+
+/// Runs a stream of the software device on a thread, calling the step with a buffer of the format while it plays.
+fn run(
+    channels: u16,
+    sample_format: SampleFormat,
+    mut step: impl FnMut(&mut Data) + Send + 'static,
+) -> SoftwareStream {
+    let length = BLOCK * usize::from(channels);
+    let playing = Arc::new(AtomicBool::new(false));
+    let exit = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let (playing, exit) = (playing.clone(), exit.clone());
+        std::thread::spawn(move || {
+            // aligned for every sample format
+            let mut buffer = vec![0u64; length];
+            while !exit.load(Ordering::Acquire) {
+                std::thread::sleep(Duration::from_millis(1));
+                if playing.load(Ordering::Acquire) {
+                    // SAFETY: the buffer holds `length` samples of any format, and outlives the data
+                    let mut data = unsafe {
+                        Data::from_parts(buffer.as_mut_ptr().cast(), length, sample_format)
+                    };
+                    step(&mut data);
+                }
+            }
+        })
+    };
+    SoftwareStream {
+        playing,
+        exit,
+        thread: Some(thread),
+    }
+}
+
+impl DeviceTrait for SoftwareDevice {
+    type SupportedInputConfigs = std::vec::IntoIter<SupportedStreamConfigRange>;
+    type SupportedOutputConfigs = std::vec::IntoIter<SupportedStreamConfigRange>;
+    type Stream = SoftwareStream;
+
+    fn description(&self) -> Result<DeviceDescription, cpal::Error> {
+        Ok(DeviceDescriptionBuilder::new("software").build())
+    }
+
+    fn id(&self) -> Result<DeviceId, cpal::Error> {
+        Err(cpal::Error::new(ErrorKind::UnsupportedOperation))
+    }
+
+    fn supported_input_configs(&self) -> Result<Self::SupportedInputConfigs, cpal::Error> {
+        Ok(self.inputs.clone().into_iter())
+    }
+
+    fn supported_output_configs(&self) -> Result<Self::SupportedOutputConfigs, cpal::Error> {
+        Ok(self.outputs.clone().into_iter())
+    }
+
+    fn default_input_config(&self) -> Result<SupportedStreamConfig, cpal::Error> {
+        Err(cpal::Error::new(ErrorKind::UnsupportedConfig))
+    }
+
+    fn default_output_config(&self) -> Result<SupportedStreamConfig, cpal::Error> {
+        Err(cpal::Error::new(ErrorKind::UnsupportedConfig))
+    }
+
+    fn build_input_stream_raw<D, E>(
+        &self,
+        config: StreamConfig,
+        sample_format: SampleFormat,
+        mut data_callback: D,
+        _: E,
+        _: Option<Duration>,
+    ) -> Result<Self::Stream, cpal::Error>
+    where
+        D: FnMut(&Data, &InputCallbackInfo) + Send + 'static,
+        E: FnMut(cpal::Error) + Send + 'static,
+    {
+        let channels = usize::from(config.channels);
+        let air = self.air.clone();
+        let info = InputCallbackInfo::new(InputStreamTimestamp {
+            callback: StreamInstant::ZERO,
+            capture: StreamInstant::ZERO,
+        });
+        Ok(run(config.channels, sample_format, move |data| {
+            let mut air = air.lock().unwrap();
+            if air.len() >= BLOCK {
+                let samples: Vec<f32> = air
+                    .drain(..BLOCK)
+                    .flat_map(|sample| std::iter::repeat_n(sample, channels))
+                    .collect();
+                drop(air);
+                from_f32(&samples, data);
+                data_callback(data, &info);
+            }
+        }))
+    }
+
+    fn build_output_stream_raw<D, E>(
+        &self,
+        config: StreamConfig,
+        sample_format: SampleFormat,
+        mut data_callback: D,
+        _: E,
+        _: Option<Duration>,
+    ) -> Result<Self::Stream, cpal::Error>
+    where
+        D: FnMut(&mut Data, &OutputCallbackInfo) + Send + 'static,
+        E: FnMut(cpal::Error) + Send + 'static,
+    {
+        let channels = usize::from(config.channels);
+        let air = self.air.clone();
+        let info = OutputCallbackInfo::new(OutputStreamTimestamp {
+            callback: StreamInstant::ZERO,
+            playback: StreamInstant::ZERO,
+        });
+        Ok(run(config.channels, sample_format, move |data| {
+            data_callback(data, &info);
+            air.lock()
+                .unwrap()
+                .extend(to_f32(data).into_iter().step_by(channels));
+        }))
+    }
+}
+
+struct SoftwareStream {
+    playing: Arc<AtomicBool>,
+    exit: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StreamTrait for SoftwareStream {
+    fn play(&self) -> Result<(), cpal::Error> {
+        self.playing.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn pause(&self) -> Result<(), cpal::Error> {
+        self.playing.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    fn buffer_size(&self) -> Result<cpal::FrameCount, cpal::Error> {
+        Ok(BLOCK as u32)
+    }
+
+    fn now(&self) -> StreamInstant {
+        StreamInstant::ZERO
+    }
+}
+
+impl Drop for SoftwareStream {
+    fn drop(&mut self) {
+        self.exit.store(true, Ordering::Release);
+        self.thread.take().unwrap().join().unwrap();
+    }
+}
+
+fn transmitter(
+    device: &SoftwareDevice,
+    config: &TransmitterConfig,
+) -> Result<(AudioTransmitter, OutputDevice), AudioError> {
+    AudioTransmitter::open(&device.device(), modulator(), config)
+}
+
+fn receiver(
+    device: &SoftwareDevice,
+    config: &ReceiverConfig,
+) -> Result<(AudioReceiver, InputDevice), AudioError> {
+    AudioReceiver::open(&device.device(), demodulator(), config)
+}
+
+/// Decodes the frames of samples at the modem rate.
+fn decode(samples: &[f32]) -> Vec<Vec<u8>> {
+    let mut stream = StreamDemodulator::new(demodulator(), 0.01, 2400);
+    let mut decoded = Vec::new();
+    for block in samples.chunks(BLOCK) {
+        decoded.extend(stream.push(block));
+    }
+    decoded.extend(stream.flush());
+    decoded
+}
+
+/// Receives a number of payloads, or fewer if one does not arrive in time.
+fn receive(receiver: &AudioReceiver, count: usize) -> Vec<Vec<u8>> {
+    (0..count)
+        .map_while(|_| receiver.recv_timeout(Duration::from_secs(60)))
+        .collect()
+}
+
+#[test]
+fn the_output_stream_plays_the_frames() {
+    let device = SoftwareDevice::new(&[(2, 8000, 96000, SampleFormat::I16)], &[]);
+    let config = TransmitterConfig {
+        device: DeviceConfig {
+            sample_rate: 48000,
+            channels: 2,
+        },
+        ..Default::default()
+    };
+    let (transmitter, output) = transmitter(&device, &config).unwrap();
+    assert_eq!(output.get_config(), config.device);
+    assert_eq!(output.get_sample_format(), SampleFormat::I16);
+
+    let payloads = [data(40), data(200), data(7)];
+    for payload in &payloads {
+        transmitter.send(payload).unwrap();
+    }
+    transmitter.shutdown();
+    let start = Instant::now();
+    while !output.is_finished() {
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "The frames are not played"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(output.take_error(), None);
+    drop(output);
+
+    let played: Vec<f32> = device.air.lock().unwrap().iter().copied().collect();
+    assert_eq!(decode(&played), payloads);
+}
+
+#[test]
+fn the_input_stream_captures_the_frames() {
+    let payloads = [data(40), data(200), data(7)];
+    let modulator = modulator();
+    let mut samples = Vec::new();
+    for payload in &payloads {
+        samples.extend(modulator.encode_frame(payload));
+        samples.extend([0.0; 4800]);
+    }
+
+    for (channels, format) in [
+        (1, SampleFormat::F32),
+        (2, SampleFormat::I16),
+        (6, SampleFormat::U16),
+    ] {
+        let device = SoftwareDevice::new(&[], &[(channels, 48000, 48000, format)]);
+        device.air.lock().unwrap().extend(&samples);
+        let (receiver, input) = receiver(&device, &ReceiverConfig::default()).unwrap();
+        assert_eq!(
+            (input.get_config().channels, input.get_sample_format()),
+            (channels, format)
+        );
+
+        assert_eq!(
+            receive(&receiver, payloads.len()),
+            payloads,
+            "{} {}",
+            channels,
+            format
+        );
+        let metrics = receiver.metrics();
+        assert_eq!(
+            (
+                metrics.frames_decoded,
+                metrics.frames_failed,
+                metrics.overruns
+            ),
+            (3, 0, 0)
+        );
+        assert!(metrics.input_level_db.is_finite());
+        assert_eq!(input.take_error(), None);
+        // the worker ends after the stream
+        drop(input);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(30)), None);
+    }
+}
+
+#[test]
+fn a_loopback_carries_the_frames_live() {
+    // a modem at 48 kHz, whose subcarriers stay below the passband of the resamplers to 44.1 kHz and back
+    let ofdm = OFDMConfig {
+        guard_subcarriers_high: 18,
+        ..ofdm()
+    };
+    let device = SoftwareDevice::new(
+        &[(2, 44100, 44100, SampleFormat::I16)],
+        &[(1, 44100, 44100, SampleFormat::F32)],
+    );
+    let (receiver, input) = AudioReceiver::open(
+        &device.device(),
+        CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default()),
+        &ReceiverConfig::default(),
+    )
+    .unwrap();
+    let (transmitter, output) = AudioTransmitter::open(
+        &device.device(),
+        CodedOFDMModulator::new(ofdm, CodingConfig::default()),
+        &TransmitterConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(output.get_config().sample_rate, 44100);
+    assert_eq!(input.get_config().sample_rate, 44100);
+
+    // every payload arrives while the streams run, before the next one is sent
+    for length in [1, 100, 1000] {
+        let payload = data(length);
+        transmitter.send(&payload).unwrap();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(60)),
+            Some(payload)
+        );
+    }
+    assert_eq!(receiver.metrics().frames_decoded, 3);
+    transmitter.shutdown();
+    drop(output);
+    drop(input);
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(30)), None);
+}
+
+#[test]
+fn the_device_is_negotiated() {
+    let negotiated = |configs: &[(u16, u32, u32, SampleFormat)], channels: u16| {
+        let device = DeviceConfig {
+            sample_rate: 48000,
+            channels,
+        };
+        let software = SoftwareDevice::new(configs, configs);
+        let (_, output) = transmitter(
+            &software,
+            &TransmitterConfig {
+                device,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let (_, input) = receiver(
+            &software,
+            &ReceiverConfig {
+                device,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let negotiated = (output.get_config(), output.get_sample_format());
+        assert_eq!((input.get_config(), input.get_sample_format()), negotiated);
+        negotiated
+    };
+    let device = |sample_rate, channels| DeviceConfig {
+        sample_rate,
+        channels,
+    };
+
+    // the modem rate, in the preferred format
+    let configs = [
+        (2, 44100, 48000, SampleFormat::U16),
+        (2, 44100, 48000, SampleFormat::F32),
+        (2, 44100, 48000, SampleFormat::I16),
+    ];
+    assert_eq!(
+        negotiated(&configs, 2),
+        (device(48000, 2), SampleFormat::F32)
+    );
+    // the requested channels come before the rate and the format
+    let configs = [
+        (1, 48000, 48000, SampleFormat::F32),
+        (2, 44100, 44100, SampleFormat::I16),
+    ];
+    assert_eq!(
+        negotiated(&configs, 2),
+        (device(44100, 2), SampleFormat::I16)
+    );
+    assert_eq!(
+        negotiated(&configs, 1),
+        (device(48000, 1), SampleFormat::F32)
+    );
+    // another channel count than requested, carrying the modem on every channel
+    let configs = [(6, 8000, 192000, SampleFormat::F32)];
+    assert_eq!(
+        negotiated(&configs, 2),
+        (device(48000, 6), SampleFormat::F32)
+    );
+    // formats of other sizes are skipped, and the rate of the range is resampled from
+    let configs = [
+        (1, 48000, 48000, SampleFormat::I32),
+        (1, 22050, 22050, SampleFormat::U16),
+    ];
+    assert_eq!(
+        negotiated(&configs, 1),
+        (device(22050, 1), SampleFormat::U16)
+    );
+}
+
+#[test]
+fn devices_that_can_not_carry_the_modem_are_rejected() {
+    let errors = |configs: &[(u16, u32, u32, SampleFormat)]| {
+        let device = SoftwareDevice::new(configs, configs);
+        let output = transmitter(&device, &TransmitterConfig::default())
+            .err()
+            .unwrap();
+        let input = receiver(&device, &ReceiverConfig::default()).err().unwrap();
+        assert_eq!(input, output);
+        output
+    };
+
+    let no_format =
+        AudioError::Device("The audio device supports no samples of f32, i16 or u16".to_string());
+    assert_eq!(errors(&[(1, 48000, 48000, SampleFormat::I32)]), no_format);
+    assert_eq!(errors(&[]), no_format);
+    // 48000 and 44101 Hz have no common divisor, the resampler would need too many phases
+    assert_eq!(
+        errors(&[(1, 44101, 44101, SampleFormat::F32)]),
+        AudioError::SampleRateMismatch {
+            modem_rate: 48000,
+            device_rate: 44101
+        }
+    );
+}
@@ -0,0 +1,130 @@
+//! Sends frames over the [chirp spread spectrum](software_modem::phy::css) modem through the frame layer,
+//! clean for every spreading factor and far below the noise after a delay, and checks how the symbol errors grow
+//! as the spreading factor drops.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    error::ModemError,
+    frame::{FrameDecoder, FrameEncoder},
+    phy::{
+        PhyDemodulator, PhyModulator,
+        css::{CssConfig, CssDemodulator, CssModulator},
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(spreading_factor: u32) -> CssConfig {
+    CssConfig {
+        spreading_factor,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn clean_frames() {
+    // the fewest symbols of whole bytes
+    for (spreading_factor, block_bytes) in [(6, 3), (7, 7), (8, 1), (10, 5), (12, 3)] {
+        let encoder = FrameEncoder::new(CssModulator::new(config(spreading_factor)));
+        let decoder = FrameDecoder::new(CssDemodulator::new(config(spreading_factor)));
+        assert_eq!(encoder.get_bytes_per_symbol(), block_bytes);
+        for length in [1, 2 * block_bytes + 1] {
+            let payload = data(length as u32);
+            let samples = encoder.encode(&payload);
+            assert_eq!(samples.len(), encoder.get_frame_length(length));
+            assert_eq!(samples.len(), decoder.get_frame_length(length));
+            let decoded = decoder.decode(&samples);
+            assert_eq!(decoded.len(), length.div_ceil(block_bytes) * block_bytes);
+            assert_eq!(decoded[..length], payload, "{spreading_factor}");
+        }
+    }
+}
+
+#[test]
+fn blocks_on_their_own() {
+    let modulator = CssModulator::new(config(8));
+    let demodulator = CssDemodulator::new(config(8));
+    let mut block = vec![0.0; 256 * 8];
+    for byte in [0x00, 0x01, 0x80, 0xff] {
+        modulator.modulate(&[byte], &mut block).unwrap();
+        // a constant envelope
+        assert!(block.iter().all(|sample| sample.abs() <= 0.5));
+        let mut output = [0];
+        demodulator.demodulate(&block, &mut output).unwrap();
+        assert_eq!(output, [byte]);
+    }
+
+    // the values are Gray coded, so the shifts 2 and 3 carry 3 and 2, a bit apart
+    for (value, shift) in [(2, 3), (3, 2)] {
+        modulator.modulate(&[value], &mut block).unwrap();
+        assert_eq!(demodulator.demodulate_symbols(&block), [shift]);
+    }
+    assert_eq!(
+        modulator.modulate(&[0, 0], &mut block),
+        Err(ModemError::BufferLength {
+            expected: 1,
+            got: 2
+        })
+    );
+}
+
+#[test]
+fn frame_at_minus_5_db() {
+    let config = config(10);
+    let encoder = FrameEncoder::new(CssModulator::new(config.clone()));
+    let decoder = FrameDecoder::new(CssDemodulator::new(config));
+    let payload = data(20);
+
+    // noise before and after the frame, as strong as the frame over the whole band
+    for (seed, delay) in [(1, 0), (2, 12345)] {
+        let mut samples = vec![0.0; delay];
+        samples.extend(encoder.encode(&payload));
+        samples.extend(vec![0.0; 5000]);
+        AwgnChannel::with_reference_power(-5.0, 0.125, seed).apply(&mut samples);
+        let decoded = decoder.decode(&samples);
+        assert_eq!(decoded, payload, "{delay}");
+    }
+}
+
+#[test]
+fn symbol_errors_grow_as_the_spreading_factor_drops() {
+    let errors: Vec<usize> = (6..=10)
+        .rev()
+        .map(|spreading_factor| {
+            let modulator = CssModulator::new(config(spreading_factor));
+            let demodulator = CssDemodulator::new(config(spreading_factor));
+            let symbols: Vec<u32> = data(100)
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| (byte as u32 * 31 + i as u32) % (1 << spreading_factor))
+                .collect();
+            let mut samples = modulator.modulate_symbols(&symbols);
+            AwgnChannel::with_reference_power(-18.0, 0.125, spreading_factor as u64)
+                .apply(&mut samples);
+            demodulator
+                .demodulate_symbols(&samples)
+                .iter()
+                .zip(&symbols)
+                .filter(|(shift, symbol)| shift != symbol)
+                .count()
+        })
+        .collect();
+
+    // every halving of the chips costs 3 dB, so the errors set in and grow rather than jump from none to all
+    assert_eq!(errors[..2], [0, 0], "{errors:?}");
+    assert!(
+        errors.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{errors:?}"
+    );
+    assert!(errors[3] > 0 && errors[4] < 90, "{errors:?}");
+}
+
+#[test]
+#[should_panic(expected = "Spreading factor must be between 6 and 12, but got 13")]
+fn spreading_factor_out_of_range() {
+    CssDemodulator::new(config(13));
+}
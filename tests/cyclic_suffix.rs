@@ -0,0 +1,158 @@
+//! Checks that a [cyclic suffix](software_modem::ofdm::modulator::OFDMModulatorConfig::cyclic_suffix_length)
+//! repeats the head of every symbol after it and grows the symbols and frames by its length,
+//! and that frames and streams decode with and without a suffix, also windowed.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::{CodingConfig, FrameDecoder, FrameEncoder},
+    ofdm::{
+        GuardType, OFDMConfig,
+        demodulator::OFDMDemodulator,
+        modulator::{OFDMModulator, OutputScale},
+    },
+    stream::{StreamDemodulator, StreamModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(cyclic_suffix_length: u32, roll_off: u32) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        cyclic_suffix_length,
+        roll_off,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn suffix_repeats_the_head_of_the_symbol() {
+    let plain = OFDMModulator::new((&config(0, 0)).into());
+    let data = data(plain.get_bytes_per_symbol() as u32);
+    let without = plain.modulate_symbol(&data).unwrap();
+    assert_eq!(without.len(), 144);
+
+    for suffix in [0, 4, 128] {
+        let modulator = OFDMModulator::new((&config(suffix, 0)).into());
+        assert_eq!(modulator.get_symbol_length(), 144 + suffix as usize);
+        let symbol = modulator.modulate_symbol(&data).unwrap();
+        // the prefix and the symbol as before, then the head of the symbol once more
+        assert_eq!(symbol[..144], without);
+        assert_eq!(symbol[144..], symbol[16..16 + suffix as usize]);
+        assert_eq!(
+            OFDMDemodulator::new((&config(suffix, 0)).into())
+                .demodulate_symbol_from_buffer(&symbol),
+            data
+        );
+    }
+
+    // with zero padding, the suffix is silent like the guard interval before it
+    let padded = OFDMConfig {
+        guard_type: GuardType::ZeroPad,
+        ..config(4, 0)
+    };
+    let symbol = OFDMModulator::new((&padded).into())
+        .modulate_symbol(&data)
+        .unwrap();
+    assert_eq!(symbol.len(), 148);
+    assert!(symbol[128..].iter().all(|&x| x == 0.0));
+}
+
+#[test]
+fn frames_with_a_suffix() {
+    let payload = data(100);
+    for (suffix, roll_off) in [(0, 0), (4, 0), (0, 8), (4, 8), (16, 8)] {
+        let config = config(suffix, roll_off);
+        let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+        let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+        let samples = encoder.encode(&payload);
+        // 5 symbols of 24 bytes, each longer by the suffix, and the roll-off of the last one
+        assert_eq!(
+            samples.len(),
+            5 * (144 + suffix as usize) + roll_off as usize,
+            "suffix {suffix} roll-off {roll_off}"
+        );
+        assert_eq!(samples.len(), decoder.get_frame_length(payload.len()));
+        assert_eq!(
+            decoder.decode(&samples)[..payload.len()],
+            payload,
+            "suffix {suffix} roll-off {roll_off}"
+        );
+    }
+}
+
+#[test]
+fn window_tapers_the_continuation_after_the_suffix() {
+    let rectangular = OFDMModulator::new((&config(4, 0)).into());
+    let windowed = OFDMModulator::new((&config(4, 8)).into());
+    let symbols: Vec<f32> = [data(24), data(48)[24..].to_vec()]
+        .iter()
+        .flat_map(|data| rectangular.modulate_symbol(data).unwrap())
+        .collect();
+    let output = windowed.apply_window(&symbols);
+    assert_eq!(output.len(), 2 * 148 + 8);
+
+    // only the starts of the prefixes change, the FFT windows and the suffixes are untouched
+    assert_ne!(output[148..156], symbols[148..156]);
+    assert_eq!(output[8..148], symbols[8..148]);
+    assert_eq!(output[156..296], symbols[156..296]);
+
+    // after the last symbol, the taper falls from the sample after its suffix, the fifth of its FFT window,
+    // and the start of the first symbol rises with the same taper
+    let falling: Vec<f32> = (0..8)
+        .map(|n| output[296 + n] / symbols[148 + 16 + 4 + n])
+        .collect();
+    assert!(
+        falling.windows(2).all(|pair| pair[0] > pair[1]),
+        "{falling:?}"
+    );
+    assert!(falling.iter().all(|&gain| gain > 0.0 && gain < 1.0));
+    for (n, gain) in falling.iter().enumerate() {
+        assert!((output[n] - (1.0 - gain) * symbols[n]).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn stream_with_a_suffix_and_windowing() {
+    let ofdm = OFDMConfig {
+        output_scale: OutputScale::PeakNormalize(0.5),
+        differential_time: true,
+        ..config(4, 8)
+    };
+    let modulator = StreamModulator::new(
+        CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()),
+        3000,
+    );
+    let mut samples = vec![0.0; 1000];
+    let payloads = [data(100), data(7)];
+    for payload in &payloads {
+        modulator.write_frame(payload, &mut samples).unwrap();
+    }
+
+    let mut stream = StreamDemodulator::new(
+        CodedOFDMDemodulator::new(ofdm, CodingConfig::default()),
+        0.01,
+        1000,
+    );
+    let decoded: Vec<_> = samples
+        .chunks(100)
+        .flat_map(|block| stream.push(block))
+        .collect();
+    assert_eq!(decoded, payloads);
+}
+
+#[test]
+fn suffix_is_serialized() {
+    let config = config(4, 8);
+    assert_eq!(OFDMConfig::from_bytes(&config.to_bytes()), Ok(config));
+}
+
+#[test]
+#[should_panic(expected = "Cyclic suffix must be at most the FFT length of 128, but got 129")]
+fn suffix_longer_than_the_symbol() {
+    OFDMDemodulator::new((&config(129, 0)).into());
+}
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""Writes aprs_afsk.wav, APRS packets in Bell 202 AFSK as a radio would put them on its audio output.
+
+Independent of the crate, from the AX.25 and Bell 202 specifications: the tones are 0.5 % high, the bit clock
+runs 800 ppm fast, the space tone is 3 dB below the mark tone like after de-emphasis, and there is noise
+20 dB below the signal. The third of the four packets has a bit flipped after its FCS was computed.
+
+    python3 aprs_afsk.py
+"""
+
+import math
+import random
+import struct
+import wave
+
+SAMPLE_RATE = 22050
+BAUD = 1200 * 1.0008
+MARK, SPACE = 1200 * 1.005, 2200 * 1.005
+SPACE_GAIN = 10 ** (-3 / 20)
+AMPLITUDE = 0.5
+
+
+def address(call, ssid, last, command=False):
+    call = call.ljust(6)
+    return bytes(ord(c) << 1 for c in call) + bytes(
+        [(0x80 if command else 0) | 0x60 | (ssid << 1) | int(last)]
+    )
+
+
+def frame(source, source_ssid, path, info):
+    data = address("APRS", 0, False, command=True) + address(source, source_ssid, not path)
+    for i, (call, ssid) in enumerate(path):
+        data += address(call, ssid, i == len(path) - 1)
+    return data + bytes([0x03, 0xF0]) + info
+
+
+def fcs(data):
+    crc = 0xFFFF
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = (crc >> 1) ^ 0x8408 if crc & 1 else crc >> 1
+    return crc ^ 0xFFFF
+
+
+def hdlc_bits(data, flags, corrupt=None):
+    data = data + struct.pack("<H", fcs(data))
+    bits = [(byte >> i) & 1 for byte in data for i in range(8)]
+    if corrupt is not None:
+        bits[corrupt] ^= 1
+    stuffed, ones = [], 0
+    for bit in bits:
+        stuffed.append(bit)
+        ones = ones + 1 if bit else 0
+        if ones == 5:
+            stuffed.append(0)
+            ones = 0
+    flag = [0, 1, 1, 1, 1, 1, 1, 0]
+    return flag * flags + stuffed + flag * 2
+
+
+def main():
+    rng = random.Random(202)
+    packets = [
+        (frame("N0CALL", 7, [("WIDE1", 1)], b"!4903.50N/07201.75W-Test 001"), 40, None),
+        (frame("N0CALL", 7, [("WIDE1", 1), ("WIDE2", 1)], b">Software modem AFSK fixture"), 25, None),
+        (frame("N0CALL", 9, [], b">this one is corrupted"), 25, 100),
+        (frame("N0CALL", 9, [], b":N0CALL   :~~ stuffed \xff\xfe ~~{01"), 25, None),
+    ]
+
+    samples = [0.0] * int(0.3 * SAMPLE_RATE)
+    phase = rng.uniform(0, 2 * math.pi)
+    for data, flags, corrupt in packets:
+        tone = 1
+        time = 0.0
+        bit_samples = SAMPLE_RATE / BAUD
+        for bit in hdlc_bits(data, flags, corrupt):
+            # NRZI, a 0 changes the tone
+            if bit == 0:
+                tone ^= 1
+            time += bit_samples
+            while time >= 1.0:
+                frequency, gain = (MARK, 1.0) if tone else (SPACE, SPACE_GAIN)
+                phase += 2 * math.pi * frequency / SAMPLE_RATE
+                samples.append(AMPLITUDE * gain * math.sin(phase))
+                time -= 1.0
+        samples.extend([0.0] * int(0.25 * SAMPLE_RATE))
+
+    power = AMPLITUDE**2 / 2
+    sigma = math.sqrt(power / 10 ** (20 / 10))
+    with wave.open("aprs_afsk.wav", "wb") as file:
+        file.setnchannels(1)
+        file.setsampwidth(2)
+        file.setframerate(SAMPLE_RATE)
+        file.writeframes(
+            b"".join(
+                struct.pack("<h", max(-32768, min(32767, round((x + rng.gauss(0, sigma)) * 32767))))
+                for x in samples
+            )
+        )
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,194 @@
+//! Checks that the [DC blocker](software_modem::dsp::DcBlocker) a stream has by default removes an offset of the input,
+//! so frames decode as without it, even loud ones close together, that it leaves the subcarriers of the modem alone,
+//! that it follows the subcarrier spacing of a new demodulator, and that it filters a stream block by block like at once.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::DcBlocker,
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    stream::StreamDemodulator,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+fn stream(config: &OFDMConfig) -> StreamDemodulator {
+    StreamDemodulator::new(
+        CodedOFDMDemodulator::new(config.clone(), CodingConfig::default()),
+        0.01,
+        2400,
+    )
+}
+
+/// Returns the payloads the stream decodes from the signal, in blocks of 128 samples.
+fn decode(stream: &mut StreamDemodulator, signal: &[f32]) -> Vec<Vec<u8>> {
+    signal
+        .chunks(128)
+        .flat_map(|block| stream.push(block))
+        .collect()
+}
+
+#[test]
+fn offset_input_decodes_like_the_clean_one() {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let payloads = [data(100), data(300), data(50)];
+    let mut signal = vec![0.0; 4800];
+    for payload in &payloads {
+        signal.extend(modulator.encode_frame(payload));
+        signal.extend([0.0; 4800]);
+    }
+    let offset: Vec<f32> = signal.iter().map(|x| x + 0.1).collect();
+
+    let clean = decode(&mut stream(&config()), &signal);
+    assert_eq!(clean, payloads);
+    // with the settings of a new stream
+    let mut blocked = stream(&config());
+    assert!(blocked.get_dc_blocker().is_some());
+    assert_eq!(decode(&mut blocked, &offset), clean);
+    assert_eq!(blocked.stats().frames_decoded, 3);
+
+    // without the blocker, the offset keeps the squelch open
+    let mut unblocked = stream(&config());
+    unblocked.set_dc_blocker(None);
+    assert!(decode(&mut unblocked, &offset).is_empty());
+}
+
+#[test]
+fn loud_frames_do_not_leave_the_blocker_ringing() {
+    // frames at the raw scale, some thousand times the squelch level, a little more than the hang apart
+    let raw = OFDMConfig {
+        output_scale: OutputScale::Raw,
+        ..config()
+    };
+    let modulator = CodedOFDMModulator::new(raw.clone(), CodingConfig::default());
+    let payloads = [data(100), data(300), data(50)];
+    let mut signal = Vec::new();
+    for payload in &payloads {
+        signal.extend(modulator.encode_frame(payload));
+        signal.extend([0.0; 3000]);
+    }
+    let offset: Vec<f32> = signal.iter().map(|x| x + 0.1).collect();
+
+    let mut stream = stream(&raw);
+    assert_eq!(decode(&mut stream, &offset), payloads);
+    // the estimate held over the frames, so the squelch closed after each of them
+    assert_eq!(stream.stats().sync_losses, 3);
+    assert_eq!(stream.stats().frames_attempted, 3);
+}
+
+#[test]
+fn subcarriers_pass_the_default_blocker() {
+    let configs = [
+        config(),
+        OFDMConfig {
+            num_subcarriers: 1024,
+            cyclic_prefix_length: 128,
+            ..config()
+        },
+        OFDMConfig {
+            num_subcarriers: 128,
+            cyclic_prefix_length: 16,
+            oversampling: 2,
+            guard_subcarriers_low: 4,
+            ..config()
+        },
+    ];
+    for config in configs {
+        let stream = stream(&config);
+        let blocker = stream.get_dc_blocker().unwrap();
+        let fft_length = 2 * config.num_subcarriers * config.oversampling;
+        let first = stream_first_subcarrier(&config) as f32 / fft_length as f32;
+
+        // the first data subcarrier within 0.1 dB, DC far below
+        assert!(blocker.gain_db(first).abs() < 0.1, "{config:?}");
+        assert!(blocker.gain_db(1e-7) < -40.0, "{config:?}");
+        // and delayed by about a sixtieth of the FFT length, within the cyclic prefix
+        assert!(
+            blocker.group_delay(first) < fft_length as f32 / 40.0,
+            "{config:?}"
+        );
+    }
+}
+
+fn stream_first_subcarrier(config: &OFDMConfig) -> u32 {
+    CodedOFDMDemodulator::new(config.clone(), CodingConfig::default()).get_data_subcarriers()[0]
+}
+
+#[test]
+fn the_blocker_follows_the_demodulator() {
+    let wide = OFDMConfig {
+        num_subcarriers: 1024,
+        cyclic_prefix_length: 128,
+        ..config()
+    };
+    let mut switched = stream(&config());
+    switched.set_demodulator(CodedOFDMDemodulator::new(
+        wide.clone(),
+        CodingConfig::default(),
+    ));
+    assert_eq!(switched.get_dc_blocker(), stream(&wide).get_dc_blocker());
+
+    // a blocker of its own is kept
+    let custom = DcBlocker::new(1e-3);
+    switched.set_dc_blocker(Some(custom.clone()));
+    switched.set_demodulator(CodedOFDMDemodulator::new(config(), CodingConfig::default()));
+    assert_eq!(switched.get_dc_blocker(), Some(&custom));
+
+    // and the one of a new stream is put back
+    switched.set_dc_blocker(None);
+    switched.enable_dc_blocker();
+    assert_eq!(
+        switched.get_dc_blocker(),
+        stream(&config()).get_dc_blocker()
+    );
+}
+
+#[test]
+fn blocks_continue_each_other() {
+    let input: Vec<f32> = (0..5000).map(|n| ((n * 37) % 11) as f32 - 4.0).collect();
+    let mut blocker = DcBlocker::from_pole(0.99);
+    let whole = blocker.clone().process(&input);
+    let mut blocks = Vec::new();
+    for block in input.chunks(7) {
+        blocks.extend(blocker.process(block));
+    }
+    assert_eq!(blocks, whole);
+
+    // the offset of 1 is gone after the filter settled
+    let mean = whole[4000..].iter().sum::<f32>() / 1000.0;
+    assert!(mean.abs() < 0.01, "{mean}");
+
+    blocker.reset();
+    assert_eq!(blocker.process(&input[..100]), whole[..100]);
+}
+
+#[test]
+fn group_delay_near_dc() {
+    let cutoff = 0.001;
+    let blocker = DcBlocker::new(cutoff);
+    assert!((blocker.get_pole() - (-std::f32::consts::TAU * cutoff).exp()).abs() < 1e-6);
+    // about 1 / (2 pi cutoff) samples at DC, a hundredth of that at 10 times the cutoff
+    let near_dc = 1.0 / (std::f32::consts::TAU * cutoff);
+    assert!((blocker.group_delay(0.0) / near_dc - 1.0).abs() < 0.01);
+    assert!(blocker.group_delay(10.0 * cutoff) < near_dc / 50.0);
+    assert!(blocker.group_delay(0.25).abs() < 0.01);
+}
+
+#[test]
+#[should_panic(expected = "Cutoff must be between 0 and 0.5 of the sample rate, but got 0")]
+fn zero_cutoff() {
+    DcBlocker::new(0.0);
+}
@@ -0,0 +1,134 @@
+//! Checks that [DFT spreading](software_modem::ofdm::modulator::OFDMModulatorConfig::dft_spread) lowers the PAPR of
+//! the symbols at the capacity of plain OFDM, and that spread symbols and frames come back byte for byte,
+//! clean and over noise.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    frame::{FrameDecoder, FrameEncoder},
+    ofdm::{
+        OFDMConfig, SlmConfig,
+        demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+        modulator::{OFDMModulator, OFDMModulatorConfig},
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(dft_spread: bool) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 128,
+        cyclic_prefix_length: 32,
+        guard_subcarriers_low: 8,
+        guard_subcarriers_high: 40,
+        pilot_subcarrier_every: 16,
+        dft_spread,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn lower_papr_at_the_same_capacity() {
+    let thresholds = [7.0, 8.0, 9.0, 10.0, 11.0];
+    let plain = OFDMModulator::new((&config(false)).into());
+    let spread = OFDMModulator::new((&config(true)).into());
+    assert_eq!(spread.get_bytes_per_symbol(), plain.get_bytes_per_symbol());
+    assert_eq!(spread.get_symbol_length(), plain.get_symbol_length());
+
+    let plain_ccdf = plain.measure_papr_ccdf(2000, &thresholds);
+    let spread_ccdf = spread.measure_papr_ccdf(2000, &thresholds);
+    // the real samples carry the envelope of the spread points on a carrier, which is a few dB on its own,
+    // but the tail of the plain symbols is several times as heavy
+    assert!(plain_ccdf[3] > 0.2, "{plain_ccdf:?}");
+    assert!(
+        spread_ccdf[3] < plain_ccdf[3] / 4.0,
+        "{spread_ccdf:?} {plain_ccdf:?}"
+    );
+    assert!(
+        spread_ccdf[4] < plain_ccdf[4] / 8.0,
+        "{spread_ccdf:?} {plain_ccdf:?}"
+    );
+    for (spread, plain) in spread_ccdf.iter().zip(&plain_ccdf) {
+        assert!(spread <= plain, "{spread_ccdf:?} {plain_ccdf:?}");
+    }
+
+    // and the worst case stays within the headroom without spreading
+    assert!(spread.required_headroom_db() <= plain.required_headroom_db());
+}
+
+#[test]
+fn symbols_round_trip() {
+    let modulator = OFDMModulator::new((&config(true)).into());
+    let demodulator = OFDMDemodulator::new((&config(true)).into());
+    let payload = data(40 * modulator.get_bytes_per_symbol() as u32);
+
+    let mut symbols = Vec::new();
+    let stats = modulator.modulate_batch(&payload, &mut symbols);
+    assert_eq!(stats.consumed, payload.len());
+    let mut decoded = Vec::new();
+    demodulator.demodulate_batch(&symbols, &mut decoded);
+    assert_eq!(decoded, payload);
+
+    // a demodulator without spreading gets other bytes
+    let mut unspread = Vec::new();
+    OFDMDemodulator::new((&config(false)).into()).demodulate_batch(&symbols, &mut unspread);
+    assert_ne!(unspread, payload);
+}
+
+#[test]
+fn frames_round_trip() {
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config(true)).into()));
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config(true)).into()));
+    // the payload comes back with the zero padding of the last symbol
+    for length in [0, 1, 100, 1000] {
+        let payload = data(length);
+        let decoded = decoder.decode(&encoder.encode(&payload));
+        assert_eq!(decoded[..payload.len()], payload);
+        assert!(decoded[payload.len()..].iter().all(|&byte| byte == 0));
+    }
+
+    let payload = data(2000);
+    let mut samples = encoder.encode(&payload);
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    AwgnChannel::with_reference_power(25.0, power, 7).apply(&mut samples);
+    assert_eq!(decoder.decode(&samples)[..payload.len()], payload);
+}
+
+#[test]
+fn config_round_trip() {
+    let bytes = config(true).to_bytes();
+    assert_eq!(OFDMConfig::from_bytes(&bytes), Ok(config(true)));
+    assert_ne!(bytes, config(false).to_bytes());
+
+    // spreading with differential mode is no valid configuration
+    let differential = OFDMConfig {
+        differential_time: true,
+        ..config(true)
+    };
+    assert!(OFDMConfig::from_bytes(&differential.to_bytes()).is_err());
+}
+
+#[test]
+#[should_panic(expected = "DFT spreading needs coherent demodulation, but got differential_time")]
+fn differential_spreading() {
+    OFDMDemodulator::new(OFDMDemodulatorConfig {
+        num_subcarriers: 64,
+        differential_time: true,
+        dft_spread: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+#[should_panic(expected = "DFT spreading does not support selected mapping, but got slm")]
+fn spreading_with_selected_mapping() {
+    OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 64,
+        slm: Some(SlmConfig::default()),
+        dft_spread: true,
+        ..Default::default()
+    });
+}
@@ -0,0 +1,171 @@
+//! Checks that [verify_transforms](software_modem::diagnostics::verify_transforms) passes the FFTs of `realfft`
+//! and fails backends broken in the ways a miscompiled one is, and that the self-test reports it.
+
+use std::sync::Arc;
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator, self_test},
+    diagnostics::{TRANSFORM_TOLERANCE, verify_transform_accuracy, verify_transforms},
+    fft::{RealForwardFft, RealInverseFft, plan_real_forward, plan_real_inverse},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+};
+
+/// How a [BrokenForward] breaks the FFT it wraps.
+#[derive(Copy, Clone, Debug)]
+enum Fault {
+    /// Scales one bin slightly, like a wrong twiddle factor.
+    Bin(usize),
+    /// Swaps the real and imaginary parts of every bin.
+    Swapped,
+    /// Leaves NaN in the last bin.
+    Nan,
+}
+
+struct BrokenForward {
+    inner: Arc<dyn RealForwardFft<f32>>,
+    fault: Fault,
+}
+
+impl RealForwardFft<f32> for BrokenForward {
+    fn fft_length(&self) -> usize {
+        self.inner.fft_length()
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.inner.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [f32],
+        output: &mut [Complex32],
+        scratch: &mut [Complex32],
+    ) {
+        self.inner.process_with_scratch(input, output, scratch);
+        match self.fault {
+            Fault::Bin(bin) => output[bin] *= 1.01,
+            Fault::Swapped => output
+                .iter_mut()
+                .for_each(|bin| *bin = Complex32::new(bin.im, bin.re)),
+            Fault::Nan => *output.last_mut().unwrap() = Complex32::new(f32::NAN, 0.0),
+        }
+    }
+}
+
+/// An inverse FFT normalized like the ones of other libraries, which the modem does not expect.
+struct NormalizedInverse(Arc<dyn RealInverseFft<f32>>);
+
+impl RealInverseFft<f32> for NormalizedInverse {
+    fn fft_length(&self) -> usize {
+        self.0.fft_length()
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        self.0.get_scratch_len()
+    }
+
+    fn process_with_scratch(
+        &self,
+        input: &mut [Complex32],
+        output: &mut [f32],
+        scratch: &mut [Complex32],
+    ) {
+        self.0.process_with_scratch(input, output, scratch);
+        let length = output.len() as f32;
+        output.iter_mut().for_each(|sample| *sample /= length);
+    }
+}
+
+#[test]
+fn realfft_passes() {
+    for fft_size in [2, 3, 8, 64, 100, 128, 1000, 2048, 16384] {
+        let check = verify_transform_accuracy(fft_size);
+        assert!(check.passed, "{check:?}");
+        assert_eq!(check.fft_size, fft_size);
+        for error in [
+            check.max_reconstruction_error,
+            check.parseval_mismatch,
+            check.max_spectrum_error,
+        ] {
+            assert!(
+                (0.0..TRANSFORM_TOLERANCE / 10.0).contains(&error),
+                "{check:?}"
+            );
+        }
+        // the same every time
+        assert_eq!(verify_transform_accuracy(fft_size), check);
+    }
+}
+
+#[test]
+fn broken_backends_fail() {
+    for fault in [Fault::Bin(0), Fault::Bin(5), Fault::Swapped, Fault::Nan] {
+        let forward = BrokenForward {
+            inner: plan_real_forward(256),
+            fault,
+        };
+        let check = verify_transforms(&forward, plan_real_inverse(256).as_ref());
+        assert!(!check.passed, "{fault:?} {check:?}");
+    }
+
+    // a wrong bin off the line of the tone shows in the round trip and the energy, not just the spectrum
+    let forward = BrokenForward {
+        inner: plan_real_forward(256),
+        fault: Fault::Bin(5),
+    };
+    let check = verify_transforms(&forward, plan_real_inverse(256).as_ref());
+    assert!(check.max_spectrum_error > TRANSFORM_TOLERANCE);
+    assert!(check.max_reconstruction_error > TRANSFORM_TOLERANCE);
+    assert!(check.parseval_mismatch > TRANSFORM_TOLERANCE);
+
+    let forward = BrokenForward {
+        inner: plan_real_forward(256),
+        fault: Fault::Nan,
+    };
+    let check = verify_transforms(&forward, plan_real_inverse(256).as_ref());
+    assert!(check.parseval_mismatch.is_nan());
+
+    // the spectrum is right, but nothing comes back at the right scale
+    let inverse = NormalizedInverse(plan_real_inverse(256));
+    let check = verify_transforms(plan_real_forward(256).as_ref(), &inverse);
+    assert!(!check.passed);
+    assert!(check.max_spectrum_error < TRANSFORM_TOLERANCE);
+    assert!(check.max_reconstruction_error > 0.9);
+}
+
+#[test]
+fn self_test_reports_the_transforms() {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+    let report = self_test(&modulator, &demodulator, None);
+    assert!(report.passed);
+    assert_eq!(report.transform, verify_transform_accuracy(128));
+
+    // a demodulator of another FFT length is checked on its own
+    let other = CodedOFDMDemodulator::new(
+        OFDMConfig {
+            num_subcarriers: 128,
+            ..ofdm
+        },
+        CodingConfig::default(),
+    );
+    let report = self_test(&modulator, &other, None);
+    assert!(!report.passed);
+    assert_eq!(report.transform, verify_transform_accuracy(256));
+}
+
+#[test]
+#[should_panic(expected = "Forward and inverse FFT lengths must match, but got 64 and 128")]
+fn lengths_must_match() {
+    verify_transforms(
+        plan_real_forward(64).as_ref(),
+        plan_real_inverse(128).as_ref(),
+    );
+}
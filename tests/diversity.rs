@@ -0,0 +1,245 @@
+//! Receives frames on two branches through independent fading with the [diversity](software_modem::ofdm::diversity)
+//! demodulator, and checks that maximal-ratio combining beats the better single branch, hard and soft,
+//! and that branches a few samples apart are aligned.
+//! Also checks that the smoothing of the channel estimate over a few symbols follows a channel fading with a Doppler spread,
+//! which one static estimate per frame does not.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    bits::{bits_to_bytes, bytes_to_bits},
+    channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel, MultipathProfile},
+    frame::FrameEncoder,
+    ofdm::{OFDMConfig, diversity::DiversityDemodulator, modulator::OFDMModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 32,
+        ..Default::default()
+    }
+}
+
+/// The Vehicular A profile at 2 MHz, taps up to 5 samples late, faded anew by every seed, and noise.
+fn fading(snr_db: f32, power: f32, seed: u64) -> ChannelChain {
+    ChannelChain::new()
+        .with(MultipathChannel::from_profile(
+            MultipathProfile::Vehicular,
+            2e6,
+            seed,
+        ))
+        .with(AwgnChannel::with_reference_power(snr_db, power, seed))
+}
+
+fn bit_errors(decoded: &[u8], payload: &[u8]) -> u32 {
+    payload
+        .iter()
+        .zip(decoded)
+        .map(|(a, b)| (a ^ b).count_ones())
+        .sum()
+}
+
+/// Returns the bit errors of the first branch, of the second, and of both combined, over the frames.
+fn errors_over_fading(
+    frames: u64,
+    snr_db: f32,
+    decode: impl Fn(&DiversityDemodulator, &[&[f32]]) -> Vec<u8>,
+) -> [u32; 3] {
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config()).into()));
+    let demodulator = DiversityDemodulator::new((&config()).into(), 4);
+    let payload = data(1000);
+    let samples = encoder.encode(&payload);
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+
+    let mut errors = [0; 3];
+    for frame in 0..frames {
+        let mut first = samples.clone();
+        fading(snr_db, power, 2 * frame).apply(&mut first);
+        let mut second = samples.clone();
+        fading(snr_db, power, 2 * frame + 1).apply(&mut second);
+
+        for (errors, branches) in
+            errors
+                .iter_mut()
+                .zip([&[&first[..]][..], &[&second[..]], &[&first, &second]])
+        {
+            *errors += bit_errors(&decode(&demodulator, branches), &payload);
+        }
+    }
+    errors
+}
+
+#[test]
+fn combining_beats_the_better_branch() {
+    let [first, second, combined] = errors_over_fading(30, 20.0, |demodulator, branches| {
+        demodulator.decode(branches)
+    });
+    let better = first.min(second);
+    // single branches lose the subcarriers in deep fades
+    assert!(better > 0, "{first} {second} {combined}");
+    // at 20 dB the combined points lose about a fifth of the bits
+    assert!(combined * 3 < better, "{first} {second} {combined}");
+}
+
+#[test]
+fn soft_decisions_are_weighted_by_the_channel() {
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config()).into()));
+    let demodulator = DiversityDemodulator::new((&config()).into(), 4);
+    let payload = data(1000);
+    let samples = encoder.encode(&payload);
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+
+    let (mut wrong, mut right) = (Vec::new(), Vec::new());
+    for frame in 0..10 {
+        let mut first = samples.clone();
+        fading(12.0, power, 2 * frame).apply(&mut first);
+        let mut second = samples.clone();
+        fading(12.0, power, 2 * frame + 1).apply(&mut second);
+
+        let branches = [&first[..], &second[..]];
+        let llrs = demodulator.decode_soft(&branches);
+        // the signs of the soft decisions are the hard decisions
+        let hard: Vec<u8> = llrs.iter().map(|&llr| u8::from(llr < 0.0)).collect();
+        assert_eq!(bits_to_bytes(&hard), demodulator.decode(&branches));
+
+        for (&llr, bit) in llrs.iter().zip(bytes_to_bits(&payload)) {
+            if u8::from(llr < 0.0) == bit {
+                right.push(llr.abs());
+            } else {
+                wrong.push(llr.abs());
+            }
+        }
+    }
+
+    // the wrong decisions are on the faded subcarriers, which the weights mark as unreliable
+    let mean = |llrs: &[f32]| llrs.iter().sum::<f32>() / llrs.len() as f32;
+    assert!(!wrong.is_empty());
+    assert!(
+        mean(&wrong) * 4.0 < mean(&right),
+        "{} {}",
+        mean(&wrong),
+        mean(&right)
+    );
+}
+
+#[test]
+fn branches_a_few_samples_apart() {
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config()).into()));
+    let demodulator = DiversityDemodulator::new((&config()).into(), 4);
+    let payload = data(600);
+    let samples = encoder.encode(&payload);
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+
+    // the second branch 3 samples late, the third 2 samples early, each through its own channel
+    let shifted = |shift: isize, seed| {
+        let mut branch = vec![0.0; samples.len()];
+        if shift >= 0 {
+            branch[shift as usize..].copy_from_slice(&samples[..samples.len() - shift as usize]);
+        } else {
+            branch[..samples.len() - (-shift) as usize]
+                .copy_from_slice(&samples[(-shift) as usize..]);
+        }
+        fading(25.0, power, seed).apply(&mut branch);
+        branch
+    };
+    let (first, second, third) = (shifted(0, 1), shifted(3, 2), shifted(-2, 3));
+    let branches = [&first[..], &second[..], &third[..]];
+
+    // the echoes delay every branch by less than a sample on average
+    assert_eq!(demodulator.get_branch_offsets(&branches), [0, 3, -2]);
+    let decoded = demodulator.decode(&branches);
+    assert_eq!(decoded[..payload.len()], payload);
+
+    // offsets beyond the maximum are not followed
+    let tight = DiversityDemodulator::new((&config()).into(), 1);
+    assert_eq!(tight.get_branch_offsets(&branches), [0, 1, -1]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Branch offset must be below half the cyclic prefix of 32 samples, but got 16"
+)]
+fn offset_beyond_the_prefix() {
+    DiversityDemodulator::new((&config()).into(), 16);
+}
+
+#[test]
+#[should_panic(
+    expected = "Diversity combining needs coherent demodulation, but got differential_time"
+)]
+fn differential_branches() {
+    let config = OFDMConfig {
+        differential_time: true,
+        ..config()
+    };
+    DiversityDemodulator::new((&config).into(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Branches must have the same length, but got 192 and 96")]
+fn branches_of_different_lengths() {
+    let demodulator = DiversityDemodulator::new((&config()).into(), 2);
+    demodulator.decode(&[&[0.0; 192], &[0.0; 96]]);
+}
+
+#[test]
+fn smoothing_follows_a_walking_transmitter() {
+    // 48 kHz audio, the edges clear of the analytic signal of the fading real samples
+    let config = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        guard_subcarriers_low: 6,
+        guard_subcarriers_high: 6,
+        ..Default::default()
+    };
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    let smoothed = DiversityDemodulator::new((&config).into(), 4);
+    let stale = DiversityDemodulator::new((&config).into(), 4).with_smoothing_symbols(usize::MAX);
+
+    // 3 frames of a second, each 330 symbols of 3 ms, through a channel fading with a Doppler spread of 2 Hz,
+    // which decorrelates in 0.19 s
+    let payloads: Vec<Vec<u8>> = (0..3)
+        .map(|frame| {
+            data(330 * encoder.get_bytes_per_symbol() as u32 + frame)[frame as usize..].to_vec()
+        })
+        .collect();
+    let frames: Vec<Vec<f32>> = payloads
+        .iter()
+        .map(|payload| encoder.encode(payload))
+        .collect();
+    let mut received: Vec<f32> = frames.concat();
+    let power = received.iter().map(|x| x * x).sum::<f32>() / received.len() as f32;
+    let channel =
+        MultipathChannel::new(&[(0, Complex32::new(1.0, 0.0)), (3, Complex32::new(0.0, 0.5))])
+            .with_doppler(2.0, 48000.0, 7);
+    let delay = channel.get_delay();
+    received.extend(vec![0.0; delay]);
+    ChannelChain::new()
+        .with(channel)
+        .with(AwgnChannel::with_reference_power(25.0, power, 1))
+        .apply(&mut received);
+
+    let mut errors = [0, 0];
+    let mut start = delay;
+    for (frame, payload) in frames.iter().zip(&payloads) {
+        let branch = &received[start..start + frame.len()];
+        for (errors, demodulator) in errors.iter_mut().zip([&smoothed, &stale]) {
+            *errors += bit_errors(&demodulator.decode(&[branch]), payload);
+        }
+        start += frame.len();
+    }
+    // the channel of one estimate over the whole frame is the mean of its fading, far from most of its symbols
+    let bits = payloads
+        .iter()
+        .map(|payload| 8 * payload.len())
+        .sum::<usize>() as f32;
+    let (smoothed, stale) = (errors[0] as f32 / bits, errors[1] as f32 / bits);
+    assert!(smoothed < 0.01, "{smoothed}");
+    assert!(stale > 0.1, "{stale}");
+}
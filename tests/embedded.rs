@@ -0,0 +1,213 @@
+//! Checks the [heapless demodulator](software_modem::ofdm::embedded) against the float demodulator:
+//! its FFT against the one of `realfft`, its data on clean symbols, its zero-forcing equalizer over an echo,
+//! and its errors and panics.
+//!
+//! The test needs the `embedded` feature: `cargo test --features embedded`.
+
+#![cfg(feature = "embedded")]
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{Channel, MultipathChannel},
+    error::ModemError,
+    fft::{RealForwardFft, plan_real_forward},
+    ofdm::{
+        OFDMConfig,
+        demodulator::OFDMDemodulator,
+        embedded::{HeaplessOFDMDemodulator, HeaplessRealFft},
+        modulator::OFDMModulator,
+    },
+};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(
+    num_subcarriers: u32,
+    cyclic_prefix_length: u32,
+    pilot_subcarrier_every: u32,
+) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers,
+        cyclic_prefix_length,
+        pilot_subcarrier_every,
+        ..Default::default()
+    }
+}
+
+/// Returns the symbols of the data and the data the heapless demodulator makes of them after the channel.
+fn round_trip<const N_SC: usize, const CP: usize>(
+    pilot_subcarrier_every: usize,
+    channel: impl Fn(&mut [f32]),
+) -> (Vec<u8>, Vec<f32>, Vec<u8>) {
+    let config = config(N_SC as u32, CP as u32, pilot_subcarrier_every as u32);
+    let modulator = OFDMModulator::new((&config).into());
+    let mut demodulator = HeaplessOFDMDemodulator::<N_SC, CP>::new(pilot_subcarrier_every);
+    assert_eq!(
+        demodulator.get_symbol_length(),
+        modulator.get_symbol_length()
+    );
+    assert_eq!(
+        demodulator.get_bytes_per_symbol(),
+        modulator.get_bytes_per_symbol()
+    );
+    assert!(
+        demodulator
+            .get_data_subcarriers()
+            .map(|idx| idx as u32)
+            .eq(config.get_data_subcarriers())
+    );
+
+    let data = data(20 * modulator.get_bytes_per_symbol());
+    let mut samples = Vec::new();
+    modulator.modulate_batch(&data, &mut samples);
+    channel(&mut samples);
+    let mut received = vec![0; data.len()];
+    for (symbol, output) in samples
+        .chunks_exact(demodulator.get_symbol_length())
+        .zip(received.chunks_exact_mut(demodulator.get_bytes_per_symbol()))
+    {
+        demodulator
+            .try_demodulate_symbol_into(symbol, output)
+            .unwrap();
+    }
+    (data, samples, received)
+}
+
+fn float_demodulation(config: &OFDMConfig, samples: &[f32]) -> Vec<u8> {
+    let mut received = Vec::new();
+    OFDMDemodulator::new(config.into()).demodulate_batch(samples, &mut received);
+    received
+}
+
+fn assert_fft_matches<const N: usize>() {
+    let fft = HeaplessRealFft::<N>::new();
+    let samples: Vec<f32> = data(2 * N)
+        .iter()
+        .map(|&x| f32::from(x) / 128.0 - 1.0)
+        .collect();
+    let mut bins = vec![Complex32::default(); N + 1];
+    fft.process_with_scratch(&mut samples.clone(), &mut bins, &mut []);
+    let mut expected = vec![Complex32::default(); N + 1];
+    plan_real_forward(2 * N).process(&mut samples.clone(), &mut expected);
+
+    let scale = (2 * N) as f32;
+    for (k, (bin, expected)) in bins.iter().zip(&expected).enumerate() {
+        assert!(
+            (bin - expected).norm() < 1e-5 * scale,
+            "{N}: bin {k}, {bin} vs {expected}"
+        );
+    }
+    assert_eq!(bins[0].im, 0.0);
+    assert_eq!(bins[N].im, 0.0);
+}
+
+#[test]
+fn fft_matches_realfft() {
+    assert_fft_matches::<2>();
+    assert_fft_matches::<4>();
+    assert_fft_matches::<64>();
+    assert_fft_matches::<1024>();
+}
+
+#[test]
+fn clean_symbols_match_the_float_demodulator() {
+    let scale = |gain: f32| move |samples: &mut [f32]| samples.iter_mut().for_each(|x| *x *= gain);
+
+    let (data, samples, received) = round_trip::<64, 16>(4, scale(0.3));
+    assert_eq!(received, data);
+    assert_eq!(float_demodulation(&config(64, 16, 4), &samples), data);
+
+    let (data, _, received) = round_trip::<64, 4>(8, scale(1.0));
+    assert_eq!(received, data);
+    let (data, _, received) = round_trip::<256, 32>(16, scale(2.0));
+    assert_eq!(received, data);
+    // a pilot interval leaving an odd number of data subcarriers
+    let (data, _, received) = round_trip::<16, 4>(3, scale(1.0));
+    assert_eq!(received, data);
+
+    // zero forcing divides by the sign of the channel too
+    let (data, samples, received) = round_trip::<64, 16>(4, scale(-0.5));
+    assert_eq!(received, data);
+    assert_ne!(float_demodulation(&config(64, 16, 4), &samples), data);
+}
+
+#[test]
+fn zero_forcing_undoes_an_echo_within_the_prefix() {
+    let echo = |samples: &mut [f32]| MultipathChannel::two_ray(2, -6.0).apply(samples);
+    let (data, samples, received) = round_trip::<64, 16>(4, echo);
+    assert_eq!(received, data);
+
+    // the mean pilot magnitude of the coherent equalizer leaves the phase of the echo
+    let float = float_demodulation(&config(64, 16, 4), &samples);
+    let errors = float.iter().zip(&data).filter(|(a, b)| a != b).count();
+    assert!(errors > data.len() / 4, "{errors} of {}", data.len());
+}
+
+#[test]
+fn buffers_of_the_wrong_length_and_garbage() {
+    let mut demodulator = HeaplessOFDMDemodulator::<64, 16>::new(4);
+    let mut output = [0; 24];
+    assert_eq!(
+        demodulator.try_demodulate_symbol_into(&[0.0; 143], &mut output),
+        Err(ModemError::BufferLength {
+            expected: 144,
+            got: 143
+        })
+    );
+    assert_eq!(
+        demodulator.try_demodulate_symbol_into(&[0.0; 144], &mut output[..23]),
+        Err(ModemError::BufferLength {
+            expected: 24,
+            got: 23
+        })
+    );
+    for garbage in [0.0, f32::NAN, f32::INFINITY] {
+        assert_eq!(
+            demodulator.try_demodulate_symbol_into(&[garbage; 144], &mut output),
+            Ok(())
+        );
+    }
+}
+
+/// An FFT of the right length which asks for scratch space.
+struct ScratchFft;
+
+impl RealForwardFft<f32> for ScratchFft {
+    fn fft_length(&self) -> usize {
+        128
+    }
+
+    fn get_scratch_len(&self) -> usize {
+        8
+    }
+
+    fn process_with_scratch(&self, _: &mut [f32], _: &mut [Complex32], _: &mut [Complex32]) {}
+}
+
+#[test]
+#[should_panic(expected = "FFT must need no scratch space, but got 8")]
+fn fft_with_scratch() {
+    HeaplessOFDMDemodulator::<64, 16, _>::with_fft(ScratchFft, 4);
+}
+
+#[test]
+#[should_panic(expected = "FFT length must be 128, but got 64")]
+fn fft_of_another_length() {
+    HeaplessOFDMDemodulator::<64, 16, _>::with_fft(HeaplessRealFft::<32>::new(), 4);
+}
+
+#[test]
+#[should_panic(expected = "Half the FFT length must be a power of two of at least 2, but got 48")]
+fn subcarriers_not_a_power_of_two() {
+    HeaplessOFDMDemodulator::<48, 8>::new(4);
+}
+
+#[test]
+#[should_panic(expected = "Pilot interval must be at least 2 and below 64, but got 1")]
+fn pilots_on_every_subcarrier() {
+    HeaplessOFDMDemodulator::<64, 16>::new(1);
+}
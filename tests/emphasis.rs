@@ -0,0 +1,206 @@
+//! Sends frames through a channel rolling off by 6 dB per octave, like a speaker and a microphone,
+//! and checks that pre-emphasis and a transmit filter designed from a calibration frame flatten
+//! the SNR of the subcarriers.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator, measure_tilt},
+    dsp::{Deemphasis, EmphasisConfig, FirFilter, Preemphasis},
+    frame::{CodingConfig, FrameDecoder, FrameEncoder},
+    ofdm::{
+        OFDMConfig,
+        demodulator::OFDMDemodulator,
+        modulator::{OFDMModulator, OutputScale},
+    },
+    stream::{StreamDemodulator, StreamModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    }
+}
+
+/// Rolls off by 6 dB per octave from 0.02 of the sample rate, 18 dB down from 0.16 of it.
+fn roll_off() -> EmphasisConfig {
+    EmphasisConfig {
+        corner: 0.02,
+        boost_db: 18.0,
+    }
+}
+
+/// Sends a frame through the channel and noise at a fixed level, and returns the spread of the SNR
+/// of the data subcarriers in dB, the best over the worst one.
+fn snr_spread(config: &OFDMConfig, preemphasis: Option<Preemphasis>) -> f32 {
+    let encoder = FrameEncoder::new(OFDMModulator::new(config.into()));
+    let decoder = FrameDecoder::new(OFDMDemodulator::new(config.into()));
+    let mut samples = encoder.encode(&data(2000));
+    if let Some(mut preemphasis) = preemphasis {
+        preemphasis.process_in_place(&mut samples);
+    }
+    // the same power into the channel either way
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    samples.iter_mut().for_each(|x| *x /= power.sqrt());
+    Deemphasis::new(roll_off()).process_in_place(&mut samples);
+    AwgnChannel::with_reference_power(30.0, 1.0, 3).apply(&mut samples);
+
+    let snr = decoder.get_subcarrier_snr(&samples);
+    assert!(snr.iter().all(|snr| snr.is_finite()), "{snr:?}");
+    let (low, high) = snr.iter().fold((f32::MAX, f32::MIN), |(low, high), &snr| {
+        (low.min(snr), high.max(snr))
+    });
+    high - low
+}
+
+/// The roll-off of a speaker and a microphone, as a channel, which filters I and Q alike for complex samples.
+struct Speaker {
+    in_phase: Deemphasis,
+    quadrature: Deemphasis,
+}
+
+impl Speaker {
+    fn new(roll_off: EmphasisConfig) -> Self {
+        Speaker {
+            in_phase: Deemphasis::new(roll_off),
+            quadrature: Deemphasis::new(roll_off),
+        }
+    }
+}
+
+impl Channel for Speaker {
+    fn apply(&mut self, samples: &mut [f32]) {
+        self.in_phase.process_in_place(samples);
+    }
+
+    fn apply_complex(&mut self, samples: &mut [Complex32]) {
+        let mut in_phase: Vec<f32> = samples.iter().map(|sample| sample.re).collect();
+        let mut quadrature: Vec<f32> = samples.iter().map(|sample| sample.im).collect();
+        self.in_phase.process_in_place(&mut in_phase);
+        self.quadrature.process_in_place(&mut quadrature);
+        for ((sample, re), im) in samples.iter_mut().zip(in_phase).zip(quadrature) {
+            *sample = Complex32::new(re, im);
+        }
+    }
+}
+
+#[test]
+fn matched_filters_undo_each_other() {
+    let config = EmphasisConfig::default();
+    let preemphasis = Preemphasis::new(config);
+    let deemphasis = Deemphasis::new(config);
+    for frequency in [0.0, 0.01, 0.05, 0.1, 0.2, 0.3, 0.4, 0.5] {
+        let sum = preemphasis.gain_db(frequency) + deemphasis.gain_db(frequency);
+        assert!(sum.abs() < 1e-3, "{frequency} {sum}");
+    }
+    assert!((deemphasis.gain_db(0.5) + config.boost_db).abs() < 1e-3);
+
+    // in blocks of any size
+    let samples: Vec<f32> = (0..1000).map(|n| ((n * 37) % 11) as f32 - 5.0).collect();
+    let whole = preemphasis.clone().process(&samples);
+    let mut blocks = preemphasis;
+    let blocks: Vec<f32> = samples
+        .chunks(7)
+        .flat_map(|block| blocks.process(block))
+        .collect();
+    assert_eq!(blocks, whole);
+
+    // no boost passes the samples
+    let mut flat = Preemphasis::new(EmphasisConfig {
+        boost_db: 0.0,
+        ..config
+    });
+    for (output, sample) in flat.process(&samples).iter().zip(&samples) {
+        assert!((output - sample).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn preemphasis_flattens_the_snr() {
+    let without = snr_spread(&config(), None);
+    let with = snr_spread(&config(), Some(Preemphasis::new(roll_off())));
+    assert!(without > 12.0, "{without} dB");
+    assert!(with < without / 2.0, "{with} dB vs {without} dB");
+}
+
+#[test]
+fn calibrated_transmit_filter_flattens_the_snr() {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(config(), CodingConfig::default());
+    let tilt = measure_tilt(&modulator, &demodulator, &mut Speaker::new(roll_off()));
+    // the channel levels off from 0.16 of the sample rate, so the line over the band is not as steep
+    assert!(
+        tilt.db_per_octave < -2.5 && tilt.db_per_octave > -6.0,
+        "{tilt:?}"
+    );
+    assert!(tilt.response.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+    let compensated = OFDMConfig {
+        tx_filter: Some(FirFilter::compensating(&tilt.response, 24.0, 63)),
+        ..config()
+    };
+    let without = snr_spread(&config(), None);
+    let with = snr_spread(&compensated, None);
+    // the filter evens out the magnitude of the channel but not its phase, whose ringing beyond the cyclic prefix
+    // still costs the lowest subcarriers more than the exact inverse of the pre-emphasis
+    assert!(with < 0.6 * without, "{with} dB vs {without} dB");
+
+    // a loopback needs no compensation
+    let flat = measure_tilt(&modulator, &demodulator, &mut ChannelChain::new());
+    assert!(flat.db_per_octave.abs() < 0.1, "{flat:?}");
+    assert!(flat.response.iter().all(|(_, gain)| gain.abs() < 0.1));
+}
+
+#[test]
+fn stream_round_trip() {
+    let ofdm = OFDMConfig {
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..config()
+    };
+    let mut modulator = StreamModulator::new(
+        CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default()),
+        3000,
+    );
+    modulator.set_preemphasis(Some(Preemphasis::new(EmphasisConfig::default())));
+    let mut samples = vec![0.0; 1000];
+    let payloads = [data(100), data(7)];
+    for payload in &payloads {
+        modulator.write_frame(payload, &mut samples).unwrap();
+    }
+
+    let mut stream = StreamDemodulator::new(
+        CodedOFDMDemodulator::new(ofdm, CodingConfig::default()),
+        0.01,
+        1000,
+    );
+    stream.set_deemphasis(Some(Deemphasis::new(EmphasisConfig::default())));
+    let decoded: Vec<_> = samples
+        .chunks(128)
+        .flat_map(|block| stream.push(block))
+        .collect();
+    assert_eq!(decoded, payloads);
+    assert!(stream.get_deemphasis().is_some());
+}
+
+#[test]
+#[should_panic(expected = "Corner must be between 0 and 0.5 of the sample rate, but got 0.5")]
+fn corner_at_nyquist() {
+    Preemphasis::new(EmphasisConfig {
+        corner: 0.5,
+        boost_db: 6.0,
+    });
+}
+
+#[test]
+#[should_panic(expected = "Response frequencies must be ascending between 0 and 0.5")]
+fn descending_response() {
+    FirFilter::compensating(&[(0.2, 0.0), (0.1, -6.0)], 12.0, 31);
+}
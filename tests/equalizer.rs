@@ -0,0 +1,107 @@
+//! Checks that the equalization kernels of `f32` and `f64`, vectorized with the `portable-simd` feature,
+//! match the scalar kernels to a relative tolerance of 1e-6.
+//!
+//! Without the feature both sides are the scalar kernels. On nightly: `cargo +nightly test --features portable-simd`.
+
+use realfft::num_complex::Complex;
+use software_modem::{
+    ofdm::equalizer::{derotate, scale, unscale},
+    samples::Sample,
+};
+
+/// Lengths around the vector widths, and the points of a 4096 subcarrier configuration.
+const LENGTHS: [usize; 9] = [0, 1, 3, 4, 7, 8, 9, 31, 4096];
+
+/// Pseudo-random values between -4 and 4, with a few zeros and negative zeros.
+fn values<T: Sample>(length: usize, seed: u32) -> Vec<T> {
+    let mut state = seed;
+    (0..length)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            match i % 17 {
+                5 => T::zero(),
+                11 => -T::zero(),
+                _ => T::cast(state as f64 / u32::MAX as f64 * 8.0 - 4.0),
+            }
+        })
+        .collect()
+}
+
+fn points<T: Sample>(length: usize, seed: u32) -> Vec<Complex<T>> {
+    let parts = values(2 * length, seed);
+    parts
+        .chunks_exact(2)
+        .map(|part| Complex::new(part[0], part[1]))
+        .collect()
+}
+
+fn assert_close<T: Sample>(vectorized: &[Complex<T>], scalar: &[Complex<T>]) {
+    assert_eq!(vectorized.len(), scalar.len());
+    for (index, (&point, &expected)) in vectorized.iter().zip(scalar).enumerate() {
+        let error = (point - expected).norm();
+        assert!(
+            error <= T::cast(1e-6) * expected.norm(),
+            "point {} of {}: {:?}, expected {:?}",
+            index,
+            scalar.len(),
+            point,
+            expected
+        );
+    }
+}
+
+fn check_kernels<T: Sample>() {
+    for length in LENGTHS {
+        let original = points::<T>(length, 0x1234_5678 ^ length as u32);
+
+        let (mut vectorized, mut scalar) = (original.clone(), original.clone());
+        T::scale_points(&mut vectorized, T::cast(0.3));
+        scale(&mut scalar, T::cast(0.3));
+        assert_close(&vectorized, &scalar);
+
+        // gains keep their sign, so the negative and zero gains zero their points
+        let gains: Vec<T> = values(length, 0x9e37_79b9);
+        let (mut vectorized, mut scalar) = (original.clone(), original.clone());
+        T::unscale_points(&mut vectorized, &gains);
+        unscale(&mut scalar, &gains);
+        assert_close(&vectorized, &scalar);
+
+        let phases: Vec<Complex<T>> = values::<T>(length, 0x0bad_cafe)
+            .into_iter()
+            .map(|angle| Complex::from_polar(T::one(), angle))
+            .collect();
+        let (mut vectorized, mut scalar) = (original.clone(), original);
+        T::derotate_points(&mut vectorized, &phases);
+        derotate(&mut scalar, &phases);
+        assert_close(&vectorized, &scalar);
+    }
+}
+
+#[test]
+fn f32_kernels_match_the_scalar_kernels() {
+    check_kernels::<f32>();
+}
+
+#[test]
+fn f64_kernels_match_the_scalar_kernels() {
+    check_kernels::<f64>();
+}
+
+#[test]
+fn derotation_turns_the_rotation_back() {
+    let original = points::<f32>(4096, 42);
+    let phases: Vec<Complex<f32>> = (0..original.len())
+        .map(|i| Complex::from_polar(1.0, i as f32 * 0.01))
+        .collect();
+    let mut points: Vec<Complex<f32>> = original
+        .iter()
+        .zip(&phases)
+        .map(|(point, phase)| point * phase)
+        .collect();
+    f32::derotate_points(&mut points, &phases);
+    for (point, original) in points.iter().zip(&original) {
+        assert!((point - original).norm() < 1e-5, "{point} != {original}");
+    }
+}
@@ -0,0 +1,137 @@
+//! Checks the [EVM](software_modem::metrics::evm) against error vectors of known magnitude,
+//! and the data-aided EVM of the demodulator and the frame decoder over a loopback and over noise.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    frame::{FrameDecoder, FrameEncoder},
+    metrics::evm,
+    ofdm::{
+        OFDMConfig,
+        demodulator::{GenericOFDMDemodulator, OFDMDemodulator},
+        modulator::{GenericOFDMModulator, OFDMModulator},
+    },
+    qam::{QAMModem, QAMOrder},
+};
+
+fn payload(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn known_error_vectors() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    // 1000 points, normalized to their own power
+    let reference = modem.modulate(&payload(500));
+    let rms = (reference.iter().map(|x| x.norm_sqr()).sum::<f32>() / 1000.0).sqrt();
+
+    // an error of a tenth of the RMS in every direction is 10 %, -20 dB, on average and at the peak
+    let received: Vec<Complex32> = reference
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x + Complex32::from_polar(0.1 * rms, i as f32))
+        .collect();
+    let result = evm(&received, &reference);
+    assert!((result.rms_percent - 10.0).abs() < 1e-3, "{result:?}");
+    assert!((result.rms_db + 20.0).abs() < 1e-3, "{result:?}");
+    assert!((result.peak_percent - 10.0).abs() < 1e-3, "{result:?}");
+
+    // errors of 3 % and 4 % on alternate points, 3.54 % RMS, and a single one of 20 %
+    let mut received: Vec<Complex32> = reference
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x + Complex32::new(if i % 2 == 0 { 0.03 } else { -0.04 } * rms, 0.0))
+        .collect();
+    received[123] = reference[123] + Complex32::new(0.0, 0.2 * rms);
+    let expected =
+        ((499.0 * 0.03f32.powi(2) + 500.0 * 0.04f32.powi(2) + 0.2f32.powi(2)) / 1000.0).sqrt();
+    let result = evm(&received, &reference);
+    assert!(
+        (result.rms_percent / 100.0 - expected).abs() < 1e-5,
+        "{result:?}"
+    );
+    assert!((result.peak_percent - 20.0).abs() < 1e-3, "{result:?}");
+    assert!(
+        (result.peak_db - 20.0 * 0.2f32.log10()).abs() < 1e-3,
+        "{result:?}"
+    );
+
+    // the scale of the constellation does not matter
+    let double = |points: &[Complex32]| points.iter().map(|x| 2.0 * x).collect::<Vec<_>>();
+    let doubled = evm(&double(&received), &double(&reference));
+    assert!((doubled.rms_db - result.rms_db).abs() < 1e-4);
+}
+
+#[test]
+fn loopback_is_below_60_db() {
+    let config = config();
+    let modulator = OFDMModulator::new((&config).into());
+    let demodulator = OFDMDemodulator::new((&config).into());
+    let data = payload(20 * modulator.get_bytes_per_symbol());
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    for data in data.chunks(modulator.get_bytes_per_symbol()) {
+        modulator.modulate_buffer_as_symbol(data, &mut symbol);
+        let (demodulated, result) = demodulator.demodulate_symbol_with_evm(&symbol);
+        assert_eq!(demodulated, data);
+        assert!(
+            result.rms_db < -60.0 && result.peak_db < -60.0,
+            "{result:?}"
+        );
+    }
+
+    // double precision rounds far below single precision
+    let modulator = GenericOFDMModulator::<f64>::new((&config).into());
+    let demodulator = GenericOFDMDemodulator::<f64>::new((&config).into());
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    modulator.modulate_buffer_as_symbol(&data[..modulator.get_bytes_per_symbol()], &mut symbol);
+    let (_, result) = demodulator.demodulate_symbol_with_evm(&symbol);
+    assert!(result.rms_db < -200.0, "{result:?}");
+
+    // and so does every symbol of a frame, differential or not
+    for differential_time in [false, true] {
+        let config = OFDMConfig {
+            differential_time,
+            ..config.clone()
+        };
+        let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+        let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+        let symbols = decoder.get_symbol_evm(&encoder.encode(&data));
+        assert_eq!(symbols.len(), 20);
+        assert!(
+            symbols.iter().all(|result| result.rms_db < -60.0),
+            "{symbols:?}"
+        );
+    }
+}
+
+#[test]
+fn decisions_stand_in_for_the_reference() {
+    let config = config();
+    let modulator = OFDMModulator::new((&config).into());
+    let demodulator = OFDMDemodulator::new((&config).into());
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    let data = payload(modulator.get_bytes_per_symbol());
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+
+    // while every decision is right, the data-aided EVM is the one against the sent points
+    for (seed, snr_db) in [(1, 20.0), (2, 25.0), (3, 30.0)] {
+        let mut received = symbol.clone();
+        AwgnChannel::new(snr_db, seed).apply(&mut received);
+        let (demodulated, data_aided) = demodulator.demodulate_symbol_with_evm(&received);
+        assert_eq!(demodulated, data);
+        let (_, points) = demodulator.demodulate_symbol_with_points(&received);
+        let sent = modem.modulate(&data);
+        assert_eq!(data_aided, evm(&points, &sent), "{snr_db} dB");
+    }
+}
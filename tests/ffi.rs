@@ -0,0 +1,106 @@
+//! Checks that `include/software_modem.h` is the header `cbindgen` generates from the `ffi` module,
+//! then builds the cdylib, compiles the C program in `tests/ffi/smoke.c` against it and the header with
+//! the `cc` crate, and runs it.
+//!
+//! The compiler is the one `cc` finds, like `CC` or `cc`, with the `CFLAGS`.
+//! The test needs the `ffi` feature: `cargo test --features ffi`.
+
+#![cfg(all(feature = "ffi", unix))]
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Returns the C compiler of the host, with the C99 standard, all warnings as errors and the include directory.
+fn c_compiler(root: &Path) -> cc::Tool {
+    // the test runs on the host, `rustc` tells its target as cargo would tell a build script
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc).arg("-vV").output().unwrap();
+    let version = String::from_utf8(version.stdout).unwrap();
+    let host = version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .unwrap();
+    cc::Build::new()
+        .cargo_metadata(false)
+        .target(host)
+        .host(host)
+        .opt_level(0)
+        .std("c99")
+        .warnings(true)
+        .warnings_into_errors(true)
+        .include(root.join("include"))
+        .get_compiler()
+}
+
+#[test]
+fn header_is_generated_by_cbindgen() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(&root)
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let header = std::fs::read(root.join("include/software_modem.h")).unwrap();
+    assert!(
+        header == generated,
+        "include/software_modem.h differs from the declarations of src/ffi.rs, \
+         regenerate it with `cbindgen --config cbindgen.toml --output include/software_modem.h`"
+    );
+}
+
+#[test]
+fn c_program_round_trips_a_frame() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    // the cdylib next to the test binary is overwritten by builds with other features, build one of our own
+    let target = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args([
+            "rustc",
+            "--lib",
+            "--crate-type",
+            "cdylib",
+            "--features",
+            "ffi",
+            "--target-dir",
+        ])
+        .arg(&target)
+        .current_dir(&root)
+        .status()
+        .unwrap();
+    assert!(status.success(), "building the cdylib failed");
+    let lib = target.join("debug");
+    let program = target.join("software_modem_ffi_smoke");
+
+    let status = c_compiler(&root)
+        .to_command()
+        .arg(root.join("tests/ffi/smoke.c"))
+        .arg("-L")
+        .arg(&lib)
+        .arg(format!("-Wl,-rpath,{}", lib.display()))
+        .arg("-lsoftware_modem")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling the C program failed");
+
+    // cargo puts its own directories with the other cdylib first on the library path
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &lib)
+        .env("DYLD_LIBRARY_PATH", &lib)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "the C program failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,108 @@
+/* Calls the C API of the modem like a C or C++ application would, run by tests/ffi.rs. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "software_modem.h"
+
+#define CHECK(condition)                                                      \
+  do {                                                                        \
+    if (!(condition)) {                                                       \
+      fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n", __FILE__, \
+              __LINE__, #condition, sm_last_error_message());                 \
+      return 1;                                                               \
+    }                                                                         \
+  } while (0)
+
+int main(void) {
+  SmConfig config = sm_config_default();
+  config.differential_time = true;
+  config.reed_solomon = true;
+  config.output_peak = 0.5f;
+
+  SmModulator *modulator = sm_modulator_new(&config);
+  SmDemodulator *demodulator = sm_demodulator_new(&config);
+  CHECK(modulator != NULL && demodulator != NULL);
+
+  /* the first call asks for the length of the frame */
+  const char *payload = "Hello from C to the modem and back";
+  size_t payload_len = strlen(payload);
+  size_t frame_len = 0;
+  CHECK(sm_modulate(modulator, (const uint8_t *)payload, payload_len, NULL, 0,
+                    &frame_len) == SmStatus_BufferTooSmall);
+  CHECK(frame_len > 0);
+
+  static float samples[1 << 16];
+  CHECK(frame_len <= sizeof(samples) / sizeof(samples[0]));
+  size_t written = 0;
+  CHECK(sm_modulate(modulator, (const uint8_t *)payload, payload_len, samples,
+                    frame_len, &written) == SmStatus_Ok);
+  CHECK(written == frame_len);
+  for (size_t i = 0; i < written; i++) {
+    CHECK(samples[i] >= -0.5f && samples[i] <= 0.5f);
+  }
+
+  uint8_t decoded[256];
+  size_t decoded_len = 0;
+  CHECK(sm_demodulate(demodulator, samples, written, decoded, sizeof(decoded),
+                      &decoded_len) == SmStatus_Ok);
+  CHECK(decoded_len == payload_len);
+  CHECK(memcmp(decoded, payload, payload_len) == 0);
+
+  /* failures return a status and leave a message */
+  CHECK(sm_demodulate(demodulator, samples, 100, decoded, sizeof(decoded),
+                      &decoded_len) == SmStatus_DecodeFailed);
+  CHECK(strlen(sm_last_error_message()) > 0);
+  CHECK(sm_demodulate(demodulator, NULL, 100, decoded, sizeof(decoded),
+                      &decoded_len) == SmStatus_NullPointer);
+  CHECK(sm_modulate(NULL, (const uint8_t *)payload, payload_len, samples,
+                    frame_len, &written) == SmStatus_NullPointer);
+  CHECK(sm_modulate(modulator, (const uint8_t *)payload, payload_len, samples,
+                    frame_len, NULL) == SmStatus_NullPointer);
+  CHECK(sm_modulator_new(NULL) == NULL);
+
+  /* an invalid configuration panics inside the modem, which is caught */
+  SmConfig invalid = sm_config_default();
+  invalid.num_subcarriers = 0;
+  CHECK(sm_modulator_new(&invalid) == NULL);
+  CHECK(strncmp(sm_last_error_message(), "Panic: ", 7) == 0);
+
+  /* single OFDM symbols and QAM-16 below the frames */
+  SmOfdmModulator *ofdm_modulator = sm_ofdm_modulator_new(&config);
+  SmOfdmDemodulator *ofdm_demodulator = sm_ofdm_demodulator_new(&config);
+  CHECK(ofdm_modulator != NULL && ofdm_demodulator != NULL);
+  size_t bytes_per_symbol = sm_ofdm_modulator_bytes_per_symbol(ofdm_modulator);
+  size_t symbol_len = sm_ofdm_modulator_symbol_length(ofdm_modulator);
+  CHECK(bytes_per_symbol == 24 && symbol_len == 132);
+  CHECK(sm_ofdm_demodulator_symbol_length(ofdm_demodulator) == symbol_len);
+  CHECK(sm_ofdm_modulate_symbol(ofdm_modulator, (const uint8_t *)payload,
+                                bytes_per_symbol, samples, symbol_len,
+                                &written) == SmStatus_Ok);
+  CHECK(sm_ofdm_demodulate_symbol(ofdm_demodulator, samples, written, decoded,
+                                  sizeof(decoded), &decoded_len) == SmStatus_Ok);
+  CHECK(decoded_len == bytes_per_symbol);
+  CHECK(memcmp(decoded, payload, bytes_per_symbol) == 0);
+  CHECK(sm_ofdm_demodulate_symbol(ofdm_demodulator, samples, written - 1,
+                                  decoded, sizeof(decoded),
+                                  &decoded_len) == SmStatus_Panic);
+  sm_ofdm_modulator_free(ofdm_modulator);
+  sm_ofdm_demodulator_free(ofdm_demodulator);
+
+  float symbols[4 * 4];
+  CHECK(sm_qam_modulate((const uint8_t *)payload, 4, symbols, 16, &written) ==
+        SmStatus_Ok);
+  CHECK(written == 16);
+  CHECK(sm_qam_demodulate(symbols, written, decoded, sizeof(decoded),
+                          &decoded_len) == SmStatus_Ok);
+  CHECK(decoded_len == 4 && memcmp(decoded, payload, 4) == 0);
+  float llrs[4 * 8];
+  CHECK(sm_qam_demodulate_soft(symbols, written, llrs, 4 * 8, &decoded_len) ==
+        SmStatus_Ok);
+  CHECK(decoded_len == 32);
+
+  sm_modulator_free(modulator);
+  sm_demodulator_free(demodulator);
+  sm_modulator_free(NULL);
+  puts("ok");
+  return 0;
+}
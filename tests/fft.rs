@@ -0,0 +1,84 @@
+//! Checks the FFTs of the crate, which the modems use without the `std` feature,
+//! against those of `rustfft` and `realfft`, for lengths of a power of 2 and for others.
+
+use realfft::{RealFftPlanner, num_complex::Complex64};
+use rustfft::FftPlanner;
+use software_modem::fft::{
+    ComplexFft, NativeComplexFft, NativeRealForward, NativeRealInverse, RealForwardFft,
+    RealInverseFft,
+};
+
+const LENGTHS: [usize; 9] = [1, 2, 3, 8, 12, 64, 100, 128, 1000];
+
+fn values(length: usize) -> Vec<Complex64> {
+    (0..length as u32)
+        .map(|i| {
+            let bits = i.wrapping_mul(2654435761);
+            Complex64::new(
+                f64::from(bits >> 11 & 0xff) - 128.0,
+                f64::from(bits >> 19 & 0xff) - 128.0,
+            )
+        })
+        .collect()
+}
+
+fn assert_close(actual: &[Complex64], expected: &[Complex64], length: usize) {
+    // the values are up to 128 in magnitude, their sums up to 128 times the length
+    let tolerance = 1e-9 * 128.0 * length as f64;
+    for (k, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (actual - expected).norm() < tolerance,
+            "bin {k} of {length}: {actual} against {expected}"
+        );
+    }
+}
+
+#[test]
+fn complex_ffts_match_rustfft() {
+    let mut planner = FftPlanner::new();
+    for length in LENGTHS {
+        for inverse in [false, true] {
+            let mut expected = values(length);
+            let mut actual = expected.clone();
+            if inverse {
+                planner.plan_fft_inverse(length).process(&mut expected);
+            } else {
+                planner.plan_fft_forward(length).process(&mut expected);
+            }
+            NativeComplexFft::new(length, inverse).process(&mut actual);
+            assert_close(&actual, &expected, length);
+        }
+    }
+}
+
+#[test]
+fn real_ffts_match_realfft() {
+    let mut planner = RealFftPlanner::new();
+    for length in LENGTHS {
+        let samples: Vec<f64> = values(length).iter().map(|value| value.re).collect();
+        let mut expected = vec![Complex64::default(); length / 2 + 1];
+        planner
+            .plan_fft_forward(length)
+            .process(&mut samples.clone(), &mut expected)
+            .unwrap();
+        let mut actual = vec![Complex64::default(); length / 2 + 1];
+        NativeRealForward::new(length).process(&mut samples.clone(), &mut actual);
+        assert_close(&actual, &expected, length);
+
+        // back to the samples, scaled by the length
+        let mut output = vec![0.0; length];
+        NativeRealInverse::new(length).process(&mut actual, &mut output);
+        for (output, sample) in output.iter().zip(&samples) {
+            assert!((output / length as f64 - sample).abs() < 1e-9, "{length}");
+        }
+    }
+}
+
+#[test]
+fn scratch_space_is_reported() {
+    let fft = NativeComplexFft::<f32>::new(100, false);
+    assert_eq!(fft.get_scratch_len(), 256);
+    assert_eq!(NativeComplexFft::<f32>::new(128, true).get_scratch_len(), 0);
+    assert_eq!(NativeRealForward::<f32>::new(100).get_scratch_len(), 356);
+    assert_eq!(NativeRealInverse::<f32>::new(64).get_scratch_len(), 64);
+}
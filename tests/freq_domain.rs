@@ -0,0 +1,205 @@
+//! Checks that the frequency-domain symbols of the demodulator are the ones its bytes are demapped from,
+//! for every kind of equalization, and that their bins and pilots are the ones of the FFT of the samples,
+//! and that the modulator makes the symbols of its bytes from the points of its QAM mapper.
+
+use realfft::{RealFftPlanner, num_complex::Complex32};
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    error::{Buffer, ModemError},
+    ofdm::{
+        OFDMConfig, SlmConfig, SlmSignaling,
+        demodulator::OFDMDemodulator,
+        modulator::{Clipping, OFDMModulator},
+    },
+    qam::{QAMModem, QAMOrder},
+};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn configs() -> Vec<OFDMConfig> {
+    let base = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    };
+    vec![
+        base.clone(),
+        OFDMConfig {
+            differential_time: true,
+            ..base.clone()
+        },
+        OFDMConfig {
+            slm: Some(SlmConfig::default()),
+            ..base.clone()
+        },
+        OFDMConfig {
+            dft_spread: true,
+            ..base.clone()
+        },
+        OFDMConfig {
+            power_allocation: Some((0..48).map(|i| 0.5 + (i % 3) as f32 * 0.5).collect()),
+            slm: Some(SlmConfig {
+                candidates: 4,
+                signaling: SlmSignaling::Blind,
+            }),
+            ..base.clone()
+        },
+        OFDMConfig {
+            oversampling: 2,
+            guard_subcarriers_low: 3,
+            ..base.clone()
+        },
+        OFDMConfig {
+            clipping: Some(Clipping::default()),
+            reserved_subcarriers: vec![13, 37],
+            ..base
+        },
+    ]
+}
+
+#[test]
+fn bytes_are_demapped_from_the_symbols() {
+    for config in configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        let demodulator = OFDMDemodulator::new((&config).into());
+        let qam_modem = QAMModem::new(QAMOrder::QAM16);
+        for seed in 1..4 {
+            let mut symbol = modulator
+                .modulate_symbol(&data(modulator.get_bytes_per_symbol() + seed)[seed..])
+                .unwrap();
+            AwgnChannel::new(12.0, seed as u64).apply(&mut symbol);
+
+            let frequency_domain = demodulator.demodulate_to_symbols(&symbol).unwrap();
+            let bytes = demodulator.demodulate_symbol_from_buffer(&symbol);
+            assert_eq!(qam_modem.demodulate(&frequency_domain.data), bytes);
+            let (with_points, points) = demodulator.demodulate_symbol_with_points(&symbol);
+            assert_eq!(
+                (with_points, points),
+                (bytes.clone(), frequency_domain.data)
+            );
+            let mut into = vec![0; bytes.len()];
+            demodulator.demodulate_symbol_into(&symbol, &mut demodulator.make_scratch(), &mut into);
+            assert_eq!(into, bytes);
+        }
+    }
+}
+
+#[test]
+fn bins_and_pilots_are_the_ones_of_the_fft() {
+    for config in configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        let demodulator = OFDMDemodulator::new((&config).into());
+        let symbol = modulator
+            .modulate_symbol(&data(modulator.get_bytes_per_symbol()))
+            .unwrap();
+        let received: Vec<f32> = symbol.iter().map(|x| 0.25 * x).collect();
+        let frequency_domain = demodulator.demodulate_to_symbols(&received).unwrap();
+
+        let fft_length = 128 * config.oversampling as usize;
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(fft_length);
+        let mut bins = fft.make_output_vec();
+        fft.process(
+            &mut received[received.len() - fft_length..].to_vec(),
+            &mut bins,
+        )
+        .unwrap();
+        assert_eq!(frequency_domain.bins.len(), bins.len());
+        for (bin, expected) in frequency_domain.bins.iter().zip(&bins) {
+            assert!((bin - expected).norm() < 1e-3, "{bin} vs {expected}");
+        }
+
+        // the pilots are the channel, which quartered them
+        let sent = demodulator.demodulate_to_symbols(&symbol).unwrap();
+        assert!(!frequency_domain.pilots.is_empty());
+        for (&(subcarrier, pilot), &(sent_subcarrier, sent_pilot)) in
+            frequency_domain.pilots.iter().zip(&sent.pilots)
+        {
+            assert_eq!(subcarrier, sent_subcarrier);
+            assert_eq!(pilot, frequency_domain.bins[subcarrier as usize]);
+            assert!((pilot - 0.25 * sent_pilot).norm() < 1e-3 * sent_pilot.norm());
+        }
+
+        match (config.differential_time, frequency_domain.channel_estimate) {
+            (true, estimate) => assert_eq!(estimate, None),
+            (false, Some(gain)) => {
+                let sent_gain = sent.channel_estimate.unwrap();
+                assert!((gain - 0.25 * sent_gain).abs() < 1e-3 * sent_gain);
+            }
+            (false, None) => panic!("no channel estimate of {config:?}"),
+        }
+    }
+
+    // a silent symbol has nothing to equalize
+    let demodulator = OFDMDemodulator::new((&configs()[0]).into());
+    let silence = demodulator
+        .demodulate_to_symbols(&vec![0.0; demodulator.get_symbol_length()])
+        .unwrap();
+    assert_eq!(silence.channel_estimate, None);
+    assert!(silence.bins.iter().all(|&bin| bin == Complex32::default()));
+}
+
+#[test]
+fn points_of_the_qam_mapper_make_the_symbols_of_the_bytes() {
+    let qam_modem = QAMModem::new(QAMOrder::QAM16);
+    for config in configs() {
+        let modulator = OFDMModulator::new((&config).into());
+        assert_eq!(
+            modulator.get_num_data_subcarriers(),
+            config.get_data_subcarriers().len()
+        );
+        for seed in 0..3 {
+            let bytes = data(modulator.get_bytes_per_symbol() + seed);
+            let bytes = &bytes[seed..];
+            let mut expected = vec![0.0; modulator.get_symbol_length()];
+            modulator.modulate_buffer_as_symbol(bytes, &mut expected);
+
+            let mut symbol = vec![0.0; modulator.get_symbol_length()];
+            modulator
+                .modulate_freq_symbols(&qam_modem.modulate(bytes), &mut symbol)
+                .unwrap();
+            assert_eq!(symbol, expected, "{config:?}");
+        }
+    }
+
+    let modulator = OFDMModulator::new((&configs()[0]).into());
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    assert_eq!(
+        modulator.modulate_freq_symbols(&[Complex32::default(); 47], &mut symbol),
+        Err(ModemError::BufferLengthMismatch {
+            buffer: Buffer::Input,
+            expected: 48,
+            got: 47,
+        })
+    );
+    assert_eq!(
+        modulator.modulate_freq_symbols(&[Complex32::default(); 48], &mut symbol[1..]),
+        Err(ModemError::BufferLengthMismatch {
+            buffer: Buffer::Output,
+            expected: 144,
+            got: 143,
+        })
+    );
+}
+
+#[test]
+fn wrong_input_is_an_error() {
+    let demodulator = OFDMDemodulator::new((&configs()[0]).into());
+    assert_eq!(
+        demodulator.demodulate_to_symbols(&[0.0; 128]),
+        Err(ModemError::BufferLengthMismatch {
+            buffer: Buffer::Input,
+            expected: 144,
+            got: 128,
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "Input buffer length must be 144, but got 145")]
+fn long_symbol() {
+    OFDMDemodulator::new((&configs()[0]).into()).demodulate_symbol_from_buffer(&[0.0; 145]);
+}
@@ -0,0 +1,120 @@
+//! Sends frames over the [FSK](software_modem::phy::fsk) modem through the frame layer, clean and
+//! over white noise as strong as the signal, late or early, and checks that tones which are not orthogonal are refused.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    error::ModemError,
+    frame::{FrameDecoder, FrameEncoder},
+    phy::{
+        PhyDemodulator, PhyModulator,
+        fsk::{FskConfig, FskDemodulator, FskModulator, FskOrder},
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(order: FskOrder) -> FskConfig {
+    FskConfig {
+        order,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn clean_frames() {
+    for (order, block_length) in [(FskOrder::Binary, 640), (FskOrder::Quaternary, 320)] {
+        let encoder = FrameEncoder::new(FskModulator::new(config(order)));
+        let decoder = FrameDecoder::new(FskDemodulator::new(config(order)));
+        assert_eq!(encoder.get_bytes_per_symbol(), 1);
+        for length in [0, 1, 2, 50] {
+            let payload = data(length as u32);
+            let samples = encoder.encode(&payload);
+            assert_eq!(samples.len(), length * block_length);
+            assert_eq!(samples.len(), decoder.get_frame_length(length));
+            // the phase continues from symbol to symbol, so the envelope stays within the amplitude
+            assert!(samples.iter().all(|sample| sample.abs() <= 0.5));
+            assert_eq!(decoder.decode(&samples), payload, "{order:?}");
+        }
+    }
+}
+
+#[test]
+fn blocks_on_their_own() {
+    let modulator = FskModulator::new(config(FskOrder::Quaternary));
+    let demodulator = FskDemodulator::new(config(FskOrder::Quaternary));
+    let mut block = vec![0.0; 320];
+    for byte in [0x00, 0x1b, 0xa5, 0xff] {
+        modulator.modulate(&[byte], &mut block).unwrap();
+        let mut output = [0];
+        demodulator.demodulate(&block, &mut output).unwrap();
+        assert_eq!(output, [byte]);
+    }
+
+    // 00, 01, 11 and 10 are sent on the tones from the lowest, so 0x1b on the first, second, fourth and third
+    modulator.modulate(&[0x1b], &mut block).unwrap();
+    for (symbol, tone) in [0, 1, 3, 2].into_iter().enumerate() {
+        let powers = demodulator.tone_powers(&block[80 * symbol..80 * (symbol + 1)]);
+        for (other, &power) in powers.iter().enumerate() {
+            if other != tone {
+                assert!(powers[tone] > 100.0 * power);
+            }
+        }
+    }
+
+    assert_eq!(
+        demodulator.demodulate(&block[1..], &mut [0]),
+        Err(ModemError::BufferLength {
+            expected: 320,
+            got: 319
+        })
+    );
+}
+
+#[test]
+fn binary_at_0_db() {
+    let config = FskConfig {
+        tone_spacing_hz: 100.0,
+        ..Default::default()
+    };
+    let encoder = FrameEncoder::new(FskModulator::new(config.clone()));
+    let decoder = FrameDecoder::new(FskDemodulator::new(config));
+    let payload = data(200);
+    let frame = encoder.encode(&payload);
+
+    // as much noise as signal, over a frame which starts late or early
+    for (seed, delay) in [(1, 0), (2, 23), (3, -31)] {
+        let mut samples = vec![0.0; frame.len()];
+        for (n, sample) in samples.iter_mut().enumerate() {
+            if let Some(&x) = frame.get((n as isize - delay) as usize) {
+                *sample = x;
+            }
+        }
+        AwgnChannel::with_reference_power(0.0, 0.125, seed).apply(&mut samples);
+        assert_eq!(decoder.decode(&samples), payload, "{delay}");
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "Tone spacing must be a whole multiple of the symbol rate of 100 Hz for orthogonal tones, but got 150 Hz"
+)]
+fn non_orthogonal_spacing() {
+    FskDemodulator::new(FskConfig {
+        tone_spacing_hz: 150.0,
+        ..Default::default()
+    });
+}
+
+#[test]
+#[should_panic(expected = "Tones must be between 0 and 4000 Hz, but got 1000 to 4600 Hz")]
+fn tones_above_nyquist() {
+    FskModulator::new(FskConfig {
+        order: FskOrder::Quaternary,
+        tone_spacing_hz: 1200.0,
+        ..Default::default()
+    });
+}
@@ -0,0 +1,190 @@
+//! Runs the [entry points of the fuzz targets](software_modem::testing::fuzz) on the interesting inputs,
+//! NaN patterns, infinities, silence and full-scale square waves, within frames and on their own,
+//! and checks that a signal which never falls silent holds a bounded number of samples in the stream demodulator.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    fec::FecScheme,
+    frame::CodingConfig,
+    stream::StreamDemodulator,
+    testing::fuzz::{
+        configs, demodulate_symbol, fec_decoders, frame_decoder, from_samples, stream,
+    },
+};
+
+/// Returns a full-scale square wave, with a period in samples.
+fn square_wave(period: usize, length: usize) -> Vec<f32> {
+    (0..length)
+        .map(|n| if n % period < period / 2 { 1.0 } else { -1.0 })
+        .collect()
+}
+
+/// The samples of the interesting inputs, for a configuration whose frames are given.
+fn corpus(frame: &[f32]) -> Vec<Vec<f32>> {
+    let mut nan_frame = frame.to_vec();
+    for sample in nan_frame.iter_mut().step_by(97) {
+        *sample = f32::NAN;
+    }
+    let mut burst_frame = frame.to_vec();
+    let end = frame.len() / 2 + 50;
+    burst_frame[frame.len() / 2..end].fill(f32::INFINITY);
+    let mut saturated_frame = frame.to_vec();
+    for sample in &mut saturated_frame {
+        *sample = sample.signum();
+    }
+
+    vec![
+        Vec::new(),
+        vec![0.0; 5000],
+        vec![f32::NAN; 5000],
+        (0..5000)
+            .map(|n| if n % 2 == 0 { f32::NAN } else { 0.0 })
+            .collect(),
+        vec![f32::INFINITY; 800],
+        (0..800)
+            .map(|n| {
+                if n % 3 == 0 {
+                    f32::NEG_INFINITY
+                } else {
+                    f32::MAX
+                }
+            })
+            .collect(),
+        vec![f32::MIN_POSITIVE / 4.0; 800],
+        square_wave(2, 800),
+        square_wave(16, 800),
+        square_wave(500, 800),
+        nan_frame,
+        burst_frame,
+        saturated_frame,
+        frame[..frame.len() / 3].to_vec(),
+    ]
+}
+
+#[test]
+fn sample_targets_survive_the_corpus() {
+    for (index, (ofdm, coding)) in configs().into_iter().enumerate() {
+        let frame = CodedOFDMModulator::new(ofdm, coding).encode_frame(&[0xa5; 20]);
+        for samples in corpus(&frame) {
+            let mut data = vec![index as u8];
+            data.extend(from_samples(&samples));
+            demodulate_symbol(&data);
+            frame_decoder(&data);
+
+            // in blocks of 1, 7, 200 and 64 samples
+            let mut data = vec![index as u8, 4, 0, 6, 199, 63];
+            data.extend(from_samples(&samples));
+            stream(&data);
+        }
+    }
+
+    // inputs that end within their header bytes
+    for data in [&[][..], &[0], &[1, 200], &[2, 3, 0, 0], &[255; 7]] {
+        demodulate_symbol(data);
+        frame_decoder(data);
+        stream(data);
+    }
+}
+
+#[test]
+fn fec_targets_survive_the_corpus() {
+    let llrs = [
+        vec![f32::NAN; 300],
+        vec![f32::INFINITY; 300],
+        (0..300)
+            .map(|n| {
+                if n % 2 == 0 {
+                    f32::INFINITY
+                } else {
+                    f32::NEG_INFINITY
+                }
+            })
+            .collect(),
+        vec![0.0; 300],
+        vec![f32::MAX; 300],
+        (0..300).map(|n| n as f32 - 150.0).collect(),
+    ];
+    for decoder in 0..=255u8 {
+        for llrs in &llrs {
+            let mut data = vec![decoder];
+            data.extend(from_samples(llrs));
+            fec_decoders(&data);
+        }
+        // all zero and all one bits, and garbage code words with erasures everywhere
+        fec_decoders(&[decoder; 1000]);
+        fec_decoders(&[vec![decoder], vec![0; 1000]].concat());
+        fec_decoders(
+            &(0..1000u32)
+                .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8 ^ decoder)
+                .collect::<Vec<u8>>(),
+        );
+        fec_decoders(&[decoder]);
+    }
+}
+
+#[test]
+fn the_longest_burst_holds_the_longest_frame() {
+    let (ofdm, coding) = configs().swap_remove(0);
+    let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), coding.clone());
+    let symbol_length = demodulator.get_symbol_length();
+    // the largest payload with the most redundant scheme the header can announce
+    let longest = CodedOFDMModulator::new(
+        ofdm,
+        CodingConfig {
+            scheme: FecScheme::Repetition(15),
+            reed_solomon: true,
+            ..coding
+        },
+    )
+    .get_frame_length(u16::MAX as usize);
+    assert_eq!(demodulator.get_max_frame_length(), longest);
+
+    let stream = StreamDemodulator::new(demodulator, 0.01, 200);
+    assert_eq!(stream.get_max_burst_length(), symbol_length + longest + 200);
+}
+
+#[test]
+fn a_signal_that_never_falls_silent_is_cut_into_bursts() {
+    let (ofdm, coding) = configs().swap_remove(0);
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, 200);
+    let frame = modulator.encode_frame(&[0x3c; 100]);
+    stream.set_max_burst_length(2 * frame.len());
+
+    // a stuck transmitter, at full scale
+    let wave = square_wave(10, 20 * frame.len());
+    for block in wave.chunks(1000) {
+        stream.push(block);
+    }
+    assert!(
+        stream.get_frames_failed() >= 9,
+        "{}",
+        stream.get_frames_failed()
+    );
+    #[cfg(feature = "perf")]
+    {
+        stream.enable_metrics(1);
+        for block in wave.chunks(1000) {
+            stream.push(block);
+        }
+        let snapshot = stream.get_metrics().unwrap().snapshot();
+        assert!(snapshot.squelch_high_water <= 4 * frame.len());
+        assert!(snapshot.frame_high_water <= 2 * frame.len());
+    }
+
+    // and the frames after it still decode
+    stream.push(&[0.0; 500]);
+    let mut signal = frame.clone();
+    signal.extend([0.0; 500]);
+    assert_eq!(stream.push(&signal), [vec![0x3c; 100]]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Maximum burst length must be at least the symbol length 136, but got 100"
+)]
+fn bursts_hold_a_symbol() {
+    let (ofdm, coding) = configs().swap_remove(0);
+    StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, 200)
+        .set_max_burst_length(100);
+}
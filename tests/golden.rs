@@ -0,0 +1,150 @@
+//! Checks that the [reference fixtures](software_modem::testing::golden::reference_fixtures) still have the samples
+//! they had when they were stored, that the stored samples still decode, and that [check_fixtures] tells the differences apart.
+//!
+//! After an intended change of the samples, `WRITE_GOLDEN=1 cargo test --test golden` stores the new ones.
+
+use software_modem::{
+    coded::CodedOFDMDemodulator,
+    frame::{CodingConfig, FrameDecoder},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator},
+    testing::golden::{
+        Fixture, GoldenMismatch, WRITE_GOLDEN, Waveform, check_fixtures, read_fixtures,
+        reference_fixtures, write_fixtures,
+    },
+};
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/golden.bin");
+
+#[test]
+fn fixtures_match_the_stored_samples() {
+    check_fixtures(GOLDEN, &reference_fixtures(), 1e-4).unwrap_or_else(|error| panic!("{error}"));
+}
+
+#[test]
+fn stored_fixtures_decode() {
+    for fixture in read_fixtures(GOLDEN).unwrap() {
+        let decoded = match &fixture.waveform {
+            Waveform::Symbol(config) => {
+                OFDMDemodulator::new(config.into()).demodulate_symbol_from_buffer(&fixture.samples)
+            }
+            Waveform::Frame(config) => {
+                let mut decoded =
+                    FrameDecoder::new(OFDMDemodulator::new(config.into())).decode(&fixture.samples);
+                decoded.truncate(fixture.payload.len());
+                decoded
+            }
+            Waveform::CodedFrame(ofdm, coding) => {
+                CodedOFDMDemodulator::new(ofdm.clone(), coding.clone())
+                    .decode_frame(&fixture.samples)
+                    .unwrap()
+            }
+        };
+        assert_eq!(decoded, fixture.payload, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn differences_are_told_apart() {
+    if std::env::var_os(WRITE_GOLDEN).is_some() {
+        return;
+    }
+    let path =
+        std::env::temp_dir().join(format!("software_modem_golden_{}.bin", std::process::id()));
+    let config = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let fixtures = vec![
+        Fixture::generate("frame", Waveform::Frame(config.clone()), b"frame"),
+        Fixture::generate(
+            "coded",
+            Waveform::CodedFrame(config.clone(), CodingConfig::default()),
+            b"coded",
+        ),
+    ];
+    write_fixtures(&path, &fixtures).unwrap();
+    assert_eq!(check_fixtures(&path, &fixtures, 0.0), Ok(()));
+
+    let mut renamed = fixtures.clone();
+    renamed[1].name = "renamed".to_string();
+    assert_eq!(
+        check_fixtures(&path, &renamed, 0.0),
+        Err(GoldenMismatch::Names {
+            expected: vec!["frame".to_string(), "coded".to_string()],
+            got: vec!["frame".to_string(), "renamed".to_string()],
+        })
+    );
+    assert!(matches!(
+        check_fixtures(&path, &fixtures[..1], 0.0),
+        Err(GoldenMismatch::Names { .. })
+    ));
+
+    // another configuration, payload or length
+    let reconfigured = vec![
+        fixtures[0].clone(),
+        Fixture::generate(
+            "coded",
+            Waveform::CodedFrame(
+                config.clone(),
+                CodingConfig {
+                    reed_solomon: true,
+                    ..Default::default()
+                },
+            ),
+            b"coded",
+        ),
+    ];
+    let waveform = Err(GoldenMismatch::Waveform {
+        name: "coded".to_string(),
+    });
+    assert_eq!(check_fixtures(&path, &reconfigured, 0.0), waveform);
+    let mut repaid = fixtures.clone();
+    repaid[1].payload[0] ^= 1;
+    assert_eq!(check_fixtures(&path, &repaid, 0.0), waveform);
+    let mut shortened = fixtures.clone();
+    shortened[0].samples.pop();
+    assert!(matches!(
+        check_fixtures(&path, &shortened, 0.0),
+        Err(GoldenMismatch::Length { expected, got, .. }) if got == expected - 1
+    ));
+
+    // a sample beyond the tolerance, or NaN
+    let mut moved = fixtures.clone();
+    let expected = moved[1].samples[42];
+    moved[1].samples[42] += 0.01;
+    let got = moved[1].samples[42];
+    assert_eq!(
+        check_fixtures(&path, &moved, 0.005),
+        Err(GoldenMismatch::Sample {
+            name: "coded".to_string(),
+            index: 42,
+            expected,
+            got,
+        })
+    );
+    assert_eq!(check_fixtures(&path, &moved, 0.02), Ok(()));
+    moved[1].samples[42] = f32::NAN;
+    assert!(matches!(
+        check_fixtures(&path, &moved, 1e6),
+        Err(GoldenMismatch::Sample { index: 42, .. })
+    ));
+
+    // a file that is not one of fixtures
+    std::fs::write(&path, [1, 2, 3]).unwrap();
+    assert!(matches!(
+        check_fixtures(&path, &fixtures, 0.0),
+        Err(GoldenMismatch::File(_))
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        check_fixtures(&path, &fixtures, 0.0),
+        Err(GoldenMismatch::File(_))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Tolerance must be non-negative and finite, but got -1")]
+fn tolerance_is_non_negative() {
+    let _ = check_fixtures(GOLDEN, &[], -1.0);
+}
@@ -0,0 +1,151 @@
+//! Checks that a [guard interval](software_modem::ofdm::GuardInterval) gives the cyclic prefix of the fraction
+//! of the FFT length, the same symbols and frames as the prefix in samples, for the real, coded and complex modems.
+//!
+//! The real modem resolves the fraction against `2 * num_subcarriers` before the oversampling,
+//! the complex one against `num_subcarriers`, and a serialized configuration keeps the samples only.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{
+        GuardInterval, OFDMConfig,
+        complex::{ComplexOFDMConfig, ComplexOFDMModulator},
+        demodulator::OFDMDemodulator,
+        modulator::OFDMModulator,
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+#[test]
+fn fractions_are_the_prefix_in_samples() {
+    for (guard, samples) in GuardInterval::FRACTIONS.into_iter().zip([64, 32, 16, 8]) {
+        assert_eq!(guard.cyclic_prefix_length(256), samples);
+        assert_eq!(
+            GuardInterval::Samples(samples).cyclic_prefix_length(256),
+            samples
+        );
+        assert_eq!(guard.get_denominator(), Some(256 / samples));
+    }
+    assert_eq!(GuardInterval::Samples(7).cyclic_prefix_length(64), 7);
+    assert_eq!(GuardInterval::Samples(7).get_denominator(), None);
+    assert_eq!(GuardInterval::default(), GuardInterval::Quarter);
+    assert_eq!(GuardInterval::ThirtySecond.to_string(), "1/32");
+    assert_eq!(GuardInterval::Samples(7).to_string(), "7 samples");
+}
+
+#[test]
+fn real_symbols_match_the_prefix_in_samples() {
+    let payload = data(400);
+    for oversampling in [1, 2] {
+        for guard in GuardInterval::FRACTIONS {
+            let fraction = OFDMConfig {
+                num_subcarriers: 128,
+                oversampling,
+                guard_interval: Some(guard),
+                ..Default::default()
+            };
+            let samples = guard.cyclic_prefix_length(256);
+            assert_eq!(fraction.get_cyclic_prefix_length(), samples);
+            let in_samples = OFDMConfig {
+                guard_interval: Some(GuardInterval::Samples(samples)),
+                ..fraction.clone()
+            };
+            // and the cyclic prefix length the guard interval replaces
+            let plain = OFDMConfig {
+                cyclic_prefix_length: samples,
+                guard_interval: None,
+                ..fraction.clone()
+            };
+
+            let modulator = OFDMModulator::new((&fraction).into());
+            assert_eq!(
+                modulator.get_symbol_length(),
+                ((256 + samples) * oversampling) as usize
+            );
+            let mut symbols = Vec::new();
+            let stats = modulator.modulate_batch(&payload, &mut symbols);
+            for config in [&in_samples, &plain] {
+                let other = OFDMModulator::new(config.into());
+                assert_eq!(other.get_symbol_length(), modulator.get_symbol_length());
+                let mut other_symbols = Vec::new();
+                other.modulate_batch(&payload, &mut other_symbols);
+                assert_eq!(other_symbols, symbols);
+                let mut decoded = Vec::new();
+                OFDMDemodulator::new(config.into()).demodulate_batch(&symbols, &mut decoded);
+                assert_eq!(decoded, payload[..stats.consumed]);
+            }
+
+            // the coded modem across both
+            let coding = CodingConfig {
+                reed_solomon: true,
+                ..Default::default()
+            };
+            let frame =
+                CodedOFDMModulator::new(fraction.clone(), coding.clone()).encode_frame(&payload);
+            assert_eq!(
+                CodedOFDMModulator::new(in_samples.clone(), coding.clone()).encode_frame(&payload),
+                frame
+            );
+            assert_eq!(
+                CodedOFDMDemodulator::new(in_samples, coding).decode_frame(&frame),
+                Ok(payload.clone())
+            );
+
+            // serialized as the samples it gives
+            let restored = OFDMConfig::from_bytes(&fraction.to_bytes()).unwrap();
+            assert_eq!(restored, plain);
+        }
+    }
+}
+
+#[test]
+fn complex_symbols_match_the_prefix_in_samples() {
+    for guard in GuardInterval::FRACTIONS {
+        let fraction = ComplexOFDMConfig {
+            num_subcarriers: 64,
+            guard_interval: Some(guard),
+            ..Default::default()
+        };
+        let samples = guard.cyclic_prefix_length(64);
+        let modulator = ComplexOFDMModulator::new(fraction.clone());
+        assert_eq!(modulator.get_symbol_length(), (64 + samples) as usize);
+
+        let payload = data(modulator.get_bytes_per_symbol() as u32 * 3);
+        let symbols = modulator.modulate_symbols(&payload);
+        for config in [
+            ComplexOFDMConfig {
+                guard_interval: Some(GuardInterval::Samples(samples)),
+                ..fraction.clone()
+            },
+            ComplexOFDMConfig {
+                cyclic_prefix_length: samples,
+                guard_interval: None,
+                ..fraction.clone()
+            },
+        ] {
+            assert_eq!(
+                ComplexOFDMModulator::new(config).modulate_symbols(&payload),
+                symbols
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "Guard interval must be a whole number of samples, but got 1/32 of 40")]
+fn fractions_are_whole_samples() {
+    // 1/32 of the 40 samples of the FFT
+    OFDMModulator::new(
+        (&OFDMConfig {
+            num_subcarriers: 20,
+            guard_interval: Some(GuardInterval::ThirtySecond),
+            ..Default::default()
+        })
+            .into(),
+    );
+}
@@ -0,0 +1,86 @@
+//! Checks the [HDLC](software_modem::hdlc) framing of packet radio: the stuffing of the bits, the flags,
+//! aborts and frame check sequences, and streams pushed in pieces.
+
+use software_modem::hdlc::{FLAG, HdlcDecoder, encode_frame, nrzi_decode, nrzi_encode};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Returns the most 1s in a row of the bits.
+fn longest_run(bits: &[u8]) -> usize {
+    bits.split(|&bit| bit == 0)
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn frames_are_stuffed_between_flags() {
+    let flag: Vec<u8> = (0..8).map(|i| (FLAG >> i) & 1).collect();
+    for frame in [vec![0xff; 20], vec![FLAG; 20], data(300), vec![0; 3]] {
+        let bits = encode_frame(&frame, 3);
+        assert_eq!(bits[..24], flag.repeat(3));
+        assert_eq!(bits[bits.len() - 8..], flag);
+        // a flag only at the ends
+        assert!(longest_run(&bits[24..bits.len() - 8]) <= 5);
+
+        let mut decoder = HdlcDecoder::new();
+        assert_eq!(decoder.push(&bits), vec![frame.clone()]);
+        assert_eq!(decoder.get_frames(), 1);
+        assert_eq!(decoder.get_fcs_errors(), 0);
+    }
+
+    // 160 1s need 32 stuffed 0s, before the 16 bits of the check
+    assert!(encode_frame(&[0xff; 20], 1).len() >= 8 + 160 + 32 + 16 + 8);
+}
+
+#[test]
+fn streams_in_pieces() {
+    let frames = [data(20), data(100), vec![0x7e, 0x7d, 0xff]];
+    // noise between the frames, which never holds six 1s
+    let mut bits = vec![1, 0, 1, 1, 0, 0, 1, 0, 1, 1, 1, 0];
+    for frame in &frames {
+        bits.extend(encode_frame(frame, 2));
+        bits.extend([0, 1, 1, 0, 1]);
+    }
+    let line = nrzi_encode(&bits, 1);
+    assert_eq!(nrzi_decode(&line, 1), bits);
+
+    for piece in [1, 7, 64, bits.len()] {
+        let mut decoder = HdlcDecoder::new();
+        let decoded: Vec<Vec<u8>> = nrzi_decode(&line, 1)
+            .chunks(piece)
+            .flat_map(|bits| decoder.push(bits))
+            .collect();
+        assert_eq!(decoded, frames, "{piece}");
+    }
+}
+
+#[test]
+fn bad_frames_are_dropped() {
+    let frame = data(30);
+    let good = encode_frame(&frame, 1);
+
+    // a flipped bit fails the check
+    let mut corrupted = good.clone();
+    corrupted[8 + 17] ^= 1;
+    let mut decoder = HdlcDecoder::new();
+    assert!(decoder.push(&corrupted).is_empty());
+    assert_eq!(decoder.get_fcs_errors(), 1);
+
+    // seven 1s abort the frame, until the next flag
+    let mut aborted = good[..8 + 50].to_vec();
+    aborted.extend([1; 7]);
+    aborted.extend(&good);
+    let mut decoder = HdlcDecoder::new();
+    assert_eq!(decoder.push(&aborted), vec![frame]);
+    assert_eq!(decoder.get_fcs_errors(), 0);
+
+    // flags in a row are no frames
+    let mut decoder = HdlcDecoder::new();
+    assert!(decoder.push(&encode_frame(&[], 10)[..80]).is_empty());
+    assert_eq!(decoder.get_fcs_errors(), 0);
+}
@@ -0,0 +1,189 @@
+//! Checks that demodulating symbols in place decides the same data as copying them out of the cyclic prefix first,
+//! to the last bit, for the symbols on their own and for the frames of the stream demodulator.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    dsp::FirFilter,
+    frame::{CodingConfig, FrameDecoder, FrameEncoder},
+    ofdm::{
+        OFDMConfig, SlmConfig, SlmSignaling,
+        demodulator::{GenericOFDMDemodulator, OFDMDemodulator},
+        modulator::{Clipping, GenericOFDMModulator, OFDMModulator},
+    },
+    qam::QAMModem,
+    samples::Sample,
+    stream::StreamDemodulator,
+};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Adds deterministic noise of the given amplitude.
+fn noisy<T: Sample>(samples: &[T], amplitude: f64, seed: u32) -> Vec<T> {
+    let mut noise = seed;
+    samples
+        .iter()
+        .map(|&sample| {
+            noise ^= noise << 13;
+            noise ^= noise >> 17;
+            noise ^= noise << 5;
+            sample + T::cast(amplitude * (noise as f64 / u32::MAX as f64 - 0.5))
+        })
+        .collect()
+}
+
+fn configs() -> Vec<OFDMConfig> {
+    let config = OFDMConfig {
+        num_subcarriers: 128,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    };
+    let reserved = OFDMConfig {
+        reserved_subcarriers: vec![11, 51, 91],
+        ..config.clone()
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&reserved).into()).get_bytes_per_symbol();
+    vec![
+        config.clone(),
+        OFDMConfig {
+            clipping: Some(Clipping::default()),
+            slm: Some(SlmConfig {
+                candidates: 4,
+                signaling: SlmSignaling::Explicit,
+            }),
+            ..config.clone()
+        },
+        OFDMConfig {
+            power_allocation: Some(
+                (0..num_data_subcarriers)
+                    .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                    .collect(),
+            ),
+            slm: Some(SlmConfig {
+                candidates: 4,
+                signaling: SlmSignaling::Blind,
+            }),
+            ..reserved
+        },
+        OFDMConfig {
+            oversampling: 2,
+            null_dc: true,
+            ..config.clone()
+        },
+        OFDMConfig {
+            differential_time: true,
+            ..config
+        },
+    ]
+}
+
+fn check_symbols<T: Sample>(config: &OFDMConfig) {
+    let modulator = GenericOFDMModulator::<T>::new(config.into());
+    let demodulator = GenericOFDMDemodulator::<T>::new(config.into());
+    let symbol_length = demodulator.get_symbol_length();
+    let bytes_per_symbol = demodulator.get_bytes_per_symbol();
+    let cyclic_prefix = (config.cyclic_prefix_length * config.oversampling) as usize;
+
+    let mut copying = demodulator.make_scratch();
+    let mut in_place = demodulator.make_scratch();
+    for index in 0..8 {
+        let data = data(bytes_per_symbol + index);
+        let mut symbol = vec![T::zero(); symbol_length];
+        modulator.modulate_buffer_as_symbol(&data[index..], &mut symbol);
+        // wrong decisions must be the same too
+        let symbol = noisy(&symbol, 0.1 * index as f64, index as u32 + 1);
+
+        let mut expected = vec![0; bytes_per_symbol];
+        demodulator.demodulate_symbol_into(&symbol, &mut copying, &mut expected);
+        let mut clobbered = symbol.clone();
+        let mut output = vec![0; bytes_per_symbol];
+        demodulator.demodulate_symbol_in_place(&mut clobbered, &mut in_place, &mut output);
+        assert_eq!(output, expected, "symbol {} of {:?}", index, config);
+        assert_eq!(clobbered[..cyclic_prefix], symbol[..cyclic_prefix]);
+        if index == 0 && !config.differential_time {
+            assert_eq!(output, data);
+        }
+    }
+}
+
+#[test]
+fn symbols_demodulate_in_place_like_copied() {
+    for config in configs() {
+        check_symbols::<f32>(&config);
+        check_symbols::<f64>(&config);
+    }
+}
+
+#[test]
+fn filtered_frames_demodulate_in_place_like_copied() {
+    // the filtered samples are demodulated in place, the batch of the hard decisions copies them
+    let filter = FirFilter::bandpass(300.0 / 8000.0, 3400.0 / 8000.0, 101);
+    let config = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        guard_subcarriers_low: 8,
+        guard_subcarriers_high: 13,
+        tx_filter: Some(filter.clone()),
+        rx_filter: Some(filter),
+        ..Default::default()
+    };
+    let encoder = FrameEncoder::new(OFDMModulator::new((&config).into()));
+    let decoder = FrameDecoder::new(OFDMDemodulator::new((&config).into()));
+    let samples = noisy(&encoder.encode(&data(1000)), 0.05, 7);
+
+    let points: Vec<_> = decoder
+        .get_constellation(&samples)
+        .into_iter()
+        .flatten()
+        .collect();
+    let decided = QAMModem::new(config.qam_order).demodulate(&points);
+    assert_eq!(decided, decoder.decode(&samples));
+    assert_eq!(&decided[..1000], data(1000));
+}
+
+#[test]
+fn stream_frames_decode_like_whole_frames() {
+    let configs = [
+        OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            ..Default::default()
+        },
+        OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            differential_time: true,
+            soft_output: false,
+            roll_off: 4,
+            ..Default::default()
+        },
+    ];
+    let coding = CodingConfig {
+        reed_solomon: true,
+        erasure_threshold: Some(10.0),
+        ..Default::default()
+    };
+    for ofdm in configs {
+        let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+        let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), coding.clone());
+        let payloads: Vec<Vec<u8>> = (0..4).map(|i| data(20 + 150 * i)).collect();
+
+        let mut samples = vec![0.0; 500];
+        for payload in &payloads {
+            let frame = modulator.encode_frame(payload);
+            assert_eq!(demodulator.decode_frame(&frame).as_ref(), Ok(payload));
+            samples.extend(frame);
+            samples.extend([0.0; 500]);
+        }
+
+        let mut stream = StreamDemodulator::new(demodulator, 0.01, 200);
+        let decoded: Vec<Vec<u8>> = samples
+            .chunks(256)
+            .flat_map(|block| stream.push(block))
+            .collect();
+        assert_eq!(decoded, payloads, "{:?}", ofdm);
+    }
+}
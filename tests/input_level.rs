@@ -0,0 +1,195 @@
+//! Checks the [meter](software_modem::stream::StreamDemodulator::input_stats) of the input of a stream
+//! over quiet, nominal and clipped versions of the same signal.
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    stream::{InputHealth, StreamDemodulator},
+};
+
+const WINDOW: usize = 1000;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+fn stream() -> StreamDemodulator {
+    let mut stream = StreamDemodulator::new(
+        CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+        0.01,
+        1000,
+    );
+    stream.set_input_meter(1.0, WINDOW);
+    stream
+}
+
+/// Returns frames back to back, with a peak at the level, in whole windows of the meter.
+fn signal(peak: f32) -> Vec<f32> {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let mut samples: Vec<f32> = (0..4)
+        .flat_map(|_| modulator.encode_frame(&data(300)))
+        .collect();
+    samples.truncate(samples.len() / WINDOW * WINDOW);
+    let max = samples.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+    samples.iter().map(|x| x * peak / max).collect()
+}
+
+/// Returns the signal with too much gain, cut at a level.
+fn clipped(level: f32) -> Vec<f32> {
+    signal(4.0)
+        .into_iter()
+        .map(|x| x.clamp(-level, level))
+        .collect()
+}
+
+#[test]
+fn levels_are_classified() {
+    let nominal = signal(0.5);
+    let quiet = signal(0.0005);
+    // the signal with the DC offset of a cheap sound card
+    let mut offset = nominal.clone();
+    offset.iter_mut().for_each(|x| *x += 0.05);
+    // limited by the analog stages just below full scale
+    let near = clipped(0.95);
+    let clipped = clipped(1.0);
+
+    for (samples, health) in [
+        (&quiet, InputHealth::TooQuiet),
+        (&nominal, InputHealth::Ok),
+        (&offset, InputHealth::Ok),
+        (&near, InputHealth::NearClipping),
+        (&clipped, InputHealth::Clipping),
+    ] {
+        let mut stream = stream();
+        for block in samples.chunks(256) {
+            stream.push(block);
+        }
+        let input = stream.input_stats();
+        assert_eq!(input.health, health, "{input:?}");
+        if health != InputHealth::Clipping {
+            assert_eq!(input.clipped_samples, 0);
+        }
+    }
+
+    // 60 dB apart, the RMS level of a window of OFDM some 10 dB below its peak
+    let mut stream = stream();
+    stream.push(&nominal);
+    let loud = stream.input_stats();
+    assert!(-20.0 < loud.rms_dbfs && loud.rms_dbfs < -10.0, "{loud:?}");
+    assert!((loud.peak_dbfs - 20.0 * 0.5f32.log10()).abs() < 3.0);
+    assert!(loud.dc_offset.abs() < 0.01);
+    let mut stream = self::stream();
+    stream.push(&quiet);
+    assert!((loud.rms_dbfs - stream.input_stats().rms_dbfs - 60.0).abs() < 0.01);
+
+    let mut stream = self::stream();
+    stream.push(&offset);
+    assert!((stream.input_stats().dc_offset - 0.05).abs() < 0.01);
+
+    // every sample cut at full scale is counted, until a reset
+    let mut stream = self::stream();
+    stream.push(&clipped);
+    let count = clipped.iter().filter(|x| x.abs() >= 1.0).count() as u64;
+    assert!(count > 0);
+    assert_eq!(stream.input_stats().clipped_samples, count);
+    stream.reset_stats();
+    assert_eq!(stream.input_stats().clipped_samples, 0);
+    assert_eq!(stream.input_stats().health, InputHealth::Clipping);
+
+    // a full scale of 16-bit samples
+    let mut stream = self::stream();
+    stream.set_input_meter(32768.0, WINDOW);
+    let scaled: Vec<f32> = nominal.iter().map(|x| x * 32768.0).collect();
+    stream.push(&scaled);
+    assert!((stream.input_stats().rms_dbfs - loud.rms_dbfs).abs() < 0.01);
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn changes_of_health_are_warned_once() {
+    use std::{
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        Event, Level, Subscriber,
+        field::{Field, Visit},
+    };
+    use tracing_subscriber::{
+        filter::LevelFilter,
+        layer::{Context, Layer, SubscriberExt},
+    };
+
+    /// Keeps the health of every warning.
+    #[derive(Clone, Default)]
+    struct Warnings(Arc<Mutex<Vec<String>>>);
+
+    /// Finds the message and the health of an event.
+    #[derive(Default)]
+    struct Health {
+        message: String,
+        health: Option<String>,
+    }
+
+    impl Visit for Health {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "health" {
+                self.health = Some(value.into());
+            }
+        }
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                self.message = format!("{value:?}");
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Warnings {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            assert_eq!(*event.metadata().level(), Level::WARN);
+            let mut health = Health::default();
+            event.record(&mut health);
+            assert_eq!(health.message, "input health changed");
+            self.0.lock().unwrap().push(health.health.unwrap());
+        }
+    }
+
+    let mut samples = signal(0.0005);
+    samples.extend(signal(0.5));
+    samples.extend(signal(0.5));
+    samples.extend(clipped(1.0));
+    samples.extend(signal(0.5));
+
+    let warnings = Warnings::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(warnings.clone())
+        .with(LevelFilter::WARN);
+    let mut stream = stream();
+    tracing::subscriber::with_default(subscriber, || {
+        for block in samples.chunks(128) {
+            stream.push(block);
+        }
+    });
+    assert_eq!(
+        *warnings.0.lock().unwrap(),
+        ["TooQuiet", "Ok", "Clipping", "Ok"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Full scale must be positive and finite, but got 0")]
+fn full_scale_is_positive() {
+    stream().set_input_meter(0.0, WINDOW);
+}
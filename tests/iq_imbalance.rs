@@ -0,0 +1,255 @@
+//! Passes symbols of the complex modem through an imbalance of the I and Q branches, and checks that
+//! the demodulator estimates it from the images between mirrored subcarriers, over noise and multipath,
+//! and that its compensation decodes the symbols which fail without it.
+//!
+//! QAM-16 takes a large imbalance to break, QAM-64 breaks at an imbalance as small as 1 dB and 5°.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, IqImbalance, MultipathChannel},
+    metrics::count_bit_errors,
+    ofdm::complex::{
+        ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator, IqCompensation,
+    },
+    qam::QAMOrder,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(qam_order: QAMOrder) -> ComplexOFDMConfig {
+    ComplexOFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        qam_order,
+        ..Default::default()
+    }
+}
+
+/// Returns the symbols of the data through the imbalance and then the channel.
+fn receive(
+    qam_order: QAMOrder,
+    data: &[u8],
+    imbalance: IqImbalance,
+    channel: &mut dyn Channel,
+) -> Vec<Complex32> {
+    let mut samples = ComplexOFDMModulator::new(config(qam_order)).modulate_symbols(data);
+    channel.apply_complex(&mut samples);
+    let mut imbalance = imbalance;
+    imbalance.apply_complex(&mut samples);
+    samples
+}
+
+fn assert_estimate(estimate: IqImbalance, imbalance: IqImbalance, gain_db: f32, phase_deg: f32) {
+    assert!(
+        (estimate.gain_db - imbalance.gain_db).abs() < gain_db
+            && (estimate.phase_deg - imbalance.phase_deg).abs() < phase_deg,
+        "{estimate:?} for {imbalance:?}"
+    );
+}
+
+#[test]
+fn compensation_decodes_what_the_imbalance_breaks() {
+    let data = data(4800);
+    let imbalance = IqImbalance {
+        gain_db: 3.0,
+        phase_deg: 15.0,
+    };
+    let samples = receive(
+        QAMOrder::QAM16,
+        &data,
+        imbalance,
+        &mut AwgnChannel::new(25.0, 1),
+    );
+    let mut demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+
+    let (decoded, uncompensated) = demodulator.demodulate_symbols_with_report(&samples);
+    let errors = count_bit_errors(&data, &decoded);
+    assert!(errors.byte_errors > 10, "{errors:?}");
+    assert_estimate(uncompensated.iq_imbalance.unwrap(), imbalance, 0.1, 0.5);
+
+    demodulator.set_iq_compensation(IqCompensation::Blind);
+    assert_eq!(demodulator.get_iq_compensation(), IqCompensation::Blind);
+    let (decoded, compensated) = demodulator.demodulate_symbols_with_report(&samples);
+    assert_eq!(decoded, data);
+    assert_eq!(demodulator.demodulate_symbols(&samples), data);
+    // the estimate is of the samples as received, the SNR of the points after the correction
+    assert_eq!(compensated.iq_imbalance, uncompensated.iq_imbalance);
+    let (before, after) = (uncompensated.snr_db.unwrap(), compensated.snr_db.unwrap());
+    assert!(before < 14.0 && after > 23.0, "{before} dB, {after} dB");
+}
+
+#[test]
+fn compensation_decodes_qam64_at_a_small_imbalance() {
+    let data = data(7200);
+    let imbalance = IqImbalance {
+        gain_db: 1.0,
+        phase_deg: 5.0,
+    };
+    for (snr_db, seed) in [(60.0, 6), (30.0, 7)] {
+        let samples = receive(
+            QAMOrder::QAM64,
+            &data,
+            imbalance,
+            &mut AwgnChannel::new(snr_db, seed),
+        );
+        let mut demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM64));
+
+        // the image 22.8 dB down moves the outer points past the decision boundaries
+        let (decoded, report) = demodulator.demodulate_symbols_with_report(&samples);
+        let errors = count_bit_errors(&data, &decoded);
+        // measured 338 bit errors at 60 dB and 635 at 30 dB
+        assert!(errors.bit_errors > 100, "{snr_db} dB: {errors:?}");
+        // fewer symbols carry the bytes than with QAM-16, measured off by 0.06 dB and 0.14°
+        assert_estimate(report.iq_imbalance.unwrap(), imbalance, 0.1, 0.5);
+
+        demodulator.set_iq_compensation(IqCompensation::Blind);
+        assert_eq!(
+            demodulator.demodulate_symbols(&samples),
+            data,
+            "{snr_db} dB"
+        );
+    }
+}
+
+#[test]
+fn small_imbalances_are_estimated_and_removed() {
+    // an image 22.8 dB down, which limits the SNR of a clean channel
+    let data = data(4800);
+    for (gain_db, phase_deg) in [(1.0, 5.0), (-1.0, -5.0), (0.5, 0.0), (0.0, 2.0)] {
+        let imbalance = IqImbalance { gain_db, phase_deg };
+        let samples = receive(
+            QAMOrder::QAM16,
+            &data,
+            imbalance,
+            &mut AwgnChannel::new(60.0, 2),
+        );
+        let mut demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+        let (decoded, report) = demodulator.demodulate_symbols_with_report(&samples);
+        assert_eq!(decoded, data);
+        assert_estimate(report.iq_imbalance.unwrap(), imbalance, 0.05, 0.3);
+        let before = report.snr_db.unwrap();
+
+        demodulator.set_iq_compensation(IqCompensation::Blind);
+        let (decoded, report) = demodulator.demodulate_symbols_with_report(&samples);
+        assert_eq!(decoded, data);
+        let after = report.snr_db.unwrap();
+        assert!(
+            before < imbalance.get_image_rejection_db() + 1.0 && after > 45.0,
+            "{imbalance:?}: {before} dB, {after} dB"
+        );
+    }
+}
+
+#[test]
+fn the_estimate_ignores_the_channel() {
+    let data = data(9600);
+    let imbalance = IqImbalance {
+        gain_db: 1.0,
+        phase_deg: 5.0,
+    };
+    let demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+
+    // an echo within the cyclic prefix gives mirrored subcarriers different gains, noise blurs the images
+    let samples = receive(
+        QAMOrder::QAM16,
+        &data,
+        imbalance,
+        &mut MultipathChannel::two_ray(5, -3.0),
+    );
+    assert_estimate(
+        demodulator.estimate_iq_imbalance(&samples),
+        imbalance,
+        0.05,
+        0.3,
+    );
+    let samples = receive(
+        QAMOrder::QAM16,
+        &data,
+        imbalance,
+        &mut AwgnChannel::new(15.0, 3),
+    );
+    assert_estimate(
+        demodulator.estimate_iq_imbalance(&samples),
+        imbalance,
+        0.1,
+        0.6,
+    );
+
+    // without an imbalance, the estimate stays near none, and the compensation keeps the decisions
+    let samples = receive(
+        QAMOrder::QAM16,
+        &data,
+        IqImbalance::default(),
+        &mut AwgnChannel::new(30.0, 4),
+    );
+    assert_estimate(
+        demodulator.estimate_iq_imbalance(&samples),
+        IqImbalance::default(),
+        0.05,
+        0.3,
+    );
+    let mut compensated = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+    compensated.set_iq_compensation(IqCompensation::Blind);
+    assert_eq!(compensated.demodulate_symbols(&samples), data);
+}
+
+#[test]
+fn single_symbols_are_compensated_by_a_fixed_imbalance() {
+    let data = data(4800);
+    let imbalance = IqImbalance {
+        gain_db: 3.0,
+        phase_deg: 15.0,
+    };
+    let samples = receive(
+        QAMOrder::QAM16,
+        &data,
+        imbalance,
+        &mut AwgnChannel::new(35.0, 5),
+    );
+    let mut demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+    let symbol_length = demodulator.get_symbol_length();
+    let bytes_per_symbol = demodulator.get_bytes_per_symbol();
+    let symbols = || {
+        samples
+            .chunks(symbol_length)
+            .zip(data.chunks(bytes_per_symbol))
+    };
+
+    assert!(
+        symbols().any(|(symbol, data)| demodulator.demodulate_symbol_from_buffer(symbol) != data)
+    );
+    // estimated once over the first symbols, and corrected in every symbol after
+    let estimate = demodulator.estimate_iq_imbalance(&samples[..50 * symbol_length]);
+    demodulator.set_iq_compensation(IqCompensation::Fixed(estimate));
+    for (symbol, data) in symbols() {
+        assert_eq!(demodulator.demodulate_symbol_from_buffer(symbol), data);
+        let llrs = demodulator.demodulate_symbol_soft_from_buffer(symbol);
+        assert_eq!(llrs.len(), 8 * bytes_per_symbol);
+    }
+}
+
+#[test]
+fn real_samples_and_silence_have_no_imbalance() {
+    let mut imbalance = IqImbalance {
+        gain_db: 1.0,
+        phase_deg: 5.0,
+    };
+    let mut samples = vec![0.5, -0.25, 1.0];
+    imbalance.apply(&mut samples);
+    assert_eq!(samples, [0.5, -0.25, 1.0]);
+
+    assert_eq!(
+        IqImbalance::default().get_image_rejection_db(),
+        f32::INFINITY
+    );
+    let demodulator = ComplexOFDMDemodulator::new(config(QAMOrder::QAM16));
+    let silence = vec![Complex32::default(); 10 * demodulator.get_symbol_length()];
+    assert_eq!(
+        demodulator.estimate_iq_imbalance(&silence),
+        IqImbalance::default()
+    );
+}
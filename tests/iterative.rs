@@ -0,0 +1,176 @@
+//! Checks the pieces of iterative demapping and decoding, the demapper with a priori LLRs and the a posteriori LLRs
+//! of the convolutional code, and that the passes after the first decode frames over a frequency-selective channel
+//! which a single pass loses.
+//!
+//! The frames are [differential](OFDMConfig::differential_time), which equalizes the two-ray channel within
+//! the cyclic prefix, at an SNR where most of them fail to decode in a single pass.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    error::ModemError,
+    fec::convolutional::ConvolutionalCode,
+    frame::CodingConfig,
+    iterative::{IterativeConfig, IterativeDecoder},
+    ofdm::OFDMConfig,
+    qam::{QAMModem, QAMOrder},
+    rng::SimulationRng,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        differential_time: true,
+        soft_output: true,
+        ..Default::default()
+    }
+}
+
+fn decoder(iterations: usize) -> IterativeDecoder {
+    IterativeDecoder::new(
+        CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+        IterativeConfig { iterations },
+    )
+}
+
+#[test]
+fn priors_weigh_the_points() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    let mut rng = SimulationRng::new(5);
+    let symbols: Vec<Complex32> = modem
+        .modulate(&data(500))
+        .iter()
+        .map(|symbol| symbol + Complex32::new(rng.gaussian() as f32, rng.gaussian() as f32))
+        .collect();
+
+    // without a priori LLRs the search over the points rounds like the demapper over the axes
+    let llrs = modem.demodulate_soft(&symbols);
+    let zero = modem.demodulate_soft_with_priors(&symbols, Some(&vec![0.0; llrs.len()]));
+    for (llr, zero) in llrs.iter().zip(&zero) {
+        assert!((llr - zero).abs() < 1e-4, "{llr} vs {zero}");
+    }
+
+    // the bits of the points, sure to the decoder, leave the decisions of Gray labels as they are,
+    // and make them surer where the other bits of the point rule out the nearest points
+    let bits: Vec<f32> = llrs
+        .iter()
+        .map(|&llr| if llr < 0.0 { -10.0 } else { 10.0 })
+        .collect();
+    let informed = modem.demodulate_soft_with_priors(&symbols, Some(&bits));
+    for (llr, informed) in llrs.iter().zip(&informed) {
+        assert_eq!(*llr < 0.0, *informed < 0.0);
+        assert!(informed.abs() >= llr.abs() - 1e-4, "{llr} vs {informed}");
+    }
+    let surer = llrs
+        .iter()
+        .zip(&informed)
+        .filter(|(llr, informed)| informed.abs() > llr.abs() + 1.0)
+        .count();
+    assert!(surer > llrs.len() / 10, "{surer} of {}", llrs.len());
+}
+
+#[test]
+fn posterior_follows_the_best_path() {
+    let code = ConvolutionalCode::k7_rate_half();
+    let bits: Vec<u8> = data(40)
+        .iter()
+        .flat_map(|&byte| (0..8).map(move |i| (byte >> i) & 1))
+        .collect();
+    let mut rng = SimulationRng::new(9);
+    let llrs: Vec<f32> = code
+        .encode(&bits)
+        .iter()
+        .map(|&bit| if bit == 0 { 1.0 } else { -1.0 } + 0.6 * rng.gaussian() as f32)
+        .collect();
+
+    // the signs of the a posteriori LLRs are the coded bits of the path the Viterbi decoder picks
+    let decoded = code.decode_soft(&llrs);
+    let posterior = code.decode_posterior(&llrs);
+    let hard: Vec<u8> = posterior.iter().map(|&llr| u8::from(llr < 0.0)).collect();
+    assert_eq!(hard, code.encode(&decoded));
+    assert_eq!(decoded, bits);
+
+    // the channel and the code agree on most bits, where the code adds to the LLR
+    let agreeing = llrs
+        .iter()
+        .zip(&posterior)
+        .filter(|(llr, posterior)| {
+            (*llr < &0.0) == (*posterior < &0.0) && posterior.abs() > llr.abs()
+        })
+        .count();
+    assert!(
+        agreeing > llrs.len() * 3 / 4,
+        "{agreeing} of {}",
+        llrs.len()
+    );
+}
+
+#[test]
+fn iterations_rescue_frames_over_a_selective_channel() {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let (single, iterative) = (decoder(1), decoder(4));
+    let payload = data(200);
+
+    const FRAMES: u64 = 200;
+    let (mut lost_single, mut lost_iterative, mut passes) = (0, 0, 0);
+    for seed in 0..FRAMES {
+        let mut samples = modulator.encode_frame(&payload);
+        ChannelChain::new()
+            .with(MultipathChannel::two_ray(5, -2.0))
+            .with(AwgnChannel::new(20.0, seed))
+            .apply(&mut samples);
+
+        // a single pass is the soft decoder of the demodulator
+        let first = single.decode(&samples);
+        assert_eq!(first.iterations, 1);
+        assert_eq!(
+            first.payload,
+            single.get_demodulator().decode_frame(&samples)
+        );
+        let outcome = iterative.decode(&samples);
+        if first.payload.is_ok() {
+            assert_eq!(outcome, first);
+        }
+        lost_single += usize::from(first.payload.is_err());
+        lost_iterative += usize::from(outcome.payload.is_err());
+        passes += outcome.iterations;
+    }
+
+    // measured 65% and 62% lost over 2000 frames, about 0.3 dB: with Gray labels the feedback only
+    // makes the decisions surer, so only a few frames are rescued
+    assert!(
+        (110..=150).contains(&lost_single),
+        "{lost_single} lost in a single pass"
+    );
+    assert!(
+        lost_iterative < lost_single,
+        "{lost_iterative} lost with iterations, {lost_single} without"
+    );
+    // the frames decoding in the first pass stop there
+    assert!(passes < 4 * FRAMES as usize, "{passes} passes");
+}
+
+#[test]
+fn noise_ends_after_the_first_pass() {
+    let decoder = decoder(4);
+    let mut samples = vec![0.0; 20 * decoder.get_demodulator().get_symbol_length()];
+    AwgnChannel::with_reference_power(0.0, 0.01, 3).apply(&mut samples);
+    let outcome = decoder.decode(&samples);
+    assert_eq!(outcome.payload, Err(ModemError::InvalidHeader));
+    assert_eq!(outcome.iterations, 1);
+    assert_eq!(decoder.get_config().iterations, 4);
+}
+
+#[test]
+#[should_panic(expected = "Iterations must be at least 1, but got 0")]
+fn zero_iterations() {
+    decoder(0);
+}
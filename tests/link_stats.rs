@@ -0,0 +1,123 @@
+//! Checks the [counters](software_modem::stream::LinkStats) of a stream over a capture of clean, noisy,
+//! corrupted and missing frames, and the ones of the modulator that wrote it.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    rng::SimulationRng,
+    stream::{LinkStats, StreamDemodulator, StreamModulator, TransmitStats},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+const GAP: usize = 2000;
+
+fn noise(rng: &mut SimulationRng, length: usize) -> Vec<f32> {
+    (0..length)
+        .map(|_| (0.3 * (rng.uniform() - 0.5)) as f32)
+        .collect()
+}
+
+/// Returns the capture with the modulator that wrote it: a clean frame, a noisy one, a burst of noise,
+/// a frame whose last symbols are lost in noise, and a clean frame cut off by the end of the capture.
+fn capture() -> (Vec<f32>, StreamModulator) {
+    let modulator = StreamModulator::new(
+        CodedOFDMModulator::new(config(), CodingConfig::default()),
+        GAP,
+    );
+    let mut rng = SimulationRng::new(7);
+    let mut samples = vec![0.0; 1000];
+    modulator.write_frame(&data(100), &mut samples).unwrap();
+
+    let mut frame = Vec::new();
+    modulator.write_frame(&data(200), &mut frame).unwrap();
+    let length = frame.len() - GAP;
+    AwgnChannel::new(10.0, 3).apply(&mut frame[..length]);
+    samples.extend(frame);
+
+    samples.extend(noise(&mut rng, 1000));
+    samples.extend([0.0; GAP]);
+
+    let mut frame = Vec::new();
+    modulator.write_frame(&data(300), &mut frame).unwrap();
+    let length = frame.len() - GAP;
+    frame[length - 4 * 72..length].copy_from_slice(&noise(&mut rng, 4 * 72));
+    samples.extend(frame);
+
+    let mut frame = Vec::new();
+    modulator.write_frame(&data(50), &mut frame).unwrap();
+    frame.truncate(frame.len() - GAP);
+    samples.extend(frame);
+
+    // a noise floor below the squelch level, the silence of a real capture
+    for sample in &mut samples {
+        *sample += (0.004 * (rng.uniform() - 0.5)) as f32;
+    }
+    (samples, modulator)
+}
+
+#[test]
+fn counters_of_a_mixed_capture() {
+    let (samples, modulator) = capture();
+    assert_eq!(
+        modulator.stats(),
+        TransmitStats {
+            frames_sent: 4,
+            bytes_sent: 650,
+            samples_written: [100, 200, 300, 50]
+                .iter()
+                .map(|&length| modulator.get_frame_length(length) as u64)
+                .sum(),
+        }
+    );
+    modulator.reset_stats();
+    assert_eq!(modulator.stats(), TransmitStats::default());
+
+    let expected = LinkStats {
+        frames_attempted: 5,
+        frames_decoded: 3,
+        // the frame with the lost symbols, and the burst of noise, whose header checks by chance with its 8 bit CRC
+        frames_crc_failed: 2,
+        frames_fec_corrected: 1,
+        // a header symbol and the payload, 24 bytes a symbol with the rate 1/2 code on 48 subcarriers of 4 bits
+        symbols_demodulated: 10 + 19 + 6,
+        sync_acquisitions: 5,
+        sync_losses: 5,
+        bytes_delivered: 350,
+        // the 5 stretches of silence after the hang, without the symbol of pre-roll before every burst,
+        // and shifted by the quiet samples at the edges of the frames
+        samples_discarded: 4320,
+    };
+    for block_length in [1, 300, samples.len()] {
+        let mut stream = StreamDemodulator::new(
+            CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+            0.01,
+            1000,
+        );
+        let mut payloads: Vec<_> = samples
+            .chunks(block_length)
+            .flat_map(|block| stream.push(block))
+            .collect();
+        payloads.extend(stream.flush());
+        assert_eq!(payloads, [data(100), data(200), data(50)]);
+        assert_eq!(stream.stats(), expected, "in blocks of {block_length}");
+
+        stream.reset_stats();
+        assert_eq!(stream.stats(), LinkStats::default());
+        assert_eq!(stream.get_frames_decoded(), 3);
+    }
+}
@@ -0,0 +1,108 @@
+//! Checks the [modulation error ratio](software_modem::metrics::mer_db) against the EVM of the same points,
+//! against the SNR of uncoded QAM-16 over known noise, and the running [MerEstimator](software_modem::metrics::MerEstimator).
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    metrics::{MerEstimator, evm, mer_db},
+    qam::{QAMModem, QAMOrder},
+};
+
+fn payload(length: usize) -> Vec<u8> {
+    (0..length as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Returns the QAM-16 points of the payload, with noise at the SNR relative to the mean power of the constellation.
+fn noisy_points(
+    modem: &QAMModem,
+    length: usize,
+    snr_db: f32,
+    seed: u64,
+) -> (Vec<Complex32>, Vec<Complex32>) {
+    let sent = modem.modulate(&payload(length));
+    let mut received = sent.clone();
+    AwgnChannel::with_reference_power(snr_db, modem.mean_power(), seed)
+        .apply_complex(&mut received);
+    (sent, received)
+}
+
+#[test]
+fn mer_is_the_negative_evm() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    for (seed, snr_db) in [(1, 5.0), (2, 15.0), (3, 30.0)] {
+        let (sent, received) = noisy_points(&modem, 2000, snr_db, seed);
+        // against the decisions, and against the sent points, even with wrong decisions at 5 dB
+        let decisions = modem.nearest_points(&received);
+        for reference in [&decisions, &sent] {
+            let mer = mer_db(&received, reference);
+            let evm = evm(&received, reference);
+            assert!((mer + evm.rms_db).abs() < 1e-4, "{mer} dB, {evm:?}");
+            assert!(
+                (mer + 20.0 * (evm.rms_percent / 100.0).log10()).abs() < 1e-4,
+                "{mer} dB, {evm:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn mer_is_the_snr_of_uncoded_qam16() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    // 8000 points estimate the noise power within a few percent, a few tenths of a dB
+    for (seed, snr_db) in [(4, 20.0), (5, 25.0), (6, 30.0), (7, 40.0)] {
+        let (sent, received) = noisy_points(&modem, 4000, snr_db, seed);
+        let decisions = modem.nearest_points(&received);
+        assert_eq!(decisions, sent, "{snr_db} dB");
+        let mer = mer_db(&received, &decisions);
+        assert!((mer - snr_db).abs() < 0.2, "{snr_db} dB: {mer} dB");
+    }
+
+    // at low SNRs the wrong decisions are nearer than the sent points, and the decisions overestimate the MER
+    let (sent, received) = noisy_points(&modem, 4000, 8.0, 8);
+    let data_aided = mer_db(&received, &modem.nearest_points(&received));
+    let true_mer = mer_db(&received, &sent);
+    assert!((true_mer - 8.0).abs() < 0.2, "{true_mer} dB");
+    assert!(data_aided > true_mer + 1.0, "{data_aided} dB");
+}
+
+#[test]
+fn estimator_tracks_the_blocks() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    let mut estimator = MerEstimator::new(0.2);
+    let add = |estimator: &mut MerEstimator, snr_db: f32, seed: u64| {
+        let (_, received) = noisy_points(&modem, 200, snr_db, seed);
+        estimator.add(&received, &modem.nearest_points(&received));
+    };
+
+    // blocks at 25 dB settle around it, closer than a single block of 400 points
+    for seed in 0..50 {
+        add(&mut estimator, 25.0, seed);
+    }
+    let settled = estimator.get_db().unwrap();
+    assert!((settled - 25.0).abs() < 0.3, "{settled} dB");
+
+    // after a drop to 15 dB, a fifth of the way per block
+    add(&mut estimator, 15.0, 100);
+    let first = estimator.get_db().unwrap();
+    let expected = -10.0 * (0.8 * 10f32.powf(-2.5) + 0.2 * 10f32.powf(-1.5)).log10();
+    assert!((first - expected).abs() < 0.5, "{first} dB, {expected} dB");
+    for seed in 101..150 {
+        add(&mut estimator, 15.0, seed);
+    }
+    let dropped = estimator.get_db().unwrap();
+    assert!((dropped - 15.0).abs() < 0.3, "{dropped} dB");
+
+    // an empty block changes nothing, and a reset forgets the blocks
+    estimator.add::<f32>(&[], &[]);
+    assert_eq!(estimator.get_db(), Some(dropped));
+    estimator.reset();
+    assert_eq!(estimator.get_db(), None);
+}
+
+#[test]
+#[should_panic(expected = "Smoothing must be between 0 and 1, but got 0")]
+fn estimator_needs_a_positive_smoothing() {
+    MerEstimator::new(0.0);
+}
@@ -0,0 +1,195 @@
+//! Checks that the modulator still produces exactly the samples it produced when they were stored,
+//! for plain symbols, for every step between the mapping and the cyclic prefix,
+//! and for every layout of the pilots and the data subcarriers in the bins.
+//!
+//! After an intended change of the samples, `WRITE_GOLDEN=1 cargo test --test modulator_golden` stores the new ones.
+
+use software_modem::ofdm::{
+    OFDMConfig, SlmConfig, SlmSignaling,
+    demodulator::OFDMDemodulator,
+    modulator::{Clipping, OFDMModulator, OFDMModulatorConfig},
+};
+
+/// Little-endian `f32` samples of the symbols of every configuration, one after the other.
+const GOLDEN: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/data/modulator_golden.f32"
+));
+
+/// Symbols modulated with every configuration.
+const NUM_SYMBOLS: usize = 3;
+
+fn configs() -> Vec<OFDMModulatorConfig> {
+    let plain = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let reserved = OFDMConfig {
+        num_subcarriers: 256,
+        cyclic_prefix_length: 32,
+        reserved_subcarriers: vec![11, 51, 91, 131, 171, 211],
+        ..Default::default()
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&reserved).into()).get_bytes_per_symbol();
+    let shaped = OFDMConfig {
+        power_allocation: Some(
+            (0..num_data_subcarriers)
+                .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                .collect(),
+        ),
+        ..reserved
+    };
+    let oversampled = OFDMConfig {
+        num_subcarriers: 128,
+        cyclic_prefix_length: 16,
+        oversampling: 2,
+        guard_subcarriers_low: 4,
+        guard_subcarriers_high: 8,
+        ..Default::default()
+    };
+    let mut configs = vec![
+        (&plain).into(),
+        OFDMModulatorConfig {
+            slm: Some(SlmConfig::default()),
+            ..(&shaped).into()
+        },
+        OFDMModulatorConfig {
+            clipping: Some(Clipping::default()),
+            ..(&oversampled).into()
+        },
+    ];
+    configs.extend(layouts().iter().map(OFDMModulatorConfig::from));
+    configs
+}
+
+/// Layouts of the pilots and the data subcarriers, from runs of one data subcarrier between pilots
+/// to a single run from DC to the highest subcarrier.
+fn layouts() -> Vec<OFDMConfig> {
+    let layout = OFDMConfig {
+        num_subcarriers: 128,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    };
+    let sparse = OFDMConfig {
+        pilot_subcarrier_every: 64,
+        guard_subcarriers_low: 5,
+        guard_subcarriers_high: 9,
+        ..layout.clone()
+    };
+    let num_data_subcarriers = 2 * OFDMModulator::new((&sparse).into()).get_bytes_per_symbol();
+    vec![
+        OFDMConfig {
+            pilot_subcarrier_every: 2,
+            ..layout.clone()
+        },
+        OFDMConfig {
+            pilot_subcarrier_every: 3,
+            ..layout.clone()
+        },
+        OFDMConfig {
+            pilot_subcarrier_every: 7,
+            null_dc: false,
+            ..layout.clone()
+        },
+        sparse.clone(),
+        // only the pilot on DC
+        OFDMConfig {
+            pilot_subcarrier_every: 1000,
+            null_dc: false,
+            ..layout.clone()
+        },
+        // pilots moved off the masked subcarriers, and reserved subcarriers splitting the runs
+        OFDMConfig {
+            masked_subcarriers: vec![8, 9, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40],
+            reserved_subcarriers: vec![50, 77],
+            ..layout.clone()
+        },
+        OFDMConfig {
+            oversampling: 4,
+            pilot_subcarrier_every: 16,
+            guard_subcarriers_low: 3,
+            guard_subcarriers_high: 10,
+            ..layout
+        },
+        // long runs with the allocated power and the phases of selected mapping
+        OFDMConfig {
+            power_allocation: Some(
+                (0..num_data_subcarriers)
+                    .map(|i| 0.5 + (i % 3) as f32 * 0.25)
+                    .collect(),
+            ),
+            slm: Some(SlmConfig {
+                candidates: 4,
+                signaling: SlmSignaling::Blind,
+            }),
+            ..sparse
+        },
+    ]
+}
+
+/// Modulates the symbols of every configuration.
+fn modulate() -> Vec<f32> {
+    let mut samples = Vec::new();
+    let mut offset = 0u32;
+    for config in configs() {
+        let modulator = OFDMModulator::new(config);
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+        for _ in 0..NUM_SYMBOLS {
+            let data: Vec<u8> = (offset..offset + modulator.get_bytes_per_symbol() as u32)
+                .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+                .collect();
+            offset += data.len() as u32;
+            modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+            samples.extend_from_slice(&symbol);
+        }
+    }
+    samples
+}
+
+#[test]
+fn symbols_match_the_stored_samples() {
+    let samples = modulate();
+    if std::env::var_os("WRITE_GOLDEN").is_some() {
+        let bytes: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+        std::fs::write(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/modulator_golden.f32"
+            ),
+            bytes,
+        )
+        .unwrap();
+        return;
+    }
+    let golden: Vec<f32> = GOLDEN
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    assert_eq!(samples.len(), golden.len());
+    for (i, (sample, golden)) in samples.iter().zip(&golden).enumerate() {
+        assert!(
+            sample.to_bits() == golden.to_bits(),
+            "Sample {i} is {sample}, but was {golden}"
+        );
+    }
+}
+
+#[test]
+fn every_layout_round_trips() {
+    for layout in layouts() {
+        let modulator = OFDMModulator::new((&layout).into());
+        let demodulator = OFDMDemodulator::new((&layout).into());
+        let data: Vec<u8> = (0..modulator.get_bytes_per_symbol() as u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+            .collect();
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+        modulator.modulate_buffer_as_symbol(&data, &mut symbol);
+        assert_eq!(
+            demodulator.demodulate_symbol_from_buffer(&symbol),
+            data,
+            "{:?}",
+            layout
+        );
+    }
+}
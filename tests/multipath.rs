@@ -0,0 +1,329 @@
+//! Round trips of coded frames through the [multipath channel](software_modem::channel::MultipathChannel):
+//! the streaming convolution itself, the equalization of differential mode over echoes within the cyclic prefix,
+//! and the interleaving of the coded bits over the notches of an echo.
+//! Also checks the delay spread of the named [profiles](software_modem::channel::MultipathProfile),
+//! the equalization over the pedestrian one, and the statistics of the fading with a Doppler spread,
+//! on real samples through their analytic signal too.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel, MultipathProfile},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::{CodingConfig, Interleaving},
+    ofdm::OFDMConfig,
+};
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        differential_time: true,
+        ..Default::default()
+    }
+}
+
+/// Returns the number of 20 frames which are lost over the channels made from the seeds.
+fn lost_frames(coding: CodingConfig, channel: impl Fn(u64) -> ChannelChain) -> usize {
+    let payload: Vec<u8> = (0..400u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let modulator = CodedOFDMModulator::new(ofdm(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm(), coding);
+    (0..20)
+        .filter(|&seed| {
+            let mut frame = modulator.encode_frame(&payload);
+            channel(seed).apply(&mut frame);
+            demodulator.decode_frame(&frame).ok().as_deref() != Some(&payload[..])
+        })
+        .count()
+}
+
+#[test]
+fn buffers_continue_each_other() {
+    let taps = [
+        (0, Complex32::new(0.9, 0.1)),
+        (3, Complex32::new(-0.4, 0.3)),
+        (17, Complex32::new(0.2, -0.2)),
+    ];
+    let real: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).sin()).collect();
+    let complex: Vec<Complex32> = real.iter().map(|&x| Complex32::new(x, -0.5 * x)).collect();
+
+    let mut channel = MultipathChannel::new(&taps);
+    let (mut whole_real, mut whole_complex) = (real.clone(), complex.clone());
+    channel.apply(&mut whole_real);
+    channel.apply_complex(&mut whole_complex);
+
+    // buffers shorter and longer than the longest delay
+    for length in [7, 100] {
+        channel.reset();
+        let (mut blocks_real, mut blocks_complex) = (real.clone(), complex.clone());
+        for block in blocks_real.chunks_mut(length) {
+            channel.apply(block);
+        }
+        for block in blocks_complex.chunks_mut(length) {
+            channel.apply_complex(block);
+        }
+        assert_eq!(blocks_real, whole_real);
+        assert_eq!(blocks_complex, whole_complex);
+    }
+
+    // an impulse comes back as the taps, the real samples with the real parts of the gains
+    let mut impulse = vec![0.0; 20];
+    impulse[0] = 1.0;
+    channel.reset();
+    channel.apply(&mut impulse);
+    for (delay, sample) in impulse.iter().enumerate() {
+        let gain = taps
+            .iter()
+            .find(|&&(tap, _)| tap == delay)
+            .map_or(0.0, |(_, gain)| gain.re);
+        assert_eq!(*sample, gain);
+    }
+}
+
+#[test]
+fn differential_mode_equalizes_echoes_within_the_prefix() {
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    // an echo 6 dB down, and random profiles reaching up to the end of the cyclic prefix
+    let two_ray = |seed| {
+        ChannelChain::new()
+            .with(MultipathChannel::two_ray(5, -6.0))
+            .with(AwgnChannel::new(25.0, seed))
+    };
+    assert_eq!(lost_frames(coding.clone(), two_ray), 0);
+    let exponential = |seed| {
+        ChannelChain::new()
+            .with(MultipathChannel::exponential(8, 2.0, seed))
+            .with(AwgnChannel::new(30.0, seed))
+    };
+    assert_eq!(lost_frames(coding, exponential), 0);
+}
+
+#[test]
+fn interleaving_spreads_the_notches_of_an_echo() {
+    // an echo as strong as the direct path and 2 samples late cancels the subcarriers around a quarter of the sample rate
+    let notched = |seed| {
+        ChannelChain::new()
+            .with(MultipathChannel::two_ray(2, 0.0))
+            .with(AwgnChannel::new(25.0, seed))
+    };
+    let plain = lost_frames(
+        CodingConfig {
+            interleaving: Interleaving::None,
+            ..Default::default()
+        },
+        notched,
+    );
+    let interleaved = lost_frames(CodingConfig::default(), notched);
+    assert!(2 * interleaved < plain, "{} against {}", interleaved, plain);
+}
+
+#[test]
+fn profiles_have_their_delay_spread() {
+    // the delay spreads of ITU-R M.1225
+    let pedestrian = MultipathProfile::Pedestrian.get_rms_delay_spread(1e6);
+    assert!((pedestrian / 46e-9 - 1.0).abs() < 0.02, "{pedestrian}");
+    let vehicular = MultipathProfile::Vehicular.get_rms_delay_spread(1e6);
+    assert!((vehicular / 370e-9 - 1.0).abs() < 0.02, "{vehicular}");
+
+    for (profile, sample_rate, tolerance) in [
+        (MultipathProfile::Pedestrian, 20e6, 0.05),
+        (MultipathProfile::Pedestrian, 100e6, 0.01),
+        (MultipathProfile::Vehicular, 20e6, 0.01),
+        (
+            MultipathProfile::TwoRay {
+                delay: 1e-3,
+                relative_db: -3.0,
+            },
+            48000.0,
+            1e-6,
+        ),
+        (
+            MultipathProfile::ExponentialDecay { rms_delay: 1e-4 },
+            48000.0,
+            0.01,
+        ),
+        (
+            MultipathProfile::ExponentialDecay { rms_delay: 2e-3 },
+            48000.0,
+            0.01,
+        ),
+    ] {
+        let channel = MultipathChannel::from_profile(profile, sample_rate, 3);
+        let power: f32 = channel
+            .get_taps()
+            .iter()
+            .map(|(_, gain)| gain.norm_sqr())
+            .sum();
+        assert!((power - 1.0).abs() < 1e-5, "{profile:?}");
+        let realized = channel.get_rms_delay_spread() / sample_rate;
+        let expected = profile.get_rms_delay_spread(sample_rate);
+        assert!(
+            (realized / expected - 1.0).abs() < tolerance,
+            "{profile:?} {realized} {expected}"
+        );
+    }
+
+    // the exponential decay has the delay spread it asks for, whatever the taps look like
+    for rms_delay in [1e-5, 1e-4, 1e-3] {
+        let spread = MultipathProfile::ExponentialDecay { rms_delay }.get_rms_delay_spread(48000.0);
+        assert!(
+            (spread / rms_delay - 1.0).abs() < 0.01,
+            "{rms_delay} {spread}"
+        );
+    }
+
+    // the gains depend on the seed only through their phases
+    let (first, second) = (
+        MultipathChannel::from_profile(MultipathProfile::Vehicular, 20e6, 1),
+        MultipathChannel::from_profile(MultipathProfile::Vehicular, 20e6, 2),
+    );
+    assert_ne!(first.get_taps(), second.get_taps());
+    for (&(delay, gain), &(other_delay, other_gain)) in
+        first.get_taps().iter().zip(second.get_taps())
+    {
+        assert_eq!(delay, other_delay);
+        assert!((gain.norm() - other_gain.norm()).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn differential_mode_equalizes_the_pedestrian_profile() {
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    // at 20 MHz the last path is 8 samples late, the end of the cyclic prefix
+    let pedestrian = |seed| {
+        ChannelChain::new()
+            .with(MultipathChannel::from_profile(
+                MultipathProfile::Pedestrian,
+                20e6,
+                seed,
+            ))
+            .with(AwgnChannel::new(20.0, seed))
+    };
+    assert_eq!(
+        MultipathChannel::from_profile(MultipathProfile::Pedestrian, 20e6, 0)
+            .get_taps()
+            .last()
+            .unwrap()
+            .0,
+        8
+    );
+    assert_eq!(lost_frames(coding, pedestrian), 0);
+}
+
+#[test]
+fn doppler_fades_the_taps() {
+    let (sample_rate, max_doppler_hz) = (10000.0, 20.0);
+    // a single tap, whose fading is the output of a constant input
+    let fading = |seed| {
+        let mut channel = MultipathChannel::new(&[(0, Complex32::new(1.0, 0.0))]).with_doppler(
+            max_doppler_hz,
+            sample_rate,
+            seed,
+        );
+        let mut samples = vec![Complex32::new(1.0, 0.0); 1 << 20];
+        channel.apply_complex(&mut samples);
+        samples
+    };
+
+    let samples = fading(1);
+    let power = samples.iter().map(|x| x.norm_sqr()).sum::<f32>() / samples.len() as f32;
+    assert!((power - 1.0).abs() < 0.1, "{power}");
+    // J0 at the lags, 0.98 and 0 at its first zero
+    let correlation = |lag: usize| {
+        let sum: Complex32 = samples
+            .iter()
+            .zip(&samples[lag..])
+            .map(|(x, y)| x.conj() * y)
+            .sum();
+        sum.norm() / (samples.len() - lag) as f32 / power
+    };
+    let coherence = (0.383 * sample_rate / max_doppler_hz) as usize;
+    assert!(
+        correlation(coherence / 20) > 0.95,
+        "{}",
+        correlation(coherence / 20)
+    );
+    assert!(correlation(coherence) < 0.1, "{}", correlation(coherence));
+
+    // the seed decides the fading, the buffers do not
+    assert_eq!(fading(1), samples);
+    assert_ne!(fading(2), samples);
+    let mut channel = MultipathChannel::new(&[(0, Complex32::new(1.0, 0.0))]).with_doppler(
+        max_doppler_hz,
+        sample_rate,
+        1,
+    );
+    let mut blocks = vec![Complex32::new(1.0, 0.0); 1 << 20];
+    for block in blocks.chunks_mut(1000) {
+        channel.apply_complex(block);
+    }
+    assert_eq!(blocks, samples);
+    channel.reset();
+    let mut again = vec![Complex32::new(1.0, 0.0); 1000];
+    channel.apply_complex(&mut again);
+    assert_eq!(again, samples[..1000]);
+
+    // no shift keeps the taps, of real samples as of complex ones
+    let mut still = MultipathChannel::from_profile(MultipathProfile::Pedestrian, 20e6, 1);
+    let mut faded = still.clone().with_doppler(0.0, 20e6, 1);
+    let (mut real, mut real_faded) = (vec![1.0; 100], vec![1.0; 100]);
+    still.apply(&mut real);
+    faded.apply(&mut real_faded);
+    assert_eq!(real, real_faded);
+    let mut complex = vec![Complex32::new(1.0, 0.0); 100];
+    faded.apply_complex(&mut complex);
+    assert!(
+        complex[10..]
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).norm() < 1e-6)
+    );
+}
+
+#[test]
+fn real_samples_fade_as_a_band() {
+    // a tone through the fading of its analytic signal is the real part of the complex tone through the same fading
+    let (sample_rate, max_doppler_hz) = (48000.0, 5.0);
+    let channel = || {
+        MultipathChannel::new(&[(0, Complex32::new(1.0, 0.0))]).with_doppler(
+            max_doppler_hz,
+            sample_rate,
+            3,
+        )
+    };
+    let mut fading = vec![Complex32::new(1.0, 0.0); 48000];
+    channel().apply_complex(&mut fading);
+
+    let phase = |n: usize| core::f64::consts::TAU * 3000.0 * n as f64 / f64::from(sample_rate);
+    let mut samples: Vec<f32> = (0..48000).map(|n| phase(n).cos() as f32).collect();
+    let mut faded = channel();
+    assert_eq!(faded.get_delay(), 127);
+    for block in samples.chunks_mut(1000) {
+        faded.apply(block);
+    }
+    // once the filters of the analytic signal are full
+    for n in 254..48000 {
+        let tone = Complex32::from_polar(
+            1.0,
+            phase(n - 127).rem_euclid(core::f64::consts::TAU) as f32,
+        );
+        let expected = (fading[n] * tone).re;
+        assert!(
+            (samples[n] - expected).abs() < 1e-2,
+            "{n}: {} vs {expected}",
+            samples[n]
+        );
+    }
+
+    // the fading starts over with the filters
+    faded.reset();
+    let mut again: Vec<f32> = (0..1000).map(|n| phase(n).cos() as f32).collect();
+    faded.apply(&mut again);
+    assert_eq!(again, samples[..1000]);
+}
@@ -0,0 +1,162 @@
+//! Sends a control and a data channel in the same symbols with the [OFDMA](software_modem::ofdm::ofdma) modem,
+//! through echoes which notch some subcarriers, decodes them together and each on its own,
+//! and checks that allocations which overlap or leave the data subcarriers are refused.
+//!
+//! QAM-16 is the only order of the modem so far, so both channels use it.
+
+use std::collections::HashMap;
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel},
+    ofdm::{
+        OFDMConfig,
+        modulator::OFDMModulator,
+        ofdma::{LogicalChannel, OFDMADemodulator, OFDMAModulator, SubcarrierAllocation},
+    },
+    qam::QAMOrder,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        differential_time: true,
+        ..Default::default()
+    }
+}
+
+/// A control channel on every sixth data subcarrier, and a data channel on the others.
+fn allocation() -> SubcarrierAllocation {
+    let (control, data): (Vec<_>, Vec<_>) = config()
+        .get_data_subcarriers()
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| i % 6 == 0);
+    let channel = |id, subcarriers: Vec<(usize, u32)>| LogicalChannel {
+        id,
+        subcarriers: subcarriers.into_iter().map(|(_, idx)| idx).collect(),
+        qam_order: QAMOrder::QAM16,
+    };
+    SubcarrierAllocation {
+        channels: vec![channel("control", control), channel("data", data)],
+    }
+}
+
+#[test]
+fn capacity_per_channel() {
+    let modulator = OFDMAModulator::new(config(), allocation());
+    let demodulator = OFDMADemodulator::new(config(), allocation());
+    let plain = OFDMModulator::new((&config()).into());
+    assert_eq!(config().get_data_subcarriers().len(), 48);
+
+    // 8 and 40 subcarriers of 4 bits
+    for (id, bytes) in [("control", 4), ("data", 20)] {
+        assert_eq!(modulator.get_channel_bytes_per_symbol(id), Some(bytes));
+        assert_eq!(demodulator.get_channel_bytes_per_symbol(id), Some(bytes));
+    }
+    assert_eq!(modulator.get_channel_bytes_per_symbol("voice"), None);
+    assert_eq!(4 + 20, plain.get_bytes_per_symbol());
+    assert_eq!(modulator.get_symbol_length(), plain.get_symbol_length());
+
+    // one subcarrier of a channel is half a byte, which is left empty
+    let odd = SubcarrierAllocation {
+        channels: vec![LogicalChannel {
+            id: "odd",
+            subcarriers: vec![1, 2, 3],
+            qam_order: QAMOrder::QAM16,
+        }],
+    };
+    assert_eq!(
+        OFDMAModulator::new(config(), odd).get_channel_bytes_per_symbol("odd"),
+        Some(1)
+    );
+}
+
+#[test]
+fn two_channels_through_echoes() {
+    let modulator = OFDMAModulator::new(config(), allocation());
+    let control = data(30);
+    let payload = data(400);
+    let samples = modulator.modulate_channels(&HashMap::from([
+        ("control", &control[..]),
+        ("data", &payload[..]),
+    ]));
+    // the data channel needs 20 symbols after the reference symbol, the control channel only 8
+    assert_eq!(samples.len(), 21 * modulator.get_symbol_length());
+
+    // echoes within the cyclic prefix, which notch some subcarriers, and noise
+    let mut received = samples.clone();
+    ChannelChain::new()
+        .with(MultipathChannel::new(&[
+            (0, Complex32::new(1.0, 0.0)),
+            (3, Complex32::new(-0.5, 0.0)),
+            (7, Complex32::new(0.3, 0.0)),
+        ]))
+        .with(AwgnChannel::new(30.0, 1))
+        .apply(&mut received);
+
+    let payloads = OFDMADemodulator::new(config(), allocation()).demodulate_channels(&received);
+    assert_eq!(payloads.len(), 2);
+    assert_eq!(payloads["control"].len(), 20 * 4);
+    assert_eq!(payloads["control"][..30], control);
+    assert!(payloads["control"][30..].iter().all(|&byte| byte == 0));
+    assert_eq!(payloads["data"], payload);
+
+    // a receiver of the control channel alone
+    let control_only = SubcarrierAllocation {
+        channels: allocation().channels[..1].to_vec(),
+    };
+    let payloads = OFDMADemodulator::new(config(), control_only).demodulate_channels(&received);
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(payloads["control"][..30], control);
+}
+
+#[test]
+fn coherent_channels_without_payload() {
+    let config = OFDMConfig {
+        differential_time: false,
+        ..config()
+    };
+    let modulator = OFDMAModulator::new(config.clone(), allocation());
+    let control = data(10);
+    let samples = modulator.modulate_channels(&HashMap::from([("control", &control[..])]));
+    assert_eq!(samples.len(), 3 * modulator.get_symbol_length());
+
+    let payloads = OFDMADemodulator::new(config, allocation()).demodulate_channels(&samples);
+    assert_eq!(payloads["control"][..10], control);
+    assert_eq!(payloads["data"], vec![0; 3 * 20]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Logical channels must have disjoint subcarriers, but got 1 in control and data"
+)]
+fn overlapping_channels() {
+    let mut allocation = allocation();
+    allocation.channels[1].subcarriers.push(1);
+    OFDMAModulator::new(config(), allocation);
+}
+
+#[test]
+#[should_panic(
+    expected = "Subcarriers of logical channel control must be data subcarriers, but got 4"
+)]
+fn pilot_in_a_channel() {
+    let mut allocation = allocation();
+    allocation.channels[0].subcarriers.push(4);
+    OFDMADemodulator::new(config(), allocation);
+}
+
+#[test]
+#[should_panic(expected = "Payloads must be for allocated logical channels, but got voice")]
+fn payload_for_an_unknown_channel() {
+    OFDMAModulator::new(config(), allocation())
+        .modulate_channels(&HashMap::from([("voice", &[1u8][..])]));
+}
@@ -0,0 +1,216 @@
+//! Checks the [offset impairment](software_modem::channel::OffsetImpairment) against tones of known frequency,
+//! and the synchronization against it: the estimate of the carrier offset of the complex modem
+//! over a sweep of the carrier and clock offsets, and the frame search of the stream demodulator over timing offsets.
+
+use realfft::num_complex::{Complex32, Complex64};
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, OffsetImpairment},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{
+        OFDMConfig,
+        complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator},
+    },
+    stream::StreamDemodulator,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+fn tone(frequency: f64, length: usize) -> Vec<Complex32> {
+    (0..length)
+        .map(|n| {
+            let phase = core::f64::consts::TAU * frequency * n as f64 / f64::from(SAMPLE_RATE);
+            Complex32::from_polar(1.0, phase.rem_euclid(core::f64::consts::TAU) as f32)
+        })
+        .collect()
+}
+
+/// Returns the power of the samples at a frequency in Hz, relative to a full scale complex tone.
+fn power_at(samples: &[Complex32], frequency: f64) -> f64 {
+    let sum: Complex64 = samples
+        .iter()
+        .zip(tone(-frequency, samples.len()))
+        .map(|(x, y)| Complex64::new((x * y).re.into(), (x * y).im.into()))
+        .sum();
+    sum.norm_sqr() / (samples.len() as f64 * samples.len() as f64)
+}
+
+/// Passes the samples through the channel in blocks of a few hundred samples.
+fn apply_in_blocks(channel: &mut OffsetImpairment, samples: &mut [Complex32]) {
+    for block in samples.chunks_mut(333) {
+        channel.apply_complex(block);
+    }
+}
+
+#[test]
+fn carrier_offset_moves_tones_without_an_image() {
+    // complex samples rotate, carrying the image along
+    let mut channel = OffsetImpairment::new(SAMPLE_RATE, -310.0, 0.0, 0.0);
+    let mut samples = tone(5000.0, 48000);
+    apply_in_blocks(&mut channel, &mut samples);
+    assert!((power_at(&samples[256..], 4690.0) - 1.0).abs() < 1e-3);
+
+    // real samples shift as a band, by their analytic signal
+    let mut channel = OffsetImpairment::new(SAMPLE_RATE, 250.0, 0.0, 0.0);
+    let mut samples: Vec<f32> = tone(6000.0, 48000).iter().map(|x| x.re).collect();
+    for block in samples.chunks_mut(333) {
+        channel.apply(block);
+    }
+    // whole periods of all three frequencies, which keeps them from leaking into each other
+    let analytic: Vec<Complex32> = samples[256..24256]
+        .iter()
+        .map(|&x| Complex32::new(x, 0.0))
+        .collect();
+    // a cosine has a quarter of the power of a complex tone at its frequency
+    let shifted = power_at(&analytic, 6250.0);
+    assert!((shifted / 0.25 - 1.0).abs() < 1e-2, "{shifted}");
+    assert!(power_at(&analytic, 5750.0) / shifted < 1e-6);
+    assert!(power_at(&analytic, 6000.0) / shifted < 1e-6);
+}
+
+#[test]
+fn clock_offset_scales_the_frequencies() {
+    for sco_ppm in [-100.0, -20.0, 0.0, 35.0, 100.0] {
+        let mut channel = OffsetImpairment::new(SAMPLE_RATE, 0.0, sco_ppm, 0.0);
+        let mut samples = tone(4800.0, 200_000);
+        apply_in_blocks(&mut channel, &mut samples);
+
+        // the phase advance per sample, over the second half of the samples
+        let rotation: Complex64 = samples[100_000..]
+            .windows(2)
+            .map(|pair| {
+                let rotation = pair[1] * pair[0].conj();
+                Complex64::new(rotation.re.into(), rotation.im.into())
+            })
+            .sum();
+        let frequency = rotation.arg() / core::f64::consts::TAU * f64::from(SAMPLE_RATE);
+        let measured_ppm = (4800.0 / frequency - 1.0) * 1e6;
+        assert!(
+            (measured_ppm - f64::from(sco_ppm)).abs() < 1.0,
+            "{sco_ppm} ppm: measured {measured_ppm}"
+        );
+    }
+}
+
+#[test]
+fn timing_offset_delays_by_fractions() {
+    // a band-limited signal, a sum of tones below a quarter of the sample rate
+    let signal = |t: f64| {
+        [(1000.0, 0.3), (4100.0, 1.1), (9700.0, 2.0)]
+            .iter()
+            .map(|&(frequency, phase)| {
+                (core::f64::consts::TAU * frequency * t / f64::from(SAMPLE_RATE) + phase).cos()
+            })
+            .sum::<f64>() as f32
+    };
+    for timing_offset in [0.0, 0.25, 0.5, 3.75, 100.125] {
+        let mut channel = OffsetImpairment::new(SAMPLE_RATE, 0.0, 0.0, timing_offset);
+        let mut samples: Vec<f32> = (0..5000).map(|n| signal(n as f64)).collect();
+        channel.apply(&mut samples);
+
+        let delay = channel.get_delay();
+        let (mut power, mut error) = (0.0, 0.0);
+        for (n, &sample) in samples.iter().enumerate().skip(delay as usize + 10) {
+            let expected = signal(n as f64 - delay);
+            power += expected * expected;
+            error += (sample - expected) * (sample - expected);
+        }
+        // the highest tone is the hardest to interpolate, 54 dB down through 8 samples
+        let snr = 10.0 * (power / error).log10();
+        assert!(snr > 50.0, "{timing_offset}: {snr} dB");
+    }
+}
+
+#[test]
+fn carrier_offset_estimate_over_the_sweep() {
+    let config = ComplexOFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        ..Default::default()
+    };
+    let modulator = ComplexOFDMModulator::new(config.clone());
+    let demodulator = ComplexOFDMDemodulator::new(config);
+    let data: Vec<u8> = (0..40 * modulator.get_bytes_per_symbol() as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let transmitted = modulator.modulate_symbols(&data);
+    let spacing = SAMPLE_RATE / 64.0;
+
+    for sco_ppm in [-100.0, 0.0, 100.0] {
+        for step in -4..=4 {
+            let offset = step as f32 * 0.1;
+            // the delay of the impairment is a whole number of samples, so the symbols stay aligned
+            let mut channel = ChannelChain::new()
+                .with(OffsetImpairment::new(
+                    SAMPLE_RATE,
+                    offset * spacing,
+                    sco_ppm,
+                    0.0,
+                ))
+                .with(AwgnChannel::new(15.0, step as u64));
+            let mut received = transmitted.clone();
+            received.extend(vec![Complex32::new(0.0, 0.0); 256]);
+            channel.apply_complex(&mut received);
+
+            let estimate = demodulator.estimate_frequency_offset(&received[256..]);
+            assert!(
+                (estimate - offset).abs() < 0.01,
+                "{offset} spacings at {sco_ppm} ppm: estimated {estimate}"
+            );
+        }
+    }
+}
+
+#[test]
+fn stream_finds_frames_at_any_timing() {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        differential_time: true,
+        // the shift of the real samples does not reach the edges of the band
+        guard_subcarriers_low: 2,
+        guard_subcarriers_high: 2,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let payload: Vec<u8> = (0..200u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let frame = modulator.encode_frame(&payload);
+
+    for sco_ppm in [-100.0, 0.0, 100.0] {
+        for timing_offset in [0.0, 0.5, 17.25, 333.7] {
+            // the carrier a hundredth of a subcarrier spacing off
+            let mut channel = ChannelChain::new()
+                .with(OffsetImpairment::new(
+                    SAMPLE_RATE,
+                    3.75,
+                    sco_ppm,
+                    timing_offset,
+                ))
+                .with(AwgnChannel::new(30.0, 1));
+            let mut stream = frame.clone();
+            stream.extend(vec![0.0; 4000]);
+
+            // the shift of the real samples rings ahead of the frame, and a squelch at a tenth of the level of the frame
+            // keeps the burst from opening there
+            let mut demodulator = StreamDemodulator::new(
+                CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default()),
+                3.0,
+                2400,
+            );
+            let decoded: Vec<Vec<u8>> = stream
+                .chunks_mut(128)
+                .flat_map(|block| {
+                    channel.apply(block);
+                    demodulator.push(block)
+                })
+                .collect();
+            assert_eq!(
+                decoded,
+                core::slice::from_ref(&payload),
+                "{timing_offset} at {sco_ppm} ppm"
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Checks that the metrics of a stream demodulator add up against a known workload.
+//!
+//! The test needs the `perf` feature: `cargo test --features perf`.
+
+#![cfg(feature = "perf")]
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    perf::StageTime,
+    stream::StreamDemodulator,
+};
+
+const BLOCK_LENGTH: usize = 256;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        roll_off: 4,
+        ..Default::default()
+    }
+}
+
+/// A stream of frames separated by silence, with a burst of noise after the second frame.
+///
+/// Returns the stream, the payloads and the frames.
+fn workload() -> (Vec<f32>, Vec<Vec<u8>>, Vec<Vec<f32>>) {
+    let modulator = CodedOFDMModulator::new(ofdm(), CodingConfig::default());
+    let payloads: Vec<Vec<u8>> = (0..4).map(|i| data(20 + 150 * i)).collect();
+    let frames: Vec<Vec<f32>> = payloads
+        .iter()
+        .map(|payload| modulator.encode_frame(payload))
+        .collect();
+
+    let mut samples = vec![0.0; 500];
+    for (index, frame) in frames.iter().enumerate() {
+        samples.extend(frame);
+        samples.extend([0.0; 500]);
+        if index == 1 {
+            let mut noise: u32 = 0x1234_5678;
+            samples.extend((0..3000).map(|_| {
+                noise ^= noise << 13;
+                noise ^= noise >> 17;
+                noise ^= noise << 5;
+                noise as f32 / u32::MAX as f32 - 0.5
+            }));
+            samples.extend([0.0; 500]);
+        }
+    }
+    (samples, payloads, frames)
+}
+
+fn stream() -> StreamDemodulator {
+    StreamDemodulator::new(
+        CodedOFDMDemodulator::new(ofdm(), CodingConfig::default()),
+        0.01,
+        200,
+    )
+}
+
+fn symbol_length() -> usize {
+    CodedOFDMDemodulator::new(ofdm(), CodingConfig::default()).get_symbol_length()
+}
+
+#[test]
+fn counters_add_up() {
+    let (samples, payloads, frames) = workload();
+    let mut stream = stream();
+    stream.enable_metrics(1);
+    let decoded: Vec<Vec<u8>> = samples
+        .chunks(BLOCK_LENGTH)
+        .flat_map(|block| stream.push(block))
+        .collect();
+    assert_eq!(decoded, payloads);
+
+    let snapshot = stream.get_metrics().unwrap().snapshot();
+    let symbol_length = symbol_length();
+    // the roll-off is shorter than a symbol
+    let symbols: usize = frames.iter().map(|frame| frame.len() / symbol_length).sum();
+    assert_eq!(snapshot.samples, samples.len() as u64);
+    assert_eq!(snapshot.symbols, symbols as u64);
+    assert_eq!((snapshot.frames_decoded, snapshot.frames_failed), (4, 1));
+    assert_eq!(
+        snapshot.payload_bytes,
+        payloads.iter().map(Vec::len).sum::<usize>() as u64
+    );
+
+    assert!(snapshot.busy > std::time::Duration::ZERO && snapshot.busy <= snapshot.elapsed);
+    let busy = snapshot.busy.as_secs_f64();
+    assert_eq!(snapshot.symbols_per_sec, snapshot.symbols as f64 / busy);
+    assert_eq!(
+        snapshot.payload_bytes_per_sec,
+        snapshot.payload_bytes as f64 / busy
+    );
+
+    let stages = snapshot.stages;
+    assert_eq!(
+        stages.sync.calls,
+        samples.len().div_ceil(BLOCK_LENGTH) as u64
+    );
+    // every symbol of every offset tried is transformed and equalized, every offset is demapped and decoded
+    assert!(stages.fft.calls >= snapshot.symbols);
+    assert_eq!(stages.equalize.calls, stages.fft.calls);
+    assert!(stages.demap.calls >= 5);
+    assert_eq!(stages.fec.calls, stages.demap.calls);
+    for stage in [
+        stages.sync,
+        stages.fft,
+        stages.equalize,
+        stages.demap,
+        stages.fec,
+    ] {
+        assert_eq!(stage.sampled, stage.calls);
+    }
+
+    let longest = frames.iter().map(Vec::len).max().unwrap();
+    assert!(snapshot.squelch_high_water >= longest);
+    assert!(snapshot.frame_high_water >= longest / symbol_length * symbol_length);
+}
+
+#[test]
+fn stages_are_sampled_every_n_calls() {
+    let (samples, payloads, _) = workload();
+    let mut stream = stream();
+    stream.enable_metrics(4);
+    let decoded: Vec<Vec<u8>> = samples
+        .chunks(BLOCK_LENGTH)
+        .flat_map(|block| stream.push(block))
+        .collect();
+    // the metrics change nothing about the decoding
+    assert_eq!(decoded, payloads);
+
+    let stages = stream.get_metrics().unwrap().snapshot().stages;
+    for stage in [
+        stages.sync,
+        stages.fft,
+        stages.equalize,
+        stages.demap,
+        stages.fec,
+    ] {
+        assert!(stage.calls > 0);
+        assert_eq!(stage.sampled, stage.calls.div_ceil(4));
+        assert!(stage.per_call() <= stage.time);
+    }
+    assert_eq!(StageTime::default().per_call(), std::time::Duration::ZERO);
+}
+
+#[test]
+fn metrics_start_over() {
+    let (samples, _, _) = workload();
+    let mut stream = stream();
+    assert!(stream.get_metrics().is_none());
+    for block in samples.chunks(BLOCK_LENGTH) {
+        stream.push(block);
+    }
+    assert_eq!(stream.get_frames_decoded(), 4);
+
+    stream.enable_metrics(1);
+    let snapshot = stream.get_metrics().unwrap().snapshot();
+    assert_eq!((snapshot.samples, snapshot.frames_decoded), (0, 0));
+    assert_eq!(snapshot.symbols_per_sec, 0.0);
+    assert_eq!(snapshot.stages.fft.calls, 0);
+
+    // a burst cut off by the end of the stream is timed by the flush
+    let modulator = CodedOFDMModulator::new(ofdm(), CodingConfig::default());
+    stream.push(&modulator.encode_frame(&data(100)));
+    assert_eq!(stream.flush(), Some(data(100)));
+    let snapshot = stream.get_metrics().unwrap().snapshot();
+    assert_eq!((snapshot.frames_decoded, snapshot.payload_bytes), (1, 100));
+    assert_eq!(snapshot.stages.sync.calls, 1);
+}
+
+#[test]
+fn mer_follows_the_noise() {
+    let (samples, _, frames) = workload();
+    let mut stream = stream();
+    stream.enable_metrics(1);
+    assert_eq!(stream.get_metrics().unwrap().snapshot().mer_db, None);
+    for block in samples.chunks(BLOCK_LENGTH) {
+        stream.push(block);
+    }
+    // the noise burst does not decode and does not count
+    let clean = stream.get_metrics().unwrap().snapshot().mer_db.unwrap();
+    assert!(clean > 50.0, "{clean} dB");
+
+    // the subcarriers fill the band, so the points get about the SNR of the samples
+    let mut stream = self::stream();
+    stream.enable_metrics(1);
+    for (seed, frame) in frames.iter().enumerate() {
+        let mut frame = frame.clone();
+        AwgnChannel::new(20.0, seed as u64).apply(&mut frame);
+        let mut samples = vec![0.0; 500];
+        samples.extend(frame);
+        samples.extend([0.0; 500]);
+        for block in samples.chunks(BLOCK_LENGTH) {
+            stream.push(block);
+        }
+    }
+    let snapshot = stream.get_metrics().unwrap().snapshot();
+    assert_eq!(snapshot.frames_decoded, 4);
+    let noisy = snapshot.mer_db.unwrap();
+    assert!((noisy - 20.0).abs() < 1.0, "{noisy} dB");
+}
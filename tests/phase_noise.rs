@@ -0,0 +1,219 @@
+//! Checks the [phase noise](software_modem::channel::PhaseNoise) against its Wiener process: the spread of the phase
+//! over a lag, the seeds, the real path against the complex one, and the frames through it.
+//!
+//! The frames compare the coherent equalizer with the [differential](OFDMConfig::differential_time) one,
+//! which references every symbol to the one before and so follows the phase as it wanders,
+//! and QAM-64 with and without [tracking the common phase](OFDMConfig::track_common_phase) of the pilots.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, PhaseNoise},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::QAMOrder,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// 128 samples of the FFT and 16 of the prefix, clear of the edges the analytic signal does not reach.
+fn config(differential_time: bool) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        guard_subcarriers_low: 6,
+        guard_subcarriers_high: 6,
+        differential_time,
+        ..Default::default()
+    }
+}
+
+/// Returns the phase of every sample of the channel, from the same seed.
+fn phases(linewidth_hz: f32, seed: u64, length: usize) -> Vec<f32> {
+    let mut samples = vec![Complex32::new(1.0, 0.0); length];
+    PhaseNoise::new(SAMPLE_RATE, linewidth_hz, seed).apply_complex(&mut samples);
+    samples.iter().map(|sample| sample.arg()).collect()
+}
+
+/// Returns the samples through the channel, the delay of the real path taken off.
+fn through(channel: &mut impl Channel, samples: &[f32], delay: usize) -> Vec<f32> {
+    let mut received = samples.to_vec();
+    received.extend(vec![0.0; delay]);
+    for block in received.chunks_mut(333) {
+        channel.apply(block);
+    }
+    received[delay..].to_vec()
+}
+
+#[test]
+fn phase_spreads_with_the_lag() {
+    for linewidth_hz in [1.0, 10.0, 100.0] {
+        let mut channel = PhaseNoise::new(SAMPLE_RATE, linewidth_hz, 7);
+        let mut samples = vec![Complex32::new(0.0, 0.5); 400_000];
+        for block in samples.chunks_mut(333) {
+            channel.apply_complex(block);
+        }
+        assert!(
+            samples
+                .iter()
+                .all(|sample| (sample.norm() - 0.5).abs() < 1e-5)
+        );
+
+        // the variance of the steps over a lag grows with the lag, 2 pi linewidth per second
+        for lag in [1, 48, 480] {
+            let expected = core::f64::consts::TAU * f64::from(linewidth_hz) * lag as f64
+                / f64::from(SAMPLE_RATE);
+            if expected > 1.0 {
+                // the steps wrap around
+                continue;
+            }
+            let steps = samples
+                .windows(lag + 1)
+                .step_by(lag)
+                .map(|pair| f64::from((pair[lag] * pair[0].conj()).arg()));
+            let (count, sum) = steps.fold((0, 0.0), |(count, sum), step| {
+                (count + 1, sum + step * step)
+            });
+            let variance = sum / count as f64;
+            assert!(
+                (variance / expected - 1.0).abs() < 0.15,
+                "{linewidth_hz} Hz over {lag}: {variance} vs {expected}"
+            );
+        }
+        assert_ne!(channel.get_phase(), 0.0);
+        assert_eq!(channel.get_linewidth_hz(), linewidth_hz);
+    }
+}
+
+#[test]
+fn seeds_and_zero_linewidth() {
+    assert_eq!(phases(10.0, 3, 5000), phases(10.0, 3, 5000));
+    assert_ne!(phases(10.0, 3, 5000), phases(10.0, 4, 5000));
+    assert!(phases(0.0, 3, 5000).iter().all(|&phase| phase == 0.0));
+
+    // without a linewidth the real path only delays
+    let samples: Vec<f32> = data(2000)
+        .iter()
+        .map(|&x| f32::from(x) / 255.0 - 0.5)
+        .collect();
+    let mut channel = PhaseNoise::new(SAMPLE_RATE, 0.0, 3);
+    assert_eq!(channel.get_delay(), 127);
+    assert_eq!(through(&mut channel, &samples, 127), samples);
+}
+
+#[test]
+fn real_path_turns_the_band() {
+    // a tone turned through its analytic signal is the real part of the complex tone turned by the same phases
+    let phase =
+        |n: usize| (core::f64::consts::TAU * 5000.0 * n as f64 / f64::from(SAMPLE_RATE)) as f32;
+    let samples: Vec<f32> = (0..20000).map(|n| phase(n).cos()).collect();
+    let mut channel = PhaseNoise::new(SAMPLE_RATE, 50.0, 11);
+    let received = through(&mut channel, &samples, 127);
+    let phases = phases(50.0, 11, 20127);
+    // the end of the samples is turned with the zeros after them
+    for (n, &sample) in received.iter().enumerate().take(20000 - 127).skip(127) {
+        let expected = (phase(n) + phases[n + 127]).cos();
+        assert!(
+            (sample - expected).abs() < 1e-2,
+            "{n}: {sample} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn symbols_report_the_phase_of_their_window() {
+    let modulator = OFDMModulator::new((&config(false)).into());
+    let demodulator = OFDMDemodulator::new((&config(false)).into());
+    let (length, bytes) = (
+        modulator.get_symbol_length(),
+        modulator.get_bytes_per_symbol(),
+    );
+    let payload = data(120 * bytes as u32);
+    let mut samples = vec![0.0; 120 * length];
+    for (symbol, data) in samples.chunks_exact_mut(length).zip(payload.chunks(bytes)) {
+        modulator.modulate_buffer_as_symbol(data, symbol);
+    }
+    let mut channel = PhaseNoise::new(SAMPLE_RATE, 0.5, 5);
+    let received = through(&mut channel, &samples, 127);
+    let phases = phases(0.5, 5, samples.len() + 127);
+
+    // the common phase error of a symbol is the mean phase over its FFT window, up to the noise
+    // of the slope of the pilots, which the wander within the window spreads into each other
+    let mut errors = Vec::new();
+    let mut wander = 0.0;
+    for (index, symbol) in received.chunks_exact(length).enumerate() {
+        let window = &phases[127 + index * length + 16..127 + (index + 1) * length];
+        let mean = window
+            .iter()
+            .map(|&phase| Complex32::from_polar(1.0, phase))
+            .sum::<Complex32>();
+        let (_, report) = demodulator.demodulate_symbol_with_report(symbol);
+        let error = Complex32::from_polar(1.0, report.common_phase_error.unwrap());
+        errors.push((error * mean.conj()).arg().abs());
+        wander += mean.arg().abs() / 120.0;
+    }
+    errors.sort_by(f32::total_cmp);
+    let mean_error = errors.iter().sum::<f32>() / 120.0;
+    assert!(errors[60] < 0.1, "{errors:?}");
+    assert!(mean_error < wander / 3.0, "{mean_error} vs {wander}");
+}
+
+#[test]
+fn differential_frames_follow_the_phase() {
+    let payload = data(100);
+    let mut decoded = [0, 0];
+    for (differential, decoded) in [false, true].into_iter().zip(&mut decoded) {
+        let modulator = CodedOFDMModulator::new(config(differential), CodingConfig::default());
+        let demodulator = CodedOFDMDemodulator::new(config(differential), CodingConfig::default());
+        for seed in 0..40 {
+            let mut chain = ChannelChain::new()
+                .with(PhaseNoise::new(SAMPLE_RATE, 3.0, seed))
+                .with(AwgnChannel::new(30.0, seed));
+            let received = through(&mut chain, &modulator.encode_frame(&payload), 127);
+            *decoded += usize::from(demodulator.decode_frame(&received).as_ref() == Ok(&payload));
+        }
+    }
+    // the coherent points turn away from their decisions within a frame,
+    // the differential ones only by the step from a symbol to the next
+    assert!(decoded[0] <= 14, "{decoded:?}");
+    assert!(decoded[1] >= 28, "{decoded:?}");
+    assert!(decoded[1] >= decoded[0] + 15, "{decoded:?}");
+}
+
+#[test]
+fn tracking_the_common_phase_decodes_qam64() {
+    let payload = data(100);
+    let mut decoded = [0, 0];
+    for (track_common_phase, decoded) in [false, true].into_iter().zip(&mut decoded) {
+        let config = OFDMConfig {
+            qam_order: QAMOrder::QAM64,
+            track_common_phase,
+            ..config(false)
+        };
+        let modulator = CodedOFDMModulator::new(config.clone(), CodingConfig::default());
+        let demodulator = CodedOFDMDemodulator::new(config, CodingConfig::default());
+        for seed in 0..40 {
+            let mut chain = ChannelChain::new()
+                .with(PhaseNoise::new(SAMPLE_RATE, 3.0, seed))
+                .with(AwgnChannel::new(30.0, seed));
+            let received = through(&mut chain, &modulator.encode_frame(&payload), 127);
+            *decoded += usize::from(demodulator.decode_frame(&received).as_ref() == Ok(&payload));
+        }
+    }
+    // measured 6 and 38 of 40, the points of QAM-64 are closer in angle than the ones of QAM-16,
+    // which the common phase turns across their boundaries unless it is taken out
+    assert!(decoded[0] <= 10, "{decoded:?}");
+    assert!(decoded[1] >= 36, "{decoded:?}");
+}
+
+#[test]
+#[should_panic(expected = "Linewidth must be positive or zero and finite, but got -1")]
+fn negative_linewidth() {
+    PhaseNoise::new(SAMPLE_RATE, -1.0, 0);
+}
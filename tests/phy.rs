@@ -0,0 +1,179 @@
+//! Runs the same checks of the frame layer over the OFDM modem and over a trivial [physical layer](software_modem::phy)
+//! which sends every byte as a sample of its value.
+
+use software_modem::{
+    error::ModemError,
+    frame::{FrameDecoder, FrameEncoder},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    phy::{PhyDemodulator, PhyModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Sends every byte as a sample of its value, in blocks of a number of bytes.
+struct Identity(usize);
+
+fn check_lengths(expected: usize, got: usize) -> Result<(), ModemError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ModemError::BufferLength { expected, got })
+    }
+}
+
+impl PhyModulator for Identity {
+    fn samples_per_block(&self) -> usize {
+        self.0
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.0
+    }
+
+    fn modulate(&self, data: &[u8], output: &mut [f32]) -> Result<(), ModemError> {
+        check_lengths(self.0, data.len())?;
+        check_lengths(self.0, output.len())?;
+        for (sample, &byte) in output.iter_mut().zip(data) {
+            *sample = byte as f32;
+        }
+        Ok(())
+    }
+}
+
+impl PhyDemodulator for Identity {
+    fn samples_per_block(&self) -> usize {
+        self.0
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.0
+    }
+
+    fn demodulate(&self, samples: &[f32], output: &mut [u8]) -> Result<(), ModemError> {
+        check_lengths(self.0, samples.len())?;
+        check_lengths(self.0, output.len())?;
+        for (byte, &sample) in output.iter_mut().zip(samples) {
+            *byte = sample.round().clamp(0.0, 255.0) as u8;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that frames of every length have the length the encoder and the decoder announce,
+/// a whole number of blocks, and decode into the payload and the zero padding of the last block.
+fn check_frames<M: PhyModulator, D: PhyDemodulator>(
+    encoder: &FrameEncoder<M>,
+    decoder: &FrameDecoder<D>,
+) {
+    let bytes_per_symbol = encoder.get_bytes_per_symbol();
+    assert_eq!(decoder.get_bytes_per_symbol(), bytes_per_symbol);
+    for length in [
+        0,
+        1,
+        bytes_per_symbol - 1,
+        bytes_per_symbol,
+        3 * bytes_per_symbol + 5,
+    ] {
+        let payload = data(length as u32);
+        let samples = encoder.encode(&payload);
+        assert_eq!(samples.len(), encoder.get_frame_length(length));
+        assert_eq!(samples.len(), decoder.get_frame_length(length));
+
+        let decoded = decoder.decode(&samples);
+        assert_eq!(
+            decoded.len(),
+            length.div_ceil(bytes_per_symbol) * bytes_per_symbol
+        );
+        assert_eq!(decoded[..length], payload);
+        assert!(decoded[length..].iter().all(|&byte| byte == 0));
+    }
+}
+
+#[test]
+fn ofdm_frames_through_the_traits() {
+    for config in [
+        OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            ..Default::default()
+        },
+        OFDMConfig {
+            num_subcarriers: 128,
+            cyclic_prefix_length: 16,
+            differential_time: true,
+            roll_off: 4,
+            ..Default::default()
+        },
+    ] {
+        let modulator = OFDMModulator::new((&config).into());
+        let demodulator = OFDMDemodulator::new((&config).into());
+        // a block is a symbol
+        assert_eq!(
+            PhyModulator::samples_per_block(&modulator),
+            modulator.get_symbol_length()
+        );
+        assert_eq!(
+            PhyDemodulator::capacity_bytes(&demodulator),
+            demodulator.get_bytes_per_symbol()
+        );
+        check_frames(
+            &FrameEncoder::new(modulator),
+            &FrameDecoder::new(demodulator),
+        );
+    }
+}
+
+#[test]
+fn identity_frames() {
+    for block in [1, 7, 64] {
+        let encoder = FrameEncoder::new(Identity(block));
+        let decoder = FrameDecoder::new(Identity(block));
+        assert_eq!(encoder.get_num_symbols(2 * block + 1), 3);
+        check_frames(&encoder, &decoder);
+
+        // the samples are the bytes
+        let samples = encoder.encode(&[3, 1, 4]);
+        assert_eq!(samples[..3], [3.0, 1.0, 4.0]);
+    }
+}
+
+#[test]
+fn blocks_have_their_length() {
+    let modulator = OFDMModulator::new(
+        (&OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            ..Default::default()
+        })
+            .into(),
+    );
+    let mut symbol = vec![0.0; 136];
+    assert_eq!(
+        PhyModulator::modulate(&modulator, &[0; 24], &mut symbol),
+        Ok(())
+    );
+    assert_eq!(
+        PhyModulator::modulate(&modulator, &[0; 23], &mut symbol),
+        Err(ModemError::BufferLength {
+            expected: 24,
+            got: 23
+        })
+    );
+    assert_eq!(
+        Identity(4).demodulate(&[0.0; 5], &mut [0; 4]),
+        Err(ModemError::BufferLength {
+            expected: 4,
+            got: 5
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "Frame length must be a multiple of 8 samples, but got 12 samples")]
+fn frames_are_whole_blocks() {
+    FrameDecoder::new(Identity(8)).decode(&[0.0; 12]);
+}
@@ -0,0 +1,171 @@
+//! Pins the arithmetic of the [planning helpers](software_modem::ofdm::plan) for a handful of links,
+//! and checks the summaries of [describe](software_modem::ofdm::plan::describe) against the modulator they plan.
+
+use core::time::Duration;
+
+use software_modem::{
+    dsp::Passband,
+    ofdm::{
+        GuardInterval, OFDMConfig,
+        modulator::OFDMModulator,
+        plan::{describe, for_bandwidth, nearest_fft_friendly},
+    },
+    qam::QAMOrder,
+};
+
+#[test]
+fn sizes_round_to_fft_friendly_ones() {
+    for (n, expected) in [
+        (0, 1),
+        (1, 1),
+        (11, 10),
+        (13, 12),
+        (14, 15),
+        (110, 108),
+        (500, 500),
+        (2047, 2048),
+    ] {
+        assert_eq!(nearest_fft_friendly(n), expected, "{n}");
+    }
+}
+
+#[test]
+fn bandwidths_give_their_subcarriers() {
+    // 5 ms at 44.1 kHz asks for 110.25 subcarriers, 110 is 2 * 5 * 11 and 108 is 2^2 * 3^3
+    let params = for_bandwidth(44100.0, 15000.0, Some(Duration::from_millis(5)));
+    assert_eq!(params.num_subcarriers, 108);
+    assert_eq!(params.subcarrier_spacing_hz, 44100.0 / 216.0);
+    assert_eq!(
+        params.symbol_duration,
+        Duration::from_secs_f64(216.0 / 44100.0)
+    );
+    // 73 of the 107 subcarriers between DC and the Nyquist bin
+    assert_eq!(
+        (params.guard_subcarriers_low, params.guard_subcarriers_high),
+        (17, 17)
+    );
+    assert!((params.occupied_bandwidth_hz - 73.0 * 44100.0 / 216.0).abs() < 1e-2);
+    assert_eq!(params.cyclic_prefix_length, 54);
+
+    // a band wider than the subcarriers between DC and the Nyquist bin takes all of them
+    let params = for_bandwidth(48000.0, 23900.0, Some(Duration::from_millis(1)));
+    assert_eq!(
+        (params.num_subcarriers, params.subcarrier_spacing_hz),
+        (24, 1000.0)
+    );
+    assert_eq!(
+        (params.guard_subcarriers_low, params.guard_subcarriers_high),
+        (0, 0)
+    );
+    assert_eq!(params.occupied_bandwidth_hz, 23000.0);
+
+    // the capacity is the one of the configuration, at every QAM order
+    assert_eq!(params.capacity_bps.len(), QAMOrder::ALL.len());
+    for (qam_order, capacity) in &params.capacity_bps {
+        let budget = describe(&params.config(*qam_order), 48000.0);
+        assert_eq!(budget.bit_rate_bps, *capacity);
+    }
+}
+
+#[test]
+fn summaries_match_the_modulator() {
+    let configs = [
+        OFDMConfig {
+            num_subcarriers: 64,
+            cyclic_prefix_length: 8,
+            ..Default::default()
+        },
+        OFDMConfig {
+            num_subcarriers: 256,
+            guard_interval: Some(GuardInterval::Eighth),
+            oversampling: 2,
+            guard_subcarriers_low: 10,
+            guard_subcarriers_high: 100,
+            pilot_subcarrier_every: 8,
+            masked_subcarriers: vec![50, 51, 52],
+            ..Default::default()
+        },
+    ];
+    for config in &configs {
+        let sample_rate = 48000.0;
+        let budget = describe(config, sample_rate);
+        let modulator = OFDMModulator::new(config.into());
+        let fft_length = 2 * config.num_subcarriers * config.oversampling;
+
+        assert_eq!(
+            budget.subcarrier_spacing_hz,
+            sample_rate / fft_length as f32
+        );
+        assert_eq!(
+            budget.symbol_duration,
+            Duration::from_secs_f64(modulator.get_symbol_length() as f64 / 48000.0)
+        );
+        assert_eq!(
+            budget.data_subcarriers * 4,
+            modulator.get_bytes_per_symbol() as u32 * 8
+        );
+        let bits_per_second =
+            (modulator.get_bytes_per_symbol() * 8) as f64 / budget.symbol_duration.as_secs_f64();
+        assert!((f64::from(budget.bit_rate_bps) / bits_per_second - 1.0).abs() < 1e-6);
+        assert!(
+            (budget.spectral_efficiency * budget.occupied_bandwidth_hz / budget.bit_rate_bps - 1.0)
+                .abs()
+                < 1e-6
+        );
+    }
+
+    // 63 subcarriers, 15 of them pilots at the multiples of 4, in 136 samples
+    let budget = describe(&configs[0], 48000.0);
+    assert_eq!(budget.subcarrier_spacing_hz, 375.0);
+    assert_eq!(budget.band_hz, (187.5, 23812.5));
+    assert_eq!(
+        (budget.data_subcarriers, budget.pilot_subcarriers),
+        (48, 15)
+    );
+    assert_eq!(
+        budget.cyclic_prefix_duration,
+        Duration::from_secs_f64(8.0 / 48000.0)
+    );
+    assert!((budget.bit_rate_bps - 192.0 * 48000.0 / 136.0).abs() < 1e-2);
+}
+
+#[test]
+fn a_passband_moves_the_band() {
+    let config = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        oversampling: 2,
+        guard_subcarriers_high: 31,
+        passband: Some(Passband {
+            sample_rate: 48000.0,
+            carrier_hz: 15000.0,
+        }),
+        ..Default::default()
+    };
+    let baseband = describe(
+        &OFDMConfig {
+            passband: None,
+            ..config.clone()
+        },
+        48000.0,
+    );
+    let budget = describe(&config, 48000.0);
+    assert_eq!(baseband.band_hz, (93.75, 6093.75));
+    assert_eq!(budget.band_hz, (12000.0, 18000.0));
+    assert_eq!(budget.occupied_bandwidth_hz, baseband.occupied_bandwidth_hz);
+    assert_eq!(budget.bit_rate_bps, baseband.bit_rate_bps);
+}
+
+#[test]
+#[should_panic(
+    expected = "Target bandwidth must be positive and below half the sample rate of 8000 Hz, but got 4000 Hz"
+)]
+fn bandwidths_fit_the_sample_rate() {
+    for_bandwidth(8000.0, 4000.0, None);
+}
+
+#[test]
+#[should_panic(expected = "Target bandwidth must hold a subcarrier of 500 Hz, but got 100 Hz")]
+fn bandwidths_hold_a_subcarrier() {
+    for_bandwidth(8000.0, 100.0, Some(Duration::from_millis(2)));
+}
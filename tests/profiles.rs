@@ -0,0 +1,334 @@
+//! Checks the [802.11a profile](software_modem::ofdm::profiles::ieee80211a_like) against the subcarrier mapping
+//! of the standard, IEEE 802.11-2020 17.3.5.10, the [DVB-T profile](software_modem::ofdm::profiles::dvbt_2k_like)
+//! against the carriers of ETSI EN 300 744, the explicit pilot frequencies of the complex configuration,
+//! frames of the [voice profile](software_modem::ofdm::profiles::narrowband_voice) across a room,
+//! and the names of the [profiles](software_modem::ofdm::profiles::OFDMProfile), each of which loops back.
+//!
+//! A frame of the DVB-T profile also times the complex modem at a realistic size: it has to round trip
+//! within a bound that even an unoptimized build on a slow machine keeps.
+
+use std::time::{Duration, Instant};
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, IrChannel, synthetic_rir},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    metrics::{SpectrumWindow, oob_power_db, power_spectrum},
+    ofdm::{
+        GuardInterval,
+        complex::{ComplexOFDMConfig, ComplexOFDMDemodulator, ComplexOFDMModulator},
+        profiles::{
+            OFDMProfile, ProfileConfig, ProfileError, VOICE_BAND, dvbt_2k_like, ieee80211a_like,
+            narrowband_voice,
+        },
+    },
+    qam::QAMOrder,
+};
+
+/// The frequency of the data subcarrier `k` of 802.11a, its function M(k).
+fn mapping(k: i32) -> i32 {
+    match k {
+        0..=4 => k - 26,
+        5..=17 => k - 25,
+        18..=23 => k - 24,
+        24..=29 => k - 23,
+        30..=42 => k - 22,
+        43..=47 => k - 21,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn the_layout_is_the_one_of_the_standard() {
+    let config = ieee80211a_like(QAMOrder::QAM16);
+    let expected: Vec<i32> = (0..48).map(mapping).collect();
+    assert_eq!(config.get_data_frequencies(), expected);
+    assert_eq!(config.get_pilot_frequencies(), [-21, -7, 7, 21]);
+
+    // 48 subcarriers of 4 bits
+    let modulator = ComplexOFDMModulator::new(config);
+    assert_eq!(modulator.get_symbol_length(), 80);
+    assert_eq!(modulator.get_bytes_per_symbol(), 24);
+}
+
+#[test]
+fn symbols_occupy_only_the_used_subcarriers() {
+    let config = ieee80211a_like(QAMOrder::QAM16);
+    let modulator = ComplexOFDMModulator::new(config.clone());
+    let data: Vec<u8> = (0..240u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let samples = modulator.modulate_symbols(&data);
+    assert_eq!(samples.len(), 10 * 80);
+    assert_eq!(
+        ComplexOFDMDemodulator::new(config).demodulate_symbols(&samples),
+        data
+    );
+
+    let fft = rustfft::FftPlanner::new().plan_fft_forward(64);
+    for symbol in samples.chunks(80) {
+        // the cyclic prefix repeats the end of the symbol
+        assert_eq!(symbol[..16], symbol[64..]);
+        let mut bins = symbol[16..].to_vec();
+        fft.process(&mut bins);
+        let at = |frequency: i32| bins[frequency.rem_euclid(64) as usize];
+        for frequency in -32..32 {
+            let power = at(frequency).norm_sqr();
+            if frequency == 0 || frequency.abs() > 26 {
+                assert!(power < 1e-6, "{frequency}: {power}");
+            } else {
+                assert!(power > 1e-3, "{frequency}: {power}");
+            }
+        }
+        // every pilot the same, at the scale of the data
+        let pilot = at(7);
+        for frequency in [-21, -7, 21] {
+            assert!((at(frequency) - pilot).norm() < 1e-4);
+        }
+        assert!(pilot.im.abs() < 1e-4 && pilot.re > 0.0);
+        assert_ne!(pilot, Complex32::ZERO);
+    }
+}
+
+#[test]
+fn the_dvbt_layout_has_the_carriers_of_the_standard() {
+    for (guard, cyclic_prefix_length) in GuardInterval::FRACTIONS
+        .into_iter()
+        .zip([512, 256, 128, 64])
+    {
+        let config = dvbt_2k_like(guard, QAMOrder::QAM16);
+        assert_eq!(config.guard_interval, Some(guard));
+
+        // the carriers 0 to 1704 around the one at DC
+        let pilots = config.get_pilot_frequencies();
+        let data = config.get_data_frequencies();
+        let mut carriers: Vec<i32> = pilots.iter().chain(&data).copied().collect();
+        carriers.sort_unstable();
+        assert_eq!(carriers, (-852..=852).collect::<Vec<_>>());
+        assert_eq!((pilots.len(), data.len()), (193, 1512));
+        assert!(pilots.contains(&0));
+
+        // the scattered pilots of the first symbol, and continual pilots between them
+        for carrier in (0..1705).step_by(12) {
+            assert!(pilots.contains(&(carrier - 852)), "{carrier}");
+        }
+        for carrier in [48, 54, 87, 1491, 1683] {
+            assert!(pilots.contains(&(carrier - 852)), "{carrier}");
+        }
+
+        let modulator = ComplexOFDMModulator::new(config);
+        assert_eq!(
+            modulator.get_symbol_length(),
+            2048 + cyclic_prefix_length as usize
+        );
+        assert_eq!(modulator.get_bytes_per_symbol(), 756);
+    }
+}
+
+#[test]
+fn a_dvbt_frame_round_trips_in_time() {
+    let config = dvbt_2k_like(GuardInterval::Quarter, QAMOrder::QAM16);
+    let modulator = ComplexOFDMModulator::new(config.clone());
+    let demodulator = ComplexOFDMDemodulator::new(config);
+    // the 68 symbols of a DVB-T frame
+    let data: Vec<u8> = (0..68 * 756u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+
+    let start = Instant::now();
+    let mut samples = modulator.modulate_symbols(&data);
+    assert_eq!(samples.len(), 68 * 2560);
+    AwgnChannel::new(25.0, 3).apply_complex(&mut samples);
+    assert_eq!(demodulator.demodulate_symbols(&samples), data);
+    let elapsed = start.elapsed();
+    assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+}
+
+#[test]
+fn voice_frames_fit_the_band_and_cross_a_room() {
+    let payload: Vec<u8> = (0..200u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    for sample_rate in [8000.0, 48000.0] {
+        let (ofdm, coding) = narrowband_voice(sample_rate);
+        let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+        let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+        let frame = modulator.encode_frame(&payload);
+
+        let spectrum = power_spectrum(&frame, (sample_rate / 20.0) as usize, SpectrumWindow::Hann);
+        let band = (VOICE_BAND.0 / sample_rate, VOICE_BAND.1 / sample_rate);
+        let out_of_band = oob_power_db(&spectrum, band);
+        assert!(out_of_band < -30.0, "{sample_rate}: {out_of_band} dB");
+
+        let lost = |rt60: Option<f32>, snr: f32, frames: u64| {
+            (0..frames)
+                .filter(|&seed| {
+                    let mut channel = ChannelChain::new();
+                    if let Some(rt60) = rt60 {
+                        let rir = synthetic_rir(sample_rate, rt60, seed);
+                        channel = channel.with(IrChannel::from_samples(&rir));
+                    }
+                    let mut channel = channel.with(AwgnChannel::new(snr, seed));
+                    let mut samples = frame.clone();
+                    channel.apply(&mut samples);
+                    demodulator.decode_frame(&samples).ok().as_deref() != Some(&payload[..])
+                })
+                .count()
+        };
+        if sample_rate == 8000.0 {
+            // a telephone line, whose noise lies mostly within the voice band
+            assert_eq!(lost(None, 8.0, 20), 0);
+        } else {
+            // small rooms, whose reverberation mostly ends within the prefix, at a sound card,
+            // whose noise spreads over 24 kHz; QPSK decodes even the rooms of the deepest fades in the band
+            assert_eq!(lost(Some(0.05), 10.0, 20), 0);
+            assert_eq!(lost(Some(0.1), 10.0, 20), 0);
+        }
+    }
+}
+
+#[test]
+fn explicit_pilots_replace_the_interval() {
+    let config = ComplexOFDMConfig {
+        num_subcarriers: 32,
+        cyclic_prefix_length: 4,
+        pilot_subcarrier_every: 2,
+        pilot_frequencies: vec![-9, 3],
+        ..Default::default()
+    };
+    assert_eq!(config.get_pilot_frequencies(), [-9, 3]);
+    let data = config.get_data_frequencies();
+    assert_eq!(data.len(), 30 - 2);
+    assert!(data.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(!data.contains(&0) && !data.contains(&-9) && !data.contains(&3));
+
+    // a pilot on DC is kept when DC is not nulled
+    let config = ComplexOFDMConfig {
+        pilot_frequencies: vec![0],
+        null_dc: false,
+        ..config
+    };
+    assert_eq!(config.get_pilot_frequencies(), [0]);
+    assert_eq!(config.get_data_frequencies().len(), 30);
+}
+
+#[test]
+#[should_panic(
+    expected = "Pilot frequencies must be subcarriers in use from -26 to 26, but got 27"
+)]
+fn pilots_are_in_the_band() {
+    ComplexOFDMModulator::new(ComplexOFDMConfig {
+        pilot_frequencies: vec![7, 27],
+        ..ieee80211a_like(QAMOrder::QAM16)
+    });
+}
+
+#[test]
+#[should_panic(expected = "Pilot frequencies must be distinct, but got 7 twice")]
+fn pilots_are_distinct() {
+    ComplexOFDMModulator::new(ComplexOFDMConfig {
+        pilot_frequencies: vec![7, -7, 7],
+        ..ieee80211a_like(QAMOrder::QAM16)
+    });
+}
+
+#[test]
+fn every_named_profile_loops_back() {
+    let payload: Vec<u8> = (0..1000u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    for profile in OFDMProfile::all() {
+        assert_eq!(profile.to_string().parse(), Ok(profile.clone()));
+        match profile.clone().build() {
+            ProfileConfig::Coded { ofdm, coding } => {
+                let expected = match profile {
+                    OFDMProfile::NarrowbandVoice { .. } => 570.0..620.0,
+                    _ => 175.0..195.0,
+                };
+                let modulator = CodedOFDMModulator::new(*ofdm.clone(), coding.clone());
+                let demodulator = CodedOFDMDemodulator::new(*ofdm, coding);
+                let samples = modulator.encode_frame(&payload);
+                assert_eq!(demodulator.decode_frame(&samples).unwrap(), payload);
+
+                // the net bit rate of the table, at 48 kHz
+                let bit_rate = 8.0 * 1000.0 / (samples.len() as f32 / 48000.0);
+                assert!(expected.contains(&bit_rate), "{profile}: {bit_rate}");
+            }
+            ProfileConfig::Complex(config) => {
+                let modulator = ComplexOFDMModulator::new(config.clone());
+                let demodulator = ComplexOFDMDemodulator::new(config);
+                let data = &payload[..modulator.get_bytes_per_symbol()];
+                let samples = modulator.modulate_symbols(data);
+                assert_eq!(demodulator.demodulate_symbols(&samples), data);
+            }
+        }
+    }
+}
+
+#[test]
+fn profiles_are_parsed_from_their_names() {
+    let parse = |name: &str| name.parse::<OFDMProfile>();
+    assert_eq!(
+        parse("wifi-like"),
+        Ok(OFDMProfile::WifiLike(QAMOrder::QAM16))
+    );
+    assert_eq!(parse(" WiFi-Like:QAM-16 "), parse("wifi-like:qam16"));
+    assert_eq!(
+        parse("dvbt-2k-like:1/32"),
+        Ok(OFDMProfile::DvbT2kLike(
+            GuardInterval::ThirtySecond,
+            QAMOrder::QAM16
+        ))
+    );
+    assert_eq!(
+        parse("narrowband-voice"),
+        Ok(OFDMProfile::NarrowbandVoice { sample_rate: 48000 })
+    );
+    assert_eq!(parse("Robust-Beacon"), Ok(OFDMProfile::RobustBeacon));
+    assert_eq!(
+        parse("narrowband-voice:8000").unwrap().to_string(),
+        "narrowband-voice:8000"
+    );
+    for guard in GuardInterval::FRACTIONS
+        .into_iter()
+        .chain([GuardInterval::Samples(100)])
+    {
+        let profile = OFDMProfile::DvbT2kLike(guard, QAMOrder::QAM16);
+        assert_eq!(parse(&profile.to_string()), Ok(profile));
+    }
+
+    let invalid = |profile: &str, parameter: &str| {
+        Err(ProfileError::InvalidParameter {
+            profile: profile.into(),
+            parameter: parameter.into(),
+        })
+    };
+    assert_eq!(parse("wifi-like:qam1024"), invalid("wifi-like", "qam1024"));
+    assert_eq!(parse("dvbt-2k-like:1/5"), invalid("dvbt-2k-like", "1/5"));
+    assert_eq!(
+        parse("dvbt-2k-like:1/4:qam16:8k"),
+        invalid("dvbt-2k-like", "8k")
+    );
+    // below twice the voice band
+    assert_eq!(
+        parse("narrowband-voice:4000"),
+        invalid("narrowband-voice", "4000")
+    );
+    assert_eq!(
+        parse("lte"),
+        Err(ProfileError::UnknownProfile("lte".into()))
+    );
+    // a custom configuration has no name to come back from
+    let custom = OFDMProfile::Custom(Box::new(ProfileConfig::Complex(ieee80211a_like(
+        QAMOrder::QAM16,
+    ))));
+    assert_eq!(custom.to_string(), "custom");
+    assert_eq!(
+        parse("custom"),
+        Err(ProfileError::UnknownProfile("custom".into()))
+    );
+    assert_eq!(
+        custom.build(),
+        ProfileConfig::Complex(ieee80211a_like(QAMOrder::QAM16))
+    );
+}
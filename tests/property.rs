@@ -0,0 +1,183 @@
+//! Property-based round trips of frames with `proptest` over configurations drawn at random,
+//! see [software_modem::testing::property], and checks that a failing property shrinks to a minimal case
+//! and that the cases only depend on the seed.
+
+use proptest::{
+    prelude::*,
+    sample,
+    strategy::ValueTree,
+    test_runner::{
+        Config, FileFailurePersistence, RngAlgorithm, RngSeed, TestError, TestRng, TestRunner,
+    },
+};
+use software_modem::{
+    ofdm::OFDMConfig,
+    testing::property::{ModemCase, round_trip},
+};
+
+/// Draws every field of a case from its range on its own, so each of them shrinks towards its minimum.
+fn modem_case() -> impl Strategy<Value = ModemCase> {
+    let (min, max) = (&ModemCase::MIN, &ModemCase::MAX);
+    (
+        min.num_subcarriers..=max.num_subcarriers,
+        min.cyclic_prefix_length..=max.cyclic_prefix_length,
+        min.pilot_subcarrier_every..=max.pilot_subcarrier_every,
+        sample::select(&ModemCase::QAM_ORDERS[..]),
+        any::<bool>(),
+        min.payload_length..=max.payload_length,
+        any::<u64>(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                num_subcarriers,
+                cyclic_prefix_length,
+                pilot_subcarrier_every,
+                qam_order,
+                differential_time,
+                payload_length,
+                seed,
+                noise,
+            )| ModemCase {
+                num_subcarriers,
+                cyclic_prefix_length,
+                pilot_subcarrier_every,
+                qam_order,
+                differential_time,
+                payload_length,
+                seed,
+                noise,
+            },
+        )
+        .prop_filter(
+            "the pilots must fall on some subcarrier",
+            ModemCase::is_valid,
+        )
+}
+
+/// Checks the cases drawn from a fixed seed, the same every run, without writing failures to files.
+fn config(cases: u32, seed: u64) -> Config {
+    Config {
+        cases,
+        rng_algorithm: RngAlgorithm::ChaCha,
+        rng_seed: RngSeed::Fixed(seed),
+        failure_persistence: Some(Box::new(FileFailurePersistence::Off)),
+        ..Config::default()
+    }
+}
+
+fn runner(cases: u32, seed: u64) -> TestRunner {
+    let mut seed_bytes = [0; 32];
+    seed_bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    TestRunner::new_with_rng(
+        config(cases, seed),
+        TestRng::from_seed(RngAlgorithm::ChaCha, &seed_bytes),
+    )
+}
+
+proptest! {
+    #![proptest_config(config(200, 0x5eed))]
+
+    #[test]
+    fn frames_round_trip(case in modem_case()) {
+        prop_assert_eq!(round_trip(&case), Ok(()));
+    }
+}
+
+#[test]
+fn the_simplest_case_round_trips() {
+    assert_eq!(round_trip(&ModemCase::MIN), Ok(()));
+    assert_eq!(
+        round_trip(&ModemCase {
+            payload_length: 600,
+            differential_time: true,
+            noise: true,
+            ..ModemCase::MIN
+        }),
+        Ok(())
+    );
+}
+
+#[test]
+fn failures_shrink_to_minimal_cases() {
+    // fails for more than 100 subcarriers together with a prefix of 11 samples or more
+    let property = |case: ModemCase| {
+        if case.num_subcarriers <= 100 || case.cyclic_prefix_length <= 10 {
+            Ok(())
+        } else {
+            Err(TestCaseError::fail(format!(
+                "{} subcarriers, {} samples",
+                case.num_subcarriers, case.cyclic_prefix_length
+            )))
+        }
+    };
+    let Err(TestError::Fail(message, case)) = runner(100, 3).run(&modem_case(), property) else {
+        panic!("no case failed");
+    };
+    assert_eq!(
+        case,
+        ModemCase {
+            num_subcarriers: 101,
+            cyclic_prefix_length: 11,
+            ..ModemCase::MIN
+        }
+    );
+    assert_eq!(message.message(), "101 subcarriers, 11 samples");
+
+    // a panic fails like an error, and the same seed finds the same case
+    let panicking = |case: ModemCase| {
+        assert!(
+            case.payload_length < 50,
+            "payload of {}",
+            case.payload_length
+        );
+        Ok(())
+    };
+    let Err(TestError::Fail(message, case)) = runner(100, 3).run(&modem_case(), panicking) else {
+        panic!("no case failed");
+    };
+    assert_eq!(
+        case,
+        ModemCase {
+            payload_length: 50,
+            ..ModemCase::MIN
+        }
+    );
+    assert!(message.message().contains("payload of 50"), "{message}");
+    let Err(TestError::Fail(_, again)) = runner(100, 3).run(&modem_case(), panicking) else {
+        panic!("no case failed");
+    };
+    assert_eq!(again, case);
+}
+
+#[test]
+fn cases_cover_the_space() {
+    let mut runner = runner(300, 9);
+    let strategy = modem_case();
+    let cases: Vec<ModemCase> = (0..300)
+        .map(|_| strategy.new_tree(&mut runner).unwrap().current())
+        .collect();
+
+    let (min, max) = (&ModemCase::MIN, &ModemCase::MAX);
+    for case in &cases {
+        assert!((min.num_subcarriers..=max.num_subcarriers).contains(&case.num_subcarriers));
+        assert!(case.cyclic_prefix_length <= max.cyclic_prefix_length);
+        assert!(
+            (min.pilot_subcarrier_every..=max.pilot_subcarrier_every)
+                .contains(&case.pilot_subcarrier_every)
+        );
+        assert!(case.payload_length <= max.payload_length);
+        assert!(case.is_valid());
+        let config = case.ofdm_config();
+        assert_eq!(OFDMConfig::from_bytes(&config.to_bytes()), Ok(config));
+    }
+    let count = |f: fn(&ModemCase) -> bool| cases.iter().filter(|case| f(case)).count();
+    assert!(count(|case| !case.num_subcarriers.is_power_of_two()) > 250);
+    assert!(count(|case| case.differential_time) > 100);
+    assert!(count(|case| case.noise) > 100);
+    assert!(count(|case| case.pilot_subcarrier_every >= case.num_subcarriers) > 0);
+    assert!(count(|case| case.payload_length < 20) > 0);
+    for qam_order in ModemCase::QAM_ORDERS {
+        assert!(cases.iter().any(|case| case.qam_order == qam_order));
+    }
+}
@@ -0,0 +1,67 @@
+//! Builds the `pyo3` extension with the `python` feature and runs the pytest suite of the bindings in `python/tests` against it.
+//!
+//! The suite needs NumPy and pytest, which are not a dependency of the crate, so the test is ignored by default:
+//! `cargo test --features python -- --ignored`. The interpreter is taken from `PYTHON`, or `python3`.
+
+#![cfg(all(feature = "python", unix))]
+
+use std::{fs, path::PathBuf, process::Command};
+
+#[test]
+#[ignore = "needs NumPy and pytest"]
+fn pytest_suite_passes() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    // the cdylib next to the test binary is overwritten by builds with other features, build one of our own,
+    // which leaves the symbols of libpython to the interpreter like maturin does
+    let target = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args([
+            "rustc",
+            "--lib",
+            "--crate-type",
+            "cdylib",
+            "--features",
+            "python",
+            "--target-dir",
+        ])
+        .arg(&target)
+        .env("PYO3_BUILD_EXTENSION_MODULE", "1")
+        .current_dir(&root)
+        .status()
+        .unwrap();
+    assert!(status.success(), "building the extension failed");
+    let extension = if cfg!(target_os = "macos") {
+        "dylib"
+    } else {
+        "so"
+    };
+    let lib = target
+        .join("debug")
+        .join(format!("libsoftware_modem.{}", extension));
+
+    // the package of python/software_modem with the extension in it, as maturin installs it
+    let package = target.join("package").join("software_modem");
+    fs::create_dir_all(&package).unwrap();
+    fs::copy(
+        root.join("python/software_modem/__init__.py"),
+        package.join("__init__.py"),
+    )
+    .unwrap();
+    fs::copy(&lib, package.join("_native.abi3.so")).unwrap();
+
+    let python = std::env::var("PYTHON").unwrap_or_else(|_| "python3".to_string());
+    let output = Command::new(&python)
+        .args(["-m", "pytest", "-q", "python/tests"])
+        .env("PYTHONPATH", target.join("package"))
+        .current_dir(&root)
+        .output()
+        .unwrap_or_else(|error| panic!("Python {} could not be run: {}", python, error));
+    assert!(
+        output.status.success(),
+        "the pytest suite failed: {}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
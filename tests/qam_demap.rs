@@ -0,0 +1,95 @@
+//! Checks that the lookup table of the table demap strategy decides every cell of its grid like the nearest point search,
+//! for every QAM order.
+
+use realfft::num_complex::Complex32;
+use software_modem::qam::{DemapStrategy, QAMModem, QAMOrder};
+
+/// The cells of the grid of the table on each axis, from one beyond the outermost level to one beyond on the other side.
+const CELLS: i32 = 256;
+
+/// Returns the index of the nearest point of the constellation.
+fn nearest_index(constellation: &[Complex32], point: Complex32) -> u32 {
+    let mut nearest = (0, f32::INFINITY);
+    for (index, candidate) in constellation.iter().enumerate() {
+        let distance = (point - candidate).norm_sqr();
+        if distance < nearest.1 {
+            nearest = (index as u32, distance);
+        }
+    }
+    nearest.0
+}
+
+/// Returns every point of the order, the one of every index in turn.
+fn constellation(modem: &QAMModem) -> Vec<Complex32> {
+    (0..1 << modem.bits_per_symbol())
+        .map(|bits| modem.map_bits(bits))
+        .collect()
+}
+
+#[test]
+fn table_decides_every_cell_like_the_nearest_point() {
+    for order in QAMOrder::ALL {
+        let modem = QAMModem::with_demap_strategy(order, DemapStrategy::Table);
+        let slicer = QAMModem::new(order);
+        let constellation = constellation(&modem);
+        // -4 to 4 and 1/32 wide for QAM-16
+        let extent = (1 << (modem.bits_per_symbol() / 2)) as f32;
+        let cell_width = 2.0 * extent / CELLS as f32;
+
+        // the center and the corners of every cell, the corners just inside so they are not on a boundary
+        let inset = cell_width / 64.0;
+        let offsets = [
+            (0.5 * cell_width, 0.5 * cell_width),
+            (inset, inset),
+            (inset, cell_width - inset),
+            (cell_width - inset, inset),
+            (cell_width - inset, cell_width - inset),
+        ];
+        let edge = |cell: i32| (cell - CELLS / 2) as f32 * cell_width;
+        for re in 0..CELLS {
+            for im in 0..CELLS {
+                for (dre, dim) in offsets {
+                    let point = Complex32::new(edge(re) + dre, edge(im) + dim);
+                    let expected = nearest_index(&constellation, point);
+                    assert_eq!(modem.demap_point(point), expected, "{order} at {point}");
+                    // and the slicer agrees
+                    assert_eq!(slicer.demap_point(point), expected, "{order} at {point}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn points_outside_the_grid_decide_like_its_edge() {
+    for order in QAMOrder::ALL {
+        let slicer = QAMModem::new(order);
+        let table = QAMModem::with_demap_strategy(order, DemapStrategy::Table);
+        // the edge of the grid, -4 and 4 for QAM-16
+        let extent = (1 << (slicer.bits_per_symbol() / 2)) as f32;
+        let coordinates = [
+            -1e30,
+            -100.0,
+            -extent,
+            -0.0,
+            0.0,
+            extent,
+            100.0,
+            1e30,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+        ];
+        let points: Vec<Complex32> = coordinates
+            .iter()
+            .flat_map(|&re| coordinates.iter().map(move |&im| Complex32::new(re, im)))
+            .collect();
+        // the points of whole bytes
+        let points = &points[..points.len() / 4 * 4];
+        assert_eq!(
+            table.demodulate(points),
+            slicer.demodulate(points),
+            "{order}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Switches the QAM order of the modulator and the demodulator at run time, every frame of a stream,
+//! and checks that the frames still decode and that the FFTs and the scratches made before are kept.
+//!
+//! The orders switched through are all of [QAMOrder::ALL], from QPSK to QAM-256.
+
+use std::sync::Arc;
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{
+        OFDMConfig,
+        demodulator::OFDMDemodulator,
+        modulator::{OFDMModulator, OutputScale},
+    },
+    qam::QAMOrder,
+    stream::{StreamDemodulator, StreamModulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn switching_keeps_the_fft_and_the_scratches() {
+    let mut modulator = OFDMModulator::new((&config()).into());
+    let mut demodulator = OFDMDemodulator::new((&config()).into());
+    let (inverse, forward) = (modulator.get_fft().clone(), demodulator.get_fft().clone());
+    let mut modulator_scratch = modulator.make_scratch();
+    let mut demodulator_scratch = demodulator.make_scratch();
+
+    for (frame, &qam_order) in QAMOrder::ALL.iter().cycle().take(100).enumerate() {
+        modulator.set_qam_order(qam_order).unwrap();
+        demodulator.set_qam_order(qam_order).unwrap();
+        assert_eq!(modulator.get_qam_order(), qam_order);
+        assert_eq!(demodulator.get_qam_order(), qam_order);
+        assert_eq!(
+            modulator.get_bytes_per_symbol(),
+            demodulator.get_bytes_per_symbol()
+        );
+
+        let data = data(modulator.get_bytes_per_symbol() as u32 + frame as u32)[frame..].to_vec();
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+        modulator
+            .try_modulate_buffer_as_symbol_with_scratch(&data, &mut modulator_scratch, &mut symbol)
+            .unwrap();
+        let mut output = vec![0; demodulator.get_bytes_per_symbol()];
+        demodulator
+            .try_demodulate_symbol_into(&symbol, &mut demodulator_scratch, &mut output)
+            .unwrap();
+        assert_eq!(output, data);
+    }
+    assert!(Arc::ptr_eq(&inverse, modulator.get_fft()));
+    assert!(Arc::ptr_eq(&forward, demodulator.get_fft()));
+}
+
+#[test]
+fn stream_switches_the_order_every_frame() {
+    let mut modulator = StreamModulator::new(
+        CodedOFDMModulator::new(config(), CodingConfig::default()),
+        3000,
+    );
+    let mut stream = StreamDemodulator::new(
+        CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+        0.01,
+        1000,
+    );
+
+    // both ends agree on the order of every frame, as an adaptive link does out of band
+    let mut decoded = Vec::new();
+    for (frame, &qam_order) in QAMOrder::ALL.iter().cycle().take(100).enumerate() {
+        modulator.set_qam_order(qam_order).unwrap();
+        let payload = data(20 + frame as u32);
+        let mut samples = Vec::new();
+        modulator.write_frame(&payload, &mut samples).unwrap();
+
+        stream.set_qam_order(qam_order).unwrap();
+        for block in samples.chunks(256) {
+            decoded.extend(stream.push(block));
+        }
+        assert_eq!(decoded.last(), Some(&payload), "frame {frame}");
+    }
+    assert_eq!(decoded.len(), 100);
+    assert_eq!(stream.get_frames_decoded(), 100);
+    assert_eq!(stream.get_frames_failed(), 0);
+}
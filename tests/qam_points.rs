@@ -0,0 +1,173 @@
+//! Known answers of the point-level QAM functions for every built-in order,
+//! and checks that the byte-level functions decide and map like them.
+
+use realfft::num_complex::Complex32;
+use software_modem::qam::{DemapStrategy, GenericQAMModem, QAMModem, QAMOrder};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// The levels of an axis in the order of its bits, the sign and the Gray code of the magnitude.
+fn axis_levels(qam_order: QAMOrder) -> Vec<f32> {
+    match qam_order {
+        QAMOrder::QPSK => vec![1.0, -1.0],
+        QAMOrder::QAM16 => vec![1.0, 3.0, -1.0, -3.0],
+        QAMOrder::QAM64 => vec![1.0, 3.0, 7.0, 5.0, -1.0, -3.0, -7.0, -5.0],
+        QAMOrder::QAM256 => vec![
+            1.0, 3.0, 7.0, 5.0, 15.0, 13.0, 9.0, 11.0, -1.0, -3.0, -7.0, -5.0, -15.0, -13.0, -9.0,
+            -11.0,
+        ],
+    }
+}
+
+/// The points of every order, in the order of their bits.
+fn known_points(qam_order: QAMOrder) -> Vec<Complex32> {
+    match qam_order {
+        QAMOrder::QAM16 => [
+            (1.0, 1.0),
+            (1.0, 3.0),
+            (3.0, 1.0),
+            (3.0, 3.0),
+            (1.0, -1.0),
+            (1.0, -3.0),
+            (3.0, -1.0),
+            (3.0, -3.0),
+            (-1.0, 1.0),
+            (-1.0, 3.0),
+            (-3.0, 1.0),
+            (-3.0, 3.0),
+            (-1.0, -1.0),
+            (-1.0, -3.0),
+            (-3.0, -1.0),
+            (-3.0, -3.0),
+        ]
+        .map(|(re, im)| Complex32::new(re, im))
+        .to_vec(),
+        // the bits of the axes take turns, the real one first
+        _ => {
+            let levels = axis_levels(qam_order);
+            let axis_bits = levels.len().trailing_zeros();
+            (0..levels.len() * levels.len())
+                .map(|index: usize| {
+                    let axis = |shift: u32| {
+                        (0..axis_bits).fold(0, |label, bit| {
+                            label | ((index >> (2 * bit + shift)) & 1) << bit
+                        })
+                    };
+                    Complex32::new(levels[axis(1)], levels[axis(0)])
+                })
+                .collect()
+        }
+    }
+}
+
+#[test]
+fn known_answers() {
+    for qam_order in QAMOrder::ALL {
+        let points = known_points(qam_order);
+        for strategy in [DemapStrategy::Slicer, DemapStrategy::Table] {
+            let modem = QAMModem::with_demap_strategy(qam_order, strategy);
+            assert_eq!(points.len(), 1 << modem.bits_per_symbol());
+            for (bits, &point) in (0..).zip(&points) {
+                assert_eq!(modem.map_bits(bits), point, "{qam_order} {bits}");
+                assert_eq!(modem.demap_point(point), bits, "{qam_order} {bits}");
+                assert_eq!(modem.nearest_point(point), (bits, 0.0));
+
+                // off by less than half the distance to the neighbours
+                for offset in [(0.4, 0.3), (-0.45, 0.1), (0.2, -0.49)] {
+                    let received = point + Complex32::new(offset.0, offset.1);
+                    assert_eq!(modem.demap_point(received), bits, "{qam_order} {received}");
+                    let (nearest, distance) = modem.nearest_point(received);
+                    assert_eq!(nearest, bits);
+                    assert!((distance - (received - point).norm_sqr()).abs() < 1e-6);
+                }
+            }
+
+            // far outside, the corners
+            let outermost = axis_levels(qam_order).into_iter().fold(0.0, f32::max);
+            let corner = modem.nearest_point(Complex32::new(100.0, -100.0));
+            assert_eq!(
+                modem.map_bits(corner.0),
+                Complex32::new(outermost, -outermost)
+            );
+            assert_eq!(corner.1, 2.0 * (100.0 - outermost).powi(2));
+            assert_eq!(modem.demap_point(Complex32::new(100.0, -100.0)), corner.0);
+        }
+
+        // the same in f64
+        let modem = GenericQAMModem::<f64>::new(qam_order);
+        for (bits, expected) in (0..).zip(points) {
+            let point = modem.map_bits(bits);
+            assert_eq!(
+                (point.re as f32, point.im as f32),
+                (expected.re, expected.im)
+            );
+            assert_eq!(modem.demap_point(point), bits);
+            assert_eq!(modem.nearest_point(point), (bits, 0.0));
+        }
+    }
+}
+
+#[test]
+fn bytes_are_built_on_points() {
+    let payload = data(300);
+    for qam_order in QAMOrder::ALL {
+        for strategy in [DemapStrategy::Slicer, DemapStrategy::Table] {
+            let modem = QAMModem::with_demap_strategy(qam_order, strategy);
+            let bits_per_symbol = modem.bits_per_symbol();
+            let symbols = modem.modulate(&payload);
+
+            // the bits of the bytes in turn, first bit highest, 3 bytes to 4 symbols for QAM-64
+            let bits: Vec<u32> = (0..payload.len() * 8 / bits_per_symbol as usize)
+                .map(|symbol| {
+                    (0..bits_per_symbol as usize).fold(0, |bits, bit| {
+                        let position = symbol * bits_per_symbol as usize + bit;
+                        (bits << 1) | u32::from(payload[position / 8] >> (7 - position % 8) & 1)
+                    })
+                })
+                .collect();
+            let mapped: Vec<Complex32> = bits.iter().map(|&bits| modem.map_bits(bits)).collect();
+            assert_eq!(symbols, mapped);
+
+            // noisy points decide like the points one by one
+            let received: Vec<Complex32> = symbols
+                .iter()
+                .zip(data(2 * symbols.len() as u32).chunks(2))
+                .map(|(symbol, noise)| {
+                    let offset = |byte: u8| (byte as f32 - 127.5) / 80.0;
+                    symbol + Complex32::new(offset(noise[0]), offset(noise[1]))
+                })
+                .collect();
+            let decided: Vec<u32> = received
+                .iter()
+                .map(|&point| modem.demap_point(point))
+                .collect();
+            let nearest: Vec<usize> = received
+                .iter()
+                .map(|&point| modem.nearest_point(point).0 as usize)
+                .collect();
+            assert_eq!(modem.nearest_indices(&received), nearest);
+            let mut bytes = Vec::new();
+            let (mut accumulator, mut available) = (0u32, 0);
+            for bits in decided {
+                accumulator = (accumulator << bits_per_symbol) | bits;
+                available += bits_per_symbol;
+                while available >= 8 {
+                    available -= 8;
+                    bytes.push((accumulator >> available) as u8);
+                }
+                accumulator &= (1 << available) - 1;
+            }
+            assert_eq!(modem.demodulate(&received), bytes);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "Bits must be below 16 for QAM-16, but got 16")]
+fn bits_beyond_the_constellation() {
+    QAMModem::new(QAMOrder::QAM16).map_bits(16);
+}
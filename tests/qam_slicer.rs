@@ -0,0 +1,135 @@
+//! Checks that the SIMD slicer of `f32` decides exactly like the scalar slicer and the nearest point search,
+//! for every QAM order, over a grid of coordinates which takes in every decision boundary.
+
+use realfft::num_complex::Complex32;
+use software_modem::qam::{QAMModem, QAMOrder, slice_qam};
+
+/// Coordinates on a grid of quarters past the outermost level of QAM-256, the decision boundaries exactly
+/// and one step off, and the special values.
+fn coordinates() -> Vec<f32> {
+    let mut coordinates: Vec<f32> = (-72..=72).map(|k| k as f32 * 0.25).collect();
+    for boundary in (0..=14).step_by(2).map(|boundary| boundary as f32) {
+        for value in [
+            f32::from_bits(boundary.to_bits() + 1),
+            f32::from_bits(boundary.to_bits().saturating_sub(1)),
+        ] {
+            coordinates.extend([value, -value]);
+        }
+    }
+    coordinates.extend([
+        -0.0,
+        f32::MIN_POSITIVE,
+        -f32::MIN_POSITIVE,
+        f32::MAX,
+        f32::MIN,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NAN,
+    ]);
+    coordinates
+}
+
+/// Every combination of the coordinates as a point.
+fn grid() -> Vec<Complex32> {
+    let coordinates = coordinates();
+    coordinates
+        .iter()
+        .flat_map(|&re| coordinates.iter().map(move |&im| Complex32::new(re, im)))
+        .collect()
+}
+
+/// Returns the index of the nearest point of the constellation, the one nearer the origin on a tie.
+fn nearest_index(modem: &QAMModem, point: Complex32) -> u32 {
+    let mut nearest = (0, f64::INFINITY);
+    for index in 0..1 << modem.bits_per_symbol() {
+        let candidate = modem.map_bits(index);
+        let distance = (f64::from(point.re) - f64::from(candidate.re)).powi(2)
+            + (f64::from(point.im) - f64::from(candidate.im)).powi(2);
+        let inner = candidate.norm_sqr() < modem.map_bits(nearest.0).norm_sqr();
+        if distance < nearest.1 || distance == nearest.1 && inner {
+            nearest = (index, distance);
+        }
+    }
+    nearest.0
+}
+
+/// Returns the number of points of whole bytes up to the number, 4 points to 3 bytes for QAM-64.
+fn whole_bytes(modem: &QAMModem, points: usize) -> usize {
+    let bits = modem.bits_per_symbol() as usize;
+    (points * bits / 8 * 8).div_ceil(bits)
+}
+
+#[test]
+fn simd_slicer_matches_the_scalar_slicer() {
+    let grid = grid();
+    for order in QAMOrder::ALL {
+        let modem = QAMModem::new(order);
+        let bits = modem.bits_per_symbol() as usize;
+        let points = &grid[..whole_bytes(&modem, grid.len())];
+
+        let mut scalar = vec![0; points.len() * bits / 8];
+        slice_qam(order, points, &mut scalar);
+        let mut simd = vec![0; points.len() * bits / 8];
+        modem.demodulate_into(points, &mut simd);
+        if let Some(byte) = (0..simd.len()).find(|&byte| simd[byte] != scalar[byte]) {
+            let point = byte * 8 / bits;
+            panic!(
+                "{order}: byte {byte}, from the points from {:?}",
+                &points[point..point + 2]
+            );
+        }
+
+        // every length and offset of whole bytes, so the blocks of the SIMD code end and start anywhere
+        for offset in (0..16).filter(|&offset: &usize| (offset * bits).is_multiple_of(8)) {
+            for length in 0..40 {
+                let length = whole_bytes(&modem, length);
+                let points = &points[offset..offset + length];
+                let mut simd = vec![0; length * bits / 8];
+                modem.demodulate_into(points, &mut simd);
+                let start = offset * bits / 8;
+                assert_eq!(
+                    simd,
+                    scalar[start..start + simd.len()],
+                    "{order}: {length} points from {offset}"
+                );
+            }
+        }
+    }
+}
+
+/// Returns the indices of the points whose bits the bytes carry whole, the first bit highest.
+fn unpack(bytes: &[u8], bits: u32) -> Vec<u32> {
+    let bits = bits as usize;
+    (0..bytes.len() * 8 / bits)
+        .map(|point| {
+            (0..bits).fold(0, |index, bit| {
+                let position = point * bits + bit;
+                (index << 1) | u32::from(bytes[position / 8] >> (7 - position % 8) & 1)
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn slicer_decides_the_nearest_point() {
+    // the distances of infinite or NaN coordinates do not order the points,
+    // and the ones of coordinates next to 0 or next to the largest value round to ties
+    let ordered = |x: f32| x == 0.0 || (1e-30..1e30).contains(&x.abs());
+    let grid: Vec<Complex32> = grid()
+        .into_iter()
+        .filter(|point| ordered(point.re) && ordered(point.im))
+        .collect();
+    for order in QAMOrder::ALL {
+        let modem = QAMModem::new(order);
+        let points = &grid[..whole_bytes(&modem, grid.len())];
+        let decisions = unpack(&modem.demodulate(points), modem.bits_per_symbol());
+        for (point, decision) in points.iter().zip(decisions) {
+            assert_eq!(
+                decision,
+                nearest_index(&modem, *point),
+                "{order} at {point}"
+            );
+            assert_eq!(modem.demap_point(*point), decision, "{order} at {point}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Checks that the soft demapper of QAM-16, which works on every axis on its own,
+//! gives the LLRs of the max-log search over all 16 points to the last bit.
+
+use realfft::num_complex::Complex;
+use software_modem::{
+    qam::{GenericQAMModem, QAMOrder},
+    samples::Sample,
+};
+
+/// The max-log LLRs from the nearest points with a `1` and with a `0` bit, searched over the whole constellation.
+fn search<T: Sample>(constellation: &[Complex<T>], symbols: &[Complex<T>]) -> Vec<T> {
+    let mut llrs = Vec::new();
+    for symbol in symbols {
+        let mut nearest_0 = [T::infinity(); 4];
+        let mut nearest_1 = [T::infinity(); 4];
+        for (index, point) in constellation.iter().enumerate() {
+            let distance = (symbol - point).norm_sqr();
+            for bit in 0..4 {
+                let nearest = if (index >> (3 - bit)) & 1 == 0 {
+                    &mut nearest_0[bit]
+                } else {
+                    &mut nearest_1[bit]
+                };
+                *nearest = nearest.min(distance);
+            }
+        }
+        llrs.extend((0..4).map(|bit| nearest_1[bit] - nearest_0[bit]));
+    }
+    llrs
+}
+
+/// Coordinates on a grid of eighths, the levels and boundaries one step off, and pseudo-random ones.
+fn coordinates<T: Sample>() -> Vec<T> {
+    let mut coordinates: Vec<T> = (-48..=48).map(|k| T::cast(k as f64 * 0.125)).collect();
+    for value in [0.0, 1.0, 2.0, 3.0, 4.0] {
+        let value = T::cast(value);
+        let step = T::epsilon() * value.max(T::one());
+        coordinates.extend([value + step, value - step, -value - step, step - value]);
+    }
+    let mut state: u32 = 0x9e37_79b9;
+    coordinates.extend((0..200).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        T::cast(state as f64 / u32::MAX as f64 * 20.0 - 10.0)
+    }));
+    coordinates.extend([T::cast(1e30), T::cast(-1e30)]);
+    coordinates
+}
+
+fn check<T: Sample>() {
+    let modem = GenericQAMModem::<T>::new(QAMOrder::QAM16);
+    let constellation = modem.modulate(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+    let coordinates = coordinates::<T>();
+    let symbols: Vec<Complex<T>> = coordinates
+        .iter()
+        .flat_map(|&re| coordinates.iter().map(move |&im| Complex::new(re, im)))
+        .collect();
+
+    let llrs = modem.demodulate_soft(&symbols);
+    let expected = search(&constellation, &symbols);
+    assert_eq!(llrs.len(), expected.len());
+    for (index, (llr, expected)) in llrs.iter().zip(&expected).enumerate() {
+        assert!(
+            llr == expected || (llr.is_nan() && expected.is_nan()),
+            "bit {} of {:?}: {:?}, expected {:?}",
+            index % 4,
+            symbols[index / 4],
+            llr,
+            expected
+        );
+    }
+}
+
+#[test]
+fn f32_llrs_match_the_search() {
+    check::<f32>();
+}
+
+#[test]
+fn f64_llrs_match_the_search() {
+    check::<f64>();
+}
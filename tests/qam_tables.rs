@@ -0,0 +1,43 @@
+//! Checks that the demap tables of the QAM orders are built once and shared, also when many threads need one at once.
+//!
+//! The tables live as long as the process, so this file holds a single test, which is the first to use them.
+
+use std::{sync::Barrier, thread};
+
+use software_modem::qam::{DemapStrategy, GenericQAMModem, QAMModem, QAMOrder};
+
+const THREADS: usize = 8;
+
+#[test]
+fn concurrent_first_use_shares_one_table() {
+    let barrier = Barrier::new(THREADS);
+    let modems: Vec<QAMModem> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    QAMModem::with_demap_strategy(QAMOrder::QAM16, DemapStrategy::Table)
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect()
+    });
+
+    let points =
+        QAMModem::new(QAMOrder::QAM16).modulate(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+    for modem in &modems {
+        assert!(modem.shares_demap_table(&modems[0]));
+        assert_eq!(
+            modem.demodulate(&points),
+            [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]
+        );
+    }
+    // later modems and other sample types get the same table
+    let later = GenericQAMModem::<f64>::with_demap_strategy(QAMOrder::QAM16, DemapStrategy::Table);
+    assert!(later.shares_demap_table(&modems[THREADS - 1]));
+    let slicer = QAMModem::new(QAMOrder::QAM16);
+    assert!(!slicer.shares_demap_table(&slicer));
+}
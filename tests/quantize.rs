@@ -0,0 +1,170 @@
+//! Checks the [quantization and clipping](software_modem::channel::QuantizeClip) against the noise of an ideal converter,
+//! and the error vector magnitude of QAM-16 and QAM-256 symbols over the bit depths and headrooms of a converter.
+//!
+//! The headroom is the distance of the clip level above the peak of the symbols, unless it is above their RMS level.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{Channel, QuantizeClip},
+    metrics::{count_bit_errors, evm},
+    ofdm::{OFDMConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator},
+    qam::{QAMModem, QAMOrder},
+    samples::TpdfDither,
+};
+
+/// Returns the SNR in dB of the quantized samples against the original ones.
+fn snr_db(original: &[f32], quantized: &[f32]) -> f64 {
+    let signal: f64 = original.iter().map(|&x| f64::from(x * x)).sum();
+    let noise: f64 = original
+        .iter()
+        .zip(quantized)
+        .map(|(x, y)| f64::from((x - y) * (x - y)))
+        .sum();
+    10.0 * (signal / noise).log10()
+}
+
+#[test]
+fn resolution_sets_the_noise_floor() {
+    // a full scale sine has an SNR of 6.02 dB per bit and 1.76 dB, this one is 0.22 dB below
+    let sine: Vec<f32> = (0..100_000)
+        .map(|n| 0.39 * (0.0123 * n as f32).sin())
+        .collect();
+    for bits in [8, 12, 16] {
+        let mut plain = sine.clone();
+        let mut channel = QuantizeClip::new(bits, 0.4, None);
+        channel.apply(&mut plain);
+        let expected = 6.02 * f64::from(bits) + 1.76 - 0.22;
+        assert!(
+            (snr_db(&sine, &plain) - expected).abs() < 0.5,
+            "{bits} bits: {} dB",
+            snr_db(&sine, &plain)
+        );
+
+        // the dither triples the noise
+        let mut dithered = sine.clone();
+        QuantizeClip::new(bits, 0.4, Some(TpdfDither::default())).apply(&mut dithered);
+        let cost = snr_db(&sine, &plain) - snr_db(&sine, &dithered);
+        assert!((cost - 4.77).abs() < 0.5, "{bits} bits: {cost} dB");
+        assert_eq!(channel.get_clipped(), 0);
+    }
+}
+
+#[test]
+fn clipping_is_counted() {
+    let mut channel = QuantizeClip::new(12, 0.5, None);
+    let mut samples = vec![0.2, -0.7, 0.51, 0.3, -0.5];
+    channel.apply(&mut samples);
+    assert_eq!(channel.get_clipped(), 2);
+    assert!(samples.iter().all(|x| x.abs() <= 0.5));
+
+    // a complex sample clips once, however many of its components clip
+    let mut samples = [
+        Complex32::new(0.6, -0.6),
+        Complex32::new(0.1, 0.9),
+        Complex32::new(0.1, 0.1),
+    ];
+    channel.apply_complex(&mut samples);
+    assert_eq!(channel.get_clipped(), 4);
+    channel.reset_clipped();
+    assert_eq!(channel.get_clipped(), 0);
+}
+
+/// The error vector magnitude in dB and the bit errors of 200 OFDM symbols of the QAM order, through a converter
+/// of a bit depth and a headroom above their peak, or above their RMS level.
+fn evm_db(qam_order: QAMOrder, bits: u32, headroom_db: f32, above_rms: bool) -> (f64, usize, u64) {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        qam_order,
+        ..Default::default()
+    };
+    let modulator = OFDMModulator::new((&ofdm).into());
+    let demodulator = OFDMDemodulator::new((&ofdm).into());
+    let bytes_per_symbol = modulator.get_bytes_per_symbol();
+    let symbol_length = modulator.get_symbol_length();
+    let data: Vec<u8> = (0..200 * bytes_per_symbol as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let mut samples = vec![0.0; 200 * symbol_length];
+    for (data, symbol) in data
+        .chunks(bytes_per_symbol)
+        .zip(samples.chunks_mut(symbol_length))
+    {
+        modulator.modulate_buffer_as_symbol(data, symbol);
+    }
+
+    let level = if above_rms {
+        (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+    } else {
+        samples.iter().fold(0.0, |peak: f32, x| peak.max(x.abs()))
+    };
+    let mut channel = QuantizeClip::new(bits, level * 10f32.powf(headroom_db / 20.0), None);
+    channel.apply(&mut samples);
+
+    let mut points = Vec::new();
+    let mut bit_errors = 0;
+    for (symbol, data) in samples
+        .chunks(symbol_length)
+        .zip(data.chunks(bytes_per_symbol))
+    {
+        let (demodulated, symbol_points) = demodulator.demodulate_symbol_with_points(symbol);
+        bit_errors += count_bit_errors(data, &demodulated).bit_errors;
+        points.extend(symbol_points);
+    }
+    let ideal = QAMModem::new(qam_order).modulate(&data);
+    (
+        f64::from(evm(&points, &ideal).rms_db),
+        bit_errors,
+        channel.get_clipped(),
+    )
+}
+
+#[test]
+fn qam16_survives_12_bits_with_3_db_of_headroom() {
+    let (evm, bit_errors, clipped) = evm_db(QAMOrder::QAM16, 12, 3.0, false);
+    assert_eq!((bit_errors, clipped), (0, 0));
+    // pinned, the quantization noise of the converter is the only error
+    assert!((evm + 60.9).abs() < 0.1, "{evm} dB");
+}
+
+#[test]
+fn resolution_and_headroom_degrade_the_constellation() {
+    let (reference, _, _) = evm_db(QAMOrder::QAM16, 12, 3.0, false);
+    // every bit less costs 6 dB, every dB of headroom one dB
+    let (fewer_bits, bit_errors, _) = evm_db(QAMOrder::QAM16, 8, 3.0, false);
+    assert_eq!(bit_errors, 0);
+    assert!(
+        (fewer_bits - reference - 24.1).abs() < 0.5,
+        "{fewer_bits} dB"
+    );
+    let (more_headroom, _, _) = evm_db(QAMOrder::QAM16, 12, 6.0, false);
+    assert!(
+        (more_headroom - reference - 3.0).abs() < 0.5,
+        "{more_headroom} dB"
+    );
+
+    // 4 bits have too few steps
+    let (evm, bit_errors, _) = evm_db(QAMOrder::QAM16, 4, 3.0, false);
+    assert!(bit_errors > 0, "{evm} dB");
+
+    // and clipping 6 dB above the RMS level costs bits, however fine the steps
+    let (evm, bit_errors, clipped) = evm_db(QAMOrder::QAM16, 16, 6.0, true);
+    assert!(bit_errors > 0 && clipped > 1000, "{evm} dB");
+    assert!(evm > -20.0, "{evm} dB");
+}
+
+#[test]
+fn qam256_degrades_at_the_same_converter() {
+    // the quantization noise is the same against the points of every order, so QAM-256 has the EVM of QAM-16
+    let (evm, bit_errors, clipped) = evm_db(QAMOrder::QAM256, 12, 3.0, false);
+    assert_eq!((bit_errors, clipped), (0, 0));
+    assert!((evm + 61.0).abs() < 0.1, "{evm} dB");
+
+    // but its points are closer, and 6 bits, which QAM-16 survives, cost bits, pinned at 1104 over the 200 symbols
+    let (evm, bit_errors, clipped) = evm_db(QAMOrder::QAM16, 6, 3.0, false);
+    assert_eq!((bit_errors, clipped), (0, 0));
+    assert!((evm + 24.7).abs() < 0.1, "{evm} dB");
+    let (evm, bit_errors, clipped) = evm_db(QAMOrder::QAM256, 6, 3.0, false);
+    assert_eq!((bit_errors, clipped), (1104, 0));
+    assert!((evm + 24.7).abs() < 0.1, "{evm} dB");
+}
@@ -0,0 +1,340 @@
+//! Checks that the [reports](software_modem::metrics::DemodulationReport) of the symbol, batch and frame APIs
+//! show the impairments of a simulated link in their fields, with the sign and the magnitude of the impairment,
+//! and leave the fields the configuration can not measure unknown.
+
+use realfft::{RealFftPlanner, num_complex::Complex32};
+use software_modem::{
+    channel::{AwgnChannel, Channel, OffsetImpairment},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    error::ModemError,
+    frame::CodingConfig,
+    ofdm::{
+        OFDMConfig, SlmConfig, demodulator::OFDMDemodulator, modulator::OFDMModulator,
+        modulator::OutputScale,
+    },
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// 128 samples of the FFT and 8 of the prefix, 375 Hz apart, clear of the edges a real offset does not reach.
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        guard_subcarriers_low: 2,
+        guard_subcarriers_high: 2,
+        ..Default::default()
+    }
+}
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// Returns the samples through the offsets, the symbols 4 samples late, within the cyclic prefix.
+fn offset(samples: &[f32], cfo_hz: f32, sco_ppm: f32) -> Vec<f32> {
+    let mut channel = OffsetImpairment::new(SAMPLE_RATE, cfo_hz, sco_ppm, 0.0);
+    let mut received = samples.to_vec();
+    received.extend([0.0; 1000]);
+    channel.apply(&mut received);
+    let start = channel.get_delay() as usize - 4;
+    received[start..start + samples.len()].to_vec()
+}
+
+/// Turns every subcarrier of a symbol by the phase, the cyclic prefix with them.
+fn rotate(symbol: &mut [f32], fft_length: usize, phase: f32) {
+    let mut planner = RealFftPlanner::<f32>::new();
+    let prefix = symbol.len() - fft_length;
+    let mut bins = planner.plan_fft_forward(fft_length).make_output_vec();
+    planner
+        .plan_fft_forward(fft_length)
+        .process(&mut symbol[prefix..].to_vec(), &mut bins)
+        .unwrap();
+    for bin in &mut bins[1..fft_length / 2] {
+        *bin *= Complex32::from_polar(1.0 / fft_length as f32, phase);
+    }
+    bins[0] /= fft_length as f32;
+    bins[fft_length / 2] /= fft_length as f32;
+    planner
+        .plan_fft_inverse(fft_length)
+        .process(&mut bins, &mut symbol[prefix..])
+        .unwrap();
+    symbol.copy_within(fft_length.., 0);
+}
+
+#[test]
+fn symbols_report_their_phase() {
+    let modulator = OFDMModulator::new((&config()).into());
+    let demodulator = OFDMDemodulator::new((&config()).into());
+    let payload = data(modulator.get_bytes_per_symbol() as u32);
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    modulator.modulate_buffer_as_symbol(&payload, &mut symbol);
+
+    let (decoded, report) = demodulator.demodulate_symbol_with_report(&symbol);
+    assert_eq!(decoded, payload);
+    assert!(report.snr_db.unwrap() > 60.0, "{report:?}");
+    assert!(report.common_phase_error.unwrap().abs() < 1e-3);
+    assert_eq!(report.subcarrier_snr_db, None);
+
+    for phase in [0.3, -0.2] {
+        let mut rotated = symbol.clone();
+        rotate(&mut rotated, 128, phase);
+        let (_, report) = demodulator.demodulate_symbol_with_report(&rotated);
+        let error = report.common_phase_error.unwrap();
+        assert!((error - phase).abs() < 1e-3, "{error} vs {phase}");
+        // the points turn with the pilots, the magnitude equalizer does not turn them back
+        assert!(report.snr_db.unwrap() < 25.0, "{report:?}");
+    }
+}
+
+#[test]
+fn batches_report_carrier_and_clock_offsets() {
+    let modulator = OFDMModulator::new((&config()).into());
+    let demodulator = OFDMDemodulator::new((&config()).into());
+    let mut samples = Vec::new();
+    let stats = modulator.modulate_batch(
+        &data(74 * modulator.get_bytes_per_symbol() as u32),
+        &mut samples,
+    );
+    assert_eq!(stats.symbols, 74);
+
+    // 3 Hz are 3 / 375 subcarrier spacings
+    for cfo_hz in [3.0, -3.0] {
+        let (stats, report) = demodulator
+            .demodulate_batch_with_report(&offset(&samples, cfo_hz, 0.0), &mut Vec::new());
+        assert_eq!(stats.symbols, 74);
+        let cfo = report.residual_cfo.unwrap();
+        assert!(
+            (cfo / (cfo_hz / 375.0) - 1.0).abs() < 0.1,
+            "{cfo} at {cfo_hz} Hz"
+        );
+        assert!(report.timing_drift.unwrap().abs() < 0.005, "{report:?}");
+    }
+
+    // a receiver clock 200 ppm fast reads 136 * 200e-6 samples more of every symbol,
+    // two samples over the batch, which evens out the ripple of the interpolation of the channel
+    for sco_ppm in [200.0, -200.0] {
+        let (_, report) = demodulator
+            .demodulate_batch_with_report(&offset(&samples, 0.0, sco_ppm), &mut Vec::new());
+        let drift = report.timing_drift.unwrap();
+        let expected = 136.0 * sco_ppm * 1e-6;
+        assert!(
+            (drift / expected - 1.0).abs() < 0.15,
+            "{drift} at {sco_ppm} ppm"
+        );
+        assert!(report.residual_cfo.unwrap().abs() < 0.002, "{report:?}");
+    }
+
+    // the noise of a clean batch is the one of the channel, on every subcarrier
+    let mut noisy = samples.clone();
+    AwgnChannel::new(20.0, 1).apply(&mut noisy);
+    let mut decoded = Vec::new();
+    let (_, report) = demodulator.demodulate_batch_with_report(&noisy, &mut decoded);
+    assert!((report.snr_db.unwrap() - 20.0).abs() < 2.0, "{report:?}");
+    let subcarrier_snr = report.subcarrier_snr_db.unwrap();
+    assert_eq!(subcarrier_snr.len(), 44);
+    assert!(
+        subcarrier_snr.iter().all(|snr| (snr - 20.0).abs() < 4.0),
+        "{subcarrier_snr:?}"
+    );
+    assert!(
+        report.residual_cfo.unwrap().abs() < 0.001 && report.timing_drift.unwrap().abs() < 0.005
+    );
+    assert_eq!((report.crc_ok, report.fec), (None, None));
+}
+
+#[test]
+fn frames_report_their_crc_and_fec() {
+    let ofdm = OFDMConfig {
+        soft_output: true,
+        ..config()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    let payload = data(300);
+    let frame = modulator.encode_frame(&payload);
+    let symbol_length = demodulator.get_symbol_length();
+
+    let (decoded, clean) = demodulator.decode_frame_with_report(&frame);
+    assert_eq!(decoded, Ok(payload.clone()));
+    assert_eq!(clean.crc_ok, Some(true));
+    assert_eq!(clean.fec.as_ref().unwrap().corrected_bits, 0);
+
+    // noise flips coded bits, the more the louder it is
+    let corrected = |snr_db| {
+        let mut received = frame.clone();
+        AwgnChannel::new(snr_db, 1).apply(&mut received);
+        let (decoded, report) = demodulator.decode_frame_with_report(&received);
+        assert_eq!(decoded, Ok(payload.clone()), "at {snr_db} dB");
+        // against the decisions, some of which are wrong at 9 dB
+        assert!((report.snr_db.unwrap() - snr_db).abs() < 2.5, "{report:?}");
+        assert_eq!(report.crc_ok, Some(true));
+        let fec = report.fec.unwrap();
+        assert_eq!(fec.iterations, None);
+        fec.corrected_bits
+    };
+    let (quiet, loud) = (corrected(14.0), corrected(9.0));
+    assert!(0 < quiet && quiet < loud, "{quiet} and {loud}");
+
+    // noise over the payload after the header symbol, the header is read and the CRC fails
+    let mut received = frame.clone();
+    AwgnChannel::new(-6.0, 2).apply(&mut received[symbol_length..]);
+    let (decoded, report) = demodulator.decode_frame_with_report(&received);
+    assert_eq!(decoded, Err(ModemError::CrcMismatch));
+    assert_eq!(report.crc_ok, Some(false));
+    let fec = report.fec.as_ref().unwrap();
+    assert!(
+        fec.corrected_bits > 100 && fec.corrected_bytes.unwrap() > 16,
+        "{fec:?}"
+    );
+    // the nearest points of the noise lie closer to it than the ones sent, but not as close as at 9 dB
+    assert!(report.snr_db.unwrap() < 8.0, "{report:?}");
+
+    // noise without a frame does not get as far as the payload
+    let mut noise = vec![0.0; frame.len()];
+    AwgnChannel::with_reference_power(0.0, 0.01, 3).apply(&mut noise);
+    let (decoded, report) = demodulator.decode_frame_with_report(&noise);
+    assert_eq!(decoded, Err(ModemError::InvalidHeader));
+    assert_eq!((report.crc_ok, report.fec), (None, None));
+    assert!(report.evm.is_some());
+}
+
+#[test]
+fn a_glitch_collapses_the_confidence_of_its_symbol() {
+    let ofdm = OFDMConfig {
+        confidence_symbols: Some(100),
+        ..config()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), CodingConfig::default());
+    let mut frame = modulator.encode_frame(&data(400));
+    AwgnChannel::new(30.0, 1).apply(&mut frame);
+
+    // a single sample of the sixth symbol jumps, after its cyclic prefix
+    let symbol_length = demodulator.get_symbol_length();
+    let peak = frame.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    frame[5 * symbol_length + 40] += 4.0 * peak;
+
+    let (_, report) = demodulator.decode_frame_with_report(&frame);
+    let confidence = report.confidence.unwrap();
+    assert_eq!(confidence.len(), frame.len() / symbol_length);
+    let subcarriers = ofdm.get_data_subcarriers().len();
+    assert!(confidence.iter().all(|symbol| symbol.len() == subcarriers));
+    let mean = |symbol: &[f32]| symbol.iter().sum::<f32>() / symbol.len() as f32;
+    for (index, symbol) in confidence.iter().enumerate() {
+        assert!(symbol.iter().all(|&c| (0.0..=1.0).contains(&c)));
+        if index == 5 {
+            assert!(mean(symbol) < 0.4, "{index}: {}", mean(symbol));
+        } else {
+            assert!(mean(symbol) > 0.8, "{index}: {}", mean(symbol));
+            assert!(symbol.iter().all(|&c| c > 0.5), "{index}: {symbol:?}");
+        }
+    }
+
+    // only the first symbols are kept
+    let capped = CodedOFDMDemodulator::new(
+        OFDMConfig {
+            confidence_symbols: Some(3),
+            ..ofdm.clone()
+        },
+        CodingConfig::default(),
+    );
+    let (_, report) = capped.decode_frame_with_report(&frame);
+    assert_eq!(report.confidence.unwrap(), confidence[..3]);
+
+    // and none by default
+    let (_, report) = CodedOFDMDemodulator::new(config(), CodingConfig::default())
+        .decode_frame_with_report(&frame);
+    assert_eq!(report.confidence, None);
+
+    // a single symbol on its own
+    let modulator = OFDMModulator::new((&ofdm).into());
+    let mut symbol = modulator.modulate_symbol(&data(20)).unwrap();
+    let (_, report) = OFDMDemodulator::new((&ofdm).into()).demodulate_symbol_with_report(&symbol);
+    let clean = report.confidence.unwrap();
+    assert_eq!(clean.len(), 1);
+    assert!(clean[0].iter().all(|&c| c > 0.99), "{clean:?}");
+    symbol[20] = f32::NAN;
+    let (_, report) = OFDMDemodulator::new((&ofdm).into()).demodulate_symbol_with_report(&symbol);
+    assert!(report.confidence.unwrap()[0].iter().all(|&c| c == 0.0));
+}
+
+#[test]
+fn differential_frames_report_a_carrier_offset() {
+    let ofdm = OFDMConfig {
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..config()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    let payload = data(200);
+    let frame = modulator.encode_frame(&payload);
+
+    // the change from symbol to symbol does not see the phase the offset turns the whole frame by
+    for cfo_hz in [2.0, -2.0] {
+        let (decoded, report) = demodulator.decode_frame_with_report(&offset(&frame, cfo_hz, 0.0));
+        assert_eq!(decoded, Ok(payload.clone()));
+        let cfo = report.residual_cfo.unwrap();
+        assert!(
+            (cfo / (cfo_hz / 375.0) - 1.0).abs() < 0.1,
+            "{cfo} at {cfo_hz} Hz"
+        );
+        assert!(report.evm.unwrap().rms_db < -15.0, "{report:?}");
+    }
+}
+
+#[test]
+fn selected_mapping_leaves_the_offsets_unknown() {
+    let ofdm = OFDMConfig {
+        slm: Some(SlmConfig::default()),
+        ..config()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    let (decoded, report) =
+        demodulator.decode_frame_with_report(&modulator.encode_frame(&data(100)));
+    assert_eq!(decoded, Ok(data(100)));
+    assert_eq!(
+        (
+            report.common_phase_error,
+            report.residual_cfo,
+            report.timing_drift
+        ),
+        (None, None, None)
+    );
+    assert!(report.snr_db.unwrap() > 60.0 && report.crc_ok == Some(true));
+}
+
+#[cfg(feature = "ldpc")]
+#[test]
+fn ldpc_frames_report_their_iterations() {
+    use software_modem::fec::{FecScheme, puncture::CodeRate};
+
+    let ofdm = OFDMConfig {
+        soft_output: true,
+        ..config()
+    };
+    let coding = CodingConfig {
+        scheme: FecScheme::Ldpc(CodeRate::Half),
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    let mut frame = modulator.encode_frame(&data(200));
+    AwgnChannel::new(8.0, 1).apply(&mut frame);
+
+    let (decoded, report) = demodulator.decode_frame_with_report(&frame);
+    assert_eq!(decoded, Ok(data(200)));
+    let fec = report.fec.unwrap();
+    // 1632 data bits in 6 code words of 324, each at least one iteration
+    assert!(fec.iterations.unwrap() >= 6, "{fec:?}");
+    assert!(fec.corrected_bits > 0);
+    assert_eq!(fec.corrected_bytes, None);
+}
@@ -0,0 +1,145 @@
+//! Checks that one master seed decides a whole simulated link: two runs through the same channels,
+//! every component seeded from a [SimulationRng](software_modem::rng::SimulationRng), give bit-identical waveforms
+//! and decoded payloads, and the streams of the components are independent of each other.
+
+use software_modem::{
+    channel::{
+        AwgnChannel, BurstNoise, BurstShape, Channel, ChannelChain, IrChannel, QuantizeClip,
+        synthetic_rir,
+    },
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    interleaver::ConvolutionalInterleaver,
+    ofdm::OFDMConfig,
+    rng::SimulationRng,
+    samples::TpdfDither,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Sends a frame of a payload drawn from the master seed through channels seeded from it,
+/// and returns the received samples and the decoded payload, or the error.
+fn run(master_seed: u64) -> (Vec<f32>, Result<Vec<u8>, String>) {
+    let rng = SimulationRng::new(master_seed);
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        differential_time: true,
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        burst_interleaver: Some(ConvolutionalInterleaver::new(16, 16)),
+        ..Default::default()
+    };
+    let mut payload = rng.stream("payload");
+    let payload: Vec<u8> = (0..300).map(|_| payload.below(256) as u8).collect();
+
+    let mut samples = CodedOFDMModulator::new(ofdm.clone(), coding.clone()).encode_frame(&payload);
+    let peak = samples.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    let rms = (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt();
+    let mut channel = ChannelChain::new()
+        .with(IrChannel::from_samples(&synthetic_rir(
+            SAMPLE_RATE,
+            12.0 / SAMPLE_RATE,
+            rng.seed("room"),
+        )))
+        .with(BurstNoise::new(
+            SAMPLE_RATE,
+            5.0,
+            20..60,
+            3.0 * rms,
+            BurstShape::White,
+            rng.seed("bursts"),
+        ))
+        .with(AwgnChannel::with_reference_power(
+            30.0,
+            rms * rms,
+            rng.seed("awgn"),
+        ))
+        .with(QuantizeClip::new(
+            12,
+            2.0 * peak,
+            Some(TpdfDither::new(rng.seed("dither") as u32)),
+        ));
+    // in blocks, as a stream would
+    for block in samples.chunks_mut(1000) {
+        channel.apply(block);
+    }
+
+    let decoded = CodedOFDMDemodulator::new(ofdm, coding)
+        .decode_frame(&samples)
+        .map_err(|error| error.to_string());
+    (samples, decoded)
+}
+
+#[test]
+fn one_master_seed_repeats_the_link() {
+    let (samples, decoded) = run(42);
+    let (again, decoded_again) = run(42);
+    let bits = |samples: &[f32]| samples.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&again), bits(&samples));
+    assert_eq!(decoded_again, decoded);
+    // the payload gets through the channels
+    let mut payload = SimulationRng::new(42).stream("payload");
+    assert_eq!(
+        decoded.unwrap(),
+        (0..300)
+            .map(|_| payload.below(256) as u8)
+            .collect::<Vec<_>>()
+    );
+
+    let (other, _) = run(43);
+    assert_eq!(other.len(), samples.len());
+    assert_ne!(bits(&other), bits(&samples));
+}
+
+#[test]
+fn the_streams_are_independent() {
+    let rng = SimulationRng::new(1);
+    let mut seeds: Vec<u64> = ["awgn", "bursts", "multipath", "room", "dither", "payload"]
+        .iter()
+        .flat_map(|component| (0..1000).map(|index| rng.seed_at(component, index)))
+        .collect();
+    assert_eq!(rng.seed("awgn"), rng.seed_at("awgn", 0));
+    seeds.sort_unstable();
+    seeds.dedup();
+    assert_eq!(seeds.len(), 6000);
+
+    // the Gaussian values of two components, and of the neighbouring master seed, do not correlate
+    let correlation = |mut first: SimulationRng, mut second: SimulationRng| {
+        let length = 100_000;
+        (0..length)
+            .map(|_| first.gaussian() * second.gaussian())
+            .sum::<f64>()
+            / length as f64
+    };
+    for (first, second) in [
+        (rng.stream("awgn"), rng.stream("bursts")),
+        (rng.stream("awgn"), SimulationRng::new(2).stream("awgn")),
+        (
+            SimulationRng::new(rng.seed_at("frame", 0)),
+            SimulationRng::new(rng.seed_at("frame", 1)),
+        ),
+    ] {
+        let correlation = correlation(first, second);
+        // 5 standard deviations of 0.003
+        assert!(correlation.abs() < 0.016, "{correlation}");
+    }
+
+    // the moments of the values
+    let mut values = rng.stream("awgn");
+    let values: Vec<f64> = (0..100_000).map(|_| values.gaussian()).collect();
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let power = values.iter().map(|x| x * x).sum::<f64>() / values.len() as f64;
+    assert!(
+        mean.abs() < 0.016 && (power - 1.0).abs() < 0.03,
+        "{mean} {power}"
+    );
+    let mut uniform = rng.stream("uniform");
+    assert!(
+        (0..100_000)
+            .map(|_| uniform.uniform())
+            .all(|x| x > 0.0 && x <= 1.0)
+    );
+}
@@ -0,0 +1,150 @@
+//! Checks the convolution of the [room impulse response channel](software_modem::channel::IrChannel)
+//! against a plain convolution, over buffers of any length, and coded frames across rooms:
+//! reverberation within the cyclic prefix is equalized, longer reverberation needs a longer prefix.
+//!
+//! Reading the response from a WAV file needs the `wav` feature: `cargo test --features wav`.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, IrChannel, MultipathChannel, synthetic_rir},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Returns the full convolution of the signal and the response, cut to the length of the signal.
+fn convolve(signal: &[f32], response: &[f32]) -> Vec<f32> {
+    (0..signal.len())
+        .map(|n| {
+            (0..response.len().min(n + 1))
+                .map(|k| f64::from(response[k]) * f64::from(signal[n - k]))
+                .sum::<f64>() as f32
+        })
+        .collect()
+}
+
+fn assert_close(samples: &[f32], expected: &[f32]) {
+    assert_eq!(samples.len(), expected.len());
+    for (i, (x, y)) in samples.iter().zip(expected).enumerate() {
+        assert!((x - y).abs() < 1e-4, "{i}: {x} against {y}");
+    }
+}
+
+#[test]
+fn buffers_continue_each_other() {
+    let signal: Vec<f32> = (0..5000).map(|i| (i as f32 * 0.37).sin()).collect();
+    // a response convolved directly, and one by FFTs of 1024 samples, 501 inputs at a time
+    for length in [40, 500] {
+        let response = synthetic_rir(SAMPLE_RATE, (length - 1) as f32 / SAMPLE_RATE, 3);
+        assert_eq!(response.len(), length);
+        let expected = convolve(&signal, &response);
+
+        let mut channel = IrChannel::from_samples(&response);
+        assert_eq!(channel.get_impulse_response(), response);
+        for block_length in [1, 7, 501, 1500, 5000] {
+            channel.reset();
+            let mut samples = signal.clone();
+            for block in samples.chunks_mut(block_length) {
+                channel.apply(block);
+            }
+            assert_close(&samples, &expected);
+        }
+
+        // complex samples see the response on each of their components
+        channel.reset();
+        let mut samples: Vec<Complex32> = signal
+            .iter()
+            .map(|&x| Complex32::new(x, -2.0 * x))
+            .collect();
+        for block in samples.chunks_mut(333) {
+            channel.apply_complex(block);
+        }
+        let re: Vec<f32> = samples.iter().map(|x| x.re).collect();
+        let im: Vec<f32> = samples.iter().map(|x| -0.5 * x.im).collect();
+        assert_close(&re, &expected);
+        assert_close(&im, &expected);
+    }
+
+    // a short response is a multipath channel with a tap on every sample
+    let response = [0.8, 0.0, -0.3, 0.1];
+    let mut multipath = MultipathChannel::new(
+        &response
+            .iter()
+            .enumerate()
+            .map(|(delay, &gain)| (delay, Complex32::new(gain, 0.0)))
+            .collect::<Vec<_>>(),
+    );
+    let (mut samples, mut expected) = (signal.clone(), signal.clone());
+    IrChannel::from_samples(&response).apply(&mut samples);
+    multipath.apply(&mut expected);
+    assert_eq!(samples, expected);
+}
+
+/// Returns the number of 10 frames which are lost across the room with the reverberation time, in samples.
+fn lost_frames(cyclic_prefix_length: u32, reverberation: u32) -> usize {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length,
+        differential_time: true,
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    let payload: Vec<u8> = (0..400u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    (0..10)
+        .filter(|&seed| {
+            let rir = synthetic_rir(SAMPLE_RATE, reverberation as f32 / SAMPLE_RATE, seed);
+            let mut channel = ChannelChain::new()
+                .with(IrChannel::from_samples(&rir))
+                .with(AwgnChannel::new(30.0, seed));
+            let mut frame = modulator.encode_frame(&payload);
+            channel.apply(&mut frame);
+            demodulator.decode_frame(&frame).ok().as_deref() != Some(&payload[..])
+        })
+        .count()
+}
+
+#[test]
+fn reverberation_within_the_prefix_is_equalized() {
+    // the response fits into the cyclic prefix
+    assert_eq!(lost_frames(16, 16), 0);
+    assert_eq!(lost_frames(128, 128), 0);
+    // the reverberation decays by 60 dB over its length, so a response a few times the prefix long still decodes
+    assert_eq!(lost_frames(16, 128), 0);
+    // but a longer one leaks every symbol into the next, which the equalizer can not undo
+    assert_eq!(lost_frames(16, 512), 10);
+    // unless the prefix holds most of its energy
+    assert!(lost_frames(128, 512) <= 2);
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn responses_are_read_from_wav_files() {
+    use software_modem::io::{WavError, write_wav};
+
+    let path = std::env::temp_dir().join("software_modem_test_room.wav");
+    let response = synthetic_rir(SAMPLE_RATE, 0.01, 1);
+    write_wav(&path, &response, 48000).unwrap();
+    let (channel, sample_rate) = IrChannel::from_wav(&path).unwrap();
+    assert_eq!(sample_rate, 48000);
+    // within the resolution of 16 bits
+    assert_eq!(channel.get_impulse_response().len(), response.len());
+    for (x, y) in channel.get_impulse_response().iter().zip(&response) {
+        assert!((x - y).abs() < 1e-4);
+    }
+
+    write_wav(&path, &[], 48000).unwrap();
+    assert!(matches!(
+        IrChannel::from_wav(&path),
+        Err(WavError::InvalidFile)
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,158 @@
+//! Plants frames at random offsets in a long recording of noise, one of them corrupted,
+//! and checks that a [Receiver] finds each of them once, at the offset it starts at, with the right outcome.
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    error::ModemError,
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    rng::SimulationRng,
+    scan::{Receiver, ScanConfig},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(differential_time: bool) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        differential_time,
+        ..Default::default()
+    }
+}
+
+/// Plants a frame of every payload at a random offset of its own stretch of noise, the frame at `corrupted`
+/// with the signs of two of its payload symbols of 144 samples flipped, in noise at the SNR,
+/// and returns the recording with the offsets of the frames.
+fn recording(
+    config: &OFDMConfig,
+    payloads: &[Vec<u8>],
+    corrupted: usize,
+    snr_db: f32,
+) -> (Vec<f32>, Vec<usize>) {
+    let modulator = CodedOFDMModulator::new(config.clone(), CodingConfig::default());
+    let mut rng = SimulationRng::new(218);
+    let mut samples = Vec::new();
+    let mut offsets = Vec::new();
+    for (index, payload) in payloads.iter().enumerate() {
+        samples.resize(samples.len() + 2000 + rng.below(8000) as usize, 0.0);
+        offsets.push(samples.len());
+        let mut frame = modulator.encode_frame(payload);
+        if index == corrupted {
+            frame[3 * 144..5 * 144].iter_mut().for_each(|x| *x = -*x);
+        }
+        samples.extend(frame);
+    }
+    samples.resize(samples.len() + 3000, 0.0);
+    let power = samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32;
+    AwgnChannel::with_reference_power(snr_db, power * 4.0, 7).apply(&mut samples);
+    (samples, offsets)
+}
+
+/// Scans a recording of three frames, the second one corrupted, at the SNR, and checks that every frame is found once
+/// at most `early` samples before it starts.
+fn check(config: OFDMConfig, scan: ScanConfig, snr_db: f32, early: usize) {
+    let payloads = [data(300), data(120), data(500)];
+    let (samples, offsets) = recording(&config, &payloads, 1, snr_db);
+    let receiver = Receiver::new(
+        CodedOFDMDemodulator::new(config, CodingConfig::default()),
+        scan,
+    );
+
+    let hits = receiver.scan(&samples);
+    assert_eq!(
+        hits.len(),
+        3,
+        "{:?}",
+        hits.iter()
+            .map(|hit| (hit.offset, &hit.payload))
+            .collect::<Vec<_>>()
+    );
+    for (index, hit) in hits.iter().enumerate() {
+        assert!(
+            hit.offset <= offsets[index] && hit.offset + early >= offsets[index],
+            "frame {index} at {} instead of {}",
+            hit.offset,
+            offsets[index]
+        );
+        if index == 1 {
+            assert_eq!(hit.payload, Err(ModemError::CrcMismatch));
+            assert_eq!(hit.report.crc_ok, Some(false));
+        } else {
+            assert_eq!(hit.payload.as_deref(), Ok(&payloads[index][..]));
+            assert_eq!(hit.report.crc_ok, Some(true));
+        }
+    }
+}
+
+#[test]
+fn finds_every_frame_once() {
+    check(config(false), ScanConfig::default(), 20.0, 0);
+}
+
+#[test]
+fn finds_differential_frames_with_a_stride() {
+    // the products of the differential symbols double the noise, and their headers decode up to the prefix early
+    check(
+        config(true),
+        ScanConfig {
+            stride: 8,
+            ..Default::default()
+        },
+        30.0,
+        16,
+    );
+}
+
+#[test]
+fn noise_has_no_frames() {
+    let mut samples = vec![0.0; 20000];
+    AwgnChannel::with_reference_power(0.0, 1.0, 3).apply(&mut samples);
+    for differential_time in [false, true] {
+        let receiver = Receiver::new(
+            CodedOFDMDemodulator::new(config(differential_time), CodingConfig::default()),
+            ScanConfig::default(),
+        );
+        assert_eq!(
+            receiver.scan(&samples),
+            [],
+            "differential {differential_time}"
+        );
+    }
+}
+
+#[test]
+fn frame_cut_off_by_the_end() {
+    let modulator = CodedOFDMModulator::new(config(false), CodingConfig::default());
+    let mut samples = vec![0.0; 500];
+    samples.extend(modulator.encode_frame(&data(300)));
+    samples.truncate(500 + 4 * 144);
+    let receiver = Receiver::new(
+        CodedOFDMDemodulator::new(config(false), CodingConfig::default()),
+        ScanConfig::default(),
+    );
+    let hits = receiver.scan(&samples);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].offset, 500);
+    assert!(matches!(
+        hits[0].payload,
+        Err(ModemError::FrameTooShort { .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Stride must be at least 1, but got 0")]
+fn zero_stride() {
+    Receiver::new(
+        CodedOFDMDemodulator::new(config(false), CodingConfig::default()),
+        ScanConfig {
+            stride: 0,
+            ..Default::default()
+        },
+    );
+}
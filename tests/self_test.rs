@@ -0,0 +1,165 @@
+//! Runs the [self_test](software_modem::coded::self_test) over a grid of configurations as a smoke test,
+//! and checks that it fails over a channel the frame can not get through and with mismatched configurations.
+//!
+//! The header of a frame announces its scheme, the outer code and the scrambling, so only a mismatch of the rest fails.
+
+use software_modem::{
+    channel::{AwgnChannel, ChannelChain, MultipathChannel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator, SELF_TEST_PAYLOAD_LENGTH, self_test},
+    dsp::{FirFilter, Passband},
+    fec::{FecScheme, hamming::HammingCode, puncture::CodeRate},
+    frame::{CodingConfig, HeaderCode, Interleaving},
+    interleaver::ConvolutionalInterleaver,
+    ofdm::OFDMConfig,
+};
+
+fn ofdm_configs() -> Vec<OFDMConfig> {
+    let plain = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    vec![
+        plain.clone(),
+        OFDMConfig {
+            num_subcarriers: 256,
+            cyclic_prefix_length: 32,
+            pilot_subcarrier_every: 8,
+            roll_off: 8,
+            ..plain.clone()
+        },
+        OFDMConfig {
+            differential_time: true,
+            soft_output: false,
+            ..plain.clone()
+        },
+        OFDMConfig {
+            oversampling: 2,
+            guard_subcarriers_low: 4,
+            guard_subcarriers_high: 8,
+            rx_filter: Some(FirFilter::lowpass(0.25, 31)),
+            passband: Some(Passband {
+                sample_rate: 48000.0,
+                carrier_hz: 12000.0,
+            }),
+            ..plain
+        },
+    ]
+}
+
+fn coding_configs() -> Vec<CodingConfig> {
+    vec![
+        CodingConfig::default(),
+        CodingConfig {
+            scheme: FecScheme::Convolutional(CodeRate::ThreeQuarters),
+            reed_solomon: true,
+            erasure_threshold: Some(3.0),
+            ..Default::default()
+        },
+        CodingConfig {
+            header_code: HeaderCode::Hamming(HammingCode::ExtendedHamming84),
+            scheme: FecScheme::Repetition(3),
+            interleaving: Interleaving::None,
+            scrambler: None,
+            ..Default::default()
+        },
+        CodingConfig {
+            burst_interleaver: Some(ConvolutionalInterleaver::new(4, 3)),
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn every_configuration_passes() {
+    for ofdm in ofdm_configs() {
+        for coding in coding_configs() {
+            let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+            let demodulator = CodedOFDMDemodulator::new(ofdm.clone(), coding.clone());
+            let report = self_test(&modulator, &demodulator, None);
+            assert!(report.passed, "{report:?} {ofdm:?} {coding:?}");
+            assert_eq!((&report.error, report.byte_errors), (&None, 0));
+            // the band edges of the passband filters cost the most
+            assert!(report.evm.unwrap().rms_db < -20.0, "{report:?} {ofdm:?}");
+            assert!(report.papr_db > 3.0 && report.papr_db < 20.0, "{report:?}");
+
+            // the same every time
+            assert_eq!(self_test(&modulator, &demodulator, None), report);
+        }
+    }
+}
+
+#[test]
+fn channels_degrade_the_report() {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+    let clean = self_test(&modulator, &demodulator, None);
+
+    // multipath within the cyclic prefix and noise, which the code corrects
+    let mut channel = ChannelChain::new()
+        .with(MultipathChannel::new(&[(0, 1.0.into()), (1, 0.2.into())]))
+        .with(AwgnChannel::new(20.0, 7));
+    let report = self_test(&modulator, &demodulator, Some(&mut channel));
+    assert!(report.passed, "{report:?}");
+    let evm = report.evm.unwrap().rms_db;
+    assert!(evm > -20.0 && evm < -10.0, "{report:?}");
+    // the PAPR is the one of the frame as sent
+    assert_eq!(report.papr_db, clean.papr_db);
+
+    // noise far above the signal loses the frame
+    let report = self_test(
+        &modulator,
+        &demodulator,
+        Some(&mut AwgnChannel::new(-10.0, 1)),
+    );
+    assert!(!report.passed && report.error.is_some(), "{report:?}");
+    assert_eq!(report.byte_errors, SELF_TEST_PAYLOAD_LENGTH);
+    // against the decisions, which the noise moves too
+    assert!(report.evm.unwrap().rms_db > -8.0, "{report:?}");
+}
+
+#[test]
+fn mismatched_configurations_fail() {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+    for (receiver, coding) in [
+        (
+            OFDMConfig {
+                differential_time: true,
+                ..ofdm.clone()
+            },
+            CodingConfig::default(),
+        ),
+        (
+            ofdm.clone(),
+            CodingConfig {
+                header_code: HeaderCode::Hamming(HammingCode::Hamming74),
+                ..Default::default()
+            },
+        ),
+        (
+            OFDMConfig {
+                cyclic_prefix_length: 4,
+                ..ofdm.clone()
+            },
+            CodingConfig::default(),
+        ),
+    ] {
+        let report = self_test(
+            &modulator,
+            &CodedOFDMDemodulator::new(receiver.clone(), coding.clone()),
+            None,
+        );
+        assert!(!report.passed, "{receiver:?} {coding:?}");
+        assert_eq!(report.byte_errors, SELF_TEST_PAYLOAD_LENGTH);
+    }
+}
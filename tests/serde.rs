@@ -0,0 +1,167 @@
+//! Checks that a [CodingConfig] and a [DemodulationReport] survive a round trip through `serde`,
+//! and that invalid parameters of a configuration are rejected.
+//!
+//! The test needs the `serde` feature: `cargo test --features serde`.
+
+#![cfg(feature = "serde")]
+
+use software_modem::{
+    channel::{AwgnChannel, Channel, IqImbalance},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    fec::{FecScheme, convolutional::ConvolutionalCode, hamming::HammingCode, puncture::CodeRate},
+    frame::{CodingConfig, HeaderCode, Interleaving},
+    interleaver::{ConvolutionalInterleaver, Interleaver},
+    metrics::DemodulationReport,
+    ofdm::OFDMConfig,
+    scrambler::Scrambler,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn configs() -> Vec<CodingConfig> {
+    vec![
+        CodingConfig::default(),
+        CodingConfig {
+            code: ConvolutionalCode::new(3, &[0b111, 0b101]),
+            scheme: FecScheme::Convolutional(CodeRate::ThreeQuarters),
+            reed_solomon: true,
+            header_code: HeaderCode::Hamming(HammingCode::ExtendedHamming84),
+            interleaving: Interleaving::Block(Interleaver::new(8, 24)),
+            scrambler: Some(Scrambler::new(0b1100_0000_0000_0001, 0x1234)),
+            burst_interleaver: Some(ConvolutionalInterleaver::new(12, 4)),
+            erasure_threshold: Some(3.5),
+            repeat_frames: 3,
+        },
+        CodingConfig {
+            scheme: FecScheme::RepeatedConvolutional(4),
+            header_code: HeaderCode::RepeatedConvolutional(4),
+            interleaving: Interleaving::None,
+            scrambler: None,
+            ..Default::default()
+        },
+        CodingConfig {
+            scheme: FecScheme::Repetition(3),
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn configs_round_trip() {
+    for config in configs() {
+        let json = serde_json::to_string(&config).unwrap();
+        let read: CodingConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, config, "{json}");
+        // the same as the bytes of the configuration
+        assert_eq!(read.to_bytes(), config.to_bytes());
+    }
+}
+
+#[test]
+fn both_ends_share_the_config() {
+    let config = configs().swap_remove(1);
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&config).unwrap();
+
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), config);
+    let demodulator = CodedOFDMDemodulator::new(ofdm, serde_json::from_str(&json).unwrap());
+    let payload = data(300);
+    let samples = modulator.encode_frame(&payload);
+    assert_eq!(demodulator.decode_frame(&samples).unwrap(), payload);
+}
+
+#[test]
+fn the_code_is_written_without_its_trellis() {
+    let json = serde_json::to_value(ConvolutionalCode::k7_rate_half()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"constraint_length": 7, "polynomials": [0o171, 0o133]})
+    );
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    let error = |json: &str| {
+        let mut config = serde_json::to_value(CodingConfig::default()).unwrap();
+        let (key, value) = json.split_once('=').unwrap();
+        config[key] = serde_json::from_str(value).unwrap();
+        serde_json::from_value::<CodingConfig>(config)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert_eq!(
+        error(r#"code={"constraint_length": 1, "polynomials": [3]}"#),
+        "Constraint length must be between 2 and 16, but got 1"
+    );
+    assert_eq!(
+        error(r#"code={"constraint_length": 7, "polynomials": []}"#),
+        "Between 1 and 8 polynomials are supported, but got 0"
+    );
+    assert_eq!(
+        error(r#"scrambler={"polynomial": 145, "seed": 0}"#),
+        "Seed must be a non-zero value of 7 bits, but got 0x0"
+    );
+    assert_eq!(
+        error(r#"scrambler={"polynomial": 1, "seed": 1}"#),
+        "Polynomial degree must be between 2 and 31, but got 0"
+    );
+    assert_eq!(
+        error(r#"interleaving={"Block": {"rows": 0, "cols": 16}}"#),
+        "Interleaver dimensions must not be zero, but got 0 rows and 16 cols"
+    );
+    assert_eq!(
+        error(r#"burst_interleaver={"branches": 12, "depth": 0}"#),
+        "Interleaver dimensions must not be zero, but got 12 branches and a depth of 0"
+    );
+    assert!(error(r#"scheme={"Turbo": 3}"#).contains("unknown variant `Turbo`"));
+}
+
+#[test]
+fn reports_round_trip() {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        soft_output: true,
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    let payload = data(300);
+    let mut samples = modulator.encode_frame(&payload);
+    // noise, so the EVM in dB is finite, which JSON needs
+    AwgnChannel::new(12.0, 1).apply(&mut samples);
+
+    let (decoded, mut report) = demodulator.decode_frame_with_report(&samples);
+    assert_eq!(decoded, Ok(payload));
+    report.iq_imbalance = Some(IqImbalance {
+        gain_db: 0.5,
+        phase_deg: -2.0,
+    });
+    assert!(report.evm.is_some() && report.subcarrier_snr_db.is_some());
+    assert!(report.fec.as_ref().unwrap().corrected_bytes.is_some());
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(
+        serde_json::from_str::<DemodulationReport>(&json).unwrap(),
+        report
+    );
+    // and the unknown fields
+    let json = serde_json::to_string(&DemodulationReport::default()).unwrap();
+    assert_eq!(
+        serde_json::from_str::<DemodulationReport>(&json).unwrap(),
+        DemodulationReport::default()
+    );
+}
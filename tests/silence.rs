@@ -0,0 +1,59 @@
+//! Checks that the header CRC rejects the headers of silence and of a stuck receiver, whose bits are all
+//! zeros or all ones, for every header code, and that a frame of zero samples does not decode.
+
+use software_modem::{
+    bits::bits_to_llrs,
+    coded::CodedOFDMDemodulator,
+    error::ModemError,
+    fec::hamming::HammingCode,
+    frame::{CodingConfig, HeaderCode},
+    ofdm::OFDMConfig,
+};
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn headers_of_constant_bits_are_rejected() {
+    for header_code in [
+        HeaderCode::Convolutional,
+        HeaderCode::Hamming(HammingCode::Hamming74),
+        HeaderCode::Hamming(HammingCode::ExtendedHamming84),
+        HeaderCode::RepeatedConvolutional(2),
+    ] {
+        for repeat_frames in [1, 3] {
+            let coding = CodingConfig {
+                header_code,
+                repeat_frames,
+                ..Default::default()
+            };
+            let demodulator = CodedOFDMDemodulator::new(ofdm(), coding);
+            // the bits of 20 symbols
+            let samples = vec![0.0; 20 * demodulator.get_symbol_length()];
+            let length = demodulator.demodulate_llrs(&samples).len();
+            for bit in [0, 1] {
+                let llrs = bits_to_llrs(&vec![bit; length]);
+                assert_eq!(
+                    demodulator.decode_repetition(&llrs),
+                    Err(ModemError::InvalidHeader),
+                    "{header_code:?} with {repeat_frames} copies, bits of {bit}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn zero_samples_do_not_decode() {
+    let demodulator = CodedOFDMDemodulator::new(ofdm(), CodingConfig::default());
+    let samples = vec![0.0; 20 * demodulator.get_symbol_length()];
+    assert_eq!(
+        demodulator.decode_frame(&samples),
+        Err(ModemError::InvalidHeader)
+    );
+}
@@ -0,0 +1,167 @@
+//! Sends frames over the [single carrier](software_modem::phy::single_carrier) modem through the frame layer,
+//! clean and over white noise with an offset of the sample clock, and with a convolutional code on top.
+
+use software_modem::{
+    bits::{bits_to_bytes, bytes_to_bits},
+    channel::{AwgnChannel, Channel, OffsetImpairment},
+    error::ModemError,
+    fec::convolutional::ConvolutionalCode,
+    frame::{FrameDecoder, FrameEncoder},
+    phy::{
+        PhyDemodulator, PhyModulator,
+        single_carrier::{
+            Constellation, SingleCarrierConfig, SingleCarrierDemodulator, SingleCarrierModulator,
+        },
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(constellation: Constellation) -> SingleCarrierConfig {
+    SingleCarrierConfig {
+        constellation,
+        ..Default::default()
+    }
+}
+
+/// Passes a frame to a receiver whose clock runs `sco_ppm` off, with noise `snr_db` below the power of the frame,
+/// and returns as many samples as the frame has from its start.
+fn impair(frame: &[f32], sample_rate: f32, sco_ppm: f32, snr_db: f32, seed: u64) -> Vec<f32> {
+    let mut offset = OffsetImpairment::new(sample_rate, 0.0, sco_ppm, 0.0);
+    let delay = offset.get_delay() as usize;
+    let mut samples = frame.to_vec();
+    samples.resize(frame.len() + 2 * delay, 0.0);
+    offset.apply(&mut samples);
+
+    let mut received = samples[delay..delay + frame.len()].to_vec();
+    let power = frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32;
+    AwgnChannel::with_reference_power(snr_db, power, seed).apply(&mut received);
+    received
+}
+
+#[test]
+fn clean_frames() {
+    for constellation in [Constellation::Qpsk, Constellation::Qam16] {
+        let encoder = FrameEncoder::new(SingleCarrierModulator::new(config(constellation)));
+        let decoder = FrameDecoder::new(SingleCarrierDemodulator::new(config(constellation)));
+        let bytes_per_block = encoder.get_bytes_per_symbol();
+        assert_eq!(
+            bytes_per_block,
+            64 * constellation.bits_per_symbol() as usize / 8
+        );
+        for length in [0, 1, bytes_per_block, 5 * bytes_per_block + 3] {
+            let payload = data(length as u32);
+            let samples = encoder.encode(&payload);
+            assert_eq!(samples.len(), encoder.get_frame_length(length));
+            assert_eq!(samples.len(), decoder.get_frame_length(length));
+            // the preamble, the blocks and the tails of the pulses, 8 samples per symbol
+            assert_eq!(
+                samples.len(),
+                (64 + length.div_ceil(bytes_per_block) * 64 + 10) * 8
+            );
+
+            let decoded = decoder.decode(&samples);
+            assert_eq!(
+                decoded.len(),
+                length.div_ceil(bytes_per_block) * bytes_per_block
+            );
+            assert_eq!(decoded[..length], payload);
+            assert!(decoded[length..].iter().all(|&byte| byte == 0));
+        }
+    }
+}
+
+#[test]
+fn awgn_with_clock_offset() {
+    for (constellation, snr_db) in [(Constellation::Qpsk, 10.0), (Constellation::Qam16, 20.0)] {
+        let config = config(constellation);
+        let demodulator = SingleCarrierDemodulator::new(config.clone());
+        let payload = data(2000);
+        let frame = FrameEncoder::new(SingleCarrierModulator::new(config.clone())).encode(&payload);
+        for (seed, sco_ppm) in [(1, 0.0), (2, 5.0), (3, -20.0), (4, 200.0)] {
+            let received = impair(&frame, config.sample_rate, sco_ppm, snr_db, seed);
+            let decoded = FrameDecoder::new(demodulator.clone()).decode(&received);
+            assert_eq!(decoded[..2000], payload, "{constellation:?} {sco_ppm} ppm");
+
+            // the timing loop follows the clock, 200 ppm drift by 6.4 samples over the 4000 symbols of QAM-16
+            let recovery = demodulator.recover(&received);
+            assert!(
+                (recovery.clock_offset_ppm - sco_ppm).abs() < 10.0,
+                "{constellation:?} {sco_ppm} ppm: {}",
+                recovery.clock_offset_ppm
+            );
+        }
+    }
+}
+
+#[test]
+fn convolutional_code_on_top() {
+    let config = config(Constellation::Qpsk);
+    let encoder = FrameEncoder::new(SingleCarrierModulator::new(config.clone()));
+    let decoder = FrameDecoder::new(SingleCarrierDemodulator::new(config.clone()));
+    let code = ConvolutionalCode::k7_rate_half();
+
+    let payload = data(500);
+    let coded = code.encode(&bytes_to_bits(&payload));
+    let frame = encoder.encode(&bits_to_bytes(&coded));
+    let received = impair(&frame, config.sample_rate, 20.0, 3.0, 7);
+
+    let mut bits = bytes_to_bits(&decoder.decode(&received));
+    bits.truncate(coded.len());
+    let flipped = bits.iter().zip(&coded).filter(|(a, b)| a != b).count();
+    assert!(flipped > 0);
+    assert_eq!(bits_to_bytes(&code.decode(&bits)), payload);
+}
+
+#[test]
+fn blocks_on_their_own() {
+    for constellation in [Constellation::Qpsk, Constellation::Qam16] {
+        let modulator = SingleCarrierModulator::new(config(constellation));
+        let demodulator = SingleCarrierDemodulator::new(config(constellation));
+        let capacity = PhyModulator::capacity_bytes(&modulator);
+        let payload = data(capacity as u32);
+
+        let mut block = vec![0.0; PhyModulator::samples_per_block(&modulator)];
+        assert_eq!(block.len(), 512);
+        modulator.modulate(&payload, &mut block).unwrap();
+        let mut decoded = vec![0; capacity];
+        demodulator.demodulate(&block, &mut decoded).unwrap();
+        assert_eq!(decoded, payload);
+
+        // the samples have the RMS of the amplitude
+        let rms = (block.iter().map(|x| x * x).sum::<f32>() / block.len() as f32).sqrt();
+        assert!((rms / (0.25 / 2f32.sqrt()) - 1.0).abs() < 0.1, "{rms}");
+
+        assert_eq!(
+            demodulator.demodulate(&block[1..], &mut decoded),
+            Err(ModemError::BufferLength {
+                expected: 512,
+                got: 511
+            })
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "Band must be between 0 and 4000 Hz, but got 3325 to 4675 Hz")]
+fn band_must_fit() {
+    SingleCarrierModulator::new(SingleCarrierConfig {
+        carrier_hz: 4000.0,
+        ..Default::default()
+    });
+}
+
+#[test]
+#[should_panic(
+    expected = "Sample rate must be a whole multiple of at least 2 times the symbol rate, but got 8000 and 1200"
+)]
+fn whole_samples_per_symbol() {
+    SingleCarrierDemodulator::new(SingleCarrierConfig {
+        symbol_rate: 1200.0,
+        ..Default::default()
+    });
+}
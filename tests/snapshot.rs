@@ -0,0 +1,134 @@
+//! Checks that the [snapshot hook](software_modem::stream::StreamDemodulator::set_snapshot_hook) of a stream
+//! sees every symbol it asks for across frames and blocks, without changing what the stream decodes.
+
+use std::sync::{Arc, Mutex, mpsc};
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    stream::{ConstellationSnapshot, StreamDemodulator},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+fn stream() -> StreamDemodulator {
+    StreamDemodulator::new(
+        CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+        0.01,
+        1000,
+    )
+}
+
+/// Returns the frames of the payloads through a noisy channel, after a silence and each followed by one.
+fn capture(payloads: &[Vec<u8>]) -> Vec<f32> {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let mut samples = vec![0.0; 1000];
+    for (seed, payload) in payloads.iter().enumerate() {
+        let mut frame = modulator.encode_frame(payload);
+        AwgnChannel::new(20.0, seed as u64).apply(&mut frame);
+        samples.extend(frame);
+        samples.extend([0.0; 2000]);
+    }
+    samples
+}
+
+fn decode(stream: &mut StreamDemodulator, samples: &[f32]) -> Vec<Vec<u8>> {
+    samples
+        .chunks(300)
+        .flat_map(|block| stream.push(block))
+        .collect()
+}
+
+#[test]
+fn snapshots_follow_the_decimation() {
+    let payloads = [data(300), data(7), data(150)];
+    let samples = capture(&payloads);
+
+    let mut plain = stream();
+    let decoded = decode(&mut plain, &samples);
+    assert_eq!(decoded, payloads);
+
+    // a header symbol and the payload, 24 bytes a symbol with the rate 1/2 code on 48 subcarriers of 4 bits
+    let symbols = [27, 2, 14];
+    for every in [1, 3, 8] {
+        let snapshots = Arc::new(Mutex::new(Vec::<ConstellationSnapshot>::new()));
+        let mut stream = stream();
+        let kept = snapshots.clone();
+        stream.set_snapshot_hook(
+            Some(Arc::new(move |snapshot| {
+                kept.lock().unwrap().push(snapshot.clone())
+            })),
+            every,
+        );
+        assert_eq!(decode(&mut stream, &samples), decoded);
+
+        let snapshots = snapshots.lock().unwrap();
+        let total: u64 = symbols.iter().sum();
+        let indices: Vec<_> = snapshots
+            .iter()
+            .map(|snapshot| snapshot.symbol_index)
+            .collect();
+        assert_eq!(indices, (0..total).step_by(every).collect::<Vec<_>>());
+        for snapshot in snapshots.iter() {
+            assert_eq!(snapshot.points.len(), 48);
+            let evm = snapshot.evm.unwrap().rms_db;
+            // about the SNR of the channel, which counts the power of every subcarrier
+            assert!(
+                -26.0 < evm && evm < -14.0,
+                "{evm} dB at {}",
+                snapshot.symbol_index
+            );
+        }
+    }
+}
+
+#[test]
+fn slow_displays_drop_snapshots() {
+    let payloads = [data(300), data(300)];
+    let samples = capture(&payloads);
+    let (sender, receiver) = mpsc::sync_channel(4);
+    let mut stream = stream();
+    stream.set_snapshot_hook(
+        Some(Arc::new(move |snapshot: &ConstellationSnapshot| {
+            let _ = sender.try_send(snapshot.symbol_index);
+        })),
+        1,
+    );
+
+    // nothing takes the snapshots of the first frame until the second one has decoded
+    assert_eq!(decode(&mut stream, &samples), payloads);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+    // without the hook, the symbols are still counted, 27 of every frame
+    stream.set_snapshot_hook(None, 1);
+    decode(&mut stream, &samples);
+    let (sender, receiver) = mpsc::sync_channel(1);
+    stream.set_snapshot_hook(
+        Some(Arc::new(move |snapshot: &ConstellationSnapshot| {
+            let _ = sender.try_send(snapshot.symbol_index);
+        })),
+        5,
+    );
+    decode(&mut stream, &samples);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [110]);
+}
+
+#[test]
+#[should_panic(expected = "Snapshot every must be at least 1, but got 0")]
+fn snapshots_are_taken_at_least_every_symbol() {
+    stream().set_snapshot_hook(None, 0);
+}
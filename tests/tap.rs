@@ -0,0 +1,201 @@
+//! Checks that the [debug tap](software_modem::tap) of a stream sees every stage of every burst,
+//! and that the [FileStageSink] writes them to the files it names.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    rng::SimulationRng,
+    stream::StreamDemodulator,
+    tap::{FileStageSink, StageSink},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    }
+}
+
+fn stream() -> StreamDemodulator {
+    StreamDemodulator::new(
+        CodedOFDMDemodulator::new(config(), CodingConfig::default()),
+        0.01,
+        1000,
+    )
+}
+
+/// Returns the frames of the payloads, after a silence and each followed by one.
+fn capture(payloads: &[Vec<u8>]) -> Vec<f32> {
+    let modulator = CodedOFDMModulator::new(config(), CodingConfig::default());
+    let mut samples = vec![0.0; 1000];
+    for payload in payloads {
+        samples.extend(modulator.encode_frame(payload));
+        samples.extend([0.0; 2000]);
+    }
+    samples
+}
+
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+fn every_stage_is_written() {
+    let payloads = [data(100), data(7)];
+    let samples = capture(&payloads);
+    let directory = directory("software_modem_test_tap");
+
+    let mut stream = stream();
+    stream.set_debug_tap(Some(Box::new(FileStageSink::new(&directory))));
+    let decoded: Vec<_> = samples
+        .chunks(300)
+        .flat_map(|block| stream.push(block))
+        .collect();
+    assert_eq!(decoded, payloads);
+    assert!(stream.set_debug_tap(None).is_some());
+
+    let read = |name: String| fs::read(directory.join(&name)).unwrap_or_else(|_| panic!("{name}"));
+    let rows = |name: String| String::from_utf8(read(name)).unwrap().lines().count() - 1;
+    // a header symbol and the payload, 24 bytes a symbol with the rate 1/2 code on 48 subcarriers of 4 bits
+    for (frame, (symbols, bytes)) in [(10, 100), (2, 7)].into_iter().enumerate() {
+        for symbol in 0..symbols {
+            let name = |stage: &str| format!("frame{frame:04}_symbol{symbol:04}_{stage}");
+            assert_eq!(read(name("raw.f32")).len(), 136 * 4);
+            assert_eq!(read(name("corrected.f32")).len(), 136 * 4);
+            assert_eq!(rows(name("bins.csv")), 65);
+            assert_eq!(rows(name("channel.csv")), 15);
+            assert_eq!(rows(name("points.csv")), 48);
+            assert_eq!(rows(name("llrs.csv")), 48 * 4);
+        }
+        // the payload and its CRC, still scrambled
+        let bits = read(format!("frame{frame:04}_fec_bits.u8"));
+        assert_eq!(bits.len(), 8 * (bytes + 4));
+        assert!(bits.iter().all(|&bit| bit <= 1));
+    }
+    // and the symbols of the silence after the frame, up to the end of the burst, with every stage
+    let names: Vec<String> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    for (frame, frame_symbols) in [("frame0000", 10), ("frame0001", 2)] {
+        let count = |stage: &str| {
+            names
+                .iter()
+                .filter(|name| name.starts_with(frame) && name.ends_with(stage))
+                .count()
+        };
+        let symbols = count("raw.f32");
+        assert!(symbols > frame_symbols, "{symbols}");
+        for stage in [
+            "corrected.f32",
+            "bins.csv",
+            "channel.csv",
+            "points.csv",
+            "llrs.csv",
+        ] {
+            assert_eq!(count(stage), symbols, "{stage}");
+        }
+    }
+    let fec = names.iter().filter(|name| name.ends_with("fec_bits.u8"));
+    assert_eq!(fec.count(), 2);
+
+    // the raw samples of the first symbol are the ones of the frame, after the silence, which the DC blocker
+    // of the stream leaves all but alone
+    let raw: Vec<f32> = read("frame0000_symbol0000_raw.f32".into())
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    assert_eq!(raw.len(), 136);
+    let error = raw
+        .iter()
+        .zip(&samples[1000..1136])
+        .map(|(raw, sample)| (raw - sample).abs())
+        .fold(0.0, f32::max);
+    // measured 7e-5
+    assert!(error < 1e-3, "{error}");
+}
+
+/// Counts the calls of every stage.
+#[derive(Default)]
+struct Calls {
+    frames: usize,
+    symbols: [usize; 6],
+    fec: usize,
+}
+
+struct Counter(Arc<Mutex<Calls>>);
+
+impl StageSink for Counter {
+    fn start_frame(&mut self) {
+        self.0.lock().unwrap().frames += 1;
+    }
+    fn raw_samples(&mut self, _: usize, _: &[f32]) {
+        self.0.lock().unwrap().symbols[0] += 1;
+    }
+    fn corrected_samples(&mut self, _: usize, _: &[f32]) {
+        self.0.lock().unwrap().symbols[1] += 1;
+    }
+    fn fft_bins(&mut self, _: usize, _: &[Complex32]) {
+        self.0.lock().unwrap().symbols[2] += 1;
+    }
+    fn channel_estimate(&mut self, _: usize, _: &[Complex32]) {
+        self.0.lock().unwrap().symbols[3] += 1;
+    }
+    fn equalized_points(&mut self, _: usize, _: &[Complex32]) {
+        self.0.lock().unwrap().symbols[4] += 1;
+    }
+    fn llrs(&mut self, _: usize, _: &[f32]) {
+        self.0.lock().unwrap().symbols[5] += 1;
+    }
+    fn fec_bits(&mut self, _: &[u8]) {
+        self.0.lock().unwrap().fec += 1;
+    }
+}
+
+#[test]
+fn failed_bursts_are_tapped_too() {
+    let mut samples = capture(&[data(100)]);
+    let mut rng = SimulationRng::new(3);
+    // a burst of noise, with a noise floor below the squelch level so no header decodes from its pre-roll
+    samples.extend((0..1000).map(|_| (0.3 * (rng.uniform() - 0.5)) as f32));
+    samples.extend([0.0; 2000]);
+    samples
+        .iter_mut()
+        .for_each(|x| *x += (0.004 * (rng.uniform() - 0.5)) as f32);
+
+    let mut plain = stream();
+    let decoded = plain.push(&samples);
+    assert_eq!(decoded, [data(100)]);
+
+    let calls = Arc::new(Mutex::new(Calls::default()));
+    let mut stream = stream();
+    stream.set_debug_tap(Some(Box::new(Counter(calls.clone()))));
+    assert_eq!(stream.push(&samples), decoded);
+    assert_eq!(stream.stats(), plain.stats());
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.frames, 2);
+    // every whole symbol of the burst, which runs on into the silence after the frame and the noise
+    assert!(calls.symbols.iter().all(|&count| count == calls.symbols[0]));
+    assert!(calls.symbols[0] > 10 + 1000 / 136);
+    // the noise has no header
+    assert_eq!(calls.fec, 1);
+}
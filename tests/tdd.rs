@@ -0,0 +1,306 @@
+//! Runs a [HalfDuplexController] against a scripted remote end, whose frames are laid out on a timeline,
+//! and checks that no frame is sent while the remote is on the air or within the guard time after it,
+//! that the input is heard again within the guard time after a frame, and that its own echo is muted.
+
+use std::ops::Range;
+
+use software_modem::{
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    stream::StreamDemodulator,
+    tdd::{HalfDuplexController, TddConfig, TddEvent, TddState},
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn ofdm() -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    }
+}
+
+fn modulator() -> CodedOFDMModulator {
+    CodedOFDMModulator::new(ofdm(), CodingConfig::default())
+}
+
+fn controller(config: TddConfig) -> HalfDuplexController {
+    let demodulator = CodedOFDMDemodulator::new(ofdm(), CodingConfig::default());
+    HalfDuplexController::new(
+        modulator(),
+        StreamDemodulator::new(demodulator, 0.01, 600),
+        config,
+    )
+}
+
+/// The frames of the remote end, at the samples they start at, over silence up to the length.
+struct Remote {
+    samples: Vec<f32>,
+    active: Vec<Range<usize>>,
+}
+
+impl Remote {
+    fn new(length: usize, frames: &[(usize, Vec<u8>)]) -> Self {
+        let mut samples = vec![0.0; length];
+        let mut active = Vec::new();
+        for (start, payload) in frames {
+            let frame = modulator().encode_frame(payload);
+            samples[*start..*start + frame.len()].copy_from_slice(&frame);
+            active.push(*start..*start + frame.len());
+        }
+        Remote { samples, active }
+    }
+}
+
+/// Processes the input in blocks of the length, and returns the output, the payloads decoded and the events.
+fn run(
+    controller: &mut HalfDuplexController,
+    input: &[f32],
+    block_length: usize,
+) -> (Vec<f32>, Vec<Vec<u8>>, Vec<TddEvent>) {
+    let mut output = vec![0.0; input.len()];
+    let mut payloads = Vec::new();
+    for (input, output) in input
+        .chunks(block_length)
+        .zip(output.chunks_mut(block_length))
+    {
+        payloads.extend(controller.process(input, output));
+    }
+    (output, payloads, controller.take_events())
+}
+
+/// Returns the ranges of samples the controller transmitted in, from its events.
+fn transmissions(events: &[TddEvent]) -> Vec<Range<u64>> {
+    let starts = events.iter().filter(|e| e.to == TddState::Transmitting);
+    let ends = events.iter().filter(|e| e.from == TddState::Transmitting);
+    starts.zip(ends).map(|(s, e)| s.sample..e.sample).collect()
+}
+
+#[test]
+fn no_frame_is_sent_while_the_remote_is_active() {
+    let config = TddConfig {
+        guard_before: 1000,
+        ..Default::default()
+    };
+    let frame_length = modulator().get_frame_length(100);
+    // the remote talks from the start, in frames further apart than the hang of the squelch but closer than
+    // the hang and the guard time, and once more later
+    let remote = Remote::new(
+        120_000,
+        &[
+            (0, data(100)),
+            (frame_length + 800, data(101)),
+            (2 * frame_length + 1600, data(102)),
+            (60_000, data(103)),
+        ],
+    );
+
+    for block_length in [128, 100, 1000] {
+        let mut controller = controller(config);
+        for length in [10, 11, 12] {
+            controller.request_transmit(&data(length));
+        }
+        assert_eq!(controller.get_pending(), 3);
+        let (output, payloads, events) = run(&mut controller, &remote.samples, block_length);
+
+        // every frame of the remote is heard, and every frame of the controller sent
+        assert_eq!(payloads, [data(100), data(101), data(102), data(103)]);
+        let sent = transmissions(&events);
+        assert_eq!(sent.len(), 3, "{events:?}");
+        assert_eq!(controller.get_pending(), 0);
+        assert_eq!(controller.get_state(), TddState::Idle);
+
+        // the output is silent but for the frames, which never start while the remote is on the air,
+        // nor within the guard time after it
+        for (index, range) in sent.iter().enumerate() {
+            let range = range.start as usize..range.end as usize;
+            assert_eq!(range.len(), modulator().get_frame_length(10 + index));
+            let guarded = range.start - config.guard_before..range.start + 1;
+            for active in &remote.active {
+                assert!(
+                    guarded.end <= active.start || active.end <= guarded.start,
+                    "{range:?} starts within the guard of {active:?}"
+                );
+            }
+            assert_eq!(
+                &output[range.clone()],
+                modulator().encode_frame(&data(10 + index as u32))
+            );
+        }
+        assert!(
+            output
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| !sent.iter().any(|range| range.contains(&(i as u64))))
+                .all(|(_, &x)| x == 0.0)
+        );
+
+        // the first frame waits out the talk of the remote
+        let talk_end = remote.active[2].end as u64;
+        assert!(sent[0].start >= talk_end + config.guard_before as u64);
+    }
+}
+
+#[test]
+fn frames_are_held_while_the_carrier_is_heard() {
+    let config = TddConfig::default();
+    let remote = Remote::new(40_000, &[(1000, data(200))]);
+    let mut controller = controller(config);
+    let (_, _, events) = run(&mut controller, &remote.samples[..2000], 128);
+    assert_eq!(controller.get_state(), TddState::Receiving);
+    assert_eq!(
+        events.iter().map(|e| e.to).collect::<Vec<_>>(),
+        [TddState::Receiving]
+    );
+
+    // a request in the middle of the frame is held until the squelch closes and the guard time passes
+    controller.request_transmit(&data(20));
+    let mut output = vec![0.0; 128];
+    let payloads = controller.process(&remote.samples[2000..2128], &mut output);
+    assert!(payloads.is_empty());
+    assert_eq!(controller.get_state(), TddState::Receiving);
+    assert!(output.iter().all(|&x| x == 0.0));
+    assert_eq!(controller.get_pending(), 1);
+
+    let (_, payloads, events) = run(&mut controller, &remote.samples[2128..], 128);
+    assert_eq!(payloads, [data(200)]);
+    let states: Vec<_> = events.iter().map(|e| (e.from, e.to)).collect();
+    assert_eq!(
+        states,
+        [
+            (TddState::Receiving, TddState::Idle),
+            (TddState::Idle, TddState::GuardBefore),
+            (TddState::GuardBefore, TddState::Transmitting),
+            (TddState::Transmitting, TddState::GuardAfter),
+            (TddState::GuardAfter, TddState::Idle),
+        ]
+    );
+    // the guard times are counted in samples, from the end of the blocks the state changed in
+    assert_eq!(
+        events[2].sample - events[1].sample,
+        config.guard_before as u64
+    );
+    assert_eq!(
+        events[3].sample - events[2].sample,
+        modulator().get_frame_length(20) as u64
+    );
+    assert_eq!(
+        events[4].sample - events[3].sample,
+        config.guard_after as u64
+    );
+    assert_eq!(controller.get_samples_processed(), 40_000);
+}
+
+#[test]
+fn reception_resumes_within_the_guard_after_a_frame() {
+    for echo_suppression in [None, Some(500), Some(2000)] {
+        let config = TddConfig {
+            guard_before: 0,
+            guard_after: 2400,
+            echo_suppression,
+        };
+        let mut controller = controller(config);
+        controller.request_transmit(&data(30));
+        let frame_length = modulator().get_frame_length(30);
+        let mut output = vec![0.0; frame_length];
+        controller.process(&vec![0.0; frame_length], &mut output);
+        assert_eq!(controller.get_state(), TddState::GuardAfter);
+        assert_eq!(
+            controller.is_receive_muted(),
+            echo_suppression.unwrap_or(0) > 0
+        );
+
+        // the remote answers as soon as the echo suppression ends
+        let start = echo_suppression.unwrap_or(0);
+        let remote = Remote::new(20_000, &[(start, data(40))]);
+        let (_, payloads, events) = run(&mut controller, &remote.samples, 256);
+        assert_eq!(payloads, [data(40)], "{echo_suppression:?}");
+        assert!(!controller.is_receive_muted());
+
+        // the carrier is heard before the guard time ends, and the state goes back to idle once it is gone
+        let heard = events.iter().find(|e| e.to == TddState::Receiving).unwrap();
+        assert_eq!(heard.from, TddState::GuardAfter);
+        let after = heard.sample - frame_length as u64;
+        assert!(
+            after >= start as u64 && after < config.guard_after as u64,
+            "{after}"
+        );
+        assert_eq!(controller.get_state(), TddState::Idle);
+    }
+}
+
+#[test]
+fn the_echo_of_a_frame_is_muted() {
+    // the input is the output played back 300 samples later at a quarter of its level
+    const DELAY: usize = 300;
+    for (echo_suppression, heard) in [(None, true), (Some(DELAY + 200), false)] {
+        let config = TddConfig {
+            guard_before: 0,
+            echo_suppression,
+            ..Default::default()
+        };
+        let mut controller = controller(config);
+        controller.request_transmit(&data(30));
+        let length = 20_000;
+        let mut played = vec![0.0; length + DELAY];
+        for start in (0..length).step_by(100) {
+            let input: Vec<f32> = played[start..start + 100]
+                .iter()
+                .map(|x| 0.25 * x)
+                .collect();
+            let payloads =
+                controller.process(&input, &mut played[start + DELAY..start + DELAY + 100]);
+            assert!(payloads.is_empty());
+        }
+        let events = controller.take_events();
+        assert_eq!(
+            events.iter().any(|e| e.to == TddState::Receiving),
+            heard,
+            "{echo_suppression:?}: {events:?}"
+        );
+        assert_eq!(controller.get_demodulator().get_frames_decoded(), 0);
+    }
+}
+
+#[test]
+fn the_defaults_guard_both_sides() {
+    assert_eq!(
+        TddConfig::default(),
+        TddConfig {
+            guard_before: 2400,
+            guard_after: 2400,
+            echo_suppression: None,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "Echo suppression must be at most the guard after of 2400 samples, but got 2401"
+)]
+fn echo_suppression_longer_than_the_guard() {
+    controller(TddConfig {
+        echo_suppression: Some(2401),
+        ..Default::default()
+    });
+}
+
+#[test]
+#[should_panic(expected = "Output length must be 128, but got 127")]
+fn output_of_the_wrong_length() {
+    controller(TddConfig::default()).process(&[0.0; 128], &mut [0.0; 127]);
+}
+
+#[test]
+#[should_panic(expected = "Payload must be at most 65535 bytes, but got 65536 bytes")]
+fn payload_too_long() {
+    controller(TddConfig::default()).request_transmit(&vec![0; 65536]);
+}
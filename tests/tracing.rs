@@ -0,0 +1,352 @@
+//! Checks that the receiver sends its `tracing` events and spans while a stream decodes frames,
+//! with the fields of the outcome, and only at the levels the subscriber records.
+//!
+//! The test needs the `tracing` feature: `cargo test --features tracing`.
+
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use software_modem::{
+    channel::{AwgnChannel, Channel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::OFDMConfig,
+    stream::StreamDemodulator,
+};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    layer::{Context, Layer, SubscriberExt},
+    registry::LookupSpan,
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+/// The value of a field, as the visitor was given it.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Str(String),
+}
+
+/// The fields of an event or a span, in the order they were recorded, with the message of an event.
+#[derive(Default, Debug)]
+struct Fields(Vec<(&'static str, Value)>);
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<Value> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+impl Visit for Fields {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.push((field.name(), Value::Bool(value)));
+    }
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.push((field.name(), Value::U64(value)));
+    }
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.push((field.name(), Value::I64(value)));
+    }
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.push((field.name(), Value::F64(value)));
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name(), Value::Str(value.into())));
+    }
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name(), Value::Str(format!("{value:?}"))));
+    }
+}
+
+/// An event as it was sent, with the name of the span it happened in.
+#[derive(Debug)]
+struct Captured {
+    level: Level,
+    message: String,
+    fields: Fields,
+    span: Option<&'static str>,
+}
+
+impl Captured {
+    fn get(&self, name: &str) -> Option<Value> {
+        self.fields.get(name)
+    }
+}
+
+/// A span as it was opened, with the fields recorded until it was closed.
+#[derive(Debug)]
+struct CapturedSpan {
+    id: Id,
+    level: Level,
+    name: &'static str,
+    fields: Fields,
+}
+
+/// Keeps every event and span.
+#[derive(Clone, Default)]
+struct Capture {
+    events: Arc<Mutex<Vec<Captured>>>,
+    spans: Arc<Mutex<Vec<CapturedSpan>>>,
+}
+
+impl Capture {
+    /// Returns the capture, and a subscriber sending it the events and spans up to the level.
+    fn new(max_level: Level) -> (Self, impl Subscriber + Send + Sync) {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(capture.clone())
+            .with(LevelFilter::from_level(max_level));
+        (capture, subscriber)
+    }
+
+    fn messages(&self) -> Vec<String> {
+        let events = self.events.lock().unwrap();
+        events.iter().map(|event| event.message.clone()).collect()
+    }
+
+    fn find(&self, message: &str) -> Vec<Captured> {
+        let mut events = self.events.lock().unwrap();
+        let (found, kept) = events.drain(..).partition(|event| event.message == message);
+        *events = kept;
+        found
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attributes.record(&mut fields);
+        self.spans.lock().unwrap().push(CapturedSpan {
+            id: id.clone(),
+            level: *attributes.metadata().level(),
+            name: attributes.metadata().name(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+        // the ids of closed spans are taken again, the last one is open
+        let mut spans = self.spans.lock().unwrap();
+        let span = spans.iter_mut().rev().find(|span| span.id == *id).unwrap();
+        values.record(&mut span.fields);
+    }
+
+    fn on_event(&self, event: &Event<'_>, context: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let Some(Value::Str(message)) = fields.get("message") else {
+            panic!("{fields:?}");
+        };
+        fields.0.retain(|(name, _)| *name != "message");
+        self.events.lock().unwrap().push(Captured {
+            level: *event.metadata().level(),
+            message,
+            fields,
+            span: context.event_span(event).map(|span| span.name()),
+        });
+    }
+}
+
+fn config() -> (OFDMConfig, CodingConfig) {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 8,
+        ..Default::default()
+    };
+    let coding = CodingConfig {
+        reed_solomon: true,
+        ..Default::default()
+    };
+    (ofdm, coding)
+}
+
+/// Returns the frames after a silence, each followed by 2000 samples of silence.
+fn capture(frames: &[Vec<f32>]) -> Vec<f32> {
+    let mut samples = vec![0.0; 1000];
+    for frame in frames {
+        samples.extend(frame);
+        samples.extend([0.0; 2000]);
+    }
+    samples
+}
+
+#[test]
+fn frame_decodes_send_their_outcome() {
+    let (ofdm, coding) = config();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let demodulator = CodedOFDMDemodulator::new(ofdm, coding);
+    let symbol_length = demodulator.get_symbol_length();
+
+    let mut noisy = modulator.encode_frame(&data(100));
+    AwgnChannel::new(12.0, 1).apply(&mut noisy);
+    let mut lost = modulator.encode_frame(&data(80));
+    AwgnChannel::new(-10.0, 2).apply(&mut lost[symbol_length..]);
+    let samples = capture(&[noisy, lost]);
+
+    let (capture, subscriber) = Capture::new(Level::DEBUG);
+    let mut stream = StreamDemodulator::new(demodulator, 0.01, 1000);
+    let payloads = tracing::subscriber::with_default(subscriber, || {
+        samples
+            .chunks(256)
+            .flat_map(|block| stream.push(block))
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(payloads, [data(100)]);
+
+    // the squelch opens and closes around both frames, at the hang
+    let messages = capture.messages();
+    assert_eq!(messages[..2], ["sync acquired", "sync lost"]);
+    let lost = capture.find("sync lost");
+    assert_eq!(lost.len(), 2);
+    assert!(
+        lost.iter()
+            .all(|event| event.get("cut") == Some(Value::Bool(false)))
+    );
+    assert_eq!(capture.find("sync acquired").len(), 2);
+
+    // the outcome of every burst, in its span
+    let decoded = capture.find("frame decoded");
+    assert_eq!(decoded.len(), 1);
+    assert_eq!(decoded[0].get("bytes"), Some(Value::U64(100)));
+    assert_eq!(decoded[0].span, Some("decode_burst"));
+    let failed = capture.find("frame failed");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].level, Level::DEBUG);
+
+    // the statistics of the FEC of every payload decoded, the noisy one corrected
+    let payloads = capture.find("payload decoded");
+    let (good, bad): (Vec<_>, Vec<_>) = payloads
+        .iter()
+        .partition(|event| event.get("crc_ok") == Some(Value::Bool(true)));
+    assert_eq!(good.len(), 1);
+    assert!(!bad.is_empty());
+    let Some(Value::U64(corrected)) = good[0].get("corrected_bits") else {
+        panic!("{:?}", good[0]);
+    };
+    assert!(corrected > 0);
+    assert!(matches!(
+        good[0].get("corrected_bytes"),
+        Some(Value::U64(_))
+    ));
+    // and none without the LDPC code
+    assert_eq!(good[0].get("iterations"), None);
+
+    // the noise the lost frame drowns in goes beyond full scale, a clipping input warned once
+    let health = capture.find("input health changed");
+    assert_eq!(health.len(), 1);
+    assert_eq!(health[0].level, Level::WARN);
+    assert_eq!(health[0].get("health"), Some(Value::Str("Clipping".into())));
+
+    // nothing else at the debug level, not even the symbols
+    assert_eq!(capture.messages(), Vec::<String>::new());
+    let spans = capture.spans.lock().unwrap();
+    assert!(spans.iter().all(|span| span.name == "decode_burst"));
+    assert_eq!(spans.len(), 2);
+}
+
+#[test]
+fn symbols_trace_their_evm() {
+    let (ofdm, coding) = config();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, 1000);
+    let mut frame = modulator.encode_frame(&data(40));
+    AwgnChannel::new(25.0, 3).apply(&mut frame);
+    let samples = capture(&[frame]);
+
+    let (capture, subscriber) = Capture::new(Level::TRACE);
+    let payloads = tracing::subscriber::with_default(subscriber, || stream.push(&samples));
+    assert_eq!(payloads, [data(40)]);
+
+    // every offset tried and its error, up to the one which decoded
+    let tried = capture.find("frame tried");
+    assert!(!tried.is_empty());
+    assert_eq!(tried.last().unwrap().get("error"), None);
+    assert!(
+        tried[..tried.len() - 1]
+            .iter()
+            .all(|event| matches!(event.get("error"), Some(Value::Str(_))))
+    );
+    assert_eq!(
+        capture
+            .find("header decoded")
+            .last()
+            .unwrap()
+            .get("payload_length"),
+        Some(Value::U64(40))
+    );
+
+    // the symbols of the frame at the offset which decoded it, at about the EVM of the channel
+    let Some(Value::U64(frame_symbols)) = capture.find("frame decoded")[0].get("symbols") else {
+        panic!("no frame decoded");
+    };
+    let spans = capture.spans.lock().unwrap();
+    let symbols: Vec<_> = spans
+        .iter()
+        .filter(|span| span.name == "symbol")
+        .map(|span| match span.fields.0[..] {
+            [("index", Value::U64(index)), ("evm_db", Value::F64(evm_db))] => (index, evm_db),
+            _ => panic!("{span:?}"),
+        })
+        .collect();
+    let start = symbols.iter().rposition(|&(index, _)| index == 0).unwrap();
+    // the burst goes on with the silence after the frame
+    assert!(symbols.len() - start > frame_symbols as usize);
+    // the EVM of the 48 points of one symbol scatters by a few dB, the one of all of them less
+    let frame = &symbols[start..start + frame_symbols as usize];
+    for &(index, evm_db) in frame {
+        assert!((evm_db + 25.0).abs() < 5.0, "{evm_db} dB at symbol {index}");
+    }
+    let mean = frame
+        .iter()
+        .map(|&(_, evm_db)| 10f64.powf(evm_db / 10.0))
+        .sum::<f64>()
+        / frame.len() as f64;
+    assert!((10.0 * mean.log10() + 25.0).abs() < 1.5, "{mean}");
+    assert!(
+        spans
+            .iter()
+            .all(|span| span.name != "symbol" || span.level == Level::TRACE)
+    );
+}
+
+#[test]
+fn long_bursts_are_cut() {
+    let (ofdm, coding) = config();
+    let modulator = CodedOFDMModulator::new(ofdm.clone(), coding.clone());
+    let mut stream = StreamDemodulator::new(CodedOFDMDemodulator::new(ofdm, coding), 0.01, 1000);
+    stream.set_max_burst_length(800);
+    let samples = capture(&[modulator.encode_frame(&data(200))]);
+
+    let (capture, subscriber) = Capture::new(Level::DEBUG);
+    tracing::subscriber::with_default(subscriber, || stream.push(&samples));
+    let lost = capture.find("sync lost");
+    assert!(!lost.is_empty());
+    assert_eq!(lost[0].get("length"), Some(Value::U64(800)));
+    assert_eq!(lost[0].get("cut"), Some(Value::Bool(true)));
+
+    // without a subscriber, nothing is sent
+    let before = capture.events.lock().unwrap().len();
+    stream.push(&samples);
+    assert_eq!(capture.events.lock().unwrap().len(), before);
+}
@@ -0,0 +1,79 @@
+//! Round-trips frames through the classes that `wasm-bindgen` exports to JavaScript, on `wasm32-unknown-unknown`.
+//!
+//! The test needs the `wasm` feature and the `wasm-bindgen-test-runner` of the `wasm-bindgen` CLI:
+//! `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test --target wasm32-unknown-unknown --features wasm --test wasm`
+//! runs it in Node.js, and with `WASM_BINDGEN_USE_BROWSER=1` in a headless browser of a WebDriver
+//! like `geckodriver` or `chromedriver`.
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use software_modem::{
+    frame::CodingConfig,
+    ofdm::{OFDMConfig, modulator::OutputScale},
+    wasm::{WasmDemodulator, WasmModulator, WasmStreamDemodulator},
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn configs() -> (Vec<u8>, Vec<u8>) {
+    let ofdm = OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 4,
+        differential_time: true,
+        output_scale: OutputScale::PeakNormalize(0.5),
+        ..Default::default()
+    };
+    (ofdm.to_bytes(), CodingConfig::default().to_bytes())
+}
+
+#[wasm_bindgen_test]
+fn frame_round_trips() {
+    let (ofdm, coding) = configs();
+    let modulator = WasmModulator::new(&ofdm, &coding).unwrap();
+    let demodulator = WasmDemodulator::new(&ofdm, &coding).unwrap();
+    let payload = data(300);
+
+    let samples = modulator.modulate(&payload);
+    assert_eq!(samples.len(), modulator.get_frame_length(payload.len()));
+    assert_eq!(demodulator.demodulate(&samples).unwrap(), payload);
+    assert!(demodulator.demodulate(&samples[..100]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn worklet_blocks_round_trip() {
+    let (ofdm, coding) = configs();
+    let modulator = WasmModulator::new(&ofdm, &coding).unwrap();
+    let mut stream = WasmStreamDemodulator::new(&ofdm, &coding, 0.01, 2400).unwrap();
+    let payloads = [data(1), data(100), data(1000)];
+
+    let mut signal = vec![0.0; 1000];
+    for payload in &payloads {
+        signal.extend(modulator.modulate(payload));
+        signal.extend([0.0; 4800]);
+    }
+    for block in signal.chunks(128) {
+        stream.push(block);
+    }
+    assert_eq!(stream.flush(), 3);
+    for payload in &payloads {
+        assert_eq!(stream.next_frame().as_ref(), Some(payload));
+    }
+    assert_eq!(stream.next_frame(), None);
+    assert_eq!(
+        (stream.get_frames_decoded(), stream.get_frames_failed()),
+        (3, 0)
+    );
+}
+
+#[wasm_bindgen_test]
+fn broken_configurations_are_errors() {
+    let (ofdm, coding) = configs();
+    assert!(WasmModulator::new(&ofdm[..5], &coding).is_err());
+    assert!(WasmDemodulator::new(&ofdm, &coding[..1]).is_err());
+    assert!(WasmStreamDemodulator::new(&ofdm, &coding, 0.0, 2400).is_err());
+}
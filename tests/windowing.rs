@@ -0,0 +1,72 @@
+//! Builds band-limited symbols by hand, an inverse FFT of random points on the lower subcarriers with the upper
+//! ones left empty plus a cyclic prefix, and checks that the [raised-cosine tapers](OFDMModulator::apply_window)
+//! between them lower the spectrum beyond the band edge by at least 15 dB with a roll-off of 16 samples.
+
+use realfft::{RealFftPlanner, num_complex::Complex32};
+use software_modem::{
+    metrics::{SpectrumWindow, oob_power_db, power_spectrum},
+    ofdm::{OFDMConfig, modulator::OFDMModulator},
+    rng::SimulationRng,
+};
+
+const FFT_LENGTH: usize = 128;
+const CYCLIC_PREFIX_LENGTH: usize = 16;
+/// Subcarriers 1 to 47 carry points, the 16 up to the Nyquist frequency are empty.
+const BAND_EDGE: usize = 48;
+
+fn modulator(roll_off: u32) -> OFDMModulator {
+    OFDMModulator::new(
+        (&OFDMConfig {
+            num_subcarriers: FFT_LENGTH as u32 / 2,
+            cyclic_prefix_length: CYCLIC_PREFIX_LENGTH as u32,
+            guard_subcarriers_high: (FFT_LENGTH / 2 - BAND_EDGE) as u32,
+            roll_off,
+            ..Default::default()
+        })
+            .into(),
+    )
+}
+
+/// Returns the symbols with random QPSK points below the band edge, each with its cyclic prefix.
+fn band_limited_symbols(count: usize) -> Vec<f32> {
+    let mut rng = SimulationRng::new(1);
+    let inverse = RealFftPlanner::<f32>::new().plan_fft_inverse(FFT_LENGTH);
+    let mut samples = Vec::new();
+    for _ in 0..count {
+        let mut bins = vec![Complex32::default(); FFT_LENGTH / 2 + 1];
+        for bin in &mut bins[1..BAND_EDGE] {
+            let level = |bit: u64| if bit == 0 { 1.0 } else { -1.0 };
+            *bin = Complex32::new(level(rng.below(2)), level(rng.below(2)));
+        }
+        let mut body = vec![0.0; FFT_LENGTH];
+        inverse.process(&mut bins, &mut body).unwrap();
+        samples.extend_from_slice(&body[FFT_LENGTH - CYCLIC_PREFIX_LENGTH..]);
+        samples.extend_from_slice(&body);
+    }
+    samples
+}
+
+/// Returns the power from 8 subcarriers above the band edge up to the Nyquist frequency, subcarrier k at k / 128.
+fn out_of_band_power_db(samples: &[f32]) -> f32 {
+    let spectrum = power_spectrum(samples, 1024, SpectrumWindow::Hann);
+    oob_power_db(&spectrum, (0.0, (BAND_EDGE + 8) as f32 / FFT_LENGTH as f32))
+}
+
+#[test]
+fn tapers_lower_the_out_of_band_power() {
+    let symbols = band_limited_symbols(200);
+    let rectangular = modulator(0);
+    assert_eq!(
+        rectangular.get_symbol_length(),
+        FFT_LENGTH + CYCLIC_PREFIX_LENGTH
+    );
+    // without a roll-off, the symbols are left as they are
+    assert_eq!(rectangular.apply_window(&symbols), symbols);
+
+    let windowed = modulator(16).apply_window(&symbols);
+    assert_eq!(windowed.len(), symbols.len() + 16);
+
+    let reduction = out_of_band_power_db(&symbols) - out_of_band_power_db(&windowed);
+    // measured 28.5 dB
+    assert!(reduction >= 15.0, "{reduction} dB");
+}
@@ -0,0 +1,204 @@
+//! Checks the fragment bookkeeping of the file receiver: fragments out of order, duplicated, missing or corrupted.
+
+use std::path::PathBuf;
+
+use software_modem::xfer::{FileReceiver, Progress, XferError, fragment_file, sha256};
+
+/// An empty directory of its own for every test.
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("software_modem_test_xfer_{}", name));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn hex(hash: [u8; 32]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn sha256_matches_the_test_vectors() {
+    assert_eq!(
+        hex(sha256(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    // two blocks, the padding does not fit after the 56 bytes
+    assert_eq!(
+        hex(sha256(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(
+        hex(sha256(&vec![b'a'; 1_000_000])),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn fragments_in_reverse_order_with_duplicates() {
+    let directory = directory("reverse");
+    let data = data(10_000);
+    let payloads = fragment_file("reverse.bin", &data, 999).unwrap();
+    assert_eq!(payloads.len(), 12);
+
+    // every payload twice, the fragments from the last to the first, the metadata in the middle
+    let mut order: Vec<usize> = (1..12).rev().collect();
+    order.insert(5, 0);
+    let mut receiver = FileReceiver::new(&directory);
+    let mut completed = Vec::new();
+    for &index in &order {
+        for _ in 0..2 {
+            if let Some(path) = receiver.push(&payloads[index]).unwrap() {
+                completed.push(path);
+            }
+        }
+    }
+    assert_eq!(completed, [directory.join("reverse.bin")]);
+    assert_eq!(std::fs::read(&completed[0]).unwrap(), data);
+    assert!(!directory.join(".reverse.bin.part").exists());
+
+    // frames repeated after the end are ignored
+    assert_eq!(receiver.push(&payloads[0]).unwrap(), None);
+    assert_eq!(receiver.push(&payloads[3]).unwrap(), None);
+    assert_eq!(receiver.get_progress(), None);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn missing_fragments_are_reported() {
+    let directory = directory("missing");
+    let data = data(5000);
+    let payloads = fragment_file("missing.bin", &data, 500).unwrap();
+
+    let mut receiver = FileReceiver::new(&directory);
+    for index in [0, 1, 2, 4, 5, 7, 10] {
+        assert_eq!(receiver.push(&payloads[index]).unwrap(), None);
+    }
+    assert_eq!(
+        receiver.get_progress(),
+        Some(Progress {
+            fragments_received: 6,
+            fragments_total: 10,
+            bytes_received: 3000,
+            size: 5000,
+        })
+    );
+    assert_eq!(receiver.get_missing_fragments(), [2, 5, 7, 8]);
+    assert_eq!(receiver.get_metadata().unwrap().name, "missing.bin");
+    assert!(directory.join(".missing.bin.part").exists());
+
+    match receiver.finish() {
+        Err(XferError::Incomplete { name, missing }) => {
+            assert_eq!(name, "missing.bin");
+            assert_eq!(missing, [2, 5, 7, 8]);
+        }
+        result => panic!("Expected an incomplete transfer, but got {:?}", result),
+    }
+    assert!(!directory.join(".missing.bin.part").exists());
+    assert!(!directory.join("missing.bin").exists());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn corrupted_fragment_fails_the_hash() {
+    let directory = directory("corrupted");
+    let mut payloads = fragment_file("corrupted.bin", &data(3000), 1000).unwrap();
+    // a frame whose CRC matched, but whose bytes were corrupted before the modulator
+    *payloads[2].last_mut().unwrap() ^= 1;
+
+    let mut receiver = FileReceiver::new(&directory);
+    assert_eq!(receiver.push(&payloads[0]).unwrap(), None);
+    assert_eq!(receiver.push(&payloads[1]).unwrap(), None);
+    assert_eq!(receiver.push(&payloads[2]).unwrap(), None);
+    assert!(matches!(
+        receiver.push(&payloads[3]),
+        Err(XferError::HashMismatch { name }) if name == "corrupted.bin"
+    ));
+    assert!(!directory.join("corrupted.bin").exists());
+    assert!(!directory.join(".corrupted.bin.part").exists());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn fragments_of_another_transfer_are_kept_apart() {
+    let directory = directory("interleaved");
+    let first = data(2000);
+    let second: Vec<u8> = first.iter().rev().copied().collect();
+    let first_payloads = fragment_file("first.bin", &first, 400).unwrap();
+    let second_payloads = fragment_file("second.bin", &second, 400).unwrap();
+
+    let mut receiver = FileReceiver::new(&directory);
+    assert_eq!(receiver.push(&first_payloads[0]).unwrap(), None);
+    assert_eq!(receiver.push(&first_payloads[1]).unwrap(), None);
+    // the fragments of the second file arrive before its metadata, which abandons the first
+    for payload in &second_payloads[1..5] {
+        assert_eq!(receiver.push(payload).unwrap(), None);
+    }
+    assert_eq!(receiver.get_progress().unwrap().fragments_received, 1);
+    assert_eq!(receiver.push(&second_payloads[0]).unwrap(), None);
+    assert_eq!(receiver.get_metadata().unwrap().name, "second.bin");
+    assert_eq!(receiver.get_missing_fragments(), [4]);
+    assert!(!directory.join(".first.bin.part").exists());
+
+    // a late fragment of the first file is not mixed into the second
+    assert_eq!(receiver.push(&first_payloads[5]).unwrap(), None);
+    let path = receiver.push(&second_payloads[5]).unwrap().unwrap();
+    assert_eq!(std::fs::read(path).unwrap(), second);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn empty_file_completes_with_its_metadata() {
+    let directory = directory("empty");
+    let payloads = fragment_file("empty.bin", &[], 100).unwrap();
+    assert_eq!(payloads.len(), 1);
+
+    let mut receiver = FileReceiver::new(&directory);
+    let path = receiver.push(&payloads[0]).unwrap().unwrap();
+    assert_eq!(std::fs::read(path).unwrap(), b"");
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn paths_and_other_payloads_are_rejected() {
+    let directory = directory("names");
+    for name in [
+        "",
+        ".",
+        "..",
+        "../escape.bin",
+        "sub/file.bin",
+        "c:\\file.bin",
+    ] {
+        assert!(matches!(
+            fragment_file(name, b"data", 100),
+            Err(XferError::InvalidName(_))
+        ));
+    }
+
+    // a metadata frame naming a path, built by hand
+    let mut payload = fragment_file("escape.bin", b"data", 100).unwrap()[0].clone();
+    let name = payload.len() - "escape.bin".len();
+    payload.truncate(name);
+    payload.extend(b"../escape.bin");
+    let mut receiver = FileReceiver::new(&directory);
+    assert!(matches!(
+        receiver.push(&payload),
+        Err(XferError::InvalidName(name)) if name == "../escape.bin"
+    ));
+    assert_eq!(receiver.get_progress(), None);
+
+    // payloads that are not frames of a transfer
+    for payload in [&b""[..], b"hello", b"D123", b"M"] {
+        assert_eq!(receiver.push(payload).unwrap(), None);
+    }
+    assert!(receiver.finish().is_ok());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
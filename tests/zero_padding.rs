@@ -0,0 +1,154 @@
+//! Round trips of frames through the [multipath channel](software_modem::channel::MultipathChannel)
+//! with echoes up to the guard interval late, with a cyclic prefix and with [zero padding](GuardType::ZeroPad),
+//! and checks that zero padding keeps the symbol length, leaves the guard interval silent, also after clipping,
+//! and is serialized.
+
+use realfft::num_complex::Complex32;
+use software_modem::{
+    channel::{AwgnChannel, Channel, ChannelChain, MultipathChannel},
+    coded::{CodedOFDMDemodulator, CodedOFDMModulator},
+    frame::CodingConfig,
+    ofdm::{
+        GuardType, OFDMConfig,
+        demodulator::OFDMDemodulator,
+        diversity::DiversityDemodulator,
+        modulator::{Clipping, OFDMModulator},
+    },
+};
+
+fn data(length: u32) -> Vec<u8> {
+    (0..length)
+        .map(|i| (i.wrapping_mul(2654435761) >> 11) as u8)
+        .collect()
+}
+
+fn config(guard_type: GuardType) -> OFDMConfig {
+    OFDMConfig {
+        num_subcarriers: 64,
+        cyclic_prefix_length: 16,
+        guard_type,
+        ..Default::default()
+    }
+}
+
+/// Echoes spread from the direct path to `delay` samples late.
+fn echoes(delay: usize) -> MultipathChannel {
+    MultipathChannel::new(&[
+        (0, Complex32::new(1.0, 0.0)),
+        (delay / 2, Complex32::new(-0.4, 0.0)),
+        (delay, Complex32::new(0.3, 0.0)),
+    ])
+}
+
+#[test]
+fn zero_padding_keeps_the_symbol_length() {
+    let cyclic = OFDMModulator::new((&config(GuardType::CyclicPrefix)).into());
+    let padded = OFDMModulator::new((&config(GuardType::ZeroPad)).into());
+    assert_eq!(padded.get_symbol_length(), 144);
+    assert_eq!(padded.get_symbol_length(), cyclic.get_symbol_length());
+    assert_eq!(padded.get_bytes_per_symbol(), cyclic.get_bytes_per_symbol());
+
+    // the same samples, in front of zeros instead of behind the end of the symbol
+    let data = data(cyclic.get_bytes_per_symbol() as u32);
+    let with_prefix = cyclic.modulate_symbol(&data).unwrap();
+    let with_zeros = padded.modulate_symbol(&data).unwrap();
+    assert_eq!(with_zeros[..128], with_prefix[16..]);
+    assert!(with_zeros[128..].iter().all(|&x| x == 0.0));
+}
+
+#[test]
+fn echoes_up_to_the_guard_stay_within_the_symbol() {
+    let received = |guard_type| {
+        let modulator = OFDMModulator::new((&config(guard_type)).into());
+        let mut samples: Vec<f32> = data(10 * modulator.get_bytes_per_symbol() as u32)
+            .chunks(modulator.get_bytes_per_symbol())
+            .flat_map(|chunk| modulator.modulate_symbol(chunk).unwrap())
+            .collect();
+        echoes(16).apply(&mut samples);
+        let demodulator = OFDMDemodulator::new((&config(guard_type)).into());
+        samples
+            .chunks(modulator.get_symbol_length())
+            .map(|symbol| demodulator.demodulate_to_symbols(symbol).unwrap().bins)
+            .collect::<Vec<_>>()
+    };
+
+    // both make the echoes cyclic, so every bin is the one sent times the response of the channel
+    let cyclic = received(GuardType::CyclicPrefix);
+    let padded = received(GuardType::ZeroPad);
+    assert_eq!(cyclic.len(), 10);
+    for (cyclic, padded) in cyclic.iter().zip(&padded) {
+        for (cyclic, padded) in cyclic.iter().zip(padded) {
+            assert!((cyclic - padded).norm() < 1e-3, "{cyclic} {padded}");
+        }
+    }
+}
+
+#[test]
+fn frames_through_echoes_up_to_the_guard() {
+    let payload = data(400);
+    for guard_type in [GuardType::CyclicPrefix, GuardType::ZeroPad] {
+        // the coherent equalizer only undoes the mean gain of the pilots, not the notches of the echoes
+        let ofdm = OFDMConfig {
+            differential_time: true,
+            ..config(guard_type)
+        };
+        let modulator = CodedOFDMModulator::new(ofdm.clone(), CodingConfig::default());
+        let demodulator = CodedOFDMDemodulator::new(ofdm, CodingConfig::default());
+        for delay in [4, 10, 16] {
+            let mut frame = modulator.encode_frame(&payload);
+            ChannelChain::new()
+                .with(echoes(delay))
+                .with(AwgnChannel::new(25.0, delay as u64))
+                .apply(&mut frame);
+            assert_eq!(
+                demodulator.decode_frame(&frame).as_deref(),
+                Ok(&payload[..]),
+                "{guard_type:?} delay {delay}"
+            );
+        }
+    }
+}
+
+#[test]
+fn clipping_leaves_the_padding_silent() {
+    let ofdm = OFDMConfig {
+        clipping: Some(Clipping::default()),
+        ..config(GuardType::ZeroPad)
+    };
+    let modulator = OFDMModulator::new((&ofdm).into());
+    let data = data(modulator.get_bytes_per_symbol() as u32);
+    let symbol = modulator.modulate_symbol(&data).unwrap();
+    assert!(symbol[128..].iter().all(|&x| x == 0.0));
+    assert!(symbol[..128].iter().any(|&x| x != 0.0));
+    assert_eq!(
+        OFDMDemodulator::new((&ofdm).into()).demodulate_symbol_from_buffer(&symbol),
+        data
+    );
+}
+
+#[test]
+fn guard_type_is_serialized() {
+    for guard_type in [GuardType::CyclicPrefix, GuardType::ZeroPad] {
+        let config = config(guard_type);
+        assert_eq!(OFDMConfig::from_bytes(&config.to_bytes()), Ok(config));
+    }
+    assert_eq!(GuardType::default(), GuardType::CyclicPrefix);
+}
+
+#[test]
+#[should_panic(expected = "Zero padding does not support a roll-off, but got 4")]
+fn zero_padding_with_a_roll_off() {
+    OFDMModulator::new(
+        (&OFDMConfig {
+            roll_off: 4,
+            ..config(GuardType::ZeroPad)
+        })
+            .into(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Diversity combining needs a cyclic prefix, but got zero padding")]
+fn diversity_with_zero_padding() {
+    DiversityDemodulator::new((&config(GuardType::ZeroPad)).into(), 2);
+}